@@ -0,0 +1,47 @@
+//! Core path benchmarks: `cargo bench --bench core [filter]`
+//!
+//! Runs the engine's benchmark scenarios at a few sizes, prints a summary
+//! and writes the JSON report to `target/haggis-bench.json`, or to the path
+//! in `HAGGIS_BENCH_OUTPUT`, for comparison across versions.
+
+use haggis::performance::benchmark::{Benchmark, BenchmarkScenario};
+
+fn main() {
+    // `cargo bench` passes `--bench`; the first other argument filters by name
+    let filter = std::env::args().skip(1).find(|arg| !arg.starts_with("--"));
+
+    let scenarios: Vec<BenchmarkScenario> = [
+        BenchmarkScenario::MeshUpload { segments: 64 },
+        BenchmarkScenario::MeshUpload { segments: 512 },
+        BenchmarkScenario::Readback { bytes: 1 << 20 },
+        BenchmarkScenario::Readback { bytes: 64 << 20 },
+        BenchmarkScenario::ComputeDispatch { dispatches: 1 },
+        BenchmarkScenario::ComputeDispatch { dispatches: 10_000 },
+        BenchmarkScenario::DrawSubmission { objects: 100 },
+        BenchmarkScenario::DrawSubmission { objects: 2_000 },
+    ]
+    .into_iter()
+    .filter(|scenario| {
+        filter
+            .as_deref()
+            .is_none_or(|f| scenario.name().contains(f))
+    })
+    .collect();
+
+    let mut benchmark = match Benchmark::new() {
+        Ok(benchmark) => benchmark.with_iterations(5, 50),
+        Err(error) => {
+            eprintln!("Skipping benchmarks: {error}");
+            return;
+        }
+    };
+    let report = benchmark
+        .run_all(&scenarios)
+        .expect("benchmark scenario failed");
+    print!("{}", report.summary());
+
+    let output = std::env::var("HAGGIS_BENCH_OUTPUT")
+        .unwrap_or_else(|_| "target/haggis-bench.json".to_string());
+    report.write_json(&output).expect("write benchmark report");
+    println!("Report written to {output}");
+}
@@ -0,0 +1,23 @@
+//! # Boids
+//!
+//! Runs the built-in `Boids` template: twenty thousand agents flocking by
+//! separation, alignment and cohesion inside a domain box. Steering and
+//! movement run in compute shaders, and the agents are drawn as instanced
+//! darts straight from the GPU buffer.
+//!
+//! ## Usage
+//!
+//! Run with: `cargo run --example boids`
+
+use haggis::simulation::templates::Boids;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut app = haggis::default();
+
+    app.attach_simulation(Boids::new(20_000).with_bounds([-10.0, -10.0, 0.0], [10.0, 10.0, 10.0]));
+
+    app.show_performance_panel(true);
+    app.run();
+
+    Ok(())
+}
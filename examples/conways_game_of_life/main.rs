@@ -8,7 +8,9 @@
 //! - GPU-accelerated Conway's Game of Life with compute shaders
 //! - Ping-pong buffer system for efficient GPU computation
 //! - Real-time 2D visualization of game state
-//! - Interactive speed controls and pattern selection
+//! - Generations per second from the step policy, stepping from the toolbar
+//! - Pattern selection
+//! - Painting live cells onto the plane in paint mode
 //! - High-performance simulation of large grids
 //!
 //! ## Conway's Rules (implemented in GPU shader)
@@ -23,8 +25,13 @@
 
 use haggis::prelude::*;
 use haggis::{
+    events::{BrushStroke, EventReceiver},
+    simulation::paint::{Brush, BrushSurface, FieldBrush, FieldPainter},
+    simulation::step_policy::StepPolicy,
     simulation::BaseSimulation,
-    visualization::{traits::VisualizationComponent, ui::cut_plane_controls::FilterMode},
+    visualization::{
+        traits::VisualizationComponent, ui::cut_plane_controls::FilterMode, GridTransform,
+    },
 };
 use std::sync::Arc;
 
@@ -32,6 +39,17 @@ use std::sync::Arc;
 const GRID_WIDTH: u32 = 256;
 const GRID_HEIGHT: u32 = 256;
 
+/// Grid cells on the data plane, centered at (0, 2, 0) with half size 2 and
+/// row 0 at the top
+fn plane_grid() -> GridTransform {
+    let half_cell = 2.0 / GRID_WIDTH as f32;
+    GridTransform::new(
+        [GRID_WIDTH, GRID_HEIGHT, 1],
+        [-2.0 + half_cell, 4.0 - half_cell, 0.0],
+        [2.0 - half_cell, half_cell, 0.0],
+    )
+}
+
 /// Classic Game of Life patterns
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum LifePattern {
@@ -78,8 +96,6 @@ struct ConwaysGpuSimulation {
     generation: u64,
     current_pattern: LifePattern,
     // Control
-    last_update: Instant,
-    speed: f32,
     is_paused: bool,
     // GPU resources
     gpu_resources: Option<GpuGameOfLifeResources>,
@@ -87,8 +103,10 @@ struct ConwaysGpuSimulation {
     cpu_grid: Vec<bool>,
     // Flag to indicate we need to reupload data to GPU
     needs_gpu_upload: bool,
-    // Flag to indicate we need to run a manual step
-    needs_manual_step: bool,
+    // Painting live cells in paint mode
+    brush: FieldBrush,
+    painter: Option<FieldPainter<u32>>,
+    strokes: Option<EventReceiver<BrushStroke>>,
 }
 
 impl ConwaysGpuSimulation {
@@ -113,13 +131,20 @@ impl ConwaysGpuSimulation {
             height: GRID_HEIGHT,
             generation: 0,
             current_pattern: LifePattern::Glider,
-            last_update: Instant::now(),
-            speed: 10.0,
             is_paused: false, // Start unpaused
             gpu_resources: None,
             cpu_grid: vec![false; (GRID_WIDTH * GRID_HEIGHT) as usize],
             needs_gpu_upload: true,
-            needs_manual_step: false,
+            brush: FieldBrush::new(
+                plane_grid(),
+                BrushSurface::Plane {
+                    axis: 2,
+                    normalized: 0.0,
+                },
+            )
+            .with_brush(Brush::new(2.0)),
+            painter: None,
+            strokes: None,
         };
 
         // Initialize with glider pattern
@@ -587,6 +612,7 @@ impl ConwaysGpuSimulation {
 impl haggis::simulation::traits::Simulation for ConwaysGpuSimulation {
     fn initialize(&mut self, scene: &mut haggis::gfx::scene::Scene) {
         self.base.initialize(scene);
+        self.strokes = Some(scene.events.subscribe());
         println!("🚀 Conway's Game of Life GPU simulation initialized");
     }
 
@@ -595,6 +621,7 @@ impl haggis::simulation::traits::Simulation for ConwaysGpuSimulation {
 
         // Initialize GPU compute resources
         self.initialize_gpu_resources(device);
+        self.painter = Some(FieldPainter::new(device, [self.width, self.height, 1], 1));
 
         // Upload initial pattern to GPU
         self.upload_grid_to_gpu(device, queue);
@@ -619,21 +646,36 @@ impl haggis::simulation::traits::Simulation for ConwaysGpuSimulation {
             self.needs_gpu_upload = false;
         }
 
-        // Handle manual step request
-        if self.needs_manual_step && self.gpu_resources.is_some() {
-            self.run_gpu_compute_step(device, queue);
+        // Paint live cells (or erase them) into the current buffer
+        let mut painted = false;
+        if let (Some(strokes), Some(painter), Some(gpu_resources)) =
+            (&self.strokes, &self.painter, &self.gpu_resources)
+        {
+            let current_buffer = if gpu_resources.ping_pong_state {
+                &gpu_resources.buffer_b
+            } else {
+                &gpu_resources.buffer_a
+            };
+            for stroke in strokes.drain() {
+                let stamps = self.brush.stamps(&stroke);
+                let brush = if stroke.erase {
+                    self.brush.brush.eraser()
+                } else {
+                    self.brush.brush
+                };
+                painter.paint(device, queue, current_buffer, &brush, &stamps);
+                painted |= !stamps.is_empty();
+            }
+        }
+        if painted {
             self.sync_gpu_to_cpu_and_viz(device, queue);
-            self.needs_manual_step = false;
         }
 
-        // Auto-evolve based on speed setting (if not paused and GPU ready)
-        if !self.is_paused && self.speed > 0.0 && self.gpu_resources.is_some() {
-            let time_per_generation = 1.0 / self.speed;
-            if self.last_update.elapsed().as_secs_f32() >= time_per_generation {
-                self.run_gpu_compute_step(device, queue);
-                self.sync_gpu_to_cpu_and_viz(device, queue);
-                self.last_update = Instant::now();
-            }
+        // One generation per step; the step policy sets generations per
+        // second and the toolbar steps while paused
+        if !self.is_paused && self.gpu_resources.is_some() {
+            self.run_gpu_compute_step(device, queue);
+            self.sync_gpu_to_cpu_and_viz(device, queue);
         }
 
         self.base.update_gpu(device, queue, _delta_time);
@@ -660,23 +702,6 @@ impl haggis::simulation::traits::Simulation for ConwaysGpuSimulation {
 
                 ui.separator();
 
-                // Play/Pause button
-                if ui.button(if self.is_paused {
-                    "▶ Play"
-                } else {
-                    "⏸ Pause"
-                }) {
-                    self.is_paused = !self.is_paused;
-                }
-                ui.same_line();
-
-                // Manual step button (only works if GPU is ready)
-                if ui.button("⏭ Step") && self.gpu_resources.is_some() {
-                    self.needs_manual_step = true;
-                }
-
-                ui.separator();
-
                 // Pattern selection
                 ui.text("Pattern:");
                 let patterns = [
@@ -695,16 +720,14 @@ impl haggis::simulation::traits::Simulation for ConwaysGpuSimulation {
 
                 ui.separator();
 
-                // Speed control
-                ui.text("Simulation Speed:");
-                let mut speed_value = self.speed;
-                if ui
-                    .slider_config("Generations/sec", 0.1, 60.0)
-                    .display_format("%.1f gen/sec")
-                    .build(&mut speed_value)
-                {
-                    self.speed = speed_value;
-                }
+                // Play, pause and stepping live in the simulation toolbar
+                ui.text("Generations/sec: \"Steps/s\" in Simulation Control");
+
+                ui.separator();
+
+                // Brush for paint mode (toggle "Paint" in the simulation toolbar)
+                ui.text("Paint (left drag draws, right drag erases):");
+                ui.slider("Brush Radius", 0.5, 16.0, &mut self.brush.brush.radius);
 
                 ui.separator();
 
@@ -713,10 +736,7 @@ impl haggis::simulation::traits::Simulation for ConwaysGpuSimulation {
                 if self.is_paused {
                     ui.text_colored([1.0, 1.0, 0.0, 1.0], "⏸ Paused");
                 } else if self.gpu_resources.is_some() {
-                    ui.text_colored(
-                        [0.0, 1.0, 0.0, 1.0],
-                        &format!("▶ Running ({:.1} gen/sec)", self.speed),
-                    );
+                    ui.text_colored([0.0, 1.0, 0.0, 1.0], "▶ Running");
                 } else {
                     ui.text_colored([1.0, 0.5, 0.0, 1.0], "⚙ Initializing GPU...");
                 }
@@ -827,8 +847,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("  • GPU-accelerated cellular automaton simulation");
     println!("  • Ping-pong buffer system for optimal performance");
     println!("  • Real-time visualization of cellular automaton (128x128)");
-    println!("  • Speed control: 0.1 to 60.0 generations per second");
+    println!("  • Generations per second from the step policy");
+    println!("  • Step by one (.) or by the toolbar count (Shift+.)");
     println!("  • Classic Game of Life patterns");
+    println!("  • Paint mode: tick \"Paint\" in the toolbar and drag on the plane");
     println!();
 
     // Create the main application
@@ -837,17 +859,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Create the GPU Conway's Game of Life simulation
     let simulation = ConwaysGpuSimulation::new();
 
-    // Attach the simulation to the app
+    // Attach the simulation to the app, ten generations per second
     app.attach_simulation(simulation);
+    app.set_step_policy(StepPolicy::StepsPerSecond(10.0));
 
-    // Add reference objects for context
-    app.add_object("examples/test/cube.obj")
-        .with_transform([0.0, 0.0, 0.0], 0.5, 0.0)
-        .with_name("Reference Cube at Origin");
-
-    app.add_object("examples/test/cube.obj")
-        .with_transform([0.0, 2.0, 0.0], 0.3, 0.0)
-        .with_name("Reference Cube at Plane Position");
+    // The built-in grid gives orientation and scale; label it for context
+    app.show_grid_labels(true);
 
     // Run the application
 
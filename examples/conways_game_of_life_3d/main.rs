@@ -25,7 +25,7 @@
 use haggis::prelude::*;
 use haggis::{
     simulation::BaseSimulation,
-    visualization::traits::VisualizationComponent,
+    visualization::{traits::VisualizationComponent, GridTransform},
 };
 use cgmath::{Vector3, Vector4};
 use std::sync::{Arc, Mutex};
@@ -110,6 +110,9 @@ struct Conways3DGpuSimulation {
     instanced_grid_data: Vec<(Vector3<f32>, f32, Vector4<f32>)>, // Instance data for render engine
     // Shared scale for both cut plane and cubes
     visualization_scale: f32, // Scale factor for both cut plane and cube visualization
+    // Cell probing by clicking in the 3D view
+    picks: Option<EventReceiver<PickEvent>>,
+    probed_cell: Option<[u32; 3]>,
 }
 
 impl Conways3DGpuSimulation {
@@ -148,12 +151,15 @@ impl Conways3DGpuSimulation {
             cube_objects_created: false,
             instanced_grid_data: Vec::new(), // No instances initially
             visualization_scale: 1.0, // Default scale - will be applied to both cut plane and cubes
+            picks: None,
+            probed_cell: None,
         };
 
-        // Set the cut plane size to match the shared visualization scale
+        // Place the cut plane over the grid at the shared visualization scale
+        let grid = simulation.grid();
         if let Some(visualization) = simulation.base.get_visualization_mut("cut_plane") {
             if let Some(cut_plane) = visualization.as_any_mut().downcast_mut::<CutPlane2D>() {
-                cut_plane.set_size(simulation.visualization_scale);
+                cut_plane.set_grid_slice(&grid, simulation.cut_plane_z);
             }
         }
 
@@ -284,27 +290,16 @@ impl Conways3DGpuSimulation {
         }
     }
 
-    /// Convert 3D grid coordinates to world position using shared scale
-    /// Now uses the same scale as the cut plane visualization
-    fn grid_to_world_position(&self, grid_x: u32, grid_y: u32, grid_z: u32) -> Vector3<f32> {
-        // Map grid coordinates [0..GRID_SIZE-1] to normalized coordinates [0.0..1.0]
-        let norm_x = grid_x as f32 / (GRID_SIZE - 1) as f32;
-        let norm_y = grid_y as f32 / (GRID_SIZE - 1) as f32;
-        let norm_z = grid_z as f32 / (GRID_SIZE - 1) as f32;
-        
-        // Convert to world coordinates using shared scale * 2 for cubes only
-        // This maps [0.0..1.0] to [-scale*2..scale*2] centered at origin for cubes
-        let world_x = (norm_x - 0.5) * self.visualization_scale * 2.0;
-        let world_y = (norm_y - 0.5) * self.visualization_scale * 2.0; 
-        let world_z = (norm_z - 0.5) * self.visualization_scale * 2.0;
-        
-        Vector3::new(world_x, world_y, world_z)
+    /// Grid placement shared by the cut plane and the cubes, spanning
+    /// `[-scale, scale]³` around the origin
+    fn grid(&self) -> GridTransform {
+        GridTransform::centered([self.width, self.height, self.depth], self.visualization_scale)
     }
 
 
     /// Extract 2D slice from 3D grid at specified Z position
     fn extract_z_slice(&self, z_normalized: f32) -> Vec<f32> {
-        let z_index = ((z_normalized * (self.depth - 1) as f32).round() as u32).min(self.depth - 1);
+        let z_index = self.grid().layer(2, z_normalized);
         let slice_start = (z_index * self.height * self.width) as usize;
         let slice_size = (self.height * self.width) as usize;
         
@@ -472,15 +467,13 @@ impl Conways3DGpuSimulation {
         // Extract Z slice at current cut plane position
         let slice_data = self.extract_z_slice(self.cut_plane_z);
 
-        // Update cut plane position in 3D space using shared scale * 2 to match cube range
-        let world_z = (self.cut_plane_z - 0.5) * self.visualization_scale * 2.0;
+        let grid = self.grid();
 
         // Update visualization
         if let Some(visualization) = self.base.get_visualization_mut("cut_plane") {
             if let Some(cut_plane) = visualization.as_any_mut().downcast_mut::<CutPlane2D>() {
                 cut_plane.update_data(slice_data, self.width, self.height);
-                cut_plane.set_position(Vector3::new(0.0, 0.0, world_z));
-                cut_plane.set_size(self.visualization_scale); // Update size with shared scale
+                cut_plane.set_grid_slice(&grid, self.cut_plane_z);
                 cut_plane.update(0.0, Some(device), Some(queue));
             }
         }
@@ -558,9 +551,9 @@ impl Conways3DGpuSimulation {
         }
 
         // Generate instance data for active cells
-        // Each grid cell spans (scale*2/GRID_SIZE) world units due to 2x cube scaling
-        // Make cubes fill their grid cells properly
-        let cube_size = (self.visualization_scale * 2.0 / GRID_SIZE as f32) * 0.8;
+        // Make cubes nearly fill the spacing between grid samples
+        let grid = self.grid();
+        let cube_size = grid.spacing().x * 0.8;
         let active_cells = self.get_active_cell_positions();
         
         let max_cubes = 8192; // Increase to show more of the grid spatially
@@ -571,7 +564,7 @@ impl Conways3DGpuSimulation {
             .iter()
             .take(cubes_to_show)
             .map(|(x, y, z)| {
-                let world_pos = self.grid_to_world_position(*x, *y, *z);
+                let world_pos = grid.cell_to_world([*x, *y, *z]);
                 let scale = cube_size;
                 let color = Vector4::new(0.8, 0.2, 0.2, 0.1); // 90% transparent red
                 (world_pos, scale, color)
@@ -652,6 +645,7 @@ impl Conways3DGpuSimulation {
 impl haggis::simulation::traits::Simulation for Conways3DGpuSimulation {
     fn initialize(&mut self, scene: &mut haggis::gfx::scene::Scene) {
         self.base.initialize(scene);
+        self.picks = Some(scene.events.subscribe::<PickEvent>());
         println!("🚀 Conway's 3D Game of Life GPU simulation initialized");
     }
 
@@ -673,7 +667,12 @@ impl haggis::simulation::traits::Simulation for Conways3DGpuSimulation {
 
     fn update(&mut self, delta_time: f32, scene: &mut haggis::gfx::scene::Scene) {
         self.base.update(delta_time, scene);
-        
+
+        // Probe the cell nearest to the last click in the 3D view
+        if let Some(pick) = self.picks.as_ref().and_then(|picks| picks.latest()) {
+            self.probed_cell = self.grid().probe(&pick);
+        }
+
         // Note: 3D cube visualization is now handled via instanced grid in apply_gpu_results_to_scene
     }
 
@@ -803,9 +802,24 @@ impl haggis::simulation::traits::Simulation for Conways3DGpuSimulation {
                 }
 
                 // Show which Z layer we're viewing
-                let z_layer = ((self.cut_plane_z * (GRID_DEPTH - 1) as f32).round() as u32).min(GRID_DEPTH - 1);
+                let z_layer = self.grid().layer(2, self.cut_plane_z);
                 ui.text(&format!("Viewing layer {}/{}", z_layer, GRID_DEPTH - 1));
 
+                // Cell under the last click
+                match self.probed_cell {
+                    Some(cell) => {
+                        let alive = self.cpu_grid[self.grid().linear_index(cell)];
+                        ui.text(format!(
+                            "Probed cell ({}, {}, {}): {}",
+                            cell[0],
+                            cell[1],
+                            cell[2],
+                            if alive { "alive" } else { "dead" }
+                        ));
+                    }
+                    None => ui.text_disabled("Click in the grid to probe a cell"),
+                }
+
                 ui.separator();
 
                 // 3D cube visualization toggle
@@ -979,17 +993,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Attach the simulation to the app
     app.attach_simulation(simulation);
 
-    // Add reference objects for context (2x2x2 bounds)
-    // Removed center cube - blocks view of Conway cubes
-
-    // Boundary markers for 2x2x2 world
-    app.add_object("examples/test/cube.obj")
-        .with_transform([-1.0, -1.0, -1.0], 0.05, 0.0)
-        .with_name("Bound (-1,-1,-1)");
-    
-    app.add_object("examples/test/cube.obj")
-        .with_transform([1.0, 1.0, 1.0], 0.05, 0.0)
-        .with_name("Bound (1,1,1)");
+    // Outline the 2x2x2 world bounds
+    app.add_visualization(
+        "bounds",
+        GridTransform::centered([GRID_WIDTH, GRID_HEIGHT, GRID_DEPTH], 1.0)
+            .domain_box()
+            .with_ticks(0.5),
+    );
 
     // Set up UI callback with Transform Studio and Conway 3D controls
     app.set_ui(|ui, scene, selected_index| {
@@ -1007,7 +1017,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 ui.text("Running on instanced grid rendering");
                 ui.separator();
                 ui.text("💡 Use Transform Studio to:");
-                ui.text("  • Select and move reference objects");
+                ui.text("  • Select and move scene objects");
                 ui.text("  • Adjust camera and lighting");
                 ui.text("  • Toggle object visibility");
             });
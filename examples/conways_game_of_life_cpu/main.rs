@@ -3,7 +3,10 @@
 //! This example demonstrates Conway's Game of Life using the 2D data plane visualization system.
 //! It follows the exact same pattern as cut_plane_demo but with Conway's Game of Life data.
 
-use haggis::{simulation::BaseSimulation, CutPlane2D};
+use haggis::{
+    app::jobs::JobSystem, simulation::step_policy::StepPolicy, simulation::BaseSimulation,
+    CutPlane2D,
+};
 use std::time::Instant;
 
 /// Classic Game of Life patterns
@@ -99,19 +102,26 @@ impl GameOfLifeState {
 
     /// Apply Conway's rules for one generation
     pub fn step(&mut self) {
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let index = (y * self.width + x) as usize;
-                let current_alive = self.current_grid[index];
-                let neighbors = self.count_neighbors(x, y);
-
-                // Conway's rules
-                self.next_grid[index] = match (current_alive, neighbors) {
-                    (true, 2) | (true, 3) => true, // Live cell survives
-                    (false, 3) => true,            // Dead cell becomes alive
-                    _ => false,                    // Cell dies or stays dead
-                };
-            }
+        // Rows are independent, so compute them in parallel on the job system
+        let state = &*self;
+        let rows = JobSystem::global().parallel_map(self.height as usize, |y| {
+            let y = y as u32;
+            (0..state.width)
+                .map(|x| {
+                    let current_alive = state.current_grid[(y * state.width + x) as usize];
+                    let neighbors = state.count_neighbors(x, y);
+
+                    // Conway's rules
+                    match (current_alive, neighbors) {
+                        (true, 2) | (true, 3) => true, // Live cell survives
+                        (false, 3) => true,            // Dead cell becomes alive
+                        _ => false,                    // Cell dies or stays dead
+                    }
+                })
+                .collect::<Vec<_>>()
+        });
+        for (next, alive) in self.next_grid.iter_mut().zip(rows.into_iter().flatten()) {
+            *next = alive;
         }
 
         // Swap grids (ping-pong)
@@ -231,11 +241,8 @@ impl GameOfLifeState {
 struct ConwaysCpuSimulation {
     base: BaseSimulation,
     game: GameOfLifeState,
-    last_update: Instant,
-    speed: f32, // Generations per second
     is_paused: bool,
     current_pattern: LifePattern,
-    needs_manual_step: bool,
 }
 
 impl ConwaysCpuSimulation {
@@ -260,11 +267,8 @@ impl ConwaysCpuSimulation {
         Self {
             base,
             game,
-            last_update: Instant::now(),
-            speed: 3.0, // Default: 3 generations per second
             is_paused: false,
             current_pattern: LifePattern::Random,
-            needs_manual_step: false,
         }
     }
 
@@ -292,21 +296,11 @@ impl haggis::simulation::traits::Simulation for ConwaysCpuSimulation {
     }
 
     fn update(&mut self, delta_time: f32, scene: &mut haggis::gfx::scene::Scene) {
-        // Handle manual stepping
-        if self.needs_manual_step {
+        // One generation per step; the step policy sets generations per
+        // second and the toolbar steps while paused
+        if !self.is_paused {
             self.game.step();
             self.update_visualization();
-            self.needs_manual_step = false;
-            self.last_update = Instant::now();
-        }
-        // Auto-evolve the game based on speed setting (if not paused)
-        else if !self.is_paused && self.speed > 0.0 {
-            let time_per_generation = 1.0 / self.speed;
-            if self.last_update.elapsed().as_secs_f32() >= time_per_generation {
-                self.game.step();
-                self.update_visualization();
-                self.last_update = Instant::now();
-            }
         }
 
         // Update the base simulation (handles visualization rendering)
@@ -351,32 +345,8 @@ impl haggis::simulation::traits::Simulation for ConwaysCpuSimulation {
 
                 ui.separator();
 
-                // Controls
-                if ui.button(if self.is_paused {
-                    "▶ Play"
-                } else {
-                    "⏸ Pause"
-                }) {
-                    self.is_paused = !self.is_paused;
-                }
-                ui.same_line();
-
-                if ui.button("⏭ Step") {
-                    self.needs_manual_step = true;
-                }
-
-                ui.separator();
-
-                // Speed control
-                ui.text("Simulation Speed:");
-                let mut speed_value = self.speed;
-                if ui
-                    .slider_config("Generations/sec", 0.1, 60.0)
-                    .display_format("%.1f gen/sec")
-                    .build(&mut speed_value)
-                {
-                    self.speed = speed_value;
-                }
+                // Play, pause and stepping live in the simulation toolbar
+                ui.text("Generations/sec: \"Steps/s\" in Simulation Control");
 
                 ui.separator();
 
@@ -385,10 +355,7 @@ impl haggis::simulation::traits::Simulation for ConwaysCpuSimulation {
                 if self.is_paused {
                     ui.text_colored([1.0, 1.0, 0.0, 1.0], "⏸ Paused");
                 } else {
-                    ui.text_colored(
-                        [0.0, 1.0, 0.0, 1.0],
-                        &format!("▶ Running ({:.1} gen/sec)", self.speed),
-                    );
+                    ui.text_colored([0.0, 1.0, 0.0, 1.0], "▶ Running");
                 }
 
                 ui.separator();
@@ -450,7 +417,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("Features:");
     println!("  • Classic Game of Life patterns (Glider, Blinker, Gosper Gun)");
     println!("  • Real-time visualization of cellular automaton (128x128)");
-    println!("  • Speed control: 0.1 to 60.0 generations per second");
+    println!("  • Generations per second from the step policy");
+    println!("  • Step by one (.) or by the toolbar count (Shift+.)");
     println!("  • Interactive pattern selection and controls");
     println!();
 
@@ -460,18 +428,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Create the dynamic Conway's Game of Life simulation
     let simulation = ConwaysCpuSimulation::new();
 
-    // Attach the simulation to the app
+    // Attach the simulation to the app, three generations per second
     app.attach_simulation(simulation);
+    app.set_step_policy(StepPolicy::StepsPerSecond(3.0));
 
-    // Add some basic 3D objects for context (same as cut_plane_demo)
-    app.add_object("examples/test/cube.obj")
-        .with_transform([0.0, 0.0, 0.0], 0.5, 0.0)
-        .with_name("Reference Cube at Origin");
-
-    // Add another cube for comparison at same position as plane
-    app.add_object("examples/test/cube.obj")
-        .with_transform([0.0, 2.0, 0.0], 0.3, 0.0)
-        .with_name("Reference Cube at Plane Position");
+    // The built-in grid gives orientation and scale; label it for context
+    app.show_grid_labels(true);
 
     // Run the application
     app.run();
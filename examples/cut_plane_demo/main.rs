@@ -64,15 +64,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Attach the simulation to the app
     app.attach_simulation(simulation);
 
-    // Add some basic 3D objects for context (optional)
-    app.add_object("examples/test/cube.obj")
-        .with_transform([0.0, 0.0, 0.0], 0.5, 0.0)
-        .with_name("Reference Cube at Origin");
-
-    // Add another cube for comparison at same position as plane
-    app.add_object("examples/test/cube.obj")
-        .with_transform([0.0, 2.0, 0.0], 0.3, 0.0)
-        .with_name("Reference Cube at Plane Position");
+    // The built-in grid gives orientation and scale; label it for context
+    app.show_grid_labels(true);
 
     // The simulation now handles its own UI through the BaseSimulation
     // No need for manual UI registration - the simulation manages its visualizations
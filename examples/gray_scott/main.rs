@@ -0,0 +1,22 @@
+//! # Gray-Scott Reaction-Diffusion
+//!
+//! Runs the built-in `GrayScott` template: two chemicals reacting and
+//! diffusing on a wrapping grid, stepped by a compute shader and shown as a
+//! heatmap. Pick a preset in the UI or tune the feed and kill rates by hand.
+//!
+//! ## Usage
+//!
+//! Run with: `cargo run --example gray_scott`
+
+use haggis::simulation::templates::{GrayScott, GrayScottPreset};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut app = haggis::default();
+
+    app.attach_simulation(GrayScott::new(256, 256).with_preset(GrayScottPreset::Coral));
+
+    app.show_performance_panel(true);
+    app.run();
+
+    Ok(())
+}
@@ -16,7 +16,8 @@
 //!
 //! 1. Stream step: Distribution functions propagate to neighboring cells
 //! 2. Collision step: BGK collision operator relaxes toward equilibrium
-//! 3. Boundary conditions: Bounce-back for walls, velocity for lid
+//! 3. Boundary conditions: Zou-He inlet and outlet, bounce-back for walls and
+//!    obstacles, from `haggis::simulation::boundary`
 //! 4. Vorticity calculation: Curl of velocity field for visualization
 //!
 //! ## Usage
@@ -25,8 +26,21 @@
 
 use haggis::prelude::*;
 use haggis::{
+    app::jobs::{JobHandle, JobSystem},
+    simulation::boundary::{
+        BoundaryBuffers, BoundaryCondition, BoundaryConditions, D3Q19_BOUNDARY_WGSL, D3Q19_WGSL,
+    },
+    simulation::forces::{projected_area, ForceCoefficients, ForceReference, MomentumExchange},
+    simulation::history::{FieldHistory, HistoryStorage},
+    simulation::stability::{check_field, suggest_tau, StabilityPolicy},
+    simulation::step_policy::StepPolicy,
+    simulation::tracer::{DyeField, DyeSource, VelocityField},
     simulation::BaseSimulation,
-    visualization::traits::VisualizationComponent,
+    visualization::{traits::VisualizationComponent, BoxFace, GridTransform, LineProbe, ProbeValue},
+    wgpu_utils::{
+        BufferRegistry, ChunkedReadback, ReadbackStatus, ShaderPreprocessor, UniformBuffer,
+        WgslStruct,
+    },
 };
 use cgmath::Vector3;
 
@@ -39,6 +53,9 @@ const GRID_DEPTH: u32 = GRID_SIZE;
 /// D3Q19 lattice model - 19 velocity directions
 const D3Q19_DIRECTIONS: u32 = 19;
 
+/// Vorticity slices kept for the history timeline
+const VORTICITY_HISTORY: usize = 240;
+
 /// LBM simulation parameters
 #[derive(Clone, Copy, Debug)]
 pub struct LbmParams {
@@ -54,6 +71,17 @@ pub struct LbmParams {
     pub reynolds: f32,
 }
 
+/// Parameters as seen by the collision shader, declared there from this struct
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable, WgslStruct)]
+#[wgsl(uniform)]
+struct LbmUniforms {
+    tau: f32,
+    inlet_velocity: f32,
+    outlet_pressure: f32,
+    sphere_radius: f32,
+}
+
 impl Default for LbmParams {
     fn default() -> Self {
         Self {
@@ -66,6 +94,33 @@ impl Default for LbmParams {
     }
 }
 
+impl LbmParams {
+    /// Shader-side parameter block
+    fn as_uniform(&self) -> LbmUniforms {
+        LbmUniforms {
+            tau: self.tau,
+            inlet_velocity: self.inlet_velocity,
+            outlet_pressure: self.outlet_pressure,
+            sphere_radius: self.sphere_radius,
+        }
+    }
+
+    /// Inlet on -X, outlet on +X, no-slip walls on the other faces
+    fn apply_to(&self, boundaries: &mut BoundaryConditions) {
+        boundaries.set_face(
+            BoxFace::NegX,
+            BoundaryCondition::VelocityInlet { velocity: [self.inlet_velocity, 0.0, 0.0] },
+        );
+        boundaries.set_face(
+            BoxFace::PosX,
+            BoundaryCondition::PressureOutlet { density: self.outlet_pressure },
+        );
+        for face in [BoxFace::NegY, BoxFace::PosY, BoxFace::NegZ, BoxFace::PosZ] {
+            boundaries.set_face(face, BoundaryCondition::NoSlip);
+        }
+    }
+}
+
 /// GPU resources for 3D LBM fluid simulation
 struct LbmGpuResources {
     // Compute pipelines
@@ -90,11 +145,14 @@ struct LbmGpuResources {
     velocity_buffer: wgpu::Buffer,   // 4 floats per cell: [vx, vy, vz, density]
     vorticity_buffer: wgpu::Buffer,  // 4 floats per cell: [ωx, ωy, ωz, magnitude]
     
-    // Boundary buffer - bit-packed obstacles (32 cells per u32)
-    boundary_buffer: wgpu::Buffer,   // u32 array with bit flags for boundaries
+    // Boundary mask (faces and obstacles) and face conditions
+    boundary_buffers: BoundaryBuffers,
+
+    // Force of the flow on the airfoil, summed on the GPU
+    forces: MomentumExchange,
     
     // Parameters buffer
-    params_buffer: wgpu::Buffer,
+    params_buffer: UniformBuffer<LbmUniforms>,
     
     // Bind groups for ping-pong
     stream_bind_group_a_to_b: wgpu::BindGroup,
@@ -125,6 +183,15 @@ struct LbmFluidSimulation {
     
     // GPU resources
     gpu_resources: Option<LbmGpuResources>,
+
+    // Vorticity readback, spread over several frames
+    vorticity_readback: Option<ChunkedReadback<f32>>,
+
+    // Obstacle voxelization, started in the background at construction
+    boundary_job: Option<JobHandle<Vec<u32>>>,
+
+    // Face conditions and obstacles
+    boundaries: BoundaryConditions,
     
     // Cut plane controls for vorticity visualization
     cut_plane_z: f32,
@@ -133,6 +200,18 @@ struct LbmFluidSimulation {
     
     // CPU backup for vorticity data (for cut plane extraction)
     cpu_vorticity: Vec<f32>, // 4 floats per cell
+
+    // Dye injected at the inlet and carried by the flow
+    dye: Option<DyeField>,
+    show_dye: bool,
+
+    // Recent vorticity slices, to step back to the onset of shedding
+    history: FieldHistory,
+
+    // Latest force on the airfoil and its coefficients
+    force: [f32; 3],
+    coefficients: ForceCoefficients,
+    frontal_area: f32,
 }
 
 /// Configuration for airfoil properties at different vertical positions
@@ -285,27 +364,29 @@ fn is_inside_winglets(dx: f32, dy: f32, fz: f32, chord: f32) -> bool {
 
 impl LbmFluidSimulation {
     /// Generate complex airfoil boundary pattern with vertical variation
+    ///
+    /// Z slices are voxelized in parallel on the job system.
     fn generate_vortex_generator_boundaries() -> Vec<u32> {
         let total_cells = (GRID_WIDTH * GRID_HEIGHT * GRID_DEPTH) as usize;
         let u32_count = (total_cells + 31) / 32; // Round up for bit packing
         let mut boundary_data = vec![0u32; u32_count];
-        
-        for z in 0..GRID_DEPTH {
+
+        let slices = JobSystem::global().parallel_map(GRID_DEPTH as usize, |z| {
+            let mut slice = Vec::with_capacity((GRID_WIDTH * GRID_HEIGHT) as usize);
             for y in 0..GRID_HEIGHT {
                 for x in 0..GRID_WIDTH {
-                    let cell_index = (z * GRID_HEIGHT * GRID_WIDTH + y * GRID_WIDTH + x) as usize;
-                    let u32_index = cell_index / 32;
-                    let bit_index = cell_index % 32;
-                    
-                    let is_boundary = Self::is_complex_airfoil_boundary(x, y, z);
-                    
-                    if is_boundary {
-                        boundary_data[u32_index] |= 1u32 << bit_index;
-                    }
+                    slice.push(Self::is_complex_airfoil_boundary(x, y, z as u32));
                 }
             }
+            slice
+        });
+
+        for (cell_index, is_boundary) in slices.into_iter().flatten().enumerate() {
+            if is_boundary {
+                boundary_data[cell_index / 32] |= 1u32 << (cell_index % 32);
+            }
         }
-        
+
         boundary_data
     }
     
@@ -361,6 +442,11 @@ impl LbmFluidSimulation {
         false
     }
 
+    /// Simulation grid mapped onto the cube scaled by `visualization_scale`
+    fn grid(&self) -> GridTransform {
+        GridTransform::centered([self.width, self.height, self.depth], self.visualization_scale)
+    }
+
     fn new() -> Self {
         let mut base = BaseSimulation::new("LBM Fluid 3D");
 
@@ -384,16 +470,33 @@ impl LbmFluidSimulation {
             is_paused: false,
             params: LbmParams::default(),
             gpu_resources: None,
+            vorticity_readback: None,
+            boundary_job: Some(JobSystem::global().spawn(Self::generate_vortex_generator_boundaries)),
+            boundaries: BoundaryConditions::new([GRID_WIDTH, GRID_HEIGHT, GRID_DEPTH]),
             cut_plane_z: 0.5,
             needs_cut_plane_update: true,
             visualization_scale: 1.0,
             cpu_vorticity: vec![0.0; (GRID_WIDTH * GRID_HEIGHT * GRID_DEPTH * 4) as usize],
+            dye: None,
+            show_dye: false,
+            history: FieldHistory::new(
+                "vorticity",
+                [GRID_WIDTH, GRID_HEIGHT, 1],
+                VORTICITY_HISTORY,
+                HistoryStorage::Compressed,
+            ),
+            force: [0.0; 3],
+            coefficients: ForceCoefficients::default(),
+            frontal_area: 0.0,
         };
 
-        // Set the cut plane size
+        // Place the cut plane over the grid
+        let grid = simulation.grid();
         if let Some(visualization) = simulation.base.get_visualization_mut("vorticity_plane") {
             if let Some(cut_plane) = visualization.as_any_mut().downcast_mut::<CutPlane2D>() {
-                cut_plane.set_size(simulation.visualization_scale);
+                cut_plane.set_grid_slice(&grid, simulation.cut_plane_z);
+                // Cut the airfoil markers open at the slice height
+                cut_plane.set_clip_geometry(true);
             }
         }
 
@@ -409,21 +512,36 @@ impl LbmFluidSimulation {
     fn initialize_gpu_resources(&mut self, device: &Device, queue: &Queue) {
         println!("🔧 Initializing LBM GPU compute resources...");
 
-        // Create shaders
-        let stream_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("LBM Stream Shader"),
-            source: wgpu::ShaderSource::Wgsl(LBM_STREAM_SHADER.into()),
-        });
-
-        let collision_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("LBM Collision Shader"),
-            source: wgpu::ShaderSource::Wgsl(LBM_COLLISION_SHADER.into()),
-        });
+        // Bit-packed obstacles (32 cells per u32) plus the face conditions
+        let obstacles = match self.boundary_job.take() {
+            Some(job) => job.wait(),
+            None => Self::generate_vortex_generator_boundaries(),
+        };
+        self.boundaries = self.boundaries.clone().with_obstacles(obstacles);
+        self.params.apply_to(&mut self.boundaries);
+        self.frontal_area = projected_area(&self.boundaries, 0);
 
-        let vorticity_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("LBM Vorticity Shader"),
-            source: wgpu::ShaderSource::Wgsl(LBM_VORTICITY_SHADER.into()),
-        });
+        // Create shaders
+        let shaders = ShaderPreprocessor::new()
+            .with_include("d3q19", D3Q19_WGSL)
+            .with_include("d3q19_boundary", D3Q19_BOUNDARY_WGSL)
+            .with_include("boundary", self.boundaries.wgsl(0, 3))
+            .with_include("lbm_uniforms", LbmUniforms::wgsl_struct())
+            .with_constant("GRID_WIDTH", GRID_WIDTH)
+            .with_constant("GRID_HEIGHT", GRID_HEIGHT)
+            .with_constant("GRID_DEPTH", GRID_DEPTH);
+
+        let stream_shader = shaders
+            .create_module(device, "LBM Stream Shader", LBM_STREAM_SHADER)
+            .expect("Invalid LBM stream shader");
+
+        let collision_shader = shaders
+            .create_module(device, "LBM Collision Shader", LBM_COLLISION_SHADER)
+            .expect("Invalid LBM collision shader");
+
+        let vorticity_shader = shaders
+            .create_module(device, "LBM Vorticity Shader", LBM_VORTICITY_SHADER)
+            .expect("Invalid LBM vorticity shader");
 
         // Create bind group layouts
         let stream_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -454,6 +572,8 @@ impl LbmFluidSimulation {
             ],
         });
 
+        let [boundary_mask_layout, boundary_params_layout] =
+            BoundaryConditions::bind_group_layout_entries(3, wgpu::ShaderStages::COMPUTE);
         let collision_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("LBM Collision Layout"),
             entries: &[
@@ -490,17 +610,9 @@ impl LbmFluidSimulation {
                     },
                     count: None,
                 },
-                // Boundary buffer (bit-packed)
-                wgpu::BindGroupLayoutEntry {
-                    binding: 3,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
+                // Boundary mask and face conditions
+                boundary_mask_layout,
+                boundary_params_layout,
             ],
         });
 
@@ -576,7 +688,6 @@ impl LbmFluidSimulation {
         let distributions_size = (self.width * self.height * self.depth * D3Q19_DIRECTIONS * std::mem::size_of::<f32>() as u32) as u64;
         let velocity_size = (self.width * self.height * self.depth * 4 * std::mem::size_of::<f32>() as u32) as u64;
         let vorticity_size = velocity_size; // Same size as velocity (4 floats per cell)
-        let params_size = 16u64; // 4 f32 values (16 bytes) for proper alignment
 
         let distributions_a = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("LBM Distributions A"),
@@ -606,25 +717,45 @@ impl LbmFluidSimulation {
             mapped_at_creation: false,
         });
 
-        // Create boundary buffer (bit-packed obstacles)
-        let boundary_data = Self::generate_vortex_generator_boundaries();
-        let boundary_size = (boundary_data.len() * std::mem::size_of::<u32>()) as u64;
-        let boundary_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("LBM Boundary Buffer"),
-            size: boundary_size,
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-        
-        // Upload boundary data
-        queue.write_buffer(&boundary_buffer, 0, bytemuck::cast_slice(&boundary_data));
+        // Create boundary mask and conditions
+        let boundary_buffers = BoundaryBuffers::new(device, queue, &self.boundaries);
+        let forces = MomentumExchange::new(
+            device,
+            &self.boundaries,
+            &boundary_buffers,
+            &[&distributions_a, &distributions_b],
+        );
 
-        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("LBM Parameters Buffer"),
-            size: params_size,
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
+        // Listed in the buffer inspector (F4)
+        let registry = BufferRegistry::global();
+        registry.register("LBM Distributions A", &distributions_a);
+        registry.register("LBM Distributions B", &distributions_b);
+        registry.register("LBM Velocity/Density", &velocity_buffer);
+        registry.register("LBM Vorticity", &vorticity_buffer);
+
+        // Dye streaks from the inlet, moved in lattice units of one cell per step
+        let mut dye = DyeField::new(device, self.grid(), VelocityField::new(&velocity_buffer, 4))
+            .with_decay(0.001);
+        dye.set_slice(self.cut_plane_z);
+        let mut dye_plane = dye.visualization();
+        dye_plane.set_enabled(self.show_dye);
+        self.base.add_visualization("dye_plane", dye_plane);
+        self.dye = Some(dye);
+        self.place_dye_sources();
+
+        // Streamwise velocity profile across the channel, behind the airfoil
+        let grid = self.grid();
+        let behind = grid.normalized_to_world(Vector3::new(0.75, 0.0, self.cut_plane_z));
+        let probe = LineProbe::new(
+            grid,
+            [behind.x, grid.min().y, behind.z],
+            [behind.x, grid.max().y, behind.z],
+        )
+        .with_buffer(&velocity_buffer, 4)
+        .with_value(ProbeValue::Component(0));
+        self.base.add_visualization("velocity_profile", probe);
+
+        let params_buffer = UniformBuffer::new_with_data(device, &self.params.as_uniform());
 
         // Create bind groups
         let stream_bind_group_a_to_b = device.create_bind_group(&wgpu::BindGroupDescriptor {
@@ -657,6 +788,7 @@ impl LbmFluidSimulation {
             ],
         });
 
+        let [boundary_mask_entry, boundary_params_entry] = boundary_buffers.bind_group_entries(3);
         let collision_bind_group_a = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("LBM Collision A"),
             layout: &collision_layout,
@@ -671,12 +803,10 @@ impl LbmFluidSimulation {
                 },
                 wgpu::BindGroupEntry {
                     binding: 2,
-                    resource: params_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 3,
-                    resource: boundary_buffer.as_entire_binding(),
+                    resource: params_buffer.binding_resource(),
                 },
+                boundary_mask_entry.clone(),
+                boundary_params_entry.clone(),
             ],
         });
 
@@ -694,12 +824,10 @@ impl LbmFluidSimulation {
                 },
                 wgpu::BindGroupEntry {
                     binding: 2,
-                    resource: params_buffer.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 3,
-                    resource: boundary_buffer.as_entire_binding(),
+                    resource: params_buffer.binding_resource(),
                 },
+                boundary_mask_entry,
+                boundary_params_entry,
             ],
         });
 
@@ -729,7 +857,8 @@ impl LbmFluidSimulation {
             distributions_b,
             velocity_buffer,
             vorticity_buffer,
-            boundary_buffer,
+            boundary_buffers,
+            forces,
             params_buffer,
             stream_bind_group_a_to_b,
             stream_bind_group_b_to_a,
@@ -742,9 +871,21 @@ impl LbmFluidSimulation {
         println!("✅ LBM GPU resources initialized successfully");
     }
 
+    /// Evenly spaced dye sources across the inlet, in the viewed layer
+    fn place_dye_sources(&mut self) {
+        let layer = self.grid().layer(2, self.cut_plane_z) as f32;
+        let Some(dye) = &mut self.dye else {
+            return;
+        };
+        dye.clear_sources();
+        for y in (self.height / 16..self.height).step_by(self.height as usize / 8) {
+            dye.add_source(DyeSource::new([2.0, y as f32, layer], 2.0, 0.2));
+        }
+    }
+
     /// Initialize LBM simulation with equilibrium distributions
-    fn initialize_simulation(&self, _device: &Device, queue: &Queue) {
-        if let Some(ref gpu_resources) = self.gpu_resources {
+    fn initialize_simulation(&mut self, _device: &Device, queue: &Queue) {
+        if let Some(gpu_resources) = &mut self.gpu_resources {
             // Initialize with rest state (zero velocity, unit density)
             let total_cells = (self.width * self.height * self.depth) as usize;
             let mut distributions = vec![0.0f32; total_cells * D3Q19_DIRECTIONS as usize];
@@ -772,13 +913,7 @@ impl LbmFluidSimulation {
             queue.write_buffer(&gpu_resources.distributions_b, 0, bytemuck::cast_slice(&distributions));
 
             // Upload parameters
-            let params_data = [
-                self.params.tau, 
-                self.params.inlet_velocity, 
-                self.params.outlet_pressure, 
-                self.params.sphere_radius
-            ];
-            queue.write_buffer(&gpu_resources.params_buffer, 0, bytemuck::cast_slice(&params_data));
+            gpu_resources.params_buffer.set_if_changed(queue, &self.params.as_uniform());
 
             println!("🌊 LBM simulation initialized with equilibrium state");
         }
@@ -862,6 +997,11 @@ impl LbmFluidSimulation {
                 vorticity_pass.dispatch_workgroups(num_workgroups_x, num_workgroups_y, num_workgroups_z);
             }
 
+            // Step 4: Carry the dye along the new velocities
+            if let Some(dye) = &mut self.dye {
+                dye.encode(queue, &mut encoder, 1.0);
+            }
+
             queue.submit(std::iter::once(encoder.finish()));
             self.generation += 1;
         }
@@ -869,7 +1009,7 @@ impl LbmFluidSimulation {
 
     /// Extract vorticity Z-component slice for directional visualization
     fn extract_vorticity_z_slice(&self, z_normalized: f32) -> Vec<f32> {
-        let z_index = ((z_normalized * (self.depth - 1) as f32).round() as u32).min(self.depth - 1);
+        let z_index = self.grid().layer(2, z_normalized);
         let slice_start = (z_index * self.height * self.width * 4) as usize; // 4 floats per cell
         let slice_size = (self.height * self.width) as usize;
         
@@ -892,59 +1032,57 @@ impl LbmFluidSimulation {
             return;
         }
 
-        // Extract vorticity slice at current cut plane position
-        let slice_data = self.extract_vorticity_z_slice(self.cut_plane_z);
+        // Extract vorticity slice at current cut plane position, or show the
+        // snapshot picked on the timeline
+        let slice_data = self
+            .history
+            .selected_cpu_values()
+            .unwrap_or_else(|| self.extract_vorticity_z_slice(self.cut_plane_z));
 
-        // Update cut plane position in 3D space
-        let world_z = (self.cut_plane_z - 0.5) * self.visualization_scale * 2.0;
+        let grid = self.grid();
 
         // Update visualization
         if let Some(visualization) = self.base.get_visualization_mut("vorticity_plane") {
             if let Some(cut_plane) = visualization.as_any_mut().downcast_mut::<CutPlane2D>() {
                 cut_plane.update_data(slice_data, self.width, self.height);
-                cut_plane.set_position(Vector3::new(0.0, 0.0, world_z));
-                cut_plane.set_size(self.visualization_scale);
+                cut_plane.set_grid_slice(&grid, self.cut_plane_z);
                 cut_plane.update(0.0, Some(device), Some(queue));
             }
         }
+
+        // Keep the dye slice and its sources on the same layer
+        if let Some(dye) = &mut self.dye {
+            dye.set_slice(self.cut_plane_z);
+        }
+        self.place_dye_sources();
+        if let Some(visualization) = self.base.get_visualization_mut("dye_plane") {
+            if let Some(dye_plane) = visualization.as_any_mut().downcast_mut::<CutPlane2D>() {
+                dye_plane.set_grid_slice(&grid, self.cut_plane_z);
+            }
+        }
     }
 
     /// Sync GPU vorticity data back to CPU for visualization
+    ///
+    /// Copies one chunk per call; the cut plane is only refreshed once every
+    /// chunk has arrived, so it never shows a half-updated field.
     fn sync_vorticity_to_cpu(&mut self, device: &Device, queue: &Queue) {
         if let Some(ref gpu_resources) = self.gpu_resources {
-            let buffer_size = (self.width * self.height * self.depth * 4 * std::mem::size_of::<f32>() as u32) as u64;
-            
-            let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-                label: Some("LBM Vorticity Staging"),
-                size: buffer_size,
-                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
-                mapped_at_creation: false,
-            });
-
-            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("LBM Vorticity Sync Encoder"),
-            });
-
-            encoder.copy_buffer_to_buffer(&gpu_resources.vorticity_buffer, 0, &staging_buffer, 0, buffer_size);
-            queue.submit(std::iter::once(encoder.finish()));
-
-            // Map and read the staging buffer
-            let buffer_slice = staging_buffer.slice(..);
-            let (tx, rx) = std::sync::mpsc::channel();
-            buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-                tx.send(result).unwrap();
-            });
-
-            let _ = device.poll(wgpu::MaintainBase::Wait);
-
-            if let Ok(Ok(())) = rx.recv() {
-                let data = buffer_slice.get_mapped_range();
-                let f32_data: &[f32] = bytemuck::cast_slice(&data);
-
+            let len = self.cpu_vorticity.len();
+            let readback = self
+                .vorticity_readback
+                .get_or_insert_with(|| ChunkedReadback::new(device, len, 4 << 20));
+            readback.start();
+
+            if readback.poll(device, queue, &gpu_resources.vorticity_buffer)
+                == ReadbackStatus::Complete
+            {
                 // Update CPU vorticity data
-                if self.cpu_vorticity.len() == f32_data.len() {
-                    self.cpu_vorticity.copy_from_slice(f32_data);
-                }
+                self.cpu_vorticity.copy_from_slice(readback.data());
+
+                // Keep the viewed slice for the timeline
+                let slice = self.extract_vorticity_z_slice(self.cut_plane_z);
+                self.history.record(self.generation, self.generation as f64, &slice);
 
                 // Update cut plane visualization
                 self.update_vorticity_cut_plane(device, queue);
@@ -960,8 +1098,9 @@ impl haggis::simulation::traits::Simulation for LbmFluidSimulation {
     }
 
     fn initialize_gpu(&mut self, device: &Device, queue: &Queue) {
-        self.base.initialize_gpu(device, queue);
+        // Resources first, they add the dye plane to the visualizations
         self.initialize_gpu_resources(device, queue);
+        self.base.initialize_gpu(device, queue);
         self.initialize_simulation(device, queue);
         self.sync_vorticity_to_cpu(device, queue);
         println!("✅ LBM GPU initialization complete");
@@ -969,18 +1108,16 @@ impl haggis::simulation::traits::Simulation for LbmFluidSimulation {
 
     fn update(&mut self, delta_time: f32, scene: &mut haggis::gfx::scene::Scene) {
         self.base.update(delta_time, scene);
+        scene.events.emit(MetricSample::new("drag_coefficient", self.coefficients.drag as f64));
+        scene.events.emit(MetricSample::new("lift_coefficient", self.coefficients.lift as f64));
     }
 
     fn update_gpu(&mut self, device: &Device, queue: &Queue, _delta_time: f32) {
-        // Update GPU parameters
-        if let Some(ref gpu_resources) = self.gpu_resources {
-            let params_data = [
-                self.params.tau, 
-                self.params.inlet_velocity, 
-                self.params.outlet_pressure, 
-                self.params.sphere_radius
-            ];
-            queue.write_buffer(&gpu_resources.params_buffer, 0, bytemuck::cast_slice(&params_data));
+        // Update GPU parameters, only written when a slider moved
+        self.params.apply_to(&mut self.boundaries);
+        if let Some(gpu_resources) = &mut self.gpu_resources {
+            gpu_resources.params_buffer.set_if_changed(queue, &self.params.as_uniform());
+            gpu_resources.boundary_buffers.update(queue, &self.boundaries);
         }
 
         // Handle cut plane updates
@@ -989,14 +1126,23 @@ impl haggis::simulation::traits::Simulation for LbmFluidSimulation {
             self.needs_cut_plane_update = false;
         }
 
-        // Run simulation continuously at maximum GPU effort
+        // One LBM step per call, the step policy sets how many run per frame
         if !self.is_paused && self.gpu_resources.is_some() {
             self.run_lbm_step(device, queue);
             
-            // Sync vorticity data every few steps for real-time vortex shedding
-            if self.generation % 3 == 0 {
-                self.sync_vorticity_to_cpu(device, queue);
+            // Stream vorticity back a chunk per frame for real-time vortex shedding
+            self.sync_vorticity_to_cpu(device, queue);
+        }
+
+        // Sum the force on the airfoil whenever the last sum has been read back
+        if let Some(gpu_resources) = &mut self.gpu_resources {
+            if let Some(force) = gpu_resources.forces.poll(device) {
+                let reference = ForceReference::new(1.0, self.params.inlet_velocity, self.frontal_area);
+                self.force = force;
+                self.coefficients = reference.coefficients(force);
             }
+            let current = gpu_resources.ping_pong_state as usize;
+            gpu_resources.forces.measure(device, queue, current);
         }
 
         self.base.update_gpu(device, queue, _delta_time);
@@ -1020,7 +1166,7 @@ impl haggis::simulation::traits::Simulation for LbmFluidSimulation {
                 
                 // Continuous GPU simulation
                 ui.text("💡 Continuous GPU Simulation");
-                ui.text("Maximum effort between frames");
+                ui.text("Steps per frame follow the step policy");
 
                 ui.separator();
 
@@ -1062,6 +1208,15 @@ impl haggis::simulation::traits::Simulation for LbmFluidSimulation {
                 let reynolds = self.params.inlet_velocity * self.params.sphere_radius * 2.0 / ((self.params.tau - 0.5) / 3.0);
                 ui.text(&format!("Reynolds Number: {:.1}", reynolds));
                 
+                ui.text(format!(
+                    "Force: ({:.4}, {:.4}, {:.4})",
+                    self.force[0], self.force[1], self.force[2]
+                ));
+                ui.text(format!(
+                    "Drag Cd: {:.3}  Lift Cl: {:.3}  (area {} cells)",
+                    self.coefficients.drag, self.coefficients.lift, self.frontal_area
+                ));
+
                 // Show flow regime
                 if reynolds < 20.0 {
                     ui.text_colored([0.7, 0.7, 0.7, 1.0], "Flow: Steady (no shedding)");
@@ -1088,9 +1243,32 @@ impl haggis::simulation::traits::Simulation for LbmFluidSimulation {
                     self.needs_cut_plane_update = true;
                 }
 
-                let z_layer = ((self.cut_plane_z * (GRID_DEPTH - 1) as f32).round() as u32).min(GRID_DEPTH - 1);
+                let z_layer = self.grid().layer(2, self.cut_plane_z);
                 ui.text(&format!("Viewing layer {}/{}", z_layer, GRID_DEPTH - 1));
 
+                // Dye shows where the flow goes, vorticity where it turns
+                if ui.checkbox("Show Dye Instead of Vorticity", &mut self.show_dye) {
+                    for (name, enabled) in [("dye_plane", self.show_dye), ("vorticity_plane", !self.show_dye)] {
+                        if let Some(visualization) = self.base.get_visualization_mut(name) {
+                            visualization.set_enabled(enabled);
+                        }
+                    }
+                }
+                if let Some(dye) = &mut self.dye {
+                    dye.render_ui(ui);
+                }
+
+                ui.separator();
+
+                // Scrubbing pauses the flow and shows the recorded slice
+                ui.text("Vorticity History:");
+                if self.history.render_ui(ui) {
+                    if self.history.is_scrubbing() {
+                        self.is_paused = true;
+                    }
+                    self.needs_cut_plane_update = true;
+                }
+
                 ui.separator();
 
 
@@ -1120,6 +1298,22 @@ impl haggis::simulation::traits::Simulation for LbmFluidSimulation {
         "LBM Fluid 3D"
     }
 
+    /// Diverged flows blow up the vorticity read back for the cut plane
+    fn check_stability(&self) -> Option<String> {
+        check_field("vorticity", &self.cpu_vorticity, 10.0)
+    }
+
+    fn stability_suggestions(&self) -> Vec<String> {
+        let mut suggestions: Vec<String> = suggest_tau(self.params.tau).into_iter().collect();
+        if self.params.inlet_velocity > 0.1 {
+            suggestions.push(format!(
+                "inlet velocity {:.3} is high for the lattice; keep it below 0.1",
+                self.params.inlet_velocity
+            ));
+        }
+        suggestions
+    }
+
     fn is_running(&self) -> bool {
         !self.is_paused
     }
@@ -1131,6 +1325,7 @@ impl haggis::simulation::traits::Simulation for LbmFluidSimulation {
     fn reset(&mut self, scene: &mut haggis::gfx::scene::Scene) {
         println!("🔄 Resetting LBM simulation");
         self.generation = 0;
+        self.history.clear();
         self.base.reset(scene);
     }
 
@@ -1141,37 +1336,17 @@ impl haggis::simulation::traits::Simulation for LbmFluidSimulation {
 
 // LBM compute shaders will be defined here
 const LBM_STREAM_SHADER: &str = r#"
+#include "d3q19"
+
 // D3Q19 lattice directions
 // 0: (0,0,0) - rest
 // 1-6: face neighbors ±x,±y,±z
 // 7-18: edge neighbors
 const D3Q19_DIRECTIONS: u32 = 19u;
-const GRID_WIDTH: u32 = 96u;
-const GRID_HEIGHT: u32 = 96u;
-const GRID_DEPTH: u32 = 96u;
-
-// D3Q19 velocity vectors
-const VELOCITY_SET: array<vec3<i32>, 19> = array<vec3<i32>, 19>(
-    vec3<i32>( 0,  0,  0),  // 0: rest
-    vec3<i32>( 1,  0,  0),  // 1: +x
-    vec3<i32>(-1,  0,  0),  // 2: -x
-    vec3<i32>( 0,  1,  0),  // 3: +y
-    vec3<i32>( 0, -1,  0),  // 4: -y
-    vec3<i32>( 0,  0,  1),  // 5: +z
-    vec3<i32>( 0,  0, -1),  // 6: -z
-    vec3<i32>( 1,  1,  0),  // 7: +x+y
-    vec3<i32>(-1, -1,  0),  // 8: -x-y
-    vec3<i32>( 1, -1,  0),  // 9: +x-y
-    vec3<i32>(-1,  1,  0),  // 10: -x+y
-    vec3<i32>( 1,  0,  1),  // 11: +x+z
-    vec3<i32>(-1,  0, -1),  // 12: -x-z
-    vec3<i32>( 1,  0, -1),  // 13: +x-z
-    vec3<i32>(-1,  0,  1),  // 14: -x+z
-    vec3<i32>( 0,  1,  1),  // 15: +y+z
-    vec3<i32>( 0, -1, -1),  // 16: -y-z
-    vec3<i32>( 0,  1, -1),  // 17: +y-z
-    vec3<i32>( 0, -1,  1),  // 18: -y+z
-);
+const GRID_WIDTH: u32 = {{GRID_WIDTH}}u;
+const GRID_HEIGHT: u32 = {{GRID_HEIGHT}}u;
+const GRID_DEPTH: u32 = {{GRID_DEPTH}}u;
+
 
 @group(0) @binding(0) var<storage, read> input_distributions: array<f32>;
 @group(0) @binding(1) var<storage, read_write> output_distributions: array<f32>;
@@ -1190,7 +1365,7 @@ fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
     
     // Stream each distribution function
     for (var i: u32 = 0u; i < D3Q19_DIRECTIONS; i++) {
-        let velocity = VELOCITY_SET[i];
+        let velocity = D3Q19_VELOCITIES[i];
         
         // Calculate source position (where this distribution came from)
         let src_x = (i32(x) - velocity.x + i32(GRID_WIDTH)) % i32(GRID_WIDTH);
@@ -1208,63 +1383,19 @@ fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
 "#;
 
 const LBM_COLLISION_SHADER: &str = r#"
+#include "boundary"
+#include "d3q19"
+#include "d3q19_boundary"
+#include "lbm_uniforms"
+
 const D3Q19_DIRECTIONS: u32 = 19u;
-const GRID_WIDTH: u32 = 96u;
-const GRID_HEIGHT: u32 = 96u;
-const GRID_DEPTH: u32 = 96u;
-
-// D3Q19 weights
-const WEIGHTS: array<f32, 19> = array<f32, 19>(
-    1.0/3.0,                                    // 0: rest
-    1.0/18.0, 1.0/18.0, 1.0/18.0,             // 1-3: face
-    1.0/18.0, 1.0/18.0, 1.0/18.0,             // 4-6: face
-    1.0/36.0, 1.0/36.0, 1.0/36.0,             // 7-9: edge
-    1.0/36.0, 1.0/36.0, 1.0/36.0,             // 10-12: edge
-    1.0/36.0, 1.0/36.0, 1.0/36.0,             // 13-15: edge
-    1.0/36.0, 1.0/36.0, 1.0/36.0,             // 16-18: edge
-);
-
-// D3Q19 velocity vectors
-const VELOCITY_SET: array<vec3<f32>, 19> = array<vec3<f32>, 19>(
-    vec3<f32>( 0.0,  0.0,  0.0),  // 0: rest
-    vec3<f32>( 1.0,  0.0,  0.0),  // 1: +x
-    vec3<f32>(-1.0,  0.0,  0.0),  // 2: -x
-    vec3<f32>( 0.0,  1.0,  0.0),  // 3: +y
-    vec3<f32>( 0.0, -1.0,  0.0),  // 4: -y
-    vec3<f32>( 0.0,  0.0,  1.0),  // 5: +z
-    vec3<f32>( 0.0,  0.0, -1.0),  // 6: -z
-    vec3<f32>( 1.0,  1.0,  0.0),  // 7: +x+y
-    vec3<f32>(-1.0, -1.0,  0.0),  // 8: -x-y
-    vec3<f32>( 1.0, -1.0,  0.0),  // 9: +x-y
-    vec3<f32>(-1.0,  1.0,  0.0),  // 10: -x+y
-    vec3<f32>( 1.0,  0.0,  1.0),  // 11: +x+z
-    vec3<f32>(-1.0,  0.0, -1.0),  // 12: -x-z
-    vec3<f32>( 1.0,  0.0, -1.0),  // 13: +x-z
-    vec3<f32>(-1.0,  0.0,  1.0),  // 14: -x+z
-    vec3<f32>( 0.0,  1.0,  1.0),  // 15: +y+z
-    vec3<f32>( 0.0, -1.0, -1.0),  // 16: -y-z
-    vec3<f32>( 0.0,  1.0, -1.0),  // 17: +y-z
-    vec3<f32>( 0.0, -1.0,  1.0),  // 18: -y+z
-);
+const GRID_WIDTH: u32 = {{GRID_WIDTH}}u;
+const GRID_HEIGHT: u32 = {{GRID_HEIGHT}}u;
+const GRID_DEPTH: u32 = {{GRID_DEPTH}}u;
 
 @group(0) @binding(0) var<storage, read_write> distributions: array<f32>;
 @group(0) @binding(1) var<storage, read_write> velocity_density: array<f32>; // [vx, vy, vz, density]
-@group(0) @binding(2) var<uniform> params: vec4<f32>; // [tau, inlet_velocity, outlet_pressure, sphere_radius]
-@group(0) @binding(3) var<storage, read> boundary_buffer: array<u32>; // bit-packed boundary flags
-
-// Check if cell is a boundary using bit-packed buffer
-fn is_boundary_cell(x: u32, y: u32, z: u32) -> bool {
-    let cell_index = z * GRID_HEIGHT * GRID_WIDTH + y * GRID_WIDTH + x;
-    let u32_index = cell_index / 32u;
-    let bit_index = cell_index % 32u;
-    
-    if (u32_index >= arrayLength(&boundary_buffer)) {
-        return false;
-    }
-    
-    let boundary_bits = boundary_buffer[u32_index];
-    return (boundary_bits & (1u << bit_index)) != 0u;
-}
+@group(0) @binding(2) var<uniform> params: LbmUniforms;
 
 @compute @workgroup_size(4, 4, 4)
 fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
@@ -1279,162 +1410,50 @@ fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
     let cell_index = z * GRID_HEIGHT * GRID_WIDTH + y * GRID_WIDTH + x;
     let base_dist_index = cell_index * D3Q19_DIRECTIONS;
     
-    // Parameters
-    let tau = params.x;
-    let inlet_velocity = params.y;
-    let outlet_pressure = params.z;
-    let sphere_radius = params.w;
+    // Parameters (inlet and outlet values reach the shader through the boundary conditions)
+    let tau = params.tau;
     
     // Calculate macroscopic quantities
+    var f: array<f32, 19>;
     var density = 0.0;
     var velocity = vec3<f32>(0.0);
     
     for (var i: u32 = 0u; i < D3Q19_DIRECTIONS; i++) {
-        let f_i = distributions[base_dist_index + i];
-        density += f_i;
-        velocity += f_i * VELOCITY_SET[i];
+        f[i] = distributions[base_dist_index + i];
+        density += f[i];
+        velocity += f[i] * d3q19_velocity(i);
     }
     
     velocity = velocity / density;
     
-    // Check boundary using bit-packed buffer (32 cells per u32)
-    let is_inside_obstacle = is_boundary_cell(x, y, z);
-    
-    // Apply boundary conditions
-    var is_boundary = false;
-    
-    // Inlet boundary (left wall, x = 0) - Zou-He velocity inlet
-    if (x == 0u && !is_inside_obstacle) {
-        velocity = vec3<f32>(inlet_velocity, 0.0, 0.0);
-        density = 1.0; // Density at inlet
-        is_boundary = true;
-        
-        // Zou-He inlet BC implementation
-        let rho = density;
-        let u = inlet_velocity;
-        let v = 0.0;
-        let w = 0.0;
-        
-        // Set equilibrium distributions for inlet
-        for (var i: u32 = 0u; i < D3Q19_DIRECTIONS; i++) {
-            let ci = VELOCITY_SET[i];
-            let weight = WEIGHTS[i];
-            let ci_dot_u = ci.x * u + ci.y * v + ci.z * w;
-            let u_dot_u = u * u + v * v + w * w;
-            distributions[base_dist_index + i] = weight * rho * (1.0 + 3.0 * ci_dot_u + 4.5 * ci_dot_u * ci_dot_u - 1.5 * u_dot_u);
-        }
-    }
-    
-    // Outlet boundary (right wall, x = GRID_WIDTH - 1) - Zou-He pressure outlet
-    else if (x == GRID_WIDTH - 1u && !is_inside_obstacle) {
-        density = outlet_pressure;
-        is_boundary = true;
-        
-        // Zou-He outlet BC implementation
-        let rho = density;
-        let u = velocity.x; // Use existing velocity
-        let v = velocity.y;
-        let w = velocity.z;
-        
-        // Set equilibrium distributions for outlet
-        for (var i: u32 = 0u; i < D3Q19_DIRECTIONS; i++) {
-            let ci = VELOCITY_SET[i];
-            let weight = WEIGHTS[i];
-            let ci_dot_u = ci.x * u + ci.y * v + ci.z * w;
-            let u_dot_u = u * u + v * v + w * w;
-            distributions[base_dist_index + i] = weight * rho * (1.0 + 3.0 * ci_dot_u + 4.5 * ci_dot_u * ci_dot_u - 1.5 * u_dot_u);
-        }
-    }
-    
-    // Solid walls (top/bottom/front/back) - bounce-back
-    else if (y == 0u || y == GRID_HEIGHT - 1u || z == 0u || z == GRID_DEPTH - 1u) {
-        velocity = vec3<f32>(0.0, 0.0, 0.0);
-        is_boundary = true;
-        
-        // Bounce-back BC  
-        for (var i: u32 = 1u; i < D3Q19_DIRECTIONS; i++) {
-            let opposite_i = get_opposite_direction(i);
-            if (i < opposite_i) { // Only swap once per pair
-                let temp = distributions[base_dist_index + i];
-                distributions[base_dist_index + i] = distributions[base_dist_index + opposite_i];
-                distributions[base_dist_index + opposite_i] = temp;
-            }
-        }
-    }
-    
-    // Vortex generator obstacles - bounce-back
-    else if (is_inside_obstacle) {
-        velocity = vec3<f32>(0.0, 0.0, 0.0);
-        is_boundary = true;
-        
-        // Bounce-back BC for vortex generator
-        for (var i: u32 = 1u; i < D3Q19_DIRECTIONS; i++) {
-            let opposite_i = get_opposite_direction(i);
-            if (i < opposite_i) { // Only swap once per pair
-                let temp = distributions[base_dist_index + i];
-                distributions[base_dist_index + i] = distributions[base_dist_index + opposite_i];
-                distributions[base_dist_index + opposite_i] = temp;
-            }
-        }
-    }
+    // Inlet, outlet, walls and obstacles; walls and obstacles bounce back without colliding
+    let is_wall = boundary_apply_d3q19(boundary_code(vec3<u32>(x, y, z)), &f, &density, &velocity);
     
     // Fluid domain - BGK collision
-    if (!is_boundary) {
+    if (!is_wall) {
         let omega = 1.0 / tau;
         
         for (var i: u32 = 0u; i < D3Q19_DIRECTIONS; i++) {
-            let ci = VELOCITY_SET[i];
-            let weight = WEIGHTS[i];
-            
-            // Equilibrium distribution
-            let ci_dot_u = dot(ci, velocity);
-            let u_dot_u = dot(velocity, velocity);
-            let f_eq = weight * density * (1.0 + 3.0 * ci_dot_u + 4.5 * ci_dot_u * ci_dot_u - 1.5 * u_dot_u);
-            
-            // BGK collision
-            let f_old = distributions[base_dist_index + i];
-            distributions[base_dist_index + i] = f_old - omega * (f_old - f_eq);
+            f[i] = f[i] - omega * (f[i] - d3q19_equilibrium(i, density, velocity));
         }
     }
     
+    for (var i: u32 = 0u; i < D3Q19_DIRECTIONS; i++) {
+        distributions[base_dist_index + i] = f[i];
+    }
+    
     // Store velocity and density for vorticity calculation
     velocity_density[cell_index * 4u + 0u] = velocity.x;
     velocity_density[cell_index * 4u + 1u] = velocity.y;
     velocity_density[cell_index * 4u + 2u] = velocity.z;
     velocity_density[cell_index * 4u + 3u] = density;
 }
-
-// Helper function to get opposite direction for bounce-back
-fn get_opposite_direction(i: u32) -> u32 {
-    // D3Q19 opposite direction mapping
-    switch i {
-        case 1u: { return 2u; }  // +x <-> -x
-        case 2u: { return 1u; }
-        case 3u: { return 4u; }  // +y <-> -y
-        case 4u: { return 3u; }
-        case 5u: { return 6u; }  // +z <-> -z
-        case 6u: { return 5u; }
-        case 7u: { return 8u; }  // +x+y <-> -x-y
-        case 8u: { return 7u; }
-        case 9u: { return 10u; } // +x-y <-> -x+y
-        case 10u: { return 9u; }
-        case 11u: { return 12u; } // +x+z <-> -x-z
-        case 12u: { return 11u; }
-        case 13u: { return 14u; } // +x-z <-> -x+z
-        case 14u: { return 13u; }
-        case 15u: { return 16u; } // +y+z <-> -y-z
-        case 16u: { return 15u; }
-        case 17u: { return 18u; } // +y-z <-> -y+z
-        case 18u: { return 17u; }
-        default: { return 0u; }   // Rest particle (no opposite)
-    }
-}
 "#;
 
 const LBM_VORTICITY_SHADER: &str = r#"
-const GRID_WIDTH: u32 = 96u;
-const GRID_HEIGHT: u32 = 96u;
-const GRID_DEPTH: u32 = 96u;
+const GRID_WIDTH: u32 = {{GRID_WIDTH}}u;
+const GRID_HEIGHT: u32 = {{GRID_HEIGHT}}u;
+const GRID_DEPTH: u32 = {{GRID_DEPTH}}u;
 
 @group(0) @binding(0) var<storage, read> velocity_density: array<f32>; // [vx, vy, vz, density]
 @group(0) @binding(1) var<storage, read_write> vorticity: array<f32>; // [ωx, ωy, ωz, magnitude]
@@ -1519,50 +1538,47 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Attach the simulation to the app
     app.attach_simulation(simulation);
 
-    // Add boundary markers to show domain extent
-    app.add_object("examples/test/cube.obj")
-        .with_transform([-1.0, -1.0, -1.0], 0.05, 0.0)
-        .with_name("Domain Corner 1");
-    
-    app.add_object("examples/test/cube.obj")
-        .with_transform([1.0, 1.0, 1.0], 0.05, 0.0)
-        .with_name("Domain Corner 2");
+    // Pause with suggestions if the vorticity field blows up
+    app.set_stability_policy(Some(StabilityPolicy::new().with_check_interval(60)));
+
+    // Spend up to 8 ms per frame on LBM steps, however fast frames render
+    app.set_step_policy(StepPolicy::TimeBudget(8.0));
+
+    // Outline the [-1, 1]³ domain, flow runs from -X to +X
+    let grid = GridTransform::centered([GRID_WIDTH, GRID_HEIGHT, GRID_DEPTH], 1.0);
+    app.add_visualization(
+        "domain",
+        grid.domain_box()
+            .with_ticks(0.25)
+            .with_face_label(BoxFace::NegX, "Inlet")
+            .with_face_label(BoxFace::PosX, "Outlet"),
+    );
 
     // Add airfoil obstacle markers (for visual reference)
     // The actual boundaries are handled by the bit-packed boundary buffer
     
     // Show main airfoil body at different vertical positions
-    let airfoil_center_x = GRID_WIDTH as f32 * 0.35; // Match shader position
-    let world_x = (airfoil_center_x / GRID_WIDTH as f32 - 0.5) * 2.0; // Convert to world coordinates
-    
-    // Airfoil main body
-    app.add_object("examples/test/cube.obj")
-        .with_transform([world_x, 0.0, -0.5], 0.25, 0.0) // Bottom section
-        .with_name("Airfoil Base");
-    
-    // Mid-section with flaps
-    app.add_object("examples/test/cube.obj")
-        .with_transform([world_x + 0.1, 0.0, 0.0], 0.2, 15.0) // Twisted middle
-        .with_name("Airfoil Mid+Flaps");
-    
-    // Top section with high-lift config
-    app.add_object("examples/test/cube.obj")
-        .with_transform([world_x + 0.15, 0.0, 0.5], 0.15, 30.0) // High AoA top
-        .with_name("Airfoil Top+Slats");
-    
-    // Leading edge slats (upper section only)
-    app.add_object("examples/test/cube.obj")
-        .with_transform([world_x - 0.2, 0.0, 0.3], 0.08, 0.0) // Slats
-        .with_name("Leading Slats");
-    
-    // Winglets at top
-    app.add_object("examples/test/cube.obj")
-        .with_transform([world_x + 0.2, -0.1, 0.7], 0.05, 45.0) // Left winglet
-        .with_name("Winglet L");
-    
-    app.add_object("examples/test/cube.obj")
-        .with_transform([world_x + 0.2, 0.1, 0.7], 0.05, -45.0) // Right winglet
-        .with_name("Winglet R");
+    let world_x = grid.normalized_to_world(Vector3::new(0.35, 0.5, 0.5)).x; // Match shader position
+    
+    // One prefab for the whole airfoil, each section placed relative to its center
+    let section = |name: &str, offset: [f32; 3], scale: f32, rotation_y: f32| {
+        Prefab::from_file("examples/test/cube.obj")
+            .with_name(name)
+            .with_offset(offset)
+            .with_scale(scale)
+            .with_rotation_y(rotation_y)
+    };
+    app.define_prefab(
+        "airfoil",
+        Prefab::group()
+            .with_child(section("Airfoil Base", [0.0, 0.0, -0.5], 0.25, 0.0)) // Bottom section
+            .with_child(section("Airfoil Mid+Flaps", [0.1, 0.0, 0.0], 0.2, 15.0)) // Twisted middle
+            .with_child(section("Airfoil Top+Slats", [0.15, 0.0, 0.5], 0.15, 30.0)) // High AoA top
+            .with_child(section("Leading Slats", [-0.2, 0.0, 0.3], 0.08, 0.0)) // Slats (upper section only)
+            .with_child(section("Winglet L", [0.2, -0.1, 0.7], 0.05, 45.0)) // Winglets at top
+            .with_child(section("Winglet R", [0.2, 0.1, 0.7], 0.05, -45.0)),
+    );
+    app.spawn_prefab("airfoil", [world_x, 0.0, 0.0]);
 
     // Set up UI
     app.set_ui(|ui, scene, selected_index| {
@@ -1607,6 +1623,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Run the application
     app.show_performance_panel(true);
+    app.bind_key(Key::F4, Action::ToggleBufferInspector);
     app.run();
 
     Ok(())
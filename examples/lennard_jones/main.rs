@@ -0,0 +1,28 @@
+//! # Lennard-Jones Molecular Dynamics
+//!
+//! Runs the built-in `LennardJones` template: 4000 atoms in a periodic box
+//! start on a crystal lattice and melt into a liquid under a thermostat. The
+//! UI plots the temperature, the energy per atom and the radial distribution
+//! function g(r). Lower the target temperature to watch the liquid condense,
+//! or turn the thermostat off to check energy conservation.
+//!
+//! ## Usage
+//!
+//! Run with: `cargo run --example lennard_jones`
+
+use haggis::simulation::templates::LennardJones;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut app = haggis::default();
+
+    app.attach_simulation(
+        LennardJones::new(4000)
+            .with_density(0.8)
+            .with_temperature(1.2),
+    );
+
+    app.show_performance_panel(true);
+    app.run();
+
+    Ok(())
+}
@@ -0,0 +1,18 @@
+//! # Project Config - A Scene Without Code
+//!
+//! Loads the window, camera, materials, objects and a heat diffusion
+//! simulation from `haggis.toml` next to this file. Edit the file and run
+//! again to change the setup; nothing here needs recompiling.
+//!
+//! ## Usage
+//!
+//! Run with: `cargo run --example project_config`
+
+fn main() -> haggis::Result<()> {
+    haggis::from_config(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/examples/project_config/haggis.toml"
+    ))?
+    .run();
+    Ok(())
+}
@@ -240,15 +240,6 @@ impl Simulation for QuickstartSimulation {
                 ui.slider("Ground Level", -2.0, 3.0, &mut self.ground_level);
                 ui.spacing();
 
-                // Control buttons
-                if ui.button("⏸️ Pause / ▶️ Play") {
-                    self.running = !self.running;
-                }
-                ui.same_line();
-                if ui.button("🔄 Reset") {
-                    self.reset_simulation();
-                }
-
                 ui.separator();
                 ui.text("💡 Tips:");
                 ui.text("• Adjust gravity to see different effects");
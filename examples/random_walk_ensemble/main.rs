@@ -0,0 +1,66 @@
+//! # Random Walk Ensemble
+//!
+//! Runs a thousand independent random walkers as a Monte Carlo ensemble.
+//! Each replica is a single walker starting in the middle of the grid; the
+//! mean of their occupancy fields is the probability density of the walk,
+//! spreading as a Gaussian, and the mean squared displacement grows linearly
+//! with the number of steps. Switch to "Replica 0" to see how little a single
+//! run says on its own.
+//!
+//! ## Usage
+//!
+//! Run with: `cargo run --example random_walk_ensemble`
+
+use haggis::simulation::ensemble::{Ensemble, EnsembleParams, StochasticModel};
+use rand::rngs::StdRng;
+use rand::Rng;
+
+const SIZE: i32 = 64;
+
+/// Walker on a square lattice, stopped by the edges of the grid
+struct Walker {
+    position: [i32; 2],
+}
+
+impl StochasticModel for Walker {
+    fn step(&mut self, rng: &mut StdRng) {
+        let [dx, dy] = [[1, 0], [-1, 0], [0, 1], [0, -1]][rng.random_range(0..4)];
+        self.position = [
+            (self.position[0] + dx).clamp(0, SIZE - 1),
+            (self.position[1] + dy).clamp(0, SIZE - 1),
+        ];
+    }
+
+    fn field_dims(&self) -> [u32; 2] {
+        [SIZE as u32, SIZE as u32]
+    }
+
+    fn observe(&self, field: &mut [f32]) {
+        field[(self.position[1] * SIZE + self.position[0]) as usize] = 1.0;
+    }
+
+    fn scalars(&self) -> Vec<(&'static str, f64)> {
+        let [dx, dy] = [self.position[0] - SIZE / 2, self.position[1] - SIZE / 2];
+        vec![
+            ("Squared displacement", (dx * dx + dy * dy) as f64),
+            ("X displacement", dx as f64),
+        ]
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut app = haggis::default();
+
+    let ensemble = Ensemble::new(1024, |_rng| Walker {
+        position: [SIZE / 2, SIZE / 2],
+    })
+    .with_name("Random Walk Ensemble")
+    .with_params(EnsembleParams { steps_per_frame: 2 });
+
+    app.attach_simulation(ensemble);
+
+    app.show_performance_panel(true);
+    app.run();
+
+    Ok(())
+}
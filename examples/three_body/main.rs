@@ -8,6 +8,7 @@
 //! ## Physics Implementation
 //! - Accurate Newtonian gravitational forces between all three bodies
 //! - Runge-Kutta 4th order integration for numerical stability
+//! - Double precision (`f64`) body state, converted to `f32` only for rendering
 //! - Conservation of energy and momentum (within numerical precision)
 //! - Realistic orbital mechanics with no artificial damping
 //!
@@ -30,11 +31,12 @@
 //! - Stable periodic solutions to chaotic systems
 
 use haggis::prelude::*;
+use haggis::simulation::integrators::{to_render, Integrator};
 
 /// Gravitational constant (scaled for the simulation)
 /// In reality, G ≈ 6.674 × 10^-11 m³ kg⁻¹ s⁻²
 /// We use a scaled value for stable, visible orbital motion
-const GRAVITATIONAL_CONSTANT: f32 = 1.0;
+const GRAVITATIONAL_CONSTANT: f64 = 1.0;
 
 /// Maximum number of trail points to store for each body
 const MAX_TRAIL_POINTS: usize = 1000;
@@ -43,11 +45,11 @@ const MAX_TRAIL_POINTS: usize = 1000;
 #[derive(Debug, Clone)]
 struct CelestialBody {
     /// Current position in 3D space
-    position: Vector3<f32>,
+    position: Vector3<f64>,
     /// Current velocity vector
-    velocity: Vector3<f32>,
+    velocity: Vector3<f64>,
     /// Mass of the body (affects gravitational force)
-    mass: f32,
+    mass: f64,
     /// Visual radius for rendering (not used in physics)
     radius: f32,
     /// Trail points showing the orbital path
@@ -59,9 +61,9 @@ struct CelestialBody {
 impl CelestialBody {
     /// Create a new celestial body with the given properties
     fn new(
-        position: Vector3<f32>,
-        velocity: Vector3<f32>,
-        mass: f32,
+        position: Vector3<f64>,
+        velocity: Vector3<f64>,
+        mass: f64,
         radius: f32,
         name: String,
     ) -> Self {
@@ -77,14 +79,14 @@ impl CelestialBody {
 
     /// Add current position to the orbital trail
     fn update_trail(&mut self) {
-        self.trail.push_back(self.position);
+        self.trail.push_back(to_render(self.position));
         if self.trail.len() > MAX_TRAIL_POINTS {
             self.trail.pop_front();
         }
     }
 
     /// Get current kinetic energy of this body
-    fn kinetic_energy(&self) -> f32 {
+    fn kinetic_energy(&self) -> f64 {
         0.5 * self.mass * self.velocity.magnitude2()
     }
 }
@@ -93,19 +95,19 @@ impl CelestialBody {
 #[derive(Debug, Clone)]
 struct OrbitalStatistics {
     /// Total kinetic energy of the system
-    kinetic_energy: f32,
+    kinetic_energy: f64,
     /// Total potential energy of the system
-    potential_energy: f32,
+    potential_energy: f64,
     /// Total energy (should be conserved)
-    total_energy: f32,
+    total_energy: f64,
     /// Total momentum of the system (should be conserved)
-    total_momentum: Vector3<f32>,
+    total_momentum: Vector3<f64>,
     /// Center of mass position
-    center_of_mass: Vector3<f32>,
+    center_of_mass: Vector3<f64>,
     /// System angular momentum
-    angular_momentum: Vector3<f32>,
+    angular_momentum: Vector3<f64>,
     /// Current orbital period estimate
-    estimated_period: f32,
+    estimated_period: f64,
 }
 
 /// Three-body orbital mechanics simulation
@@ -116,10 +118,15 @@ struct ThreeBodySimulation {
     time: f32,
     /// Integration time step (smaller = more accurate, slower)
     time_step: f32,
+    /// Time integration scheme
+    integrator: Integrator,
     /// Whether the simulation is currently running
     running: bool,
     /// Camera follow mode
     camera_follow_center: bool,
+    /// Whether the camera is following because of `camera_follow_center`,
+    /// so a follow started elsewhere is left alone
+    camera_following: bool,
     /// Orbital statistics
     stats: OrbitalStatistics,
     /// Trail rendering enabled
@@ -148,8 +155,10 @@ impl ThreeBodySimulation {
             bodies: Vec::new(),
             time: 0.0,
             time_step: 0.005, // Smaller timestep for better stability
+            integrator: Integrator::Rk4,
             running: true,
             camera_follow_center: true,
+            camera_following: false,
             stats: OrbitalStatistics {
                 kinetic_energy: 0.0,
                 potential_energy: 0.0,
@@ -223,8 +232,8 @@ impl ThreeBodySimulation {
         // Equilateral triangle configuration
         let angles = [
             0.0,
-            2.0 * std::f32::consts::PI / 3.0,
-            4.0 * std::f32::consts::PI / 3.0,
+            2.0 * std::f64::consts::PI / 3.0,
+            4.0 * std::f64::consts::PI / 3.0,
         ];
         let names = ["Alpha", "Beta", "Gamma"];
         let masses = [1.0, 1.0, 1.0]; // Equal masses for stability
@@ -376,70 +385,20 @@ impl ThreeBodySimulation {
         };
     }
 
-    /// Update physics using Runge-Kutta 4th order integration
+    /// Update physics with the selected integrator, in double precision
     fn update_physics(&mut self, dt: f32) {
-        let scaled_dt = dt * self.time_multiplier;
-
-        // Store initial state
-        let initial_positions: Vec<Vector3<f32>> = self.bodies.iter().map(|b| b.position).collect();
-        let initial_velocities: Vec<Vector3<f32>> =
-            self.bodies.iter().map(|b| b.velocity).collect();
-
-        // RK4 integration
-        let k1_v = self.compute_accelerations(&initial_positions);
-        let k1_r: Vec<Vector3<f32>> = initial_velocities.clone();
-
-        // Compute k2
-        let pos_k2: Vec<Vector3<f32>> = initial_positions
-            .iter()
-            .zip(k1_r.iter())
-            .map(|(pos, vel)| pos + vel * (scaled_dt / 2.0))
-            .collect();
-        let vel_k2: Vec<Vector3<f32>> = initial_velocities
-            .iter()
-            .zip(k1_v.iter())
-            .map(|(vel, acc)| vel + acc * (scaled_dt / 2.0))
-            .collect();
-
-        let k2_v = self.compute_accelerations(&pos_k2);
-        let k2_r = vel_k2;
-
-        // Compute k3
-        let pos_k3: Vec<Vector3<f32>> = initial_positions
-            .iter()
-            .zip(k2_r.iter())
-            .map(|(pos, vel)| pos + vel * (scaled_dt / 2.0))
-            .collect();
-        let vel_k3: Vec<Vector3<f32>> = initial_velocities
-            .iter()
-            .zip(k2_v.iter())
-            .map(|(vel, acc)| vel + acc * (scaled_dt / 2.0))
-            .collect();
-
-        let k3_v = self.compute_accelerations(&pos_k3);
-        let k3_r = vel_k3;
-
-        // Compute k4
-        let pos_k4: Vec<Vector3<f32>> = initial_positions
-            .iter()
-            .zip(k3_r.iter())
-            .map(|(pos, vel)| pos + vel * scaled_dt)
-            .collect();
-        let vel_k4: Vec<Vector3<f32>> = initial_velocities
-            .iter()
-            .zip(k3_v.iter())
-            .map(|(vel, acc)| vel + acc * scaled_dt)
-            .collect();
-
-        let k4_v = self.compute_accelerations(&pos_k4);
-        let k4_r = vel_k4;
-
-        // Apply RK4 update
-        for (i, body) in self.bodies.iter_mut().enumerate() {
-            body.position = initial_positions[i]
-                + (k1_r[i] + k2_r[i] * 2.0 + k3_r[i] * 2.0 + k4_r[i]) * (scaled_dt / 6.0);
-            body.velocity = initial_velocities[i]
-                + (k1_v[i] + k2_v[i] * 2.0 + k3_v[i] * 2.0 + k4_v[i]) * (scaled_dt / 6.0);
+        let scaled_dt = f64::from(dt * self.time_multiplier);
+
+        let mut positions: Vec<Vector3<f64>> = self.bodies.iter().map(|b| b.position).collect();
+        let mut velocities: Vec<Vector3<f64>> = self.bodies.iter().map(|b| b.velocity).collect();
+        self.integrator
+            .step(&mut positions, &mut velocities, scaled_dt, |positions, _| {
+                self.compute_accelerations(positions)
+            });
+
+        for ((body, position), velocity) in self.bodies.iter_mut().zip(positions).zip(velocities) {
+            body.position = position;
+            body.velocity = velocity;
 
             // Update trail
             if self.show_trails {
@@ -450,7 +409,7 @@ impl ThreeBodySimulation {
 
     /// Compute gravitational accelerations for all bodies
     /// This is the core physics calculation - must be exact for stability
-    fn compute_accelerations(&self, positions: &[Vector3<f32>]) -> Vec<Vector3<f32>> {
+    fn compute_accelerations(&self, positions: &[Vector3<f64>]) -> Vec<Vector3<f64>> {
         let mut accelerations = vec![Vector3::zero(); positions.len()];
 
         // Calculate pairwise gravitational forces
@@ -493,11 +452,26 @@ impl ThreeBodySimulation {
         accelerations
     }
 
+    /// Mass-weighted center of the system
+    fn center_of_mass(&self) -> Vector3<f32> {
+        let total_mass: f64 = self.bodies.iter().map(|body| body.mass).sum();
+        if total_mass <= 0.0 {
+            return Vector3::zero();
+        }
+        to_render(
+            self.bodies
+                .iter()
+                .fold(Vector3::zero(), |acc, body| acc + body.position * body.mass)
+                / total_mass,
+        )
+    }
+
     /// Synchronize simulation bodies with visual objects
     fn sync_to_scene(&self, scene: &mut Scene) {
         for (i, body) in self.bodies.iter().enumerate() {
             if let Some(object) = scene.objects.get_mut(i) {
-                object.ui_transform.position = [body.position.x, body.position.y, body.position.z];
+                let position = to_render(body.position);
+                object.ui_transform.position = [position.x, position.y, position.z];
 
                 // Note: Visual scale is set in the initial object creation
                 // Individual scaling during runtime isn't needed for this example
@@ -538,7 +512,7 @@ impl Simulation for ThreeBodySimulation {
         println!("   Configuration: {:?}", self.configuration);
         println!("   Bodies: {}", self.bodies.len());
         println!("   Gravitational constant: {}", GRAVITATIONAL_CONSTANT);
-        println!("   Integration method: Runge-Kutta 4th Order");
+        println!("   Integration method: {} (f64 state)", self.integrator.name());
         println!();
         println!("📖 This simulation demonstrates:");
         println!("   • Multi-body gravitational interactions");
@@ -572,6 +546,15 @@ impl Simulation for ThreeBodySimulation {
 
         // Sync with visual scene
         self.sync_to_scene(scene);
+
+        // Keep the orbit camera centred on the system's center of mass
+        if self.camera_follow_center {
+            scene.camera_manager.set_follow_point(self.center_of_mass());
+            self.camera_following = true;
+        } else if self.camera_following {
+            scene.camera_manager.stop_following();
+            self.camera_following = false;
+        }
     }
 
     fn render_ui(&mut self, ui: &Ui) {
@@ -628,6 +611,14 @@ impl Simulation for ThreeBodySimulation {
                 ui.text("Simulation Controls:");
                 ui.slider("Time Multiplier", 0.1, 2.0, &mut self.time_multiplier);
                 ui.slider("Integration Step", 0.001, 0.02, &mut self.time_step);
+                let mut integrator_index = Integrator::ALL
+                    .iter()
+                    .position(|integrator| *integrator == self.integrator)
+                    .unwrap_or(0);
+                let names = Integrator::ALL.map(|integrator| integrator.name());
+                if ui.combo_simple_string("Integrator", &mut integrator_index, &names) {
+                    self.integrator = Integrator::ALL[integrator_index];
+                }
                 ui.checkbox("Show Orbital Trails", &mut self.show_trails);
                 ui.checkbox("Camera Follow Center", &mut self.camera_follow_center);
 
@@ -641,15 +632,6 @@ impl Simulation for ThreeBodySimulation {
                 ui.text(&format!("Energy Drift: {:.4}%", energy_change));
                 ui.spacing();
 
-                // Control buttons
-                if ui.button("⏸️ Pause / ▶️ Play") {
-                    self.running = !self.running;
-                }
-                ui.same_line();
-                if ui.button("🔄 Reset System") {
-                    self.set_configuration(self.configuration);
-                }
-
                 ui.separator();
                 ui.text("💡 Try different configurations!");
                 ui.text("Figure-8 is the most visually striking.");
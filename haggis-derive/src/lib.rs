@@ -0,0 +1,371 @@
+//! Derive macros for the haggis simulation engine.
+//!
+//! This crate is re-exported by `haggis`; use the macros through
+//! `haggis::simulation::params` rather than depending on it directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Expr, Fields, LitStr, RangeLimits};
+
+/// Derives `haggis::simulation::params::SimParams` for a struct with named fields.
+///
+/// Every field becomes a parameter unless marked `#[param(skip)]`, and must
+/// implement `ParamField` (`f32`, `f64`, `i32`, `u32`, `usize`, `bool`).
+///
+/// Field attributes:
+/// - `range = 0.0..=1.0` - slider bounds, values are clamped to this range
+/// - `step = 0.01` - drag speed / input step
+/// - `label = "Time Step"` - UI label, defaults to the title-cased field name
+/// - `skip` - exclude the field
+#[proc_macro_derive(SimParams, attributes(param))]
+pub fn derive_sim_params(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+struct ParamAttrs {
+    min: Option<Expr>,
+    max: Option<Expr>,
+    step: Option<Expr>,
+    label: Option<String>,
+    skip: bool,
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "SimParams can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "SimParams requires a struct with named fields",
+        ));
+    };
+
+    let mut descriptors = Vec::new();
+    let mut getters = Vec::new();
+    let mut setters = Vec::new();
+
+    for field in &fields.named {
+        let attrs = parse_attrs(field)?;
+        if attrs.skip {
+            continue;
+        }
+
+        let ident = field.ident.as_ref().expect("named field");
+        let index = descriptors.len();
+        let field_name = ident.to_string();
+        let label = attrs.label.unwrap_or_else(|| title_case(&field_name));
+        let min = optional_f64(&attrs.min);
+        let max = optional_f64(&attrs.max);
+        let step = optional_f64(&attrs.step);
+
+        descriptors.push(quote! {
+            ::haggis::simulation::params::ParamDescriptor {
+                name: #field_name,
+                label: #label,
+                min: #min,
+                max: #max,
+                step: #step,
+            }
+        });
+        getters.push(quote! {
+            #index => ::core::option::Option::Some(
+                ::haggis::simulation::params::ParamField::to_param_value(&self.#ident)
+            ),
+        });
+        setters.push(quote! {
+            #index => ::haggis::simulation::params::ParamField::set_param_value(&mut self.#ident, value),
+        });
+    }
+
+    Ok(quote! {
+        impl #impl_generics ::haggis::simulation::params::SimParams for #name #ty_generics #where_clause {
+            fn descriptors(&self) -> &'static [::haggis::simulation::params::ParamDescriptor] {
+                const DESCRIPTORS: &[::haggis::simulation::params::ParamDescriptor] = &[
+                    #(#descriptors),*
+                ];
+                DESCRIPTORS
+            }
+
+            fn value(&self, index: usize) -> ::core::option::Option<::haggis::simulation::params::ParamValue> {
+                match index {
+                    #(#getters)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+
+            fn set_value(&mut self, index: usize, value: ::haggis::simulation::params::ParamValue) -> bool {
+                match index {
+                    #(#setters)*
+                    _ => false,
+                }
+            }
+        }
+    })
+}
+
+fn parse_attrs(field: &syn::Field) -> syn::Result<ParamAttrs> {
+    let mut attrs = ParamAttrs {
+        min: None,
+        max: None,
+        step: None,
+        label: None,
+        skip: false,
+    };
+
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("param")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                attrs.skip = true;
+            } else if meta.path.is_ident("range") {
+                let expr: Expr = meta.value()?.parse()?;
+                let Expr::Range(range) = expr else {
+                    return Err(meta.error("expected a range like `0.0..=1.0`"));
+                };
+                if !matches!(range.limits, RangeLimits::Closed(_)) {
+                    return Err(meta.error("parameter ranges must be inclusive (`..=`)"));
+                }
+                attrs.min = range.start.map(|e| *e);
+                attrs.max = range.end.map(|e| *e);
+            } else if meta.path.is_ident("step") {
+                attrs.step = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("label") {
+                let label: LitStr = meta.value()?.parse()?;
+                attrs.label = Some(label.value());
+            } else {
+                return Err(meta.error("unknown param attribute, expected `range`, `step`, `label` or `skip`"));
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(attrs)
+}
+
+fn optional_f64(expr: &Option<Expr>) -> TokenStream2 {
+    match expr {
+        Some(expr) => quote! { ::core::option::Option::Some((#expr) as f64) },
+        None => quote! { ::core::option::Option::None },
+    }
+}
+
+/// `time_step` -> `Time Step`
+fn title_case(name: &str) -> String {
+    name.split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Derives `haggis::wgpu_utils::WgslStruct` (and `WgslType`) for a
+/// `#[repr(C)]` struct with named fields.
+///
+/// Every field type must implement `WgslType`. The generated code checks at
+/// compile time that each field offset and the struct size agree with WGSL's
+/// storage buffer layout, so misplaced padding is a build error.
+///
+/// Struct attributes:
+/// - `name = "Particle"` - WGSL struct name, defaults to the Rust name
+/// - `uniform` - also check the uniform buffer layout rules
+#[proc_macro_derive(WgslStruct, attributes(wgsl))]
+pub fn derive_wgsl_struct(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand_wgsl_struct(input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+fn expand_wgsl_struct(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "WgslStruct can't be derived for generic structs",
+        ));
+    }
+
+    let mut repr_c = false;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("repr")) {
+        attr.parse_nested_meta(|meta| {
+            repr_c |= meta.path.is_ident("C");
+            Ok(())
+        })?;
+    }
+    if !repr_c {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "WgslStruct requires #[repr(C)]",
+        ));
+    }
+
+    let mut wgsl_name = name.to_string();
+    let mut uniform = false;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("wgsl")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                let value: LitStr = meta.value()?.parse()?;
+                wgsl_name = value.value();
+            } else if meta.path.is_ident("uniform") {
+                uniform = true;
+            } else {
+                return Err(meta.error("unknown wgsl attribute, expected `name` or `uniform`"));
+            }
+            Ok(())
+        })?;
+    }
+
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "WgslStruct can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "WgslStruct requires a struct with named fields",
+        ));
+    };
+
+    let module = quote! { ::haggis::wgpu_utils::wgsl_struct };
+    let mut field_entries = Vec::new();
+    let mut storage_checks = Vec::new();
+    let mut uniform_layouts = Vec::new();
+    let mut uniform_checks = Vec::new();
+    let mut storage_aligns = Vec::new();
+
+    for field in &fields.named {
+        let ident = field.ident.as_ref().expect("named field");
+        let ty = &field.ty;
+        let field_name = ident.to_string();
+        let offset_message = format!(
+            "{name}.{field_name}: WGSL places this field at a different offset than Rust, add explicit padding fields"
+        );
+        let uniform_message = format!(
+            "{name}.{field_name}: type can't be used in a uniform buffer (array strides must be multiples of 16)"
+        );
+
+        field_entries.push(quote! {
+            (#field_name, <#ty as #module::WgslType>::wgsl_type())
+        });
+        storage_aligns.push(quote! { <#ty as #module::WgslType>::STORAGE.align });
+        storage_checks.push(quote! {
+            let layout = <#ty as #module::WgslType>::STORAGE;
+            offset = #module::align_to(offset, layout.align);
+            assert!(offset == ::core::mem::offset_of!(#name, #ident), #offset_message);
+            offset += layout.size;
+        });
+        uniform_layouts.push(quote! { <#ty as #module::WgslType>::UNIFORM });
+        uniform_checks.push(quote! {
+            let layout = match <#ty as #module::WgslType>::UNIFORM {
+                ::core::option::Option::Some(layout) => layout,
+                ::core::option::Option::None => panic!(#uniform_message),
+            };
+            offset = #module::align_to(offset, layout.align);
+            assert!(offset == ::core::mem::offset_of!(#name, #ident), #offset_message);
+            offset += layout.size;
+        });
+    }
+
+    let size_message = format!(
+        "{name}: WGSL rounds the struct size up to its alignment, add padding fields at the end"
+    );
+    let uniform_check = if uniform {
+        quote! {
+            const _: () = {
+                let mut offset = 0usize;
+                #(#uniform_checks)*
+                let align = #module::align_to(<#name as #module::WgslType>::STORAGE.align, 16);
+                assert!(
+                    #module::align_to(offset, align) == ::core::mem::size_of::<#name>(),
+                    #size_message
+                );
+            };
+        }
+    } else {
+        quote! {}
+    };
+
+    Ok(quote! {
+        impl #module::WgslType for #name {
+            const STORAGE: #module::WgslLayout = {
+                let aligns: &[usize] = &[#(#storage_aligns),*];
+                let mut align = 1;
+                let mut i = 0;
+                while i < aligns.len() {
+                    if aligns[i] > align {
+                        align = aligns[i];
+                    }
+                    i += 1;
+                }
+                #module::WgslLayout::new(align, ::core::mem::size_of::<#name>())
+            };
+            const UNIFORM: ::core::option::Option<#module::WgslLayout> = {
+                let layouts: &[::core::option::Option<#module::WgslLayout>] = &[#(#uniform_layouts),*];
+                let mut align = 16;
+                let mut i = 0;
+                let mut compatible = true;
+                while i < layouts.len() {
+                    match layouts[i] {
+                        ::core::option::Option::Some(layout) => {
+                            if layout.align > align {
+                                align = layout.align;
+                            }
+                        }
+                        ::core::option::Option::None => compatible = false,
+                    }
+                    i += 1;
+                }
+                let size = ::core::mem::size_of::<#name>();
+                if compatible && size % align == 0 {
+                    ::core::option::Option::Some(#module::WgslLayout::new(align, size))
+                } else {
+                    ::core::option::Option::None
+                }
+            };
+
+            fn wgsl_type() -> ::std::string::String {
+                ::std::string::String::from(#wgsl_name)
+            }
+        }
+
+        impl #module::WgslStruct for #name {
+            const WGSL_NAME: &'static str = #wgsl_name;
+
+            fn wgsl_fields() -> ::std::vec::Vec<(&'static str, ::std::string::String)> {
+                ::std::vec![#(#field_entries),*]
+            }
+        }
+
+        const _: () = {
+            let mut offset = 0usize;
+            #(#storage_checks)*
+            let align = <#name as #module::WgslType>::STORAGE.align;
+            assert!(
+                #module::align_to(offset, align) == ::core::mem::size_of::<#name>(),
+                #size_message
+            );
+        };
+
+        #uniform_check
+    })
+}
@@ -13,6 +13,8 @@
 //! - [`HaggisApp`] - Main application struct with builder pattern configuration
 //! - [`AppState`] - Internal state management for graphics, UI, and simulation
 //! - [`UiCallback`] - Type alias for user-defined UI callback functions
+//! - [`jobs::JobSystem`] - Thread pool for background and data-parallel work
+//! - [`session::Session`] - Window, camera, UI and parameters kept between runs
 //!
 //! ## Event Handling
 //!
@@ -44,6 +46,12 @@
 //! app.run();
 //! ```
 
+pub mod builder;
+pub mod jobs;
+pub mod pacing;
+pub mod session;
+
+use builder::{HaggisAppBuilder, WindowConfig};
 use cgmath::Vector3;
 use std::sync::Arc;
 use winit::{
@@ -51,25 +59,53 @@ use winit::{
     dpi::PhysicalSize,
     event::WindowEvent,
     event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
-    window::{Window, WindowAttributes},
+    window::{Fullscreen, Window, WindowAttributes},
 };
 
 use crate::{
+    console::{CommandResult, CommandRegistry, Console},
+    error::{HaggisError, Result},
+    events::{
+        BrushStroke, DragEvent, DragPhase, EventBus, EventReceiver, MetricSample, PickEvent, SimulationEvent,
+        WindowResizeEvent,
+    },
+    input::{Action, InputMap, KeyChord},
+    logging::LogWindow,
     gfx::{
         camera::{
-            camera_controller::CameraController, camera_utils::CameraManager,
-            orbit_camera::OrbitCamera,
+            camera_controller::CameraController, camera_follow::FollowTarget,
+            camera_utils::CameraManager, orbit_camera::OrbitCamera,
         },
+        coordinates::CoordinateSystem,
+        measure::MeasureTool,
+        overlay::OverlayConfig,
         picking::ObjectPicker,
-        rendering::render_engine::RenderEngine,
-        scene::{object::ObjectBuilder, scene::Scene},
+        rendering::{
+            render_config::RenderConfig,
+            render_engine::RenderEngine,
+            render_settings::RenderSettings,
+            render_texture::RenderTexture,
+            viewport::{Viewport, ViewportRect},
+        },
+        scene::{object::ObjectBuilder, prefab::Prefab, scene::Scene, Annotation},
     },
     performance::PerformanceMonitor,
-    simulation::{manager::SimulationManager, traits::Simulation},
-    ui::{manager::UiManager, panel::default_transform_panel, UiFont, UiStyle},
+    simulation::{
+        manager::SimulationManager, stability::StabilityPolicy, step_policy::StepPolicy,
+        traits::Simulation,
+    },
+    ui::{
+        manager::UiManager,
+        notifications::{Notification, NotificationLevel, Notifications},
+        panel::default_transform_panel,
+        BufferInspector, DockLayout, FallbackFont, ThemeEditor, UiFont, UiStyle, UI_SCALE_RANGE,
+    },
     visualization::{manager::VisualizationManager, traits::VisualizationComponent},
 };
 
+/// UI scale change per [`Action::IncreaseUiScale`] or [`Action::DecreaseUiScale`]
+const UI_SCALE_STEP: f32 = 0.1;
+
 /// UI callback function signature for custom user interface rendering.
 ///
 /// This type defines the signature for user-provided UI callback functions that are called
@@ -167,8 +203,18 @@ pub struct AppState {
     pub ui_style: UiStyle,
     /// UI font configuration
     pub ui_font: UiFont,
+    /// Fonts merged into the UI font for glyphs it lacks
+    pub ui_fallback_fonts: Vec<FallbackFont>,
+    /// User UI scale on top of the window's scale factor
+    pub ui_scale: f32,
     /// Whether to show the default transform panel
     pub show_transform_panel: bool,
+    /// Default docking layout applied when no saved layout exists
+    pub ui_default_layout: Option<DockLayout>,
+    /// File used to persist window/docking layouts (None = no persistence)
+    pub ui_layout_file: Option<std::path::PathBuf>,
+    /// TOML theme loaded over the UI style at startup
+    pub ui_theme_file: Option<std::path::PathBuf>,
     /// 3D scene containing objects, materials, and camera
     pub scene: Scene,
     /// User-defined UI callback function
@@ -184,20 +230,87 @@ pub struct AppState {
     pub performance_monitor: PerformanceMonitor,
     /// Whether to show the performance metrics panel
     pub show_performance_panel: bool,
+    /// Whether to show the simulation toolbar while a simulation is attached
+    pub show_simulation_toolbar: bool,
+    /// Whether to show the clear color, background and fog panel
+    pub show_render_settings_panel: bool,
     /// Enable VSync for smoother visuals vs higher FPS
     pub enable_vsync: bool,
     /// Framerate limit (None = unlimited, Some(fps) = limited)
     pub framerate_limit: Option<f32>,
-    /// Frame timing for FPS limiting
-    last_frame_time: std::time::Instant,
+    /// Schedules redraws at the framerate limit
+    frame_pacer: pacing::FramePacer,
     /// Frame timing for performance monitoring (tracks actual frame cycle)
-    last_performance_frame_time: std::time::Instant,
+    last_performance_frame_time: crate::platform::Instant,
     /// Object picker for mouse selection
     pub object_picker: ObjectPicker,
     /// Current mouse position for picking
     mouse_position: (f32, f32),
     /// Whether UI captured input in the last frame
     ui_wants_input: bool,
+    /// Button of the brush stroke in progress, `Some(true)` when erasing
+    painting: Option<bool>,
+    /// Draggable object held with the left button, and the point grabbed
+    dragging: Option<(usize, Vector3<f32>)>,
+    /// Secondary viewports rendered on top of the main view
+    pub viewports: Vec<Viewport>,
+    /// Offscreen 3D views that can be displayed inside UI windows
+    pub render_textures: Vec<RenderTexture>,
+    /// Keyboard shortcut bindings
+    pub input_map: InputMap,
+    /// Simulation control requests the app acts on, from `scene.events`
+    simulation_events: EventReceiver<SimulationEvent>,
+    /// Commands available in the console and scripts
+    pub commands: CommandRegistry,
+    /// Dropdown command console
+    pub console: Console,
+    /// Command lines to run on the next frame
+    pending_commands: Vec<String>,
+    /// Render engine whose async creation finished but isn't set up yet
+    pending_renderer: std::rc::Rc<std::cell::RefCell<Option<Result<RenderEngine>>>>,
+    /// Window title, size and fullscreen settings used at creation
+    pub window_config: WindowConfig,
+    /// Adapter and device settings used to create the render engine
+    pub render_config: RenderConfig,
+    /// Messages, warnings and non-fatal errors shown as toasts
+    pub notifications: Notifications,
+    /// Log console showing captured `tracing` and `log` records
+    pub log_window: LogWindow,
+    /// Panel editing the UI theme with live preview
+    pub theme_editor: ThemeEditor,
+    /// Panel listing registered GPU buffers and reading them back
+    pub buffer_inspector: BufferInspector,
+    /// Distance and angle measurements placed by clicking geometry
+    pub measure_tool: MeasureTool,
+    /// File the scene annotations are saved to on exit (None = not saved)
+    pub annotations_file: Option<std::path::PathBuf>,
+    /// File the session is saved to on exit (None = not saved)
+    pub session_file: Option<std::path::PathBuf>,
+    /// Session restored at startup, with the parameters of every simulation
+    /// run so far
+    pub session: session::Session,
+    /// Simulation whose saved parameters were last restored
+    session_simulation: Option<String>,
+    /// Notifications posted by simulations through `scene.events`
+    notification_events: EventReceiver<Notification>,
+    /// Metric samples posted by simulations through `scene.events`
+    metric_events: EventReceiver<MetricSample>,
+    /// Latest value of each metric emitted as a [`MetricSample`]
+    pub metrics: std::collections::BTreeMap<String, f64>,
+    /// Error that stopped the app, e.g. no compatible GPU
+    fatal_error: Option<HaggisError>,
+    /// Sound playback, emitters and simulation sound hooks
+    #[cfg(feature = "audio")]
+    pub audio: Option<crate::audio::AudioSystem>,
+    /// Headset session rendering the scene in stereo
+    #[cfg(feature = "xr")]
+    pub xr_session: Option<crate::xr::XrSession>,
+    /// HTTP server for monitoring and steering the app remotely
+    #[cfg(feature = "remote")]
+    pub remote_server: Option<crate::remote::RemoteServer>,
+    /// Exporter pushing metrics to external dashboards
+    #[cfg(feature = "telemetry")]
+    pub telemetry: Option<crate::telemetry::TelemetryExporter>,
 }
 
 impl HaggisApp {
@@ -222,16 +335,32 @@ impl HaggisApp {
     /// # }
     /// ```
     pub async fn new() -> Self {
-        let event_loop = EventLoop::new().expect("Failed to create event loop");
+        Self::try_new()
+            .await
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Creates a new Haggis application, returning an error instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::Window`] if the event loop can't be created (for
+    /// example when no display is available)
+    pub async fn try_new() -> Result<Self> {
+        crate::logging::init();
+        let event_loop = EventLoop::new().map_err(|error| HaggisError::Window(error.to_string()))?;
 
         // Configure default orbit camera
         let mut camera = OrbitCamera::new(8.0, 0.4, 0.2, Vector3::new(0.0, 0.0, 0.0), 1.0);
         camera.bounds.min_distance = Some(1.1);
         let controller = CameraController::new(0.005, 0.1);
         let camera_manager = CameraManager::new(camera, controller);
-        let scene = Scene::new(camera_manager);
+        let mut scene = Scene::new(camera_manager);
+        let simulation_events = scene.events.subscribe::<SimulationEvent>();
+        let notification_events = scene.events.subscribe::<Notification>();
+        let metric_events = scene.events.subscribe::<MetricSample>();
 
-        Self {
+        Ok(Self {
             event_loop: Some(event_loop),
             app_state: AppState {
                 window: None,
@@ -240,7 +369,12 @@ impl HaggisApp {
                 ui_manager: None,
                 ui_style: UiStyle::default(),
                 ui_font: UiFont::default(),
+                ui_fallback_fonts: vec![FallbackFont::builtin()],
+                ui_scale: 1.0,
                 show_transform_panel: true,
+                ui_default_layout: None,
+                ui_layout_file: None,
+                ui_theme_file: None,
                 ui_callback: None,
                 selected_object_index: Some(0),
                 simulation_manager: SimulationManager::new(),
@@ -248,15 +382,67 @@ impl HaggisApp {
                 gizmo_manager: crate::gfx::gizmos::GizmoManager::new(),
                 performance_monitor: PerformanceMonitor::new(),
                 show_performance_panel: false, // Hidden by default
+                show_simulation_toolbar: true,
+                show_render_settings_panel: false,
                 enable_vsync: false, // Disabled when framerate limiting is enabled
                 framerate_limit: Some(144.0), // Higher limit to ensure we hit 120fps target
-                last_frame_time: std::time::Instant::now(),
-                last_performance_frame_time: std::time::Instant::now(),
+                frame_pacer: pacing::FramePacer::new(),
+                last_performance_frame_time: crate::platform::Instant::now(),
                 object_picker: ObjectPicker::new(),
                 mouse_position: (0.0, 0.0),
                 ui_wants_input: false,
+                painting: None,
+                dragging: None,
+                viewports: Vec::new(),
+                render_textures: Vec::new(),
+                input_map: InputMap::with_defaults(),
+                simulation_events,
+                commands: CommandRegistry::with_builtins(),
+                console: Console::new(),
+                pending_commands: Vec::new(),
+                pending_renderer: Default::default(),
+                window_config: WindowConfig::default(),
+                render_config: RenderConfig::default(),
+                notifications: Notifications::new(),
+                log_window: LogWindow::new(),
+                theme_editor: ThemeEditor::new(),
+                buffer_inspector: BufferInspector::new(),
+                measure_tool: MeasureTool::new(),
+                annotations_file: None,
+                session_file: None,
+                session: Default::default(),
+                session_simulation: None,
+                notification_events,
+                metric_events,
+                metrics: Default::default(),
+                fatal_error: None,
+                #[cfg(feature = "audio")]
+                audio: None,
+                #[cfg(feature = "xr")]
+                xr_session: None,
+                #[cfg(feature = "remote")]
+                remote_server: None,
+                #[cfg(feature = "telemetry")]
+                telemetry: None,
             },
-        }
+        })
+    }
+
+    /// Start configuring an app with a custom window and GPU device.
+    ///
+    /// See [`HaggisAppBuilder`] for the available settings.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let app = haggis::HaggisApp::builder()
+    ///     .title("Heat Diffusion")
+    ///     .size(1920, 1080)
+    ///     .prefer_discrete_gpu()
+    ///     .build();
+    /// ```
+    pub fn builder() -> HaggisAppBuilder {
+        HaggisAppBuilder::new()
     }
 
     /// Attach a user-defined simulation to the engine.
@@ -291,6 +477,54 @@ impl HaggisApp {
             .attach_simulation(Box::new(simulation), &mut self.app_state.scene);
     }
 
+    /// Let GPU simulation steps overlap rendering.
+    ///
+    /// GPU results are applied once each step has finished on the GPU rather
+    /// than waited for in the frame that submitted it, so they reach the
+    /// scene a frame or more later. See
+    /// [`SimulationManager::set_async_compute`](crate::simulation::manager::SimulationManager::set_async_compute).
+    pub fn set_async_compute(&mut self, enable: bool) {
+        self.app_state.simulation_manager.set_async_compute(enable);
+    }
+
+    /// Check the simulation for divergence and pause or roll it back.
+    ///
+    /// Failed checks show up as a warning notification and in the simulation
+    /// control panel with the simulation's parameter suggestions. See
+    /// [`stability`](crate::simulation::stability); `None` turns checks off.
+    pub fn set_stability_policy(&mut self, policy: Option<StabilityPolicy>) {
+        self.app_state.simulation_manager.set_stability_policy(policy);
+    }
+
+    /// Run simulation steps independently of the frame rate.
+    ///
+    /// Steps per frame, steps per second, or as many steps per frame as fit
+    /// in a time budget. See [`step_policy`](crate::simulation::step_policy).
+    pub fn set_step_policy(&mut self, policy: StepPolicy) {
+        self.app_state.simulation_manager.set_step_policy(policy);
+    }
+
+    /// Turn mouse presses and drags in the 3D view into brush strokes.
+    ///
+    /// Left drags paint and right drags erase, emitted as
+    /// [`BrushStroke`] events for simulations to apply with a
+    /// [`FieldBrush`](crate::simulation::paint::FieldBrush). Object picking
+    /// and camera orbiting are off meanwhile. Also toggled from the
+    /// simulation toolbar or with [`Action::TogglePaintMode`].
+    pub fn set_paint_mode(&mut self, enable: bool) {
+        self.app_state.simulation_manager.set_paint_mode(enable);
+    }
+
+    /// Turn left clicks in the 3D view into measurement points.
+    ///
+    /// Points land on object surfaces; two make a distance and three an
+    /// angle, labeled in the reference overlay's unit. Object selection is
+    /// off meanwhile. Also toggled with [`Action::ToggleMeasureMode`]. See
+    /// [`measure`](crate::gfx::measure).
+    pub fn set_measure_mode(&mut self, enable: bool) {
+        self.app_state.measure_tool.open = enable;
+    }
+
     /// Remove the current simulation from the engine.
     ///
     /// This method detaches any currently running simulation and cleans up
@@ -532,6 +766,57 @@ impl HaggisApp {
         self.app_state.ui_font = font;
     }
 
+    /// Merges a fallback font into the UI font.
+    ///
+    /// The font supplies the glyphs of its range that the UI font lacks, so
+    /// text in other scripts renders instead of showing `?` boxes. Bundled
+    /// Roboto is merged by default for Greek, Cyrillic and math symbols;
+    /// clear `app_state.ui_fallback_fonts` to drop it. Applied when the UI
+    /// manager is initialized.
+    ///
+    /// # Arguments
+    ///
+    /// * `font` - TTF font and the glyph range it covers
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use haggis::ui::{FallbackFont, GlyphRange};
+    ///
+    /// let font = std::fs::read("fonts/NotoSansSC-Regular.ttf").expect("font file");
+    /// let mut app = haggis::default();
+    /// app.add_ui_fallback_font(FallbackFont::new(
+    ///     font.leak(),
+    ///     GlyphRange::ChineseSimplifiedCommon,
+    /// ));
+    /// ```
+    pub fn add_ui_fallback_font(&mut self, font: FallbackFont) {
+        self.app_state.ui_fallback_fonts.push(font);
+    }
+
+    /// Sets the user UI scale.
+    ///
+    /// Fonts and UI metrics are sized for the window's monitor and then
+    /// multiplied by this scale, clamped to [`UI_SCALE_RANGE`]. It can be
+    /// changed at runtime through `app_state.ui_scale` or the
+    /// [`Action::IncreaseUiScale`] and [`Action::DecreaseUiScale`] bindings.
+    ///
+    /// # Arguments
+    ///
+    /// * `scale` - UI scale, 1.0 for the platform's native size
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let mut app = haggis::default();
+    /// app.set_ui_scale(1.25);
+    /// ```
+    ///
+    /// [`UI_SCALE_RANGE`]: crate::ui::UI_SCALE_RANGE
+    pub fn set_ui_scale(&mut self, scale: f32) {
+        self.app_state.ui_scale = scale;
+    }
+
     /// Sets whether to show the default transform panel.
     ///
     /// The transform panel allows editing object position, rotation, and scale
@@ -555,6 +840,52 @@ impl HaggisApp {
         self.app_state.show_transform_panel = show;
     }
 
+    /// Sets the default docking layout for UI panels.
+    ///
+    /// Panels are docked to the edges of the window as described by the
+    /// layout, leaving the central area for the 3D view. The layout is applied
+    /// on startup unless a saved layout is loaded from the layout file.
+    /// Requires the `docking` feature (enabled by default).
+    ///
+    /// # Arguments
+    ///
+    /// * `layout` - Docking layout referencing windows by title
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use haggis::ui::DockLayout;
+    ///
+    /// let mut app = haggis::default();
+    /// app.set_default_layout(
+    ///     DockLayout::new()
+    ///         .left(0.25, &["Simulation Control"])
+    ///         .right(0.25, &["Transform Studio"]),
+    /// );
+    /// ```
+    pub fn set_default_layout(&mut self, layout: DockLayout) {
+        self.app_state.ui_default_layout = Some(layout);
+    }
+
+    /// Sets the file used to persist UI window and docking layouts.
+    ///
+    /// Layout changes made by the user are saved to this file and restored on
+    /// the next run. Persistence is disabled by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path of the ini file (e.g. `"imgui.ini"`), or `None` to disable
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let mut app = haggis::default();
+    /// app.set_layout_file(Some("imgui.ini"));
+    /// ```
+    pub fn set_layout_file<P: Into<std::path::PathBuf>>(&mut self, path: Option<P>) {
+        self.app_state.ui_layout_file = path.map(Into::into);
+    }
+
     /// Sets the UI callback function for custom user interface rendering.
     ///
     /// The callback is called every frame during the UI update phase,
@@ -626,195 +957,965 @@ impl HaggisApp {
         self.app_state.show_performance_panel = enabled;
     }
 
-
-    /// Set framerate limit to prioritize simulation over rendering.
+    /// Shows or hides the simulation toolbar.
+    ///
+    /// The toolbar is shown by default whenever a simulation is attached. It
+    /// has play/pause, step and reset buttons, a speed multiplier, and the
+    /// frame counter and simulation time, and drives the simulation through
+    /// its [`Simulation`](crate::simulation::traits::Simulation) methods.
     ///
-    /// Limits the maximum framerate to free up resources for simulation computation.
-    /// Use None for unlimited framerate, or Some(fps) to set a specific limit.
-    /// 
     /// # Arguments
-    /// * `limit` - Framerate limit in FPS (None for unlimited)
     ///
-    /// # Examples
-    /// ```rust
-    /// let mut app = haggis::default();
-    /// app.set_framerate_limit(Some(120.0)); // Limit to 120 FPS
-    /// app.set_framerate_limit(None);        // Unlimited FPS
-    /// ```
-    pub fn set_framerate_limit(&mut self, limit: Option<f32>) {
-        self.app_state.framerate_limit = limit;
+    /// * `enabled` - Whether to show the simulation toolbar
+    pub fn show_simulation_toolbar(&mut self, enabled: bool) {
+        self.app_state.show_simulation_toolbar = enabled;
     }
 
-    /// Set VSync (vertical synchronization) state.
-    ///
-    /// When VSync is enabled, the application will sync to the display refresh rate.
-    /// When disabled with framerate limiting, the application can achieve consistent
-    /// frame times regardless of display refresh rate.
+    /// Show a toast notification in the bottom-right corner of the window.
     ///
-    /// # Arguments
-    /// * `enable` - Whether to enable VSync
+    /// Info and warning toasts fade out after a few seconds, errors stay
+    /// until clicked. The message is also logged.
     ///
     /// # Examples
-    /// ```rust
+    ///
+    /// ```no_run
+    /// use haggis::ui::notifications::NotificationLevel;
+    ///
     /// let mut app = haggis::default();
-    /// app.set_vsync(false); // Disable VSync for consistent framerate limiting
+    /// app.notify(NotificationLevel::Info, "Checkpoint saved");
     /// ```
-    pub fn set_vsync(&mut self, enable: bool) {
-        self.app_state.enable_vsync = enable;
-        
-        // Update render engine surface configuration if available
-        if let Some(render_engine) = &mut self.app_state.render_engine {
-            render_engine.set_vsync(enable);
-        }
+    pub fn notify(&mut self, level: NotificationLevel, message: impl Into<String>) {
+        self.app_state
+            .notifications
+            .push(Notification::new(level, message));
     }
 
-    /// Get the current performance metrics.
-    ///
-    /// Returns a reference to the current performance metrics which include
-    /// FPS, frame time, memory usage, and render statistics.
+    /// Show or hide the log console.
     ///
-    /// # Returns
-    ///
-    /// A reference to the current [`PerformanceMetrics`](crate::performance::PerformanceMetrics).
+    /// The log console lists engine and simulation messages logged through
+    /// `tracing` (and wgpu warnings), with level, target and text filters.
+    /// Bind [`Action::ToggleLogConsole`] to a key to toggle it at runtime.
     ///
     /// # Examples
     ///
-    /// ```rust
-    /// let app = haggis::default();
-    /// let metrics = app.get_performance_metrics();
-    /// println!("Current FPS: {:.1}", metrics.fps);
+    /// ```no_run
+    /// use haggis::input::{Action, Key};
+    ///
+    /// let mut app = haggis::default();
+    /// app.show_log_console(true);
+    /// app.bind_key(Key::F2, Action::ToggleLogConsole);
     /// ```
-    pub fn get_performance_metrics(&self) -> &crate::performance::PerformanceMetrics {
-        self.app_state.performance_monitor.get_metrics()
+    pub fn show_log_console(&mut self, show: bool) {
+        self.app_state.log_window.open = show;
     }
 
-    /// Reset performance metrics and history.
+    /// Loads a TOML UI theme over the UI style at startup.
     ///
-    /// This clears all accumulated performance data and restarts tracking
-    /// from the current frame. Useful for benchmarking specific scenarios.
+    /// The file only needs the colors and metrics it changes; everything else
+    /// keeps the values of the [`UiStyle`] preset. A missing or malformed file
+    /// is reported as a notification. See [`crate::ui::theme`] for
+    /// the file format.
     ///
     /// # Examples
     ///
-    /// ```rust
+    /// ```no_run
     /// let mut app = haggis::default();
-    /// // ... run for a while ...
-    /// app.reset_performance_metrics(); // Start fresh
+    /// app.set_ui_theme_file("themes/dusk.toml");
     /// ```
-    pub fn reset_performance_metrics(&mut self) {
-        self.app_state.performance_monitor.reset();
+    pub fn set_ui_theme_file(&mut self, path: impl Into<std::path::PathBuf>) {
+        self.app_state.ui_theme_file = Some(path.into());
     }
 
+    /// Show or hide the theme editor.
+    ///
+    /// The editor changes UI colors, rounding, padding and spacing with a
+    /// live preview, and loads and saves themes as TOML files.
+    /// Bind [`Action::ToggleThemeEditor`] to a key to toggle it at runtime.
+    pub fn show_theme_editor(&mut self, show: bool) {
+        self.app_state.theme_editor.open = show;
+    }
 
-    /// Runs the application.
+    /// Show or hide the GPU buffer inspector.
     ///
-    /// Consumes the [`HaggisApp`] and starts the main event loop.
-    /// This function will block until the application is closed by the user.
+    /// The inspector lists buffers registered with
+    /// [`BufferRegistry`](crate::wgpu_utils::BufferRegistry), and reads slices
+    /// of them back as `f32`, `u32` or `i32` tables or a small image.
+    /// Bind [`Action::ToggleBufferInspector`] to a key to toggle it at runtime.
     ///
-    /// The event loop handles:
-    /// - Window events (resize, close, input)
-    /// - Graphics rendering
-    /// - Simulation updates
-    /// - UI rendering
+    /// # Examples
     ///
-    /// # Panics
+    /// ```no_run
+    /// use haggis::input::{Action, Key};
     ///
-    /// Panics if the event loop fails to start or if called multiple times.
+    /// let mut app = haggis::default();
+    /// app.show_buffer_inspector(true);
+    /// app.bind_key(Key::F4, Action::ToggleBufferInspector);
+    /// ```
+    pub fn show_buffer_inspector(&mut self, show: bool) {
+        self.app_state.buffer_inspector.open = show;
+    }
+
+    /// Add a named marker with a note to the scene, returning its index.
+    ///
+    /// Markers are drawn over the scene and listed in the annotations panel,
+    /// toggled with [`Action::ToggleAnnotations`].
     ///
     /// # Examples
     ///
     /// ```no_run
-    /// use haggis::HaggisApp;
+    /// use haggis::gfx::scene::Annotation;
     ///
-    /// let app = haggis::default();
-    /// app.run(); // Blocks until application is closed
+    /// let mut app = haggis::default();
+    /// app.add_annotation(
+    ///     Annotation::new("Separation", [1.5, 0.0, 0.5])
+    ///         .with_text("Flow detaches behind the step")
+    ///         .with_color([1.0, 0.2, 0.2, 1.0]),
+    /// );
     /// ```
-    pub fn run(mut self) {
-        let event_loop = self.event_loop.take().expect("Event loop already consumed");
-        event_loop.set_control_flow(ControlFlow::Poll);
-
-        event_loop
-            .run_app(&mut self.app_state)
-            .expect("Failed to run event loop");
+    pub fn add_annotation(&mut self, annotation: Annotation) -> usize {
+        self.app_state.scene.annotations.add(annotation)
     }
 
-    /// Adds a 3D object to the scene with builder pattern support.
+    /// Keep the scene annotations in a TOML file across sessions.
     ///
-    /// Loads a 3D model file and adds it to the scene. Returns an [`ObjectBuilder`]
-    /// for method chaining to set transform, materials, and other properties.
-    /// The object name is automatically extracted from the file path.
+    /// Annotations in the file, if it exists, replace the current ones now,
+    /// and all annotations are written back when the app exits. Errors are
+    /// shown as notifications. See [`annotations`](crate::gfx::scene::annotations).
+    pub fn set_annotations_file(&mut self, path: impl Into<std::path::PathBuf>) {
+        let path = path.into();
+        if path.exists() {
+            if let Err(error) = self.app_state.scene.annotations.load(&path) {
+                self.app_state.notifications.error(error.to_string());
+            }
+        }
+        self.app_state.annotations_file = Some(path);
+    }
+
+    /// Keep the window, camera, UI and simulation parameters in a project
+    /// file across sessions.
     ///
-    /// # Arguments
+    /// If the file exists, the camera pose and panel visibility are restored
+    /// now, the window geometry and UI layout when the window opens, and the
+    /// parameters of each simulation when it is attached. Everything is
+    /// written back when the app exits. Errors are shown as notifications.
+    /// See [`session`] for the file format.
     ///
-    /// * `object_path` - Path to the 3D model file (OBJ format supported)
+    /// # Examples
     ///
-    /// # Returns
+    /// ```no_run
+    /// let mut app = haggis::default();
+    /// app.enable_session_persistence("project.haggis");
+    /// app.run();
+    /// ```
+    pub fn enable_session_persistence(&mut self, path: impl Into<std::path::PathBuf>) {
+        let path = path.into();
+        if path.exists() {
+            match session::Session::load(&path) {
+                Ok(session) => self.app_state.restore_session(session),
+                Err(error) => self.app_state.notifications.error(error.to_string()),
+            }
+        }
+        self.app_state.session_file = Some(path);
+    }
+
+    /// Writes a crash report into `dir` when the program panics.
     ///
-    /// An [`ObjectBuilder`] for configuring the added object
+    /// Crash reports are disabled by default. Once enabled, every panic in the
+    /// process writes one, including panics on other threads. Each report
+    /// holds the panic and backtrace together with the GPU adapter, the render
+    /// and compute passes of the last frames and recent log records; see
+    /// [`crash`](crate::logging::crash).
     ///
     /// # Examples
     ///
-    /// ## Basic Usage
     /// ```no_run
-    /// use haggis::HaggisApp;
-    ///
     /// let mut app = haggis::default();
-    /// app.add_object("models/cube.obj")
-    ///     .with_transform([2.0, 0.0, 0.0], 1.5, 45.0);
+    /// app.enable_crash_reports("crash-reports");
+    /// app.run();
     /// ```
+    pub fn enable_crash_reports(&mut self, dir: impl Into<std::path::PathBuf>) {
+        crate::logging::crash::install(dir);
+    }
+
+    /// Configure the world grid, axes and unit labels.
+    ///
+    /// The grid and axes are on by default, so examples get orientation and
+    /// scale without adding reference objects. The config lives in
+    /// `scene.reference_overlay` and can also be changed from UI callbacks,
+    /// simulations or the `grid` console command.
+    ///
+    /// # Examples
     ///
-    /// ## With Material
     /// ```no_run
-    /// use haggis::HaggisApp;
+    /// use haggis::gfx::overlay::OverlayConfig;
     ///
     /// let mut app = haggis::default();
-    /// app.add_object("models/sphere.obj")
-    ///     .with_material("gold")
-    ///     .with_transform([0.0, 1.0, 0.0], 2.0, 0.0);
+    /// app.set_reference_overlay(OverlayConfig {
+    ///     spacing: 1.0,
+    ///     half_lines: 10,
+    ///     show_labels: true,
+    ///     unit: " m".to_string(),
+    ///     ..OverlayConfig::default()
+    /// });
     /// ```
-    pub fn add_object(&mut self, object_path: &str) -> ObjectBuilder {
-        let object_index = self.app_state.scene.objects.len();
-        self.app_state.scene.add_object(object_path);
+    pub fn set_reference_overlay(&mut self, config: OverlayConfig) {
+        self.app_state.scene.reference_overlay = config;
+    }
 
-        // Extract object name from file path for UI display
-        if let Some(object) = self.app_state.scene.objects.get_mut(object_index) {
-            let object_name = std::path::Path::new(object_path)
-                .file_stem()
-                .and_then(|s| s.to_str())
-                .unwrap_or("Object")
-                .to_string();
-            object.set_name(object_name);
-            object.sync_transform_to_ui();
-        }
+    /// Show or hide the world grid and world axes.
+    ///
+    /// Bind [`Action::ToggleGrid`] to a key to toggle them at runtime.
+    pub fn show_grid(&mut self, show: bool) {
+        let overlay = &mut self.app_state.scene.reference_overlay;
+        overlay.show_grid = show;
+        overlay.show_world_axes = show;
+    }
 
-        ObjectBuilder::new(self, object_index)
+    /// Show or hide the XYZ axis indicator in the bottom-left corner
+    pub fn show_axes_indicator(&mut self, show: bool) {
+        self.app_state.scene.reference_overlay.show_corner_axes = show;
     }
 
-    /// Adds a 3D object without builder pattern (legacy compatibility).
+    /// Show or hide distance labels on major grid lines
+    pub fn show_grid_labels(&mut self, show: bool) {
+        self.app_state.scene.reference_overlay.show_labels = show;
+    }
+
+    /// Set the clear color, background gradient and depth fog of the main view.
     ///
-    /// This is a simple method for adding objects without the builder pattern.
-    /// Use [`add_object`] for more configuration options.
+    /// The settings live in `scene.render_settings` and can also be changed
+    /// from UI callbacks or the render settings panel.
     ///
-    /// # Arguments
+    /// # Examples
     ///
-    /// * `object_path` - Path to the 3D model file
+    /// ```no_run
+    /// use haggis::gfx::rendering::{FogSettings, RenderSettings};
     ///
-    /// [`add_object`]: Self::add_object
-    pub fn add_object_simple(&mut self, object_path: &str) {
-        self.app_state.scene.add_object(object_path);
+    /// let mut app = haggis::default();
+    /// app.set_render_settings(RenderSettings {
+    ///     fog: FogSettings::linear([0.1, 0.2, 0.3], 5.0, 40.0),
+    ///     ..RenderSettings::default()
+    /// });
+    /// ```
+    pub fn set_render_settings(&mut self, settings: RenderSettings) {
+        self.app_state.scene.render_settings = settings;
     }
 
-    /// Add a procedural cube to the scene.
+    /// Show or hide the render settings panel.
     ///
-    /// Creates a unit cube (1x1x1) centered at the origin with proper normals and texture coordinates.
-    /// The cube can be scaled and positioned using the returned [`ObjectBuilder`].
+    /// Bind [`Action::ToggleRenderSettings`] to a key to toggle it at runtime.
+    pub fn show_render_settings_panel(&mut self, show: bool) {
+        self.app_state.show_render_settings_panel = show;
+    }
+
+    /// Play sounds through `backend`.
+    ///
+    /// Enables [`play_sound`](Self::play_sound) and
+    /// [`SoundEmitter`](crate::audio::SoundEmitter) components. Set collision
+    /// and alert sounds through `app_state.audio`; see [`crate::audio`].
+    #[cfg(feature = "audio")]
+    pub fn enable_audio(&mut self, backend: impl crate::audio::AudioBackend + 'static) {
+        let audio = crate::audio::AudioSystem::new(backend, &mut self.app_state.scene.events);
+        tracing::info!("Playing audio through {}", audio.backend_name());
+        self.app_state.audio = Some(audio);
+    }
+
+    /// Play a sound file once.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::Audio`] if audio isn't enabled or the backend
+    /// can't play the file
+    #[cfg(feature = "audio")]
+    pub fn play_sound(&mut self, path: impl AsRef<std::path::Path>) -> Result<()> {
+        let path = path.as_ref();
+        let Some(audio) = self.app_state.audio.as_mut() else {
+            return Err(HaggisError::Audio {
+                path: path.to_path_buf(),
+                message: "audio is not enabled, see `enable_audio`".to_string(),
+            });
+        };
+        audio.play(path).map(|_| ())
+    }
+
+    /// Render the scene to a VR headset in addition to the window.
+    ///
+    /// Eyes are rendered from the headset's tracked poses every frame, and
+    /// pressing a controller's select button picks the object it points at,
+    /// emitting a [`PickEvent`] like a mouse click. Adjust where the tracking
+    /// space sits in the world through `app_state.xr_session`.
+    #[cfg(feature = "xr")]
+    pub fn enable_xr(&mut self, runtime: impl crate::xr::XrRuntime + 'static) {
+        let session = crate::xr::XrSession::new(runtime);
+        tracing::info!("Rendering to headset through {}", session.runtime_name());
+        self.app_state.xr_session = Some(session);
+    }
+
+    /// Serve the remote control dashboard and HTTP API.
+    ///
+    /// Simulations can be paused, stepped and reset, their parameters read
+    /// and set, console commands run and screenshots taken from a browser or
+    /// script. See [`crate::remote`] for the endpoints and security model.
     ///
     /// # Returns
     ///
-    /// An [`ObjectBuilder`] for further configuration of the cube.
+    /// The address the server is bound to
     ///
-    /// # Examples
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::Remote`] if the server can't be started
+    #[cfg(feature = "remote")]
+    pub fn enable_remote_control(
+        &mut self,
+        config: crate::remote::RemoteConfig,
+    ) -> Result<std::net::SocketAddr> {
+        let server = crate::remote::RemoteServer::start(config)?;
+        let address = server.address();
+        self.app_state.remote_server = Some(server);
+        Ok(address)
+    }
+
+    /// Export metrics to external dashboards such as Grafana.
+    ///
+    /// Every [`MetricSample`] emitted by simulations is exported along with
+    /// `fps`, `frame` and `simulation_time`. See [`crate::telemetry`] for the
+    /// OSC and Prometheus formats.
+    #[cfg(feature = "telemetry")]
+    pub fn enable_telemetry(&mut self, exporter: crate::telemetry::TelemetryExporter) {
+        self.app_state.telemetry = Some(exporter);
+    }
+
+    /// Set the world up axis, the up axis of imported models and world units.
+    ///
+    /// The world is Z-up by default and OBJ files are assumed to be Y-up. See
+    /// [`Scene::set_coordinate_system`] for what is updated; call this before
+    /// adding models so they are loaded with the new convention.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use haggis::gfx::coordinates::CoordinateSystem;
     ///
-    /// ```rust
+    /// let mut app = haggis::default();
+    /// app.set_coordinate_system(CoordinateSystem::y_up());
+    /// app.add_object("models/bunny.obj");
+    /// ```
+    pub fn set_coordinate_system(&mut self, coordinate_system: CoordinateSystem) {
+        self.app_state
+            .scene
+            .set_coordinate_system(coordinate_system);
+    }
+
+
+    /// Set framerate limit to prioritize simulation over rendering.
+    ///
+    /// Limits the maximum framerate to free up resources for simulation computation.
+    /// Use None for unlimited framerate, or Some(fps) to set a specific limit.
+    /// 
+    /// # Arguments
+    /// * `limit` - Framerate limit in FPS (None for unlimited)
+    ///
+    /// # Examples
+    /// ```rust
+    /// let mut app = haggis::default();
+    /// app.set_framerate_limit(Some(120.0)); // Limit to 120 FPS
+    /// app.set_framerate_limit(None);        // Unlimited FPS
+    /// ```
+    pub fn set_framerate_limit(&mut self, limit: Option<f32>) {
+        self.app_state.framerate_limit = limit;
+        self.app_state.frame_pacer.reset();
+    }
+
+    /// Cap the framerate to a whole number of frames per second.
+    ///
+    /// Between frames the event loop sleeps instead of spinning, which keeps
+    /// GPU time free for compute work and the machine cool when the scene is
+    /// cheap to draw. `None` removes the cap; with `Fifo` presentation the
+    /// display refresh rate still limits the framerate.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// let mut app = haggis::default();
+    /// app.set_frame_limit(Some(60));
+    /// ```
+    pub fn set_frame_limit(&mut self, fps: Option<u32>) {
+        self.set_framerate_limit(fps.map(|fps| fps as f32));
+    }
+
+    /// The framerate limit in frames per second, if any
+    pub fn frame_limit(&self) -> Option<f32> {
+        self.app_state.framerate_limit
+    }
+
+    /// Choose how frames are presented, overriding the VSync setting.
+    ///
+    /// - `Fifo` waits for the display refresh (VSync), always supported
+    /// - `Mailbox` replaces queued frames with newer ones, low latency without tearing
+    /// - `Immediate` presents right away and may tear
+    ///
+    /// Unsupported modes fall back to the closest supported one with a
+    /// warning notification. Takes effect immediately when the app is running.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// let mut app = haggis::default();
+    /// app.set_present_mode(wgpu::PresentMode::Mailbox);
+    /// app.set_frame_limit(Some(240));
+    /// ```
+    pub fn set_present_mode(&mut self, present_mode: wgpu::PresentMode) {
+        self.app_state.render_config.present_mode = Some(present_mode);
+        self.app_state.apply_present_mode(present_mode);
+    }
+
+    /// The present mode in use, once the renderer exists
+    pub fn present_mode(&self) -> Option<wgpu::PresentMode> {
+        self.app_state
+            .render_engine
+            .as_ref()
+            .map(RenderEngine::present_mode)
+    }
+
+    /// Set VSync (vertical synchronization) state.
+    ///
+    /// When VSync is enabled, the application will sync to the display refresh rate.
+    /// When disabled with framerate limiting, the application can achieve consistent
+    /// frame times regardless of display refresh rate.
+    ///
+    /// # Arguments
+    /// * `enable` - Whether to enable VSync
+    ///
+    /// # Examples
+    /// ```rust
+    /// let mut app = haggis::default();
+    /// app.set_vsync(false); // Disable VSync for consistent framerate limiting
+    /// ```
+    pub fn set_vsync(&mut self, enable: bool) {
+        self.app_state.enable_vsync = enable;
+        
+        // Update render engine surface configuration if available
+        if let Some(render_engine) = &mut self.app_state.render_engine {
+            render_engine.set_vsync(enable);
+        }
+    }
+
+    /// Get the current performance metrics.
+    ///
+    /// Returns a reference to the current performance metrics which include
+    /// FPS, frame time, memory usage, and render statistics.
+    ///
+    /// # Returns
+    ///
+    /// A reference to the current [`PerformanceMetrics`](crate::performance::PerformanceMetrics).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let app = haggis::default();
+    /// let metrics = app.get_performance_metrics();
+    /// println!("Current FPS: {:.1}", metrics.fps);
+    /// ```
+    pub fn get_performance_metrics(&self) -> &crate::performance::PerformanceMetrics {
+        self.app_state.performance_monitor.get_metrics()
+    }
+
+    /// Reset performance metrics and history.
+    ///
+    /// This clears all accumulated performance data and restarts tracking
+    /// from the current frame. Useful for benchmarking specific scenarios.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut app = haggis::default();
+    /// // ... run for a while ...
+    /// app.reset_performance_metrics(); // Start fresh
+    /// ```
+    pub fn reset_performance_metrics(&mut self) {
+        self.app_state.performance_monitor.reset();
+    }
+
+    /// Time a core engine path on a headless device with the app's render config.
+    ///
+    /// Runs before [`run`](Self::run), without opening a window. The report
+    /// can be written as JSON to track performance across versions; see
+    /// [`benchmark`](crate::performance::benchmark).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use haggis::performance::benchmark::BenchmarkScenario;
+    ///
+    /// let app = haggis::default();
+    /// let report = app
+    ///     .run_benchmark(BenchmarkScenario::Readback { bytes: 64 << 20 })
+    ///     .expect("benchmark");
+    /// print!("{}", report.summary());
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn run_benchmark(
+        &self,
+        scenario: crate::performance::benchmark::BenchmarkScenario,
+    ) -> Result<crate::performance::benchmark::BenchmarkReport> {
+        crate::performance::benchmark::Benchmark::with_config(&self.app_state.render_config)?
+            .run_all(&[scenario])
+    }
+
+    /// Adds a secondary viewport rendering the scene from another camera.
+    ///
+    /// Viewports are drawn on top of the main view inside their rectangle,
+    /// which is given in normalized window coordinates. Use this for
+    /// picture-in-picture views or split screen layouts.
+    ///
+    /// # Arguments
+    ///
+    /// * `camera` - Camera used for this viewport (aspect ratio is managed automatically)
+    /// * `rect` - Placement within the window
+    ///
+    /// # Returns
+    ///
+    /// Index of the new viewport in [`AppState::viewports`]
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use haggis::gfx::{camera::OrbitCamera, rendering::ViewportRect};
+    /// use cgmath::Vector3;
+    ///
+    /// let mut app = haggis::default();
+    /// // Fixed top-down view in the bottom-right corner
+    /// let top_down = OrbitCamera::new(12.0, 1.55, 0.0, Vector3::new(0.0, 0.0, 0.0), 1.0);
+    /// app.add_viewport(top_down, ViewportRect::picture_in_picture(0.3));
+    /// ```
+    pub fn add_viewport(&mut self, camera: OrbitCamera, rect: ViewportRect) -> usize {
+        let index = self.app_state.viewports.len();
+        self.app_state
+            .viewports
+            .push(Viewport::new(&format!("Viewport {}", index + 1), camera, rect));
+        index
+    }
+
+    /// Removes all secondary viewports.
+    pub fn clear_viewports(&mut self) {
+        self.app_state.viewports.clear();
+    }
+
+    /// Adds an offscreen 3D view that can be embedded in UI windows.
+    ///
+    /// The scene is rendered from `camera` into a texture every frame. Draw it
+    /// inside any ImGui window with [`RenderTexture::image`], or use
+    /// [`RenderTexture::texture_id`] with `imgui::Image` directly.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Display name of the view
+    /// * `camera` - Camera used for this view (aspect ratio follows the texture size)
+    /// * `width` - Texture width in pixels
+    /// * `height` - Texture height in pixels
+    ///
+    /// # Returns
+    ///
+    /// A clonable handle to the render texture
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use haggis::gfx::camera::OrbitCamera;
+    /// use cgmath::Vector3;
+    ///
+    /// let mut app = haggis::default();
+    /// let camera = OrbitCamera::new(10.0, 0.6, 0.8, Vector3::new(0.0, 0.0, 0.0), 1.0);
+    /// let preview = app.add_render_texture("Preview", camera, 256, 256);
+    /// app.set_ui(move |ui, _scene, _selected| {
+    ///     ui.window("Preview").build(|| preview.image(ui, [256.0, 256.0]));
+    /// });
+    /// ```
+    pub fn add_render_texture(
+        &mut self,
+        name: &str,
+        camera: OrbitCamera,
+        width: u32,
+        height: u32,
+    ) -> RenderTexture {
+        let render_texture = RenderTexture::new(name, camera, width, height);
+        self.app_state.render_textures.push(render_texture.clone());
+        render_texture
+    }
+
+    /// Removes a render texture added with [`HaggisApp::add_render_texture`].
+    pub fn remove_render_texture(&mut self, render_texture: &RenderTexture) {
+        self.app_state
+            .render_textures
+            .retain(|existing| existing.id() != render_texture.id());
+    }
+
+    /// Returns the event bus shared by the app, simulations and UI.
+    ///
+    /// Subscribe before calling [`run`](Self::run) and move the receivers into
+    /// UI callbacks or simulations. At runtime the same bus is available as
+    /// `scene.events`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use haggis::events::WindowResizeEvent;
+    ///
+    /// let mut app = haggis::default();
+    /// let resizes = app.events().subscribe::<WindowResizeEvent>();
+    /// ```
+    pub fn events(&mut self) -> &mut EventBus {
+        &mut self.app_state.scene.events
+    }
+
+    /// Limits and features of the GPU in use.
+    ///
+    /// `None` until the render engine is created when the app starts running;
+    /// simulations read the same report from `scene.gpu_capabilities`.
+    pub fn gpu_capabilities(&self) -> Option<&crate::gfx::GpuCapabilities> {
+        self.app_state.scene.gpu_capabilities.as_ref()
+    }
+
+    /// Registers a console command.
+    ///
+    /// Commands can be typed into the console (backquote by default) or run
+    /// from scripts with [`run_script`](Self::run_script). Simulations can also
+    /// handle their own commands by overriding `Simulation::handle_command`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Command name, the first word of the line
+    /// * `help` - One-line usage shown by `help`
+    /// * `handler` - Called with all words of the line (`args[0]` is `name`)
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let mut app = haggis::default();
+    /// app.register_command("count", "count - number of objects", |_args, scene| {
+    ///     Ok(format!("{} objects", scene.objects.len()))
+    /// });
+    /// ```
+    pub fn register_command<F>(&mut self, name: &str, help: &str, handler: F)
+    where
+        F: Fn(&[&str], &mut Scene) -> CommandResult + Send + Sync + 'static,
+    {
+        self.app_state.commands.register(name, help, handler);
+    }
+
+    /// Queues command lines to run, one per line, starting on the next frame.
+    ///
+    /// Blank lines and lines starting with `#` are skipped. Output and errors
+    /// are written to the console log.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let mut app = haggis::default();
+    /// app.run_script("camera goto iso\nsim pause");
+    /// ```
+    pub fn run_script(&mut self, script: &str) {
+        self.app_state
+            .pending_commands
+            .extend(script.lines().map(str::to_string));
+    }
+
+    /// Binds a key (or key chord with modifiers) to an action.
+    ///
+    /// Built-in actions such as [`Action::ToggleSimulation`] are handled by the
+    /// engine; [`Action::Custom`] actions can be queried from simulations via
+    /// `scene.input.action_triggered(...)`. Key presses are ignored while the
+    /// UI has keyboard focus. Escape is bound to [`Action::Quit`] by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `chord` - Key or [`KeyChord`] to bind
+    /// * `action` - Action to trigger
+    ///
+    /// # Returns
+    ///
+    /// The action previously bound to this chord, if it was replaced
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use haggis::input::{Action, Key, KeyChord, Modifiers};
+    ///
+    /// let mut app = haggis::default();
+    /// app.bind_key(Key::Space, Action::ToggleSimulation);
+    /// app.bind_key(KeyChord::new(Key::KeyR, Modifiers::CTRL), Action::ResetSimulation);
+    /// ```
+    pub fn bind_key(&mut self, chord: impl Into<KeyChord>, action: Action) -> Option<Action> {
+        self.app_state.input_map.bind(chord, action)
+    }
+
+    /// Removes the binding for a key or key chord.
+    pub fn unbind_key(&mut self, chord: impl Into<KeyChord>) -> Option<Action> {
+        self.app_state.input_map.unbind(chord)
+    }
+
+    /// Makes the orbit camera track a moving target.
+    ///
+    /// The orbit target is smoothly moved towards the target every frame while
+    /// distance, pitch and yaw remain under user control.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - Object index/name, a point updated by a simulation, or a closure
+    /// * `smoothing` - Tracking rate in 1/seconds (`0.0` snaps to the target)
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use haggis::gfx::camera::FollowTarget;
+    ///
+    /// let mut app = haggis::default();
+    /// app.add_sphere(32, 16).with_name("planet");
+    /// app.camera_follow(FollowTarget::ObjectNamed("planet".to_string()), 5.0);
+    /// ```
+    pub fn camera_follow(&mut self, target: FollowTarget, smoothing: f32) {
+        self.app_state
+            .scene
+            .camera_manager
+            .follow_with_smoothing(target, smoothing);
+    }
+
+    /// Stops camera follow mode, leaving the orbit target where it is.
+    pub fn stop_camera_follow(&mut self) {
+        self.app_state.scene.camera_manager.stop_following();
+    }
+
+
+    /// Runs the application.
+    ///
+    /// Consumes the [`HaggisApp`] and starts the main event loop.
+    /// This function will block until the application is closed by the user.
+    ///
+    /// The event loop handles:
+    /// - Window events (resize, close, input)
+    /// - Graphics rendering
+    /// - Simulation updates
+    /// - UI rendering
+    ///
+    /// # Panics
+    ///
+    /// Panics if the event loop fails to start or if called multiple times.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use haggis::HaggisApp;
+    ///
+    /// let app = haggis::default();
+    /// app.run(); // Blocks until application is closed
+    /// ```
+    pub fn run(self) {
+        if let Err(error) = self.try_run() {
+            panic!("{}", error);
+        }
+    }
+
+    /// Runs the application like [`run`](Self::run), returning the error that
+    /// stopped it (such as no compatible GPU) instead of panicking.
+    ///
+    /// On the web the browser owns the event loop, so this returns immediately
+    /// and startup errors are only logged.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// fn main() -> haggis::Result<()> {
+    ///     let mut app = haggis::default();
+    ///     app.add_cube();
+    ///     app.try_run()
+    /// }
+    /// ```
+    pub fn try_run(mut self) -> Result<()> {
+        let event_loop = self.event_loop.take().expect("Event loop already consumed");
+        event_loop.set_control_flow(ControlFlow::Poll);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            // Release the GPU and close the window before a panic unwinds out,
+            // so a crash doesn't leave a frozen window behind the backtrace
+            let app_state = &mut self.app_state;
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                event_loop.run_app(app_state)
+            }));
+            let result = result.unwrap_or_else(|panic| {
+                self.app_state.ui_manager = None;
+                self.app_state.render_engine = None;
+                self.app_state.window = None;
+                std::panic::resume_unwind(panic)
+            });
+            result.map_err(|error| HaggisError::Window(error.to_string()))?;
+            if let Some(error) = self.app_state.fatal_error.take() {
+                return Err(error);
+            }
+        }
+
+        // The browser owns the event loop; hand the app over and return immediately
+        #[cfg(target_arch = "wasm32")]
+        {
+            use winit::platform::web::EventLoopExtWebSys;
+            event_loop.spawn_app(self.app_state);
+        }
+
+        Ok(())
+    }
+
+    /// Adds a 3D object to the scene with builder pattern support.
+    ///
+    /// Loads a 3D model file and adds it to the scene. Returns an [`ObjectBuilder`]
+    /// for method chaining to set transform, materials, and other properties.
+    /// The object name is automatically extracted from the file path.
+    ///
+    /// # Arguments
+    ///
+    /// * `object_path` - Path to the 3D model file (OBJ format supported)
+    ///
+    /// # Returns
+    ///
+    /// An [`ObjectBuilder`] for configuring the added object. If the file can't
+    /// be loaded, the error is shown as a notification and the builder
+    /// does nothing; use [`try_add_object`] to handle the error instead.
+    ///
+    /// # Examples
+    ///
+    /// ## Basic Usage
+    /// ```no_run
+    /// use haggis::HaggisApp;
+    ///
+    /// let mut app = haggis::default();
+    /// app.add_object("models/cube.obj")
+    ///     .with_transform([2.0, 0.0, 0.0], 1.5, 45.0);
+    /// ```
+    ///
+    /// ## With Material
+    /// ```no_run
+    /// use haggis::HaggisApp;
+    ///
+    /// let mut app = haggis::default();
+    /// app.add_object("models/sphere.obj")
+    ///     .with_material("gold")
+    ///     .with_transform([0.0, 1.0, 0.0], 2.0, 0.0);
+    /// ```
+    ///
+    /// [`try_add_object`]: Self::try_add_object
+    pub fn add_object(&mut self, object_path: &str) -> ObjectBuilder {
+        let object_index = self.app_state.scene.objects.len();
+        if let Err(error) = self.load_object(object_path) {
+            self.app_state.notifications.error(error.to_string());
+        }
+        ObjectBuilder::new(self, object_index)
+    }
+
+    /// Adds a 3D object to the scene, returning an error if it can't be loaded.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::ModelLoad`] if the file is missing or malformed
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let mut app = haggis::default();
+    /// app.try_add_object("models/bunny.obj")?
+    ///     .with_transform([0.0, 0.0, 0.0], 1.0, 0.0);
+    /// # Ok::<(), haggis::HaggisError>(())
+    /// ```
+    pub fn try_add_object(&mut self, object_path: &str) -> Result<ObjectBuilder<'_>> {
+        let object_index = self.app_state.scene.objects.len();
+        self.load_object(object_path)?;
+        Ok(ObjectBuilder::new(self, object_index))
+    }
+
+    /// Load an OBJ file into the scene, named after the file
+    fn load_object(&mut self, object_path: &str) -> Result<()> {
+        let object_index = self.app_state.scene.objects.len();
+        self.app_state.scene.add_object(object_path)?;
+        self.name_object_from_path(object_index, object_path);
+        Ok(())
+    }
+
+    /// Adds several 3D objects, parsing the files in parallel.
+    ///
+    /// Much faster than calling [`add_object`](Self::add_object) in a loop for
+    /// large models. Files that fail to load are reported in the notification
+    /// panel and skipped.
+    ///
+    /// # Returns
+    ///
+    /// The scene indices of the objects that were added, in path order
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// let mut app = haggis::default();
+    /// let added = app.add_objects(&["models/bunny.obj", "models/dragon.obj"]);
+    /// for (i, index) in added.into_iter().enumerate() {
+    ///     if let Some(object) = app.app_state.scene.get_object_mut(index) {
+    ///         object.set_translation([i as f32 * 2.0, 0.0, 0.0].into());
+    ///     }
+    /// }
+    /// ```
+    pub fn add_objects(&mut self, object_paths: &[&str]) -> Vec<usize> {
+        let mut object_index = self.app_state.scene.objects.len();
+        let results = self.app_state.scene.add_objects(object_paths);
+
+        let mut added = Vec::new();
+        for (path, result) in object_paths.iter().zip(results) {
+            match result {
+                Ok(()) => {
+                    self.name_object_from_path(object_index, path);
+                    added.push(object_index);
+                    object_index += 1;
+                }
+                Err(error) => self.app_state.notifications.error(error.to_string()),
+            }
+        }
+        added
+    }
+
+    /// Name an object after its file for UI display
+    fn name_object_from_path(&mut self, object_index: usize, object_path: &str) {
+        if let Some(object) = self.app_state.scene.objects.get_mut(object_index) {
+            let object_name = std::path::Path::new(object_path)
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("Object")
+                .to_string();
+            object.set_name(object_name);
+            object.sync_transform_to_ui();
+        }
+    }
+
+    /// Adds a 3D object without builder pattern (legacy compatibility).
+    ///
+    /// This is a simple method for adding objects without the builder pattern.
+    /// Use [`add_object`] for more configuration options.
+    ///
+    /// # Arguments
+    ///
+    /// * `object_path` - Path to the 3D model file
+    ///
+    /// [`add_object`]: Self::add_object
+    pub fn add_object_simple(&mut self, object_path: &str) {
+        if let Err(error) = self.app_state.scene.add_object(object_path) {
+            self.app_state.notifications.error(error.to_string());
+        }
+    }
+
+    /// Add a procedural cube to the scene.
+    ///
+    /// Creates a unit cube (1x1x1) centered at the origin with proper normals and texture coordinates.
+    /// The cube can be scaled and positioned using the returned [`ObjectBuilder`].
+    ///
+    /// # Returns
+    ///
+    /// An [`ObjectBuilder`] for further configuration of the cube.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
     /// let mut app = haggis::default();
     /// 
     /// // Add a simple cube
@@ -950,22 +2051,83 @@ impl HaggisApp {
     ///     .with_name("Pillar")
     ///     .with_material("stone");
     ///
-    /// // Add a low-poly cylinder
-    /// app.add_cylinder(0.5, 1.5, 6)
-    ///     .with_name("Hex Pillar")
-    ///     .with_transform([3.0, 0.0, 0.0], 1.0, 0.0);
+    /// // Add a low-poly cylinder
+    /// app.add_cylinder(0.5, 1.5, 6)
+    ///     .with_name("Hex Pillar")
+    ///     .with_transform([3.0, 0.0, 0.0], 1.0, 0.0);
+    /// ```
+    pub fn add_cylinder(&mut self, radius: f32, height: f32, segments: u32) -> ObjectBuilder {
+        let object_index = self.app_state.scene.objects.len();
+        let cylinder_geometry = crate::gfx::geometry::generate_cylinder(radius, height, segments);
+        self.app_state.scene.add_procedural_object(cylinder_geometry, "Cylinder");
+
+        // Sync transform for UI
+        if let Some(object) = self.app_state.scene.objects.get_mut(object_index) {
+            object.sync_transform_to_ui();
+        }
+
+        ObjectBuilder::new(self, object_index)
+    }
+
+    /// Registers a prefab that can then be spawned by name.
+    ///
+    /// Defining a prefab under an existing name replaces it.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use haggis::gfx::{geometry::generate_cylinder, scene::Prefab};
+    ///
+    /// let mut app = haggis::default();
+    /// // A small sphere on a stem; child offsets are in the parent's scaled frame
+    /// app.define_prefab(
+    ///     "probe",
+    ///     Prefab::sphere().with_scale(0.1).with_child(
+    ///         Prefab::from_geometry(generate_cylinder(0.2, 5.0, 12)).with_offset([0.0, 0.0, -2.5]),
+    ///     ),
+    /// );
+    /// ```
+    pub fn define_prefab(&mut self, name: &str, prefab: Prefab) {
+        self.app_state.scene.define_prefab(name, prefab);
+    }
+
+    /// Spawns a registered prefab with its root at `position`.
+    ///
+    /// Errors (unknown prefab, missing model file) are shown as a
+    /// notification and nothing is spawned.
+    ///
+    /// # Returns
+    ///
+    /// Indices of the created objects, parents before children
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use haggis::gfx::scene::Prefab;
+    ///
+    /// let mut app = haggis::default();
+    /// app.define_prefab("boundary_marker", Prefab::cube().with_scale(0.05));
+    /// app.spawn_prefab("boundary_marker", [-1.0, -1.0, -1.0]);
+    /// app.spawn_prefab("boundary_marker", [1.0, 1.0, 1.0]);
     /// ```
-    pub fn add_cylinder(&mut self, radius: f32, height: f32, segments: u32) -> ObjectBuilder {
-        let object_index = self.app_state.scene.objects.len();
-        let cylinder_geometry = crate::gfx::geometry::generate_cylinder(radius, height, segments);
-        self.app_state.scene.add_procedural_object(cylinder_geometry, "Cylinder");
-
-        // Sync transform for UI
-        if let Some(object) = self.app_state.scene.objects.get_mut(object_index) {
-            object.sync_transform_to_ui();
+    pub fn spawn_prefab(&mut self, name: &str, position: [f32; 3]) -> Vec<usize> {
+        match self.try_spawn_prefab(name, position) {
+            Ok(objects) => objects,
+            Err(error) => {
+                self.app_state.notifications.error(error.to_string());
+                Vec::new()
+            }
         }
+    }
 
-        ObjectBuilder::new(self, object_index)
+    /// Spawns a registered prefab, returning an error if it can't be created.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::UnknownPrefab`] if no prefab has that name, or the
+    /// model loading error for a missing or malformed file
+    pub fn try_spawn_prefab(&mut self, name: &str, position: [f32; 3]) -> Result<Vec<usize>> {
+        self.app_state.scene.spawn_prefab(name, position)
     }
 
     /// Initialize the instanced grid system for high-performance rendering
@@ -978,7 +2140,7 @@ impl HaggisApp {
     pub fn initialize_instanced_grid(&mut self, max_instances: u32) {
         if let Some(ref mut render_engine) = self.app_state.render_engine {
             render_engine.initialize_instanced_grid(max_instances);
-            println!("🎲 Initialized instanced grid renderer (max {} instances)", max_instances);
+            tracing::info!("Initialized instanced grid renderer (max {} instances)", max_instances);
         }
     }
 
@@ -1023,70 +2185,57 @@ impl ApplicationHandler for AppState {
             return;
         }
 
-        if let Ok(window) = event_loop.create_window(
-            WindowAttributes::default().with_inner_size(winit::dpi::LogicalSize::new(1200, 800)),
-        ) {
-            let window_handle = Arc::new(window);
-            self.window = Some(window_handle.clone());
-
-            // Use physical size for render engine, not logical size
-            let physical_size = window_handle.inner_size();
-            let (width, height) = (physical_size.width, physical_size.height);
-
-            #[cfg(debug_assertions)]
-            {
-                println!("Window created - Physical size: {}x{}", width, height);
-                println!("Window scale factor: {}", window_handle.scale_factor());
-            }
-
-            let window_clone = window_handle.clone();
-            let renderer =
-                pollster::block_on(
-                    async move { RenderEngine::new(window_clone, width, height).await },
-                );
-
-            // Initialize scene GPU resources (objects)
-            self.scene
-                .init_gpu_resources(renderer.device(), renderer.queue());
-
-            // Update all transforms after GPU initialization
-            self.scene.update_all_transforms(renderer.queue());
-
-            // Force material update if needed
-            self.scene
-                .update_materials(renderer.device(), renderer.queue());
-
-            // Create UI manager with correct surface dimensions, style, and font
-            let mut ui_manager = UiManager::new(
-                renderer.device(),
-                renderer.queue(),
-                renderer.surface_format(),
-                &window_handle,
-                self.ui_style,
-                self.ui_font.clone(),
-            );
-
-            // Set ImGui display size to match actual surface size
-            let (surface_width, surface_height) = renderer.get_surface_size();
-            ui_manager.update_display_size(surface_width, surface_height);
-
-            self.ui_manager = Some(ui_manager);
-            self.render_engine = Some(renderer);
-
-            // Configure VSync based on initial settings
-            if let Some(render_engine) = &mut self.render_engine {
-                render_engine.set_vsync(self.enable_vsync);
-            }
+        let (width, height) = self.window_config.size;
+        let attributes = WindowAttributes::default()
+            .with_title(self.window_config.title.clone())
+            .with_inner_size(winit::dpi::LogicalSize::new(width, height))
+            .with_fullscreen(
+                self.window_config
+                    .fullscreen
+                    .then_some(Fullscreen::Borderless(None)),
+            )
+            .with_maximized(self.session.maximized);
+        let attributes = match self.session.window_position {
+            Some((x, y)) => attributes.with_position(winit::dpi::PhysicalPosition::new(x, y)),
+            None => attributes,
+        };
 
-            // Initialize GPU resources for current simulation
-            if let Some(render_engine) = &mut self.render_engine {
-                self.simulation_manager
-                    .initialize_gpu(render_engine.device(), render_engine.queue());
+        // Render into the page's canvas on the web
+        #[cfg(target_arch = "wasm32")]
+        let attributes = {
+            use winit::platform::web::WindowAttributesExtWebSys;
+            attributes.with_canvas(crate::platform::canvas())
+        };
 
-                // Initialize GPU resources for visualizations
-                self.visualization_manager
-                    .initialize_gpu(render_engine.device(), render_engine.queue());
+        let window = match event_loop.create_window(attributes) {
+            Ok(window) => window,
+            Err(error) => {
+                self.fatal_error = Some(HaggisError::Window(error.to_string()));
+                event_loop.exit();
+                return;
             }
+        };
+        let window_handle = Arc::new(window);
+        self.window = Some(window_handle.clone());
+
+        // Use physical size for render engine, not logical size
+        let physical_size = window_handle.inner_size();
+        let (width, height) = (physical_size.width.max(1), physical_size.height.max(1));
+
+        tracing::debug!(
+            "Window created - physical size {}x{}, scale factor {}",
+            width,
+            height,
+            window_handle.scale_factor()
+        );
+
+        self.create_render_engine(window_handle, width, height);
+        self.finish_gpu_setup();
+
+        // Native GPU setup is synchronous, so a failure is known right away;
+        // `try_run` reports it once the event loop has stopped
+        if self.fatal_error.is_some() {
+            event_loop.exit();
         }
     }
 
@@ -1109,6 +2258,21 @@ impl ApplicationHandler for AppState {
         window_id: winit::window::WindowId,
         event: winit::event::WindowEvent,
     ) {
+        // On the web the render engine arrives asynchronously after `resumed`
+        self.finish_gpu_setup();
+
+        if self.fatal_error.is_some() {
+            event_loop.exit();
+            return;
+        }
+
+        if let Some(error) = self.render_engine.as_ref().and_then(RenderEngine::device_lost) {
+            self.notifications
+                .error(format!("{}. Recreating GPU resources.", error));
+            self.recover_from_device_lost();
+            return;
+        }
+
         let Some(render_engine) = self.render_engine.as_mut() else {
             return;
         };
@@ -1117,6 +2281,36 @@ impl ApplicationHandler for AppState {
             return;
         };
 
+        // Track input state and key bindings before the UI can swallow the event,
+        // so releases are never lost; presses are ignored while the UI has focus
+        match &event {
+            WindowEvent::KeyboardInput { event: key_event, .. } => {
+                let ui_wants_keyboard = self
+                    .ui_manager
+                    .as_ref()
+                    .is_some_and(|ui_manager| ui_manager.wants_keyboard());
+                let action = self.scene.input.process_key_event(
+                    key_event,
+                    &self.input_map,
+                    ui_wants_keyboard,
+                );
+                if action == Some(Action::Quit) {
+                    event_loop.exit();
+                    return;
+                }
+            }
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.scene.input.set_modifiers(modifiers.state());
+            }
+            WindowEvent::Focused(false) => {
+                self.scene.input.clear();
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                self.scene.input.mouse_position = (position.x as f32, position.y as f32);
+            }
+            _ => {}
+        }
+
         // UI input handling takes precedence over camera controls
         if let Some(ui_manager) = self.ui_manager.as_mut() {
             let ui_event: winit::event::Event<()> = winit::event::Event::WindowEvent {
@@ -1132,19 +2326,8 @@ impl ApplicationHandler for AppState {
         match event {
             WindowEvent::KeyboardInput { event, .. } => {
                 // Handle camera keyboard events (like Shift for panning)
+                // Other shortcuts are resolved through the input map above
                 self.scene.camera_manager.process_keyboard_event(&event);
-
-                // Handle other keyboard shortcuts
-                if let winit::event::KeyEvent {
-                    physical_key: winit::keyboard::PhysicalKey::Code(key_code),
-                    state: winit::event::ElementState::Pressed,
-                    ..
-                } = event
-                {
-                    if matches!(key_code, winit::keyboard::KeyCode::Escape) {
-                        event_loop.exit();
-                    }
-                }
             }
             WindowEvent::Resized(PhysicalSize { width, height }) => {
                 // Update all systems to handle new window size
@@ -1159,10 +2342,10 @@ impl ApplicationHandler for AppState {
                     let (actual_width, actual_height) = render_engine.get_surface_size();
                     ui_manager.update_display_size(actual_width, actual_height);
                 }
+
+                self.scene.events.emit(WindowResizeEvent { width, height });
             }
-            WindowEvent::ScaleFactorChanged {
-                scale_factor: _, ..
-            } => {
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
                 let PhysicalSize { width, height } = window.inner_size();
 
                 // Handle high-DPI display changes
@@ -1172,14 +2355,46 @@ impl ApplicationHandler for AppState {
                     .resize_projection(width, height);
                 render_engine.resize(width, height);
 
+                // Re-rasterize fonts and rescale metrics for the new monitor
                 if let Some(ui_manager) = self.ui_manager.as_mut() {
                     let (actual_width, actual_height) = render_engine.get_surface_size();
                     ui_manager.update_display_size(actual_width, actual_height);
+                    ui_manager.set_scale_factor(
+                        render_engine.device(),
+                        render_engine.queue(),
+                        scale_factor as f32,
+                    );
                 }
             }
             WindowEvent::CursorMoved { position, .. } => {
                 // Track mouse position for picking
                 self.mouse_position = (position.x as f32, position.y as f32);
+                if self.painting.is_some() {
+                    self.handle_brush_stroke(false);
+                }
+                if self.dragging.is_some() {
+                    self.handle_drag(DragPhase::Move);
+                }
+            }
+            WindowEvent::MouseInput {
+                button: winit::event::MouseButton::Left,
+                state: winit::event::ElementState::Released,
+                ..
+            } if self.dragging.is_some() => {
+                self.handle_drag(DragPhase::End);
+                self.dragging = None;
+            }
+            WindowEvent::MouseInput {
+                button:
+                    button @ (winit::event::MouseButton::Left | winit::event::MouseButton::Right),
+                state,
+                ..
+            } if self.simulation_manager.paint_mode() || self.painting.is_some() => {
+                self.painting = None;
+                if state == winit::event::ElementState::Pressed && !self.ui_wants_input {
+                    self.painting = Some(button == winit::event::MouseButton::Right);
+                    self.handle_brush_stroke(true);
+                }
             }
             WindowEvent::MouseInput { 
                 button: winit::event::MouseButton::Left,
@@ -1193,13 +2408,117 @@ impl ApplicationHandler for AppState {
                 event_loop.exit();
             }
             WindowEvent::RedrawRequested => {
+                if self.render_engine.is_none() {
+                    return;
+                }
+                crate::logging::crash::end_frame();
+
+                // Apply built-in actions triggered by key bindings since the last frame;
+                // custom actions are left for user code to query from `scene.input`
+                let triggered_actions = self.scene.input.triggered_actions().to_vec();
+                for action in triggered_actions {
+                    match action {
+                        Action::ToggleSimulation => {
+                            let event = if self.simulation_manager.is_paused() {
+                                SimulationEvent::Resume
+                            } else {
+                                SimulationEvent::Pause
+                            };
+                            self.scene.events.emit(event);
+                        }
+                        Action::StepSimulation => {
+                            self.scene.events.emit(SimulationEvent::Step);
+                        }
+                        Action::StepSimulationBatch => {
+                            let steps = self.simulation_manager.step_count();
+                            self.scene.events.emit(SimulationEvent::StepBy(steps));
+                        }
+                        Action::ResetSimulation => {
+                            self.scene.events.emit(SimulationEvent::Reset);
+                        }
+                        Action::ResetCamera => {
+                            self.scene.camera_manager.camera.reset_to_default();
+                        }
+                        Action::TogglePerformancePanel => {
+                            self.show_performance_panel = !self.show_performance_panel;
+                        }
+                        Action::ToggleTransformPanel => {
+                            self.show_transform_panel = !self.show_transform_panel;
+                        }
+                        Action::ToggleConsole => self.console.toggle(),
+                        Action::ToggleLogConsole => self.log_window.toggle(),
+                        Action::ToggleThemeEditor => self.theme_editor.toggle(),
+                        Action::ToggleBufferInspector => self.buffer_inspector.toggle(),
+                        Action::ToggleMeasureMode => self.measure_tool.toggle(),
+                        Action::ToggleAnnotations => self.scene.annotations.toggle(),
+                        Action::TogglePaintMode => {
+                            let paint_mode = self.simulation_manager.paint_mode();
+                            self.simulation_manager.set_paint_mode(!paint_mode);
+                        }
+                        Action::ToggleGrid => {
+                            let overlay = &mut self.scene.reference_overlay;
+                            overlay.show_grid = !overlay.show_grid;
+                            overlay.show_world_axes = overlay.show_grid;
+                        }
+                        Action::ToggleRenderSettings => {
+                            self.show_render_settings_panel = !self.show_render_settings_panel;
+                        }
+                        Action::IncreaseUiScale => {
+                            self.ui_scale = (self.ui_scale + UI_SCALE_STEP).min(UI_SCALE_RANGE.1);
+                        }
+                        Action::DecreaseUiScale => {
+                            self.ui_scale = (self.ui_scale - UI_SCALE_STEP).max(UI_SCALE_RANGE.0);
+                        }
+                        // Quit is handled immediately when the key is pressed
+                        Action::Quit | Action::Custom(_) => {}
+                    }
+                }
+
+                // Run commands from the console and scripts
+                for line in std::mem::take(&mut self.pending_commands) {
+                    if line.trim() == "clear" {
+                        self.console.clear();
+                        continue;
+                    }
+                    let result = self.commands.run_line(
+                        &line,
+                        &mut self.scene,
+                        Some(&mut self.simulation_manager),
+                    );
+                    if !line.trim().is_empty() {
+                        self.console.push_result(&line, &result);
+                    }
+                }
+
+                // Act on simulation control requests from key bindings, UI or simulations
+                for event in self.simulation_events.drain() {
+                    match event {
+                        SimulationEvent::Reset => {
+                            self.simulation_manager.reset_simulation(&mut self.scene);
+                        }
+                        SimulationEvent::Pause => self.simulation_manager.set_paused(true),
+                        SimulationEvent::Resume => self.simulation_manager.set_paused(false),
+                        SimulationEvent::Step => self.simulation_manager.step(),
+                        SimulationEvent::StepBy(steps) => self.simulation_manager.step_by(steps),
+                        SimulationEvent::RunUntil(step) => {
+                            self.simulation_manager.run_until(step);
+                        }
+                    }
+                }
+                for notification in self.notification_events.drain() {
+                    self.notifications.push(notification);
+                }
+                for sample in self.metric_events.drain() {
+                    self.metrics.insert(sample.name, sample.value);
+                }
+
                 let Some(render_engine) = self.render_engine.as_mut() else {
                     return;
                 };
 
                 // Custom frame timing that accounts for framerate limiting
                 let actual_frame_time = self.last_performance_frame_time.elapsed();
-                self.last_performance_frame_time = std::time::Instant::now();
+                self.last_performance_frame_time = crate::platform::Instant::now();
                 
                 // Manually add frame time to performance monitor to show correct limited FPS
                 self.performance_monitor.add_manual_frame_time(actual_frame_time);
@@ -1214,6 +2533,18 @@ impl ApplicationHandler for AppState {
                     Some(render_engine.device()),
                     Some(render_engine.queue()),
                 );
+                if let Some(divergence) = self.simulation_manager.take_divergence_report() {
+                    self.notifications.warning(divergence.to_string());
+                    #[cfg(feature = "audio")]
+                    if let Some(audio) = self.audio.as_mut() {
+                        audio.alert();
+                    }
+                }
+                #[cfg(feature = "audio")]
+                if let Some(audio) = self.audio.as_mut() {
+                    audio.update(&self.scene);
+                    audio.update_sonification(&self.metrics);
+                }
 
                 // Update visualizations (no longer creates scene objects)
                 self.visualization_manager.update(
@@ -1255,7 +2586,26 @@ impl ApplicationHandler for AppState {
                 self.scene
                     .update_materials(render_engine.device(), render_engine.queue());
 
+                // Track the follow target (if any) after simulations have moved objects
+                self.scene.update_camera_follow(delta_time);
+
+                // Apply UI scale and theme changes before the UI frame starts
+                if let Some(ui_manager) = self.ui_manager.as_mut() {
+                    ui_manager.set_ui_scale(
+                        render_engine.device(),
+                        render_engine.queue(),
+                        self.ui_scale,
+                    );
+                    if let Some(theme) = self.theme_editor.take_changed() {
+                        ui_manager.set_theme(&theme);
+                    }
+                }
+                self.buffer_inspector
+                    .update(render_engine.device(), render_engine.queue());
+
                 // Update phase: Scene logic and UI interaction
+                // The camera projects with the engine's depth convention
+                self.scene.camera_manager.camera.depth_mode = render_engine.depth_mode();
                 self.scene.update();
                 if let (Some(ui_manager), Some(ui_callback)) =
                     (self.ui_manager.as_mut(), &self.ui_callback)
@@ -1264,7 +2614,21 @@ impl ApplicationHandler for AppState {
                         // When user provides a UI callback, they have full control over UI
                         // The user can call default_transform_panel() if they want it
                         
+                        self.scene
+                            .reference_overlay
+                            .render_ui(ui, &self.scene.camera_manager.camera);
+                        let view_proj = self.scene.camera_manager.get_view_proj_matrix();
+                        self.visualization_manager.render_labels(ui, view_proj);
+                        self.simulation_manager
+                            .render_visualization_labels(ui, view_proj);
+                        let unit = &self.scene.reference_overlay.unit;
+                        self.measure_tool.render_labels(ui, view_proj, unit);
+                        self.scene.annotations.render_labels(ui, view_proj);
+
                         // Render simulation UI first
+                        if self.show_simulation_toolbar {
+                            self.simulation_manager.render_toolbar(ui, &mut self.scene);
+                        }
                         self.simulation_manager.render_ui(ui, &mut self.scene);
 
                         // Render visualization UI (right side)
@@ -1277,9 +2641,26 @@ impl ApplicationHandler for AppState {
                         if self.show_performance_panel {
                             self.performance_monitor.render_ui(ui);
                         }
+                        if self.show_render_settings_panel {
+                            self.scene.render_settings.render_ui(ui);
+                        }
 
                         // Then render user UI callback if provided
                         ui_callback(ui, &mut self.scene, &mut self.selected_object_index);
+
+                        self.notifications.render_ui(ui);
+                        self.log_window.render_ui(ui);
+                        self.theme_editor.render_ui(ui);
+                        self.buffer_inspector.render_ui(ui);
+                        self.measure_tool
+                            .render_ui(ui, &self.scene.reference_overlay.unit);
+                        let target = self.scene.camera_manager.camera.target;
+                        self.scene.annotations.render_ui(ui, target.into());
+
+                        // Console last so it draws over other windows
+                        if let Some(line) = self.console.render_ui(ui) {
+                            self.pending_commands.push(line);
+                        }
                     });
 
                     // Store UI input state for object picking
@@ -1292,6 +2673,17 @@ impl ApplicationHandler for AppState {
                 } else if let Some(ui_manager) = self.ui_manager.as_mut() {
                     // If no user UI callback, still render default UI, simulation UI and visualizations
                     let ui_wants_input = ui_manager.update_logic(window, |ui| {
+                        self.scene
+                            .reference_overlay
+                            .render_ui(ui, &self.scene.camera_manager.camera);
+                        let view_proj = self.scene.camera_manager.get_view_proj_matrix();
+                        self.visualization_manager.render_labels(ui, view_proj);
+                        self.simulation_manager
+                            .render_visualization_labels(ui, view_proj);
+                        let unit = &self.scene.reference_overlay.unit;
+                        self.measure_tool.render_labels(ui, view_proj, unit);
+                        self.scene.annotations.render_labels(ui, view_proj);
+
                         // Render default object transformation UI (left side) if enabled
                         if self.show_transform_panel {
                             default_transform_panel(
@@ -1301,6 +2693,9 @@ impl ApplicationHandler for AppState {
                             );
                         }
 
+                        if self.show_simulation_toolbar {
+                            self.simulation_manager.render_toolbar(ui, &mut self.scene);
+                        }
                         self.simulation_manager.render_ui(ui, &mut self.scene);
                         self.visualization_manager.render_ui(ui);
                         self.gizmo_manager.render_ui(ui, &mut self.scene);
@@ -1309,6 +2704,22 @@ impl ApplicationHandler for AppState {
                         if self.show_performance_panel {
                             self.performance_monitor.render_ui(ui);
                         }
+                        if self.show_render_settings_panel {
+                            self.scene.render_settings.render_ui(ui);
+                        }
+
+                        self.notifications.render_ui(ui);
+                        self.log_window.render_ui(ui);
+                        self.theme_editor.render_ui(ui);
+                        self.buffer_inspector.render_ui(ui);
+                        self.measure_tool
+                            .render_ui(ui, &self.scene.reference_overlay.unit);
+                        let target = self.scene.camera_manager.camera.target;
+                        self.scene.annotations.render_ui(ui, target.into());
+
+                        if let Some(line) = self.console.render_ui(ui) {
+                            self.pending_commands.push(line);
+                        }
                     });
 
                     // Store UI input state for object picking
@@ -1326,7 +2737,22 @@ impl ApplicationHandler for AppState {
                     return;
                 };
 
+                render_engine.set_render_settings(&self.scene.render_settings);
+                let mut clip_planes = self.scene.clip_planes.clone();
+                clip_planes.extend(self.visualization_manager.get_clip_planes());
+                clip_planes.extend(self.simulation_manager.get_clip_planes());
+                render_engine.set_clip_planes(&clip_planes);
+                render_engine.set_projector(self.scene.projector.as_ref());
                 render_engine.update(self.scene.camera_manager.camera.uniform);
+                let mut lines = self.visualization_manager.get_visualization_lines();
+                lines.extend(self.simulation_manager.get_visualization_lines());
+                render_engine.update_lines(&self.scene.reference_overlay, &lines);
+                let mut agents = self.visualization_manager.get_agent_batches();
+                agents.extend(self.simulation_manager.get_agent_batches());
+                render_engine.update_agents(agents);
+                let mut volumes = self.visualization_manager.get_volume_batches();
+                volumes.extend(self.simulation_manager.get_volume_batches());
+                render_engine.update_volumes(volumes);
 
                 // Collect visualization planes from both the visualization manager and simulation manager
                 let mut visualization_planes =
@@ -1334,26 +2760,83 @@ impl ApplicationHandler for AppState {
                 let simulation_planes = self.simulation_manager.get_visualization_planes();
                 visualization_planes.extend(simulation_planes);
 
+                // Render offscreen 3D views and hand new textures to the UI renderer
+                let texture_updates = render_engine.render_textures(
+                    &self.scene,
+                    &visualization_planes,
+                    &self.render_textures,
+                );
+                if let Some(ui_manager) = self.ui_manager.as_mut() {
+                    for update in texture_updates {
+                        let texture_id = ui_manager.register_texture(
+                            render_engine.device(),
+                            update.texture,
+                            update.view,
+                            update.size,
+                            update.handle.texture_id(),
+                        );
+                        update.handle.set_texture_id(texture_id);
+                    }
+                }
+
+                // Render both headset eyes and pick with controllers
+                #[cfg(feature = "xr")]
+                if let Some(session) = self.xr_session.as_mut() {
+                    let picks = session.render_frame(
+                        render_engine,
+                        &self.scene,
+                        &visualization_planes,
+                        &mut self.object_picker,
+                    );
+                    for pick in picks {
+                        self.selected_object_index = Some(pick.object_index);
+                        self.scene.events.emit(pick);
+                    }
+                }
+
                 if self.ui_manager.is_some() {
-                    // Render 3D scene with visualization planes and UI overlay
-                    render_engine.render_frame_with_visualizations_and_ui(
+                    // Render 3D scene with visualization planes, viewports and UI overlay
+                    let presented = render_engine.render_frame_with_viewports(
                         &self.scene,
                         &visualization_planes,
-                        |device, queue, encoder, color_attachment| {
-                            self.ui_manager.as_mut().unwrap().render_display_only(
-                                device,
-                                queue,
-                                encoder,
-                                window,
-                                color_attachment,
-                            );
-                        },
+                        &self.viewports,
+                        Some(
+                            |device: &wgpu::Device,
+                             queue: &wgpu::Queue,
+                             encoder: &mut wgpu::CommandEncoder,
+                             color_attachment: &wgpu::TextureView| {
+                                self.ui_manager.as_mut().unwrap().render_display_only(
+                                    device,
+                                    queue,
+                                    encoder,
+                                    window,
+                                    color_attachment,
+                                );
+                            },
+                        ),
                     );
+
+                    // The UI frame must still be ended when no surface texture was available
+                    if !presented {
+                        if let Some(ui_manager) = self.ui_manager.as_mut() {
+                            ui_manager.discard_frame();
+                        }
+                    }
                 } else {
-                    // Render 3D scene with visualization planes only
-                    render_engine
-                        .render_frame_with_visualizations(&self.scene, &visualization_planes);
+                    // Render 3D scene with visualization planes and viewports only
+                    render_engine.render_frame_with_viewports(
+                        &self.scene,
+                        &visualization_planes,
+                        &self.viewports,
+                        None::<fn(&wgpu::Device, &wgpu::Queue, &mut wgpu::CommandEncoder, &wgpu::TextureView)>,
+                    );
                 }
+
+                self.performance_monitor
+                    .update_prepass_timing(render_engine.prepass_timing());
+
+                // Pressed/released keys and actions only last for one frame
+                self.scene.input.end_frame();
             }
             _ => (),
         }
@@ -1387,6 +2870,13 @@ impl ApplicationHandler for AppState {
             }
         }
 
+        // Drags paint instead of orbiting in paint mode, or move a draggable object
+        if (self.simulation_manager.paint_mode() || self.dragging.is_some())
+            && matches!(event, winit::event::DeviceEvent::MouseMotion { .. })
+        {
+            return;
+        }
+
         self.scene.camera_manager.process_event(&event, window);
     }
 
@@ -1397,33 +2887,399 @@ impl ApplicationHandler for AppState {
     ///
     /// # Arguments
     ///
-    /// * `_event_loop` - The active event loop (unused)
-    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
+    /// * `event_loop` - The active event loop, told how long it may sleep
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        // Served here so remote clients get answers even while the window is hidden
+        #[cfg(feature = "remote")]
+        self.serve_remote_requests();
+        #[cfg(feature = "telemetry")]
+        self.export_telemetry();
+        self.restore_session_parameters();
+
         if let Some(ref window) = self.window {
-            // Apply framerate limiting here to control redraw frequency
-            if let Some(fps_limit) = self.framerate_limit {
-                let target_frame_time = std::time::Duration::from_secs_f32(1.0 / fps_limit);
-                let elapsed = self.last_frame_time.elapsed();
-                
-                if elapsed >= target_frame_time {
-                    // Enough time has passed, request redraw
-                    self.last_frame_time = std::time::Instant::now();
+            // Apply framerate limiting here to control redraw frequency; between
+            // frames the loop sleeps until the next one is due (or an event arrives)
+            let now = crate::platform::Instant::now();
+            match self.frame_pacer.poll(now, self.framerate_limit) {
+                None => {
+                    event_loop.set_control_flow(ControlFlow::Poll);
                     window.request_redraw();
-                } else {
-                    // Not enough time has passed, just short sleep
-                    // The simulation runs continuously in its own update loop, 
-                    // we don't need to drive it from the framerate limiter
-                    std::thread::sleep(std::time::Duration::from_millis(1));
                 }
-            } else {
-                // No framerate limit, request redraw immediately
-                window.request_redraw();
+                Some(next_frame) => event_loop.set_control_flow(ControlFlow::WaitUntil(next_frame)),
+            }
+        }
+    }
+
+    /// Called once when the event loop stops; saves the annotations and
+    /// session files
+    fn exiting(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(path) = &self.annotations_file {
+            if let Err(error) = self.scene.annotations.save(path) {
+                tracing::error!("{error}");
+            }
+        }
+        if let Some(path) = self.session_file.clone() {
+            self.capture_session();
+            if let Err(error) = self.session.save(&path) {
+                tracing::error!("{error}");
             }
         }
     }
 }
 
 impl AppState {
+    /// Visibility flags of the built-in panels by session name
+    fn panel_flags_mut(&mut self) -> [(&'static str, &mut bool); 8] {
+        [
+            ("transform", &mut self.show_transform_panel),
+            ("performance", &mut self.show_performance_panel),
+            ("simulation_toolbar", &mut self.show_simulation_toolbar),
+            ("render_settings", &mut self.show_render_settings_panel),
+            ("log", &mut self.log_window.open),
+            ("theme_editor", &mut self.theme_editor.open),
+            ("buffer_inspector", &mut self.buffer_inspector.open),
+            ("annotations", &mut self.scene.annotations.open),
+        ]
+    }
+
+    /// Apply the parts of a loaded session that don't need the window
+    fn restore_session(&mut self, session: session::Session) {
+        if let Some(size) = session.window_size {
+            self.window_config.size = size;
+        }
+        if let Some(pose) = session.camera {
+            pose.apply(&mut self.scene.camera_manager.camera);
+        }
+        for (name, flag) in self.panel_flags_mut() {
+            if let Some(&visible) = session.panels.get(name) {
+                *flag = visible;
+            }
+        }
+        self.session = session;
+        self.session_simulation = None;
+    }
+
+    /// Restore the saved parameters of a newly attached simulation
+    fn restore_session_parameters(&mut self) {
+        if self.session_file.is_none() {
+            return;
+        }
+        let name = self.simulation_manager.simulation_name().map(str::to_string);
+        if name == self.session_simulation {
+            return;
+        }
+        self.session_simulation = name.clone();
+        let (Some(name), Some(params)) = (name, self.simulation_manager.params_mut()) else {
+            return;
+        };
+        let Some(text) = self.session.parameters.get(&name) else {
+            return;
+        };
+        match params.apply_text(text) {
+            Ok(changed) => params.emit_changes(&changed, &mut self.scene.events),
+            Err(message) => self
+                .notifications
+                .error(format!("Saved parameters of {name}: {message}")),
+        }
+    }
+
+    /// Record the window, camera, UI and current parameters in `session`
+    fn capture_session(&mut self) {
+        if let Some(window) = &self.window {
+            let size = window.inner_size().to_logical::<u32>(window.scale_factor());
+            self.session.window_size = Some((size.width, size.height));
+            self.session.window_position = window.outer_position().ok().map(|p| (p.x, p.y));
+            self.session.maximized = window.is_maximized();
+        }
+        self.session.camera = Some(session::CameraPose::of(&self.scene.camera_manager.camera));
+        let panels = self
+            .panel_flags_mut()
+            .map(|(name, flag)| (name.to_string(), *flag));
+        self.session.panels = panels.into_iter().collect();
+        if let Some(ui_manager) = self.ui_manager.as_mut() {
+            self.session.ui_layout = Some(ui_manager.save_layout());
+        }
+        let name = self.simulation_manager.simulation_name().map(str::to_string);
+        if let (Some(name), Some(params)) = (name, self.simulation_manager.params_mut()) {
+            self.session.parameters.insert(name, params.to_text());
+        }
+    }
+
+    /// Switch the renderer to `present_mode`, warning if it falls back to another
+    fn apply_present_mode(&mut self, present_mode: wgpu::PresentMode) {
+        let Some(render_engine) = &mut self.render_engine else {
+            return;
+        };
+        render_engine.set_present_mode(present_mode);
+        if render_engine.present_mode() != present_mode {
+            self.notifications.warning(format!(
+                "Present mode {:?} not supported, using {:?}",
+                present_mode,
+                render_engine.present_mode()
+            ));
+        }
+    }
+
+    /// Hand the latest metrics and simulation clock to the telemetry exporter
+    #[cfg(feature = "telemetry")]
+    fn export_telemetry(&mut self) {
+        let Some(telemetry) = self.telemetry.as_mut() else {
+            return;
+        };
+        let mut metrics = self.metrics.clone();
+        metrics.insert("fps".to_string(), self.performance_monitor.get_metrics().fps as f64);
+        metrics.insert("frame".to_string(), self.simulation_manager.frame_count() as f64);
+        metrics.insert("simulation_time".to_string(), self.simulation_manager.simulation_time());
+        telemetry.update(&metrics);
+    }
+
+    /// Answer remote control requests and publish the current status
+    #[cfg(feature = "remote")]
+    fn serve_remote_requests(&mut self) {
+        use crate::remote::http::json_string;
+        use crate::remote::{params_json, RemoteCommand, RemoteResponse, RemoteStatus};
+        use crate::simulation::params::ParamValue;
+
+        let Some(server) = self.remote_server.as_ref() else {
+            return;
+        };
+        server.publish(RemoteStatus {
+            simulation: self.simulation_manager.simulation_name().map(str::to_string),
+            paused: self.simulation_manager.is_paused(),
+            frame: self.simulation_manager.frame_count(),
+            time: self.simulation_manager.simulation_time(),
+            fps: self.performance_monitor.get_metrics().fps,
+            metrics: self.metrics.clone(),
+        });
+
+        for request in server.poll() {
+            let response = match &request.command {
+                RemoteCommand::GetParams => Ok(RemoteResponse::Json(
+                    self.simulation_manager
+                        .params_mut()
+                        .map_or_else(|| "{}".to_string(), |params| params_json(params, None)),
+                )),
+                RemoteCommand::SetParam { name, value } => {
+                    match self.simulation_manager.params_mut() {
+                        Some(params) if params.get(name).is_some() => {
+                            if params.set(name, ParamValue::Float(*value)) {
+                                params.emit_changes(&[name.as_str()], &mut self.scene.events);
+                            }
+                            Ok(RemoteResponse::Json(params_json(params, Some(name))))
+                        }
+                        _ => Err(format!("unknown parameter '{}'", name)),
+                    }
+                }
+                RemoteCommand::Simulation(event) => {
+                    self.scene.events.emit(*event);
+                    Ok(RemoteResponse::Json("{}".to_string()))
+                }
+                RemoteCommand::Command(line) => {
+                    let result = self.commands.run_line(
+                        line,
+                        &mut self.scene,
+                        Some(&mut self.simulation_manager),
+                    );
+                    self.console.push_result(line, &result);
+                    result.map(|output| {
+                        RemoteResponse::Json(format!("{{\"output\":{}}}", json_string(&output)))
+                    })
+                }
+                RemoteCommand::Screenshot { size } => match self.render_engine.as_mut() {
+                    Some(render_engine) => {
+                        let size = size.unwrap_or_else(|| render_engine.get_surface_size());
+                        let mut planes = self.visualization_manager.get_visualization_planes();
+                        planes.extend(self.simulation_manager.get_visualization_planes());
+                        render_engine
+                            .capture_frame(&self.scene, &planes, size)
+                            .map(|frame| RemoteResponse::Png(frame.to_png()))
+                            .map_err(|error| format!("capture failed: {}", error))
+                    }
+                    None => Err("no render engine".to_string()),
+                },
+            };
+            request.respond(response);
+        }
+    }
+
+    /// Start creating the render engine for a window
+    ///
+    /// GPU setup is async; native blocks on it, the web finishes it on a later
+    /// event. Either way [`finish_gpu_setup`](Self::finish_gpu_setup) picks it up.
+    fn create_render_engine(&mut self, window_handle: Arc<Window>, width: u32, height: u32) {
+        let pending_renderer = self.pending_renderer.clone();
+        let render_config = self.render_config.clone();
+        crate::platform::run_async(
+            async move {
+                RenderEngine::try_with_config(window_handle, width, height, &render_config).await
+            },
+            move |renderer| {
+                *pending_renderer.borrow_mut() = Some(renderer);
+            },
+        );
+    }
+
+    /// Drop everything created from a lost GPU device and start over with a new one
+    ///
+    /// Scene objects and materials are re-uploaded and simulations get
+    /// `initialize_gpu` again once the new render engine is ready.
+    fn recover_from_device_lost(&mut self) {
+        self.render_engine = None;
+        self.ui_manager = None;
+        self.scene.release_gpu_resources();
+
+        if let Some(window_handle) = self.window.clone() {
+            let size = window_handle.inner_size();
+            self.create_render_engine(window_handle, size.width.max(1), size.height.max(1));
+        }
+        self.finish_gpu_setup();
+    }
+
+    /// Finish initialization once the render engine has been created
+    ///
+    /// Sets up scene GPU resources, the UI and simulation/visualization GPU state.
+    /// Does nothing until the (possibly asynchronous) render engine creation is done.
+    fn finish_gpu_setup(&mut self) {
+        if self.render_engine.is_some() {
+            return;
+        }
+        let Some(window_handle) = self.window.clone() else {
+            return;
+        };
+        let renderer = match self.pending_renderer.borrow_mut().take() {
+            Some(Ok(renderer)) => renderer,
+            Some(Err(error)) => {
+                self.fatal_error = Some(error);
+                return;
+            }
+            None => return,
+        };
+
+        // Let simulations size their GPU work (or fall back to CPU) before GPU init,
+        // against the compute GPU when they run on one
+        self.simulation_manager
+            .set_compute_device(renderer.compute_device().cloned());
+        self.scene.gpu_capabilities = Some(
+            renderer
+                .compute_device()
+                .map_or(renderer.capabilities(), |compute| &compute.capabilities)
+                .clone(),
+        );
+        if renderer.capabilities().device_type == wgpu::DeviceType::Cpu {
+            self.notifications.warning(format!(
+                "No hardware GPU available, rendering on software adapter {}",
+                renderer.capabilities().adapter_name
+            ));
+        }
+
+        // Initialize scene GPU resources (objects)
+        self.scene
+            .init_gpu_resources(renderer.device(), renderer.queue());
+
+        // Update all transforms after GPU initialization
+        self.scene.update_all_transforms(renderer.queue());
+
+        // Force material update if needed
+        self.scene
+            .update_materials(renderer.device(), renderer.queue());
+
+        // Create UI manager with correct surface dimensions, style, and font
+        let mut ui_manager = UiManager::new(
+            renderer.device(),
+            renderer.queue(),
+            renderer.surface_format(),
+            &window_handle,
+            self.ui_style,
+            self.ui_font.clone(),
+            self.ui_fallback_fonts.clone(),
+        );
+
+        // Restore persisted layouts and apply the default docking layout
+        ui_manager.set_layout_file(self.ui_layout_file.clone());
+        if let Some(layout) = &self.session.ui_layout {
+            ui_manager.load_layout(layout);
+        }
+        ui_manager.set_default_layout(self.ui_default_layout.clone());
+
+        // Set ImGui display size to match actual surface size
+        let (surface_width, surface_height) = renderer.get_surface_size();
+        ui_manager.update_display_size(surface_width, surface_height);
+
+        // Load the theme file over the style preset
+        if let Some(path) = &self.ui_theme_file {
+            let mut theme = ui_manager.theme();
+            match theme.load(path) {
+                Ok(()) => ui_manager.set_theme(&theme),
+                Err(error) => self.notifications.error(error.to_string()),
+            }
+        }
+        self.theme_editor.set_theme(ui_manager.theme());
+
+        self.ui_manager = Some(ui_manager);
+        self.render_engine = Some(renderer);
+
+        // Configure VSync based on initial settings, unless a present mode was requested
+        match self.render_config.present_mode {
+            Some(present_mode) => self.apply_present_mode(present_mode),
+            None => {
+                if let Some(render_engine) = &mut self.render_engine {
+                    render_engine.set_vsync(self.enable_vsync);
+                }
+            }
+        }
+
+        // Initialize GPU resources for current simulation
+        if let Some(render_engine) = &mut self.render_engine {
+            self.simulation_manager
+                .initialize_gpu(render_engine.device(), render_engine.queue());
+
+            // Initialize GPU resources for visualizations
+            self.visualization_manager
+                .initialize_gpu(render_engine.device(), render_engine.queue());
+        }
+
+        window_handle.request_redraw();
+    }
+
+    /// Emit a brush stroke along the ray under the mouse
+    ///
+    /// # Arguments
+    ///
+    /// * `begin` - Whether the stroke starts here, from a button press
+    fn handle_brush_stroke(&mut self, begin: bool) {
+        let (Some(render_engine), Some(erase)) = (self.render_engine.as_ref(), self.painting) else {
+            return;
+        };
+        let (screen_width, screen_height) = render_engine.get_surface_size();
+        let ray = self.object_picker.screen_to_ray(
+            self.mouse_position,
+            (screen_width as f32, screen_height as f32),
+            &self.scene.camera_manager.camera,
+        );
+        self.scene.events.emit(BrushStroke { ray, begin, erase });
+    }
+
+    /// Emit a drag event for the held object along the ray under the mouse
+    fn handle_drag(&mut self, phase: DragPhase) {
+        let (Some(render_engine), Some((object_index, point))) =
+            (self.render_engine.as_ref(), self.dragging)
+        else {
+            return;
+        };
+        let (screen_width, screen_height) = render_engine.get_surface_size();
+        let ray = self.object_picker.screen_to_ray(
+            self.mouse_position,
+            (screen_width as f32, screen_height as f32),
+            &self.scene.camera_manager.camera,
+        );
+        self.scene.events.emit(DragEvent {
+            object_index,
+            phase,
+            ray,
+            point,
+        });
+    }
+
     /// Handle mouse click for object picking
     fn handle_mouse_click(&mut self) {
         // Only pick objects if UI is not capturing input and we have a render engine
@@ -1443,6 +3299,21 @@ impl AppState {
         // Get camera
         let camera = &self.scene.camera_manager.camera;
 
+        // Measurement mode places points on surfaces instead of selecting
+        if self.measure_tool.open {
+            let ray = self
+                .object_picker
+                .screen_to_ray(self.mouse_position, screen_size, camera);
+            if let Some(pick_result) = self.object_picker.pick_surface(&ray, &self.scene) {
+                if let Some(measurement) =
+                    self.measure_tool.add_point(pick_result.intersection_point)
+                {
+                    tracing::debug!("Measured {:?} {}", measurement.mode, measurement.value());
+                }
+            }
+            return;
+        }
+
         // Perform object picking
         if let Some(pick_result) = self.object_picker.pick_object(
             self.mouse_position,
@@ -1450,22 +3321,37 @@ impl AppState {
             camera,
             &self.scene,
         ) {
-            #[cfg(debug_assertions)]
-            {
-                println!(
-                    "Picked object {} at distance {:.2}",
-                    pick_result.object_index, pick_result.distance
+            if let Some(object) = self.scene.objects.get(pick_result.object_index) {
+                tracing::debug!(
+                    "Picked object {} '{}' at distance {:.2}",
+                    pick_result.object_index,
+                    object.name,
+                    pick_result.distance
                 );
-                if let Some(object) = self.scene.objects.get(pick_result.object_index) {
-                    println!("Selected object: '{}'", object.name);
-                }
             }
 
             // Update selected object index
             self.selected_object_index = Some(pick_result.object_index);
+
+            self.scene.events.emit(PickEvent {
+                object_index: pick_result.object_index,
+                distance: pick_result.distance,
+                point: pick_result.intersection_point,
+            });
+
+            // Draggable objects follow the mouse until the button is released
+            let draggable = self
+                .scene
+                .objects
+                .get(pick_result.object_index)
+                .and_then(|object| object.entity)
+                .is_some_and(|entity| self.scene.world.has::<crate::ecs::Draggable>(entity));
+            if draggable {
+                self.dragging = Some((pick_result.object_index, pick_result.intersection_point));
+                self.handle_drag(DragPhase::Begin);
+            }
         } else {
-            #[cfg(debug_assertions)]
-            println!("No object picked");
+            tracing::debug!("No object picked");
             // Optionally deselect when clicking empty space
             // self.selected_object_index = None;
         }
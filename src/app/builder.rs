@@ -0,0 +1,211 @@
+//! Builder for configuring the window and GPU device before startup
+//!
+//! [`haggis::default()`](crate::default) creates a 1200x800 window on the
+//! default adapter with conservative limits. [`HaggisAppBuilder`] exposes the
+//! same setup with every knob available, for simulations that need a specific
+//! GPU, large storage buffers or a different presentation mode.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! let mut app = haggis::HaggisApp::builder()
+//!     .title("Lattice Boltzmann")
+//!     .size(1600, 900)
+//!     .vsync(true)
+//!     .prefer_discrete_gpu()
+//!     .adapter_limits() // LBM grids need large storage buffers
+//!     .build();
+//! app.run();
+//! ```
+
+use crate::error::Result;
+use crate::gfx::coordinates::CoordinateSystem;
+use crate::gfx::rendering::color::SurfaceGamma;
+use crate::gfx::rendering::depth::DepthMode;
+use crate::gfx::rendering::render_config::{DeviceLimits, RenderConfig};
+use crate::simulation::gpu::AdapterPreference;
+
+use super::HaggisApp;
+
+/// Window creation settings
+#[derive(Debug, Clone)]
+pub struct WindowConfig {
+    /// Window title
+    pub title: String,
+    /// Initial inner size in logical pixels
+    pub size: (u32, u32),
+    /// Start in borderless fullscreen on the current monitor
+    pub fullscreen: bool,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            title: "Haggis".to_string(),
+            size: (1200, 800),
+            fullscreen: false,
+        }
+    }
+}
+
+/// Configures and creates a [`HaggisApp`]
+#[derive(Debug, Clone, Default)]
+pub struct HaggisAppBuilder {
+    window: WindowConfig,
+    render: RenderConfig,
+    vsync: Option<bool>,
+    frame_limit: Option<Option<u32>>,
+    coordinate_system: Option<CoordinateSystem>,
+}
+
+impl HaggisAppBuilder {
+    /// Create a builder with default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the window title
+    pub fn title(mut self, title: &str) -> Self {
+        self.window.title = title.to_string();
+        self
+    }
+
+    /// Set the initial window size in logical pixels
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.window.size = (width.max(1), height.max(1));
+        self
+    }
+
+    /// Start in borderless fullscreen
+    pub fn fullscreen(mut self, fullscreen: bool) -> Self {
+        self.window.fullscreen = fullscreen;
+        self
+    }
+
+    /// Enable or disable vsync (see [`HaggisApp::set_vsync`])
+    pub fn vsync(mut self, enable: bool) -> Self {
+        self.vsync = Some(enable);
+        self
+    }
+
+    /// Use an explicit present mode, overriding the vsync setting.
+    ///
+    /// Unsupported modes fall back to the closest supported one.
+    pub fn present_mode(mut self, present_mode: wgpu::PresentMode) -> Self {
+        self.render.present_mode = Some(present_mode);
+        self
+    }
+
+    /// Cap the framerate (see [`HaggisApp::set_frame_limit`])
+    pub fn frame_limit(mut self, fps: Option<u32>) -> Self {
+        self.frame_limit = Some(fps);
+        self
+    }
+
+    /// Ask for an sRGB surface (`SurfaceGamma::Hardware`) or a plain one
+    /// whose sRGB curve the shaders apply (the default).
+    ///
+    /// Both look the same; see [`color`](crate::gfx::rendering::color).
+    pub fn surface_gamma(mut self, surface_gamma: SurfaceGamma) -> Self {
+        self.render.surface_gamma = surface_gamma;
+        self
+    }
+
+    /// Use a reverse-Z depth buffer (`DepthMode::Reverse`) against Z-fighting
+    /// far from the camera in large domains.
+    ///
+    /// See [`depth`](crate::gfx::rendering::depth).
+    pub fn depth_mode(mut self, depth_mode: DepthMode) -> Self {
+        self.render.depth_mode = depth_mode;
+        self
+    }
+
+    /// Set the adapter power preference
+    pub fn power_preference(mut self, power_preference: wgpu::PowerPreference) -> Self {
+        self.render.power_preference = power_preference;
+        self
+    }
+
+    /// Prefer a discrete GPU when several are available
+    pub fn prefer_discrete_gpu(self) -> Self {
+        self.power_preference(wgpu::PowerPreference::HighPerformance)
+    }
+
+    /// Prefer an integrated GPU when several are available
+    pub fn prefer_integrated_gpu(self) -> Self {
+        self.power_preference(wgpu::PowerPreference::LowPower)
+    }
+
+    /// Run simulation compute on a second adapter, e.g. a discrete GPU while
+    /// an integrated one drives the display.
+    ///
+    /// Falls back to the display GPU if no other adapter matches. See
+    /// [`ComputeDevice`](crate::simulation::gpu::ComputeDevice).
+    pub fn compute_adapter(mut self, preference: AdapterPreference) -> Self {
+        self.render.compute_adapter = Some(preference);
+        self
+    }
+
+    /// Require optional wgpu features; startup fails if the adapter lacks them
+    pub fn required_features(mut self, features: wgpu::Features) -> Self {
+        self.render.required_features |= features;
+        self
+    }
+
+    /// Request exactly these device limits
+    pub fn required_limits(mut self, limits: wgpu::Limits) -> Self {
+        self.render.limits = DeviceLimits::Custom(limits);
+        self
+    }
+
+    /// Request everything the adapter supports instead of conservative defaults
+    pub fn adapter_limits(mut self) -> Self {
+        self.render.limits = DeviceLimits::Adapter;
+        self
+    }
+
+    /// Set the world up axis, asset up axis and units
+    /// (see [`HaggisApp::set_coordinate_system`])
+    pub fn coordinate_system(mut self, coordinate_system: CoordinateSystem) -> Self {
+        self.coordinate_system = Some(coordinate_system);
+        self
+    }
+
+    /// Create the app, returning an error if the event loop can't be created
+    pub async fn try_build_async(self) -> Result<HaggisApp> {
+        let mut app = HaggisApp::try_new().await?;
+        app.app_state.window_config = self.window;
+        app.app_state.render_config = self.render;
+        if let Some(vsync) = self.vsync {
+            app.set_vsync(vsync);
+        }
+        if let Some(fps) = self.frame_limit {
+            app.set_frame_limit(fps);
+        }
+        if let Some(coordinate_system) = self.coordinate_system {
+            app.set_coordinate_system(coordinate_system);
+        }
+        Ok(app)
+    }
+
+    /// Create the app, blocking on async setup
+    pub fn try_build(self) -> Result<HaggisApp> {
+        pollster::block_on(self.try_build_async())
+    }
+
+    /// Create the app
+    ///
+    /// # Panics
+    ///
+    /// Panics if the event loop can't be created; see [`try_build`](Self::try_build)
+    pub async fn build_async(self) -> HaggisApp {
+        self.try_build_async()
+            .await
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Create the app, blocking on async setup (like [`haggis::default()`](crate::default))
+    pub fn build(self) -> HaggisApp {
+        pollster::block_on(self.build_async())
+    }
+}
@@ -0,0 +1,513 @@
+//! Job system for parallel engine work
+//!
+//! A small thread pool that runs closures in the background, optionally after
+//! other jobs have finished. The engine uses it to parse model files, compute
+//! bounding boxes and build voxel grids without stalling the window, and
+//! simulations can use it for their own CPU stepping.
+//!
+//! [`JobSystem::global()`] is shared by the whole process and sized to the
+//! machine. [`JobSystem::spawn`] runs `'static` work on the pool and returns a
+//! [`JobHandle`] to wait on; [`JobSystem::parallel_map`] and
+//! [`JobSystem::parallel_for`] split an index range across the same workers
+//! and may borrow from the caller. While it waits for its chunks, the calling
+//! thread runs queued tasks itself, so parallel loops nested inside jobs
+//! cannot starve the pool.
+//!
+//! On the web there are no threads: jobs run immediately on the calling
+//! thread and the parallel helpers run sequentially.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::app::jobs::JobSystem;
+//!
+//! let jobs = JobSystem::global();
+//!
+//! // Background work with a dependency
+//! let grid = jobs.spawn(|| vec![0.0f32; 256 * 256]);
+//! let total = jobs.spawn_after(&[grid.dependency()], || 42);
+//! let (grid, total) = (grid.wait(), total.wait());
+//!
+//! // Data-parallel loop that borrows local data
+//! let squares = jobs.parallel_map(grid.len(), |i| grid[i] * grid[i] + total as f32);
+//! ```
+
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::thread;
+
+use wgpu::{WasmNotSend, WasmNotSync};
+
+#[cfg(not(target_arch = "wasm32"))]
+type Task = Box<dyn FnOnce() + Send>;
+/// A task borrowing from the stack of a [`JobSystem::parallel_map`] call
+#[cfg(not(target_arch = "wasm32"))]
+type ScopedTask<'a> = Box<dyn FnOnce() + Send + 'a>;
+#[cfg(target_arch = "wasm32")]
+type Task = Box<dyn FnOnce()>;
+
+/// Queue shared between the pool and its workers
+struct Queue {
+    tasks: Mutex<QueueState>,
+    available: Condvar,
+    workers: usize,
+}
+
+struct QueueState {
+    tasks: VecDeque<Task>,
+    shutdown: bool,
+}
+
+impl Queue {
+    /// Run `task` on a worker, or right away when the pool has no workers
+    fn submit(&self, task: Task) {
+        if self.workers == 0 {
+            task();
+            return;
+        }
+        self.tasks.lock().unwrap().tasks.push_back(task);
+        self.available.notify_one();
+    }
+
+    /// Take the next queued task, if any, without waiting
+    #[cfg(not(target_arch = "wasm32"))]
+    fn try_pop(&self) -> Option<Task> {
+        self.tasks.lock().unwrap().tasks.pop_front()
+    }
+
+    fn worker_loop(&self) {
+        loop {
+            let task = {
+                let mut state = self.tasks.lock().unwrap();
+                loop {
+                    if let Some(task) = state.tasks.pop_front() {
+                        break task;
+                    }
+                    if state.shutdown {
+                        return;
+                    }
+                    state = self.available.wait(state).unwrap();
+                }
+            };
+            task();
+        }
+    }
+}
+
+/// Count of outstanding chunks of one [`JobSystem::parallel_map`] call
+#[cfg(not(target_arch = "wasm32"))]
+struct Latch {
+    remaining: Mutex<usize>,
+    finished: Condvar,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Latch {
+    fn count_down(&self) {
+        *self.remaining.lock().unwrap() -= 1;
+        self.finished.notify_all();
+    }
+
+    /// Wait for every chunk, running queued tasks meanwhile
+    ///
+    /// Chunks nobody has picked up yet are still in the queue, so the caller
+    /// ends up running them when all workers are busy.
+    fn wait(&self, queue: &Queue) {
+        loop {
+            if *self.remaining.lock().unwrap() == 0 {
+                return;
+            }
+            match queue.try_pop() {
+                Some(task) => task(),
+                None => {
+                    // Everything left is running on a worker
+                    let mut remaining = self.remaining.lock().unwrap();
+                    while *remaining > 0 {
+                        remaining = self.finished.wait(remaining).unwrap();
+                    }
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Completion state of a job, shared with the jobs that depend on it
+struct Completion {
+    state: Mutex<CompletionState>,
+    finished: Condvar,
+}
+
+#[derive(Default)]
+struct CompletionState {
+    done: bool,
+    dependents: Vec<Arc<PendingJob>>,
+}
+
+impl Completion {
+    fn new() -> Arc<Self> {
+        Arc::new(Self {
+            state: Mutex::new(CompletionState::default()),
+            finished: Condvar::new(),
+        })
+    }
+
+    fn is_done(&self) -> bool {
+        self.state.lock().unwrap().done
+    }
+
+    fn wait(&self) {
+        let mut state = self.state.lock().unwrap();
+        while !state.done {
+            state = self.finished.wait(state).unwrap();
+        }
+    }
+
+    /// Mark the job finished and release dependents whose last dependency it was
+    fn finish(&self) {
+        let dependents = {
+            let mut state = self.state.lock().unwrap();
+            state.done = true;
+            std::mem::take(&mut state.dependents)
+        };
+        self.finished.notify_all();
+        for pending in dependents {
+            pending.dependency_finished();
+        }
+    }
+}
+
+/// A job waiting for its dependencies
+struct PendingJob {
+    remaining: AtomicUsize,
+    task: Mutex<Option<Task>>,
+    queue: Arc<Queue>,
+}
+
+impl PendingJob {
+    fn dependency_finished(&self) {
+        if self.remaining.fetch_sub(1, Ordering::AcqRel) == 1 {
+            if let Some(task) = self.task.lock().unwrap().take() {
+                self.queue.submit(task);
+            }
+        }
+    }
+}
+
+/// A finished-or-not marker for a job, used to order other jobs after it
+#[derive(Clone)]
+pub struct JobDependency {
+    completion: Arc<Completion>,
+}
+
+/// Handle to a job's result
+pub struct JobHandle<T> {
+    completion: Arc<Completion>,
+    result: Arc<Mutex<Option<thread::Result<T>>>>,
+}
+
+impl<T> JobHandle<T> {
+    /// Whether the job has finished (successfully or by panicking)
+    pub fn is_finished(&self) -> bool {
+        self.completion.is_done()
+    }
+
+    /// Dependency token to pass to [`JobSystem::spawn_after`]
+    pub fn dependency(&self) -> JobDependency {
+        JobDependency {
+            completion: self.completion.clone(),
+        }
+    }
+
+    /// Block until the job finishes and return its result.
+    ///
+    /// # Panics
+    ///
+    /// Re-raises the panic if the job panicked
+    pub fn wait(self) -> T {
+        self.completion.wait();
+        let result = self
+            .result
+            .lock()
+            .unwrap()
+            .take()
+            .expect("job finished without a result");
+        result.unwrap_or_else(|payload| panic::resume_unwind(payload))
+    }
+
+    /// Return the result if the job has finished, or the handle back otherwise.
+    ///
+    /// Useful for polling once per frame instead of blocking.
+    pub fn try_wait(self) -> std::result::Result<T, Self> {
+        if self.is_finished() {
+            Ok(self.wait())
+        } else {
+            Err(self)
+        }
+    }
+}
+
+/// Thread pool with job dependencies
+pub struct JobSystem {
+    queue: Arc<Queue>,
+    threads: Vec<thread::JoinHandle<()>>,
+}
+
+impl JobSystem {
+    /// Create a pool with `workers` background threads.
+    ///
+    /// With zero workers (always the case on the web) jobs run on the thread
+    /// that spawns them.
+    pub fn new(workers: usize) -> Self {
+        let workers = if cfg!(target_arch = "wasm32") { 0 } else { workers };
+        let queue = Arc::new(Queue {
+            tasks: Mutex::new(QueueState {
+                tasks: VecDeque::new(),
+                shutdown: false,
+            }),
+            available: Condvar::new(),
+            workers,
+        });
+
+        let threads = (0..workers)
+            .map(|i| {
+                let queue = queue.clone();
+                thread::Builder::new()
+                    .name(format!("haggis-job-{}", i))
+                    .spawn(move || queue.worker_loop())
+                    .expect("failed to spawn job thread")
+            })
+            .collect();
+
+        Self { queue, threads }
+    }
+
+    /// Process-wide pool with one worker per core, minus the main thread
+    pub fn global() -> &'static JobSystem {
+        static GLOBAL: OnceLock<JobSystem> = OnceLock::new();
+        GLOBAL.get_or_init(|| {
+            let cores = thread::available_parallelism().map_or(1, |n| n.get());
+            let jobs = JobSystem::new(cores.saturating_sub(1).max(1));
+            tracing::debug!("Job system started with {} workers", jobs.worker_count());
+            jobs
+        })
+    }
+
+    /// Number of background threads
+    pub fn worker_count(&self) -> usize {
+        self.queue.workers
+    }
+
+    /// Run `job` on the pool
+    pub fn spawn<T, F>(&self, job: F) -> JobHandle<T>
+    where
+        F: FnOnce() -> T + WasmNotSend + 'static,
+        T: WasmNotSend + 'static,
+    {
+        self.spawn_after(&[], job)
+    }
+
+    /// Run `job` on the pool once every job in `dependencies` has finished.
+    ///
+    /// A dependency that panicked still counts as finished; the panic is only
+    /// raised when its own handle is waited on.
+    pub fn spawn_after<T, F>(&self, dependencies: &[JobDependency], job: F) -> JobHandle<T>
+    where
+        F: FnOnce() -> T + WasmNotSend + 'static,
+        T: WasmNotSend + 'static,
+    {
+        let completion = Completion::new();
+        let result = Arc::new(Mutex::new(None));
+
+        let task: Task = {
+            let completion = completion.clone();
+            let result = result.clone();
+            Box::new(move || {
+                let outcome = panic::catch_unwind(AssertUnwindSafe(job));
+                *result.lock().unwrap() = Some(outcome);
+                completion.finish();
+            })
+        };
+
+        // One extra count held while registering, so the job can't start
+        // before every dependency has been looked at
+        let pending = Arc::new(PendingJob {
+            remaining: AtomicUsize::new(dependencies.len() + 1),
+            task: Mutex::new(Some(task)),
+            queue: self.queue.clone(),
+        });
+        for dependency in dependencies {
+            let mut state = dependency.completion.state.lock().unwrap();
+            if state.done {
+                drop(state);
+                pending.dependency_finished();
+            } else {
+                state.dependents.push(pending.clone());
+            }
+        }
+        pending.dependency_finished();
+
+        JobHandle { completion, result }
+    }
+
+    /// Compute `f(i)` for every `i` in `0..count`, split across the workers.
+    ///
+    /// Results are returned in index order. The calling thread takes part in
+    /// the work and blocks until all of it is done, so `f` may borrow local
+    /// data. A panic in `f` is raised here once every chunk has stopped.
+    pub fn parallel_map<T, F>(&self, count: usize, f: F) -> Vec<T>
+    where
+        F: Fn(usize) -> T + WasmNotSync,
+        T: WasmNotSend,
+    {
+        let chunks = (self.worker_count() + 1).min(count);
+        if chunks <= 1 {
+            return (0..count).map(f).collect();
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            (0..count).map(f).collect()
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let chunk_size = count.div_ceil(chunks);
+            let f = &f;
+            let starts: Vec<usize> = (chunk_size..count).step_by(chunk_size).collect();
+            let slots: Vec<Mutex<Option<thread::Result<Vec<T>>>>> =
+                starts.iter().map(|_| Mutex::new(None)).collect();
+            let latch = Latch {
+                remaining: Mutex::new(starts.len()),
+                finished: Condvar::new(),
+            };
+
+            for (&start, slot) in starts.iter().zip(&slots) {
+                let end = (start + chunk_size).min(count);
+                let latch = &latch;
+                let task: ScopedTask<'_> = Box::new(move || {
+                    let chunk =
+                        panic::catch_unwind(AssertUnwindSafe(|| (start..end).map(f).collect()));
+                    *slot.lock().unwrap() = Some(chunk);
+                    latch.count_down();
+                });
+                // SAFETY: the task borrows `f`, `slots` and `latch` from this
+                // frame. Every task catches its own panics and counts down
+                // the latch, and the latch is waited on below before the
+                // frame is left, also when the caller's own chunk panics.
+                let task: Task = unsafe { std::mem::transmute::<ScopedTask<'_>, Task>(task) };
+                self.queue.submit(task);
+            }
+
+            let first = panic::catch_unwind(AssertUnwindSafe(|| {
+                (0..chunk_size).map(f).collect::<Vec<T>>()
+            }));
+            latch.wait(&self.queue);
+
+            let mut results = first.unwrap_or_else(|payload| panic::resume_unwind(payload));
+            results.reserve(count - chunk_size);
+            for slot in slots {
+                let chunk = slot.into_inner().unwrap();
+                match chunk.expect("chunk finished without a result") {
+                    Ok(chunk) => results.extend(chunk),
+                    Err(payload) => panic::resume_unwind(payload),
+                }
+            }
+            results
+        }
+    }
+
+    /// Call `f(i)` for every `i` in `0..count`, split across the workers
+    pub fn parallel_for<F>(&self, count: usize, f: F)
+    where
+        F: Fn(usize) + WasmNotSync,
+    {
+        self.parallel_map(count, f);
+    }
+}
+
+impl Drop for JobSystem {
+    fn drop(&mut self) {
+        self.queue.tasks.lock().unwrap().shutdown = true;
+        self.queue.available.notify_all();
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dependencies_run_in_order() {
+        let jobs = JobSystem::new(3);
+        let log = Arc::new(Mutex::new(Vec::new()));
+
+        let first = {
+            let log = log.clone();
+            jobs.spawn(move || {
+                thread::sleep(std::time::Duration::from_millis(20));
+                log.lock().unwrap().push("first");
+            })
+        };
+        let second = {
+            let log = log.clone();
+            jobs.spawn_after(&[first.dependency()], move || {
+                log.lock().unwrap().push("second");
+                7
+            })
+        };
+
+        assert_eq!(second.wait(), 7);
+        first.wait();
+        assert_eq!(*log.lock().unwrap(), vec!["first", "second"]);
+    }
+
+    #[test]
+    fn test_parallel_map_keeps_order() {
+        let jobs = JobSystem::new(4);
+        let offset = 10;
+        let values = jobs.parallel_map(1001, |i| i + offset);
+        assert_eq!(values.len(), 1001);
+        assert!(values.iter().enumerate().all(|(i, &v)| v == i + offset));
+
+        let inline = JobSystem::new(0);
+        assert_eq!(inline.spawn(|| 5).wait(), 5);
+    }
+
+    #[test]
+    fn test_parallel_map_runs_on_the_pool() {
+        let jobs = Arc::new(JobSystem::new(2));
+        let names = jobs.parallel_map(64, |_| {
+            thread::sleep(std::time::Duration::from_millis(1));
+            thread::current().name().map(str::to_string)
+        });
+        // No threads are spawned per call: chunks run on the caller or the workers
+        assert!(names
+            .iter()
+            .flatten()
+            .all(|name| name.starts_with("haggis-job-") || thread::current().name() == Some(name)));
+
+        // Nested loops inside jobs finish even when every worker is waiting
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let jobs = jobs.clone();
+                jobs.clone()
+                    .spawn(move || jobs.parallel_map(100, |i| i).into_iter().sum::<usize>())
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.wait(), 4950);
+        }
+
+        let panicked = panic::catch_unwind(AssertUnwindSafe(|| {
+            jobs.parallel_map(100, |i| if i == 90 { panic!("chunk failed") } else { i })
+        }));
+        assert!(panicked.is_err());
+        assert_eq!(jobs.parallel_map(10, |i| i).len(), 10);
+    }
+}
@@ -0,0 +1,90 @@
+//! Frame pacing for the framerate limit
+//!
+//! Without a limit, an app with a light scene and `Immediate` or `Mailbox`
+//! presentation redraws thousands of times per second, keeping the GPU busy
+//! with frames nobody sees instead of simulation work. [`FramePacer`] spaces
+//! redraws evenly at the limit and tells the event loop how long it may sleep,
+//! so the thread idles between frames instead of spinning.
+
+use std::time::Duration;
+
+use crate::platform::Instant;
+
+/// Schedules redraws at most `limit` times per second
+#[derive(Debug, Clone, Default)]
+pub struct FramePacer {
+    /// When the next frame is due
+    next_frame: Option<Instant>,
+}
+
+impl FramePacer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether to draw a frame at `now`
+    ///
+    /// # Returns
+    ///
+    /// `None` if a frame is due, which schedules the next one, otherwise the
+    /// time to wait until. `limit` is in frames per second; `None` or a
+    /// non-positive limit draws every time.
+    pub fn poll(&mut self, now: Instant, limit: Option<f32>) -> Option<Instant> {
+        let Some(interval) = limit
+            .filter(|fps| *fps > 0.0 && fps.is_finite())
+            .map(|fps| Duration::from_secs_f32(1.0 / fps))
+        else {
+            self.next_frame = None;
+            return None;
+        };
+        match self.next_frame {
+            Some(next_frame) if now < next_frame => Some(next_frame),
+            Some(next_frame) => {
+                // Keep an even cadence, but don't burst to catch up after a stall
+                let scheduled = next_frame + interval;
+                self.next_frame = Some(if scheduled > now {
+                    scheduled
+                } else {
+                    now + interval
+                });
+                None
+            }
+            None => {
+                self.next_frame = Some(now + interval);
+                None
+            }
+        }
+    }
+
+    /// Forget the schedule, e.g. after the limit changed
+    pub fn reset(&mut self) {
+        self.next_frame = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frames_follow_limit() {
+        let mut pacer = FramePacer::new();
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+
+        assert_eq!(pacer.poll(start, Some(50.0)), None);
+        assert_eq!(pacer.poll(start + ms(5), Some(50.0)), Some(start + ms(20)));
+        // A late frame keeps the cadence
+        assert_eq!(pacer.poll(start + ms(25), Some(50.0)), None);
+        assert_eq!(pacer.poll(start + ms(30), Some(50.0)), Some(start + ms(40)));
+        // After a stall the schedule restarts from now
+        assert_eq!(pacer.poll(start + ms(200), Some(50.0)), None);
+        assert_eq!(
+            pacer.poll(start + ms(201), Some(50.0)),
+            Some(start + ms(220))
+        );
+
+        assert_eq!(pacer.poll(start + ms(202), None), None);
+        assert_eq!(pacer.poll(start + ms(202), Some(0.0)), None);
+    }
+}
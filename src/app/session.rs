@@ -0,0 +1,324 @@
+//! Session persistence
+//!
+//! A [`Session`] is the state a user would otherwise set up again on every
+//! run of a project: window size and position, camera pose, which built-in
+//! panels are open, the ImGui window and docking layout, and the parameters
+//! of each simulation that was run. It is kept in a per-project TOML file:
+//!
+//! ```toml
+//! [window]
+//! size = [1600, 900]
+//! position = [120, 80]
+//! maximized = false
+//!
+//! [camera]
+//! target = [0.0, 0.0, 0.5]
+//! distance = 6.0
+//! pitch = 0.4
+//! yaw = 0.2
+//!
+//! [panels]
+//! performance = true
+//! transform = false
+//!
+//! [ui]
+//! layout = "[Window][Debug##Default]\nPos=60,60\n..."
+//!
+//! [parameters."Lattice Boltzmann"]
+//! viscosity = 0.02
+//! iterations = 8
+//! ```
+//!
+//! See [`HaggisApp::enable_session_persistence`](crate::HaggisApp::enable_session_persistence)
+//! for when it is restored and saved.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::error::{HaggisError, Result};
+use crate::gfx::camera::orbit_camera::OrbitCamera;
+use crate::util::toml_lite::{parse_array, parse_string, quote};
+
+/// Orbit camera placement
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraPose {
+    pub target: [f32; 3],
+    pub distance: f32,
+    /// Radians
+    pub pitch: f32,
+    /// Radians
+    pub yaw: f32,
+}
+
+impl CameraPose {
+    pub fn of(camera: &OrbitCamera) -> Self {
+        Self {
+            target: camera.target.into(),
+            distance: camera.distance,
+            pitch: camera.pitch,
+            yaw: camera.yaw,
+        }
+    }
+
+    /// Move `camera` to this pose, within its bounds
+    pub fn apply(&self, camera: &mut OrbitCamera) {
+        camera.set_target(self.target.into());
+        camera.set_distance(self.distance);
+        camera.set_pitch(self.pitch);
+        camera.set_yaw(self.yaw);
+    }
+}
+
+/// App state restored between runs
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Session {
+    /// Inner window size in logical pixels
+    pub window_size: Option<(u32, u32)>,
+    /// Outer window position in physical pixels
+    pub window_position: Option<(i32, i32)>,
+    pub maximized: bool,
+    pub camera: Option<CameraPose>,
+    /// Visibility of the built-in panels by name
+    pub panels: BTreeMap<String, bool>,
+    /// ImGui ini settings with window placement and docking
+    pub ui_layout: Option<String>,
+    /// `name = value` parameter lines by simulation name, see
+    /// [`SimParams::to_text`](crate::simulation::params::SimParams::to_text)
+    pub parameters: BTreeMap<String, String>,
+}
+
+impl Session {
+    /// Serialize the session as TOML
+    pub fn to_toml(&self) -> String {
+        let mut out = String::from("# Haggis session\n");
+        if self.window_size.is_some() || self.window_position.is_some() {
+            out.push_str("\n[window]\n");
+            if let Some((width, height)) = self.window_size {
+                out.push_str(&format!("size = [{width}, {height}]\n"));
+            }
+            if let Some((x, y)) = self.window_position {
+                out.push_str(&format!("position = [{x}, {y}]\n"));
+            }
+            out.push_str(&format!("maximized = {}\n", self.maximized));
+        }
+        if let Some(camera) = &self.camera {
+            out.push_str("\n[camera]\n");
+            let [x, y, z] = camera.target;
+            out.push_str(&format!("target = [{x:?}, {y:?}, {z:?}]\n"));
+            out.push_str(&format!("distance = {:?}\n", camera.distance));
+            out.push_str(&format!("pitch = {:?}\n", camera.pitch));
+            out.push_str(&format!("yaw = {:?}\n", camera.yaw));
+        }
+        if !self.panels.is_empty() {
+            out.push_str("\n[panels]\n");
+            for (panel, visible) in &self.panels {
+                out.push_str(&format!("{panel} = {visible}\n"));
+            }
+        }
+        if let Some(layout) = &self.ui_layout {
+            out.push_str(&format!("\n[ui]\nlayout = {}\n", quote(layout)));
+        }
+        for (simulation, parameters) in &self.parameters {
+            out.push_str(&format!("\n[parameters.{}]\n", quote(simulation)));
+            out.push_str(parameters);
+            if !parameters.ends_with('\n') {
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    /// Parse a session from TOML text
+    ///
+    /// Every section is optional, but a `[camera]` table needs all four
+    /// keys. Unknown sections and keys are errors, reported with their line
+    /// number; parameter values are checked when they are applied.
+    pub fn from_toml(text: &str) -> std::result::Result<Self, String> {
+        let mut session = Self::default();
+        let mut section = String::new();
+        let mut camera = [None; 6];
+
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = header.trim().to_string();
+                if let Some(simulation) = section.strip_prefix("parameters.") {
+                    let simulation = parse_string(simulation.trim()).ok_or_else(|| {
+                        format!("line {line_number}: expected [parameters.\"<simulation>\"]")
+                    })?;
+                    session.parameters.insert(simulation.clone(), String::new());
+                    section = format!("parameters.{simulation}");
+                } else if !["window", "camera", "panels", "ui"].contains(&section.as_str()) {
+                    return Err(format!("line {line_number}: unknown section [{section}]"));
+                }
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(format!("line {line_number}: expected `key = value`"));
+            };
+            let (key, value) = (key.trim(), value.trim());
+            let invalid = || format!("line {line_number}: invalid value for {key}");
+            match (section.as_str(), key) {
+                ("window", "size") => {
+                    let [width, height] = parse_array(value).ok_or_else(invalid)?;
+                    session.window_size = Some((width, height));
+                }
+                ("window", "position") => {
+                    let [x, y] = parse_array(value).ok_or_else(invalid)?;
+                    session.window_position = Some((x, y));
+                }
+                ("window", "maximized") => {
+                    session.maximized = value.parse().map_err(|_| invalid())?;
+                }
+                ("camera", "target") => {
+                    let target: [f32; 3] = parse_array(value).ok_or_else(invalid)?;
+                    camera[..3].copy_from_slice(&target.map(Some));
+                }
+                ("camera", "distance" | "pitch" | "yaw") => {
+                    let slot = match key {
+                        "distance" => 3,
+                        "pitch" => 4,
+                        _ => 5,
+                    };
+                    camera[slot] = Some(value.parse().map_err(|_| invalid())?);
+                }
+                ("panels", panel) => {
+                    let visible = value.parse().map_err(|_| invalid())?;
+                    session.panels.insert(panel.to_string(), visible);
+                }
+                ("ui", "layout") => {
+                    session.ui_layout = Some(parse_string(value).ok_or_else(invalid)?);
+                }
+                (section, _) if section.starts_with("parameters.") => {
+                    let simulation = &section["parameters.".len()..];
+                    let lines = session
+                        .parameters
+                        .entry(simulation.to_string())
+                        .or_default();
+                    lines.push_str(&format!("{key} = {value}\n"));
+                }
+                ("", _) => return Err(format!("line {line_number}: expected a [section]")),
+                (section, key) => {
+                    return Err(format!(
+                        "line {line_number}: unknown key {key} in [{section}]"
+                    ));
+                }
+            }
+        }
+
+        match camera {
+            [Some(x), Some(y), Some(z), Some(distance), Some(pitch), Some(yaw)] => {
+                session.camera = Some(CameraPose {
+                    target: [x, y, z],
+                    distance,
+                    pitch,
+                    yaw,
+                });
+            }
+            [None, None, None, None, None, None] => {}
+            _ => return Err("[camera] needs target, distance, pitch and yaw".to_string()),
+        }
+        Ok(session)
+    }
+
+    /// Read a session file
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::Io`] if the file cannot be read,
+    /// [`HaggisError::Session`] if it is malformed, and
+    /// [`HaggisError::NoFileSystem`] on platforms without file access.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if !crate::platform::HAS_FILESYSTEM {
+            return Err(HaggisError::NoFileSystem { path: path.into() });
+        }
+        let text = std::fs::read_to_string(path).map_err(|source| HaggisError::Io {
+            path: path.into(),
+            source,
+        })?;
+        Self::from_toml(&text).map_err(|message| HaggisError::Session {
+            path: path.into(),
+            message,
+        })
+    }
+
+    /// Save the session as a TOML file
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::Io`] if the file cannot be written and
+    /// [`HaggisError::NoFileSystem`] on platforms without file access.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if !crate::platform::HAS_FILESYSTEM {
+            return Err(HaggisError::NoFileSystem { path: path.into() });
+        }
+        std::fs::write(path, self.to_toml()).map_err(|source| HaggisError::Io {
+            path: path.into(),
+            source,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_toml_round_trip() {
+        let session = Session {
+            window_size: Some((1600, 900)),
+            window_position: Some((-8, 40)),
+            maximized: true,
+            camera: Some(CameraPose {
+                target: [0.0, 1.5, -2.0],
+                distance: 6.25,
+                pitch: 0.4,
+                yaw: -1.2,
+            }),
+            panels: [
+                ("performance".to_string(), true),
+                ("transform".to_string(), false),
+            ]
+            .into_iter()
+            .collect(),
+            ui_layout: Some("[Window][Debug##Default]\nPos=60,60\nSize=400,400\n".to_string()),
+            parameters: [
+                (
+                    "Lattice \"LBM\"".to_string(),
+                    "viscosity = 0.02\n".to_string(),
+                ),
+                ("Life".to_string(), "wrap = true\nrule = 3\n".to_string()),
+            ]
+            .into_iter()
+            .collect(),
+        };
+        assert_eq!(Session::from_toml(&session.to_toml()), Ok(session));
+        assert_eq!(
+            Session::from_toml("# nothing yet\n"),
+            Ok(Session::default())
+        );
+    }
+
+    #[test]
+    fn test_malformed_sessions_are_rejected() {
+        for text in [
+            "size = [1, 2]",
+            "[windows]\nsize = [1, 2]",
+            "[window]\nsize = [1, 2, 3]",
+            "[window]\nmaximized = yes",
+            "[camera]\ndistance = 4.0",
+            "[parameters.Life]\nwrap = true",
+            "[ui]\nlayout = unquoted",
+        ] {
+            assert!(Session::from_toml(text).is_err(), "accepted {text:?}");
+        }
+    }
+}
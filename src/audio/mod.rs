@@ -0,0 +1,592 @@
+//! # Audio
+//!
+//! This module (behind the `audio` feature) plays sounds for the app: one-off
+//! sounds from [`HaggisApp::play_sound`](crate::app::HaggisApp::play_sound),
+//! positional sounds from [`SoundEmitter`] components attached to objects,
+//! and simulation hooks that turn physics [`CollisionEvent`]s into impact
+//! sounds and simulation divergence into an alert chime. [`Sonification`]s
+//! turn metric streams into tones whose pitch and volume follow the data.
+//!
+//! Decoding and output go through an [`AudioBackend`], which adapts an audio
+//! library such as rodio (or cpal with a decoder) to a handful of calls:
+//! start a file or a sine tone on a voice, change a voice's gain, pan and
+//! tone frequency, stop it and ask whether it is still playing.
+//! Spatialization is done here, so every backend gets the same listener
+//! model: the listener sits at the orbit camera's eye, sounds fade out
+//! towards their range, and pan follows where the source is on screen.
+//!
+//! ## Scope
+//!
+//! Haggis does not bundle an audio output. The `audio` feature compiles the
+//! mixing logic above only; hearing anything needs an [`AudioBackend`]
+//! written against a crate such as `rodio` or `cpal`, in the application or
+//! a companion crate. `MyRodioBackend` below stands for such an
+//! implementation.
+//!
+//! ## Usage
+//!
+//! ```ignore
+//! use haggis::audio::{CollisionSound, Sonification, SoundEmitter};
+//!
+//! let mut app = haggis::default();
+//! app.enable_audio(MyRodioBackend::new()?);
+//! if let Some(audio) = app.app_state.audio.as_mut() {
+//!     audio.collision_sound = Some(CollisionSound::new("assets/knock.wav"));
+//!     audio.alert_sound = Some("assets/chime.wav".into());
+//!     // Higher pitch as the probe sees more vorticity
+//!     audio.sonify(Sonification::new("probe_vorticity", 0.0..=5.0));
+//! }
+//!
+//! // A humming pump you can walk up to
+//! app.add_cube()
+//!     .with_name("Pump")
+//!     .with_component(SoundEmitter::looping("assets/hum.ogg").with_range(8.0));
+//!
+//! app.play_sound("assets/start.wav")?;
+//! app.run();
+//! ```
+
+pub mod sonification;
+
+pub use sonification::Sonification;
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use cgmath::{InnerSpace, Vector3};
+
+use crate::ecs::Entity;
+use crate::error::Result;
+use crate::events::{CollisionEvent, EventBus, EventReceiver};
+use crate::gfx::scene::Scene;
+
+/// Handle to a sound started on an [`AudioBackend`]
+pub type VoiceId = u64;
+
+/// How a voice is played
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VoiceSettings {
+    /// Volume multiplier, `1.0` plays the file as is
+    pub gain: f32,
+    /// Stereo position from `-1.0` (left) to `1.0` (right)
+    pub pan: f32,
+    /// Start over at the end instead of stopping
+    pub looping: bool,
+}
+
+impl Default for VoiceSettings {
+    fn default() -> Self {
+        Self {
+            gain: 1.0,
+            pan: 0.0,
+            looping: false,
+        }
+    }
+}
+
+/// Sound output implemented on top of an audio library
+///
+/// Implementations should return from every call without blocking on
+/// playback; they are called from the render loop.
+pub trait AudioBackend: Send {
+    /// Library name, for logs
+    fn name(&self) -> &str;
+
+    /// Decode the file at `path` and start playing it
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::Audio`](crate::HaggisError::Audio) if the file
+    /// can't be read or decoded, or no output device is available.
+    fn play(&mut self, path: &Path, settings: VoiceSettings) -> Result<VoiceId>;
+
+    /// Start a sine tone at `frequency` Hz, playing until stopped
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no output device is available.
+    fn play_tone(&mut self, frequency: f32, settings: VoiceSettings) -> Result<VoiceId>;
+
+    /// Change the gain and pan of a playing voice
+    fn set_voice(&mut self, voice: VoiceId, gain: f32, pan: f32);
+
+    /// Change the frequency of a tone started with
+    /// [`play_tone`](Self::play_tone)
+    fn set_frequency(&mut self, voice: VoiceId, frequency: f32);
+
+    /// Stop a voice; stopping a finished voice does nothing
+    fn stop(&mut self, voice: VoiceId);
+
+    /// Whether the voice hasn't finished or been stopped
+    fn is_playing(&self, voice: VoiceId) -> bool;
+}
+
+/// Where sounds are heard from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Listener {
+    pub position: Vector3<f32>,
+    /// View direction
+    pub forward: Vector3<f32>,
+    pub up: Vector3<f32>,
+}
+
+impl Listener {
+    /// Listener at the camera `eye`, looking at `target`
+    pub fn looking_at(eye: Vector3<f32>, target: Vector3<f32>, up: Vector3<f32>) -> Self {
+        Self {
+            position: eye,
+            forward: target - eye,
+            up,
+        }
+    }
+
+    /// Gain and pan of a source at `position` heard from up to `range` away
+    ///
+    /// Gain falls off smoothly from `1.0` at the listener to `0.0` at
+    /// `range`; pan is the source's direction along the listener's right.
+    pub fn spatialize(&self, position: Vector3<f32>, range: f32) -> (f32, f32) {
+        let offset = position - self.position;
+        let distance = offset.magnitude();
+        let falloff = (1.0 - distance / range.max(f32::EPSILON)).clamp(0.0, 1.0);
+        let right = self.forward.cross(self.up);
+        let pan = if distance > 1e-6 && right.magnitude2() > 1e-12 {
+            (offset / distance).dot(right.normalize()).clamp(-1.0, 1.0)
+        } else {
+            0.0
+        };
+        (falloff * falloff, pan)
+    }
+}
+
+/// Sound attached to an entity, played from its position
+///
+/// Attach it to an object with
+/// [`ObjectBuilder::with_component`](crate::gfx::scene::object::ObjectBuilder::with_component);
+/// the sound starts on the next frame and follows the object. One-shot
+/// sounds play once; removing the component or the object stops the sound.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoundEmitter {
+    pub sound: PathBuf,
+    /// Volume at the emitter
+    pub volume: f32,
+    /// Distance at which the sound fades out completely, in world units
+    pub range: f32,
+    pub looping: bool,
+}
+
+impl SoundEmitter {
+    /// Sound played once, audible up to 10 units away
+    pub fn new(sound: impl Into<PathBuf>) -> Self {
+        Self {
+            sound: sound.into(),
+            volume: 1.0,
+            range: 10.0,
+            looping: false,
+        }
+    }
+
+    /// Sound repeated for as long as the emitter exists
+    pub fn looping(sound: impl Into<PathBuf>) -> Self {
+        Self {
+            looping: true,
+            ..Self::new(sound)
+        }
+    }
+
+    pub fn with_volume(mut self, volume: f32) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    pub fn with_range(mut self, range: f32) -> Self {
+        self.range = range;
+        self
+    }
+}
+
+/// Sound played where physics collisions happen
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollisionSound {
+    pub sound: PathBuf,
+    /// Impacts slower than this are silent
+    pub min_speed: f32,
+    /// Impacts at this speed or faster play at full volume
+    pub full_speed: f32,
+    /// Distance at which impacts fade out completely, in world units
+    pub range: f32,
+    /// Loudest impacts played per frame, so a pile landing doesn't start
+    /// hundreds of voices
+    pub max_per_frame: usize,
+}
+
+impl CollisionSound {
+    pub fn new(sound: impl Into<PathBuf>) -> Self {
+        Self {
+            sound: sound.into(),
+            min_speed: 0.5,
+            full_speed: 5.0,
+            range: 20.0,
+            max_per_frame: 4,
+        }
+    }
+}
+
+/// Sound playback and spatialization for the app
+pub struct AudioSystem {
+    backend: Box<dyn AudioBackend>,
+    /// Volume multiplier for every sound
+    pub volume: f32,
+    /// Played for [`CollisionEvent`]s emitted on the scene's event bus
+    pub collision_sound: Option<CollisionSound>,
+    /// Played when a simulation diverges
+    pub alert_sound: Option<PathBuf>,
+    listener: Option<Listener>,
+    collisions: EventReceiver<CollisionEvent>,
+    /// Voice of each emitter entity, `None` if it failed to start; finished
+    /// one-shot sounds stay listed so they aren't replayed
+    emitters: HashMap<Entity, Option<VoiceId>>,
+    /// Metric tones and their voices, started at the first sample
+    sonifications: Vec<(Sonification, Option<VoiceId>)>,
+}
+
+impl AudioSystem {
+    /// Audio through `backend`, listening for collisions on `events`
+    pub fn new(backend: impl AudioBackend + 'static, events: &mut EventBus) -> Self {
+        Self {
+            backend: Box::new(backend),
+            volume: 1.0,
+            collision_sound: None,
+            alert_sound: None,
+            listener: None,
+            collisions: events.subscribe(),
+            emitters: HashMap::new(),
+            sonifications: Vec::new(),
+        }
+    }
+
+    /// Name of the backend's audio library
+    pub fn backend_name(&self) -> &str {
+        self.backend.name()
+    }
+
+    /// Listener used for the last update
+    pub fn listener(&self) -> Option<Listener> {
+        self.listener
+    }
+
+    /// Play a sound once, without spatialization
+    ///
+    /// # Errors
+    ///
+    /// Returns the backend's error if the sound can't be played
+    pub fn play(&mut self, path: impl AsRef<Path>) -> Result<VoiceId> {
+        let settings = VoiceSettings {
+            gain: self.volume,
+            ..Default::default()
+        };
+        self.backend.play(path.as_ref(), settings)
+    }
+
+    /// Play a sound once from `position`, heard up to `range` away
+    ///
+    /// # Errors
+    ///
+    /// Returns the backend's error if the sound can't be played
+    pub fn play_at(
+        &mut self,
+        path: impl AsRef<Path>,
+        position: Vector3<f32>,
+        range: f32,
+    ) -> Result<VoiceId> {
+        let (gain, pan) = self
+            .listener
+            .map_or((1.0, 0.0), |listener| listener.spatialize(position, range));
+        let settings = VoiceSettings {
+            gain: gain * self.volume,
+            pan,
+            looping: false,
+        };
+        self.backend.play(path.as_ref(), settings)
+    }
+
+    /// Stop a sound started by this system
+    pub fn stop(&mut self, voice: VoiceId) {
+        self.backend.stop(voice);
+    }
+
+    /// Play the alert sound, if one is set
+    pub fn alert(&mut self) {
+        if let Some(path) = self.alert_sound.clone() {
+            if let Err(error) = self.play(path) {
+                tracing::warn!("{error}");
+            }
+        }
+    }
+
+    /// Play a tone following a metric, replacing any tone already following
+    /// the same metric
+    pub fn sonify(&mut self, sonification: Sonification) {
+        self.stop_sonifying(&sonification.metric);
+        self.sonifications.push((sonification, None));
+    }
+
+    /// Stop the tone following `metric`
+    pub fn stop_sonifying(&mut self, metric: &str) {
+        let backend = &mut self.backend;
+        self.sonifications.retain(|(sonification, voice)| {
+            let keep = sonification.metric != metric;
+            if let (false, Some(voice)) = (keep, *voice) {
+                backend.stop(voice);
+            }
+            keep
+        });
+    }
+
+    /// Metrics currently played as tones
+    pub fn sonifications(&self) -> impl Iterator<Item = &Sonification> {
+        self.sonifications
+            .iter()
+            .map(|(sonification, _)| sonification)
+    }
+
+    /// Retune the metric tones to the latest metric values
+    ///
+    /// Tones start with the first value of their metric.
+    pub fn update_sonification(&mut self, metrics: &BTreeMap<String, f64>) {
+        for (sonification, voice) in &mut self.sonifications {
+            let Some(&value) = metrics.get(&sonification.metric) else {
+                continue;
+            };
+            let (frequency, gain) = sonification.map(value);
+            let gain = gain * self.volume;
+            match *voice {
+                Some(voice) => {
+                    self.backend.set_frequency(voice, frequency);
+                    self.backend.set_voice(voice, gain, 0.0);
+                }
+                None => {
+                    let settings = VoiceSettings {
+                        gain,
+                        ..Default::default()
+                    };
+                    match self.backend.play_tone(frequency, settings) {
+                        Ok(started) => *voice = Some(started),
+                        Err(error) => tracing::warn!("{error}"),
+                    }
+                }
+            }
+        }
+    }
+
+    /// Move the listener to the camera, sync emitters with their entities
+    /// and play sounds for new collisions
+    pub fn update(&mut self, scene: &Scene) {
+        let camera = &scene.camera_manager.camera;
+        let listener = Listener::looking_at(camera.eye, camera.target, camera.up);
+        self.listener = Some(listener);
+
+        let positions: HashMap<Entity, Vector3<f32>> = scene
+            .objects
+            .iter()
+            .filter_map(|object| Some((object.entity?, object.transform.w.truncate())))
+            .collect();
+        let mut alive = Vec::new();
+        for (entity, emitter) in scene.world.query::<SoundEmitter>() {
+            let position = positions.get(&entity).copied().or_else(|| {
+                scene
+                    .world
+                    .get::<crate::ecs::components::Transform>(entity)
+                    .map(|transform| transform.position)
+            });
+            let (gain, pan) = position.map_or((1.0, 0.0), |position| {
+                listener.spatialize(position, emitter.range)
+            });
+            let gain = gain * emitter.volume * self.volume;
+            alive.push(entity);
+            match self.emitters.get(&entity) {
+                Some(&Some(voice)) => {
+                    if self.backend.is_playing(voice) {
+                        self.backend.set_voice(voice, gain, pan);
+                    }
+                }
+                Some(None) => {}
+                None => {
+                    let settings = VoiceSettings {
+                        gain,
+                        pan,
+                        looping: emitter.looping,
+                    };
+                    // Failures stay listed so a broken file isn't retried every frame
+                    let voice = self
+                        .backend
+                        .play(&emitter.sound, settings)
+                        .inspect_err(|error| tracing::warn!("{error}"))
+                        .ok();
+                    self.emitters.insert(entity, voice);
+                }
+            }
+        }
+        let backend = &mut self.backend;
+        self.emitters.retain(|entity, voice| {
+            let keep = alive.contains(entity);
+            if let (false, Some(voice)) = (keep, *voice) {
+                backend.stop(voice);
+            }
+            keep
+        });
+
+        let mut collisions = self.collisions.drain();
+        let Some(sound) = self.collision_sound.clone() else {
+            return;
+        };
+        collisions.retain(|collision| collision.speed >= sound.min_speed);
+        collisions.sort_by(|a, b| b.speed.total_cmp(&a.speed));
+        for collision in collisions.into_iter().take(sound.max_per_frame) {
+            let strength = ((collision.speed - sound.min_speed)
+                / (sound.full_speed - sound.min_speed).max(f32::EPSILON))
+            .clamp(0.0, 1.0);
+            let (gain, pan) = listener.spatialize(collision.position, sound.range);
+            let settings = VoiceSettings {
+                gain: gain * strength * self.volume,
+                pan,
+                looping: false,
+            };
+            if let Err(error) = self.backend.play(&sound.sound, settings) {
+                tracing::warn!("{error}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gfx::camera::{
+        camera_controller::CameraController, camera_utils::CameraManager, orbit_camera::OrbitCamera,
+    };
+    use crate::gfx::scene::object::Object;
+    use std::sync::{Arc, Mutex};
+
+    /// Backend recording the voices it was asked to play
+    #[derive(Clone, Default)]
+    struct Recorder {
+        voices: Arc<Mutex<Vec<(PathBuf, VoiceSettings, bool)>>>,
+    }
+
+    impl AudioBackend for Recorder {
+        fn name(&self) -> &str {
+            "recorder"
+        }
+
+        fn play(&mut self, path: &Path, settings: VoiceSettings) -> Result<VoiceId> {
+            let mut voices = self.voices.lock().unwrap();
+            voices.push((path.to_path_buf(), settings, true));
+            Ok(voices.len() as VoiceId - 1)
+        }
+
+        fn play_tone(&mut self, frequency: f32, settings: VoiceSettings) -> Result<VoiceId> {
+            self.play(Path::new(&format!("{frequency} Hz")), settings)
+        }
+
+        fn set_voice(&mut self, voice: VoiceId, gain: f32, pan: f32) {
+            let voice = &mut self.voices.lock().unwrap()[voice as usize].1;
+            voice.gain = gain;
+            voice.pan = pan;
+        }
+
+        fn set_frequency(&mut self, voice: VoiceId, frequency: f32) {
+            self.voices.lock().unwrap()[voice as usize].0 = format!("{frequency} Hz").into();
+        }
+
+        fn stop(&mut self, voice: VoiceId) {
+            self.voices.lock().unwrap()[voice as usize].2 = false;
+        }
+
+        fn is_playing(&self, voice: VoiceId) -> bool {
+            self.voices.lock().unwrap()[voice as usize].2
+        }
+    }
+
+    #[test]
+    fn test_emitters_follow_objects_and_collisions_play() {
+        let camera = OrbitCamera::new(10.0, 0.0, 0.0, Vector3::new(0.0, 0.0, 0.0), 1.0);
+        let mut scene = Scene::new(CameraManager::new(
+            camera,
+            CameraController::new(0.005, 0.1),
+        ));
+        let camera = &scene.camera_manager.camera;
+        let eye = camera.eye;
+        let right = (camera.target - eye).cross(camera.up).normalize();
+        let recorder = Recorder::default();
+        let mut audio = AudioSystem::new(recorder.clone(), &mut scene.events);
+
+        // Halfway to the edge of its range, hard right
+        let mut object = Object::new(Vec::new());
+        object.set_translation(eye + right * 5.0);
+        let index = scene.push_object(object);
+        let entity = scene.entity_of(index).unwrap();
+        scene
+            .world
+            .insert(entity, SoundEmitter::looping("hum.wav").with_range(10.0));
+
+        audio.update(&scene);
+        let voice = recorder.voices.lock().unwrap()[0].1;
+        assert!((voice.gain - 0.25).abs() < 1e-4 && (voice.pan - 1.0).abs() < 1e-4);
+        assert!(voice.looping);
+
+        // Moving the object updates the same voice; it isn't restarted
+        scene.objects[index].set_translation(eye);
+        audio.update(&scene);
+        assert_eq!(recorder.voices.lock().unwrap().len(), 1);
+        let voice = recorder.voices.lock().unwrap()[0].1;
+        assert_eq!((voice.gain, voice.pan), (1.0, 0.0));
+
+        // Only collisions above the threshold play, louder when faster
+        audio.collision_sound = Some(CollisionSound::new("knock.wav"));
+        for speed in [0.1, 2.75, 10.0] {
+            scene.events.emit(CollisionEvent {
+                position: eye,
+                normal: Vector3::unit_z(),
+                speed,
+            });
+        }
+        audio.update(&scene);
+        {
+            let voices = recorder.voices.lock().unwrap();
+            let gains: Vec<f32> = voices[1..].iter().map(|voice| voice.1.gain).collect();
+            assert_eq!(gains, vec![1.0, 0.5]);
+        }
+
+        // Removing the object stops its sound
+        scene.remove_object(index);
+        audio.update(&scene);
+        assert!(!recorder.is_playing(0));
+    }
+
+    #[test]
+    fn test_sonification_follows_metric() {
+        let recorder = Recorder::default();
+        let mut audio = AudioSystem::new(recorder.clone(), &mut EventBus::new());
+        audio.sonify(Sonification::new("energy", 0.0..=1.0));
+
+        // Silent until the metric is first reported
+        let mut metrics = BTreeMap::new();
+        audio.update_sonification(&metrics);
+        assert!(recorder.voices.lock().unwrap().is_empty());
+
+        metrics.insert("energy".to_string(), 0.0);
+        audio.update_sonification(&metrics);
+        metrics.insert("energy".to_string(), 1.0);
+        audio.update_sonification(&metrics);
+        {
+            let voices = recorder.voices.lock().unwrap();
+            assert_eq!(voices.len(), 1);
+            assert_eq!(voices[0].0, PathBuf::from("880 Hz"));
+            assert!((voices[0].1.gain - 0.3).abs() < 1e-6);
+        }
+
+        audio.stop_sonifying("energy");
+        assert!(!recorder.is_playing(0));
+        assert_eq!(audio.sonifications().count(), 0);
+    }
+}
@@ -0,0 +1,80 @@
+//! Sonification of simulation metrics
+//!
+//! A [`Sonification`] plays a continuous tone whose pitch and volume follow
+//! one [`MetricSample`](crate::events::MetricSample) stream, such as the
+//! vorticity at a probe or a live cell count. Left running in the background
+//! of a long run, a drifting or jumping tone gives away regime changes
+//! without anyone watching the plots.
+//!
+//! Values are mapped linearly onto volume and exponentially onto pitch, so
+//! equal steps in the metric sound like equal musical intervals.
+
+use std::ops::RangeInclusive;
+
+/// Tone driven by one named metric
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sonification {
+    /// Name of the metric samples to follow
+    pub metric: String,
+    /// Metric values mapped onto the low and high ends of pitch and volume;
+    /// values outside are clamped
+    pub values: RangeInclusive<f64>,
+    /// Tone frequency in Hz at the low and high end of `values`
+    pub pitch: RangeInclusive<f32>,
+    /// Gain at the low and high end of `values`
+    pub volume: RangeInclusive<f32>,
+}
+
+impl Sonification {
+    /// Two octaves from 220 Hz to 880 Hz over `values`, at a constant,
+    /// quiet volume
+    pub fn new(metric: &str, values: RangeInclusive<f64>) -> Self {
+        Self {
+            metric: metric.to_string(),
+            values,
+            pitch: 220.0..=880.0,
+            volume: 0.3..=0.3,
+        }
+    }
+
+    pub fn with_pitch(mut self, pitch: RangeInclusive<f32>) -> Self {
+        self.pitch = pitch;
+        self
+    }
+
+    pub fn with_volume(mut self, volume: RangeInclusive<f32>) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    /// Frequency and gain of the tone for a metric `value`
+    pub fn map(&self, value: f64) -> (f32, f32) {
+        let (low, high) = (*self.values.start(), *self.values.end());
+        let t = if high > low {
+            ((value - low) / (high - low)).clamp(0.0, 1.0) as f32
+        } else {
+            0.0
+        };
+        let (pitch_low, pitch_high) = (*self.pitch.start(), *self.pitch.end());
+        let frequency = pitch_low * (pitch_high / pitch_low).powf(t);
+        let gain = self.volume.start() + (self.volume.end() - self.volume.start()) * t;
+        (frequency, gain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_values_map_to_intervals_and_volume() {
+        let sonification = Sonification::new("live_cells", 0.0..=100.0).with_volume(0.0..=1.0);
+        assert_eq!(sonification.map(0.0), (220.0, 0.0));
+        assert_eq!(sonification.map(100.0), (880.0, 1.0));
+        // Halfway is one octave up, and out of range values are clamped
+        let (frequency, gain) = sonification.map(50.0);
+        assert!((frequency - 440.0).abs() < 1e-3 && (gain - 0.5).abs() < 1e-6);
+        assert_eq!(sonification.map(-20.0), sonification.map(0.0));
+        assert_eq!(sonification.map(1e9), sonification.map(100.0));
+    }
+}
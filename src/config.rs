@@ -0,0 +1,607 @@
+//! # Project Configuration Files
+//!
+//! Simple setups, a few objects around a simulation template, can be
+//! described in a TOML file instead of Rust code and opened with
+//! [`haggis::from_config`](crate::from_config):
+//!
+//! ```toml
+//! [window]
+//! title = "Heat plate"
+//! size = [1280, 720]
+//!
+//! [camera]
+//! target = [0.0, 0.0, 0.0]
+//! distance = 6.0
+//! pitch = 0.6
+//! yaw = 0.3
+//!
+//! [ui]
+//! style = "dark"
+//! performance_panel = true
+//!
+//! [[material]]
+//! name = "steel"
+//! color = [0.7, 0.7, 0.75, 1.0]
+//! metallic = 1.0
+//! roughness = 0.3
+//!
+//! [[object]]
+//! mesh = "cylinder"
+//! name = "Post"
+//! position = [1.5, 0.0, 0.0]
+//! material = "steel"
+//!
+//! [simulation]
+//! template = "heat_diffusion"
+//! resolution = [128, 128]
+//!
+//! [simulation.parameters]
+//! diffusivity = 80.0
+//! ```
+//!
+//! Every section is optional. Object meshes are `cube`, `sphere`, `plane`,
+//! `cylinder` or the path of an OBJ file; relative paths are resolved against
+//! the config file's directory. Simulation templates are `heat_diffusion`
+//! and `gray_scott` (sized by `resolution`), `boids` (sized by `count`) and
+//! `cloth` (sized by `size` in world units and `resolution` in particles);
+//! `[simulation.parameters]` sets fields of the template's parameter struct
+//! by name.
+//!
+//! ```no_run
+//! fn main() -> haggis::Result<()> {
+//!     haggis::from_config("haggis.toml")?.run();
+//!     Ok(())
+//! }
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use crate::app::session::CameraPose;
+use crate::error::{HaggisError, Result};
+use crate::util::toml_lite::{parse_array, parse_string};
+use crate::simulation::params::SimParams;
+use crate::simulation::templates::{
+    Boids, BoidsParams, Cloth, ClothParams, GrayScott, GrayScottParams, HeatDiffusion2D, HeatParams,
+};
+use crate::simulation::traits::Simulation;
+use crate::ui::UiStyle;
+use crate::HaggisApp;
+
+/// Mesh names that aren't files
+const PRIMITIVES: [&str; 4] = ["cube", "sphere", "plane", "cylinder"];
+
+/// Simulation templates by config name
+const TEMPLATES: [&str; 4] = ["heat_diffusion", "gray_scott", "boids", "cloth"];
+
+/// UI options of a project
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UiConfig {
+    /// `default`, `light`, `dark` or `matrix`
+    pub style: Option<String>,
+    pub scale: Option<f32>,
+    /// TOML theme file, see [`UiTheme`](crate::ui::UiTheme)
+    pub theme: Option<PathBuf>,
+    pub performance_panel: Option<bool>,
+    pub simulation_toolbar: Option<bool>,
+    pub transform_panel: Option<bool>,
+    pub grid: Option<bool>,
+}
+
+/// A `[[material]]` table
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaterialConfig {
+    pub name: String,
+    pub color: [f32; 4],
+    pub metallic: f32,
+    pub roughness: f32,
+}
+
+/// An `[[object]]` table
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectConfig {
+    /// Primitive name or OBJ file path
+    pub mesh: String,
+    pub name: Option<String>,
+    pub position: [f32; 3],
+    pub scale: f32,
+    /// Rotation around X, Y and Z in degrees
+    pub rotation: [f32; 3],
+    pub material: Option<String>,
+}
+
+/// The `[simulation]` table
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationConfig {
+    pub template: String,
+    /// Grid cells, or cloth particles, per side
+    pub resolution: Option<[u32; 2]>,
+    /// Number of boids
+    pub count: Option<u32>,
+    /// Cloth size in world units
+    pub size: Option<[f32; 2]>,
+    /// `name = value` lines for the template's parameters
+    pub parameters: String,
+}
+
+impl SimulationConfig {
+    fn empty() -> Self {
+        Self {
+            template: String::new(),
+            resolution: None,
+            count: None,
+            size: None,
+            parameters: String::new(),
+        }
+    }
+
+    /// Create the template with its parameters
+    ///
+    /// # Errors
+    ///
+    /// Returns a message if a parameter value is malformed or a size is out
+    /// of range.
+    pub fn build(&self) -> std::result::Result<Box<dyn Simulation>, String> {
+        let [width, height] = self.resolution.unwrap_or([128, 128]);
+        match self.template.as_str() {
+            "heat_diffusion" => {
+                let params = with_parameters(HeatParams::default(), &self.parameters)?;
+                Ok(Box::new(
+                    HeatDiffusion2D::new(width, height).with_params(params),
+                ))
+            }
+            "gray_scott" => {
+                let params = with_parameters(GrayScottParams::default(), &self.parameters)?;
+                Ok(Box::new(GrayScott::new(width, height).with_params(params)))
+            }
+            "boids" => {
+                let count = self.count.unwrap_or(500);
+                if count == 0 {
+                    return Err("boids count must be at least 1".to_string());
+                }
+                let params = with_parameters(BoidsParams::default(), &self.parameters)?;
+                Ok(Box::new(Boids::new(count).with_params(params)))
+            }
+            "cloth" => {
+                let resolution = self.resolution.unwrap_or([32, 32]);
+                if resolution.iter().any(|&particles| particles < 2) {
+                    return Err("cloth resolution must be at least [2, 2]".to_string());
+                }
+                let params = with_parameters(ClothParams::default(), &self.parameters)?;
+                let size = self.size.unwrap_or([2.0, 2.0]);
+                Ok(Box::new(Cloth::new(size, resolution).with_params(params)))
+            }
+            other => Err(format!("unknown simulation template '{other}'")),
+        }
+    }
+}
+
+/// Scene, simulation and UI setup of a project
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProjectConfig {
+    pub title: Option<String>,
+    /// Window size in logical pixels
+    pub window_size: Option<(u32, u32)>,
+    pub camera: Option<CameraPose>,
+    pub ui: UiConfig,
+    pub materials: Vec<MaterialConfig>,
+    pub objects: Vec<ObjectConfig>,
+    pub simulation: Option<SimulationConfig>,
+}
+
+/// Table the parser is in
+enum Table {
+    Top,
+    Window,
+    Camera,
+    Ui,
+    Material,
+    Object,
+    Simulation,
+    Parameters,
+}
+
+impl ProjectConfig {
+    /// Parse a project from TOML text
+    ///
+    /// Unknown tables, keys, UI styles and templates are errors, reported
+    /// with their line number; parameter values are checked by
+    /// [`SimulationConfig::build`].
+    pub fn from_toml(text: &str) -> std::result::Result<Self, String> {
+        let mut config = Self::default();
+        let mut table = Table::Top;
+        let mut camera = [None; 6];
+
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if line.starts_with('[') {
+                table = match line {
+                    "[window]" => Table::Window,
+                    "[camera]" => Table::Camera,
+                    "[ui]" => Table::Ui,
+                    "[[material]]" => {
+                        config.materials.push(MaterialConfig {
+                            name: String::new(),
+                            color: [0.8, 0.8, 0.8, 1.0],
+                            metallic: 0.0,
+                            roughness: 0.5,
+                        });
+                        Table::Material
+                    }
+                    "[[object]]" => {
+                        config.objects.push(ObjectConfig {
+                            mesh: String::new(),
+                            name: None,
+                            position: [0.0; 3],
+                            scale: 1.0,
+                            rotation: [0.0; 3],
+                            material: None,
+                        });
+                        Table::Object
+                    }
+                    "[simulation]" => {
+                        config
+                            .simulation
+                            .get_or_insert_with(SimulationConfig::empty);
+                        Table::Simulation
+                    }
+                    "[simulation.parameters]" => {
+                        config
+                            .simulation
+                            .get_or_insert_with(SimulationConfig::empty);
+                        Table::Parameters
+                    }
+                    other => return Err(format!("line {line_number}: unknown table {other}")),
+                };
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(format!("line {line_number}: expected `key = value`"));
+            };
+            let (key, value) = (key.trim(), value.trim());
+            let invalid = || format!("line {line_number}: invalid value for {key}");
+            let unknown = || format!("line {line_number}: unknown key {key}");
+            let number = || value.parse::<f32>().map_err(|_| invalid());
+            let flag = || value.parse::<bool>().map_err(|_| invalid());
+            let string = || parse_string(value).ok_or_else(invalid);
+            match table {
+                Table::Top => return Err(format!("line {line_number}: expected a [table]")),
+                Table::Window => match key {
+                    "title" => config.title = Some(string()?),
+                    "size" => {
+                        let [width, height] = parse_array(value).ok_or_else(invalid)?;
+                        config.window_size = Some((width, height));
+                    }
+                    _ => return Err(unknown()),
+                },
+                Table::Camera => match key {
+                    "target" => {
+                        let target: [f32; 3] = parse_array(value).ok_or_else(invalid)?;
+                        camera[..3].copy_from_slice(&target.map(Some));
+                    }
+                    "distance" => camera[3] = Some(number()?),
+                    "pitch" => camera[4] = Some(number()?),
+                    "yaw" => camera[5] = Some(number()?),
+                    _ => return Err(unknown()),
+                },
+                Table::Ui => match key {
+                    "style" => {
+                        let style = string()?;
+                        if ui_style(&style).is_none() {
+                            return Err(format!("line {line_number}: unknown UI style '{style}'"));
+                        }
+                        config.ui.style = Some(style);
+                    }
+                    "scale" => config.ui.scale = Some(number()?),
+                    "theme" => config.ui.theme = Some(string()?.into()),
+                    "performance_panel" => config.ui.performance_panel = Some(flag()?),
+                    "simulation_toolbar" => config.ui.simulation_toolbar = Some(flag()?),
+                    "transform_panel" => config.ui.transform_panel = Some(flag()?),
+                    "grid" => config.ui.grid = Some(flag()?),
+                    _ => return Err(unknown()),
+                },
+                Table::Material => {
+                    let material = config.materials.last_mut().expect("material table");
+                    match key {
+                        "name" => material.name = string()?,
+                        "color" => material.color = parse_array(value).ok_or_else(invalid)?,
+                        "metallic" => material.metallic = number()?,
+                        "roughness" => material.roughness = number()?,
+                        _ => return Err(unknown()),
+                    }
+                }
+                Table::Object => {
+                    let object = config.objects.last_mut().expect("object table");
+                    match key {
+                        "mesh" => object.mesh = string()?,
+                        "name" => object.name = Some(string()?),
+                        "position" => object.position = parse_array(value).ok_or_else(invalid)?,
+                        "scale" => object.scale = number()?,
+                        "rotation" => object.rotation = parse_array(value).ok_or_else(invalid)?,
+                        "material" => object.material = Some(string()?),
+                        _ => return Err(unknown()),
+                    }
+                }
+                Table::Simulation => {
+                    let simulation = config.simulation.as_mut().expect("simulation table");
+                    match key {
+                        "template" => {
+                            let template = string()?;
+                            if !TEMPLATES.contains(&template.as_str()) {
+                                return Err(format!(
+                                    "line {line_number}: unknown simulation template '{template}'"
+                                ));
+                            }
+                            simulation.template = template;
+                        }
+                        "resolution" => {
+                            simulation.resolution = Some(parse_array(value).ok_or_else(invalid)?);
+                        }
+                        "count" => simulation.count = Some(value.parse().map_err(|_| invalid())?),
+                        "size" => simulation.size = Some(parse_array(value).ok_or_else(invalid)?),
+                        _ => return Err(unknown()),
+                    }
+                }
+                Table::Parameters => {
+                    let simulation = config.simulation.as_mut().expect("simulation table");
+                    simulation
+                        .parameters
+                        .push_str(&format!("{key} = {value}\n"));
+                }
+            }
+        }
+
+        match camera {
+            [Some(x), Some(y), Some(z), Some(distance), Some(pitch), Some(yaw)] => {
+                config.camera = Some(CameraPose {
+                    target: [x, y, z],
+                    distance,
+                    pitch,
+                    yaw,
+                });
+            }
+            [None, None, None, None, None, None] => {}
+            _ => return Err("[camera] needs target, distance, pitch and yaw".to_string()),
+        }
+        if let Some(object) = config.objects.iter().find(|object| object.mesh.is_empty()) {
+            return Err(format!("object {:?} has no mesh", object.name));
+        }
+        if let Some(material) = config
+            .materials
+            .iter()
+            .find(|material| material.name.is_empty())
+        {
+            return Err(format!(
+                "material with color {:?} has no name",
+                material.color
+            ));
+        }
+        if config
+            .simulation
+            .as_ref()
+            .is_some_and(|simulation| simulation.template.is_empty())
+        {
+            return Err("[simulation] has no template".to_string());
+        }
+        Ok(config)
+    }
+
+    /// Read a project file, resolving relative mesh and theme paths against
+    /// its directory
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::Io`] if the file cannot be read,
+    /// [`HaggisError::Config`] if it is malformed, and
+    /// [`HaggisError::NoFileSystem`] on platforms without file access.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if !crate::platform::HAS_FILESYSTEM {
+            return Err(HaggisError::NoFileSystem { path: path.into() });
+        }
+        let text = std::fs::read_to_string(path).map_err(|source| HaggisError::Io {
+            path: path.into(),
+            source,
+        })?;
+        let mut config = Self::from_toml(&text).map_err(|message| HaggisError::Config {
+            path: path.into(),
+            message,
+        })?;
+
+        let directory = path.parent().unwrap_or(Path::new(""));
+        for object in &mut config.objects {
+            if !PRIMITIVES.contains(&object.mesh.as_str()) {
+                object.mesh = directory.join(&object.mesh).to_string_lossy().into_owned();
+            }
+        }
+        if let Some(theme) = &mut config.ui.theme {
+            *theme = directory.join(&*theme);
+        }
+        Ok(config)
+    }
+
+    /// Set up `app` as described
+    ///
+    /// Window options only take effect before the app runs.
+    ///
+    /// # Errors
+    ///
+    /// Returns the simulation template's message if its parameters are
+    /// malformed.
+    pub fn apply(&self, app: &mut HaggisApp) -> std::result::Result<(), String> {
+        if let Some(title) = &self.title {
+            app.app_state.window_config.title = title.clone();
+        }
+        if let Some(size) = self.window_size {
+            app.app_state.window_config.size = size;
+        }
+        if let Some(pose) = &self.camera {
+            pose.apply(&mut app.app_state.scene.camera_manager.camera);
+        }
+
+        let ui = &self.ui;
+        if let Some(style) = ui.style.as_deref().and_then(ui_style) {
+            app.set_ui_style(style);
+        }
+        if let Some(scale) = ui.scale {
+            app.set_ui_scale(scale);
+        }
+        if let Some(theme) = &ui.theme {
+            app.set_ui_theme_file(theme);
+        }
+        if let Some(show) = ui.performance_panel {
+            app.show_performance_panel(show);
+        }
+        if let Some(show) = ui.simulation_toolbar {
+            app.show_simulation_toolbar(show);
+        }
+        if let Some(show) = ui.transform_panel {
+            app.set_transform_panel_visible(show);
+        }
+        if let Some(show) = ui.grid {
+            app.show_grid(show);
+        }
+
+        for material in &self.materials {
+            app.app_state.scene.add_material(
+                &material.name,
+                material.color,
+                material.metallic,
+                material.roughness,
+            );
+        }
+        for object in &self.objects {
+            let builder = match object.mesh.as_str() {
+                "cube" => app.add_cube(),
+                "sphere" => app.add_sphere(32, 16),
+                "plane" => app.add_plane(1.0, 1.0, 1, 1),
+                "cylinder" => app.add_cylinder(0.5, 1.0, 32),
+                path => app.add_object(path),
+            };
+            let mut builder = builder
+                .with_position(object.position)
+                .with_scale(object.scale)
+                .with_rotation_xyz(object.rotation);
+            if let Some(name) = &object.name {
+                builder = builder.with_name(name);
+            }
+            if let Some(material) = &object.material {
+                builder.with_material(material);
+            }
+        }
+
+        if let Some(simulation) = &self.simulation {
+            let simulation = simulation.build()?;
+            app.app_state
+                .simulation_manager
+                .attach_simulation(simulation, &mut app.app_state.scene);
+        }
+        Ok(())
+    }
+}
+
+/// `params` with the `name = value` lines of `text` applied
+fn with_parameters<T: SimParams>(mut params: T, text: &str) -> std::result::Result<T, String> {
+    params.apply_text(text)?;
+    Ok(params)
+}
+
+/// UI style preset by config name
+fn ui_style(name: &str) -> Option<UiStyle> {
+    match name {
+        "default" => Some(UiStyle::Default),
+        "light" => Some(UiStyle::Light),
+        "dark" => Some(UiStyle::Dark),
+        "matrix" => Some(UiStyle::Matrix),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROJECT: &str = r#"
+        # Heated plate with a post
+        [window]
+        title = "Heat plate"
+        size = [1280, 720]
+
+        [camera]
+        target = [0.0, 0.0, 0.0]
+        distance = 6.0
+        pitch = 0.6
+        yaw = 0.3
+
+        [ui]
+        style = "dark"
+        performance_panel = true
+
+        [[material]]
+        name = "steel"
+        color = [0.7, 0.7, 0.75, 1.0]
+        metallic = 1.0
+
+        [[object]]
+        mesh = "cylinder"
+        name = "Post"
+        position = [1.5, 0.0, 0.0]
+        material = "steel"
+
+        [[object]]
+        mesh = "models/plate.obj"
+
+        [simulation]
+        template = "heat_diffusion"
+        resolution = [64, 32]
+
+        [simulation.parameters]
+        diffusivity = 80.0
+    "#;
+
+    #[test]
+    fn test_project_parses_and_builds() {
+        let config = ProjectConfig::from_toml(PROJECT).unwrap();
+        assert_eq!(config.title.as_deref(), Some("Heat plate"));
+        assert_eq!(config.window_size, Some((1280, 720)));
+        assert_eq!(config.camera.unwrap().distance, 6.0);
+        assert_eq!(config.ui.style.as_deref(), Some("dark"));
+        assert_eq!(config.materials[0].roughness, 0.5);
+        assert_eq!(config.objects.len(), 2);
+        assert_eq!(config.objects[0].name.as_deref(), Some("Post"));
+        assert_eq!(config.objects[1].scale, 1.0);
+
+        let simulation = config.simulation.unwrap();
+        assert_eq!(simulation.resolution, Some([64, 32]));
+        assert_eq!(simulation.parameters, "diffusivity = 80.0\n");
+        assert!(simulation.build().is_ok());
+
+        let broken = SimulationConfig {
+            parameters: "diffusivity = hot\n".to_string(),
+            ..simulation
+        };
+        assert!(broken.build().is_err());
+    }
+
+    #[test]
+    fn test_malformed_projects_are_rejected() {
+        for text in [
+            "title = \"no table\"",
+            "[windows]\ntitle = \"x\"",
+            "[window]\nsize = [1, 2, 3]",
+            "[camera]\ndistance = 4.0",
+            "[ui]\nstyle = \"neon\"",
+            "[[object]]\nname = \"No mesh\"",
+            "[[material]]\ncolor = [1.0, 0.0, 0.0, 1.0]",
+            "[simulation]\ntemplate = \"navier_stokes\"",
+            "[simulation.parameters]\ndiffusivity = 80.0",
+        ] {
+            assert!(ProjectConfig::from_toml(text).is_err(), "accepted {text:?}");
+        }
+    }
+}
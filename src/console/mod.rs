@@ -0,0 +1,366 @@
+//! # Command Console
+//!
+//! This module provides a registry of text commands such as `sim pause` or
+//! `camera goto top`, and a dropdown console window to type them into
+//! (toggled with the backquote key by default).
+//!
+//! ## Features
+//!
+//! - **Command Registry**: Named commands with help text and closure handlers
+//! - **Simulation Commands**: Lines no registered command handles are offered
+//!   to the attached simulation via `Simulation::handle_command`
+//! - **Batch Scripting**: Queue command lines from code with
+//!   `HaggisApp::run_script`, the same path the console uses
+//!
+//! ## Built-in Commands
+//!
+//! - `help` - List registered commands
+//! - `sim pause|resume|reset` - Control the attached simulation
+//! - `camera goto <top|bottom|front|back|left|right|iso|home>` - Preset views
+//! - `camera distance <d>` / `camera target <x> <y> <z>` - Orbit camera setup
+//! - `echo <text>` - Print text to the console
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! let mut app = haggis::default();
+//! app.register_command("spawn", "spawn <count> - add cubes", |args, scene| {
+//!     let count: usize = args
+//!         .get(1)
+//!         .ok_or("usage: spawn <count>")?
+//!         .parse()
+//!         .map_err(|_| "count must be a number")?;
+//!     Ok(format!("spawned {} objects ({} total)", count, scene.objects.len()))
+//! });
+//! app.run_script("camera goto top\nsim pause");
+//! app.run();
+//! ```
+
+pub mod window;
+
+pub use window::Console;
+
+use std::collections::BTreeMap;
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+use cgmath::Vector3;
+
+use crate::events::SimulationEvent;
+use crate::gfx::scene::Scene;
+use crate::simulation::manager::SimulationManager;
+
+/// Result of running a command: output text, or an error message
+pub type CommandResult = Result<String, String>;
+
+/// Command handler, called with the full argument list (`args[0]` is the
+/// command name)
+pub type CommandHandler = Box<dyn Fn(&[&str], &mut Scene) -> CommandResult + Send + Sync>;
+
+struct RegisteredCommand {
+    help: String,
+    handler: CommandHandler,
+}
+
+/// Named commands that can be run from the console or from scripts
+pub struct CommandRegistry {
+    commands: BTreeMap<String, RegisteredCommand>,
+}
+
+impl CommandRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self {
+            commands: BTreeMap::new(),
+        }
+    }
+
+    /// Create a registry with the built-in `sim`, `camera`, `grid` and `echo` commands
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        registry.register("sim", "sim <pause|resume|step [count]|until <step>|reset>", sim_command);
+        registry.register(
+            "camera",
+            "camera goto <view> | distance <d> | target <x> <y> <z>",
+            camera_command,
+        );
+        registry.register(
+            "grid",
+            "grid <on|off> | axes <on|off> | labels <on|off> | spacing <s>",
+            grid_command,
+        );
+        registry.register("echo", "echo <text>", |args, _scene| Ok(args[1..].join(" ")));
+        registry
+    }
+
+    /// Register a command, replacing any existing command with the same name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Command name, the first word of the line
+    /// * `help` - One-line usage shown by `help`
+    /// * `handler` - Called with all words of the line (`args[0]` is `name`)
+    pub fn register<F>(&mut self, name: &str, help: &str, handler: F)
+    where
+        F: Fn(&[&str], &mut Scene) -> CommandResult + Send + Sync + 'static,
+    {
+        self.commands.insert(
+            name.to_string(),
+            RegisteredCommand {
+                help: help.to_string(),
+                handler: Box::new(handler),
+            },
+        );
+    }
+
+    /// Remove a command, returning whether it existed
+    pub fn unregister(&mut self, name: &str) -> bool {
+        self.commands.remove(name).is_some()
+    }
+
+    /// Whether a command with this name is registered
+    pub fn contains(&self, name: &str) -> bool {
+        self.commands.contains_key(name)
+    }
+
+    /// Registered command names and help text, sorted by name
+    pub fn commands(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.commands
+            .iter()
+            .map(|(name, command)| (name.as_str(), command.help.as_str()))
+    }
+
+    /// Run an already tokenized command.
+    ///
+    /// # Returns
+    ///
+    /// `None` if no registered command has this name, so the caller can try
+    /// other handlers (e.g. the attached simulation)
+    pub fn execute(&self, args: &[&str], scene: &mut Scene) -> Option<CommandResult> {
+        let name = *args.first()?;
+        if name == "help" {
+            return Some(Ok(self.help_text()));
+        }
+        let command = self.commands.get(name)?;
+        Some((command.handler)(args, scene))
+    }
+
+    /// Tokenize and run a full command line.
+    ///
+    /// Lines no registered command handles are offered to the attached
+    /// simulation, if a simulation manager is given.
+    ///
+    /// # Returns
+    ///
+    /// Command output (empty for blank lines and comments), or an error
+    pub fn run_line(
+        &self,
+        line: &str,
+        scene: &mut Scene,
+        simulation_manager: Option<&mut SimulationManager>,
+    ) -> CommandResult {
+        let words = tokenize(line)?;
+        if words.is_empty() {
+            return Ok(String::new());
+        }
+        let args: Vec<&str> = words.iter().map(String::as_str).collect();
+
+        if let Some(result) = self.execute(&args, scene) {
+            return result;
+        }
+        if let Some(result) =
+            simulation_manager.and_then(|manager| manager.handle_command(&args, scene))
+        {
+            return result;
+        }
+        Err(format!("unknown command '{}', try 'help'", args[0]))
+    }
+
+    fn help_text(&self) -> String {
+        let mut text = String::from("Commands:");
+        for (name, help) in self.commands() {
+            text.push_str(&format!("\n  {:<10} {}", name, help));
+        }
+        text
+    }
+}
+
+impl Default for CommandRegistry {
+    fn default() -> Self {
+        Self::with_builtins()
+    }
+}
+
+/// Split a command line into words.
+///
+/// Words are separated by whitespace; double quotes group words containing
+/// spaces. Blank lines and lines starting with `#` produce no words.
+pub fn tokenize(line: &str) -> Result<Vec<String>, String> {
+    let line = line.trim();
+    if line.starts_with('#') {
+        return Ok(Vec::new());
+    }
+
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_word = false;
+
+    for c in line.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_word = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if has_word {
+                    words.push(std::mem::take(&mut current));
+                    has_word = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_word = true;
+            }
+        }
+    }
+
+    if in_quotes {
+        return Err("unterminated quote".to_string());
+    }
+    if has_word {
+        words.push(current);
+    }
+    Ok(words)
+}
+
+fn sim_command(args: &[&str], scene: &mut Scene) -> CommandResult {
+    let event = match args.get(1).copied() {
+        Some("pause") => SimulationEvent::Pause,
+        Some("resume") => SimulationEvent::Resume,
+        Some("step") => match args.get(2) {
+            Some(count) => match count.parse() {
+                Ok(steps) => SimulationEvent::StepBy(steps),
+                Err(_) => return Err(format!("invalid step count '{count}'")),
+            },
+            None => SimulationEvent::Step,
+        },
+        Some("until") => match args.get(2).map(|step| step.parse()) {
+            Some(Ok(step)) => SimulationEvent::RunUntil(step),
+            _ => return Err("usage: sim until <step>".to_string()),
+        },
+        Some("reset") => SimulationEvent::Reset,
+        _ => return Err("usage: sim <pause|resume|step [count]|until <step>|reset>".to_string()),
+    };
+    scene.events.emit(event);
+    Ok(format!("simulation {}", args[1..].join(" ")))
+}
+
+fn camera_command(args: &[&str], scene: &mut Scene) -> CommandResult {
+    let camera = &mut scene.camera_manager.camera;
+
+    match args.get(1).copied() {
+        Some("goto") => {
+            // Canonical Z-up frame: pitch tilts towards up, yaw 0 looks along +Y from -Y
+            let (pitch, yaw) = match args.get(2).copied() {
+                Some("top") => (FRAC_PI_2, 0.0),
+                Some("bottom") => (-FRAC_PI_2, 0.0),
+                Some("front") => (0.0, 0.0),
+                Some("back") => (0.0, PI),
+                Some("right") => (0.0, -FRAC_PI_2),
+                Some("left") => (0.0, FRAC_PI_2),
+                Some("iso") => (0.6155, FRAC_PI_4),
+                Some("home") => {
+                    camera.reset_to_default();
+                    return Ok("camera reset".to_string());
+                }
+                _ => {
+                    return Err(
+                        "usage: camera goto <top|bottom|front|back|left|right|iso|home>"
+                            .to_string(),
+                    )
+                }
+            };
+            camera.set_yaw(yaw);
+            camera.set_pitch(pitch);
+            Ok(format!("camera view {}", args[2]))
+        }
+        Some("distance") => {
+            let distance = parse_number(args.get(2), "distance")?;
+            camera.set_distance(distance);
+            Ok(format!("camera distance {:.2}", camera.distance))
+        }
+        Some("target") => {
+            let x = parse_number(args.get(2), "x")?;
+            let y = parse_number(args.get(3), "y")?;
+            let z = parse_number(args.get(4), "z")?;
+            camera.set_target(Vector3::new(x, y, z));
+            Ok(format!("camera target ({}, {}, {})", x, y, z))
+        }
+        _ => Err("usage: camera goto <view> | distance <d> | target <x> <y> <z>".to_string()),
+    }
+}
+
+fn grid_command(args: &[&str], scene: &mut Scene) -> CommandResult {
+    let overlay = &mut scene.reference_overlay;
+    let usage = "usage: grid <on|off> | axes <on|off> | labels <on|off> | spacing <s>";
+
+    let (flag, name) = match args.get(1).copied() {
+        Some("on") | Some("off") => {
+            overlay.show_grid = args[1] == "on";
+            return Ok(format!("grid {}", args[1]));
+        }
+        Some("spacing") => {
+            let spacing = parse_number(args.get(2), "spacing")?;
+            if spacing <= 0.0 {
+                return Err("spacing must be positive".to_string());
+            }
+            overlay.spacing = spacing;
+            return Ok(format!("grid spacing {}", spacing));
+        }
+        Some("axes") => (&mut overlay.show_world_axes, "axes"),
+        Some("labels") => (&mut overlay.show_labels, "labels"),
+        _ => return Err(usage.to_string()),
+    };
+    match args.get(2).copied() {
+        Some("on") => *flag = true,
+        Some("off") => *flag = false,
+        _ => return Err(usage.to_string()),
+    }
+    if name == "axes" {
+        overlay.show_corner_axes = overlay.show_world_axes;
+    }
+    Ok(format!("grid {} {}", name, args[2]))
+}
+
+fn parse_number(arg: Option<&&str>, name: &str) -> Result<f32, String> {
+    let arg = arg.ok_or_else(|| format!("missing {}", name))?;
+    arg.parse()
+        .map_err(|_| format!("{} must be a number, got '{}'", name, arg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize_quotes_and_comments() {
+        assert_eq!(
+            tokenize(r#"export field "my field" frame_%04d.vtk"#).unwrap(),
+            vec!["export", "field", "my field", "frame_%04d.vtk"]
+        );
+        assert_eq!(tokenize("  sim   pause ").unwrap(), vec!["sim", "pause"]);
+        assert_eq!(tokenize(r#"echo """#).unwrap(), vec!["echo", ""]);
+        assert!(tokenize("# comment").unwrap().is_empty());
+        assert!(tokenize(r#"echo "open"#).is_err());
+    }
+
+    #[test]
+    fn test_registry_lists_and_replaces_commands() {
+        let mut registry = CommandRegistry::new();
+        registry.register("a", "first", |_, _| Ok(String::new()));
+        registry.register("a", "second", |_, _| Ok(String::new()));
+        assert_eq!(registry.commands().collect::<Vec<_>>(), vec![("a", "second")]);
+        assert!(registry.unregister("a"));
+        assert!(!registry.contains("a"));
+    }
+}
@@ -0,0 +1,202 @@
+//! Dropdown console window
+//!
+//! Draws the command log and input line at the top of the screen. The window
+//! only collects submitted lines; the application runs them afterwards so
+//! commands can reach the scene and the attached simulation.
+
+use imgui::Ui;
+
+/// Maximum number of log lines kept
+const MAX_LOG_LINES: usize = 500;
+
+/// Kind of console log line, used for coloring
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsoleLineKind {
+    /// A command the user entered
+    Input,
+    /// Command output
+    Output,
+    /// Command error
+    Error,
+}
+
+/// Dropdown console state: log, input line and command history
+pub struct Console {
+    /// Whether the console is visible
+    pub open: bool,
+    input: String,
+    log: Vec<(ConsoleLineKind, String)>,
+    history: Vec<String>,
+    history_cursor: Option<usize>,
+    focus_input: bool,
+    scroll_to_bottom: bool,
+}
+
+impl Console {
+    /// Create a closed, empty console
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            input: String::new(),
+            log: Vec::new(),
+            history: Vec::new(),
+            history_cursor: None,
+            focus_input: false,
+            scroll_to_bottom: false,
+        }
+    }
+
+    /// Show or hide the console
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.focus_input = self.open;
+    }
+
+    /// Append a line to the log
+    pub fn push(&mut self, kind: ConsoleLineKind, text: &str) {
+        for line in text.lines() {
+            self.log.push((kind, line.to_string()));
+        }
+        if self.log.len() > MAX_LOG_LINES {
+            let excess = self.log.len() - MAX_LOG_LINES;
+            self.log.drain(..excess);
+        }
+        self.scroll_to_bottom = true;
+    }
+
+    /// Append a command and its result to the log
+    pub fn push_result(&mut self, command: &str, result: &Result<String, String>) {
+        self.push(ConsoleLineKind::Input, &format!("> {}", command));
+        match result {
+            Ok(output) if !output.is_empty() => self.push(ConsoleLineKind::Output, output),
+            Ok(_) => {}
+            Err(error) => self.push(ConsoleLineKind::Error, error),
+        }
+    }
+
+    /// Remove all log lines
+    pub fn clear(&mut self) {
+        self.log.clear();
+    }
+
+    /// Draw the console if it is open.
+    ///
+    /// # Returns
+    ///
+    /// The command line submitted this frame, if any
+    pub fn render_ui(&mut self, ui: &Ui) -> Option<String> {
+        if !self.open {
+            return None;
+        }
+
+        let display_size = ui.io().display_size;
+        let mut submitted = None;
+
+        ui.window("Console")
+            .position([0.0, 0.0], imgui::Condition::Always)
+            .size(
+                [display_size[0], (display_size[1] * 0.35).max(120.0)],
+                imgui::Condition::Always,
+            )
+            .movable(false)
+            .resizable(false)
+            .collapsible(false)
+            .title_bar(false)
+            .build(|| {
+                let footer_height = ui.frame_height_with_spacing();
+                ui.child_window("##console_log")
+                    .size([0.0, -footer_height])
+                    .build(|| {
+                        for (kind, line) in &self.log {
+                            let color = match kind {
+                                ConsoleLineKind::Input => [0.6, 0.8, 1.0, 1.0],
+                                ConsoleLineKind::Output => [0.9, 0.9, 0.9, 1.0],
+                                ConsoleLineKind::Error => [1.0, 0.4, 0.4, 1.0],
+                            };
+                            ui.text_colored(color, line);
+                        }
+                        if self.scroll_to_bottom {
+                            ui.set_scroll_here_y_with_ratio(1.0);
+                            self.scroll_to_bottom = false;
+                        }
+                    });
+
+                // The toggle key can't reach the input map while the input line has
+                // keyboard focus, so close on it here instead
+                if ui.is_window_focused_with_flags(imgui::WindowFocusedFlags::ROOT_AND_CHILD_WINDOWS)
+                    && ui.is_key_pressed(imgui::Key::GraveAccent)
+                {
+                    self.open = false;
+                    self.input.retain(|c| c != '`');
+                    return;
+                }
+
+                if self.focus_input || ui.is_window_appearing() {
+                    ui.set_keyboard_focus_here();
+                    self.focus_input = false;
+                }
+
+                ui.set_next_item_width(-1.0);
+                let entered = ui
+                    .input_text("##console_input", &mut self.input)
+                    .enter_returns_true(true)
+                    .callback(
+                        imgui::InputTextCallback::HISTORY,
+                        HistoryCallback {
+                            history: &self.history,
+                            cursor: &mut self.history_cursor,
+                        },
+                    )
+                    .build();
+
+                if entered {
+                    let line = self.input.trim().to_string();
+                    self.input.clear();
+                    self.history_cursor = None;
+                    self.focus_input = true;
+                    if !line.is_empty() {
+                        if self.history.last() != Some(&line) {
+                            self.history.push(line.clone());
+                        }
+                        submitted = Some(line);
+                    }
+                }
+            });
+
+        submitted
+    }
+}
+
+/// Recalls previous commands with the up/down arrow keys
+struct HistoryCallback<'a> {
+    history: &'a [String],
+    cursor: &'a mut Option<usize>,
+}
+
+impl imgui::InputTextCallbackHandler for HistoryCallback<'_> {
+    fn on_history(&mut self, direction: imgui::HistoryDirection, mut data: imgui::TextCallbackData) {
+        if self.history.is_empty() {
+            return;
+        }
+
+        *self.cursor = match (direction, *self.cursor) {
+            (imgui::HistoryDirection::Up, None) => Some(self.history.len() - 1),
+            (imgui::HistoryDirection::Up, Some(cursor)) => Some(cursor.saturating_sub(1)),
+            (imgui::HistoryDirection::Down, Some(cursor)) if cursor + 1 < self.history.len() => {
+                Some(cursor + 1)
+            }
+            (imgui::HistoryDirection::Down, _) => None,
+        };
+
+        data.clear();
+        if let Some(cursor) = *self.cursor {
+            data.push_str(&self.history[cursor]);
+        }
+    }
+}
+
+impl Default for Console {
+    fn default() -> Self {
+        Self::new()
+    }
+}
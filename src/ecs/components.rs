@@ -0,0 +1,109 @@
+//! Built-in components
+//!
+//! The scene attaches these to the entity of every object and applies changes
+//! to them back to the object.
+
+use cgmath::{Deg, Matrix4, Vector3};
+
+use crate::gfx::scene::object::UiTransformState;
+
+/// Display name of an entity
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Name(pub String);
+
+/// Position, rotation and uniform scale of an entity
+///
+/// Uses the same conventions as the transform panel: rotation is in degrees
+/// around X, Y and Z.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub position: Vector3<f32>,
+    /// Rotation in degrees around X, Y and Z
+    pub rotation: Vector3<f32>,
+    pub scale: f32,
+}
+
+impl Transform {
+    /// Transform at `position` with no rotation and unit scale
+    pub fn from_position(position: Vector3<f32>) -> Self {
+        Self {
+            position,
+            ..Self::default()
+        }
+    }
+
+    /// Model matrix, composed the same way as [`Object::apply_ui_transform`]
+    ///
+    /// [`Object::apply_ui_transform`]: crate::gfx::scene::Object::apply_ui_transform
+    pub fn to_matrix(&self) -> Matrix4<f32> {
+        Matrix4::from_translation(self.position)
+            * Matrix4::from_angle_y(Deg(self.rotation.y))
+            * Matrix4::from_scale(self.scale)
+            * Matrix4::from_angle_x(Deg(self.rotation.x))
+            * Matrix4::from_angle_z(Deg(self.rotation.z))
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            position: Vector3::new(0.0, 0.0, 0.0),
+            rotation: Vector3::new(0.0, 0.0, 0.0),
+            scale: 1.0,
+        }
+    }
+}
+
+impl From<&UiTransformState> for Transform {
+    fn from(state: &UiTransformState) -> Self {
+        Self {
+            position: state.position.into(),
+            rotation: state.rotation.into(),
+            scale: state.scale,
+        }
+    }
+}
+
+impl From<Transform> for UiTransformState {
+    fn from(transform: Transform) -> Self {
+        Self {
+            position: transform.position.into(),
+            rotation: transform.rotation.into(),
+            scale: transform.scale,
+        }
+    }
+}
+
+/// Material assigned to an entity's object, by material name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaterialRef(pub String);
+
+/// Marks an object whose mouse drags are sent to simulations as
+/// [`DragEvent`](crate::events::DragEvent)s instead of orbiting the camera
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Draggable;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gfx::scene::Object;
+
+    #[test]
+    fn test_matrix_matches_object_transform() {
+        let transform = Transform {
+            position: Vector3::new(1.0, -2.0, 3.0),
+            rotation: Vector3::new(10.0, 45.0, -30.0),
+            scale: 2.0,
+        };
+        let mut object = Object::new(Vec::new());
+        object.ui_transform = transform.into();
+        object.apply_ui_transform();
+
+        let expected: &[f32; 16] = object.transform.as_ref();
+        let matrix = transform.to_matrix();
+        let actual: &[f32; 16] = matrix.as_ref();
+        for (a, b) in actual.iter().zip(expected) {
+            assert!((a - b).abs() < 1e-5);
+        }
+    }
+}
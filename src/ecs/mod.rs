@@ -0,0 +1,405 @@
+//! # Entity Component Storage
+//!
+//! A lightweight ECS layer for attaching structured data to scene objects and
+//! simulation entities. An [`Entity`] is a generational id; components are any
+//! `Send + 'static` type, stored densely per type in a [`World`].
+//!
+//! Every object added to a [`Scene`] gets an entity with [`Name`] and
+//! [`Transform`] components (see [`Object::entity`]). Changing a `Transform`
+//! or [`MaterialRef`] through the world moves or re-materials the linked
+//! object on the next frame, and the world's transforms are kept up to date
+//! with edits made through the UI or the object API. Simulations can attach
+//! their own per-entity data next to these built-in components, or spawn
+//! entities that have no object at all.
+//!
+//! ## Features
+//!
+//! - **Generational Ids**: A despawned entity's id is never confused with a
+//!   later entity reusing its slot
+//! - **Typed Storage**: One dense array per component type, no downcasts in
+//!   user code
+//! - **Queries**: Iterate one component type, or two at once with
+//!   [`World::for_each2_mut`]
+//! - **Change Tracking**: Mutable access marks components changed, so the
+//!   scene only applies what simulations touched
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::ecs::Transform;
+//! use haggis::gfx::scene::Scene;
+//! use cgmath::Vector3;
+//!
+//! /// Per-entity simulation data
+//! struct Velocity(Vector3<f32>);
+//!
+//! # fn setup(scene: &mut Scene) {
+//! // Attach data to the first object
+//! if let Some(entity) = scene.objects[0].entity {
+//!     scene.world.insert(entity, Velocity(Vector3::new(0.0, 0.0, 1.0)));
+//! }
+//! # }
+//! # fn step(scene: &mut Scene, dt: f32) {
+//! // Integrate every entity that has both a transform and a velocity
+//! scene.world.for_each2_mut::<Transform, Velocity>(|_entity, transform, velocity| {
+//!     transform.position += velocity.0 * dt;
+//! });
+//! # }
+//! ```
+//!
+//! [`Scene`]: crate::gfx::scene::Scene
+//! [`Object::entity`]: crate::gfx::scene::Object::entity
+
+pub mod components;
+
+pub use components::{Draggable, MaterialRef, Name, Transform};
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+/// Marker for types that can be stored as components
+pub trait Component: Send + 'static {}
+
+impl<T: Send + 'static> Component for T {}
+
+/// Generational entity id
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+impl Entity {
+    /// Slot index, reused after the entity is despawned
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Generation of the slot when this entity was spawned
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+/// Dense storage for one component type
+struct Storage<T> {
+    values: Vec<T>,
+    entities: Vec<Entity>,
+    changed: Vec<bool>,
+    /// Position in `values` for each entity slot
+    sparse: Vec<Option<usize>>,
+}
+
+impl<T> Storage<T> {
+    fn new() -> Self {
+        Self {
+            values: Vec::new(),
+            entities: Vec::new(),
+            changed: Vec::new(),
+            sparse: Vec::new(),
+        }
+    }
+
+    fn position(&self, entity: Entity) -> Option<usize> {
+        let position = (*self.sparse.get(entity.index as usize)?)?;
+        (self.entities[position] == entity).then_some(position)
+    }
+
+    fn insert(&mut self, entity: Entity, value: T) -> Option<T> {
+        if let Some(position) = self.position(entity) {
+            self.changed[position] = true;
+            return Some(std::mem::replace(&mut self.values[position], value));
+        }
+
+        let slot = entity.index as usize;
+        if self.sparse.len() <= slot {
+            self.sparse.resize(slot + 1, None);
+        }
+        self.sparse[slot] = Some(self.values.len());
+        self.values.push(value);
+        self.entities.push(entity);
+        self.changed.push(true);
+        None
+    }
+
+    fn remove(&mut self, entity: Entity) -> Option<T> {
+        let position = self.position(entity)?;
+        self.sparse[entity.index as usize] = None;
+        self.entities.swap_remove(position);
+        self.changed.swap_remove(position);
+        let value = self.values.swap_remove(position);
+        if let Some(moved) = self.entities.get(position) {
+            self.sparse[moved.index as usize] = Some(position);
+        }
+        Some(value)
+    }
+}
+
+/// Type-erased component storage
+trait AnyStorage: Send {
+    fn remove_entity(&mut self, entity: Entity);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Component> AnyStorage for Storage<T> {
+    fn remove_entity(&mut self, entity: Entity) {
+        self.remove(entity);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Entities and their components
+#[derive(Default)]
+pub struct World {
+    /// Current generation of each slot; odd while the slot is alive
+    generations: Vec<u32>,
+    free: Vec<u32>,
+    storages: HashMap<TypeId, Box<dyn AnyStorage>>,
+}
+
+impl World {
+    /// Create an empty world
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new entity with no components
+    pub fn spawn(&mut self) -> Entity {
+        if let Some(index) = self.free.pop() {
+            let generation = &mut self.generations[index as usize];
+            *generation += 1;
+            Entity {
+                index,
+                generation: *generation,
+            }
+        } else {
+            self.generations.push(1);
+            Entity {
+                index: self.generations.len() as u32 - 1,
+                generation: 1,
+            }
+        }
+    }
+
+    /// Remove an entity and all its components
+    ///
+    /// # Returns
+    ///
+    /// `false` if the entity was already despawned
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+        for storage in self.storages.values_mut() {
+            storage.remove_entity(entity);
+        }
+        self.generations[entity.index as usize] += 1;
+        self.free.push(entity.index);
+        true
+    }
+
+    /// Whether the entity exists
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.generations.get(entity.index as usize) == Some(&entity.generation)
+            && entity.generation % 2 == 1
+    }
+
+    /// Number of live entities
+    pub fn entity_count(&self) -> usize {
+        self.generations.len() - self.free.len()
+    }
+
+    fn storage<T: Component>(&self) -> Option<&Storage<T>> {
+        self.storages
+            .get(&TypeId::of::<T>())
+            .and_then(|storage| storage.as_any().downcast_ref())
+    }
+
+    fn storage_mut<T: Component>(&mut self) -> Option<&mut Storage<T>> {
+        self.storages
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|storage| storage.as_any_mut().downcast_mut())
+    }
+
+    /// Attach a component, replacing and returning any previous one of the
+    /// same type. Does nothing if the entity is not alive.
+    pub fn insert<T: Component>(&mut self, entity: Entity, component: T) -> Option<T> {
+        if !self.is_alive(entity) {
+            return None;
+        }
+        self.storages
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Storage::<T>::new()))
+            .as_any_mut()
+            .downcast_mut::<Storage<T>>()
+            .expect("component storage type mismatch")
+            .insert(entity, component)
+    }
+
+    /// Detach and return a component
+    pub fn remove<T: Component>(&mut self, entity: Entity) -> Option<T> {
+        self.storage_mut::<T>()?.remove(entity)
+    }
+
+    /// Component of an entity
+    pub fn get<T: Component>(&self, entity: Entity) -> Option<&T> {
+        let storage = self.storage::<T>()?;
+        storage.position(entity).map(|position| &storage.values[position])
+    }
+
+    /// Mutable component of an entity; marks it changed
+    pub fn get_mut<T: Component>(&mut self, entity: Entity) -> Option<&mut T> {
+        let storage = self.storage_mut::<T>()?;
+        let position = storage.position(entity)?;
+        storage.changed[position] = true;
+        Some(&mut storage.values[position])
+    }
+
+    /// Whether an entity has a component
+    pub fn has<T: Component>(&self, entity: Entity) -> bool {
+        self.get::<T>(entity).is_some()
+    }
+
+    /// Number of entities with a component
+    pub fn count<T: Component>(&self) -> usize {
+        self.storage::<T>().map_or(0, |storage| storage.values.len())
+    }
+
+    /// Iterate over every entity with a component
+    pub fn query<T: Component>(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.storage::<T>()
+            .into_iter()
+            .flat_map(|storage| storage.entities.iter().copied().zip(&storage.values))
+    }
+
+    /// Iterate mutably over every entity with a component; marks them all changed
+    pub fn query_mut<T: Component>(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        self.storage_mut::<T>().into_iter().flat_map(|storage| {
+            storage.changed.fill(true);
+            storage.entities.iter().copied().zip(&mut storage.values)
+        })
+    }
+
+    /// Call `f` for every entity that has both components; marks `A` changed.
+    ///
+    /// `A` and `B` must be different types.
+    pub fn for_each2_mut<A: Component, B: Component>(
+        &mut self,
+        mut f: impl FnMut(Entity, &mut A, &B),
+    ) {
+        assert_ne!(
+            TypeId::of::<A>(),
+            TypeId::of::<B>(),
+            "for_each2_mut needs two different component types"
+        );
+        // Take `A` out of the map so `B` can be borrowed alongside it
+        let Some(mut boxed) = self.storages.remove(&TypeId::of::<A>()) else {
+            return;
+        };
+        let storage_a = boxed
+            .as_any_mut()
+            .downcast_mut::<Storage<A>>()
+            .expect("component storage type mismatch");
+
+        if let Some(storage_b) = self.storage::<B>() {
+            for position in 0..storage_a.values.len() {
+                let entity = storage_a.entities[position];
+                if let Some(b) = storage_b.position(entity).map(|p| &storage_b.values[p]) {
+                    storage_a.changed[position] = true;
+                    f(entity, &mut storage_a.values[position], b);
+                }
+            }
+        }
+
+        self.storages.insert(TypeId::of::<A>(), boxed);
+    }
+
+    /// Entities whose component of type `T` changed since the last call,
+    /// clearing their changed flags
+    pub fn take_changed<T: Component>(&mut self) -> Vec<Entity> {
+        let Some(storage) = self.storage_mut::<T>() else {
+            return Vec::new();
+        };
+        let mut changed = Vec::new();
+        for (position, flag) in storage.changed.iter_mut().enumerate() {
+            if std::mem::take(flag) {
+                changed.push(storage.entities[position]);
+            }
+        }
+        changed
+    }
+
+    /// Overwrite a component without marking it changed, for engine code that
+    /// mirrors state into the world
+    pub(crate) fn set_untracked<T: Component>(&mut self, entity: Entity, component: T) {
+        let was_changed = self
+            .storage::<T>()
+            .and_then(|storage| storage.position(entity).map(|p| storage.changed[p]))
+            .unwrap_or(false);
+        self.insert(entity, component);
+        if let Some(storage) = self.storage_mut::<T>() {
+            if let Some(position) = storage.position(entity) {
+                storage.changed[position] = was_changed;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_despawned_entities_are_not_reused() {
+        let mut world = World::new();
+        let first = world.spawn();
+        world.insert(first, 1.0f32);
+        assert!(world.despawn(first));
+        assert!(!world.despawn(first));
+
+        let second = world.spawn();
+        assert_eq!(second.index(), first.index());
+        assert!(!world.is_alive(first));
+        assert!(world.get::<f32>(second).is_none());
+        assert!(world.insert(first, 2.0f32).is_none());
+        assert_eq!(world.count::<f32>(), 0);
+        assert_eq!(world.entity_count(), 1);
+    }
+
+    #[test]
+    fn test_queries_and_change_tracking() {
+        let mut world = World::new();
+        let a = world.spawn();
+        let b = world.spawn();
+        let c = world.spawn();
+        world.insert(a, 1u32);
+        world.insert(b, 2u32);
+        world.insert(c, 3u32);
+        world.insert(b, "b");
+        world.insert(c, "c");
+        world.take_changed::<u32>();
+
+        world.remove::<u32>(a);
+        world.for_each2_mut::<u32, &str>(|_, value, _| *value *= 10);
+        let mut values: Vec<_> = world.query::<u32>().map(|(e, v)| (e, *v)).collect();
+        values.sort();
+        assert_eq!(values, vec![(b, 20), (c, 30)]);
+
+        let mut changed = world.take_changed::<u32>();
+        changed.sort();
+        assert_eq!(changed, vec![b, c]);
+        assert!(world.take_changed::<u32>().is_empty());
+
+        world.set_untracked(b, 5u32);
+        assert_eq!(world.get::<u32>(b), Some(&5));
+        assert!(world.take_changed::<u32>().is_empty());
+    }
+}
@@ -0,0 +1,107 @@
+//! # Error Handling
+//!
+//! [`HaggisError`] covers the failures the engine reports instead of
+//! panicking: missing or malformed model files, GPU adapter and device
+//! creation, and device loss at runtime. Fallible APIs return [`Result`].
+//!
+//! Errors that happen while the app is running and can be recovered from are
+//! also shown as in-app notification toasts (see
+//! [`Notifications`](crate::ui::notifications::Notifications)).
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! let mut app = haggis::default();
+//! match app.try_add_object("models/bunny.obj") {
+//!     Ok(object) => {
+//!         object.with_transform([0.0, 0.0, 0.0], 1.0, 0.0);
+//!     }
+//!     Err(error) => eprintln!("{}", error),
+//! }
+//! ```
+
+use std::path::PathBuf;
+
+/// Result type for fallible haggis APIs
+pub type Result<T> = std::result::Result<T, HaggisError>;
+
+/// Errors reported by the engine
+#[derive(Debug, thiserror::Error)]
+pub enum HaggisError {
+    /// Reading or writing a file failed
+    #[error("Failed to access {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// A model file could not be loaded or parsed
+    #[error("Failed to load model {}: {message}", path.display())]
+    ModelLoad { path: PathBuf, message: String },
+    /// File access is not available on this platform (web builds)
+    #[error(
+        "Cannot load {}: no file system on this platform, use procedural geometry instead",
+        path.display()
+    )]
+    NoFileSystem { path: PathBuf },
+    /// A UI theme file could not be parsed
+    #[error("Failed to load theme {}: {message}", path.display())]
+    Theme { path: PathBuf, message: String },
+    /// A localization string table could not be parsed
+    #[error("Failed to load string table {}: {message}", path.display())]
+    Localization { path: PathBuf, message: String },
+    /// An image file could not be decoded
+    #[error("Failed to load image {}: {message}", path.display())]
+    Image { path: PathBuf, message: String },
+    /// A rendered frame doesn't match its reference image
+    #[error("Frame doesn't match {}: {message}", path.display())]
+    ImageMismatch { path: PathBuf, message: String },
+    /// Simulation state hashes don't match the stored ones
+    #[error("Step hashes don't match {}: {message}", path.display())]
+    HashMismatch { path: PathBuf, message: String },
+    /// A checkpoint file could not be parsed
+    #[error("Failed to load checkpoint {}: {message}", path.display())]
+    Checkpoint { path: PathBuf, message: String },
+    /// A scene annotations file could not be parsed
+    #[error("Failed to load annotations {}: {message}", path.display())]
+    Annotations { path: PathBuf, message: String },
+    /// A project configuration file could not be parsed or applied
+    #[error("Failed to load project {}: {message}", path.display())]
+    Config { path: PathBuf, message: String },
+    /// A session file could not be parsed
+    #[error("Failed to load session {}: {message}", path.display())]
+    Session { path: PathBuf, message: String },
+    /// The remote control server could not be started
+    #[error("Remote control server on {address}: {message}")]
+    Remote { address: String, message: String },
+    /// A telemetry socket could not be opened
+    #[error("Telemetry exporter on {address}: {message}")]
+    Telemetry { address: String, message: String },
+    /// A sound could not be played
+    #[error("Failed to play {}: {message}", path.display())]
+    Audio { path: PathBuf, message: String },
+    /// A WGSL shader could not be preprocessed or failed validation
+    #[error("Shader {label}: {message}")]
+    Shader { label: String, message: String },
+    /// No prefab was defined under this name
+    #[error("Unknown prefab '{0}'")]
+    UnknownPrefab(String),
+    /// The window or its event loop could not be created
+    #[error("Failed to create window: {0}")]
+    Window(String),
+    /// A rendering surface could not be created for the window
+    #[error("Failed to create surface: {0}")]
+    Surface(#[from] wgpu::CreateSurfaceError),
+    /// No GPU adapter is compatible with the window surface
+    #[error("No compatible GPU adapter: {0}")]
+    NoAdapter(#[from] wgpu::RequestAdapterError),
+    /// The adapter could not create a device, usually because the requested
+    /// features or limits are not supported
+    #[error("Failed to create GPU device: {0}")]
+    Device(#[from] wgpu::RequestDeviceError),
+    /// Reading data back from the GPU failed
+    #[error("GPU readback failed: {0}")]
+    Readback(#[from] wgpu::BufferAsyncError),
+    /// The GPU device was lost (driver reset, GPU removed or out of memory)
+    #[error("GPU device lost: {0}")]
+    DeviceLost(String),
+}
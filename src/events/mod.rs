@@ -0,0 +1,315 @@
+//! # Event Bus
+//!
+//! This module provides a typed message bus shared by the application,
+//! simulations and UI code. Any `Clone + Send + 'static` type can be used as an
+//! event: components emit events through the bus on the [`Scene`], and anyone
+//! holding an [`EventReceiver`] for that type gets a copy.
+//!
+//! ## Features
+//!
+//! - **Typed Channels**: One channel per event type, no string keys or downcasts
+//! - **Decoupled Receivers**: Receivers don't borrow the bus, so they can live
+//!   inside simulations or UI closures
+//! - **Built-in Events**: Picking, dragging, brush strokes, window resize and
+//!   simulation control events are emitted by the engine; [`ParameterChanged`] is
+//!   provided for UI code, [`MetricSample`] for simulations reporting
+//!   named scalars and [`CollisionEvent`] for physics impacts
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::events::{PickEvent, SimulationEvent};
+//!
+//! let mut app = haggis::default();
+//! let picks = app.events().subscribe::<PickEvent>();
+//!
+//! app.set_ui(move |ui, scene, _selected| {
+//!     for pick in picks.drain() {
+//!         println!("Picked object {}", pick.object_index);
+//!     }
+//!     ui.window("Controls").build(|| {
+//!         if ui.button("Reset") {
+//!             scene.events.emit(SimulationEvent::Reset);
+//!         }
+//!     });
+//! });
+//! ```
+//!
+//! [`Scene`]: crate::gfx::scene::Scene
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+
+use cgmath::Vector3;
+
+use crate::gfx::picking::Ray;
+
+/// Simulation control requests.
+///
+/// The application acts on these (resetting or pausing the current
+/// simulation), and every subscriber sees them too, so they double as
+/// notifications. Key bindings for simulation actions are routed through here.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimulationEvent {
+    /// Reset the current simulation to its initial state
+    Reset,
+    /// Pause the current simulation
+    Pause,
+    /// Resume the current simulation
+    Resume,
+    /// Advance the paused simulation by one step
+    Step,
+    /// Advance the paused simulation by this many steps
+    StepBy(u32),
+    /// Run the simulation and pause it at this step
+    RunUntil(u64),
+}
+
+/// An object was selected by clicking in the 3D view
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PickEvent {
+    /// Index of the picked object in the scene
+    pub object_index: usize,
+    /// Distance from the camera to the hit point
+    pub distance: f32,
+    /// World space hit point
+    pub point: Vector3<f32>,
+}
+
+/// Stage of a [`DragEvent`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DragPhase {
+    /// The left button was pressed on the object
+    Begin,
+    /// The cursor moved with the button held
+    Move,
+    /// The button was released
+    End,
+}
+
+/// The mouse is dragging a [`Draggable`](crate::ecs::Draggable) object
+///
+/// Emitted after the [`PickEvent`] of the press, on every cursor move while
+/// the left button is held and once more on release. The camera doesn't
+/// orbit meanwhile; simulations move the object, or the part of it that was
+/// grabbed, to follow `ray`.
+#[derive(Debug, Clone, Copy)]
+pub struct DragEvent {
+    /// Index of the dragged object in the scene
+    pub object_index: usize,
+    pub phase: DragPhase,
+    /// World space ray under the mouse
+    pub ray: Ray,
+    /// World space point grabbed on the press
+    pub point: Vector3<f32>,
+}
+
+/// The mouse was pressed or dragged in the 3D view in paint mode
+///
+/// Emitted instead of [`PickEvent`] while paint mode is on, see
+/// [`HaggisApp::set_paint_mode`](crate::app::HaggisApp::set_paint_mode).
+/// [`FieldBrush`](crate::simulation::paint::FieldBrush) turns strokes into
+/// grid positions to paint.
+#[derive(Debug, Clone, Copy)]
+pub struct BrushStroke {
+    /// World space ray under the mouse
+    pub ray: Ray,
+    /// First event of a stroke, from the button press
+    pub begin: bool,
+    /// Painted with the right button, to clear instead of paint
+    pub erase: bool,
+}
+
+/// The window was resized
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowResizeEvent {
+    /// New surface width in pixels
+    pub width: u32,
+    /// New surface height in pixels
+    pub height: u32,
+}
+
+/// A named parameter changed, typically from a UI control
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParameterChanged {
+    /// Parameter name
+    pub name: String,
+    /// New value
+    pub value: f32,
+}
+
+impl ParameterChanged {
+    pub fn new(name: &str, value: f32) -> Self {
+        Self {
+            name: name.to_string(),
+            value,
+        }
+    }
+}
+
+/// Something hit a collider in a physics simulation
+///
+/// [`CpuPbdSolver`](crate::simulation::pbd::CpuPbdSolver) collects these for
+/// the simulation driving it to emit; the `audio` module plays collision
+/// sounds for them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CollisionEvent {
+    /// World space contact point
+    pub position: Vector3<f32>,
+    /// Collider surface normal at the contact
+    pub normal: Vector3<f32>,
+    /// Speed into the surface just before the impact
+    pub speed: f32,
+}
+
+/// Latest value of a named simulation scalar, such as total energy
+///
+/// The app keeps the most recent sample of each metric and serves them to
+/// monitoring tools such as the remote control server.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricSample {
+    /// Metric name
+    pub name: String,
+    /// Current value
+    pub value: f64,
+}
+
+impl MetricSample {
+    pub fn new(name: &str, value: f64) -> Self {
+        Self {
+            name: name.to_string(),
+            value,
+        }
+    }
+}
+
+/// Receiving end of a subscription to events of type `E`
+///
+/// Events queue up until they are drained; dropping the receiver ends the
+/// subscription. Receivers are `Sync`, so they can be captured by UI callbacks.
+pub struct EventReceiver<E> {
+    receiver: Mutex<Receiver<E>>,
+}
+
+impl<E> EventReceiver<E> {
+    /// Take all queued events, oldest first
+    pub fn drain(&self) -> Vec<E> {
+        self.lock().try_iter().collect()
+    }
+
+    /// Take the next queued event, if any
+    pub fn try_recv(&self) -> Option<E> {
+        self.lock().try_recv().ok()
+    }
+
+    /// Take all queued events and keep only the most recent one
+    pub fn latest(&self) -> Option<E> {
+        self.lock().try_iter().last()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Receiver<E>> {
+        self.receiver
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Typed publish/subscribe bus
+#[derive(Default)]
+pub struct EventBus {
+    /// `Vec<Sender<E>>` per event type, keyed by the type id of `E`
+    channels: HashMap<TypeId, Box<dyn Any + Send>>,
+}
+
+impl EventBus {
+    /// Create an empty event bus
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscribe to events of type `E`.
+    ///
+    /// Only events emitted after subscribing are received.
+    pub fn subscribe<E: Clone + Send + 'static>(&mut self) -> EventReceiver<E> {
+        let (sender, receiver) = mpsc::channel();
+        self.senders_mut::<E>().push(sender);
+        EventReceiver {
+            receiver: Mutex::new(receiver),
+        }
+    }
+
+    /// Send an event to every current subscriber of its type.
+    ///
+    /// Subscribers whose receiver was dropped are removed.
+    pub fn emit<E: Clone + Send + 'static>(&mut self, event: E) {
+        let Some(senders) = self
+            .channels
+            .get_mut(&TypeId::of::<E>())
+            .and_then(|channel| channel.downcast_mut::<Vec<Sender<E>>>())
+        else {
+            return;
+        };
+
+        senders.retain(|sender| sender.send(event.clone()).is_ok());
+    }
+
+    /// Number of live subscribers for events of type `E`
+    pub fn subscriber_count<E: Clone + Send + 'static>(&self) -> usize {
+        self.channels
+            .get(&TypeId::of::<E>())
+            .and_then(|channel| channel.downcast_ref::<Vec<Sender<E>>>())
+            .map_or(0, |senders| senders.len())
+    }
+
+    fn senders_mut<E: Clone + Send + 'static>(&mut self) -> &mut Vec<Sender<E>> {
+        self.channels
+            .entry(TypeId::of::<E>())
+            .or_insert_with(|| Box::new(Vec::<Sender<E>>::new()))
+            .downcast_mut::<Vec<Sender<E>>>()
+            .expect("Event channel stored under the wrong type id")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_subscriber_receives_events() {
+        let mut bus = EventBus::new();
+        let first = bus.subscribe::<SimulationEvent>();
+        let second = bus.subscribe::<SimulationEvent>();
+        bus.emit(SimulationEvent::Reset);
+        bus.emit(SimulationEvent::Pause);
+
+        assert_eq!(first.drain(), vec![SimulationEvent::Reset, SimulationEvent::Pause]);
+        assert_eq!(second.latest(), Some(SimulationEvent::Pause));
+        assert!(first.try_recv().is_none());
+    }
+
+    #[test]
+    fn test_event_types_are_separate() {
+        let mut bus = EventBus::new();
+        let resizes = bus.subscribe::<WindowResizeEvent>();
+        bus.emit(SimulationEvent::Reset);
+        bus.emit(WindowResizeEvent {
+            width: 800,
+            height: 600,
+        });
+
+        assert_eq!(resizes.drain().len(), 1);
+    }
+
+    #[test]
+    fn test_dropped_receivers_are_pruned() {
+        let mut bus = EventBus::new();
+        let receiver = bus.subscribe::<ParameterChanged>();
+        assert_eq!(bus.subscriber_count::<ParameterChanged>(), 1);
+
+        drop(receiver);
+        bus.emit(ParameterChanged::new("viscosity", 0.1));
+        assert_eq!(bus.subscriber_count::<ParameterChanged>(), 0);
+    }
+}
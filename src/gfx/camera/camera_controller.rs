@@ -83,9 +83,8 @@ impl CameraController {
                 let was_shift_held = self.is_shift_held;
                 self.is_shift_held = *state == ElementState::Pressed;
 
-                // Debug output
                 if was_shift_held != self.is_shift_held {
-                    println!("Shift state changed: {}", self.is_shift_held);
+                    tracing::trace!("Shift state changed: {}", self.is_shift_held);
                 }
             }
             KeyEvent {
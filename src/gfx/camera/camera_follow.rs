@@ -0,0 +1,210 @@
+//! # Camera Follow Mode
+//!
+//! Lets the orbit camera track a moving point of interest instead of a fixed
+//! target. The follow target can be a scene object, a point that a simulation
+//! updates every frame (e.g. a center of mass), or an arbitrary closure.
+//!
+//! The orbit target is moved towards the tracked point with exponential
+//! smoothing, so distance, pitch and yaw remain under user control while the
+//! camera follows.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::gfx::camera::FollowTarget;
+//! use cgmath::Vector3;
+//!
+//! let mut app = haggis::default();
+//!
+//! // Track the first object in the scene
+//! app.app_state.scene.camera_manager.follow(FollowTarget::Object(0));
+//!
+//! // Or track a point that a simulation updates from `Simulation::update`
+//! app.app_state
+//!     .scene
+//!     .camera_manager
+//!     .follow(FollowTarget::Point(Vector3::new(0.0, 0.0, 0.0)));
+//! ```
+
+use cgmath::Vector3;
+
+use crate::gfx::scene::object::Object;
+
+/// What the camera should keep its orbit target on.
+pub enum FollowTarget {
+    /// Track the world-space position of the scene object at this index
+    Object(usize),
+    /// Track the scene object with this name
+    ObjectNamed(String),
+    /// Track a fixed point, typically updated every frame via
+    /// [`CameraFollow::set_point`]
+    Point(Vector3<f32>),
+    /// Track the point returned by a closure, evaluated once per frame
+    Function(Box<dyn Fn() -> Vector3<f32> + Send + Sync>),
+}
+
+impl FollowTarget {
+    /// Create a closure-based follow target
+    pub fn from_fn<F>(f: F) -> Self
+    where
+        F: Fn() -> Vector3<f32> + Send + Sync + 'static,
+    {
+        FollowTarget::Function(Box::new(f))
+    }
+
+    /// Resolve the tracked point for the current frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `objects` - Scene objects used to resolve object-based targets
+    ///
+    /// # Returns
+    ///
+    /// The world-space point to follow, or `None` if the target no longer exists
+    pub fn resolve(&self, objects: &[Object]) -> Option<Vector3<f32>> {
+        match self {
+            FollowTarget::Object(index) => objects.get(*index).map(object_position),
+            FollowTarget::ObjectNamed(name) => objects
+                .iter()
+                .find(|object| &object.name == name)
+                .map(object_position),
+            FollowTarget::Point(point) => Some(*point),
+            FollowTarget::Function(f) => Some(f()),
+        }
+    }
+}
+
+impl std::fmt::Debug for FollowTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FollowTarget::Object(index) => f.debug_tuple("Object").field(index).finish(),
+            FollowTarget::ObjectNamed(name) => f.debug_tuple("ObjectNamed").field(name).finish(),
+            FollowTarget::Point(point) => f.debug_tuple("Point").field(point).finish(),
+            FollowTarget::Function(_) => f.write_str("Function(..)"),
+        }
+    }
+}
+
+/// Active follow state held by the camera manager.
+#[derive(Debug)]
+pub struct CameraFollow {
+    /// The point of interest being tracked
+    pub target: FollowTarget,
+    /// Smoothing rate in 1/seconds. Higher values track more tightly;
+    /// `0.0` snaps the orbit target to the tracked point every frame.
+    pub smoothing: f32,
+    /// Constant offset added to the tracked point
+    pub offset: Vector3<f32>,
+}
+
+impl CameraFollow {
+    /// Default smoothing rate used by [`CameraFollow::new`]
+    pub const DEFAULT_SMOOTHING: f32 = 5.0;
+
+    /// Create a new follow state with default smoothing and no offset
+    pub fn new(target: FollowTarget) -> Self {
+        Self {
+            target,
+            smoothing: Self::DEFAULT_SMOOTHING,
+            offset: Vector3::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    /// Set the smoothing rate
+    pub fn with_smoothing(mut self, smoothing: f32) -> Self {
+        self.smoothing = smoothing.max(0.0);
+        self
+    }
+
+    /// Set a constant offset from the tracked point
+    pub fn with_offset(mut self, offset: Vector3<f32>) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Update the tracked point when following a [`FollowTarget::Point`].
+    ///
+    /// Switches the target to a point target if it was something else.
+    pub fn set_point(&mut self, point: Vector3<f32>) {
+        self.target = FollowTarget::Point(point);
+    }
+
+    /// Compute the new orbit target for this frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `current` - The camera's current orbit target
+    /// * `delta_time` - Time elapsed since the last frame in seconds
+    /// * `objects` - Scene objects used to resolve object-based targets
+    ///
+    /// # Returns
+    ///
+    /// The smoothed orbit target, or `None` if the target could not be resolved
+    pub fn step(
+        &self,
+        current: Vector3<f32>,
+        delta_time: f32,
+        objects: &[Object],
+    ) -> Option<Vector3<f32>> {
+        let goal = self.target.resolve(objects)? + self.offset;
+        Some(smooth_towards(current, goal, self.smoothing, delta_time))
+    }
+}
+
+/// Frame-rate independent exponential approach from `current` to `goal`.
+pub fn smooth_towards(
+    current: Vector3<f32>,
+    goal: Vector3<f32>,
+    smoothing: f32,
+    delta_time: f32,
+) -> Vector3<f32> {
+    if smoothing <= 0.0 {
+        return goal;
+    }
+    let alpha = 1.0 - (-smoothing * delta_time.max(0.0)).exp();
+    current + (goal - current) * alpha
+}
+
+fn object_position(object: &Object) -> Vector3<f32> {
+    object.transform.w.truncate()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_smoothing_snaps_to_goal() {
+        let result = smooth_towards(
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 2.0, 3.0),
+            0.0,
+            0.016,
+        );
+        assert_eq!(result, Vector3::new(1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn test_smoothing_moves_partway() {
+        let goal = Vector3::new(10.0, 0.0, 0.0);
+        let result = smooth_towards(Vector3::new(0.0, 0.0, 0.0), goal, 5.0, 0.1);
+        assert!(result.x > 0.0 && result.x < goal.x);
+    }
+
+    #[test]
+    fn test_point_target_resolves_with_offset() {
+        let follow = CameraFollow::new(FollowTarget::Point(Vector3::new(1.0, 1.0, 1.0)))
+            .with_smoothing(0.0)
+            .with_offset(Vector3::new(0.0, 0.0, 1.0));
+        let result = follow.step(Vector3::new(0.0, 0.0, 0.0), 0.016, &[]);
+        assert_eq!(result, Some(Vector3::new(1.0, 1.0, 2.0)));
+    }
+
+    #[test]
+    fn test_missing_object_target_resolves_to_none() {
+        let follow = CameraFollow::new(FollowTarget::Object(3));
+        assert!(follow
+            .step(Vector3::new(0.0, 0.0, 0.0), 0.016, &[])
+            .is_none());
+    }
+}
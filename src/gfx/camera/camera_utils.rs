@@ -4,16 +4,67 @@ use winit::{
     window::Window,
 };
 
-use super::{camera_controller::CameraController, orbit_camera::OrbitCamera};
+use super::{
+    camera_controller::CameraController,
+    camera_follow::{CameraFollow, FollowTarget},
+    orbit_camera::OrbitCamera,
+};
+use crate::gfx::scene::object::Object;
 
 pub struct CameraManager {
     pub camera: OrbitCamera,
     pub controller: CameraController,
+    /// Active follow mode, if the camera is tracking a moving target
+    pub follow: Option<CameraFollow>,
 }
 
 impl CameraManager {
     pub fn new(camera: OrbitCamera, controller: CameraController) -> Self {
-        Self { camera, controller }
+        Self {
+            camera,
+            controller,
+            follow: None,
+        }
+    }
+
+    /// Start tracking a target with the default smoothing
+    pub fn follow(&mut self, target: FollowTarget) {
+        self.follow = Some(CameraFollow::new(target));
+    }
+
+    /// Start tracking a target with a custom smoothing rate (1/seconds, `0.0` snaps)
+    pub fn follow_with_smoothing(&mut self, target: FollowTarget, smoothing: f32) {
+        self.follow = Some(CameraFollow::new(target).with_smoothing(smoothing));
+    }
+
+    /// Stop tracking; the orbit target stays where it currently is
+    pub fn stop_following(&mut self) {
+        self.follow = None;
+    }
+
+    /// Whether a follow target is active
+    pub fn is_following(&self) -> bool {
+        self.follow.is_some()
+    }
+
+    /// Update the tracked point, e.g. from a simulation's `update()` with its center of mass.
+    ///
+    /// Starts following the point if no follow target was active.
+    pub fn set_follow_point(&mut self, point: cgmath::Vector3<f32>) {
+        match self.follow.as_mut() {
+            Some(follow) => follow.set_point(point),
+            None => self.follow(FollowTarget::Point(point)),
+        }
+    }
+
+    /// Move the orbit target towards the follow target for this frame
+    pub fn update_follow(&mut self, delta_time: f32, objects: &[Object]) {
+        let Some(follow) = self.follow.as_ref() else {
+            return;
+        };
+        if let Some(target) = follow.step(self.camera.target, delta_time, objects) {
+            self.camera.set_target(target);
+        }
     }
 
     pub fn process_event(&mut self, event: &DeviceEvent, window: &Window) {
@@ -1,8 +1,10 @@
 pub mod camera_controller;
+pub mod camera_follow;
 pub mod camera_utils;
 pub mod orbit_camera;
 
 // Re-export main types
 pub use camera_controller::CameraController;
+pub use camera_follow::{CameraFollow, FollowTarget};
 pub use camera_utils::{CameraManager, CameraUniform};
 pub use orbit_camera::OrbitCamera;
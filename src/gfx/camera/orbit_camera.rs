@@ -1,12 +1,18 @@
 use super::camera_utils::{convert_matrix4_to_array, Camera, CameraUniform};
+use crate::gfx::coordinates::UpAxis;
+use crate::gfx::rendering::depth::DepthMode;
+use crate::gfx::scene::Layers;
 use cgmath::*;
 
+/// Maps OpenGL's -1 to 1 clip depth to wgpu's 0 to 1, leaving w alone
+///
+/// `Matrix4::new` takes columns, so the 0.5 offset goes in the last one.
 #[rustfmt::skip]
 pub const OPENGL_TO_WGPU_MATRIX: cgmath::Matrix4<f32> = cgmath::Matrix4::new(
     1.0, 0.0, 0.0, 0.0,
     0.0, 1.0, 0.0, 0.0,
-    0.0, 0.0, 0.5, 0.5,
-    0.0, 0.0, 0.0, 1.0,
+    0.0, 0.0, 0.5, 0.0,
+    0.0, 0.0, 0.5, 1.0,
 );
 
 #[derive(Debug, Clone, Copy)]
@@ -17,12 +23,19 @@ pub struct OrbitCamera {
     pub eye: Vector3<f32>,
     pub target: Vector3<f32>,
     pub up: Vector3<f32>,
+    /// World up axis; pitch and yaw are measured around it
+    pub up_axis: UpAxis,
     pub bounds: OrbitCameraBounds,
     pub aspect: f32,
     pub fovy: Rad<f32>,
     pub znear: f32,
     pub zfar: f32,
     pub uniform: CameraUniform,
+    /// Layers this camera draws, see [`Layers`]
+    pub layers: Layers,
+    /// Depth convention of the projection; the app keeps it in line with the
+    /// render engine's [`RenderConfig::depth_mode`](crate::gfx::rendering::RenderConfig::depth_mode)
+    pub depth_mode: DepthMode,
 }
 
 impl Camera for OrbitCamera {
@@ -30,9 +43,7 @@ impl Camera for OrbitCamera {
         let eye = Point3::from_vec(self.eye);
         let target = Point3::from_vec(self.target);
         let view = Matrix4::look_at_rh(eye, target, self.up);
-        let proj =
-            OPENGL_TO_WGPU_MATRIX * perspective(self.fovy, self.aspect, self.znear, self.zfar);
-        proj * view
+        self.projection_matrix() * view
     }
 }
 
@@ -45,12 +56,15 @@ impl OrbitCamera {
             eye: Vector3::zero(), // Will be auto-calculted in `update()` nevertheless.
             target,
             up: Vector3::unit_z(), // Z-up coordinate system
+            up_axis: UpAxis::Z,
             bounds: OrbitCameraBounds::default(),
             aspect,
             fovy: cgmath::Rad(std::f32::consts::PI / 4.0),
             znear: 0.1,
             zfar: 1000.0,
             uniform: CameraUniform::default(),
+            layers: Layers::ALL,
+            depth_mode: DepthMode::default(),
         };
         camera.update();
         camera
@@ -130,10 +144,35 @@ impl OrbitCamera {
         // println!("Pan delta: ({:.3}, {:.3}), Movement: {:?}", delta.0, delta.1, total_movement);
     }
 
+    /// Moves the orbit target, keeping distance, pitch and yaw unchanged
+    pub fn set_target(&mut self, target: Vector3<f32>) {
+        self.target = target;
+        self.update();
+    }
+
+    /// Switches the world up axis, keeping distance, pitch and yaw unchanged
+    pub fn set_up_axis(&mut self, up_axis: UpAxis) {
+        self.up_axis = up_axis;
+        self.up = up_axis.up();
+        self.update();
+    }
+
     /// Updates the camera after changing `distance`, `pitch` or `yaw`.
     fn update(&mut self) {
-        self.eye =
-            calculate_cartesian_eye_position(self.pitch, self.yaw, self.distance, self.target);
+        let offset = calculate_cartesian_eye_position(
+            self.pitch,
+            self.yaw,
+            self.distance,
+            Vector3::zero(),
+        );
+        self.eye = self.target + self.up_axis.from_z_up(offset);
+    }
+
+    /// Perspective projection with wgpu's depth range in the camera's [`DepthMode`]
+    pub fn projection_matrix(&self) -> Matrix4<f32> {
+        let projection =
+            OPENGL_TO_WGPU_MATRIX * perspective(self.fovy, self.aspect, self.znear, self.zfar);
+        self.depth_mode.projection(projection, self.znear, self.zfar)
     }
 
     pub fn resize_projection(&mut self, width: u32, height: u32) {
@@ -146,7 +185,6 @@ impl OrbitCamera {
     }
 }
 
-#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
 #[derive(Debug, Clone, Copy)]
 pub struct OrbitCameraBounds {
     pub min_distance: Option<f32>,
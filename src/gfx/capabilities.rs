@@ -0,0 +1,235 @@
+//! GPU capability report
+//!
+//! [`GpuCapabilities`] is gathered once when the render engine creates its
+//! device and is available to simulations as `scene.gpu_capabilities`. GPU
+//! simulations can use it to pick grid sizes and workgroup sizes that fit the
+//! device, or fall back to a CPU path, instead of hitting wgpu validation
+//! errors on weaker hardware.
+//!
+//! Limits describe the device as created, which may be lower than what the
+//! adapter supports (see `HaggisAppBuilder::adapter_limits`).
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! # use haggis::gfx::scene::Scene;
+//! # fn choose(scene: &Scene) {
+//! let use_gpu = scene
+//!     .gpu_capabilities
+//!     .as_ref()
+//!     .is_some_and(|caps| caps.supports_compute() && caps.fits_workgroup([16, 16, 1]));
+//! # }
+//! ```
+
+use std::fmt;
+
+/// Limits and features of the GPU the engine is running on
+#[derive(Debug, Clone)]
+pub struct GpuCapabilities {
+    /// Adapter name as reported by the driver
+    pub adapter_name: String,
+    /// Graphics API backend in use
+    pub backend: wgpu::Backend,
+    /// Discrete, integrated, virtual or CPU adapter
+    pub device_type: wgpu::DeviceType,
+    /// Limits the device was created with
+    pub limits: wgpu::Limits,
+    /// Best limits the adapter supports
+    pub adapter_limits: wgpu::Limits,
+    /// Features enabled on the device
+    pub features: wgpu::Features,
+    /// Features the adapter could enable
+    pub adapter_features: wgpu::Features,
+    /// Capabilities missing on downlevel backends (WebGL2, GLES, DX11)
+    pub downlevel_flags: wgpu::DownlevelFlags,
+}
+
+impl GpuCapabilities {
+    /// Gather capabilities for a device created from `adapter`
+    pub fn new(adapter: &wgpu::Adapter, device: &wgpu::Device) -> Self {
+        let info = adapter.get_info();
+        Self {
+            adapter_name: info.name,
+            backend: info.backend,
+            device_type: info.device_type,
+            limits: device.limits(),
+            adapter_limits: adapter.limits(),
+            features: device.features(),
+            adapter_features: adapter.features(),
+            downlevel_flags: adapter.get_downlevel_capabilities().flags,
+        }
+    }
+
+    /// Whether compute shaders can run at all (false on WebGL2)
+    pub fn supports_compute(&self) -> bool {
+        self.downlevel_flags
+            .contains(wgpu::DownlevelFlags::COMPUTE_SHADERS)
+            && self.limits.max_compute_invocations_per_workgroup > 0
+    }
+
+    /// Whether timestamp queries are enabled on the device, for GPU profiling
+    pub fn supports_timestamp_queries(&self) -> bool {
+        self.features.contains(wgpu::Features::TIMESTAMP_QUERY)
+    }
+
+    /// Whether the adapter could enable timestamp queries if requested
+    pub fn timestamp_queries_available(&self) -> bool {
+        self.adapter_features
+            .contains(wgpu::Features::TIMESTAMP_QUERY)
+    }
+
+    /// Largest buffer that can be created, in bytes
+    pub fn max_buffer_size(&self) -> u64 {
+        self.limits.max_buffer_size
+    }
+
+    /// Largest range of a buffer that can be bound as storage, in bytes
+    pub fn max_storage_binding_size(&self) -> u64 {
+        u64::from(self.limits.max_storage_buffer_binding_size).min(self.limits.max_buffer_size)
+    }
+
+    /// Whether a storage buffer of `bytes` can be created and bound in one piece
+    pub fn fits_storage_buffer(&self, bytes: u64) -> bool {
+        bytes <= self.max_storage_binding_size()
+    }
+
+    /// Maximum workgroup size per dimension
+    pub fn max_workgroup_size(&self) -> [u32; 3] {
+        [
+            self.limits.max_compute_workgroup_size_x,
+            self.limits.max_compute_workgroup_size_y,
+            self.limits.max_compute_workgroup_size_z,
+        ]
+    }
+
+    /// Whether a `@workgroup_size(x, y, z)` is valid on this device
+    pub fn fits_workgroup(&self, size: [u32; 3]) -> bool {
+        let max = self.max_workgroup_size();
+        let invocations = size.iter().map(|&n| u64::from(n)).product::<u64>();
+        self.supports_compute()
+            && size.iter().zip(max).all(|(&n, max)| n > 0 && n <= max)
+            && invocations <= u64::from(self.limits.max_compute_invocations_per_workgroup)
+    }
+
+    /// Whether dispatching `workgroups` groups per dimension is valid on this device
+    pub fn fits_dispatch(&self, workgroups: [u32; 3]) -> bool {
+        let max = self.limits.max_compute_workgroups_per_dimension;
+        self.supports_compute() && workgroups.iter().all(|&n| n <= max)
+    }
+
+    /// Largest square 2D grid whose cells fit in one storage binding
+    pub fn max_grid_2d(&self, bytes_per_cell: u64) -> u32 {
+        let cells = self.max_storage_binding_size() / bytes_per_cell.max(1);
+        integer_root(cells, 2).min(u32::MAX as u64) as u32
+    }
+
+    /// Largest cubic 3D grid whose cells fit in one storage binding
+    pub fn max_grid_3d(&self, bytes_per_cell: u64) -> u32 {
+        let cells = self.max_storage_binding_size() / bytes_per_cell.max(1);
+        integer_root(cells, 3).min(u32::MAX as u64) as u32
+    }
+}
+
+impl fmt::Display for GpuCapabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [x, y, z] = self.max_workgroup_size();
+        writeln!(
+            f,
+            "{} ({:?}, {:?})",
+            self.adapter_name, self.backend, self.device_type
+        )?;
+        writeln!(
+            f,
+            "Max buffer: {} MiB, storage binding: {} MiB",
+            self.max_buffer_size() >> 20,
+            self.max_storage_binding_size() >> 20
+        )?;
+        writeln!(
+            f,
+            "Workgroup: {}x{}x{} ({} invocations), {} groups per dimension",
+            x,
+            y,
+            z,
+            self.limits.max_compute_invocations_per_workgroup,
+            self.limits.max_compute_workgroups_per_dimension
+        )?;
+        write!(
+            f,
+            "Compute: {}, timestamp queries: {}",
+            if self.supports_compute() { "yes" } else { "no" },
+            if self.supports_timestamp_queries() {
+                "enabled"
+            } else if self.timestamp_queries_available() {
+                "available"
+            } else {
+                "unsupported"
+            }
+        )
+    }
+}
+
+/// Largest `r` with `r^n <= value`
+fn integer_root(value: u64, n: u32) -> u64 {
+    let mut root = (value as f64).powf(1.0 / n as f64) as u64;
+    while root > 0 && root.checked_pow(n).is_none_or(|p| p > value) {
+        root -= 1;
+    }
+    while (root + 1).checked_pow(n).is_some_and(|p| p <= value) {
+        root += 1;
+    }
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capabilities(limits: wgpu::Limits, downlevel_flags: wgpu::DownlevelFlags) -> GpuCapabilities {
+        GpuCapabilities {
+            adapter_name: "Test".to_string(),
+            backend: wgpu::Backend::Noop,
+            device_type: wgpu::DeviceType::Other,
+            adapter_limits: limits.clone(),
+            limits,
+            features: wgpu::Features::empty(),
+            adapter_features: wgpu::Features::TIMESTAMP_QUERY,
+            downlevel_flags,
+        }
+    }
+
+    #[test]
+    fn test_workgroup_and_grid_limits() {
+        let caps = capabilities(wgpu::Limits::downlevel_defaults(), wgpu::DownlevelFlags::all());
+        assert!(caps.supports_compute());
+        assert!(caps.fits_workgroup([16, 16, 1]));
+        assert!(!caps.fits_workgroup([32, 32, 1])); // 1024 > 256 invocations
+        assert!(!caps.fits_workgroup([0, 1, 1]));
+        assert!(!caps.fits_dispatch([70_000, 1, 1]));
+
+        // 128 MiB storage binding of 16-byte cells
+        let side = caps.max_grid_3d(16) as u64;
+        assert!(side.pow(3) * 16 <= caps.max_storage_binding_size());
+        assert!((side + 1).pow(3) * 16 > caps.max_storage_binding_size());
+        assert!(!caps.supports_timestamp_queries());
+        assert!(caps.timestamp_queries_available());
+    }
+
+    #[test]
+    fn test_webgl_has_no_compute() {
+        let caps = capabilities(
+            wgpu::Limits::downlevel_webgl2_defaults(),
+            wgpu::DownlevelFlags::empty(),
+        );
+        assert!(!caps.supports_compute());
+        assert!(!caps.fits_workgroup([1, 1, 1]));
+    }
+
+    #[test]
+    fn test_integer_root() {
+        assert_eq!(integer_root(0, 2), 0);
+        assert_eq!(integer_root(99, 2), 9);
+        assert_eq!(integer_root(100, 2), 10);
+        assert_eq!(integer_root(26, 3), 2);
+        assert_eq!(integer_root(u64::MAX, 2), u32::MAX as u64);
+    }
+}
@@ -0,0 +1,209 @@
+//! # World Units and Coordinate System
+//!
+//! Haggis works in a right-handed world. By default Z is up, X points right
+//! and Y points forward, so the ground is the XY plane. Scenes built around
+//! Y-up data (most game engines and DCC tools) can switch the world to Y-up
+//! instead, with X right and -Z forward.
+//!
+//! [`CoordinateSystem`] holds the world up axis, the up axis imported assets
+//! were authored in, and the world unit. It is applied in one place,
+//! [`Scene::set_coordinate_system`](crate::gfx::scene::Scene::set_coordinate_system),
+//! which keeps the camera, the reference grid, the viewport gizmo and the OBJ
+//! loader consistent:
+//!
+//! - the orbit camera rotates around the world up axis
+//! - the grid lies in the ground plane and labels distances in the world unit
+//! - loaded models are rotated from the asset up axis into the world up axis
+//!
+//! Orbit camera pitch and yaw, and the viewport gizmo's view directions, are
+//! always expressed in the canonical Z-up frame ("top" looks down the up
+//! axis), so they mean the same thing in both conventions.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::gfx::coordinates::{CoordinateSystem, UpAxis, WorldUnits};
+//!
+//! let mut app = haggis::HaggisApp::builder()
+//!     .coordinate_system(CoordinateSystem {
+//!         up_axis: UpAxis::Y,
+//!         asset_up_axis: UpAxis::Y,
+//!         units: WorldUnits::new("cm", 0.01),
+//!     })
+//!     .build();
+//! ```
+
+use cgmath::Vector3;
+
+use super::overlay::GridPlane;
+
+/// World axis pointing up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UpAxis {
+    /// Y up, -Z forward; ground is the XZ plane
+    Y,
+    /// Z up, Y forward; ground is the XY plane (the Haggis default)
+    #[default]
+    Z,
+}
+
+impl UpAxis {
+    /// Unit vector pointing up
+    pub fn up(self) -> Vector3<f32> {
+        match self {
+            UpAxis::Y => Vector3::unit_y(),
+            UpAxis::Z => Vector3::unit_z(),
+        }
+    }
+
+    /// Plane the ground lies in
+    pub fn ground_plane(self) -> GridPlane {
+        match self {
+            UpAxis::Y => GridPlane::XZ,
+            UpAxis::Z => GridPlane::XY,
+        }
+    }
+
+    /// Map a vector from the canonical Z-up frame into this convention
+    pub fn from_z_up(self, v: Vector3<f32>) -> Vector3<f32> {
+        match self {
+            // (x, y, z) -> (x, z, -y)
+            UpAxis::Y => Vector3::new(v.x, v.z, -v.y),
+            UpAxis::Z => v,
+        }
+    }
+
+    /// Map a vector in this convention into the canonical Z-up frame
+    pub fn to_z_up(self, v: Vector3<f32>) -> Vector3<f32> {
+        match self {
+            // (x, y, z) -> (x, -z, y)
+            UpAxis::Y => Vector3::new(v.x, -v.z, v.y),
+            UpAxis::Z => v,
+        }
+    }
+
+    /// Map a vector given in the `source` convention into this one
+    pub fn convert_from(self, source: UpAxis, v: Vector3<f32>) -> Vector3<f32> {
+        self.from_z_up(source.to_z_up(v))
+    }
+
+    /// Convert packed `[x, y, z, x, y, z, ...]` positions or normals in place
+    /// from the `source` convention into this one
+    pub fn convert_slice_from(self, source: UpAxis, data: &mut [f32]) {
+        if source == self {
+            return;
+        }
+        for point in data.chunks_exact_mut(3) {
+            let v = self.convert_from(source, Vector3::new(point[0], point[1], point[2]));
+            point.copy_from_slice(&[v.x, v.y, v.z]);
+        }
+    }
+}
+
+/// Length unit of world coordinates
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorldUnits {
+    /// Suffix shown next to distances, e.g. `"m"`
+    pub name: String,
+    /// Size of one world unit in meters
+    pub meters_per_unit: f32,
+}
+
+impl WorldUnits {
+    /// Create a unit named `name` that is `meters_per_unit` meters long
+    pub fn new(name: &str, meters_per_unit: f32) -> Self {
+        Self {
+            name: name.to_string(),
+            meters_per_unit,
+        }
+    }
+
+    /// One world unit is one meter
+    pub fn meters() -> Self {
+        Self::new("m", 1.0)
+    }
+
+    /// Convert a distance in meters to world units
+    pub fn from_meters(&self, meters: f32) -> f32 {
+        meters / self.meters_per_unit
+    }
+
+    /// Convert a distance in world units to meters
+    pub fn to_meters(&self, units: f32) -> f32 {
+        units * self.meters_per_unit
+    }
+}
+
+impl Default for WorldUnits {
+    /// Unitless coordinates, treated as meters
+    fn default() -> Self {
+        Self::new("", 1.0)
+    }
+}
+
+/// Up-axis and unit conventions of the world and of imported assets
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoordinateSystem {
+    /// Up axis of the world
+    pub up_axis: UpAxis,
+    /// Up axis models are authored in; OBJ exporters usually write Y-up
+    pub asset_up_axis: UpAxis,
+    /// Length unit of world coordinates
+    pub units: WorldUnits,
+}
+
+impl Default for CoordinateSystem {
+    fn default() -> Self {
+        Self {
+            up_axis: UpAxis::Z,
+            asset_up_axis: UpAxis::Y,
+            units: WorldUnits::default(),
+        }
+    }
+}
+
+impl CoordinateSystem {
+    /// Z-up world, Y-up assets (the default)
+    pub fn z_up() -> Self {
+        Self::default()
+    }
+
+    /// Y-up world with Y-up assets, loaded without rotation
+    pub fn y_up() -> Self {
+        Self {
+            up_axis: UpAxis::Y,
+            asset_up_axis: UpAxis::Y,
+            units: WorldUnits::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_up_axis_conversions_round_trip() {
+        let v = Vector3::new(1.0, 2.0, 3.0);
+        for axis in [UpAxis::Y, UpAxis::Z] {
+            assert_eq!(axis.to_z_up(axis.from_z_up(v)), v);
+            assert_eq!(axis.from_z_up(Vector3::unit_z()), axis.up());
+        }
+        // Y-up asset into a Z-up world, as the OBJ loader always did
+        assert_eq!(
+            UpAxis::Z.convert_from(UpAxis::Y, v),
+            Vector3::new(1.0, -3.0, 2.0)
+        );
+
+        let mut data = [0.0, 1.0, 0.0, 1.0, 0.0, 0.0];
+        UpAxis::Z.convert_slice_from(UpAxis::Y, &mut data);
+        assert_eq!(data, [0.0, 0.0, 1.0, 1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_units_convert_to_meters() {
+        let centimeters = WorldUnits::new("cm", 0.01);
+        assert_eq!(centimeters.from_meters(1.5), 150.0);
+        assert_eq!(centimeters.to_meters(50.0), 0.5);
+    }
+}
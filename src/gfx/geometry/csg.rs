@@ -0,0 +1,567 @@
+//! # Constructive Solid Geometry
+//!
+//! Boolean operations on closed meshes, for building obstacles in code:
+//! a sphere with a bore through it, a plate with holes, a channel with a
+//! bend. Results convert back to [`GeometryData`] for rendering, and answer
+//! inside/outside queries for turning them into simulation obstacles, e.g.
+//! with [`Csg::voxelize`] and
+//! [`BoundaryConditions::with_obstacles`](crate::simulation::boundary::BoundaryConditions::with_obstacles).
+//!
+//! Solids are kept as polygons in a BSP tree, following the classic csg.js
+//! approach. Inputs must be closed; triangles are oriented by their vertex
+//! normals, so primitives wound either way work. Cost grows roughly with the
+//! product of the two polygon counts, so keep primitive resolutions modest.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use cgmath::{Matrix4, Vector3};
+//! use haggis::gfx::geometry::{csg::Csg, generate_cube, generate_cylinder};
+//!
+//! // A plate with two holes
+//! let plate = Csg::from_geometry(&generate_cube())
+//!     .transformed(Matrix4::from_nonuniform_scale(2.0, 1.0, 0.2));
+//! let hole = Csg::from_geometry(&generate_cylinder(0.2, 1.0, 16));
+//! let plate = plate
+//!     .subtract(&hole.transformed(Matrix4::from_translation(Vector3::new(-0.5, 0.0, 0.0))))
+//!     .subtract(&hole.transformed(Matrix4::from_translation(Vector3::new(0.5, 0.0, 0.0))));
+//!
+//! assert!(!plate.contains(Vector3::new(0.5, 0.0, 0.0)));
+//! let mesh = plate.to_geometry();
+//! ```
+
+use cgmath::{InnerSpace, Matrix, Matrix3, Matrix4, SquareMatrix, Vector2, Vector3};
+
+use super::GeometryData;
+use crate::app::jobs::JobSystem;
+
+/// Distance within which points count as lying on a plane
+const EPSILON: f32 = 1e-5;
+
+/// A closed solid made of polygons
+#[derive(Debug, Clone, Default)]
+pub struct Csg {
+    polygons: Vec<Polygon>,
+}
+
+impl Csg {
+    /// Solid bounded by the triangles of a closed mesh
+    pub fn from_geometry(geometry: &GeometryData) -> Self {
+        let vertex = |index: u32| {
+            let i = index as usize;
+            Vertex {
+                position: geometry.vertices[i].into(),
+                normal: geometry.normals.get(i).copied().unwrap_or_default().into(),
+                tex_coord: geometry
+                    .tex_coords
+                    .get(i)
+                    .copied()
+                    .unwrap_or_default()
+                    .into(),
+            }
+        };
+        let polygons = geometry
+            .indices
+            .chunks_exact(3)
+            .filter_map(|triangle| {
+                let mut vertices: Vec<Vertex> = triangle.iter().map(|&i| vertex(i)).collect();
+                let plane = Plane::from_points(
+                    vertices[0].position,
+                    vertices[1].position,
+                    vertices[2].position,
+                )?;
+                let facing: Vector3<f32> = vertices.iter().map(|v| v.normal).sum();
+                if facing.dot(plane.normal) < 0.0 {
+                    vertices.reverse();
+                    return Some(Polygon {
+                        vertices,
+                        plane: plane.flipped(),
+                    });
+                }
+                Some(Polygon { vertices, plane })
+            })
+            .collect();
+        Self { polygons }
+    }
+
+    /// The solid moved, rotated or scaled by `matrix`
+    pub fn transformed(&self, matrix: Matrix4<f32>) -> Self {
+        let linear = Matrix3::from_cols(
+            matrix.x.truncate(),
+            matrix.y.truncate(),
+            matrix.z.truncate(),
+        );
+        let normal_matrix = linear
+            .invert()
+            .map(|inverse| inverse.transpose())
+            .unwrap_or(linear);
+        let mirrored = linear.determinant() < 0.0;
+        let polygons = self
+            .polygons
+            .iter()
+            .filter_map(|polygon| {
+                let mut vertices: Vec<Vertex> = polygon
+                    .vertices
+                    .iter()
+                    .map(|v| Vertex {
+                        position: (matrix * v.position.extend(1.0)).truncate(),
+                        normal: (normal_matrix * v.normal).normalize(),
+                        tex_coord: v.tex_coord,
+                    })
+                    .collect();
+                if mirrored {
+                    vertices.reverse();
+                }
+                let plane = Plane::from_polygon(&vertices)?;
+                Some(Polygon { vertices, plane })
+            })
+            .collect();
+        Self { polygons }
+    }
+
+    /// Space inside either solid
+    pub fn union(&self, other: &Csg) -> Csg {
+        let mut a = Node::new(self.polygons.clone());
+        let mut b = Node::new(other.polygons.clone());
+        a.clip_to(&b);
+        b.clip_to(&a);
+        b.invert();
+        b.clip_to(&a);
+        b.invert();
+        a.build(b.all_polygons());
+        Csg {
+            polygons: a.all_polygons(),
+        }
+    }
+
+    /// Space inside this solid but not `other`
+    pub fn subtract(&self, other: &Csg) -> Csg {
+        let mut a = Node::new(self.polygons.clone());
+        let mut b = Node::new(other.polygons.clone());
+        a.invert();
+        a.clip_to(&b);
+        b.clip_to(&a);
+        b.invert();
+        b.clip_to(&a);
+        b.invert();
+        a.build(b.all_polygons());
+        a.invert();
+        Csg {
+            polygons: a.all_polygons(),
+        }
+    }
+
+    /// Space inside both solids
+    pub fn intersect(&self, other: &Csg) -> Csg {
+        let mut a = Node::new(self.polygons.clone());
+        let mut b = Node::new(other.polygons.clone());
+        a.invert();
+        b.clip_to(&a);
+        b.invert();
+        a.clip_to(&b);
+        b.clip_to(&a);
+        a.build(b.all_polygons());
+        a.invert();
+        Csg {
+            polygons: a.all_polygons(),
+        }
+    }
+
+    pub fn polygon_count(&self) -> usize {
+        self.polygons.len()
+    }
+
+    /// Triangulated mesh of the solid's surface
+    ///
+    /// Surfaces from the inputs keep their interpolated normals and texture
+    /// coordinates, so a sphere stays smooth where it was cut.
+    pub fn to_geometry(&self) -> GeometryData {
+        let mut data = GeometryData::new();
+        for polygon in &self.polygons {
+            let first = data.vertices.len() as u32;
+            for vertex in &polygon.vertices {
+                data.vertices.push(vertex.position.into());
+                data.normals.push(vertex.normal.into());
+                data.tex_coords.push(vertex.tex_coord.into());
+            }
+            for i in 1..polygon.vertices.len() as u32 - 1 {
+                data.indices.push(first);
+                data.indices.push(first + i);
+                data.indices.push(first + i + 1);
+            }
+        }
+        data
+    }
+
+    /// Whether `point` is inside the solid
+    ///
+    /// Builds a BSP tree on every call; use [`Csg::voxelize`] for many points.
+    pub fn contains(&self, point: Vector3<f32>) -> bool {
+        Node::new(self.polygons.clone()).contains(point)
+    }
+
+    /// Obstacle bits of a `size` grid of cells of `cell_size`, with the
+    /// corner of the first cell at `origin`
+    ///
+    /// A cell is solid when its center is inside. Bits are laid out like
+    /// [`BoundaryConditions::with_obstacles`](crate::simulation::boundary::BoundaryConditions::with_obstacles)
+    /// expects: one per cell, x fastest, 32 cells per word.
+    pub fn voxelize(&self, size: [u32; 3], origin: Vector3<f32>, cell_size: f32) -> Vec<u32> {
+        let tree = Node::new(self.polygons.clone());
+        let cells = size.iter().map(|&s| s as usize).product::<usize>();
+        JobSystem::global().parallel_map(cells.div_ceil(32), |word| {
+            let mut bits = 0;
+            for bit in 0..32 {
+                let index = word * 32 + bit;
+                if index >= cells {
+                    break;
+                }
+                let index = index as u32;
+                let cell = Vector3::new(
+                    index % size[0],
+                    index / size[0] % size[1],
+                    index / (size[0] * size[1]),
+                );
+                let center = origin + cell.map(|c| c as f32 + 0.5) * cell_size;
+                if tree.contains(center) {
+                    bits |= 1 << bit;
+                }
+            }
+            bits
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    position: Vector3<f32>,
+    normal: Vector3<f32>,
+    tex_coord: Vector2<f32>,
+}
+
+impl Vertex {
+    fn lerp(&self, other: &Vertex, t: f32) -> Vertex {
+        Vertex {
+            position: self.position + (other.position - self.position) * t,
+            normal: self.normal + (other.normal - self.normal) * t,
+            tex_coord: self.tex_coord + (other.tex_coord - self.tex_coord) * t,
+        }
+    }
+}
+
+/// Plane of points `p` with `normal · p = w`
+#[derive(Debug, Clone, Copy)]
+struct Plane {
+    normal: Vector3<f32>,
+    w: f32,
+}
+
+/// Where a polygon lies relative to a plane
+enum Split {
+    CoplanarFront(Polygon),
+    CoplanarBack(Polygon),
+    Front(Polygon),
+    Back(Polygon),
+    Spanning {
+        front: Option<Polygon>,
+        back: Option<Polygon>,
+    },
+}
+
+const COPLANAR: u8 = 0;
+const FRONT: u8 = 1;
+const BACK: u8 = 2;
+const SPANNING: u8 = 3;
+
+impl Plane {
+    /// Plane through counter-clockwise points, unless they're collinear
+    fn from_points(a: Vector3<f32>, b: Vector3<f32>, c: Vector3<f32>) -> Option<Plane> {
+        let normal = (b - a).cross(c - a);
+        if normal.magnitude2() < 1e-14 {
+            return None;
+        }
+        let normal = normal.normalize();
+        Some(Plane {
+            normal,
+            w: normal.dot(a),
+        })
+    }
+
+    fn from_polygon(vertices: &[Vertex]) -> Option<Plane> {
+        Self::from_points(
+            vertices[0].position,
+            vertices[1].position,
+            vertices[2].position,
+        )
+    }
+
+    fn flipped(self) -> Plane {
+        Plane {
+            normal: -self.normal,
+            w: -self.w,
+        }
+    }
+
+    fn distance(&self, point: Vector3<f32>) -> f32 {
+        self.normal.dot(point) - self.w
+    }
+
+    fn split(&self, polygon: Polygon) -> Split {
+        let sides: Vec<u8> = polygon
+            .vertices
+            .iter()
+            .map(|v| {
+                let distance = self.distance(v.position);
+                if distance < -EPSILON {
+                    BACK
+                } else if distance > EPSILON {
+                    FRONT
+                } else {
+                    COPLANAR
+                }
+            })
+            .collect();
+
+        match sides.iter().fold(COPLANAR, |all, side| all | side) {
+            COPLANAR if self.normal.dot(polygon.plane.normal) > 0.0 => {
+                Split::CoplanarFront(polygon)
+            }
+            COPLANAR => Split::CoplanarBack(polygon),
+            FRONT => Split::Front(polygon),
+            BACK => Split::Back(polygon),
+            _ => {
+                let count = polygon.vertices.len();
+                let mut front = Vec::with_capacity(count + 1);
+                let mut back = Vec::with_capacity(count + 1);
+                for i in 0..count {
+                    let j = (i + 1) % count;
+                    let (vi, vj) = (&polygon.vertices[i], &polygon.vertices[j]);
+                    if sides[i] != BACK {
+                        front.push(*vi);
+                    }
+                    if sides[i] != FRONT {
+                        back.push(*vi);
+                    }
+                    if sides[i] | sides[j] == SPANNING {
+                        let t = (self.w - self.normal.dot(vi.position))
+                            / self.normal.dot(vj.position - vi.position);
+                        let crossing = vi.lerp(vj, t);
+                        front.push(crossing);
+                        back.push(crossing);
+                    }
+                }
+                let piece = |vertices: Vec<Vertex>| {
+                    (vertices.len() >= 3).then_some(Polygon {
+                        vertices,
+                        plane: polygon.plane,
+                    })
+                };
+                Split::Spanning {
+                    front: piece(front),
+                    back: piece(back),
+                }
+            }
+        }
+    }
+}
+
+/// Convex polygon with counter-clockwise vertices seen from outside
+#[derive(Debug, Clone)]
+struct Polygon {
+    vertices: Vec<Vertex>,
+    plane: Plane,
+}
+
+impl Polygon {
+    fn flip(&mut self) {
+        self.vertices.reverse();
+        for vertex in &mut self.vertices {
+            vertex.normal = -vertex.normal;
+        }
+        self.plane = self.plane.flipped();
+    }
+}
+
+/// BSP tree node; space behind a plane without a back child is solid
+#[derive(Debug, Default)]
+struct Node {
+    plane: Option<Plane>,
+    front: Option<Box<Node>>,
+    back: Option<Box<Node>>,
+    polygons: Vec<Polygon>,
+}
+
+impl Node {
+    fn new(polygons: Vec<Polygon>) -> Node {
+        let mut node = Node::default();
+        node.build(polygons);
+        node
+    }
+
+    /// Swap solid and empty space
+    fn invert(&mut self) {
+        for polygon in &mut self.polygons {
+            polygon.flip();
+        }
+        self.plane = self.plane.map(Plane::flipped);
+        if let Some(front) = &mut self.front {
+            front.invert();
+        }
+        if let Some(back) = &mut self.back {
+            back.invert();
+        }
+        std::mem::swap(&mut self.front, &mut self.back);
+    }
+
+    /// The parts of `polygons` outside this tree's solid
+    fn clip_polygons(&self, polygons: Vec<Polygon>) -> Vec<Polygon> {
+        let Some(plane) = self.plane else {
+            return polygons;
+        };
+        let mut front = Vec::new();
+        let mut back = Vec::new();
+        for polygon in polygons {
+            match plane.split(polygon) {
+                Split::CoplanarFront(polygon) | Split::Front(polygon) => front.push(polygon),
+                Split::CoplanarBack(polygon) | Split::Back(polygon) => back.push(polygon),
+                Split::Spanning { front: f, back: b } => {
+                    front.extend(f);
+                    back.extend(b);
+                }
+            }
+        }
+        let mut front = match &self.front {
+            Some(node) => node.clip_polygons(front),
+            None => front,
+        };
+        if let Some(node) = &self.back {
+            front.extend(node.clip_polygons(back));
+        }
+        front
+    }
+
+    /// Remove the parts of this tree's polygons inside `other`'s solid
+    fn clip_to(&mut self, other: &Node) {
+        self.polygons = other.clip_polygons(std::mem::take(&mut self.polygons));
+        if let Some(front) = &mut self.front {
+            front.clip_to(other);
+        }
+        if let Some(back) = &mut self.back {
+            back.clip_to(other);
+        }
+    }
+
+    fn all_polygons(&self) -> Vec<Polygon> {
+        let mut polygons = self.polygons.clone();
+        if let Some(front) = &self.front {
+            polygons.extend(front.all_polygons());
+        }
+        if let Some(back) = &self.back {
+            polygons.extend(back.all_polygons());
+        }
+        polygons
+    }
+
+    fn build(&mut self, polygons: Vec<Polygon>) {
+        let Some(first) = polygons.first() else {
+            return;
+        };
+        let plane = *self.plane.get_or_insert(first.plane);
+        let mut front = Vec::new();
+        let mut back = Vec::new();
+        for polygon in polygons {
+            match plane.split(polygon) {
+                Split::CoplanarFront(polygon) | Split::CoplanarBack(polygon) => {
+                    self.polygons.push(polygon)
+                }
+                Split::Front(polygon) => front.push(polygon),
+                Split::Back(polygon) => back.push(polygon),
+                Split::Spanning { front: f, back: b } => {
+                    front.extend(f);
+                    back.extend(b);
+                }
+            }
+        }
+        if !front.is_empty() {
+            self.front.get_or_insert_with(Default::default).build(front);
+        }
+        if !back.is_empty() {
+            self.back.get_or_insert_with(Default::default).build(back);
+        }
+    }
+
+    fn contains(&self, point: Vector3<f32>) -> bool {
+        let mut node = self;
+        loop {
+            let Some(plane) = node.plane else {
+                return false;
+            };
+            let next = if plane.distance(point) >= 0.0 {
+                node.front.as_deref().ok_or(false)
+            } else {
+                node.back.as_deref().ok_or(true)
+            };
+            match next {
+                Ok(child) => node = child,
+                Err(inside) => return inside,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gfx::geometry::{generate_cube, generate_cylinder, generate_sphere};
+    use std::f32::consts::PI;
+
+    /// Enclosed volume by the divergence theorem
+    fn volume(geometry: &GeometryData) -> f32 {
+        geometry
+            .indices
+            .chunks(3)
+            .map(|t| {
+                let [a, b, c] = [0, 1, 2].map(|k| Vector3::from(geometry.vertices[t[k] as usize]));
+                a.dot(b.cross(c)) / 6.0
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_boolean_operations() {
+        let cube = Csg::from_geometry(&generate_cube());
+        let shifted = cube.transformed(Matrix4::from_translation(Vector3::new(0.5, 0.0, 0.0)));
+
+        let union = cube.union(&shifted);
+        assert!((volume(&union.to_geometry()) - 1.5).abs() < 1e-4);
+        assert!(union.contains(Vector3::new(0.9, 0.0, 0.0)));
+
+        let intersection = cube.intersect(&shifted);
+        assert!((volume(&intersection.to_geometry()) - 0.5).abs() < 1e-4);
+        assert!(!intersection.contains(Vector3::new(-0.2, 0.0, 0.0)));
+
+        // A bore through the cube along z
+        let bore = Csg::from_geometry(&generate_cylinder(0.25, 2.0, 32));
+        let bored = cube.subtract(&bore);
+        let bore_area = 0.5 * 32.0 * 0.25 * 0.25 * (2.0 * PI / 32.0).sin();
+        assert!((volume(&bored.to_geometry()) - (1.0 - bore_area)).abs() < 1e-4);
+        assert!(!bored.contains(Vector3::new(0.0, 0.1, 0.3)));
+        assert!(bored.contains(Vector3::new(0.4, 0.4, 0.0)));
+
+        // Sphere minus cylinder keeps the sphere's smooth normals
+        let sphere = Csg::from_geometry(&generate_sphere(16, 8));
+        let drilled = sphere.subtract(&bore).to_geometry();
+        assert!(drilled.triangle_count() > 0);
+        assert!(volume(&drilled) > 0.0);
+    }
+
+    #[test]
+    fn test_voxelize_matches_obstacle_layout() {
+        let cube = Csg::from_geometry(&generate_cube());
+        // Four cells across, the cube covering the middle two in x and y
+        let bits = cube.voxelize([4, 4, 1], Vector3::new(-1.0, -1.0, -0.25), 0.5);
+        assert_eq!(bits.len(), 1);
+        let solid: Vec<usize> = (0..16).filter(|i| bits[0] & (1 << i) != 0).collect();
+        assert_eq!(solid, vec![5, 6, 9, 10]);
+    }
+}
@@ -0,0 +1,499 @@
+//! # Curves and Extruded Meshes
+//!
+//! Smooth curves through or near control points, arc-length lookups along
+//! sampled curves, and meshes extruded along them: round tubes for
+//! streamlines and pipes, flat ribbons for trails. Camera paths, trails and
+//! streamline tubes share these instead of each sampling curves their own
+//! way.
+//!
+//! Extrusion follows parallel-transport frames, so a tube's seam and a
+//! ribbon's face twist no more than the path itself does.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use cgmath::Vector3;
+//! use haggis::gfx::geometry::curves::{generate_tube, sample_catmull_rom, ArcLengthTable};
+//!
+//! let controls = [
+//!     Vector3::new(0.0, 0.0, 0.0),
+//!     Vector3::new(1.0, 1.0, 0.0),
+//!     Vector3::new(2.0, 0.0, 1.0),
+//! ];
+//! let path = sample_catmull_rom(&controls, 8);
+//!
+//! // Evenly spaced points, e.g. for a camera moving at constant speed
+//! let table = ArcLengthTable::new(path.clone());
+//! let halfway = table.point_at(table.length() * 0.5);
+//!
+//! let tube = generate_tube(&path, 0.05, 12);
+//! assert!(tube.triangle_count() > 0);
+//! ```
+
+use cgmath::{InnerSpace, Vector3};
+use std::f32::consts::PI;
+
+use super::GeometryData;
+
+/// Point at `t` in 0 to 1 on the uniform Catmull-Rom segment from `p1` to
+/// `p2`, with `p0` and `p3` shaping the tangents
+pub fn catmull_rom(
+    p0: Vector3<f32>,
+    p1: Vector3<f32>,
+    p2: Vector3<f32>,
+    p3: Vector3<f32>,
+    t: f32,
+) -> Vector3<f32> {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    (p1 * 2.0
+        + (p2 - p0) * t
+        + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2
+        + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3)
+        * 0.5
+}
+
+/// Point at `t` in 0 to 1 on the cubic Bezier curve from `p0` to `p3`
+pub fn cubic_bezier(
+    p0: Vector3<f32>,
+    p1: Vector3<f32>,
+    p2: Vector3<f32>,
+    p3: Vector3<f32>,
+    t: f32,
+) -> Vector3<f32> {
+    let s = 1.0 - t;
+    p0 * (s * s * s) + p1 * (3.0 * s * s * t) + p2 * (3.0 * s * t * t) + p3 * (t * t * t)
+}
+
+/// Sample a Catmull-Rom spline passing through every control point
+///
+/// Each span between neighbouring controls gets `samples_per_segment`
+/// points; the end spans mirror their outer control so the curve starts and
+/// ends at the first and last controls.
+pub fn sample_catmull_rom(
+    controls: &[Vector3<f32>],
+    samples_per_segment: u32,
+) -> Vec<Vector3<f32>> {
+    if controls.len() < 2 {
+        return controls.to_vec();
+    }
+    let samples = samples_per_segment.max(1);
+    let last = controls.len() - 1;
+    let mut points = Vec::with_capacity(last * samples as usize + 1);
+    for i in 0..last {
+        let p1 = controls[i];
+        let p2 = controls[i + 1];
+        let p0 = if i > 0 {
+            controls[i - 1]
+        } else {
+            p1 * 2.0 - p2
+        };
+        let p3 = if i + 1 < last {
+            controls[i + 2]
+        } else {
+            p2 * 2.0 - p1
+        };
+        for s in 0..samples {
+            points.push(catmull_rom(p0, p1, p2, p3, s as f32 / samples as f32));
+        }
+    }
+    points.push(controls[last]);
+    points
+}
+
+/// Sample a cubic Bezier curve at `samples + 1` evenly spaced parameters
+pub fn sample_cubic_bezier(
+    p0: Vector3<f32>,
+    p1: Vector3<f32>,
+    p2: Vector3<f32>,
+    p3: Vector3<f32>,
+    samples: u32,
+) -> Vec<Vector3<f32>> {
+    let samples = samples.max(1);
+    (0..=samples)
+        .map(|s| cubic_bezier(p0, p1, p2, p3, s as f32 / samples as f32))
+        .collect()
+}
+
+/// Cumulative distances along a polyline, for looking up points by the
+/// distance travelled rather than by curve parameter
+#[derive(Debug, Clone)]
+pub struct ArcLengthTable {
+    points: Vec<Vector3<f32>>,
+    distances: Vec<f32>,
+}
+
+impl ArcLengthTable {
+    pub fn new(points: Vec<Vector3<f32>>) -> Self {
+        let mut distances = Vec::with_capacity(points.len());
+        let mut total = 0.0;
+        for (i, point) in points.iter().enumerate() {
+            if i > 0 {
+                total += (point - points[i - 1]).magnitude();
+            }
+            distances.push(total);
+        }
+        Self { points, distances }
+    }
+
+    pub fn points(&self) -> &[Vector3<f32>] {
+        &self.points
+    }
+
+    /// Distance from the first point to each point
+    pub fn distances(&self) -> &[f32] {
+        &self.distances
+    }
+
+    pub fn length(&self) -> f32 {
+        self.distances.last().copied().unwrap_or(0.0)
+    }
+
+    /// Point `distance` along the polyline, clamped to its ends
+    pub fn point_at(&self, distance: f32) -> Vector3<f32> {
+        match self.points.len() {
+            0 => Vector3::new(0.0, 0.0, 0.0),
+            1 => self.points[0],
+            _ => {
+                let (i, t) = self.locate(distance);
+                self.points[i] + (self.points[i + 1] - self.points[i]) * t
+            }
+        }
+    }
+
+    /// Unit direction of travel `distance` along the polyline
+    pub fn tangent_at(&self, distance: f32) -> Vector3<f32> {
+        if self.points.len() < 2 {
+            return Vector3::unit_x();
+        }
+        let (i, _) = self.locate(distance);
+        let step = self.points[i + 1] - self.points[i];
+        if step.magnitude2() > 0.0 {
+            step.normalize()
+        } else {
+            Vector3::unit_x()
+        }
+    }
+
+    /// `count` points spaced evenly by distance from the first point to the
+    /// last
+    pub fn resample(&self, count: usize) -> Vec<Vector3<f32>> {
+        if count < 2 {
+            return self
+                .points
+                .first()
+                .copied()
+                .into_iter()
+                .take(count)
+                .collect();
+        }
+        let length = self.length();
+        (0..count)
+            .map(|i| self.point_at(length * i as f32 / (count - 1) as f32))
+            .collect()
+    }
+
+    /// Segment containing `distance` and the fraction along it
+    fn locate(&self, distance: f32) -> (usize, f32) {
+        let distance = distance.clamp(0.0, self.length());
+        let last_segment = self.points.len() - 2;
+        let i = self
+            .distances
+            .partition_point(|&d| d <= distance)
+            .saturating_sub(1)
+            .min(last_segment);
+        let span = self.distances[i + 1] - self.distances[i];
+        let t = if span > 0.0 {
+            (distance - self.distances[i]) / span
+        } else {
+            0.0
+        };
+        (i, t)
+    }
+}
+
+/// Generate a round tube of `radius` along `path`
+///
+/// # Arguments
+/// * `path` - Points along the tube's center line
+/// * `radius` - Radius of the tube
+/// * `sides` - Number of segments around the tube
+///
+/// Both ends are closed with flat caps. U runs from 0 to 1 along the path by
+/// distance, V around the tube. Paths with fewer than two distinct points
+/// give empty geometry.
+pub fn generate_tube(path: &[Vector3<f32>], radius: f32, sides: u32) -> GeometryData {
+    let mut data = GeometryData::new();
+    let Some(frames) = transport_frames(path, None) else {
+        return data;
+    };
+
+    let sides = sides.max(3);
+    let ring = sides + 1;
+    for frame in &frames {
+        let binormal = frame.tangent.cross(frame.normal);
+        for j in 0..=sides {
+            let angle = j as f32 * 2.0 * PI / sides as f32;
+            let normal = frame.normal * angle.cos() + binormal * angle.sin();
+            data.vertices
+                .push((frame.position + normal * radius).into());
+            data.normals.push(normal.into());
+            data.tex_coords.push([frame.u, j as f32 / sides as f32]);
+        }
+    }
+
+    for i in 0..frames.len() as u32 - 1 {
+        for j in 0..sides {
+            let current = i * ring + j;
+            let next = current + ring;
+
+            data.indices.push(current);
+            data.indices.push(current + 1);
+            data.indices.push(next);
+
+            data.indices.push(current + 1);
+            data.indices.push(next + 1);
+            data.indices.push(next);
+        }
+    }
+
+    // Caps get their own vertices for flat normals
+    for (frame, outward) in [(&frames[0], -1.0), (&frames[frames.len() - 1], 1.0)] {
+        let normal = frame.tangent * outward;
+        let binormal = frame.tangent.cross(frame.normal);
+        let center = data.vertices.len() as u32;
+        data.vertices.push(frame.position.into());
+        data.normals.push(normal.into());
+        data.tex_coords.push([0.5, 0.5]);
+        for j in 0..sides {
+            let angle = j as f32 * 2.0 * PI / sides as f32;
+            let (sin_a, cos_a) = angle.sin_cos();
+            let offset = frame.normal * cos_a + binormal * sin_a;
+            data.vertices
+                .push((frame.position + offset * radius).into());
+            data.normals.push(normal.into());
+            data.tex_coords.push([0.5 + 0.5 * cos_a, 0.5 + 0.5 * sin_a]);
+        }
+        for j in 0..sides {
+            let current = center + 1 + j;
+            let next = center + 1 + (j + 1) % sides;
+            data.indices.push(center);
+            if outward < 0.0 {
+                data.indices.push(next);
+                data.indices.push(current);
+            } else {
+                data.indices.push(current);
+                data.indices.push(next);
+            }
+        }
+    }
+
+    data
+}
+
+/// Generate a flat ribbon of `width` along `path`
+///
+/// # Arguments
+/// * `path` - Points along the ribbon's center line
+/// * `width` - Width of the ribbon across the path
+/// * `up` - Direction the ribbon faces at the start of the path
+///
+/// The ribbon keeps facing `up` as far as the path allows, twisting only as
+/// much as the path turns. U runs from 0 to 1 along the path by distance, V
+/// across the ribbon. Paths with fewer than two distinct points give empty
+/// geometry.
+pub fn generate_ribbon(path: &[Vector3<f32>], width: f32, up: Vector3<f32>) -> GeometryData {
+    let mut data = GeometryData::new();
+    let Some(frames) = transport_frames(path, Some(up)) else {
+        return data;
+    };
+
+    let half_width = width * 0.5;
+    for frame in &frames {
+        let side = frame.tangent.cross(frame.normal);
+        for (offset, v) in [(-half_width, 0.0), (half_width, 1.0)] {
+            data.vertices.push((frame.position + side * offset).into());
+            data.normals.push(frame.normal.into());
+            data.tex_coords.push([frame.u, v]);
+        }
+    }
+
+    for i in 0..frames.len() as u32 - 1 {
+        let left = i * 2;
+        let right = left + 1;
+
+        data.indices.push(left);
+        data.indices.push(right);
+        data.indices.push(left + 2);
+
+        data.indices.push(right);
+        data.indices.push(right + 2);
+        data.indices.push(left + 2);
+    }
+
+    data
+}
+
+/// Position and orientation at one point of an extruded path
+struct Frame {
+    position: Vector3<f32>,
+    tangent: Vector3<f32>,
+    normal: Vector3<f32>,
+    /// Fraction of the path's length up to this point
+    u: f32,
+}
+
+/// Parallel-transport frames along `path` by the double reflection method,
+/// starting from `up` (or any perpendicular) made perpendicular to the path
+fn transport_frames(path: &[Vector3<f32>], up: Option<Vector3<f32>>) -> Option<Vec<Frame>> {
+    // Repeated points have no direction
+    let mut points: Vec<Vector3<f32>> = Vec::with_capacity(path.len());
+    for &point in path {
+        if points
+            .last()
+            .is_none_or(|last| (point - last).magnitude2() > 1e-12)
+        {
+            points.push(point);
+        }
+    }
+    if points.len() < 2 {
+        return None;
+    }
+
+    let last = points.len() - 1;
+    let tangents: Vec<Vector3<f32>> = (0..points.len())
+        .map(|i| (points[(i + 1).min(last)] - points[i.saturating_sub(1)]).normalize())
+        .collect();
+    let table = ArcLengthTable::new(points);
+    let length = table.length();
+
+    let first = tangents[0];
+    let mut normal = perpendicular(first, up.unwrap_or_else(|| any_perpendicular(first)));
+    let mut frames = Vec::with_capacity(tangents.len());
+    for (i, &tangent) in tangents.iter().enumerate() {
+        if i > 0 {
+            let step = table.points()[i] - table.points()[i - 1];
+            let reflected = reflect(normal, step);
+            let reflected_tangent = reflect(tangents[i - 1], step);
+            normal = perpendicular(tangent, reflect(reflected, tangent - reflected_tangent));
+        }
+        frames.push(Frame {
+            position: table.points()[i],
+            tangent,
+            normal,
+            u: table.distances()[i] / length,
+        });
+    }
+    Some(frames)
+}
+
+/// Reflect `v` in the plane perpendicular to `axis`
+fn reflect(v: Vector3<f32>, axis: Vector3<f32>) -> Vector3<f32> {
+    let length2 = axis.magnitude2();
+    if length2 < 1e-12 {
+        return v;
+    }
+    v - axis * (2.0 * v.dot(axis) / length2)
+}
+
+/// `v` with its component along unit `tangent` removed, normalized, or any
+/// perpendicular when `v` is parallel to it
+fn perpendicular(tangent: Vector3<f32>, v: Vector3<f32>) -> Vector3<f32> {
+    let projected = v - tangent * v.dot(tangent);
+    if projected.magnitude2() > 1e-8 {
+        projected.normalize()
+    } else {
+        any_perpendicular(tangent)
+    }
+}
+
+fn any_perpendicular(tangent: Vector3<f32>) -> Vector3<f32> {
+    let axis = if tangent.z.abs() < 0.9 {
+        Vector3::unit_z()
+    } else {
+        Vector3::unit_x()
+    };
+    tangent.cross(axis).normalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_splines_and_arc_length() {
+        let controls = [
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 2.0, 0.0),
+            Vector3::new(3.0, 1.0, 1.0),
+            Vector3::new(4.0, 0.0, 0.0),
+        ];
+        // Catmull-Rom passes through every control point
+        let path = sample_catmull_rom(&controls, 10);
+        assert_eq!(path.len(), 31);
+        for (i, control) in controls.iter().enumerate() {
+            assert!((path[i * 10] - control).magnitude() < 1e-5);
+        }
+
+        // Bezier touches only its end points
+        let bezier = sample_cubic_bezier(controls[0], controls[1], controls[2], controls[3], 4);
+        assert_eq!(bezier[0], controls[0]);
+        assert_eq!(bezier[4], controls[3]);
+        assert!((bezier[2] - Vector3::new(2.0, 1.125, 0.375)).magnitude() < 1e-6);
+
+        let table = ArcLengthTable::new(vec![
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(3.0, 0.0, 0.0),
+            Vector3::new(3.0, 1.0, 0.0),
+        ]);
+        assert_eq!(table.length(), 4.0);
+        assert_eq!(table.point_at(3.5), Vector3::new(3.0, 0.5, 0.0));
+        assert_eq!(table.point_at(10.0), Vector3::new(3.0, 1.0, 0.0));
+        assert_eq!(table.tangent_at(3.5), Vector3::unit_y());
+        let even = table.resample(5);
+        assert_eq!(even[1], Vector3::new(1.0, 0.0, 0.0));
+        assert_eq!(even[3], Vector3::new(3.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_extruded_meshes_face_outward() {
+        let mut path = sample_catmull_rom(
+            &[
+                Vector3::new(0.0, 0.0, 0.0),
+                Vector3::new(1.0, 1.0, 0.0),
+                Vector3::new(2.0, 0.0, 1.0),
+                Vector3::new(3.0, -1.0, 3.0),
+            ],
+            6,
+        );
+        // Repeated points are skipped
+        path.insert(6, path[6]);
+        let tube = generate_tube(&path, 0.1, 8);
+        let ribbon = generate_ribbon(&path, 0.2, Vector3::unit_z());
+
+        for mesh in [&tube, &ribbon] {
+            assert_eq!(mesh.vertices.len(), mesh.normals.len());
+            assert_eq!(mesh.vertices.len(), mesh.tex_coords.len());
+            assert!(mesh.triangle_count() > 0);
+            // Counter-clockwise winding agrees with the vertex normals
+            for triangle in mesh.indices.chunks(3) {
+                let [a, b, c] =
+                    [0, 1, 2].map(|k| Vector3::from(mesh.vertices[triangle[k] as usize]));
+                let face = (b - a).cross(c - a);
+                let normal = Vector3::from(mesh.normals[triangle[0] as usize]);
+                assert!(face.dot(normal) > 0.0);
+            }
+        }
+
+        // The ribbon starts out facing up and keeps its width
+        assert!((Vector3::from(ribbon.normals[0]) - Vector3::unit_z()).magnitude() < 0.5);
+        let end = ribbon.vertices.len() - 2;
+        let across = Vector3::from(ribbon.vertices[end + 1]) - Vector3::from(ribbon.vertices[end]);
+        assert!((across.magnitude() - 0.2).abs() < 1e-5);
+
+        assert_eq!(
+            generate_tube(&[Vector3::unit_x(); 3], 0.1, 8).vertex_count(),
+            0
+        );
+    }
+}
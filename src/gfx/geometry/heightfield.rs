@@ -0,0 +1,187 @@
+//! Height fields
+//!
+//! A [`HeightField`] is a regular grid of heights over the XY plane, turned
+//! into a mesh whose vertices can be rewritten every frame. Simulations that
+//! produce a height per grid node (shallow water, terrain erosion, wave
+//! equations) create the scene object once from [`HeightField::geometry`] and
+//! then hand [`HeightField::vertices`] to [`Mesh::set_vertices`].
+//!
+//! Normals come from central differences of the heights instead of the
+//! triangles, which is cheaper than [`Mesh::set_positions`] on large grids
+//! and gives smooth shading across the diagonal of every quad.
+//!
+//! ## Usage
+//!
+//! ```
+//! use haggis::gfx::geometry::heightfield::HeightField;
+//!
+//! let field = HeightField::new([3, 2], [2.0, 1.0]).at([0.0, 0.0, 0.5]);
+//! let heights = [0.0, 0.1, 0.0, 0.0, 0.2, 0.0];
+//! let geometry = field.geometry(&heights);
+//! assert_eq!(geometry.vertex_count(), 6);
+//! assert_eq!(geometry.triangle_count(), 4);
+//! ```
+//!
+//! [`Mesh::set_vertices`]: crate::gfx::scene::object::Mesh::set_vertices
+//! [`Mesh::set_positions`]: crate::gfx::scene::object::Mesh::set_positions
+
+use super::GeometryData;
+use crate::gfx::scene::vertex::Vertex3D;
+
+/// Regular grid of heights over a rectangle of the XY plane
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeightField {
+    dims: [u32; 2],
+    size: [f32; 2],
+    origin: [f32; 3],
+}
+
+impl HeightField {
+    /// `dims` nodes spread over a `size` rectangle centered on the origin
+    ///
+    /// # Panics
+    ///
+    /// If either side has fewer than two nodes
+    pub fn new(dims: [u32; 2], size: [f32; 2]) -> Self {
+        assert!(
+            dims[0] >= 2 && dims[1] >= 2,
+            "height field needs two nodes per side"
+        );
+        Self {
+            dims,
+            size,
+            origin: [0.0; 3],
+        }
+    }
+
+    /// Center the rectangle on `origin`; heights are added to its Z
+    pub fn at(mut self, origin: [f32; 3]) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    pub fn dims(&self) -> [u32; 2] {
+        self.dims
+    }
+
+    pub fn size(&self) -> [f32; 2] {
+        self.size
+    }
+
+    pub fn origin(&self) -> [f32; 3] {
+        self.origin
+    }
+
+    /// Number of nodes, and of heights expected by the other methods
+    pub fn len(&self) -> usize {
+        self.dims[0] as usize * self.dims[1] as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Distance between neighboring nodes along X and Y
+    pub fn spacing(&self) -> [f32; 2] {
+        [
+            self.size[0] / (self.dims[0] - 1) as f32,
+            self.size[1] / (self.dims[1] - 1) as f32,
+        ]
+    }
+
+    /// World position of node `(column, row)` at `height`
+    pub fn position(&self, column: u32, row: u32, height: f32) -> [f32; 3] {
+        let [dx, dy] = self.spacing();
+        [
+            self.origin[0] - 0.5 * self.size[0] + column as f32 * dx,
+            self.origin[1] - 0.5 * self.size[1] + row as f32 * dy,
+            self.origin[2] + height,
+        ]
+    }
+
+    /// Vertices for `heights`, given row by row from the -Y edge
+    ///
+    /// # Panics
+    ///
+    /// If `heights` doesn't have one entry per node
+    pub fn vertices(&self, heights: &[f32]) -> Vec<Vertex3D> {
+        assert_eq!(heights.len(), self.len(), "one height per node");
+        let [columns, rows] = self.dims;
+        let [dx, dy] = self.spacing();
+        let height = |column: u32, row: u32| heights[(row * columns + column) as usize];
+
+        let mut vertices = Vec::with_capacity(heights.len());
+        for row in 0..rows {
+            for column in 0..columns {
+                // One-sided differences at the edges
+                let (left, right) = (column.saturating_sub(1), (column + 1).min(columns - 1));
+                let (below, above) = (row.saturating_sub(1), (row + 1).min(rows - 1));
+                let slope_x =
+                    (height(right, row) - height(left, row)) / ((right - left) as f32 * dx);
+                let slope_y =
+                    (height(column, above) - height(column, below)) / ((above - below) as f32 * dy);
+                let length = (slope_x * slope_x + slope_y * slope_y + 1.0).sqrt();
+                vertices.push(Vertex3D {
+                    position: self.position(column, row, height(column, row)),
+                    normal: [-slope_x / length, -slope_y / length, 1.0 / length],
+                });
+            }
+        }
+        vertices
+    }
+
+    /// Mesh for `heights` with texture coordinates over the rectangle and
+    /// triangles facing +Z
+    pub fn geometry(&self, heights: &[f32]) -> GeometryData {
+        let [columns, rows] = self.dims;
+        let mut geometry = GeometryData::new();
+        for (i, vertex) in self.vertices(heights).into_iter().enumerate() {
+            let i = i as u32;
+            geometry.vertices.push(vertex.position);
+            geometry.normals.push(vertex.normal);
+            geometry.tex_coords.push([
+                (i % columns) as f32 / (columns - 1) as f32,
+                (i / columns) as f32 / (rows - 1) as f32,
+            ]);
+        }
+        for row in 0..rows - 1 {
+            for column in 0..columns - 1 {
+                let i = row * columns + column;
+                let above = i + columns;
+                geometry
+                    .indices
+                    .extend_from_slice(&[i, i + 1, above, above, i + 1, above + 1]);
+            }
+        }
+        geometry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slope_normals_and_winding() {
+        // Rising by 0.5 per unit along X
+        let field = HeightField::new([3, 3], [2.0, 2.0]);
+        let heights: Vec<f32> = (0..9).map(|i| (i % 3) as f32 * 0.5).collect();
+        let geometry = field.geometry(&heights);
+
+        let expected = [-0.5, 0.0, 1.0].map(|n: f32| n / 1.25f32.sqrt());
+        for normal in &geometry.normals {
+            for (n, e) in normal.iter().zip(expected) {
+                assert!((n - e).abs() < 1e-6);
+            }
+        }
+        assert_eq!(geometry.vertices[8], [1.0, 1.0, 1.0]);
+
+        // Flat triangles face up
+        let flat = field.geometry(&[0.0; 9]);
+        for triangle in flat.indices.chunks(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| flat.vertices[triangle[k] as usize]);
+            let cross_z = (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]);
+            assert!(cross_z > 0.0);
+        }
+    }
+}
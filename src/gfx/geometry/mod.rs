@@ -9,6 +9,15 @@
 //! - **Sphere**: UV sphere with configurable resolution
 //! - **Plane**: Flat plane with configurable size and subdivisions
 //!
+//! The [`curves`] module samples splines and extrudes tubes and ribbons along
+//! them, and [`csg`] combines closed shapes with boolean operations.
+//! [`heightfield`] builds meshes over grids of heights that a simulation
+//! rewrites every frame.
+//! [`compute_smooth_normals`], [`compute_flat_normals`] and
+//! [`compute_tangents`] regenerate shading data for any mesh, and
+//! [`generate_box_uvs`] and [`generate_spherical_uvs`] give it texture
+//! coordinates.
+//!
 //! ## Usage
 //!
 //! ```rust
@@ -24,9 +33,16 @@
 //! let plane_data = generate_plane(10.0, 10.0, 4, 4);
 //! ```
 
+pub mod csg;
+pub mod curves;
+pub mod heightfield;
+pub mod normals;
 pub mod primitives;
+pub mod uv;
 
+pub use normals::*;
 pub use primitives::*;
+pub use uv::*;
 
 /// Represents generated geometry data ready for GPU upload
 #[derive(Debug, Clone)]
@@ -37,6 +53,9 @@ pub struct GeometryData {
     pub tex_coords: Vec<[f32; 2]>,
     /// Normal vectors (x, y, z)
     pub normals: Vec<[f32; 3]>,
+    /// Tangents (x, y, z) with the bitangent's sign in w, empty until
+    /// [`compute_tangents`] fills them
+    pub tangents: Vec<[f32; 4]>,
     /// Triangle indices (counter-clockwise winding)
     pub indices: Vec<u32>,
 }
@@ -48,6 +67,7 @@ impl GeometryData {
             vertices: Vec::new(),
             tex_coords: Vec::new(),
             normals: Vec::new(),
+            tangents: Vec::new(),
             indices: Vec::new(),
         }
     }
@@ -0,0 +1,240 @@
+//! # Normal and Tangent Generation
+//!
+//! Vertex normals for meshes that come without them, with smoothing groups
+//! decided by the angle between neighbouring faces, and tangents for normal
+//! mapping.
+//!
+//! Faces face the way of their counter-clockwise winding, or of the mesh's
+//! existing vertex normals where it has them, so regenerating the normals of
+//! a mesh never turns it inside out.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use cgmath::Deg;
+//! use haggis::gfx::geometry::{compute_smooth_normals, compute_tangents, generate_cylinder};
+//!
+//! let mut mesh = generate_cylinder(0.5, 2.0, 24);
+//! // Smooth around the side, sharp where it meets the caps
+//! compute_smooth_normals(&mut mesh, Deg(30.0));
+//! compute_tangents(&mut mesh);
+//! ```
+
+use std::collections::HashMap;
+
+use cgmath::{Angle, Deg, InnerSpace, Vector2, Vector3};
+
+use super::GeometryData;
+
+/// Crease angle used for imported meshes without normals
+pub const DEFAULT_SMOOTHING_ANGLE: Deg<f32> = Deg(60.0);
+
+/// Replace the normals with averages of the faces around each vertex,
+/// weighted by the faces' angles at the vertex
+///
+/// Faces meeting at less than `angle_threshold` are smoothed together;
+/// steeper edges stay sharp, which splits the vertices along them. Vertices
+/// at the same position are treated as one, so meshes split at texture seams
+/// still shade smoothly across them. Tangents are cleared.
+pub fn compute_smooth_normals(mesh: &mut GeometryData, angle_threshold: Deg<f32>) {
+    let faces: Vec<Vector3<f32>> = face_normals(mesh).into_iter().map(unit).collect();
+    let min_cos = angle_threshold.cos();
+
+    // Faces around each distinct position
+    let mut positions: HashMap<[u32; 3], usize> = HashMap::new();
+    let welded: Vec<usize> = mesh
+        .vertices
+        .iter()
+        .map(|p| {
+            let next = positions.len();
+            *positions.entry(p.map(f32::to_bits)).or_insert(next)
+        })
+        .collect();
+    let mut faces_at = vec![Vec::new(); positions.len()];
+    for (face, triangle) in mesh.indices.chunks_exact(3).enumerate() {
+        let corners = [0, 1, 2].map(|k| Vector3::from(mesh.vertices[triangle[k] as usize]));
+        for (k, &index) in triangle.iter().enumerate() {
+            let (a, b) = (corners[(k + 1) % 3], corners[(k + 2) % 3]);
+            let angle = unit(a - corners[k])
+                .dot(unit(b - corners[k]))
+                .clamp(-1.0, 1.0)
+                .acos();
+            faces_at[welded[index as usize]].push((face, angle));
+        }
+    }
+
+    let mut rebuilt = GeometryData::new();
+    let mut corners: HashMap<(u32, [u32; 3]), u32> = HashMap::new();
+    for (face, triangle) in mesh.indices.chunks_exact(3).enumerate() {
+        for &index in triangle {
+            let normal: Vector3<f32> = faces_at[welded[index as usize]]
+                .iter()
+                .filter(|&&(other, _)| other == face || faces[other].dot(faces[face]) >= min_cos)
+                .map(|&(other, angle)| faces[other] * angle)
+                .sum();
+            let normal: [f32; 3] = unit(normal).into();
+            let vertex = *corners
+                .entry((index, normal.map(f32::to_bits)))
+                .or_insert_with(|| {
+                    push_vertex(&mut rebuilt, mesh, index as usize, normal);
+                    rebuilt.vertices.len() as u32 - 1
+                });
+            rebuilt.indices.push(vertex);
+        }
+    }
+    *mesh = rebuilt;
+}
+
+/// Give every triangle its own vertices with the face's normal
+///
+/// Tangents are cleared.
+pub fn compute_flat_normals(mesh: &mut GeometryData) {
+    let faces = face_normals(mesh);
+    let mut rebuilt = GeometryData::new();
+    for (face, triangle) in mesh.indices.chunks_exact(3).enumerate() {
+        let normal = unit(faces[face]).into();
+        for &index in triangle {
+            push_vertex(&mut rebuilt, mesh, index as usize, normal);
+            rebuilt.indices.push(rebuilt.vertices.len() as u32 - 1);
+        }
+    }
+    *mesh = rebuilt;
+}
+
+/// Fill [`GeometryData::tangents`] from the normals and texture coordinates
+///
+/// Tangents point along increasing U, perpendicular to the normal, with the
+/// bitangent's sign in `w` for mirrored UVs. Vertices without usable texture
+/// coordinates get an arbitrary tangent perpendicular to the normal.
+pub fn compute_tangents(mesh: &mut GeometryData) {
+    let count = mesh.vertices.len();
+    let mut along_u = vec![Vector3::new(0.0, 0.0, 0.0); count];
+    let mut along_v = vec![Vector3::new(0.0, 0.0, 0.0); count];
+    if mesh.tex_coords.len() == count {
+        for triangle in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| triangle[k] as usize);
+            let position = |i: usize| Vector3::from(mesh.vertices[i]);
+            let uv = |i: usize| Vector2::from(mesh.tex_coords[i]);
+            let (edge1, edge2) = (position(b) - position(a), position(c) - position(a));
+            let (duv1, duv2) = (uv(b) - uv(a), uv(c) - uv(a));
+            let determinant = duv1.x * duv2.y - duv2.x * duv1.y;
+            if determinant.abs() < 1e-12 {
+                continue;
+            }
+            let u = (edge1 * duv2.y - edge2 * duv1.y) / determinant;
+            let v = (edge2 * duv1.x - edge1 * duv2.x) / determinant;
+            for i in [a, b, c] {
+                along_u[i] += u;
+                along_v[i] += v;
+            }
+        }
+    }
+
+    mesh.tangents = (0..count)
+        .map(|i| {
+            let normal = Vector3::from(mesh.normals.get(i).copied().unwrap_or([0.0, 0.0, 1.0]));
+            let mut tangent = along_u[i] - normal * normal.dot(along_u[i]);
+            if tangent.magnitude2() < 1e-12 {
+                let axis = if normal.x.abs() < 0.9 {
+                    Vector3::unit_x()
+                } else {
+                    Vector3::unit_y()
+                };
+                tangent = axis - normal * normal.dot(axis);
+            }
+            let tangent = tangent.normalize();
+            let handedness = if normal.cross(tangent).dot(along_v[i]) < 0.0 {
+                -1.0
+            } else {
+                1.0
+            };
+            tangent.extend(handedness).into()
+        })
+        .collect();
+}
+
+/// Normal of each triangle, as long as twice its area, facing along the vertex normals
+/// where the mesh has them
+fn face_normals(mesh: &GeometryData) -> Vec<Vector3<f32>> {
+    let has_normals = mesh.normals.len() == mesh.vertices.len();
+    mesh.indices
+        .chunks_exact(3)
+        .map(|triangle| {
+            let [a, b, c] = [0, 1, 2].map(|k| Vector3::from(mesh.vertices[triangle[k] as usize]));
+            let normal = (b - a).cross(c - a);
+            if has_normals {
+                let facing: Vector3<f32> = triangle
+                    .iter()
+                    .map(|&i| Vector3::from(mesh.normals[i as usize]))
+                    .sum();
+                if facing.dot(normal) < 0.0 {
+                    return -normal;
+                }
+            }
+            normal
+        })
+        .collect()
+}
+
+/// `v` normalized, or zero for degenerate triangles
+fn unit(v: Vector3<f32>) -> Vector3<f32> {
+    if v.magnitude2() > 1e-20 {
+        v.normalize()
+    } else {
+        v * 0.0
+    }
+}
+
+/// Append vertex `index` of `source` to `target` with a new normal
+fn push_vertex(target: &mut GeometryData, source: &GeometryData, index: usize, normal: [f32; 3]) {
+    target.vertices.push(source.vertices[index]);
+    target.normals.push(normal);
+    if let Some(&tex_coord) = source.tex_coords.get(index) {
+        target.tex_coords.push(tex_coord);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gfx::geometry::{generate_cube, generate_plane, generate_sphere};
+
+    #[test]
+    fn test_smoothing_angle_decides_creases() {
+        // The cube's faces meet at 90 degrees: sharp below that, rounded above
+        let mut sharp = generate_cube();
+        compute_smooth_normals(&mut sharp, Deg(60.0));
+        assert_eq!(sharp.vertex_count(), 24);
+        assert_eq!(sharp.normals, generate_cube().normals);
+
+        let mut rounded = generate_cube();
+        compute_smooth_normals(&mut rounded, Deg(100.0));
+        assert_eq!(rounded.vertex_count(), 24);
+        for (position, normal) in rounded.vertices.iter().zip(&rounded.normals) {
+            let expected = Vector3::from(*position).normalize();
+            assert!((Vector3::from(*normal) - expected).magnitude() < 1e-5);
+        }
+
+        // Flat shading unwelds, and regenerated normals keep facing out
+        let mut faceted = generate_sphere(8, 6);
+        compute_flat_normals(&mut faceted);
+        assert_eq!(faceted.vertex_count(), faceted.indices.len());
+        for (position, normal) in faceted.vertices.iter().zip(&faceted.normals) {
+            // Skipping the degenerate triangles at the poles
+            let normal = Vector3::from(*normal);
+            assert!(
+                normal == Vector3::new(0.0, 0.0, 0.0) || Vector3::from(*position).dot(normal) > 0.0
+            );
+        }
+    }
+
+    #[test]
+    fn test_tangents_follow_u() {
+        let mut plane = generate_plane(2.0, 2.0, 2, 2);
+        compute_tangents(&mut plane);
+        assert_eq!(plane.tangents.len(), plane.vertex_count());
+        for tangent in &plane.tangents {
+            assert_eq!(*tangent, [1.0, 0.0, 0.0, 1.0]);
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! # Texture Coordinate Generation
+//!
+//! Simple projections that give texture coordinates to meshes built without
+//! them, such as marching-cubes surfaces or CSG results. For shading in the
+//! renderer, [`TextureMapping::Triplanar`](crate::gfx::resources::material::TextureMapping)
+//! needs no coordinates at all; these are for tangents, exports and custom
+//! uses.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use cgmath::Vector3;
+//! use haggis::gfx::geometry::{generate_box_uvs, generate_spherical_uvs, generate_sphere};
+//!
+//! let mut sphere = generate_sphere(32, 16);
+//! generate_spherical_uvs(&mut sphere, Vector3::new(0.0, 0.0, 0.0), Vector3::unit_z());
+//!
+//! let mut blob = generate_sphere(8, 4);
+//! generate_box_uvs(&mut blob, 2.0);
+//! ```
+
+use cgmath::{InnerSpace, Vector3};
+use std::f32::consts::PI;
+
+use super::GeometryData;
+
+/// Replace the texture coordinates with a box projection
+///
+/// Each vertex is projected along the axis its normal points most along,
+/// with `scale` repeats per unit length. Vertices shared by faces pointing
+/// different ways show a seam; flat-shaded meshes have none.
+pub fn generate_box_uvs(mesh: &mut GeometryData, scale: f32) {
+    mesh.tex_coords = mesh
+        .vertices
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let n = mesh.normals.get(i).copied().unwrap_or([0.0, 0.0, 1.0]);
+            let [x, y, z] = n.map(f32::abs);
+            let (u, v) = if x >= y && x >= z {
+                (p[1] * n[0].signum(), p[2])
+            } else if y >= z {
+                (-p[0] * n[1].signum(), p[2])
+            } else {
+                (p[0] * n[2].signum(), p[1])
+            };
+            [u * scale, v * scale]
+        })
+        .collect();
+}
+
+/// Replace the texture coordinates with a spherical projection around
+/// `center`
+///
+/// U runs once around `pole` and V from the end `pole` points to (0) to the
+/// opposite end (1), like latitude and longitude. Triangles crossing the
+/// U seam stretch across the whole texture.
+pub fn generate_spherical_uvs(mesh: &mut GeometryData, center: Vector3<f32>, pole: Vector3<f32>) {
+    let pole = pole.normalize();
+    let reference = if pole.x.abs() < 0.9 {
+        Vector3::unit_x()
+    } else {
+        Vector3::unit_y()
+    };
+    let across = (reference - pole * pole.dot(reference)).normalize();
+    let side = pole.cross(across);
+    mesh.tex_coords = mesh
+        .vertices
+        .iter()
+        .map(|&p| {
+            let direction = Vector3::from(p) - center;
+            let length = direction.magnitude();
+            if length < 1e-12 {
+                return [0.5, 0.5];
+            }
+            let direction = direction / length;
+            let longitude = direction.dot(side).atan2(direction.dot(across));
+            let latitude = direction.dot(pole).clamp(-1.0, 1.0).acos();
+            [longitude / (2.0 * PI) + 0.5, latitude / PI]
+        })
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gfx::geometry::{compute_flat_normals, generate_cube, generate_sphere};
+
+    #[test]
+    fn test_projections_cover_the_mesh() {
+        let mut cube = generate_cube();
+        compute_flat_normals(&mut cube);
+        generate_box_uvs(&mut cube, 1.0);
+        assert_eq!(cube.tex_coords.len(), cube.vertex_count());
+        // Every face spans one unit of texture
+        for face in cube.tex_coords.chunks(6) {
+            let span = |axis: usize| {
+                let values = face.iter().map(|uv| uv[axis]);
+                values.clone().fold(f32::MIN, f32::max) - values.fold(f32::MAX, f32::min)
+            };
+            assert!((span(0) - 1.0).abs() < 1e-6 && (span(1) - 1.0).abs() < 1e-6);
+        }
+
+        let mut sphere = generate_sphere(16, 8);
+        generate_spherical_uvs(&mut sphere, Vector3::new(0.0, 0.0, 0.0), Vector3::unit_y());
+        // The sphere's north pole is at +y
+        assert!(sphere.tex_coords[0][1].abs() < 1e-6);
+        assert!((sphere.tex_coords.last().unwrap()[1] - 1.0).abs() < 1e-6);
+        assert!(sphere
+            .tex_coords
+            .iter()
+            .all(|uv| (0.0..=1.0).contains(&uv[0]) && (0.0..=1.0).contains(&uv[1])));
+    }
+}
@@ -214,9 +214,7 @@ impl CameraGizmo {
     
     /// Remove object from scene by index
     fn remove_object_from_scene(&self, scene: &mut Scene, object_index: usize) {
-        if object_index < scene.objects.len() {
-            scene.objects.remove(object_index);
-        }
+        scene.remove_object(object_index);
     }
     
     /// Clear all history
@@ -31,7 +31,8 @@ pub enum ViewDirection {
 }
 
 impl ViewDirection {
-    /// Get the camera position for this view direction
+    /// Get the camera position for this view direction, in the canonical
+    /// Z-up frame (see [`UpAxis::from_z_up`](crate::gfx::coordinates::UpAxis::from_z_up))
     pub fn get_camera_position(&self, distance: f32) -> Vector3<f32> {
         match self {
             ViewDirection::Front => Vector3::new(0.0, distance, 0.0),
@@ -181,9 +182,7 @@ impl ViewportGizmo {
     fn cleanup_faces(&mut self, scene: &mut Scene) {
         // Remove in reverse order to maintain indices
         for &index in self.face_object_indices.iter().rev() {
-            if index < scene.objects.len() {
-                scene.objects.remove(index);
-            }
+            scene.remove_object(index);
         }
         self.face_object_indices.clear();
     }
@@ -312,14 +311,18 @@ impl ViewportGizmo {
         let camera = &mut scene.camera_manager.camera;
         let target = camera.target; // Keep current target
         
-        let new_position = target + view.get_camera_position(self.camera_distance);
+        // View directions are in the canonical Z-up frame
+        let new_position = target
+            + camera
+                .up_axis
+                .from_z_up(view.get_camera_position(self.camera_distance));
         
         // Update camera with new position
         camera.eye = new_position;
         camera.distance = self.camera_distance;
         
         // Calculate new pitch and yaw based on position
-        let direction = (target - new_position).normalize();
+        let direction = camera.up_axis.to_z_up((target - new_position).normalize());
         camera.yaw = direction.y.atan2(direction.x).to_degrees();
         camera.pitch = direction.z.asin().to_degrees();
     }
@@ -330,7 +333,10 @@ impl ViewportGizmo {
         let target = camera.target;
         
         let start_pos = camera.eye;
-        let end_pos = target + view.get_camera_position(self.camera_distance);
+        let end_pos = target
+            + camera
+                .up_axis
+                .from_z_up(view.get_camera_position(self.camera_distance));
         
         // Use smooth interpolation (ease-in-out)
         let smooth_progress = progress * progress * (3.0 - 2.0 * progress);
@@ -339,7 +345,7 @@ impl ViewportGizmo {
         camera.eye = new_position;
         
         // Update camera parameters
-        let direction = (target - new_position).normalize();
+        let direction = camera.up_axis.to_z_up((target - new_position).normalize());
         camera.yaw = direction.y.atan2(direction.x).to_degrees();
         camera.pitch = direction.z.asin().to_degrees();
         camera.distance = (new_position - target).magnitude();
@@ -0,0 +1,299 @@
+//! # Measurement Tools
+//!
+//! Measurement mode places points on scene geometry with surface picking and
+//! shows world-space distances and angles as labels over the scene, e.g. to
+//! check the simulation domain size or the scale of an imported model.
+//!
+//! While the mode is on, left clicks place points instead of selecting
+//! objects. A distance is complete after two points, an angle after three,
+//! measured at the middle point. Lines, markers and labels are drawn over the
+//! scene with ImGui, in the unit of the reference overlay.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::input::{Action, Key};
+//!
+//! let mut app = haggis::default();
+//! app.set_measure_mode(true);
+//! app.bind_key(Key::KeyM, Action::ToggleMeasureMode);
+//! ```
+
+use cgmath::{InnerSpace, Matrix4, Vector3};
+use imgui::Ui;
+
+use crate::gfx::overlay::{format_distance, project};
+
+const LINE_COLOR: [f32; 4] = [1.0, 0.85, 0.2, 1.0];
+const LABEL_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const LABEL_BACKGROUND: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+
+/// What the next placed points measure
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MeasureMode {
+    /// Distance between two points
+    #[default]
+    Distance,
+    /// Angle at the second of three points
+    Angle,
+}
+
+impl MeasureMode {
+    /// Number of points a measurement of this mode takes
+    pub fn point_count(self) -> usize {
+        match self {
+            MeasureMode::Distance => 2,
+            MeasureMode::Angle => 3,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MeasureMode::Distance => "Distance",
+            MeasureMode::Angle => "Angle",
+        }
+    }
+}
+
+/// A completed measurement
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    pub mode: MeasureMode,
+    /// World-space points, as many as the mode takes
+    pub points: Vec<Vector3<f32>>,
+}
+
+impl Measurement {
+    /// Distance in world units, or the angle in degrees
+    pub fn value(&self) -> f32 {
+        match self.mode {
+            MeasureMode::Distance => distance(self.points[0], self.points[1]),
+            MeasureMode::Angle => angle_degrees(self.points[0], self.points[1], self.points[2]),
+        }
+    }
+
+    /// Value with its unit, e.g. `"1.25 m"` or `"90.0°"`
+    pub fn label(&self, unit: &str) -> String {
+        match self.mode {
+            MeasureMode::Distance => format!("{}{}", format_distance(self.value()), unit),
+            MeasureMode::Angle => format!("{:.1}°", self.value()),
+        }
+    }
+
+    /// Where the label is drawn: the middle of a distance, the angle's vertex
+    fn anchor(&self) -> Vector3<f32> {
+        match self.mode {
+            MeasureMode::Distance => (self.points[0] + self.points[1]) * 0.5,
+            MeasureMode::Angle => self.points[1],
+        }
+    }
+}
+
+/// Distance between two world-space points
+pub fn distance(a: Vector3<f32>, b: Vector3<f32>) -> f32 {
+    (b - a).magnitude()
+}
+
+/// Angle at `vertex` between the directions to `a` and `c`, in degrees
+pub fn angle_degrees(a: Vector3<f32>, vertex: Vector3<f32>, c: Vector3<f32>) -> f32 {
+    let u = a - vertex;
+    let v = c - vertex;
+    if u.magnitude2() == 0.0 || v.magnitude2() == 0.0 {
+        return 0.0;
+    }
+    u.angle(v).0.to_degrees()
+}
+
+/// Measurement mode state: placed points and completed measurements
+#[derive(Debug, Clone, Default)]
+pub struct MeasureTool {
+    /// Whether measurement mode is on; clicks then place points
+    pub open: bool,
+    pub mode: MeasureMode,
+    pending: Vec<Vector3<f32>>,
+    measurements: Vec<Measurement>,
+}
+
+impl MeasureTool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    /// Measure something else from the next point on
+    pub fn set_mode(&mut self, mode: MeasureMode) {
+        self.mode = mode;
+        self.pending.clear();
+    }
+
+    /// Points placed for the measurement in progress
+    pub fn pending(&self) -> &[Vector3<f32>] {
+        &self.pending
+    }
+
+    pub fn measurements(&self) -> &[Measurement] {
+        &self.measurements
+    }
+
+    /// Place the next point
+    ///
+    /// # Returns
+    ///
+    /// The measurement this point completed, if any
+    pub fn add_point(&mut self, point: Vector3<f32>) -> Option<&Measurement> {
+        self.pending.push(point);
+        if self.pending.len() < self.mode.point_count() {
+            return None;
+        }
+        self.measurements.push(Measurement {
+            mode: self.mode,
+            points: std::mem::take(&mut self.pending),
+        });
+        self.measurements.last()
+    }
+
+    /// Remove the last placed point, or the last measurement if none is pending
+    pub fn undo(&mut self) {
+        if self.pending.pop().is_none() {
+            self.measurements.pop();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+        self.measurements.clear();
+    }
+
+    /// Draw lines, points and labels over the scene
+    ///
+    /// # Arguments
+    ///
+    /// * `view_proj` - View-projection matrix of the main camera
+    /// * `unit` - Unit suffix of distances, e.g. the reference overlay's
+    pub fn render_labels(&self, ui: &Ui, view_proj: Matrix4<f32>, unit: &str) {
+        if !self.open {
+            return;
+        }
+        let display_size = ui.io().display_size;
+        let draw_list = ui.get_background_draw_list();
+        let to_screen = |point: Vector3<f32>| project(view_proj, point, display_size);
+
+        let polylines = self
+            .measurements
+            .iter()
+            .map(|measurement| measurement.points.as_slice())
+            .chain(std::iter::once(self.pending.as_slice()));
+        for points in polylines {
+            let screen: Vec<Option<[f32; 2]>> = points.iter().map(|&p| to_screen(p)).collect();
+            for pair in screen.windows(2) {
+                if let [Some(a), Some(b)] = pair {
+                    draw_list
+                        .add_line(*a, *b, LINE_COLOR)
+                        .thickness(2.0)
+                        .build();
+                }
+            }
+            for point in screen.into_iter().flatten() {
+                draw_list
+                    .add_circle(point, 4.0, LINE_COLOR)
+                    .filled(true)
+                    .build();
+            }
+        }
+
+        for measurement in &self.measurements {
+            let Some([x, y]) = to_screen(measurement.anchor()) else {
+                continue;
+            };
+            let label = measurement.label(unit);
+            let size = ui.calc_text_size(&label);
+            let min = [x + 6.0, y - size[1] - 6.0];
+            let max = [min[0] + size[0] + 6.0, min[1] + size[1] + 4.0];
+            draw_list
+                .add_rect(min, max, LABEL_BACKGROUND)
+                .filled(true)
+                .rounding(3.0)
+                .build();
+            draw_list.add_text([min[0] + 3.0, min[1] + 2.0], LABEL_COLOR, label);
+        }
+    }
+
+    /// Measurement window: mode, measured values, undo and clear
+    pub fn render_ui(&mut self, ui: &Ui, unit: &str) {
+        if !self.open {
+            return;
+        }
+
+        let mut open = self.open;
+        ui.window("Measure")
+            .size([260.0, 300.0], imgui::Condition::FirstUseEver)
+            .opened(&mut open)
+            .build(|| {
+                for mode in [MeasureMode::Distance, MeasureMode::Angle] {
+                    if ui.radio_button_bool(mode.as_str(), self.mode == mode) {
+                        self.set_mode(mode);
+                    }
+                    ui.same_line();
+                }
+                ui.new_line();
+                ui.text_disabled(format!(
+                    "Click geometry to place points ({}/{})",
+                    self.pending.len(),
+                    self.mode.point_count()
+                ));
+                ui.separator();
+
+                for (index, measurement) in self.measurements.iter().enumerate() {
+                    ui.text(format!(
+                        "{}. {}: {}",
+                        index + 1,
+                        measurement.mode.as_str(),
+                        measurement.label(unit)
+                    ));
+                }
+                if self.measurements.is_empty() {
+                    ui.text_disabled("No measurements");
+                }
+
+                ui.separator();
+                if ui.button("Undo") {
+                    self.undo();
+                }
+                ui.same_line();
+                if ui.button("Clear") {
+                    self.clear();
+                }
+            });
+        self.open = open;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distance_and_angle_measurements() {
+        let mut tool = MeasureTool::new();
+        assert!(tool.add_point(Vector3::new(0.0, 0.0, 0.0)).is_none());
+        let measurement = tool.add_point(Vector3::new(3.0, 4.0, 0.0)).unwrap();
+        assert_eq!(measurement.value(), 5.0);
+        assert_eq!(measurement.label(" m"), "5 m");
+
+        tool.set_mode(MeasureMode::Angle);
+        tool.add_point(Vector3::new(1.0, 0.0, 0.0));
+        tool.add_point(Vector3::new(0.0, 0.0, 0.0));
+        let angle = tool.add_point(Vector3::new(0.0, 0.0, 2.0)).unwrap();
+        assert!((angle.value() - 90.0).abs() < 1e-4);
+        assert_eq!(tool.measurements().len(), 2);
+
+        tool.add_point(Vector3::new(1.0, 1.0, 1.0));
+        tool.undo();
+        assert!(tool.pending().is_empty());
+        tool.undo();
+        assert_eq!(tool.measurements().len(), 1);
+    }
+}
@@ -8,8 +8,12 @@
 //! The graphics system is organized into several key components:
 //!
 //! - **Camera System** ([`camera`]) - Orbit camera with smooth controls
+//! - **Coordinate System** ([`coordinates`]) - World up axis and units
 //! - **Rendering Pipeline** ([`rendering`]) - PBR rendering with shadow mapping
+//! - **GPU Capabilities** ([`capabilities`]) - Device limits and features for choosing GPU or CPU paths
 //! - **Scene Management** ([`scene`]) - Object hierarchy and scene graph
+//! - **Reference Overlay** ([`overlay`]) - World grid, axes and scale labels
+//! - **Measurement Tools** ([`measure`]) - Distances and angles between picked surface points
 //! - **Resource Management** ([`resources`]) - Materials, textures, and GPU resources
 //!
 //! ## Key Features
@@ -37,8 +41,12 @@
 //! [`Scene`]: scene::Scene
 
 pub mod camera;
+pub mod capabilities;
+pub mod coordinates;
 pub mod geometry;
 pub mod gizmos;
+pub mod measure;
+pub mod overlay;
 pub mod picking;
 pub mod rendering;
 pub mod resources;
@@ -46,5 +54,7 @@ pub mod scene;
 
 // Re-export commonly used types
 pub use camera::orbit_camera::OrbitCamera;
+pub use capabilities::GpuCapabilities;
+pub use coordinates::{CoordinateSystem, UpAxis, WorldUnits};
 pub use gizmos::{CameraGizmo, Gizmo, GizmoManager, ViewportGizmo, ViewDirection};
 pub use rendering::render_engine::RenderEngine;
@@ -0,0 +1,298 @@
+//! # Reference Overlay
+//!
+//! Built-in visual references for orientation and scale: a world grid on the
+//! ground plane, the world axes through the origin, an XYZ axis indicator in a
+//! screen corner, and optional distance labels along the grid axes.
+//!
+//! The grid and world axes are drawn by the render engine with depth testing,
+//! so objects hide them; the corner indicator and labels are drawn over the
+//! scene with ImGui. Everything is configured through [`OverlayConfig`].
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::gfx::overlay::{GridPlane, OverlayConfig};
+//!
+//! let mut app = haggis::default();
+//! app.set_reference_overlay(OverlayConfig {
+//!     spacing: 0.25,
+//!     major_every: 4,
+//!     plane: GridPlane::XY,
+//!     show_labels: true,
+//!     unit: "m".to_string(),
+//!     ..OverlayConfig::default()
+//! });
+//! ```
+
+use cgmath::{EuclideanSpace, InnerSpace, Matrix4, Point3, Vector3, Vector4};
+use imgui::Ui;
+
+use crate::gfx::camera::{camera_utils::Camera, orbit_camera::OrbitCamera};
+use crate::gfx::rendering::line_renderer::LineVertex;
+
+/// Plane the grid is drawn in. The world is Z-up, so `XY` is the ground.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GridPlane {
+    #[default]
+    XY,
+    XZ,
+    YZ,
+}
+
+impl GridPlane {
+    /// In-plane axes and plane normal as axis indices
+    fn axes(self) -> (usize, usize, usize) {
+        match self {
+            GridPlane::XY => (0, 1, 2),
+            GridPlane::XZ => (0, 2, 1),
+            GridPlane::YZ => (1, 2, 0),
+        }
+    }
+}
+
+/// Settings of the reference overlay
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverlayConfig {
+    /// Draw the world grid
+    pub show_grid: bool,
+    /// Draw the world X (red), Y (green) and Z (blue) axes through the origin
+    pub show_world_axes: bool,
+    /// Draw the axis indicator in the bottom-left corner of the window
+    pub show_corner_axes: bool,
+    /// Label major grid lines with their distance from the origin
+    pub show_labels: bool,
+    /// Plane the grid lies in
+    pub plane: GridPlane,
+    /// Offset of the grid along the plane normal
+    pub offset: f32,
+    /// Distance between grid lines
+    pub spacing: f32,
+    /// Number of lines on each side of the origin
+    pub half_lines: u32,
+    /// Every n-th line is drawn as a major line (and labeled)
+    pub major_every: u32,
+    pub minor_color: [f32; 4],
+    pub major_color: [f32; 4],
+    /// Unit suffix for labels, e.g. `"m"`
+    pub unit: String,
+}
+
+impl Default for OverlayConfig {
+    fn default() -> Self {
+        Self {
+            show_grid: true,
+            show_world_axes: true,
+            show_corner_axes: true,
+            show_labels: false,
+            plane: GridPlane::XY,
+            offset: 0.0,
+            spacing: 0.5,
+            half_lines: 20,
+            major_every: 2,
+            minor_color: [0.5, 0.5, 0.5, 0.25],
+            major_color: [0.7, 0.7, 0.7, 0.5],
+            unit: String::new(),
+        }
+    }
+}
+
+/// Axis colors shared by the world axes and the corner indicator
+pub const AXIS_COLORS: [[f32; 4]; 3] = [
+    [0.9, 0.25, 0.25, 1.0],
+    [0.3, 0.85, 0.3, 1.0],
+    [0.3, 0.45, 1.0, 1.0],
+];
+
+impl OverlayConfig {
+    /// Half the side length of the grid
+    pub fn extent(&self) -> f32 {
+        self.spacing * self.half_lines as f32
+    }
+
+    fn point(&self, u: f32, v: f32) -> [f32; 3] {
+        let (a, b, n) = self.plane.axes();
+        let mut point = [0.0; 3];
+        point[a] = u;
+        point[b] = v;
+        point[n] = self.offset;
+        point
+    }
+
+    fn is_major(&self, line: i32) -> bool {
+        self.major_every > 0 && line % self.major_every as i32 == 0
+    }
+
+    /// Line list (pairs of vertices) for the grid and world axes
+    pub fn line_vertices(&self) -> Vec<LineVertex> {
+        let mut vertices = Vec::new();
+        let extent = self.extent();
+        let half_lines = self.half_lines as i32;
+
+        if self.show_grid && self.spacing > 0.0 {
+            for line in -half_lines..=half_lines {
+                // The world axes replace the center lines
+                if line == 0 && self.show_world_axes {
+                    continue;
+                }
+                let color = if self.is_major(line) {
+                    self.major_color
+                } else {
+                    self.minor_color
+                };
+                let t = line as f32 * self.spacing;
+                for (start, end) in [
+                    (self.point(t, -extent), self.point(t, extent)),
+                    (self.point(-extent, t), self.point(extent, t)),
+                ] {
+                    vertices.push(LineVertex {
+                        position: start,
+                        color,
+                    });
+                    vertices.push(LineVertex {
+                        position: end,
+                        color,
+                    });
+                }
+            }
+        }
+
+        if self.show_world_axes {
+            let length = if extent > 0.0 { extent } else { 1.0 };
+            for (axis, color) in AXIS_COLORS.iter().enumerate() {
+                let mut end = [0.0; 3];
+                end[axis] = length;
+                vertices.push(LineVertex {
+                    position: [0.0; 3],
+                    color: *color,
+                });
+                vertices.push(LineVertex {
+                    position: end,
+                    color: *color,
+                });
+            }
+        }
+
+        vertices
+    }
+
+    /// Draw the corner axis indicator and grid labels over the scene
+    pub fn render_ui(&self, ui: &Ui, camera: &OrbitCamera) {
+        let display_size = ui.io().display_size;
+        let draw_list = ui.get_background_draw_list();
+
+        if self.show_labels && self.show_grid && self.major_every > 0 {
+            let view_proj = camera.build_view_projection_matrix();
+            let (a, b, _) = self.plane.axes();
+            let names = ["x", "y", "z"];
+            for line in (-(self.half_lines as i32)..=self.half_lines as i32)
+                .filter(|&line| line != 0 && self.is_major(line))
+            {
+                let t = line as f32 * self.spacing;
+                for (axis, point) in [(a, self.point(t, 0.0)), (b, self.point(0.0, t))] {
+                    if let Some(screen) = project(view_proj, point.into(), display_size) {
+                        let label = format!("{}={}{}", names[axis], format_distance(t), self.unit);
+                        draw_list.add_text(screen, [0.85, 0.85, 0.85, 0.8], label);
+                    }
+                }
+            }
+        }
+
+        if self.show_corner_axes {
+            let center = [60.0, display_size[1] - 60.0];
+            let length = 40.0;
+            let view = Matrix4::look_at_rh(
+                Point3::from_vec(camera.eye),
+                Point3::from_vec(camera.target),
+                camera.up,
+            );
+
+            // Draw the axes pointing away from the viewer first
+            let mut axes: Vec<(usize, Vector4<f32>)> = (0..3)
+                .map(|axis| {
+                    let mut direction = Vector4::new(0.0, 0.0, 0.0, 0.0);
+                    direction[axis] = 1.0;
+                    (axis, view * direction)
+                })
+                .collect();
+            axes.sort_by(|(_, p), (_, q)| p.z.total_cmp(&q.z));
+
+            draw_list
+                .add_circle(center, length + 8.0, [0.1, 0.1, 0.1, 0.4])
+                .filled(true)
+                .build();
+            for (axis, direction) in axes {
+                let end = [
+                    center[0] + direction.x * length,
+                    center[1] - direction.y * length,
+                ];
+                let color = AXIS_COLORS[axis];
+                draw_list
+                    .add_line(center, end, color)
+                    .thickness(2.0)
+                    .build();
+                draw_list.add_text(end, color, ["X", "Y", "Z"][axis]);
+            }
+        }
+    }
+}
+
+/// World point to window pixels, `None` if behind the camera or off screen
+pub(crate) fn project(
+    view_proj: Matrix4<f32>,
+    point: Vector3<f32>,
+    display_size: [f32; 2],
+) -> Option<[f32; 2]> {
+    let clip = view_proj * point.extend(1.0);
+    if clip.w <= 0.0 {
+        return None;
+    }
+    let ndc = clip.truncate() / clip.w;
+    if ndc.x.abs() > 1.0 || ndc.y.abs() > 1.0 || ndc.magnitude2().is_nan() {
+        return None;
+    }
+    Some([
+        (ndc.x + 1.0) * 0.5 * display_size[0],
+        (1.0 - ndc.y) * 0.5 * display_size[1],
+    ])
+}
+
+/// Distance without trailing zeros ("1.5", "2", "0.25")
+pub(crate) fn format_distance(value: f32) -> String {
+    let text = format!("{:.3}", value);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grid_lines_skip_center_when_axes_are_shown() {
+        let config = OverlayConfig {
+            half_lines: 2,
+            spacing: 1.0,
+            major_every: 2,
+            ..OverlayConfig::default()
+        };
+        let vertices = config.line_vertices();
+        // 4 lines per direction without the center, plus 3 axes
+        assert_eq!(vertices.len(), (4 * 2 + 3) * 2);
+        let (grid, axes) = vertices.split_at(4 * 2 * 2);
+        assert!(grid.iter().all(|vertex| vertex.position[2] == 0.0));
+        assert_eq!(axes[5].position, [0.0, 0.0, 2.0]);
+        assert_eq!(vertices[0].color, config.major_color);
+        assert_eq!(vertices[4].color, config.minor_color);
+
+        let side = OverlayConfig {
+            plane: GridPlane::XZ,
+            offset: -1.0,
+            show_world_axes: false,
+            ..config
+        };
+        let vertices = side.line_vertices();
+        assert_eq!(vertices.len(), 5 * 2 * 2);
+        assert!(vertices.iter().all(|vertex| vertex.position[1] == -1.0));
+        assert_eq!(format_distance(1.50), "1.5");
+        assert_eq!(format_distance(-2.0), "-2");
+    }
+}
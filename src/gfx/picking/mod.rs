@@ -22,6 +22,7 @@
 //! ```
 
 use cgmath::{Vector3, Vector4, Matrix4, InnerSpace, Zero, ElementWise, EuclideanSpace, SquareMatrix};
+use crate::app::jobs::JobSystem;
 use crate::gfx::{
     scene::Scene,
     camera::orbit_camera::OrbitCamera,
@@ -49,6 +50,38 @@ impl Ray {
     pub fn point_at(&self, t: f32) -> Vector3<f32> {
         self.origin + self.direction * t
     }
+
+    /// Test ray-triangle intersection (Möller–Trumbore), both faces count
+    /// Returns the distance to intersection point, or None if no intersection
+    pub fn intersect_triangle(
+        &self,
+        a: Vector3<f32>,
+        b: Vector3<f32>,
+        c: Vector3<f32>,
+    ) -> Option<f32> {
+        let edge1 = b - a;
+        let edge2 = c - a;
+        let p = self.direction.cross(edge2);
+        let det = edge1.dot(p);
+        if det.abs() < 1e-8 {
+            return None;
+        }
+
+        let inv_det = 1.0 / det;
+        let s = self.origin - a;
+        let u = s.dot(p) * inv_det;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = s.cross(edge1);
+        let v = self.direction.dot(q) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let t = edge2.dot(q) * inv_det;
+        (t >= 0.0).then_some(t)
+    }
 }
 
 /// Axis-aligned bounding box for intersection testing
@@ -194,15 +227,18 @@ impl ObjectPicker {
         let target = cgmath::Point3::from_vec(camera.target);
         let view_matrix = cgmath::Matrix4::look_at_rh(eye, target, camera.up);
         
-        let proj_matrix = cgmath::perspective(camera.fovy, camera.aspect, camera.znear, camera.zfar);
+        // Same projection as the renderer, so rays match what is drawn
+        let proj_matrix = camera.projection_matrix();
         
         // Calculate inverse matrices
         let view_proj_matrix = proj_matrix * view_matrix;
         let inv_view_proj = view_proj_matrix.invert().unwrap_or(Matrix4::from_scale(1.0));
 
-        // Transform near and far points from NDC to world space
-        let near_point = Vector4::new(ndc_x, ndc_y, -1.0, 1.0); // Near plane in NDC
-        let far_point = Vector4::new(ndc_x, ndc_y, 1.0, 1.0);   // Far plane in NDC
+        // Transform near and far points from NDC to world space; which depth
+        // is near depends on the camera's depth mode
+        let (near_depth, far_depth) = camera.depth_mode.near_far();
+        let near_point = Vector4::new(ndc_x, ndc_y, near_depth, 1.0);
+        let far_point = Vector4::new(ndc_x, ndc_y, far_depth, 1.0);
 
         let world_near = inv_view_proj * near_point;
         let world_far = inv_view_proj * far_point;
@@ -233,30 +269,30 @@ impl ObjectPicker {
         scene: &Scene,
     ) -> Option<PickResult> {
         let ray = self.screen_to_ray(screen_pos, screen_size, camera);
-        
-        // Ensure we have enough cached AABBs
-        while self.cached_aabbs.len() < scene.objects.len() {
-            self.cached_aabbs.push(None);
-        }
+        self.pick_ray(&ray, scene)
+    }
+
+    /// Pick the closest object hit by a world-space ray, e.g. from a VR controller
+    pub fn pick_ray(&mut self, ray: &Ray, scene: &Scene) -> Option<PickResult> {
+        self.update_aabbs(scene);
 
         let mut closest_result: Option<PickResult> = None;
+        // Objects on hidden layers can't be picked
+        let shown = scene.layers.visible() & scene.camera_manager.camera.layers;
 
         for (i, object) in scene.objects.iter().enumerate() {
-            // Get or compute AABB for this object
-            let aabb = if let Some(cached) = &self.cached_aabbs[i] {
-                *cached
-            } else {
-                // Compute AABB from object vertices
-                let aabb = self.compute_object_aabb(object);
-                self.cached_aabbs[i] = Some(aabb);
-                aabb
+            if !shown.intersects(object.layers) {
+                continue;
+            }
+            let Some(aabb) = self.cached_aabbs[i] else {
+                continue;
             };
 
             // Apply object's transform to AABB
             let world_aabb = aabb.transform(&object.transform);
 
             // Test ray intersection
-            if let Some(distance) = world_aabb.intersect_ray(&ray) {
+            if let Some(distance) = world_aabb.intersect_ray(ray) {
                 let intersection_point = ray.point_at(distance);
                 
                 // Keep the closest intersection
@@ -276,6 +312,85 @@ impl ObjectPicker {
         closest_result
     }
 
+    /// Pick the closest point on object geometry hit by a world-space ray
+    ///
+    /// Unlike [`pick_ray`](Self::pick_ray), which stops at bounding boxes, this
+    /// tests the triangles of every object whose box is hit, so the
+    /// intersection point lies on the surface, e.g. for measurements.
+    pub fn pick_surface(&mut self, ray: &Ray, scene: &Scene) -> Option<PickResult> {
+        self.update_aabbs(scene);
+
+        let mut closest_result: Option<PickResult> = None;
+        let shown = scene.layers.visible() & scene.camera_manager.camera.layers;
+
+        for (i, object) in scene.objects.iter().enumerate() {
+            if !shown.intersects(object.layers) {
+                continue;
+            }
+            let Some(aabb) = self.cached_aabbs[i] else {
+                continue;
+            };
+            let Some(box_distance) = aabb.transform(&object.transform).intersect_ray(ray) else {
+                continue;
+            };
+            // The surface can't be closer than the box
+            if closest_result
+                .as_ref()
+                .is_some_and(|result| result.distance <= box_distance)
+            {
+                continue;
+            }
+
+            let to_world = |position: [f32; 3]| {
+                (object.transform * Vector4::new(position[0], position[1], position[2], 1.0))
+                    .truncate()
+            };
+            for mesh in &object.meshes {
+                let vertices = mesh.vertices();
+                for triangle in mesh.indices().chunks_exact(3) {
+                    let [a, b, c] =
+                        [0, 1, 2].map(|k| to_world(vertices[triangle[k] as usize].position));
+                    let Some(distance) = ray.intersect_triangle(a, b, c) else {
+                        continue;
+                    };
+                    if closest_result
+                        .as_ref()
+                        .is_none_or(|result| distance < result.distance)
+                    {
+                        closest_result = Some(PickResult {
+                            object_index: i,
+                            distance,
+                            intersection_point: ray.point_at(distance),
+                        });
+                    }
+                }
+            }
+        }
+
+        closest_result
+    }
+
+    /// Compute missing AABBs, in parallel across objects
+    pub fn update_aabbs(&mut self, scene: &Scene) {
+        self.cached_aabbs.resize(scene.objects.len(), None);
+
+        let missing: Vec<usize> = (0..scene.objects.len())
+            .filter(|&i| self.cached_aabbs[i].is_none())
+            .collect();
+        if missing.is_empty() {
+            return;
+        }
+
+        let picker = &*self;
+        let objects = &scene.objects;
+        let aabbs = JobSystem::global().parallel_map(missing.len(), |i| {
+            picker.compute_object_aabb(&objects[missing[i]])
+        });
+        for (index, aabb) in missing.into_iter().zip(aabbs) {
+            self.cached_aabbs[index] = Some(aabb);
+        }
+    }
+
     /// Compute AABB for an object from its mesh data
     fn compute_object_aabb(&self, object: &crate::gfx::scene::object::Object) -> AABB {
         let mut all_vertices = Vec::new();
@@ -358,4 +473,47 @@ mod tests {
         
         assert!(aabb.intersect_ray(&ray_miss).is_none());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_ray_triangle_intersection() {
+        let a = Vector3::new(0.0, 0.0, 0.0);
+        let b = Vector3::new(2.0, 0.0, 0.0);
+        let c = Vector3::new(0.0, 2.0, 0.0);
+
+        let ray = Ray::new(Vector3::new(0.5, 0.5, 3.0), Vector3::new(0.0, 0.0, -1.0));
+        let distance = ray.intersect_triangle(a, b, c).unwrap();
+        assert!((distance - 3.0).abs() < 1e-5);
+
+        // Outside the triangle, and behind the ray origin
+        let ray_miss = Ray::new(Vector3::new(1.5, 1.5, 3.0), Vector3::new(0.0, 0.0, -1.0));
+        assert!(ray_miss.intersect_triangle(a, b, c).is_none());
+        let ray_away = Ray::new(Vector3::new(0.5, 0.5, 3.0), Vector3::new(0.0, 0.0, 1.0));
+        assert!(ray_away.intersect_triangle(a, b, c).is_none());
+    }
+
+    #[test]
+    fn test_screen_ray_passes_through_rendered_point() {
+        use crate::gfx::camera::camera_utils::Camera;
+        use crate::gfx::rendering::DepthMode;
+
+        let picker = ObjectPicker::new();
+        let mut camera = OrbitCamera::new(8.0, 0.4, 0.2, Vector3::new(1.0, 2.0, 0.0), 1.5);
+        let point = Vector3::new(2.0, 2.5, 0.3);
+
+        for depth_mode in [DepthMode::Standard, DepthMode::Reverse] {
+            camera.depth_mode = depth_mode;
+            // Where the renderer draws the point
+            let clip = camera.build_view_projection_matrix() * point.extend(1.0);
+            let screen = (
+                (clip.x / clip.w + 1.0) * 0.5 * 800.0,
+                (1.0 - clip.y / clip.w) * 0.5 * 600.0,
+            );
+
+            let ray = picker.screen_to_ray(screen, (800.0, 600.0), &camera);
+            let along = (point - ray.origin).dot(ray.direction);
+            let miss = (point - ray.origin - ray.direction * along).magnitude();
+            assert!(miss < 1e-3, "{depth_mode:?} ray misses by {miss}");
+            assert!(((ray.origin - camera.eye).magnitude() - camera.znear).abs() < 0.05);
+        }
+    }
+}
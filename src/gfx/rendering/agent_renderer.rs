@@ -0,0 +1,423 @@
+//! Agent rendering
+//!
+//! Draws large numbers of agents (boids, pedestrians, particles with a
+//! heading) as small darts pointing along their velocity, or particles
+//! without one (grains, droplets) as spheres, one instanced draw call per
+//! batch. Instances are read straight from a vertex buffer, so a compute
+//! shader can write them without any readback.
+
+use wgpu::util::DeviceExt;
+use wgpu::{BindGroup, Buffer, Device, RenderPass, RenderPipeline};
+
+use crate::gfx::resources::global_bindings::GlobalBindings;
+use crate::gfx::scene::vertex::Vertex3D;
+
+use super::color::with_color_functions;
+use super::depth::DepthMode;
+
+/// Per-agent instance data, as laid out in instance buffers
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct AgentInstance {
+    /// World position, size in `w`
+    pub position: [f32; 4],
+    /// Heading the dart points along; `w` is free for simulation use
+    pub velocity: [f32; 4],
+    pub color: [f32; 4],
+}
+
+impl AgentInstance {
+    pub fn new(position: [f32; 3], velocity: [f32; 3], size: f32, color: [f32; 4]) -> Self {
+        Self {
+            position: [position[0], position[1], position[2], size],
+            velocity: [velocity[0], velocity[1], velocity[2], 0.0],
+            color,
+        }
+    }
+}
+
+/// Mesh drawn for every instance of a batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AgentShape {
+    /// Dart pointing along the velocity, as long as the instance size
+    #[default]
+    Dart,
+    /// Sphere with the instance size as radius
+    Sphere,
+}
+
+/// Instances to draw in one call
+///
+/// The buffer needs `VERTEX` usage and holds at least `count`
+/// [`AgentInstance`]s. With `indirect` set, the number of agents drawn is
+/// the instance count of the `DrawIndexedIndirectArgs` in that buffer,
+/// usually written by a compute shader, and `count` is only an upper bound.
+#[derive(Debug, Clone)]
+pub struct AgentBatch {
+    pub instances: Buffer,
+    pub count: u32,
+    pub indirect: Option<Buffer>,
+    pub shape: AgentShape,
+}
+
+struct AgentMesh {
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    index_count: u32,
+}
+
+impl AgentMesh {
+    fn new(device: &Device, name: &str, vertices: &[Vertex3D], indices: &[u16]) -> Self {
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("Agent {name} Vertex Buffer")),
+            contents: bytemuck::cast_slice(vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("Agent {name} Index Buffer")),
+            contents: bytemuck::cast_slice(indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        Self {
+            vertex_buffer,
+            index_buffer,
+            index_count: indices.len() as u32,
+        }
+    }
+}
+
+/// Renderer for batches of agent darts and spheres
+pub struct AgentRenderer {
+    pipeline: RenderPipeline,
+    dart: AgentMesh,
+    sphere: AgentMesh,
+    batches: Vec<AgentBatch>,
+}
+
+/// Flat-shaded triangular pyramid pointing along +X
+fn dart_mesh() -> (Vec<Vertex3D>, Vec<u16>) {
+    let tip: [f32; 3] = [0.6, 0.0, 0.0];
+    let top = [-0.4, 0.0, 0.18];
+    let left = [-0.4, 0.25, -0.1];
+    let right = [-0.4, -0.25, -0.1];
+    let faces = [
+        [tip, left, top],
+        [tip, top, right],
+        [tip, right, left],
+        [top, left, right],
+    ];
+
+    let mut vertices = Vec::with_capacity(12);
+    for [a, b, c] in faces {
+        let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+        let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+        let n = [
+            u[1] * v[2] - u[2] * v[1],
+            u[2] * v[0] - u[0] * v[2],
+            u[0] * v[1] - u[1] * v[0],
+        ];
+        let length = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+        let normal = [n[0] / length, n[1] / length, n[2] / length];
+        for position in [a, b, c] {
+            vertices.push(Vertex3D { position, normal });
+        }
+    }
+    let indices = (0..vertices.len() as u16).collect();
+    (vertices, indices)
+}
+
+/// Latitude rings of the sphere mesh, poles excluded
+const SPHERE_RINGS: u16 = 7;
+/// Vertices around each ring
+const SPHERE_SEGMENTS: u16 = 12;
+
+/// Smooth-shaded unit sphere around the origin, poles on the Z axis
+fn sphere_mesh() -> (Vec<Vertex3D>, Vec<u16>) {
+    let mut vertices = vec![Vertex3D {
+        position: [0.0, 0.0, 1.0],
+        normal: [0.0, 0.0, 1.0],
+    }];
+    for ring in 1..=SPHERE_RINGS {
+        let polar = std::f32::consts::PI * ring as f32 / (SPHERE_RINGS + 1) as f32;
+        for segment in 0..SPHERE_SEGMENTS {
+            let azimuth = std::f32::consts::TAU * segment as f32 / SPHERE_SEGMENTS as f32;
+            let position = [
+                polar.sin() * azimuth.cos(),
+                polar.sin() * azimuth.sin(),
+                polar.cos(),
+            ];
+            vertices.push(Vertex3D {
+                position,
+                normal: position,
+            });
+        }
+    }
+    let south = vertices.len() as u16;
+    vertices.push(Vertex3D {
+        position: [0.0, 0.0, -1.0],
+        normal: [0.0, 0.0, -1.0],
+    });
+
+    let ring_start = |ring: u16| 1 + ring * SPHERE_SEGMENTS;
+    let mut indices = Vec::new();
+    for segment in 0..SPHERE_SEGMENTS {
+        let next = (segment + 1) % SPHERE_SEGMENTS;
+        indices.extend([0, ring_start(0) + segment, ring_start(0) + next]);
+        for ring in 0..SPHERE_RINGS - 1 {
+            let (a, b) = (ring_start(ring) + segment, ring_start(ring) + next);
+            let (c, d) = (ring_start(ring + 1) + segment, ring_start(ring + 1) + next);
+            indices.extend([a, c, d, a, d, b]);
+        }
+        let last = ring_start(SPHERE_RINGS - 1);
+        indices.extend([last + segment, south, last + next]);
+    }
+    (vertices, indices)
+}
+
+impl AgentRenderer {
+    /// Indices of the dart mesh, for indirect draw arguments
+    pub const INDEX_COUNT: u32 = 12;
+    /// Indices of the sphere mesh, for indirect draw arguments
+    pub const SPHERE_INDEX_COUNT: u32 = 6 * SPHERE_SEGMENTS as u32 * SPHERE_RINGS as u32;
+
+    pub fn new(
+        device: &Device,
+        surface_format: wgpu::TextureFormat,
+        global_bindings: &GlobalBindings,
+        depth_mode: DepthMode,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Agent Shader"),
+            source: wgpu::ShaderSource::Wgsl(with_color_functions(AGENT_SHADER).into()),
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Agent Pipeline Layout"),
+            bind_group_layouts: &[global_bindings.bind_group_layouts()],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Agent Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[
+                    Vertex3D::desc(),
+                    wgpu::VertexBufferLayout {
+                        array_stride: std::mem::size_of::<AgentInstance>() as wgpu::BufferAddress,
+                        step_mode: wgpu::VertexStepMode::Instance,
+                        attributes: &wgpu::vertex_attr_array![
+                            2 => Float32x4,
+                            3 => Float32x4,
+                            4 => Float32x4
+                        ],
+                    },
+                ],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: depth_mode.compare(wgpu::CompareFunction::Less),
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let (vertices, indices) = dart_mesh();
+        let dart = AgentMesh::new(device, "Dart", &vertices, &indices);
+        let (vertices, indices) = sphere_mesh();
+        let sphere = AgentMesh::new(device, "Sphere", &vertices, &indices);
+
+        Self {
+            pipeline,
+            dart,
+            sphere,
+            batches: Vec::new(),
+        }
+    }
+
+    /// Replace the batches to draw
+    pub fn set_batches(&mut self, batches: Vec<AgentBatch>) {
+        self.batches = batches;
+    }
+
+    /// Total number of agents drawn per frame, at most for indirect batches
+    pub fn agent_count(&self) -> u32 {
+        self.batches.iter().map(|batch| batch.count).sum()
+    }
+
+    pub fn render<'a>(
+        &'a self,
+        render_pass: &mut RenderPass<'a>,
+        global_bind_group: &'a BindGroup,
+    ) {
+        if self.batches.is_empty() {
+            return;
+        }
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, global_bind_group, &[]);
+        for batch in self.batches.iter().filter(|batch| batch.count > 0) {
+            let mesh = match batch.shape {
+                AgentShape::Dart => &self.dart,
+                AgentShape::Sphere => &self.sphere,
+            };
+            render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+            render_pass.set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+            render_pass.set_vertex_buffer(1, batch.instances.slice(..));
+            match &batch.indirect {
+                Some(indirect) => render_pass.draw_indexed_indirect(indirect, 0),
+                None => render_pass.draw_indexed(0..mesh.index_count, 0, 0..batch.count),
+            }
+        }
+    }
+}
+
+const AGENT_SHADER: &str = r#"
+struct GlobalUniform {
+    view_position: vec4<f32>,
+    view_proj: mat4x4<f32>,
+    light_position: vec3<f32>,
+    _padding1: f32,
+    light_color: vec3<f32>,
+    light_intensity: f32,
+    light_view_proj: mat4x4<f32>,
+    fog_color: vec3<f32>,
+    fog_mode: u32,
+    fog_start: f32,
+    fog_end: f32,
+    fog_density: f32,
+    color_flags: u32,
+    background_top: vec4<f32>,
+    background_bottom: vec4<f32>,
+    clip_planes: array<vec4<f32>, 4>,
+    clip_plane_count: u32,
+}
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+}
+
+struct InstanceInput {
+    @location(2) position: vec4<f32>, // xyz = position, w = size
+    @location(3) velocity: vec4<f32>,
+    @location(4) color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) color: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> global: GlobalUniform;
+
+@vertex
+fn vs_main(vertex: VertexInput, instance: InstanceInput) -> VertexOutput {
+    // Orthonormal basis with the dart's +X along the velocity (Z-up world)
+    let speed = length(instance.velocity.xyz);
+    let forward = select(vec3<f32>(1.0, 0.0, 0.0), instance.velocity.xyz / speed, speed > 1e-6);
+    let reference = select(vec3<f32>(0.0, 0.0, 1.0), vec3<f32>(0.0, 1.0, 0.0), abs(forward.z) > 0.99);
+    let left = normalize(cross(reference, forward));
+    let up = cross(forward, left);
+    let rotation = mat3x3<f32>(forward, left, up);
+
+    let world_position = instance.position.xyz + rotation * (vertex.position * instance.position.w);
+    return VertexOutput(
+        global.view_proj * vec4<f32>(world_position, 1.0),
+        world_position,
+        rotation * vertex.normal,
+        instance.color,
+    );
+}
+
+// Fraction of the fog color mixed in at a world position (see FogSettings::amount)
+fn fog_amount(world_position: vec3<f32>) -> f32 {
+    let distance = length(world_position - global.view_position.xyz);
+    if global.fog_mode == 1u {
+        let range = max(global.fog_end - global.fog_start, 1e-4);
+        return clamp((distance - global.fog_start) / range, 0.0, 1.0);
+    }
+    if global.fog_mode == 2u {
+        return 1.0 - exp(-max(global.fog_density, 0.0) * distance);
+    }
+    return 0.0;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let light_dir = normalize(global.light_position - in.world_position);
+    let ndotl = max(dot(normalize(in.world_normal), light_dir), 0.25);
+    let albedo = srgb_to_linear(in.color.rgb);
+    let lit_color = albedo * ndotl * global.light_color * global.light_intensity;
+    let fog_color = srgb_to_linear(global.fog_color);
+    let fogged = mix(lit_color, fog_color, fog_amount(in.world_position));
+    return vec4<f32>(encode_output(fogged, global.color_flags), in.color.a);
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dart_normals_point_outward() {
+        let (vertices, indices) = dart_mesh();
+        assert_eq!(indices.len(), vertices.len());
+        assert_eq!(indices.len() as u32, AgentRenderer::INDEX_COUNT);
+
+        // Centroid of the four corners
+        let center = [-0.15, 0.0, -0.005];
+        for triangle in vertices.chunks(3) {
+            let p = triangle[0].position;
+            let n = triangle[0].normal;
+            let outward =
+                (p[0] - center[0]) * n[0] + (p[1] - center[1]) * n[1] + (p[2] - center[2]) * n[2];
+            assert!(outward > 0.0);
+        }
+        assert_eq!(std::mem::size_of::<AgentInstance>(), 48);
+    }
+
+    #[test]
+    fn test_sphere_triangles_face_outward() {
+        let (vertices, indices) = sphere_mesh();
+        assert_eq!(indices.len() as u32, AgentRenderer::SPHERE_INDEX_COUNT);
+        for triangle in indices.chunks(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| vertices[triangle[i] as usize].position);
+            let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+            let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+            let n = [
+                u[1] * v[2] - u[2] * v[1],
+                u[2] * v[0] - u[0] * v[2],
+                u[0] * v[1] - u[1] * v[0],
+            ];
+            // Counter-clockwise seen from outside, so back-face culling keeps them
+            assert!(n[0] * a[0] + n[1] * a[1] + n[2] * a[2] > 0.0);
+        }
+    }
+}
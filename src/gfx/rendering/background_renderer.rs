@@ -0,0 +1,127 @@
+//! Background rendering
+//!
+//! Draws the vertical background gradient of the render settings as a
+//! fullscreen triangle at the start of the main pass. The colors come from
+//! the global uniforms; the triangle neither tests nor writes depth, so the
+//! scene is drawn over it.
+
+use wgpu::{BindGroup, Device, RenderPass, RenderPipeline};
+
+use crate::gfx::resources::global_bindings::GlobalBindings;
+
+use super::color::with_color_functions;
+
+/// Renderer for the background gradient
+pub struct BackgroundRenderer {
+    pipeline: RenderPipeline,
+}
+
+impl BackgroundRenderer {
+    pub fn new(
+        device: &Device,
+        surface_format: wgpu::TextureFormat,
+        global_bindings: &GlobalBindings,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Background Shader"),
+            source: wgpu::ShaderSource::Wgsl(with_color_functions(BACKGROUND_SHADER).into()),
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Background Pipeline Layout"),
+            bind_group_layouts: &[global_bindings.bind_group_layouts()],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Background Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        Self { pipeline }
+    }
+
+    pub fn render<'a>(
+        &'a self,
+        render_pass: &mut RenderPass<'a>,
+        global_bind_group: &'a BindGroup,
+    ) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, global_bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+const BACKGROUND_SHADER: &str = r#"
+struct GlobalUniform {
+    view_position: vec4<f32>,
+    view_proj: mat4x4<f32>,
+    light_position: vec3<f32>,
+    _padding1: f32,
+    light_color: vec3<f32>,
+    light_intensity: f32,
+    light_view_proj: mat4x4<f32>,
+    fog_color: vec3<f32>,
+    fog_mode: u32,
+    fog_start: f32,
+    fog_end: f32,
+    fog_density: f32,
+    color_flags: u32,
+    background_top: vec4<f32>,
+    background_bottom: vec4<f32>,
+    clip_planes: array<vec4<f32>, 4>,
+    clip_plane_count: u32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // 0 at the bottom of the view, 1 at the top
+    @location(0) height: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> global: GlobalUniform;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    // One triangle covering the whole view
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    let position = uv * 2.0 - 1.0;
+    return VertexOutput(vec4<f32>(position, 1.0, 1.0), uv.y);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let t = clamp(in.height, 0.0, 1.0);
+    let bottom = srgb_to_linear(global.background_bottom.rgb);
+    let top = srgb_to_linear(global.background_top.rgb);
+    return vec4<f32>(encode_output(mix(bottom, top, t), global.color_flags), 1.0);
+}
+"#;
@@ -0,0 +1,306 @@
+//! Frame capture
+//!
+//! [`CapturedFrame`] holds an RGBA image read back from the GPU, as produced
+//! by [`RenderEngine::capture_frame`](super::RenderEngine::capture_frame), and
+//! encodes it as PNG. The encoder writes uncompressed (stored) deflate blocks,
+//! which keeps it dependency-free at the cost of larger files. Frames can also
+//! be loaded from 8-bit, non-interlaced PNG files written by other tools, e.g.
+//! as references for golden-image tests.
+
+use std::path::Path;
+
+use super::inflate::zlib_decompress;
+use crate::error::{HaggisError, Result};
+
+/// RGBA8 image, rows top to bottom
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapturedFrame {
+    pub width: u32,
+    pub height: u32,
+    /// Four bytes per pixel, `width * height * 4` in total
+    pub rgba: Vec<u8>,
+}
+
+impl CapturedFrame {
+    /// Build a frame from rows of BGRA or RGBA texels in `format`
+    pub(crate) fn from_texels(
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+        mut texels: Vec<u8>,
+    ) -> Self {
+        if matches!(
+            format,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+        ) {
+            for pixel in texels.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+        Self {
+            width,
+            height,
+            rgba: texels,
+        }
+    }
+
+    /// Encode the frame as a PNG file
+    pub fn to_png(&self) -> Vec<u8> {
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&self.width.to_be_bytes());
+        header.extend_from_slice(&self.height.to_be_bytes());
+        // 8 bits per channel, RGBA, deflate, no filter, no interlace
+        header.extend_from_slice(&[8, 6, 0, 0, 0]);
+        write_chunk(&mut png, b"IHDR", &header);
+
+        // Each scanline starts with filter type 0 (none)
+        let row_bytes = self.width as usize * 4;
+        let mut scanlines = Vec::with_capacity((row_bytes + 1) * self.height as usize);
+        for row in self.rgba.chunks_exact(row_bytes.max(1)) {
+            scanlines.push(0);
+            scanlines.extend_from_slice(row);
+        }
+        write_chunk(&mut png, b"IDAT", &zlib_stored(&scanlines));
+        write_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    /// Write the frame as a PNG file
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::Io`] if the file cannot be written and
+    /// [`HaggisError::NoFileSystem`] on platforms without file access.
+    pub fn save_png(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if !crate::platform::HAS_FILESYSTEM {
+            return Err(HaggisError::NoFileSystem { path: path.into() });
+        }
+        std::fs::write(path, self.to_png()).map_err(|source| HaggisError::Io {
+            path: path.into(),
+            source,
+        })
+    }
+
+    /// Decode an 8-bit grayscale, RGB or RGBA PNG file
+    ///
+    /// Interlaced images and other bit depths are not supported.
+    pub fn from_png(png: &[u8]) -> std::result::Result<Self, String> {
+        let mut chunks = png
+            .strip_prefix(b"\x89PNG\r\n\x1a\n")
+            .ok_or("not a PNG file")?;
+        let mut header = None;
+        let mut idat = Vec::new();
+        while chunks.len() >= 12 {
+            let len = u32::from_be_bytes(chunks[..4].try_into().unwrap()) as usize;
+            let data = chunks.get(8..8 + len).ok_or("truncated chunk")?;
+            match &chunks[4..8] {
+                b"IHDR" => header = Some(data),
+                b"IDAT" => idat.extend_from_slice(data),
+                b"IEND" => break,
+                _ => {}
+            }
+            chunks = &chunks[(12 + len).min(chunks.len())..];
+        }
+
+        let header = header.filter(|h| h.len() == 13).ok_or("missing IHDR chunk")?;
+        let width = u32::from_be_bytes(header[..4].try_into().unwrap());
+        let height = u32::from_be_bytes(header[4..8].try_into().unwrap());
+        let channels = match (header[8], header[9]) {
+            (8, 0) => 1,
+            (8, 4) => 2,
+            (8, 2) => 3,
+            (8, 6) => 4,
+            (depth, color) => {
+                return Err(format!(
+                    "unsupported bit depth {depth} with color type {color}"
+                ))
+            }
+        };
+        if header[12] != 0 {
+            return Err("interlaced PNGs are not supported".to_string());
+        }
+
+        let data = zlib_decompress(&idat)?;
+        let stride = width as usize * channels;
+        if data.len() < (stride + 1) * height as usize {
+            return Err("image data is truncated".to_string());
+        }
+        let mut pixels = vec![0u8; stride * height as usize];
+        for (y, line) in data.chunks_exact(stride + 1).take(height as usize).enumerate() {
+            let (previous, current) = pixels.split_at_mut(y * stride);
+            let above = (y > 0).then(|| &previous[(y - 1) * stride..]);
+            unfilter(line[0], &line[1..], above, &mut current[..stride], channels)?;
+        }
+
+        let rgba = pixels
+            .chunks_exact(channels)
+            .flat_map(|pixel| match *pixel {
+                [gray] => [gray, gray, gray, 255],
+                [gray, alpha] => [gray, gray, gray, alpha],
+                [r, g, b] => [r, g, b, 255],
+                [r, g, b, a] => [r, g, b, a],
+                _ => unreachable!(),
+            })
+            .collect();
+        Ok(Self {
+            width,
+            height,
+            rgba,
+        })
+    }
+
+    /// Read a PNG file written by [`save_png`](Self::save_png) or another encoder
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::Io`] if the file cannot be read and
+    /// [`HaggisError::Image`] if it isn't a supported PNG.
+    pub fn load_png(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let png = std::fs::read(path).map_err(|source| HaggisError::Io {
+            path: path.into(),
+            source,
+        })?;
+        Self::from_png(&png).map_err(|message| HaggisError::Image {
+            path: path.into(),
+            message,
+        })
+    }
+}
+
+/// Undo a PNG scanline filter into `out`
+fn unfilter(
+    filter: u8,
+    line: &[u8],
+    above: Option<&[u8]>,
+    out: &mut [u8],
+    bpp: usize,
+) -> std::result::Result<(), String> {
+    for i in 0..line.len() {
+        let left = if i >= bpp { out[i - bpp] } else { 0 };
+        let up = above.map_or(0, |above| above[i]);
+        let up_left = match above {
+            Some(above) if i >= bpp => above[i - bpp],
+            _ => 0,
+        };
+        let predictor = match filter {
+            0 => 0,
+            1 => left,
+            2 => up,
+            3 => ((left as u16 + up as u16) / 2) as u8,
+            4 => paeth(left, up, up_left),
+            _ => return Err(format!("invalid filter type {filter}")),
+        };
+        out[i] = line[i].wrapping_add(predictor);
+    }
+    Ok(())
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs());
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Zlib stream of uncompressed deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = 65535;
+    let mut out = Vec::with_capacity(data.len() + data.len() / MAX_BLOCK * 5 + 11);
+    out.extend_from_slice(&[0x78, 0x01]);
+
+    let mut blocks = data.chunks(MAX_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        out.push(last as u8);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+
+    #[test]
+    fn test_png_layout() {
+        let frame = CapturedFrame::from_texels(
+            2,
+            1,
+            wgpu::TextureFormat::Bgra8Unorm,
+            vec![1, 2, 3, 4, 5, 6, 7, 8],
+        );
+        assert_eq!(frame.rgba, vec![3, 2, 1, 4, 7, 6, 5, 8]);
+
+        let png = frame.to_png();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+        // One filter byte and 8 pixel bytes, stored in one final block
+        let idat = &png[33..];
+        assert_eq!(&idat[4..8], b"IDAT");
+        assert_eq!(&idat[8..15], &[0x78, 0x01, 1, 9, 0, 0xF6, 0xFF]);
+
+        assert_eq!(CapturedFrame::from_png(&png).unwrap(), frame);
+        assert!(CapturedFrame::from_png(&png[..40]).is_err());
+    }
+}
@@ -0,0 +1,92 @@
+//! # Clip Planes
+//!
+//! Half-space cuts applied to solid geometry in the scene pass. Each
+//! [`ClipPlane`] removes everything on the side its normal points to, so
+//! meshes inside a simulation domain can be opened up to reveal a
+//! [`CutPlane2D`](crate::visualization::CutPlane2D) placed within them.
+//!
+//! Planes come from `scene.clip_planes` and from cut planes with geometry
+//! clipping enabled, and are written into the global uniforms every frame.
+//! Up to [`MAX_CLIP_PLANES`] planes are applied; scene objects and the
+//! instanced grid are clipped, while shadows are still cast by the full
+//! geometry.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use cgmath::Vector3;
+//! use haggis::gfx::rendering::ClipPlane;
+//!
+//! let mut app = haggis::default();
+//! // Remove everything above z = 0.25
+//! app.app_state.scene.clip_planes.push(ClipPlane::new(
+//!     Vector3::new(0.0, 0.0, 0.25),
+//!     Vector3::unit_z(),
+//! ));
+//! ```
+
+use cgmath::{InnerSpace, Vector3};
+
+/// Number of clip planes the shaders apply
+pub const MAX_CLIP_PLANES: usize = 4;
+
+/// Plane cutting away the geometry on the side its normal points to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClipPlane {
+    /// Unit normal pointing towards the removed half-space
+    pub normal: Vector3<f32>,
+    /// Distance of the plane from the origin along `normal`
+    pub offset: f32,
+}
+
+impl ClipPlane {
+    /// Plane through `point` removing everything `normal` points to
+    pub fn new(point: Vector3<f32>, normal: Vector3<f32>) -> Self {
+        let normal = normal.normalize();
+        Self {
+            normal,
+            offset: normal.dot(point),
+        }
+    }
+
+    /// The same plane removing the opposite half-space
+    pub fn flipped(&self) -> Self {
+        Self {
+            normal: -self.normal,
+            offset: -self.offset,
+        }
+    }
+
+    /// Distance of `point` from the plane, positive on the removed side
+    pub fn signed_distance(&self, point: Vector3<f32>) -> f32 {
+        self.normal.dot(point) - self.offset
+    }
+
+    /// Whether `point` is cut away by this plane
+    pub fn clips(&self, point: Vector3<f32>) -> bool {
+        self.signed_distance(point) > 0.0
+    }
+
+    /// Plane as `(normal, offset)`, the layout the shaders use
+    pub fn to_vec4(self) -> [f32; 4] {
+        [self.normal.x, self.normal.y, self.normal.z, self.offset]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plane_clips_side_of_its_normal() {
+        let plane = ClipPlane::new(Vector3::new(0.0, 0.0, 0.5), Vector3::new(0.0, 0.0, 2.0));
+        assert_eq!(plane.to_vec4(), [0.0, 0.0, 1.0, 0.5]);
+        assert!(plane.clips(Vector3::new(3.0, -1.0, 0.75)));
+        assert!(!plane.clips(Vector3::new(3.0, -1.0, 0.25)));
+        assert_eq!(plane.signed_distance(Vector3::new(0.0, 0.0, 0.0)), -0.5);
+
+        let below = plane.flipped();
+        assert!(below.clips(Vector3::new(0.0, 0.0, 0.25)));
+        assert!(!below.clips(Vector3::new(0.0, 0.0, 0.75)));
+    }
+}
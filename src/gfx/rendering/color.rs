@@ -0,0 +1,192 @@
+//! Color management
+//!
+//! The engine shades in linear space and applies the sRGB transfer curve
+//! exactly once, when a pass writes its color target:
+//!
+//! - Material base and emissive colors are linear.
+//! - Colors picked for display are sRGB: line, agent and instance colors,
+//!   colormap output, clear, background and fog colors, and UI colors.
+//!   Shaders convert them to linear before lighting, fog or blending.
+//! - On an sRGB surface (`*UnormSrgb`) the hardware applies the curve on
+//!   write. On a plain `Unorm` surface the shaders apply it themselves, so
+//!   both look the same. [`SurfaceGamma`] picks which kind of surface the
+//!   engine asks for.
+//!
+//! Shaders get the target's encoding through `color_flags` in the global
+//! uniform and call `encode_output` from [`COLOR_WGSL`] on their final color.
+//! [`RenderSettings::linear_debug`](super::RenderSettings::linear_debug)
+//! skips the curve to show raw linear values, which makes a pass that
+//! handles gamma differently from the others stand out.
+
+/// Kind of window surface the engine asks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SurfaceGamma {
+    /// A `Unorm` surface; shaders apply the sRGB curve (the default)
+    #[default]
+    Shader,
+    /// An `UnormSrgb` surface; the hardware applies the sRGB curve and blends
+    /// in linear space
+    Hardware,
+}
+
+impl SurfaceGamma {
+    /// Pick a surface format from those the surface supports, falling back
+    /// to the first one if none matches
+    pub fn choose_format(self, formats: &[wgpu::TextureFormat]) -> wgpu::TextureFormat {
+        let want_srgb = self == SurfaceGamma::Hardware;
+        formats
+            .iter()
+            .copied()
+            .find(|format| format.is_srgb() == want_srgb)
+            .unwrap_or(formats[0])
+    }
+}
+
+/// Shaders apply the sRGB curve themselves, the target is `Unorm`
+pub const COLOR_ENCODE_SRGB: u32 = 1;
+/// Show linear values without the sRGB curve
+pub const COLOR_DEBUG_LINEAR: u32 = 2;
+
+/// `color_flags` for a pass writing to `target_format`
+pub fn color_flags(target_format: wgpu::TextureFormat, linear_debug: bool) -> u32 {
+    let mut flags = 0;
+    if !target_format.is_srgb() {
+        flags |= COLOR_ENCODE_SRGB;
+    }
+    if linear_debug {
+        flags |= COLOR_DEBUG_LINEAR;
+    }
+    flags
+}
+
+/// sRGB-encoded channel to linear
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Linear channel to sRGB-encoded
+pub fn linear_to_srgb(value: f32) -> f32 {
+    let value = value.max(0.0);
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Value to write to a target for a linear channel, mirrors `encode_output`
+/// in [`COLOR_WGSL`]
+pub fn encode_output(linear: f32, flags: u32) -> f32 {
+    let debug = flags & COLOR_DEBUG_LINEAR != 0;
+    match (flags & COLOR_ENCODE_SRGB != 0, debug) {
+        (true, false) => linear_to_srgb(linear),
+        (true, true) => linear,
+        // The hardware encodes, so decode once more to show raw linear values
+        (false, true) => srgb_to_linear(linear),
+        (false, false) => linear,
+    }
+}
+
+/// Clear value for an sRGB display color, e.g. a clear color from the UI
+pub fn clear_color(color: wgpu::Color, flags: u32) -> wgpu::Color {
+    let channel = |value: f64| encode_output(srgb_to_linear(value as f32), flags) as f64;
+    wgpu::Color {
+        r: channel(color.r),
+        g: channel(color.g),
+        b: channel(color.b),
+        a: color.a,
+    }
+}
+
+/// WGSL color functions, prepended to scene shaders with [`with_color_functions`]
+pub const COLOR_WGSL: &str = r#"
+const COLOR_ENCODE_SRGB: u32 = 1u;
+const COLOR_DEBUG_LINEAR: u32 = 2u;
+
+fn srgb_to_linear(color: vec3<f32>) -> vec3<f32> {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, color <= vec3<f32>(0.04045));
+}
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+// Value to write to the color target for a linear color
+fn encode_output(color: vec3<f32>, flags: u32) -> vec3<f32> {
+    let linear = max(color, vec3<f32>(0.0));
+    let debug = (flags & COLOR_DEBUG_LINEAR) != 0u;
+    if (flags & COLOR_ENCODE_SRGB) != 0u {
+        if debug {
+            return linear;
+        }
+        return linear_to_srgb(linear);
+    }
+    if debug {
+        return srgb_to_linear(linear);
+    }
+    return linear;
+}
+"#;
+
+/// Shader source with [`COLOR_WGSL`] in front
+pub fn with_color_functions(source: &str) -> String {
+    format!("{COLOR_WGSL}\n{source}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_looks_the_same_on_srgb_and_unorm_targets() {
+        for value in [0.0, 0.002, 0.18, 0.5, 1.0] {
+            assert!((linear_to_srgb(srgb_to_linear(value)) - value).abs() < 1e-5);
+
+            // What the display shows: the written value, or its encoding on
+            // sRGB targets
+            let unorm = encode_output(value, color_flags(wgpu::TextureFormat::Bgra8Unorm, false));
+            let srgb = linear_to_srgb(encode_output(
+                value,
+                color_flags(wgpu::TextureFormat::Bgra8UnormSrgb, false),
+            ));
+            assert!((unorm - srgb).abs() < 1e-5);
+
+            let unorm = encode_output(value, color_flags(wgpu::TextureFormat::Bgra8Unorm, true));
+            let srgb = linear_to_srgb(encode_output(
+                value,
+                color_flags(wgpu::TextureFormat::Bgra8UnormSrgb, true),
+            ));
+            assert!((unorm - value).abs() < 1e-5);
+            assert!((srgb - value).abs() < 1e-5);
+        }
+
+        let formats = [
+            wgpu::TextureFormat::Bgra8UnormSrgb,
+            wgpu::TextureFormat::Bgra8Unorm,
+        ];
+        assert_eq!(SurfaceGamma::Shader.choose_format(&formats), formats[1]);
+        assert_eq!(SurfaceGamma::Hardware.choose_format(&formats), formats[0]);
+        assert_eq!(
+            SurfaceGamma::Hardware.choose_format(&formats[1..]),
+            formats[1]
+        );
+    }
+
+    #[test]
+    fn test_shaders_validate_with_color_functions() {
+        use crate::wgpu_utils::shader::validate_wgsl;
+
+        let visualization =
+            include_str!("../../visualization/rendering/shaders/visualization.wgsl");
+        validate_wgsl("pbr", &with_color_functions(include_str!("pbr.wgsl"))).unwrap();
+        validate_wgsl("visualization", &with_color_functions(visualization)).unwrap();
+    }
+}
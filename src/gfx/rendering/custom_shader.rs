@@ -0,0 +1,181 @@
+//! Custom material shaders
+//!
+//! A material can replace PBR shading with its own WGSL, for stylized views
+//! of simulation geometry such as toon shading or false color by height,
+//! without forking the engine's pipelines. The engine puts [`PRELUDE_WGSL`]
+//! in front of the source, which binds the same groups as the PBR pipeline:
+//!
+//! - group 0, `global`: camera, light, fog and clip planes
+//! - group 1, `transforms`: model matrices, indexed by instance
+//! - group 2, `material`, `diffuse_texture` and `diffuse_sampler`, fragment
+//!   stage only
+//! - group 3, `shadow_map`, `shadow_sampler` and the [`projector`](super::projector)
+//!   bindings, fragment stage only
+//!
+//! It also declares `VertexInput`, `VertexOutput`, `default_vertex`,
+//! `is_clipped`, `fog_amount`, `apply_projector`, `triplanar_sample` and the
+//! [`color`](super::color) functions. The
+//! source defines `fs_main(in: VertexOutput) -> @location(0) vec4<f32>` and
+//! returns its color through `encode_output(color, global.color_flags)`. It
+//! may define its own `vs_main`; without one the PBR vertex stage is used.
+//! `in.opacity` carries the object's [`opacity`](crate::gfx::scene::Object::opacity)
+//! for shaders that want to fade with it.
+//!
+//! Shadows and the depth pre-pass still use the unmodified mesh, so a vertex
+//! stage that moves vertices doesn't move their shadow. Objects with a
+//! custom shader skip the pre-pass for that reason.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! # fn run(scene: &mut haggis::gfx::scene::Scene) -> haggis::error::Result<()> {
+//! // toon.wgsl:
+//! // @fragment
+//! // fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+//! //     let light = dot(normalize(in.world_normal), normalize(global.light_position - in.world_position));
+//! //     let band = select(0.4, 1.0, light > 0.3);
+//! //     return vec4<f32>(encode_output(material.base_color.rgb * band, global.color_flags), 1.0);
+//! // }
+//! let material = scene.material_manager.create_material("Toon");
+//! material.set_shader("toon.wgsl")?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::path::Path;
+
+use super::color::with_color_functions;
+use crate::{
+    error::{HaggisError, Result},
+    wgpu_utils::shader::validate_wgsl,
+};
+
+/// Bindings, vertex stage and helpers in front of every custom shader
+pub const PRELUDE_WGSL: &str = include_str!("custom_shader_prelude.wgsl");
+
+/// WGSL source replacing the PBR shading of a material
+///
+/// Materials with the same label share one pipeline, so the label should
+/// name the source uniquely; [`CustomShader::load`] uses the file path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CustomShader {
+    label: String,
+    source: String,
+}
+
+impl CustomShader {
+    pub fn new(label: &str, source: impl Into<String>) -> Self {
+        Self {
+            label: label.to_string(),
+            source: source.into(),
+        }
+    }
+
+    /// Read a shader from a WGSL file and check that it compiles
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::Io`] if the file cannot be read,
+    /// [`HaggisError::Shader`] if it doesn't compile with the prelude, and
+    /// [`HaggisError::NoFileSystem`] on platforms without file access.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if !crate::platform::HAS_FILESYSTEM {
+            return Err(HaggisError::NoFileSystem { path: path.into() });
+        }
+        let source = std::fs::read_to_string(path).map_err(|source| HaggisError::Io {
+            path: path.into(),
+            source,
+        })?;
+        let shader = Self::new(&path.display().to_string(), source);
+        shader.validate()?;
+        Ok(shader)
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Full module source: the prelude, the custom source and the default
+    /// vertex stage if the source has none
+    pub fn compose(&self) -> String {
+        let mut source = format!("{PRELUDE_WGSL}\n{}\n", self.source);
+        if !self.source.contains("fn vs_main") {
+            source.push_str(
+                "@vertex\n\
+                 fn vs_main(model: VertexInput, @builtin(instance_index) instance: u32) -> VertexOutput {\n    \
+                 return default_vertex(model, instance);\n\
+                 }\n",
+            );
+        }
+        with_color_functions(&source)
+    }
+
+    /// Check that the composed module compiles
+    pub fn validate(&self) -> Result<()> {
+        validate_wgsl(&self.label, &self.compose())
+    }
+
+    /// Name of the shader module in the pipeline manager
+    pub(crate) fn module_name(&self) -> String {
+        format!("Custom {}", self.label)
+    }
+
+    /// Name of the pipeline drawing opaque or transparent materials
+    pub(crate) fn pipeline_name(&self, transparent: bool) -> String {
+        if transparent {
+            format!("Custom {} Transparent", self.label)
+        } else {
+            format!("Custom {}", self.label)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_shaders_compile_with_the_prelude() {
+        let flat = CustomShader::new(
+            "flat",
+            "@fragment\n\
+             fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {\n\
+                 return vec4<f32>(encode_output(material.base_color.rgb, global.color_flags), 1.0);\n\
+             }\n",
+        );
+        flat.validate().unwrap();
+        assert!(flat
+            .compose()
+            .contains("return default_vertex(model, instance);"));
+
+        // A vertex stage of its own replaces the default one
+        let inflated = CustomShader::new(
+            "inflated",
+            "@vertex\n\
+             fn vs_main(model: VertexInput, @builtin(instance_index) instance: u32) -> VertexOutput {\n\
+                 var moved = model;\n\
+                 moved.position = model.position + model.normal * 0.1;\n\
+                 return default_vertex(moved, instance);\n\
+             }\n\
+             @fragment\n\
+             fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {\n\
+                 return vec4<f32>(in.world_normal * 0.5 + 0.5, 1.0);\n\
+             }\n",
+        );
+        inflated.validate().unwrap();
+        assert!(!inflated
+            .compose()
+            .contains("return default_vertex(model, instance);"));
+
+        let broken = CustomShader::new("broken", "fn fs_main() -> f32 { return missing; }");
+        assert!(matches!(
+            broken.validate(),
+            Err(HaggisError::Shader { label, .. }) if label == "broken"
+        ));
+    }
+}
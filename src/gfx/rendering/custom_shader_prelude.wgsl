@@ -0,0 +1,154 @@
+// Custom material shader prelude - the PBR pipeline's bindings and vertex stage
+struct GlobalUniform {
+    view_position: vec4<f32>,
+    view_proj: mat4x4<f32>,
+    light_position: vec3<f32>,
+    _padding1: f32,
+    light_color: vec3<f32>,
+    light_intensity: f32,
+    light_view_proj: mat4x4<f32>,
+    fog_color: vec3<f32>,
+    fog_mode: u32,
+    fog_start: f32,
+    fog_end: f32,
+    fog_density: f32,
+    color_flags: u32,
+    background_top: vec4<f32>,
+    background_bottom: vec4<f32>,
+    clip_planes: array<vec4<f32>, 4>,
+    clip_plane_count: u32,
+};
+
+struct Material {
+    base_color: vec4<f32>,
+    metallic: f32,
+    roughness: f32,
+    normal_scale: f32,
+    occlusion_strength: f32,
+    emissive: vec3<f32>,
+    // 1 when the diffuse texture is mapped triplanar (see TextureMapping)
+    texture_mode: u32,
+    triplanar_scale: f32,
+    triplanar_sharpness: f32,
+    // Mirror reflection of the sky, see Material::reflectance
+    reflectance: f32,
+    _padding: f32,
+};
+
+// Model matrix and opacity of every object, see transform_buffer.rs
+struct ObjectTransform {
+    model: mat4x4<f32>,
+    opacity: f32,
+};
+
+@group(0) @binding(0) var<uniform> global: GlobalUniform;
+// Transforms of all objects, indexed by the object's draw instance
+@group(1) @binding(0) var<storage, read> transforms: array<ObjectTransform>;
+// Material bindings are only visible to the fragment stage
+@group(2) @binding(0) var<uniform> material: Material;
+@group(2) @binding(1) var diffuse_texture: texture_2d<f32>;
+@group(2) @binding(2) var diffuse_sampler: sampler;
+@group(3) @binding(0) var shadow_map: texture_depth_2d;
+@group(3) @binding(1) var shadow_sampler: sampler_comparison;
+
+// Image projected onto geometry along an axis, see gfx::rendering::projector
+struct Projector {
+    world_to_image: mat4x4<f32>,
+    opacity: f32,
+    range: f32,
+    enabled: u32,
+    decode_srgb: u32,
+};
+
+@group(3) @binding(2) var<uniform> projector: Projector;
+@group(3) @binding(3) var projector_texture: texture_2d<f32>;
+@group(3) @binding(4) var projector_sampler: sampler;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_normal: vec3<f32>,
+    @location(1) world_position: vec3<f32>,
+    @location(2) light_space_position: vec4<f32>,
+    // Object opacity, multiplied into the material alpha
+    @location(3) @interpolate(flat) opacity: f32,
+};
+
+// The PBR vertex stage, used when the custom shader has no vs_main
+fn default_vertex(model: VertexInput, instance: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let model_matrix = transforms[instance].model;
+
+    let world_position = model_matrix * vec4<f32>(model.position, 1.0);
+    out.world_position = world_position.xyz;
+    out.clip_position = global.view_proj * world_position;
+    out.light_space_position = global.light_view_proj * world_position;
+    out.opacity = transforms[instance].opacity;
+
+    let normal_matrix = mat3x3<f32>(
+        normalize(model_matrix[0].xyz),
+        normalize(model_matrix[1].xyz),
+        normalize(model_matrix[2].xyz)
+    );
+    out.world_normal = normalize(normal_matrix * model.normal);
+
+    return out;
+}
+
+// Whether a world position is cut away by one of the clip planes
+fn is_clipped(world_position: vec3<f32>) -> bool {
+    for (var i = 0u; i < global.clip_plane_count; i = i + 1u) {
+        let plane = global.clip_planes[i];
+        if dot(plane.xyz, world_position) > plane.w {
+            return true;
+        }
+    }
+    return false;
+}
+
+// Fraction of the fog color mixed in at a world position (see FogSettings::amount)
+fn fog_amount(world_position: vec3<f32>) -> f32 {
+    let distance = length(world_position - global.view_position.xyz);
+    if global.fog_mode == 1u {
+        let range = max(global.fog_end - global.fog_start, 1e-4);
+        return clamp((distance - global.fog_start) / range, 0.0, 1.0);
+    }
+    if global.fog_mode == 2u {
+        return 1.0 - exp(-max(global.fog_density, 0.0) * distance);
+    }
+    return 0.0;
+}
+
+// Linear color with the projected image laid over it (see Projector::project)
+fn apply_projector(color: vec3<f32>, world_position: vec3<f32>) -> vec3<f32> {
+    if projector.enabled == 0u {
+        return color;
+    }
+    let image = (projector.world_to_image * vec4<f32>(world_position, 1.0)).xyz;
+    if any(image.xy < vec2<f32>(0.0)) || any(image.xy > vec2<f32>(1.0))
+        || image.z < 0.0 || image.z > projector.range {
+        return color;
+    }
+    let texel = textureSampleLevel(projector_texture, projector_sampler, image.xy, 0.0);
+    var projected = texel.rgb;
+    if projector.decode_srgb != 0u {
+        projected = srgb_to_linear(projected);
+    }
+    return mix(color, projected, texel.a * projector.opacity);
+}
+
+// Diffuse texture projected along the world axes, blended by the normal.
+// Unwrapped coordinates give the gradients so tiling has no seams.
+fn triplanar_sample(world_position: vec3<f32>, normal: vec3<f32>) -> vec4<f32> {
+    let p = world_position * material.triplanar_scale;
+    var weights = pow(abs(normal), vec3<f32>(material.triplanar_sharpness));
+    weights = weights / max(weights.x + weights.y + weights.z, 1e-4);
+    let x = textureSampleGrad(diffuse_texture, diffuse_sampler, fract(p.yz), dpdx(p.yz), dpdy(p.yz));
+    let y = textureSampleGrad(diffuse_texture, diffuse_sampler, fract(p.xz), dpdx(p.xz), dpdy(p.xz));
+    let z = textureSampleGrad(diffuse_texture, diffuse_sampler, fract(p.xy), dpdx(p.xy), dpdy(p.xy));
+    return x * weights.x + y * weights.y + z * weights.z;
+}
@@ -0,0 +1,128 @@
+//! Depth buffer conventions
+//!
+//! By default nearer surfaces have smaller depth: the depth buffer clears to
+//! 1 and fragments pass with `Less`. With [`DepthMode::Reverse`] the camera
+//! projection maps the near plane to 1 and the far plane to 0, the buffer
+//! clears to 0 and fragments pass with `Greater`. The 32-bit float depth
+//! buffer is most precise near 0, which reverse-Z spends on distant geometry,
+//! so coplanar markers and ground planes far from a small near plane stop
+//! Z-fighting in large simulation domains.
+//!
+//! The mode is chosen once, with [`RenderConfig::depth_mode`](super::RenderConfig::depth_mode),
+//! because pipelines bake in their depth test. The shadow map keeps the
+//! standard convention either way: its orthographic light projection already
+//! spreads precision evenly, so shadow comparisons are unchanged.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::gfx::rendering::DepthMode;
+//!
+//! let app = haggis::HaggisApp::builder()
+//!     .depth_mode(DepthMode::Reverse)
+//!     .build();
+//! ```
+
+use cgmath::Matrix4;
+
+/// Which end of the depth range is near the camera
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DepthMode {
+    /// Near plane at depth 0, far plane at 1 (the default)
+    #[default]
+    Standard,
+    /// Near plane at depth 1, far plane at 0
+    Reverse,
+}
+
+impl DepthMode {
+    /// Value the depth buffer is cleared to, the depth of the far plane
+    pub fn clear_value(self) -> f32 {
+        match self {
+            DepthMode::Standard => 1.0,
+            DepthMode::Reverse => 0.0,
+        }
+    }
+
+    /// Depth test in this mode for `compare`, written for the standard mode
+    ///
+    /// `Less` becomes `Greater`, `LessEqual` becomes `GreaterEqual` and so
+    /// on; tests that don't depend on the order stay as they are.
+    pub fn compare(self, compare: wgpu::CompareFunction) -> wgpu::CompareFunction {
+        use wgpu::CompareFunction::*;
+        match (self, compare) {
+            (DepthMode::Standard, compare) => compare,
+            (DepthMode::Reverse, Less) => Greater,
+            (DepthMode::Reverse, LessEqual) => GreaterEqual,
+            (DepthMode::Reverse, Greater) => Less,
+            (DepthMode::Reverse, GreaterEqual) => LessEqual,
+            (DepthMode::Reverse, compare) => compare,
+        }
+    }
+
+    /// Normalized device depth of the near and far planes
+    pub fn near_far(self) -> (f32, f32) {
+        match self {
+            DepthMode::Standard => (0.0, 1.0),
+            DepthMode::Reverse => (1.0, 0.0),
+        }
+    }
+
+    /// `projection`, a perspective projection with wgpu's 0 to 1 depth range,
+    /// in this mode
+    ///
+    /// The reversed depth row is built from `znear` and `zfar` directly
+    /// rather than by flipping the standard one, which would lose the
+    /// precision reverse-Z is meant to gain.
+    pub fn projection(self, projection: Matrix4<f32>, znear: f32, zfar: f32) -> Matrix4<f32> {
+        if self == DepthMode::Standard {
+            return projection;
+        }
+        // Row 2 of the column-major matrix: depth 1 at znear, 0 at zfar
+        let mut projection = projection;
+        let range = zfar - znear;
+        projection.x.z = 0.0;
+        projection.y.z = 0.0;
+        projection.z.z = znear / range;
+        projection.w.z = znear * zfar / range;
+        projection
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{Rad, Vector4};
+
+    #[test]
+    fn test_reverse_projection_maps_near_to_one_and_far_to_zero() {
+        let (znear, zfar) = (0.1, 1000.0);
+        let standard = crate::gfx::camera::orbit_camera::OPENGL_TO_WGPU_MATRIX
+            * cgmath::perspective(Rad(1.0), 1.5, znear, zfar);
+        let reverse = DepthMode::Reverse.projection(standard, znear, zfar);
+        let depth = |projection: Matrix4<f32>, distance: f32| {
+            let clip = projection * Vector4::new(0.3, -0.2, -distance, 1.0);
+            clip.z / clip.w
+        };
+
+        assert!((depth(reverse, znear) - 1.0).abs() < 1e-6);
+        assert!(depth(reverse, zfar).abs() < 1e-6);
+        assert!(depth(reverse, 10.0) > depth(reverse, 11.0));
+        for distance in [0.1, 1.0, 50.0, 999.0] {
+            let sum = depth(standard, distance) + depth(reverse, distance);
+            assert!((sum - 1.0).abs() < 1e-4);
+        }
+        // x, y and w are unchanged, so picking rays and screen positions agree
+        assert_eq!(reverse.x, standard.x);
+        assert_eq!(reverse.z.w, standard.z.w);
+
+        assert_eq!(
+            DepthMode::Reverse.compare(wgpu::CompareFunction::LessEqual),
+            wgpu::CompareFunction::GreaterEqual
+        );
+        assert_eq!(
+            DepthMode::Reverse.compare(wgpu::CompareFunction::Always),
+            wgpu::CompareFunction::Always
+        );
+    }
+}
@@ -0,0 +1,65 @@
+// Depth pre-pass - writes the depth of opaque objects from the camera, no color
+struct GlobalUniform {
+    view_position: vec4<f32>,
+    view_proj: mat4x4<f32>,
+    light_position: vec3<f32>,
+    _padding1: f32,
+    light_color: vec3<f32>,
+    light_intensity: f32,
+    light_view_proj: mat4x4<f32>,
+    fog_color: vec3<f32>,
+    fog_mode: u32,
+    fog_start: f32,
+    fog_end: f32,
+    fog_density: f32,
+    color_flags: u32,
+    background_top: vec4<f32>,
+    background_bottom: vec4<f32>,
+    clip_planes: array<vec4<f32>, 4>,
+    clip_plane_count: u32,
+};
+
+// Model matrix and opacity of every object, see transform_buffer.rs
+struct ObjectTransform {
+    model: mat4x4<f32>,
+    opacity: f32,
+};
+
+@group(0) @binding(0) var<uniform> global: GlobalUniform;
+// Transforms of all objects, indexed by the object's draw instance
+@group(1) @binding(0) var<storage, read> transforms: array<ObjectTransform>;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+};
+
+// Same transform as the PBR vertex shader, so the depths match exactly
+@vertex
+fn vs_main(model: VertexInput, @builtin(instance_index) instance: u32) -> VertexOutput {
+    var out: VertexOutput;
+    let model_matrix = transforms[instance].model;
+
+    let world_position = model_matrix * vec4<f32>(model.position, 1.0);
+    out.world_position = world_position.xyz;
+    out.clip_position = global.view_proj * world_position;
+
+    return out;
+}
+
+// Clipped fragments must not write depth, or they would hide what the clip
+// plane reveals
+@fragment
+fn fs_main(in: VertexOutput) {
+    for (var i = 0u; i < global.clip_plane_count; i = i + 1u) {
+        let plane = global.clip_planes[i];
+        if dot(plane.xyz, in.world_position) > plane.w {
+            discard;
+        }
+    }
+}
@@ -0,0 +1,274 @@
+//! # Draw List
+//!
+//! Orders scene objects for drawing. Opaque objects are grouped by pipeline,
+//! then material, then mesh, so consecutive draws share pipelines and bind
+//! groups; objects built from clones of one [`Mesh`] share its [`MeshId`]
+//! and end up next to each other. Objects whose material
+//! has an alpha below 1, or with an [`opacity`](Object::opacity) below 1, go
+//! to a separate transparent list, sorted back to front from the camera,
+//! which the render engine draws last with blending and without depth writes.
+//! [Ghosted](Object::ghost) objects get a third list, also back to front,
+//! drawn faintly after everything else, cut planes and volumes included.
+//!
+//! [`DrawList::sort_front_to_back`] orders the opaque list nearest first
+//! instead, so early depth testing rejects hidden fragments when objects
+//! overlap a lot, at the cost of more material switches.
+
+use cgmath::{InnerSpace, Vector3};
+
+use crate::gfx::{
+    resources::material::Material,
+    scene::{
+        object::{Mesh, MeshId},
+        Layers, Object, Scene,
+    },
+};
+
+/// One object to draw
+#[derive(Clone, Copy)]
+pub struct DrawItem<'a> {
+    pub object: &'a Object,
+    pub material: &'a Material,
+    /// Slot of the object's model matrix and opacity in the scene transform buffer
+    pub transform_slot: u32,
+    /// Squared distance from the camera to the object's origin
+    pub distance2: f32,
+}
+
+impl DrawItem<'_> {
+    /// Whether the item uses the same material as `other`
+    pub fn shares_material(&self, other: &DrawItem) -> bool {
+        std::ptr::eq(self.material, other.material)
+    }
+
+    /// Whether the item blends with what is behind it, through its material
+    /// alpha or the object's opacity
+    pub fn is_transparent(&self) -> bool {
+        self.material.is_transparent() || self.object.opacity() < 1.0
+    }
+
+    /// Grouping key: custom shader pipeline first (PBR sorts first), then
+    /// material, then the first mesh
+    fn batch_key(&self) -> (Option<&str>, &str, Option<MeshId>) {
+        (
+            self.material.shader.as_ref().map(|shader| shader.label()),
+            &self.material.name,
+            self.object.meshes.first().map(Mesh::id),
+        )
+    }
+}
+
+/// Visible scene objects in draw order
+pub struct DrawList<'a> {
+    opaque: Vec<DrawItem<'a>>,
+    transparent: Vec<DrawItem<'a>>,
+    ghost: Vec<DrawItem<'a>>,
+}
+
+impl<'a> DrawList<'a> {
+    /// Sort the visible objects of `scene` for a camera at `eye` that draws
+    /// `layers`
+    pub fn build(scene: &'a Scene, eye: Vector3<f32>, layers: Layers) -> Self {
+        let mut opaque = Vec::new();
+        let mut transparent = Vec::new();
+        let mut ghost = Vec::new();
+
+        for (slot, object) in scene.objects.iter().enumerate() {
+            if !scene.is_object_visible(object) || !layers.intersects(object.layers) {
+                continue;
+            }
+            let material = scene.get_material_for_object(object);
+            let item = DrawItem {
+                object,
+                material,
+                transform_slot: slot as u32,
+                distance2: (object.transform.w.truncate() - eye).magnitude2(),
+            };
+            if object.ghost {
+                ghost.push(item);
+            } else if item.is_transparent() {
+                transparent.push(item);
+            } else {
+                opaque.push(item);
+            }
+        }
+
+        opaque.sort_by(|a, b| a.batch_key().cmp(&b.batch_key()));
+        transparent.sort_by(|a, b| b.distance2.total_cmp(&a.distance2));
+        ghost.sort_by(|a, b| b.distance2.total_cmp(&a.distance2));
+
+        Self {
+            opaque,
+            transparent,
+            ghost,
+        }
+    }
+
+    /// Order opaque objects nearest first instead of by material
+    pub fn sort_front_to_back(&mut self) {
+        self.opaque.sort_by(|a, b| a.distance2.total_cmp(&b.distance2));
+    }
+
+    /// Opaque objects, grouped by pipeline, material and mesh unless sorted
+    /// front to back
+    pub fn opaque(&self) -> &[DrawItem<'a>] {
+        &self.opaque
+    }
+
+    /// Transparent objects, farthest first
+    pub fn transparent(&self) -> &[DrawItem<'a>] {
+        &self.transparent
+    }
+
+    /// Ghosted objects, farthest first
+    pub fn ghost(&self) -> &[DrawItem<'a>] {
+        &self.ghost
+    }
+
+    /// Number of material bind group changes needed to draw each list once
+    pub fn material_switches(&self) -> usize {
+        [&self.opaque, &self.transparent, &self.ghost]
+            .iter()
+            .map(|items| {
+                items
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, item)| *i == 0 || !item.shares_material(&items[i - 1]))
+                    .count()
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gfx::camera::camera_utils::CameraManager;
+    use crate::gfx::camera::{camera_controller::CameraController, orbit_camera::OrbitCamera};
+
+    fn scene_with(objects: &[(&str, f32)]) -> Scene {
+        let camera = OrbitCamera::new(8.0, 0.4, 0.2, Vector3::new(0.0, 0.0, 0.0), 1.0);
+        let mut scene = Scene::new(CameraManager::new(
+            camera,
+            CameraController::new(0.005, 0.1),
+        ));
+        scene.add_material_rgb("red", 1.0, 0.0, 0.0, 0.0, 0.5);
+        scene.add_material("glass", [0.6, 0.8, 1.0, 0.3], 0.0, 0.1);
+        for (material, x) in objects {
+            let mut object = Object::new(Vec::new());
+            object.set_material(material);
+            object.set_translation(Vector3::new(*x, 0.0, 0.0));
+            scene.objects.push(object);
+        }
+        scene
+    }
+
+    #[test]
+    fn test_opaque_objects_are_grouped_by_material() {
+        let scene = scene_with(&[
+            ("red", 0.0),
+            ("default", 1.0),
+            ("red", 2.0),
+            ("default", 3.0),
+        ]);
+        let list = DrawList::build(&scene, Vector3::new(0.0, 0.0, 0.0), Layers::ALL);
+        assert_eq!(list.opaque().len(), 4);
+        assert!(list.transparent().is_empty());
+        // Two switches instead of four in scene order
+        assert_eq!(list.material_switches(), 2);
+    }
+
+    #[test]
+    fn test_opaque_objects_are_grouped_by_pipeline_and_mesh() {
+        use crate::gfx::rendering::custom_shader::CustomShader;
+
+        let mut scene = scene_with(&[("red", 0.0), ("red", 1.0), ("red", 2.0), ("default", 3.0)]);
+        let cube = Mesh::from_geometry(&crate::gfx::geometry::generate_cube());
+        let plane = Mesh::from_geometry(&crate::gfx::geometry::generate_plane(1.0, 1.0, 1, 1));
+        scene.objects[0].meshes = vec![cube.clone()];
+        scene.objects[1].meshes = vec![plane];
+        scene.objects[2].meshes = vec![cube];
+        scene
+            .material_manager
+            .get_material_mut(&"default".to_string())
+            .unwrap()
+            .shader = Some(CustomShader::new("Toon", "// toon"));
+
+        let list = DrawList::build(&scene, Vector3::new(0.0, 0.0, 0.0), Layers::ALL);
+        let slots: Vec<u32> = list.opaque().iter().map(|item| item.transform_slot).collect();
+        // PBR before the custom pipeline, clones of the cube next to each other
+        assert_eq!(slots, vec![0, 2, 1, 3]);
+    }
+
+    #[test]
+    fn test_transparent_objects_are_sorted_back_to_front() {
+        let scene = scene_with(&[("glass", 1.0), ("red", 0.0), ("glass", 5.0), ("glass", 3.0)]);
+        let list = DrawList::build(&scene, Vector3::new(0.0, 0.0, 0.0), Layers::ALL);
+        let slots: Vec<u32> = list
+            .transparent()
+            .iter()
+            .map(|item| item.transform_slot)
+            .collect();
+        assert_eq!(slots, vec![2, 3, 0]);
+        assert_eq!(list.opaque().len(), 1);
+    }
+
+    #[test]
+    fn test_faded_objects_are_drawn_transparent() {
+        let mut scene = scene_with(&[("red", 1.0), ("red", 2.0), ("glass", 3.0)]);
+        scene.objects[0].set_opacity(0.4);
+        // Clamped back to opaque
+        scene.objects[1].set_opacity(1.5);
+        assert_eq!(scene.objects[1].opacity(), 1.0);
+
+        let list = DrawList::build(&scene, Vector3::new(0.0, 0.0, 0.0), Layers::ALL);
+        let slots: Vec<u32> = list
+            .transparent()
+            .iter()
+            .map(|item| item.transform_slot)
+            .collect();
+        assert_eq!(slots, vec![2, 0]);
+        assert_eq!(list.opaque().len(), 1);
+    }
+
+    #[test]
+    fn test_ghosts_are_drawn_apart() {
+        let mut scene = scene_with(&[("red", 1.0), ("glass", 2.0), ("red", 4.0), ("red", 3.0)]);
+        scene.objects[1].ghost = true;
+        scene.objects[2].ghost = true;
+        scene.objects[3].set_opacity(0.5);
+        let list = DrawList::build(&scene, Vector3::new(0.0, 0.0, 0.0), Layers::ALL);
+        let ghosts: Vec<u32> = list.ghost().iter().map(|item| item.transform_slot).collect();
+        assert_eq!(ghosts, vec![2, 1]);
+        assert_eq!(list.opaque().len(), 1);
+        assert_eq!(list.transparent().len(), 1);
+    }
+
+    #[test]
+    fn test_opaque_objects_sorted_front_to_back() {
+        let scene = scene_with(&[("red", 4.0), ("default", 1.0), ("red", 2.0), ("glass", 0.5)]);
+        let mut list = DrawList::build(&scene, Vector3::new(0.0, 0.0, 0.0), Layers::ALL);
+        list.sort_front_to_back();
+        let slots: Vec<u32> = list.opaque().iter().map(|item| item.transform_slot).collect();
+        assert_eq!(slots, vec![1, 2, 0]);
+        assert_eq!(list.transparent().len(), 1);
+    }
+
+    #[test]
+    fn test_hidden_layers_are_skipped() {
+        let mut scene = scene_with(&[("red", 0.0), ("red", 1.0), ("red", 2.0)]);
+        scene.objects[1].layers = Layers::BOUNDARY;
+        scene.objects[2].layers = Layers::REFERENCE;
+        let eye = Vector3::new(0.0, 0.0, 0.0);
+
+        // The camera leaves out reference geometry
+        let list = DrawList::build(&scene, eye, !Layers::REFERENCE);
+        assert_eq!(list.opaque().len(), 2);
+
+        // The scene hides boundary markers for every camera
+        scene.layers.set_visible(Layers::BOUNDARY, false);
+        let list = DrawList::build(&scene, eye, !Layers::REFERENCE);
+        let slots: Vec<u32> = list.opaque().iter().map(|item| item.transform_slot).collect();
+        assert_eq!(slots, vec![0]);
+    }
+}
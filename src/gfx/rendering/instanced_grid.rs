@@ -19,6 +19,9 @@ use crate::gfx::{
     resources::global_bindings::GlobalBindings,
 };
 
+use super::color::with_color_functions;
+use super::depth::DepthMode;
+
 /// Instance data for a single cube in the grid
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
@@ -159,11 +162,17 @@ impl InstancedGrid {
     }
 
     /// Initialize rendering pipeline (call this after creating global bindings)
-    pub fn initialize_pipeline(&mut self, device: &Device, surface_format: wgpu::TextureFormat, global_bindings: &GlobalBindings) {
+    pub fn initialize_pipeline(
+        &mut self,
+        device: &Device,
+        surface_format: wgpu::TextureFormat,
+        global_bindings: &GlobalBindings,
+        depth_mode: DepthMode,
+    ) {
         // Create instanced rendering pipeline
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Instanced Grid Shader"),
-            source: wgpu::ShaderSource::Wgsl(INSTANCED_GRID_SHADER.into()),
+            source: wgpu::ShaderSource::Wgsl(with_color_functions(INSTANCED_GRID_SHADER).into()),
         });
 
         let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -224,7 +233,7 @@ impl InstancedGrid {
             depth_stencil: Some(wgpu::DepthStencilState {
                 format: wgpu::TextureFormat::Depth32Float,
                 depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::Less,
+                depth_compare: depth_mode.compare(wgpu::CompareFunction::Less),
                 stencil: wgpu::StencilState::default(),
                 bias: wgpu::DepthBiasState::default(),
             }),
@@ -261,8 +270,7 @@ impl InstancedGrid {
         }
 
         let Some(ref pipeline) = self.render_pipeline else {
-            #[cfg(debug_assertions)]
-            println!("❌ Instanced grid render pipeline not found!");
+            tracing::warn!("Instanced grid render pipeline not found");
             return;
         };
 
@@ -308,6 +316,16 @@ struct GlobalUniform {
     light_color: vec3<f32>,
     light_intensity: f32,
     light_view_proj: mat4x4<f32>,
+    fog_color: vec3<f32>,
+    fog_mode: u32,
+    fog_start: f32,
+    fog_end: f32,
+    fog_density: f32,
+    color_flags: u32,
+    background_top: vec4<f32>,
+    background_bottom: vec4<f32>,
+    clip_planes: array<vec4<f32>, 4>,
+    clip_plane_count: u32,
 }
 
 struct VertexInput {
@@ -369,16 +387,47 @@ fn vs_main(
     );
 }
 
+// Whether a world position is cut away by one of the clip planes
+fn is_clipped(world_position: vec3<f32>) -> bool {
+    for (var i = 0u; i < global.clip_plane_count; i = i + 1u) {
+        let plane = global.clip_planes[i];
+        if dot(plane.xyz, world_position) > plane.w {
+            return true;
+        }
+    }
+    return false;
+}
+
+// Fraction of the fog color mixed in at a world position (see FogSettings::amount)
+fn fog_amount(world_position: vec3<f32>) -> f32 {
+    let distance = length(world_position - global.view_position.xyz);
+    if global.fog_mode == 1u {
+        let range = max(global.fog_end - global.fog_start, 1e-4);
+        return clamp((distance - global.fog_start) / range, 0.0, 1.0);
+    }
+    if global.fog_mode == 2u {
+        return 1.0 - exp(-max(global.fog_density, 0.0) * distance);
+    }
+    return 0.0;
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    if is_clipped(in.world_position) {
+        discard;
+    }
+
     let light_dir = normalize(global.light_position - in.world_position);
     let normal = normalize(in.world_normal);
     
     // Simple lighting
     let ndotl = max(dot(normal, light_dir), 0.2); // Minimum ambient
     
-    let lit_color = in.color.rgb * ndotl * global.light_color * global.light_intensity;
+    let albedo = srgb_to_linear(in.color.rgb);
+    let lit_color = albedo * ndotl * global.light_color * global.light_intensity;
     
-    return vec4<f32>(lit_color, in.color.a);
+    let fog_color = srgb_to_linear(global.fog_color);
+    let fogged = mix(lit_color, fog_color, fog_amount(in.world_position));
+    return vec4<f32>(encode_output(fogged, global.color_flags), in.color.a);
 }
 "#;
\ No newline at end of file
@@ -0,0 +1,197 @@
+//! Line rendering
+//!
+//! Draws world-space line lists, such as the reference grid and domain boxes,
+//! as depth-tested lines in the scene pass. Lines don't write depth, so
+//! transparent objects and the instanced grid still blend over them correctly.
+
+use std::ops::Range;
+
+use wgpu::{BindGroup, Buffer, Device, Queue, RenderPass, RenderPipeline};
+
+use crate::gfx::resources::global_bindings::GlobalBindings;
+
+use super::color::with_color_functions;
+use super::depth::DepthMode;
+
+/// Vertex of a line list; every two vertices form one line
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LineVertex {
+    pub position: [f32; 3],
+    pub color: [f32; 4],
+}
+
+impl LineVertex {
+    pub fn new(position: [f32; 3], color: [f32; 4]) -> Self {
+        Self { position, color }
+    }
+}
+
+/// Renderer for a list of colored world-space lines
+pub struct LineRenderer {
+    pipeline: RenderPipeline,
+    vertex_buffer: Option<Buffer>,
+    /// Vertices the buffer can hold
+    capacity: usize,
+    vertices: Vec<LineVertex>,
+}
+
+impl LineRenderer {
+    pub fn new(
+        device: &Device,
+        surface_format: wgpu::TextureFormat,
+        global_bindings: &GlobalBindings,
+        depth_mode: DepthMode,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Line Shader"),
+            source: wgpu::ShaderSource::Wgsl(with_color_functions(LINE_SHADER).into()),
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Line Pipeline Layout"),
+            bind_group_layouts: &[global_bindings.bind_group_layouts()],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Line Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<LineVertex>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x4],
+                }],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::LineList,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: depth_mode.compare(wgpu::CompareFunction::LessEqual),
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        Self {
+            pipeline,
+            vertex_buffer: None,
+            capacity: 0,
+            vertices: Vec::new(),
+        }
+    }
+
+    /// Replace the lines to draw, uploading only if they changed
+    pub fn set_lines(&mut self, device: &Device, queue: &Queue, vertices: &[LineVertex]) {
+        if self.vertices == vertices {
+            return;
+        }
+        self.vertices.clear();
+        self.vertices.extend_from_slice(vertices);
+        if vertices.is_empty() {
+            return;
+        }
+
+        if vertices.len() > self.capacity {
+            self.capacity = vertices.len().next_power_of_two();
+            self.vertex_buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Line Vertex Buffer"),
+                size: (self.capacity * std::mem::size_of::<LineVertex>()) as u64,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+        }
+        if let Some(ref buffer) = self.vertex_buffer {
+            queue.write_buffer(buffer, 0, bytemuck::cast_slice(vertices));
+        }
+    }
+
+    pub fn render<'a>(
+        &'a self,
+        render_pass: &mut RenderPass<'a>,
+        global_bind_group: &'a BindGroup,
+    ) {
+        self.render_range(render_pass, global_bind_group, 0..self.vertices.len() as u32);
+    }
+
+    /// Draw only the vertices in `range`, clamped to the lines set
+    pub fn render_range<'a>(
+        &'a self,
+        render_pass: &mut RenderPass<'a>,
+        global_bind_group: &'a BindGroup,
+        range: Range<u32>,
+    ) {
+        let Some(ref vertex_buffer) = self.vertex_buffer else {
+            return;
+        };
+        let end = range.end.min(self.vertices.len() as u32);
+        if range.start >= end {
+            return;
+        }
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, global_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+        render_pass.draw(range.start..end, 0..1);
+    }
+}
+
+const LINE_SHADER: &str = r#"
+struct GlobalUniform {
+    view_position: vec4<f32>,
+    view_proj: mat4x4<f32>,
+    light_position: vec3<f32>,
+    _padding1: f32,
+    light_color: vec3<f32>,
+    light_intensity: f32,
+    light_view_proj: mat4x4<f32>,
+    fog_color: vec3<f32>,
+    fog_mode: u32,
+    fog_start: f32,
+    fog_end: f32,
+    fog_density: f32,
+    color_flags: u32,
+    background_top: vec4<f32>,
+    background_bottom: vec4<f32>,
+    clip_planes: array<vec4<f32>, 4>,
+    clip_plane_count: u32,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> global: GlobalUniform;
+
+@vertex
+fn vs_main(@location(0) position: vec3<f32>, @location(1) color: vec4<f32>) -> VertexOutput {
+    return VertexOutput(global.view_proj * vec4<f32>(position, 1.0), color);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(encode_output(srgb_to_linear(in.color.rgb), global.color_flags), in.color.a);
+}
+"#;
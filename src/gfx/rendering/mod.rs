@@ -3,19 +3,50 @@
 //!
 //! Handles render pipelines, GPU resource management, and frame rendering.
 
+pub mod agent_renderer;
+pub mod background_renderer;
+pub mod capture;
+pub mod clip_plane;
+pub mod color;
+pub mod custom_shader;
+pub mod depth;
+pub mod draw_list;
+mod inflate;
 pub mod pipeline_manager;
+pub mod projector;
+pub mod render_config;
 pub mod render_engine;
 pub mod render_pass_ext;
+pub mod render_settings;
+pub mod render_texture;
 pub mod shadow_cache;
 pub mod visualization_renderer;
 pub mod instanced_renderer;
 pub mod instanced_grid;
+pub mod line_renderer;
+pub mod viewport;
+pub mod volume_renderer;
 
 // Re-export main types
+pub use agent_renderer::{AgentBatch, AgentInstance, AgentRenderer, AgentShape};
+pub use background_renderer::BackgroundRenderer;
+pub use capture::CapturedFrame;
+pub use clip_plane::{ClipPlane, MAX_CLIP_PLANES};
+pub use color::SurfaceGamma;
+pub use custom_shader::CustomShader;
+pub use depth::DepthMode;
+pub use draw_list::{DrawItem, DrawList};
 pub use pipeline_manager::{PipelineConfig, PipelineManager, PipelineStats};
+pub use projector::{Projector, ProjectorImage};
+pub use render_config::{DeviceLimits, RenderConfig};
 pub use render_engine::RenderEngine;
 pub use render_pass_ext::RenderPassExt;
+pub use render_settings::{BackgroundGradient, FogMode, FogSettings, RenderSettings};
+pub use render_texture::RenderTexture;
 pub use shadow_cache::{ShadowCache, ShadowCacheStats};
 pub use visualization_renderer::{VisualizationPlane, VisualizationRenderer};
 pub use instanced_renderer::{InstancedRenderer, InstanceData, CubeMesh};
 pub use instanced_grid::{InstancedGrid, GridInstanceData};
+pub use line_renderer::{LineRenderer, LineVertex};
+pub use viewport::{Viewport, ViewportRect};
+pub use volume_renderer::{VolumeBatch, VolumeRenderer, MAX_VOLUME_STEPS};
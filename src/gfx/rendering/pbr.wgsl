@@ -6,11 +6,18 @@ struct GlobalUniform {
     light_color: vec3<f32>,
     light_intensity: f32,
     light_view_proj: mat4x4<f32>,
+    fog_color: vec3<f32>,
+    fog_mode: u32,
+    fog_start: f32,
+    fog_end: f32,
+    fog_density: f32,
+    color_flags: u32,
+    background_top: vec4<f32>,
+    background_bottom: vec4<f32>,
+    clip_planes: array<vec4<f32>, 4>,
+    clip_plane_count: u32,
 };
 
-struct Transform {
-    model: mat4x4<f32>,
-};
 
 struct Material {
     base_color: vec4<f32>,
@@ -19,15 +26,46 @@ struct Material {
     normal_scale: f32,
     occlusion_strength: f32,
     emissive: vec3<f32>,
+    // 1 when the diffuse texture is mapped triplanar (see TextureMapping)
+    texture_mode: u32,
+    triplanar_scale: f32,
+    triplanar_sharpness: f32,
+    // Mirror reflection of the sky, see Material::reflectance
+    reflectance: f32,
     _padding: f32,
 };
 
+// Model matrix and opacity of every object, see transform_buffer.rs
+struct ObjectTransform {
+    model: mat4x4<f32>,
+    opacity: f32,
+};
+
+// Scales the final alpha, lowered by the pipelines drawing ghosted objects
+override alpha_scale: f32 = 1.0;
+
 @group(0) @binding(0) var<uniform> global: GlobalUniform;
-@group(1) @binding(0) var<uniform> transform: Transform;
+// Transforms of all objects, indexed by the object's draw instance
+@group(1) @binding(0) var<storage, read> transforms: array<ObjectTransform>;
 @group(2) @binding(0) var<uniform> material: Material;
+@group(2) @binding(1) var diffuse_texture: texture_2d<f32>;
+@group(2) @binding(2) var diffuse_sampler: sampler;
 @group(3) @binding(0) var shadow_map: texture_depth_2d;
 @group(3) @binding(1) var shadow_sampler: sampler_comparison;
 
+// Image projected onto geometry along an axis, see gfx::rendering::projector
+struct Projector {
+    world_to_image: mat4x4<f32>,
+    opacity: f32,
+    range: f32,
+    enabled: u32,
+    decode_srgb: u32,
+};
+
+@group(3) @binding(2) var<uniform> projector: Projector;
+@group(3) @binding(3) var projector_texture: texture_2d<f32>;
+@group(3) @binding(4) var projector_sampler: sampler;
+
 struct VertexInput {
     @location(0) position: vec3<f32>,
     @location(1) normal: vec3<f32>,
@@ -38,21 +76,25 @@ struct VertexOutput {
     @location(0) world_normal: vec3<f32>,
     @location(1) world_position: vec3<f32>,
     @location(2) light_space_position: vec4<f32>,
+    // Object opacity, multiplied into the material alpha
+    @location(3) @interpolate(flat) opacity: f32,
 };
 
 @vertex
-fn vs_main(model: VertexInput) -> VertexOutput {
+fn vs_main(model: VertexInput, @builtin(instance_index) instance: u32) -> VertexOutput {
     var out: VertexOutput;
+    let model_matrix = transforms[instance].model;
 
-    let world_position = transform.model * vec4<f32>(model.position, 1.0);
+    let world_position = model_matrix * vec4<f32>(model.position, 1.0);
     out.world_position = world_position.xyz;
     out.clip_position = global.view_proj * world_position;
     out.light_space_position = global.light_view_proj * world_position;
+    out.opacity = transforms[instance].opacity;
 
     let normal_matrix = mat3x3<f32>(
-        normalize(transform.model[0].xyz),
-        normalize(transform.model[1].xyz),
-        normalize(transform.model[2].xyz)
+        normalize(model_matrix[0].xyz),
+        normalize(model_matrix[1].xyz),
+        normalize(model_matrix[2].xyz)
     );
     out.world_normal = normalize(normal_matrix * model.normal);
 
@@ -78,6 +120,48 @@ fn geometry_smith(n_dot_v: f32, n_dot_l: f32, roughness: f32) -> f32 {
     return ggx1 * ggx2;
 }
 
+// Whether a world position is cut away by one of the clip planes
+fn is_clipped(world_position: vec3<f32>) -> bool {
+    for (var i = 0u; i < global.clip_plane_count; i = i + 1u) {
+        let plane = global.clip_planes[i];
+        if dot(plane.xyz, world_position) > plane.w {
+            return true;
+        }
+    }
+    return false;
+}
+
+// Fraction of the fog color mixed in at a world position (see FogSettings::amount)
+fn fog_amount(world_position: vec3<f32>) -> f32 {
+    let distance = length(world_position - global.view_position.xyz);
+    if global.fog_mode == 1u {
+        let range = max(global.fog_end - global.fog_start, 1e-4);
+        return clamp((distance - global.fog_start) / range, 0.0, 1.0);
+    }
+    if global.fog_mode == 2u {
+        return 1.0 - exp(-max(global.fog_density, 0.0) * distance);
+    }
+    return 0.0;
+}
+
+// Linear color with the projected image laid over it (see Projector::project)
+fn apply_projector(color: vec3<f32>, world_position: vec3<f32>) -> vec3<f32> {
+    if projector.enabled == 0u {
+        return color;
+    }
+    let image = (projector.world_to_image * vec4<f32>(world_position, 1.0)).xyz;
+    if any(image.xy < vec2<f32>(0.0)) || any(image.xy > vec2<f32>(1.0))
+        || image.z < 0.0 || image.z > projector.range {
+        return color;
+    }
+    let texel = textureSampleLevel(projector_texture, projector_sampler, image.xy, 0.0);
+    var projected = texel.rgb;
+    if projector.decode_srgb != 0u {
+        projected = srgb_to_linear(projected);
+    }
+    return mix(color, projected, texel.a * projector.opacity);
+}
+
 fn calculate_shadow(in: VertexOutput, light_dir: vec3<f32>) -> f32 {
     let ndc = in.light_space_position.xyz / in.light_space_position.w;
     let shadow_coord = vec2<f32>(ndc.x * 0.5 + 0.5, -ndc.y * 0.5 + 0.5);
@@ -117,14 +201,44 @@ fn calculate_shadow(in: VertexOutput, light_dir: vec3<f32>) -> f32 {
 }
 
 
+// Diffuse texture projected along the world axes, blended by the normal.
+// Unwrapped coordinates give the gradients so tiling has no seams.
+fn triplanar_sample(world_position: vec3<f32>, normal: vec3<f32>) -> vec4<f32> {
+    let p = world_position * material.triplanar_scale;
+    var weights = pow(abs(normal), vec3<f32>(material.triplanar_sharpness));
+    weights = weights / max(weights.x + weights.y + weights.z, 1e-4);
+    let x = textureSampleGrad(diffuse_texture, diffuse_sampler, fract(p.yz), dpdx(p.yz), dpdy(p.yz));
+    let y = textureSampleGrad(diffuse_texture, diffuse_sampler, fract(p.xz), dpdx(p.xz), dpdy(p.xz));
+    let z = textureSampleGrad(diffuse_texture, diffuse_sampler, fract(p.xy), dpdx(p.xy), dpdy(p.xy));
+    return x * weights.x + y * weights.y + z * weights.z;
+}
+
+// Sky seen along `direction`: the background gradient from straight down to
+// straight up, or a dim sky lit by the light when the gradient is off
+fn sky_color(direction: vec3<f32>) -> vec3<f32> {
+    if global.background_top.w < 0.5 {
+        return global.light_color * 0.25;
+    }
+    let bottom = srgb_to_linear(global.background_bottom.rgb);
+    let top = srgb_to_linear(global.background_top.rgb);
+    return mix(bottom, top, clamp(direction.z * 0.5 + 0.5, 0.0, 1.0));
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    if is_clipped(in.world_position) {
+        discard;
+    }
+
     let normal = normalize(in.world_normal);
     let view_dir = normalize(global.view_position.xyz - in.world_position);
     let light_dir = normalize(global.light_position - in.world_position);
     let halfway_dir = normalize(view_dir + light_dir);
 
-    let albedo = material.base_color.rgb;
+    var albedo = material.base_color.rgb;
+    if material.texture_mode == 1u {
+        albedo = albedo * triplanar_sample(in.world_position, normal).rgb;
+    }
     let metallic = material.metallic;
     let roughness = max(material.roughness, 0.04);
     
@@ -162,12 +276,20 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let rim = pow(1.0 - max(dot(normal, view_dir), 0.0), 4.0);
     let rim_light = rim * 0.15 * global.light_color * (1.0 - metallic);
 
+    // Mirrored sky, strongest at grazing angles and on smooth surfaces
+    let sky_fresnel = fresnel_schlick(n_dot_v, f0) * material.reflectance * (1.0 - roughness);
+    let reflection = sky_color(reflect(-view_dir, normal)) * sky_fresnel;
+
     // Cleaner color calculation without additional shadow blending
-    let color = ambient + lo + material.emissive + rim_light;
+    let color = ambient + lo + material.emissive + rim_light + reflection;
 
-    // Tone mapping and gamma correction
+    // Tone mapping, projected image, fog and the sRGB curve (see color.rs)
     let mapped = color / (color + vec3<f32>(1.0));
-    let gamma_corrected = pow(mapped, vec3<f32>(1.0 / 2.2));
+    let fog_color = srgb_to_linear(global.fog_color);
+    let projected = apply_projector(mapped, in.world_position);
+    let fogged = mix(projected, fog_color, fog_amount(in.world_position));
 
-    return vec4<f32>(gamma_corrected, material.base_color.a);
+    // Reflections stay visible on transparent surfaces such as water
+    let alpha = mix(material.base_color.a, 1.0, max(sky_fresnel.r, max(sky_fresnel.g, sky_fresnel.b))) * in.opacity * alpha_scale;
+    return vec4<f32>(encode_output(fogged, global.color_flags), alpha);
 }
\ No newline at end of file
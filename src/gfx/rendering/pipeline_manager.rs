@@ -24,6 +24,12 @@ pub struct PipelineConfig {
     pub color_targets: Vec<Option<ColorTargetState>>,
     pub vertex_only: bool,       //for shadow pass
     pub no_vertex_buffers: bool, // NEW: for fullscreen quads
+    /// Whether fragments write depth; disabled for blended geometry
+    pub depth_write: bool,
+    /// Depth test of fragments against the depth buffer
+    pub depth_compare: CompareFunction,
+    /// Values of pipeline-overridable constants (`override` in WGSL)
+    pub constants: Vec<(String, f64)>,
 }
 
 impl Default for PipelineConfig {
@@ -43,6 +49,9 @@ impl Default for PipelineConfig {
             })],
             vertex_only: false,
             no_vertex_buffers: false, // NEW
+            depth_write: true,
+            depth_compare: CompareFunction::Less,
+            constants: Vec::new(),
         }
     }
 }
@@ -119,6 +128,44 @@ impl PipelineConfig {
         self
     }
 
+    /// Sets the blend state of every color target (builder pattern)
+    ///
+    /// # Arguments
+    /// * `blend` - Blend state, e.g. `BlendState::ALPHA_BLENDING`
+    pub fn with_blend(mut self, blend: BlendState) -> Self {
+        for target in self.color_targets.iter_mut().flatten() {
+            target.blend = Some(blend);
+        }
+        self
+    }
+
+    /// Enables or disables depth writes (builder pattern)
+    ///
+    /// Depth testing stays on; blended geometry disables writes so objects
+    /// behind it still draw.
+    pub fn with_depth_write(mut self, enabled: bool) -> Self {
+        self.depth_write = enabled;
+        self
+    }
+
+    /// Sets the depth test (builder pattern)
+    ///
+    /// Geometry drawn over its own depth pre-pass uses `LessEqual`.
+    pub fn with_depth_compare(mut self, compare: CompareFunction) -> Self {
+        self.depth_compare = compare;
+        self
+    }
+
+    /// Sets a pipeline-overridable constant of the shader (builder pattern)
+    ///
+    /// # Arguments
+    /// * `name` - Name of the `override` declaration
+    /// * `value` - Value, converted to the declared scalar type
+    pub fn with_constant(mut self, name: &str, value: f64) -> Self {
+        self.constants.push((name.to_owned(), value));
+        self
+    }
+
     /// Configures pipeline for fullscreen quad rendering (no vertex buffers needed)
     ///
     /// Used for post-processing effects like blur passes
@@ -188,13 +235,25 @@ impl PipelineManager {
     /// Registers a pipeline configuration without creating it
     ///
     /// Pipelines are created lazily when first requested via `get_pipeline()`.
+    /// Registering a name again replaces its pipeline.
     ///
     /// # Arguments
     /// * `name` - Unique identifier for this pipeline
     /// * `config` - Pipeline configuration
     pub fn register_pipeline(&mut self, name: &str, config: PipelineConfig) {
         self.pipeline_configs.insert(name.to_string(), config);
-        self.pending_pipelines.push(name.to_string());
+        self.pipelines.remove(name);
+        if !self.pending_pipelines.iter().any(|pending| pending == name) {
+            self.pending_pipelines.push(name.to_string());
+        }
+    }
+
+    /// Gets the configuration a pipeline was registered with
+    ///
+    /// # Arguments
+    /// * `name` - Pipeline identifier
+    pub fn get_config(&self, name: &str) -> Option<&PipelineConfig> {
+        self.pipeline_configs.get(name)
     }
 
     /// Loads and compiles a shader module
@@ -219,6 +278,14 @@ impl PipelineManager {
         Ok(())
     }
 
+    /// Gets the source a shader was loaded from
+    ///
+    /// # Arguments
+    /// * `name` - Shader identifier
+    pub fn get_shader_source(&self, name: &str) -> Option<&str> {
+        self.shader_sources.get(name).map(String::as_str)
+    }
+
     /// Gets or creates a pipeline (lazy loading)
     ///
     /// Returns an existing pipeline if available, otherwise creates it
@@ -242,7 +309,7 @@ impl PipelineManager {
                     return self.pipelines.get(name);
                 }
                 Err(e) => {
-                    eprintln!("Failed to create pipeline '{}': {}", name, e);
+                    tracing::error!("Failed to create pipeline '{}': {}", name, e);
                     return None;
                 }
             }
@@ -251,6 +318,17 @@ impl PipelineManager {
         None
     }
 
+    /// Gets a pipeline only if it has already been created
+    ///
+    /// Unlike `get_pipeline()`, this never creates the pipeline lazily, so it
+    /// can be used through a shared reference while recording render passes.
+    ///
+    /// # Arguments
+    /// * `name` - Pipeline identifier
+    pub fn get_created_pipeline(&self, name: &str) -> Option<&RenderPipeline> {
+        self.pipelines.get(name)
+    }
+
     /// Creates all pending pipelines immediately
     ///
     /// Useful for pre-loading pipelines or validating configurations.
@@ -315,7 +393,7 @@ impl PipelineManager {
                         self.pipelines.insert(pipeline_name.clone(), pipeline);
                     }
                     Err(e) => {
-                        eprintln!(
+                        tracing::error!(
                             "Failed to recreate pipeline '{}' after shader reload: {}",
                             pipeline_name, e
                         );
@@ -348,6 +426,16 @@ impl PipelineManager {
                 push_constant_ranges: &[],
             });
 
+        let constants: Vec<(&str, f64)> = config
+            .constants
+            .iter()
+            .map(|(name, value)| (name.as_str(), *value))
+            .collect();
+        let compilation_options = PipelineCompilationOptions {
+            constants: &constants,
+            ..Default::default()
+        };
+
         // Handle vertex-only pipelines (like shadow pass)
         let fragment_state = if config.vertex_only {
             None // No fragment shader for vertex-only pipelines
@@ -356,7 +444,7 @@ impl PipelineManager {
                 module: shader,
                 entry_point: Some("fs_main"),
                 targets: &config.color_targets,
-                compilation_options: PipelineCompilationOptions::default(),
+                compilation_options: compilation_options.clone(),
             })
         };
 
@@ -373,8 +461,8 @@ impl PipelineManager {
             .as_ref()
             .map(|texture| DepthStencilState {
                 format: texture.format(),
-                depth_write_enabled: true,
-                depth_compare: CompareFunction::Less,
+                depth_write_enabled: config.depth_write,
+                depth_compare: config.depth_compare,
                 stencil: StencilState::default(),
                 bias: DepthBiasState::default(),
             });
@@ -388,7 +476,7 @@ impl PipelineManager {
                     module: shader,
                     entry_point: Some("vs_main"),
                     buffers: vertex_buffers, // Now respects no_vertex_buffers flag
-                    compilation_options: PipelineCompilationOptions::default(),
+                    compilation_options,
                 },
                 fragment: fragment_state, // Respects vertex_only flag
                 primitive: PrimitiveState {
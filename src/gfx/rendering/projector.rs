@@ -0,0 +1,229 @@
+//! # Projector
+//!
+//! Lays a 2D image over scene geometry along an axis, like a slide
+//! projector with parallel rays. Every surface point inside the projected
+//! rectangle and within [`Projector::range`] of it takes the image's color,
+//! whichever way the surface faces, so data such as wall shear stress or a
+//! boundary mask from a 2D simulation shows directly on the obstacle meshes
+//! extruded from it.
+//!
+//! The image is either CPU data mapped through the same heatmap as
+//! [`CutPlane2D`](crate::visualization::CutPlane2D) GPU buffers, or a texture
+//! holding display colors, such as one written by a
+//! [`ProceduralTexture`](crate::gfx::resources::ProceduralTexture).
+//! Projected colors are drawn unlit over the PBR shading, blended by
+//! [`Projector::opacity`] and the image's alpha, and fogged like the surface.
+//! The scene's projector is set in `scene.projector`.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use cgmath::Vector3;
+//! use haggis::gfx::rendering::Projector;
+//!
+//! let mut app = haggis::default();
+//! // A 4 x 2 image projected down the z axis onto everything in 0 < z < 1
+//! let mut projector = Projector::new(Vector3::new(0.0, 0.0, 1.0), -Vector3::unit_z(), [4.0, 2.0]);
+//! projector.range = 1.0;
+//! let shear: Vec<f32> = vec![0.0; 64 * 32];
+//! projector.set_data(&shear, 64, 32, [0.0, 0.5]);
+//! app.app_state.scene.projector = Some(projector);
+//! ```
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use cgmath::{InnerSpace, Matrix, Matrix4, Vector3, Vector4};
+
+use crate::gfx::resources::texture_resource::TextureResource;
+
+static NEXT_REVISION: AtomicU64 = AtomicU64::new(1);
+
+/// Image shown by a [`Projector`]
+#[derive(Clone, Default)]
+pub enum ProjectorImage {
+    /// Nothing is projected
+    #[default]
+    None,
+    /// RGBA display colors, top row first
+    Pixels {
+        rgba: Vec<u8>,
+        width: u32,
+        height: u32,
+    },
+    /// A filterable float texture holding display colors
+    Texture(TextureResource),
+}
+
+/// Image projected onto geometry along an axis
+#[derive(Clone)]
+pub struct Projector {
+    pub enabled: bool,
+    /// Center of the projected rectangle
+    pub origin: Vector3<f32>,
+    /// Direction the image is projected in
+    pub direction: Vector3<f32>,
+    /// Direction of the image's top edge, made perpendicular to `direction`
+    pub up: Vector3<f32>,
+    /// Width and height of the projected rectangle in world units
+    pub size: [f32; 2],
+    /// How far beyond `origin` along `direction` surfaces are reached
+    pub range: f32,
+    /// Blend of the image over the surface, 0 to 1
+    pub opacity: f32,
+    image: ProjectorImage,
+    revision: u64,
+}
+
+impl Projector {
+    /// Projector at `origin` shining along `direction`, with `up` along +z,
+    /// or +y when projecting along z
+    pub fn new(origin: Vector3<f32>, direction: Vector3<f32>, size: [f32; 2]) -> Self {
+        let direction = direction.normalize();
+        let up = if direction.z.abs() > 0.9 {
+            Vector3::unit_y()
+        } else {
+            Vector3::unit_z()
+        };
+        Self {
+            enabled: true,
+            origin,
+            direction,
+            up,
+            size,
+            range: 1.0,
+            opacity: 1.0,
+            image: ProjectorImage::None,
+            revision: NEXT_REVISION.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    /// Project `values`, `width` by `height` with the first row at the top,
+    /// colored with the heatmap over `value_range`
+    pub fn set_data(&mut self, values: &[f32], width: u32, height: u32, value_range: [f32; 2]) {
+        let range = (value_range[1] - value_range[0]).max(1e-6);
+        let rgba = values
+            .iter()
+            .take((width * height) as usize)
+            .flat_map(|value| heat_color((value - value_range[0]) / range))
+            .collect();
+        self.set_image(ProjectorImage::Pixels {
+            rgba,
+            width,
+            height,
+        });
+    }
+
+    /// Project a texture, e.g. one written by a compute shader
+    pub fn set_texture(&mut self, texture: TextureResource) {
+        self.set_image(ProjectorImage::Texture(texture));
+    }
+
+    pub fn set_image(&mut self, image: ProjectorImage) {
+        self.image = image;
+        self.revision = NEXT_REVISION.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn image(&self) -> &ProjectorImage {
+        &self.image
+    }
+
+    /// Changes whenever the image is replaced, so the renderer only uploads
+    /// new images
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Maps world positions to image coordinates: x and y from 0 to 1 across
+    /// the image with y down, z the distance along `direction`
+    pub fn world_to_image(&self) -> Matrix4<f32> {
+        let forward = self.direction.normalize();
+        let right = forward.cross(self.up).normalize();
+        let up = right.cross(forward);
+        let row = |axis: Vector3<f32>, scale: f32, offset: f32| {
+            let axis = axis * scale;
+            axis.extend(offset - axis.dot(self.origin))
+        };
+        Matrix4::from_cols(
+            row(right, 1.0 / self.size[0].max(1e-6), 0.5),
+            row(up, -1.0 / self.size[1].max(1e-6), 0.5),
+            row(forward, 1.0, 0.0),
+            Vector4::unit_w(),
+        )
+        .transpose()
+    }
+
+    /// Image coordinates `point` is drawn with, if the projector reaches it;
+    /// mirrors `apply_projector` in the PBR shader
+    pub fn project(&self, point: Vector3<f32>) -> Option<[f32; 2]> {
+        let image = self.world_to_image() * point.extend(1.0);
+        let inside = (0.0..=1.0).contains(&image.x)
+            && (0.0..=1.0).contains(&image.y)
+            && (0.0..=self.range).contains(&image.z);
+        inside.then_some([image.x, image.y])
+    }
+
+    pub(crate) fn uniform(&self, decode_srgb: bool) -> ProjectorUniform {
+        ProjectorUniform {
+            world_to_image: self.world_to_image().into(),
+            opacity: self.opacity.clamp(0.0, 1.0),
+            range: self.range,
+            enabled: (self.enabled && !matches!(self.image, ProjectorImage::None)) as u32,
+            decode_srgb: decode_srgb as u32,
+        }
+    }
+}
+
+/// Projector uniforms, matching `Projector` in the PBR shader
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct ProjectorUniform {
+    world_to_image: [[f32; 4]; 4],
+    opacity: f32,
+    range: f32,
+    enabled: u32,
+    /// Whether sampled texels are sRGB-encoded and need decoding
+    decode_srgb: u32,
+}
+
+/// Blue-cyan-yellow-red heatmap color of `t` in 0 to 1, as in the
+/// visualization shader
+fn heat_color(t: f32) -> [u8; 4] {
+    let t = t.clamp(0.0, 1.0);
+    let channel = |center: f32| ((1.5 - (4.0 * t - center).abs()).clamp(0.0, 1.0) * 255.0) as u8;
+    [channel(3.0), channel(2.0), channel(1.0), 255]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_projector_maps_its_rectangle_along_the_axis() {
+        let mut projector =
+            Projector::new(Vector3::new(1.0, 2.0, 1.0), -Vector3::unit_z(), [4.0, 2.0]);
+        projector.range = 1.0;
+
+        // Seen down -z, image x runs along +x and the top edge is at +y
+        let center = projector.project(Vector3::new(1.0, 2.0, 0.5)).unwrap();
+        assert!((center[0] - 0.5).abs() < 1e-6 && (center[1] - 0.5).abs() < 1e-6);
+        let top = projector.project(Vector3::new(1.0, 2.9, 0.0)).unwrap();
+        assert!((top[1] - 0.05).abs() < 1e-5);
+        let side = projector.project(Vector3::new(2.5, 2.0, 0.2)).unwrap();
+        assert!((side[0] - 0.5).abs() > 0.3);
+
+        // Outside the rectangle, behind the projector or out of range
+        assert!(projector.project(Vector3::new(3.5, 2.0, 0.5)).is_none());
+        assert!(projector.project(Vector3::new(1.0, 2.0, 1.5)).is_none());
+        assert!(projector.project(Vector3::new(1.0, 2.0, -0.5)).is_none());
+
+        let revision = projector.revision();
+        projector.set_data(&[0.0, 0.25, 1.0, 2.0], 2, 2, [0.0, 1.0]);
+        assert_ne!(projector.revision(), revision);
+        let ProjectorImage::Pixels { rgba, .. } = projector.image() else {
+            panic!("expected pixels");
+        };
+        assert_eq!(&rgba[..4], &[0, 0, 127, 255]);
+        assert_eq!(&rgba[8..12], &[127, 0, 0, 255]);
+        assert_eq!(&rgba[8..12], &rgba[12..16]);
+    }
+}
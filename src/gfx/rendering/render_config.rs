@@ -0,0 +1,71 @@
+//! GPU device and surface configuration
+//!
+//! [`RenderConfig`] controls how the [`RenderEngine`] picks its adapter and
+//! creates its device: power preference, presentation mode, surface gamma,
+//! depth convention and the wgpu features and limits a simulation needs, and optionally a second
+//! adapter for simulation compute. It is usually filled in through
+//! `HaggisAppBuilder` rather than directly.
+//!
+//! [`RenderEngine`]: super::render_engine::RenderEngine
+
+use super::color::SurfaceGamma;
+use super::depth::DepthMode;
+use crate::simulation::gpu::AdapterPreference;
+
+/// Limits the device is created with
+#[derive(Debug, Clone)]
+pub enum DeviceLimits {
+    /// Conservative limits that work on almost any hardware (the default)
+    Downlevel,
+    /// Everything the chosen adapter supports, e.g. for very large storage buffers
+    Adapter,
+    /// Exactly these limits; device creation fails if the adapter can't meet them
+    Custom(wgpu::Limits),
+}
+
+impl DeviceLimits {
+    /// Resolve the limits to request from the given adapter
+    pub fn resolve(&self, adapter: &wgpu::Adapter) -> wgpu::Limits {
+        match self {
+            DeviceLimits::Downlevel => wgpu::Limits {
+                max_texture_dimension_2d: 4096,
+                ..wgpu::Limits::downlevel_defaults()
+            },
+            DeviceLimits::Adapter => adapter.limits(),
+            DeviceLimits::Custom(limits) => limits.clone(),
+        }
+    }
+}
+
+/// Adapter and device creation settings for the render engine
+#[derive(Debug, Clone)]
+pub struct RenderConfig {
+    /// Prefer a discrete (`HighPerformance`) or integrated (`LowPower`) GPU
+    pub power_preference: wgpu::PowerPreference,
+    /// Presentation mode; `None` follows the app's vsync setting
+    pub present_mode: Option<wgpu::PresentMode>,
+    /// Whether shaders or the hardware apply the sRGB curve to the surface
+    pub surface_gamma: SurfaceGamma,
+    /// Standard or reverse-Z depth buffer
+    pub depth_mode: DepthMode,
+    /// Optional features the device must support
+    pub required_features: wgpu::Features,
+    /// Limits requested from the device
+    pub limits: DeviceLimits,
+    /// Separate adapter for simulation compute; `None` computes on the display GPU
+    pub compute_adapter: Option<AdapterPreference>,
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        Self {
+            power_preference: wgpu::PowerPreference::default(),
+            present_mode: None,
+            surface_gamma: SurfaceGamma::default(),
+            depth_mode: DepthMode::default(),
+            required_features: wgpu::Features::default(),
+            limits: DeviceLimits::Downlevel,
+            compute_adapter: None,
+        }
+    }
+}
@@ -3,22 +3,57 @@
 //! Provides high-level rendering functionality built on top of wgpu, including
 //! pipeline management, depth testing, shadow mapping with blur, and UI overlay support.
 
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use wgpu::{Device, TextureFormat};
 
+use crate::error::{HaggisError, Result};
+use crate::wgpu_utils::{binding_types, uniform_buffer::UniformBuffer};
+use crate::performance::PrepassTiming;
+use crate::simulation::gpu::{ComputeDevice, GpuTimer};
 use crate::gfx::{
     camera::camera_utils::CameraUniform,
+    capabilities::GpuCapabilities,
+    overlay::OverlayConfig,
     resources::{
-        global_bindings::{update_global_ubo_with_light, GlobalBindings, GlobalUBO, LightConfig},
+        global_bindings::{update_global_ubo_with_settings, GlobalBindings, GlobalUBO, LightConfig},
         texture_resource::TextureResource,
     },
-    scene::{object::DrawObject, scene::Scene},
+    scene::{
+        layers::Layers, object::DrawObject, scene::Scene, transform_buffer::TransformBuffer,
+    },
 };
 
+use super::color::{self, with_color_functions};
+use super::custom_shader::CustomShader;
+use super::depth::DepthMode;
 use super::pipeline_manager::{PipelineConfig, PipelineManager};
+use super::render_config::RenderConfig;
+use super::render_settings::RenderSettings;
 use super::shadow_cache::ShadowCache;
 use super::visualization_renderer::{VisualizationPlane, VisualizationRenderer};
+use super::agent_renderer::{AgentBatch, AgentRenderer};
+use super::volume_renderer::{VolumeBatch, VolumeRenderer};
+use super::capture::CapturedFrame;
+use super::background_renderer::BackgroundRenderer;
+use super::clip_plane::ClipPlane;
+use super::draw_list::{DrawItem, DrawList};
+use super::projector::{Projector, ProjectorImage, ProjectorUniform};
 use super::instanced_grid::InstancedGrid;
+use super::line_renderer::{LineRenderer, LineVertex};
+use super::render_texture::{RenderTexture, RenderTextureUpdate};
+use super::viewport::{Viewport, ViewportTarget};
+
+/// Alpha scale of ghosted objects where nothing is in front of them
+const GHOST_ALPHA: f64 = 0.35;
+/// Alpha scale of ghosted objects behind other geometry
+const GHOST_HIDDEN_ALPHA: f64 = 0.12;
+
+/// Camera of an offscreen view and the layers it draws
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ViewCamera {
+    pub uniform: CameraUniform,
+    pub layers: Layers,
+}
 
 /// Core rendering engine managing GPU resources and draw calls
 ///
@@ -30,11 +65,14 @@ use super::instanced_grid::InstancedGrid;
 /// - Camera uniform updates
 /// - UI overlay rendering
 pub struct RenderEngine {
-    surface: wgpu::Surface<'static>,
+    /// Window surface, `None` for [headless](Self::headless) engines
+    surface: Option<wgpu::Surface<'static>>,
     device: Arc<wgpu::Device>,
     queue: Arc<wgpu::Queue>,
     config: wgpu::SurfaceConfiguration,
     depth_texture: TextureResource,
+    /// Standard or reverse-Z depth, baked into the pipelines
+    depth_mode: DepthMode,
     format: TextureFormat,
     pub pipeline_manager: PipelineManager,
     global_ubo: GlobalUBO,
@@ -46,19 +84,72 @@ pub struct RenderEngine {
     blurred_shadow_view: wgpu::TextureView,
 
     // Bind groups and layouts
-    shadow_bind_group: wgpu::BindGroup, // For final rendering
+    shadow_bind_group: wgpu::BindGroup, // For final rendering, with the projector
+    shadow_final_layout: wgpu::BindGroupLayout,
+    shadow_sampler: wgpu::Sampler,
+    projector_ubo: UniformBuffer<ProjectorUniform>,
+    // Image of the projector, a white texel when none is set
+    projector_texture: TextureResource,
+    // Revision of the projector image in `projector_texture`
+    projector_revision: Option<u64>,
     blur_bind_group: wgpu::BindGroup,   // For blur pass
 
     light_config: LightConfig,
+    // Clear color, background gradient and fog
+    render_settings: RenderSettings,
+    // Planes cutting away scene geometry
+    clip_planes: Vec<ClipPlane>,
+    // Background gradient, created when a gradient is first enabled
+    background_renderer: Option<BackgroundRenderer>,
 
     // Shadow map caching system
     shadow_cache: ShadowCache,
+    // Scene layers shown when the shadow map was last drawn
+    shadow_layers: Option<Layers>,
 
     // Visualization rendering system
     visualization_renderer: VisualizationRenderer,
 
     // Instanced grid rendering system
     instanced_grid: Option<InstancedGrid>,
+
+    // Agent darts drawn from simulation buffers, created when agents first appear
+    agent_renderer: Option<AgentRenderer>,
+
+    // Ray marched volumes of simulation fields, created when volumes first appear
+    volume_renderer: Option<VolumeRenderer>,
+
+    // World grid, axes and visualization lines, created on first update
+    line_renderer: Option<LineRenderer>,
+    // Reference overlay the cached grid lines were built from
+    overlay_config: Option<OverlayConfig>,
+    overlay_lines: Vec<LineVertex>,
+
+    // Offscreen targets for secondary viewports, indexed like the viewport list
+    viewport_targets: Vec<Option<ViewportTarget>>,
+    // Whether the surface accepts texture copies (required for viewports)
+    surface_supports_copy: bool,
+    /// Present modes the surface supports, for falling back gracefully
+    supported_present_modes: Vec<wgpu::PresentMode>,
+    /// Limits and features of the adapter and device
+    capabilities: GpuCapabilities,
+    /// Set by wgpu's device-lost callback with the driver's message
+    device_lost: Arc<Mutex<Option<String>>>,
+    /// Second GPU running simulation compute, if one was requested and found
+    compute_device: Option<ComputeDevice>,
+
+    // Offscreen targets for render textures, keyed by render texture id
+    render_texture_targets: std::collections::HashMap<usize, ViewportTarget>,
+
+    // GPU frame timer, `None` without timestamp queries
+    frame_timer: Option<GpuTimer>,
+    // Whether the frame being timed used the depth pre-pass
+    timed_prepass: bool,
+    prepass_timing: PrepassTiming,
+
+    // Custom material shaders that failed to compile, by module name, with
+    // the failing source so the error is reported once
+    rejected_shaders: std::collections::HashMap<String, String>,
 }
 
 impl RenderEngine {
@@ -82,56 +173,179 @@ impl RenderEngine {
         width: u32,
         height: u32,
     ) -> RenderEngine {
+        Self::with_config(window, width, height, &RenderConfig::default()).await
+    }
+
+    /// Creates a new render engine with explicit adapter and device settings
+    ///
+    /// # Arguments
+    /// * `window` - Window surface target for rendering
+    /// * `width` - Initial surface width in pixels
+    /// * `height` - Initial surface height in pixels
+    /// * `render_config` - Power preference, present mode, features and limits
+    ///
+    /// # Panics
+    /// Panics if unable to create wgpu adapter or a device with the requested
+    /// features and limits
+    pub async fn with_config(
+        window: impl Into<wgpu::SurfaceTarget<'static>>,
+        width: u32,
+        height: u32,
+        render_config: &RenderConfig,
+    ) -> RenderEngine {
+        Self::try_with_config(window, width, height, render_config)
+            .await
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Creates a new render engine, returning an error instead of panicking
+    ///
+    /// # Errors
+    /// Returns [`HaggisError::Surface`], [`HaggisError::NoAdapter`] or
+    /// [`HaggisError::Device`] if the corresponding wgpu object can't be created
+    pub async fn try_with_config(
+        window: impl Into<wgpu::SurfaceTarget<'static>>,
+        width: u32,
+        height: u32,
+        render_config: &RenderConfig,
+    ) -> Result<RenderEngine> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::all(),
+            ..Default::default()
+        });
+        let surface = instance.create_surface(window)?;
+        Self::create(instance, Some(surface), width, height, render_config).await
+    }
+
+    /// Creates a render engine without a window
+    ///
+    /// Headless engines render offscreen only, through
+    /// [`capture_frame`](Self::capture_frame) and render textures; the
+    /// `render_frame` methods skip every frame. `width` and `height` size the
+    /// depth buffer of the (unused) main view. Used by
+    /// [`testing`](crate::testing) for golden-image tests.
+    ///
+    /// # Errors
+    /// Returns [`HaggisError::NoAdapter`] or [`HaggisError::Device`] if no
+    /// usable GPU is available
+    pub async fn headless(
+        width: u32,
+        height: u32,
+        render_config: &RenderConfig,
+    ) -> Result<RenderEngine> {
         let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
             backends: wgpu::Backends::all(),
             ..Default::default()
         });
-        let surface = instance.create_surface(window).unwrap();
+        Self::create(instance, None, width, height, render_config).await
+    }
 
+    async fn create(
+        instance: wgpu::Instance,
+        surface: Option<wgpu::Surface<'static>>,
+        width: u32,
+        height: u32,
+        render_config: &RenderConfig,
+    ) -> Result<RenderEngine> {
         let adapter = instance
             .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
-                compatible_surface: Some(&surface),
+                power_preference: render_config.power_preference,
+                compatible_surface: surface.as_ref(),
                 force_fallback_adapter: false,
             })
-            .await
-            .expect("Failed to request adapter!");
-
-        let (device, queue) = {
-            adapter
-                .request_device(&wgpu::DeviceDescriptor {
-                    label: Some("WGPU Device"),
-                    required_features: wgpu::Features::default(),
-                    required_limits: wgpu::Limits {
-                        max_texture_dimension_2d: 4096,
-                        ..wgpu::Limits::downlevel_defaults()
-                    },
-                    memory_hints: wgpu::MemoryHints::default(),
-                    trace: wgpu::Trace::Off,
-                })
-                .await
-                .expect("Failed to request a device!")
+            .await?;
+
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor {
+                label: Some("WGPU Device"),
+                required_features: render_config.required_features,
+                required_limits: render_config.limits.resolve(&adapter),
+                memory_hints: wgpu::MemoryHints::default(),
+                trace: wgpu::Trace::Off,
+            })
+            .await?;
+
+        // Record device loss so the app can recreate the engine instead of crashing
+        let device_lost = Arc::new(Mutex::new(None));
+        {
+            let device_lost = device_lost.clone();
+            device.set_device_lost_callback(move |reason, message| {
+                if reason != wgpu::DeviceLostReason::Destroyed {
+                    *device_lost.lock().unwrap() = Some(message);
+                }
+            });
+        }
+
+        let capabilities = GpuCapabilities::new(&adapter, &device);
+        tracing::info!("GPU capabilities:\n{}", capabilities);
+        let adapter_info = adapter.get_info();
+        crate::logging::crash::set_gpu_info(format!(
+            "{capabilities}\nDriver: {} {}",
+            adapter_info.driver, adapter_info.driver_info
+        ));
+
+        let compute_device = match &render_config.compute_adapter {
+            Some(preference) => {
+                ComputeDevice::request(
+                    &instance,
+                    &adapter.get_info(),
+                    preference,
+                    render_config.required_features,
+                    &render_config.limits,
+                )
+                .await?
+            }
+            None => None,
+        };
+
+        // Headless engines never present; BGRA matches the pipelines' default targets
+        let surface_capabilities = match &surface {
+            Some(surface) => surface.get_capabilities(&adapter),
+            None => wgpu::SurfaceCapabilities {
+                formats: vec![TextureFormat::Bgra8Unorm],
+                present_modes: vec![wgpu::PresentMode::Fifo],
+                alpha_modes: vec![wgpu::CompositeAlphaMode::Opaque],
+                usages: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            },
+        };
+        // Some platforms only offer sRGB surfaces (or only plain ones); the
+        // color flags in the global uniforms follow whichever format we get
+        let format = render_config
+            .surface_gamma
+            .choose_format(&surface_capabilities.formats);
+        let depth_mode = render_config.depth_mode;
+
+        // Secondary viewports are composited by copying into the surface texture
+        let surface_supports_copy = surface_capabilities
+            .usages
+            .contains(wgpu::TextureUsages::COPY_DST);
+        let surface_usage = if surface_supports_copy {
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_DST
+        } else {
+            wgpu::TextureUsages::RENDER_ATTACHMENT
         };
 
-        let surface_capabilities = surface.get_capabilities(&adapter);
-        let format = surface_capabilities
-            .formats
-            .iter()
-            .copied()
-            .find(|f| !f.is_srgb())
-            .unwrap_or(surface_capabilities.formats[0]);
+        let supported_present_modes = surface_capabilities.present_modes.clone();
+        let present_mode = resolve_present_mode(
+            render_config
+                .present_mode
+                .unwrap_or(wgpu::PresentMode::Immediate), // High performance by default
+            &supported_present_modes,
+        );
 
         let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            usage: surface_usage,
             format,
             width,
             height,
-            present_mode: wgpu::PresentMode::Immediate, // High performance by default
+            present_mode,
             alpha_mode: surface_capabilities.alpha_modes[0],
             view_formats: vec![],
             desired_maximum_frame_latency: 2,
         };
-        surface.configure(&device, &config);
+        if let Some(surface) = &surface {
+            surface.configure(&device, &config);
+        }
 
         // Create depth texture for main rendering
         let depth_texture =
@@ -239,6 +453,25 @@ impl RenderEngine {
                         ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison), // Comparison sampler
                         count: None,
                     },
+                    // Projector uniforms, image and sampler
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: binding_types::uniform(),
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: binding_types::texture_2d(),
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 4,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: binding_types::sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
                 ],
             });
 
@@ -273,20 +506,24 @@ impl RenderEngine {
             ..Default::default()
         });
 
-        let shadow_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Shadow Bind Group"),
-            layout: &shadow_final_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&shadow_depth_texture.view), // Use depth texture
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&shadow_sampler), // Use comparison sampler
-                },
-            ],
-        });
+        let projector_ubo = UniformBuffer::<ProjectorUniform>::new(&device);
+        let projector_texture = TextureResource::create_from_rgba_data_with_filter(
+            &device,
+            &queue,
+            &[255; 4],
+            1,
+            1,
+            "Projector Image",
+            wgpu::FilterMode::Linear,
+        );
+        let shadow_bind_group = create_shadow_bind_group(
+            &device,
+            &shadow_final_layout,
+            &shadow_depth_texture,
+            &shadow_sampler,
+            &projector_ubo,
+            &projector_texture,
+        );
 
         // Initialize global uniform bindings for camera and lighting
         let light_config = LightConfig {
@@ -298,21 +535,8 @@ impl RenderEngine {
         let mut global_bindings = GlobalBindings::new(&device);
         global_bindings.create_bind_group(&device, &global_ubo);
 
-        // Create transform bind group layout for per-object transforms
-        let transform_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("Transform Bind Group Layout"),
-                entries: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                }],
-            });
+        // Transforms of all objects live in one storage buffer owned by the scene
+        let transform_bind_group_layout = TransformBuffer::bind_group_layout(&device);
 
         // Create a temporary material bindings to get the layout that matches our material system
         let temp_material_bindings =
@@ -320,7 +544,7 @@ impl RenderEngine {
         let material_bind_group_layout = temp_material_bindings.bind_group_layouts().clone();
 
         // Create visualization renderer (before device is moved)
-        let visualization_renderer = VisualizationRenderer::new(&device, format);
+        let visualization_renderer = VisualizationRenderer::new(&device, format, depth_mode);
 
         // Wrap device and queue in Arc for pipeline manager
         let device_handle: Arc<Device> = device.into();
@@ -328,9 +552,10 @@ impl RenderEngine {
         let mut pipeline_manager = PipelineManager::new(device_handle.clone());
 
         // Load shaders
-        let _ = pipeline_manager.load_shader("default", include_str!("pbr.wgsl"));
+        let _ = pipeline_manager.load_shader("default", &with_color_functions(include_str!("pbr.wgsl")));
         let _ = pipeline_manager.load_shader("shadow", include_str!("shadow_pass.wgsl"));
         let _ = pipeline_manager.load_shader("blur", include_str!("shadow_blur.wgsl"));
+        let _ = pipeline_manager.load_shader("depth_prepass", include_str!("depth_prepass.wgsl"));
 
         // Register shadow depth pass - NO CULLING to prevent light leaks
         pipeline_manager.register_pipeline(
@@ -364,29 +589,86 @@ impl RenderEngine {
                 .with_no_vertex_buffers(), // This is crucial!
         );
 
-        // Register PBR pipeline with shadow support
+        // Depth-only pass over opaque objects, culled like the PBR pipeline
         pipeline_manager.register_pipeline(
-            "PBR",
+            "Depth Prepass",
             PipelineConfig::default()
-                .with_shader("default")
+                .with_label("Depth Prepass")
+                .with_shader("depth_prepass")
                 .with_depth_stencil(depth_texture.texture.clone())
+                .with_depth_compare(depth_mode.compare(wgpu::CompareFunction::Less))
                 .with_bind_group_layouts(vec![
                     global_bindings.bind_group_layouts().clone(),
-                    transform_bind_group_layout,
-                    material_bind_group_layout,
-                    shadow_final_layout,
-                ]),
+                    transform_bind_group_layout.clone(),
+                ])
+                .with_color_targets(vec![]),
+        );
+
+        // Register PBR pipeline with shadow support
+        let pbr_config = PipelineConfig::default()
+            .with_shader("default")
+            .with_depth_stencil(depth_texture.texture.clone())
+            .with_depth_compare(depth_mode.compare(wgpu::CompareFunction::Less))
+            .with_bind_group_layouts(vec![
+                global_bindings.bind_group_layouts().clone(),
+                transform_bind_group_layout,
+                material_bind_group_layout,
+                shadow_final_layout.clone(),
+            ]);
+
+        // Opaque shading over the pre-pass depth: only the nearest surface
+        // passes the test, and depth is already written
+        pipeline_manager.register_pipeline(
+            "PBR Early-Z",
+            pbr_config
+                .clone()
+                .with_label("PBR Early-Z")
+                .with_depth_compare(depth_mode.compare(wgpu::CompareFunction::LessEqual))
+                .with_depth_write(false),
+        );
+
+        // Same shading for transparent materials, blended without depth writes
+        pipeline_manager.register_pipeline(
+            "PBR Transparent",
+            pbr_config
+                .clone()
+                .with_label("PBR Transparent")
+                .with_blend(wgpu::BlendState::ALPHA_BLENDING)
+                .with_depth_write(false),
+        );
+        // Ghosted objects, blended in two passes without depth writes: faint
+        // where other geometry hides them, stronger where nothing does
+        let ghost_config = pbr_config
+            .clone()
+            .with_blend(wgpu::BlendState::ALPHA_BLENDING)
+            .with_depth_write(false);
+        pipeline_manager.register_pipeline(
+            "PBR Ghost Hidden",
+            ghost_config
+                .clone()
+                .with_label("PBR Ghost Hidden")
+                .with_depth_compare(depth_mode.compare(wgpu::CompareFunction::Greater))
+                .with_constant("alpha_scale", GHOST_HIDDEN_ALPHA),
         );
+        pipeline_manager.register_pipeline(
+            "PBR Ghost",
+            ghost_config
+                .with_label("PBR Ghost")
+                .with_constant("alpha_scale", GHOST_ALPHA),
+        );
+        pipeline_manager.register_pipeline("PBR", pbr_config);
 
         let _ = pipeline_manager.create_all_pipelines();
+        let frame_timer = GpuTimer::new(&device_handle, &queue_handle);
 
-        RenderEngine {
+        Ok(RenderEngine {
             device: device_handle,
             config,
             format,
             surface,
             queue: queue_handle,
             depth_texture,
+            depth_mode,
             pipeline_manager,
             global_bindings,
             global_ubo,
@@ -394,12 +676,37 @@ impl RenderEngine {
             shadow_color_view,
             blurred_shadow_view,
             shadow_bind_group,
+            shadow_final_layout,
+            shadow_sampler,
+            projector_ubo,
+            projector_texture,
+            projector_revision: None,
             blur_bind_group,
             light_config,
+            render_settings: RenderSettings::default(),
+            clip_planes: Vec::new(),
+            background_renderer: None,
             shadow_cache: ShadowCache::new(),
+            shadow_layers: None,
             visualization_renderer,
             instanced_grid: None,
-        }
+            agent_renderer: None,
+            volume_renderer: None,
+            line_renderer: None,
+            overlay_config: None,
+            overlay_lines: Vec::new(),
+            viewport_targets: Vec::new(),
+            surface_supports_copy,
+            supported_present_modes,
+            capabilities,
+            compute_device,
+            device_lost,
+            render_texture_targets: std::collections::HashMap::new(),
+            frame_timer,
+            timed_prepass: false,
+            prepass_timing: PrepassTiming::default(),
+            rejected_shaders: std::collections::HashMap::new(),
+        })
     }
 
     /// Renders a frame with optional UI overlay and visualization planes
@@ -411,23 +718,74 @@ impl RenderEngine {
     /// * `scene` - Scene containing objects to render
     /// * `visualization_planes` - Visualization planes with simulation data
     /// * `ui_callback` - Optional function that renders UI elements
+    ///
+    /// # Returns
+    /// `false` if the frame was skipped because no surface texture was available
     pub fn render_frame<F>(
         &mut self,
         scene: &Scene,
         visualization_planes: &[VisualizationPlane],
         ui_callback: Option<F>,
-    ) where
+    ) -> bool
+    where
+        F: FnOnce(&wgpu::Device, &wgpu::Queue, &mut wgpu::CommandEncoder, &wgpu::TextureView),
+    {
+        self.render_frame_with_viewports(scene, visualization_planes, &[], ui_callback)
+    }
+
+    /// Renders a frame like [`RenderEngine::render_frame`], plus secondary viewports
+    ///
+    /// Each enabled viewport is rendered from its own camera into an offscreen
+    /// target and copied into its rectangle of the window after the main view,
+    /// before the UI overlay. Viewports are skipped if the surface does not
+    /// support copy destinations.
+    ///
+    /// # Arguments
+    /// * `scene` - Scene containing objects to render
+    /// * `visualization_planes` - Visualization planes with simulation data
+    /// * `viewports` - Secondary viewports to render on top of the main view
+    /// * `ui_callback` - Optional function that renders UI elements
+    ///
+    /// # Returns
+    /// `false` if the frame was skipped because no surface texture was available.
+    /// Lost or outdated surfaces are reconfigured for the next frame; other
+    /// failures are reported through [`RenderEngine::device_lost`].
+    pub fn render_frame_with_viewports<F>(
+        &mut self,
+        scene: &Scene,
+        visualization_planes: &[VisualizationPlane],
+        viewports: &[Viewport],
+        ui_callback: Option<F>,
+    ) -> bool
+    where
         F: FnOnce(&wgpu::Device, &wgpu::Queue, &mut wgpu::CommandEncoder, &wgpu::TextureView),
     {
-        let surface_texture = self
-            .surface
-            .get_current_texture()
-            .expect("Failed to get surface texture!");
+        let Some(surface) = &self.surface else {
+            return false;
+        };
+        let surface_texture = match surface.get_current_texture() {
+            Ok(surface_texture) => surface_texture,
+            // Window moved between monitors, minimized, etc. - reconfigure and retry next frame
+            Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                surface.configure(&self.device, &self.config);
+                return false;
+            }
+            Err(wgpu::SurfaceError::Timeout) => return false,
+            Err(error) => {
+                self.device_lost
+                    .lock()
+                    .unwrap()
+                    .get_or_insert_with(|| format!("Failed to acquire surface texture: {}", error));
+                return false;
+            }
+        };
 
         let surface_texture_view = surface_texture
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
 
+        self.begin_frame_timing();
+
         let mut encoder = self
             .device
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
@@ -436,17 +794,16 @@ impl RenderEngine {
 
         // PASS 1: Shadow mapping (render to depth AND color for depth extraction)
         // Check if shadow map needs to be regenerated using cache
+        if self.shadow_layers != Some(scene.layers.visible()) {
+            self.shadow_cache.invalidate();
+            self.shadow_layers = Some(scene.layers.visible());
+        }
         let needs_shadow_update = self.shadow_cache.needs_update(&self.light_config, &scene.objects);
         
         if needs_shadow_update {
-            // #[cfg(debug_assertions)]
-            // println!("🌒 Shadow map cache MISS - regenerating shadows");
-            
-            // Alternative: Environment variable debug
-            if std::env::var("HAGGIS_SHADOW_DEBUG").is_ok() {
-                eprintln!("SHADOW DEBUG: Regenerating shadow map");
-            }
-            
+            tracing::trace!(target: "haggis::shadow", "Shadow map cache miss - regenerating shadows");
+
+            crate::logging::crash::record_pass("Shadow Depth Pass");
             let mut shadow_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Shadow Depth Pass"),
                 color_attachments: &[], // No color attachment - depth only
@@ -464,17 +821,20 @@ impl RenderEngine {
 
             shadow_pass.set_bind_group(0, self.global_bindings.bind_groups(), &[]);
 
-            if let Some(shadow_pipeline) = self.pipeline_manager.get_pipeline("Shadow") {
+            if let (Some(shadow_pipeline), Some(transforms)) = (
+                self.pipeline_manager.get_pipeline("Shadow"),
+                scene.transform_buffer(),
+            ) {
                 shadow_pass.set_pipeline(shadow_pipeline);
+                shadow_pass.set_bind_group(1, transforms.bind_group(), &[]);
 
-                for object in scene.objects.iter() {
-                    if object.visible {
-                        shadow_pass.draw_object(object);
+                for (slot, object) in scene.objects.iter().enumerate() {
+                    if scene.is_object_visible(object) && !object.ghost {
+                        shadow_pass.draw_object(object, slot as u32);
                     }
                 }
             } else {
-                #[cfg(debug_assertions)]
-                println!("❌ Shadow pipeline not found!");
+                tracing::warn!("Shadow pipeline not found");
             }
         }
 
@@ -487,35 +847,38 @@ impl RenderEngine {
             // Mark shadow cache as valid after successful update
             self.shadow_cache.mark_valid(&self.light_config, &scene.objects);
         } else {
-            // #[cfg(debug_assertions)]
-            // println!("✨ Shadow map cache HIT - skipping shadow passes");
-            
-            if std::env::var("HAGGIS_SHADOW_DEBUG").is_ok() {
-                eprintln!("SHADOW DEBUG: Using cached shadow map");
-            }
+            tracing::trace!(target: "haggis::shadow", "Shadow map cache hit - skipping shadow passes");
         }
 
-        // PASS 4: Main rendering with shadows
+        // PASS 4: Main rendering with shadows, after the optional depth pre-pass
+        // Make sure the pipelines exist before recording passes through `&self`
+        self.prepare_scene_pipelines(scene);
+        let depth_load = self.record_depth_prepass(
+            &mut encoder,
+            &self.depth_texture.view,
+            self.global_bindings.bind_groups(),
+            scene,
+            scene.camera_manager.camera.eye,
+            scene.camera_manager.camera.layers,
+        );
         {
+            crate::logging::crash::record_pass("Main Render Pass");
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Main Render Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
                     view: &surface_texture_view,
                     resolve_target: None,
                     ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.1,
-                            g: 0.2,
-                            b: 0.3,
-                            a: 1.0,
-                        }),
+                        load: wgpu::LoadOp::Clear(self.output_clear_color(
+                            self.render_settings.wgpu_clear_color(),
+                        )),
                         store: wgpu::StoreOp::Store,
                     },
                 })],
                 depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
                     view: &self.depth_texture.view,
                     depth_ops: Some(wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(1.0),
+                        load: depth_load,
                         store: wgpu::StoreOp::Store,
                     }),
                     stencil_ops: None,
@@ -524,39 +887,30 @@ impl RenderEngine {
                 timestamp_writes: None,
             });
 
-            render_pass.set_bind_group(0, self.global_bindings.bind_groups(), &[]);
-            render_pass.set_bind_group(3, &self.shadow_bind_group, &[]);
-
-            if let Some(pipeline) = self.pipeline_manager.get_pipeline("PBR") {
-                render_pass.set_pipeline(pipeline);
-
-                for object in scene.objects.iter() {
-                    if object.visible {
-                        let material = scene.get_material_for_object(object);
-
-                        if let Some(material_bind_group) = material.get_bind_group() {
-                            render_pass.set_bind_group(2, material_bind_group, &[]);
-                            render_pass.draw_object(object);
-                        } else {
-                            #[cfg(debug_assertions)]
-                            println!(
-                                "Skipping '{}' - material '{}' has no GPU resources",
-                                object.name, material.name
-                            );
-                        }
-                    }
+            if self.render_settings.background.is_some() {
+                if let Some(ref background) = self.background_renderer {
+                    background.render(&mut render_pass, self.global_bindings.bind_groups());
                 }
             }
 
-            // Render instanced grid after scene objects (same render pass for proper depth testing)
-            self.render_instanced_grid(&mut render_pass);
+            self.draw_scene_objects(
+                &mut render_pass,
+                self.global_bindings.bind_groups(),
+                scene,
+                scene.camera_manager.camera.eye,
+                scene.camera_manager.camera.layers,
+            );
         }
 
         // PASS 5: Visualization rendering (separate from scene objects)
-        if !visualization_planes.is_empty() {
+        let shown = scene.layers.visible() & scene.camera_manager.camera.layers;
+        if !visualization_planes.is_empty() && shown.intersects(Layers::VISUALIZATION) {
             // Update visualization camera with scene camera
-            self.visualization_renderer
-                .update_camera(&self.queue, scene.camera_manager.get_view_proj_matrix());
+            self.visualization_renderer.update_camera(
+                &self.queue,
+                scene.camera_manager.get_view_proj_matrix(),
+                self.color_flags(),
+            );
 
             // Render visualization planes with their simulation data
             self.visualization_renderer.render_visualization_pass(
@@ -568,7 +922,35 @@ impl RenderEngine {
             );
         }
 
-        // PASS 6: UI overlay (if provided)
+        // Ghosted objects over the scene, volumes and cut planes
+        self.record_ghost_pass(
+            &mut encoder,
+            &surface_texture_view,
+            &self.depth_texture.view,
+            self.global_bindings.bind_groups(),
+            scene,
+            scene.camera_manager.camera.eye,
+            scene.camera_manager.camera.layers,
+        );
+
+        // PASS 6: Secondary viewports, each in its own submission so that the
+        // shared visualization camera buffer can be rewritten per viewport
+        if viewports.iter().any(|viewport| viewport.enabled) {
+            if self.surface_supports_copy {
+                self.queue.submit(std::iter::once(encoder.finish()));
+                self.render_viewports(scene, visualization_planes, viewports, &surface_texture.texture);
+
+                encoder = self
+                    .device
+                    .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                        label: Some("UI Encoder"),
+                    });
+            } else {
+                tracing::trace!("Surface does not support copies - skipping secondary viewports");
+            }
+        }
+
+        // PASS 7: UI overlay (if provided)
         if let Some(ui_callback) = ui_callback {
             ui_callback(
                 &self.device,
@@ -579,7 +961,740 @@ impl RenderEngine {
         }
 
         self.queue.submit(std::iter::once(encoder.finish()));
+        if let Some(timer) = self.frame_timer.as_mut() {
+            timer.end(&self.device, &self.queue);
+        }
         surface_texture.present();
+        true
+    }
+
+    /// Collects the last GPU frame time and starts timing this frame
+    fn begin_frame_timing(&mut self) {
+        let Some(timer) = self.frame_timer.as_mut() else {
+            return;
+        };
+        if let Some(time) = timer.poll(&self.device) {
+            self.prepass_timing
+                .record(self.timed_prepass, time.as_secs_f32() * 1000.0);
+        }
+        if timer.begin(&self.device, &self.queue) {
+            self.timed_prepass = self.render_settings.depth_prepass;
+        }
+        self.prepass_timing.enabled = self.render_settings.depth_prepass;
+    }
+
+    /// Standard or reverse-Z depth, chosen with
+    /// [`RenderConfig::depth_mode`](super::RenderConfig::depth_mode)
+    ///
+    /// Cameras rendered by the engine must project with the same mode.
+    pub fn depth_mode(&self) -> DepthMode {
+        self.depth_mode
+    }
+
+    /// GPU frame times with and without the depth pre-pass
+    ///
+    /// Both stay `None` unless the device has
+    /// [`wgpu::Features::TIMESTAMP_QUERY`].
+    pub fn prepass_timing(&self) -> PrepassTiming {
+        self.prepass_timing
+    }
+
+    /// Creates the scene pipelines, including those of custom material
+    /// shaders, so passes can be recorded through `&self`
+    fn prepare_scene_pipelines(&mut self, scene: &Scene) {
+        let _ = self.pipeline_manager.get_pipeline("PBR");
+        if self.render_settings.depth_prepass {
+            let _ = self.pipeline_manager.get_pipeline("Depth Prepass");
+            let _ = self.pipeline_manager.get_pipeline("PBR Early-Z");
+        }
+
+        let materials = scene.material_manager.list_materials();
+        for material in materials
+            .into_iter()
+            .filter_map(|id| scene.material_manager.get_material(id))
+        {
+            if let Some(shader) = &material.shader {
+                self.prepare_custom_pipeline(shader, material.is_transparent());
+            }
+        }
+        // Faded objects blend even when their material is opaque
+        for object in scene.objects.iter().filter(|object| object.opacity() < 1.0) {
+            if let Some(shader) = &scene.get_material_for_object(object).shader {
+                self.prepare_custom_pipeline(shader, true);
+            }
+        }
+    }
+
+    /// Compiles a custom material shader when its source is new or changed
+    /// and creates its pipeline, with the PBR pipeline's bind groups and
+    /// depth state
+    fn prepare_custom_pipeline(&mut self, shader: &CustomShader, transparent: bool) {
+        let module = shader.module_name();
+        let source = shader.compose();
+        if self.pipeline_manager.get_shader_source(&module) != Some(source.as_str()) {
+            if self.rejected_shaders.get(&module) == Some(&source) {
+                return;
+            }
+            // Check with naga first, the device would only report a
+            // validation error
+            if let Err(error) = shader.validate() {
+                tracing::error!("Custom shader falls back to PBR: {error}");
+                self.rejected_shaders.insert(module, source);
+                return;
+            }
+            self.rejected_shaders.remove(&module);
+            let _ = self.pipeline_manager.load_shader(&module, &source);
+
+            let Some(pbr) = self.pipeline_manager.get_config("PBR").cloned() else {
+                return;
+            };
+            let name = shader.pipeline_name(false);
+            let opaque = pbr.with_shader(&module).with_label(&name);
+            self.pipeline_manager.register_pipeline(
+                &shader.pipeline_name(true),
+                opaque
+                    .clone()
+                    .with_label(&shader.pipeline_name(true))
+                    .with_blend(wgpu::BlendState::ALPHA_BLENDING)
+                    .with_depth_write(false),
+            );
+            self.pipeline_manager.register_pipeline(&name, opaque);
+        }
+        let _ = self
+            .pipeline_manager
+            .get_pipeline(&shader.pipeline_name(transparent));
+    }
+
+    /// Records the depth pre-pass if it is enabled: opaque objects write
+    /// their depth without shading, so the main pass shades only the nearest
+    /// surface of each pixel
+    ///
+    /// # Returns
+    /// How the main pass should load `depth_view`: keep the pre-pass depth,
+    /// or clear it
+    fn record_depth_prepass(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        depth_view: &wgpu::TextureView,
+        global_bind_group: &wgpu::BindGroup,
+        scene: &Scene,
+        eye: cgmath::Vector3<f32>,
+        layers: Layers,
+    ) -> wgpu::LoadOp<f32> {
+        let (Some(pipeline), Some(transforms)) =
+            (self.depth_prepass_pipeline(), scene.transform_buffer())
+        else {
+            return wgpu::LoadOp::Clear(self.depth_mode.clear_value());
+        };
+
+        crate::logging::crash::record_pass("Depth Prepass");
+        let mut prepass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Depth Prepass"),
+            color_attachments: &[],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(self.depth_mode.clear_value()),
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        prepass.set_pipeline(pipeline);
+        prepass.set_bind_group(0, global_bind_group, &[]);
+        prepass.set_bind_group(1, transforms.bind_group(), &[]);
+        // Custom vertex stages may move vertices, so those objects write
+        // their own depth in the main pass
+        let draw_list = self.draw_list(scene, eye, layers);
+        for item in draw_list.opaque().iter().filter(|item| item.material.shader.is_none()) {
+            prepass.draw_object(item.object, item.transform_slot);
+        }
+        wgpu::LoadOp::Load
+    }
+
+    /// Pre-pass pipeline, if the pre-pass is enabled and its pipelines exist
+    fn depth_prepass_pipeline(&self) -> Option<&wgpu::RenderPipeline> {
+        if !self.render_settings.depth_prepass
+            || self.pipeline_manager.get_created_pipeline("PBR Early-Z").is_none()
+        {
+            return None;
+        }
+        self.pipeline_manager.get_created_pipeline("Depth Prepass")
+    }
+
+    /// Visible objects in draw order for the current render settings
+    fn draw_list<'a>(
+        &self,
+        scene: &'a Scene,
+        eye: cgmath::Vector3<f32>,
+        layers: Layers,
+    ) -> DrawList<'a> {
+        let mut draw_list = DrawList::build(scene, eye, layers);
+        if self.render_settings.front_to_back {
+            draw_list.sort_front_to_back();
+        }
+        draw_list
+    }
+
+    /// Draws all visible scene objects, the instanced grid, agents, volumes and the reference overlay into an open render pass
+    ///
+    /// Opaque objects are drawn first, grouped by material or front to back;
+    /// transparent objects are drawn last, back to front as seen from `eye`.
+    /// Ghosted objects are left to [`record_ghost_pass`](Self::record_ghost_pass).
+    /// Only layers shown in the scene and in the camera's `layers` are drawn.
+    /// After a depth pre-pass, opaque objects only shade the surfaces that
+    /// passed it.
+    fn draw_scene_objects<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        global_bind_group: &'a wgpu::BindGroup,
+        scene: &'a Scene,
+        eye: cgmath::Vector3<f32>,
+        layers: Layers,
+    ) {
+        let draw_list = self.draw_list(scene, eye, layers);
+        let shown = scene.layers.visible() & layers;
+
+        let opaque_pipeline = if self.depth_prepass_pipeline().is_some() {
+            "PBR Early-Z"
+        } else {
+            "PBR"
+        };
+        if let Some(pipeline) = self.pipeline_manager.get_created_pipeline(opaque_pipeline) {
+            self.draw_items(render_pass, pipeline, global_bind_group, scene, draw_list.opaque());
+        }
+
+        // Render instanced grid after scene objects (same render pass for proper depth testing)
+        if let Some(ref grid) = self.instanced_grid {
+            grid.render(render_pass, global_bind_group);
+        }
+
+        if shown.intersects(Layers::VISUALIZATION) {
+            if let Some(ref agents) = self.agent_renderer {
+                agents.render(render_pass, global_bind_group);
+            }
+        }
+
+        // Reference overlay lines come first, visualization lines after them
+        if let Some(ref lines) = self.line_renderer {
+            let overlay = self.overlay_lines.len() as u32;
+            if shown.intersects(Layers::REFERENCE) {
+                lines.render_range(render_pass, global_bind_group, 0..overlay);
+            }
+            if shown.intersects(Layers::VISUALIZATION) {
+                lines.render_range(render_pass, global_bind_group, overlay..u32::MAX);
+            }
+        }
+
+        // Transparent objects last, so everything behind them is already drawn
+        if !draw_list.transparent().is_empty() {
+            if let Some(pipeline) = self.pipeline_manager.get_created_pipeline("PBR Transparent") {
+                self.draw_items(render_pass, pipeline, global_bind_group, scene, draw_list.transparent());
+            }
+        }
+
+        // Volumes blend over everything, hidden only by opaque objects in front of them
+        if shown.intersects(Layers::VISUALIZATION) {
+            if let Some(ref volumes) = self.volume_renderer {
+                volumes.render(render_pass, global_bind_group);
+            }
+        }
+    }
+
+    /// Draws sorted objects with the PBR bind groups, switching the material
+    /// bind group only when it changes
+    ///
+    /// Objects are drawn with `pipeline`, or with their material's custom
+    /// shader if it has one and they are not ghosted.
+    fn draw_items<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        pipeline: &'a wgpu::RenderPipeline,
+        global_bind_group: &'a wgpu::BindGroup,
+        scene: &'a Scene,
+        items: &[DrawItem<'a>],
+    ) {
+        let Some(transforms) = scene.transform_buffer() else {
+            return;
+        };
+        render_pass.set_bind_group(0, global_bind_group, &[]);
+        render_pass.set_bind_group(1, transforms.bind_group(), &[]);
+        render_pass.set_bind_group(3, &self.shadow_bind_group, &[]);
+
+        let mut previous: Option<&DrawItem> = None;
+        let mut current_pipeline: Option<&wgpu::RenderPipeline> = None;
+        for item in items {
+            let Some(material_bind_group) = item.material.get_bind_group() else {
+                tracing::trace!(
+                    "Skipping '{}' - material '{}' has no GPU resources",
+                    item.object.name, item.material.name
+                );
+                continue;
+            };
+            let item_pipeline = item
+                .material
+                .shader
+                .as_ref()
+                .filter(|_| !item.object.ghost)
+                .and_then(|shader| {
+                    self.pipeline_manager
+                        .get_created_pipeline(&shader.pipeline_name(item.is_transparent()))
+                })
+                .unwrap_or(pipeline);
+            if !current_pipeline.is_some_and(|current| std::ptr::eq(current, item_pipeline)) {
+                render_pass.set_pipeline(item_pipeline);
+                current_pipeline = Some(item_pipeline);
+            }
+            if !previous.is_some_and(|previous| previous.shares_material(item)) {
+                render_pass.set_bind_group(2, material_bind_group, &[]);
+            }
+            render_pass.draw_object(item.object, item.transform_slot);
+            previous = Some(item);
+        }
+    }
+
+    /// Records the scene and visualization passes for an offscreen camera
+    ///
+    /// The target's global uniforms must already hold this camera. The shared
+    /// visualization camera buffer is rewritten, so the encoder has to be
+    /// submitted before recording passes for another camera.
+    fn record_offscreen_passes(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        scene: &Scene,
+        visualization_planes: &[VisualizationPlane],
+        camera: ViewCamera,
+        clear_color: wgpu::Color,
+        target: &ViewportTarget,
+    ) {
+        let [x, y, z, _] = camera.uniform.view_position;
+        let eye = cgmath::Vector3::new(x, y, z);
+        let depth_load = self.record_depth_prepass(
+            encoder,
+            &target.depth.view,
+            target.global_bindings.bind_groups(),
+            scene,
+            eye,
+            camera.layers,
+        );
+        {
+            crate::logging::crash::record_pass("Offscreen Render Pass");
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Offscreen Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &target.color_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(self.output_clear_color(clear_color)),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &target.depth.view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: depth_load,
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+
+            self.draw_scene_objects(
+                &mut render_pass,
+                target.global_bindings.bind_groups(),
+                scene,
+                eye,
+                camera.layers,
+            );
+        }
+
+        let shown = scene.layers.visible() & camera.layers;
+        if !visualization_planes.is_empty() && shown.intersects(Layers::VISUALIZATION) {
+            self.visualization_renderer.update_camera(
+                &self.queue,
+                camera.uniform.view_proj.into(),
+                self.color_flags(),
+            );
+            self.visualization_renderer.render_visualization_pass(
+                encoder,
+                &target.color_view,
+                &target.depth.view,
+                visualization_planes,
+                &self.queue,
+            );
+        }
+
+        self.record_ghost_pass(
+            encoder,
+            &target.color_view,
+            &target.depth.view,
+            target.global_bindings.bind_groups(),
+            scene,
+            eye,
+            camera.layers,
+        );
+    }
+
+    /// Records ghosted objects over everything else drawn into `color_view`
+    ///
+    /// Runs after the visualization pass, so ghosts show faintly through cut
+    /// planes, volumes and objects in front of them, and somewhat stronger
+    /// where nothing is. They write no depth and never hide what is behind
+    /// them.
+    #[allow(clippy::too_many_arguments)]
+    fn record_ghost_pass(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        color_view: &wgpu::TextureView,
+        depth_view: &wgpu::TextureView,
+        global_bind_group: &wgpu::BindGroup,
+        scene: &Scene,
+        eye: cgmath::Vector3<f32>,
+        layers: Layers,
+    ) {
+        let draw_list = DrawList::build(scene, eye, layers);
+        if draw_list.ghost().is_empty() {
+            return;
+        }
+
+        crate::logging::crash::record_pass("Ghost Pass");
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Ghost Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: color_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        for name in ["PBR Ghost Hidden", "PBR Ghost"] {
+            if let Some(pipeline) = self.pipeline_manager.get_created_pipeline(name) {
+                self.draw_items(&mut render_pass, pipeline, global_bind_group, scene, draw_list.ghost());
+            }
+        }
+    }
+
+    /// Renders the scene from `camera` into `target` and submits the work
+    ///
+    /// (Re)creates the target when it is missing or its size differs from `size`.
+    pub(crate) fn render_camera_to_target(
+        &mut self,
+        target: &mut Option<ViewportTarget>,
+        size: (u32, u32),
+        scene: &Scene,
+        visualization_planes: &[VisualizationPlane],
+        camera: ViewCamera,
+        clear_color: wgpu::Color,
+    ) {
+        self.prepare_scene_pipelines(scene);
+        if target.as_ref().is_none_or(|target| target.size != size) {
+            *target = Some(ViewportTarget::new(&self.device, self.format, size.0, size.1));
+        }
+        let Some(target) = target.as_mut() else {
+            return;
+        };
+        update_global_ubo_with_settings(
+            &mut target.global_ubo,
+            &self.queue,
+            camera.uniform,
+            self.light_config,
+            &self.render_settings,
+            &self.clip_planes,
+            self.format,
+        );
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Offscreen View Encoder"),
+            });
+        self.record_offscreen_passes(
+            &mut encoder,
+            scene,
+            visualization_planes,
+            camera,
+            clear_color,
+            target,
+        );
+        self.queue.submit(std::iter::once(encoder.finish()));
+    }
+
+    /// Renders each enabled viewport offscreen and copies it into the surface texture
+    /// Renders the main camera view offscreen and reads it back to the CPU
+    ///
+    /// The capture shows the scene objects and visualization planes at `size`
+    /// pixels, without the UI. Blocks until the GPU has finished.
+    pub fn capture_frame(
+        &mut self,
+        scene: &Scene,
+        visualization_planes: &[VisualizationPlane],
+        size: (u32, u32),
+    ) -> std::result::Result<CapturedFrame, wgpu::BufferAsyncError> {
+        let size = (size.0.max(1), size.1.max(1));
+        let mut camera = scene.camera_manager.camera;
+        camera.depth_mode = self.depth_mode;
+        camera.resize_projection(size.0, size.1);
+        camera.update_view_proj();
+
+        let mut target = None;
+        self.render_camera_to_target(
+            &mut target,
+            size,
+            scene,
+            visualization_planes,
+            ViewCamera {
+                uniform: camera.uniform,
+                layers: camera.layers,
+            },
+            self.render_settings.wgpu_clear_color(),
+        );
+        let Some(target) = target else {
+            return Err(wgpu::BufferAsyncError);
+        };
+
+        // Copies need rows aligned to 256 bytes, the padding is dropped below
+        let row_bytes = size.0 * 4;
+        let padded_row_bytes = row_bytes.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Frame Capture Buffer"),
+            size: padded_row_bytes as u64 * size.1 as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Frame Capture Encoder"),
+            });
+        encoder.copy_texture_to_buffer(
+            target.color.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &staging,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_bytes),
+                    rows_per_image: Some(size.1),
+                },
+            },
+            target.color.size(),
+        );
+        self.queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = staging.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        let _ = self.device.poll(wgpu::MaintainBase::Wait);
+        receiver.recv().unwrap_or(Err(wgpu::BufferAsyncError))?;
+
+        let mapped = slice.get_mapped_range();
+        let mut texels = Vec::with_capacity((row_bytes * size.1) as usize);
+        for row in mapped.chunks_exact(padded_row_bytes as usize) {
+            texels.extend_from_slice(&row[..row_bytes as usize]);
+        }
+        drop(mapped);
+        staging.unmap();
+        Ok(CapturedFrame::from_texels(size.0, size.1, self.format, texels))
+    }
+
+    fn render_viewports(
+        &mut self,
+        scene: &Scene,
+        visualization_planes: &[VisualizationPlane],
+        viewports: &[Viewport],
+        surface_texture: &wgpu::Texture,
+    ) {
+        let (surface_width, surface_height) = (self.config.width, self.config.height);
+
+        // Keep one target slot per viewport; drop stale ones
+        self.viewport_targets.resize_with(viewports.len(), || None);
+
+        for (index, viewport) in viewports.iter().enumerate() {
+            if !viewport.enabled {
+                continue;
+            }
+            let Some((x, y, width, height)) = viewport.rect.to_pixels(surface_width, surface_height)
+            else {
+                continue;
+            };
+
+            // (Re)create the offscreen target when the pixel size changes
+            let needs_target = self.viewport_targets[index]
+                .as_ref()
+                .is_none_or(|target| target.size != (width, height));
+            if needs_target {
+                self.viewport_targets[index] =
+                    Some(ViewportTarget::new(&self.device, self.format, width, height));
+            }
+
+            let camera_uniform = viewport.camera_uniform(width, height, self.depth_mode);
+            if let Some(target) = self.viewport_targets[index].as_mut() {
+                update_global_ubo_with_settings(
+                    &mut target.global_ubo,
+                    &self.queue,
+                    camera_uniform,
+                    self.light_config,
+                    &self.render_settings,
+                    &self.clip_planes,
+                    self.format,
+                );
+            }
+
+            let Some(target) = self.viewport_targets[index].as_ref() else {
+                continue;
+            };
+
+            let mut encoder = self
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Viewport Encoder"),
+                });
+
+            self.record_offscreen_passes(
+                &mut encoder,
+                scene,
+                visualization_planes,
+                ViewCamera {
+                    uniform: camera_uniform,
+                    layers: viewport.camera.layers,
+                },
+                viewport.clear_color,
+                target,
+            );
+
+            encoder.copy_texture_to_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture: &target.color,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                wgpu::TexelCopyTextureInfo {
+                    texture: surface_texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d { x, y, z: 0 },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+            );
+
+            self.queue.submit(std::iter::once(encoder.finish()));
+        }
+    }
+
+    /// Renders every enabled render texture from its own camera
+    ///
+    /// Should be called before `render_frame()` each frame. Targets are created
+    /// or resized as needed; those are returned so the caller can register
+    /// them with the UI renderer. Targets of handles no longer passed in are freed.
+    ///
+    /// # Arguments
+    /// * `scene` - Scene containing objects to render
+    /// * `visualization_planes` - Visualization planes with simulation data
+    /// * `render_textures` - Render texture handles to update
+    ///
+    /// # Returns
+    /// Render textures whose GPU texture changed this frame
+    pub fn render_textures(
+        &mut self,
+        scene: &Scene,
+        visualization_planes: &[VisualizationPlane],
+        render_textures: &[RenderTexture],
+    ) -> Vec<RenderTextureUpdate> {
+        let mut updates = Vec::new();
+
+        self.render_texture_targets
+            .retain(|id, _| render_textures.iter().any(|handle| handle.id() == *id));
+
+        if render_textures.is_empty() {
+            return updates;
+        }
+
+        // Make sure the pipelines exist before recording passes through `&self`
+        self.prepare_scene_pipelines(scene);
+
+        for handle in render_textures {
+            let state = handle.state();
+            let size = (state.width, state.height);
+
+            let needs_target = self
+                .render_texture_targets
+                .get(&handle.id())
+                .is_none_or(|target| target.size != size);
+            if needs_target {
+                let target = ViewportTarget::new(&self.device, self.format, size.0, size.1);
+                updates.push(RenderTextureUpdate {
+                    handle: handle.clone(),
+                    texture: target.color.clone(),
+                    view: target.color_view.clone(),
+                    size,
+                });
+                self.render_texture_targets.insert(handle.id(), target);
+            } else if !state.enabled {
+                continue;
+            }
+
+            let mut camera = state.camera;
+            camera.depth_mode = self.depth_mode;
+            camera.resize_projection(size.0, size.1);
+            camera.update_view_proj();
+            if let Some(target) = self.render_texture_targets.get_mut(&handle.id()) {
+                update_global_ubo_with_settings(
+                    &mut target.global_ubo,
+                    &self.queue,
+                    camera.uniform,
+                    self.light_config,
+                    &self.render_settings,
+                    &self.clip_planes,
+                    self.format,
+                );
+            }
+
+            let Some(target) = self.render_texture_targets.get(&handle.id()) else {
+                continue;
+            };
+
+            let mut encoder = self
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Texture Encoder"),
+                });
+            self.record_offscreen_passes(
+                &mut encoder,
+                scene,
+                visualization_planes,
+                ViewCamera {
+                    uniform: camera.uniform,
+                    layers: camera.layers,
+                },
+                state.clear_color,
+                target,
+            );
+            self.queue.submit(std::iter::once(encoder.finish()));
+        }
+
+        updates
     }
 
     /// Convenience method for rendering without UI or visualizations
@@ -632,11 +1747,14 @@ impl RenderEngine {
     /// # Arguments
     /// * `camera_uniform` - Updated camera uniform data
     pub fn update(&mut self, camera_uniform: CameraUniform) {
-        update_global_ubo_with_light(
+        update_global_ubo_with_settings(
             &mut self.global_ubo,
             &self.queue,
             camera_uniform,
             self.light_config,
+            &self.render_settings,
+            &self.clip_planes,
+            self.format,
         );
     }
 
@@ -656,6 +1774,90 @@ impl RenderEngine {
         self.light_config
     }
 
+    /// Updates the clear color, background gradient and fog
+    ///
+    /// Fog and the gradient reach the shaders with the next update() call.
+    pub fn set_render_settings(&mut self, settings: &RenderSettings) {
+        self.render_settings = *settings;
+        if settings.background.is_some() && self.background_renderer.is_none() {
+            self.background_renderer = Some(BackgroundRenderer::new(
+                &self.device,
+                self.format,
+                &self.global_bindings,
+            ));
+        }
+    }
+
+    /// Gets the current clear color, background gradient and fog
+    pub fn render_settings(&self) -> &RenderSettings {
+        &self.render_settings
+    }
+
+    /// Sets the planes cutting away scene geometry
+    ///
+    /// Only the first [`MAX_CLIP_PLANES`](super::clip_plane::MAX_CLIP_PLANES)
+    /// planes are applied. Takes effect with the next update() call.
+    pub fn set_clip_planes(&mut self, clip_planes: &[ClipPlane]) {
+        self.clip_planes.clear();
+        self.clip_planes.extend_from_slice(clip_planes);
+    }
+
+    /// Sets the image projected onto scene geometry, or removes it
+    ///
+    /// A new image is uploaded when the projector's
+    /// [`revision`](Projector::revision) changes.
+    pub fn set_projector(&mut self, projector: Option<&Projector>) {
+        let revision = projector.map(Projector::revision);
+        if revision != self.projector_revision {
+            self.projector_revision = revision;
+            self.projector_texture = match projector.map(Projector::image) {
+                Some(ProjectorImage::Pixels {
+                    rgba,
+                    width,
+                    height,
+                }) if rgba.len() == (width * height * 4) as usize => {
+                    TextureResource::create_from_rgba_data_with_filter(
+                        &self.device,
+                        &self.queue,
+                        rgba,
+                        *width,
+                        *height,
+                        "Projector Image",
+                        wgpu::FilterMode::Linear,
+                    )
+                }
+                Some(ProjectorImage::Texture(texture)) => texture.clone(),
+                _ => TextureResource::create_from_rgba_data_with_filter(
+                    &self.device,
+                    &self.queue,
+                    &[255; 4],
+                    1,
+                    1,
+                    "Projector Image",
+                    wgpu::FilterMode::Linear,
+                ),
+            };
+            self.shadow_bind_group = create_shadow_bind_group(
+                &self.device,
+                &self.shadow_final_layout,
+                &self.shadow_depth_texture,
+                &self.shadow_sampler,
+                &self.projector_ubo,
+                &self.projector_texture,
+            );
+        }
+
+        let uniform = projector
+            .map(|projector| projector.uniform(!self.projector_texture.texture.format().is_srgb()))
+            .unwrap_or_default();
+        self.projector_ubo.set_if_changed(&self.queue, &uniform);
+    }
+
+    /// Gets the planes currently cutting away scene geometry
+    pub fn clip_planes(&self) -> &[ClipPlane] {
+        &self.clip_planes
+    }
+
     /// Resizes the render engine surface and recreates depth buffer
     ///
     /// Validates dimensions and clamps to minimum viable size to prevent
@@ -677,7 +1879,9 @@ impl RenderEngine {
         self.config.height = safe_height;
 
         // Reconfigure surface with new dimensions
-        self.surface.configure(&self.device, &self.config);
+        if let Some(surface) = &self.surface {
+            surface.configure(&self.device, &self.config);
+        }
 
         // Recreate depth texture to match new surface size
         self.depth_texture =
@@ -710,6 +1914,29 @@ impl RenderEngine {
         &self.queue
     }
 
+    /// Returns the limits and features of the GPU in use
+    pub fn capabilities(&self) -> &GpuCapabilities {
+        &self.capabilities
+    }
+
+    /// Device on the separate compute adapter, if one is in use
+    pub fn compute_device(&self) -> Option<&ComputeDevice> {
+        self.compute_device.as_ref()
+    }
+
+    /// Returns an error if the GPU device has been lost
+    ///
+    /// A lost device can't be used again; the engine (and every GPU resource
+    /// created from its device) has to be recreated. `HaggisApp` does this
+    /// automatically.
+    pub fn device_lost(&self) -> Option<HaggisError> {
+        self.device_lost
+            .lock()
+            .unwrap()
+            .clone()
+            .map(HaggisError::DeviceLost)
+    }
+
     /// Returns the surface texture format
     ///
     /// Used for creating compatible render targets and UI systems.
@@ -717,6 +1944,17 @@ impl RenderEngine {
         self.format
     }
 
+    /// `color_flags` of passes writing the surface or offscreen targets,
+    /// see [`color`](super::color)
+    pub fn color_flags(&self) -> u32 {
+        color::color_flags(self.format, self.render_settings.linear_debug)
+    }
+
+    /// Clear value for an sRGB clear color
+    fn output_clear_color(&self, color: wgpu::Color) -> wgpu::Color {
+        color::clear_color(color, self.color_flags())
+    }
+
     /// Invalidates the shadow map cache, forcing regeneration on next frame
     ///
     /// Use this when you know that shadow-affecting changes have occurred
@@ -752,7 +1990,7 @@ impl RenderEngine {
     /// This should be called after the render engine is created and before rendering.
     pub fn initialize_instanced_grid(&mut self, max_instances: u32) {
         let mut grid = InstancedGrid::new(&self.device, max_instances);
-        grid.initialize_pipeline(&self.device, self.format, &self.global_bindings);
+        grid.initialize_pipeline(&self.device, self.format, &self.global_bindings, self.depth_mode);
         self.instanced_grid = Some(grid);
     }
 
@@ -789,6 +2027,53 @@ impl RenderEngine {
         }
     }
 
+    /// Update the lines drawn with the scene
+    ///
+    /// Draws the grid and axes of `overlay` plus `extra_lines`, such as domain
+    /// boxes of visualizations. Grid geometry is only rebuilt when `overlay`
+    /// changed, and nothing is uploaded if the lines are the same as last frame.
+    pub fn update_lines(&mut self, overlay: &OverlayConfig, extra_lines: &[LineVertex]) {
+        if self.overlay_config.as_ref() != Some(overlay) {
+            self.overlay_lines = overlay.line_vertices();
+            self.overlay_config = Some(overlay.clone());
+        }
+
+        let mut vertices = Vec::with_capacity(self.overlay_lines.len() + extra_lines.len());
+        vertices.extend_from_slice(&self.overlay_lines);
+        vertices.extend_from_slice(extra_lines);
+
+        let lines = self.line_renderer.get_or_insert_with(|| {
+            LineRenderer::new(&self.device, self.format, &self.global_bindings, self.depth_mode)
+        });
+        lines.set_lines(&self.device, &self.queue, &vertices);
+    }
+
+    /// Update the agent batches drawn with the scene
+    ///
+    /// The renderer is only created once there is something to draw.
+    pub fn update_agents(&mut self, batches: Vec<AgentBatch>) {
+        if batches.is_empty() && self.agent_renderer.is_none() {
+            return;
+        }
+        let agents = self.agent_renderer.get_or_insert_with(|| {
+            AgentRenderer::new(&self.device, self.format, &self.global_bindings, self.depth_mode)
+        });
+        agents.set_batches(batches);
+    }
+
+    /// Update the volumes drawn with the scene
+    ///
+    /// The renderer is only created once there is something to draw.
+    pub fn update_volumes(&mut self, batches: Vec<VolumeBatch>) {
+        if batches.is_empty() && self.volume_renderer.is_none() {
+            return;
+        }
+        let volumes = self.volume_renderer.get_or_insert_with(|| {
+            VolumeRenderer::new(&self.device, self.format, &self.global_bindings, self.depth_mode)
+        });
+        volumes.set_batches(&self.device, &self.queue, &batches);
+    }
+
     /// Set VSync (vertical synchronization) state
     ///
     /// When VSync is enabled, rendering is synchronized to the display refresh rate.
@@ -797,15 +2082,32 @@ impl RenderEngine {
     /// # Arguments
     /// * `enable` - Whether to enable VSync
     pub fn set_vsync(&mut self, enable: bool) {
-        // Update the surface configuration with new present mode
-        self.config.present_mode = if enable {
+        self.set_present_mode(if enable {
             wgpu::PresentMode::Fifo        // VSync enabled
         } else {
             wgpu::PresentMode::Immediate   // VSync disabled, immediate presentation
-        };
-        
+        });
+    }
+
+    /// Set the surface present mode
+    ///
+    /// Falls back to the closest supported mode (and finally `Fifo`, which is
+    /// always available) if the surface doesn't support the requested one.
+    ///
+    /// # Arguments
+    /// * `present_mode` - Requested present mode
+    pub fn set_present_mode(&mut self, present_mode: wgpu::PresentMode) {
+        self.config.present_mode = resolve_present_mode(present_mode, &self.supported_present_modes);
+
         // Reconfigure surface with new present mode
-        self.surface.configure(&self.device, &self.config);
+        if let Some(surface) = &self.surface {
+            surface.configure(&self.device, &self.config);
+        }
+    }
+
+    /// The present mode currently in use
+    pub fn present_mode(&self) -> wgpu::PresentMode {
+        self.config.present_mode
     }
 
     /// Render the instanced grid during the main render pass
@@ -818,3 +2120,72 @@ impl RenderEngine {
         }
     }
 }
+
+/// Pick `requested` if supported, otherwise the closest supported present mode
+/// Bind group of the PBR pass's group 3: shadow map and projector
+fn create_shadow_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    shadow_depth_texture: &TextureResource,
+    shadow_sampler: &wgpu::Sampler,
+    projector_ubo: &UniformBuffer<ProjectorUniform>,
+    projector_texture: &TextureResource,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Shadow Bind Group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&shadow_depth_texture.view), // Use depth texture
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(shadow_sampler), // Use comparison sampler
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: projector_ubo.binding_resource(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: wgpu::BindingResource::TextureView(&projector_texture.view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 4,
+                resource: wgpu::BindingResource::Sampler(&projector_texture.sampler),
+            },
+        ],
+    })
+}
+
+fn resolve_present_mode(
+    requested: wgpu::PresentMode,
+    supported: &[wgpu::PresentMode],
+) -> wgpu::PresentMode {
+    use wgpu::PresentMode::*;
+
+    // Auto modes are resolved by wgpu itself
+    if supported.contains(&requested) || matches!(requested, AutoVsync | AutoNoVsync) {
+        return requested;
+    }
+
+    let fallbacks: &[wgpu::PresentMode] = match requested {
+        Immediate => &[Mailbox, FifoRelaxed],
+        Mailbox => &[Immediate, Fifo],
+        FifoRelaxed => &[Fifo],
+        _ => &[],
+    };
+    let mode = fallbacks
+        .iter()
+        .copied()
+        .find(|mode| supported.contains(mode))
+        .unwrap_or(Fifo);
+
+    tracing::debug!(
+        "Present mode {:?} not supported, using {:?}",
+        requested, mode
+    );
+
+    mode
+}
@@ -0,0 +1,264 @@
+//! # Render Settings
+//!
+//! Background and atmosphere of the main view: the clear color, an optional
+//! vertical background gradient and depth fog. Fog fades geometry towards a
+//! color with distance from the camera, which makes depth much easier to
+//! read in large particle clouds and long fluid domains.
+//!
+//! The settings live in `scene.render_settings` and are handed to the render
+//! engine every frame. Fog and the gradient are written into the global
+//! uniforms, so objects, the instanced grid and agents are fogged alike, in
+//! the main view as well as in secondary viewports and render textures.
+//! Secondary views keep their own clear color.
+//!
+//! Colors here are sRGB, as shown by the color pickers; see
+//! [`color`](super::color) for how they are converted for shading.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::gfx::rendering::{FogSettings, RenderSettings};
+//!
+//! let mut app = haggis::default();
+//! app.set_render_settings(RenderSettings {
+//!     clear_color: [0.05, 0.05, 0.08],
+//!     fog: FogSettings::exponential([0.05, 0.05, 0.08], 0.08),
+//!     ..RenderSettings::default()
+//! });
+//! app.show_render_settings_panel(true);
+//! ```
+
+use imgui::Ui;
+
+/// How fog density grows with distance from the camera
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FogMode {
+    /// No fog
+    #[default]
+    Off,
+    /// Fog rises linearly from `start` to full at `end`
+    Linear,
+    /// Fog amount is `1 - exp(-density * distance)`
+    Exponential,
+}
+
+impl FogMode {
+    /// All modes, for UI selection
+    pub const ALL: [FogMode; 3] = [FogMode::Off, FogMode::Linear, FogMode::Exponential];
+
+    /// Display name of the mode
+    pub fn name(&self) -> &'static str {
+        match self {
+            FogMode::Off => "Off",
+            FogMode::Linear => "Linear",
+            FogMode::Exponential => "Exponential",
+        }
+    }
+
+    /// Mode id used by the shaders
+    pub fn shader_id(&self) -> u32 {
+        match self {
+            FogMode::Off => 0,
+            FogMode::Linear => 1,
+            FogMode::Exponential => 2,
+        }
+    }
+}
+
+/// Depth fog applied to shaded geometry
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FogSettings {
+    pub mode: FogMode,
+    /// Color geometry fades to, usually the clear color
+    pub color: [f32; 3],
+    /// Distance where linear fog begins
+    pub start: f32,
+    /// Distance where linear fog is opaque
+    pub end: f32,
+    /// Density of exponential fog per world unit
+    pub density: f32,
+}
+
+impl Default for FogSettings {
+    fn default() -> Self {
+        Self {
+            mode: FogMode::Off,
+            color: RenderSettings::DEFAULT_CLEAR_COLOR,
+            start: 10.0,
+            end: 50.0,
+            density: 0.05,
+        }
+    }
+}
+
+impl FogSettings {
+    /// Linear fog from `start` to `end` world units away from the camera
+    pub fn linear(color: [f32; 3], start: f32, end: f32) -> Self {
+        Self {
+            mode: FogMode::Linear,
+            color,
+            start,
+            end,
+            ..Self::default()
+        }
+    }
+
+    /// Exponential fog with the given density per world unit
+    pub fn exponential(color: [f32; 3], density: f32) -> Self {
+        Self {
+            mode: FogMode::Exponential,
+            color,
+            density,
+            ..Self::default()
+        }
+    }
+
+    /// Fraction of the fog color mixed in at `distance` from the camera
+    ///
+    /// Mirrors the `fog_amount` function of the scene shaders.
+    pub fn amount(&self, distance: f32) -> f32 {
+        match self.mode {
+            FogMode::Off => 0.0,
+            FogMode::Linear => {
+                let range = (self.end - self.start).max(1e-4);
+                ((distance - self.start) / range).clamp(0.0, 1.0)
+            }
+            FogMode::Exponential => 1.0 - (-self.density.max(0.0) * distance.max(0.0)).exp(),
+        }
+    }
+}
+
+/// Vertical background gradient drawn behind the scene
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BackgroundGradient {
+    /// Color at the top of the view
+    pub top: [f32; 3],
+    /// Color at the bottom of the view
+    pub bottom: [f32; 3],
+}
+
+/// Clear color, background and fog of the main view
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderSettings {
+    /// Color the main view is cleared to
+    pub clear_color: [f32; 3],
+    /// Gradient drawn over the clear color, if any
+    pub background: Option<BackgroundGradient>,
+    pub fog: FogSettings,
+    /// Show raw linear colors without the sRGB curve, to debug gamma handling
+    pub linear_debug: bool,
+    /// Write the depth of opaque objects in a depth-only pass first, so the
+    /// main pass shades each pixel once
+    pub depth_prepass: bool,
+    /// Draw opaque objects nearest first instead of grouped by material
+    pub front_to_back: bool,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            clear_color: Self::DEFAULT_CLEAR_COLOR,
+            background: None,
+            fog: FogSettings::default(),
+            linear_debug: false,
+            depth_prepass: false,
+            front_to_back: false,
+        }
+    }
+}
+
+impl RenderSettings {
+    /// Blue-gray background the engine has always cleared to
+    pub const DEFAULT_CLEAR_COLOR: [f32; 3] = [0.1, 0.2, 0.3];
+
+    /// Clear color as a render pass load color
+    pub fn wgpu_clear_color(&self) -> wgpu::Color {
+        let [r, g, b] = self.clear_color.map(f64::from);
+        wgpu::Color { r, g, b, a: 1.0 }
+    }
+
+    /// Draw the "Render Settings" window
+    pub fn render_ui(&mut self, ui: &Ui) {
+        ui.window("Render Settings")
+            .size([300.0, 260.0], imgui::Condition::FirstUseEver)
+            .position([10.0, 220.0], imgui::Condition::FirstUseEver)
+            .build(|| {
+                ui.color_edit3("Clear color", &mut self.clear_color);
+
+                let mut gradient = self.background.is_some();
+                if ui.checkbox("Background gradient", &mut gradient) {
+                    self.background = gradient.then_some(BackgroundGradient {
+                        top: self.clear_color,
+                        bottom: self.clear_color.map(|c| c * 0.3),
+                    });
+                }
+                if let Some(background) = self.background.as_mut() {
+                    ui.color_edit3("Top", &mut background.top);
+                    ui.color_edit3("Bottom", &mut background.bottom);
+                }
+
+                ui.separator();
+                let fog = &mut self.fog;
+                let mut mode = FogMode::ALL
+                    .iter()
+                    .position(|mode| *mode == fog.mode)
+                    .unwrap_or(0);
+                if ui.combo("Fog", &mut mode, &FogMode::ALL, |mode| mode.name().into()) {
+                    fog.mode = FogMode::ALL[mode];
+                }
+                match fog.mode {
+                    FogMode::Off => {}
+                    FogMode::Linear => {
+                        ui.slider("Start", 0.0, 500.0, &mut fog.start);
+                        ui.slider("End", 0.0, 500.0, &mut fog.end);
+                    }
+                    FogMode::Exponential => {
+                        ui.slider("Density", 0.0, 1.0, &mut fog.density);
+                    }
+                }
+                if fog.mode != FogMode::Off {
+                    ui.color_edit3("Fog color", &mut fog.color);
+                    if ui.button("Match clear color") {
+                        fog.color = self.clear_color;
+                    }
+                }
+
+                ui.separator();
+                ui.checkbox("Linear output (debug)", &mut self.linear_debug);
+                if ui.is_item_hovered() {
+                    ui.tooltip_text("Skip the sRGB curve to spot passes that handle gamma differently");
+                }
+                ui.checkbox("Depth pre-pass", &mut self.depth_prepass);
+                if ui.is_item_hovered() {
+                    ui.tooltip_text(
+                        "Lay down opaque depth first; helps with heavy overdraw, costs a second vertex pass",
+                    );
+                }
+                ui.checkbox("Sort opaque front to back", &mut self.front_to_back);
+                if ui.is_item_hovered() {
+                    ui.tooltip_text("Nearest first for early depth rejection, at the cost of material batching");
+                }
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fog_amount_by_mode() {
+        let color = [0.5, 0.5, 0.5];
+        assert_eq!(FogSettings::default().amount(100.0), 0.0);
+
+        let linear = FogSettings::linear(color, 10.0, 20.0);
+        assert_eq!(linear.amount(5.0), 0.0);
+        assert_eq!(linear.amount(15.0), 0.5);
+        assert_eq!(linear.amount(30.0), 1.0);
+
+        let exponential = FogSettings::exponential(color, 0.1);
+        assert_eq!(exponential.amount(0.0), 0.0);
+        assert!((exponential.amount(10.0) - (1.0 - (-1.0f32).exp())).abs() < 1e-6);
+        assert!(exponential.amount(100.0) > 0.99);
+    }
+}
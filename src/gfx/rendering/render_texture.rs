@@ -0,0 +1,169 @@
+//! Render-to-texture for embedding 3D views in the UI
+//!
+//! A [`RenderTexture`] renders the scene from its own camera into an
+//! offscreen texture every frame. The texture is registered with the ImGui
+//! renderer, so it can be shown inside any window with `ui.image(...)` —
+//! handy for dashboards with small per-simulation previews.
+//!
+//! [`RenderTexture`] is a cheap, clonable handle: keep one clone in your UI
+//! callback to draw it, and another wherever you want to move its camera.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::gfx::camera::OrbitCamera;
+//! use cgmath::Vector3;
+//!
+//! let mut app = haggis::default();
+//! let camera = OrbitCamera::new(10.0, 1.2, 0.0, Vector3::new(0.0, 0.0, 0.0), 1.0);
+//! let preview = app.add_render_texture("Top view", camera, 320, 240);
+//!
+//! app.set_ui(move |ui, _scene, _selected| {
+//!     ui.window("Preview").build(|| {
+//!         preview.image(ui, [320.0, 240.0]);
+//!     });
+//! });
+//! ```
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::gfx::camera::orbit_camera::OrbitCamera;
+
+static NEXT_RENDER_TEXTURE_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Shared state behind a [`RenderTexture`] handle
+#[derive(Debug, Clone)]
+pub struct RenderTextureState {
+    /// Display name, used for debugging and placeholder text
+    pub name: String,
+    /// Camera the texture is rendered from. Aspect ratio follows the texture size.
+    pub camera: OrbitCamera,
+    /// Texture width in pixels
+    pub width: u32,
+    /// Texture height in pixels
+    pub height: u32,
+    /// Background color
+    pub clear_color: wgpu::Color,
+    /// Disabled textures keep their last contents and are not re-rendered
+    pub enabled: bool,
+    texture_id: Option<imgui::TextureId>,
+}
+
+/// Handle to an offscreen 3D view that can be displayed in ImGui
+#[derive(Debug, Clone)]
+pub struct RenderTexture {
+    id: usize,
+    state: Arc<Mutex<RenderTextureState>>,
+}
+
+impl RenderTexture {
+    /// Create a new render texture handle.
+    ///
+    /// GPU resources are created lazily on the first frame after the
+    /// application starts running.
+    pub fn new(name: &str, camera: OrbitCamera, width: u32, height: u32) -> Self {
+        Self {
+            id: NEXT_RENDER_TEXTURE_ID.fetch_add(1, Ordering::Relaxed),
+            state: Arc::new(Mutex::new(RenderTextureState {
+                name: name.to_string(),
+                camera,
+                width: width.max(1),
+                height: height.max(1),
+                clear_color: wgpu::Color {
+                    r: 0.1,
+                    g: 0.2,
+                    b: 0.3,
+                    a: 1.0,
+                },
+                enabled: true,
+                texture_id: None,
+            })),
+        }
+    }
+
+    /// Unique identifier of this render texture
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// ImGui texture id, available once the texture has been created on the GPU
+    pub fn texture_id(&self) -> Option<imgui::TextureId> {
+        self.lock().texture_id
+    }
+
+    /// Current texture size in pixels
+    pub fn size(&self) -> (u32, u32) {
+        let state = self.lock();
+        (state.width, state.height)
+    }
+
+    /// Resize the texture; GPU resources are recreated on the next frame
+    pub fn resize(&self, width: u32, height: u32) {
+        let mut state = self.lock();
+        state.width = width.max(1);
+        state.height = height.max(1);
+    }
+
+    /// Copy of the current camera
+    pub fn camera(&self) -> OrbitCamera {
+        self.lock().camera
+    }
+
+    /// Replace the camera
+    pub fn set_camera(&self, camera: OrbitCamera) {
+        self.lock().camera = camera;
+    }
+
+    /// Modify the camera in place, e.g. `preview.update_camera(|c| c.add_yaw(0.01))`
+    pub fn update_camera<F: FnOnce(&mut OrbitCamera)>(&self, f: F) {
+        f(&mut self.lock().camera);
+    }
+
+    /// Enable or disable rendering
+    pub fn set_enabled(&self, enabled: bool) {
+        self.lock().enabled = enabled;
+    }
+
+    /// Set the background color
+    pub fn set_clear_color(&self, clear_color: wgpu::Color) {
+        self.lock().clear_color = clear_color;
+    }
+
+    /// Snapshot of the full state
+    pub fn state(&self) -> RenderTextureState {
+        self.lock().clone()
+    }
+
+    /// Draw the texture as an ImGui image, or a placeholder until it is ready
+    ///
+    /// # Arguments
+    ///
+    /// * `ui` - ImGui UI context
+    /// * `size` - Displayed size in UI pixels
+    pub fn image(&self, ui: &imgui::Ui, size: [f32; 2]) {
+        let state = self.lock();
+        match state.texture_id {
+            Some(texture_id) => imgui::Image::new(texture_id, size).build(ui),
+            None => ui.text(format!("{} (initializing...)", state.name)),
+        }
+    }
+
+    pub(crate) fn set_texture_id(&self, texture_id: imgui::TextureId) {
+        self.lock().texture_id = Some(texture_id);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, RenderTextureState> {
+        // A panic while holding the lock leaves plain data behind; keep using it
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A render texture whose GPU target was (re)created this frame and needs
+/// to be registered with the UI renderer
+pub struct RenderTextureUpdate {
+    pub handle: RenderTexture,
+    pub texture: Arc<wgpu::Texture>,
+    pub view: Arc<wgpu::TextureView>,
+    pub size: (u32, u32),
+}
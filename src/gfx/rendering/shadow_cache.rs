@@ -233,7 +233,7 @@ impl ShadowCache {
         // Check if any objects in shadow bounds have changed
         for object in objects {
             let object_id = &object.name;
-            let current_state = ObjectTransformState::new(object.transform, object.visible);
+            let current_state = ObjectTransformState::new(object.transform, casts_shadow(object));
 
             // Check if object is in shadow bounds
             let in_bounds = if let Some(ref bounds) = self.shadow_bounds {
@@ -301,7 +301,7 @@ impl ShadowCache {
         // Update cached object states
         self.last_object_states.clear();  // Clear old states
         for object in objects {
-            let state = ObjectTransformState::new(object.transform, object.visible);
+            let state = ObjectTransformState::new(object.transform, casts_shadow(object));
             self.last_object_states.insert(object.name.clone(), state);
         }
 
@@ -370,4 +370,10 @@ impl Default for ShadowCache {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+/// Whether the object is drawn into the shadow map; ghosted objects are
+/// see-through and cast no shadow
+pub(crate) fn casts_shadow(object: &crate::gfx::scene::object::Object) -> bool {
+    object.visible && !object.ghost
+}
@@ -7,14 +7,28 @@ struct GlobalUniform {
     light_color: vec3<f32>,
     light_intensity: f32,
     light_view_proj: mat4x4<f32>,
+    fog_color: vec3<f32>,
+    fog_mode: u32,
+    fog_start: f32,
+    fog_end: f32,
+    fog_density: f32,
+    _padding2: f32,
+    background_top: vec4<f32>,
+    background_bottom: vec4<f32>,
+    clip_planes: array<vec4<f32>, 4>,
+    clip_plane_count: u32,
 };
 
-struct Transform {
+
+// Model matrix and opacity of every object, see transform_buffer.rs
+struct ObjectTransform {
     model: mat4x4<f32>,
+    opacity: f32,
 };
 
 @group(0) @binding(0) var<uniform> global: GlobalUniform;
-@group(1) @binding(0) var<uniform> transform: Transform;
+// Transforms of all objects, indexed by the object's draw instance
+@group(1) @binding(0) var<storage, read> transforms: array<ObjectTransform>;
 
 struct VertexInput {
     @location(0) position: vec3<f32>,
@@ -26,10 +40,11 @@ struct VertexOutput {
 };
 
 @vertex
-fn vs_main(model: VertexInput) -> VertexOutput {
+fn vs_main(model: VertexInput, @builtin(instance_index) instance: u32) -> VertexOutput {
     var out: VertexOutput;
+    let model_matrix = transforms[instance].model;
     
-    let world_position = transform.model * vec4<f32>(model.position, 1.0);
+    let world_position = model_matrix * vec4<f32>(model.position, 1.0);
     out.clip_position = global.light_view_proj * world_position;
     
     return out;
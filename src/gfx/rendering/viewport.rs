@@ -0,0 +1,215 @@
+//! Multi-viewport support
+//!
+//! Secondary viewports render the scene from their own camera into a
+//! rectangle of the window, on top of the main view. This covers
+//! picture-in-picture (a small fixed top-down view in a corner) as well as
+//! split screen (a viewport covering one half of the window).
+//!
+//! Each viewport renders into its own offscreen color/depth target, which is
+//! then copied into the window surface, so viewports get a proper clear
+//! color and depth buffer of their own.
+
+use std::sync::Arc;
+
+use crate::gfx::{
+    camera::{
+        camera_utils::CameraUniform,
+        orbit_camera::OrbitCamera,
+    },
+    resources::{
+        global_bindings::{GlobalBindings, GlobalUBO},
+        texture_resource::TextureResource,
+    },
+};
+
+use super::depth::DepthMode;
+
+/// Viewport rectangle in normalized window coordinates.
+///
+/// `x`/`y` is the top-left corner, `(0, 0)` is the top-left of the window and
+/// `(1, 1)` the bottom-right.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewportRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl ViewportRect {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// The whole window
+    pub fn full() -> Self {
+        Self::new(0.0, 0.0, 1.0, 1.0)
+    }
+
+    /// Left half of the window
+    pub fn left_half() -> Self {
+        Self::new(0.0, 0.0, 0.5, 1.0)
+    }
+
+    /// Right half of the window
+    pub fn right_half() -> Self {
+        Self::new(0.5, 0.0, 0.5, 1.0)
+    }
+
+    /// Small inset in the bottom-right corner, `size` as a fraction of the window
+    pub fn picture_in_picture(size: f32) -> Self {
+        let size = size.clamp(0.05, 1.0);
+        let margin = 0.02;
+        Self::new(1.0 - size - margin, 1.0 - size - margin, size, size)
+    }
+
+    /// Convert to a pixel rectangle `(x, y, width, height)` clamped to the surface.
+    ///
+    /// Returns `None` if the rectangle is empty or lies outside the surface.
+    pub fn to_pixels(&self, surface_width: u32, surface_height: u32) -> Option<(u32, u32, u32, u32)> {
+        let sw = surface_width as f32;
+        let sh = surface_height as f32;
+
+        let x0 = (self.x.clamp(0.0, 1.0) * sw).round() as u32;
+        let y0 = (self.y.clamp(0.0, 1.0) * sh).round() as u32;
+        let x1 = ((self.x + self.width).clamp(0.0, 1.0) * sw).round() as u32;
+        let y1 = ((self.y + self.height).clamp(0.0, 1.0) * sh).round() as u32;
+
+        if x1 <= x0 || y1 <= y0 {
+            return None;
+        }
+        Some((x0, y0, x1 - x0, y1 - y0))
+    }
+}
+
+/// A secondary view of the scene rendered into part of the window
+#[derive(Debug, Clone)]
+pub struct Viewport {
+    /// Display name, used for debugging and UI
+    pub name: String,
+    /// Camera used to render this viewport. Its aspect ratio is kept in sync
+    /// with the viewport rectangle automatically.
+    pub camera: OrbitCamera,
+    /// Placement within the window
+    pub rect: ViewportRect,
+    /// Disabled viewports are skipped entirely
+    pub enabled: bool,
+    /// Background color of the viewport
+    pub clear_color: wgpu::Color,
+}
+
+impl Viewport {
+    pub fn new(name: &str, camera: OrbitCamera, rect: ViewportRect) -> Self {
+        Self {
+            name: name.to_string(),
+            camera,
+            rect,
+            enabled: true,
+            clear_color: wgpu::Color {
+                r: 0.1,
+                g: 0.2,
+                b: 0.3,
+                a: 1.0,
+            },
+        }
+    }
+
+    pub fn with_clear_color(mut self, clear_color: wgpu::Color) -> Self {
+        self.clear_color = clear_color;
+        self
+    }
+
+    /// Camera uniform for this viewport given its pixel size and the render
+    /// engine's depth mode
+    pub fn camera_uniform(&self, width: u32, height: u32, depth_mode: DepthMode) -> CameraUniform {
+        let mut camera = self.camera;
+        camera.depth_mode = depth_mode;
+        camera.resize_projection(width, height);
+        camera.update_view_proj();
+        camera.uniform
+    }
+}
+
+/// Offscreen color/depth target with its own camera uniforms
+///
+/// Backs both secondary viewports and render textures. The color texture and
+/// view are reference counted so they can be shared with the UI renderer.
+pub(crate) struct ViewportTarget {
+    pub size: (u32, u32),
+    pub color: Arc<wgpu::Texture>,
+    pub color_view: Arc<wgpu::TextureView>,
+    pub depth: TextureResource,
+    pub global_ubo: GlobalUBO,
+    pub global_bindings: GlobalBindings,
+}
+
+impl ViewportTarget {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, width: u32, height: u32) -> Self {
+        let color = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Viewport Color Texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let color_view = color.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // Depth helper only needs the dimensions out of the surface config
+        let depth_config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format,
+            width,
+            height,
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+        let depth = TextureResource::create_depth_texture(device, &depth_config, "viewport_depth");
+
+        let global_ubo = GlobalUBO::new(device);
+        let mut global_bindings = GlobalBindings::new(device);
+        global_bindings.create_bind_group(device, &global_ubo);
+
+        Self {
+            size: (width, height),
+            color: Arc::new(color),
+            color_view: Arc::new(color_view),
+            depth,
+            global_ubo,
+            global_bindings,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rect_to_pixels() {
+        let rect = ViewportRect::right_half();
+        assert_eq!(rect.to_pixels(800, 600), Some((400, 0, 400, 600)));
+    }
+
+    #[test]
+    fn test_rect_clamped_to_surface() {
+        let rect = ViewportRect::new(0.75, 0.75, 0.5, 0.5);
+        assert_eq!(rect.to_pixels(100, 100), Some((75, 75, 25, 25)));
+        assert_eq!(ViewportRect::new(1.2, 0.0, 0.5, 0.5).to_pixels(100, 100), None);
+    }
+}
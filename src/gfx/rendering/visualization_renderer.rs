@@ -3,8 +3,9 @@
 //! Handles rendering of visualization planes separately from scene objects,
 //! ensuring simulation data is preserved and not overwritten by default materials.
 
+use super::color::with_color_functions;
+use super::depth::DepthMode;
 use super::render_pass_ext::RenderPassExt;
-use crate::gfx::camera::camera_utils::CameraUniform;
 use crate::visualization::rendering::VisualizationMaterial;
 use cgmath::{Matrix4, Vector3};
 use wgpu::*;
@@ -18,6 +19,16 @@ pub struct VisualizationRenderer {
     quad_index_buffer: Buffer,
 }
 
+/// Camera uniform of the visualization shader
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct VisualizationCamera {
+    view_position: [f32; 4],
+    view_proj: [[f32; 4]; 4],
+    color_flags: u32,
+    _padding: [u32; 3],
+}
+
 /// Represents a visualization plane with its simulation data
 pub struct VisualizationPlane {
     pub position: Vector3<f32>,
@@ -28,19 +39,22 @@ pub struct VisualizationPlane {
 }
 
 impl VisualizationRenderer {
-    pub fn new(device: &Device, surface_format: TextureFormat) -> Self {
+    pub fn new(device: &Device, surface_format: TextureFormat, depth_mode: DepthMode) -> Self {
         // Create visualization-specific shader
         let shader = device.create_shader_module(ShaderModuleDescriptor {
             label: Some("Visualization Shader"),
             source: ShaderSource::Wgsl(
-                include_str!("../../visualization/rendering/shaders/visualization.wgsl").into(),
+                with_color_functions(include_str!(
+                    "../../visualization/rendering/shaders/visualization.wgsl"
+                ))
+                .into(),
             ),
         });
 
         // Create camera resources
         let camera_buffer = device.create_buffer(&BufferDescriptor {
             label: Some("Visualization Camera Buffer"),
-            size: std::mem::size_of::<CameraUniform>() as BufferAddress,
+            size: std::mem::size_of::<VisualizationCamera>() as BufferAddress,
             usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
@@ -50,7 +64,7 @@ impl VisualizationRenderer {
                 label: Some("Visualization Camera Layout"),
                 entries: &[BindGroupLayoutEntry {
                     binding: 0,
-                    visibility: ShaderStages::VERTEX,
+                    visibility: ShaderStages::VERTEX_FRAGMENT,
                     ty: BindingType::Buffer {
                         ty: BufferBindingType::Uniform,
                         has_dynamic_offset: false,
@@ -166,7 +180,7 @@ impl VisualizationRenderer {
             depth_stencil: Some(DepthStencilState {
                 format: TextureFormat::Depth32Float,
                 depth_write_enabled: true,
-                depth_compare: CompareFunction::Less,
+                depth_compare: depth_mode.compare(CompareFunction::Less),
                 stencil: StencilState::default(),
                 bias: DepthBiasState::default(),
             }),
@@ -246,10 +260,15 @@ impl VisualizationRenderer {
     }
 
     /// Update camera uniforms for visualization rendering
-    pub fn update_camera(&self, queue: &Queue, view_proj_matrix: Matrix4<f32>) {
-        let camera_uniform = CameraUniform {
+    ///
+    /// `color_flags` is the output encoding of the target, see
+    /// [`color`](super::color)
+    pub fn update_camera(&self, queue: &Queue, view_proj_matrix: Matrix4<f32>, color_flags: u32) {
+        let camera_uniform = VisualizationCamera {
             view_position: [0.0, 0.0, 0.0, 1.0], // Placeholder for view position
             view_proj: view_proj_matrix.into(),
+            color_flags,
+            _padding: [0; 3],
         };
 
         queue.write_buffer(
@@ -272,7 +291,7 @@ impl VisualizationRenderer {
             // Draw the plane geometry
             render_pass.draw_indexed(0..6, 0, 0..1);
         } else {
-            println!("Warning: Visualization plane material has no bind group");
+            tracing::trace!("Visualization plane material has no bind group");
         }
     }
 
@@ -0,0 +1,398 @@
+//! Volume rendering
+//!
+//! Draws scalar fields on simulation grids, such as smoke density, as
+//! colored fog by ray marching through the grid box in the fragment shader.
+//! The field is read straight from a storage buffer with one `f32` per grid
+//! node, so a compute shader can write it without any readback. Each ray
+//! starts where it enters the box, or at the camera inside it, and ends at
+//! the back of the box; the box's back faces are depth tested, so opaque
+//! objects in front of a volume hide it.
+
+use wgpu::util::DeviceExt;
+use wgpu::{BindGroup, Buffer, Device, Queue, RenderPass, RenderPipeline};
+
+use crate::gfx::resources::global_bindings::GlobalBindings;
+
+use super::color::with_color_functions;
+use super::depth::DepthMode;
+
+/// Most samples taken along one ray
+pub const MAX_VOLUME_STEPS: u32 = 256;
+
+/// A scalar field drawn as a volume
+///
+/// The buffer needs `STORAGE` usage and holds one `f32` per node of a grid
+/// of `dims` nodes, x fastest, spanning `min` to `max` in world space like a
+/// [`GridTransform`](crate::visualization::GridTransform).
+#[derive(Debug, Clone)]
+pub struct VolumeBatch {
+    pub field: Buffer,
+    pub dims: [u32; 3],
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+    /// Color of the medium; alpha scales its overall opacity
+    pub color: [f32; 4],
+    /// Extinction per world unit at a field value of one
+    pub density: f32,
+}
+
+/// Uniforms of one volume
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct VolumeUniforms {
+    /// Density in `w`
+    min: [f32; 4],
+    /// Sample count along the box diagonal in `w`
+    max: [f32; 4],
+    color: [f32; 4],
+    dims: [u32; 4],
+}
+
+impl VolumeUniforms {
+    fn new(batch: &VolumeBatch) -> Self {
+        // About one sample per grid node along the longest path through the box
+        let nodes = batch.dims.map(|n| n as f32);
+        let steps = (nodes[0] * nodes[0] + nodes[1] * nodes[1] + nodes[2] * nodes[2]).sqrt();
+        Self {
+            min: [batch.min[0], batch.min[1], batch.min[2], batch.density],
+            max: [
+                batch.max[0],
+                batch.max[1],
+                batch.max[2],
+                steps.clamp(8.0, MAX_VOLUME_STEPS as f32),
+            ],
+            color: batch.color,
+            dims: [batch.dims[0], batch.dims[1], batch.dims[2], 0],
+        }
+    }
+}
+
+/// GPU resources of one drawn volume, kept while its field buffer stays the same
+struct VolumeDraw {
+    field: Buffer,
+    uniforms: Buffer,
+    bind_group: BindGroup,
+}
+
+/// Renderer for ray marched volumes
+pub struct VolumeRenderer {
+    pipeline: RenderPipeline,
+    layout: wgpu::BindGroupLayout,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    draws: Vec<VolumeDraw>,
+}
+
+/// Corners of the unit cube
+const CUBE_CORNERS: [[f32; 3]; 8] = [
+    [0.0, 0.0, 0.0],
+    [1.0, 0.0, 0.0],
+    [1.0, 1.0, 0.0],
+    [0.0, 1.0, 0.0],
+    [0.0, 0.0, 1.0],
+    [1.0, 0.0, 1.0],
+    [1.0, 1.0, 1.0],
+    [0.0, 1.0, 1.0],
+];
+
+/// Triangles of the unit cube, counter-clockwise seen from outside
+const CUBE_INDICES: [u16; 36] = [
+    0, 2, 1, 0, 3, 2, // -Z
+    4, 5, 6, 4, 6, 7, // +Z
+    0, 1, 5, 0, 5, 4, // -Y
+    3, 6, 2, 3, 7, 6, // +Y
+    0, 4, 7, 0, 7, 3, // -X
+    1, 2, 6, 1, 6, 5, // +X
+];
+
+impl VolumeRenderer {
+    pub fn new(
+        device: &Device,
+        surface_format: wgpu::TextureFormat,
+        global_bindings: &GlobalBindings,
+        depth_mode: DepthMode,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Volume Shader"),
+            source: wgpu::ShaderSource::Wgsl(with_color_functions(VOLUME_SHADER).into()),
+        });
+
+        let entry = |binding, ty| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Volume Bind Group Layout"),
+            entries: &[
+                entry(0, wgpu::BufferBindingType::Uniform),
+                entry(1, wgpu::BufferBindingType::Storage { read_only: true }),
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Volume Pipeline Layout"),
+            bind_group_layouts: &[global_bindings.bind_group_layouts(), &layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Volume Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x3],
+                }],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            // Back faces, so rays still reach the far side with the camera inside the box
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Front),
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: depth_mode.compare(wgpu::CompareFunction::Less),
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Volume Cube Vertex Buffer"),
+            contents: bytemuck::cast_slice(&CUBE_CORNERS),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Volume Cube Index Buffer"),
+            contents: bytemuck::cast_slice(&CUBE_INDICES),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        Self {
+            pipeline,
+            layout,
+            vertex_buffer,
+            index_buffer,
+            draws: Vec::new(),
+        }
+    }
+
+    /// Replace the volumes to draw
+    ///
+    /// Bind groups are kept for volumes whose field buffer did not change.
+    pub fn set_batches(&mut self, device: &Device, queue: &Queue, batches: &[VolumeBatch]) {
+        self.draws.truncate(batches.len());
+        for (index, batch) in batches.iter().enumerate() {
+            let uniforms = VolumeUniforms::new(batch);
+            if let Some(draw) = self
+                .draws
+                .get(index)
+                .filter(|draw| draw.field == batch.field)
+            {
+                queue.write_buffer(&draw.uniforms, 0, bytemuck::bytes_of(&uniforms));
+                continue;
+            }
+            let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Volume Uniforms"),
+                contents: bytemuck::bytes_of(&uniforms),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Volume Bind Group"),
+                layout: &self.layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: uniform_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: batch.field.as_entire_binding(),
+                    },
+                ],
+            });
+            let draw = VolumeDraw {
+                field: batch.field.clone(),
+                uniforms: uniform_buffer,
+                bind_group,
+            };
+            if index < self.draws.len() {
+                self.draws[index] = draw;
+            } else {
+                self.draws.push(draw);
+            }
+        }
+    }
+
+    /// Number of volumes drawn per frame
+    pub fn volume_count(&self) -> usize {
+        self.draws.len()
+    }
+
+    pub fn render<'a>(
+        &'a self,
+        render_pass: &mut RenderPass<'a>,
+        global_bind_group: &'a BindGroup,
+    ) {
+        if self.draws.is_empty() {
+            return;
+        }
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, global_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        for draw in &self.draws {
+            render_pass.set_bind_group(1, &draw.bind_group, &[]);
+            render_pass.draw_indexed(0..CUBE_INDICES.len() as u32, 0, 0..1);
+        }
+    }
+}
+
+const VOLUME_SHADER: &str = r#"
+struct GlobalUniform {
+    view_position: vec4<f32>,
+    view_proj: mat4x4<f32>,
+    light_position: vec3<f32>,
+    _padding1: f32,
+    light_color: vec3<f32>,
+    light_intensity: f32,
+    light_view_proj: mat4x4<f32>,
+    fog_color: vec3<f32>,
+    fog_mode: u32,
+    fog_start: f32,
+    fog_end: f32,
+    fog_density: f32,
+    color_flags: u32,
+    background_top: vec4<f32>,
+    background_bottom: vec4<f32>,
+    clip_planes: array<vec4<f32>, 4>,
+    clip_plane_count: u32,
+}
+
+struct VolumeUniforms {
+    min: vec4<f32>, // density in w
+    max: vec4<f32>, // steps in w
+    color: vec4<f32>,
+    dims: vec4<u32>,
+}
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> global: GlobalUniform;
+
+@group(1) @binding(0) var<uniform> volume: VolumeUniforms;
+@group(1) @binding(1) var<storage, read> field: array<f32>;
+
+@vertex
+fn vs_main(@location(0) corner: vec3<f32>) -> VertexOutput {
+    let world_position = volume.min.xyz + corner * (volume.max.xyz - volume.min.xyz);
+    return VertexOutput(global.view_proj * vec4<f32>(world_position, 1.0), world_position);
+}
+
+fn load(node: vec3<i32>) -> f32 {
+    let last = vec3<i32>(volume.dims.xyz) - vec3<i32>(1);
+    let n = vec3<u32>(clamp(node, vec3<i32>(0), last));
+    return field[(n.z * volume.dims.y + n.y) * volume.dims.x + n.x];
+}
+
+// Trilinear interpolation between grid nodes
+fn sample(world: vec3<f32>) -> f32 {
+    let last = vec3<f32>(volume.dims.xyz) - vec3<f32>(1.0);
+    let position = (world - volume.min.xyz) / (volume.max.xyz - volume.min.xyz) * last;
+    let base = floor(position);
+    let f = position - base;
+    let b = vec3<i32>(base);
+    let x00 = mix(load(b), load(b + vec3<i32>(1, 0, 0)), f.x);
+    let x10 = mix(load(b + vec3<i32>(0, 1, 0)), load(b + vec3<i32>(1, 1, 0)), f.x);
+    let x01 = mix(load(b + vec3<i32>(0, 0, 1)), load(b + vec3<i32>(1, 0, 1)), f.x);
+    let x11 = mix(load(b + vec3<i32>(0, 1, 1)), load(b + vec3<i32>(1, 1, 1)), f.x);
+    return mix(mix(x00, x10, f.y), mix(x01, x11, f.y), f.z);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // The fragment is where the ray leaves the box; find where it enters
+    let eye = global.view_position.xyz;
+    let offset = in.world_position - eye;
+    let far = length(offset);
+    let direction = offset / far;
+    let inverse = 1.0 / direction;
+    let t0 = (volume.min.xyz - eye) * inverse;
+    let t1 = (volume.max.xyz - eye) * inverse;
+    let entry = min(t0, t1);
+    let near = clamp(max(max(entry.x, entry.y), entry.z), 0.0, far);
+
+    let diagonal = length(volume.max.xyz - volume.min.xyz);
+    let step = diagonal / volume.max.w;
+    // No ray through the box is longer than its diagonal, so this is at most MAX_VOLUME_STEPS
+    let steps = u32(ceil((far - near) / step));
+    var transmittance = 1.0;
+    for (var i = 0u; i < steps; i++) {
+        let t = near + (f32(i) + 0.5) * step;
+        if (t > far) {
+            break;
+        }
+        let value = max(sample(eye + direction * t), 0.0);
+        transmittance *= exp(-value * volume.min.w * step);
+        if (transmittance < 0.01) {
+            break;
+        }
+    }
+
+    let alpha = (1.0 - transmittance) * volume.color.a;
+    return vec4<f32>(encode_output(srgb_to_linear(volume.color.rgb), global.color_flags), alpha);
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cube_faces_wind_outward() {
+        let center = [0.5, 0.5, 0.5];
+        for triangle in CUBE_INDICES.chunks(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| CUBE_CORNERS[triangle[i] as usize]);
+            let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+            let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+            let n = [
+                u[1] * v[2] - u[2] * v[1],
+                u[2] * v[0] - u[0] * v[2],
+                u[0] * v[1] - u[1] * v[0],
+            ];
+            let outward = (0..3).map(|i| n[i] * (a[i] - center[i])).sum::<f32>();
+            assert!(outward > 0.0);
+        }
+        assert_eq!(std::mem::size_of::<VolumeUniforms>(), 64);
+    }
+}
@@ -1,11 +1,19 @@
 //! Global uniform bindings for camera and scene data
 //!
 //! Manages GPU uniform buffers and bind groups for global rendering state
-//! that is shared across all objects in a scene, including camera matrices
-//! and lighting data for shadow mapping.
+//! that is shared across all objects in a scene, including camera matrices,
+//! lighting data for shadow mapping, fog, the background gradient and clip
+//! planes.
 
 use crate::{
-    gfx::camera::camera_utils::CameraUniform,
+    gfx::{
+        camera::camera_utils::CameraUniform,
+        rendering::{
+            clip_plane::{ClipPlane, MAX_CLIP_PLANES},
+            color::color_flags,
+            render_settings::RenderSettings,
+        },
+    },
     wgpu_utils::{
         binding_builder::{BindGroupBuilder, BindGroupLayoutBuilder, BindGroupLayoutWithDesc},
         binding_types,
@@ -31,8 +39,23 @@ pub struct GlobalUBOContent {
     light_color: [f32; 3],          // Light color
     light_intensity: f32,           // Light intensity
     light_view_proj: [[f32; 4]; 4], // Light's view-projection matrix for shadows
+
+    // Fog and background data
+    fog_color: [f32; 3],         // Color geometry fades to
+    fog_mode: u32,               // 0 = off, 1 = linear, 2 = exponential
+    fog_start: f32,              // Distance where linear fog begins
+    fog_end: f32,                // Distance where linear fog is opaque
+    fog_density: f32,            // Exponential fog density
+    color_flags: u32,            // Output encoding, see rendering::color
+    background_top: [f32; 4],    // Gradient top color, w = 1 when enabled
+    background_bottom: [f32; 4], // Gradient bottom color
+
+    // Clip planes
+    clip_planes: [[f32; 4]; MAX_CLIP_PLANES], // Normal and offset of each plane
+    clip_plane_count: u32,                    // Number of planes in use
+    _padding3: [u32; 3],                      // Padding for alignment
 }
-// Total: 4*4 + 16*4 + 3*4 + 4 + 3*4 + 4 + 16*4 = 16 + 64 + 12 + 4 + 12 + 4 + 64 = 176 bytes
+// Total: 240 bytes of camera, light, fog and background data + 64 + 16 = 320 bytes
 
 unsafe impl bytemuck::Pod for GlobalUBOContent {}
 unsafe impl bytemuck::Zeroable for GlobalUBOContent {}
@@ -76,7 +99,9 @@ pub fn update_global_ubo(ubo: &mut GlobalUBO, queue: &wgpu::Queue, camera: Camer
 /// Updates the global uniform buffer with camera and light data
 ///
 /// Should be called each frame with updated camera and light data to ensure
-/// correct rendering and shadow mapping for all objects in the scene.
+/// correct rendering and shadow mapping for all objects in the scene. Fog and
+/// the background gradient are set to their defaults (off); use
+/// `update_global_ubo_with_settings` to change them.
 ///
 /// # Arguments
 /// * `ubo` - The global uniform buffer to update
@@ -89,6 +114,46 @@ pub fn update_global_ubo_with_light(
     camera: CameraUniform,
     light: LightConfig,
 ) {
+    update_global_ubo_with_settings(
+        ubo,
+        queue,
+        camera,
+        light,
+        &RenderSettings::default(),
+        &[],
+        // The engine's default surface format
+        wgpu::TextureFormat::Bgra8Unorm,
+    );
+}
+
+/// Updates the global uniform buffer with camera, light, fog, background and
+/// clip plane data
+///
+/// Planes past [`MAX_CLIP_PLANES`] are ignored.
+///
+/// # Arguments
+/// * `ubo` - The global uniform buffer to update
+/// * `queue` - WGPU command queue for buffer updates
+/// * `camera` - Updated camera uniform data
+/// * `light` - Light configuration for shadow mapping
+/// * `settings` - Fog and background gradient of the view
+/// * `clip_planes` - Planes cutting away scene geometry
+/// * `target_format` - Format of the color target, for the sRGB encoding
+pub fn update_global_ubo_with_settings(
+    ubo: &mut GlobalUBO,
+    queue: &wgpu::Queue,
+    camera: CameraUniform,
+    light: LightConfig,
+    settings: &RenderSettings,
+    clip_planes: &[ClipPlane],
+    target_format: wgpu::TextureFormat,
+) {
+    let clip_plane_count = clip_planes.len().min(MAX_CLIP_PLANES);
+    let mut clip_plane_data = [[0.0; 4]; MAX_CLIP_PLANES];
+    for (data, plane) in clip_plane_data.iter_mut().zip(clip_planes) {
+        *data = plane.to_vec4();
+    }
+
     // Better light setup for your scene layout
     let light_pos = cgmath::Point3::new(light.position[0], light.position[1], light.position[2]);
     let light_view = cgmath::Matrix4::look_at_rh(
@@ -112,6 +177,27 @@ pub fn update_global_ubo_with_light(
         light_color: light.color,
         light_intensity: light.intensity,
         light_view_proj: light_view_proj.into(),
+
+        // Fog and background data
+        fog_color: settings.fog.color,
+        fog_mode: settings.fog.mode.shader_id(),
+        fog_start: settings.fog.start,
+        fog_end: settings.fog.end,
+        fog_density: settings.fog.density,
+        color_flags: color_flags(target_format, settings.linear_debug),
+        background_top: match settings.background {
+            Some(gradient) => [gradient.top[0], gradient.top[1], gradient.top[2], 1.0],
+            None => [0.0; 4],
+        },
+        background_bottom: match settings.background {
+            Some(gradient) => [gradient.bottom[0], gradient.bottom[1], gradient.bottom[2], 1.0],
+            None => [0.0; 4],
+        },
+
+        // Clip planes
+        clip_planes: clip_plane_data,
+        clip_plane_count: clip_plane_count as u32,
+        _padding3: [0; 3],
     };
 
     ubo.update_content(queue, content);
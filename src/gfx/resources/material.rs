@@ -3,11 +3,15 @@
 //! Provides material definitions and centralized management with GPU resource handling.
 //! Materials are stored in MaterialManager and objects reference them by ID.
 
-use std::collections::HashMap;
+use std::{collections::HashMap, path::Path};
 use wgpu::Device;
 
 use crate::{
-    gfx::resources::texture_resource::TextureResource,
+    error::Result,
+    gfx::{
+        rendering::custom_shader::CustomShader,
+        resources::{procedural_texture::ProceduralTexture, texture_resource::TextureResource},
+    },
     wgpu_utils::{
         binding_builder::{BindGroupBuilder, BindGroupLayoutBuilder, BindGroupLayoutWithDesc},
         binding_types,
@@ -28,9 +32,42 @@ pub struct MaterialUniform {
     pub normal_scale: f32,
     pub occlusion_strength: f32,
     pub emissive: [f32; 3],
+    /// 1 when the diffuse texture is mapped triplanar, 0 when unused
+    pub texture_mode: u32,
+    pub triplanar_scale: f32,
+    pub triplanar_sharpness: f32,
+    pub reflectance: f32,
     _padding: f32,
 }
 
+/// How the PBR shader lays the diffuse texture onto meshes
+///
+/// Scene meshes have no texture coordinates, so the PBR shader projects
+/// textures instead. Custom shaders sample `diffuse_texture` themselves and
+/// may call `triplanar_sample` from the prelude.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextureMapping {
+    /// The texture is bound but only custom shaders read it
+    None,
+    /// Projected along the world axes and blended by the surface normal, so
+    /// it works on any mesh, e.g. marching-cubes output
+    Triplanar {
+        /// Texture repeats per world unit
+        scale: f32,
+        /// How sharply the three projections blend; higher is crisper
+        sharpness: f32,
+    },
+}
+
+impl Default for TextureMapping {
+    fn default() -> Self {
+        Self::Triplanar {
+            scale: 1.0,
+            sharpness: 4.0,
+        }
+    }
+}
+
 type MaterialUBO = UniformBuffer<MaterialUniform>;
 
 /// Material bind group management
@@ -134,12 +171,16 @@ impl MaterialBindings {
 /// centrally in MaterialManager and shared between objects.
 pub struct Material {
     pub name: String,
+    /// Linear RGBA, see [`color`](crate::gfx::rendering::color)
     pub base_color: [f32; 4],
     pub metallic: f32,
     pub roughness: f32,
     pub normal_scale: f32,
     pub occlusion_strength: f32,
     pub emissive: [f32; 3],
+    /// How strongly the surface mirrors the sky, scaled by Fresnel and
+    /// fading with roughness (0.0 = none, 1.0 = full mirror at grazing angles)
+    pub reflectance: f32,
 
     // GPU resources - shared by all objects using this material
     material_ubo: Option<MaterialUBO>,
@@ -147,6 +188,13 @@ pub struct Material {
 
     // Texture support
     pub diffuse_texture: Option<TextureResource>,
+    /// Compute-generated diffuse texture, rewritten every frame
+    pub procedural_texture: Option<ProceduralTexture>,
+    /// How PBR shading maps the diffuse texture
+    pub texture_mapping: TextureMapping,
+
+    /// Shading that replaces PBR, see [`custom_shader`](crate::gfx::rendering::custom_shader)
+    pub shader: Option<CustomShader>,
 }
 
 impl Default for Material {
@@ -159,9 +207,13 @@ impl Default for Material {
             normal_scale: 1.0,
             occlusion_strength: 1.0,
             emissive: [0.0, 0.0, 0.0],
+            reflectance: 0.0,
             material_ubo: None,
             material_bindings: None,
             diffuse_texture: None,
+            procedural_texture: None,
+            texture_mapping: TextureMapping::default(),
+            shader: None,
         }
     }
 }
@@ -183,9 +235,13 @@ impl Material {
             normal_scale: 1.0,
             occlusion_strength: 1.0,
             emissive: [0.0, 0.0, 0.0],
+            reflectance: 0.0,
             material_ubo: None,
             material_bindings: None,
             diffuse_texture: None,
+            procedural_texture: None,
+            texture_mapping: TextureMapping::default(),
+            shader: None,
         }
     }
 
@@ -201,6 +257,11 @@ impl Material {
         self
     }
 
+    /// Whether the material is drawn blended, after all opaque objects
+    pub fn is_transparent(&self) -> bool {
+        self.base_color[3] < 1.0
+    }
+
     /// Builder pattern: Set metallic factor
     pub fn with_metallic(mut self, metallic: f32) -> Self {
         self.metallic = metallic.clamp(0.0, 1.0);
@@ -213,6 +274,12 @@ impl Material {
         self
     }
 
+    /// Builder pattern: Set how strongly the surface mirrors the sky
+    pub fn with_reflectance(mut self, reflectance: f32) -> Self {
+        self.reflectance = reflectance.clamp(0.0, 1.0);
+        self
+    }
+
     /// Builder pattern: Set emissive color
     pub fn with_emission(mut self, r: f32, g: f32, b: f32) -> Self {
         self.emissive = [r, g, b];
@@ -225,6 +292,37 @@ impl Material {
         self
     }
 
+    /// Builder pattern: Set how PBR shading maps the diffuse texture
+    pub fn with_texture_mapping(mut self, mapping: TextureMapping) -> Self {
+        self.texture_mapping = mapping;
+        self
+    }
+
+    /// Sample a texture that a compute pass writes every frame as the
+    /// diffuse texture, see [`procedural_texture`](super::procedural_texture)
+    pub fn set_procedural_texture(&mut self, texture: ProceduralTexture) {
+        self.procedural_texture = Some(texture);
+        self.diffuse_texture = None;
+        self.material_bindings = None;
+    }
+
+    /// Builder pattern: Shade with a custom shader instead of PBR
+    pub fn with_shader(mut self, shader: CustomShader) -> Self {
+        self.shader = Some(shader);
+        self
+    }
+
+    /// Shade with the custom WGSL shader in `path` instead of PBR
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file cannot be read or doesn't compile, see
+    /// [`CustomShader::load`]. The material keeps its previous shading then.
+    pub fn set_shader(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        self.shader = Some(CustomShader::load(path)?);
+        Ok(())
+    }
+
     /// Set diffuse texture on existing material
     pub fn set_texture(&mut self, texture: TextureResource) {
         self.diffuse_texture = Some(texture);
@@ -232,6 +330,19 @@ impl Material {
         self.material_bindings = None;
     }
 
+    /// Drops GPU resources so they are recreated on the next update
+    ///
+    /// Textures can't be re-uploaded without their source data, so they are
+    /// dropped too and have to be set again.
+    pub fn release_gpu_resources(&mut self) {
+        self.material_ubo = None;
+        self.material_bindings = None;
+        self.diffuse_texture = None;
+        if let Some(procedural) = &mut self.procedural_texture {
+            procedural.release_gpu_resources();
+        }
+    }
+
     /// Updates GPU resources for this material
     ///
     /// Must be called after material properties change to sync with GPU.
@@ -242,6 +353,15 @@ impl Material {
         } else {
         }
 
+        // Write this frame's procedural texture before it is sampled, and
+        // bind it once it exists
+        if let Some(procedural) = &mut self.procedural_texture {
+            if let Some(texture) = procedural.update(device, queue) {
+                self.diffuse_texture = Some(texture);
+                self.material_bindings = None;
+            }
+        }
+
         // Create bindings if needed
         if self.material_bindings.is_none() {
             let mut bindings = MaterialBindings::new(device);
@@ -256,6 +376,11 @@ impl Material {
         }
 
         // Update uniform data
+        let (texture_mode, triplanar_scale, triplanar_sharpness) =
+            match (self.texture_mapping, &self.diffuse_texture) {
+                (TextureMapping::Triplanar { scale, sharpness }, Some(_)) => (1, scale, sharpness),
+                _ => (0, 1.0, 1.0),
+            };
         let uniform_data = MaterialUniform {
             base_color: self.base_color,
             metallic: self.metallic,
@@ -263,11 +388,15 @@ impl Material {
             normal_scale: self.normal_scale,
             occlusion_strength: self.occlusion_strength,
             emissive: self.emissive,
+            texture_mode,
+            triplanar_scale,
+            triplanar_sharpness,
+            reflectance: self.reflectance,
             _padding: 0.0,
         };
 
         if let Some(ubo) = &mut self.material_ubo {
-            ubo.update_content(queue, uniform_data);
+            ubo.set_if_changed(queue, &uniform_data);
         }
     }
     /// Gets the bind group for rendering
@@ -277,9 +406,8 @@ impl Material {
                 bind_group => Some(bind_group),
             },
             None => {
-                #[cfg(debug_assertions)]
-                println!(
-                    "DEBUG: get_bind_group() for '{}' - no material_bindings",
+                tracing::trace!(
+                    "get_bind_group() for '{}' - no material_bindings",
                     self.name
                 );
                 None
@@ -395,6 +523,13 @@ impl MaterialManager {
         self.materials.keys().collect()
     }
 
+    /// Drops GPU resources of all materials, e.g. after the device was lost
+    pub fn release_gpu_resources(&mut self) {
+        for material in self.materials.values_mut() {
+            material.release_gpu_resources();
+        }
+    }
+
     /// Updates GPU resources for all materials
     ///
     /// Should be called when the GPU context is available or when
@@ -1,12 +1,16 @@
 // src/gfx/resources/mod.rs
 //! GPU resource management
 //!
-//! Handles textures, buffers, and bind groups for rendering.
+//! Handles textures, volume textures, buffers, and bind groups for rendering.
 
 pub mod global_bindings;
 pub mod material;
+pub mod procedural_texture;
+pub mod texture_3d;
 pub mod texture_resource;
 
 // Re-export main types
 pub use global_bindings::{update_global_ubo, GlobalBindings, GlobalUBO};
+pub use procedural_texture::{ProceduralTarget, ProceduralTexture, TextureGenerator};
+pub use texture_3d::Texture3D;
 pub use texture_resource::TextureResource;
@@ -0,0 +1,354 @@
+//! Compute-generated textures for materials
+//!
+//! A [`ProceduralTexture`] is a 2D texture that a [`TextureGenerator`]
+//! rewrites with a compute pass every frame, and that a material samples as
+//! its diffuse texture, for example to show a 2D simulation field on the
+//! surface of a loaded mesh without a round trip through the CPU.
+//!
+//! The material runs the generator on the render device while its GPU
+//! resources are updated, which the app does each frame after simulations
+//! step and before the scene is drawn. The compute work is submitted on the
+//! render queue ahead of the frame, and wgpu orders the storage writes
+//! before the sampled reads, so the frame always shows the finished texture.
+//! A generator reading simulation buffers needs them on the render device;
+//! with a separate [compute device](crate::simulation::gpu::ComputeDevice)
+//! the simulation has to [sync them to the display](crate::simulation::traits::Simulation::sync_to_display) first.
+//!
+//! The texture is [`ProceduralTarget::FORMAT`]: colors written by the
+//! generator are sRGB display colors, see [`color`](crate::gfx::rendering::color).
+//! Meshes have no texture coordinates, so the material needs a
+//! [custom shader](crate::gfx::rendering::custom_shader) that samples
+//! `diffuse_texture` with a projection of its choice.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::gfx::resources::procedural_texture::{
+//!     ProceduralTarget, ProceduralTexture, TextureGenerator,
+//! };
+//!
+//! struct Ripples {
+//!     pipeline: Option<wgpu::ComputePipeline>,
+//! }
+//!
+//! impl TextureGenerator for Ripples {
+//!     fn initialize(&mut self, device: &wgpu::Device, target: &ProceduralTarget) {
+//!         // A compute pipeline whose group 0 is `target.storage_layout()`,
+//!         // writing `@group(0) @binding(0) var output: texture_storage_2d<rgba8unorm, write>;`
+//! #       let _ = (device, target);
+//!     }
+//!
+//!     fn update(&mut self, _queue: &wgpu::Queue, encoder: &mut wgpu::CommandEncoder, target: &ProceduralTarget) {
+//!         if let Some(pipeline) = &self.pipeline {
+//!             target.dispatch(encoder, pipeline, &[], (8, 8));
+//!         }
+//!     }
+//! }
+//!
+//! # fn run(scene: &mut haggis::gfx::scene::Scene) {
+//! let material = scene.material_manager.create_material("Ripples");
+//! material.set_procedural_texture(ProceduralTexture::new("Ripples", 256, 256, Ripples { pipeline: None }));
+//! # }
+//! ```
+
+use wgpu::{CommandEncoder, Device, Queue};
+
+use super::texture_resource::TextureResource;
+use crate::wgpu_utils::{
+    binding_builder::{BindGroupBuilder, BindGroupLayoutBuilder, BindGroupLayoutWithDesc},
+    binding_types,
+};
+
+/// Compute work that writes a [`ProceduralTexture`]
+pub trait TextureGenerator {
+    /// Create pipelines and buffers, called when the texture is created on
+    /// the render device, and again after the device is lost
+    fn initialize(&mut self, device: &Device, target: &ProceduralTarget);
+
+    /// Record this frame's writes to the texture, usually with
+    /// [`ProceduralTarget::dispatch`]; recording nothing keeps the last
+    /// contents
+    fn update(&mut self, queue: &Queue, encoder: &mut CommandEncoder, target: &ProceduralTarget);
+}
+
+/// GPU side of a [`ProceduralTexture`]: the texture and its storage binding
+pub struct ProceduralTarget {
+    texture: TextureResource,
+    storage_layout: BindGroupLayoutWithDesc,
+    storage_bind_group: wgpu::BindGroup,
+    width: u32,
+    height: u32,
+}
+
+impl ProceduralTarget {
+    /// Texel format, writable from compute shaders and filterable when sampled
+    pub const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+    pub fn new(device: &Device, width: u32, height: u32, label: &str) -> Self {
+        let texture = TextureResource::create_storage_texture(
+            device,
+            width,
+            height,
+            Self::FORMAT,
+            label,
+            wgpu::FilterMode::Linear,
+        );
+        let storage_layout = BindGroupLayoutBuilder::new()
+            .next_binding_compute(binding_types::image_2d_write(Self::FORMAT))
+            .create(device, &format!("{label} Storage Layout"));
+        let storage_bind_group = BindGroupBuilder::new(&storage_layout)
+            .texture(&texture.view)
+            .create(device, &format!("{label} Storage"));
+        Self {
+            texture,
+            storage_layout,
+            storage_bind_group,
+            width,
+            height,
+        }
+    }
+
+    /// The texture, as sampled by the material
+    pub fn texture(&self) -> &TextureResource {
+        &self.texture
+    }
+
+    /// Layout of [`storage_bind_group`](Self::storage_bind_group), for the
+    /// generator's pipeline layout
+    pub fn storage_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.storage_layout.layout
+    }
+
+    /// The texture as a write-only storage texture at binding 0
+    pub fn storage_bind_group(&self) -> &wgpu::BindGroup {
+        &self.storage_bind_group
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Workgroups needed to cover every texel with `workgroup_size`
+    pub fn workgroup_count(&self, workgroup_size: (u32, u32)) -> (u32, u32) {
+        (
+            self.width.div_ceil(workgroup_size.0),
+            self.height.div_ceil(workgroup_size.1),
+        )
+    }
+
+    /// Run `pipeline` over the texture in its own compute pass
+    ///
+    /// The storage bind group is group 0 and `bind_groups` follow from
+    /// group 1.
+    pub fn dispatch(
+        &self,
+        encoder: &mut CommandEncoder,
+        pipeline: &wgpu::ComputePipeline,
+        bind_groups: &[&wgpu::BindGroup],
+        workgroup_size: (u32, u32),
+    ) {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Procedural Texture"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, &self.storage_bind_group, &[]);
+        for (index, bind_group) in bind_groups.iter().enumerate() {
+            pass.set_bind_group(index as u32 + 1, *bind_group, &[]);
+        }
+        let (x, y) = self.workgroup_count(workgroup_size);
+        pass.dispatch_workgroups(x, y, 1);
+    }
+}
+
+/// Texture a [`TextureGenerator`] rewrites every frame, set on a material
+/// with [`Material::set_procedural_texture`](super::material::Material::set_procedural_texture)
+pub struct ProceduralTexture {
+    label: String,
+    width: u32,
+    height: u32,
+    generator: Box<dyn TextureGenerator>,
+    target: Option<ProceduralTarget>,
+}
+
+impl ProceduralTexture {
+    /// GPU resources are created on the first material update after the
+    /// render device exists
+    pub fn new(
+        label: &str,
+        width: u32,
+        height: u32,
+        generator: impl TextureGenerator + 'static,
+    ) -> Self {
+        Self {
+            label: label.to_string(),
+            width: width.max(1),
+            height: height.max(1),
+            generator: Box::new(generator),
+            target: None,
+        }
+    }
+
+    /// The GPU texture, once created
+    pub fn target(&self) -> Option<&ProceduralTarget> {
+        self.target.as_ref()
+    }
+
+    /// Runs the generator for this frame, creating the texture first if
+    /// needed
+    ///
+    /// # Returns
+    /// The texture if it was just created, for the material to bind
+    pub fn update(&mut self, device: &Device, queue: &Queue) -> Option<TextureResource> {
+        let created = self.target.is_none();
+        let target = self.target.get_or_insert_with(|| {
+            let target = ProceduralTarget::new(device, self.width, self.height, &self.label);
+            self.generator.initialize(device, &target);
+            target
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some(&self.label),
+        });
+        self.generator.update(queue, &mut encoder, target);
+        queue.submit(std::iter::once(encoder.finish()));
+
+        created.then(|| target.texture.clone())
+    }
+
+    /// Drops the texture; the next update recreates it and initializes the
+    /// generator again
+    pub fn release_gpu_resources(&mut self) {
+        self.target = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wgpu_utils::compute_primitives::{read_buffer, test_device};
+    use wgpu::util::DeviceExt;
+
+    /// Fills the texture with the number of frames written so far, in red
+    #[derive(Default)]
+    struct FrameCounter {
+        pipeline: Option<(wgpu::ComputePipeline, wgpu::Buffer, wgpu::BindGroup)>,
+        frames: u32,
+    }
+
+    impl TextureGenerator for FrameCounter {
+        fn initialize(&mut self, device: &Device, target: &ProceduralTarget) {
+            let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: None,
+                source: wgpu::ShaderSource::Wgsl(
+                    r#"
+@group(0) @binding(0) var output: texture_storage_2d<rgba8unorm, write>;
+@group(1) @binding(0) var<uniform> frames: u32;
+@compute @workgroup_size(8, 8)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id.xy >= textureDimensions(output))) {
+        return;
+    }
+    textureStore(output, id.xy, vec4<f32>(f32(frames) / 255.0, 0.0, 0.0, 1.0));
+}
+"#
+                    .into(),
+                ),
+            });
+            let frames_layout = BindGroupLayoutBuilder::new()
+                .next_binding_compute(binding_types::uniform())
+                .create(device, "Frames Layout");
+            let frames = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: bytemuck::cast_slice(&[0u32; 4]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+            let bind_group = BindGroupBuilder::new(&frames_layout)
+                .buffer(&frames)
+                .create(device, "Frames");
+            let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: &[target.storage_layout(), &frames_layout.layout],
+                push_constant_ranges: &[],
+            });
+            let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: None,
+                layout: Some(&layout),
+                module: &shader,
+                entry_point: Some("main"),
+                compilation_options: Default::default(),
+                cache: None,
+            });
+            self.pipeline = Some((pipeline, frames, bind_group));
+        }
+
+        fn update(
+            &mut self,
+            queue: &Queue,
+            encoder: &mut CommandEncoder,
+            target: &ProceduralTarget,
+        ) {
+            let Some((pipeline, frames, bind_group)) = &self.pipeline else {
+                return;
+            };
+            self.frames += 1;
+            queue.write_buffer(frames, 0, bytemuck::cast_slice(&[self.frames]));
+            target.dispatch(encoder, pipeline, &[bind_group], (8, 8));
+        }
+    }
+
+    /// Red channel of every texel
+    fn read_red(device: &Device, queue: &Queue, target: &ProceduralTarget) -> Vec<u8> {
+        let (width, height) = target.size();
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: (width * height * 4) as u64,
+            usage: wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&Default::default());
+        encoder.copy_texture_to_buffer(
+            target.texture().texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(width * 4),
+                    rows_per_image: None,
+                },
+            },
+            target.texture().texture.size(),
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+        let texels: Vec<u8> =
+            read_buffer(device, queue, &buffer, (width * height * 4) as usize).unwrap();
+        texels.chunks(4).map(|texel| texel[0]).collect()
+    }
+
+    #[test]
+    fn test_generator_rewrites_the_texture_every_frame() {
+        // Needs a GPU adapter, skipped without one
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        // 64 texels make a row of 256 bytes, as texture copies need
+        let mut texture = ProceduralTexture::new("Counter", 64, 3, FrameCounter::default());
+        assert!(texture.update(&device, &queue).is_some());
+        let target = texture.target().unwrap();
+        assert_eq!(target.workgroup_count((8, 8)), (8, 1));
+        assert!(read_red(&device, &queue, target)
+            .iter()
+            .all(|&red| red == 1));
+
+        assert!(texture.update(&device, &queue).is_none());
+        assert!(texture.update(&device, &queue).is_none());
+        let target = texture.target().unwrap();
+        assert!(read_red(&device, &queue, target)
+            .iter()
+            .all(|&red| red == 3));
+
+        // A new device gets a new texture and an initialized generator
+        texture.release_gpu_resources();
+        assert!(texture.update(&device, &queue).is_some());
+    }
+}
@@ -0,0 +1,335 @@
+//! 3D texture resources for volume data
+//!
+//! Wraps a 3D texture that compute shaders can write as a storage texture
+//! and render passes can sample, so volume data produced on the GPU never
+//! goes through a storage buffer.
+
+/// GPU 3D texture with a view and a sampler matching its format
+///
+/// Storage usage is only added for formats that support it, and the sampler
+/// only filters linearly when the format is filterable on the device (`r32float`
+/// needs [wgpu::Features::FLOAT32_FILTERABLE]). Bind it with
+/// [sample_binding_type](Self::sample_binding_type) and
+/// [storage_binding_type](Self::storage_binding_type), which match these
+/// capabilities.
+///
+/// The GL backend binds 3D storage textures one slice at a time, so compute
+/// shaders only reach `z = 0` there; the other backends see the whole volume.
+pub struct Texture3D {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub sampler: wgpu::Sampler,
+    format: wgpu::TextureFormat,
+    sample_type: wgpu::TextureSampleType,
+    storage: bool,
+}
+
+impl Texture3D {
+    /// Creates an empty 3D texture, `size.depth_or_array_layers` being the depth
+    pub fn new(
+        device: &wgpu::Device,
+        size: wgpu::Extent3d,
+        format: wgpu::TextureFormat,
+        label: &str,
+    ) -> Self {
+        let features = format.guaranteed_format_features(device.features());
+        let storage = features
+            .allowed_usages
+            .contains(wgpu::TextureUsages::STORAGE_BINDING);
+        let mut usage = wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::COPY_DST
+            | wgpu::TextureUsages::COPY_SRC;
+        if storage {
+            usage |= wgpu::TextureUsages::STORAGE_BINDING;
+        }
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D3,
+            format,
+            usage,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let sample_type = format
+            .sample_type(None, Some(device.features()))
+            .unwrap_or(wgpu::TextureSampleType::Float { filterable: false });
+        let filter = if matches!(
+            sample_type,
+            wgpu::TextureSampleType::Float { filterable: true }
+        ) {
+            wgpu::FilterMode::Linear
+        } else {
+            wgpu::FilterMode::Nearest
+        };
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some(&format!("{} Sampler", label)),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: filter,
+            min_filter: filter,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        Self {
+            texture,
+            view,
+            sampler,
+            format,
+            sample_type,
+            storage,
+        }
+    }
+
+    /// Creates a 3D texture holding `data`, stored x fastest, then y, then z
+    pub fn from_data<T: bytemuck::Pod>(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        data: &[T],
+        size: wgpu::Extent3d,
+        format: wgpu::TextureFormat,
+        label: &str,
+    ) -> Self {
+        let texture = Self::new(device, size, format, label);
+        texture.write(queue, data);
+        texture
+    }
+
+    /// Replaces the whole texture with `data`, stored x fastest, then y, then z
+    pub fn write<T: bytemuck::Pod>(&self, queue: &wgpu::Queue, data: &[T]) {
+        let size = self.size();
+        let bytes: &[u8] = bytemuck::cast_slice(data);
+        assert_eq!(
+            bytes.len() as u64,
+            self.texel_size() as u64
+                * size.width as u64
+                * size.height as u64
+                * size.depth_or_array_layers as u64,
+            "volume data size doesn't match the texture"
+        );
+        queue.write_texture(
+            self.texture.as_image_copy(),
+            bytes,
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(self.texel_size() * size.width),
+                rows_per_image: Some(size.height),
+            },
+            size,
+        );
+    }
+
+    /// Copies the texture to the CPU, waiting for the GPU
+    pub fn read<T: bytemuck::Pod>(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<Vec<T>, wgpu::BufferAsyncError> {
+        let size = self.size();
+        let row_bytes = self.texel_size() * size.width;
+        // Copies need rows aligned to 256 bytes, the padding is dropped below
+        let padded_row_bytes = row_bytes.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let rows = size.height * size.depth_or_array_layers;
+        let staging = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Texture3D Readback Buffer"),
+            size: padded_row_bytes as u64 * rows as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Texture3D Readback Encoder"),
+        });
+        encoder.copy_texture_to_buffer(
+            self.texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &staging,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_bytes),
+                    rows_per_image: Some(size.height),
+                },
+            },
+            size,
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = staging.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        let _ = device.poll(wgpu::MaintainBase::Wait);
+        receiver.recv().unwrap_or(Err(wgpu::BufferAsyncError))?;
+
+        let mapped = slice.get_mapped_range();
+        let mut bytes = Vec::with_capacity((row_bytes * rows) as usize);
+        for row in mapped.chunks_exact(padded_row_bytes as usize) {
+            bytes.extend_from_slice(&row[..row_bytes as usize]);
+        }
+        drop(mapped);
+        staging.unmap();
+        Ok(bytemuck::pod_collect_to_vec(&bytes))
+    }
+
+    /// Texture size in texels
+    pub fn size(&self) -> wgpu::Extent3d {
+        self.texture.size()
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
+
+    /// Whether compute shaders can bind the texture as a storage texture
+    pub fn is_storage(&self) -> bool {
+        self.storage
+    }
+
+    /// Whether the sampler filters linearly
+    pub fn is_filterable(&self) -> bool {
+        matches!(
+            self.sample_type,
+            wgpu::TextureSampleType::Float { filterable: true }
+        )
+    }
+
+    /// Binding type for sampling the texture as a `texture_3d`
+    pub fn sample_binding_type(&self) -> wgpu::BindingType {
+        wgpu::BindingType::Texture {
+            sample_type: self.sample_type,
+            view_dimension: wgpu::TextureViewDimension::D3,
+            multisampled: false,
+        }
+    }
+
+    /// Binding type of the sampler, filtering only when the format allows it
+    pub fn sampler_binding_type(&self) -> wgpu::BindingType {
+        if self.is_filterable() {
+            wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering)
+        } else {
+            wgpu::BindingType::Sampler(wgpu::SamplerBindingType::NonFiltering)
+        }
+    }
+
+    /// Binding type for writing or reading the texture as a `texture_storage_3d`
+    pub fn storage_binding_type(&self, access: wgpu::StorageTextureAccess) -> wgpu::BindingType {
+        assert!(
+            self.storage,
+            "{:?} textures can't be bound as storage textures",
+            self.format
+        );
+        crate::wgpu_utils::binding_types::image_3d(self.format, access)
+    }
+
+    /// Workgroups covering every texel with one invocation each
+    pub fn workgroup_count(&self, workgroup_size: (u32, u32, u32)) -> (u32, u32, u32) {
+        let size = self.size();
+        (
+            size.width.div_ceil(workgroup_size.0),
+            size.height.div_ceil(workgroup_size.1),
+            size.depth_or_array_layers.div_ceil(workgroup_size.2),
+        )
+    }
+
+    fn texel_size(&self) -> u32 {
+        self.format
+            .block_copy_size(None)
+            .expect("3D textures need a single-aspect format")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wgpu_utils::binding_builder::{BindGroupBuilder, BindGroupLayoutBuilder};
+    use crate::wgpu_utils::binding_types;
+    use crate::wgpu_utils::compute_primitives::{read_buffer, test_buffer, test_device};
+
+    #[test]
+    fn test_volume_data_is_loaded_by_compute() {
+        // Needs a GPU adapter, skipped without one
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let size = wgpu::Extent3d {
+            width: 5,
+            height: 3,
+            depth_or_array_layers: 4,
+        };
+        let data: Vec<f32> = (0..4)
+            .flat_map(|z| {
+                (0..3).flat_map(move |y| (0..5).map(move |x| (x + 10 * y + 100 * z) as f32))
+            })
+            .collect();
+        let volume = Texture3D::from_data(
+            &device,
+            &queue,
+            &data,
+            size,
+            wgpu::TextureFormat::R32Float,
+            "Volume",
+        );
+        assert!(volume.is_storage());
+        assert_eq!(volume.read::<f32>(&device, &queue).unwrap(), data);
+
+        // Copy the texels back out through a sampled binding
+        let output = test_buffer(&device, &vec![0.0f32; data.len()]);
+        let layout = BindGroupLayoutBuilder::new()
+            .next_binding_compute(volume.sample_binding_type())
+            .next_binding_compute(binding_types::storage_buffer_read_write())
+            .create(&device, "Volume Layout");
+        let bind_group = BindGroupBuilder::new(&layout)
+            .texture(&volume.view)
+            .buffer(&output)
+            .create(&device, "Volume Bind Group");
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(
+                r#"
+@group(0) @binding(0) var volume: texture_3d<f32>;
+@group(0) @binding(1) var<storage, read_write> output: array<f32>;
+@compute @workgroup_size(4, 4, 4)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let size = textureDimensions(volume);
+    if (any(id >= size)) {
+        return;
+    }
+    output[id.x + size.x * (id.y + size.y * id.z)] = textureLoad(volume, id, 0).r;
+}
+"#
+                .into(),
+            ),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[&layout.layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let mut encoder = device.create_command_encoder(&Default::default());
+        {
+            let mut pass = encoder.begin_compute_pass(&Default::default());
+            pass.set_pipeline(&pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            let (x, y, z) = volume.workgroup_count((4, 4, 4));
+            pass.dispatch_workgroups(x, y, z);
+        }
+        queue.submit(std::iter::once(encoder.finish()));
+        let loaded: Vec<f32> = read_buffer(&device, &queue, &output, data.len()).unwrap();
+        assert_eq!(loaded, data);
+    }
+}
@@ -230,4 +230,67 @@ impl TextureResource {
             wgpu::FilterMode::Linear, // Default to smooth for backwards compatibility
         )
     }
+
+    /// Creates an empty 2D texture that compute shaders write as a storage texture
+    ///
+    /// The texture can be sampled afterwards, for instance by
+    /// [CutPlane2D](crate::visualization::CutPlane2D). `format` needs storage
+    /// support; `Rgba8Unorm` and `Rgba16Float` are also filterable, so they can be
+    /// shown smooth.
+    ///
+    /// # Arguments
+    /// * `device` - WGPU device for creating resources
+    /// * `width` - Width of the texture in pixels
+    /// * `height` - Height of the texture in pixels
+    /// * `format` - Storage texture format
+    /// * `label` - Debug label for the texture
+    /// * `filter_mode` - Texture filtering mode (Nearest for sharp, Linear for smooth)
+    ///
+    /// # Returns
+    /// TextureResource bindable with `image_2d_write(format)` and as a sampled texture
+    pub fn create_storage_texture(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+        label: &str,
+        filter_mode: wgpu::FilterMode,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::STORAGE_BINDING
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some(&format!("{} Sampler", label)),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: filter_mode,
+            min_filter: filter_mode,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        Self {
+            texture,
+            view,
+            sampler,
+        }
+    }
 }
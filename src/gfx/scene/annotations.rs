@@ -0,0 +1,384 @@
+//! Scene annotations
+//!
+//! Named markers with a note and a color, placed in world space to flag flow
+//! features and other points of interest during long interactive sessions.
+//! Markers are drawn over the scene with their name; hovering one shows its
+//! note. They are added from code or from the annotations panel, and saved
+//! to and loaded from TOML files with one `[[annotation]]` table each:
+//!
+//! ```toml
+//! [[annotation]]
+//! name = "Vortex shedding"
+//! position = [2.5, 0.0, 1.0]
+//! color = [1.0, 0.6, 0.2, 1.0]
+//! text = "Starts around step 1200"
+//! ```
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::gfx::scene::Annotation;
+//!
+//! let mut app = haggis::default();
+//! app.set_annotations_file("session_notes.toml");
+//! app.add_annotation(
+//!     Annotation::new("Inlet", [0.0, 0.0, 1.0]).with_text("Velocity ramps up here"),
+//! );
+//! ```
+
+use std::path::Path;
+
+use cgmath::Matrix4;
+use imgui::Ui;
+
+use crate::error::{HaggisError, Result};
+use crate::gfx::overlay::project;
+use crate::util::toml_lite::{format_array, parse_array, parse_string, quote};
+
+/// Marker radius in screen pixels
+const MARKER_RADIUS: f32 = 6.0;
+
+/// A named marker with a note
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    pub name: String,
+    /// World-space position
+    pub position: [f32; 3],
+    /// Note shown when hovering the marker
+    pub text: String,
+    pub color: [f32; 4],
+}
+
+impl Annotation {
+    pub fn new(name: &str, position: [f32; 3]) -> Self {
+        Self {
+            name: name.to_string(),
+            position,
+            text: String::new(),
+            color: [1.0, 0.6, 0.2, 1.0],
+        }
+    }
+
+    pub fn with_text(mut self, text: &str) -> Self {
+        self.text = text.to_string();
+        self
+    }
+
+    pub fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = color;
+        self
+    }
+}
+
+/// The scene's annotations and the annotations panel state
+#[derive(Debug, Clone)]
+pub struct Annotations {
+    items: Vec<Annotation>,
+    /// Draw the markers over the scene
+    pub visible: bool,
+    /// Show the annotations panel
+    pub open: bool,
+    new_name: String,
+}
+
+impl Annotations {
+    pub fn new() -> Self {
+        Self {
+            items: Vec::new(),
+            visible: true,
+            open: false,
+            new_name: String::new(),
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    /// Add an annotation, returning its index
+    pub fn add(&mut self, annotation: Annotation) -> usize {
+        self.items.push(annotation);
+        self.items.len() - 1
+    }
+
+    /// Remove the annotation at `index`
+    pub fn remove(&mut self, index: usize) -> Option<Annotation> {
+        (index < self.items.len()).then(|| self.items.remove(index))
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Annotation> {
+        self.items.get(index)
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Annotation> {
+        self.items.get_mut(index)
+    }
+
+    /// First annotation with the given name
+    pub fn find(&self, name: &str) -> Option<&Annotation> {
+        self.items.iter().find(|annotation| annotation.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Annotation> {
+        self.items.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+    }
+
+    /// Serialize the annotations as TOML
+    pub fn to_toml(&self) -> String {
+        let mut out = String::from("# Haggis scene annotations\n");
+        for annotation in &self.items {
+            out.push_str("\n[[annotation]]\n");
+            out.push_str(&format!("name = {}\n", quote(&annotation.name)));
+            out.push_str(&format!(
+                "position = {}\n",
+                format_array(&annotation.position)
+            ));
+            out.push_str(&format!("color = {}\n", format_array(&annotation.color)));
+            out.push_str(&format!("text = {}\n", quote(&annotation.text)));
+        }
+        out
+    }
+
+    /// Parse annotations from TOML text
+    ///
+    /// Every `[[annotation]]` table needs a `position`; `name`, `text` and
+    /// `color` are optional. Unknown keys and malformed values are
+    /// errors, reported with their line number.
+    pub fn from_toml(text: &str) -> std::result::Result<Vec<Annotation>, String> {
+        let mut items: Vec<Annotation> = Vec::new();
+        let mut has_position = true;
+
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if line == "[[annotation]]" {
+                if !has_position {
+                    return Err(format!(
+                        "line {line_number}: previous annotation has no position"
+                    ));
+                }
+                items.push(Annotation::new("", [0.0; 3]));
+                has_position = false;
+                continue;
+            }
+            let Some(annotation) = items.last_mut() else {
+                return Err(format!("line {line_number}: expected [[annotation]]"));
+            };
+
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(format!("line {line_number}: expected `key = value`"));
+            };
+            let (key, value) = (key.trim(), value.trim());
+            let invalid = || format!("line {line_number}: invalid value for {key}");
+            match key {
+                "name" => annotation.name = parse_string(value).ok_or_else(invalid)?,
+                "text" => annotation.text = parse_string(value).ok_or_else(invalid)?,
+                "position" => {
+                    annotation.position = parse_array(value).ok_or_else(invalid)?;
+                    has_position = true;
+                }
+                "color" => annotation.color = parse_array(value).ok_or_else(invalid)?,
+                other => return Err(format!("line {line_number}: unknown key {other}")),
+            }
+        }
+
+        if !has_position {
+            return Err("last annotation has no position".to_string());
+        }
+        Ok(items)
+    }
+
+    /// Replace the annotations with those of a TOML file
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::Io`] if the file cannot be read,
+    /// [`HaggisError::Annotations`] if it is malformed, and
+    /// [`HaggisError::NoFileSystem`] on platforms without file access.
+    pub fn load(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if !crate::platform::HAS_FILESYSTEM {
+            return Err(HaggisError::NoFileSystem { path: path.into() });
+        }
+        let text = std::fs::read_to_string(path).map_err(|source| HaggisError::Io {
+            path: path.into(),
+            source,
+        })?;
+        self.items = Self::from_toml(&text).map_err(|message| HaggisError::Annotations {
+            path: path.into(),
+            message,
+        })?;
+        Ok(())
+    }
+
+    /// Save the annotations as a TOML file
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::Io`] if the file cannot be written and
+    /// [`HaggisError::NoFileSystem`] on platforms without file access.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if !crate::platform::HAS_FILESYSTEM {
+            return Err(HaggisError::NoFileSystem { path: path.into() });
+        }
+        std::fs::write(path, self.to_toml()).map_err(|source| HaggisError::Io {
+            path: path.into(),
+            source,
+        })
+    }
+
+    /// Draw the markers and their names over the scene, the note of the
+    /// hovered marker as a tooltip
+    pub fn render_labels(&self, ui: &Ui, view_proj: Matrix4<f32>) {
+        if !self.visible {
+            return;
+        }
+        let display_size = ui.io().display_size;
+        let mouse = ui.io().mouse_pos;
+        let draw_list = ui.get_background_draw_list();
+        let mut hovered = None;
+
+        for annotation in &self.items {
+            let Some(center) = project(view_proj, annotation.position.into(), display_size) else {
+                continue;
+            };
+            draw_list
+                .add_circle(center, MARKER_RADIUS, annotation.color)
+                .filled(true)
+                .build();
+            draw_list
+                .add_circle(center, MARKER_RADIUS, [0.0, 0.0, 0.0, 0.8])
+                .thickness(1.5)
+                .build();
+            draw_list.add_text(
+                [
+                    center[0] + MARKER_RADIUS + 3.0,
+                    center[1] - MARKER_RADIUS - 6.0,
+                ],
+                [1.0, 1.0, 1.0, 1.0],
+                &annotation.name,
+            );
+
+            let (dx, dy) = (mouse[0] - center[0], mouse[1] - center[1]);
+            if dx * dx + dy * dy <= (MARKER_RADIUS * 2.0).powi(2) && !annotation.text.is_empty() {
+                hovered = Some(annotation);
+            }
+        }
+
+        if let Some(annotation) = hovered {
+            if !ui.is_window_hovered_with_flags(imgui::WindowHoveredFlags::ANY_WINDOW) {
+                ui.tooltip_text(&annotation.text);
+            }
+        }
+    }
+
+    /// Annotations panel: add, edit and delete markers
+    ///
+    /// # Arguments
+    ///
+    /// * `new_position` - Where the "Add" button places new markers, e.g. the
+    ///   camera target
+    pub fn render_ui(&mut self, ui: &Ui, new_position: [f32; 3]) {
+        if !self.open {
+            return;
+        }
+
+        let mut open = self.open;
+        ui.window("Annotations")
+            .size([320.0, 400.0], imgui::Condition::FirstUseEver)
+            .opened(&mut open)
+            .build(|| {
+                ui.checkbox("Show markers", &mut self.visible);
+                ui.input_text("##new_annotation", &mut self.new_name)
+                    .hint("Name")
+                    .build();
+                ui.same_line();
+                if ui.button("Add at target") {
+                    let name = match self.new_name.trim() {
+                        "" => format!("Marker {}", self.items.len() + 1),
+                        name => name.to_string(),
+                    };
+                    self.add(Annotation::new(&name, new_position));
+                    self.new_name.clear();
+                }
+                ui.separator();
+
+                let mut removed = None;
+                for (index, annotation) in self.items.iter_mut().enumerate() {
+                    let _id = ui.push_id_usize(index);
+                    if let Some(_node) = ui.tree_node(format!("{}###node", annotation.name)) {
+                        ui.input_text("Name", &mut annotation.name).build();
+                        ui.input_float3("Position", &mut annotation.position)
+                            .build();
+                        ui.color_edit4("Color", &mut annotation.color);
+                        ui.input_text_multiline("Note", &mut annotation.text, [0.0, 60.0])
+                            .build();
+                        if ui.button("Delete") {
+                            removed = Some(index);
+                        }
+                    }
+                }
+                if let Some(index) = removed {
+                    self.items.remove(index);
+                }
+                if self.items.is_empty() {
+                    ui.text_disabled("No annotations");
+                }
+            });
+        self.open = open;
+    }
+}
+
+impl Default for Annotations {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotations_toml_round_trip() {
+        let mut annotations = Annotations::new();
+        annotations.add(Annotation::new("Inlet", [0.0, -1.5, 2.0]));
+        annotations.add(
+            Annotation::new("Vortex \"A\"", [2.5, 0.0, 1.0])
+                .with_text("Sheds at step 1200\nthen drifts \\ down")
+                .with_color([0.2, 0.4, 1.0, 0.5]),
+        );
+
+        let parsed = Annotations::from_toml(&annotations.to_toml()).unwrap();
+        assert_eq!(parsed, annotations.iter().cloned().collect::<Vec<_>>());
+        assert_eq!(
+            annotations.find("Inlet").unwrap().position,
+            [0.0, -1.5, 2.0]
+        );
+    }
+
+    #[test]
+    fn test_malformed_annotations_are_rejected() {
+        assert!(Annotations::from_toml("name = \"orphan\"").is_err());
+        assert!(Annotations::from_toml("[[annotation]]\nname = \"no position\"").is_err());
+        let error = Annotations::from_toml("[[annotation]]\nposition = [1.0, 2.0]").unwrap_err();
+        assert!(error.starts_with("line 2"));
+    }
+}
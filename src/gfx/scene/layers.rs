@@ -0,0 +1,198 @@
+//! Object layers
+//!
+//! Every [`Object`](super::Object) belongs to one or more of 32 layers, given
+//! as a [`Layers`] bitmask. The scene names layers and shows or hides them as
+//! groups with [`SceneLayers`], and each camera draws only the layers in its
+//! own mask, e.g. a viewport that leaves out reference geometry.
+//!
+//! An object is drawn if any of its layers is both visible in the scene and
+//! in the camera's mask. Besides objects, the engine puts the world grid and
+//! axes on [`Layers::REFERENCE`] and visualization planes and lines on
+//! [`Layers::VISUALIZATION`].
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::gfx::scene::Layers;
+//!
+//! let mut app = haggis::default();
+//! app.add_object("obstacle.obj").with_layers(Layers::BOUNDARY);
+//!
+//! // Hide every boundary marker at once
+//! app.app_state.scene.layers.set_visible(Layers::BOUNDARY, false);
+//! ```
+
+use std::ops::{BitAnd, BitOr, Not};
+
+/// Set of layers as a bitmask, one bit per layer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Layers(pub u32);
+
+impl Layers {
+    /// Number of layers
+    pub const COUNT: u32 = 32;
+    pub const NONE: Self = Self(0);
+    pub const ALL: Self = Self(u32::MAX);
+    /// Layer of objects that don't set any
+    pub const DEFAULT: Self = Self::layer(0);
+    /// World grid, axes and other reference geometry
+    pub const REFERENCE: Self = Self::layer(1);
+    /// Obstacles, inlets and other boundary markers
+    pub const BOUNDARY: Self = Self::layer(2);
+    /// Visualization planes, probes and domain boxes
+    pub const VISUALIZATION: Self = Self::layer(3);
+
+    /// The single layer `index`, below [`COUNT`](Self::COUNT)
+    pub const fn layer(index: u32) -> Self {
+        Self(1 << index)
+    }
+
+    /// Whether every layer of `other` is in this set
+    pub fn contains(self, other: Layers) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Whether this set and `other` share a layer
+    pub fn intersects(self, other: Layers) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    /// Indices of the layers in this set
+    pub fn indices(self) -> impl Iterator<Item = u32> {
+        (0..Self::COUNT).filter(move |&index| self.intersects(Self::layer(index)))
+    }
+}
+
+impl Default for Layers {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl BitOr for Layers {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+impl BitAnd for Layers {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+}
+
+impl Not for Layers {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        Self(!self.0)
+    }
+}
+
+/// Names and visibility of the scene's layers
+#[derive(Debug, Clone)]
+pub struct SceneLayers {
+    names: Vec<String>,
+    visible: Layers,
+}
+
+impl SceneLayers {
+    /// Every layer visible, the built-in layers named
+    pub fn new() -> Self {
+        let names = (0..Layers::COUNT)
+            .map(|index| match index {
+                0 => "Default".to_string(),
+                1 => "Reference".to_string(),
+                2 => "Boundary".to_string(),
+                3 => "Visualization".to_string(),
+                _ => format!("Layer {index}"),
+            })
+            .collect();
+        Self {
+            names,
+            visible: Layers::ALL,
+        }
+    }
+
+    /// Name of layer `index`
+    pub fn name(&self, index: u32) -> &str {
+        &self.names[index as usize]
+    }
+
+    /// Rename layer `index`, e.g. for user-defined layers
+    pub fn set_name(&mut self, index: u32, name: &str) {
+        self.names[index as usize] = name.to_string();
+    }
+
+    /// Layers currently shown
+    pub fn visible(&self) -> Layers {
+        self.visible
+    }
+
+    /// Whether any of `layers` is shown
+    pub fn is_visible(&self, layers: Layers) -> bool {
+        self.visible.intersects(layers)
+    }
+
+    /// Show or hide every layer in `layers`
+    pub fn set_visible(&mut self, layers: Layers, visible: bool) {
+        self.visible = if visible {
+            self.visible | layers
+        } else {
+            self.visible & !layers
+        };
+    }
+
+    /// Checkboxes showing or hiding each of `layers`
+    ///
+    /// # Returns
+    ///
+    /// `true` if a layer was shown or hidden
+    pub fn render_ui(&mut self, ui: &imgui::Ui, layers: Layers) -> bool {
+        let mut changed = false;
+        for index in layers.indices() {
+            let layer = Layers::layer(index);
+            let mut visible = self.is_visible(layer);
+            if ui.checkbox(
+                format!("{}##layer{index}", self.names[index as usize]),
+                &mut visible,
+            ) {
+                self.set_visible(layer, visible);
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+impl Default for SceneLayers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layer_sets() {
+        let markers = Layers::BOUNDARY | Layers::layer(7);
+        assert!(markers.contains(Layers::BOUNDARY));
+        assert!(!markers.contains(Layers::BOUNDARY | Layers::DEFAULT));
+        assert!(markers.intersects(Layers::layer(7) | Layers::DEFAULT));
+        assert_eq!(markers.indices().collect::<Vec<_>>(), [2, 7]);
+
+        let mut scene = SceneLayers::new();
+        scene.set_visible(Layers::BOUNDARY, false);
+        assert!(scene.is_visible(markers));
+        scene.set_visible(Layers::layer(7), false);
+        assert!(!scene.is_visible(markers));
+        assert!(scene.is_visible(Layers::DEFAULT));
+        assert_eq!(scene.name(2), "Boundary");
+    }
+}
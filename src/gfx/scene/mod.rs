@@ -9,6 +9,10 @@
 //! - [`Scene`] - The main scene container that manages objects, camera, and materials
 //! - [`Object`] - Individual 3D objects with meshes, materials, and transforms
 //! - [`ObjectBuilder`] - Builder pattern for configuring objects
+//! - [`Layers`] - Layer bitmasks for showing and hiding objects as groups
+//! - [`Annotations`] - Named markers with notes, saved to and loaded from files
+//! - [`Prefab`] - Reusable object compositions spawned by name
+//! - [`TransformBuffer`] - Model matrices and opacities of all objects in one storage buffer
 //! - [`Vertex3D`] - 3D vertex data structure with position, normal, and texture coordinates
 //!
 //! ## Usage
@@ -35,11 +39,19 @@
 //! - GPU resource management
 //! - Builder pattern configuration
 
+pub mod annotations;
+pub mod layers;
 pub mod object;
+pub mod prefab;
 pub mod scene;
+pub mod transform_buffer;
 pub mod vertex;
 
 // Re-export main types
+pub use annotations::{Annotation, Annotations};
+pub use layers::{Layers, SceneLayers};
 pub use object::{DrawObject, Object, ObjectBuilder};
+pub use prefab::Prefab;
 pub use scene::Scene;
+pub use transform_buffer::{ObjectTransform, TransformBuffer};
 pub use vertex::Vertex3D;
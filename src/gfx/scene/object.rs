@@ -1,26 +1,189 @@
 use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
 use wgpu::Device;
 
-use crate::{app::HaggisApp, gfx::resources::material::MaterialId};
+use crate::{
+    app::HaggisApp,
+    ecs::{Component, Entity},
+    gfx::{
+        geometry::{compute_flat_normals, GeometryData},
+        resources::material::MaterialId,
+    },
+};
+
+use super::{layers::Layers, vertex::Vertex3D};
+
+/// How a mesh's surface is shaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Shading {
+    /// With the mesh's own vertex normals
+    #[default]
+    Smooth,
+    /// With one normal per triangle, showing the facets
+    Flat,
+}
+
+/// Identity of a mesh's geometry, shared by its clones until one of them
+/// changes its vertices or shading
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MeshId(u64);
 
-use super::vertex::Vertex3D;
+impl MeshId {
+    fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
 
+/// Triangle mesh with its GPU buffers
+///
+/// Cloning a mesh keeps its [`MeshId`] and shares any GPU buffers already
+/// created, so objects built from clones of one mesh are drawn together.
+#[derive(Clone)]
 pub struct Mesh {
+    id: MeshId,
     vertices: Vec<Vertex3D>,
     indices: Vec<u32>,
-    vertex_buffer: Option<wgpu::Buffer>,
-    index_buffer: Option<wgpu::Buffer>,
+    vertex_buffer: Option<Arc<wgpu::Buffer>>,
+    index_buffer: Option<Arc<wgpu::Buffer>>,
+    /// Vertices changed since the last upload
+    vertices_dirty: bool,
+    shading: Shading,
+    /// The mesh's own vertices and indices while flat shaded
+    smooth: Option<(Vec<Vertex3D>, Vec<u32>)>,
     pub index_count: u32,
     pub vertex_count: u32,
 }
 
 impl Mesh {
+    pub fn id(&self) -> MeshId {
+        self.id
+    }
+
     /// Get the vertices for this mesh
     pub fn vertices(&self) -> &[Vertex3D] {
         &self.vertices
     }
 
+    /// Get the triangle indices for this mesh
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    /// Replace the vertices, e.g. with the output of a simulation
+    ///
+    /// The existing vertex buffer is rewritten in place on the next
+    /// [`upload_vertices`](Self::upload_vertices); it is only recreated if the
+    /// vertex count changes. Indices are kept, so they must stay valid. While
+    /// flat shaded, `vertices` replace the mesh's own vertices and are
+    /// flattened again.
+    pub fn set_vertices(&mut self, mut vertices: Vec<Vertex3D>) {
+        if let Some((smooth_vertices, smooth_indices)) = &mut self.smooth {
+            let flat = Self::flattened(&vertices, smooth_indices).0;
+            *smooth_vertices = std::mem::replace(&mut vertices, flat);
+        }
+        // Clones keep the old geometry, so stop sharing the vertex buffer
+        self.id = MeshId::next();
+        let shared = self
+            .vertex_buffer
+            .as_ref()
+            .is_some_and(|buffer| Arc::strong_count(buffer) > 1);
+        if shared || vertices.len() != self.vertices.len() {
+            self.vertex_buffer = None;
+        }
+        self.vertex_count = vertices.len() as u32;
+        self.vertices = vertices;
+        self.vertices_dirty = true;
+    }
+
+    /// Move the vertices and recompute smooth normals from the triangles
+    ///
+    /// While flat shaded, `positions` are those of the mesh's own vertices.
+    ///
+    /// # Panics
+    ///
+    /// If `positions` doesn't have one entry per vertex
+    pub fn set_positions(&mut self, positions: &[[f32; 3]]) {
+        let (vertices, indices) = match &self.smooth {
+            Some((vertices, indices)) => (vertices, indices),
+            None => (&self.vertices, &self.indices),
+        };
+        assert_eq!(
+            positions.len(),
+            vertices.len(),
+            "set_positions needs one position per vertex"
+        );
+        let flat: Vec<f32> = positions.iter().flatten().copied().collect();
+        let normals = Self::calculate_face_normals(&flat, indices);
+        let vertices = positions
+            .iter()
+            .enumerate()
+            .map(|(i, &position)| Vertex3D {
+                position,
+                normal: [normals[i * 3], normals[i * 3 + 1], normals[i * 3 + 2]],
+            })
+            .collect();
+        self.set_vertices(vertices);
+    }
+
+    pub fn shading(&self) -> Shading {
+        self.shading
+    }
+
+    /// Switch between the mesh's own normals and one normal per triangle
+    ///
+    /// Flat shading gives every triangle its own vertices, so the buffers are
+    /// recreated; the mesh's own vertices are kept and restored when
+    /// switching back.
+    pub fn set_shading(&mut self, shading: Shading) {
+        if shading == self.shading {
+            return;
+        }
+        let (vertices, indices) = match self.smooth.take() {
+            Some(smooth) => smooth,
+            None => {
+                let flat = Self::flattened(&self.vertices, &self.indices);
+                self.smooth = Some((
+                    std::mem::take(&mut self.vertices),
+                    std::mem::take(&mut self.indices),
+                ));
+                flat
+            }
+        };
+        self.vertex_count = vertices.len() as u32;
+        self.index_count = indices.len() as u32;
+        self.vertices = vertices;
+        self.indices = indices;
+        self.id = MeshId::next();
+        self.vertex_buffer = None;
+        self.index_buffer = None;
+        self.vertices_dirty = false;
+        self.shading = shading;
+    }
+
+    /// Vertices and indices with every triangle's vertices unshared
+    fn flattened(vertices: &[Vertex3D], indices: &[u32]) -> (Vec<Vertex3D>, Vec<u32>) {
+        let mut geometry = GeometryData::new();
+        geometry.vertices = vertices.iter().map(|v| v.position).collect();
+        geometry.normals = vertices.iter().map(|v| v.normal).collect();
+        geometry.indices = indices.to_vec();
+        compute_flat_normals(&mut geometry);
+        geometry.to_scene_format()
+    }
+
+    /// Write changed vertices to the existing vertex buffer
+    pub fn upload_vertices(&mut self, queue: &wgpu::Queue) {
+        if !self.vertices_dirty {
+            return;
+        }
+        if let Some(buffer) = &self.vertex_buffer {
+            queue.write_buffer(buffer, 0, bytemuck::cast_slice(&self.vertices));
+            self.vertices_dirty = false;
+        }
+    }
+
     pub fn new(positions: Vec<f32>, normals: Vec<f32>, indices: Vec<u32>) -> Self {
         let index_count = indices.len() as u32;
 
@@ -35,19 +198,30 @@ impl Mesh {
         let vertex_count = vertices.len() as u32;
 
         Self {
+            id: MeshId::next(),
             vertices,
             indices,
             vertex_buffer: None,
             index_buffer: None,
+            vertices_dirty: false,
+            shading: Shading::Smooth,
+            smooth: None,
             index_count,
             vertex_count,
         }
     }
 
+    /// Mesh of procedural or imported geometry
+    pub fn from_geometry(geometry: &GeometryData) -> Self {
+        let positions = geometry.vertices.iter().flatten().copied().collect();
+        let (vertices, indices) = geometry.to_scene_format();
+        let normals = vertices.iter().flat_map(|v| v.normal).collect();
+        Self::new(positions, normals, indices)
+    }
+
     // Helper function to calculate face normals if OBJ doesn't have them
     pub fn calculate_face_normals(positions: &[f32], indices: &[u32]) -> Vec<f32> {
-        #[cfg(debug_assertions)]
-        println!("Calculating face normals...");
+        tracing::debug!("Calculating face normals...");
         let vertex_count = positions.len() / 3;
         let mut normals = vec![0.0; positions.len()]; // Same length as positions
         let mut counts = vec![0; vertex_count]; // Count contributions per vertex
@@ -131,6 +305,20 @@ impl<'a> ObjectBuilder<'a> {
         Self { app, object_index }
     }
 
+    /// Entity of the object in the scene's [`World`](crate::ecs::World)
+    pub fn entity(&self) -> Option<Entity> {
+        self.app.app_state.scene.entity_of(self.object_index)
+    }
+
+    /// Attaches a component to the object's entity, e.g. per-object
+    /// simulation data
+    pub fn with_component<T: Component>(self, component: T) -> Self {
+        if let Some(entity) = self.entity() {
+            self.app.app_state.scene.world.insert(entity, component);
+        }
+        self
+    }
+
     /// Sets the object name
     pub fn with_name(self, name: &str) -> Self {
         // First, generate unique name (immutable borrow)
@@ -204,12 +392,38 @@ impl<'a> ObjectBuilder<'a> {
         }
         self
     }
-}
 
-/// GPU resources struct to hold all uniform buffers and bind groups
-pub struct ObjectGpuResources {
-    pub transform_buffer: wgpu::Buffer,
-    pub transform_bind_group: wgpu::BindGroup,
+    /// Sets the object's opacity, see [`Object::set_opacity`]
+    pub fn with_opacity(self, opacity: f32) -> Self {
+        if let Some(object) = self.app.app_state.scene.objects.get_mut(self.object_index) {
+            object.set_opacity(opacity);
+        }
+        self
+    }
+
+    /// Sets whether the object is drawn ghosted, see [`Object::ghost`]
+    pub fn with_ghost(self, ghost: bool) -> Self {
+        if let Some(object) = self.app.app_state.scene.objects.get_mut(self.object_index) {
+            object.ghost = ghost;
+        }
+        self
+    }
+
+    /// Sets whether the object is shaded smooth or flat
+    pub fn with_shading(self, shading: Shading) -> Self {
+        if let Some(object) = self.app.app_state.scene.objects.get_mut(self.object_index) {
+            object.set_shading(shading);
+        }
+        self
+    }
+
+    /// Sets the layers the object belongs to, replacing [`Layers::DEFAULT`]
+    pub fn with_layers(self, layers: Layers) -> Self {
+        if let Some(object) = self.app.app_state.scene.objects.get_mut(self.object_index) {
+            object.layers = layers;
+        }
+        self
+    }
 }
 
 /// UI transform state for interactive editing
@@ -234,15 +448,25 @@ impl Default for UiTransformState {
 pub struct Object {
     pub meshes: Vec<Mesh>,
     pub transform: Matrix4<f32>,
-    pub gpu_resources: Option<ObjectGpuResources>,
 
     // Object properties
     pub name: String,
     pub ui_transform: UiTransformState,
     pub visible: bool,
+    /// Layers the object belongs to, see [`Layers`]
+    pub layers: Layers,
+    /// Opacity multiplied into the material alpha, see [`set_opacity`](Self::set_opacity)
+    opacity: f32,
+    /// Draws the object X-ray style: faint and see-through, still visible
+    /// behind other geometry, and never hiding what is behind it
+    pub ghost: bool,
 
     // Material reference (stored as ID, actual material is in MaterialManager)
     pub material_id: Option<MaterialId>,
+
+    /// Entity in the scene's [`World`](crate::ecs::World), assigned when the
+    /// object is added to a scene
+    pub entity: Option<Entity>,
 }
 
 impl Object {
@@ -251,11 +475,14 @@ impl Object {
         Self {
             meshes,
             transform: Matrix4::identity(),
-            gpu_resources: None,
             name: "Object".to_string(),
             ui_transform: UiTransformState::default(),
             visible: true,
+            layers: Layers::DEFAULT,
+            opacity: 1.0,
+            ghost: false,
             material_id: None, // No material assigned initially (will use default)
+            entity: None,
         }
     }
 
@@ -285,6 +512,35 @@ impl Object {
         self.material_id = None;
     }
 
+    /// Opacity of the object, 1 for opaque
+    pub fn opacity(&self) -> f32 {
+        self.opacity
+    }
+
+    /// Fades the object independently of its material
+    ///
+    /// The opacity is clamped to 0..=1 and multiplies the material's base
+    /// color alpha. Objects below 1 are drawn in the sorted transparent queue
+    /// even when their material is opaque, so several objects can share one
+    /// material at different opacities.
+    pub fn set_opacity(&mut self, opacity: f32) {
+        self.opacity = opacity.clamp(0.0, 1.0);
+    }
+
+    /// Shading of the object's meshes
+    pub fn shading(&self) -> Shading {
+        self.meshes
+            .first()
+            .map_or(Shading::Smooth, |mesh| mesh.shading())
+    }
+
+    /// Shade all meshes smooth or flat, see [`Mesh::set_shading`]
+    pub fn set_shading(&mut self, shading: Shading) {
+        for mesh in &mut self.meshes {
+            mesh.set_shading(shading);
+        }
+    }
+
     /// Applies UI transform state to the actual transform matrix
     pub fn apply_ui_transform(&mut self) {
         self.reset_transform();
@@ -403,97 +659,49 @@ impl Object {
         self.transform = Matrix4::identity();
     }
 
-    /// Updates the transformation matrix and syncs to GPU if resources exist
-    pub fn update_transform(&mut self, queue: &wgpu::Queue) {
-        if let Some(gpu_resources) = &self.gpu_resources {
-            // cgmath matrices are column-major, which is what GPU expects
-            let transform_data: &[f32; 16] = self.transform.as_ref();
-
-            queue.write_buffer(
-                &gpu_resources.transform_buffer,
-                0,
-                bytemuck::cast_slice(transform_data),
-            );
+    /// Drops GPU buffers so they are recreated by [`init_gpu_resources`](Self::init_gpu_resources)
+    pub fn release_gpu_resources(&mut self) {
+        for mesh in self.meshes.iter_mut() {
+            mesh.vertex_buffer = None;
+            mesh.index_buffer = None;
         }
     }
 
-    /// Gets the transform bind group for rendering
-    pub fn get_transform_bind_group(&self) -> Option<&wgpu::BindGroup> {
-        self.gpu_resources
-            .as_ref()
-            .map(|res| &res.transform_bind_group)
+    /// Writes changed mesh vertices to the GPU
+    pub fn upload_mesh_changes(&mut self, queue: &wgpu::Queue) {
+        for mesh in self.meshes.iter_mut() {
+            mesh.upload_vertices(queue);
+        }
     }
 
     /// Initializes GPU resources for this object
     pub fn init_gpu_resources(&mut self, device: &Device) {
-        // Initialize mesh buffers
+        // Initialize mesh buffers; existing ones are kept and updated through
+        // `Mesh::upload_vertices`
         for mesh in self.meshes.iter_mut() {
-            let vertex_bytes = bytemuck::cast_slice(&mesh.vertices);
-            let index_bytes = bytemuck::cast_slice(&mesh.indices);
-
-            let vertex_buffer = wgpu::util::DeviceExt::create_buffer_init(
-                device,
-                &wgpu::util::BufferInitDescriptor {
-                    label: Some("Vertex Buffer"),
-                    contents: vertex_bytes,
-                    usage: wgpu::BufferUsages::VERTEX,
-                },
-            );
-
-            let index_buffer = wgpu::util::DeviceExt::create_buffer_init(
-                device,
-                &wgpu::util::BufferInitDescriptor {
-                    label: Some("Index Buffer"),
-                    contents: index_bytes,
-                    usage: wgpu::BufferUsages::INDEX,
-                },
-            );
-
-            mesh.vertex_buffer = Some(vertex_buffer);
-            mesh.index_buffer = Some(index_buffer);
-        }
-
-        // Create transform uniform buffer and bind group
-        let transform_data: &[f32; 16] = self.transform.as_ref();
-
-        let transform_buffer = wgpu::util::DeviceExt::create_buffer_init(
-            device,
-            &wgpu::util::BufferInitDescriptor {
-                label: Some("Transform Uniform Buffer"),
-                contents: bytemuck::cast_slice(transform_data),
-                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            },
-        );
-
-        let transform_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                label: Some("Transform Bind Group Layout"),
-                entries: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
+            if mesh.vertex_buffer.is_none() {
+                mesh.vertex_buffer = Some(Arc::new(wgpu::util::DeviceExt::create_buffer_init(
+                    device,
+                    &wgpu::util::BufferInitDescriptor {
+                        label: Some("Vertex Buffer"),
+                        contents: bytemuck::cast_slice(&mesh.vertices),
+                        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
                     },
-                    count: None,
-                }],
-            });
+                )));
+                mesh.vertices_dirty = false;
+            }
 
-        let transform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Transform Bind Group"),
-            layout: &transform_bind_group_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: transform_buffer.as_entire_binding(),
-            }],
-        });
-
-        // Store GPU resources
-        self.gpu_resources = Some(ObjectGpuResources {
-            transform_buffer,
-            transform_bind_group,
-        });
+            if mesh.index_buffer.is_none() {
+                mesh.index_buffer = Some(Arc::new(wgpu::util::DeviceExt::create_buffer_init(
+                    device,
+                    &wgpu::util::BufferInitDescriptor {
+                        label: Some("Index Buffer"),
+                        contents: bytemuck::cast_slice(&mesh.indices),
+                        usage: wgpu::BufferUsages::INDEX,
+                    },
+                )));
+            }
+        }
     }
 }
 
@@ -501,7 +709,13 @@ impl Object {
 pub trait DrawObject<'a> {
     fn draw_mesh(&mut self, mesh: &'a Mesh);
     fn draw_mesh_instanced(&mut self, mesh: &'a Mesh, instances: Range<u32>);
-    fn draw_object(&mut self, object: &'a Object);
+    /// Draw `mesh` with `DrawIndexedIndirectArgs` read from `indirect` at `offset`
+    fn draw_mesh_indirect(&mut self, mesh: &'a Mesh, indirect: &'a wgpu::Buffer, offset: u64);
+    /// Draw `object` with the model matrix and opacity in `transform_slot` of the bound
+    /// [`TransformBuffer`](super::transform_buffer::TransformBuffer)
+    fn draw_object(&mut self, object: &'a Object, transform_slot: u32);
+    /// Draw every mesh of `object` for `instances`, which index the bound
+    /// transform buffer
     fn draw_object_instanced(&mut self, object: &'a Object, instances: Range<u32>);
 }
 
@@ -528,19 +742,53 @@ where
         self.draw_indexed(0..mesh.index_count, 0, instances);
     }
 
-    fn draw_object(&mut self, object: &'b Object) {
-        self.draw_object_instanced(object, 0..1);
+    fn draw_mesh_indirect(&mut self, mesh: &'b Mesh, indirect: &'b wgpu::Buffer, offset: u64) {
+        let (Some(vertex_buffer), Some(index_buffer)) = (&mesh.vertex_buffer, &mesh.index_buffer)
+        else {
+            return;
+        };
+
+        self.set_vertex_buffer(0, vertex_buffer.slice(..));
+        self.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        self.draw_indexed_indirect(indirect, offset);
     }
 
-    fn draw_object_instanced(&mut self, object: &'b Object, instances: Range<u32>) {
-        // Bind transform for this object (bind group slot 1)
-        if let Some(gpu_resources) = &object.gpu_resources {
-            self.set_bind_group(1, &gpu_resources.transform_bind_group, &[]);
-        }
+    fn draw_object(&mut self, object: &'b Object, transform_slot: u32) {
+        self.draw_object_instanced(object, transform_slot..transform_slot + 1);
+    }
 
+    fn draw_object_instanced(&mut self, object: &'b Object, instances: Range<u32>) {
         // Draw all meshes
         for mesh in &object.meshes {
             self.draw_mesh_instanced(mesh, instances.clone());
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gfx::geometry::generate_sphere;
+
+    #[test]
+    fn test_flat_shading_round_trip() {
+        let sphere = generate_sphere(8, 4);
+        let mut mesh = Mesh::from_geometry(&sphere);
+        let smooth_count = mesh.vertices().len();
+
+        mesh.set_shading(Shading::Flat);
+        assert_eq!(mesh.vertices().len(), mesh.indices().len());
+        assert_eq!(mesh.vertex_count as usize, mesh.indices().len());
+        let triangle = &mesh.vertices()[3..6];
+        assert!(triangle.iter().all(|v| v.normal == triangle[0].normal));
+
+        // Simulations keep writing the mesh's own vertices while it's flat
+        let moved: Vec<[f32; 3]> = sphere.vertices.iter().map(|p| p.map(|c| c * 2.0)).collect();
+        mesh.set_positions(&moved);
+        assert_eq!(mesh.vertices()[3].position, moved[sphere.indices[3] as usize]);
+
+        mesh.set_shading(Shading::Smooth);
+        assert_eq!(mesh.vertices().len(), smooth_count);
+        assert_eq!(mesh.vertices()[5].position, moved[5]);
+    }
+}
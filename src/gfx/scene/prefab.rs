@@ -0,0 +1,194 @@
+//! Prefabs: reusable object compositions
+//!
+//! A [`Prefab`] describes a mesh, material, scale and rotation, plus child
+//! prefabs placed relative to it. Define it once under a name, then spawn it
+//! wherever it's needed instead of repeating the same `add_object` chain.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::gfx::scene::prefab::Prefab;
+//!
+//! let mut app = haggis::default();
+//! app.app_state.scene.add_material_rgb("marker", 1.0, 0.8, 0.2, 0.0, 0.5);
+//! app.define_prefab(
+//!     "boundary_marker",
+//!     Prefab::cube().with_scale(0.05).with_material("marker"),
+//! );
+//!
+//! for corner in [[-1.0, -1.0, -1.0], [1.0, 1.0, 1.0]] {
+//!     app.spawn_prefab("boundary_marker", corner);
+//! }
+//! ```
+
+use cgmath::{Deg, Matrix3, Vector3};
+
+use crate::gfx::geometry::GeometryData;
+
+/// Where a prefab's geometry comes from
+#[derive(Debug, Clone)]
+pub enum PrefabMesh {
+    /// An OBJ file, loaded for every instance
+    File(String),
+    /// Procedural geometry
+    Geometry(GeometryData),
+}
+
+/// A named composition of mesh, material, transform and children
+#[derive(Debug, Clone)]
+pub struct Prefab {
+    /// Geometry of this node; `None` for a pure group of children
+    pub mesh: Option<PrefabMesh>,
+    /// Object name of instances; the prefab name is used when empty
+    pub name: String,
+    pub material: Option<String>,
+    /// Position relative to the parent, in the parent's scaled frame
+    pub offset: [f32; 3],
+    pub scale: f32,
+    /// Rotation around Y in degrees, relative to the parent
+    pub rotation_y: f32,
+    pub children: Vec<Prefab>,
+}
+
+impl Prefab {
+    fn with_mesh(mesh: Option<PrefabMesh>) -> Self {
+        Self {
+            mesh,
+            name: String::new(),
+            material: None,
+            offset: [0.0, 0.0, 0.0],
+            scale: 1.0,
+            rotation_y: 0.0,
+            children: Vec::new(),
+        }
+    }
+
+    /// Prefab loaded from an OBJ file
+    pub fn from_file(path: &str) -> Self {
+        Self::with_mesh(Some(PrefabMesh::File(path.to_string())))
+    }
+
+    /// Prefab with procedural geometry
+    pub fn from_geometry(geometry: GeometryData) -> Self {
+        Self::with_mesh(Some(PrefabMesh::Geometry(geometry)))
+    }
+
+    /// Unit cube prefab
+    pub fn cube() -> Self {
+        Self::from_geometry(crate::gfx::geometry::generate_cube())
+    }
+
+    /// UV sphere prefab with 32x16 segments
+    pub fn sphere() -> Self {
+        Self::from_geometry(crate::gfx::geometry::generate_sphere(32, 16))
+    }
+
+    /// Prefab without geometry, only grouping its children
+    pub fn group() -> Self {
+        Self::with_mesh(None)
+    }
+
+    /// Sets the object name of instances
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// Sets the material by ID
+    pub fn with_material(mut self, material_id: &str) -> Self {
+        self.material = Some(material_id.to_string());
+        self
+    }
+
+    /// Sets the uniform scale
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Sets the rotation around Y in degrees
+    pub fn with_rotation_y(mut self, rotation_y: f32) -> Self {
+        self.rotation_y = rotation_y;
+        self
+    }
+
+    /// Sets the position relative to the parent (only meaningful for children)
+    pub fn with_offset(mut self, offset: [f32; 3]) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Adds a child prefab
+    pub fn with_child(mut self, child: Prefab) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    /// World placement of every node with geometry, parents before children
+    ///
+    /// # Returns
+    ///
+    /// `(node, position, scale, rotation_y)` for each node with a mesh
+    pub(crate) fn placements(
+        &self,
+        position: Vector3<f32>,
+        scale: f32,
+        rotation_y: f32,
+    ) -> Vec<(&Prefab, Vector3<f32>, f32, f32)> {
+        let mut placements = Vec::new();
+        self.collect_placements(position, scale, rotation_y, &mut placements);
+        placements
+    }
+
+    fn collect_placements<'a>(
+        &'a self,
+        parent_position: Vector3<f32>,
+        parent_scale: f32,
+        parent_rotation_y: f32,
+        placements: &mut Vec<(&'a Prefab, Vector3<f32>, f32, f32)>,
+    ) {
+        let offset = Matrix3::from_angle_y(Deg(parent_rotation_y))
+            * (Vector3::from(self.offset) * parent_scale);
+        let position = parent_position + offset;
+        let scale = parent_scale * self.scale;
+        let rotation_y = parent_rotation_y + self.rotation_y;
+
+        if self.mesh.is_some() {
+            placements.push((self, position, scale, rotation_y));
+        }
+        for child in &self.children {
+            child.collect_placements(position, scale, rotation_y, placements);
+        }
+    }
+}
+
+/// Component attached to the entity of every object spawned from a prefab
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrefabInstance {
+    /// Name the prefab was defined under
+    pub prefab: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::InnerSpace;
+
+    #[test]
+    fn test_children_are_placed_in_parent_frame() {
+        let prefab = Prefab::cube()
+            .with_scale(2.0)
+            .with_rotation_y(90.0)
+            .with_child(Prefab::sphere().with_offset([1.0, 0.0, 0.0]).with_scale(0.5))
+            .with_child(Prefab::group().with_child(Prefab::cube()));
+
+        let placements = prefab.placements(Vector3::new(0.0, 5.0, 0.0), 1.0, 0.0);
+        assert_eq!(placements.len(), 3);
+
+        let (_, position, scale, rotation) = placements[1];
+        // X offset rotated 90 degrees around Y ends up on -Z, scaled by the parent
+        assert!((position - Vector3::new(0.0, 5.0, -2.0)).magnitude2() < 1e-8);
+        assert_eq!(scale, 1.0);
+        assert_eq!(rotation, 90.0);
+    }
+}
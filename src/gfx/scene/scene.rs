@@ -1,17 +1,71 @@
+use cgmath::Deg;
 use wgpu::Device;
 
 use crate::gfx::{
     camera::camera_utils::CameraManager,
+    capabilities::GpuCapabilities,
+    coordinates::CoordinateSystem,
+    geometry::{compute_smooth_normals, GeometryData, DEFAULT_SMOOTHING_ANGLE},
+    overlay::OverlayConfig,
+    rendering::{clip_plane::ClipPlane, projector::Projector, render_settings::RenderSettings},
     resources::material::{Material, MaterialManager},
 };
+use std::collections::HashMap;
+
+use crate::app::jobs::JobSystem;
+use crate::ecs::{Entity, MaterialRef, Name, Transform, World};
+use crate::error::{HaggisError, Result};
+use crate::events::EventBus;
+use crate::input::InputState;
+
+use super::{
+    annotations::Annotations,
+    layers::{Layers, SceneLayers},
+    object::Mesh,
+    object::Object,
+    prefab::{Prefab, PrefabInstance, PrefabMesh},
+    transform_buffer::{ObjectTransform, TransformBuffer},
+};
 
-use super::{object::Mesh, object::Object};
+/// Geometry and materials parsed from an OBJ file, ready to add to a scene
+struct LoadedModel {
+    meshes: Vec<Mesh>,
+    materials: Vec<tobj::Material>,
+    /// Name of the first model in the file
+    name: Option<String>,
+    /// Material of the first model, as an index into `materials`
+    material_id: Option<usize>,
+}
 
 /// Main scene containing objects, materials, and camera
 pub struct Scene {
     pub camera_manager: CameraManager,
     pub objects: Vec<Object>,
     pub material_manager: MaterialManager, // Centralized material storage
+    /// Keyboard/mouse state for the current frame, readable from simulations
+    pub input: InputState,
+    /// Event bus shared by the app, simulations and UI
+    pub events: EventBus,
+    /// Limits and features of the GPU, available once the render engine is created
+    pub gpu_capabilities: Option<GpuCapabilities>,
+    /// Entity components attached to objects and simulations
+    pub world: World,
+    /// World grid, axes and scale labels drawn by the engine
+    pub reference_overlay: OverlayConfig,
+    /// Clear color, background gradient and fog of the main view
+    pub render_settings: RenderSettings,
+    /// Planes cutting away scene geometry, in addition to clipping cut planes
+    pub clip_planes: Vec<ClipPlane>,
+    /// Image projected onto scene geometry along an axis
+    pub projector: Option<Projector>,
+    /// Layer names and which layers are shown
+    pub layers: SceneLayers,
+    /// Named markers with notes
+    pub annotations: Annotations,
+    prefabs: HashMap<String, Prefab>,
+    coordinate_system: CoordinateSystem,
+    /// Model matrices of all objects, indexed like `objects`
+    transform_buffer: Option<TransformBuffer>,
 }
 
 impl Scene {
@@ -21,25 +75,40 @@ impl Scene {
             camera_manager,
             objects: Vec::new(),
             material_manager: MaterialManager::new(), // Initialize with default material
+            input: InputState::new(),
+            events: EventBus::new(),
+            gpu_capabilities: None,
+            world: World::new(),
+            reference_overlay: OverlayConfig::default(),
+            render_settings: RenderSettings::default(),
+            clip_planes: Vec::new(),
+            projector: None,
+            layers: SceneLayers::new(),
+            annotations: Annotations::new(),
+            prefabs: HashMap::new(),
+            coordinate_system: CoordinateSystem::default(),
+            transform_buffer: None,
         }
     }
 
-    /// Converts coordinate data from Y-up to Z-up coordinate system
-    /// 
-    /// This fixes the common issue where OBJ files exported from Y-up programs
-    /// (like Blender) appear rotated 90 degrees when loaded into Z-up engines.
-    /// 
-    /// Transformation: (x, y, z) -> (x, -z, y)
-    fn convert_y_up_to_z_up(data: &mut [f32]) {
-        for i in 0..data.len() / 3 {
-            let base = i * 3;
-            let old_y = data[base + 1];
-            let old_z = data[base + 2];
-            
-            // Y-up to Z-up conversion: (x, y, z) -> (x, -z, y)
-            data[base + 1] = -old_z;  // new Y = -old Z
-            data[base + 2] = old_y;   // new Z = old Y
-        }
+    /// World up axis, asset up axis and units
+    pub fn coordinate_system(&self) -> &CoordinateSystem {
+        &self.coordinate_system
+    }
+
+    /// Switches the world's up axis and units
+    ///
+    /// Re-orients the camera and moves the reference grid into the new
+    /// ground plane with the new unit labels. Models loaded afterwards are
+    /// rotated from `asset_up_axis` into the world up axis; objects already
+    /// in the scene are left as they are.
+    pub fn set_coordinate_system(&mut self, coordinate_system: CoordinateSystem) {
+        self.camera_manager
+            .camera
+            .set_up_axis(coordinate_system.up_axis);
+        self.reference_overlay.plane = coordinate_system.up_axis.ground_plane();
+        self.reference_overlay.unit = coordinate_system.units.name.clone();
+        self.coordinate_system = coordinate_system;
     }
 
     /// Updates the scene (camera matrices, etc.)
@@ -47,11 +116,58 @@ impl Scene {
         self.camera_manager.camera.update_view_proj();
     }
 
+    /// Advances camera follow mode, if active, using the current object transforms
+    pub fn update_camera_follow(&mut self, delta_time: f32) {
+        self.camera_manager
+            .update_follow(delta_time, &self.objects);
+    }
+
     /// Loads a 3D object from an OBJ file with automatic material extraction
     ///
     /// Loads both geometry and materials from the OBJ/MTL files and automatically
     /// assigns materials to objects based on the material IDs in the OBJ file.
-    pub fn add_object(&mut self, object_path: &str) {
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::ModelLoad`] if the file is missing or malformed, and
+    /// [`HaggisError::NoFileSystem`] on platforms without file access. The scene
+    /// is left unchanged in both cases.
+    pub fn add_object(&mut self, object_path: &str) -> Result<()> {
+        let model = Self::load_model(object_path, &self.coordinate_system)?;
+        self.insert_model(model);
+        Ok(())
+    }
+
+    /// Loads several OBJ files, parsing them in parallel on the job system
+    ///
+    /// Objects are appended in the order of `object_paths`, skipping the ones
+    /// that fail to load.
+    ///
+    /// # Returns
+    ///
+    /// One result per path, in the same order
+    pub fn add_objects(&mut self, object_paths: &[&str]) -> Vec<Result<()>> {
+        let coordinate_system = &self.coordinate_system;
+        let models = JobSystem::global().parallel_map(object_paths.len(), |i| {
+            Self::load_model(object_paths[i], coordinate_system)
+        });
+
+        models
+            .into_iter()
+            .map(|model| model.map(|model| {
+                self.insert_model(model);
+            }))
+            .collect()
+    }
+
+    /// Parses an OBJ file into meshes, without touching the scene
+    fn load_model(object_path: &str, coordinate_system: &CoordinateSystem) -> Result<LoadedModel> {
+        if !crate::platform::HAS_FILESYSTEM {
+            return Err(HaggisError::NoFileSystem {
+                path: object_path.into(),
+            });
+        }
+
         let (models, materials) = tobj::load_obj(
             object_path,
             &tobj::LoadOptions {
@@ -60,20 +176,62 @@ impl Scene {
                 ..Default::default()
             },
         )
-        .expect("Failed to OBJ load file");
+        .map_err(|error| HaggisError::ModelLoad {
+            path: object_path.into(),
+            message: error.to_string(),
+        })?;
 
         let materials = materials.unwrap_or_else(|_| {
-            println!("No MTL file found, using default materials");
+            tracing::info!("No MTL file found, using default materials");
             Vec::new()
         });
 
-        // Load materials from OBJ file into material manager
-        for (i, mtl) in materials.iter().enumerate() {
-            let material_name = if mtl.name.is_empty() {
-                format!("material_{}", i)
+        let mut meshes = Vec::new();
+
+        for m in models.iter() {
+            let mesh = &m.mesh;
+
+            // Rotate from the asset's up axis (Y for most OBJ exporters) into the
+            // world's, so models don't appear tilted by 90 degrees
+            let up_axis = coordinate_system.up_axis;
+            let asset_up_axis = coordinate_system.asset_up_axis;
+            let mut positions = mesh.positions.clone();
+            up_axis.convert_slice_from(asset_up_axis, &mut positions);
+
+            // Use normals from OBJ if available, otherwise generate smooth
+            // normals that stay sharp across creases
+            if !mesh.normals.is_empty() && mesh.normals.len() == mesh.positions.len() {
+                let mut normals = mesh.normals.clone();
+                up_axis.convert_slice_from(asset_up_axis, &mut normals);
+                meshes.push(Mesh::new(positions, normals, mesh.indices.clone()));
             } else {
-                mtl.name.clone()
-            };
+                let mut geometry = GeometryData::new();
+                geometry.vertices = positions.chunks_exact(3).map(|p| [p[0], p[1], p[2]]).collect();
+                geometry.tex_coords = mesh.texcoords.chunks_exact(2).map(|t| [t[0], t[1]]).collect();
+                geometry.indices = mesh.indices.clone();
+                compute_smooth_normals(&mut geometry, DEFAULT_SMOOTHING_ANGLE);
+                meshes.push(Mesh::from_geometry(&geometry));
+            }
+        }
+
+        let first_model = models.first();
+        Ok(LoadedModel {
+            meshes,
+            name: first_model
+                .map(|model| model.name.clone())
+                .filter(|name| !name.is_empty()),
+            material_id: first_model
+                .and_then(|model| model.mesh.material_id)
+                .filter(|&id| id < materials.len()),
+            materials,
+        })
+    }
+
+    /// Adds a parsed model and its materials to the scene
+    fn insert_model(&mut self, model: LoadedModel) -> usize {
+        // Load materials from OBJ file into material manager
+        for (i, mtl) in model.materials.iter().enumerate() {
+            let material_name = Self::obj_material_name(mtl, i);
 
             // Skip if material already exists
             if self.material_manager.get_material(&material_name).is_some() {
@@ -96,70 +254,30 @@ impl Scene {
             self.material_manager.add_material(material);
         }
 
-        let mut meshes = Vec::new();
-
-        for m in models.iter() {
-            let mesh = &m.mesh;
-
-            // DEBUG: Print what we're getting from tobj
-            // println!(
-            //     "Positions: {} ({} vertices)",
-            //     mesh.positions.len(),
-            //     mesh.positions.len() / 3
-            // );
-            // println!(
-            //     "Normals: {} ({} normals)",
-            //     mesh.normals.len(),
-            //     mesh.normals.len() / 3
-            // );
-            // println!(
-            //     "Indices: {} ({} triangles)",
-            //     mesh.indices.len(),
-            //     mesh.indices.len() / 3
-            // );
-
-            // Convert from Y-up (OBJ standard) to Z-up (Haggis coordinate system)
-            // This fixes the 90-degree rotation issue where objects appear tilted
-            let mut positions = mesh.positions.clone();
-            Self::convert_y_up_to_z_up(&mut positions);
-
-            // Use normals from OBJ if available, otherwise calculate them
-            let normals = if !mesh.normals.is_empty() && mesh.normals.len() == mesh.positions.len()
-            {
-                let mut normals = mesh.normals.clone();
-                Self::convert_y_up_to_z_up(&mut normals);
-                normals
-            } else {
-                Mesh::calculate_face_normals(&positions, &mesh.indices)
-            };
-
-            let our_mesh = Mesh::new(positions, normals, mesh.indices.clone());
-            meshes.push(our_mesh);
-        }
-
         // Create object and assign material if available
-        let mut object = Object::new(meshes);
+        let mut object = Object::new(model.meshes);
 
         // Set object name from the first model
-        if let Some(first_model) = models.first() {
-            if !first_model.name.is_empty() {
-                object.set_name(first_model.name.clone());
-            }
+        if let Some(name) = model.name {
+            object.set_name(name);
+        }
 
-            // Assign material from OBJ file if available
-            if let Some(material_id) = first_model.mesh.material_id {
-                if material_id < materials.len() {
-                    let material_name = if materials[material_id].name.is_empty() {
-                        format!("material_{}", material_id)
-                    } else {
-                        materials[material_id].name.clone()
-                    };
-                    object.set_material(&material_name);
-                }
-            }
+        // Assign material from OBJ file if available
+        if let Some(material_id) = model.material_id {
+            let material_name = Self::obj_material_name(&model.materials[material_id], material_id);
+            object.set_material(&material_name);
         }
 
-        self.objects.push(object);
+        self.push_object(object)
+    }
+
+    /// Name under which an OBJ material is stored in the material manager
+    fn obj_material_name(material: &tobj::Material, index: usize) -> String {
+        if material.name.is_empty() {
+            format!("material_{}", index)
+        } else {
+            material.name.clone()
+        }
     }
 
     /// Add a procedural geometry object to the scene
@@ -170,25 +288,213 @@ impl Scene {
     /// # Arguments
     /// * `geometry_data` - The procedural geometry data
     /// * `name` - Name for the object
-    pub fn add_procedural_object(&mut self, geometry_data: crate::gfx::geometry::GeometryData, name: &str) {
-        let (vertices, indices) = geometry_data.to_scene_format();
-        
-        // Extract positions and normals from vertex data
-        let positions: Vec<f32> = vertices.iter()
-            .flat_map(|v| v.position.iter())
-            .cloned()
-            .collect();
-            
-        let normals: Vec<f32> = vertices.iter()
-            .flat_map(|v| v.normal.iter())
-            .cloned()
-            .collect();
-        
-        let mesh = Mesh::new(positions, normals, indices);
+    pub fn add_procedural_object(&mut self, geometry_data: GeometryData, name: &str) {
+        let mesh = Mesh::from_geometry(&geometry_data);
         let mut object = Object::new(vec![mesh]);
         object.set_name(name.to_string());
         
+        self.push_object(object);
+    }
+
+    /// Adds an object to the scene and spawns its entity
+    ///
+    /// # Returns
+    ///
+    /// Index of the object in [`objects`](Self::objects)
+    pub fn push_object(&mut self, object: Object) -> usize {
         self.objects.push(object);
+        let object_index = self.objects.len() - 1;
+        self.attach_entity(object_index);
+        object_index
+    }
+
+    /// Removes an object and despawns its entity
+    ///
+    /// Objects after it shift down by one index; their entities are unchanged.
+    pub fn remove_object(&mut self, object_index: usize) -> Option<Object> {
+        if object_index >= self.objects.len() {
+            return None;
+        }
+        let object = self.objects.remove(object_index);
+        if let Some(entity) = object.entity {
+            self.world.despawn(entity);
+        }
+        Some(object)
+    }
+
+    /// Entity of the object at `object_index`
+    pub fn entity_of(&self, object_index: usize) -> Option<Entity> {
+        self.objects.get(object_index).and_then(|object| object.entity)
+    }
+
+    /// Index of the object linked to `entity`
+    pub fn object_index_of(&self, entity: Entity) -> Option<usize> {
+        self.objects
+            .iter()
+            .position(|object| object.entity == Some(entity))
+    }
+
+    /// Object linked to `entity`
+    pub fn object_for_entity(&self, entity: Entity) -> Option<&Object> {
+        self.objects
+            .iter()
+            .find(|object| object.entity == Some(entity))
+    }
+
+    /// Mutable object linked to `entity`
+    pub fn object_for_entity_mut(&mut self, entity: Entity) -> Option<&mut Object> {
+        self.objects
+            .iter_mut()
+            .find(|object| object.entity == Some(entity))
+    }
+
+    /// Spawn an entity for an object that doesn't have one yet
+    fn attach_entity(&mut self, object_index: usize) {
+        let object = &mut self.objects[object_index];
+        if object.entity.is_some_and(|entity| self.world.is_alive(entity)) {
+            return;
+        }
+        let entity = self.world.spawn();
+        object.entity = Some(entity);
+        self.world.set_untracked(entity, Name(object.name.clone()));
+        self.world
+            .set_untracked(entity, Transform::from(&object.ui_transform));
+        if let Some(material_id) = &object.material_id {
+            self.world.set_untracked(entity, MaterialRef(material_id.clone()));
+        }
+    }
+
+    /// Applies component changes made through [`world`](Self::world) to the
+    /// linked objects, then mirrors the objects' current state back into the
+    /// world.
+    ///
+    /// Called every frame before transforms are uploaded.
+    pub fn sync_world(&mut self) {
+        let changed_transforms = self.world.take_changed::<Transform>();
+        let changed_materials = self.world.take_changed::<MaterialRef>();
+        if !changed_transforms.is_empty() || !changed_materials.is_empty() {
+            let indices: HashMap<Entity, usize> = self
+                .objects
+                .iter()
+                .enumerate()
+                .filter_map(|(i, object)| object.entity.map(|entity| (entity, i)))
+                .collect();
+
+            for entity in changed_transforms {
+                if let (Some(&i), Some(transform)) =
+                    (indices.get(&entity), self.world.get::<Transform>(entity))
+                {
+                    self.objects[i].ui_transform = (*transform).into();
+                }
+            }
+            for entity in changed_materials {
+                if let (Some(&i), Some(material)) =
+                    (indices.get(&entity), self.world.get::<MaterialRef>(entity))
+                {
+                    self.objects[i].set_material(&material.0);
+                }
+            }
+        }
+
+        for object_index in 0..self.objects.len() {
+            self.attach_entity(object_index);
+            let object = &self.objects[object_index];
+            let Some(entity) = object.entity else {
+                continue;
+            };
+            self.world
+                .set_untracked(entity, Transform::from(&object.ui_transform));
+            if self.world.get::<Name>(entity).map(|name| &name.0) != Some(&object.name) {
+                self.world.set_untracked(entity, Name(object.name.clone()));
+            }
+            match &object.material_id {
+                Some(material_id)
+                    if self.world.get::<MaterialRef>(entity).map(|m| &m.0) != Some(material_id) =>
+                {
+                    self.world.set_untracked(entity, MaterialRef(material_id.clone()));
+                }
+                None if self.world.has::<MaterialRef>(entity) => {
+                    self.world.remove::<MaterialRef>(entity);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Registers a prefab under `name`, replacing any previous definition
+    pub fn define_prefab(&mut self, name: &str, prefab: Prefab) {
+        self.prefabs.insert(name.to_string(), prefab);
+    }
+
+    /// Prefab registered under `name`
+    pub fn prefab(&self, name: &str) -> Option<&Prefab> {
+        self.prefabs.get(name)
+    }
+
+    /// Instantiates a registered prefab with its root at `position`
+    ///
+    /// Every node with geometry becomes an object whose entity carries a
+    /// [`PrefabInstance`] component.
+    ///
+    /// # Returns
+    ///
+    /// Indices of the created objects, parents before children
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::UnknownPrefab`] if no prefab has that name, or the
+    /// model loading error if one of its files can't be loaded. No objects are
+    /// added in either case.
+    pub fn spawn_prefab(&mut self, name: &str, position: [f32; 3]) -> Result<Vec<usize>> {
+        let prefab = self
+            .prefabs
+            .get(name)
+            .cloned()
+            .ok_or_else(|| HaggisError::UnknownPrefab(name.to_string()))?;
+
+        let mut created = Vec::new();
+        for (node, position, scale, rotation_y) in prefab.placements(position.into(), 1.0, 0.0) {
+            let object_index = match &node.mesh {
+                Some(PrefabMesh::File(path)) => match Self::load_model(path, &self.coordinate_system) {
+                    Ok(model) => self.insert_model(model),
+                    Err(error) => {
+                        for &index in created.iter().rev() {
+                            self.remove_object(index);
+                        }
+                        return Err(error);
+                    }
+                },
+                Some(PrefabMesh::Geometry(geometry)) => {
+                    self.add_procedural_object(geometry.clone(), name);
+                    self.objects.len() - 1
+                }
+                None => continue,
+            };
+
+            let object_name = self.ensure_unique_name(if node.name.is_empty() {
+                name
+            } else {
+                &node.name
+            });
+            let object = &mut self.objects[object_index];
+            object.set_name(object_name);
+            if let Some(material) = &node.material {
+                object.set_material(material);
+            }
+            object.set_transform_trs(position, Deg(rotation_y), scale);
+            object.sync_transform_to_ui();
+
+            if let Some(entity) = object.entity {
+                self.world.insert(
+                    entity,
+                    PrefabInstance {
+                        prefab: name.to_string(),
+                    },
+                );
+            }
+            created.push(object_index);
+        }
+        Ok(created)
     }
 
     /// Creates a new material and adds it to the material manager
@@ -235,6 +541,43 @@ impl Scene {
         self.add_material(name, [r, g, b, 1.0], metallic, roughness)
     }
 
+    /// Convenience method for creating see-through materials with RGBA colors
+    ///
+    /// Materials with `a` below 1.0 are drawn back to front after all opaque
+    /// objects, without writing depth.
+    ///
+    /// # Arguments
+    /// * `name` - Unique name for the material
+    /// * `r`, `g`, `b`, `a` - RGBA color components (0.0-1.0)
+    /// * `metallic` - Metallic factor (0.0-1.0)
+    /// * `roughness` - Roughness factor (0.0-1.0)
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_material_rgba(
+        &mut self,
+        name: &str,
+        r: f32,
+        g: f32,
+        b: f32,
+        a: f32,
+        metallic: f32,
+        roughness: f32,
+    ) -> &mut Material {
+        self.add_material(name, [r, g, b, a], metallic, roughness)
+    }
+
+    /// Drops GPU resources of all objects and materials
+    ///
+    /// Used when the GPU device is lost; [`init_gpu_resources`](Self::init_gpu_resources)
+    /// recreates them on the new device.
+    pub fn release_gpu_resources(&mut self) {
+        for object in self.objects.iter_mut() {
+            object.release_gpu_resources();
+        }
+        self.material_manager.release_gpu_resources();
+        self.transform_buffer = None;
+        self.gpu_capabilities = None;
+    }
+
     /// Initializes GPU resources for all objects and materials
     ///
     /// Must be called after the GPU context is available and before rendering.
@@ -242,7 +585,15 @@ impl Scene {
         // Initialize object GPU resources
         for object in self.objects.iter_mut() {
             object.init_gpu_resources(device);
+            object.upload_mesh_changes(queue);
+        }
+
+        // Make room for every object's transform
+        match &mut self.transform_buffer {
+            Some(transforms) => transforms.ensure_capacity(device, self.objects.len()),
+            None => self.transform_buffer = Some(TransformBuffer::new(device, self.objects.len())),
         }
+        self.upload_transforms(queue);
 
         // Initialize material GPU resources
         self.material_manager
@@ -251,13 +602,36 @@ impl Scene {
 
     /// Updates all object transforms and syncs to GPU
     pub fn update_all_transforms(&mut self, queue: &wgpu::Queue) {
-        for object in &mut self.objects {
-            if object.gpu_resources.is_some() {
-                object.update_transform(queue);
+        self.upload_transforms(queue);
+    }
+
+    /// Writes changed object transforms to the transform buffer
+    ///
+    /// Returns the number of objects whose transform was written; objects
+    /// added since the last [`init_gpu_resources`](Self::init_gpu_resources)
+    /// are picked up by its next call.
+    pub fn upload_transforms(&mut self, queue: &wgpu::Queue) -> usize {
+        match &mut self.transform_buffer {
+            Some(transforms) => {
+                transforms.upload(
+                    queue,
+                    self.objects.iter().map(|object| {
+                        ObjectTransform::new(object.transform.into(), object.opacity())
+                    }),
+                )
             }
+            None => 0,
         }
     }
 
+    /// Model matrices and opacities of all objects, bound at group 1 when drawing them
+    ///
+    /// Object `i` of [`objects`](Self::objects) is in slot `i`. `None` until
+    /// GPU resources are initialized.
+    pub fn transform_buffer(&self) -> Option<&TransformBuffer> {
+        self.transform_buffer.as_ref()
+    }
+
     /// Updates material GPU resources when materials have changed
     ///
     /// Call this after modifying material properties to sync changes to GPU.
@@ -307,6 +681,19 @@ impl Scene {
         self.objects.get_mut(index)
     }
 
+    /// Whether `object` is visible and on a shown layer
+    pub fn is_object_visible(&self, object: &Object) -> bool {
+        object.visible && self.layers.is_visible(object.layers)
+    }
+
+    /// Layers in use by objects, plus the built-in layers
+    pub fn used_layers(&self) -> Layers {
+        self.objects.iter().fold(
+            Layers::DEFAULT | Layers::REFERENCE | Layers::BOUNDARY | Layers::VISUALIZATION,
+            |layers, object| layers | object.layers,
+        )
+    }
+
     /// Gets immutable reference to an object by index
     pub fn get_object(&self, index: usize) -> Option<&Object> {
         self.objects.get(index)
@@ -317,12 +704,13 @@ impl Scene {
     /// Should be called each frame after UI updates to sync transform
     /// changes from the UI to the actual object transforms and GPU.
     pub fn apply_ui_transforms_and_update_gpu(&mut self, queue: &wgpu::Queue) {
+        self.sync_world();
         for object in &mut self.objects {
             if object.visible {
                 object.apply_ui_transform();
-                object.update_transform(queue);
             }
         }
+        self.upload_transforms(queue);
     }
 
     /// Assigns a material to an object by index
@@ -389,7 +777,7 @@ impl Scene {
         size: f32,
         material_name: &str,
     ) -> usize {
-        println!(
+        tracing::debug!(
             "Creating plane object: {} ({} at {})",
             name, orientation, position
         );
@@ -412,10 +800,9 @@ impl Scene {
         object.visible = true;
 
         // Add to scene
-        self.objects.push(object);
-        let object_index = self.objects.len() - 1;
+        let object_index = self.push_object(object);
 
-        println!("Plane object created at index {}", object_index);
+        tracing::debug!("Plane object created at index {}", object_index);
         object_index
     }
 }
@@ -0,0 +1,251 @@
+//! # Transform Buffer
+//!
+//! Model matrices and opacities of all scene objects in one read-only
+//! storage buffer.
+//! The buffer is bound once per pass at bind group 1 and the vertex shader
+//! picks its matrix with `@builtin(instance_index)`: an object is drawn as
+//! the single instance `slot..slot + 1`, where the slot is its index in
+//! [`Scene::objects`](super::Scene::objects). This replaces a uniform buffer
+//! and bind group per object, so scenes with thousands of objects no longer
+//! switch bind groups between draws.
+//!
+//! Only slots that changed since the last upload are written, merged into
+//! contiguous ranges, so static scenes cost no queue writes.
+
+use std::ops::Range;
+
+use wgpu::{BindGroup, BindGroupLayout, Buffer, Device, Queue};
+
+/// Column-major model matrix as stored on the GPU
+pub type TransformMatrix = [[f32; 4]; 4];
+
+/// Per-object data as stored on the GPU, matching `ObjectTransform` in the
+/// scene shaders
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ObjectTransform {
+    pub model: TransformMatrix,
+    /// Multiplies the alpha of the object's material, 1 is opaque
+    pub opacity: f32,
+    _padding: [f32; 3],
+}
+
+impl ObjectTransform {
+    pub fn new(model: TransformMatrix, opacity: f32) -> Self {
+        Self {
+            model,
+            opacity,
+            _padding: [0.0; 3],
+        }
+    }
+}
+
+impl From<TransformMatrix> for ObjectTransform {
+    fn from(model: TransformMatrix) -> Self {
+        Self::new(model, 1.0)
+    }
+}
+
+/// Smallest number of slots allocated
+const MIN_CAPACITY: usize = 64;
+
+/// Storage buffer holding one [`ObjectTransform`] per scene object
+pub struct TransformBuffer {
+    buffer: Buffer,
+    bind_group: BindGroup,
+    capacity: usize,
+    /// Slots as last written to the GPU, one per slot in use
+    uploaded: Vec<ObjectTransform>,
+}
+
+impl TransformBuffer {
+    /// Layout of bind group 1 in the scene pipelines
+    pub fn bind_group_layout(device: &Device) -> BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Transform Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        })
+    }
+
+    /// Create a buffer with room for at least `capacity` objects
+    pub fn new(device: &Device, capacity: usize) -> Self {
+        let capacity = capacity.max(MIN_CAPACITY).next_power_of_two();
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Transform Storage Buffer"),
+            size: (capacity * std::mem::size_of::<ObjectTransform>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Transform Bind Group"),
+            layout: &Self::bind_group_layout(device),
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+
+        Self {
+            buffer,
+            bind_group,
+            capacity,
+            uploaded: Vec::new(),
+        }
+    }
+
+    /// Grow the buffer so it holds `len` objects
+    ///
+    /// Growing replaces the buffer and bind group, and the next
+    /// [`upload`](Self::upload) rewrites every slot.
+    pub fn ensure_capacity(&mut self, device: &Device, len: usize) {
+        if len > self.capacity {
+            *self = Self::new(device, len);
+        }
+    }
+
+    /// Write the slots that differ from the last upload
+    ///
+    /// Slots past the capacity are ignored; call
+    /// [`ensure_capacity`](Self::ensure_capacity) first. Returns the number of
+    /// slots written.
+    pub fn upload(
+        &mut self,
+        queue: &Queue,
+        transforms: impl IntoIterator<Item = impl Into<ObjectTransform>>,
+    ) -> usize {
+        let transforms: Vec<ObjectTransform> = transforms
+            .into_iter()
+            .take(self.capacity)
+            .map(Into::into)
+            .collect();
+
+        let mut written = 0;
+        for range in changed_ranges(&self.uploaded, &transforms) {
+            let offset = (range.start * std::mem::size_of::<ObjectTransform>()) as u64;
+            queue.write_buffer(
+                &self.buffer,
+                offset,
+                bytemuck::cast_slice(&transforms[range.clone()]),
+            );
+            written += range.len();
+        }
+        self.uploaded = transforms;
+        written
+    }
+
+    /// Bind group exposing the buffer at binding 0
+    pub fn bind_group(&self) -> &BindGroup {
+        &self.bind_group
+    }
+
+    /// The underlying storage buffer
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    /// Number of slots the buffer holds
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of slots filled by the last upload
+    pub fn len(&self) -> usize {
+        self.uploaded.len()
+    }
+
+    /// Whether no slots have been uploaded yet
+    pub fn is_empty(&self) -> bool {
+        self.uploaded.is_empty()
+    }
+}
+
+/// Contiguous slot ranges where `new` differs from `old`, including slots
+/// `old` does not have yet
+fn changed_ranges<T: PartialEq>(old: &[T], new: &[T]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for (slot, value) in new.iter().enumerate() {
+        if old.get(slot) == Some(value) {
+            continue;
+        }
+        match ranges.last_mut() {
+            Some(range) if range.end == slot => range.end += 1,
+            _ => ranges.push(slot..slot + 1),
+        }
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wgpu_utils::compute_primitives::test_device;
+
+    fn translation(x: f32) -> TransformMatrix {
+        let mut matrix = [[0.0; 4]; 4];
+        for (i, column) in matrix.iter_mut().enumerate() {
+            column[i] = 1.0;
+        }
+        matrix[3][0] = x;
+        matrix
+    }
+
+    #[test]
+    fn test_object_transform_layout() {
+        // mat4x4 plus opacity, padded to the struct's 16 byte alignment
+        assert_eq!(std::mem::size_of::<ObjectTransform>(), 80);
+        assert_eq!(ObjectTransform::from(translation(1.0)).opacity, 1.0);
+    }
+
+    #[test]
+    fn test_changed_ranges_merge_neighbors() {
+        let old: Vec<_> = (0..6).map(|i| translation(i as f32)).collect();
+        let mut new = old.clone();
+        assert!(changed_ranges(&old, &new).is_empty());
+
+        new[1] = translation(10.0);
+        new[2] = translation(20.0);
+        new[4] = translation(40.0);
+        new.push(translation(6.0));
+        assert_eq!(changed_ranges(&old, &new), vec![1..3, 4..5, 6..7]);
+
+        // Everything is new after the buffer was recreated
+        assert_eq!(changed_ranges(&[], &new), vec![0..7]);
+    }
+
+    #[test]
+    fn test_upload_writes_only_changed_slots() {
+        // Needs a GPU adapter, skipped without one
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let mut transforms = TransformBuffer::new(&device, 3);
+        assert_eq!(transforms.capacity(), MIN_CAPACITY);
+
+        let matrices: Vec<_> = (0..3).map(|i| translation(i as f32)).collect();
+        assert_eq!(transforms.upload(&queue, matrices.clone()), 3);
+        assert_eq!(transforms.upload(&queue, matrices.clone()), 0);
+
+        let mut moved = matrices.clone();
+        moved[2] = translation(5.0);
+        assert_eq!(transforms.upload(&queue, moved), 1);
+
+        // Fading an object rewrites its slot even if the matrix is unchanged
+        let faded = (0..3).map(|i| ObjectTransform::new(translation(i as f32), 0.5));
+        assert_eq!(transforms.upload(&queue, faded), 3);
+
+        transforms.ensure_capacity(&device, 100);
+        assert_eq!(transforms.capacity(), 128);
+        assert!(transforms.is_empty());
+        assert_eq!(transforms.upload(&queue, matrices.clone()), 3);
+        assert_eq!(transforms.len(), 3);
+    }
+}
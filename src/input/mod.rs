@@ -0,0 +1,407 @@
+//! # Input Mapping System
+//!
+//! This module provides keyboard shortcuts and per-frame input state for the
+//! Haggis engine. Keys (optionally with modifiers) are bound to [`Action`]s,
+//! either engine built-ins like pausing the simulation or custom named actions
+//! that user code reacts to.
+//!
+//! ## Features
+//!
+//! - **Action Mapping**: Bind key chords to built-in or custom actions
+//! - **Input State**: Query held, pressed and released keys every frame
+//! - **UI Awareness**: Key presses are ignored while ImGui has keyboard focus
+//! - **Conflict Handling**: Rebinding a chord replaces and reports the old action
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::input::{Action, Key};
+//!
+//! let mut app = haggis::default();
+//! app.bind_key(Key::Space, Action::ToggleSimulation);
+//! app.bind_key(Key::KeyR, Action::custom("spawn_particles"));
+//! ```
+//!
+//! Simulations read the current state from the scene in `update`:
+//!
+//! ```no_run
+//! # use haggis::gfx::scene::Scene;
+//! # use haggis::input::{Action, Key};
+//! # fn update(scene: &mut Scene) {
+//! if scene.input.action_triggered(&Action::custom("spawn_particles")) {
+//!     // ...
+//! }
+//! if scene.input.is_key_down(Key::ArrowUp) {
+//!     // ...
+//! }
+//! # }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use winit::event::{ElementState, KeyEvent};
+use winit::keyboard::{ModifiersState, PhysicalKey};
+
+/// Physical key codes (re-exported from winit)
+pub use winit::keyboard::KeyCode as Key;
+
+/// Modifier keys that must be held for a binding to trigger
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+}
+
+impl Modifiers {
+    /// No modifiers
+    pub const NONE: Self = Self {
+        shift: false,
+        ctrl: false,
+        alt: false,
+    };
+    /// Shift only
+    pub const SHIFT: Self = Self {
+        shift: true,
+        ctrl: false,
+        alt: false,
+    };
+    /// Ctrl only
+    pub const CTRL: Self = Self {
+        shift: false,
+        ctrl: true,
+        alt: false,
+    };
+    /// Alt only
+    pub const ALT: Self = Self {
+        shift: false,
+        ctrl: false,
+        alt: true,
+    };
+
+    fn from_winit(state: ModifiersState) -> Self {
+        Self {
+            shift: state.shift_key(),
+            ctrl: state.control_key(),
+            alt: state.alt_key(),
+        }
+    }
+}
+
+/// A key together with the modifiers that must be held
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub key: Key,
+    pub modifiers: Modifiers,
+}
+
+impl KeyChord {
+    pub fn new(key: Key, modifiers: Modifiers) -> Self {
+        Self { key, modifiers }
+    }
+}
+
+impl From<Key> for KeyChord {
+    fn from(key: Key) -> Self {
+        Self::new(key, Modifiers::NONE)
+    }
+}
+
+/// Something a key binding can trigger
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Action {
+    /// Pause or resume the attached simulation
+    ToggleSimulation,
+    /// Advance the paused simulation by one step
+    StepSimulation,
+    /// Advance the paused simulation by the toolbar's "Step ×N" count
+    StepSimulationBatch,
+    /// Reset the attached simulation
+    ResetSimulation,
+    /// Reset the orbit camera to its default view
+    ResetCamera,
+    /// Show or hide the performance metrics panel
+    TogglePerformancePanel,
+    /// Show or hide the default transform panel
+    ToggleTransformPanel,
+    /// Open or close the command console
+    ToggleConsole,
+    /// Show or hide the log console
+    ToggleLogConsole,
+    /// Show or hide the world grid and axes
+    ToggleGrid,
+    /// Show or hide the render settings panel (clear color, background, fog)
+    ToggleRenderSettings,
+    /// Show or hide the UI theme editor
+    ToggleThemeEditor,
+    /// Show or hide the GPU buffer inspector
+    ToggleBufferInspector,
+    /// Switch mouse drags between camera and field painting
+    TogglePaintMode,
+    /// Switch left clicks between object selection and placing measurement points
+    ToggleMeasureMode,
+    /// Show or hide the scene annotations panel
+    ToggleAnnotations,
+    /// Make fonts and UI metrics larger
+    IncreaseUiScale,
+    /// Make fonts and UI metrics smaller
+    DecreaseUiScale,
+    /// Close the application
+    Quit,
+    /// User-defined action, queried with [`InputState::action_triggered`]
+    Custom(String),
+}
+
+impl Action {
+    /// Create a custom named action
+    pub fn custom(name: &str) -> Self {
+        Action::Custom(name.to_string())
+    }
+}
+
+/// Key chord to action bindings
+#[derive(Debug, Clone)]
+pub struct InputMap {
+    bindings: HashMap<KeyChord, Action>,
+}
+
+impl InputMap {
+    /// Create an empty input map
+    pub fn new() -> Self {
+        Self {
+            bindings: HashMap::new(),
+        }
+    }
+
+    /// Create the engine's default bindings (Escape quits, backquote opens
+    /// the console, period steps the paused simulation once and
+    /// Shift+period by the toolbar's step count)
+    pub fn with_defaults() -> Self {
+        let mut map = Self::new();
+        map.bind(Key::Escape, Action::Quit);
+        map.bind(Key::Backquote, Action::ToggleConsole);
+        map.bind(Key::Period, Action::StepSimulation);
+        map.bind(KeyChord::new(Key::Period, Modifiers::SHIFT), Action::StepSimulationBatch);
+        map
+    }
+
+    /// Bind a key chord to an action.
+    ///
+    /// # Arguments
+    ///
+    /// * `chord` - Key or key chord to bind
+    /// * `action` - Action to trigger
+    ///
+    /// # Returns
+    ///
+    /// The action previously bound to this chord, if it was replaced
+    pub fn bind(&mut self, chord: impl Into<KeyChord>, action: Action) -> Option<Action> {
+        let chord = chord.into();
+        let previous = self.bindings.insert(chord, action);
+
+        if let Some(previous) = &previous {
+            tracing::warn!(
+                "Key binding {:?} replaced previous action {:?}",
+                chord, previous
+            );
+        }
+
+        previous
+    }
+
+    /// Remove the binding for a key chord, returning its action
+    pub fn unbind(&mut self, chord: impl Into<KeyChord>) -> Option<Action> {
+        self.bindings.remove(&chord.into())
+    }
+
+    /// Remove every binding that triggers the given action
+    pub fn unbind_action(&mut self, action: &Action) {
+        self.bindings.retain(|_, bound| bound != action);
+    }
+
+    /// Action bound to a key chord
+    pub fn action_for(&self, chord: &KeyChord) -> Option<&Action> {
+        self.bindings.get(chord)
+    }
+
+    /// All chords that trigger the given action
+    pub fn chords_for(&self, action: &Action) -> Vec<KeyChord> {
+        self.bindings
+            .iter()
+            .filter(|(_, bound)| *bound == action)
+            .map(|(chord, _)| *chord)
+            .collect()
+    }
+
+    /// Iterate over all bindings
+    pub fn bindings(&self) -> impl Iterator<Item = (&KeyChord, &Action)> {
+        self.bindings.iter()
+    }
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        Self::with_defaults()
+    }
+}
+
+/// Per-frame keyboard and mouse state
+///
+/// Pressed/released sets and triggered actions are cleared at the end of
+/// every frame, so they hold exactly the events since the previous frame.
+#[derive(Debug, Clone, Default)]
+pub struct InputState {
+    keys_down: HashSet<Key>,
+    keys_pressed: HashSet<Key>,
+    keys_released: HashSet<Key>,
+    modifiers: Modifiers,
+    triggered_actions: Vec<Action>,
+    /// Mouse position in physical window pixels
+    pub mouse_position: (f32, f32),
+    /// Whether ImGui wanted keyboard input when keys were last processed
+    pub ui_captured_keyboard: bool,
+}
+
+impl InputState {
+    /// Create an empty input state
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether a key is currently held
+    pub fn is_key_down(&self, key: Key) -> bool {
+        self.keys_down.contains(&key)
+    }
+
+    /// Whether a key went down since the last frame
+    pub fn was_key_pressed(&self, key: Key) -> bool {
+        self.keys_pressed.contains(&key)
+    }
+
+    /// Whether a key was released since the last frame
+    pub fn was_key_released(&self, key: Key) -> bool {
+        self.keys_released.contains(&key)
+    }
+
+    /// Currently held modifier keys
+    pub fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    /// Whether an action was triggered since the last frame
+    pub fn action_triggered(&self, action: &Action) -> bool {
+        self.triggered_actions.contains(action)
+    }
+
+    /// All actions triggered since the last frame, in order
+    pub fn triggered_actions(&self) -> &[Action] {
+        &self.triggered_actions
+    }
+
+    /// Update modifier state from winit
+    pub fn set_modifiers(&mut self, state: ModifiersState) {
+        self.modifiers = Modifiers::from_winit(state);
+    }
+
+    /// Process a keyboard event and resolve bindings.
+    ///
+    /// Releases are always recorded so keys never get stuck, but presses are
+    /// ignored while the UI has keyboard focus (e.g. typing in a text field).
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - Winit keyboard event
+    /// * `input_map` - Bindings used to resolve actions
+    /// * `ui_wants_keyboard` - Whether ImGui currently captures the keyboard
+    ///
+    /// # Returns
+    ///
+    /// The action triggered by this event, if any
+    pub fn process_key_event(
+        &mut self,
+        event: &KeyEvent,
+        input_map: &InputMap,
+        ui_wants_keyboard: bool,
+    ) -> Option<Action> {
+        let PhysicalKey::Code(key) = event.physical_key else {
+            return None;
+        };
+        self.ui_captured_keyboard = ui_wants_keyboard;
+
+        match event.state {
+            ElementState::Released => {
+                self.keys_down.remove(&key);
+                self.keys_released.insert(key);
+                None
+            }
+            ElementState::Pressed => {
+                if ui_wants_keyboard {
+                    return None;
+                }
+
+                self.keys_down.insert(key);
+                if event.repeat {
+                    return None;
+                }
+                self.keys_pressed.insert(key);
+
+                let action = input_map
+                    .action_for(&KeyChord::new(key, self.modifiers))
+                    .cloned()?;
+                self.triggered_actions.push(action.clone());
+                Some(action)
+            }
+        }
+    }
+
+    /// Clear per-frame state; call once at the end of every frame
+    pub fn end_frame(&mut self) {
+        self.keys_pressed.clear();
+        self.keys_released.clear();
+        self.triggered_actions.clear();
+    }
+
+    /// Forget all held keys, e.g. when the window loses focus
+    pub fn clear(&mut self) {
+        self.keys_down.clear();
+        self.end_frame();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rebinding_reports_previous_action() {
+        let mut map = InputMap::new();
+        assert!(map.bind(Key::Space, Action::ToggleSimulation).is_none());
+        let previous = map.bind(Key::Space, Action::ResetCamera);
+        assert_eq!(previous, Some(Action::ToggleSimulation));
+        assert_eq!(map.action_for(&Key::Space.into()), Some(&Action::ResetCamera));
+    }
+
+    #[test]
+    fn test_modifiers_distinguish_chords() {
+        let mut map = InputMap::new();
+        map.bind(Key::KeyS, Action::custom("save"));
+        map.bind(KeyChord::new(Key::KeyS, Modifiers::CTRL), Action::custom("save_as"));
+        assert_eq!(map.chords_for(&Action::custom("save")).len(), 1);
+        assert_eq!(
+            map.action_for(&KeyChord::new(Key::KeyS, Modifiers::CTRL)),
+            Some(&Action::custom("save_as"))
+        );
+    }
+
+    #[test]
+    fn test_end_frame_keeps_held_keys() {
+        let mut state = InputState::new();
+        state.keys_down.insert(Key::KeyW);
+        state.keys_pressed.insert(Key::KeyW);
+        state.triggered_actions.push(Action::Quit);
+        state.end_frame();
+        assert!(state.is_key_down(Key::KeyW));
+        assert!(!state.was_key_pressed(Key::KeyW));
+        assert!(state.triggered_actions().is_empty());
+    }
+}
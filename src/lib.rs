@@ -40,29 +40,69 @@
 //!
 //! The engine is organized into several key modules:
 //!
-//! - [`app`] - Main application lifecycle and event handling
+//! - [`app`] - Main application lifecycle, event handling and job system
+//! - [`audio`] - Positional sound logic for an application-provided audio backend (`audio` feature)
+//! - [`config`] - Declarative project files loaded by [`from_config`]
+//! - [`console`] - Command registry and dropdown console
+//! - [`ecs`] - Entity component storage for scene objects and simulations
+//! - [`error`] - Error type for fallible APIs and GPU failures
+//! - [`events`] - Typed event bus between app, simulations and UI
 //! - [`gfx`] - Graphics rendering, camera system, and scene management
+//! - [`input`] - Keyboard shortcuts and per-frame input state
+//! - [`logging`] - Tracing subscriber and in-app log console
+//! - [`platform`] - Native/web differences (timing, async startup, canvas)
 //! - [`prelude`] - Common imports and types for convenient usage
+//! - [`remote`] - HTTP dashboard and API for remote monitoring (`remote` feature)
+//! - [`scripting`] - Script-driven simulations through an application-provided interpreter (`scripting` feature)
 //! - [`simulation`] - CPU and GPU simulation framework
+//! - [`telemetry`] - Metrics export to OSC and Prometheus (`telemetry` feature)
+//! - [`testing`] - Golden-image and deterministic simulation tests
 //! - [`ui`] - User interface system using Dear ImGui
 //! - [`visualization`] - Modular visualization system for 3D data
 //! - [`wgpu_utils`] - Utility functions for wgpu resource management
+//! - [`xr`] - Stereo rendering for an application-provided headset runtime (`xr` feature)
+
+// Lets derive macros refer to `::haggis` paths from inside this crate
+extern crate self as haggis;
 
 pub mod app;
+#[cfg(feature = "audio")]
+pub mod audio;
+pub mod config;
+pub mod console;
+pub mod ecs;
+pub mod error;
+pub mod events;
 pub mod gfx;
+pub mod input;
+pub mod logging;
 pub mod performance;
+pub mod platform;
 pub mod prelude;
+#[cfg(feature = "remote")]
+pub mod remote;
+#[cfg(feature = "scripting")]
+pub mod scripting;
 pub mod simulation;
+#[cfg(feature = "telemetry")]
+pub mod telemetry;
+pub mod testing;
 pub mod ui;
+mod util;
 pub mod visualization;
 pub mod wgpu_utils;
+#[cfg(feature = "xr")]
+pub mod xr;
 
 // Re-export main types for convenience
-pub use app::HaggisApp;
+pub use app::{builder::HaggisAppBuilder, HaggisApp};
+pub use error::{HaggisError, Result};
 pub use ui::{UiFont, UiStyle};
 
 // Re-export visualization types for external use
-pub use visualization::{CutPlane2D, VisualizationComponent, VisualizationManager};
+pub use visualization::{
+    AgentView, CutPlane2D, DomainBox, VisualizationComponent, VisualizationManager, VolumeView,
+};
 
 /// Creates a default Haggis application instance.
 ///
@@ -83,3 +123,35 @@ pub use visualization::{CutPlane2D, VisualizationComponent, VisualizationManager
 pub fn default() -> HaggisApp {
     pollster::block_on(HaggisApp::new())
 }
+
+/// Creates an application set up by a project configuration file.
+///
+/// The file describes the window, camera, UI options, materials, objects and
+/// a simulation template with its parameters; see [`config`] for the format.
+///
+/// # Errors
+///
+/// Returns [`HaggisError::Io`] if the file cannot be read and
+/// [`HaggisError::Config`] if it is malformed or its simulation parameters
+/// are invalid.
+///
+/// # Examples
+///
+/// ```no_run
+/// fn main() -> haggis::Result<()> {
+///     haggis::from_config("haggis.toml")?.run();
+///     Ok(())
+/// }
+/// ```
+pub fn from_config(path: impl AsRef<std::path::Path>) -> Result<HaggisApp> {
+    let path = path.as_ref();
+    let config = config::ProjectConfig::load(path)?;
+    let mut app = default();
+    config
+        .apply(&mut app)
+        .map_err(|message| HaggisError::Config {
+            path: path.into(),
+            message,
+        })?;
+    Ok(app)
+}
@@ -0,0 +1,208 @@
+//! Crash reports
+//!
+//! A wgpu validation error panics with a backtrace that says where the
+//! failing call was made, but not what the GPU was doing at the time. The
+//! panic hook set by [`install`] writes a report file next to the usual
+//! panic output with:
+//!
+//! - the panic message, location and backtrace,
+//! - the adapter, backend and driver the app was running on,
+//! - the labels of the render and compute passes recorded in the frame that
+//!   panicked and the one before it,
+//! - the most recent log records.
+//!
+//! Reports are off by default; enable them with
+//! [`HaggisApp::enable_crash_reports`](crate::HaggisApp::enable_crash_reports)
+//! or [`install`].
+//! Passes encoded by the engine and the simulation templates are recorded
+//! already; custom GPU code can add its own with [`record_pass`].
+
+use std::fmt::Write as _;
+use std::panic::PanicHookInfo;
+use std::path::PathBuf;
+use std::sync::{Mutex, Once};
+
+/// Pass labels kept per frame; later passes in a runaway frame are dropped
+const MAX_PASSES: usize = 256;
+
+/// Log records included in a report
+const LOG_LINES: usize = 200;
+
+struct FramePasses {
+    current: Vec<String>,
+    last: Vec<String>,
+}
+
+static PASSES: Mutex<FramePasses> = Mutex::new(FramePasses {
+    current: Vec::new(),
+    last: Vec::new(),
+});
+static GPU_INFO: Mutex<Option<String>> = Mutex::new(None);
+static REPORT_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// Record that a render or compute pass labelled `label` is being encoded
+pub fn record_pass(label: &str) {
+    let mut passes = PASSES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if passes.current.len() < MAX_PASSES {
+        passes.current.push(label.to_string());
+    }
+}
+
+/// Start a new frame, keeping the passes of the one that just finished
+pub(crate) fn end_frame() {
+    let mut passes = PASSES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let FramePasses { current, last } = &mut *passes;
+    std::mem::swap(current, last);
+    current.clear();
+}
+
+/// Describe the GPU in crash reports, usually the
+/// [`GpuCapabilities`](crate::gfx::GpuCapabilities) summary
+pub(crate) fn set_gpu_info(info: String) {
+    *GPU_INFO
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(info);
+}
+
+/// Set the panic hook that writes crash reports into `dir`
+///
+/// The hook runs the previously installed one first, so the usual panic
+/// message is still printed. Calling this again only changes the directory.
+/// Reports are named `haggis-crash-<unix time>.txt`; on platforms without
+/// file access the report is printed to stderr instead.
+pub fn install(dir: impl Into<PathBuf>) {
+    set_report_dir(Some(dir.into()));
+    static INSTALLED: Once = Once::new();
+    INSTALLED.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            previous(info);
+            write_report(info);
+        }));
+    });
+}
+
+/// Change where crash reports are written, or stop writing them with `None`
+pub fn set_report_dir(dir: Option<PathBuf>) {
+    *REPORT_DIR
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = dir;
+}
+
+fn write_report(info: &PanicHookInfo) {
+    // A panic while one of these locks is held must not deadlock the hook
+    let Some(dir) = REPORT_DIR.try_lock().ok().and_then(|dir| dir.clone()) else {
+        return;
+    };
+    let message = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "Box<dyn Any>".to_string());
+    let location = info
+        .location()
+        .map(|location| location.to_string())
+        .unwrap_or_default();
+    let backtrace = std::backtrace::Backtrace::force_capture().to_string();
+    let gpu = GPU_INFO.try_lock().ok().and_then(|info| info.clone());
+    let (current, last) = PASSES
+        .try_lock()
+        .map(|passes| (passes.current.clone(), passes.last.clone()))
+        .unwrap_or_default();
+    let log = super::try_recent(LOG_LINES);
+
+    let report = format_report(
+        &message,
+        &location,
+        &backtrace,
+        gpu.as_deref(),
+        &current,
+        &last,
+        &log,
+    );
+
+    if !crate::platform::HAS_FILESYSTEM {
+        eprintln!("{report}");
+        return;
+    }
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    let path = dir.join(format!("haggis-crash-{seconds}.txt"));
+    match std::fs::write(&path, report) {
+        Ok(()) => eprintln!("Crash report written to {}", path.display()),
+        Err(error) => eprintln!("Could not write crash report {}: {error}", path.display()),
+    }
+}
+
+fn format_report(
+    message: &str,
+    location: &str,
+    backtrace: &str,
+    gpu: Option<&str>,
+    current: &[String],
+    last: &[String],
+    log: &[String],
+) -> String {
+    let mut out = String::from("Haggis crash report\n\n");
+    let _ = writeln!(out, "Panic: {message}");
+    if !location.is_empty() {
+        let _ = writeln!(out, "At: {location}");
+    }
+    let _ = writeln!(out, "Version: {}", env!("CARGO_PKG_VERSION"));
+
+    out.push_str("\n== GPU ==\n");
+    out.push_str(gpu.unwrap_or("No device was created"));
+    out.push('\n');
+
+    for (title, passes) in [("Passes this frame", current), ("Passes last frame", last)] {
+        let _ = writeln!(out, "\n== {title} ==");
+        if passes.is_empty() {
+            out.push_str("(none)\n");
+        }
+        for label in passes {
+            let _ = writeln!(out, "{label}");
+        }
+    }
+
+    out.push_str("\n== Recent log ==\n");
+    for line in log {
+        let _ = writeln!(out, "{line}");
+    }
+
+    out.push_str("\n== Backtrace ==\n");
+    out.push_str(backtrace);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_sections() {
+        let passes = |labels: &[&str]| labels.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        let report = format_report(
+            "Validation Error",
+            "src/gfx/rendering/render_engine.rs:830:13",
+            "0: haggis::main",
+            Some("Adapter: Test GPU (Vulkan)"),
+            &passes(&["Depth Prepass", "Main Render Pass"]),
+            &[],
+            &passes(&["[   1.000] WARN  wgpu: slow"]),
+        );
+        assert!(report
+            .contains("Panic: Validation Error\nAt: src/gfx/rendering/render_engine.rs:830:13\n"));
+        assert!(report.contains("== GPU ==\nAdapter: Test GPU (Vulkan)\n"));
+        assert!(report.contains("== Passes this frame ==\nDepth Prepass\nMain Render Pass\n"));
+        assert!(report.contains("== Passes last frame ==\n(none)\n"));
+        assert!(report.contains("== Recent log ==\n[   1.000] WARN  wgpu: slow\n"));
+        assert!(report.ends_with("== Backtrace ==\n0: haggis::main"));
+    }
+}
@@ -0,0 +1,333 @@
+//! # Logging
+//!
+//! Engine and simulation diagnostics go through the [`tracing`] macros
+//! (`tracing::info!`, `tracing::warn!`, ...). [`init`], called when a
+//! [`HaggisApp`](crate::HaggisApp) is created, installs a subscriber that keeps
+//! recent records in memory for the in-app [`LogWindow`] and echoes them to
+//! stdout. Records from the `log` crate (used by wgpu and naga) are captured
+//! too, with the GPU crates limited to warnings and errors.
+//!
+//! If the application installs its own tracing subscriber before creating the
+//! app, that subscriber is kept and the log window only shows `log` records.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! # use haggis::gfx::scene::Scene;
+//! # fn step(_scene: &mut Scene, residual: f32) {
+//! tracing::debug!(target: "solver", residual, "Pressure solve converged");
+//! if residual.is_nan() {
+//!     tracing::error!(target: "solver", "Pressure solve diverged");
+//! }
+//! # }
+//! ```
+
+pub mod crash;
+pub mod window;
+
+pub use window::LogWindow;
+
+use std::collections::VecDeque;
+use std::fmt::{self, Write as _};
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::subscriber::Interest;
+use tracing::{span, Event, Level, Metadata};
+
+/// Maximum number of records kept in memory
+const MAX_RECORDS: usize = 2000;
+
+/// `log` targets that are only captured at warning level and above
+const QUIET_TARGETS: [&str; 3] = ["wgpu", "naga", "imgui"];
+
+/// A captured log message
+#[derive(Debug, Clone)]
+pub struct LogRecord {
+    /// Sequence number, increasing across all records
+    pub id: u64,
+    /// Seconds since logging was initialized
+    pub time: f64,
+    pub level: Level,
+    /// Module path or explicit `target:` of the record
+    pub target: String,
+    /// Message followed by any extra `key=value` fields
+    pub message: String,
+}
+
+impl fmt::Display for LogRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{:8.3}] {:5} {}: {}",
+            self.time, self.level, self.target, self.message
+        )
+    }
+}
+
+struct LogState {
+    records: Mutex<VecDeque<LogRecord>>,
+    next_id: AtomicU64,
+    max_level: AtomicU8,
+    echo: bool,
+    start: crate::platform::Instant,
+}
+
+fn state() -> &'static LogState {
+    static STATE: OnceLock<LogState> = OnceLock::new();
+    STATE.get_or_init(|| LogState {
+        records: Mutex::new(VecDeque::new()),
+        next_id: AtomicU64::new(0),
+        max_level: AtomicU8::new(level_to_u8(default_level())),
+        echo: cfg!(not(target_arch = "wasm32")),
+        start: crate::platform::Instant::now(),
+    })
+}
+
+/// Debug builds capture debug messages, release builds stop at info
+fn default_level() -> Level {
+    if cfg!(debug_assertions) {
+        Level::DEBUG
+    } else {
+        Level::INFO
+    }
+}
+
+fn level_to_u8(level: Level) -> u8 {
+    match level {
+        Level::ERROR => 0,
+        Level::WARN => 1,
+        Level::INFO => 2,
+        Level::DEBUG => 3,
+        Level::TRACE => 4,
+    }
+}
+
+fn level_from_u8(value: u8) -> Level {
+    match value {
+        0 => Level::ERROR,
+        1 => Level::WARN,
+        2 => Level::INFO,
+        3 => Level::DEBUG,
+        _ => Level::TRACE,
+    }
+}
+
+/// Most verbose level that is currently captured
+pub fn level() -> Level {
+    level_from_u8(state().max_level.load(Ordering::Relaxed))
+}
+
+/// Change the most verbose level that is captured (e.g. `Level::TRACE`)
+pub fn set_level(level: Level) {
+    state()
+        .max_level
+        .store(level_to_u8(level), Ordering::Relaxed);
+    tracing::callsite::rebuild_interest_cache();
+}
+
+fn is_enabled(level: Level) -> bool {
+    level_to_u8(level) <= state().max_level.load(Ordering::Relaxed)
+}
+
+/// Store a record and echo it to stdout
+fn push(level: Level, target: &str, message: String) {
+    let state = state();
+    let record = LogRecord {
+        id: state.next_id.fetch_add(1, Ordering::Relaxed),
+        time: state.start.elapsed().as_secs_f64(),
+        level,
+        target: target.to_string(),
+        message,
+    };
+
+    if state.echo {
+        println!("{}", record);
+    }
+
+    let mut records = state.records.lock().unwrap();
+    records.push_back(record);
+    if records.len() > MAX_RECORDS {
+        records.pop_front();
+    }
+}
+
+/// Call `f` for every stored record with an id greater than or equal to `from`.
+///
+/// # Returns
+///
+/// The id to pass next time to only see newer records
+pub fn read_from(from: u64, mut f: impl FnMut(&LogRecord)) -> u64 {
+    let records = state().records.lock().unwrap();
+    let mut next = from;
+    for record in records.iter().filter(|record| record.id >= from) {
+        f(record);
+        next = record.id + 1;
+    }
+    next
+}
+
+/// The last `count` records as lines, or none if the store is locked
+///
+/// Used from the panic hook, which may run while the store is being written.
+pub(crate) fn try_recent(count: usize) -> Vec<String> {
+    let Ok(records) = state().records.try_lock() else {
+        return Vec::new();
+    };
+    let skip = records.len().saturating_sub(count);
+    records.iter().skip(skip).map(ToString::to_string).collect()
+}
+
+/// Install the haggis tracing subscriber and `log` bridge.
+///
+/// Safe to call more than once; only the first call installs anything.
+///
+/// # Returns
+///
+/// `false` if another tracing subscriber was already installed
+pub fn init() -> bool {
+    static INSTALLED: OnceLock<bool> = OnceLock::new();
+    *INSTALLED.get_or_init(|| {
+        if log::set_logger(&LOG_BRIDGE).is_ok() {
+            log::set_max_level(log::LevelFilter::Info);
+        }
+        tracing::subscriber::set_global_default(LogSubscriber {
+            next_span: AtomicU64::new(1),
+        })
+        .is_ok()
+    })
+}
+
+/// Formats an event's fields as `message key=value ...`
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.fields, " {}={}", field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+/// Tracing subscriber that feeds the in-memory log
+struct LogSubscriber {
+    next_span: AtomicU64,
+}
+
+impl tracing::Subscriber for LogSubscriber {
+    // Always ask `enabled`, so `set_level` applies to existing call sites
+    fn register_callsite(&self, _metadata: &'static Metadata<'static>) -> Interest {
+        Interest::sometimes()
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        is_enabled(*metadata.level())
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(LevelFilter::from_level(level()))
+    }
+
+    fn new_span(&self, _span: &span::Attributes<'_>) -> span::Id {
+        span::Id::from_u64(self.next_span.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let metadata = event.metadata();
+        push(
+            *metadata.level(),
+            metadata.target(),
+            visitor.message + &visitor.fields,
+        );
+    }
+
+    fn enter(&self, _span: &span::Id) {}
+
+    fn exit(&self, _span: &span::Id) {}
+}
+
+/// Forwards `log` records (wgpu, naga, ...) into the same in-memory log
+struct LogBridge;
+
+static LOG_BRIDGE: LogBridge = LogBridge;
+
+impl log::Log for LogBridge {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        let quiet = QUIET_TARGETS
+            .iter()
+            .any(|target| metadata.target().starts_with(target));
+        (!quiet || metadata.level() <= log::Level::Warn)
+            && is_enabled(from_log_level(metadata.level()))
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        push(
+            from_log_level(record.level()),
+            record.target(),
+            record.args().to_string(),
+        );
+    }
+
+    fn flush(&self) {}
+}
+
+fn from_log_level(level: log::Level) -> Level {
+    match level {
+        log::Level::Error => Level::ERROR,
+        log::Level::Warn => Level::WARN,
+        log::Level::Info => Level::INFO,
+        log::Level::Debug => Level::DEBUG,
+        log::Level::Trace => Level::TRACE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_are_captured_with_fields() {
+        let subscriber = LogSubscriber {
+            next_span: AtomicU64::new(1),
+        };
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!(target: "haggis_log_test", step = 3, "CFL number {} too high", 1.5);
+            tracing::trace!(target: "haggis_log_test", "filtered out");
+        });
+
+        let mut captured = Vec::new();
+        read_from(0, |record| {
+            if record.target == "haggis_log_test" {
+                captured.push(record.clone());
+            }
+        });
+        assert_eq!(captured.len(), 1);
+        assert_eq!(captured[0].level, Level::WARN);
+        assert_eq!(captured[0].message, "CFL number 1.5 too high step=3");
+    }
+}
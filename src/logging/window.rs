@@ -0,0 +1,139 @@
+//! Log console window
+//!
+//! Shows the records captured by the haggis subscriber, filtered by level,
+//! target and a search string, with buttons to copy the visible lines or
+//! clear the view.
+
+use imgui::Ui;
+use tracing::Level;
+
+use super::LogRecord;
+
+/// Maximum number of records kept by the window
+const MAX_LINES: usize = 2000;
+
+const LEVELS: [Level; 5] = [
+    Level::ERROR,
+    Level::WARN,
+    Level::INFO,
+    Level::DEBUG,
+    Level::TRACE,
+];
+
+/// Log console state: collected records and view filters
+pub struct LogWindow {
+    /// Whether the window is visible
+    pub open: bool,
+    records: Vec<LogRecord>,
+    next_id: u64,
+    /// Index into `LEVELS` of the most verbose level shown
+    level_index: usize,
+    target_filter: String,
+    search: String,
+    auto_scroll: bool,
+}
+
+impl LogWindow {
+    /// Create a closed log window
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            records: Vec::new(),
+            next_id: 0,
+            level_index: 3,
+            target_filter: String::new(),
+            search: String::new(),
+            auto_scroll: true,
+        }
+    }
+
+    /// Show or hide the window
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    /// Pull new records from the global log
+    pub fn collect(&mut self) {
+        self.next_id = super::read_from(self.next_id, |record| self.records.push(record.clone()));
+        if self.records.len() > MAX_LINES {
+            let excess = self.records.len() - MAX_LINES;
+            self.records.drain(..excess);
+        }
+    }
+
+    /// Whether a record passes the current filters
+    fn is_visible(&self, record: &LogRecord) -> bool {
+        record.level <= LEVELS[self.level_index]
+            && (self.target_filter.is_empty() || record.target.contains(&self.target_filter))
+            && (self.search.is_empty()
+                || record
+                    .message
+                    .to_lowercase()
+                    .contains(&self.search.to_lowercase()))
+    }
+
+    /// Draw the window if it is open
+    pub fn render_ui(&mut self, ui: &Ui) {
+        self.collect();
+        if !self.open {
+            return;
+        }
+
+        let mut open = self.open;
+        ui.window("Log")
+            .size([700.0, 300.0], imgui::Condition::FirstUseEver)
+            .opened(&mut open)
+            .build(|| {
+                ui.set_next_item_width(90.0);
+                let labels = LEVELS.map(|level| level.as_str());
+                ui.combo_simple_string("Level", &mut self.level_index, &labels);
+                ui.same_line();
+                ui.set_next_item_width(140.0);
+                ui.input_text("Target", &mut self.target_filter).build();
+                ui.same_line();
+                ui.set_next_item_width(160.0);
+                ui.input_text("Search", &mut self.search).build();
+
+                ui.same_line();
+                if ui.button("Copy") {
+                    let text = self
+                        .records
+                        .iter()
+                        .filter(|record| self.is_visible(record))
+                        .map(|record| record.to_string())
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    ui.set_clipboard_text(text);
+                }
+                ui.same_line();
+                if ui.button("Clear") {
+                    self.records.clear();
+                }
+                ui.same_line();
+                ui.checkbox("Auto-scroll", &mut self.auto_scroll);
+                ui.separator();
+
+                ui.child_window("##log_lines").build(|| {
+                    for record in self.records.iter().filter(|record| self.is_visible(record)) {
+                        let color = match record.level {
+                            Level::ERROR => [1.0, 0.4, 0.4, 1.0],
+                            Level::WARN => [1.0, 0.8, 0.3, 1.0],
+                            Level::INFO => [0.9, 0.9, 0.9, 1.0],
+                            _ => [0.6, 0.6, 0.6, 1.0],
+                        };
+                        ui.text_colored(color, record.to_string());
+                    }
+                    if self.auto_scroll && ui.scroll_y() >= ui.scroll_max_y() {
+                        ui.set_scroll_here_y_with_ratio(1.0);
+                    }
+                });
+            });
+        self.open = open;
+    }
+}
+
+impl Default for LogWindow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
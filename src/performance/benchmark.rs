@@ -0,0 +1,541 @@
+//! Micro-benchmarks of the engine's core GPU paths
+//!
+//! [`Benchmark`] runs a [`BenchmarkScenario`] on a headless device a fixed
+//! number of times and collects timing statistics. The results form a
+//! [`BenchmarkReport`] tagged with the crate version and adapter, written as
+//! JSON so runs can be compared across versions and machines. The same
+//! scenarios back the `cargo bench` target and
+//! [`HaggisApp::run_benchmark`](crate::app::HaggisApp::run_benchmark).
+//!
+//! Times include waiting for the GPU, except for draw submission, which
+//! measures recording and submitting the passes on the CPU.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::performance::benchmark::{Benchmark, BenchmarkScenario};
+//!
+//! # fn main() -> haggis::Result<()> {
+//! let mut benchmark = Benchmark::new()?.with_iterations(5, 50);
+//! let report = benchmark.run_all(&BenchmarkScenario::defaults())?;
+//! report.write_json("target/haggis-bench.json")?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt::Write as _;
+use std::path::Path;
+use std::time::Duration;
+
+use cgmath::Vector3;
+
+use crate::error::{HaggisError, Result};
+use crate::gfx::camera::{
+    camera_controller::CameraController, camera_utils::CameraManager, orbit_camera::OrbitCamera,
+};
+use crate::gfx::geometry::primitives::{generate_cube, generate_sphere};
+use crate::gfx::rendering::{RenderConfig, RenderEngine, RenderTexture};
+use crate::gfx::scene::Scene;
+use crate::platform::Instant;
+use crate::wgpu_utils::compute_primitives::read_buffer;
+
+/// Compute shader doing the least possible work per dispatch
+const DISPATCH_SHADER: &str = r#"
+@group(0) @binding(0) var<storage, read_write> counters: array<u32>;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    counters[id.x] += 1u;
+}
+"#;
+
+/// A core path to measure and its problem size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BenchmarkScenario {
+    /// Create vertex and index buffers for a UV sphere with this many
+    /// longitude and latitude segments
+    MeshUpload { segments: u32 },
+    /// Copy a GPU buffer of this many bytes back to the CPU
+    Readback { bytes: u64 },
+    /// Submit this many minimal compute dispatches in one pass
+    ComputeDispatch { dispatches: u32 },
+    /// Record and submit the passes for a scene of this many cubes
+    DrawSubmission { objects: u32 },
+}
+
+impl BenchmarkScenario {
+    /// One scenario of each kind at a moderate size
+    pub fn defaults() -> [Self; 4] {
+        [
+            Self::MeshUpload { segments: 256 },
+            Self::Readback { bytes: 16 << 20 },
+            Self::ComputeDispatch { dispatches: 1000 },
+            Self::DrawSubmission { objects: 500 },
+        ]
+    }
+
+    /// Identifier used in reports, e.g. `readback`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::MeshUpload { .. } => "mesh_upload",
+            Self::Readback { .. } => "readback",
+            Self::ComputeDispatch { .. } => "compute_dispatch",
+            Self::DrawSubmission { .. } => "draw_submission",
+        }
+    }
+
+    /// Problem size and its unit
+    fn size(&self) -> (u64, &'static str) {
+        match *self {
+            Self::MeshUpload { segments } => (segments as u64, "segments"),
+            Self::Readback { bytes } => (bytes, "bytes"),
+            Self::ComputeDispatch { dispatches } => (dispatches as u64, "dispatches"),
+            Self::DrawSubmission { objects } => (objects as u64, "objects"),
+        }
+    }
+}
+
+/// Timings of one scenario
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchmarkResult {
+    pub scenario: BenchmarkScenario,
+    /// Timed iterations, after warmup
+    pub iterations: u32,
+    pub mean: Duration,
+    pub median: Duration,
+    pub min: Duration,
+    pub max: Duration,
+    pub std_dev: Duration,
+    /// Work per second at the mean time, e.g. bytes or draws
+    pub throughput: f64,
+    /// Unit of `throughput`, e.g. `bytes/s`
+    pub throughput_unit: &'static str,
+}
+
+impl BenchmarkResult {
+    /// Statistics of `samples`, with `work` units of `unit` done per sample
+    fn from_samples(
+        scenario: BenchmarkScenario,
+        samples: &[Duration],
+        work: f64,
+        unit: &'static str,
+    ) -> Self {
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+        let count = sorted.len().max(1) as f64;
+        let seconds: Vec<f64> = sorted.iter().map(Duration::as_secs_f64).collect();
+        let mean = seconds.iter().sum::<f64>() / count;
+        let variance = seconds.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / count;
+        Self {
+            scenario,
+            iterations: samples.len() as u32,
+            mean: Duration::from_secs_f64(mean),
+            median: sorted.get(sorted.len() / 2).copied().unwrap_or_default(),
+            min: sorted.first().copied().unwrap_or_default(),
+            max: sorted.last().copied().unwrap_or_default(),
+            std_dev: Duration::from_secs_f64(variance.sqrt()),
+            throughput: if mean > 0.0 { work / mean } else { 0.0 },
+            throughput_unit: unit,
+        }
+    }
+}
+
+/// Results of a benchmark run with what they were measured on
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchmarkReport {
+    /// Version of this crate
+    pub version: &'static str,
+    pub adapter: String,
+    pub backend: String,
+    pub results: Vec<BenchmarkResult>,
+}
+
+impl BenchmarkReport {
+    /// JSON object with one entry per result, times in nanoseconds
+    pub fn to_json(&self) -> String {
+        let mut json = format!(
+            "{{\"version\":{},\"adapter\":{},\"backend\":{},\"results\":[",
+            json_string(self.version),
+            json_string(&self.adapter),
+            json_string(&self.backend)
+        );
+        for (index, result) in self.results.iter().enumerate() {
+            let (size, size_unit) = result.scenario.size();
+            let _ = write!(
+                json,
+                "{}{{\"name\":{},\"size\":{size},\"size_unit\":{},\"iterations\":{},\
+                 \"mean_ns\":{},\"median_ns\":{},\"min_ns\":{},\"max_ns\":{},\"std_dev_ns\":{},\
+                 \"throughput\":{},\"throughput_unit\":{}}}",
+                if index == 0 { "" } else { "," },
+                json_string(result.scenario.name()),
+                json_string(size_unit),
+                result.iterations,
+                result.mean.as_nanos(),
+                result.median.as_nanos(),
+                result.min.as_nanos(),
+                result.max.as_nanos(),
+                result.std_dev.as_nanos(),
+                if result.throughput.is_finite() {
+                    result.throughput
+                } else {
+                    0.0
+                },
+                json_string(result.throughput_unit)
+            );
+        }
+        json.push_str("]}");
+        json
+    }
+
+    /// Write [`to_json`](Self::to_json) to a file, creating its directory
+    pub fn write_json(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let io_error = |source| HaggisError::Io {
+            path: path.into(),
+            source,
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(io_error)?;
+        }
+        std::fs::write(path, self.to_json()).map_err(io_error)
+    }
+
+    /// Human-readable table, one line per result
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "haggis {} on {} ({})\n",
+            self.version, self.adapter, self.backend
+        );
+        for result in &self.results {
+            let (size, size_unit) = result.scenario.size();
+            let _ = writeln!(
+                summary,
+                "{:<18} {size:>10} {size_unit:<10} mean {:>10.3?} ± {:>9.3?}  {:.3e} {}",
+                result.scenario.name(),
+                result.mean,
+                result.std_dev,
+                result.throughput,
+                result.throughput_unit
+            );
+        }
+        summary
+    }
+}
+
+/// Runs benchmark scenarios on a headless render engine
+pub struct Benchmark {
+    engine: RenderEngine,
+    warmup: u32,
+    iterations: u32,
+}
+
+impl Benchmark {
+    /// Benchmark on the default adapter, 3 warmup and 30 timed iterations
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::NoAdapter`] or [`HaggisError::Device`] without
+    /// a usable GPU.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new() -> Result<Self> {
+        Self::with_config(&RenderConfig::default())
+    }
+
+    /// Benchmark with explicit adapter and device settings
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_config(config: &RenderConfig) -> Result<Self> {
+        let engine = pollster::block_on(RenderEngine::headless(256, 256, config))?;
+        Ok(Self {
+            engine,
+            warmup: 3,
+            iterations: 30,
+        })
+    }
+
+    pub fn with_iterations(mut self, warmup: u32, iterations: u32) -> Self {
+        self.warmup = warmup;
+        self.iterations = iterations.max(1);
+        self
+    }
+
+    /// Run each scenario in turn
+    pub fn run_all(&mut self, scenarios: &[BenchmarkScenario]) -> Result<BenchmarkReport> {
+        let results = scenarios
+            .iter()
+            .map(|&scenario| self.run(scenario))
+            .collect::<Result<Vec<_>>>()?;
+        let capabilities = self.engine.capabilities();
+        Ok(BenchmarkReport {
+            version: env!("CARGO_PKG_VERSION"),
+            adapter: capabilities.adapter_name.clone(),
+            backend: format!("{:?}", capabilities.backend),
+            results,
+        })
+    }
+
+    /// Time one scenario
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::Readback`] if a readback scenario fails.
+    pub fn run(&mut self, scenario: BenchmarkScenario) -> Result<BenchmarkResult> {
+        match scenario {
+            BenchmarkScenario::MeshUpload { segments } => self.mesh_upload(scenario, segments),
+            BenchmarkScenario::Readback { bytes } => self.readback(scenario, bytes),
+            BenchmarkScenario::ComputeDispatch { dispatches } => {
+                self.compute_dispatch(scenario, dispatches)
+            }
+            BenchmarkScenario::DrawSubmission { objects } => {
+                self.draw_submission(scenario, objects)
+            }
+        }
+    }
+
+    /// Run the warmup and timed iterations; each returns its own timing so
+    /// it can leave setup and cleanup out
+    fn sample(
+        &mut self,
+        mut iteration: impl FnMut(&mut Self) -> Result<Duration>,
+    ) -> Result<Vec<Duration>> {
+        let mut samples = Vec::with_capacity(self.iterations as usize);
+        for index in 0..self.warmup + self.iterations {
+            let elapsed = iteration(self)?;
+            if index >= self.warmup {
+                samples.push(elapsed);
+            }
+        }
+        Ok(samples)
+    }
+
+    fn mesh_upload(
+        &mut self,
+        scenario: BenchmarkScenario,
+        segments: u32,
+    ) -> Result<BenchmarkResult> {
+        let geometry = generate_sphere(segments, segments);
+        let mut scene = empty_scene();
+        scene.add_procedural_object(geometry, "Benchmark Sphere");
+        let mut object = scene.objects.swap_remove(0);
+        let bytes: usize = object
+            .meshes
+            .iter()
+            .map(|mesh| {
+                std::mem::size_of_val(mesh.vertices()) + std::mem::size_of_val(mesh.indices())
+            })
+            .sum();
+
+        let samples = self.sample(|benchmark| {
+            object.release_gpu_resources();
+            let device = benchmark.engine.device();
+            let _ = device.poll(wgpu::MaintainBase::Wait);
+            let start = Instant::now();
+            object.init_gpu_resources(device);
+            let _ = device.poll(wgpu::MaintainBase::Wait);
+            Ok(start.elapsed())
+        })?;
+        Ok(BenchmarkResult::from_samples(
+            scenario,
+            &samples,
+            bytes as f64,
+            "bytes/s",
+        ))
+    }
+
+    fn readback(&mut self, scenario: BenchmarkScenario, bytes: u64) -> Result<BenchmarkResult> {
+        let len = (bytes / 4).max(1) as usize;
+        let buffer = self.engine.device().create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Benchmark Readback Buffer"),
+            size: len as u64 * 4,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let samples = self.sample(|benchmark| {
+            let (device, queue) = (benchmark.engine.device(), benchmark.engine.queue());
+            let start = Instant::now();
+            read_buffer::<u32>(device, queue, &buffer, len)?;
+            Ok(start.elapsed())
+        })?;
+        Ok(BenchmarkResult::from_samples(
+            scenario,
+            &samples,
+            (len * 4) as f64,
+            "bytes/s",
+        ))
+    }
+
+    fn compute_dispatch(
+        &mut self,
+        scenario: BenchmarkScenario,
+        dispatches: u32,
+    ) -> Result<BenchmarkResult> {
+        let device = self.engine.device();
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Benchmark Dispatch Shader"),
+            source: wgpu::ShaderSource::Wgsl(DISPATCH_SHADER.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Benchmark Dispatch Pipeline"),
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        let counters = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Benchmark Counters"),
+            size: 64 * 4,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Benchmark Dispatch Bind Group"),
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: counters.as_entire_binding(),
+            }],
+        });
+
+        let samples = self.sample(|benchmark| {
+            let device = benchmark.engine.device();
+            let start = Instant::now();
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Benchmark Dispatch Encoder"),
+            });
+            {
+                let mut pass = encoder.begin_compute_pass(&Default::default());
+                pass.set_pipeline(&pipeline);
+                pass.set_bind_group(0, &bind_group, &[]);
+                for _ in 0..dispatches {
+                    pass.dispatch_workgroups(1, 1, 1);
+                }
+            }
+            benchmark.engine.queue().submit(Some(encoder.finish()));
+            let _ = device.poll(wgpu::MaintainBase::Wait);
+            Ok(start.elapsed())
+        })?;
+        Ok(BenchmarkResult::from_samples(
+            scenario,
+            &samples,
+            dispatches as f64,
+            "dispatches/s",
+        ))
+    }
+
+    fn draw_submission(
+        &mut self,
+        scenario: BenchmarkScenario,
+        objects: u32,
+    ) -> Result<BenchmarkResult> {
+        let mut scene = empty_scene();
+        let columns = (objects as f32).sqrt().ceil().max(1.0) as u32;
+        for index in 0..objects {
+            scene.add_procedural_object(generate_cube(), &format!("Cube {index}"));
+            if let Some(object) = scene.objects.last_mut() {
+                object.set_translation(Vector3::new(
+                    (index % columns) as f32 * 1.5,
+                    0.0,
+                    (index / columns) as f32 * 1.5,
+                ));
+            }
+        }
+        let (device, queue) = (self.engine.device(), self.engine.queue());
+        scene.init_gpu_resources(device, queue);
+        scene.update_materials(device, queue);
+
+        let extent = columns as f32 * 1.5;
+        let camera = OrbitCamera::new(
+            extent * 1.5,
+            0.6,
+            0.8,
+            Vector3::new(extent / 2.0, 0.0, extent / 2.0),
+            1.0,
+        );
+        let target = RenderTexture::new("Benchmark Target", camera, 256, 256);
+        let targets = std::slice::from_ref(&target);
+
+        let samples = self.sample(|benchmark| {
+            // Keep the queue from backing up between timed submissions
+            let _ = benchmark.engine.device().poll(wgpu::MaintainBase::Wait);
+            let start = Instant::now();
+            benchmark.engine.render_textures(&scene, &[], targets);
+            Ok(start.elapsed())
+        })?;
+        Ok(BenchmarkResult::from_samples(
+            scenario,
+            &samples,
+            objects as f64,
+            "draws/s",
+        ))
+    }
+}
+
+fn empty_scene() -> Scene {
+    let camera = OrbitCamera::new(8.0, 0.4, 0.2, Vector3::new(0.0, 0.0, 0.0), 1.0);
+    Scene::new(CameraManager::new(
+        camera,
+        CameraController::new(0.005, 0.1),
+    ))
+}
+
+/// Quoted JSON string
+fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_statistics_and_json() {
+        let samples = [1, 2, 3, 6].map(Duration::from_millis);
+        let scenario = BenchmarkScenario::Readback { bytes: 1000 };
+        let result = BenchmarkResult::from_samples(scenario, &samples, 1000.0, "bytes/s");
+        assert_eq!(result.mean, Duration::from_millis(3));
+        assert_eq!(result.median, Duration::from_millis(3));
+        assert_eq!(result.max, Duration::from_millis(6));
+        assert!((result.throughput - 333_333.3).abs() < 1.0);
+
+        let report = BenchmarkReport {
+            version: "0.1.0",
+            adapter: "GPU \"A\"".to_string(),
+            backend: "Vulkan".to_string(),
+            results: vec![result],
+        };
+        let json = report.to_json();
+        assert!(json.starts_with(r#"{"version":"0.1.0","adapter":"GPU \"A\"","#));
+        assert!(json.contains(r#""name":"readback","size":1000,"size_unit":"bytes""#));
+        assert!(json.contains(r#""mean_ns":3000000,"#));
+    }
+
+    #[test]
+    fn test_scenarios_run() {
+        let Ok(benchmark) = Benchmark::new() else {
+            return;
+        };
+        let mut benchmark = benchmark.with_iterations(0, 2);
+        let scenarios = [
+            BenchmarkScenario::MeshUpload { segments: 8 },
+            BenchmarkScenario::Readback { bytes: 4096 },
+            BenchmarkScenario::ComputeDispatch { dispatches: 4 },
+            BenchmarkScenario::DrawSubmission { objects: 4 },
+        ];
+        let report = benchmark.run_all(&scenarios).unwrap();
+        assert_eq!(report.results.len(), 4);
+        assert!(report.results.iter().all(|result| result.iterations == 2));
+    }
+}
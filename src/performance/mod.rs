@@ -10,6 +10,7 @@
 //! - **Memory Usage**: Track memory consumption and allocation patterns
 //! - **Render Statistics**: GPU performance and draw call metrics
 //! - **UI Integration**: Built-in ImGui panels for real-time display
+//! - **Benchmarks**: Timed runs of core GPU paths in [`benchmark`]
 //!
 //! ## Usage
 //!
@@ -27,8 +28,12 @@
 //! monitor.render_ui(&ui);
 //! ```
 
+pub mod benchmark;
+
 use std::collections::VecDeque;
-use std::time::{Duration, Instant};
+use std::time::Duration;
+
+use crate::platform::Instant;
 
 /// Comprehensive performance metrics for the engine
 #[derive(Debug, Clone)]
@@ -49,6 +54,8 @@ pub struct PerformanceMetrics {
     pub vertex_count: u32,
     /// GPU memory usage in bytes (if available)
     pub gpu_memory_bytes: Option<u64>,
+    /// GPU frame times with and without the depth pre-pass
+    pub prepass_timing: PrepassTiming,
 }
 
 impl Default for PerformanceMetrics {
@@ -62,10 +69,53 @@ impl Default for PerformanceMetrics {
             draw_calls: 0,
             vertex_count: 0,
             gpu_memory_bytes: None,
+            prepass_timing: PrepassTiming::default(),
         }
     }
 }
 
+/// GPU frame times with and without the depth pre-pass, to show what it saves
+///
+/// Frames are timed with timestamp queries when the device has
+/// [`wgpu::Features::TIMESTAMP_QUERY`]; otherwise both times stay `None`.
+/// Toggle [`RenderSettings::depth_prepass`](crate::gfx::rendering::RenderSettings::depth_prepass)
+/// for a while in each state to measure both.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PrepassTiming {
+    /// Whether the pre-pass is currently on
+    pub enabled: bool,
+    /// Smoothed GPU frame time with the pre-pass, in milliseconds
+    pub with_prepass_ms: Option<f32>,
+    /// Smoothed GPU frame time without the pre-pass, in milliseconds
+    pub without_prepass_ms: Option<f32>,
+}
+
+impl PrepassTiming {
+    /// Weight of a new frame in the smoothed times
+    const SMOOTHING: f32 = 0.1;
+
+    /// Fold in the GPU time of a frame rendered with or without the pre-pass
+    pub fn record(&mut self, prepass: bool, gpu_ms: f32) {
+        let average = if prepass {
+            &mut self.with_prepass_ms
+        } else {
+            &mut self.without_prepass_ms
+        };
+        *average = Some(match *average {
+            Some(previous) => previous + (gpu_ms - previous) * Self::SMOOTHING,
+            None => gpu_ms,
+        });
+    }
+
+    /// Relative change in GPU frame time from the pre-pass, e.g. `-0.2` when
+    /// it makes frames 20% faster; `None` until both states were measured
+    pub fn impact(&self) -> Option<f32> {
+        let with = self.with_prepass_ms?;
+        let without = self.without_prepass_ms?;
+        (without > 0.0).then(|| with / without - 1.0)
+    }
+}
+
 /// Performance monitoring system
 pub struct PerformanceMonitor {
     /// Ring buffer of recent frame times for averaging
@@ -148,7 +198,7 @@ impl PerformanceMonitor {
         // Update metrics immediately for manual timing
         if self.last_update.elapsed() >= self.update_interval {
             self.update_metrics();
-            self.last_update = std::time::Instant::now();
+            self.last_update = Instant::now();
         }
     }
 
@@ -203,6 +253,12 @@ impl PerformanceMonitor {
         self.current_metrics.vertex_count = vertex_count;
     }
 
+    /// Update the depth pre-pass timings, e.g. from
+    /// [`RenderEngine::prepass_timing`](crate::gfx::rendering::RenderEngine::prepass_timing)
+    pub fn update_prepass_timing(&mut self, timing: PrepassTiming) {
+        self.current_metrics.prepass_timing = timing;
+    }
+
     /// Get current performance metrics
     pub fn get_metrics(&self) -> &PerformanceMetrics {
         &self.current_metrics
@@ -256,6 +312,22 @@ impl PerformanceMonitor {
                 ui.text("Render Stats:");
                 ui.text(format!("  Draw Calls: {}", metrics.draw_calls));
                 ui.text(format!("  Vertices: {}", metrics.vertex_count));
+
+                ui.separator();
+                let timing = &metrics.prepass_timing;
+                let state = if timing.enabled { "on" } else { "off" };
+                ui.text(format!("Depth Pre-pass: {}", state));
+                let format_ms = |ms: Option<f32>| match ms {
+                    Some(ms) => format!("{:.2}ms", ms),
+                    None => "-".to_string(),
+                };
+                ui.text(format!("  GPU with: {}", format_ms(timing.with_prepass_ms)));
+                ui.text(format!("  GPU without: {}", format_ms(timing.without_prepass_ms)));
+                if let Some(impact) = timing.impact() {
+                    ui.text(format!("  Impact: {:+.1}%", impact * 100.0));
+                } else if timing.with_prepass_ms.is_none() && timing.without_prepass_ms.is_none() {
+                    ui.text_disabled("  GPU timing needs TIMESTAMP_QUERY");
+                }
                 
                 // Memory information (if available)
                 if let Some(memory_bytes) = metrics.memory_usage_bytes {
@@ -303,4 +375,23 @@ impl Default for PerformanceMonitor {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prepass_timing_impact() {
+        let mut timing = PrepassTiming::default();
+        timing.record(false, 10.0);
+        assert_eq!(timing.impact(), None);
+
+        timing.record(true, 8.0);
+        assert!((timing.impact().unwrap() + 0.2).abs() < 1e-6);
+
+        // New frames move the smoothed time a little at a time
+        timing.record(true, 18.0);
+        assert!((timing.with_prepass_ms.unwrap() - 9.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,82 @@
+//! # Platform Support
+//!
+//! Small shims over the differences between native and web
+//! (`wasm32-unknown-unknown`) builds, so the rest of the engine can use one
+//! code path for timing and async work.
+//!
+//! ## Web Builds
+//!
+//! On the web, haggis renders into a `<canvas>` through WebGPU. The canvas is
+//! looked up by the id `haggis-canvas`, and created and appended to the page
+//! body if it doesn't exist. GPU initialization is asynchronous in the browser,
+//! so the first frames after startup are skipped until the device is ready.
+//!
+//! File system access is unavailable: OBJ files, ImGui layout files and script
+//! files can't be loaded, so web demos should build their geometry
+//! procedurally (`add_cube`, `add_sphere`, ...). Blocking GPU readback
+//! (`ComputeContext::read_buffer`) is unavailable too.
+//!
+//! Build with `cargo build --target wasm32-unknown-unknown` and bind the
+//! output with `wasm-bindgen --target web`.
+//!
+//! ```ignore
+//! use wasm_bindgen::prelude::*;
+//!
+//! #[wasm_bindgen(start)]
+//! pub fn start() {
+//!     wasm_bindgen_futures::spawn_local(async {
+//!         let mut app = haggis::HaggisApp::new().await;
+//!         app.add_cube();
+//!         app.run();
+//!     });
+//! }
+//! ```
+
+/// Monotonic clock that also works in the browser
+#[cfg(not(target_arch = "wasm32"))]
+pub use std::time::Instant;
+
+/// Monotonic clock that also works in the browser
+#[cfg(target_arch = "wasm32")]
+pub use web_time::Instant;
+
+/// Id of the canvas element haggis renders into on the web
+pub const CANVAS_ID: &str = "haggis-canvas";
+
+/// Whether the engine can read and write files on this platform
+pub const HAS_FILESYSTEM: bool = cfg!(not(target_arch = "wasm32"));
+
+/// Run a future to completion.
+///
+/// Blocks the current thread on native targets. On the web, blocking is not
+/// possible, so the future is spawned on the browser's event loop instead and
+/// `on_ready` runs when it completes.
+pub fn run_async<F, T>(future: F, on_ready: impl FnOnce(T) + 'static)
+where
+    F: std::future::Future<Output = T> + 'static,
+    T: 'static,
+{
+    #[cfg(not(target_arch = "wasm32"))]
+    on_ready(pollster::block_on(future));
+
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_futures::spawn_local(async move { on_ready(future.await) });
+}
+
+/// Find the haggis canvas in the page, creating it if needed
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn canvas() -> Option<web_sys::HtmlCanvasElement> {
+    use wasm_bindgen::JsCast;
+
+    let document = web_sys::window()?.document()?;
+    let element = match document.get_element_by_id(CANVAS_ID) {
+        Some(element) => element,
+        None => {
+            let element = document.create_element("canvas").ok()?;
+            element.set_id(CANVAS_ID);
+            document.body()?.append_child(&element).ok()?;
+            element
+        }
+    };
+    element.dyn_into::<web_sys::HtmlCanvasElement>().ok()
+}
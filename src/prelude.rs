@@ -44,20 +44,35 @@
 //! ```
 
 // Re-export core application types
-pub use crate::app::HaggisApp;
+pub use crate::app::{builder::HaggisAppBuilder, jobs::{JobHandle, JobSystem}, HaggisApp};
 pub use crate::default;
+pub use crate::error::HaggisError;
 
 // Re-export graphics and scene types
-pub use crate::gfx::scene::Scene;
+pub use crate::gfx::scene::{Prefab, Scene};
+pub use crate::ecs::{Entity, World};
+pub use crate::gfx::GpuCapabilities;
+pub use crate::gfx::overlay::{GridPlane, OverlayConfig};
 pub use crate::gfx::camera::CameraManager;
 pub use crate::gfx::geometry::{GeometryData, generate_cube, generate_sphere, generate_plane, generate_cylinder};
 
 // Re-export simulation framework 
 pub use crate::simulation::traits::Simulation;
 pub use crate::simulation::manager::SimulationManager;
+pub use crate::simulation::params::{ParamValue, ParamsUniform, SimParams};
+
+// Re-export event bus and built-in events
+pub use crate::events::{
+    DragEvent, DragPhase, EventBus, EventReceiver, MetricSample, ParameterChanged, PickEvent,
+    SimulationEvent, WindowResizeEvent,
+};
+
+// Re-export input mapping
+pub use crate::input::{Action, InputState, Key, KeyChord, Modifiers};
 
 // Re-export UI types and utilities
 pub use crate::ui::{UiFont, UiStyle, default_transform_panel};
+pub use crate::ui::notifications::Notification;
 
 // Re-export visualization types
 pub use crate::visualization::{
@@ -75,7 +90,7 @@ pub use imgui::Ui;
 
 // Re-export common standard library types
 pub use std::collections::VecDeque;
-pub use std::time::Instant;
+pub use crate::platform::Instant;
 
 // Re-export wgpu types commonly used in GPU simulations
 pub use wgpu::{Device, Queue};
\ No newline at end of file
@@ -0,0 +1,621 @@
+//! # Remote Control
+//!
+//! An HTTP server for monitoring and steering a running app from another
+//! machine, e.g. a long simulation on a workstation watched from a laptop
+//! browser. Opening the server address shows a small dashboard; the same
+//! functions are available as JSON endpoints for scripts:
+//!
+//! | Endpoint | Description |
+//! |----------|-------------|
+//! | `GET /` | Dashboard page |
+//! | `GET /status` | Simulation state, FPS and metrics as JSON |
+//! | `GET /metrics/stream` | Status as server-sent events, for `EventSource` |
+//! | `GET /params` | Parameters exposed by the simulation |
+//! | `PUT /params/<name>` | Set a parameter, the body is the new value |
+//! | `POST /pause`, `/resume`, `/step`, `/reset` | Simulation control |
+//! | `POST /command` | Run a console command, the body is the command line |
+//! | `GET /screenshot?width=&height=` | PNG of the main camera view |
+//!
+//! Metrics are the latest [`MetricSample`](crate::events::MetricSample)
+//! events emitted by simulations; parameters come from
+//! [`Simulation::params_mut`](crate::simulation::traits::Simulation::params_mut).
+//!
+//! ## Security
+//!
+//! The server listens on `127.0.0.1` by default, so only the local machine can
+//! reach it; forward the port over SSH to use it from elsewhere. Listening on
+//! other interfaces requires a token, which clients then send as an
+//! `Authorization: Bearer` header or a `token` query parameter (open the
+//! dashboard as `http://host:7878/?token=...`). Control requests carrying a
+//! foreign `Origin` header are rejected, so web pages cannot steer the app
+//! through the browser. Without a token, requests must also name the server
+//! as `localhost`, `127.0.0.1` or `[::1]` in their `Host` header, which stops
+//! pages that rebind their own domain to the loopback address.
+//!
+//! At most [`MAX_CONNECTIONS`] clients are served at once, each on its own
+//! thread; further connections are answered with `503` and closed.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::remote::RemoteConfig;
+//!
+//! let mut app = haggis::default();
+//! let address = app
+//!     .enable_remote_control(RemoteConfig::default())
+//!     .expect("start remote control");
+//! println!("Dashboard at http://{address}/");
+//! app.run();
+//! ```
+
+pub(crate) mod http;
+
+use std::collections::BTreeMap;
+use std::io::{BufReader, Write};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::error::{HaggisError, Result};
+use crate::events::SimulationEvent;
+use crate::simulation::params::SimParams;
+
+use http::{json_number, json_string, write_error, write_response, Request};
+
+/// Port used by [`RemoteConfig::default`]
+pub const DEFAULT_PORT: u16 = 7878;
+
+/// Connections served at once, including open `/metrics/stream` clients
+pub const MAX_CONNECTIONS: usize = 16;
+
+/// How long a request waits for the app to answer
+const REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+const DASHBOARD: &str = include_str!("dashboard.html");
+
+/// Where the server listens and how clients authenticate
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteConfig {
+    pub address: SocketAddr,
+    /// Token clients must present; required for non-loopback addresses
+    pub token: Option<String>,
+    /// Interval between status events on `/metrics/stream`
+    pub stream_interval: Duration,
+}
+
+impl RemoteConfig {
+    /// Listen on `127.0.0.1:port`, reachable from this machine only
+    pub fn localhost(port: u16) -> Self {
+        Self {
+            address: SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port),
+            token: None,
+            stream_interval: Duration::from_millis(250),
+        }
+    }
+
+    /// Listen on all interfaces, with clients authenticating with `token`
+    pub fn public(port: u16, token: impl Into<String>) -> Self {
+        Self {
+            address: SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), port),
+            ..Self::localhost(port)
+        }
+        .with_token(token)
+    }
+
+    /// Require `token` from every client
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+}
+
+impl Default for RemoteConfig {
+    fn default() -> Self {
+        Self::localhost(DEFAULT_PORT)
+    }
+}
+
+/// Snapshot of the app served by `/status` and `/metrics/stream`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RemoteStatus {
+    /// Name of the attached simulation
+    pub simulation: Option<String>,
+    pub paused: bool,
+    /// Simulation updates since the last reset
+    pub frame: u64,
+    /// Simulated seconds since the last reset
+    pub time: f64,
+    pub fps: f32,
+    /// Latest value of each metric
+    pub metrics: BTreeMap<String, f64>,
+}
+
+impl RemoteStatus {
+    /// Status as a JSON object
+    pub fn to_json(&self) -> String {
+        let metrics: Vec<String> = self
+            .metrics
+            .iter()
+            .map(|(name, value)| format!("{}:{}", json_string(name), json_number(*value)))
+            .collect();
+        format!(
+            "{{\"simulation\":{},\"paused\":{},\"frame\":{},\"time\":{},\"fps\":{},\"metrics\":{{{}}}}}",
+            self.simulation
+                .as_deref()
+                .map_or("null".to_string(), json_string),
+            self.paused,
+            self.frame,
+            json_number(self.time),
+            json_number(self.fps as f64),
+            metrics.join(",")
+        )
+    }
+}
+
+/// Request that needs the app's state, answered on the main thread
+#[derive(Debug, Clone, PartialEq)]
+pub enum RemoteCommand {
+    /// Read all simulation parameters
+    GetParams,
+    /// Set one simulation parameter
+    SetParam { name: String, value: f64 },
+    /// Pause, resume, step or reset the simulation
+    Simulation(SimulationEvent),
+    /// Run a console command line
+    Command(String),
+    /// Capture the main camera view, at the window size if not given
+    Screenshot { size: Option<(u32, u32)> },
+}
+
+/// Successful answer to a [`RemoteCommand`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum RemoteResponse {
+    /// JSON document
+    Json(String),
+    /// PNG image
+    Png(Vec<u8>),
+}
+
+/// A [`RemoteCommand`] waiting for its answer
+pub struct RemoteRequest {
+    pub command: RemoteCommand,
+    reply: Sender<std::result::Result<RemoteResponse, String>>,
+}
+
+impl RemoteRequest {
+    /// Answer the request; an error message is returned to the client as 400
+    pub fn respond(self, response: std::result::Result<RemoteResponse, String>) {
+        // The client may have timed out and gone away
+        let _ = self.reply.send(response);
+    }
+}
+
+/// Parameter values as a JSON object, all of them or only `only`
+pub(crate) fn params_json(params: &dyn SimParams, only: Option<&str>) -> String {
+    let fields: Vec<String> = params
+        .descriptors()
+        .iter()
+        .enumerate()
+        .filter(|(_, descriptor)| only.is_none_or(|name| descriptor.name == name))
+        .filter_map(|(index, descriptor)| {
+            let value = params.value(index)?.as_f64();
+            Some(format!("{}:{}", json_string(descriptor.name), json_number(value)))
+        })
+        .collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+/// State shared with the connection threads
+struct Shared {
+    config: RemoteConfig,
+    status: Mutex<RemoteStatus>,
+    requests: Mutex<Sender<RemoteRequest>>,
+    shutdown: AtomicBool,
+    /// Connections currently being served
+    connections: AtomicUsize,
+    /// Port the server is bound to, checked against `Host` headers
+    port: u16,
+}
+
+/// One of the [`MAX_CONNECTIONS`] slots, freed when the connection ends
+struct ConnectionSlot(Arc<Shared>);
+
+impl ConnectionSlot {
+    fn acquire(shared: &Arc<Shared>) -> Option<Self> {
+        shared
+            .connections
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                (count < MAX_CONNECTIONS).then_some(count + 1)
+            })
+            .ok()
+            .map(|_| Self(shared.clone()))
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.connections.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Running remote control server
+///
+/// The app publishes its status and answers commands once per frame; the
+/// server stops when dropped.
+pub struct RemoteServer {
+    address: SocketAddr,
+    shared: Arc<Shared>,
+    requests: Receiver<RemoteRequest>,
+}
+
+impl RemoteServer {
+    /// Start listening in the background
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::Remote`] if the address is not loopback and no
+    /// token is configured, or the address cannot be bound.
+    pub fn start(config: RemoteConfig) -> Result<Self> {
+        let requested = config.address;
+        let remote_error = move |message: String| HaggisError::Remote {
+            address: requested.to_string(),
+            message,
+        };
+        if !config.address.ip().is_loopback() && config.token.is_none() {
+            return Err(remote_error(
+                "a token is required to listen on non-loopback addresses".to_string(),
+            ));
+        }
+
+        let listener =
+            TcpListener::bind(config.address).map_err(|error| remote_error(error.to_string()))?;
+        let address = listener
+            .local_addr()
+            .map_err(|error| remote_error(error.to_string()))?;
+        // Polled so the thread notices shutdown
+        listener
+            .set_nonblocking(true)
+            .map_err(|error| remote_error(error.to_string()))?;
+
+        let (sender, requests) = mpsc::channel();
+        let shared = Arc::new(Shared {
+            config,
+            status: Mutex::new(RemoteStatus::default()),
+            requests: Mutex::new(sender),
+            shutdown: AtomicBool::new(false),
+            connections: AtomicUsize::new(0),
+            port: address.port(),
+        });
+
+        let accept_shared = shared.clone();
+        thread::Builder::new()
+            .name("haggis-remote".to_string())
+            .spawn(move || accept_loop(listener, accept_shared))
+            .map_err(|error| remote_error(error.to_string()))?;
+
+        tracing::info!("Remote control listening on http://{}", address);
+        Ok(Self {
+            address,
+            shared,
+            requests,
+        })
+    }
+
+    /// Address the server is bound to
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Replace the status served to clients
+    pub fn publish(&self, status: RemoteStatus) {
+        *self.shared.status.lock().unwrap() = status;
+    }
+
+    /// Commands received since the last call
+    pub fn poll(&self) -> Vec<RemoteRequest> {
+        self.requests.try_iter().collect()
+    }
+}
+
+impl Drop for RemoteServer {
+    fn drop(&mut self) {
+        self.shared.shutdown.store(true, Ordering::Relaxed);
+    }
+}
+
+fn accept_loop(listener: TcpListener, shared: Arc<Shared>) {
+    while !shared.shutdown.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((mut stream, peer)) => {
+                let Some(slot) = ConnectionSlot::acquire(&shared) else {
+                    reject(&mut stream);
+                    continue;
+                };
+                // A failed spawn drops the closure and with it the slot
+                let _ = thread::Builder::new()
+                    .name("haggis-remote-client".to_string())
+                    .spawn(move || {
+                        if let Err(error) = handle_connection(stream, &slot.0) {
+                            tracing::debug!("Remote client {} failed: {}", peer, error);
+                        }
+                    });
+            }
+            Err(error) if error.kind() == std::io::ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(20));
+            }
+            Err(error) => {
+                tracing::warn!("Remote control stopped accepting connections: {}", error);
+                return;
+            }
+        }
+    }
+}
+
+/// Answer a connection over the limit with 503
+///
+/// The request head already sent is read first, as closing a socket with
+/// unread data resets the connection before the client sees the answer.
+fn reject(stream: &mut TcpStream) {
+    let _ = stream.set_nonblocking(false);
+    let _ = stream.set_read_timeout(Some(Duration::from_millis(50)));
+    let _ = std::io::Read::read(stream, &mut [0; 4096]);
+    let _ = write_error(stream, 503, "too many connections");
+    let _ = stream.shutdown(std::net::Shutdown::Both);
+}
+
+fn handle_connection(stream: TcpStream, shared: &Shared) -> std::io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    let mut writer = stream.try_clone()?;
+    let request = match http::read_request(&mut BufReader::new(stream)) {
+        Ok(request) => request,
+        Err(error) => return write_error(&mut writer, 400, &error.to_string()),
+    };
+
+    if let Err((status, message)) = authorize(&request, &shared.config, shared.port) {
+        return write_error(&mut writer, status, message);
+    }
+
+    let command = match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/") => {
+            return write_response(
+                &mut writer,
+                200,
+                "text/html; charset=utf-8",
+                DASHBOARD.as_bytes(),
+            )
+        }
+        ("GET", "/status") => {
+            let json = shared.status.lock().unwrap().to_json();
+            return write_response(&mut writer, 200, "application/json", json.as_bytes());
+        }
+        ("GET", "/metrics/stream") => return stream_status(&mut writer, shared),
+        ("GET", "/params") => RemoteCommand::GetParams,
+        ("PUT" | "POST", path) if path.starts_with("/params/") => {
+            let Ok(value) = request.body.trim().parse() else {
+                return write_error(&mut writer, 400, "the body must be a number");
+            };
+            RemoteCommand::SetParam {
+                name: path["/params/".len()..].to_string(),
+                value,
+            }
+        }
+        ("POST", "/pause") => RemoteCommand::Simulation(SimulationEvent::Pause),
+        ("POST", "/resume") => RemoteCommand::Simulation(SimulationEvent::Resume),
+        ("POST", "/step") => RemoteCommand::Simulation(SimulationEvent::Step),
+        ("POST", "/reset") => RemoteCommand::Simulation(SimulationEvent::Reset),
+        ("POST", "/command") => RemoteCommand::Command(request.body.trim().to_string()),
+        ("GET", "/screenshot") => {
+            let dimension = |name: &str| request.query.get(name)?.parse::<u32>().ok();
+            let size = dimension("width").zip(dimension("height"));
+            RemoteCommand::Screenshot {
+                size: size.map(|(width, height)| (width.clamp(1, 8192), height.clamp(1, 8192))),
+            }
+        }
+        (
+            _,
+            "/" | "/status" | "/metrics/stream" | "/params" | "/pause" | "/resume" | "/step"
+            | "/reset" | "/command" | "/screenshot",
+        ) => {
+            return write_error(&mut writer, 405, "method not allowed");
+        }
+        _ => return write_error(&mut writer, 404, "not found"),
+    };
+
+    let (reply, answer) = mpsc::channel();
+    let request = RemoteRequest { command, reply };
+    if shared.requests.lock().unwrap().send(request).is_err() {
+        return write_error(&mut writer, 503, "the app has stopped");
+    }
+    match answer.recv_timeout(REPLY_TIMEOUT) {
+        Ok(Ok(RemoteResponse::Json(json))) => {
+            write_response(&mut writer, 200, "application/json", json.as_bytes())
+        }
+        Ok(Ok(RemoteResponse::Png(png))) => write_response(&mut writer, 200, "image/png", &png),
+        Ok(Err(message)) => write_error(&mut writer, 400, &message),
+        Err(_) => write_error(&mut writer, 503, "the app did not respond"),
+    }
+}
+
+/// Check the token or, without one, that the request is addressed to the
+/// loopback server on `port`; for control requests also the origin
+fn authorize(
+    request: &Request,
+    config: &RemoteConfig,
+    port: u16,
+) -> std::result::Result<(), (u16, &'static str)> {
+    if let Some(token) = &config.token {
+        let bearer = request
+            .header("authorization")
+            .and_then(|value| value.strip_prefix("Bearer "));
+        let presented = bearer.or(request.query.get("token").map(String::as_str));
+        if presented != Some(token.as_str()) {
+            return Err((401, "missing or wrong token"));
+        }
+    } else if let Some(host) = request.header("host") {
+        // Browsers always send Host; a rebound domain keeps its own name there
+        if !is_loopback_host(host, port) {
+            return Err((403, "the Host header must name the local server"));
+        }
+    }
+
+    // Browsers send Origin with cross-site requests; only the dashboard itself may control
+    if request.method != "GET" {
+        if let Some(origin) = request.header("origin") {
+            let host = request.header("host").unwrap_or_default();
+            let origin_host = origin.split_once("://").map_or(origin, |(_, host)| host);
+            if origin_host != host {
+                return Err((403, "cross-origin requests are not allowed"));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether a `Host` header names this machine's loopback server on `port`
+fn is_loopback_host(host: &str, port: u16) -> bool {
+    let (name, host_port) = match host.rsplit_once(':') {
+        Some((name, host_port)) if !host_port.ends_with(']') => (name, host_port),
+        _ => (host, "80"),
+    };
+    host_port.parse() == Ok(port)
+        && matches!(
+            name.to_ascii_lowercase().as_str(),
+            "localhost" | "127.0.0.1" | "[::1]"
+        )
+}
+
+/// Send the status as server-sent events until the client disconnects
+fn stream_status(writer: &mut TcpStream, shared: &Shared) -> std::io::Result<()> {
+    write!(
+        writer,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-store\r\nConnection: keep-alive\r\n\r\n"
+    )?;
+    while !shared.shutdown.load(Ordering::Relaxed) {
+        let json = shared.status.lock().unwrap().to_json();
+        write!(writer, "data: {}\n\n", json)?;
+        writer.flush()?;
+        thread::sleep(shared.config.stream_interval);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn get(address: SocketAddr, request: &str) -> String {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_status_and_commands() {
+        let server = RemoteServer::start(RemoteConfig::localhost(0)).unwrap();
+        let address = server.address();
+        server.publish(RemoteStatus {
+            paused: true,
+            frame: 12,
+            metrics: BTreeMap::from([("energy".to_string(), 1.5)]),
+            ..Default::default()
+        });
+
+        let response = get(address, "GET /status HTTP/1.1\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.ends_with(
+            "{\"simulation\":null,\"paused\":true,\"frame\":12,\"time\":0,\"fps\":0,\"metrics\":{\"energy\":1.5}}"
+        ));
+        assert!(get(address, "GET /nope HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 404"));
+
+        // Control requests wait for the app to answer
+        let client = thread::spawn(move || {
+            get(
+                address,
+                &format!(
+                    "PUT /params/tau HTTP/1.1\r\nHost: localhost:{}\r\nContent-Length: 3\r\n\r\n0.6",
+                    address.port()
+                ),
+            )
+        });
+        let request = loop {
+            if let Some(request) = server.poll().pop() {
+                break request;
+            }
+            thread::sleep(Duration::from_millis(5));
+        };
+        assert_eq!(
+            request.command,
+            RemoteCommand::SetParam {
+                name: "tau".to_string(),
+                value: 0.6
+            }
+        );
+        request.respond(Ok(RemoteResponse::Json("{}".to_string())));
+        assert!(client.join().unwrap().starts_with("HTTP/1.1 200"));
+
+        let port = address.port();
+        let response = get(
+            address,
+            &format!("POST /pause HTTP/1.1\r\nHost: localhost:{port}\r\nOrigin: http://evil.example\r\n\r\n"),
+        );
+        assert!(response.starts_with("HTTP/1.1 403"));
+
+        // A page on a domain rebound to 127.0.0.1 sends its own name as Host
+        let response = get(
+            address,
+            &format!("POST /command HTTP/1.1\r\nHost: evil.example:{port}\r\nOrigin: http://evil.example:{port}\r\n\r\nquit"),
+        );
+        assert!(response.starts_with("HTTP/1.1 403"));
+        assert!(is_loopback_host(&format!("[::1]:{port}"), port));
+        assert!(!is_loopback_host("localhost", port));
+    }
+
+    #[test]
+    fn test_connections_are_capped() {
+        let server = RemoteServer::start(RemoteConfig::localhost(0)).unwrap();
+        let address = server.address();
+        // Idle clients hold their slots until they disconnect
+        let idle: Vec<_> = (0..MAX_CONNECTIONS)
+            .map(|_| TcpStream::connect(address).unwrap())
+            .collect();
+        while server.shared.connections.load(Ordering::Acquire) < MAX_CONNECTIONS {
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert!(get(address, "GET /status HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 503"));
+
+        drop(idle);
+        while server.shared.connections.load(Ordering::Acquire) > 0 {
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert!(get(address, "GET /status HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 200"));
+    }
+
+    #[test]
+    fn test_token_required() {
+        let public = RemoteConfig {
+            address: "0.0.0.0:0".parse().unwrap(),
+            token: None,
+            stream_interval: Duration::from_millis(10),
+        };
+        assert!(RemoteServer::start(public).is_err());
+
+        let server = RemoteServer::start(RemoteConfig::localhost(0).with_token("secret")).unwrap();
+        let address = server.address();
+        assert!(get(address, "GET /status HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 401"));
+        assert!(
+            get(address, "GET /status?token=secret HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 200")
+        );
+        assert!(get(
+            address,
+            "GET /status HTTP/1.1\r\nAuthorization: Bearer secret\r\n\r\n"
+        )
+        .starts_with("HTTP/1.1 200"));
+    }
+}
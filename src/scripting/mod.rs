@@ -0,0 +1,405 @@
+//! # Scripting Integration
+//!
+//! This module (behind the `scripting` feature) lets simulation logic live in
+//! a script instead of compiled Rust. A [`ScriptedSimulation`] implements
+//! [`Simulation`] by calling the script's `init`, `step` and `reset` functions,
+//! handing each call a [`ScriptHost`] with bindings for scene manipulation,
+//! parameter access and logging.
+//!
+//! The language itself is provided by a [`ScriptEngine`] implementation, which
+//! adapts an embedded interpreter (Rhai, Lua, ...) to these three calls and
+//! exposes the host methods to script code. Keeping the engine behind a trait
+//! means the scene bindings and simulation lifecycle are written once,
+//! whichever interpreter a project prefers.
+//!
+//! ## Scope
+//!
+//! Haggis does not bundle an interpreter. The `scripting` feature compiles
+//! this integration layer only; running scripts needs a [`ScriptEngine`]
+//! written against an interpreter crate such as `rhai` or `mlua`, in the
+//! application or a companion crate. `MyRhaiEngine` below stands for such an
+//! implementation.
+//!
+//! ## Script Functions
+//!
+//! - `init()` - called once when the simulation is attached
+//! - `step()` - called every update; read `host.delta_time()` for the timestep
+//! - `reset()` - called when the simulation is reset
+//!
+//! All three are optional.
+//!
+//! ## Usage
+//!
+//! ```ignore
+//! use haggis::scripting::ScriptedSimulation;
+//!
+//! let mut app = haggis::default();
+//! app.add_cube().with_name("ball");
+//!
+//! let mut sim = ScriptedSimulation::from_file(MyRhaiEngine::new(), "bounce.rhai")?;
+//! sim.set_param("gravity", -9.81); // batch experiments can sweep this from Rust
+//! app.attach_simulation(sim);
+//! app.run();
+//! ```
+//!
+//! [`Simulation`]: crate::simulation::traits::Simulation
+
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use imgui::Ui;
+
+use crate::gfx::scene::Scene;
+use crate::simulation::traits::Simulation;
+
+/// Maximum number of script log lines kept for the UI
+const MAX_LOG_LINES: usize = 100;
+
+/// An embedded scripting language
+pub trait ScriptEngine {
+    /// Language name, shown in the UI (e.g. "Rhai")
+    fn language(&self) -> &str;
+
+    /// Compile and load script source, replacing any previous script
+    fn load(&mut self, source: &str) -> Result<(), String>;
+
+    /// Call a script function with access to the host bindings.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(false)` if the script does not define `function`
+    fn call(&mut self, function: &str, host: &mut ScriptHost) -> Result<bool, String>;
+}
+
+/// Bindings available to scripts during a call
+pub struct ScriptHost<'a> {
+    scene: &'a mut Scene,
+    params: &'a mut BTreeMap<String, f32>,
+    log: &'a mut Vec<String>,
+    time: f32,
+    delta_time: f32,
+}
+
+impl ScriptHost<'_> {
+    /// Simulation time in seconds since the last reset
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// Timestep of the current `step` call (0 in `init` and `reset`)
+    pub fn delta_time(&self) -> f32 {
+        self.delta_time
+    }
+
+    /// Number of objects in the scene
+    pub fn object_count(&self) -> usize {
+        self.scene.objects.len()
+    }
+
+    /// Index of the first object with this name
+    pub fn find_object(&self, name: &str) -> Option<usize> {
+        self.scene.objects.iter().position(|object| object.name == name)
+    }
+
+    /// Object position
+    pub fn object_position(&self, index: usize) -> Option<[f32; 3]> {
+        self.scene
+            .objects
+            .get(index)
+            .map(|object| object.ui_transform.position)
+    }
+
+    /// Move an object, returning `false` if the index is out of range
+    pub fn set_object_position(&mut self, index: usize, position: [f32; 3]) -> bool {
+        let Some(object) = self.scene.objects.get_mut(index) else {
+            return false;
+        };
+        object.ui_transform.position = position;
+        object.apply_ui_transform();
+        true
+    }
+
+    /// Set an object's rotation in degrees around X, Y and Z
+    pub fn set_object_rotation(&mut self, index: usize, rotation: [f32; 3]) -> bool {
+        let Some(object) = self.scene.objects.get_mut(index) else {
+            return false;
+        };
+        object.ui_transform.rotation = rotation;
+        object.apply_ui_transform();
+        true
+    }
+
+    /// Show or hide an object
+    pub fn set_object_visible(&mut self, index: usize, visible: bool) -> bool {
+        let Some(object) = self.scene.objects.get_mut(index) else {
+            return false;
+        };
+        object.visible = visible;
+        true
+    }
+
+    /// Value of a simulation parameter
+    pub fn param(&self, name: &str) -> Option<f32> {
+        self.params.get(name).copied()
+    }
+
+    /// Set a simulation parameter, creating it if needed.
+    ///
+    /// Parameters show up as editable fields in the simulation's UI panel.
+    pub fn set_param(&mut self, name: &str, value: f32) {
+        self.params.insert(name.to_string(), value);
+    }
+
+    /// Write a line to the script log shown in the UI
+    pub fn log(&mut self, message: &str) {
+        self.log.push(message.to_string());
+        if self.log.len() > MAX_LOG_LINES {
+            self.log.remove(0);
+        }
+    }
+
+    /// Direct access to the scene for bindings not covered above
+    pub fn scene(&mut self) -> &mut Scene {
+        self.scene
+    }
+}
+
+/// A simulation whose logic is a script
+pub struct ScriptedSimulation<E: ScriptEngine> {
+    engine: E,
+    name: String,
+    path: Option<PathBuf>,
+    params: BTreeMap<String, f32>,
+    log: Vec<String>,
+    last_error: Option<String>,
+    time: f32,
+    running: bool,
+}
+
+impl<E: ScriptEngine> ScriptedSimulation<E> {
+    /// Create a scripted simulation from source code.
+    ///
+    /// # Errors
+    ///
+    /// Returns the engine's error if the script fails to compile
+    pub fn new(mut engine: E, source: &str) -> Result<Self, String> {
+        engine.load(source)?;
+        Ok(Self {
+            name: format!("{} Script", engine.language()),
+            engine,
+            path: None,
+            params: BTreeMap::new(),
+            log: Vec::new(),
+            last_error: None,
+            time: 0.0,
+            running: true,
+        })
+    }
+
+    /// Create a scripted simulation from a script file.
+    ///
+    /// The file can be reloaded at runtime from the simulation's UI panel.
+    pub fn from_file<P: Into<PathBuf>>(engine: E, path: P) -> Result<Self, String> {
+        let path = path.into();
+        let source = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read script {}: {}", path.display(), e))?;
+        let mut simulation = Self::new(engine, &source)?;
+        if let Some(file_name) = path.file_name() {
+            simulation.name = file_name.to_string_lossy().into_owned();
+        }
+        simulation.path = Some(path);
+        Ok(simulation)
+    }
+
+    /// Set the name shown in the UI
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// Set a parameter before or while running
+    pub fn set_param(&mut self, name: &str, value: f32) {
+        self.params.insert(name.to_string(), value);
+    }
+
+    /// All parameters, sorted by name
+    pub fn params(&self) -> &BTreeMap<String, f32> {
+        &self.params
+    }
+
+    /// The most recent script error, if any
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    /// Replace the script source, keeping parameters
+    pub fn reload(&mut self, source: &str) -> Result<(), String> {
+        self.engine.load(source)?;
+        self.last_error = None;
+        Ok(())
+    }
+
+    /// Reload the script from its file, if it was created with [`from_file`](Self::from_file)
+    pub fn reload_file(&mut self) -> Result<(), String> {
+        let path = self
+            .path
+            .clone()
+            .ok_or_else(|| "Script was not loaded from a file".to_string())?;
+        let source = std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read script {}: {}", path.display(), e))?;
+        self.reload(&source)
+    }
+
+    /// Call a script function, pausing the simulation if it fails
+    fn call(&mut self, function: &str, scene: &mut Scene, delta_time: f32) {
+        let mut host = ScriptHost {
+            scene,
+            params: &mut self.params,
+            log: &mut self.log,
+            time: self.time,
+            delta_time,
+        };
+
+        if let Err(error) = self.engine.call(function, &mut host) {
+            tracing::error!("Script error in {}(): {}", function, error);
+
+            self.last_error = Some(format!("{}(): {}", function, error));
+            self.running = false;
+        }
+    }
+}
+
+impl<E: ScriptEngine + 'static> Simulation for ScriptedSimulation<E> {
+    fn initialize(&mut self, scene: &mut Scene) {
+        self.call("init", scene, 0.0);
+    }
+
+    fn update(&mut self, delta_time: f32, scene: &mut Scene) {
+        if !self.running {
+            return;
+        }
+        self.time += delta_time;
+        self.call("step", scene, delta_time);
+    }
+
+    fn render_ui(&mut self, ui: &Ui) {
+        let title = self.name.clone();
+        ui.window(&title)
+            .size([300.0, 250.0], imgui::Condition::FirstUseEver)
+            .build(|| {
+                ui.checkbox("Running", &mut self.running);
+                ui.text(format!("Time: {:.2}s", self.time));
+
+                if self.path.is_some() && ui.button("Reload Script") {
+                    if let Err(error) = self.reload_file() {
+                        self.last_error = Some(error);
+                    }
+                }
+
+                if !self.params.is_empty() {
+                    ui.separator();
+                    for (name, value) in self.params.iter_mut() {
+                        imgui::Drag::new(name).speed(0.01).build(ui, value);
+                    }
+                }
+
+                if let Some(error) = &self.last_error {
+                    ui.separator();
+                    ui.text_colored([1.0, 0.4, 0.4, 1.0], error);
+                }
+
+                if !self.log.is_empty() {
+                    ui.separator();
+                    for line in self.log.iter().rev().take(10).rev() {
+                        ui.text(line);
+                    }
+                }
+            });
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn is_running(&self) -> bool {
+        self.running
+    }
+
+    fn set_running(&mut self, running: bool) {
+        self.running = running;
+    }
+
+    fn reset(&mut self, scene: &mut Scene) {
+        self.time = 0.0;
+        self.last_error = None;
+        self.running = true;
+        self.call("reset", scene, 0.0);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gfx::camera::{
+        camera_controller::CameraController, camera_utils::CameraManager,
+        orbit_camera::OrbitCamera,
+    };
+    use cgmath::Vector3;
+
+    /// Engine whose only function, `step`, counts calls in a parameter
+    struct CountingEngine {
+        fail: bool,
+    }
+
+    impl ScriptEngine for CountingEngine {
+        fn language(&self) -> &str {
+            "Test"
+        }
+
+        fn load(&mut self, _source: &str) -> Result<(), String> {
+            Ok(())
+        }
+
+        fn call(&mut self, function: &str, host: &mut ScriptHost) -> Result<bool, String> {
+            if function != "step" {
+                return Ok(false);
+            }
+            if self.fail {
+                return Err("boom".to_string());
+            }
+            let steps = host.param("steps").unwrap_or(0.0);
+            host.set_param("steps", steps + 1.0);
+            Ok(true)
+        }
+    }
+
+    fn scene() -> Scene {
+        let camera = OrbitCamera::new(8.0, 0.4, 0.2, Vector3::new(0.0, 0.0, 0.0), 1.0);
+        Scene::new(CameraManager::new(camera, CameraController::new(0.005, 0.1)))
+    }
+
+    #[test]
+    fn test_step_calls_script_with_params() {
+        let mut scene = scene();
+        let mut sim = ScriptedSimulation::new(CountingEngine { fail: false }, "").unwrap();
+        sim.initialize(&mut scene);
+        sim.update(0.1, &mut scene);
+        sim.update(0.1, &mut scene);
+        assert_eq!(sim.params().get("steps"), Some(&2.0));
+        assert!((sim.time - 0.2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_script_error_pauses_simulation() {
+        let mut scene = scene();
+        let mut sim = ScriptedSimulation::new(CountingEngine { fail: true }, "").unwrap();
+        sim.update(0.1, &mut scene);
+        assert!(!sim.is_running());
+        assert_eq!(sim.last_error(), Some("step(): boom"));
+    }
+}
@@ -0,0 +1,244 @@
+//! # Agent-Based Simulation
+//!
+//! Building blocks for simulations of many individually steered agents, such
+//! as flocks, schools and crowds:
+//!
+//! - [`Swarm`] - agent positions and velocities with perception queries
+//! - [`SpatialHash`] - uniform grid behind the neighbor queries
+//! - [`steering`] - seek, flee, arrive, separation, alignment, cohesion and
+//!   containment behaviors
+//!
+//! Agents are drawn with [`AgentView`](crate::visualization::AgentView), as
+//! instanced darts pointing along their velocity. For tens of thousands of
+//! agents, the [`Boids`](crate::simulation::templates::Boids) template runs
+//! the whole flock in compute shaders and draws it straight from its buffer.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::simulation::agents::{steering, Swarm};
+//! use cgmath::Vector3;
+//!
+//! let mut swarm = Swarm::new(1.0);
+//! swarm.scatter(500, [-5.0, -5.0, 0.0], [5.0, 5.0, 5.0], 2.0);
+//!
+//! let (min, max) = (Vector3::new(-5.0, -5.0, 0.0), Vector3::new(5.0, 5.0, 5.0));
+//! swarm.step(1.0 / 60.0, |swarm, agent| {
+//!     let neighbors = swarm.neighbors(agent);
+//!     steering::separation(swarm, agent, &neighbors, 0.4) * 1.5
+//!         + steering::alignment(swarm, agent, &neighbors)
+//!         + steering::cohesion(swarm, agent, &neighbors)
+//!         + steering::contain(swarm, agent, min, max, 1.0) * 2.0
+//! });
+//! ```
+
+pub mod spatial_hash;
+pub mod steering;
+
+pub use spatial_hash::SpatialHash;
+
+use cgmath::{InnerSpace, Vector3};
+use rand::Rng;
+
+use crate::gfx::rendering::AgentInstance;
+
+/// What an agent can see of its neighbors
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Perception {
+    pub radius: f32,
+    /// Full viewing angle in radians around the heading; `2π` sees all around
+    pub field_of_view: f32,
+}
+
+impl Perception {
+    pub fn new(radius: f32) -> Self {
+        Self {
+            radius,
+            field_of_view: std::f32::consts::TAU,
+        }
+    }
+
+    /// Limit perception to a cone of `degrees` around the heading
+    pub fn with_field_of_view(mut self, degrees: f32) -> Self {
+        self.field_of_view = degrees.clamp(0.0, 360.0).to_radians();
+        self
+    }
+
+    /// Whether `offset` (from the agent to a neighbor) lies within the view
+    /// cone of an agent moving along `velocity`
+    pub fn sees(&self, velocity: Vector3<f32>, offset: Vector3<f32>) -> bool {
+        if self.field_of_view >= std::f32::consts::TAU {
+            return true;
+        }
+        let (speed2, distance2) = (velocity.magnitude2(), offset.magnitude2());
+        if speed2 < 1e-12 || distance2 < 1e-12 {
+            return true;
+        }
+        velocity.dot(offset) / (speed2 * distance2).sqrt() >= (self.field_of_view * 0.5).cos()
+    }
+}
+
+/// Set of agents moved by steering forces
+///
+/// Forces are limited to `max_force` and speeds to `max_speed`. The neighbor
+/// index is refreshed after every [`step`](Self::step), so
+/// [`neighbors`](Self::neighbors) is valid between steps.
+#[derive(Debug, Clone)]
+pub struct Swarm {
+    pub positions: Vec<Vector3<f32>>,
+    pub velocities: Vec<Vector3<f32>>,
+    pub max_speed: f32,
+    pub max_force: f32,
+    perception: Perception,
+    index: SpatialHash,
+}
+
+impl Swarm {
+    /// Empty swarm whose agents see neighbors within `perception_radius`
+    pub fn new(perception_radius: f32) -> Self {
+        Self {
+            positions: Vec::new(),
+            velocities: Vec::new(),
+            max_speed: 4.0,
+            max_force: 8.0,
+            perception: Perception::new(perception_radius),
+            index: SpatialHash::new(perception_radius),
+        }
+    }
+
+    pub fn with_perception(mut self, perception: Perception) -> Self {
+        self.set_perception(perception);
+        self
+    }
+
+    pub fn with_limits(mut self, max_speed: f32, max_force: f32) -> Self {
+        self.max_speed = max_speed;
+        self.max_force = max_force;
+        self
+    }
+
+    pub fn perception(&self) -> Perception {
+        self.perception
+    }
+
+    /// Change the perception; the index is rebuilt for the new radius
+    pub fn set_perception(&mut self, perception: Perception) {
+        self.perception = perception;
+        self.index.set_cell_size(perception.radius.max(1e-3));
+        self.index.rebuild(&self.positions);
+    }
+
+    /// Add an agent and return its index
+    pub fn add(
+        &mut self,
+        position: impl Into<Vector3<f32>>,
+        velocity: impl Into<Vector3<f32>>,
+    ) -> usize {
+        let index = self.positions.len();
+        let position = position.into();
+        self.positions.push(position);
+        self.velocities.push(velocity.into());
+        self.index.insert(index, position);
+        index
+    }
+
+    /// Add `count` agents at random positions in the box `min..max`, moving in
+    /// random directions at `speed`
+    pub fn scatter(&mut self, count: usize, min: [f32; 3], max: [f32; 3], speed: f32) {
+        let mut rng = rand::rng();
+        for _ in 0..count {
+            let position: [f32; 3] =
+                std::array::from_fn(|axis| rng.random_range(min[axis]..=max[axis]));
+            let direction = Vector3::new(
+                rng.random_range(-1.0..=1.0f32),
+                rng.random_range(-1.0..=1.0f32),
+                rng.random_range(-1.0..=1.0f32),
+            );
+            let velocity = if direction.magnitude2() > 1e-6 {
+                direction.normalize() * speed
+            } else {
+                Vector3::new(speed, 0.0, 0.0)
+            };
+            self.add(position, velocity);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Agents `agent` perceives, itself excluded
+    pub fn neighbors(&self, agent: usize) -> Vec<usize> {
+        let position = self.positions[agent];
+        let velocity = self.velocities[agent];
+        self.index
+            .query(&self.positions, position, self.perception.radius)
+            .filter(|&other| {
+                other != agent
+                    && self
+                        .perception
+                        .sees(velocity, self.positions[other] - position)
+            })
+            .collect()
+    }
+
+    /// Advance by `dt` seconds, steering every agent with the force `steer` returns
+    ///
+    /// All forces are computed before any agent moves, so the result doesn't
+    /// depend on the agent order.
+    pub fn step(&mut self, dt: f32, mut steer: impl FnMut(&Swarm, usize) -> Vector3<f32>) {
+        let forces: Vec<Vector3<f32>> = (0..self.len())
+            .map(|agent| steering::limit(steer(self, agent), self.max_force))
+            .collect();
+        for ((position, velocity), force) in self
+            .positions
+            .iter_mut()
+            .zip(&mut self.velocities)
+            .zip(forces)
+        {
+            *velocity = steering::limit(*velocity + force * dt, self.max_speed);
+            *position += *velocity * dt;
+        }
+        self.index.rebuild(&self.positions);
+    }
+
+    /// Instances for an [`AgentView`](crate::visualization::AgentView)
+    pub fn instances(&self, size: f32, color: [f32; 4]) -> Vec<AgentInstance> {
+        self.positions
+            .iter()
+            .zip(&self.velocities)
+            .map(|(&position, &velocity)| {
+                AgentInstance::new(position.into(), velocity.into(), size, color)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_neighbors_respect_radius_and_field_of_view() {
+        let mut swarm =
+            Swarm::new(1.0).with_perception(Perception::new(1.0).with_field_of_view(180.0));
+        let agent = swarm.add([0.0, 0.0, 0.0], [1.0, 0.0, 0.0]);
+        let ahead = swarm.add([0.5, 0.0, 0.0], [0.0, 0.0, 0.0]);
+        swarm.add([-0.5, 0.0, 0.0], [0.0, 0.0, 0.0]);
+        swarm.add([2.0, 0.0, 0.0], [0.0, 0.0, 0.0]);
+
+        assert_eq!(swarm.neighbors(agent), vec![ahead]);
+
+        swarm.step(0.1, |_, _| Vector3::new(0.0, 100.0, 0.0));
+        // Force limited to max_force, speed to max_speed
+        assert!((swarm.velocities[agent].y - 0.8).abs() < 1e-5);
+        assert!(swarm
+            .velocities
+            .iter()
+            .all(|v| v.magnitude() <= swarm.max_speed + 1e-5));
+    }
+}
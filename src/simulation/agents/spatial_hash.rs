@@ -0,0 +1,119 @@
+//! Uniform grid hash for neighbor queries on the CPU
+
+use std::collections::HashMap;
+
+use cgmath::{InnerSpace, Vector3};
+
+/// Buckets point indices by grid cell, so a radius query only visits the
+/// cells overlapping the query sphere
+///
+/// With a cell size close to the query radius, each query touches 27 cells at
+/// most, whatever the total number of points.
+#[derive(Debug, Clone)]
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<[i32; 3], Vec<usize>>,
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32) -> Self {
+        assert!(cell_size > 0.0, "spatial hash cell size must be positive");
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+        }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    /// Change the cell size; takes effect on the next [`rebuild`](Self::rebuild)
+    pub fn set_cell_size(&mut self, cell_size: f32) {
+        assert!(cell_size > 0.0, "spatial hash cell size must be positive");
+        self.cell_size = cell_size;
+    }
+
+    /// Grid cell containing `point`
+    pub fn cell_of(&self, point: Vector3<f32>) -> [i32; 3] {
+        let cell = point / self.cell_size;
+        [
+            cell.x.floor() as i32,
+            cell.y.floor() as i32,
+            cell.z.floor() as i32,
+        ]
+    }
+
+    /// Replace the contents with `positions`, indexed by their position in the slice
+    pub fn rebuild(&mut self, positions: &[Vector3<f32>]) {
+        // Keep the bucket allocations of cells that stay occupied
+        for bucket in self.cells.values_mut() {
+            bucket.clear();
+        }
+        for (index, &position) in positions.iter().enumerate() {
+            self.insert(index, position);
+        }
+        self.cells.retain(|_, bucket| !bucket.is_empty());
+    }
+
+    pub fn insert(&mut self, index: usize, position: Vector3<f32>) {
+        let cell = self.cell_of(position);
+        self.cells.entry(cell).or_default().push(index);
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+    }
+
+    /// Indices of all points within `radius` of `center`
+    ///
+    /// `positions` must be the slice the hash was built from.
+    pub fn query<'a>(
+        &'a self,
+        positions: &'a [Vector3<f32>],
+        center: Vector3<f32>,
+        radius: f32,
+    ) -> impl Iterator<Item = usize> + 'a {
+        let offset = Vector3::new(radius, radius, radius);
+        let min = self.cell_of(center - offset);
+        let max = self.cell_of(center + offset);
+        (min[0]..=max[0])
+            .flat_map(move |x| {
+                (min[1]..=max[1]).flat_map(move |y| (min[2]..=max[2]).map(move |z| [x, y, z]))
+            })
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .filter(move |&index| (positions[index] - center).magnitude2() <= radius * radius)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_matches_brute_force() {
+        let positions: Vec<Vector3<f32>> = (0..200)
+            .map(|i| {
+                let t = i as f32;
+                Vector3::new(
+                    (t * 0.37).sin() * 3.0,
+                    (t * 0.91).cos() * 3.0,
+                    (t * 0.13) % 2.0 - 1.0,
+                )
+            })
+            .collect();
+        let mut hash = SpatialHash::new(0.5);
+        hash.rebuild(&positions);
+
+        let center = Vector3::new(0.5, -0.25, 0.0);
+        let mut found: Vec<usize> = hash.query(&positions, center, 1.2).collect();
+        found.sort_unstable();
+        let expected: Vec<usize> = (0..positions.len())
+            .filter(|&i| (positions[i] - center).magnitude() <= 1.2)
+            .collect();
+        assert!(!expected.is_empty());
+        assert_eq!(found, expected);
+    }
+}
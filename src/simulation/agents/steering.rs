@@ -0,0 +1,167 @@
+//! Steering behaviors
+//!
+//! Reynolds-style behaviors returning a steering force (a change of
+//! velocity per second). Combine them with weights and pass the sum to
+//! [`Swarm::step`], which limits it to the swarm's maximum force.
+//!
+//! The flocking rules take the neighbor indices from
+//! [`Swarm::neighbors`], so they only see what the agent perceives.
+
+use cgmath::{InnerSpace, Vector3, Zero};
+
+use super::Swarm;
+
+/// `vector` shortened to at most `max` length
+pub fn limit(vector: Vector3<f32>, max: f32) -> Vector3<f32> {
+    let length2 = vector.magnitude2();
+    if length2 > max * max {
+        vector * (max / length2.sqrt())
+    } else {
+        vector
+    }
+}
+
+/// Force turning `velocity` towards moving along `direction` at full speed
+///
+/// Zero if `direction` is zero, so an empty rule doesn't brake the agent.
+pub fn steer_towards(
+    direction: Vector3<f32>,
+    velocity: Vector3<f32>,
+    max_speed: f32,
+    max_force: f32,
+) -> Vector3<f32> {
+    if direction.magnitude2() < 1e-12 {
+        return Vector3::zero();
+    }
+    limit(direction.normalize() * max_speed - velocity, max_force)
+}
+
+/// Head straight for `target`
+pub fn seek(swarm: &Swarm, agent: usize, target: Vector3<f32>) -> Vector3<f32> {
+    steer_towards(
+        target - swarm.positions[agent],
+        swarm.velocities[agent],
+        swarm.max_speed,
+        swarm.max_force,
+    )
+}
+
+/// Head straight away from `threat`
+pub fn flee(swarm: &Swarm, agent: usize, threat: Vector3<f32>) -> Vector3<f32> {
+    steer_towards(
+        swarm.positions[agent] - threat,
+        swarm.velocities[agent],
+        swarm.max_speed,
+        swarm.max_force,
+    )
+}
+
+/// Seek `target`, slowing down linearly within `slowing_radius` of it
+pub fn arrive(
+    swarm: &Swarm,
+    agent: usize,
+    target: Vector3<f32>,
+    slowing_radius: f32,
+) -> Vector3<f32> {
+    let offset = target - swarm.positions[agent];
+    let distance = offset.magnitude();
+    if distance < 1e-6 {
+        return limit(-swarm.velocities[agent], swarm.max_force);
+    }
+    let speed = swarm.max_speed * (distance / slowing_radius.max(1e-6)).min(1.0);
+    limit(
+        offset * (speed / distance) - swarm.velocities[agent],
+        swarm.max_force,
+    )
+}
+
+/// Steer away from neighbors closer than `radius`, weighted by `1 / distance`
+pub fn separation(swarm: &Swarm, agent: usize, neighbors: &[usize], radius: f32) -> Vector3<f32> {
+    let position = swarm.positions[agent];
+    let away = neighbors
+        .iter()
+        .map(|&other| position - swarm.positions[other])
+        .filter(|offset| {
+            let distance2 = offset.magnitude2();
+            distance2 > 1e-12 && distance2 < radius * radius
+        })
+        .map(|offset| offset / offset.magnitude2())
+        .fold(Vector3::zero(), |sum, push| sum + push);
+    steer_towards(
+        away,
+        swarm.velocities[agent],
+        swarm.max_speed,
+        swarm.max_force,
+    )
+}
+
+/// Match the average heading of the neighbors
+pub fn alignment(swarm: &Swarm, agent: usize, neighbors: &[usize]) -> Vector3<f32> {
+    let heading = neighbors
+        .iter()
+        .fold(Vector3::zero(), |sum, &other| sum + swarm.velocities[other]);
+    steer_towards(
+        heading,
+        swarm.velocities[agent],
+        swarm.max_speed,
+        swarm.max_force,
+    )
+}
+
+/// Move towards the center of the neighbors
+pub fn cohesion(swarm: &Swarm, agent: usize, neighbors: &[usize]) -> Vector3<f32> {
+    if neighbors.is_empty() {
+        return Vector3::zero();
+    }
+    let center = neighbors
+        .iter()
+        .fold(Vector3::zero(), |sum, &other| sum + swarm.positions[other])
+        / neighbors.len() as f32;
+    seek(swarm, agent, center)
+}
+
+/// Turn back when closer than `margin` to the faces of the box `min..max`
+pub fn contain(
+    swarm: &Swarm,
+    agent: usize,
+    min: Vector3<f32>,
+    max: Vector3<f32>,
+    margin: f32,
+) -> Vector3<f32> {
+    let position = swarm.positions[agent];
+    let mut inward = Vector3::zero();
+    for axis in 0..3 {
+        if position[axis] < min[axis] + margin {
+            inward[axis] = 1.0;
+        } else if position[axis] > max[axis] - margin {
+            inward[axis] = -1.0;
+        }
+    }
+    steer_towards(
+        inward,
+        swarm.velocities[agent],
+        swarm.max_speed,
+        swarm.max_force,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flocking_rules_point_the_right_way() {
+        let mut swarm = Swarm::new(2.0);
+        let agent = swarm.add([0.0, 0.0, 0.0], [0.0, 0.0, 0.0]);
+        let other = swarm.add([0.5, 0.0, 0.0], [0.0, 1.0, 0.0]);
+
+        assert!(separation(&swarm, agent, &[other], 1.0).x < 0.0);
+        assert!(cohesion(&swarm, agent, &[other]).x > 0.0);
+        assert!(alignment(&swarm, agent, &[other]).y > 0.0);
+        assert_eq!(separation(&swarm, agent, &[other], 0.25), Vector3::zero());
+
+        // From rest, seeking asks for full speed towards the target
+        let force = seek(&swarm, agent, Vector3::new(0.0, 0.0, 100.0));
+        assert!((force - Vector3::new(0.0, 0.0, swarm.max_speed)).magnitude() < 1e-4);
+    }
+}
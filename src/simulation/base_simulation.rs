@@ -61,6 +61,31 @@ impl BaseSimulation {
         self.visualization_manager.get_visualization_planes()
     }
 
+    /// Get world-space lines from this simulation's visualizations
+    pub fn get_visualization_lines(&self) -> Vec<crate::gfx::rendering::LineVertex> {
+        self.visualization_manager.get_visualization_lines()
+    }
+
+    /// Get clip planes from this simulation's cut planes
+    pub fn get_clip_planes(&self) -> Vec<crate::gfx::rendering::ClipPlane> {
+        self.visualization_manager.get_clip_planes()
+    }
+
+    /// Get agent batches from this simulation's agent views
+    pub fn get_agent_batches(&self) -> Vec<crate::gfx::rendering::AgentBatch> {
+        self.visualization_manager.get_agent_batches()
+    }
+
+    /// Get volume batches from this simulation's volume views
+    pub fn get_volume_batches(&self) -> Vec<crate::gfx::rendering::VolumeBatch> {
+        self.visualization_manager.get_volume_batches()
+    }
+
+    /// Draw world-space labels of this simulation's visualizations
+    pub fn render_visualization_labels(&self, ui: &Ui, view_proj: cgmath::Matrix4<f32>) {
+        self.visualization_manager.render_labels(ui, view_proj);
+    }
+
     /// Update all visualization components
     pub fn update_visualizations(
         &mut self,
@@ -0,0 +1,661 @@
+//! Boundary conditions for grid simulations
+//!
+//! [`BoundaryConditions`] describes a 3D grid's boundaries once, and shaders
+//! and the CPU share that description instead of repeating inlet and
+//! bounce-back code in every solver:
+//!
+//! - One [`BoundaryCondition`] per face of the domain, periodic by default
+//! - Solid obstacle cells inside the domain, always no-slip
+//! - A bit-packed mask from [`mask`](BoundaryConditions::mask) with 4 bits per
+//!   cell: `0` for fluid, `1..=6` for the face a cell lies on, `7` for
+//!   obstacles. Cells on edges and corners belong to the first non-periodic
+//!   face in [`BoxFace::ALL`] order.
+//! - The condition of each face, with its inlet velocity or outlet density, in
+//!   a uniform from [`params`](BoundaryConditions::params), so conditions can
+//!   change without a new mask (except to or from [`BoundaryCondition::Periodic`])
+//!
+//! [`BoundaryBuffers`] holds both on the GPU, and
+//! [`BoundaryConditions::wgsl`] declares them for a shader with lookup
+//! functions. Lattice Boltzmann solvers add [`D3Q19_WGSL`] and
+//! [`D3Q19_BOUNDARY_WGSL`], which apply bounce-back, Zou-He and mirror
+//! conditions to a cell's distributions:
+//!
+//! ```wgsl
+//! var f: array<f32, 19>;
+//! // ... load f, compute density `rho` and velocity `u` ...
+//! if (!boundary_apply_d3q19(boundary_code(cell), &f, &rho, &u)) {
+//!     for (var i = 0u; i < 19u; i++) {
+//!         f[i] -= omega * (f[i] - d3q19_equilibrium(i, rho, u));
+//!     }
+//! }
+//! ```
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::simulation::boundary::{BoundaryCondition, BoundaryConditions};
+//! use haggis::visualization::BoxFace;
+//!
+//! let boundaries = BoundaryConditions::new([128, 64, 64])
+//!     .with_face(BoxFace::NegX, BoundaryCondition::VelocityInlet { velocity: [0.05, 0.0, 0.0] })
+//!     .with_face(BoxFace::PosX, BoundaryCondition::PressureOutlet { density: 1.0 })
+//!     .with_face(BoxFace::NegY, BoundaryCondition::NoSlip)
+//!     .with_face(BoxFace::PosY, BoundaryCondition::NoSlip);
+//! let mask = boundaries.mask();
+//! let shader = format!("{}{}", boundaries.wgsl(0, 3), haggis::simulation::boundary::D3Q19_WGSL);
+//! ```
+
+use wgpu::{Buffer, Device, Queue};
+
+use crate::simulation::params::ParamsUniform;
+use crate::visualization::BoxFace;
+
+/// Mask value of solid obstacle cells
+pub const OBSTACLE: u32 = 7;
+
+/// Condition on one face of the domain
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum BoundaryCondition {
+    /// Wraps around to the opposite face; the face cells are fluid
+    #[default]
+    Periodic,
+    /// Solid wall with zero velocity (bounce-back)
+    NoSlip,
+    /// Prescribed velocity, density follows from the flow (Zou-He)
+    VelocityInlet { velocity: [f32; 3] },
+    /// Prescribed density (pressure), normal velocity follows from the flow
+    /// (Zou-He)
+    PressureOutlet { density: f32 },
+    /// Mirror plane: no flow through the face, free slip along it
+    Symmetry,
+}
+
+impl BoundaryCondition {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BoundaryCondition::Periodic => "Periodic",
+            BoundaryCondition::NoSlip => "No-Slip",
+            BoundaryCondition::VelocityInlet { .. } => "Velocity Inlet",
+            BoundaryCondition::PressureOutlet { .. } => "Pressure Outlet",
+            BoundaryCondition::Symmetry => "Symmetry",
+        }
+    }
+
+    /// Kind constant and values (velocity, density) as seen by the shader
+    fn to_gpu(self) -> (u32, [f32; 4]) {
+        match self {
+            BoundaryCondition::Periodic => (0, [0.0; 4]),
+            BoundaryCondition::NoSlip => (1, [0.0; 4]),
+            BoundaryCondition::VelocityInlet {
+                velocity: [x, y, z],
+            } => (2, [x, y, z, 1.0]),
+            BoundaryCondition::PressureOutlet { density } => (3, [0.0, 0.0, 0.0, density]),
+            BoundaryCondition::Symmetry => (4, [0.0; 4]),
+        }
+    }
+}
+
+/// Uniform block with the condition of every face
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct BoundaryParams {
+    /// Velocity and density per face, in [`BoxFace::ALL`] order
+    values: [[f32; 4]; 6],
+    /// Kind per face, padded to two `vec4<u32>`
+    kinds: [u32; 8],
+}
+
+/// Face conditions and obstacles of a 3D grid
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoundaryConditions {
+    size: [u32; 3],
+    faces: [BoundaryCondition; 6],
+    /// One bit per cell, x fastest
+    obstacles: Vec<u32>,
+}
+
+impl BoundaryConditions {
+    /// Periodic boundaries without obstacles on a grid of `size` cells
+    pub fn new(size: [u32; 3]) -> Self {
+        assert!(
+            size.iter().all(|&s| s > 0),
+            "boundary grid must not be empty"
+        );
+        Self {
+            size,
+            faces: [BoundaryCondition::Periodic; 6],
+            obstacles: vec![0; (size.iter().product::<u32>() as usize).div_ceil(32)],
+        }
+    }
+
+    pub fn with_face(mut self, face: BoxFace, condition: BoundaryCondition) -> Self {
+        self.set_face(face, condition);
+        self
+    }
+
+    /// Solid cells, one bit per cell with x fastest, 32 cells per word
+    ///
+    /// # Panics
+    ///
+    /// If `bits` doesn't have a bit for every cell
+    pub fn with_obstacles(mut self, bits: Vec<u32>) -> Self {
+        assert_eq!(
+            bits.len(),
+            self.obstacles.len(),
+            "obstacle bits must cover the boundary grid"
+        );
+        self.obstacles = bits;
+        self
+    }
+
+    /// Change the condition of a face
+    ///
+    /// Switching to or from [`BoundaryCondition::Periodic`] changes the
+    /// [`mask`](Self::mask); other changes only the [`params`](Self::params).
+    pub fn set_face(&mut self, face: BoxFace, condition: BoundaryCondition) {
+        self.faces[face as usize] = condition;
+    }
+
+    pub fn face(&self, face: BoxFace) -> BoundaryCondition {
+        self.faces[face as usize]
+    }
+
+    pub fn size(&self) -> [u32; 3] {
+        self.size
+    }
+
+    fn cell_index(&self, [x, y, z]: [u32; 3]) -> usize {
+        (x + self.size[0] * (y + self.size[1] * z)) as usize
+    }
+
+    pub fn set_obstacle(&mut self, cell: [u32; 3], solid: bool) {
+        let index = self.cell_index(cell);
+        if solid {
+            self.obstacles[index / 32] |= 1 << (index % 32);
+        } else {
+            self.obstacles[index / 32] &= !(1 << (index % 32));
+        }
+    }
+
+    pub fn is_obstacle(&self, cell: [u32; 3]) -> bool {
+        let index = self.cell_index(cell);
+        self.obstacles[index / 32] & (1 << (index % 32)) != 0
+    }
+
+    /// Mask value of a cell: `0` fluid, `1 + face` on a face, [`OBSTACLE`]
+    pub fn cell_code(&self, cell: [u32; 3]) -> u32 {
+        if self.is_obstacle(cell) {
+            return OBSTACLE;
+        }
+        BoxFace::ALL
+            .into_iter()
+            .position(|face| {
+                let axis = face as usize / 2;
+                let edge = if face as usize % 2 == 1 {
+                    self.size[axis] - 1
+                } else {
+                    0
+                };
+                self.faces[face as usize] != BoundaryCondition::Periodic && cell[axis] == edge
+            })
+            .map_or(0, |face| face as u32 + 1)
+    }
+
+    /// Cell codes packed 8 per word, 4 bits each, for [`BoundaryBuffers`]
+    pub fn mask(&self) -> Vec<u32> {
+        let [sx, sy, sz] = self.size;
+        let mut mask = vec![0u32; ((sx * sy * sz) as usize).div_ceil(8)];
+        for z in 0..sz {
+            for y in 0..sy {
+                for x in 0..sx {
+                    let index = self.cell_index([x, y, z]);
+                    mask[index / 8] |= self.cell_code([x, y, z]) << (4 * (index % 8));
+                }
+            }
+        }
+        mask
+    }
+
+    /// Uniform block with the face conditions
+    pub fn params(&self) -> BoundaryParams {
+        let mut params = BoundaryParams {
+            values: [[0.0; 4]; 6],
+            kinds: [0; 8],
+        };
+        for (face, condition) in self.faces.iter().enumerate() {
+            (params.kinds[face], params.values[face]) = condition.to_gpu();
+        }
+        params
+    }
+
+    /// Layout entries for the mask (storage) and params (uniform) at
+    /// `first_binding` and `first_binding + 1`
+    pub fn bind_group_layout_entries(
+        first_binding: u32,
+        visibility: wgpu::ShaderStages,
+    ) -> [wgpu::BindGroupLayoutEntry; 2] {
+        let entry = |offset: u32, ty: wgpu::BufferBindingType| wgpu::BindGroupLayoutEntry {
+            binding: first_binding + offset,
+            visibility,
+            ty: wgpu::BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        [
+            entry(0, wgpu::BufferBindingType::Storage { read_only: true }),
+            entry(1, wgpu::BufferBindingType::Uniform),
+        ]
+    }
+
+    /// WGSL declaring the boundary bindings and lookup functions
+    ///
+    /// Defines `boundary_code` (mask value of a cell), `boundary_kind`
+    /// (`BOUNDARY_FLUID`, `BOUNDARY_NO_SLIP`, ...), `boundary_normal` (inward
+    /// normal of a face cell), `boundary_value` (velocity and density of a
+    /// face cell) and `boundary_wrap` (periodic neighbor lookup).
+    pub fn wgsl(&self, group: u32, first_binding: u32) -> String {
+        let [sx, sy, sz] = self.size;
+        format!(
+            "{DECLARATIONS_WGSL}
+const BOUNDARY_SIZE: vec3<u32> = vec3<u32>({sx}u, {sy}u, {sz}u);
+@group({group}) @binding({b0}) var<storage, read> boundary_mask: array<u32>;
+@group({group}) @binding({b1}) var<uniform> boundary_params: BoundaryParams;
+{FUNCTIONS_WGSL}",
+            b0 = first_binding,
+            b1 = first_binding + 1,
+        )
+    }
+}
+
+/// Mask and params of a [`BoundaryConditions`] on the GPU
+pub struct BoundaryBuffers {
+    mask: Buffer,
+    params: ParamsUniform<BoundaryParams>,
+}
+
+impl BoundaryBuffers {
+    pub fn new(device: &Device, queue: &Queue, conditions: &BoundaryConditions) -> Self {
+        let mask = conditions.mask();
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Boundary Mask"),
+            size: mask.len() as u64 * 4,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        queue.write_buffer(&buffer, 0, bytemuck::cast_slice(&mask));
+        Self {
+            mask: buffer,
+            params: ParamsUniform::new(device, "Boundary Params", &conditions.params()),
+        }
+    }
+
+    /// Upload changed face conditions; cheap enough to call every frame
+    pub fn update(&mut self, queue: &Queue, conditions: &BoundaryConditions) {
+        self.params.update(queue, &conditions.params());
+    }
+
+    /// Upload the mask after obstacles or periodic faces changed
+    pub fn upload_mask(&self, queue: &Queue, conditions: &BoundaryConditions) {
+        queue.write_buffer(&self.mask, 0, bytemuck::cast_slice(&conditions.mask()));
+    }
+
+    pub fn mask(&self) -> &Buffer {
+        &self.mask
+    }
+
+    /// Bind group entries matching [`BoundaryConditions::bind_group_layout_entries`]
+    pub fn bind_group_entries(&self, first_binding: u32) -> [wgpu::BindGroupEntry<'_>; 2] {
+        [
+            wgpu::BindGroupEntry {
+                binding: first_binding,
+                resource: self.mask.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: first_binding + 1,
+                resource: self.params.buffer().as_entire_binding(),
+            },
+        ]
+    }
+}
+
+const DECLARATIONS_WGSL: &str = r#"
+const BOUNDARY_FLUID: u32 = 0u;
+const BOUNDARY_NO_SLIP: u32 = 1u;
+const BOUNDARY_VELOCITY_INLET: u32 = 2u;
+const BOUNDARY_PRESSURE_OUTLET: u32 = 3u;
+const BOUNDARY_SYMMETRY: u32 = 4u;
+const BOUNDARY_OBSTACLE: u32 = 7u;
+
+struct BoundaryParams {
+    // Velocity and density per face: -x, +x, -y, +y, -z, +z
+    values: array<vec4<f32>, 6>,
+    kinds: array<vec4<u32>, 2>,
+}
+"#;
+
+const FUNCTIONS_WGSL: &str = r#"
+// Mask value of a cell: 0 fluid, 1 + face on a face, BOUNDARY_OBSTACLE
+fn boundary_code(cell: vec3<u32>) -> u32 {
+    let index = cell.x + BOUNDARY_SIZE.x * (cell.y + BOUNDARY_SIZE.y * cell.z);
+    return (boundary_mask[index / 8u] >> (4u * (index % 8u))) & 15u;
+}
+
+// Condition of a cell, one of the BOUNDARY_* kinds
+fn boundary_kind(code: u32) -> u32 {
+    if (code == 0u) {
+        return BOUNDARY_FLUID;
+    }
+    if (code == BOUNDARY_OBSTACLE) {
+        return BOUNDARY_NO_SLIP;
+    }
+    return boundary_params.kinds[(code - 1u) / 4u][(code - 1u) % 4u];
+}
+
+// Normal of a face cell pointing into the domain, zero elsewhere
+fn boundary_normal(code: u32) -> vec3<f32> {
+    if (code == 0u || code == BOUNDARY_OBSTACLE) {
+        return vec3<f32>(0.0);
+    }
+    var normal = vec3<f32>(0.0);
+    normal[(code - 1u) / 2u] = select(1.0, -1.0, (code - 1u) % 2u == 1u);
+    return normal;
+}
+
+// Prescribed velocity (xyz) and density (w) of a face cell
+fn boundary_value(code: u32) -> vec4<f32> {
+    return boundary_params.values[code - 1u];
+}
+
+// Cell at `cell`, wrapped around every axis
+fn boundary_wrap(cell: vec3<i32>) -> vec3<u32> {
+    let size = vec3<i32>(BOUNDARY_SIZE);
+    return vec3<u32>((cell % size + size) % size);
+}
+"#;
+
+/// D3Q19 lattice: velocities, weights and equilibrium
+///
+/// Directions come in opposite pairs, `1 <-> 2`, `3 <-> 4`, ... `17 <-> 18`.
+pub const D3Q19_WGSL: &str = r#"
+const D3Q19_VELOCITIES: array<vec3<i32>, 19> = array<vec3<i32>, 19>(
+    vec3<i32>(0, 0, 0),
+    vec3<i32>(1, 0, 0), vec3<i32>(-1, 0, 0),
+    vec3<i32>(0, 1, 0), vec3<i32>(0, -1, 0),
+    vec3<i32>(0, 0, 1), vec3<i32>(0, 0, -1),
+    vec3<i32>(1, 1, 0), vec3<i32>(-1, -1, 0),
+    vec3<i32>(1, -1, 0), vec3<i32>(-1, 1, 0),
+    vec3<i32>(1, 0, 1), vec3<i32>(-1, 0, -1),
+    vec3<i32>(1, 0, -1), vec3<i32>(-1, 0, 1),
+    vec3<i32>(0, 1, 1), vec3<i32>(0, -1, -1),
+    vec3<i32>(0, 1, -1), vec3<i32>(0, -1, 1),
+);
+
+const D3Q19_WEIGHTS: array<f32, 19> = array<f32, 19>(
+    1.0 / 3.0,
+    1.0 / 18.0, 1.0 / 18.0, 1.0 / 18.0, 1.0 / 18.0, 1.0 / 18.0, 1.0 / 18.0,
+    1.0 / 36.0, 1.0 / 36.0, 1.0 / 36.0, 1.0 / 36.0, 1.0 / 36.0, 1.0 / 36.0,
+    1.0 / 36.0, 1.0 / 36.0, 1.0 / 36.0, 1.0 / 36.0, 1.0 / 36.0, 1.0 / 36.0,
+);
+
+fn d3q19_velocity(i: u32) -> vec3<f32> {
+    return vec3<f32>(D3Q19_VELOCITIES[i]);
+}
+
+fn d3q19_opposite(i: u32) -> u32 {
+    if (i == 0u) {
+        return 0u;
+    }
+    return select(i - 1u, i + 1u, i % 2u == 1u);
+}
+
+// Direction with velocity `c`
+fn d3q19_direction(c: vec3<f32>) -> u32 {
+    for (var i = 0u; i < 19u; i++) {
+        if (all(d3q19_velocity(i) == c)) {
+            return i;
+        }
+    }
+    return 0u;
+}
+
+fn d3q19_equilibrium(i: u32, rho: f32, u: vec3<f32>) -> f32 {
+    let cu = dot(d3q19_velocity(i), u);
+    return D3Q19_WEIGHTS[i] * rho * (1.0 + 3.0 * cu + 4.5 * cu * cu - 1.5 * dot(u, u));
+}
+"#;
+
+/// Boundary conditions on D3Q19 distributions, after [`D3Q19_WGSL`] and
+/// [`BoundaryConditions::wgsl`]
+///
+/// `boundary_apply_d3q19(code, &f, &rho, &u)` runs after streaming, with the
+/// density and velocity of the streamed distributions. It returns `true` for
+/// no-slip cells, whose distributions were bounced back and must not collide.
+/// Inlets and outlets rebuild the distributions entering the domain (Zou-He:
+/// non-equilibrium bounce-back with a transverse momentum correction),
+/// symmetry faces mirror them; `rho` and
+/// `u` are updated to the boundary values, and the cell collides as usual.
+pub const D3Q19_BOUNDARY_WGSL: &str = r#"
+fn boundary_apply_d3q19(
+    code: u32,
+    f: ptr<function, array<f32, 19>>,
+    rho: ptr<function, f32>,
+    u: ptr<function, vec3<f32>>,
+) -> bool {
+    let kind = boundary_kind(code);
+    if (kind == BOUNDARY_FLUID) {
+        return false;
+    }
+    if (kind == BOUNDARY_NO_SLIP) {
+        for (var i = 1u; i < 19u; i += 2u) {
+            let swapped = (*f)[i];
+            (*f)[i] = (*f)[i + 1u];
+            (*f)[i + 1u] = swapped;
+        }
+        *u = vec3<f32>(0.0);
+        return true;
+    }
+
+    let n = boundary_normal(code);
+    if (kind == BOUNDARY_SYMMETRY) {
+        // Distributions entering the domain mirror the ones leaving it
+        for (var i = 1u; i < 19u; i++) {
+            let c = d3q19_velocity(i);
+            let cn = dot(c, n);
+            if (cn > 0.0) {
+                (*f)[i] = (*f)[d3q19_direction(c - 2.0 * cn * n)];
+            }
+        }
+        *u = *u - dot(*u, n) * n;
+        return false;
+    }
+
+    // Density of the known distributions: along the face once, leaving twice
+    var known = 0.0;
+    for (var i = 0u; i < 19u; i++) {
+        let cn = dot(d3q19_velocity(i), n);
+        if (cn == 0.0) {
+            known += (*f)[i];
+        } else if (cn < 0.0) {
+            known += 2.0 * (*f)[i];
+        }
+    }
+    let value = boundary_value(code);
+    if (kind == BOUNDARY_VELOCITY_INLET) {
+        *u = value.xyz;
+        *rho = known / (1.0 - dot(*u, n));
+    } else {
+        *rho = value.w;
+        let normal_velocity = 1.0 - known / *rho;
+        *u = *u - dot(*u, n) * n + normal_velocity * n;
+    }
+
+    for (var i = 1u; i < 19u; i++) {
+        if (dot(d3q19_velocity(i), n) > 0.0) {
+            let o = d3q19_opposite(i);
+            (*f)[i] = (*f)[o] + d3q19_equilibrium(i, *rho, *u) - d3q19_equilibrium(o, *rho, *u);
+        }
+    }
+
+    // Transverse correction: the diagonal distributions entering the domain
+    // restore the momentum along the face
+    var momentum = vec3<f32>(0.0);
+    for (var i = 1u; i < 19u; i++) {
+        momentum += (*f)[i] * d3q19_velocity(i);
+    }
+    let deficit = *rho * *u - momentum;
+    let tangential = deficit - dot(deficit, n) * n;
+    for (var i = 1u; i < 19u; i++) {
+        let c = d3q19_velocity(i);
+        if (dot(c, n) > 0.0) {
+            (*f)[i] += 0.5 * dot(c - dot(c, n) * n, tangential);
+        }
+    }
+    return false;
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_codes_and_params() {
+        let mut boundaries = BoundaryConditions::new([4, 3, 2])
+            .with_face(
+                BoxFace::NegX,
+                BoundaryCondition::VelocityInlet {
+                    velocity: [0.1, 0.0, 0.0],
+                },
+            )
+            .with_face(BoxFace::NegY, BoundaryCondition::NoSlip);
+        boundaries.set_obstacle([2, 1, 1], true);
+
+        // The inlet takes the edge it shares with the wall
+        assert_eq!(boundaries.cell_code([0, 0, 0]), 1);
+        assert_eq!(boundaries.cell_code([1, 0, 0]), 3);
+        // Periodic faces stay fluid
+        assert_eq!(boundaries.cell_code([3, 2, 1]), 0);
+        assert_eq!(boundaries.cell_code([2, 1, 1]), OBSTACLE);
+
+        let mask = boundaries.mask();
+        assert_eq!(mask.len(), 3);
+        assert_eq!(mask[0] & 0xF, 1);
+        assert_eq!((mask[0] >> 4) & 0xF, 3);
+        let obstacle = 2 + 4 * (1 + 3);
+        assert_eq!((mask[obstacle / 8] >> (4 * (obstacle % 8))) & 0xF, OBSTACLE);
+
+        let params = boundaries.params();
+        assert_eq!(params.kinds[..6], [2, 0, 1, 0, 0, 0]);
+        assert_eq!(params.values[0], [0.1, 0.0, 0.0, 1.0]);
+        assert_eq!(std::mem::size_of::<BoundaryParams>(), 128);
+    }
+
+    #[test]
+    fn test_zou_he_inlet_sets_velocity() {
+        use crate::wgpu_utils::compute_primitives::{read_buffer, test_buffer, test_device};
+
+        // Needs a GPU adapter, skipped without one
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let velocity = [0.05, 0.02, 0.0];
+        let boundaries = BoundaryConditions::new([3, 3, 3])
+            .with_face(BoxFace::NegX, BoundaryCondition::VelocityInlet { velocity });
+        let buffers = BoundaryBuffers::new(&device, &queue, &boundaries);
+
+        // Fluid at rest streamed into the inlet cell at (0, 1, 1)
+        let weights = [1.0 / 3.0]
+            .into_iter()
+            .chain([1.0 / 18.0; 6])
+            .chain([1.0 / 36.0; 12])
+            .collect::<Vec<f32>>();
+        let distributions = test_buffer(&device, &weights);
+        let shader = format!(
+            "{}{D3Q19_WGSL}{D3Q19_BOUNDARY_WGSL}
+@group(0) @binding(0) var<storage, read_write> distributions: array<f32>;
+
+@compute @workgroup_size(1)
+fn main() {{
+    var f: array<f32, 19>;
+    var rho = 0.0;
+    var u = vec3<f32>(0.0);
+    for (var i = 0u; i < 19u; i++) {{
+        f[i] = distributions[i];
+        rho += f[i];
+        u += f[i] * d3q19_velocity(i);
+    }}
+    u /= rho;
+    if (boundary_apply_d3q19(boundary_code(vec3<u32>(0u, 1u, 1u)), &f, &rho, &u)) {{
+        return;
+    }}
+    for (var i = 0u; i < 19u; i++) {{
+        distributions[i] = f[i];
+    }}
+}}
+",
+            boundaries.wgsl(0, 1)
+        );
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(shader.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: None,
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        let mut entries = vec![wgpu::BindGroupEntry {
+            binding: 0,
+            resource: distributions.as_entire_binding(),
+        }];
+        entries.extend(buffers.bind_group_entries(1));
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &entries,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+            pass.set_pipeline(&pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(1, 1, 1);
+        }
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let f: Vec<f32> = read_buffer(&device, &queue, &distributions, 19).unwrap();
+        let velocities: [[f32; 3]; 19] = [
+            [0., 0., 0.],
+            [1., 0., 0.],
+            [-1., 0., 0.],
+            [0., 1., 0.],
+            [0., -1., 0.],
+            [0., 0., 1.],
+            [0., 0., -1.],
+            [1., 1., 0.],
+            [-1., -1., 0.],
+            [1., -1., 0.],
+            [-1., 1., 0.],
+            [1., 0., 1.],
+            [-1., 0., -1.],
+            [1., 0., -1.],
+            [-1., 0., 1.],
+            [0., 1., 1.],
+            [0., -1., -1.],
+            [0., 1., -1.],
+            [0., -1., 1.],
+        ];
+        let rho: f32 = f.iter().sum();
+        for axis in 0..3 {
+            let momentum: f32 = f.iter().zip(&velocities).map(|(f, c)| f * c[axis]).sum();
+            assert!((momentum / rho - velocity[axis]).abs() < 1e-3);
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! Closure-driven simulations
+//!
+//! [`CallbackSimulation`] wraps a step closure as a full [`Simulation`], for
+//! logic that lives outside a dedicated simulation type: quick prototypes, or
+//! language bindings that forward each step to a foreign callback.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::simulation::callback::CallbackSimulation;
+//!
+//! let mut app = haggis::default();
+//! app.add_cube().with_name("spinner");
+//!
+//! app.attach_simulation(CallbackSimulation::new("Spinner", |dt, scene| {
+//!     if let Some(object) = scene.objects.get_mut(0) {
+//!         object.ui_transform.rotation[2] += 90.0 * dt;
+//!         object.apply_ui_transform();
+//!     }
+//! }));
+//! app.run();
+//! ```
+
+use std::any::Any;
+
+use imgui::Ui;
+
+use crate::gfx::scene::Scene;
+use crate::simulation::traits::Simulation;
+
+type StepFn = Box<dyn FnMut(f32, &mut Scene)>;
+type SceneFn = Box<dyn FnMut(&mut Scene)>;
+
+/// A simulation whose update is a closure
+pub struct CallbackSimulation {
+    name: String,
+    step: StepFn,
+    on_reset: Option<SceneFn>,
+    running: bool,
+    time: f32,
+    steps: u64,
+}
+
+impl CallbackSimulation {
+    /// Create a simulation that calls `step(delta_time, scene)` every update
+    pub fn new<F>(name: &str, step: F) -> Self
+    where
+        F: FnMut(f32, &mut Scene) + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            step: Box::new(step),
+            on_reset: None,
+            running: true,
+            time: 0.0,
+            steps: 0,
+        }
+    }
+
+    /// Call `reset(scene)` when the simulation is reset
+    pub fn with_reset<F>(mut self, reset: F) -> Self
+    where
+        F: FnMut(&mut Scene) + 'static,
+    {
+        self.on_reset = Some(Box::new(reset));
+        self
+    }
+
+    /// Simulation time in seconds since the last reset
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// Number of steps since the last reset
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+}
+
+impl Simulation for CallbackSimulation {
+    fn initialize(&mut self, _scene: &mut Scene) {}
+
+    fn update(&mut self, delta_time: f32, scene: &mut Scene) {
+        if !self.running {
+            return;
+        }
+        (self.step)(delta_time, scene);
+        self.time += delta_time;
+        self.steps += 1;
+    }
+
+    fn render_ui(&mut self, ui: &Ui) {
+        let title = self.name.clone();
+        ui.window(&title)
+            .size([250.0, 100.0], imgui::Condition::FirstUseEver)
+            .build(|| {
+                ui.checkbox("Running", &mut self.running);
+                ui.text(format!("Time: {:.2}s ({} steps)", self.time, self.steps));
+            });
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn is_running(&self) -> bool {
+        self.running
+    }
+
+    fn set_running(&mut self, running: bool) {
+        self.running = running;
+    }
+
+    fn reset(&mut self, scene: &mut Scene) {
+        self.time = 0.0;
+        self.steps = 0;
+        if let Some(reset) = &mut self.on_reset {
+            reset(scene);
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
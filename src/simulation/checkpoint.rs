@@ -0,0 +1,403 @@
+//! Field checkpoint files
+//!
+//! A [`Checkpoint`] stores the named scalar fields of a simulation together
+//! with its step count and time, so multi-hour runs can be saved and resumed.
+//! Fields can be stored in half precision and run-length encoded, which
+//! shrinks fields with large uniform regions (walls, quiescent fluid, empty
+//! space) by orders of magnitude.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::simulation::checkpoint::{Checkpoint, CheckpointOptions};
+//!
+//! # let (step, time, density): (u64, f64, Vec<f32>) = (0, 0.0, vec![]);
+//! let mut checkpoint = Checkpoint::new(step, time);
+//! checkpoint.add_field("density", [192, 192, 192], density);
+//! checkpoint.save("lbm_0042.hgck", CheckpointOptions::compact())?;
+//!
+//! let restored = Checkpoint::load("lbm_0042.hgck")?;
+//! let density = &restored.field("density").unwrap().data;
+//! # Ok::<(), haggis::error::HaggisError>(())
+//! ```
+//!
+//! ## Format
+//!
+//! Little-endian: the magic `HGCK`, a format version, the step and time, then
+//! each field's name, dimensions, precision, encoding and payload. Run-length
+//! encoding works on whole values: a signed `i32` header followed by either
+//! `n` literal values (`n > 0`) or one value repeated `-n` times (`n < 0`).
+
+use std::path::Path;
+
+use crate::error::{HaggisError, Result};
+use crate::wgpu_utils::half::{f16_to_f32, f32_to_f16, FieldPrecision};
+
+const MAGIC: &[u8; 4] = b"HGCK";
+const VERSION: u32 = 1;
+/// Shortest run worth encoding as a repeat
+const MIN_RUN: usize = 3;
+
+/// How field payloads are encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compression {
+    /// Raw values
+    #[default]
+    None,
+    /// Runs of identical values stored once
+    RunLength,
+}
+
+/// Precision and compression used when writing a checkpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CheckpointOptions {
+    pub precision: FieldPrecision,
+    pub compression: Compression,
+}
+
+impl CheckpointOptions {
+    /// Full precision, no compression
+    pub fn exact() -> Self {
+        Self::default()
+    }
+
+    /// Half precision with run-length encoding
+    pub fn compact() -> Self {
+        Self {
+            precision: FieldPrecision::F16,
+            compression: Compression::RunLength,
+        }
+    }
+}
+
+/// A named scalar field
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckpointField {
+    pub name: String,
+    /// Grid size in cells; `[n, 1, 1]` for plain arrays
+    pub dims: [u32; 3],
+    pub data: Vec<f32>,
+}
+
+/// Simulation state saved to or loaded from a checkpoint file
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Checkpoint {
+    /// Simulation step the state was saved at
+    pub step: u64,
+    /// Simulated seconds at that step
+    pub time: f64,
+    fields: Vec<CheckpointField>,
+}
+
+impl Checkpoint {
+    /// Empty checkpoint at `step` and `time`
+    pub fn new(step: u64, time: f64) -> Self {
+        Self {
+            step,
+            time,
+            fields: Vec::new(),
+        }
+    }
+
+    /// Add a field, replacing one with the same name
+    ///
+    /// # Panics
+    ///
+    /// Panics if `data` doesn't hold `dims[0] * dims[1] * dims[2]` values.
+    pub fn add_field(&mut self, name: &str, dims: [u32; 3], data: Vec<f32>) {
+        assert_eq!(
+            data.len(),
+            dims.iter().map(|&d| d as usize).product::<usize>(),
+            "field '{}' size doesn't match its dimensions",
+            name
+        );
+        self.fields.retain(|field| field.name != name);
+        self.fields.push(CheckpointField {
+            name: name.to_string(),
+            dims,
+            data,
+        });
+    }
+
+    /// Field with the given name
+    pub fn field(&self, name: &str) -> Option<&CheckpointField> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    /// All fields in the order they were added
+    pub fn fields(&self) -> &[CheckpointField] {
+        &self.fields
+    }
+
+    /// Encode the checkpoint
+    pub fn to_bytes(&self, options: CheckpointOptions) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+        out.extend_from_slice(&VERSION.to_le_bytes());
+        out.extend_from_slice(&self.step.to_le_bytes());
+        out.extend_from_slice(&self.time.to_le_bytes());
+        out.extend_from_slice(&(self.fields.len() as u32).to_le_bytes());
+
+        for field in &self.fields {
+            out.extend_from_slice(&(field.name.len() as u16).to_le_bytes());
+            out.extend_from_slice(field.name.as_bytes());
+            for dim in field.dims {
+                out.extend_from_slice(&dim.to_le_bytes());
+            }
+            out.push(options.precision as u8);
+            out.push(options.compression as u8);
+
+            let values: Vec<u8> = match options.precision {
+                FieldPrecision::F32 => field
+                    .data
+                    .iter()
+                    .flat_map(|value| value.to_le_bytes())
+                    .collect(),
+                FieldPrecision::F16 => field
+                    .data
+                    .iter()
+                    .flat_map(|&value| f32_to_f16(value).to_le_bytes())
+                    .collect(),
+            };
+            let width = value_width(options.precision);
+            let payload = match options.compression {
+                Compression::None => values,
+                Compression::RunLength => rle_encode(&values, width),
+            };
+            out.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+            out.extend_from_slice(&payload);
+        }
+        out
+    }
+
+    /// Decode a checkpoint written by [`to_bytes`](Self::to_bytes)
+    pub fn from_bytes(bytes: &[u8]) -> std::result::Result<Self, String> {
+        let mut reader = Reader { bytes, offset: 0 };
+        if reader.take(4)? != MAGIC {
+            return Err("not a checkpoint file".to_string());
+        }
+        let version = reader.u32()?;
+        if version != VERSION {
+            return Err(format!("unsupported checkpoint version {}", version));
+        }
+        let mut checkpoint = Checkpoint::new(reader.u64()?, f64::from_bits(reader.u64()?));
+
+        for _ in 0..reader.u32()? {
+            let name_len = u16::from_le_bytes(reader.take(2)?.try_into().unwrap()) as usize;
+            let name = String::from_utf8(reader.take(name_len)?.to_vec())
+                .map_err(|_| "field name is not UTF-8".to_string())?;
+            let dims = [reader.u32()?, reader.u32()?, reader.u32()?];
+            let len = dims.iter().map(|&d| d as usize).product::<usize>();
+
+            let precision = match reader.take(1)?[0] {
+                0 => FieldPrecision::F32,
+                1 => FieldPrecision::F16,
+                other => return Err(format!("field '{}': unknown precision {}", name, other)),
+            };
+            let width = value_width(precision);
+            let compression = reader.take(1)?[0];
+            let payload = {
+                let payload_len = reader.u64()? as usize;
+                let payload = reader.take(payload_len)?;
+                match compression {
+                    0 => payload.to_vec(),
+                    1 => rle_decode(payload, width, len)?,
+                    other => {
+                        return Err(format!("field '{}': unknown compression {}", name, other))
+                    }
+                }
+            };
+            if payload.len() != len * width {
+                return Err(format!("field '{}' is truncated", name));
+            }
+
+            let data = match precision {
+                FieldPrecision::F32 => payload
+                    .chunks_exact(4)
+                    .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+                    .collect(),
+                FieldPrecision::F16 => payload
+                    .chunks_exact(2)
+                    .map(|b| f16_to_f32(u16::from_le_bytes(b.try_into().unwrap())))
+                    .collect(),
+            };
+            checkpoint.fields.push(CheckpointField { name, dims, data });
+        }
+        Ok(checkpoint)
+    }
+
+    /// Write the checkpoint to a file
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::Io`] if the file cannot be written and
+    /// [`HaggisError::NoFileSystem`] on platforms without file access.
+    pub fn save(&self, path: impl AsRef<Path>, options: CheckpointOptions) -> Result<()> {
+        let path = path.as_ref();
+        if !crate::platform::HAS_FILESYSTEM {
+            return Err(HaggisError::NoFileSystem { path: path.into() });
+        }
+        std::fs::write(path, self.to_bytes(options)).map_err(|source| HaggisError::Io {
+            path: path.into(),
+            source,
+        })
+    }
+
+    /// Read a checkpoint file
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::Io`] if the file cannot be read,
+    /// [`HaggisError::Checkpoint`] if it is malformed, and
+    /// [`HaggisError::NoFileSystem`] on platforms without file access.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if !crate::platform::HAS_FILESYSTEM {
+            return Err(HaggisError::NoFileSystem { path: path.into() });
+        }
+        let bytes = std::fs::read(path).map_err(|source| HaggisError::Io {
+            path: path.into(),
+            source,
+        })?;
+        Self::from_bytes(&bytes).map_err(|message| HaggisError::Checkpoint {
+            path: path.into(),
+            message,
+        })
+    }
+}
+
+fn value_width(precision: FieldPrecision) -> usize {
+    match precision {
+        FieldPrecision::F32 => 4,
+        FieldPrecision::F16 => 2,
+    }
+}
+
+/// Run-length encode values of `width` bytes
+fn rle_encode(bytes: &[u8], width: usize) -> Vec<u8> {
+    let values: Vec<&[u8]> = bytes.chunks_exact(width).collect();
+    let mut out = Vec::new();
+    let mut literal_start = 0;
+    let mut i = 0;
+
+    let flush_literals = |out: &mut Vec<u8>, values: &[&[u8]]| {
+        for chunk in values.chunks(i32::MAX as usize) {
+            out.extend_from_slice(&(chunk.len() as i32).to_le_bytes());
+            chunk.iter().for_each(|value| out.extend_from_slice(value));
+        }
+    };
+
+    while i < values.len() {
+        let run = values[i..]
+            .iter()
+            .take(i32::MAX as usize)
+            .take_while(|&&value| value == values[i])
+            .count();
+        if run >= MIN_RUN {
+            flush_literals(&mut out, &values[literal_start..i]);
+            out.extend_from_slice(&(-(run as i32)).to_le_bytes());
+            out.extend_from_slice(values[i]);
+            i += run;
+            literal_start = i;
+        } else {
+            i += run;
+        }
+    }
+    flush_literals(&mut out, &values[literal_start..]);
+    out
+}
+
+/// Decode [`rle_encode`] output of at most `len` values
+fn rle_decode(bytes: &[u8], width: usize, len: usize) -> std::result::Result<Vec<u8>, String> {
+    let mut reader = Reader { bytes, offset: 0 };
+    let mut out = Vec::with_capacity(len * width);
+    while reader.offset < bytes.len() {
+        let header = i32::from_le_bytes(reader.take(4)?.try_into().unwrap());
+        let count = header.unsigned_abs() as usize;
+        if out.len() / width + count > len {
+            return Err("run-length data exceeds field size".to_string());
+        }
+        if header > 0 {
+            out.extend_from_slice(reader.take(count * width)?);
+        } else {
+            let value = reader.take(width)?;
+            for _ in 0..count {
+                out.extend_from_slice(value);
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Bounds-checked little-endian reader
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> std::result::Result<&'a [u8], String> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| "unexpected end of checkpoint data".to_string())?;
+        let slice = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(slice)
+    }
+
+    fn u32(&mut self) -> std::result::Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> std::result::Result<u64, String> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut checkpoint = Checkpoint::new(420, 7.0);
+        let mut density = vec![1.0; 1000];
+        density[500] = 1.25;
+        density[501] = -3.5;
+        checkpoint.add_field("density", [10, 10, 10], density.clone());
+        checkpoint.add_field("flags", [3, 1, 1], vec![0.0, 1.0, 2.0]);
+
+        for options in [
+            CheckpointOptions::exact(),
+            CheckpointOptions::compact(),
+            CheckpointOptions {
+                precision: FieldPrecision::F32,
+                compression: Compression::RunLength,
+            },
+        ] {
+            let restored = Checkpoint::from_bytes(&checkpoint.to_bytes(options)).unwrap();
+            assert_eq!(restored, checkpoint);
+        }
+
+        // Half precision rounds values it can't represent
+        checkpoint.add_field("flags", [1, 1, 1], vec![0.1]);
+        let restored =
+            Checkpoint::from_bytes(&checkpoint.to_bytes(CheckpointOptions::compact())).unwrap();
+        assert!((restored.field("flags").unwrap().data[0] - 0.1).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_run_length_size() {
+        let mut checkpoint = Checkpoint::new(0, 0.0);
+        checkpoint.add_field("velocity", [64, 64, 64], vec![0.0; 64 * 64 * 64]);
+        let raw = checkpoint.to_bytes(CheckpointOptions::exact()).len();
+        let compact = checkpoint.to_bytes(CheckpointOptions::compact()).len();
+        assert!(raw > 1_000_000);
+        assert!(compact < 100);
+
+        let bytes = checkpoint.to_bytes(CheckpointOptions::compact());
+        assert!(Checkpoint::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Checkpoint::from_bytes(b"HGCK").is_err());
+    }
+}
@@ -0,0 +1,682 @@
+//! # Monte Carlo Ensembles
+//!
+//! Stochastic models such as random walks, percolation or kinetic Monte Carlo
+//! only make sense in aggregate: one run is one sample. An [`Ensemble`] runs
+//! many replicas of a [`StochasticModel`] side by side, each with its own
+//! seeded random number generator, and gathers their observations into
+//! per-cell mean and variance fields plus statistics of scalar observables.
+//!
+//! Replicas are stepped in parallel on the [`JobSystem`]. Every replica draws
+//! only from its own generator, so results are the same on one thread or many
+//! and the whole ensemble is reproducible from its seed.
+//!
+//! As a [`Simulation`] the ensemble shows the mean, variance, standard
+//! deviation or a single replica on a heatmap, and a table of the scalar
+//! observables with their standard errors. It also runs headless through
+//! [`Ensemble::advance`].
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::simulation::ensemble::{Ensemble, StochasticModel};
+//! use rand::rngs::StdRng;
+//! use rand::Rng;
+//!
+//! /// A walker on a 64x64 grid, leaving a trail of visits
+//! struct Walker {
+//!     position: [i32; 2],
+//!     visits: Vec<f32>,
+//! }
+//!
+//! impl StochasticModel for Walker {
+//!     fn step(&mut self, rng: &mut StdRng) {
+//!         let [dx, dy] = [[1, 0], [-1, 0], [0, 1], [0, -1]][rng.random_range(0..4)];
+//!         self.position = [(self.position[0] + dx).clamp(0, 63), (self.position[1] + dy).clamp(0, 63)];
+//!         self.visits[(self.position[1] * 64 + self.position[0]) as usize] += 1.0;
+//!     }
+//!
+//!     fn field_dims(&self) -> [u32; 2] {
+//!         [64, 64]
+//!     }
+//!
+//!     fn observe(&self, field: &mut [f32]) {
+//!         field.copy_from_slice(&self.visits);
+//!     }
+//! }
+//!
+//! let ensemble = Ensemble::new(256, |_rng| Walker {
+//!     position: [32, 32],
+//!     visits: vec![0.0; 64 * 64],
+//! });
+//!
+//! let mut app = haggis::default();
+//! app.attach_simulation(ensemble);
+//! app.run();
+//! ```
+
+use std::any::Any;
+use std::sync::Mutex;
+
+use cgmath::Vector3;
+use imgui::Ui;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use wgpu::{Device, Queue};
+
+use super::params::SimParams;
+use super::traits::Simulation;
+use super::BaseSimulation;
+use crate::app::jobs::JobSystem;
+use crate::gfx::scene::Scene;
+use crate::visualization::CutPlane2D;
+
+type Factory<M> = Box<dyn Fn(&mut StdRng) -> M + Send + Sync>;
+
+/// One realization of a stochastic process
+///
+/// All randomness must come from the generator passed to
+/// [`step`](Self::step), which keeps replicas independent and runs
+/// reproducible.
+pub trait StochasticModel: Send {
+    /// Advance the model by one step
+    fn step(&mut self, rng: &mut StdRng);
+
+    /// Width and height of the observed field
+    fn field_dims(&self) -> [u32; 2];
+
+    /// Write the observed field, row by row, into `field`
+    fn observe(&self, field: &mut [f32]);
+
+    /// Named scalar observables, e.g. the mean squared displacement
+    ///
+    /// Every replica must return the same names in the same order.
+    fn scalars(&self) -> Vec<(&'static str, f64)> {
+        Vec::new()
+    }
+}
+
+/// Seed of replica `index` in an ensemble seeded with `seed`
+///
+/// Neighboring seeds give unrelated replicas, so ensembles with seeds 1 and
+/// 2 don't share any.
+pub fn replica_seed(seed: u64, index: usize) -> u64 {
+    seed ^ (index as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+}
+
+/// Mean and variance of a stream of samples, updated one sample at a time
+/// with Welford's algorithm
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RunningStats {
+    count: usize,
+    mean: f64,
+    m2: f64,
+}
+
+impl RunningStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    /// Unbiased sample variance, zero for fewer than two samples
+    pub fn variance(&self) -> f64 {
+        if self.count < 2 {
+            0.0
+        } else {
+            self.m2 / (self.count - 1) as f64
+        }
+    }
+
+    pub fn std_dev(&self) -> f64 {
+        self.variance().sqrt()
+    }
+
+    /// Standard error of the mean
+    pub fn std_error(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            (self.variance() / self.count as f64).sqrt()
+        }
+    }
+}
+
+/// Per-cell statistics of a field over the replicas of an ensemble
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FieldStats {
+    dims: [u32; 2],
+    cells: Vec<RunningStats>,
+}
+
+impl FieldStats {
+    pub fn new(dims: [u32; 2]) -> Self {
+        Self {
+            dims,
+            cells: vec![RunningStats::new(); dims[0] as usize * dims[1] as usize],
+        }
+    }
+
+    /// Add one replica's field
+    pub fn push(&mut self, field: &[f32]) {
+        for (cell, &value) in self.cells.iter_mut().zip(field) {
+            cell.push(value as f64);
+        }
+    }
+
+    pub fn dims(&self) -> [u32; 2] {
+        self.dims
+    }
+
+    /// Number of fields gathered
+    pub fn count(&self) -> usize {
+        self.cells.first().map_or(0, RunningStats::count)
+    }
+
+    /// Statistics of the cell at `(x, y)`
+    pub fn cell(&self, x: u32, y: u32) -> RunningStats {
+        self.cells[(y * self.dims[0] + x) as usize]
+    }
+
+    pub fn mean(&self) -> Vec<f32> {
+        self.cells.iter().map(|cell| cell.mean() as f32).collect()
+    }
+
+    pub fn variance(&self) -> Vec<f32> {
+        self.cells
+            .iter()
+            .map(|cell| cell.variance() as f32)
+            .collect()
+    }
+
+    pub fn std_dev(&self) -> Vec<f32> {
+        self.cells
+            .iter()
+            .map(|cell| cell.std_dev() as f32)
+            .collect()
+    }
+}
+
+/// Field shown on the heatmap
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EnsembleView {
+    #[default]
+    Mean,
+    Variance,
+    StdDev,
+    /// The field of the first replica, one sample of the process
+    Replica,
+}
+
+impl EnsembleView {
+    pub const ALL: [EnsembleView; 4] = [
+        EnsembleView::Mean,
+        EnsembleView::Variance,
+        EnsembleView::StdDev,
+        EnsembleView::Replica,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EnsembleView::Mean => "Mean",
+            EnsembleView::Variance => "Variance",
+            EnsembleView::StdDev => "Std Dev",
+            EnsembleView::Replica => "Replica 0",
+        }
+    }
+}
+
+/// Tunable parameters of an [`Ensemble`]
+#[derive(Debug, Clone, SimParams)]
+pub struct EnsembleParams {
+    #[param(range = 1..=1000, label = "Steps / Frame")]
+    pub steps_per_frame: u32,
+}
+
+impl Default for EnsembleParams {
+    fn default() -> Self {
+        Self { steps_per_frame: 1 }
+    }
+}
+
+struct Replica<M> {
+    model: M,
+    rng: StdRng,
+}
+
+/// Observations of one replica after a batch of steps
+struct Observation {
+    field: Vec<f32>,
+    scalars: Vec<(&'static str, f64)>,
+}
+
+/// Replicas of a stochastic model run together, with their statistics
+pub struct Ensemble<M: StochasticModel> {
+    base: BaseSimulation,
+    name: String,
+    factory: Factory<M>,
+    seed: u64,
+    /// Locked one at a time by the job that steps them
+    replicas: Vec<Mutex<Replica<M>>>,
+    parallel: bool,
+    pub params: EnsembleParams,
+    view: EnsembleView,
+    field_stats: FieldStats,
+    scalar_stats: Vec<(&'static str, RunningStats)>,
+    sample: Vec<f32>,
+    /// Largest value of the shown field, mapped to the top of the heatmap
+    display_max: f32,
+    plane_position: Vector3<f32>,
+    plane_size: f32,
+    running: bool,
+    needs_step: bool,
+    steps: u64,
+}
+
+impl<M: StochasticModel> Ensemble<M> {
+    /// `replicas` models made by `factory`, each from its own generator
+    ///
+    /// The factory gets the replica's generator, so initial conditions may be
+    /// random too.
+    ///
+    /// # Panics
+    ///
+    /// If `replicas` is zero
+    pub fn new<F>(replicas: usize, factory: F) -> Self
+    where
+        F: Fn(&mut StdRng) -> M + Send + Sync + 'static,
+    {
+        assert!(replicas > 0, "an ensemble needs at least one replica");
+        let mut ensemble = Self {
+            base: BaseSimulation::new("Ensemble"),
+            name: "Ensemble".to_string(),
+            factory: Box::new(factory),
+            seed: 0,
+            replicas: Vec::with_capacity(replicas),
+            parallel: true,
+            params: EnsembleParams::default(),
+            view: EnsembleView::default(),
+            field_stats: FieldStats::default(),
+            scalar_stats: Vec::new(),
+            sample: Vec::new(),
+            display_max: 0.0,
+            plane_position: Vector3::new(0.0, 2.0, 0.0),
+            plane_size: 2.0,
+            running: true,
+            needs_step: false,
+            steps: 0,
+        };
+        ensemble.rebuild(replicas);
+        ensemble
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// Seed the replicas from `seed` instead of 0
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self.rebuild(self.replicas.len());
+        self
+    }
+
+    /// Step the replicas on the calling thread only
+    pub fn serial(mut self) -> Self {
+        self.parallel = false;
+        self
+    }
+
+    pub fn with_params(mut self, params: EnsembleParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    pub fn with_view(mut self, view: EnsembleView) -> Self {
+        self.view = view;
+        self
+    }
+
+    /// Place the heatmap in the world
+    pub fn with_plane(mut self, position: Vector3<f32>, size: f32) -> Self {
+        self.plane_position = position;
+        self.plane_size = size;
+        self
+    }
+
+    pub fn replica_count(&self) -> usize {
+        self.replicas.len()
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Steps taken by every replica since the last reset
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn view(&self) -> EnsembleView {
+        self.view
+    }
+
+    pub fn set_view(&mut self, view: EnsembleView) {
+        self.view = view;
+        self.update_heatmap();
+    }
+
+    /// Per-cell statistics of the observed fields after the last step
+    pub fn field_stats(&self) -> &FieldStats {
+        &self.field_stats
+    }
+
+    /// Statistics of the scalar observables after the last step
+    pub fn scalar_stats(&self) -> &[(&'static str, RunningStats)] {
+        &self.scalar_stats
+    }
+
+    /// Start over with `replicas` fresh replicas from the same seed
+    pub fn restart(&mut self, replicas: usize) {
+        assert!(replicas > 0, "an ensemble needs at least one replica");
+        self.rebuild(replicas);
+        self.update_heatmap();
+    }
+
+    /// Step every replica `steps` times, then gather their statistics
+    pub fn advance(&mut self, steps: u32) {
+        let step = |index: usize| {
+            let mut replica = self.replicas[index].lock().unwrap();
+            let Replica { model, rng } = &mut *replica;
+            for _ in 0..steps {
+                model.step(rng);
+            }
+            observe(model)
+        };
+        let observations = if self.parallel {
+            JobSystem::global().parallel_map(self.replicas.len(), step)
+        } else {
+            (0..self.replicas.len()).map(step).collect()
+        };
+        self.steps += steps as u64;
+        self.gather(observations);
+    }
+
+    fn rebuild(&mut self, replicas: usize) {
+        self.replicas = (0..replicas)
+            .map(|index| {
+                let mut rng = StdRng::seed_from_u64(replica_seed(self.seed, index));
+                let model = (self.factory)(&mut rng);
+                Mutex::new(Replica { model, rng })
+            })
+            .collect();
+        self.steps = 0;
+        let observations = self
+            .replicas
+            .iter()
+            .map(|replica| observe(&replica.lock().unwrap().model))
+            .collect();
+        self.gather(observations);
+    }
+
+    fn gather(&mut self, observations: Vec<Observation>) {
+        let dims = self.replicas[0].lock().unwrap().model.field_dims();
+        self.field_stats = FieldStats::new(dims);
+        self.scalar_stats.clear();
+        for (index, observation) in observations.into_iter().enumerate() {
+            self.field_stats.push(&observation.field);
+            if index == 0 {
+                self.scalar_stats = observation
+                    .scalars
+                    .iter()
+                    .map(|&(name, _)| (name, RunningStats::new()))
+                    .collect();
+                self.sample = observation.field;
+            }
+            for ((_, stats), (_, value)) in self.scalar_stats.iter_mut().zip(&observation.scalars) {
+                stats.push(*value);
+            }
+        }
+    }
+
+    /// Field of the current view, divided by its largest value for the
+    /// heatmap's 0 to 1 range
+    fn display_field(&mut self) -> Vec<f32> {
+        let mut field = match self.view {
+            EnsembleView::Mean => self.field_stats.mean(),
+            EnsembleView::Variance => self.field_stats.variance(),
+            EnsembleView::StdDev => self.field_stats.std_dev(),
+            EnsembleView::Replica => self.sample.clone(),
+        };
+        self.display_max = field.iter().copied().fold(0.0, f32::max);
+        if self.display_max > 0.0 {
+            for value in &mut field {
+                *value /= self.display_max;
+            }
+        }
+        field
+    }
+
+    fn update_heatmap(&mut self) {
+        let [width, height] = self.field_stats.dims();
+        let field = self.display_field();
+        if let Some(plane) = self
+            .base
+            .get_visualization_mut("heatmap")
+            .and_then(|component| component.as_any_mut().downcast_mut::<CutPlane2D>())
+        {
+            plane.update_data(field, width, height);
+        }
+    }
+}
+
+fn observe<M: StochasticModel>(model: &M) -> Observation {
+    let [width, height] = model.field_dims();
+    let mut field = vec![0.0; width as usize * height as usize];
+    model.observe(&mut field);
+    Observation {
+        field,
+        scalars: model.scalars(),
+    }
+}
+
+impl<M: StochasticModel + 'static> Simulation for Ensemble<M> {
+    fn initialize(&mut self, scene: &mut Scene) {
+        let mut heatmap = CutPlane2D::new();
+        heatmap.set_position(self.plane_position);
+        heatmap.set_size(self.plane_size);
+        self.base.remove_visualization("heatmap");
+        self.base.add_visualization("heatmap", heatmap);
+        self.update_heatmap();
+        self.base.initialize(scene);
+    }
+
+    fn update(&mut self, delta_time: f32, scene: &mut Scene) {
+        if self.running || self.needs_step {
+            let steps = if self.running {
+                self.params.steps_per_frame.max(1)
+            } else {
+                1
+            };
+            self.advance(steps);
+            self.update_heatmap();
+            self.needs_step = false;
+        }
+        self.base.update(delta_time, scene);
+    }
+
+    fn render_ui(&mut self, ui: &Ui) {
+        let title = self.name.clone();
+        ui.window(&title)
+            .size([360.0, 380.0], imgui::Condition::FirstUseEver)
+            .build(|| {
+                ui.text(format!(
+                    "Replicas: {} (seed {}), steps: {}",
+                    self.replicas.len(),
+                    self.seed,
+                    self.steps
+                ));
+                ui.separator();
+
+                if ui.button(if self.running { "Pause" } else { "Play" }) {
+                    self.running = !self.running;
+                }
+                ui.same_line();
+                if ui.button("Step") {
+                    self.needs_step = true;
+                }
+                ui.same_line();
+                if ui.button("Reset") {
+                    self.restart(self.replicas.len());
+                }
+                ui.same_line();
+                if ui.button("Reseed") {
+                    self.seed = self.seed.wrapping_add(1);
+                    self.restart(self.replicas.len());
+                }
+
+                ui.separator();
+                ui.text("Show:");
+                for view in EnsembleView::ALL {
+                    if ui.radio_button_bool(view.as_str(), self.view == view) {
+                        self.set_view(view);
+                    }
+                }
+                ui.text(format!("Heatmap top: {:.4}", self.display_max));
+
+                if !self.scalar_stats.is_empty() {
+                    ui.separator();
+                    for (name, stats) in &self.scalar_stats {
+                        ui.text(format!(
+                            "{name}: {:.4} ± {:.4} (std dev {:.4})",
+                            stats.mean(),
+                            stats.std_error(),
+                            stats.std_dev()
+                        ));
+                    }
+                }
+
+                ui.separator();
+                self.params.build_ui(ui);
+            });
+
+        self.base.render_ui(ui);
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn is_running(&self) -> bool {
+        self.running
+    }
+
+    fn set_running(&mut self, running: bool) {
+        self.running = running;
+    }
+
+    fn reset(&mut self, scene: &mut Scene) {
+        self.restart(self.replicas.len());
+        self.base.reset(scene);
+    }
+
+    fn initialize_gpu(&mut self, device: &Device, queue: &Queue) {
+        self.base.initialize_gpu(device, queue);
+    }
+
+    fn update_gpu(&mut self, device: &Device, queue: &Queue, delta_time: f32) {
+        self.base.update_gpu(device, queue, delta_time);
+    }
+
+    fn apply_gpu_results_to_scene(&mut self, device: &Device, scene: &mut Scene) {
+        self.base.apply_gpu_results_to_scene(device, scene);
+    }
+
+    fn params_mut(&mut self) -> Option<&mut dyn SimParams> {
+        Some(&mut self.params)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        &self.base
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    /// Unbiased ±1 walk on a line, observed as a one-hot field
+    struct LineWalker {
+        position: i32,
+    }
+
+    impl StochasticModel for LineWalker {
+        fn step(&mut self, rng: &mut StdRng) {
+            self.position =
+                (self.position + if rng.random_bool(0.5) { 1 } else { -1 }).clamp(0, 40);
+        }
+
+        fn field_dims(&self) -> [u32; 2] {
+            [41, 1]
+        }
+
+        fn observe(&self, field: &mut [f32]) {
+            field[self.position as usize] = 1.0;
+        }
+
+        fn scalars(&self) -> Vec<(&'static str, f64)> {
+            vec![("displacement", (self.position - 20) as f64)]
+        }
+    }
+
+    #[test]
+    fn test_running_stats() {
+        let mut stats = RunningStats::new();
+        for value in [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0] {
+            stats.push(value);
+        }
+        assert_eq!(stats.count(), 8);
+        assert!((stats.mean() - 5.0).abs() < 1e-12);
+        assert!((stats.variance() - 32.0 / 7.0).abs() < 1e-12);
+        assert!((stats.std_error() - (4.0f64 / 7.0).sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_parallel_matches_serial_and_diffuses() {
+        let walkers = |_: &mut StdRng| LineWalker { position: 20 };
+        let mut parallel = Ensemble::new(400, walkers).with_seed(7);
+        let mut serial = Ensemble::new(400, walkers).with_seed(7).serial();
+        parallel.advance(16);
+        serial.advance(16);
+        assert_eq!(parallel.field_stats(), serial.field_stats());
+        assert_eq!(parallel.scalar_stats(), serial.scalar_stats());
+
+        // Occupancy sums to one, and the displacement variance grows like
+        // the number of steps
+        let mean = parallel.field_stats().mean();
+        assert!((mean.iter().sum::<f32>() - 1.0).abs() < 1e-4);
+        let (name, displacement) = parallel.scalar_stats()[0];
+        assert_eq!(name, "displacement");
+        assert!(displacement.mean().abs() < 4.0 * displacement.std_error());
+        assert!((displacement.variance() - 16.0).abs() < 4.0);
+
+        let mut other = Ensemble::new(400, walkers).with_seed(8);
+        other.advance(16);
+        assert_ne!(other.field_stats(), parallel.field_stats());
+    }
+}
@@ -31,7 +31,7 @@ impl SimplyMove {
 
 impl Simulation for SimplyMove {
     fn initialize(&mut self, scene: &mut Scene) {
-        println!("Initializing SimplyMove simulation...");
+        tracing::info!("Initializing SimplyMove simulation...");
 
         // Store initial Y positions of all objects
         self.initial_positions.clear();
@@ -121,7 +121,7 @@ fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
 
 impl Simulation for GpuSimplyMove {
     fn initialize(&mut self, scene: &mut Scene) {
-        println!("Initializing GPU SimplyMove simulation...");
+        tracing::info!("Initializing GPU SimplyMove simulation...");
 
         // Store initial Y positions
         self.initial_positions.clear();
@@ -134,7 +134,7 @@ impl Simulation for GpuSimplyMove {
             }
         }
 
-        println!("Found {} objects for GPU simulation", self.object_count);
+        tracing::info!("Found {} objects for GPU simulation", self.object_count);
     }
 
     fn update(&mut self, delta_time: f32, _scene: &mut Scene) {
@@ -232,11 +232,11 @@ impl Simulation for GpuSimplyMove {
     // GPU-specific methods - now much cleaner!
     fn initialize_gpu(&mut self, device: &Device, _queue: &Queue) {
         if self.object_count == 0 {
-            println!("No objects to simulate on GPU");
+            tracing::info!("No objects to simulate on GPU");
             return;
         }
 
-        println!(
+        tracing::info!(
             "Initializing GPU resources for {} objects",
             self.object_count
         );
@@ -292,7 +292,7 @@ impl Simulation for GpuSimplyMove {
         self.compute_pipeline = Some(compute_pipeline);
         self.gpu_ready = true;
 
-        println!("GPU resources initialized successfully");
+        tracing::info!("GPU resources initialized successfully");
     }
 
     fn update_gpu(&mut self, device: &Device, queue: &Queue, delta_time: f32) {
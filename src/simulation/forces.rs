@@ -0,0 +1,421 @@
+//! Forces on obstacles in lattice Boltzmann flows
+//!
+//! [`MomentumExchange`] measures the force a D3Q19 flow exerts on the
+//! obstacle cells of a [`BoundaryConditions`], by summing the momentum each
+//! distribution hands over when it bounces back off an obstacle: `2 c_i f_i`
+//! for every link from a non-obstacle cell into an obstacle. The sum runs on
+//! the GPU and is read back without blocking, so it can run every frame.
+//! [`ForceReference`] turns the force into drag and lift coefficients.
+//!
+//! The distributions are read after [`D3Q19_BOUNDARY_WGSL`] bounced them back,
+//! i.e. after the collision pass, with 19 values per cell and cells x fastest.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::simulation::boundary::{BoundaryBuffers, BoundaryConditions};
+//! use haggis::simulation::forces::{projected_area, ForceReference, MomentumExchange};
+//!
+//! # fn example(device: &wgpu::Device, queue: &wgpu::Queue, distributions: [&wgpu::Buffer; 2]) {
+//! let boundaries = BoundaryConditions::new([128, 64, 64]);
+//! let buffers = BoundaryBuffers::new(device, queue, &boundaries);
+//! let mut forces = MomentumExchange::new(device, &boundaries, &buffers, &distributions);
+//! let reference = ForceReference::new(1.0, 0.05, projected_area(&boundaries, 0));
+//!
+//! // After a step that left the distributions in the second buffer
+//! forces.measure(device, queue, 1);
+//! // On a later frame
+//! if let Some(force) = forces.poll(device) {
+//!     let coefficients = reference.coefficients(force);
+//!     println!("Cd {:.3}, Cl {:.3}", coefficients.drag, coefficients.lift);
+//! }
+//! # }
+//! ```
+//!
+//! [`D3Q19_BOUNDARY_WGSL`]: super::boundary::D3Q19_BOUNDARY_WGSL
+
+use std::sync::{Arc, OnceLock};
+
+use wgpu::{Buffer, Device, Queue};
+
+use crate::simulation::boundary::{BoundaryBuffers, BoundaryConditions, D3Q19_WGSL};
+use crate::wgpu_utils::compute_primitives::{Reduce, ReduceOp};
+
+/// Size of the three read back force components
+const FORCE_SIZE: u64 = 3 * std::mem::size_of::<f32>() as u64;
+
+/// Drag and lift relative to the dynamic pressure on a reference area
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ForceCoefficients {
+    pub drag: f32,
+    pub lift: f32,
+}
+
+/// Free-stream scales and directions that normalize a force
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ForceReference {
+    pub density: f32,
+    pub velocity: f32,
+    /// Reference area in cells, e.g. the frontal area from [`projected_area`]
+    pub area: f32,
+    /// Unit vector along the free stream
+    pub drag_direction: [f32; 3],
+    /// Unit vector across the free stream
+    pub lift_direction: [f32; 3],
+}
+
+impl ForceReference {
+    /// Flow along +X with lift along +Y
+    pub fn new(density: f32, velocity: f32, area: f32) -> Self {
+        Self {
+            density,
+            velocity,
+            area,
+            drag_direction: [1.0, 0.0, 0.0],
+            lift_direction: [0.0, 1.0, 0.0],
+        }
+    }
+
+    pub fn with_directions(mut self, drag: [f32; 3], lift: [f32; 3]) -> Self {
+        self.drag_direction = drag;
+        self.lift_direction = lift;
+        self
+    }
+
+    /// `0.5 * density * velocity² * area`
+    pub fn dynamic_force(&self) -> f32 {
+        0.5 * self.density * self.velocity * self.velocity * self.area
+    }
+
+    /// Drag and lift coefficients of `force`, zero without a flow or area
+    pub fn coefficients(&self, force: [f32; 3]) -> ForceCoefficients {
+        let scale = self.dynamic_force();
+        if scale <= 0.0 {
+            return ForceCoefficients::default();
+        }
+        let along = |direction: [f32; 3]| {
+            (0..3)
+                .map(|axis| force[axis] * direction[axis])
+                .sum::<f32>()
+                / scale
+        };
+        ForceCoefficients {
+            drag: along(self.drag_direction),
+            lift: along(self.lift_direction),
+        }
+    }
+}
+
+/// Obstacle cells seen along `axis`, in cells, as a reference area
+pub fn projected_area(boundaries: &BoundaryConditions, axis: usize) -> f32 {
+    let size = boundaries.size();
+    let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+    let mut area = 0;
+    for a in 0..size[u] {
+        for b in 0..size[v] {
+            let covered = (0..size[axis]).any(|depth| {
+                let mut cell = [0; 3];
+                (cell[axis], cell[u], cell[v]) = (depth, a, b);
+                boundaries.is_obstacle(cell)
+            });
+            area += covered as u32;
+        }
+    }
+    area as f32
+}
+
+#[derive(Debug)]
+enum ReadState {
+    Idle,
+    /// Waiting for the readback buffer to map, then whether it mapped
+    Reading(Arc<OnceLock<bool>>),
+}
+
+/// Total force of a D3Q19 flow on the obstacle cells, measured on the GPU
+pub struct MomentumExchange {
+    cells: u32,
+    size: [u32; 3],
+    pipeline: wgpu::ComputePipeline,
+    /// One per distribution buffer
+    bind_groups: Vec<wgpu::BindGroup>,
+    /// Force per cell, one buffer per component
+    _cell_forces: [Buffer; 3],
+    reductions: [Reduce<f32>; 3],
+    readback: Buffer,
+    state: ReadState,
+    latest: Option<[f32; 3]>,
+}
+
+impl MomentumExchange {
+    /// Measure forces on the obstacles of `boundaries` in any of
+    /// `distributions`, which all need `STORAGE` usage
+    pub fn new(
+        device: &Device,
+        boundaries: &BoundaryConditions,
+        buffers: &BoundaryBuffers,
+        distributions: &[&Buffer],
+    ) -> Self {
+        let size = boundaries.size();
+        let cells = size.iter().product::<u32>();
+        let cell_forces = ["X", "Y", "Z"].map(|axis| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(&format!("Cell Force {axis}")),
+                size: cells as u64 * 4,
+                usage: wgpu::BufferUsages::STORAGE,
+                mapped_at_creation: false,
+            })
+        });
+        let reductions =
+            [0, 1, 2].map(|axis| Reduce::new(device, ReduceOp::Sum, &cell_forces[axis], cells));
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Obstacle Force Readback"),
+            size: FORCE_SIZE,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let storage = |binding, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let mut entries = vec![storage(0, true)];
+        entries.extend(BoundaryConditions::bind_group_layout_entries(
+            1,
+            wgpu::ShaderStages::COMPUTE,
+        ));
+        entries.extend([storage(3, false), storage(4, false), storage(5, false)]);
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Momentum Exchange Layout"),
+            entries: &entries,
+        });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Momentum Exchange Shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                format!("{}{D3Q19_WGSL}{FORCE_SHADER}", boundaries.wgsl(0, 1)).into(),
+            ),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Momentum Exchange Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Momentum Exchange Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: Some("cell_forces"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let bind_groups = distributions
+            .iter()
+            .map(|distributions| {
+                let mut entries = vec![wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: distributions.as_entire_binding(),
+                }];
+                entries.extend(buffers.bind_group_entries(1));
+                entries.extend((0..3).map(|axis| wgpu::BindGroupEntry {
+                    binding: 3 + axis as u32,
+                    resource: cell_forces[axis].as_entire_binding(),
+                }));
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Momentum Exchange Bind Group"),
+                    layout: &layout,
+                    entries: &entries,
+                })
+            })
+            .collect();
+
+        Self {
+            cells,
+            size,
+            pipeline,
+            bind_groups,
+            _cell_forces: cell_forces,
+            reductions,
+            readback,
+            state: ReadState::Idle,
+            latest: None,
+        }
+    }
+
+    /// Whether a new measurement can start; false while one is read back
+    pub fn is_idle(&self) -> bool {
+        matches!(self.state, ReadState::Idle)
+    }
+
+    /// Sum the force in distribution buffer `index` and start reading it back
+    ///
+    /// # Returns
+    ///
+    /// `false`, measuring nothing, if the last measurement is still pending
+    pub fn measure(&mut self, device: &Device, queue: &Queue, index: usize) -> bool {
+        if !self.is_idle() {
+            return false;
+        }
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Momentum Exchange Encoder"),
+        });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Momentum Exchange"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &self.bind_groups[index], &[]);
+            let [x, y, z] = self.size.map(|size| size.div_ceil(4));
+            pass.dispatch_workgroups(x, y, z);
+        }
+        for (axis, reduction) in self.reductions.iter().enumerate() {
+            reduction.encode(queue, &mut encoder, self.cells);
+            encoder.copy_buffer_to_buffer(
+                reduction.result(),
+                0,
+                &self.readback,
+                axis as u64 * 4,
+                4,
+            );
+        }
+        queue.submit(Some(encoder.finish()));
+
+        let mapped = Arc::new(OnceLock::new());
+        {
+            let mapped = mapped.clone();
+            self.readback
+                .slice(..)
+                .map_async(wgpu::MapMode::Read, move |result| {
+                    let _ = mapped.set(result.is_ok());
+                });
+        }
+        self.state = ReadState::Reading(mapped);
+        true
+    }
+
+    /// The finished measurement, if it has been read back
+    ///
+    /// Processes device callbacks without blocking.
+    pub fn poll(&mut self, device: &Device) -> Option<[f32; 3]> {
+        let ReadState::Reading(mapped) = &self.state else {
+            return None;
+        };
+        if mapped.get().is_none() {
+            let _ = device.poll(wgpu::MaintainBase::Poll);
+        }
+        match mapped.get() {
+            None => return None,
+            Some(false) => {
+                self.state = ReadState::Idle;
+                return None;
+            }
+            Some(true) => {}
+        }
+        let force: [f32; 3] = {
+            let range = self.readback.slice(..).get_mapped_range();
+            bytemuck::pod_read_unaligned(&range)
+        };
+        self.readback.unmap();
+        self.state = ReadState::Idle;
+        self.latest = Some(force);
+        Some(force)
+    }
+
+    /// Most recent force read back, in lattice units
+    pub fn latest(&self) -> Option<[f32; 3]> {
+        self.latest
+    }
+}
+
+const FORCE_SHADER: &str = r#"
+@group(0) @binding(0) var<storage, read> distributions: array<f32>;
+@group(0) @binding(3) var<storage, read_write> force_x: array<f32>;
+@group(0) @binding(4) var<storage, read_write> force_y: array<f32>;
+@group(0) @binding(5) var<storage, read_write> force_z: array<f32>;
+
+@compute @workgroup_size(4, 4, 4)
+fn cell_forces(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id >= BOUNDARY_SIZE)) {
+        return;
+    }
+    let index = id.x + BOUNDARY_SIZE.x * (id.y + BOUNDARY_SIZE.y * id.z);
+    var force = vec3<f32>(0.0);
+    if (boundary_code(id) == BOUNDARY_OBSTACLE) {
+        for (var i = 1u; i < 19u; i++) {
+            // Arrived along c_i from a fluid cell, now bounced into the opposite slot
+            let source = boundary_wrap(vec3<i32>(id) - D3Q19_VELOCITIES[i]);
+            if (boundary_code(source) != BOUNDARY_OBSTACLE) {
+                force += 2.0 * d3q19_velocity(i) * distributions[index * 19u + d3q19_opposite(i)];
+            }
+        }
+    }
+    force_x[index] = force.x;
+    force_y[index] = force.y;
+    force_z[index] = force.z;
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wgpu_utils::compute_primitives::{test_buffer, test_device};
+
+    #[test]
+    fn test_coefficients_and_area() {
+        let mut boundaries = BoundaryConditions::new([4, 4, 4]);
+        boundaries.set_obstacle([1, 1, 1], true);
+        boundaries.set_obstacle([2, 1, 1], true);
+        boundaries.set_obstacle([2, 2, 1], true);
+        assert_eq!(projected_area(&boundaries, 0), 2.0);
+        assert_eq!(projected_area(&boundaries, 2), 3.0);
+
+        // Dynamic force 0.5 * 1 * 0.1² * 2 = 0.01
+        let reference = ForceReference::new(1.0, 0.1, 2.0);
+        let coefficients = reference.coefficients([0.02, -0.005, 1.0]);
+        assert!((coefficients.drag - 2.0).abs() < 1e-5);
+        assert!((coefficients.lift + 0.5).abs() < 1e-5);
+        assert_eq!(
+            ForceReference::new(1.0, 0.0, 2.0).coefficients([1.0; 3]),
+            ForceCoefficients::default()
+        );
+    }
+
+    #[test]
+    fn test_momentum_exchange_on_single_obstacle() {
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let mut boundaries = BoundaryConditions::new([3, 3, 3]);
+        boundaries.set_obstacle([1, 1, 1], true);
+        let buffers = BoundaryBuffers::new(&device, &queue, &boundaries);
+
+        // Fluid at rest, with the bounced-back distributions in the obstacle
+        // carrying momentum 0.01 along +X
+        let weights = [1.0 / 3.0]
+            .into_iter()
+            .chain([1.0 / 18.0; 6])
+            .chain([1.0 / 36.0; 12])
+            .collect::<Vec<f32>>();
+        let mut f = weights.repeat(27);
+        f[13 * 19 + 1] += 0.01;
+        let distributions = test_buffer(&device, &f);
+
+        let mut forces = MomentumExchange::new(&device, &boundaries, &buffers, &[&distributions]);
+        assert!(forces.measure(&device, &queue, 0));
+        assert!(!forces.measure(&device, &queue, 0));
+        let _ = device.poll(wgpu::MaintainBase::Wait);
+        let force = forces.poll(&device).unwrap();
+
+        // The fluid pushed that momentum, twice over, into -X
+        assert!((force[0] + 0.02).abs() < 1e-5, "{force:?}");
+        assert!(force[1].abs() < 1e-5 && force[2].abs() < 1e-5);
+        assert_eq!(forces.latest(), Some(force));
+        assert!(forces.is_idle());
+    }
+}
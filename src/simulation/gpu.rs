@@ -1,6 +1,27 @@
 //! GPU simulation utilities and base types
 //!
-//! Provides compute shader infrastructure for GPU-based simulations
+//! Provides compute shader infrastructure for GPU-based simulations,
+//! [`SpatialHash`], a counting-sort spatial hash for neighbor queries in
+//! compute shaders, [`ComputeDevice`] for running simulations on a second
+//! GPU with [`SharedBuffer`] copies to the display GPU, [`GpuFence`] for
+//! overlapping simulation steps with rendering, [`GpuTimer`] for measuring
+//! their GPU time, [`SparseGrid`] for
+//! mostly-empty domains, and [`AmrHierarchy2D`] for locally refined patches
+//! on 2D fields
+
+pub mod amr;
+pub mod compute_device;
+pub mod fence;
+pub mod sparse_grid;
+pub mod spatial_hash;
+pub mod timer;
+
+pub use amr::{AmrHierarchy2D, AmrLayout, AmrPatch};
+pub use compute_device::{AdapterPreference, ComputeDevice, SharedBuffer};
+pub use fence::GpuFence;
+pub use sparse_grid::{SparseGrid, SparseSnapshot};
+pub use spatial_hash::SpatialHash;
+pub use timer::GpuTimer;
 
 use std::marker::PhantomData;
 use wgpu::{BindGroup, Buffer, ComputePipeline, Device, Queue};
@@ -99,6 +120,7 @@ impl<T: bytemuck::Pod + bytemuck::Zeroable> GpuSimulationBase<T> {
                 });
 
                 {
+                    crate::logging::crash::record_pass("Compute Pass");
                     let mut compute_pass =
                         encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
                             label: Some("Compute Pass"),
@@ -119,6 +141,39 @@ impl<T: bytemuck::Pod + bytemuck::Zeroable> GpuSimulationBase<T> {
         }
     }
 
+    /// Execute compute shader with workgroup counts read from `indirect`
+    ///
+    /// `indirect` holds [`DispatchIndirectArgs`](crate::wgpu_utils::indirect::DispatchIndirectArgs)
+    /// at `offset`, usually written by an earlier compute pass, so the
+    /// dispatch follows a count that only the GPU knows.
+    pub fn dispatch_compute_indirect(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        indirect: &Buffer,
+        offset: u64,
+    ) {
+        if let (Some(pipeline), Some(bind_group)) = (&self.compute_pipeline, &self.bind_group) {
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Compute Encoder"),
+            });
+
+            {
+                crate::logging::crash::record_pass("Indirect Compute Pass");
+                let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: Some("Indirect Compute Pass"),
+                    timestamp_writes: None,
+                });
+
+                compute_pass.set_pipeline(pipeline);
+                compute_pass.set_bind_group(0, bind_group, &[]);
+                compute_pass.dispatch_workgroups_indirect(indirect, offset);
+            }
+
+            queue.submit(std::iter::once(encoder.finish()));
+        }
+    }
+
     /// Update uniform buffer data
     pub fn update_uniforms<U: bytemuck::Pod>(&self, queue: &Queue, data: &U) {
         if let Some(buffer) = &self.uniform_buffer {
@@ -0,0 +1,705 @@
+//! Two-level adaptive mesh refinement for 2D field simulations
+//!
+//! Resolving the flow around an obstacle or a sharp front doesn't need a fine
+//! grid everywhere. An [`AmrLayout`] places rectangular patches on a coarse
+//! grid, each refined by the same integer ratio, and an [`AmrHierarchy2D`]
+//! keeps the fine values of all patches in one GPU buffer next to the
+//! solver's coarse field:
+//!
+//! - **Prolong** - fill the patches by bilinear interpolation of the coarse
+//!   field, e.g. after a reset
+//! - **Ghost cells** - a fine stencil reaching past its patch reads the
+//!   coarse field interpolated at the fine cell center
+//! - **Restrict** - average every patch back onto the coarse cells it covers,
+//!   so the coarse solve and visualization see the refined solution
+//!
+//! A step solves the coarse grid, then the patches with ghost values from the
+//! new coarse field, then restricts. Fine stencils use the WGSL from
+//! [`AmrHierarchy2D::query_wgsl`]:
+//!
+//! ```wgsl
+//! @compute @workgroup_size(256)
+//! fn fine_step(@builtin(global_invocation_id) id: vec3<u32>) {
+//!     if (id.x >= amr_grid.fine_cells) {
+//!         return;
+//!     }
+//!     let patch_index = amr_patch_at(id.x);
+//!     let cell = amr_global_cell(patch_index, id.x);
+//!     let sum = amr_sample(patch_index, cell + vec2<i32>(-1, 0)) + amr_sample(patch_index, cell + vec2<i32>(1, 0))
+//!         + amr_sample(patch_index, cell + vec2<i32>(0, -1)) + amr_sample(patch_index, cell + vec2<i32>(0, 1));
+//!     fine_out[id.x] = 0.25 * sum;
+//! }
+//! ```
+//!
+//! `amr_sample` takes cells in global fine coordinates and clamps coarse
+//! reads to the domain, so stencils at the domain edge apply their boundary
+//! condition before sampling.
+//! [`HeatDiffusion2D::with_refinement`](crate::simulation::templates::HeatDiffusion2D::with_refinement)
+//! is a complete example, including patch outlines over the heatmap.
+
+use wgpu::util::DeviceExt;
+use wgpu::{Buffer, CommandEncoder, Device, Queue};
+
+use crate::wgpu_utils::compute_primitives::read_buffer;
+use crate::wgpu_utils::WgslStruct;
+
+const WORKGROUP_SIZE: u32 = 256;
+const MAX_WORKGROUPS: u32 = 65_535;
+
+/// Refined rectangle of the coarse grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AmrPatch {
+    /// First coarse cell covered
+    pub origin: [u32; 2],
+    /// Coarse cells covered
+    pub size: [u32; 2],
+}
+
+impl AmrPatch {
+    fn contains(&self, cell: [u32; 2]) -> bool {
+        (0..2).all(|axis| {
+            cell[axis] >= self.origin[axis] && cell[axis] < self.origin[axis] + self.size[axis]
+        })
+    }
+
+    fn overlaps(&self, other: &AmrPatch) -> bool {
+        (0..2).all(|axis| {
+            self.origin[axis] < other.origin[axis] + other.size[axis]
+                && other.origin[axis] < self.origin[axis] + self.size[axis]
+        })
+    }
+}
+
+/// Coarse grid size, refinement ratio and patch placement
+#[derive(Debug, Clone, PartialEq)]
+pub struct AmrLayout {
+    coarse: [u32; 2],
+    ratio: u32,
+    patches: Vec<AmrPatch>,
+}
+
+impl AmrLayout {
+    /// Layout without patches over a `coarse` grid, refining by `ratio`
+    pub fn new(coarse: [u32; 2], ratio: u32) -> Self {
+        assert!(
+            coarse[0] > 0 && coarse[1] > 0,
+            "coarse grid must not be empty"
+        );
+        assert!(ratio >= 2, "refinement ratio must be at least 2");
+        Self {
+            coarse,
+            ratio,
+            patches: Vec::new(),
+        }
+    }
+
+    /// Refine `size` coarse cells starting at `origin`
+    ///
+    /// # Panics
+    ///
+    /// If the patch is empty, leaves the coarse grid or overlaps another patch
+    pub fn with_patch(mut self, origin: [u32; 2], size: [u32; 2]) -> Self {
+        let patch = AmrPatch { origin, size };
+        assert!(
+            size[0] > 0 && size[1] > 0,
+            "refined patch must not be empty"
+        );
+        assert!(
+            (0..2).all(|axis| origin[axis] + size[axis] <= self.coarse[axis]),
+            "refined patch {:?} leaves the {:?} coarse grid",
+            patch,
+            self.coarse
+        );
+        assert!(
+            !self.patches.iter().any(|other| other.overlaps(&patch)),
+            "refined patch {:?} overlaps another patch",
+            patch
+        );
+        self.patches.push(patch);
+        self
+    }
+
+    pub fn coarse_size(&self) -> [u32; 2] {
+        self.coarse
+    }
+
+    pub fn ratio(&self) -> u32 {
+        self.ratio
+    }
+
+    pub fn patches(&self) -> &[AmrPatch] {
+        &self.patches
+    }
+
+    /// Fine cells of a patch along each axis
+    pub fn fine_size(&self, patch: usize) -> [u32; 2] {
+        self.patches[patch].size.map(|size| size * self.ratio)
+    }
+
+    /// Index of a patch's first value in the fine buffer
+    pub fn fine_offset(&self, patch: usize) -> usize {
+        (0..patch)
+            .map(|index| {
+                let [w, h] = self.fine_size(index);
+                (w * h) as usize
+            })
+            .sum()
+    }
+
+    /// Fine values of all patches together
+    pub fn fine_len(&self) -> usize {
+        self.fine_offset(self.patches.len())
+    }
+
+    /// Patch bounds as fractions of the coarse grid, `[min, max]`
+    pub fn patch_rect(&self, patch: usize) -> [[f32; 2]; 2] {
+        let AmrPatch { origin, size } = self.patches[patch];
+        [
+            [0, 1].map(|axis| origin[axis] as f32 / self.coarse[axis] as f32),
+            [0, 1].map(|axis| (origin[axis] + size[axis]) as f32 / self.coarse[axis] as f32),
+        ]
+    }
+
+    /// Bilinear interpolation of `coarse` at a point in coarse cell units,
+    /// clamped to the cell centers at the domain edge
+    pub fn sample_coarse(&self, coarse: &[f32], point: [f32; 2]) -> f32 {
+        let [w, h] = self.coarse;
+        let lerp_axis = |p: f32, size: u32| {
+            let p = (p - 0.5).clamp(0.0, (size - 1) as f32);
+            let i0 = (p.floor() as u32).min(size.saturating_sub(2));
+            let i1 = (i0 + 1).min(size - 1);
+            (i0, i1, p - i0 as f32)
+        };
+        let (x0, x1, tx) = lerp_axis(point[0], w);
+        let (y0, y1, ty) = lerp_axis(point[1], h);
+        let at = |x: u32, y: u32| coarse[(y * w + x) as usize];
+        let bottom = at(x0, y0) + (at(x1, y0) - at(x0, y0)) * tx;
+        let top = at(x0, y1) + (at(x1, y1) - at(x0, y1)) * tx;
+        bottom + (top - bottom) * ty
+    }
+
+    /// Fine values of all patches interpolated from a coarse field, the CPU
+    /// equivalent of [`AmrHierarchy2D::prolong`]
+    pub fn prolong(&self, coarse: &[f32]) -> Vec<f32> {
+        let ratio = self.ratio as f32;
+        let mut fine = Vec::with_capacity(self.fine_len());
+        for (index, patch) in self.patches.iter().enumerate() {
+            let [w, h] = self.fine_size(index);
+            for y in 0..h {
+                for x in 0..w {
+                    let point = [
+                        patch.origin[0] as f32 + (x as f32 + 0.5) / ratio,
+                        patch.origin[1] as f32 + (y as f32 + 0.5) / ratio,
+                    ];
+                    fine.push(self.sample_coarse(coarse, point));
+                }
+            }
+        }
+        fine
+    }
+
+    /// Replace covered coarse cells with the mean of their fine cells, the CPU
+    /// equivalent of [`AmrHierarchy2D::restrict`]
+    pub fn restrict(&self, coarse: &mut [f32], fine: &[f32]) {
+        let ratio = self.ratio;
+        for (index, patch) in self.patches.iter().enumerate() {
+            let offset = self.fine_offset(index);
+            let [w, _] = self.fine_size(index);
+            for cy in 0..patch.size[1] {
+                for cx in 0..patch.size[0] {
+                    let mut sum = 0.0;
+                    for y in cy * ratio..(cy + 1) * ratio {
+                        for x in cx * ratio..(cx + 1) * ratio {
+                            sum += fine[offset + (y * w + x) as usize];
+                        }
+                    }
+                    let cell = (patch.origin[1] + cy) * self.coarse[0] + patch.origin[0] + cx;
+                    coarse[cell as usize] = sum / (ratio * ratio) as f32;
+                }
+            }
+        }
+    }
+
+    /// Patch covering a coarse cell
+    pub fn patch_at(&self, cell: [u32; 2]) -> Option<usize> {
+        self.patches.iter().position(|patch| patch.contains(cell))
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable, WgslStruct)]
+#[wgsl(uniform)]
+struct AmrUniforms {
+    coarse: [u32; 2],
+    ratio: u32,
+    patch_count: u32,
+    fine_cells: u32,
+    coarse_cells: u32,
+    _padding: [u32; 2],
+}
+
+/// Patch as seen by the shaders
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable, WgslStruct)]
+#[wgsl(name = "AmrPatch")]
+struct GpuAmrPatch {
+    origin: [u32; 2],
+    size: [u32; 2],
+    /// First fine value of the patch
+    fine_offset: u32,
+    /// Covered coarse cells of earlier patches
+    coarse_offset: u32,
+    _padding: [u32; 2],
+}
+
+/// Fine patches of an [`AmrLayout`] on the GPU, attached to a coarse field
+///
+/// The coarse buffer holds `f32` per cell, row by row from the bottom, and
+/// needs `STORAGE` usage.
+pub struct AmrHierarchy2D {
+    layout: AmrLayout,
+    uniforms: Buffer,
+    patches: Buffer,
+    coarse: Buffer,
+    fine: Buffer,
+    bind_group: wgpu::BindGroup,
+    prolong_pipeline: wgpu::ComputePipeline,
+    restrict_pipeline: wgpu::ComputePipeline,
+    fine_groups: u32,
+    coarse_groups: u32,
+}
+
+impl AmrHierarchy2D {
+    /// Allocate the fine buffer of `layout` and attach it to `coarse`
+    ///
+    /// # Panics
+    ///
+    /// If the layout has no patches or too many fine cells for one dispatch
+    pub fn new(device: &Device, layout: AmrLayout, coarse: &Buffer) -> Self {
+        assert!(!layout.patches.is_empty(), "AMR layout has no patches");
+        let fine_cells = layout.fine_len() as u32;
+        let coarse_cells: u32 = layout.patches.iter().map(|p| p.size[0] * p.size[1]).sum();
+        assert!(
+            fine_cells.div_ceil(WORKGROUP_SIZE) <= MAX_WORKGROUPS,
+            "AMR patches exceed {} fine cells",
+            MAX_WORKGROUPS * WORKGROUP_SIZE
+        );
+
+        let settings = AmrUniforms {
+            coarse: layout.coarse,
+            ratio: layout.ratio,
+            patch_count: layout.patches.len() as u32,
+            fine_cells,
+            coarse_cells,
+            _padding: [0; 2],
+        };
+        let mut coarse_offset = 0;
+        let gpu_patches: Vec<GpuAmrPatch> = layout
+            .patches
+            .iter()
+            .enumerate()
+            .map(|(index, patch)| {
+                let gpu = GpuAmrPatch {
+                    origin: patch.origin,
+                    size: patch.size,
+                    fine_offset: layout.fine_offset(index) as u32,
+                    coarse_offset,
+                    _padding: [0; 2],
+                };
+                coarse_offset += patch.size[0] * patch.size[1];
+                gpu
+            })
+            .collect();
+
+        let uniforms = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("AMR Uniforms"),
+            contents: bytemuck::bytes_of(&settings),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let patches = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("AMR Patches"),
+            contents: bytemuck::cast_slice(&gpu_patches),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let fine = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("AMR Fine Field"),
+            size: fine_cells as u64 * 4,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("AMR Shader"),
+            source: wgpu::ShaderSource::Wgsl(
+                format!("{}{BUILD_WGSL}{FUNCTIONS_WGSL}", declarations_wgsl()).into(),
+            ),
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("AMR Bind Group Layout"),
+            entries: &Self::layout_entries(0, wgpu::ShaderStages::COMPUTE, false),
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("AMR Bind Group"),
+            layout: &bind_group_layout,
+            entries: &Self::entries(0, &uniforms, &patches, coarse, &fine),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("AMR Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |entry_point: &str| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+
+        Self {
+            layout,
+            uniforms,
+            patches,
+            coarse: coarse.clone(),
+            fine,
+            bind_group,
+            prolong_pipeline: pipeline("prolong_patches"),
+            restrict_pipeline: pipeline("restrict_patches"),
+            fine_groups: fine_cells.div_ceil(WORKGROUP_SIZE),
+            coarse_groups: coarse_cells.div_ceil(WORKGROUP_SIZE),
+        }
+    }
+
+    pub fn layout(&self) -> &AmrLayout {
+        &self.layout
+    }
+
+    /// Fine values of all patches, patch by patch and row by row (`f32` each)
+    pub fn fine(&self) -> &Buffer {
+        &self.fine
+    }
+
+    /// Coarse field the patches are attached to
+    pub fn coarse(&self) -> &Buffer {
+        &self.coarse
+    }
+
+    /// Record filling the patches from the coarse field
+    pub fn prolong(&self, encoder: &mut CommandEncoder) {
+        self.dispatch(
+            encoder,
+            "AMR Prolong",
+            &self.prolong_pipeline,
+            self.fine_groups,
+        );
+    }
+
+    /// Record averaging the patches onto the coarse field
+    pub fn restrict(&self, encoder: &mut CommandEncoder) {
+        self.dispatch(
+            encoder,
+            "AMR Restrict",
+            &self.restrict_pipeline,
+            self.coarse_groups,
+        );
+    }
+
+    /// Upload fine values for all patches, e.g. from [`AmrLayout::prolong`]
+    pub fn write_fine(&self, queue: &Queue, values: &[f32]) {
+        assert_eq!(
+            values.len(),
+            self.layout.fine_len(),
+            "fine values don't match the AMR layout"
+        );
+        queue.write_buffer(&self.fine, 0, bytemuck::cast_slice(values));
+    }
+
+    /// Read the fine values of all patches, waiting for the GPU
+    pub fn read_fine(
+        &self,
+        device: &Device,
+        queue: &Queue,
+    ) -> Result<Vec<f32>, wgpu::BufferAsyncError> {
+        read_buffer(device, queue, &self.fine, self.layout.fine_len())
+    }
+
+    fn dispatch(
+        &self,
+        encoder: &mut CommandEncoder,
+        label: &str,
+        pipeline: &wgpu::ComputePipeline,
+        groups: u32,
+    ) {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some(label),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.dispatch_workgroups(groups, 1, 1);
+    }
+
+    fn layout_entries(
+        first_binding: u32,
+        visibility: wgpu::ShaderStages,
+        read_only: bool,
+    ) -> [wgpu::BindGroupLayoutEntry; 4] {
+        let entry = |offset: u32, ty: wgpu::BufferBindingType| wgpu::BindGroupLayoutEntry {
+            binding: first_binding + offset,
+            visibility,
+            ty: wgpu::BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        [
+            entry(0, wgpu::BufferBindingType::Uniform),
+            entry(1, wgpu::BufferBindingType::Storage { read_only: true }),
+            entry(2, wgpu::BufferBindingType::Storage { read_only }),
+            entry(3, wgpu::BufferBindingType::Storage { read_only }),
+        ]
+    }
+
+    fn entries<'a>(
+        first_binding: u32,
+        uniforms: &'a Buffer,
+        patches: &'a Buffer,
+        coarse: &'a Buffer,
+        fine: &'a Buffer,
+    ) -> [wgpu::BindGroupEntry<'a>; 4] {
+        let buffers = [uniforms, patches, coarse, fine];
+        std::array::from_fn(|offset| wgpu::BindGroupEntry {
+            binding: first_binding + offset as u32,
+            resource: buffers[offset].as_entire_binding(),
+        })
+    }
+
+    /// Layout entries for fine stencils: uniforms, patches, coarse field and
+    /// fine field (read only) at `first_binding..first_binding + 4`
+    pub fn bind_group_layout_entries(
+        first_binding: u32,
+        visibility: wgpu::ShaderStages,
+    ) -> [wgpu::BindGroupLayoutEntry; 4] {
+        Self::layout_entries(first_binding, visibility, true)
+    }
+
+    /// Bind group entries matching [`bind_group_layout_entries`](Self::bind_group_layout_entries)
+    ///
+    /// `fine` is read as the fine field, either [`fine`](Self::fine) or a
+    /// solver's scratch copy with the same layout.
+    pub fn bind_group_entries<'a>(
+        &'a self,
+        first_binding: u32,
+        fine: &'a Buffer,
+    ) -> [wgpu::BindGroupEntry<'a>; 4] {
+        Self::entries(
+            first_binding,
+            &self.uniforms,
+            &self.patches,
+            &self.coarse,
+            fine,
+        )
+    }
+
+    /// WGSL declaring the AMR bindings and helper functions
+    ///
+    /// Defines `amr_patch_at` (patch of a fine index), `amr_global_cell`
+    /// (global fine cell of a fine index), `amr_sample` (fine value, or the
+    /// interpolated coarse field outside the patch) and `amr_coarse_sample`.
+    pub fn query_wgsl(group: u32, first_binding: u32) -> String {
+        format!(
+            "{declarations}
+@group({group}) @binding({b0}) var<uniform> amr_grid: AmrUniforms;
+@group({group}) @binding({b1}) var<storage, read> amr_patches: array<AmrPatch>;
+@group({group}) @binding({b2}) var<storage, read> amr_coarse: array<f32>;
+@group({group}) @binding({b3}) var<storage, read> amr_fine: array<f32>;
+{FUNCTIONS_WGSL}",
+            declarations = declarations_wgsl(),
+            b0 = first_binding,
+            b1 = first_binding + 1,
+            b2 = first_binding + 2,
+            b3 = first_binding + 3,
+        )
+    }
+}
+
+fn declarations_wgsl() -> String {
+    format!("\n{}\n{}", AmrUniforms::wgsl_struct(), GpuAmrPatch::wgsl_struct())
+}
+
+const FUNCTIONS_WGSL: &str = r#"
+// Patch holding the fine value at `index`
+fn amr_patch_at(index: u32) -> u32 {
+    var found = 0u;
+    for (var p = 1u; p < amr_grid.patch_count; p++) {
+        if (amr_patches[p].fine_offset <= index) {
+            found = p;
+        }
+    }
+    return found;
+}
+
+// Global fine cell of the value at `index` in patch `patch_index`
+fn amr_global_cell(patch_index: u32, index: u32) -> vec2<i32> {
+    let p = amr_patches[patch_index];
+    let width = p.size.x * amr_grid.ratio;
+    let local = index - p.fine_offset;
+    return vec2<i32>(p.origin * amr_grid.ratio + vec2<u32>(local % width, local / width));
+}
+
+// Bilinear interpolation of the coarse field at a point in coarse cell units
+fn amr_coarse_sample(point: vec2<f32>) -> f32 {
+    let size = vec2<i32>(amr_grid.coarse);
+    let p = clamp(point - 0.5, vec2<f32>(0.0), vec2<f32>(size - 1));
+    let i0 = min(vec2<i32>(floor(p)), max(size - 2, vec2<i32>(0)));
+    let i1 = min(i0 + 1, size - 1);
+    let t = p - vec2<f32>(i0);
+    let w = size.x;
+    let bottom = mix(amr_coarse[i0.y * w + i0.x], amr_coarse[i0.y * w + i1.x], t.x);
+    let top = mix(amr_coarse[i1.y * w + i0.x], amr_coarse[i1.y * w + i1.x], t.x);
+    return mix(bottom, top, t.y);
+}
+
+// Value of a global fine cell: from patch `patch_index` inside it, else interpolated
+// from the coarse field
+fn amr_sample(patch_index: u32, cell: vec2<i32>) -> f32 {
+    let p = amr_patches[patch_index];
+    let local = cell - vec2<i32>(p.origin * amr_grid.ratio);
+    let size = vec2<i32>(p.size * amr_grid.ratio);
+    if (all(local >= vec2<i32>(0)) && all(local < size)) {
+        return amr_fine[p.fine_offset + u32(local.y * size.x + local.x)];
+    }
+    return amr_coarse_sample((vec2<f32>(cell) + 0.5) / f32(amr_grid.ratio));
+}
+"#;
+
+const BUILD_WGSL: &str = r#"
+@group(0) @binding(0) var<uniform> amr_grid: AmrUniforms;
+@group(0) @binding(1) var<storage, read> amr_patches: array<AmrPatch>;
+@group(0) @binding(2) var<storage, read_write> amr_coarse: array<f32>;
+@group(0) @binding(3) var<storage, read_write> amr_fine: array<f32>;
+
+@compute @workgroup_size(256)
+fn prolong_patches(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= amr_grid.fine_cells) {
+        return;
+    }
+    let cell = amr_global_cell(amr_patch_at(id.x), id.x);
+    amr_fine[id.x] = amr_coarse_sample((vec2<f32>(cell) + 0.5) / f32(amr_grid.ratio));
+}
+
+@compute @workgroup_size(256)
+fn restrict_patches(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= amr_grid.coarse_cells) {
+        return;
+    }
+    var found = 0u;
+    for (var p = 1u; p < amr_grid.patch_count; p++) {
+        if (amr_patches[p].coarse_offset <= id.x) {
+            found = p;
+        }
+    }
+    let p = amr_patches[found];
+    let local = id.x - p.coarse_offset;
+    let coarse_cell = vec2<u32>(local % p.size.x, local / p.size.x);
+    let ratio = amr_grid.ratio;
+    let width = p.size.x * ratio;
+    var sum = 0.0;
+    for (var y = 0u; y < ratio; y++) {
+        for (var x = 0u; x < ratio; x++) {
+            let fine = coarse_cell * ratio + vec2<u32>(x, y);
+            sum += amr_fine[p.fine_offset + fine.y * width + fine.x];
+        }
+    }
+    let global = p.origin + coarse_cell;
+    amr_coarse[global.y * amr_grid.coarse.x + global.x] = sum / f32(ratio * ratio);
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prolong_restrict_round_trip() {
+        let layout = AmrLayout::new([8, 6], 2)
+            .with_patch([1, 1], [3, 2])
+            .with_patch([5, 0], [3, 6]);
+        assert_eq!(layout.fine_size(1), [6, 12]);
+        assert_eq!(layout.fine_offset(1), 24);
+        assert_eq!(layout.fine_len(), 96);
+        assert_eq!(layout.patch_at([6, 5]), Some(1));
+        assert_eq!(layout.patch_at([0, 0]), None);
+        assert_eq!(layout.patch_rect(0), [[0.125, 1.0 / 6.0], [0.5, 0.5]]);
+
+        // Linear fields are interpolated exactly away from the clamped edge
+        let coarse: Vec<f32> = (0..48)
+            .map(|i| (i % 8) as f32 + 2.0 * (i / 8) as f32)
+            .collect();
+        let fine = layout.prolong(&coarse);
+        assert_eq!(fine[0], 0.75 + 2.0 * 0.75);
+
+        // Averaging symmetric interpolants recovers interior coarse values
+        let mut restricted = coarse.clone();
+        layout.restrict(&mut restricted, &fine);
+        assert_eq!(restricted[8 + 1], coarse[8 + 1]);
+        assert_eq!(restricted[2 * 8 + 3], coarse[2 * 8 + 3]);
+        assert_eq!(std::mem::size_of::<AmrUniforms>(), 32);
+        assert_eq!(std::mem::size_of::<GpuAmrPatch>(), 32);
+    }
+
+    #[test]
+    #[should_panic(expected = "overlaps")]
+    fn test_overlapping_patches_panic() {
+        let _ = AmrLayout::new([8, 8], 2)
+            .with_patch([0, 0], [4, 4])
+            .with_patch([3, 3], [2, 2]);
+    }
+
+    #[test]
+    fn test_gpu_matches_cpu() {
+        use crate::wgpu_utils::compute_primitives::{test_buffer, test_device};
+
+        // Needs a GPU adapter, skipped without one
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let layout = AmrLayout::new([16, 12], 4)
+            .with_patch([2, 3], [4, 5])
+            .with_patch([9, 0], [7, 3]);
+        let coarse: Vec<f32> = (0..16 * 12).map(|i| ((i * 37) % 11) as f32).collect();
+        let coarse_buffer = test_buffer(&device, &coarse);
+        let amr = AmrHierarchy2D::new(&device, layout.clone(), &coarse_buffer);
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        amr.prolong(&mut encoder);
+        queue.submit(std::iter::once(encoder.finish()));
+        let fine = amr.read_fine(&device, &queue).unwrap();
+        let expected = layout.prolong(&coarse);
+        assert!(fine
+            .iter()
+            .zip(&expected)
+            .all(|(a, b)| (a - b).abs() < 1e-4));
+
+        let shifted: Vec<f32> = expected.iter().map(|v| v + 1.0).collect();
+        amr.write_fine(&queue, &shifted);
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        amr.restrict(&mut encoder);
+        queue.submit(std::iter::once(encoder.finish()));
+        let restricted: Vec<f32> =
+            read_buffer(&device, &queue, &coarse_buffer, coarse.len()).unwrap();
+        let mut expected = coarse.clone();
+        layout.restrict(&mut expected, &shifted);
+        assert!(restricted
+            .iter()
+            .zip(&expected)
+            .all(|(a, b)| (a - b).abs() < 1e-4));
+    }
+}
@@ -0,0 +1,246 @@
+//! Secondary GPU for compute-only simulation work
+//!
+//! On machines with two GPUs, heavy stepping (e.g. a lattice Boltzmann grid)
+//! can run on the discrete GPU while the integrated one drives the display.
+//! The adapter is chosen with
+//! [`HaggisAppBuilder::compute_adapter`](crate::app::builder::HaggisAppBuilder::compute_adapter);
+//! simulations then receive the compute device in `initialize_gpu`,
+//! `update_gpu` and `apply_gpu_results_to_scene`.
+//!
+//! Buffers can't be shared between devices. Results needed by the renderer
+//! are copied across with a [`SharedBuffer`] in
+//! [`Simulation::sync_to_display`](crate::simulation::traits::Simulation::sync_to_display):
+//! a readback on the compute device followed by an upload on the display
+//! device.
+
+use wgpu::{Buffer, Device, Queue};
+
+use crate::error::Result;
+use crate::gfx::capabilities::GpuCapabilities;
+use crate::gfx::rendering::render_config::DeviceLimits;
+
+/// Which adapter runs simulation compute work
+#[derive(Debug, Clone, PartialEq)]
+pub enum AdapterPreference {
+    /// First discrete GPU
+    Discrete,
+    /// First integrated GPU
+    Integrated,
+    /// First adapter whose name contains this text (case-insensitive)
+    Name(String),
+    /// Adapter at this position in wgpu's adapter list
+    Index(usize),
+}
+
+impl AdapterPreference {
+    fn matches(&self, index: usize, info: &wgpu::AdapterInfo) -> bool {
+        match self {
+            AdapterPreference::Discrete => info.device_type == wgpu::DeviceType::DiscreteGpu,
+            AdapterPreference::Integrated => info.device_type == wgpu::DeviceType::IntegratedGpu,
+            AdapterPreference::Name(name) => {
+                info.name.to_lowercase().contains(&name.to_lowercase())
+            }
+            AdapterPreference::Index(wanted) => index == *wanted,
+        }
+    }
+}
+
+/// Device and queue on the compute adapter
+#[derive(Debug, Clone)]
+pub struct ComputeDevice {
+    pub device: Device,
+    pub queue: Queue,
+    /// Limits and features of the compute adapter and device
+    pub capabilities: GpuCapabilities,
+}
+
+impl ComputeDevice {
+    /// Create a device on the adapter matching `preference`
+    ///
+    /// # Returns
+    ///
+    /// `None` if no adapter matches, or the match is the display adapter, in
+    /// which case simulations share the display device.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::Device`](crate::error::HaggisError::Device) if
+    /// the matching adapter can't create a device with `features` and `limits`.
+    pub async fn request(
+        instance: &wgpu::Instance,
+        display: &wgpu::AdapterInfo,
+        preference: &AdapterPreference,
+        features: wgpu::Features,
+        limits: &DeviceLimits,
+    ) -> Result<Option<Self>> {
+        #[cfg(not(target_arch = "wasm32"))]
+        let adapters = instance.enumerate_adapters(wgpu::Backends::all());
+        // Browsers expose a single adapter
+        #[cfg(target_arch = "wasm32")]
+        let adapters: Vec<wgpu::Adapter> = {
+            let _ = instance;
+            Vec::new()
+        };
+
+        let Some(adapter) = adapters
+            .into_iter()
+            .enumerate()
+            .find(|(index, adapter)| preference.matches(*index, &adapter.get_info()))
+            .map(|(_, adapter)| adapter)
+        else {
+            tracing::warn!(
+                "No adapter matches {:?}, simulations run on the display GPU",
+                preference
+            );
+            return Ok(None);
+        };
+
+        let info = adapter.get_info();
+        if info.name == display.name && info.device == display.device {
+            tracing::info!("Compute adapter is the display adapter, sharing its device");
+            return Ok(None);
+        }
+
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor {
+                label: Some("Compute Device"),
+                required_features: features,
+                required_limits: limits.resolve(&adapter),
+                memory_hints: wgpu::MemoryHints::Performance,
+                trace: wgpu::Trace::Off,
+            })
+            .await?;
+        let capabilities = GpuCapabilities::new(&adapter, &device);
+        tracing::info!("Simulation compute on {} ({:?})", info.name, info.backend);
+
+        Ok(Some(Self {
+            device,
+            queue,
+            capabilities,
+        }))
+    }
+}
+
+/// Buffer mirrored from the compute device to the display device
+///
+/// [`target`](Self::target) lives on the display device and can be bound by
+/// renderers and visualizations; [`sync`](Self::sync) refreshes it from a
+/// buffer on the compute device.
+pub struct SharedBuffer {
+    staging: Buffer,
+    target: Buffer,
+    size: u64,
+}
+
+impl SharedBuffer {
+    /// Create the staging buffer on `compute` and a `size`-byte buffer with
+    /// `usage` (plus `COPY_DST`) on `display`
+    pub fn new(
+        compute: &Device,
+        display: &Device,
+        size: u64,
+        usage: wgpu::BufferUsages,
+        label: &str,
+    ) -> Self {
+        let staging = compute.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("{} Staging", label)),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let target = display.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size,
+            usage: usage | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        Self {
+            staging,
+            target,
+            size,
+        }
+    }
+
+    /// The copy on the display device
+    pub fn target(&self) -> &Buffer {
+        &self.target
+    }
+
+    /// Size in bytes
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Copy `source` (on the compute device, with `COPY_SRC` usage) to the
+    /// display buffer
+    ///
+    /// Blocks until the compute device has finished its queued work.
+    ///
+    /// # Errors
+    ///
+    /// Returns the mapping error if the staging buffer can't be read back.
+    pub fn sync(
+        &self,
+        compute_device: &Device,
+        compute_queue: &Queue,
+        source: &Buffer,
+        display_queue: &Queue,
+    ) -> std::result::Result<(), wgpu::BufferAsyncError> {
+        let mut encoder = compute_device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Shared Buffer Copy"),
+        });
+        encoder.copy_buffer_to_buffer(source, 0, &self.staging, 0, self.size);
+        compute_queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = self.staging.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        let _ = compute_device.poll(wgpu::MaintainBase::Wait);
+        receiver.recv().unwrap_or(Err(wgpu::BufferAsyncError))?;
+
+        display_queue.write_buffer(&self.target, 0, &slice.get_mapped_range());
+        self.staging.unmap();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adapter_matching() {
+        let info = wgpu::AdapterInfo {
+            name: "NVIDIA GeForce RTX 4070".to_string(),
+            vendor: 0x10de,
+            device: 0x2786,
+            device_type: wgpu::DeviceType::DiscreteGpu,
+            driver: String::new(),
+            driver_info: String::new(),
+            backend: wgpu::Backend::Vulkan,
+        };
+        assert!(AdapterPreference::Discrete.matches(0, &info));
+        assert!(!AdapterPreference::Integrated.matches(0, &info));
+        assert!(AdapterPreference::Name("geforce".to_string()).matches(0, &info));
+        assert!(AdapterPreference::Index(1).matches(1, &info));
+        assert!(!AdapterPreference::Index(1).matches(0, &info));
+    }
+
+    #[test]
+    fn test_shared_buffer_sync() {
+        use crate::wgpu_utils::compute_primitives::{read_buffer, test_buffer, test_device};
+
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        // One device plays both roles; the copy path is the same
+        let source = test_buffer(&device, &[1u32, 2, 3, 4]);
+        let shared = SharedBuffer::new(&device, &device, 16, wgpu::BufferUsages::COPY_SRC, "Test");
+        shared.sync(&device, &queue, &source, &queue).unwrap();
+
+        let data: Vec<u32> = read_buffer(&device, &queue, shared.target(), 4).unwrap();
+        assert_eq!(data, vec![1, 2, 3, 4]);
+    }
+}
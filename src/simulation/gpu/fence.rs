@@ -0,0 +1,75 @@
+//! Completion fences for GPU submissions
+//!
+//! wgpu exposes one queue per device, so simulation compute and rendering
+//! share a queue unless simulations run on a separate
+//! [`ComputeDevice`](super::ComputeDevice). What can still overlap is the CPU
+//! side: instead of blocking on a readback right after submitting a step, the
+//! [`SimulationManager`](crate::simulation::manager::SimulationManager) puts
+//! a [`GpuFence`] behind the step and applies its results on a later frame
+//! once the fence is signaled, while the frame is recorded and rendered.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use wgpu::{Device, Queue};
+
+/// Signaled once all work submitted to a queue before it has finished
+#[derive(Debug, Clone)]
+pub struct GpuFence {
+    signaled: Arc<AtomicBool>,
+}
+
+impl GpuFence {
+    /// Fence behind everything submitted to `queue` so far
+    pub fn after(queue: &Queue) -> Self {
+        let signaled = Arc::new(AtomicBool::new(false));
+        {
+            let signaled = signaled.clone();
+            queue.on_submitted_work_done(move || signaled.store(true, Ordering::Release));
+        }
+        Self { signaled }
+    }
+
+    /// Whether the work has finished, without processing device callbacks
+    pub fn is_signaled(&self) -> bool {
+        self.signaled.load(Ordering::Acquire)
+    }
+
+    /// Process finished work on `device` without blocking and check the fence
+    pub fn poll(&self, device: &Device) -> bool {
+        if !self.is_signaled() {
+            let _ = device.poll(wgpu::MaintainBase::Poll);
+        }
+        self.is_signaled()
+    }
+
+    /// Block until the work has finished
+    pub fn wait(&self, device: &Device) {
+        while !self.is_signaled() {
+            if device.poll(wgpu::MaintainBase::Wait).is_err() {
+                // Device lost; nothing will signal the fence anymore
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wgpu_utils::compute_primitives::test_device;
+
+    #[test]
+    fn test_fence_signals() {
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let fence = GpuFence::after(&queue);
+        fence.wait(&device);
+        assert!(fence.is_signaled());
+        assert!(fence.poll(&device));
+    }
+}
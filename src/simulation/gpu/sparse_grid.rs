@@ -0,0 +1,948 @@
+//! Block-sparse grid for mostly-empty domains
+//!
+//! 3D Game of Life, smoke or a fluid front touch a small part of a large
+//! domain. [`SparseGrid`] divides the domain into 8×8×8 tiles and stores only
+//! active ones, packed into a pool of `capacity` tiles:
+//!
+//! - **Indirection table** - pool slot of every tile, or `SPARSE_EMPTY`
+//! - **Active-tile list** - tile of every used slot, so work is dispatched
+//!   over active cells only, through [`dispatch_args`](SparseGrid::dispatch_args)
+//!
+//! A simulation step reads [`field`](SparseGrid::field), writes
+//! [`scratch`](SparseGrid::scratch) and calls
+//! [`commit_scratch`](SparseGrid::commit_scratch). Every few steps
+//! [`compact`](SparseGrid::compact) rebuilds the tile set in compute passes:
+//!
+//! 1. **Mark** - tiles with a cell above the threshold stay active, and so do
+//!    neighbor tiles next to such a cell on the tile border, so activity can
+//!    spread by one cell per step
+//! 2. **Scan** - prefix sum of the marks assigns new slots, with a [`Scan`]
+//! 3. **Compact** - rewrite the indirection table and active-tile list
+//! 4. **Copy** - move tile data to its new slot, zero-filling new tiles
+//!
+//! Shaders access the grid through [`SparseGrid::query_wgsl`]:
+//!
+//! ```wgsl
+//! @compute @workgroup_size(256)
+//! fn step(@builtin(global_invocation_id) id: vec3<u32>) {
+//!     if (id.x >= sparse_count[0] * SPARSE_TILE_CELLS) {
+//!         return;
+//!     }
+//!     let cell = sparse_grid_cell(id.x);
+//!     var neighbors = 0u;
+//!     for (var n = 0u; n < 27u; n++) {
+//!         let offset = vec3<i32>(i32(n % 3u) - 1, i32(n / 3u % 3u) - 1, i32(n / 9u) - 1);
+//!         let index = sparse_grid_index(cell + offset);
+//!         if (n != 13u && index != SPARSE_EMPTY && sparse_field[index] > 0.5) {
+//!             neighbors++;
+//!         }
+//!     }
+//!     sparse_scratch[id.x] = select(0.0, 1.0, neighbors == 5u || (neighbors == 4u && sparse_field[id.x] > 0.5));
+//! }
+//! ```
+//!
+//! For visualization, [`encode_slice`](SparseGrid::encode_slice) fills a
+//! [`CutPlane2D`](crate::visualization::cut_plane_2d::CutPlane2D) buffer
+//! sampling active tiles only, and [`read_active`](SparseGrid::read_active)
+//! reads back just the active tiles as a [`SparseSnapshot`].
+
+use std::sync::Arc;
+
+use wgpu::{Buffer, CommandEncoder, Device, Queue};
+
+use crate::simulation::params::ParamsUniform;
+use crate::visualization::cut_plane_2d::{BufferElementType, BufferFormat};
+use crate::wgpu_utils::compute_primitives::{read_buffer, Scan};
+
+/// Cells along each edge of a tile
+pub const TILE_SIZE: u32 = 8;
+/// Cells in a tile
+pub const TILE_CELLS: u32 = TILE_SIZE * TILE_SIZE * TILE_SIZE;
+/// Indirection table entry of an inactive tile
+pub const EMPTY: u32 = u32::MAX;
+
+const WORKGROUP_SIZE: u32 = 256;
+const MAX_WORKGROUPS: u32 = 65_535;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct SparseGridUniforms {
+    size: [u32; 3],
+    capacity: u32,
+    tiles: [u32; 3],
+    threshold: f32,
+}
+
+/// Tiled grid storing only active 8×8×8 tiles
+pub struct SparseGrid {
+    uniforms: ParamsUniform<SparseGridUniforms>,
+    settings: SparseGridUniforms,
+    tile_count: u32,
+    field: Buffer,
+    scratch: Buffer,
+    indirection: Buffer,
+    active: Buffer,
+    /// Active tile count and the tile count requested by the last compaction
+    count: Buffer,
+    dispatch_args: Buffer,
+    flags: Buffer,
+    new_indirection: Buffer,
+    new_active: Buffer,
+    scan: Scan<u32>,
+    bind_group: wgpu::BindGroup,
+    mark_pipeline: wgpu::ComputePipeline,
+    compact_pipeline: wgpu::ComputePipeline,
+    finish_pipeline: wgpu::ComputePipeline,
+    copy_pipeline: wgpu::ComputePipeline,
+    slice_uniforms: Buffer,
+    slice: Arc<Buffer>,
+    slice_bind_group: wgpu::BindGroup,
+    slice_pipeline: wgpu::ComputePipeline,
+}
+
+impl SparseGrid {
+    /// Create an empty grid of `size` cells with room for `capacity` tiles
+    ///
+    /// Cells count as active when their absolute value exceeds `threshold`.
+    pub fn new(device: &Device, size: [u32; 3], capacity: u32, threshold: f32) -> Self {
+        let tiles = size.map(|s| s.div_ceil(TILE_SIZE));
+        let tile_count = tiles.iter().product::<u32>();
+        assert!(tile_count > 0, "sparse grid size must not be zero");
+        assert!(
+            capacity > 0 && capacity * (TILE_CELLS / WORKGROUP_SIZE) <= MAX_WORKGROUPS,
+            "sparse grid capacity must be between 1 and {} tiles",
+            MAX_WORKGROUPS / (TILE_CELLS / WORKGROUP_SIZE)
+        );
+        assert!(
+            tile_count.div_ceil(WORKGROUP_SIZE) <= MAX_WORKGROUPS,
+            "sparse grid exceeds {} tiles",
+            MAX_WORKGROUPS * WORKGROUP_SIZE
+        );
+
+        let settings = SparseGridUniforms {
+            size,
+            capacity,
+            tiles,
+            threshold,
+        };
+        let uniforms = ParamsUniform::new(device, "Sparse Grid Uniforms", &settings);
+
+        let buffer = |label: &str, size: u64, usage: wgpu::BufferUsages| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size,
+                usage: usage
+                    | wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_DST
+                    | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            })
+        };
+        let none = wgpu::BufferUsages::empty();
+        let pool_size = capacity as u64 * TILE_CELLS as u64 * 4;
+        let field = buffer("Sparse Grid Field", pool_size, none);
+        let scratch = buffer("Sparse Grid Scratch", pool_size, none);
+        let indirection = buffer("Sparse Grid Indirection", tile_count as u64 * 4, none);
+        let active = buffer("Sparse Grid Active Tiles", capacity as u64 * 4, none);
+        let count = buffer("Sparse Grid Count", 8, none);
+        let dispatch_args = buffer(
+            "Sparse Grid Dispatch Args",
+            12,
+            wgpu::BufferUsages::INDIRECT,
+        );
+        let flags = buffer("Sparse Grid Flags", tile_count as u64 * 4, none);
+        let offsets = buffer("Sparse Grid Offsets", tile_count as u64 * 4, none);
+        let new_indirection = buffer("Sparse Grid New Indirection", tile_count as u64 * 4, none);
+        let new_active = buffer("Sparse Grid New Active Tiles", capacity as u64 * 4, none);
+        let scan = Scan::new(device, &flags, &offsets, tile_count);
+
+        let slice_uniforms = buffer(
+            "Sparse Grid Slice Uniforms",
+            16,
+            wgpu::BufferUsages::UNIFORM,
+        );
+        let slice = Arc::new(buffer(
+            "Sparse Grid Slice",
+            size[0] as u64 * size[1] as u64 * 4,
+            none,
+        ));
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Sparse Grid Shader"),
+            source: wgpu::ShaderSource::Wgsl(build_wgsl().into()),
+        });
+        let storage = |read_only| wgpu::BufferBindingType::Storage { read_only };
+        let layout = |label: &str, types: &[wgpu::BufferBindingType]| {
+            let entries: Vec<_> = types
+                .iter()
+                .enumerate()
+                .map(|(binding, &ty)| wgpu::BindGroupLayoutEntry {
+                    binding: binding as u32,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                })
+                .collect();
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some(label),
+                entries: &entries,
+            })
+        };
+        let bind_group = |label: &str, layout: &wgpu::BindGroupLayout, buffers: &[&Buffer]| {
+            let entries: Vec<_> = buffers
+                .iter()
+                .enumerate()
+                .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                    binding: binding as u32,
+                    resource: buffer.as_entire_binding(),
+                })
+                .collect();
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout,
+                entries: &entries,
+            })
+        };
+
+        let grid_layout = layout(
+            "Sparse Grid Layout",
+            &[
+                wgpu::BufferBindingType::Uniform,
+                storage(true),
+                storage(false),
+                storage(false),
+                storage(false),
+                storage(false),
+                storage(true),
+                storage(true),
+                storage(false),
+                storage(false),
+                storage(false),
+                storage(false),
+            ],
+        );
+        let grid_bind_group = bind_group(
+            "Sparse Grid Bind Group",
+            &grid_layout,
+            &[
+                uniforms.buffer(),
+                &field,
+                &indirection,
+                &active,
+                &count,
+                &flags,
+                &offsets,
+                scan.total(),
+                &scratch,
+                &new_indirection,
+                &new_active,
+                &dispatch_args,
+            ],
+        );
+        let slice_layout = layout(
+            "Sparse Grid Slice Layout",
+            &[wgpu::BufferBindingType::Uniform, storage(false)],
+        );
+        let slice_bind_group = bind_group(
+            "Sparse Grid Slice Bind Group",
+            &slice_layout,
+            &[&slice_uniforms, &slice],
+        );
+
+        let pipeline = |entry_point: &str, layouts: &[&wgpu::BindGroupLayout]| {
+            let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some(entry_point),
+                bind_group_layouts: layouts,
+                push_constant_ranges: &[],
+            });
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+
+        Self {
+            uniforms,
+            settings,
+            tile_count,
+            field,
+            scratch,
+            indirection,
+            active,
+            count,
+            dispatch_args,
+            flags,
+            new_indirection,
+            new_active,
+            scan,
+            bind_group: grid_bind_group,
+            mark_pipeline: pipeline("mark", &[&grid_layout]),
+            compact_pipeline: pipeline("compact", &[&grid_layout]),
+            finish_pipeline: pipeline("finish", &[&grid_layout]),
+            copy_pipeline: pipeline("copy", &[&grid_layout]),
+            slice_uniforms,
+            slice,
+            slice_bind_group,
+            slice_pipeline: pipeline("slice", &[&grid_layout, &slice_layout]),
+        }
+    }
+
+    /// Domain size in cells
+    pub fn size(&self) -> [u32; 3] {
+        self.settings.size
+    }
+
+    /// Domain size in tiles
+    pub fn tiles(&self) -> [u32; 3] {
+        self.settings.tiles
+    }
+
+    /// Maximum number of active tiles
+    pub fn capacity(&self) -> u32 {
+        self.settings.capacity
+    }
+
+    /// Values at or below this magnitude don't keep a tile active
+    pub fn set_threshold(&mut self, threshold: f32) {
+        self.settings.threshold = threshold;
+    }
+
+    pub fn threshold(&self) -> f32 {
+        self.settings.threshold
+    }
+
+    /// Values of the active tiles, slot by slot (`f32` each)
+    pub fn field(&self) -> &Buffer {
+        &self.field
+    }
+
+    /// Output of a simulation step, same layout as the field (`f32` each)
+    pub fn scratch(&self) -> &Buffer {
+        &self.scratch
+    }
+
+    /// Pool slot of every tile, or [`EMPTY`] (`u32` each)
+    pub fn indirection(&self) -> &Buffer {
+        &self.indirection
+    }
+
+    /// Tile index of every used pool slot (`u32` each)
+    pub fn active_tiles(&self) -> &Buffer {
+        &self.active
+    }
+
+    /// Active tile count, followed by the count the last compaction asked for
+    pub fn count(&self) -> &Buffer {
+        &self.count
+    }
+
+    /// Dispatch arguments covering every active cell with 256-thread workgroups
+    pub fn dispatch_args(&self) -> &Buffer {
+        &self.dispatch_args
+    }
+
+    /// Slice written by [`encode_slice`](Self::encode_slice), `size.x * size.y` values
+    pub fn slice_buffer(&self) -> Arc<Buffer> {
+        self.slice.clone()
+    }
+
+    /// Format of [`slice_buffer`](Self::slice_buffer) for `CutPlane2D::update_gpu_buffer`
+    pub fn slice_format(&self) -> BufferFormat {
+        BufferFormat {
+            element_type: BufferElementType::F32,
+            width: self.settings.size[0],
+            height: self.settings.size[1],
+        }
+    }
+
+    /// Replace the grid contents with a dense field of `size` cells, x fastest
+    ///
+    /// Tiles are activated by the same rule as [`compact`](Self::compact);
+    /// tiles beyond the capacity are dropped with a warning.
+    pub fn upload(&mut self, queue: &Queue, dense: &[f32]) {
+        let [sx, sy, sz] = self.settings.size;
+        assert_eq!(
+            dense.len(),
+            (sx * sy * sz) as usize,
+            "dense field doesn't match the sparse grid size"
+        );
+        self.uniforms.update(queue, &self.settings);
+
+        let marked = mark_tiles(self.settings.size, self.settings.threshold, |cell| {
+            dense[(cell[0] + sx * (cell[1] + sy * cell[2])) as usize]
+        });
+        let requested = marked.len() as u32;
+        if requested > self.settings.capacity {
+            tracing::warn!(
+                "Sparse grid needs {} tiles but holds {}, dropping the rest",
+                requested,
+                self.settings.capacity
+            );
+        }
+        let active: Vec<u32> = marked
+            .into_iter()
+            .take(self.settings.capacity as usize)
+            .collect();
+
+        let mut indirection = vec![EMPTY; self.tile_count as usize];
+        let mut pool = vec![0.0f32; active.len() * TILE_CELLS as usize];
+        for (slot, &tile) in active.iter().enumerate() {
+            indirection[tile as usize] = slot as u32;
+            let origin = tile_origin(tile, self.settings.tiles);
+            for local in 0..TILE_CELLS {
+                let cell = local_cell(origin, local);
+                if cell[0] < sx && cell[1] < sy && cell[2] < sz {
+                    pool[slot * TILE_CELLS as usize + local as usize] =
+                        dense[(cell[0] + sx * (cell[1] + sy * cell[2])) as usize];
+                }
+            }
+        }
+
+        let count = active.len() as u32;
+        queue.write_buffer(&self.indirection, 0, bytemuck::cast_slice(&indirection));
+        queue.write_buffer(&self.count, 0, bytemuck::cast_slice(&[count, requested]));
+        queue.write_buffer(
+            &self.dispatch_args,
+            0,
+            bytemuck::cast_slice(&dispatch_args(count)),
+        );
+        if count > 0 {
+            queue.write_buffer(&self.active, 0, bytemuck::cast_slice(&active));
+            queue.write_buffer(&self.field, 0, bytemuck::cast_slice(&pool));
+        }
+    }
+
+    /// Record copying the scratch values over the field after a step
+    pub fn commit_scratch(&self, encoder: &mut CommandEncoder) {
+        encoder.copy_buffer_to_buffer(&self.scratch, 0, &self.field, 0, self.field.size());
+    }
+
+    /// Record the passes rebuilding the active tile set from the field
+    pub fn compact(&mut self, queue: &Queue, encoder: &mut CommandEncoder) {
+        self.uniforms.update(queue, &self.settings);
+        encoder.clear_buffer(&self.flags, 0, None);
+
+        let pool_groups = self.settings.capacity * (TILE_CELLS / WORKGROUP_SIZE);
+        let tile_groups = self.tile_count.div_ceil(WORKGROUP_SIZE);
+        self.dispatch(
+            encoder,
+            "Sparse Grid Mark",
+            &self.mark_pipeline,
+            pool_groups,
+        );
+        self.scan.encode(queue, encoder, self.tile_count);
+        self.dispatch(
+            encoder,
+            "Sparse Grid Compact",
+            &self.compact_pipeline,
+            tile_groups,
+        );
+        self.dispatch(encoder, "Sparse Grid Finish", &self.finish_pipeline, 1);
+        self.dispatch(
+            encoder,
+            "Sparse Grid Copy",
+            &self.copy_pipeline,
+            pool_groups,
+        );
+
+        encoder.copy_buffer_to_buffer(&self.scratch, 0, &self.field, 0, self.field.size());
+        encoder.copy_buffer_to_buffer(
+            &self.new_indirection,
+            0,
+            &self.indirection,
+            0,
+            self.indirection.size(),
+        );
+        encoder.copy_buffer_to_buffer(&self.new_active, 0, &self.active, 0, self.active.size());
+    }
+
+    /// Rebuild the active tile set in a submission of its own
+    pub fn compact_and_submit(&mut self, device: &Device, queue: &Queue) {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Sparse Grid Encoder"),
+        });
+        self.compact(queue, &mut encoder);
+        queue.submit(std::iter::once(encoder.finish()));
+    }
+
+    /// Record writing the z = `z` slice into [`slice_buffer`](Self::slice_buffer)
+    ///
+    /// Cells of inactive tiles read as zero.
+    pub fn encode_slice(&mut self, queue: &Queue, encoder: &mut CommandEncoder, z: u32) {
+        self.uniforms.update(queue, &self.settings);
+        let z = z.min(self.settings.size[2] - 1);
+        queue.write_buffer(&self.slice_uniforms, 0, bytemuck::cast_slice(&[z, 0, 0, 0]));
+
+        let cells = self.settings.size[0] * self.settings.size[1];
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Sparse Grid Slice"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(&self.slice_pipeline);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.set_bind_group(1, &self.slice_bind_group, &[]);
+        pass.dispatch_workgroups(cells.div_ceil(WORKGROUP_SIZE), 1, 1);
+    }
+
+    /// Read the active tiles back to the CPU, waiting for the GPU
+    pub fn read_active(
+        &self,
+        device: &Device,
+        queue: &Queue,
+    ) -> Result<SparseSnapshot, wgpu::BufferAsyncError> {
+        let count: Vec<u32> = read_buffer(device, queue, &self.count, 2)?;
+        let active = read_buffer(device, queue, &self.active, count[0] as usize)?;
+        let values = read_buffer(
+            device,
+            queue,
+            &self.field,
+            count[0] as usize * TILE_CELLS as usize,
+        )?;
+        Ok(SparseSnapshot {
+            size: self.settings.size,
+            tiles: self.settings.tiles,
+            active,
+            values,
+            overflowed: count[1] > count[0],
+        })
+    }
+
+    fn dispatch(
+        &self,
+        encoder: &mut CommandEncoder,
+        label: &str,
+        pipeline: &wgpu::ComputePipeline,
+        groups: u32,
+    ) {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some(label),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(pipeline);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.dispatch_workgroups(groups, 1, 1);
+    }
+
+    /// Layout entries for stepping a simulation on the grid: uniforms,
+    /// indirection, active tiles, count, field and scratch at
+    /// `first_binding..first_binding + 6`
+    pub fn bind_group_layout_entries(
+        first_binding: u32,
+        visibility: wgpu::ShaderStages,
+    ) -> [wgpu::BindGroupLayoutEntry; 6] {
+        let entry = |offset: u32, ty: wgpu::BufferBindingType| wgpu::BindGroupLayoutEntry {
+            binding: first_binding + offset,
+            visibility,
+            ty: wgpu::BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let read_only = wgpu::BufferBindingType::Storage { read_only: true };
+        [
+            entry(0, wgpu::BufferBindingType::Uniform),
+            entry(1, read_only),
+            entry(2, read_only),
+            entry(3, read_only),
+            entry(4, read_only),
+            entry(5, wgpu::BufferBindingType::Storage { read_only: false }),
+        ]
+    }
+
+    /// Bind group entries matching [`bind_group_layout_entries`](Self::bind_group_layout_entries)
+    pub fn bind_group_entries(&self, first_binding: u32) -> [wgpu::BindGroupEntry<'_>; 6] {
+        let buffers = [
+            self.uniforms.buffer(),
+            &self.indirection,
+            &self.active,
+            &self.count,
+            &self.field,
+            &self.scratch,
+        ];
+        std::array::from_fn(|offset| wgpu::BindGroupEntry {
+            binding: first_binding + offset as u32,
+            resource: buffers[offset].as_entire_binding(),
+        })
+    }
+
+    /// WGSL declaring the grid bindings and helper functions
+    ///
+    /// Defines `sparse_grid_index` (pool index of a cell, or `SPARSE_EMPTY`)
+    /// and `sparse_grid_cell` (cell of a pool index), plus the
+    /// `sparse_field` and `sparse_scratch` arrays.
+    pub fn query_wgsl(group: u32, first_binding: u32) -> String {
+        format!(
+            "{CONSTANTS_WGSL}
+@group({group}) @binding({b0}) var<uniform> sparse_grid: SparseGridUniforms;
+@group({group}) @binding({b1}) var<storage, read> sparse_indirection: array<u32>;
+@group({group}) @binding({b2}) var<storage, read> sparse_active: array<u32>;
+@group({group}) @binding({b3}) var<storage, read> sparse_count: array<u32>;
+@group({group}) @binding({b4}) var<storage, read> sparse_field: array<f32>;
+@group({group}) @binding({b5}) var<storage, read_write> sparse_scratch: array<f32>;
+{FUNCTIONS_WGSL}",
+            b0 = first_binding,
+            b1 = first_binding + 1,
+            b2 = first_binding + 2,
+            b3 = first_binding + 3,
+            b4 = first_binding + 4,
+            b5 = first_binding + 5,
+        )
+    }
+}
+
+/// Active tiles read back from a [`SparseGrid`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseSnapshot {
+    size: [u32; 3],
+    tiles: [u32; 3],
+    /// Tile index of every slot
+    active: Vec<u32>,
+    /// `TILE_CELLS` values per slot
+    values: Vec<f32>,
+    /// Whether the last compaction dropped tiles for lack of capacity
+    pub overflowed: bool,
+}
+
+impl SparseSnapshot {
+    /// Number of active tiles
+    pub fn tile_count(&self) -> usize {
+        self.active.len()
+    }
+
+    /// Coordinates of the active tiles, in tiles
+    pub fn active_tiles(&self) -> impl Iterator<Item = [u32; 3]> + '_ {
+        self.active.iter().map(|&tile| {
+            let [x, y, z] = tile_origin(tile, self.tiles);
+            [x / TILE_SIZE, y / TILE_SIZE, z / TILE_SIZE]
+        })
+    }
+
+    /// Value of a cell, zero outside the active tiles
+    pub fn get(&self, cell: [u32; 3]) -> f32 {
+        if (0..3).any(|axis| cell[axis] >= self.size[axis]) {
+            return 0.0;
+        }
+        let tile = cell.map(|c| c / TILE_SIZE);
+        let tile = tile[0] + self.tiles[0] * (tile[1] + self.tiles[1] * tile[2]);
+        self.active
+            .iter()
+            .position(|&active| active == tile)
+            .map_or(0.0, |slot| {
+                let [x, y, z] = cell.map(|c| c % TILE_SIZE);
+                self.values
+                    [slot * TILE_CELLS as usize + (x + TILE_SIZE * (y + TILE_SIZE * z)) as usize]
+            })
+    }
+
+    /// The whole field, x fastest, with zeros outside the active tiles
+    pub fn to_dense(&self) -> Vec<f32> {
+        let [sx, sy, sz] = self.size;
+        let mut dense = vec![0.0; (sx * sy * sz) as usize];
+        for (slot, &tile) in self.active.iter().enumerate() {
+            let origin = tile_origin(tile, self.tiles);
+            for local in 0..TILE_CELLS {
+                let [x, y, z] = local_cell(origin, local);
+                if x < sx && y < sy && z < sz {
+                    dense[(x + sx * (y + sy * z)) as usize] =
+                        self.values[slot * TILE_CELLS as usize + local as usize];
+                }
+            }
+        }
+        dense
+    }
+
+    /// The z = `z` slice, `size.x * size.y` values for `CutPlane2D::update_data`
+    pub fn slice_z(&self, z: u32) -> Vec<f32> {
+        let [sx, sy, _] = self.size;
+        (0..sx * sy)
+            .map(|i| self.get([i % sx, i / sx, z]))
+            .collect()
+    }
+}
+
+/// First cell of a tile
+fn tile_origin(tile: u32, tiles: [u32; 3]) -> [u32; 3] {
+    [
+        tile % tiles[0] * TILE_SIZE,
+        tile / tiles[0] % tiles[1] * TILE_SIZE,
+        tile / (tiles[0] * tiles[1]) * TILE_SIZE,
+    ]
+}
+
+/// Cell `local` of the tile starting at `origin`
+fn local_cell(origin: [u32; 3], local: u32) -> [u32; 3] {
+    [
+        origin[0] + local % TILE_SIZE,
+        origin[1] + local / TILE_SIZE % TILE_SIZE,
+        origin[2] + local / (TILE_SIZE * TILE_SIZE),
+    ]
+}
+
+/// Tiles the mark pass keeps, in ascending order
+fn mark_tiles(size: [u32; 3], threshold: f32, value: impl Fn([u32; 3]) -> f32) -> Vec<u32> {
+    let tiles = size.map(|s| s.div_ceil(TILE_SIZE));
+    let mut marked = vec![false; tiles.iter().product::<u32>() as usize];
+    for z in 0..size[2] {
+        for y in 0..size[1] {
+            for x in 0..size[0] {
+                let cell = [x, y, z];
+                if value(cell).abs() <= threshold {
+                    continue;
+                }
+                for n in 0..27i32 {
+                    let offset = [n % 3, n / 3 % 3, n / 9].map(|o| o - 1);
+                    // Only cells on a tile border reach into the neighbor
+                    let reaches = (0..3).all(|axis| match offset[axis] {
+                        -1 => cell[axis] % TILE_SIZE == 0,
+                        1 => cell[axis] % TILE_SIZE == TILE_SIZE - 1,
+                        _ => true,
+                    });
+                    let tile: [i64; 3] = std::array::from_fn(|axis| {
+                        (cell[axis] / TILE_SIZE) as i64 + offset[axis] as i64
+                    });
+                    if reaches && (0..3).all(|axis| (0..tiles[axis] as i64).contains(&tile[axis])) {
+                        let index =
+                            tile[0] + tiles[0] as i64 * (tile[1] + tiles[1] as i64 * tile[2]);
+                        marked[index as usize] = true;
+                    }
+                }
+            }
+        }
+    }
+    (0..marked.len() as u32)
+        .filter(|&tile| marked[tile as usize])
+        .collect()
+}
+
+fn dispatch_args(count: u32) -> [u32; 3] {
+    [count * (TILE_CELLS / WORKGROUP_SIZE), 1, 1]
+}
+
+fn build_wgsl() -> String {
+    format!("{CONSTANTS_WGSL}{BUILD_WGSL}{FUNCTIONS_WGSL}{SLICE_WGSL}")
+}
+
+const CONSTANTS_WGSL: &str = r#"
+const SPARSE_TILE: u32 = 8u;
+const SPARSE_TILE_CELLS: u32 = 512u;
+const SPARSE_EMPTY: u32 = 0xffffffffu;
+
+struct SparseGridUniforms {
+    size: vec3<u32>,
+    capacity: u32,
+    tiles: vec3<u32>,
+    threshold: f32,
+}
+"#;
+
+const FUNCTIONS_WGSL: &str = r#"
+// Pool index of `cell`, or SPARSE_EMPTY outside the domain and active tiles
+fn sparse_grid_index(cell: vec3<i32>) -> u32 {
+    if (any(cell < vec3<i32>(0)) || any(vec3<u32>(cell) >= sparse_grid.size)) {
+        return SPARSE_EMPTY;
+    }
+    let c = vec3<u32>(cell);
+    let tile = c / SPARSE_TILE;
+    let slot = sparse_indirection[tile.x + sparse_grid.tiles.x * (tile.y + sparse_grid.tiles.y * tile.z)];
+    if (slot == SPARSE_EMPTY) {
+        return SPARSE_EMPTY;
+    }
+    let local = c % SPARSE_TILE;
+    return slot * SPARSE_TILE_CELLS + local.x + SPARSE_TILE * (local.y + SPARSE_TILE * local.z);
+}
+
+// Cell of an index into the pool of active tiles
+fn sparse_grid_cell(index: u32) -> vec3<i32> {
+    let tile = sparse_active[index / SPARSE_TILE_CELLS];
+    let tiles = sparse_grid.tiles;
+    let origin = vec3<u32>(tile % tiles.x, tile / tiles.x % tiles.y, tile / (tiles.x * tiles.y)) * SPARSE_TILE;
+    let local = index % SPARSE_TILE_CELLS;
+    return vec3<i32>(origin + vec3<u32>(local % SPARSE_TILE, local / SPARSE_TILE % SPARSE_TILE, local / (SPARSE_TILE * SPARSE_TILE)));
+}
+"#;
+
+const BUILD_WGSL: &str = r#"
+@group(0) @binding(0) var<uniform> sparse_grid: SparseGridUniforms;
+@group(0) @binding(1) var<storage, read> sparse_field: array<f32>;
+@group(0) @binding(2) var<storage, read_write> sparse_indirection: array<u32>;
+@group(0) @binding(3) var<storage, read_write> sparse_active: array<u32>;
+@group(0) @binding(4) var<storage, read_write> sparse_count: array<u32>;
+@group(0) @binding(5) var<storage, read_write> flags: array<atomic<u32>>;
+@group(0) @binding(6) var<storage, read> offsets: array<u32>;
+@group(0) @binding(7) var<storage, read> total: array<u32>;
+@group(0) @binding(8) var<storage, read_write> sparse_scratch: array<f32>;
+@group(0) @binding(9) var<storage, read_write> new_indirection: array<u32>;
+@group(0) @binding(10) var<storage, read_write> new_active: array<u32>;
+@group(0) @binding(11) var<storage, read_write> dispatch_args: array<u32>;
+
+fn tile_count() -> u32 {
+    return sparse_grid.tiles.x * sparse_grid.tiles.y * sparse_grid.tiles.z;
+}
+
+@compute @workgroup_size(256)
+fn mark(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= sparse_count[0] * SPARSE_TILE_CELLS || abs(sparse_field[id.x]) <= sparse_grid.threshold) {
+        return;
+    }
+    let cell = sparse_grid_cell(id.x);
+    let local = vec3<u32>(cell) % SPARSE_TILE;
+    let tile = vec3<i32>(vec3<u32>(cell) / SPARSE_TILE);
+    for (var n = 0u; n < 27u; n++) {
+        let offset = vec3<i32>(i32(n % 3u) - 1, i32(n / 3u % 3u) - 1, i32(n / 9u) - 1);
+        // Only cells on a tile border reach into the neighbor
+        let reaches = (offset != vec3<i32>(-1) | local == vec3<u32>(0u))
+            & (offset != vec3<i32>(1) | local == vec3<u32>(SPARSE_TILE - 1u));
+        let neighbor = tile + offset;
+        if (all(reaches) && all(neighbor >= vec3<i32>(0)) && all(vec3<u32>(neighbor) < sparse_grid.tiles)) {
+            let t = vec3<u32>(neighbor);
+            atomicStore(&flags[t.x + sparse_grid.tiles.x * (t.y + sparse_grid.tiles.y * t.z)], 1u);
+        }
+    }
+}
+
+@compute @workgroup_size(256)
+fn compact(@builtin(global_invocation_id) id: vec3<u32>) {
+    let tile = id.x;
+    if (tile >= tile_count()) {
+        return;
+    }
+    let slot = offsets[tile];
+    if (atomicLoad(&flags[tile]) != 0u && slot < sparse_grid.capacity) {
+        new_indirection[tile] = slot;
+        new_active[slot] = tile;
+    } else {
+        new_indirection[tile] = SPARSE_EMPTY;
+    }
+}
+
+@compute @workgroup_size(1)
+fn finish() {
+    let count = min(total[0], sparse_grid.capacity);
+    sparse_count[0] = count;
+    sparse_count[1] = total[0];
+    dispatch_args[0] = count * (SPARSE_TILE_CELLS / 256u);
+    dispatch_args[1] = 1u;
+    dispatch_args[2] = 1u;
+}
+
+@compute @workgroup_size(256)
+fn copy(@builtin(global_invocation_id) id: vec3<u32>) {
+    let slot = id.x / SPARSE_TILE_CELLS;
+    if (slot >= sparse_count[0]) {
+        return;
+    }
+    let old = sparse_indirection[new_active[slot]];
+    if (old == SPARSE_EMPTY) {
+        sparse_scratch[id.x] = 0.0;
+    } else {
+        sparse_scratch[id.x] = sparse_field[old * SPARSE_TILE_CELLS + id.x % SPARSE_TILE_CELLS];
+    }
+}
+"#;
+
+const SLICE_WGSL: &str = r#"
+struct SliceUniforms {
+    z: u32,
+}
+
+@group(1) @binding(0) var<uniform> slice_uniforms: SliceUniforms;
+@group(1) @binding(1) var<storage, read_write> slice_values: array<f32>;
+
+@compute @workgroup_size(256)
+fn slice(@builtin(global_invocation_id) id: vec3<u32>) {
+    let width = sparse_grid.size.x;
+    if (id.x >= width * sparse_grid.size.y) {
+        return;
+    }
+    let index = sparse_grid_index(vec3<i32>(i32(id.x % width), i32(id.x / width), i32(slice_uniforms.z)));
+    if (index == SPARSE_EMPTY) {
+        slice_values[id.x] = 0.0;
+    } else {
+        slice_values[id.x] = sparse_field[index];
+    }
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mark_tiles() {
+        // An interior cell keeps its own tile; a corner cell reaches 7 more
+        let size = [24, 24, 24];
+        let interior = mark_tiles(size, 0.0, |cell| (cell == [12, 12, 12]) as u32 as f32);
+        assert_eq!(interior, vec![13]);
+
+        let corner = mark_tiles(size, 0.0, |cell| (cell == [15, 15, 15]) as u32 as f32);
+        assert_eq!(corner.len(), 8);
+        assert!(corner.contains(&13) && corner.contains(&26));
+
+        // Neighbors outside the domain are skipped; values at the threshold don't count
+        let edge = mark_tiles(size, 0.5, |cell| if cell == [0, 0, 0] { 1.0 } else { 0.5 });
+        assert_eq!(edge, vec![0]);
+    }
+
+    #[test]
+    fn test_snapshot_indexing() {
+        let tiles = [2, 1, 1];
+        let mut values = vec![0.0; TILE_CELLS as usize];
+        values[(3 + TILE_SIZE * 2) as usize] = 7.0;
+        let snapshot = SparseSnapshot {
+            size: [12, 8, 8],
+            tiles,
+            active: vec![1],
+            values,
+            overflowed: false,
+        };
+        assert_eq!(snapshot.get([11, 2, 0]), 7.0);
+        assert_eq!(snapshot.get([3, 2, 0]), 0.0);
+        assert_eq!(snapshot.get([20, 0, 0]), 0.0);
+        assert_eq!(snapshot.active_tiles().collect::<Vec<_>>(), vec![[1, 0, 0]]);
+        assert_eq!(snapshot.slice_z(0)[11 + 12 * 2], 7.0);
+        assert_eq!(snapshot.to_dense().iter().filter(|&&v| v != 0.0).count(), 1);
+        assert_eq!(std::mem::size_of::<SparseGridUniforms>(), 32);
+    }
+
+    #[test]
+    fn test_compaction_follows_activity() {
+        use crate::wgpu_utils::compute_primitives::test_device;
+
+        // Needs a GPU adapter, skipped without one
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let size = [40, 24, 16];
+        let [sx, sy, sz] = size;
+        let index = |cell: [u32; 3]| (cell[0] + sx * (cell[1] + sy * cell[2])) as usize;
+        let mut dense = vec![0.0f32; (sx * sy * sz) as usize];
+        dense[index([3, 3, 3])] = 1.0;
+        dense[index([20, 10, 5])] = 2.0;
+        dense[index([23, 15, 7])] = 3.0;
+
+        let mut grid = SparseGrid::new(&device, size, 64, 0.0);
+        grid.upload(&queue, &dense);
+        let snapshot = grid.read_active(&device, &queue).unwrap();
+        assert_eq!(snapshot.to_dense(), dense);
+
+        // Raising the threshold drops the first tile and keeps the corner's neighbors
+        grid.set_threshold(1.5);
+        grid.compact_and_submit(&device, &queue);
+        let compacted = grid.read_active(&device, &queue).unwrap();
+        dense[index([3, 3, 3])] = 0.0;
+        assert_eq!(compacted.to_dense(), dense);
+        let expected = mark_tiles(size, 1.5, |cell| dense[index(cell)]);
+        assert_eq!(compacted.active, expected);
+        assert!(!compacted.overflowed);
+    }
+}
@@ -0,0 +1,483 @@
+//! GPU spatial hash for neighbor queries
+//!
+//! Sorts point indices by hashed grid cell with a counting sort, entirely in
+//! compute passes:
+//!
+//! 1. **Count** - hash every point's cell and count the points per key
+//! 2. **Scan** - exclusive prefix sum of the counts gives each key's start,
+//!    with a [`Scan`]
+//! 3. **Scatter** - write every point index into its key's range
+//!
+//! Afterwards the points of a cell are `sorted[start[key]..start[key] + count[key]]`.
+//! Shaders query the hash through the bindings from
+//! [`SpatialHash::bind_group_layout_entries`] and the WGSL from
+//! [`SpatialHash::query_wgsl`]:
+//!
+//! ```wgsl
+//! let keys = spatial_hash_neighbor_keys(position);
+//! for (var c = 0u; c < 27u; c++) {
+//!     let range = spatial_hash_range(keys[c]);
+//!     for (var k = range.x; k < range.y; k++) {
+//!         let other = spatial_hash_sorted[k];
+//!         // Distance check against `other` ...
+//!     }
+//! }
+//! ```
+//!
+//! The 27 cells around a point cover every neighbor within one cell size,
+//! so the cell size should be at least the query radius. Different cells
+//! can share a key; the neighbor keys are deduplicated, but a query may
+//! still see points from far away cells and has to check distances.
+
+use wgpu::{CommandEncoder, Device, Queue};
+
+use crate::simulation::params::ParamsUniform;
+use crate::wgpu_utils::compute_primitives::Scan;
+
+const WORKGROUP_SIZE: u32 = 256;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct SpatialHashUniforms {
+    count: u32,
+    table_size: u32,
+    /// Distance between points in `vec4`s
+    stride: u32,
+    cell_size: f32,
+}
+
+/// Counting-sort spatial hash over a buffer of points
+///
+/// Points are read as `vec4<f32>` (`w` ignored) at the start of every
+/// element of the positions buffer, so particle and agent buffers can be
+/// hashed in place.
+pub struct SpatialHash {
+    uniforms: ParamsUniform<SpatialHashUniforms>,
+    settings: SpatialHashUniforms,
+    capacity: u32,
+    cell_start: wgpu::Buffer,
+    cell_count: wgpu::Buffer,
+    sorted: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    scan: Scan<u32>,
+    count_pipeline: wgpu::ComputePipeline,
+    scatter_pipeline: wgpu::ComputePipeline,
+}
+
+/// Key of a grid cell, as computed by the shaders
+pub fn hash_cell(cell: [i32; 3], table_size: u32) -> u32 {
+    let [x, y, z] = cell.map(|c| c as u32);
+    (x.wrapping_mul(73856093) ^ y.wrapping_mul(19349663) ^ z.wrapping_mul(83492791)) % table_size
+}
+
+impl SpatialHash {
+    /// Create a hash for up to `capacity` points in `positions`
+    ///
+    /// # Arguments
+    ///
+    /// * `positions` - Storage buffer with a `vec4<f32>` position at the start of each element
+    /// * `stride` - Bytes from one element to the next, a multiple of 16
+    /// * `capacity` - Maximum number of points, also the initial point count
+    /// * `cell_size` - Grid cell size, at least the largest query radius
+    pub fn new(
+        device: &Device,
+        positions: &wgpu::Buffer,
+        stride: u64,
+        capacity: u32,
+        cell_size: f32,
+    ) -> Self {
+        assert!(
+            stride > 0 && stride.is_multiple_of(16),
+            "spatial hash stride must be a multiple of 16 bytes"
+        );
+        assert!(capacity > 0, "spatial hash capacity must not be zero");
+
+        // About two keys per point keeps collisions rare
+        let table_size = (capacity * 2).next_power_of_two().max(WORKGROUP_SIZE);
+        let settings = SpatialHashUniforms {
+            count: capacity,
+            table_size,
+            stride: (stride / 16) as u32,
+            cell_size,
+        };
+        let uniforms = ParamsUniform::new(device, "Spatial Hash Uniforms", &settings);
+
+        let u32_buffer = |label: &str, len: u32| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: len as u64 * 4,
+                usage: wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_DST
+                    | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            })
+        };
+        let cell_start = u32_buffer("Spatial Hash Cell Start", table_size);
+        let cell_count = u32_buffer("Spatial Hash Cell Count", table_size);
+        let sorted = u32_buffer("Spatial Hash Sorted Indices", capacity);
+        let keys = u32_buffer("Spatial Hash Keys", capacity);
+        let slots = u32_buffer("Spatial Hash Slots", capacity);
+        let scan = Scan::new(device, &cell_count, &cell_start, table_size);
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Spatial Hash Build Shader"),
+            source: wgpu::ShaderSource::Wgsl(build_wgsl().into()),
+        });
+        let entry = |binding: u32, ty: wgpu::BufferBindingType| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let storage = |read_only| wgpu::BufferBindingType::Storage { read_only };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Spatial Hash Build Layout"),
+            entries: &[
+                entry(0, wgpu::BufferBindingType::Uniform),
+                entry(1, storage(true)),
+                entry(2, storage(false)),
+                entry(3, storage(false)),
+                entry(4, storage(false)),
+                entry(5, storage(true)),
+                entry(6, storage(false)),
+            ],
+        });
+        let resources = [
+            uniforms.buffer(),
+            positions,
+            &keys,
+            &slots,
+            &cell_count,
+            &cell_start,
+            &sorted,
+        ];
+        let entries: Vec<wgpu::BindGroupEntry> = resources
+            .iter()
+            .enumerate()
+            .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                binding: binding as u32,
+                resource: buffer.as_entire_binding(),
+            })
+            .collect();
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Spatial Hash Build Bind Group"),
+            layout: &bind_group_layout,
+            entries: &entries,
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Spatial Hash Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |entry_point: &str| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+
+        Self {
+            uniforms,
+            settings,
+            capacity,
+            cell_start,
+            cell_count,
+            sorted,
+            bind_group,
+            scan,
+            count_pipeline: pipeline("count"),
+            scatter_pipeline: pipeline("scatter"),
+        }
+    }
+
+    /// Number of points hashed by [`build`](Self::build), at most the capacity
+    pub fn set_count(&mut self, count: u32) {
+        self.settings.count = count.min(self.capacity);
+    }
+
+    pub fn count(&self) -> u32 {
+        self.settings.count
+    }
+
+    pub fn set_cell_size(&mut self, cell_size: f32) {
+        self.settings.cell_size = cell_size;
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.settings.cell_size
+    }
+
+    /// Number of hash keys
+    pub fn table_size(&self) -> u32 {
+        self.settings.table_size
+    }
+
+    /// Point indices sorted by key (`u32` each)
+    pub fn sorted_indices(&self) -> &wgpu::Buffer {
+        &self.sorted
+    }
+
+    /// First sorted index of every key (`u32` each)
+    pub fn cell_start(&self) -> &wgpu::Buffer {
+        &self.cell_start
+    }
+
+    /// Number of points of every key (`u32` each)
+    pub fn cell_count(&self) -> &wgpu::Buffer {
+        &self.cell_count
+    }
+
+    /// Record the passes rebuilding the hash from the current positions
+    pub fn build(&mut self, queue: &Queue, encoder: &mut CommandEncoder) {
+        self.uniforms.update(queue, &self.settings);
+        encoder.clear_buffer(&self.cell_count, 0, None);
+
+        let point_groups = self.settings.count.div_ceil(WORKGROUP_SIZE);
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Spatial Hash Count"),
+                timestamp_writes: None,
+            });
+            pass.set_bind_group(0, &self.bind_group, &[]);
+            pass.set_pipeline(&self.count_pipeline);
+            pass.dispatch_workgroups(point_groups, 1, 1);
+        }
+        self.scan.encode(queue, encoder, self.settings.table_size);
+
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Spatial Hash Scatter"),
+            timestamp_writes: None,
+        });
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.set_pipeline(&self.scatter_pipeline);
+        pass.dispatch_workgroups(point_groups, 1, 1);
+    }
+
+    /// Rebuild the hash in a submission of its own
+    pub fn build_and_submit(&mut self, device: &Device, queue: &Queue) {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Spatial Hash Encoder"),
+        });
+        self.build(queue, &mut encoder);
+        queue.submit(std::iter::once(encoder.finish()));
+    }
+
+    /// Layout entries for querying the hash: uniforms, cell starts, cell
+    /// counts and sorted indices at `first_binding..first_binding + 4`
+    pub fn bind_group_layout_entries(
+        first_binding: u32,
+        visibility: wgpu::ShaderStages,
+    ) -> [wgpu::BindGroupLayoutEntry; 4] {
+        let entry = |offset: u32, ty: wgpu::BufferBindingType| wgpu::BindGroupLayoutEntry {
+            binding: first_binding + offset,
+            visibility,
+            ty: wgpu::BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let read_only = wgpu::BufferBindingType::Storage { read_only: true };
+        [
+            entry(0, wgpu::BufferBindingType::Uniform),
+            entry(1, read_only),
+            entry(2, read_only),
+            entry(3, read_only),
+        ]
+    }
+
+    /// Bind group entries matching [`bind_group_layout_entries`](Self::bind_group_layout_entries)
+    pub fn bind_group_entries(&self, first_binding: u32) -> [wgpu::BindGroupEntry<'_>; 4] {
+        let buffers = [
+            self.uniforms.buffer(),
+            &self.cell_start,
+            &self.cell_count,
+            &self.sorted,
+        ];
+        std::array::from_fn(|offset| wgpu::BindGroupEntry {
+            binding: first_binding + offset as u32,
+            resource: buffers[offset].as_entire_binding(),
+        })
+    }
+
+    /// WGSL declaring the query bindings and helper functions
+    ///
+    /// Defines `spatial_hash_cell`, `spatial_hash_key`,
+    /// `spatial_hash_neighbor_keys` and `spatial_hash_range`, plus the
+    /// `spatial_hash_sorted` index array.
+    pub fn query_wgsl(group: u32, first_binding: u32) -> String {
+        format!(
+            "{UNIFORMS_WGSL}
+@group({group}) @binding({b0}) var<uniform> spatial_hash: SpatialHashUniforms;
+@group({group}) @binding({b1}) var<storage, read> spatial_hash_start: array<u32>;
+@group({group}) @binding({b2}) var<storage, read> spatial_hash_count: array<u32>;
+@group({group}) @binding({b3}) var<storage, read> spatial_hash_sorted: array<u32>;
+{FUNCTIONS_WGSL}{QUERY_WGSL}",
+            b0 = first_binding,
+            b1 = first_binding + 1,
+            b2 = first_binding + 2,
+            b3 = first_binding + 3,
+        )
+    }
+}
+
+fn build_wgsl() -> String {
+    format!("{UNIFORMS_WGSL}{FUNCTIONS_WGSL}{BUILD_WGSL}")
+}
+
+const UNIFORMS_WGSL: &str = r#"
+struct SpatialHashUniforms {
+    count: u32,
+    table_size: u32,
+    stride: u32,
+    cell_size: f32,
+}
+"#;
+
+const FUNCTIONS_WGSL: &str = r#"
+fn spatial_hash_cell(position: vec3<f32>) -> vec3<i32> {
+    return vec3<i32>(floor(position / spatial_hash.cell_size));
+}
+
+fn spatial_hash_key(cell: vec3<i32>) -> u32 {
+    let c = bitcast<vec3<u32>>(cell);
+    return ((c.x * 73856093u) ^ (c.y * 19349663u) ^ (c.z * 83492791u)) % spatial_hash.table_size;
+}
+"#;
+
+const QUERY_WGSL: &str = r#"
+const SPATIAL_HASH_NONE: u32 = 0xffffffffu;
+
+// Keys of the 27 cells around `position`; repeated keys are SPATIAL_HASH_NONE
+fn spatial_hash_neighbor_keys(position: vec3<f32>) -> array<u32, 27> {
+    let center = spatial_hash_cell(position);
+    var keys: array<u32, 27>;
+    for (var n = 0u; n < 27u; n++) {
+        let offset = vec3<i32>(i32(n % 3u) - 1, i32(n / 3u % 3u) - 1, i32(n / 9u) - 1);
+        let key = spatial_hash_key(center + offset);
+        keys[n] = key;
+        for (var m = 0u; m < n; m++) {
+            if (keys[m] == key) {
+                keys[n] = SPATIAL_HASH_NONE;
+                break;
+            }
+        }
+    }
+    return keys;
+}
+
+// Range of `spatial_hash_sorted` holding the points of `key`
+fn spatial_hash_range(key: u32) -> vec2<u32> {
+    if (key == SPATIAL_HASH_NONE) {
+        return vec2<u32>(0u, 0u);
+    }
+    let start = spatial_hash_start[key];
+    return vec2<u32>(start, start + spatial_hash_count[key]);
+}
+"#;
+
+const BUILD_WGSL: &str = r#"
+@group(0) @binding(0) var<uniform> spatial_hash: SpatialHashUniforms;
+@group(0) @binding(1) var<storage, read> positions: array<vec4<f32>>;
+@group(0) @binding(2) var<storage, read_write> keys: array<u32>;
+@group(0) @binding(3) var<storage, read_write> slots: array<u32>;
+@group(0) @binding(4) var<storage, read_write> cell_count: array<atomic<u32>>;
+@group(0) @binding(5) var<storage, read> cell_start: array<u32>;
+@group(0) @binding(6) var<storage, read_write> sorted: array<u32>;
+
+@compute @workgroup_size(256)
+fn count(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if (i >= spatial_hash.count) {
+        return;
+    }
+    let key = spatial_hash_key(spatial_hash_cell(positions[i * spatial_hash.stride].xyz));
+    keys[i] = key;
+    slots[i] = atomicAdd(&cell_count[key], 1u);
+}
+
+@compute @workgroup_size(256)
+fn scatter(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if (i >= spatial_hash.count) {
+        return;
+    }
+    sorted[cell_start[keys[i]] + slots[i]] = i;
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_cell_matches_shader_arithmetic() {
+        // Negative cells wrap like the shader's bitcast to u32
+        let table_size = 1024;
+        let key = hash_cell([-1, 0, 2], table_size);
+        let expected = (u32::MAX.wrapping_mul(73856093) ^ 2u32.wrapping_mul(83492791)) % table_size;
+        assert_eq!(key, expected);
+
+        // All neighbor keys stay inside the table
+        for n in 0..27 {
+            let cell = [n % 3 - 1, n / 3 % 3 - 1, n / 9 - 1];
+            assert!(hash_cell(cell, table_size) < table_size);
+        }
+        assert_eq!(std::mem::size_of::<SpatialHashUniforms>(), 16);
+    }
+
+    #[test]
+    fn test_build_groups_points_by_key() {
+        use crate::wgpu_utils::compute_primitives::{read_buffer, test_buffer, test_device};
+
+        // Needs a GPU adapter, skipped without one
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        // Three floats of padding after each position, like a particle struct
+        let points: Vec<[f32; 8]> = (0..3000)
+            .map(|i| {
+                let t = i as f32;
+                let p = [(t * 0.37).sin() * 5.0, (t * 0.91).cos() * 5.0, t % 4.0];
+                [p[0], p[1], p[2], 0.0, 1.0, 1.0, 1.0, 1.0]
+            })
+            .collect();
+        let buffer = test_buffer(&device, &points);
+        let mut hash = SpatialHash::new(&device, &buffer, 32, points.len() as u32, 0.5);
+        hash.build_and_submit(&device, &queue);
+
+        let table_size = hash.table_size();
+        let sorted: Vec<u32> =
+            read_buffer(&device, &queue, hash.sorted_indices(), points.len()).unwrap();
+        let start: Vec<u32> =
+            read_buffer(&device, &queue, hash.cell_start(), table_size as usize).unwrap();
+        let count: Vec<u32> =
+            read_buffer(&device, &queue, hash.cell_count(), table_size as usize).unwrap();
+
+        let key_of = |point: &[f32; 8]| {
+            hash_cell(
+                [0, 1, 2].map(|axis| (point[axis] / 0.5).floor() as i32),
+                table_size,
+            )
+        };
+        let mut seen = vec![false; points.len()];
+        for key in 0..table_size {
+            let range = start[key as usize]..start[key as usize] + count[key as usize];
+            for &index in &sorted[range.start as usize..range.end as usize] {
+                assert_eq!(key_of(&points[index as usize]), key);
+                seen[index as usize] = true;
+            }
+        }
+        assert!(seen.iter().all(|&seen| seen));
+    }
+}
@@ -0,0 +1,197 @@
+//! GPU time of submitted work from timestamp queries
+//!
+//! [`GpuTimer`] brackets a run of submissions with two timestamps, written by
+//! empty compute passes so the timed work needs no changes, and reads the
+//! difference back without blocking. Results arrive a frame or more after
+//! the work was submitted. The device needs
+//! [`wgpu::Features::TIMESTAMP_QUERY`], e.g. through
+//! `HaggisAppBuilder::required_features`.
+
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use wgpu::{Buffer, Device, QuerySet, Queue};
+
+/// Size of the two resolved timestamps
+const TIMESTAMPS_SIZE: u64 = 2 * std::mem::size_of::<u64>() as u64;
+
+/// Measures the GPU time between [`begin`](Self::begin) and [`end`](Self::end)
+#[derive(Debug)]
+pub struct GpuTimer {
+    query_set: QuerySet,
+    resolve: Buffer,
+    readback: Buffer,
+    /// Nanoseconds per timestamp tick
+    period: f32,
+    state: TimerState,
+}
+
+#[derive(Debug)]
+enum TimerState {
+    Idle,
+    Recording,
+    /// Waiting for the readback buffer to map, then whether it mapped
+    Reading(Arc<OnceLock<bool>>),
+}
+
+impl GpuTimer {
+    /// Create a timer, or `None` if the device lacks timestamp queries
+    pub fn new(device: &Device, queue: &Queue) -> Option<Self> {
+        if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("GPU Timer Queries"),
+            ty: wgpu::QueryType::Timestamp,
+            count: 2,
+        });
+        let resolve = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("GPU Timer Resolve"),
+            size: TIMESTAMPS_SIZE,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("GPU Timer Readback"),
+            size: TIMESTAMPS_SIZE,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        Some(Self {
+            query_set,
+            resolve,
+            readback,
+            period: queue.get_timestamp_period(),
+            state: TimerState::Idle,
+        })
+    }
+
+    /// Whether a new measurement can begin; false while one is read back
+    pub fn is_idle(&self) -> bool {
+        matches!(self.state, TimerState::Idle)
+    }
+
+    /// Write the start timestamp after the work submitted so far
+    ///
+    /// # Returns
+    ///
+    /// `false`, writing nothing, if the last measurement is still pending
+    pub fn begin(&mut self, device: &Device, queue: &Queue) -> bool {
+        if !self.is_idle() {
+            return false;
+        }
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("GPU Timer Begin"),
+        });
+        self.write_timestamp(&mut encoder, 0);
+        queue.submit(Some(encoder.finish()));
+        self.state = TimerState::Recording;
+        true
+    }
+
+    /// Write the end timestamp and start reading the measurement back
+    ///
+    /// Does nothing unless [`begin`](Self::begin) started a measurement.
+    pub fn end(&mut self, device: &Device, queue: &Queue) {
+        if !matches!(self.state, TimerState::Recording) {
+            return;
+        }
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("GPU Timer End"),
+        });
+        self.write_timestamp(&mut encoder, 1);
+        encoder.resolve_query_set(&self.query_set, 0..2, &self.resolve, 0);
+        encoder.copy_buffer_to_buffer(&self.resolve, 0, &self.readback, 0, TIMESTAMPS_SIZE);
+        queue.submit(Some(encoder.finish()));
+
+        let mapped = Arc::new(OnceLock::new());
+        {
+            let mapped = mapped.clone();
+            self.readback
+                .slice(..)
+                .map_async(wgpu::MapMode::Read, move |result| {
+                    let _ = mapped.set(result.is_ok());
+                });
+        }
+        self.state = TimerState::Reading(mapped);
+    }
+
+    /// The finished measurement, if it has been read back
+    ///
+    /// Processes device callbacks without blocking.
+    pub fn poll(&mut self, device: &Device) -> Option<Duration> {
+        let TimerState::Reading(mapped) = &self.state else {
+            return None;
+        };
+        if mapped.get().is_none() {
+            let _ = device.poll(wgpu::MaintainBase::Poll);
+        }
+        match mapped.get() {
+            None => return None,
+            Some(false) => {
+                self.state = TimerState::Idle;
+                return None;
+            }
+            Some(true) => {}
+        }
+        let timestamps: [u64; 2] = {
+            let range = self.readback.slice(..).get_mapped_range();
+            bytemuck::pod_read_unaligned(&range)
+        };
+        self.readback.unmap();
+        self.state = TimerState::Idle;
+        let ticks = timestamps[1].saturating_sub(timestamps[0]);
+        Some(Duration::from_nanos(
+            (ticks as f64 * self.period as f64) as u64,
+        ))
+    }
+
+    /// Record an empty compute pass that writes timestamp `index` at its start
+    fn write_timestamp(&self, encoder: &mut wgpu::CommandEncoder, index: u32) {
+        encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("GPU Timer Timestamp"),
+            timestamp_writes: Some(wgpu::ComputePassTimestampWrites {
+                query_set: &self.query_set,
+                beginning_of_pass_write_index: Some(index),
+                end_of_pass_write_index: None,
+            }),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wgpu_utils::compute_primitives::test_device;
+
+    #[test]
+    fn test_timer_measures_submissions() {
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        // Test devices don't request timestamp queries
+        assert!(GpuTimer::new(&device, &queue).is_none());
+
+        let adapter =
+            pollster::block_on(wgpu::Instance::default().request_adapter(&Default::default()));
+        let Some(adapter) = adapter
+            .ok()
+            .filter(|adapter| adapter.features().contains(wgpu::Features::TIMESTAMP_QUERY))
+        else {
+            return;
+        };
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+            required_features: wgpu::Features::TIMESTAMP_QUERY,
+            ..Default::default()
+        }))
+        .unwrap();
+        let mut timer = GpuTimer::new(&device, &queue).unwrap();
+        assert!(timer.begin(&device, &queue));
+        assert!(!timer.begin(&device, &queue));
+        timer.end(&device, &queue);
+
+        let _ = device.poll(wgpu::MaintainBase::Wait);
+        assert!(timer.poll(&device).is_some());
+        assert!(timer.is_idle());
+    }
+}
@@ -0,0 +1,424 @@
+//! Recent history of a field, for stepping back through a run
+//!
+//! A [`FieldHistory`] keeps the last N snapshots of one field in a ring, so
+//! transient events such as the onset of vortex shedding can be found again
+//! after they have scrolled past. Snapshots are kept either as copies in GPU
+//! buffers, which is fast but costs video memory, or on the CPU in the
+//! compact [`Checkpoint`] encoding (half precision, run-length encoded).
+//!
+//! [`FieldHistory::render_ui`] draws a timeline scrubber. While a snapshot
+//! is selected the history is "scrubbing", and simulations usually pause and
+//! show the selected snapshot instead of the live field, e.g. by copying it
+//! into the buffer a cut plane displays with
+//! [`copy_selected_to`](FieldHistory::copy_selected_to).
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::simulation::history::{FieldHistory, HistoryStorage};
+//!
+//! # fn example(device: &wgpu::Device, queue: &wgpu::Queue, field: &wgpu::Buffer, step: u64, time: f64) {
+//! // The last 64 snapshots, one every 10 steps, in video memory
+//! let mut history = FieldHistory::new("vorticity", [256, 128, 1], 64, HistoryStorage::Gpu)
+//!     .with_interval(10);
+//!
+//! // After each step
+//! if history.is_due(step) {
+//!     history.record_buffer(device, queue, field, step, time);
+//! }
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+
+use wgpu::{Buffer, Device, Queue};
+
+use crate::simulation::checkpoint::{Checkpoint, CheckpointOptions};
+use crate::wgpu_utils::compute_primitives::read_buffer;
+
+/// Where a [`FieldHistory`] keeps its snapshots
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HistoryStorage {
+    /// Copies in GPU buffers, recorded without a readback
+    Gpu,
+    /// Half precision, run-length encoded bytes on the CPU
+    #[default]
+    Compressed,
+}
+
+#[derive(Debug)]
+enum SnapshotData {
+    /// Index of the GPU buffer holding the snapshot
+    Gpu(usize),
+    /// Encoded checkpoint with the field
+    Compressed(Vec<u8>),
+}
+
+/// One recorded state of the field
+#[derive(Debug)]
+struct Snapshot {
+    step: u64,
+    time: f64,
+    data: SnapshotData,
+}
+
+/// Ring of the most recent snapshots of one field
+#[derive(Debug)]
+pub struct FieldHistory {
+    name: String,
+    dims: [u32; 3],
+    capacity: usize,
+    storage: HistoryStorage,
+    /// Record every this many steps
+    interval: u64,
+    /// Oldest first
+    snapshots: VecDeque<Snapshot>,
+    /// GPU buffers, reused once the ring is full
+    buffers: Vec<Buffer>,
+    /// Buffers no longer holding a snapshot
+    free: Vec<usize>,
+    /// Selected snapshot while scrubbing, `None` when live
+    cursor: Option<usize>,
+}
+
+impl FieldHistory {
+    /// History of the last `capacity` snapshots of a field of `dims` values
+    pub fn new(name: &str, dims: [u32; 3], capacity: usize, storage: HistoryStorage) -> Self {
+        Self {
+            name: name.to_string(),
+            dims,
+            capacity: capacity.max(1),
+            storage,
+            interval: 1,
+            snapshots: VecDeque::new(),
+            buffers: Vec::new(),
+            free: Vec::new(),
+            cursor: None,
+        }
+    }
+
+    /// Record every `steps` steps when checking with [`is_due`](Self::is_due)
+    pub fn with_interval(mut self, steps: u64) -> Self {
+        self.interval = steps.max(1);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Drop every snapshot, e.g. after a reset
+    pub fn clear(&mut self) {
+        while let Some(snapshot) = self.snapshots.pop_front() {
+            if let SnapshotData::Gpu(buffer) = snapshot.data {
+                self.free.push(buffer);
+            }
+        }
+        self.cursor = None;
+    }
+
+    /// Whether `step` falls on the recording interval
+    pub fn is_due(&self, step: u64) -> bool {
+        step.is_multiple_of(self.interval)
+    }
+
+    fn value_count(&self) -> usize {
+        self.dims.iter().map(|&d| d as usize).product()
+    }
+
+    /// Bytes held by the snapshots, on the GPU or the CPU
+    pub fn memory_bytes(&self) -> u64 {
+        match self.storage {
+            HistoryStorage::Gpu => self.buffers.iter().map(Buffer::size).sum(),
+            HistoryStorage::Compressed => self
+                .snapshots
+                .iter()
+                .map(|snapshot| match &snapshot.data {
+                    SnapshotData::Compressed(bytes) => bytes.len() as u64,
+                    SnapshotData::Gpu(_) => 0,
+                })
+                .sum(),
+        }
+    }
+
+    /// Make room for one more snapshot
+    fn evict(&mut self) {
+        if self.snapshots.len() < self.capacity {
+            return;
+        }
+        if let Some(oldest) = self.snapshots.pop_front() {
+            // The selection stays on the same snapshot, or the oldest left
+            self.cursor = self.cursor.map(|cursor| cursor.saturating_sub(1));
+            if let SnapshotData::Gpu(buffer) = oldest.data {
+                self.free.push(buffer);
+            }
+        }
+    }
+
+    fn push(&mut self, step: u64, time: f64, data: SnapshotData) {
+        self.snapshots.push_back(Snapshot { step, time, data });
+    }
+
+    /// Record field values from the CPU
+    ///
+    /// With [`HistoryStorage::Gpu`], the values are compressed like
+    /// [`HistoryStorage::Compressed`], as there's no device to upload to.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `values` doesn't hold one value per cell.
+    pub fn record(&mut self, step: u64, time: f64, values: &[f32]) {
+        self.evict();
+        let mut checkpoint = Checkpoint::new(step, time);
+        checkpoint.add_field(&self.name, self.dims, values.to_vec());
+        let bytes = checkpoint.to_bytes(CheckpointOptions::compact());
+        self.push(step, time, SnapshotData::Compressed(bytes));
+    }
+
+    /// Record the field held in `source`, one `f32` per cell
+    ///
+    /// GPU snapshots are copied without waiting; compressed snapshots wait
+    /// for the field to be read back, so record them on an interval. The
+    /// buffer needs `COPY_SRC` usage.
+    pub fn record_buffer(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        source: &Buffer,
+        step: u64,
+        time: f64,
+    ) {
+        let size = self.value_count() as u64 * 4;
+        match self.storage {
+            HistoryStorage::Gpu => {
+                self.evict();
+                let index = match self.free.pop() {
+                    Some(index) => index,
+                    None => {
+                        self.buffers
+                            .push(device.create_buffer(&wgpu::BufferDescriptor {
+                                label: Some(&format!("{} History", self.name)),
+                                size,
+                                usage: wgpu::BufferUsages::STORAGE
+                                    | wgpu::BufferUsages::COPY_SRC
+                                    | wgpu::BufferUsages::COPY_DST,
+                                mapped_at_creation: false,
+                            }));
+                        self.buffers.len() - 1
+                    }
+                };
+                let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Field History Copy"),
+                });
+                encoder.copy_buffer_to_buffer(source, 0, &self.buffers[index], 0, size);
+                queue.submit(Some(encoder.finish()));
+                self.push(step, time, SnapshotData::Gpu(index));
+            }
+            HistoryStorage::Compressed => {
+                match read_buffer::<f32>(device, queue, source, self.value_count()) {
+                    Ok(values) => self.record(step, time, &values),
+                    Err(error) => {
+                        log::warn!("Field history '{}' readback failed: {error}", self.name)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Index of the selected snapshot, oldest first, while scrubbing
+    pub fn selected(&self) -> Option<usize> {
+        self.cursor
+    }
+
+    /// Whether a past snapshot is selected instead of the live field
+    pub fn is_scrubbing(&self) -> bool {
+        self.cursor.is_some()
+    }
+
+    /// Select a snapshot, oldest first
+    pub fn select(&mut self, index: usize) {
+        if !self.snapshots.is_empty() {
+            self.cursor = Some(index.min(self.snapshots.len() - 1));
+        }
+    }
+
+    /// Stop scrubbing and follow the live field again
+    pub fn go_live(&mut self) {
+        self.cursor = None;
+    }
+
+    /// Step and time of the selected snapshot
+    pub fn selected_step(&self) -> Option<(u64, f64)> {
+        let snapshot = self.snapshots.get(self.cursor?)?;
+        Some((snapshot.step, snapshot.time))
+    }
+
+    /// Values of the selected snapshot, decoded or read back from the GPU
+    pub fn selected_values(&self, device: &Device, queue: &Queue) -> Option<Vec<f32>> {
+        let snapshot = self.snapshots.get(self.cursor?)?;
+        match &snapshot.data {
+            SnapshotData::Gpu(index) => {
+                read_buffer(device, queue, &self.buffers[*index], self.value_count()).ok()
+            }
+            SnapshotData::Compressed(bytes) => Self::decode(bytes),
+        }
+    }
+
+    /// Copy the selected snapshot into `target`, e.g. the buffer a cut plane
+    /// shows; does nothing when live
+    pub fn copy_selected_to(&self, device: &Device, queue: &Queue, target: &Buffer) {
+        let Some(snapshot) = self.cursor.and_then(|cursor| self.snapshots.get(cursor)) else {
+            return;
+        };
+        match &snapshot.data {
+            SnapshotData::Gpu(index) => {
+                let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Field History Restore"),
+                });
+                let size = self.value_count() as u64 * 4;
+                encoder.copy_buffer_to_buffer(&self.buffers[*index], 0, target, 0, size);
+                queue.submit(Some(encoder.finish()));
+            }
+            SnapshotData::Compressed(bytes) => {
+                if let Some(values) = Self::decode(bytes) {
+                    queue.write_buffer(target, 0, bytemuck::cast_slice(&values));
+                }
+            }
+        }
+    }
+
+    /// Values of the selected compressed snapshot, without a device
+    pub fn selected_cpu_values(&self) -> Option<Vec<f32>> {
+        match &self.snapshots.get(self.cursor?)?.data {
+            SnapshotData::Compressed(bytes) => Self::decode(bytes),
+            SnapshotData::Gpu(_) => None,
+        }
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Vec<f32>> {
+        let checkpoint = Checkpoint::from_bytes(bytes).ok()?;
+        Some(checkpoint.fields().first()?.data.clone())
+    }
+
+    /// Draw the timeline scrubber
+    ///
+    /// # Returns
+    ///
+    /// `true` if the selection changed, including going live
+    pub fn render_ui(&mut self, ui: &imgui::Ui) -> bool {
+        if self.snapshots.is_empty() {
+            ui.text_disabled("No snapshots recorded yet");
+            return false;
+        }
+        let last = self.snapshots.len() - 1;
+        let before = self.cursor;
+        let mut index = self.cursor.unwrap_or(last) as i32;
+
+        if ui.arrow_button("##history_back", imgui::Direction::Left) {
+            index -= 1;
+            self.cursor = Some(index.max(0) as usize);
+        }
+        ui.same_line();
+        if ui.arrow_button("##history_forward", imgui::Direction::Right) && self.cursor.is_some() {
+            index += 1;
+            self.cursor = Some((index as usize).min(last));
+        }
+        ui.same_line();
+        if ui.slider(
+            format!("##{}_timeline", self.name),
+            0,
+            last as i32,
+            &mut index,
+        ) {
+            self.cursor = Some(index as usize);
+        }
+        ui.same_line();
+        if ui.button(if self.cursor.is_some() {
+            "Live"
+        } else {
+            "Live ●"
+        }) {
+            self.cursor = None;
+        }
+
+        match self.selected_step() {
+            Some((step, time)) => ui.text(format!(
+                "Snapshot {} of {}: step {step}, t = {time:.3}",
+                index + 1,
+                last + 1
+            )),
+            None => ui.text(format!("Live, {} snapshots", last + 1)),
+        }
+        ui.text_disabled(format!(
+            "{:.1} MB {}",
+            self.memory_bytes() as f64 / 1_048_576.0,
+            match self.storage {
+                HistoryStorage::Gpu => "of video memory",
+                HistoryStorage::Compressed => "compressed",
+            }
+        ));
+        self.cursor != before
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wgpu_utils::compute_primitives::{test_buffer, test_device};
+
+    #[test]
+    fn test_ring_keeps_latest_snapshots() {
+        let mut history =
+            FieldHistory::new("field", [4, 2, 1], 3, HistoryStorage::Compressed).with_interval(5);
+        assert!(history.is_due(10) && !history.is_due(12));
+        for step in 0..5 {
+            history.record(step, step as f64, &[step as f32; 8]);
+        }
+        assert_eq!(history.len(), 3);
+        assert!(!history.is_scrubbing());
+
+        history.select(0);
+        assert_eq!(history.selected_step(), Some((2, 2.0)));
+        assert_eq!(history.selected_cpu_values(), Some(vec![2.0; 8]));
+        // Recording evicts the oldest, the selection moves to the next one
+        history.record(5, 5.0, &[5.0; 8]);
+        assert_eq!(history.selected_step(), Some((3, 3.0)));
+        history.go_live();
+        assert_eq!(history.selected_cpu_values(), None);
+    }
+
+    #[test]
+    fn test_gpu_snapshots_restore() {
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let field = test_buffer(&device, &[0.0f32; 4]);
+        let target = test_buffer(&device, &[0.0f32; 4]);
+        let mut history = FieldHistory::new("field", [4, 1, 1], 2, HistoryStorage::Gpu);
+        for step in 1..=3u64 {
+            queue.write_buffer(&field, 0, bytemuck::cast_slice(&[step as f32; 4]));
+            history.record_buffer(&device, &queue, &field, step, 0.0);
+        }
+        // Two buffers, the first reused for step 3
+        assert_eq!(history.memory_bytes(), 32);
+
+        history.select(0);
+        assert_eq!(history.selected_values(&device, &queue), Some(vec![2.0; 4]));
+        history.select(1);
+        history.copy_selected_to(&device, &queue, &target);
+        let restored: Vec<f32> = read_buffer(&device, &queue, &target, 4).unwrap();
+        assert_eq!(restored, vec![3.0; 4]);
+    }
+}
@@ -32,6 +32,8 @@ use wgpu::{
     Queue, ShaderModule, ShaderModuleDescriptor, ShaderSource, ShaderStages,
 };
 
+use crate::wgpu_utils::{BoundResource, ShaderInterface};
+
 /// Low-level GPU compute context for simulations
 pub struct ComputeContext {
     device: Arc<Device>,
@@ -43,6 +45,10 @@ pub struct ComputeContext {
     buffers: HashMap<String, Arc<Buffer>>,
     bind_groups: HashMap<String, Arc<BindGroup>>,
     layouts: HashMap<String, Arc<BindGroupLayout>>,
+    // Debug builds check bind groups against the shader before dispatching
+    shader_interfaces: HashMap<ShaderModule, ShaderInterface>,
+    pipeline_interfaces: HashMap<String, ShaderInterface>,
+    bind_group_resources: HashMap<String, Vec<(u32, BoundResource)>>,
 }
 
 impl ComputeContext {
@@ -57,6 +63,9 @@ impl ComputeContext {
             buffers: HashMap::new(),
             bind_groups: HashMap::new(),
             layouts: HashMap::new(),
+            shader_interfaces: HashMap::new(),
+            pipeline_interfaces: HashMap::new(),
+            bind_group_resources: HashMap::new(),
         }
     }
 
@@ -122,11 +131,26 @@ impl ComputeContext {
     }
 
     /// Creates a compute shader module
-    pub fn create_shader_module(&self, name: &str, source: &str) -> Result<ShaderModule, String> {
+    ///
+    /// Debug builds validate the source first and report errors as `Err`.
+    pub fn create_shader_module(
+        &mut self,
+        name: &str,
+        source: &str,
+    ) -> Result<ShaderModule, String> {
+        let interface = if cfg!(debug_assertions) {
+            Some(ShaderInterface::from_wgsl(name, source).map_err(|e| e.to_string())?)
+        } else {
+            None
+        };
+
         let module = self.device.create_shader_module(ShaderModuleDescriptor {
             label: Some(name),
             source: ShaderSource::Wgsl(source.into()),
         });
+        if let Some(interface) = interface {
+            self.shader_interfaces.insert(module.clone(), interface);
+        }
         Ok(module)
     }
 
@@ -150,6 +174,10 @@ impl ComputeContext {
             });
 
         self.pipelines.insert(name.to_string(), Arc::new(pipeline));
+        if let Some(interface) = self.shader_interfaces.get(shader) {
+            self.pipeline_interfaces
+                .insert(name.to_string(), interface.entry_point(entry_point));
+        }
         Ok(())
     }
 
@@ -192,6 +220,13 @@ impl ComputeContext {
 
         self.bind_groups
             .insert(name.to_string(), Arc::new(bind_group));
+        if cfg!(debug_assertions) {
+            let resources = entries
+                .iter()
+                .map(|entry| (entry.binding, BoundResource::of(&entry.resource)))
+                .collect();
+            self.bind_group_resources.insert(name.to_string(), resources);
+        }
         Ok(())
     }
 
@@ -230,10 +265,13 @@ impl ComputeContext {
             .bind_groups
             .get(bind_group_name)
             .ok_or("Bind group not found")?;
+        self.check_bindings(pipeline_name, bind_group_name)?;
 
         if let Some(encoder) = &mut self.command_encoder {
+            let label = format!("{}_pass", pipeline_name);
+            crate::logging::crash::record_pass(&label);
             let mut compute_pass = encoder.begin_compute_pass(&ComputePassDescriptor {
-                label: Some(&format!("{}_pass", pipeline_name)),
+                label: Some(&label),
                 timestamp_writes: None,
             });
 
@@ -251,6 +289,64 @@ impl ComputeContext {
         Ok(())
     }
 
+    /// Dispatches a compute shader with workgroup counts read from a buffer
+    ///
+    /// The buffer registered as `indirect_buffer_name` needs `INDIRECT` usage
+    /// and holds `DispatchIndirectArgs` at `offset`.
+    pub fn dispatch_indirect(
+        &mut self,
+        pipeline_name: &str,
+        bind_group_name: &str,
+        indirect_buffer_name: &str,
+        offset: u64,
+    ) -> Result<(), String> {
+        let pipeline = self
+            .pipelines
+            .get(pipeline_name)
+            .ok_or("Pipeline not found")?;
+        let bind_group = self
+            .bind_groups
+            .get(bind_group_name)
+            .ok_or("Bind group not found")?;
+        self.check_bindings(pipeline_name, bind_group_name)?;
+        let indirect = self
+            .buffers
+            .get(indirect_buffer_name)
+            .ok_or("Indirect buffer not found")?;
+
+        if let Some(encoder) = &mut self.command_encoder {
+            let label = format!("{}_indirect_pass", pipeline_name);
+            crate::logging::crash::record_pass(&label);
+            let mut compute_pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some(&label),
+                timestamp_writes: None,
+            });
+
+            compute_pass.set_pipeline(pipeline);
+            compute_pass.set_bind_group(0, Some(bind_group.as_ref()), &[]);
+            compute_pass.dispatch_workgroups_indirect(indirect, offset);
+        } else {
+            return Err("No active command encoder".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Debug builds: compare a bind group with the bindings its pipeline's
+    /// shader declares, so mismatches are reported by name instead of as a
+    /// wgpu validation panic
+    fn check_bindings(&self, pipeline_name: &str, bind_group_name: &str) -> Result<(), String> {
+        match (
+            self.pipeline_interfaces.get(pipeline_name),
+            self.bind_group_resources.get(bind_group_name),
+        ) {
+            (Some(interface), Some(resources)) => interface
+                .check_resources(0, resources)
+                .map_err(|e| format!("Bind group '{bind_group_name}': {e}")),
+            _ => Ok(()),
+        }
+    }
+
     /// Submits all recorded commands
     pub fn submit(&mut self) -> Result<(), String> {
         if let Some(encoder) = self.command_encoder.take() {
@@ -265,6 +361,11 @@ impl ComputeContext {
         buffer_name: &str,
         size: usize,
     ) -> Result<Vec<T>, String> {
+        // Browsers only complete buffer mapping once control returns to the page
+        if cfg!(target_arch = "wasm32") {
+            return Err("Blocking buffer readback is not supported on the web".to_string());
+        }
+
         let buffer = self.buffers.get(buffer_name).ok_or("Buffer not found")?;
 
         // Create a staging buffer
@@ -685,61 +786,7 @@ impl RawGpuSimulation {
 }
 
 /// Memory pool for efficient buffer allocation
-pub struct BufferPool {
-    device: Arc<Device>,
-    free_buffers: HashMap<u64, Vec<Arc<Buffer>>>,
-    used_buffers: HashMap<String, Arc<Buffer>>,
-}
-
-impl BufferPool {
-    /// Creates a new buffer pool
-    pub fn new(device: Arc<Device>) -> Self {
-        Self {
-            device,
-            free_buffers: HashMap::new(),
-            used_buffers: HashMap::new(),
-        }
-    }
-
-    /// Allocates a buffer from the pool
-    pub fn allocate(&mut self, name: &str, size: u64, usage: BufferUsages) -> Arc<Buffer> {
-        // Try to reuse an existing buffer
-        if let Some(buffers) = self.free_buffers.get_mut(&size) {
-            if let Some(buffer) = buffers.pop() {
-                self.used_buffers.insert(name.to_string(), buffer.clone());
-                return buffer;
-            }
-        }
-
-        // Create a new buffer
-        let buffer = Arc::new(self.device.create_buffer(&BufferDescriptor {
-            label: Some(name),
-            size,
-            usage,
-            mapped_at_creation: false,
-        }));
-
-        self.used_buffers.insert(name.to_string(), buffer.clone());
-        buffer
-    }
-
-    /// Returns a buffer to the pool
-    pub fn deallocate(&mut self, name: &str) {
-        if let Some(buffer) = self.used_buffers.remove(name) {
-            let size = buffer.size();
-            self.free_buffers
-                .entry(size)
-                .or_insert_with(Vec::new)
-                .push(buffer);
-        }
-    }
-
-    /// Clears all buffers
-    pub fn clear(&mut self) {
-        self.free_buffers.clear();
-        self.used_buffers.clear();
-    }
-}
+pub use crate::wgpu_utils::buffer_pool::BufferPool;
 
 /// Performance monitoring for low-level operations
 #[allow(dead_code)]
@@ -1,14 +1,31 @@
 //! Simulation manager for the Haggis engine
 //!
 //! Manages the lifecycle of user simulations and integrates them with
-//! the main engine loop.
+//! the main engine loop. The manager also draws the simulation toolbar
+//! (play/pause/step/reset, stepping by a typed count, running to a given
+//! step, speed, frame counter and simulation time), which drives any
+//! attached simulation through its trait methods.
 
-use super::{base_simulation::BaseSimulation, traits::Simulation};
+use super::{
+    base_simulation::BaseSimulation,
+    checkpoint::Checkpoint,
+    gpu::{ComputeDevice, GpuFence},
+    progress::{EtaEstimator, ProgressSnapshot},
+    stability::{Divergence, StabilityPolicy},
+    step_policy::{StepPolicy, StepScheduler},
+    traits::Simulation,
+};
 use crate::gfx::scene::Scene;
 use imgui::Ui;
 use wgpu::{Device, Queue};
 use std::sync::{Arc, Mutex};
 
+/// Time step of single steps before the simulation has run
+const DEFAULT_STEP: f32 = 1.0 / 60.0;
+
+/// Steps taken by "Step ×N" until the count is changed
+const DEFAULT_STEP_COUNT: u32 = 10;
+
 // Global state for Conway instanced grid data - shared between examples and core
 static GLOBAL_CONWAY_GRID_DATA: Mutex<Vec<(cgmath::Vector3<f32>, f32, cgmath::Vector4<f32>)>> = Mutex::new(Vec::new());
 
@@ -19,6 +36,63 @@ pub struct SimulationManager {
     time_scale: f32,
     accumulated_time: f32,
     fixed_timestep: Option<f32>,
+    /// Simulation updates since the last reset
+    frame_count: u64,
+    /// Simulated seconds since the last reset
+    simulation_time: f64,
+    /// Single steps requested while paused
+    pending_steps: u32,
+    /// Steps taken by "Step ×N" and its key binding
+    step_count: u32,
+    /// Step to pause at while running until it
+    stop_at: Option<u64>,
+    /// Target step typed into the toolbar
+    target_step: u64,
+    /// Last variable time step, reused for single steps
+    last_step: f32,
+    /// Second GPU that runs simulation compute instead of the display GPU
+    compute: Option<ComputeDevice>,
+    /// Apply GPU results once fenced steps finish instead of right away
+    async_compute: bool,
+    /// Fence behind the last GPU step whose results are not applied yet
+    in_flight: Option<GpuFence>,
+    /// Divergence checks, `None` to never check
+    stability: Option<StabilityPolicy>,
+    /// State saved after the last passing check, for rollback
+    last_checkpoint: Option<Checkpoint>,
+    /// Last failed check, until the simulation is resumed or reset
+    divergence: Option<Divergence>,
+    /// Whether `divergence` was handed out by `take_divergence_report`
+    divergence_reported: bool,
+    /// How many steps each frame runs
+    step_policy: StepPolicy,
+    /// Step counts and timings for the step policy
+    scheduler: StepScheduler,
+    /// Time left for simulations that report progress without an ETA
+    eta: EtaEstimator,
+    /// Whether mouse drags in the 3D view paint instead of picking
+    paint_mode: bool,
+    /// Last validation error raised by the simulation's GPU work, filled in
+    /// when its error scope resolves
+    gpu_error: Arc<Mutex<Option<String>>>,
+}
+
+/// Device that runs simulation GPU work and device that displays it
+struct GpuContext<'a> {
+    device: &'a Device,
+    queue: &'a Queue,
+    compute: Option<&'a ComputeDevice>,
+    gpu_error: &'a Arc<Mutex<Option<String>>>,
+}
+
+impl GpuContext<'_> {
+    fn compute_device(&self) -> &Device {
+        self.compute.map_or(self.device, |compute| &compute.device)
+    }
+
+    fn compute_queue(&self) -> &Queue {
+        self.compute.map_or(self.queue, |compute| &compute.queue)
+    }
 }
 
 impl SimulationManager {
@@ -30,6 +104,25 @@ impl SimulationManager {
             time_scale: 1.0,
             accumulated_time: 0.0,
             fixed_timestep: None,
+            frame_count: 0,
+            simulation_time: 0.0,
+            pending_steps: 0,
+            step_count: DEFAULT_STEP_COUNT,
+            stop_at: None,
+            target_step: 0,
+            last_step: DEFAULT_STEP,
+            compute: None,
+            async_compute: false,
+            in_flight: None,
+            stability: None,
+            last_checkpoint: None,
+            divergence: None,
+            divergence_reported: true,
+            step_policy: StepPolicy::PerFrame,
+            scheduler: StepScheduler::default(),
+            eta: EtaEstimator::new(),
+            paint_mode: false,
+            gpu_error: Arc::new(Mutex::new(None)),
         }
     }
 
@@ -44,16 +137,39 @@ impl SimulationManager {
         simulation.initialize(scene);
         self.simulation = Some(simulation);
         self.is_paused = false;
+        self.clear_counters();
     }
 
     /// Initialize GPU resources for current simulation
     /// Called when device/queue become available (e.g., on WindowEvent::Resumed)
+    ///
+    /// Uses the compute device instead of `device` when one is set.
     pub fn initialize_gpu(&mut self, device: &Device, queue: &Queue) {
         if let Some(simulation) = &mut self.simulation {
-            simulation.initialize_gpu(device, queue);
+            let (device, queue) = match &self.compute {
+                Some(compute) => (&compute.device, &compute.queue),
+                None => (device, queue),
+            };
+            let name = simulation.name().to_string();
+            Self::with_error_scope(device, name, &self.gpu_error, || {
+                simulation.initialize_gpu(device, queue)
+            });
         }
     }
 
+    /// Run GPU simulation work on a separate device
+    ///
+    /// Set before [`initialize_gpu`](Self::initialize_gpu); `None` runs it on
+    /// the display device passed to `update`.
+    pub fn set_compute_device(&mut self, compute: Option<ComputeDevice>) {
+        self.compute = compute;
+    }
+
+    /// The separate compute device, if any
+    pub fn compute_device(&self) -> Option<&ComputeDevice> {
+        self.compute.as_ref()
+    }
+
     /// Remove current simulation
     pub fn detach_simulation(&mut self, scene: &mut Scene) {
         if let Some(mut sim) = self.simulation.take() {
@@ -62,6 +178,10 @@ impl SimulationManager {
     }
 
     /// Update simulation (called every frame)
+    ///
+    /// With [async compute](Self::set_async_compute), GPU results are applied
+    /// once the step's work has finished on a later frame, and no new step
+    /// starts while one is still in flight.
     pub fn update(
         &mut self,
         delta_time: f32,
@@ -69,47 +189,467 @@ impl SimulationManager {
         device: Option<&Device>,
         queue: Option<&Queue>,
     ) {
-        if self.is_paused {
+        let Some(simulation) = &mut self.simulation else {
             return;
+        };
+        let gpu = device.zip(queue).map(|(device, queue)| GpuContext {
+            device,
+            queue,
+            compute: self.compute.as_ref(),
+            gpu_error: &self.gpu_error,
+        });
+
+        // The previous step is still running on the GPU
+        if let Some(fence) = self.in_flight.take() {
+            match &gpu {
+                Some(gpu) if !fence.poll(gpu.compute_device()) => {
+                    self.in_flight = Some(fence);
+                    return;
+                }
+                Some(gpu) => Self::present(simulation.as_mut(), scene, gpu),
+                None => {}
+            }
         }
 
-        if let Some(simulation) = &mut self.simulation {
+        let present = !self.async_compute;
+        let steps_before = self.frame_count;
+
+        if self.is_paused {
+            // Single steps run while paused, with the simulation briefly running
+            if self.pending_steps > 0 {
+                let dt = self.fixed_timestep.unwrap_or(self.last_step);
+                simulation.set_running(true);
+                for _ in 0..std::mem::take(&mut self.pending_steps) {
+                    Self::advance(simulation.as_mut(), dt, scene, gpu.as_ref(), present);
+                    self.frame_count += 1;
+                    self.simulation_time += dt as f64;
+                }
+                simulation.set_running(false);
+            }
+        } else if self.step_policy != StepPolicy::PerFrame {
+            let policy = self.step_policy;
+            let dt = self.fixed_timestep.unwrap_or_else(|| policy.default_dt());
+            let mut steps = self
+                .scheduler
+                .steps_due(policy, self.time_scale, crate::platform::Instant::now());
+            if let Some(stop) = self.stop_at {
+                let left = stop.saturating_sub(self.frame_count);
+                steps = steps.min(left.try_into().unwrap_or(u32::MAX));
+            }
+            let compute = gpu
+                .as_ref()
+                .map(|gpu| (gpu.compute_device(), gpu.compute_queue()));
+
+            if let StepPolicy::TimeBudget(_) = policy {
+                self.scheduler.begin_timing(compute);
+            }
+            for _ in 0..steps {
+                Self::advance(simulation.as_mut(), dt, scene, gpu.as_ref(), present);
+                self.frame_count += 1;
+                self.simulation_time += dt as f64;
+            }
+            if let StepPolicy::TimeBudget(budget_ms) = policy {
+                self.scheduler.end_timing(budget_ms, steps, compute);
+            }
+        } else {
             let scaled_delta = delta_time * self.time_scale;
 
             if let Some(fixed_dt) = self.fixed_timestep {
                 // Fixed timestep simulation for deterministic results
                 self.accumulated_time += scaled_delta;
 
-                while self.accumulated_time >= fixed_dt {
-                    simulation.update(fixed_dt, scene);
-
-                    // GPU update if available
-                    if let (Some(device), Some(queue)) = (device, queue) {
-                        simulation.update_gpu(device, queue, fixed_dt);
-                        simulation.apply_gpu_results_to_scene(device, scene);
-
-                        // Material texture updates for visualizations will be handled separately
-                    }
-
+                while self.accumulated_time >= fixed_dt
+                    && self.stop_at.is_none_or(|stop| self.frame_count < stop)
+                {
+                    Self::advance(simulation.as_mut(), fixed_dt, scene, gpu.as_ref(), present);
+                    self.frame_count += 1;
+                    self.simulation_time += fixed_dt as f64;
                     self.accumulated_time -= fixed_dt;
                 }
-            } else {
+            } else if self.stop_at.is_none_or(|stop| self.frame_count < stop) {
                 // Variable timestep
-                simulation.update(scaled_delta, scene);
+                Self::advance(simulation.as_mut(), scaled_delta, scene, gpu.as_ref(), present);
+                self.frame_count += 1;
+                self.simulation_time += scaled_delta as f64;
+                self.last_step = scaled_delta;
+            }
+        }
+
+        if self.async_compute && self.frame_count > steps_before {
+            if let Some(gpu) = &gpu {
+                self.in_flight = Some(GpuFence::after(gpu.compute_queue()));
+            }
+        }
 
-                // GPU update if available
-                if let (Some(device), Some(queue)) = (device, queue) {
-                    simulation.update_gpu(device, queue, scaled_delta);
-                    simulation.apply_gpu_results_to_scene(device, scene);
+        self.guard_stability(steps_before, scene);
 
-                    // Material texture updates for visualizations will be handled separately
+        // Stop exactly on the step the run was meant to reach
+        if self.stop_at.is_some_and(|stop| self.frame_count >= stop) {
+            self.accumulated_time = 0.0;
+            self.set_paused(true);
+        }
+    }
+
+    /// Check the simulation if a check is due, and pause and roll it back on
+    /// divergence as the stability policy says
+    fn guard_stability(&mut self, steps_before: u64, scene: &mut Scene) {
+        let (Some(policy), Some(simulation)) = (self.stability, &mut self.simulation) else {
+            return;
+        };
+        let frame_count = self.frame_count;
+        let due = |interval: u64| frame_count / interval > steps_before / interval;
+        if !due(policy.check_interval) {
+            return;
+        }
+
+        let Some(reason) = simulation.check_stability() else {
+            if policy.checkpoint_interval.is_some_and(due) {
+                if let Some(checkpoint) = simulation.save_checkpoint(frame_count, self.simulation_time) {
+                    self.last_checkpoint = Some(checkpoint);
                 }
             }
+            return;
+        };
+
+        let mut divergence = Divergence {
+            step: frame_count,
+            time: self.simulation_time,
+            reason,
+            rolled_back_to: None,
+            suggestions: simulation.stability_suggestions(),
+        };
+        if let Some(checkpoint) = &self.last_checkpoint {
+            if policy.checkpoint_interval.is_some() && simulation.restore_checkpoint(checkpoint, scene) {
+                divergence.rolled_back_to = Some(checkpoint.step);
+                self.frame_count = checkpoint.step;
+                self.simulation_time = checkpoint.time;
+                self.accumulated_time = 0.0;
+                self.in_flight = None;
+            }
+        }
+        tracing::warn!(target: "simulation", "{divergence}");
+
+        if policy.pause {
+            self.set_paused(true);
+        }
+        self.divergence = Some(divergence);
+        self.divergence_reported = false;
+    }
+
+    /// Run one CPU and, if available, GPU update of `simulation`
+    ///
+    /// GPU results are applied right away if `present` is set.
+    fn advance(
+        simulation: &mut dyn Simulation,
+        dt: f32,
+        scene: &mut Scene,
+        gpu: Option<&GpuContext>,
+        present: bool,
+    ) {
+        simulation.update(dt, scene);
+
+        // GPU update if available
+        if let Some(gpu) = gpu {
+            let name = simulation.name().to_string();
+            Self::with_error_scope(gpu.compute_device(), name, gpu.gpu_error, || {
+                simulation.update_gpu(gpu.compute_device(), gpu.compute_queue(), dt)
+            });
+            if present {
+                Self::present(simulation, scene, gpu);
+            }
+
+            // Material texture updates for visualizations will be handled separately
+        }
+    }
+
+    /// Run `f` inside a validation error scope on `device`
+    ///
+    /// A bad bind group or dispatch in the simulation is logged under its
+    /// `name` instead of reaching the device's uncaptured error handler,
+    /// which panics without saying which simulation was at fault. Repeats of
+    /// the last error are not logged again, so a dispatch failing every step
+    /// doesn't flood the console.
+    fn with_error_scope(
+        device: &Device,
+        name: String,
+        gpu_error: &Arc<Mutex<Option<String>>>,
+        f: impl FnOnce(),
+    ) {
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        f();
+        let gpu_error = gpu_error.clone();
+        crate::platform::run_async(device.pop_error_scope(), move |error| {
+            let Some(error) = error else {
+                return;
+            };
+            let message = format!("{name}: {error}");
+            let mut last = gpu_error.lock().unwrap();
+            if last.as_deref() != Some(message.as_str()) {
+                tracing::error!(target: "simulation", "{message}");
+                *last = Some(message);
+            }
+        });
+    }
+
+    /// Last GPU validation error of the simulation, prefixed with its name
+    ///
+    /// Cleared when a simulation is attached or reset.
+    pub fn gpu_error(&self) -> Option<String> {
+        self.gpu_error.lock().unwrap().clone()
+    }
+
+    /// Apply finished GPU results to the scene and the display device
+    fn present(simulation: &mut dyn Simulation, scene: &mut Scene, gpu: &GpuContext) {
+        simulation.apply_gpu_results_to_scene(gpu.compute_device(), scene);
+        if let Some(compute) = gpu.compute {
+            simulation.sync_to_display(compute, gpu.device, gpu.queue);
+        }
+    }
+
+    /// Overlap GPU simulation steps with rendering
+    ///
+    /// Instead of applying GPU results in the frame that submitted the step,
+    /// the manager fences the step and applies them once it has finished, so
+    /// the CPU records and submits the frame meanwhile. Results reach the
+    /// scene a frame or more later, and the simulation steps no faster than
+    /// the GPU completes them.
+    pub fn set_async_compute(&mut self, enable: bool) {
+        self.async_compute = enable;
+    }
+
+    /// Turn mouse presses and drags in the 3D view into brush strokes
+    ///
+    /// See [`paint`](crate::simulation::paint). While on, the app emits
+    /// [`BrushStroke`](crate::events::BrushStroke) events instead of picking
+    /// objects, and dragging no longer orbits the camera.
+    pub fn set_paint_mode(&mut self, enable: bool) {
+        self.paint_mode = enable;
+    }
+
+    pub fn paint_mode(&self) -> bool {
+        self.paint_mode
+    }
+
+    /// Whether GPU steps overlap rendering (see [`set_async_compute`](Self::set_async_compute))
+    pub fn async_compute(&self) -> bool {
+        self.async_compute
+    }
+
+    /// Run simulation steps independently of the frame rate
+    ///
+    /// See [`step_policy`](crate::simulation::step_policy). Steps use the
+    /// fixed timestep if one is set, otherwise [`StepPolicy::default_dt`].
+    pub fn set_step_policy(&mut self, policy: StepPolicy) {
+        self.step_policy = policy;
+        self.scheduler.reset();
+    }
+
+    pub fn step_policy(&self) -> StepPolicy {
+        self.step_policy
+    }
+
+    /// Steps per frame the time budget currently allows
+    pub fn budget_steps(&self) -> u32 {
+        self.scheduler.budget_steps()
+    }
+
+    /// Check the simulation for divergence and react as `policy` says
+    ///
+    /// `None` turns the checks off. See [`stability`](crate::simulation::stability).
+    pub fn set_stability_policy(&mut self, policy: Option<StabilityPolicy>) {
+        self.stability = policy;
+        self.last_checkpoint = None;
+    }
+
+    /// Current stability policy, if checks are on
+    pub fn stability_policy(&self) -> Option<StabilityPolicy> {
+        self.stability
+    }
+
+    /// Last failed stability check, until the simulation is resumed or reset
+    pub fn divergence(&self) -> Option<&Divergence> {
+        self.divergence.as_ref()
+    }
+
+    /// The last failed stability check, returned once per divergence
+    ///
+    /// Lets the app notify about each divergence exactly once.
+    pub fn take_divergence_report(&mut self) -> Option<Divergence> {
+        if std::mem::replace(&mut self.divergence_reported, true) {
+            return None;
+        }
+        self.divergence.clone()
+    }
+
+    /// State the simulation rolls back to on divergence
+    pub fn last_checkpoint(&self) -> Option<&Checkpoint> {
+        self.last_checkpoint.as_ref()
+    }
+
+    /// Advance a paused simulation by one step on the next update
+    ///
+    /// The step uses the fixed timestep if one is set, otherwise the last
+    /// variable time step. Has no effect while the simulation is running.
+    pub fn step(&mut self) {
+        self.step_by(1);
+    }
+
+    /// Advance a paused simulation by `steps` steps on the next update
+    ///
+    /// Like [`step`](Self::step), all steps run in the next update.
+    pub fn step_by(&mut self, steps: u32) {
+        if self.is_paused && self.simulation.is_some() {
+            self.pending_steps = self.pending_steps.saturating_add(steps);
+        }
+    }
+
+    /// Steps taken by "Step ×N" in the toolbar and by
+    /// [`Action::StepSimulationBatch`](crate::input::Action::StepSimulationBatch)
+    pub fn step_count(&self) -> u32 {
+        self.step_count
+    }
+
+    pub fn set_step_count(&mut self, steps: u32) {
+        self.step_count = steps.max(1);
+    }
+
+    /// Run the simulation and pause it once [`frame_count`](Self::frame_count)
+    /// reaches `step`
+    ///
+    /// Steps follow the step policy as usual, but no frame runs past
+    /// `step`. Pausing or resetting cancels the run.
+    ///
+    /// # Returns
+    ///
+    /// `false` if there's no simulation or it's already at or past `step`
+    pub fn run_until(&mut self, step: u64) -> bool {
+        if self.simulation.is_none() || step <= self.frame_count {
+            return false;
+        }
+        self.set_paused(false);
+        self.stop_at = Some(step);
+        true
+    }
+
+    /// Step a [`run_until`](Self::run_until) run pauses at
+    pub fn stop_at(&self) -> Option<u64> {
+        self.stop_at
+    }
+
+    /// Number of simulation updates since the simulation was attached or reset
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    /// Simulated seconds since the simulation was attached or reset
+    pub fn simulation_time(&self) -> f64 {
+        self.simulation_time
+    }
+
+    fn clear_counters(&mut self) {
+        self.accumulated_time = 0.0;
+        self.frame_count = 0;
+        self.simulation_time = 0.0;
+        self.pending_steps = 0;
+        self.stop_at = None;
+        self.in_flight = None;
+        self.last_checkpoint = None;
+        self.divergence = None;
+        self.eta.reset();
+        *self.gpu_error.lock().unwrap() = None;
+    }
+
+    /// Draw the simulation toolbar at the top of the window
+    ///
+    /// Play/pause, step and reset buttons, "Step ×N" with a typed count,
+    /// "Run to" a typed step, a speed multiplier, and the frame counter and
+    /// simulation time. Does nothing without a simulation.
+    pub fn render_toolbar(&mut self, ui: &Ui, scene: &mut Scene) {
+        if self.simulation.is_none() {
+            return;
         }
+        let display_size = ui.io().display_size;
+
+        ui.window("Simulation Toolbar")
+            .position([display_size[0] * 0.5, 10.0], imgui::Condition::FirstUseEver)
+            .position_pivot([0.5, 0.0])
+            .title_bar(false)
+            .resizable(false)
+            .always_auto_resize(true)
+            .focus_on_appearing(false)
+            .build(|| {
+                if ui.button(if self.is_paused { "Play" } else { "Pause" }) {
+                    self.set_paused(!self.is_paused);
+                }
+                ui.same_line();
+                ui.disabled(!self.is_paused, || {
+                    if ui.button("Step") {
+                        self.step();
+                    }
+                    ui.same_line();
+                    if ui.button(format!("Step x{}", self.step_count)) {
+                        self.step_by(self.step_count);
+                    }
+                });
+                ui.same_line();
+                ui.set_next_item_width(60.0);
+                let mut count = self.step_count;
+                if ui.input_scalar("##step_count", &mut count).build() {
+                    self.set_step_count(count);
+                }
+
+                ui.same_line();
+                match self.stop_at {
+                    Some(stop) => {
+                        if ui.button(format!("Stop ({stop})")) {
+                            self.set_paused(true);
+                        }
+                    }
+                    None => {
+                        if ui.button("Run to") {
+                            self.run_until(self.target_step);
+                        }
+                    }
+                }
+                ui.same_line();
+                ui.set_next_item_width(90.0);
+                ui.input_scalar("##target_step", &mut self.target_step).build();
+                ui.same_line();
+                if ui.button("Reset") {
+                    self.reset_simulation(scene);
+                }
+
+                ui.same_line();
+                ui.checkbox("Paint", &mut self.paint_mode);
+
+                ui.same_line();
+                ui.set_next_item_width(110.0);
+                let mut speed = self.time_scale;
+                if ui
+                    .slider_config("Speed", 0.1, 4.0)
+                    .display_format("%.2fx")
+                    .build(&mut speed)
+                {
+                    self.set_time_scale(speed);
+                }
+                if ui.is_item_hovered() && ui.is_mouse_double_clicked(imgui::MouseButton::Left) {
+                    self.set_time_scale(1.0);
+                }
+
+                ui.same_line();
+                ui.text(format!(
+                    "Frame {}  t = {:.3} s",
+                    self.frame_count, self.simulation_time
+                ));
+                if self.divergence.is_some() {
+                    ui.same_line();
+                    ui.text_colored([1.0, 0.3, 0.3, 1.0], "Diverged");
+                }
+            });
     }
 
     /// Render simulation UI controls
-    pub fn render_ui(&mut self, ui: &Ui, scene: &mut Scene) {
+    pub fn render_ui(&mut self, ui: &Ui, _scene: &mut Scene) {
         let display_size = ui.io().display_size;
         let panel_width = 300.0;
         let panel_x = display_size[0] - panel_width - 20.0; // Position on right side
@@ -117,7 +657,7 @@ impl SimulationManager {
         if let Some(simulation) = &mut self.simulation {
             // Main simulation controls
             ui.window("Simulation Control")
-                .size([panel_width, 200.0], imgui::Condition::FirstUseEver)
+                .size([panel_width, 130.0], imgui::Condition::FirstUseEver)
                 .position([panel_x, 240.0], imgui::Condition::FirstUseEver) // Stack below SimplyMove panel
                 .build(|| {
                     ui.text(&format!("Simulation: {}", simulation.name()));
@@ -131,26 +671,7 @@ impl SimulationManager {
 
                     ui.separator();
 
-                    // Play/Pause controls
-                    if ui.button(if self.is_paused {
-                        "▶ Play"
-                    } else {
-                        "⏸ Pause"
-                    }) {
-                        self.is_paused = !self.is_paused;
-                        simulation.set_running(!self.is_paused);
-                    }
-
-                    ui.same_line();
-                    if ui.button("⏹ Reset") {
-                        simulation.reset(scene);
-                    }
-
-                    ui.separator();
-
-                    // Time controls
-                    ui.slider("Time Scale", 0.1, 3.0, &mut self.time_scale);
-
+                    // Playback controls live in the simulation toolbar
                     let mut use_fixed_timestep = self.fixed_timestep.is_some();
                     if ui.checkbox("Fixed Timestep", &mut use_fixed_timestep) {
                         if use_fixed_timestep && self.fixed_timestep.is_none() {
@@ -163,8 +684,48 @@ impl SimulationManager {
                     if let Some(ref mut fixed_dt) = self.fixed_timestep {
                         ui.slider("Fixed DT", 1.0 / 120.0, 1.0 / 30.0, fixed_dt);
                     }
+
+                    let kind = std::mem::discriminant(&self.step_policy);
+                    let mut mode = StepPolicy::ALL
+                        .iter()
+                        .position(|policy| std::mem::discriminant(policy) == kind)
+                        .unwrap_or(0);
+                    if ui.combo("Steps", &mut mode, &StepPolicy::ALL, |policy| policy.name().into()) {
+                        self.step_policy = StepPolicy::ALL[mode];
+                        self.scheduler.reset();
+                    }
+                    match &mut self.step_policy {
+                        StepPolicy::PerFrame => {}
+                        StepPolicy::StepsPerFrame(steps) => {
+                            ui.slider("Steps/frame", 1, 1000, steps);
+                        }
+                        StepPolicy::StepsPerSecond(rate) => {
+                            ui.slider("Steps/s", 1.0, 10_000.0, rate);
+                        }
+                        StepPolicy::TimeBudget(budget_ms) => {
+                            ui.slider("Budget (ms)", 0.5, 50.0, budget_ms);
+                            ui.text(format!("{} steps/frame", self.scheduler.budget_steps()));
+                        }
+                    }
+
+                    if let Some(divergence) = &self.divergence {
+                        ui.separator();
+                        ui.text_colored([1.0, 0.3, 0.3, 1.0], divergence.to_string());
+                        for suggestion in &divergence.suggestions {
+                            ui.bullet_text(suggestion);
+                        }
+                    }
                 });
 
+            if let Some(progress) = simulation.progress() {
+                let snapshot = ProgressSnapshot::read(progress, &mut self.eta);
+                ui.window("Simulation Progress")
+                    .size([panel_width, 0.0], imgui::Condition::FirstUseEver)
+                    .position([panel_x, 380.0], imgui::Condition::FirstUseEver)
+                    .always_auto_resize(true)
+                    .build(|| snapshot.render_ui(ui));
+            }
+
             // Let simulation render its own UI (positioned at top of right side)
             simulation.render_ui(ui);
         } else {
@@ -179,6 +740,31 @@ impl SimulationManager {
         }
     }
 
+    /// Offer a console or script command to the current simulation
+    ///
+    /// # Returns
+    ///
+    /// `None` if there is no simulation or it doesn't recognize the command
+    pub fn handle_command(&mut self, args: &[&str], scene: &mut Scene) -> Option<Result<String, String>> {
+        self.simulation.as_mut()?.handle_command(args, scene)
+    }
+
+    /// Parameters exposed by the current simulation, if any
+    pub fn params_mut(&mut self) -> Option<&mut dyn crate::simulation::params::SimParams> {
+        self.simulation.as_mut()?.params_mut()
+    }
+
+    /// Reset the current simulation to its initial state
+    ///
+    /// # Arguments
+    /// * `scene` - Scene passed to the simulation's `reset()`
+    pub fn reset_simulation(&mut self, scene: &mut Scene) {
+        if let Some(simulation) = &mut self.simulation {
+            simulation.reset(scene);
+        }
+        self.clear_counters();
+    }
+
     /// Get current simulation name
     ///
     /// # Returns
@@ -207,8 +793,18 @@ impl SimulationManager {
     ///
     /// # Arguments
     /// * `paused` - Whether to pause the simulation
+    ///
+    /// Resuming clears the last [divergence](Self::divergence), pausing
+    /// cancels a [`run_until`](Self::run_until) run.
     pub fn set_paused(&mut self, paused: bool) {
         self.is_paused = paused;
+        if paused {
+            self.stop_at = None;
+        } else {
+            self.divergence = None;
+            // Don't catch up on steps for the time spent paused
+            self.scheduler.reset();
+        }
         if let Some(simulation) = &mut self.simulation {
             simulation.set_running(!paused);
         }
@@ -239,6 +835,11 @@ impl SimulationManager {
         self.time_scale = scale.max(0.0); // Prevent negative time
     }
 
+    /// Name of the attached simulation
+    pub fn simulation_name(&self) -> Option<&str> {
+        self.simulation.as_deref().map(|simulation| simulation.name())
+    }
+
     /// Check if a simulation is currently attached
     ///
     /// # Returns
@@ -259,6 +860,53 @@ impl SimulationManager {
         Vec::new()
     }
 
+    /// Get world-space lines from the current simulation's visualizations
+    pub fn get_visualization_lines(&self) -> Vec<crate::gfx::rendering::LineVertex> {
+        self.simulation
+            .as_ref()
+            .and_then(|simulation| simulation.as_any().downcast_ref::<BaseSimulation>())
+            .map(|base_sim| base_sim.get_visualization_lines())
+            .unwrap_or_default()
+    }
+
+    /// Get clip planes from the current simulation's cut planes
+    pub fn get_clip_planes(&self) -> Vec<crate::gfx::rendering::ClipPlane> {
+        self.simulation
+            .as_ref()
+            .and_then(|simulation| simulation.as_any().downcast_ref::<BaseSimulation>())
+            .map(|base_sim| base_sim.get_clip_planes())
+            .unwrap_or_default()
+    }
+
+    /// Get agent batches from the current simulation's agent views
+    pub fn get_agent_batches(&self) -> Vec<crate::gfx::rendering::AgentBatch> {
+        self.simulation
+            .as_ref()
+            .and_then(|simulation| simulation.as_any().downcast_ref::<BaseSimulation>())
+            .map(|base_sim| base_sim.get_agent_batches())
+            .unwrap_or_default()
+    }
+
+    /// Get volume batches from the current simulation's volume views
+    pub fn get_volume_batches(&self) -> Vec<crate::gfx::rendering::VolumeBatch> {
+        self.simulation
+            .as_ref()
+            .and_then(|simulation| simulation.as_any().downcast_ref::<BaseSimulation>())
+            .map(|base_sim| base_sim.get_volume_batches())
+            .unwrap_or_default()
+    }
+
+    /// Draw world-space labels of the current simulation's visualizations
+    pub fn render_visualization_labels(&self, ui: &Ui, view_proj: cgmath::Matrix4<f32>) {
+        if let Some(base_sim) = self
+            .simulation
+            .as_ref()
+            .and_then(|simulation| simulation.as_any().downcast_ref::<BaseSimulation>())
+        {
+            base_sim.render_visualization_labels(ui, view_proj);
+        }
+    }
+
     /// Get instanced grid data from Conway 3D simulation if available  
     pub fn get_instanced_grid_data(&self) -> Option<Vec<(cgmath::Vector3<f32>, f32, cgmath::Vector4<f32>)>> {
         if let Some(simulation) = &self.simulation {
@@ -284,3 +932,200 @@ impl SimulationManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gfx::camera::{
+        camera_controller::CameraController, camera_utils::CameraManager,
+        orbit_camera::OrbitCamera,
+    };
+    use cgmath::Vector3;
+    use std::any::Any;
+
+    #[derive(Default)]
+    struct CountingSimulation {
+        running: bool,
+        updates: u32,
+    }
+
+    impl Simulation for CountingSimulation {
+        fn initialize(&mut self, _scene: &mut Scene) {}
+        fn update(&mut self, _delta_time: f32, _scene: &mut Scene) {
+            if self.running {
+                self.updates += 1;
+            }
+        }
+        fn render_ui(&mut self, _ui: &Ui) {}
+        fn name(&self) -> &str {
+            "Counting"
+        }
+        fn is_running(&self) -> bool {
+            self.running
+        }
+        fn set_running(&mut self, running: bool) {
+            self.running = running;
+        }
+        fn reset(&mut self, _scene: &mut Scene) {
+            self.updates = 0;
+        }
+        // Diverges after five updates
+        fn check_stability(&self) -> Option<String> {
+            (self.updates > 5).then(|| format!("{} updates", self.updates))
+        }
+        fn stability_suggestions(&self) -> Vec<String> {
+            vec!["count slower".to_string()]
+        }
+        fn save_checkpoint(&self, step: u64, time: f64) -> Option<Checkpoint> {
+            let mut checkpoint = Checkpoint::new(step, time);
+            checkpoint.add_field("updates", [1, 1, 1], vec![self.updates as f32]);
+            Some(checkpoint)
+        }
+        fn restore_checkpoint(&mut self, checkpoint: &Checkpoint, _scene: &mut Scene) -> bool {
+            self.updates = checkpoint.field("updates").unwrap().data[0] as u32;
+            true
+        }
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    fn updates(manager: &SimulationManager) -> u32 {
+        manager
+            .simulation
+            .as_ref()
+            .and_then(|sim| sim.as_any().downcast_ref::<CountingSimulation>())
+            .map_or(0, |sim| sim.updates)
+    }
+
+    fn test_scene() -> Scene {
+        let camera = OrbitCamera::new(8.0, 0.4, 0.2, Vector3::new(0.0, 0.0, 0.0), 1.0);
+        Scene::new(CameraManager::new(camera, CameraController::new(0.005, 0.1)))
+    }
+
+    #[test]
+    fn test_step_and_counters() {
+        let mut scene = test_scene();
+        let mut manager = SimulationManager::new();
+        manager.attach_simulation(Box::new(CountingSimulation::default()), &mut scene);
+        manager.set_paused(false);
+
+        manager.update(0.5, &mut scene, None, None);
+        assert_eq!(manager.frame_count(), 1);
+        assert_eq!(updates(&manager), 1);
+
+        // Steps only count while paused and use the last time step
+        manager.step();
+        manager.set_paused(true);
+        manager.update(0.5, &mut scene, None, None);
+        assert_eq!(manager.frame_count(), 1);
+        manager.step();
+        manager.step();
+        manager.update(0.5, &mut scene, None, None);
+        assert_eq!(manager.frame_count(), 3);
+        assert_eq!(updates(&manager), 3);
+        assert!((manager.simulation_time() - 1.5).abs() < 1e-6);
+        assert!(!manager.simulation.as_ref().unwrap().is_running());
+
+        manager.reset_simulation(&mut scene);
+        assert_eq!(manager.frame_count(), 0);
+        assert_eq!(manager.simulation_time(), 0.0);
+    }
+
+    #[test]
+    fn test_step_by_and_run_until() {
+        let mut scene = test_scene();
+        let mut manager = SimulationManager::new();
+        manager.attach_simulation(Box::new(CountingSimulation::default()), &mut scene);
+        manager.set_paused(true);
+
+        manager.step_by(4);
+        manager.update(0.5, &mut scene, None, None);
+        assert_eq!(manager.frame_count(), 4);
+
+        // A long frame covers ten fixed steps, but the run stops at step 7
+        manager.set_fixed_timestep(Some(0.1));
+        assert!(manager.run_until(7));
+        assert!(!manager.run_until(2));
+        manager.update(1.0, &mut scene, None, None);
+        assert_eq!(manager.frame_count(), 7);
+        assert_eq!(updates(&manager), 7);
+        assert!(manager.is_paused());
+        assert_eq!(manager.stop_at(), None);
+    }
+
+    #[test]
+    fn test_stability_guard_pauses_and_rolls_back() {
+        let mut scene = test_scene();
+        let mut manager = SimulationManager::new();
+        manager.attach_simulation(Box::new(CountingSimulation::default()), &mut scene);
+        manager.set_stability_policy(Some(
+            StabilityPolicy::new().with_check_interval(2).with_rollback(4),
+        ));
+        manager.set_paused(false);
+
+        for _ in 0..6 {
+            manager.update(0.5, &mut scene, None, None);
+        }
+        // Checked at steps 2, 4 and 6; the check at 6 fails and restores step 4
+        let divergence = manager.take_divergence_report().expect("diverged");
+        assert_eq!(divergence.step, 6);
+        assert_eq!(divergence.rolled_back_to, Some(4));
+        assert_eq!(divergence.suggestions, ["count slower"]);
+        assert!(manager.take_divergence_report().is_none());
+        assert!(manager.is_paused());
+        assert_eq!(manager.frame_count(), 4);
+        assert_eq!(updates(&manager), 4);
+
+        manager.set_paused(false);
+        assert!(manager.divergence().is_none());
+    }
+
+    /// Creates a buffer with usages that can't be combined on every step
+    struct InvalidBufferSimulation;
+
+    impl Simulation for InvalidBufferSimulation {
+        fn initialize(&mut self, _scene: &mut Scene) {}
+        fn update(&mut self, _delta_time: f32, _scene: &mut Scene) {}
+        fn render_ui(&mut self, _ui: &Ui) {}
+        fn name(&self) -> &str {
+            "Invalid Buffer"
+        }
+        fn is_running(&self) -> bool {
+            true
+        }
+        fn set_running(&mut self, _running: bool) {}
+        fn reset(&mut self, _scene: &mut Scene) {}
+        fn update_gpu(&mut self, device: &Device, _queue: &Queue, _delta_time: f32) {
+            let _buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Invalid"),
+                size: 16,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::STORAGE,
+                mapped_at_creation: false,
+            });
+        }
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    #[test]
+    fn test_gpu_validation_errors_are_attributed() {
+        use crate::wgpu_utils::compute_primitives::test_device;
+
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let mut scene = test_scene();
+        let mut manager = SimulationManager::new();
+        manager.attach_simulation(Box::new(InvalidBufferSimulation), &mut scene);
+
+        // Caught by the scope rather than the uncaptured error handler's panic
+        manager.update(0.5, &mut scene, Some(&device), Some(&queue));
+        let error = manager.gpu_error().expect("validation error");
+        assert!(error.starts_with("Invalid Buffer: "), "{error}");
+
+        manager.reset_simulation(&mut scene);
+        assert!(manager.gpu_error().is_none());
+    }
+}
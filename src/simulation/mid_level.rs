@@ -19,7 +19,7 @@
 use crate::gfx::scene::Scene;
 use crate::simulation::traits::Simulation;
 use std::collections::HashMap;
-use std::time::Instant;
+use crate::platform::Instant;
 use wgpu::util::DeviceExt;
 use wgpu::{Device, Queue};
 
@@ -210,8 +210,8 @@ impl<T: Simulation + 'static> Simulation for ManagedSimulation<T> {
         self.update_timing(elapsed);
 
         if self.debug_mode && elapsed > 0.016 {
-            println!(
-                "Warning: Simulation update took {:.3}ms (>16ms)",
+            tracing::warn!(
+                "Simulation update took {:.3}ms (>16ms)",
                 elapsed * 1000.0
             );
         }
@@ -248,6 +248,10 @@ impl<T: Simulation + 'static> Simulation for ManagedSimulation<T> {
         self.simulation.name()
     }
 
+    fn handle_command(&mut self, args: &[&str], scene: &mut Scene) -> Option<Result<String, String>> {
+        self.simulation.handle_command(args, scene)
+    }
+
     fn is_running(&self) -> bool {
         self.simulation.is_running()
     }
@@ -280,6 +284,16 @@ impl<T: Simulation + 'static> Simulation for ManagedSimulation<T> {
         self.simulation.apply_gpu_results_to_scene(device, scene);
     }
 
+    fn sync_to_display(
+        &mut self,
+        compute: &crate::simulation::gpu::ComputeDevice,
+        display_device: &Device,
+        display_queue: &Queue,
+    ) {
+        self.simulation
+            .sync_to_display(compute, display_device, display_queue);
+    }
+
     fn is_gpu_ready(&self) -> bool {
         self.simulation.is_gpu_ready()
     }
@@ -497,7 +511,7 @@ impl SimulationProfiler {
 #[macro_export]
 macro_rules! time_section {
     ($profiler:expr, $name:expr, $code:block) => {{
-        let start = std::time::Instant::now();
+        let start = Instant::now();
         let result = $code;
         let elapsed = start.elapsed().as_secs_f32();
         $profiler.record($name, elapsed);
@@ -16,10 +16,27 @@
 //! ## Key Components
 //!
 //! - [`traits::Simulation`] - Core simulation trait that all simulations must implement
+//! - [`callback::CallbackSimulation`] - Simulation driven by a step closure
 //! - [`manager::SimulationManager`] - Manages simulation lifecycle and execution
+//! - [`params`] - Declarative parameters with auto-generated UI (`#[derive(SimParams)]`)
+//! - [`boundary`] - Grid boundary conditions shared between masks and WGSL
+//! - [`forces`] - Drag and lift on obstacles from momentum exchange in LBM flows
+//! - [`stability`] - Divergence checks with automatic pause and rollback
+//! - [`step_policy`] - Steps per frame, per second or per time budget, independent of the frame rate
+//! - [`paint`] - Brush strokes from the 3D view painted into simulation fields on the GPU
+//! - [`tracer`] - Passive dye advected through a velocity buffer, to show flow structure
+//! - [`progress`] - Progress and time remaining of finite runs, in the UI and on the console
+//! - [`sweep`] - Headless parameter sweeps with per-run metrics and CSV output
+//! - [`ensemble`] - Replicas of a stochastic model with mean and variance fields
+//! - [`checkpoint`] - Field checkpoint files with half precision and run-length encoding
+//! - [`history`] - Ring of recent field snapshots with a timeline scrubber
 //! - [`cpu`] - CPU-based simulation utilities and examples
+//! - [`integrators`] - Fixed-step integrators generic over `f32`/`f64` state
 //! - [`gpu`] - GPU compute shader simulation utilities and examples
 //! - [`examples`] - Ready-to-use simulation examples for both CPU and GPU
+//! - [`templates`] - Complete GPU solvers for common models, such as heat diffusion
+//! - [`pbd`] - Position-based dynamics with CPU and GPU solvers
+//! - [`agents`] - Agent-based simulation with perception queries and steering behaviors
 //!
 //! ## Usage
 //!
@@ -53,11 +70,28 @@
 //!
 //! [`HaggisApp`]: crate::app::HaggisApp
 
+pub mod agents;
 pub mod base_simulation;
+pub mod boundary;
+pub mod callback;
+pub mod checkpoint;
 pub mod cpu;
+pub mod ensemble;
 pub mod examples;
+pub mod forces;
 pub mod gpu;
+pub mod history;
+pub mod integrators;
 pub mod manager;
+pub mod paint;
+pub mod params;
+pub mod pbd;
+pub mod progress;
+pub mod stability;
+pub mod step_policy;
+pub mod sweep;
+pub mod templates;
+pub mod tracer;
 pub mod traits;
 
 // New API layers
@@ -0,0 +1,567 @@
+//! Interactive painting into simulation fields
+//!
+//! In paint mode (see
+//! [`HaggisApp::set_paint_mode`](crate::app::HaggisApp::set_paint_mode)) the
+//! app turns mouse presses and drags in the 3D view into [`BrushStroke`]
+//! events instead of picking objects. A [`FieldBrush`] maps each stroke onto
+//! a simulation grid, either on a cut plane through the grid or where the
+//! mouse ray enters the grid box, and spaces stamps along the drag. A
+//! [`FieldPainter`] then writes the [`Brush`] value into the field's storage
+//! buffer with a small compute pass, so a running GPU simulation can be
+//! edited without a round trip through the CPU: seeding live cells in a
+//! cellular automaton, or injecting dye into a flow.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::events::BrushStroke;
+//! use haggis::simulation::paint::{Brush, BrushSurface, FieldBrush, FieldPainter};
+//! use haggis::visualization::GridTransform;
+//!
+//! # fn example(
+//! #     device: &wgpu::Device,
+//! #     queue: &wgpu::Queue,
+//! #     cells: &wgpu::Buffer,
+//! #     strokes: &haggis::events::EventReceiver<BrushStroke>,
+//! # ) {
+//! let grid = GridTransform::centered([256, 256, 1], 2.0);
+//! let mut brush = FieldBrush::new(grid, BrushSurface::Plane { axis: 2, normalized: 0.0 });
+//! let painter = FieldPainter::<u32>::new(device, grid.dims(), 1);
+//!
+//! for stroke in strokes.drain() {
+//!     let stamps = brush.stamps(&stroke);
+//!     let paint = if stroke.erase { brush.brush.eraser() } else { brush.brush };
+//!     painter.paint(device, queue, cells, &paint, &stamps);
+//! }
+//! # }
+//! ```
+
+use std::marker::PhantomData;
+
+use cgmath::{InnerSpace, Vector3};
+use wgpu::{Buffer, CommandEncoder, Device, Queue};
+
+use crate::events::BrushStroke;
+use crate::gfx::picking::{Ray, AABB};
+use crate::visualization::GridTransform;
+use crate::wgpu_utils::compute_primitives::GpuScalar;
+
+/// Most stamps one paint pass applies; longer drags space stamps further apart
+pub const MAX_STAMPS: usize = 64;
+
+/// Threads per workgroup along each axis
+const WORKGROUP_SIZE: u32 = 4;
+
+/// How a brush combines its value with the field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BrushMode {
+    /// Replace the field value, blended by the brush weight
+    #[default]
+    Set,
+    /// Add the brush value, scaled by the brush weight
+    Add,
+}
+
+/// Shape and value painted into a field
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Brush {
+    /// Radius in grid cells
+    pub radius: f32,
+    /// Value per channel; channels beyond the field's are ignored
+    pub value: [f32; 4],
+    pub mode: BrushMode,
+    /// Fade the weight smoothly to zero at the radius instead of a hard edge
+    ///
+    /// Integer fields are truncated, so they usually want hard brushes.
+    pub soft: bool,
+}
+
+impl Default for Brush {
+    fn default() -> Self {
+        Self {
+            radius: 3.0,
+            value: [1.0, 0.0, 0.0, 0.0],
+            mode: BrushMode::Set,
+            soft: false,
+        }
+    }
+}
+
+impl Brush {
+    /// Hard brush setting the first channel to 1
+    pub fn new(radius: f32) -> Self {
+        Self {
+            radius,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_value(mut self, value: [f32; 4]) -> Self {
+        self.value = value;
+        self
+    }
+
+    pub fn with_mode(mut self, mode: BrushMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn with_soft_edge(mut self, soft: bool) -> Self {
+        self.soft = soft;
+        self
+    }
+
+    /// The same brush setting the field to zero, e.g. for erase strokes
+    pub fn eraser(&self) -> Self {
+        Self {
+            value: [0.0; 4],
+            mode: BrushMode::Set,
+            ..*self
+        }
+    }
+
+    /// Draw controls for the radius, mode and the first `channels` values
+    pub fn render_ui(&mut self, ui: &imgui::Ui, channels: usize) {
+        ui.slider("Brush Radius", 0.5, 32.0, &mut self.radius);
+        let mut add = self.mode == BrushMode::Add;
+        if ui.checkbox("Add", &mut add) {
+            self.mode = if add { BrushMode::Add } else { BrushMode::Set };
+        }
+        ui.same_line();
+        ui.checkbox("Soft Edge", &mut self.soft);
+        for (channel, value) in self.value.iter_mut().take(channels.min(4)).enumerate() {
+            let label = if channels == 1 {
+                "Value".to_string()
+            } else {
+                format!("Value {channel}")
+            };
+            ui.input_float(label, value).build();
+        }
+    }
+}
+
+/// Where strokes meet the grid
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BrushSurface {
+    /// The grid layer nearest to `normalized` (0 to 1) along `axis`, such as
+    /// the layer a cut plane shows
+    Plane { axis: usize, normalized: f32 },
+    /// The point where the mouse ray enters the grid box
+    Volume,
+}
+
+/// Turns brush strokes into stamp positions on a grid
+#[derive(Debug, Clone)]
+pub struct FieldBrush {
+    pub grid: GridTransform,
+    pub surface: BrushSurface,
+    pub brush: Brush,
+    /// Grid position of the last stamp of the current stroke
+    last: Option<Vector3<f32>>,
+}
+
+impl FieldBrush {
+    /// Brush painting on `surface` of `grid`, with the default [`Brush`]
+    pub fn new(grid: GridTransform, surface: BrushSurface) -> Self {
+        Self {
+            grid,
+            surface,
+            brush: Brush::default(),
+            last: None,
+        }
+    }
+
+    pub fn with_brush(mut self, brush: Brush) -> Self {
+        self.brush = brush;
+        self
+    }
+
+    /// Continuous grid coordinates where `ray` meets the surface
+    ///
+    /// # Returns
+    ///
+    /// `None` if the ray misses the grid
+    pub fn hit(&self, ray: &Ray) -> Option<Vector3<f32>> {
+        let dims = self.grid.dims();
+        let inside = |grid: Vector3<f32>, skip: Option<usize>| {
+            (0..3).all(|axis| {
+                Some(axis) == skip || (-0.5..dims[axis] as f32 - 0.5).contains(&grid[axis])
+            })
+        };
+        match self.surface {
+            BrushSurface::Plane { axis, normalized } => {
+                let axis = axis.min(2);
+                let layer = self.grid.layer_position(axis, normalized);
+                if ray.direction[axis].abs() < f32::EPSILON {
+                    return None;
+                }
+                let t = (layer - ray.origin[axis]) / ray.direction[axis];
+                if t < 0.0 {
+                    return None;
+                }
+                let mut grid = self.grid.world_to_grid(ray.point_at(t));
+                grid[axis] = self.grid.layer(axis, normalized) as f32;
+                inside(grid, Some(axis)).then_some(grid)
+            }
+            BrushSurface::Volume => {
+                let (a, b) = (self.grid.min(), self.grid.max());
+                let bounds = AABB::new(
+                    Vector3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+                    Vector3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+                );
+                let t = bounds.intersect_ray(ray)?;
+                let grid = self.grid.world_to_grid(ray.point_at(t));
+                let last = dims.map(|n| (n - 1) as f32);
+                Some(Vector3::new(
+                    grid.x.clamp(0.0, last[0]),
+                    grid.y.clamp(0.0, last[1]),
+                    grid.z.clamp(0.0, last[2]),
+                ))
+            }
+        }
+    }
+
+    /// Grid positions to stamp for `stroke`
+    ///
+    /// Drags are filled in with stamps half a radius apart, at most
+    /// [`MAX_STAMPS`], so fast mouse moves leave a continuous line.
+    pub fn stamps(&mut self, stroke: &BrushStroke) -> Vec<Vector3<f32>> {
+        if stroke.begin {
+            self.last = None;
+        }
+        let Some(hit) = self.hit(&stroke.ray) else {
+            self.last = None;
+            return Vec::new();
+        };
+        let from = self.last.replace(hit).unwrap_or(hit);
+        let spacing = (self.brush.radius * 0.5).max(0.5);
+        let segments = ((hit - from).magnitude() / spacing).ceil() as usize;
+        let segments = segments.clamp(1, MAX_STAMPS - 1);
+        if from == hit {
+            return vec![hit];
+        }
+        (1..=segments)
+            .map(|step| from + (hit - from) * (step as f32 / segments as f32))
+            .collect()
+    }
+}
+
+/// Uniforms of the paint shader
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct PaintUniforms {
+    /// Grid size, then channels per cell
+    dims: [u32; 4],
+    /// First cell of the dispatch, then the stamp count
+    origin: [u32; 4],
+    /// Cells in the dispatch, then the brush mode
+    extent: [u32; 4],
+    value: [f32; 4],
+    /// Radius, then whether the edge is soft
+    shape: [f32; 4],
+}
+
+/// Writes brush stamps into a storage buffer of `T` on the GPU
+///
+/// The field holds `channels` interleaved values per cell, cells in
+/// x-fastest, then y, then z order, like [`GridTransform::linear_index`].
+pub struct FieldPainter<T: GpuScalar> {
+    dims: [u32; 3],
+    channels: u32,
+    pipeline: wgpu::ComputePipeline,
+    layout: wgpu::BindGroupLayout,
+    uniforms: Buffer,
+    stamps: Buffer,
+    _scalar: PhantomData<T>,
+}
+
+impl<T: GpuScalar> FieldPainter<T> {
+    /// Painter for fields of `dims` cells with `channels` values each
+    pub fn new(device: &Device, dims: [u32; 3], channels: u32) -> Self {
+        let entry = |binding, ty| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Field Paint Layout"),
+            entries: &[
+                entry(0, wgpu::BufferBindingType::Uniform),
+                entry(1, wgpu::BufferBindingType::Storage { read_only: true }),
+                entry(2, wgpu::BufferBindingType::Storage { read_only: false }),
+            ],
+        });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Field Paint Shader"),
+            source: wgpu::ShaderSource::Wgsl(PAINT_SHADER.replace("SCALAR", T::WGSL_TYPE).into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Field Paint Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Field Paint Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: Some("paint"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        let uniforms = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Field Paint Uniforms"),
+            size: std::mem::size_of::<PaintUniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let stamps = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Field Paint Stamps"),
+            size: (MAX_STAMPS * std::mem::size_of::<[f32; 4]>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        Self {
+            dims: dims.map(|n| n.max(1)),
+            channels: channels.max(1),
+            pipeline,
+            layout,
+            uniforms,
+            stamps,
+            _scalar: PhantomData,
+        }
+    }
+
+    /// Record a pass painting `brush` at grid positions `stamps` into `field`
+    ///
+    /// `field` needs `STORAGE` usage. Only the first [`MAX_STAMPS`] stamps
+    /// are painted, and the uniforms are written through the queue, so use
+    /// one call per submission.
+    pub fn encode(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        encoder: &mut CommandEncoder,
+        field: &Buffer,
+        brush: &Brush,
+        stamps: &[Vector3<f32>],
+    ) {
+        let stamps = &stamps[..stamps.len().min(MAX_STAMPS)];
+        let Some((origin, extent)) = self.bounds(brush.radius, stamps) else {
+            return;
+        };
+        let uniforms = PaintUniforms {
+            dims: [self.dims[0], self.dims[1], self.dims[2], self.channels],
+            origin: [origin[0], origin[1], origin[2], stamps.len() as u32],
+            extent: [extent[0], extent[1], extent[2], brush.mode as u32],
+            value: brush.value,
+            shape: [brush.radius.max(0.5), brush.soft as u32 as f32, 0.0, 0.0],
+        };
+        let positions: Vec<[f32; 4]> = stamps.iter().map(|p| [p.x, p.y, p.z, 0.0]).collect();
+        queue.write_buffer(&self.uniforms, 0, bytemuck::bytes_of(&uniforms));
+        queue.write_buffer(&self.stamps, 0, bytemuck::cast_slice(&positions));
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Field Paint Bind Group"),
+            layout: &self.layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.uniforms.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: self.stamps.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: field.as_entire_binding(),
+                },
+            ],
+        });
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Field Paint"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        let [x, y, z] = extent.map(|n| n.div_ceil(WORKGROUP_SIZE));
+        pass.dispatch_workgroups(x, y, z);
+    }
+
+    /// Paint `brush` at `stamps` into `field` and submit right away
+    pub fn paint(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        field: &Buffer,
+        brush: &Brush,
+        stamps: &[Vector3<f32>],
+    ) {
+        if stamps.is_empty() {
+            return;
+        }
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Field Paint Encoder"),
+        });
+        self.encode(device, queue, &mut encoder, field, brush, stamps);
+        queue.submit(std::iter::once(encoder.finish()));
+    }
+
+    /// First cell and size of the box of cells the stamps can reach
+    fn bounds(&self, radius: f32, stamps: &[Vector3<f32>]) -> Option<([u32; 3], [u32; 3])> {
+        let radius = radius.max(0.5);
+        let mut origin = [0; 3];
+        let mut extent = [0; 3];
+        for axis in 0..3 {
+            let low = stamps.iter().map(|p| p[axis]).fold(f32::INFINITY, f32::min) - radius;
+            let high = stamps
+                .iter()
+                .map(|p| p[axis])
+                .fold(f32::NEG_INFINITY, f32::max)
+                + radius;
+            let last = self.dims[axis] as f32 - 1.0;
+            if high < 0.0 || low > last {
+                return None;
+            }
+            let (low, high) = (low.ceil().max(0.0), high.floor().min(last));
+            if low > high {
+                return None;
+            }
+            origin[axis] = low as u32;
+            extent[axis] = (high - low) as u32 + 1;
+        }
+        Some((origin, extent))
+    }
+}
+
+const PAINT_SHADER: &str = r#"
+struct PaintUniforms {
+    dims: vec4<u32>,
+    origin: vec4<u32>,
+    extent: vec4<u32>,
+    value: vec4<f32>,
+    shape: vec4<f32>,
+}
+
+@group(0) @binding(0) var<uniform> params: PaintUniforms;
+@group(0) @binding(1) var<storage, read> stamps: array<vec4<f32>>;
+@group(0) @binding(2) var<storage, read_write> field: array<SCALAR>;
+
+@compute @workgroup_size(4, 4, 4)
+fn paint(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id >= params.extent.xyz)) {
+        return;
+    }
+    let cell = params.origin.xyz + id;
+    let position = vec3<f32>(cell);
+    let radius = params.shape.x;
+
+    // Overlapping stamps of one pass paint a cell once, with the nearest
+    var weight = 0.0;
+    for (var i = 0u; i < params.origin.w; i++) {
+        let d = distance(position, stamps[i].xyz);
+        if (d <= radius) {
+            var w = 1.0;
+            if (params.shape.y > 0.5) {
+                w = 1.0 - smoothstep(0.0, radius, d);
+            }
+            weight = max(weight, w);
+        }
+    }
+    if (weight <= 0.0) {
+        return;
+    }
+
+    let channels = params.dims.w;
+    let base = ((cell.z * params.dims.y + cell.y) * params.dims.x + cell.x) * channels;
+    for (var c = 0u; c < min(channels, 4u); c++) {
+        let old = f32(field[base + c]);
+        var painted: f32;
+        if (params.extent.w == 0u) {
+            painted = mix(old, params.value[c], weight);
+        } else {
+            painted = old + params.value[c] * weight;
+        }
+        field[base + c] = SCALAR(painted);
+    }
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wgpu_utils::compute_primitives::{read_buffer, test_buffer, test_device};
+
+    fn stroke(origin: [f32; 3], direction: [f32; 3], begin: bool) -> BrushStroke {
+        BrushStroke {
+            ray: Ray::new(origin.into(), direction.into()),
+            begin,
+            erase: false,
+        }
+    }
+
+    #[test]
+    fn test_strokes_map_to_grid() {
+        let grid = GridTransform::new([11, 11, 1], [0.0, 0.0, 0.0], [10.0, 10.0, 0.0]);
+        let surface = BrushSurface::Plane {
+            axis: 2,
+            normalized: 0.0,
+        };
+        let mut brush = FieldBrush::new(grid, surface).with_brush(Brush::new(2.0));
+
+        let stamps = brush.stamps(&stroke([3.0, 4.0, 5.0], [0.0, 0.0, -1.0], true));
+        assert_eq!(stamps, vec![Vector3::new(3.0, 4.0, 0.0)]);
+        // A drag of 4 cells is filled in every radius / 2
+        let stamps = brush.stamps(&stroke([7.0, 4.0, 5.0], [0.0, 0.0, -1.0], false));
+        assert_eq!(stamps.len(), 4);
+        assert_eq!(stamps[3], Vector3::new(7.0, 4.0, 0.0));
+        // Misses and rays pointing away paint nothing
+        assert!(brush
+            .stamps(&stroke([30.0, 4.0, 5.0], [0.0, 0.0, -1.0], false))
+            .is_empty());
+        assert!(brush
+            .stamps(&stroke([3.0, 4.0, 5.0], [0.0, 0.0, 1.0], true))
+            .is_empty());
+
+        let volume = GridTransform::centered([5, 5, 5], 1.0);
+        let brush = FieldBrush::new(volume, BrushSurface::Volume);
+        let hit = brush.hit(&Ray::new([0.0, 0.0, 5.0].into(), [0.0, 0.0, -1.0].into()));
+        assert_eq!(hit, Some(Vector3::new(2.0, 2.0, 4.0)));
+    }
+
+    #[test]
+    fn test_painter_writes_cells_in_radius() {
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let field = test_buffer(&device, &[0u32; 64]);
+        let painter = FieldPainter::<u32>::new(&device, [8, 8, 1], 1);
+        let brush = Brush::new(1.0);
+        painter.paint(
+            &device,
+            &queue,
+            &field,
+            &brush,
+            &[Vector3::new(2.0, 2.0, 0.0)],
+        );
+
+        let cells: Vec<u32> = read_buffer(&device, &queue, &field, 64).unwrap();
+        let live: Vec<usize> = (0..64).filter(|&i| cells[i] == 1).collect();
+        assert_eq!(live, vec![10, 17, 18, 19, 26]);
+
+        painter.paint(
+            &device,
+            &queue,
+            &field,
+            &brush.eraser(),
+            &[Vector3::new(2.0, 2.0, 0.0)],
+        );
+        let cells: Vec<u32> = read_buffer(&device, &queue, &field, 64).unwrap();
+        assert!(cells.iter().all(|&cell| cell == 0));
+    }
+}
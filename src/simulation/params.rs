@@ -0,0 +1,415 @@
+//! # Declarative Simulation Parameters
+//!
+//! Describe simulation parameters once, on a plain struct, and get ImGui
+//! controls, text serialization, change notifications and GPU uniform upload
+//! without hand-written slider code.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::simulation::params::{ParamsUniform, SimParams};
+//!
+//! #[repr(C)]
+//! #[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, SimParams)]
+//! struct FluidParams {
+//!     #[param(range = 0.0..=0.1, step = 0.001)]
+//!     viscosity: f32,
+//!     #[param(range = 1..=64, label = "Solver Iterations")]
+//!     iterations: u32,
+//!     #[param(skip)]
+//!     frame: u32,
+//!     gravity: f32,
+//! }
+//!
+//! # fn example(params: &mut FluidParams, ui: &imgui::Ui, scene: &mut haggis::gfx::scene::Scene,
+//! #            uniform: &mut ParamsUniform<FluidParams>, queue: &wgpu::Queue) {
+//! // In `render_ui`: one line instead of a slider per field
+//! let changed = params.build_ui(ui);
+//! params.emit_changes(&changed, &mut scene.events);
+//!
+//! // In `update`: re-uploads only when a value actually changed
+//! uniform.update(queue, params);
+//! # }
+//! ```
+//!
+//! Parameters can be saved and restored as `name = value` lines with
+//! [`SimParams::to_text`] and [`SimParams::apply_text`].
+
+use std::marker::PhantomData;
+
+use crate::events::{EventBus, ParameterChanged};
+
+pub use haggis_derive::SimParams;
+
+/// Static description of one parameter, generated by `#[derive(SimParams)]`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParamDescriptor {
+    /// Field name, used for serialization and change events
+    pub name: &'static str,
+    /// Label shown in the UI
+    pub label: &'static str,
+    /// Lower bound, if the parameter has a range
+    pub min: Option<f64>,
+    /// Upper bound, if the parameter has a range
+    pub max: Option<f64>,
+    /// Drag speed / input step
+    pub step: Option<f64>,
+}
+
+impl ParamDescriptor {
+    /// Clamp a value into this parameter's range
+    pub fn clamp(&self, value: f64) -> f64 {
+        let value = self.min.map_or(value, |min| value.max(min));
+        self.max.map_or(value, |max| value.min(max))
+    }
+}
+
+/// Type-erased parameter value
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParamValue {
+    Float(f64),
+    Int(i64),
+    Bool(bool),
+}
+
+impl ParamValue {
+    /// Numeric value as `f64` (`true` is 1.0)
+    pub fn as_f64(&self) -> f64 {
+        match *self {
+            ParamValue::Float(value) => value,
+            ParamValue::Int(value) => value as f64,
+            ParamValue::Bool(value) => value as i32 as f64,
+        }
+    }
+
+    /// Parse a value of the same kind as `self` from text
+    fn parse_like(&self, text: &str) -> Option<ParamValue> {
+        match self {
+            ParamValue::Float(_) => text.parse().ok().map(ParamValue::Float),
+            ParamValue::Int(_) => text.parse().ok().map(ParamValue::Int),
+            ParamValue::Bool(_) => text.parse().ok().map(ParamValue::Bool),
+        }
+    }
+}
+
+impl std::fmt::Display for ParamValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParamValue::Float(value) => write!(f, "{}", value),
+            ParamValue::Int(value) => write!(f, "{}", value),
+            ParamValue::Bool(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// Field types that can be used as parameters
+pub trait ParamField {
+    /// Current value
+    fn to_param_value(&self) -> ParamValue;
+    /// Set from a value, returning whether the field changed
+    fn set_param_value(&mut self, value: ParamValue) -> bool;
+}
+
+macro_rules! impl_param_field {
+    ($variant:ident, $repr:ty, $($ty:ty),*) => {
+        $(
+            impl ParamField for $ty {
+                fn to_param_value(&self) -> ParamValue {
+                    ParamValue::$variant(*self as $repr)
+                }
+
+                fn set_param_value(&mut self, value: ParamValue) -> bool {
+                    let new_value = value.as_f64() as $ty;
+                    let changed = *self != new_value;
+                    *self = new_value;
+                    changed
+                }
+            }
+        )*
+    };
+}
+
+impl_param_field!(Float, f64, f32, f64);
+impl_param_field!(Int, i64, i32, u32, usize);
+
+impl ParamField for bool {
+    fn to_param_value(&self) -> ParamValue {
+        ParamValue::Bool(*self)
+    }
+
+    fn set_param_value(&mut self, value: ParamValue) -> bool {
+        let new_value = value.as_f64() != 0.0;
+        let changed = *self != new_value;
+        *self = new_value;
+        changed
+    }
+}
+
+/// A set of simulation parameters with auto-generated UI.
+///
+/// Implement with `#[derive(SimParams)]`; only the three required methods are
+/// generated, everything else is provided.
+pub trait SimParams {
+    /// Descriptions of all parameters, in declaration order
+    fn descriptors(&self) -> &'static [ParamDescriptor];
+
+    /// Value of the parameter at `index`
+    fn value(&self, index: usize) -> Option<ParamValue>;
+
+    /// Set the parameter at `index`, returning whether it changed
+    fn set_value(&mut self, index: usize, value: ParamValue) -> bool;
+
+    /// Look up a parameter value by field name
+    fn get(&self, name: &str) -> Option<ParamValue> {
+        let index = self.descriptors().iter().position(|d| d.name == name)?;
+        self.value(index)
+    }
+
+    /// Set a parameter by field name, clamped to its range.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the parameter exists and its value changed
+    fn set(&mut self, name: &str, value: ParamValue) -> bool {
+        let Some(index) = self.descriptors().iter().position(|d| d.name == name) else {
+            return false;
+        };
+        let descriptor = self.descriptors()[index];
+        let value = match value {
+            ParamValue::Bool(_) => value,
+            ParamValue::Float(v) => ParamValue::Float(descriptor.clamp(v)),
+            ParamValue::Int(v) => ParamValue::Int(descriptor.clamp(v as f64) as i64),
+        };
+        self.set_value(index, value)
+    }
+
+    /// Draw an ImGui control for every parameter.
+    ///
+    /// Ranged parameters get sliders, unbounded ones get drag fields and
+    /// booleans get checkboxes.
+    ///
+    /// # Returns
+    ///
+    /// Names of the parameters changed this frame
+    fn build_ui(&mut self, ui: &imgui::Ui) -> Vec<&'static str> {
+        let mut changed = Vec::new();
+
+        for (index, descriptor) in self.descriptors().iter().enumerate() {
+            let Some(value) = self.value(index) else {
+                continue;
+            };
+
+            let new_value = match value {
+                ParamValue::Float(v) => {
+                    let mut v = v as f32;
+                    let edited = match (descriptor.min, descriptor.max) {
+                        (Some(min), Some(max)) => {
+                            ui.slider(descriptor.label, min as f32, max as f32, &mut v)
+                        }
+                        _ => imgui::Drag::new(descriptor.label)
+                            .speed(descriptor.step.unwrap_or(0.01) as f32)
+                            .build(ui, &mut v),
+                    };
+                    edited.then_some(ParamValue::Float(descriptor.clamp(v as f64)))
+                }
+                ParamValue::Int(v) => {
+                    let mut v = v as i32;
+                    let edited = match (descriptor.min, descriptor.max) {
+                        (Some(min), Some(max)) => {
+                            ui.slider(descriptor.label, min as i32, max as i32, &mut v)
+                        }
+                        _ => imgui::Drag::new(descriptor.label)
+                            .speed(descriptor.step.unwrap_or(1.0) as f32)
+                            .build(ui, &mut v),
+                    };
+                    edited.then_some(ParamValue::Int(descriptor.clamp(v as f64) as i64))
+                }
+                ParamValue::Bool(mut v) => ui
+                    .checkbox(descriptor.label, &mut v)
+                    .then_some(ParamValue::Bool(v)),
+            };
+
+            if let Some(new_value) = new_value {
+                if self.set_value(index, new_value) {
+                    changed.push(descriptor.name);
+                }
+            }
+        }
+
+        changed
+    }
+
+    /// Emit a [`ParameterChanged`] event for each changed parameter
+    fn emit_changes(&self, changed: &[&str], events: &mut EventBus) {
+        for name in changed {
+            if let Some(value) = self.get(name) {
+                events.emit(ParameterChanged::new(name, value.as_f64() as f32));
+            }
+        }
+    }
+
+    /// Serialize all parameters as `name = value` lines
+    fn to_text(&self) -> String {
+        let mut text = String::new();
+        for (index, descriptor) in self.descriptors().iter().enumerate() {
+            if let Some(value) = self.value(index) {
+                text.push_str(&format!("{} = {}\n", descriptor.name, value));
+            }
+        }
+        text
+    }
+
+    /// Restore parameters from `name = value` lines.
+    ///
+    /// Unknown names are ignored so older files keep loading; blank lines and
+    /// lines starting with `#` are skipped.
+    ///
+    /// # Returns
+    ///
+    /// Names of the parameters that changed, or an error for malformed lines
+    fn apply_text(&mut self, text: &str) -> Result<Vec<&'static str>, String> {
+        let mut changed = Vec::new();
+
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let Some((name, value)) = line.split_once('=') else {
+                return Err(format!("line {}: expected `name = value`", line_number + 1));
+            };
+            let (name, value) = (name.trim(), value.trim());
+
+            let Some(index) = self.descriptors().iter().position(|d| d.name == name) else {
+                continue;
+            };
+            let descriptor = self.descriptors()[index];
+            let parsed = self
+                .value(index)
+                .and_then(|current| current.parse_like(value))
+                .ok_or_else(|| {
+                    format!("line {}: invalid value '{}' for {}", line_number + 1, value, name)
+                })?;
+
+            if self.set(name, parsed) {
+                changed.push(descriptor.name);
+            }
+        }
+
+        Ok(changed)
+    }
+}
+
+/// Uniform buffer mirroring a `#[repr(C)]` parameter struct
+///
+/// [`update`](Self::update) compares against the last uploaded bytes, so it is
+/// cheap to call every frame and only writes to the GPU after a change.
+pub struct ParamsUniform<T: bytemuck::Pod> {
+    buffer: wgpu::Buffer,
+    uploaded: Vec<u8>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: bytemuck::Pod> ParamsUniform<T> {
+    /// Create the uniform buffer initialized with `params`
+    pub fn new(device: &wgpu::Device, label: &str, params: &T) -> Self {
+        use wgpu::util::DeviceExt;
+
+        let bytes = bytemuck::bytes_of(params);
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(label),
+            contents: bytes,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        Self {
+            buffer,
+            uploaded: bytes.to_vec(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Upload `params` if they differ from the last upload.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the buffer was written
+    pub fn update(&mut self, queue: &wgpu::Queue, params: &T) -> bool {
+        let bytes = bytemuck::bytes_of(params);
+        if bytes == self.uploaded.as_slice() {
+            return false;
+        }
+
+        queue.write_buffer(&self.buffer, 0, bytes);
+        self.uploaded.clear();
+        self.uploaded.extend_from_slice(bytes);
+        true
+    }
+
+    /// The underlying uniform buffer, for bind group creation
+    pub fn buffer(&self) -> &wgpu::Buffer {
+        &self.buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(SimParams)]
+    struct TestParams {
+        #[param(range = 0.0..=1.0, step = 0.1)]
+        damping: f32,
+        #[param(range = 1..=10, label = "Iterations")]
+        solver_iterations: u32,
+        enabled: bool,
+        #[param(skip)]
+        #[allow(dead_code)]
+        internal: String,
+    }
+
+    fn params() -> TestParams {
+        TestParams {
+            damping: 0.5,
+            solver_iterations: 4,
+            enabled: true,
+            internal: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_derive_generates_descriptors() {
+        let params = params();
+        let descriptors = params.descriptors();
+        assert_eq!(descriptors.len(), 3);
+        assert_eq!(descriptors[0].name, "damping");
+        assert_eq!(descriptors[0].label, "Damping");
+        assert_eq!(descriptors[0].max, Some(1.0));
+        assert_eq!(descriptors[1].label, "Iterations");
+        assert_eq!(params.get("solver_iterations"), Some(ParamValue::Int(4)));
+    }
+
+    #[test]
+    fn test_set_clamps_to_range() {
+        let mut params = params();
+        assert!(params.set("damping", ParamValue::Float(3.0)));
+        assert_eq!(params.damping, 1.0);
+        assert!(!params.set("missing", ParamValue::Float(3.0)));
+    }
+
+    #[test]
+    fn test_text_round_trip() {
+        let mut source = params();
+        source.damping = 0.25;
+        source.enabled = false;
+        let text = source.to_text();
+
+        let mut restored = params();
+        let changed = restored.apply_text(&text).unwrap();
+        assert_eq!(changed, vec!["damping", "enabled"]);
+        assert_eq!(restored.damping, 0.25);
+        assert!(!restored.enabled);
+        assert!(restored.apply_text("damping = fast").is_err());
+    }
+}
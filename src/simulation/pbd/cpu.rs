@@ -0,0 +1,270 @@
+//! CPU backend for position-based dynamics
+//!
+//! Projects constraints one after another (Gauss-Seidel), which converges
+//! quickly and supports every collider type, including SDF closures.
+
+use cgmath::{InnerSpace, Vector3};
+
+use super::{bending_offset, tetrahedron_volume, PbdConstraint, PbdSystem};
+use crate::events::CollisionEvent;
+
+/// Sequential XPBD solver
+#[derive(Debug, Default)]
+pub struct CpuPbdSolver {
+    previous: Vec<Vector3<f32>>,
+    /// Particles that touched a collider in the last substep
+    touching: Vec<bool>,
+    impacts: Vec<CollisionEvent>,
+}
+
+impl CpuPbdSolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Collisions during the last step faster than
+    /// [`PbdSettings::impact_speed`](super::PbdSettings::impact_speed)
+    ///
+    /// Simulations emit these on `scene.events` for collision sounds or
+    /// effects.
+    pub fn impacts(&self) -> &[CollisionEvent] {
+        &self.impacts
+    }
+
+    /// Advance `system` by `dt` seconds
+    pub fn step(&mut self, system: &mut PbdSystem, dt: f32) {
+        let substeps = system.settings.substeps.max(1);
+        let h = dt / substeps as f32;
+        let damping = system.settings.damping.powf(h);
+        self.impacts.clear();
+        self.touching.resize(system.positions.len(), false);
+
+        for _ in 0..substeps {
+            self.previous.clone_from(&system.positions);
+
+            for i in 0..system.positions.len() {
+                if system.inverse_masses[i] == 0.0 {
+                    continue;
+                }
+                system.velocities[i] += system.settings.gravity * h;
+                system.positions[i] += system.velocities[i] * h;
+            }
+
+            for constraint in &system.constraints {
+                project(constraint, &mut system.positions, &system.inverse_masses, h);
+            }
+
+            collide(system, &self.previous, h, &mut self.touching, &mut self.impacts);
+
+            for i in 0..system.positions.len() {
+                if system.inverse_masses[i] != 0.0 {
+                    system.velocities[i] = (system.positions[i] - self.previous[i]) / h * damping;
+                }
+            }
+        }
+    }
+}
+
+/// Apply one XPBD correction for `constraint`
+///
+/// Without accumulated multipliers (one iteration per substep), so the step
+/// is `-C / (Σ w |∇C|² + α / h²)`.
+fn project(constraint: &PbdConstraint, x: &mut [Vector3<f32>], w: &[f32], h: f32) {
+    match *constraint {
+        PbdConstraint::Distance {
+            particles: [a, b],
+            rest,
+            compliance,
+        } => {
+            let delta = x[a] - x[b];
+            let distance = delta.magnitude();
+            let weight = w[a] + w[b];
+            if distance < 1e-9 || weight == 0.0 {
+                return;
+            }
+            let n = delta / distance;
+            let lambda = -(distance - rest) / (weight + compliance / (h * h));
+            x[a] += n * (lambda * w[a]);
+            x[b] -= n * (lambda * w[b]);
+        }
+        PbdConstraint::Bending {
+            particles,
+            rest,
+            compliance,
+        } => {
+            let offset = bending_offset(&particles.map(|i| x[i]));
+            let length = offset.magnitude();
+            if length < 1e-9 {
+                return;
+            }
+            let n = offset / length;
+            let gradients = [-n / 3.0, -n / 3.0, n * (2.0 / 3.0)];
+            apply(&particles, &gradients, length - rest, compliance, x, w, h);
+        }
+        PbdConstraint::Volume {
+            particles,
+            rest,
+            compliance,
+        } => {
+            let p = particles.map(|i| x[i]);
+            let gradients = [
+                (p[3] - p[1]).cross(p[2] - p[1]) / 6.0,
+                (p[2] - p[0]).cross(p[3] - p[0]) / 6.0,
+                (p[3] - p[0]).cross(p[1] - p[0]) / 6.0,
+                (p[1] - p[0]).cross(p[2] - p[0]) / 6.0,
+            ];
+            apply(
+                &particles,
+                &gradients,
+                tetrahedron_volume(&p) - rest,
+                compliance,
+                x,
+                w,
+                h,
+            );
+        }
+    }
+}
+
+fn apply(
+    particles: &[usize],
+    gradients: &[Vector3<f32>],
+    c: f32,
+    compliance: f32,
+    x: &mut [Vector3<f32>],
+    w: &[f32],
+    h: f32,
+) {
+    let weight: f32 = particles
+        .iter()
+        .zip(gradients)
+        .map(|(&i, g)| w[i] * g.magnitude2())
+        .sum();
+    if weight < 1e-12 {
+        return;
+    }
+    let lambda = -c / (weight + compliance / (h * h));
+    for (&i, g) in particles.iter().zip(gradients) {
+        x[i] += g * (lambda * w[i]);
+    }
+}
+
+/// Push particles out of the colliders and apply friction, recording fast
+/// new contacts in `impacts`
+///
+/// Particles resting on a collider sink back into it every substep, so only
+/// particles that weren't `touching` one in the previous substep count as
+/// impacts.
+fn collide(
+    system: &mut PbdSystem,
+    previous: &[Vector3<f32>],
+    h: f32,
+    touching: &mut [bool],
+    impacts: &mut Vec<CollisionEvent>,
+) {
+    let margin = system.settings.collision_margin;
+    let friction = system.settings.friction;
+    let impact_speed = system.settings.impact_speed;
+    let particles = system
+        .positions
+        .iter_mut()
+        .zip(&system.inverse_masses)
+        .zip(previous)
+        .zip(touching);
+    for (((position, &inverse_mass), previous), touching) in particles {
+        let was_touching = std::mem::take(touching);
+        if inverse_mass == 0.0 {
+            continue;
+        }
+        for collider in &system.colliders {
+            let depth = collider.distance(*position) - margin;
+            if depth >= 0.0 {
+                continue;
+            }
+            let normal = collider.gradient(*position);
+            let speed = -(*position - previous).dot(normal) / h;
+            if !was_touching && speed > impact_speed {
+                impacts.push(CollisionEvent {
+                    position: *position,
+                    normal,
+                    speed,
+                });
+            }
+            *touching = true;
+            let resolved = *position - normal * depth;
+            // Remove part of the motion along the surface
+            let motion = resolved - previous;
+            let tangential = motion - normal * motion.dot(normal);
+            *position = resolved - tangential * friction;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::pbd::PbdCollider;
+
+    #[test]
+    fn test_rope_keeps_length_and_rests_on_ground() {
+        let mut rope = PbdSystem::rope([0.0, 0.0, 1.0], [1.0, 0.0, 1.0], 10, 1.0);
+        rope.pin(0);
+        rope.add_collider(PbdCollider::ground(0.0));
+
+        let mut solver = CpuPbdSolver::new();
+        for _ in 0..240 {
+            solver.step(&mut rope, 1.0 / 60.0);
+        }
+
+        // Pinned end stays, the rope hangs below it without stretching much
+        assert_eq!(rope.positions[0], Vector3::new(0.0, 0.0, 1.0));
+        for pair in rope.positions.windows(2) {
+            assert!(((pair[0] - pair[1]).magnitude() - 0.1).abs() < 0.01);
+        }
+        assert!(rope.positions.iter().all(|p| p.z >= 0.0));
+        assert!(rope.positions[10].z < 0.2);
+    }
+
+    #[test]
+    fn test_landing_is_reported_once() {
+        let mut system = PbdSystem::new();
+        system.add_particle([0.0, 0.0, 1.0], 1.0);
+        system.add_collider(PbdCollider::ground(0.0));
+
+        let mut solver = CpuPbdSolver::new();
+        let mut impacts = Vec::new();
+        for _ in 0..120 {
+            solver.step(&mut system, 1.0 / 60.0);
+            impacts.extend_from_slice(solver.impacts());
+        }
+
+        // Falling 1 m hits at about sqrt(2 g h); resting contacts are quiet
+        assert_eq!(impacts.len(), 1);
+        assert!((impacts[0].speed - (2.0f32 * 9.81).sqrt()).abs() < 0.3);
+        assert_eq!(impacts[0].normal, Vector3::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_volume_constraint_resists_collapse() {
+        let mut system = PbdSystem::new();
+        let corners = [
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 0.0, 1.0],
+        ];
+        for corner in corners {
+            system.add_particle(corner, 1.0);
+        }
+        system.add_volume([0, 1, 2, 3], 0.0);
+        system.add_collider(PbdCollider::ground(0.0));
+
+        let mut solver = CpuPbdSolver::new();
+        for _ in 0..120 {
+            solver.step(&mut system, 1.0 / 60.0);
+        }
+
+        let volume = tetrahedron_volume(&[0, 1, 2, 3].map(|i| system.positions[i]));
+        assert!((volume - 1.0 / 6.0).abs() < 0.01, "volume {}", volume);
+    }
+}
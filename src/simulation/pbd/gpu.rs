@@ -0,0 +1,671 @@
+//! GPU backend for position-based dynamics
+//!
+//! Constraints are split into colors on the CPU so that no two constraints of
+//! a color share a particle; each color is then projected in parallel without
+//! write conflicts, one dispatch per color. Plane, sphere and box colliders are
+//! supported; SDF closures only run on the CPU and are skipped here.
+
+use bytemuck::Zeroable;
+use cgmath::InnerSpace;
+use wgpu::util::DeviceExt;
+use wgpu::{Device, Queue};
+
+use super::{PbdCollider, PbdConstraint, PbdSystem};
+use crate::simulation::params::ParamsUniform;
+
+const WORKGROUP_SIZE: u32 = 64;
+
+/// Colliders passed to the shader; more are ignored
+const MAX_COLLIDERS: usize = 8;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct GpuConstraint {
+    /// Unused slots repeat the first particle
+    particles: [u32; 4],
+    /// 0 = distance, 1 = bending, 2 = volume
+    kind: u32,
+    rest: f32,
+    compliance: f32,
+    _padding: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct GpuCollider {
+    a: [f32; 4],
+    b: [f32; 4],
+    /// 0 = plane, 1 = sphere, 2 = box in `x`
+    kind: [u32; 4],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct GpuSettings {
+    /// Gravity, substep length in `w`
+    gravity: [f32; 4],
+    /// Particle count, collider count
+    counts: [u32; 4],
+    /// Damping per substep, collision margin, friction
+    coefficients: [f32; 4],
+    colliders: [GpuCollider; MAX_COLLIDERS],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct ColorRange {
+    offset: u32,
+    count: u32,
+    _padding: [u32; 2],
+}
+
+/// Compute-shader XPBD solver for a [`PbdSystem`]
+///
+/// Built from a system once; afterwards the particles live on the GPU. The
+/// settings and colliders are re-read from the system on every
+/// [`step`](Self::step), particles only on [`upload`](Self::upload).
+pub struct GpuPbdSolver {
+    predict_pipeline: wgpu::ComputePipeline,
+    solve_pipeline: wgpu::ComputePipeline,
+    finalize_pipeline: wgpu::ComputePipeline,
+    settings: ParamsUniform<GpuSettings>,
+    positions: wgpu::Buffer,
+    velocities: wgpu::Buffer,
+    staging: wgpu::Buffer,
+    particle_bind_group: wgpu::BindGroup,
+    /// One bind group and constraint count per color
+    colors: Vec<(wgpu::BindGroup, u32)>,
+    particle_count: u32,
+}
+
+/// Greedy coloring: each constraint gets the lowest color none of its
+/// particles is already used in
+///
+/// # Returns
+///
+/// Constraint indices grouped by color
+pub(crate) fn color_constraints(
+    constraints: &[PbdConstraint],
+    particle_count: usize,
+) -> Vec<Vec<usize>> {
+    let mut particle_colors: Vec<Vec<usize>> = vec![Vec::new(); particle_count];
+    let mut colors: Vec<Vec<usize>> = Vec::new();
+
+    for (index, constraint) in constraints.iter().enumerate() {
+        let particles = constraint.particles();
+        let color = (0..)
+            .find(|color| {
+                particles
+                    .iter()
+                    .all(|&p| !particle_colors[p].contains(color))
+            })
+            .unwrap_or_default();
+        for &p in particles {
+            particle_colors[p].push(color);
+        }
+        if color == colors.len() {
+            colors.push(Vec::new());
+        }
+        colors[color].push(index);
+    }
+    colors
+}
+
+fn gpu_constraint(constraint: &PbdConstraint) -> GpuConstraint {
+    let (kind, rest, compliance) = match *constraint {
+        PbdConstraint::Distance {
+            rest, compliance, ..
+        } => (0, rest, compliance),
+        PbdConstraint::Bending {
+            rest, compliance, ..
+        } => (1, rest, compliance),
+        PbdConstraint::Volume {
+            rest, compliance, ..
+        } => (2, rest, compliance),
+    };
+    let indices = constraint.particles();
+    let mut particles = [indices[0] as u32; 4];
+    for (slot, &index) in particles.iter_mut().zip(indices) {
+        *slot = index as u32;
+    }
+    GpuConstraint {
+        particles,
+        kind,
+        rest,
+        compliance,
+        _padding: 0,
+    }
+}
+
+fn gpu_settings(system: &PbdSystem, dt: f32) -> GpuSettings {
+    let settings = &system.settings;
+    let substeps = settings.substeps.max(1);
+    let h = dt / substeps as f32;
+
+    let mut colliders = [GpuCollider {
+        a: [0.0; 4],
+        b: [0.0; 4],
+        kind: [0; 4],
+    }; MAX_COLLIDERS];
+    let mut count = 0;
+    for collider in &system.colliders {
+        let gpu = match collider {
+            PbdCollider::Plane { normal, offset } => {
+                let n = normal.normalize();
+                GpuCollider {
+                    a: [n.x, n.y, n.z, *offset],
+                    b: [0.0; 4],
+                    kind: [0; 4],
+                }
+            }
+            PbdCollider::Sphere { center, radius } => GpuCollider {
+                a: [center.x, center.y, center.z, *radius],
+                b: [0.0; 4],
+                kind: [1, 0, 0, 0],
+            },
+            PbdCollider::Box(aabb) => GpuCollider {
+                a: [aabb.min.x, aabb.min.y, aabb.min.z, 0.0],
+                b: [aabb.max.x, aabb.max.y, aabb.max.z, 0.0],
+                kind: [2, 0, 0, 0],
+            },
+            PbdCollider::Sdf(_) => continue,
+        };
+        if count == MAX_COLLIDERS {
+            break;
+        }
+        colliders[count] = gpu;
+        count += 1;
+    }
+
+    GpuSettings {
+        gravity: [
+            settings.gravity.x,
+            settings.gravity.y,
+            settings.gravity.z,
+            h,
+        ],
+        counts: [system.particle_count() as u32, count as u32, 0, 0],
+        coefficients: [
+            settings.damping.powf(h),
+            settings.collision_margin,
+            settings.friction,
+            0.0,
+        ],
+        colliders,
+    }
+}
+
+fn particle_data(system: &PbdSystem) -> (Vec<[f32; 4]>, Vec<[f32; 4]>) {
+    let positions = system
+        .positions
+        .iter()
+        .zip(&system.inverse_masses)
+        .map(|(p, &w)| [p.x, p.y, p.z, w])
+        .collect();
+    let velocities = system
+        .velocities
+        .iter()
+        .map(|v| [v.x, v.y, v.z, 0.0])
+        .collect();
+    (positions, velocities)
+}
+
+impl GpuPbdSolver {
+    /// Create GPU buffers for the particles and colored constraints of `system`
+    pub fn new(device: &Device, system: &PbdSystem) -> Self {
+        if system
+            .colliders
+            .iter()
+            .any(|collider| matches!(collider, PbdCollider::Sdf(_)))
+        {
+            tracing::warn!("SDF colliders are not supported by the GPU PBD solver and are ignored");
+        }
+        if system.colliders.len() > MAX_COLLIDERS {
+            tracing::warn!(
+                "GPU PBD solver supports {} colliders, ignoring {}",
+                MAX_COLLIDERS,
+                system.colliders.len() - MAX_COLLIDERS
+            );
+        }
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("PBD Shader"),
+            source: wgpu::ShaderSource::Wgsl(PBD_SHADER.into()),
+        });
+
+        let buffer_entry = |binding: u32, ty: wgpu::BufferBindingType| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let storage = |read_only| wgpu::BufferBindingType::Storage { read_only };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("PBD Bind Group Layout"),
+            entries: &[
+                buffer_entry(0, wgpu::BufferBindingType::Uniform),
+                buffer_entry(1, storage(false)),
+                buffer_entry(2, storage(false)),
+                buffer_entry(3, storage(false)),
+                buffer_entry(4, storage(true)),
+                buffer_entry(5, wgpu::BufferBindingType::Uniform),
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("PBD Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |entry_point: &str| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+
+        let (positions_data, velocities_data) = particle_data(system);
+        let particle_buffer = |label: &str, contents: &[[f32; 4]]| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents: bytemuck::cast_slice(contents),
+                usage: wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_DST
+                    | wgpu::BufferUsages::COPY_SRC,
+            })
+        };
+        let positions = particle_buffer("PBD Positions", &positions_data);
+        let previous = particle_buffer("PBD Previous Positions", &positions_data);
+        let velocities = particle_buffer("PBD Velocities", &velocities_data);
+        let staging = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("PBD Staging Buffer"),
+            size: positions.size() * 2,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let settings = ParamsUniform::new(device, "PBD Settings", &gpu_settings(system, 0.0));
+
+        // Constraints sorted by color, so each color is a contiguous range
+        let coloring = color_constraints(&system.constraints, system.particle_count());
+        let mut constraints: Vec<GpuConstraint> = coloring
+            .iter()
+            .flatten()
+            .map(|&index| gpu_constraint(&system.constraints[index]))
+            .collect();
+        if constraints.is_empty() {
+            // Bindings can't be empty
+            constraints.push(GpuConstraint::zeroed());
+        }
+        let constraint_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("PBD Constraints"),
+            contents: bytemuck::cast_slice(&constraints),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+
+        let bind_group = |label: &str, range: ColorRange| {
+            let range_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents: bytemuck::bytes_of(&range),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+            let resources = [
+                settings.buffer(),
+                &positions,
+                &previous,
+                &velocities,
+                &constraint_buffer,
+                &range_buffer,
+            ];
+            let entries: Vec<wgpu::BindGroupEntry> = resources
+                .iter()
+                .enumerate()
+                .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                    binding: binding as u32,
+                    resource: buffer.as_entire_binding(),
+                })
+                .collect();
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout: &bind_group_layout,
+                entries: &entries,
+            })
+        };
+
+        let particle_bind_group = bind_group(
+            "PBD Particles",
+            ColorRange {
+                offset: 0,
+                count: 0,
+                _padding: [0; 2],
+            },
+        );
+        let mut offset = 0;
+        let colors = coloring
+            .iter()
+            .map(|color| {
+                let count = color.len() as u32;
+                let range = ColorRange {
+                    offset,
+                    count,
+                    _padding: [0; 2],
+                };
+                offset += count;
+                (bind_group("PBD Constraint Color", range), count)
+            })
+            .collect();
+
+        Self {
+            predict_pipeline: pipeline("predict"),
+            solve_pipeline: pipeline("solve"),
+            finalize_pipeline: pipeline("finalize"),
+            settings,
+            positions,
+            velocities,
+            staging,
+            particle_bind_group,
+            colors,
+            particle_count: system.particle_count() as u32,
+        }
+    }
+
+    /// Number of constraint colors, i.e. solve dispatches per substep
+    pub fn color_count(&self) -> usize {
+        self.colors.len()
+    }
+
+    /// Positions on the GPU as `vec4<f32>` with the inverse mass in `w`
+    pub fn positions_buffer(&self) -> &wgpu::Buffer {
+        &self.positions
+    }
+
+    /// Overwrite the GPU particles with the system's, e.g. after a reset
+    ///
+    /// The particle count must be the one the solver was created with.
+    pub fn upload(&self, queue: &Queue, system: &PbdSystem) {
+        let (positions, velocities) = particle_data(system);
+        queue.write_buffer(&self.positions, 0, bytemuck::cast_slice(&positions));
+        queue.write_buffer(&self.velocities, 0, bytemuck::cast_slice(&velocities));
+    }
+
+    /// Advance by `dt` seconds with the settings and colliders of `system`
+    pub fn step(&mut self, device: &Device, queue: &Queue, system: &PbdSystem, dt: f32) {
+        self.settings.update(queue, &gpu_settings(system, dt));
+
+        let particle_groups = self.particle_count.div_ceil(WORKGROUP_SIZE);
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("PBD Step Encoder"),
+        });
+        {
+            crate::logging::crash::record_pass("PBD Step");
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("PBD Step"),
+                timestamp_writes: None,
+            });
+            for _ in 0..system.settings.substeps.max(1) {
+                pass.set_pipeline(&self.predict_pipeline);
+                pass.set_bind_group(0, &self.particle_bind_group, &[]);
+                pass.dispatch_workgroups(particle_groups, 1, 1);
+
+                pass.set_pipeline(&self.solve_pipeline);
+                for (bind_group, count) in &self.colors {
+                    pass.set_bind_group(0, bind_group, &[]);
+                    pass.dispatch_workgroups(count.div_ceil(WORKGROUP_SIZE), 1, 1);
+                }
+
+                pass.set_pipeline(&self.finalize_pipeline);
+                pass.set_bind_group(0, &self.particle_bind_group, &[]);
+                pass.dispatch_workgroups(particle_groups, 1, 1);
+            }
+        }
+        queue.submit(std::iter::once(encoder.finish()));
+    }
+
+    /// Copy positions and velocities back into `system`, waiting for the GPU
+    pub fn read_back(&self, device: &Device, queue: &Queue, system: &mut PbdSystem) {
+        let size = self.positions.size();
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("PBD Readback Encoder"),
+        });
+        encoder.copy_buffer_to_buffer(&self.positions, 0, &self.staging, 0, size);
+        encoder.copy_buffer_to_buffer(&self.velocities, 0, &self.staging, size, size);
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = self.staging.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        let _ = device.poll(wgpu::MaintainBase::Wait);
+
+        if let Ok(Ok(())) = receiver.recv() {
+            let data = slice.get_mapped_range();
+            let values: &[[f32; 4]] = bytemuck::cast_slice(&data);
+            let (positions, velocities) = values.split_at(values.len() / 2);
+            for (target, p) in system.positions.iter_mut().zip(positions) {
+                *target = cgmath::Vector3::new(p[0], p[1], p[2]);
+            }
+            for (target, v) in system.velocities.iter_mut().zip(velocities) {
+                *target = cgmath::Vector3::new(v[0], v[1], v[2]);
+            }
+        }
+        self.staging.unmap();
+    }
+}
+
+const PBD_SHADER: &str = r#"
+struct Collider {
+    a: vec4<f32>,
+    b: vec4<f32>,
+    kind: vec4<u32>,
+}
+
+struct Settings {
+    gravity: vec4<f32>,      // substep length in w
+    counts: vec4<u32>,       // particles, colliders
+    coefficients: vec4<f32>, // damping, margin, friction
+    colliders: array<Collider, 8>,
+}
+
+struct Constraint {
+    particles: vec4<u32>,
+    kind: u32,
+    rest: f32,
+    compliance: f32,
+    _padding: u32,
+}
+
+struct ColorRange {
+    offset: u32,
+    count: u32,
+    _padding: vec2<u32>,
+}
+
+@group(0) @binding(0) var<uniform> settings: Settings;
+@group(0) @binding(1) var<storage, read_write> positions: array<vec4<f32>>;
+@group(0) @binding(2) var<storage, read_write> previous: array<vec4<f32>>;
+@group(0) @binding(3) var<storage, read_write> velocities: array<vec4<f32>>;
+@group(0) @binding(4) var<storage, read> constraints: array<Constraint>;
+@group(0) @binding(5) var<uniform> color: ColorRange;
+
+@compute @workgroup_size(64)
+fn predict(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if (i >= settings.counts.x) {
+        return;
+    }
+    let p = positions[i];
+    previous[i] = p;
+    if (p.w == 0.0) {
+        return;
+    }
+    let h = settings.gravity.w;
+    let v = velocities[i].xyz + settings.gravity.xyz * h;
+    velocities[i] = vec4<f32>(v, 0.0);
+    positions[i] = vec4<f32>(p.xyz + v * h, p.w);
+}
+
+fn move_particle(index: u32, delta: vec3<f32>) {
+    let p = positions[index];
+    positions[index] = vec4<f32>(p.xyz + delta * p.w, p.w);
+}
+
+@compute @workgroup_size(64)
+fn solve(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= color.count) {
+        return;
+    }
+    let constraint = constraints[color.offset + id.x];
+    let ids = constraint.particles;
+    let h = settings.gravity.w;
+    let alpha = constraint.compliance / (h * h);
+
+    let p0 = positions[ids.x];
+    let p1 = positions[ids.y];
+    let p2 = positions[ids.z];
+    let p3 = positions[ids.w];
+
+    var c = 0.0;
+    var g0 = vec3<f32>(0.0);
+    var g1 = vec3<f32>(0.0);
+    var g2 = vec3<f32>(0.0);
+    var g3 = vec3<f32>(0.0);
+
+    if (constraint.kind == 0u) {
+        let delta = p0.xyz - p1.xyz;
+        let distance = length(delta);
+        if (distance < 1e-9) {
+            return;
+        }
+        c = distance - constraint.rest;
+        g0 = delta / distance;
+        g1 = -g0;
+    } else if (constraint.kind == 1u) {
+        let offset = p2.xyz - (p0.xyz + p1.xyz + p2.xyz) / 3.0;
+        let distance = length(offset);
+        if (distance < 1e-9) {
+            return;
+        }
+        let n = offset / distance;
+        c = distance - constraint.rest;
+        g0 = -n / 3.0;
+        g1 = -n / 3.0;
+        g2 = n * (2.0 / 3.0);
+    } else {
+        g0 = cross(p3.xyz - p1.xyz, p2.xyz - p1.xyz) / 6.0;
+        g1 = cross(p2.xyz - p0.xyz, p3.xyz - p0.xyz) / 6.0;
+        g2 = cross(p3.xyz - p0.xyz, p1.xyz - p0.xyz) / 6.0;
+        g3 = cross(p1.xyz - p0.xyz, p2.xyz - p0.xyz) / 6.0;
+        c = dot(g3, p3.xyz - p0.xyz) - constraint.rest;
+    }
+
+    let weight = p0.w * dot(g0, g0) + p1.w * dot(g1, g1) + p2.w * dot(g2, g2) + p3.w * dot(g3, g3);
+    if (weight < 1e-12) {
+        return;
+    }
+    let lambda = -c / (weight + alpha);
+    move_particle(ids.x, g0 * lambda);
+    move_particle(ids.y, g1 * lambda);
+    if (constraint.kind != 0u) {
+        move_particle(ids.z, g2 * lambda);
+    }
+    if (constraint.kind == 2u) {
+        move_particle(ids.w, g3 * lambda);
+    }
+}
+
+// Signed distance and outward normal of a collider
+fn collider_distance(collider: Collider, p: vec3<f32>) -> vec4<f32> {
+    if (collider.kind.x == 0u) {
+        return vec4<f32>(collider.a.xyz, dot(collider.a.xyz, p) - collider.a.w);
+    }
+    if (collider.kind.x == 1u) {
+        let offset = p - collider.a.xyz;
+        let distance = length(offset);
+        let n = select(vec3<f32>(0.0, 0.0, 1.0), offset / distance, distance > 1e-9);
+        return vec4<f32>(n, distance - collider.a.w);
+    }
+    let center = (collider.a.xyz + collider.b.xyz) * 0.5;
+    let half_size = (collider.b.xyz - collider.a.xyz) * 0.5;
+    let d = p - center;
+    let q = abs(d) - half_size;
+    if (all(q < vec3<f32>(0.0))) {
+        // Inside: leave through the nearest face
+        var n = vec3<f32>(0.0, 0.0, sign(d.z));
+        var depth = q.z;
+        if (q.x > depth) {
+            n = vec3<f32>(sign(d.x), 0.0, 0.0);
+            depth = q.x;
+        }
+        if (q.y > depth) {
+            n = vec3<f32>(0.0, sign(d.y), 0.0);
+            depth = q.y;
+        }
+        return vec4<f32>(n, depth);
+    }
+    let outside = max(q, vec3<f32>(0.0));
+    let distance = length(outside);
+    return vec4<f32>(sign(d) * outside / max(distance, 1e-9), distance);
+}
+
+@compute @workgroup_size(64)
+fn finalize(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if (i >= settings.counts.x) {
+        return;
+    }
+    var p = positions[i];
+    if (p.w == 0.0) {
+        velocities[i] = vec4<f32>(0.0);
+        return;
+    }
+
+    let margin = settings.coefficients.y;
+    let friction = settings.coefficients.z;
+    let before = previous[i].xyz;
+    for (var c = 0u; c < settings.counts.y; c++) {
+        let hit = collider_distance(settings.colliders[c], p.xyz);
+        let depth = hit.w - margin;
+        if (depth < 0.0) {
+            let resolved = p.xyz - hit.xyz * depth;
+            let motion = resolved - before;
+            let tangential = motion - hit.xyz * dot(motion, hit.xyz);
+            p = vec4<f32>(resolved - tangential * friction, p.w);
+        }
+    }
+    positions[i] = p;
+
+    let h = settings.gravity.w;
+    velocities[i] = vec4<f32>((p.xyz - before) / h * settings.coefficients.x, 0.0);
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coloring_separates_shared_particles() {
+        let system = PbdSystem::rope([0.0, 0.0, 0.0], [1.0, 0.0, 0.0], 8, 1.0);
+        let colors = color_constraints(&system.constraints, system.particle_count());
+
+        let total: usize = colors.iter().map(Vec::len).sum();
+        assert_eq!(total, system.constraints.len());
+        for color in &colors {
+            let mut used = Vec::new();
+            for &index in color {
+                for &particle in system.constraints[index].particles() {
+                    assert!(!used.contains(&particle));
+                    used.push(particle);
+                }
+            }
+        }
+        assert_eq!(std::mem::size_of::<GpuSettings>(), 48 + 48 * MAX_COLLIDERS);
+    }
+}
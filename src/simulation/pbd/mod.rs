@@ -0,0 +1,333 @@
+//! # Position-Based Dynamics
+//!
+//! Particles, constraints and colliders for extended position-based dynamics
+//! (XPBD), with a CPU and a GPU solver sharing one description. Cloth, ropes
+//! and soft bodies are all particle sets tied together by constraints:
+//!
+//! - [`PbdConstraint::Distance`] - keeps two particles at their rest distance
+//! - [`PbdConstraint::Bending`] - keeps a particle in line with two neighbors
+//! - [`PbdConstraint::Volume`] - keeps the volume of a tetrahedron
+//!
+//! Each step is split into substeps that predict positions from velocities,
+//! project every constraint once, resolve collisions and derive velocities
+//! from the corrected positions. Stiffness is given as compliance (inverse
+//! stiffness, `0.0` is rigid), so behavior doesn't depend on the substep count.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::simulation::pbd::{CpuPbdSolver, PbdCollider, PbdSystem};
+//!
+//! // A rope hanging from a fixed point, falling onto the ground
+//! let mut rope = PbdSystem::rope([0.0, 0.0, 2.0], [1.5, 0.0, 2.0], 30, 0.1);
+//! rope.pin(0);
+//! rope.add_collider(PbdCollider::ground(0.0));
+//!
+//! let mut solver = CpuPbdSolver::new();
+//! solver.step(&mut rope, 1.0 / 60.0);
+//! ```
+//!
+//! On the GPU, [`GpuPbdSolver`] runs the same steps in compute shaders and
+//! reads the positions back into the system on request.
+//!
+//! [`CpuPbdSolver::impacts`] lists the collisions of the last step for the
+//! simulation to emit as [`CollisionEvent`](crate::events::CollisionEvent)s,
+//! e.g. for collision sounds.
+
+pub mod cpu;
+pub mod gpu;
+
+pub use cpu::CpuPbdSolver;
+pub use gpu::GpuPbdSolver;
+
+use std::sync::Arc;
+
+use cgmath::{InnerSpace, Matrix4, Vector3};
+
+use crate::gfx::picking::AABB;
+use crate::gfx::scene::object::Object;
+
+/// Constraint between particles, referenced by index
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PbdConstraint {
+    /// `|p[a] - p[b]| = rest`
+    Distance {
+        particles: [usize; 2],
+        rest: f32,
+        compliance: f32,
+    },
+    /// Distance of `particles[2]` from the centroid of all three stays at `rest`
+    ///
+    /// Resists bending at `particles[2]` between its neighbors `particles[0]`
+    /// and `particles[1]` (Kelager et al. triangle bending).
+    Bending {
+        particles: [usize; 3],
+        rest: f32,
+        compliance: f32,
+    },
+    /// Signed volume of the tetrahedron stays at `rest`
+    Volume {
+        particles: [usize; 4],
+        rest: f32,
+        compliance: f32,
+    },
+}
+
+impl PbdConstraint {
+    /// Indices of the particles this constraint moves
+    pub fn particles(&self) -> &[usize] {
+        match self {
+            PbdConstraint::Distance { particles, .. } => particles,
+            PbdConstraint::Bending { particles, .. } => particles,
+            PbdConstraint::Volume { particles, .. } => particles,
+        }
+    }
+}
+
+/// Shape particles are pushed out of
+#[derive(Clone)]
+pub enum PbdCollider {
+    /// Half-space below the plane `dot(normal, p) = offset`
+    Plane {
+        normal: Vector3<f32>,
+        offset: f32,
+    },
+    Sphere {
+        center: Vector3<f32>,
+        radius: f32,
+    },
+    /// Solid axis-aligned box
+    Box(AABB),
+    /// Signed distance function, negative inside; CPU solver only
+    Sdf(Arc<dyn Fn(Vector3<f32>) -> f32 + Send + Sync>),
+}
+
+impl std::fmt::Debug for PbdCollider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PbdCollider::Plane { normal, offset } => f
+                .debug_struct("Plane")
+                .field("normal", normal)
+                .field("offset", offset)
+                .finish(),
+            PbdCollider::Sphere { center, radius } => f
+                .debug_struct("Sphere")
+                .field("center", center)
+                .field("radius", radius)
+                .finish(),
+            PbdCollider::Box(aabb) => f.debug_tuple("Box").field(aabb).finish(),
+            PbdCollider::Sdf(_) => f.write_str("Sdf(..)"),
+        }
+    }
+}
+
+impl PbdCollider {
+    /// Ground plane at height `z` (the world is Z-up)
+    pub fn ground(z: f32) -> Self {
+        PbdCollider::Plane {
+            normal: Vector3::unit_z(),
+            offset: z,
+        }
+    }
+
+    /// World-space bounding box of a scene object
+    pub fn object_bounds(object: &Object) -> Self {
+        let vertices: Vec<[f32; 3]> = object
+            .meshes
+            .iter()
+            .flat_map(|mesh| mesh.vertices().iter().map(|vertex| vertex.position))
+            .collect();
+        PbdCollider::Box(AABB::from_vertices(&vertices).transform(&object.transform))
+    }
+
+    /// Signed distance function collider
+    pub fn sdf(distance: impl Fn(Vector3<f32>) -> f32 + Send + Sync + 'static) -> Self {
+        PbdCollider::Sdf(Arc::new(distance))
+    }
+
+    /// Signed distance from the surface, negative inside
+    pub fn distance(&self, point: Vector3<f32>) -> f32 {
+        match self {
+            PbdCollider::Plane { normal, offset } => normal.dot(point) - offset,
+            PbdCollider::Sphere { center, radius } => (point - center).magnitude() - radius,
+            PbdCollider::Box(aabb) => {
+                let center = (aabb.min + aabb.max) * 0.5;
+                let half = (aabb.max - aabb.min) * 0.5;
+                let d = point - center;
+                let q = Vector3::new(d.x.abs() - half.x, d.y.abs() - half.y, d.z.abs() - half.z);
+                let outside = Vector3::new(q.x.max(0.0), q.y.max(0.0), q.z.max(0.0));
+                outside.magnitude() + q.x.max(q.y).max(q.z).min(0.0)
+            }
+            PbdCollider::Sdf(distance) => distance(point),
+        }
+    }
+
+    /// Outward surface direction at `point`, from central differences
+    pub fn gradient(&self, point: Vector3<f32>) -> Vector3<f32> {
+        const H: f32 = 1e-3;
+        let axis = |offset: Vector3<f32>| {
+            (self.distance(point + offset) - self.distance(point - offset)) / (2.0 * H)
+        };
+        let gradient = Vector3::new(
+            axis(Vector3::new(H, 0.0, 0.0)),
+            axis(Vector3::new(0.0, H, 0.0)),
+            axis(Vector3::new(0.0, 0.0, H)),
+        );
+        if gradient.magnitude2() > 0.0 {
+            gradient.normalize()
+        } else {
+            Vector3::unit_z()
+        }
+    }
+}
+
+/// Global settings of a [`PbdSystem`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PbdSettings {
+    pub gravity: Vector3<f32>,
+    pub substeps: u32,
+    /// Share of the velocity kept per second
+    pub damping: f32,
+    /// Particles are kept this far from collider surfaces
+    pub collision_margin: f32,
+    /// Fraction of the tangential motion removed on contact
+    pub friction: f32,
+    /// Contacts faster than this into a collider are reported as impacts
+    pub impact_speed: f32,
+}
+
+impl Default for PbdSettings {
+    fn default() -> Self {
+        Self {
+            gravity: Vector3::new(0.0, 0.0, -9.81),
+            substeps: 10,
+            damping: 0.99,
+            collision_margin: 0.005,
+            friction: 0.2,
+            impact_speed: 0.5,
+        }
+    }
+}
+
+/// Particles, constraints and colliders of one simulated body or scene
+#[derive(Debug, Clone, Default)]
+pub struct PbdSystem {
+    pub positions: Vec<Vector3<f32>>,
+    pub velocities: Vec<Vector3<f32>>,
+    /// `0.0` for pinned particles
+    pub inverse_masses: Vec<f32>,
+    pub constraints: Vec<PbdConstraint>,
+    pub colliders: Vec<PbdCollider>,
+    pub settings: PbdSettings,
+}
+
+impl PbdSystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Chain of `segments + 1` particles from `start` to `end`, with distance
+    /// and bending constraints
+    pub fn rope(start: [f32; 3], end: [f32; 3], segments: usize, mass: f32) -> Self {
+        let mut system = Self::new();
+        let (start, end) = (Vector3::from(start), Vector3::from(end));
+        let segments = segments.max(1);
+        let particle_mass = mass / (segments + 1) as f32;
+        for i in 0..=segments {
+            let t = i as f32 / segments as f32;
+            system.add_particle(start + (end - start) * t, particle_mass);
+        }
+        for i in 0..segments {
+            system.add_distance(i, i + 1, 0.0);
+        }
+        for i in 1..segments {
+            system.add_bending(i - 1, i + 1, i, 1e-4);
+        }
+        system
+    }
+
+    /// Add a particle and return its index; a `mass` of zero pins it
+    pub fn add_particle(&mut self, position: impl Into<Vector3<f32>>, mass: f32) -> usize {
+        self.positions.push(position.into());
+        self.velocities.push(Vector3::new(0.0, 0.0, 0.0));
+        self.inverse_masses
+            .push(if mass > 0.0 { 1.0 / mass } else { 0.0 });
+        self.positions.len() - 1
+    }
+
+    /// Fix a particle in place
+    pub fn pin(&mut self, particle: usize) {
+        self.inverse_masses[particle] = 0.0;
+        self.velocities[particle] = Vector3::new(0.0, 0.0, 0.0);
+    }
+
+    /// Distance constraint at the particles' current distance
+    pub fn add_distance(&mut self, a: usize, b: usize, compliance: f32) {
+        let rest = (self.positions[a] - self.positions[b]).magnitude();
+        self.constraints.push(PbdConstraint::Distance {
+            particles: [a, b],
+            rest,
+            compliance,
+        });
+    }
+
+    /// Bending constraint keeping `middle` at its current offset from the
+    /// centroid of `a`, `b` and itself
+    pub fn add_bending(&mut self, a: usize, b: usize, middle: usize, compliance: f32) {
+        let particles = [a, b, middle];
+        let rest = bending_offset(&particles.map(|i| self.positions[i])).magnitude();
+        self.constraints.push(PbdConstraint::Bending {
+            particles,
+            rest,
+            compliance,
+        });
+    }
+
+    /// Volume constraint at the tetrahedron's current volume
+    pub fn add_volume(&mut self, particles: [usize; 4], compliance: f32) {
+        let rest = tetrahedron_volume(&particles.map(|i| self.positions[i]));
+        self.constraints.push(PbdConstraint::Volume {
+            particles,
+            rest,
+            compliance,
+        });
+    }
+
+    pub fn add_collider(&mut self, collider: PbdCollider) {
+        self.colliders.push(collider);
+    }
+
+    /// Add the bounding box of every visible scene object as a collider
+    pub fn add_scene_colliders<'a>(&mut self, objects: impl IntoIterator<Item = &'a Object>) {
+        for object in objects.into_iter().filter(|object| object.visible) {
+            self.colliders.push(PbdCollider::object_bounds(object));
+        }
+    }
+
+    pub fn particle_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Positions as plain arrays, e.g. for [`Mesh::set_positions`]
+    ///
+    /// [`Mesh::set_positions`]: crate::gfx::scene::object::Mesh::set_positions
+    pub fn positions_array(&self) -> Vec<[f32; 3]> {
+        self.positions.iter().map(|&p| p.into()).collect()
+    }
+
+    /// Move every particle by a transform, e.g. to place a body in the world
+    pub fn transform(&mut self, matrix: Matrix4<f32>) {
+        for position in &mut self.positions {
+            *position = (matrix * position.extend(1.0)).truncate();
+        }
+    }
+}
+
+/// Offset of the last particle from the centroid of all three
+pub(crate) fn bending_offset(p: &[Vector3<f32>; 3]) -> Vector3<f32> {
+    p[2] - (p[0] + p[1] + p[2]) / 3.0
+}
+
+pub(crate) fn tetrahedron_volume(p: &[Vector3<f32>; 4]) -> f32 {
+    (p[1] - p[0]).cross(p[2] - p[0]).dot(p[3] - p[0]) / 6.0
+}
@@ -0,0 +1,285 @@
+//! Progress and time remaining of finite-horizon runs
+//!
+//! Parameter sweeps, bakes and other runs with a known end implement
+//! [`SimulationProgress`] and hand it out through
+//! [`Simulation::progress`](super::traits::Simulation::progress). The
+//! [`SimulationManager`](super::manager::SimulationManager) then shows a
+//! progress window, and headless runs can print the same information with
+//! [`ConsoleProgress`]. Runs that can't predict their remaining time leave
+//! [`SimulationProgress::eta`] at `None` and get an estimate from the rate
+//! of progress so far.
+//!
+//! ## Usage
+//!
+//! ```
+//! use haggis::simulation::progress::{ConsoleProgress, SimulationProgress};
+//!
+//! struct Bake {
+//!     done: usize,
+//!     total: usize,
+//! }
+//!
+//! impl SimulationProgress for Bake {
+//!     fn fraction_complete(&self) -> f32 {
+//!         self.done as f32 / self.total as f32
+//!     }
+//!
+//!     fn phase_name(&self) -> Option<String> {
+//!         Some("Baking".into())
+//!     }
+//! }
+//!
+//! let mut bake = Bake { done: 0, total: 100 };
+//! let mut console = ConsoleProgress::new();
+//! while bake.done < bake.total {
+//!     bake.done += 1;
+//!     console.report(&bake);
+//! }
+//! ```
+
+use std::time::Duration;
+
+use crate::platform::Instant;
+
+/// Progress of a run that finishes after a known amount of work
+pub trait SimulationProgress {
+    /// Finished fraction of the run, from 0 to 1
+    fn fraction_complete(&self) -> f32;
+
+    /// Time left until the run finishes
+    ///
+    /// The default `None` lets the UI and console estimate it from the rate
+    /// of progress.
+    fn eta(&self) -> Option<Duration> {
+        None
+    }
+
+    /// What the run is currently doing, e.g. "Baking" or the current sweep point
+    fn phase_name(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Estimates the time left from how fast the fraction complete grows
+#[derive(Debug, Clone, Default)]
+pub struct EtaEstimator {
+    /// When and at what fraction the estimate started
+    start: Option<(Instant, f32)>,
+    last_fraction: f32,
+}
+
+impl EtaEstimator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Time left at `fraction` complete, observed at `now`
+    ///
+    /// # Returns
+    ///
+    /// `None` until the fraction has grown since the first observation. A
+    /// shrinking fraction, e.g. after a reset, restarts the estimate.
+    pub fn estimate(&mut self, fraction: f32, now: Instant) -> Option<Duration> {
+        let fraction = fraction.clamp(0.0, 1.0);
+        if fraction < self.last_fraction {
+            self.start = None;
+        }
+        self.last_fraction = fraction;
+        let (start, start_fraction) = *self.start.get_or_insert((now, fraction));
+        let done = fraction - start_fraction;
+        if done <= 0.0 {
+            return None;
+        }
+        let elapsed = now.duration_since(start).as_secs_f64();
+        let left = elapsed * (1.0 - fraction) as f64 / done as f64;
+        Some(Duration::from_secs_f64(left))
+    }
+
+    /// Forget the observations so far
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// A [`SimulationProgress`] read at one point in time
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProgressSnapshot {
+    /// Finished fraction, from 0 to 1
+    pub fraction: f32,
+    /// Time left, reported or estimated
+    pub eta: Option<Duration>,
+    pub phase: Option<String>,
+}
+
+impl ProgressSnapshot {
+    /// Read `progress`, estimating its ETA with `estimator` if it has none
+    pub fn read(progress: &dyn SimulationProgress, estimator: &mut EtaEstimator) -> Self {
+        let fraction = progress.fraction_complete().clamp(0.0, 1.0);
+        let estimate = estimator.estimate(fraction, Instant::now());
+        Self {
+            fraction,
+            eta: progress.eta().or(estimate),
+            phase: progress.phase_name(),
+        }
+    }
+
+    /// Percentage and time left, e.g. "42.0%, 1m 05s left"
+    pub fn summary(&self) -> String {
+        let percent = format!("{:.1}%", self.fraction * 100.0);
+        match self.eta {
+            Some(eta) if self.fraction < 1.0 => format!("{percent}, {} left", format_eta(eta)),
+            _ => percent,
+        }
+    }
+
+    /// Draw a progress bar with the summary, and the phase above it
+    pub fn render_ui(&self, ui: &imgui::Ui) {
+        if let Some(phase) = &self.phase {
+            ui.text(phase);
+        }
+        imgui::ProgressBar::new(self.fraction)
+            .overlay_text(self.summary())
+            .build(ui);
+    }
+}
+
+/// Compact duration for progress displays: "1h 02m", "3m 05s" or "12s"
+pub fn format_eta(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}h {minutes:02}m")
+    } else if minutes > 0 {
+        format!("{minutes}m {seconds:02}s")
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+/// Prints progress lines to stderr for runs without a window
+#[derive(Debug, Clone)]
+pub struct ConsoleProgress {
+    /// Shortest time between two printed lines
+    interval: Duration,
+    last_print: Option<Instant>,
+    estimator: EtaEstimator,
+    finished: bool,
+}
+
+impl Default for ConsoleProgress {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(1),
+            last_print: None,
+            estimator: EtaEstimator::new(),
+            finished: false,
+        }
+    }
+}
+
+impl ConsoleProgress {
+    /// Print at most once per second, and once more when the run finishes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Print a line for `progress` if the interval has passed or it finished
+    pub fn report(&mut self, progress: &dyn SimulationProgress) {
+        if let Some(line) = self.line(progress, Instant::now()) {
+            eprintln!("{line}");
+        }
+    }
+
+    /// The line to print at `now`, if any
+    fn line(&mut self, progress: &dyn SimulationProgress, now: Instant) -> Option<String> {
+        let fraction = progress.fraction_complete().clamp(0.0, 1.0);
+        let estimate = self.estimator.estimate(fraction, now);
+        let finished = fraction >= 1.0;
+        let due = self
+            .last_print
+            .is_none_or(|last| now.duration_since(last) >= self.interval);
+        if self.finished && finished || !(due || finished) {
+            return None;
+        }
+        self.last_print = Some(now);
+        self.finished = finished;
+
+        let snapshot = ProgressSnapshot {
+            fraction,
+            eta: progress.eta().or(estimate),
+            phase: progress.phase_name(),
+        };
+        const WIDTH: usize = 30;
+        let filled = (fraction * WIDTH as f32).round() as usize;
+        let bar = format!("[{}{}]", "#".repeat(filled), "-".repeat(WIDTH - filled));
+        Some(match &snapshot.phase {
+            Some(phase) => format!("{bar} {} {phase}", snapshot.summary()),
+            None => format!("{bar} {}", snapshot.summary()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixed(f32);
+
+    impl SimulationProgress for Fixed {
+        fn fraction_complete(&self) -> f32 {
+            self.0
+        }
+
+        fn phase_name(&self) -> Option<String> {
+            Some("Baking".into())
+        }
+    }
+
+    #[test]
+    fn test_eta_from_progress_rate() {
+        let mut estimator = EtaEstimator::new();
+        let start = Instant::now();
+        assert_eq!(estimator.estimate(0.2, start), None);
+        // 20% more in 10 s leaves 60% for 30 s
+        let eta = estimator.estimate(0.4, start + Duration::from_secs(10));
+        assert_eq!(eta.map(|eta| eta.as_secs()), Some(30));
+        // Going backwards restarts the estimate
+        assert_eq!(
+            estimator.estimate(0.1, start + Duration::from_secs(11)),
+            None
+        );
+
+        assert_eq!(format_eta(Duration::from_secs(12)), "12s");
+        assert_eq!(format_eta(Duration::from_secs(185)), "3m 05s");
+        assert_eq!(format_eta(Duration::from_secs(3720)), "1h 02m");
+    }
+
+    #[test]
+    fn test_console_lines_are_throttled() {
+        let mut console = ConsoleProgress::new().with_interval(Duration::from_secs(1));
+        let start = Instant::now();
+        let line = console.line(&Fixed(0.5), start).unwrap();
+        assert!(line.starts_with("[###############---------------] 50.0%"));
+        assert!(line.ends_with("Baking"));
+        assert_eq!(
+            console.line(&Fixed(0.6), start + Duration::from_millis(100)),
+            None
+        );
+        // Finishing always prints, once
+        let done = start + Duration::from_millis(200);
+        assert!(console
+            .line(&Fixed(1.0), done)
+            .unwrap()
+            .contains("100.0% Baking"));
+        assert_eq!(console.line(&Fixed(1.0), done), None);
+        assert_eq!(
+            console.line(&Fixed(1.0), done + Duration::from_secs(5)),
+            None
+        );
+    }
+}
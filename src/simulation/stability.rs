@@ -0,0 +1,164 @@
+//! Simulation stability guard
+//!
+//! A diverging solver fills its fields with NaN or ever larger values long
+//! before anything on screen looks wrong. Simulations report their state
+//! through [`Simulation::check_stability`](super::traits::Simulation::check_stability),
+//! typically with [`check_field`] on data they already read back, and the
+//! [`SimulationManager`](super::manager::SimulationManager) runs the check
+//! according to its [`StabilityPolicy`]. When a check fails the manager
+//! pauses the simulation, optionally rolls it back to the last checkpoint
+//! taken while it was still stable, and records a [`Divergence`] with the
+//! simulation's parameter suggestions, such as [`suggest_tau`].
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::simulation::stability::StabilityPolicy;
+//!
+//! let mut app = haggis::default();
+//! app.set_stability_policy(Some(
+//!     StabilityPolicy::new()
+//!         .with_check_interval(10)
+//!         .with_rollback(200),
+//! ));
+//! ```
+
+use std::fmt;
+
+/// When the manager checks a simulation and what it does on divergence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StabilityPolicy {
+    /// Steps between stability checks
+    pub check_interval: u64,
+    /// Pause the simulation when a check fails
+    pub pause: bool,
+    /// Steps between checkpoints to roll back to, `None` to never roll back
+    pub checkpoint_interval: Option<u64>,
+}
+
+impl Default for StabilityPolicy {
+    fn default() -> Self {
+        Self {
+            check_interval: 1,
+            pause: true,
+            checkpoint_interval: None,
+        }
+    }
+}
+
+impl StabilityPolicy {
+    /// Check every step and pause on divergence, without rollback
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_check_interval(mut self, steps: u64) -> Self {
+        self.check_interval = steps.max(1);
+        self
+    }
+
+    pub fn with_pause(mut self, pause: bool) -> Self {
+        self.pause = pause;
+        self
+    }
+
+    /// Checkpoint every `steps` steps and restore the last one on divergence
+    ///
+    /// Checkpoints are only taken right after a passing check, so the
+    /// interval is rounded up to a multiple of the check interval.
+    pub fn with_rollback(mut self, steps: u64) -> Self {
+        self.checkpoint_interval = Some(steps.max(1));
+        self
+    }
+}
+
+/// A failed stability check
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    /// Step at which the check failed
+    pub step: u64,
+    /// Simulated seconds at that step
+    pub time: f64,
+    /// What the simulation found, e.g. `vorticity[1042] is NaN`
+    pub reason: String,
+    /// Step of the checkpoint the simulation was rolled back to
+    pub rolled_back_to: Option<u64>,
+    /// Parameter changes that may keep the simulation stable
+    pub suggestions: Vec<String>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Simulation diverged at step {}: {}",
+            self.step, self.reason
+        )?;
+        if let Some(step) = self.rolled_back_to {
+            write!(f, " (rolled back to step {step})")?;
+        }
+        Ok(())
+    }
+}
+
+/// First value of a field that is not finite or exceeds `limit` in magnitude
+///
+/// # Returns
+///
+/// `None` if all values are finite and within the limit, otherwise a
+/// description such as `density[17] is NaN`
+pub fn check_field(name: &str, values: &[f32], limit: f32) -> Option<String> {
+    let (index, value) = values
+        .iter()
+        .enumerate()
+        .find(|(_, value)| !value.is_finite() || value.abs() > limit)?;
+    Some(if value.is_nan() {
+        format!("{name}[{index}] is NaN")
+    } else if value.is_infinite() {
+        format!("{name}[{index}] is infinite")
+    } else {
+        format!("{name}[{index}] = {value} exceeds {limit}")
+    })
+}
+
+/// Suggestion for an LBM relaxation time close to the stability limit of 0.5
+pub fn suggest_tau(tau: f32) -> Option<String> {
+    (tau < 0.52).then(|| {
+        format!("tau = {tau:.3} is close to 0.5; raise it to 0.55 or more, or lower the velocity")
+    })
+}
+
+/// Suggestion for an explicit scheme whose Courant number `velocity * dt / dx`
+/// exceeds `limit` (1 for most advection schemes)
+pub fn suggest_courant(velocity: f32, dt: f32, dx: f32, limit: f32) -> Option<String> {
+    let courant = velocity * dt / dx;
+    (courant > limit).then(|| {
+        format!(
+            "Courant number {courant:.2} exceeds {limit}; lower the time step below {:.3e}",
+            limit * dx / velocity
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checks_and_suggestions() {
+        assert_eq!(check_field("rho", &[1.0, 0.9, 1.1], 10.0), None);
+        assert_eq!(
+            check_field("rho", &[1.0, f32::NAN], 10.0).as_deref(),
+            Some("rho[1] is NaN")
+        );
+        assert_eq!(
+            check_field("u", &[-20.0], 10.0).as_deref(),
+            Some("u[0] = -20 exceeds 10")
+        );
+
+        assert!(suggest_tau(0.6).is_none());
+        assert!(suggest_tau(0.505).unwrap().contains("0.505"));
+        assert!(suggest_courant(1.0, 0.01, 0.1, 1.0).is_none());
+        assert!(suggest_courant(1.0, 0.2, 0.1, 1.0).is_some());
+    }
+}
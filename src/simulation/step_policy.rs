@@ -0,0 +1,247 @@
+//! Simulation step rate, independent of the frame rate
+//!
+//! By default the [`SimulationManager`](super::manager::SimulationManager)
+//! advances a simulation once per rendered frame, so a GPU solver that could
+//! run hundreds of steps per frame is bound to the display's refresh rate. A
+//! [`StepPolicy`] sets how many steps each frame runs instead: a fixed count,
+//! a rate in steps per second of wall-clock time, or as many as fit in a time
+//! budget per frame.
+//!
+//! The time budget is measured on the GPU with timestamp queries when the
+//! device has [`wgpu::Features::TIMESTAMP_QUERY`] (see
+//! [`GpuTimer`]); otherwise the manager waits for each frame's steps to
+//! finish and times them on the CPU.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::simulation::step_policy::StepPolicy;
+//!
+//! let mut app = haggis::default();
+//! // Spend up to 8 ms of GPU time per frame on simulation steps
+//! app.set_step_policy(StepPolicy::TimeBudget(8.0));
+//! ```
+
+use std::time::Duration;
+
+use wgpu::{Device, Queue};
+
+use super::gpu::{GpuFence, GpuTimer};
+use crate::platform::Instant;
+
+/// Most steps a single frame runs under any policy
+pub const MAX_STEPS_PER_FRAME: u32 = 10_000;
+
+/// Longest frame time a steps-per-second policy catches up on, in seconds
+const MAX_CATCH_UP: f64 = 0.25;
+
+/// How many simulation steps each frame runs
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum StepPolicy {
+    /// One step per frame, or as many fixed timesteps as the frame covers
+    #[default]
+    PerFrame,
+    /// This many steps every frame
+    StepsPerFrame(u32),
+    /// This many steps per second, scaled by the time scale, however fast
+    /// frames render
+    StepsPerSecond(f32),
+    /// As many steps per frame as fit in this many milliseconds
+    TimeBudget(f32),
+}
+
+impl StepPolicy {
+    /// One policy of each kind with typical values, e.g. for a mode selector
+    pub const ALL: [StepPolicy; 4] = [
+        StepPolicy::PerFrame,
+        StepPolicy::StepsPerFrame(10),
+        StepPolicy::StepsPerSecond(600.0),
+        StepPolicy::TimeBudget(8.0),
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            StepPolicy::PerFrame => "Per frame",
+            StepPolicy::StepsPerFrame(_) => "Steps per frame",
+            StepPolicy::StepsPerSecond(_) => "Steps per second",
+            StepPolicy::TimeBudget(_) => "Time budget",
+        }
+    }
+
+    /// Time step of each step when no fixed timestep is set
+    ///
+    /// Steps per second advance simulated time at wall-clock speed; the other
+    /// policies use 1/60 s.
+    pub fn default_dt(&self) -> f32 {
+        match *self {
+            StepPolicy::StepsPerSecond(rate) if rate > 0.0 => 1.0 / rate,
+            _ => 1.0 / 60.0,
+        }
+    }
+}
+
+/// Turns a step policy into a step count per frame
+#[derive(Debug)]
+pub(crate) struct StepScheduler {
+    /// Fractional steps carried over to the next frame
+    carry: f64,
+    /// Start of the last frame under a steps-per-second policy
+    last_frame: Option<Instant>,
+    /// Steps per frame that fit the time budget
+    budget_steps: u32,
+    /// Timestamp timer, once it was tried on the compute device
+    timer: Option<Option<GpuTimer>>,
+    /// Whether this frame's steps are bracketed by the timer
+    gpu_timing: bool,
+    /// Steps covered by the GPU measurement being read back
+    measured_steps: u32,
+    timing_start: Option<Instant>,
+}
+
+impl Default for StepScheduler {
+    fn default() -> Self {
+        Self {
+            carry: 0.0,
+            last_frame: None,
+            budget_steps: 1,
+            timer: None,
+            gpu_timing: false,
+            measured_steps: 0,
+            timing_start: None,
+        }
+    }
+}
+
+impl StepScheduler {
+    /// Steps to run in the frame starting at `now`
+    pub(crate) fn steps_due(&mut self, policy: StepPolicy, time_scale: f32, now: Instant) -> u32 {
+        match policy {
+            StepPolicy::PerFrame => 1,
+            StepPolicy::StepsPerFrame(steps) => steps.min(MAX_STEPS_PER_FRAME),
+            StepPolicy::StepsPerSecond(rate) => {
+                let elapsed = self
+                    .last_frame
+                    .map_or(0.0, |last| now.duration_since(last).as_secs_f64());
+                self.last_frame = Some(now);
+                self.carry += elapsed.min(MAX_CATCH_UP) * rate.max(0.0) as f64 * time_scale as f64;
+                let steps = self.carry.floor();
+                self.carry -= steps;
+                (steps as u32).min(MAX_STEPS_PER_FRAME)
+            }
+            StepPolicy::TimeBudget(_) => self.budget_steps,
+        }
+    }
+
+    /// Steps per frame the time budget currently allows
+    pub(crate) fn budget_steps(&self) -> u32 {
+        self.budget_steps
+    }
+
+    /// Start timing a frame's steps for the time budget
+    pub(crate) fn begin_timing(&mut self, gpu: Option<(&Device, &Queue)>) {
+        self.timing_start = Some(Instant::now());
+        self.gpu_timing = false;
+        let Some((device, queue)) = gpu else {
+            return;
+        };
+        let timer = self
+            .timer
+            .get_or_insert_with(|| GpuTimer::new(device, queue));
+        if let Some(timer) = timer {
+            self.gpu_timing = timer.begin(device, queue);
+        }
+    }
+
+    /// Finish timing `steps` steps and fit the step count to `budget_ms`
+    ///
+    /// GPU measurements are read back on a later frame, without timestamp
+    /// queries this blocks until the steps have finished.
+    pub(crate) fn end_timing(
+        &mut self,
+        budget_ms: f32,
+        steps: u32,
+        gpu: Option<(&Device, &Queue)>,
+    ) {
+        let cpu_elapsed = || self.timing_start.map(|start| start.elapsed());
+        let measurement = match (gpu, &mut self.timer) {
+            (Some((device, queue)), Some(Some(timer))) => {
+                if self.gpu_timing {
+                    timer.end(device, queue);
+                    self.measured_steps = steps;
+                }
+                timer
+                    .poll(device)
+                    .map(|elapsed| (self.measured_steps, elapsed))
+            }
+            (Some((device, queue)), _) => {
+                GpuFence::after(queue).wait(device);
+                cpu_elapsed().map(|elapsed| (steps, elapsed))
+            }
+            (None, _) => cpu_elapsed().map(|elapsed| (steps, elapsed)),
+        };
+        if let Some((steps, elapsed)) = measurement {
+            self.fit_budget(budget_ms, steps, elapsed);
+        }
+    }
+
+    /// Set the budgeted step count from the time `steps` steps took
+    fn fit_budget(&mut self, budget_ms: f32, steps: u32, elapsed: Duration) {
+        if steps == 0 {
+            return;
+        }
+        let step_ms = elapsed.as_secs_f64() * 1000.0 / steps as f64;
+        let fitting = if step_ms > 0.0 {
+            (budget_ms as f64 / step_ms).floor() as u32
+        } else {
+            MAX_STEPS_PER_FRAME
+        };
+        // At most double per measurement, so one fast sample doesn't overshoot
+        let ceiling = self.budget_steps.saturating_mul(2).min(MAX_STEPS_PER_FRAME);
+        self.budget_steps = fitting.clamp(1, ceiling.max(1));
+    }
+
+    /// Forget carried steps and timings, e.g. after a pause or policy change
+    pub(crate) fn reset(&mut self) {
+        self.carry = 0.0;
+        self.last_frame = None;
+        self.budget_steps = 1;
+        self.gpu_timing = false;
+        self.timing_start = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step_counts() {
+        let mut scheduler = StepScheduler::default();
+        let start = Instant::now();
+        let rate = StepPolicy::StepsPerSecond(100.0);
+        assert_eq!(scheduler.steps_due(rate, 1.0, start), 0);
+        // 25 ms at 100 steps/s is 2.5 steps, the half step carries over
+        let later = start + Duration::from_millis(25);
+        assert_eq!(scheduler.steps_due(rate, 1.0, later), 2);
+        let later = later + Duration::from_millis(25);
+        assert_eq!(scheduler.steps_due(rate, 1.0, later), 3);
+        // Double speed, and stalls are capped
+        let later = later + Duration::from_secs(10);
+        assert_eq!(scheduler.steps_due(rate, 2.0, later), 50);
+        assert_eq!(
+            scheduler.steps_due(StepPolicy::StepsPerFrame(7), 1.0, later),
+            7
+        );
+
+        // 2 ms per step fits 5 steps in 10 ms, reached by doubling
+        let budget = StepPolicy::TimeBudget(10.0);
+        for expected in [2, 4, 5, 5] {
+            let steps = scheduler.steps_due(budget, 1.0, later);
+            scheduler.fit_budget(10.0, steps, Duration::from_millis(2 * steps as u64));
+            assert_eq!(scheduler.budget_steps(), expected);
+        }
+        scheduler.fit_budget(10.0, 1, Duration::from_millis(50));
+        assert_eq!(scheduler.budget_steps(), 1);
+        assert_eq!(rate.default_dt(), 0.01);
+    }
+}
@@ -0,0 +1,592 @@
+//! # Parameter Sweeps
+//!
+//! Run a simulation headless over a grid of parameter values and collect
+//! metrics from each run, e.g. the drag of an LBM channel for every
+//! combination of relaxation time and inlet velocity. Each grid point gets a
+//! fresh simulation from a factory closure, is stepped a fixed number of times
+//! without a window, and is then measured by the registered metric closures.
+//! The results can be written as a CSV table with one row per run.
+//!
+//! Sweeps run either on the calling thread with [`ParameterSweep::run`] or in
+//! the background with [`ParameterSweep::spawn`], whose [`SweepHandle`] can
+//! draw a progress window in an app's UI callback. Headless sweeps can print
+//! their progress to the console with [`ParameterSweep::console_progress`].
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::simulation::sweep::{ParameterSweep, SweepAxis};
+//! # use haggis::gfx::scene::Scene;
+//! # struct Channel;
+//! # impl Channel {
+//! #     fn new(_tau: f32, _velocity: f32) -> Self { Channel }
+//! #     fn drag(&self) -> f64 { 0.0 }
+//! # }
+//! # impl haggis::simulation::traits::Simulation for Channel {
+//! #     fn initialize(&mut self, _scene: &mut Scene) {}
+//! #     fn update(&mut self, _dt: f32, _scene: &mut Scene) {}
+//! #     fn render_ui(&mut self, _ui: &imgui::Ui) {}
+//! #     fn name(&self) -> &str { "Channel" }
+//! #     fn is_running(&self) -> bool { true }
+//! #     fn set_running(&mut self, _running: bool) {}
+//! #     fn reset(&mut self, _scene: &mut Scene) {}
+//! #     fn as_any(&self) -> &dyn std::any::Any { self }
+//! # }
+//!
+//! let results = ParameterSweep::new(|point| {
+//!     Channel::new(point.get("tau").unwrap() as f32, point.get("velocity").unwrap() as f32)
+//! })
+//! .axis(SweepAxis::linspace("tau", 0.55, 1.0, 10))
+//! .axis(SweepAxis::new("velocity", vec![0.02, 0.05, 0.1]))
+//! .steps(2000)
+//! .metric("drag", |channel, _scene| channel.drag())
+//! .run();
+//!
+//! results.write_csv("sweep.csv").expect("write sweep results");
+//! ```
+//!
+//! GPU simulations need a device: pass one from [`headless_gpu`] (or the
+//! app's render engine) to [`ParameterSweep::with_gpu`].
+
+use std::fmt::Write as _;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use cgmath::Vector3;
+use wgpu::{Device, Queue};
+
+use super::progress::{
+    format_eta, ConsoleProgress, EtaEstimator, ProgressSnapshot, SimulationProgress,
+};
+use super::traits::Simulation;
+use crate::app::jobs::{JobHandle, JobSystem};
+use crate::error::{HaggisError, Result};
+use crate::gfx::camera::{
+    camera_controller::CameraController, camera_utils::CameraManager, orbit_camera::OrbitCamera,
+};
+use crate::gfx::scene::Scene;
+
+type Factory<S> = Box<dyn Fn(&SweepPoint) -> S + Send + Sync>;
+type Metric<S> = Box<dyn Fn(&S, &Scene) -> f64 + Send + Sync>;
+
+/// One swept parameter and the values it takes
+#[derive(Debug, Clone, PartialEq)]
+pub struct SweepAxis {
+    pub name: String,
+    pub values: Vec<f64>,
+}
+
+impl SweepAxis {
+    /// Axis taking the given values, in order
+    pub fn new(name: impl Into<String>, values: Vec<f64>) -> Self {
+        Self {
+            name: name.into(),
+            values,
+        }
+    }
+
+    /// Axis of `count` evenly spaced values from `start` to `end` inclusive
+    pub fn linspace(name: impl Into<String>, start: f64, end: f64, count: usize) -> Self {
+        let values = match count {
+            0 => Vec::new(),
+            1 => vec![start],
+            _ => (0..count)
+                .map(|i| start + (end - start) * i as f64 / (count - 1) as f64)
+                .collect(),
+        };
+        Self::new(name, values)
+    }
+}
+
+/// Parameter values of one run
+#[derive(Debug, Clone, PartialEq)]
+pub struct SweepPoint {
+    /// Index of the run in the sweep
+    pub index: usize,
+    /// Axis names and values, in axis order
+    pub values: Vec<(String, f64)>,
+}
+
+impl SweepPoint {
+    /// Value of the named axis
+    pub fn get(&self, name: &str) -> Option<f64> {
+        self.values
+            .iter()
+            .find(|(axis, _)| axis == name)
+            .map(|&(_, value)| value)
+    }
+
+    /// Set the matching fields of a parameter struct, clamped to their ranges
+    ///
+    /// Axes without a parameter of the same name are ignored.
+    pub fn apply_to(&self, params: &mut impl super::params::SimParams) {
+        for (name, value) in &self.values {
+            params.set(name, super::params::ParamValue::Float(*value));
+        }
+    }
+
+    /// Axis names and values, e.g. "tau = 0.5500, velocity = 0.0200"
+    pub fn label(&self) -> String {
+        let values: Vec<String> = self
+            .values
+            .iter()
+            .map(|(name, value)| format!("{name} = {value:.4}"))
+            .collect();
+        values.join(", ")
+    }
+}
+
+/// Metrics measured at the end of one run
+#[derive(Debug, Clone, PartialEq)]
+pub struct SweepRun {
+    pub point: SweepPoint,
+    /// One value per metric, in registration order
+    pub metrics: Vec<f64>,
+}
+
+/// Results of a sweep, one run per grid point
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SweepResults {
+    pub axis_names: Vec<String>,
+    pub metric_names: Vec<String>,
+    pub runs: Vec<SweepRun>,
+}
+
+impl SweepResults {
+    /// CSV table with a header row, then the axis values and metrics of each run
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        let header: Vec<String> = self
+            .axis_names
+            .iter()
+            .chain(&self.metric_names)
+            .map(|name| csv_field(name))
+            .collect();
+        csv.push_str(&header.join(","));
+        csv.push('\n');
+
+        for run in &self.runs {
+            let fields: Vec<String> = run
+                .point
+                .values
+                .iter()
+                .map(|(_, value)| value)
+                .chain(&run.metrics)
+                .map(|value| value.to_string())
+                .collect();
+            let _ = writeln!(csv, "{}", fields.join(","));
+        }
+        csv
+    }
+
+    /// Write the results as a CSV file
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::Io`] if the file cannot be written and
+    /// [`HaggisError::NoFileSystem`] on platforms without file access.
+    pub fn write_csv(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if !crate::platform::HAS_FILESYSTEM {
+            return Err(HaggisError::NoFileSystem { path: path.into() });
+        }
+        std::fs::write(path, self.to_csv()).map_err(|source| HaggisError::Io {
+            path: path.into(),
+            source,
+        })
+    }
+}
+
+/// Quote a CSV field if it contains separators or quotes
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Progress of a running sweep
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SweepProgress {
+    /// Runs finished so far
+    pub completed: usize,
+    /// Runs in the whole sweep
+    pub total: usize,
+    /// Point currently being simulated
+    pub current: Option<SweepPoint>,
+}
+
+impl SweepProgress {
+    /// Finished fraction of the sweep, from 0 to 1
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.completed as f32 / self.total as f32
+        }
+    }
+}
+
+impl SimulationProgress for SweepProgress {
+    fn fraction_complete(&self) -> f32 {
+        self.fraction()
+    }
+
+    fn phase_name(&self) -> Option<String> {
+        let point = self.current.as_ref()?;
+        Some(format!(
+            "Run {} / {}: {}",
+            point.index + 1,
+            self.total,
+            point.label()
+        ))
+    }
+}
+
+/// Headless runner for a simulation over a grid of parameter values
+pub struct ParameterSweep<S> {
+    factory: Factory<S>,
+    axes: Vec<SweepAxis>,
+    metrics: Vec<(String, Metric<S>)>,
+    steps: u32,
+    dt: f32,
+    gpu: Option<(Device, Queue)>,
+    /// Print progress lines to stderr while running
+    console: bool,
+}
+
+impl<S: Simulation + 'static> ParameterSweep<S> {
+    /// Sweep creating a fresh simulation for each grid point with `factory`
+    ///
+    /// Defaults to 1000 steps of 1/60 s per run.
+    pub fn new(factory: impl Fn(&SweepPoint) -> S + Send + Sync + 'static) -> Self {
+        Self {
+            factory: Box::new(factory),
+            axes: Vec::new(),
+            metrics: Vec::new(),
+            steps: 1000,
+            dt: 1.0 / 60.0,
+            gpu: None,
+            console: false,
+        }
+    }
+
+    /// Add a swept parameter; runs cover every combination of axis values
+    pub fn axis(mut self, axis: SweepAxis) -> Self {
+        self.axes.push(axis);
+        self
+    }
+
+    /// Number of simulation steps per run
+    pub fn steps(mut self, steps: u32) -> Self {
+        self.steps = steps;
+        self
+    }
+
+    /// Time step passed to the simulation
+    pub fn dt(mut self, dt: f32) -> Self {
+        self.dt = dt;
+        self
+    }
+
+    /// Print a progress bar with the time left to stderr as runs finish
+    pub fn console_progress(mut self, enable: bool) -> Self {
+        self.console = enable;
+        self
+    }
+
+    /// Add a metric measured at the end of each run
+    pub fn metric(
+        mut self,
+        name: impl Into<String>,
+        metric: impl Fn(&S, &Scene) -> f64 + Send + Sync + 'static,
+    ) -> Self {
+        self.metrics.push((name.into(), Box::new(metric)));
+        self
+    }
+
+    /// Run the GPU side of the simulations on this device
+    ///
+    /// Without a device only [`Simulation::update`] is called.
+    pub fn with_gpu(mut self, device: Device, queue: Queue) -> Self {
+        self.gpu = Some((device, queue));
+        self
+    }
+
+    /// Every grid point, with the last axis varying fastest
+    pub fn points(&self) -> Vec<SweepPoint> {
+        let total = self.run_count();
+        (0..total)
+            .map(|index| {
+                let mut rest = index;
+                let mut values = vec![(String::new(), 0.0); self.axes.len()];
+                for (slot, axis) in values.iter_mut().zip(&self.axes).rev() {
+                    *slot = (axis.name.clone(), axis.values[rest % axis.values.len()]);
+                    rest /= axis.values.len();
+                }
+                SweepPoint { index, values }
+            })
+            .collect()
+    }
+
+    /// Number of runs, the product of the axis lengths
+    pub fn run_count(&self) -> usize {
+        self.axes.iter().map(|axis| axis.values.len()).product()
+    }
+
+    /// Run the whole sweep on this thread
+    pub fn run(self) -> SweepResults {
+        let progress = Arc::new(Mutex::new(SweepProgress::default()));
+        self.run_with(&progress, &AtomicBool::new(false))
+    }
+
+    /// Run the sweep on the [`JobSystem`] and return a handle to poll
+    pub fn spawn(self) -> SweepHandle {
+        let progress = Arc::new(Mutex::new(SweepProgress {
+            total: self.run_count(),
+            ..Default::default()
+        }));
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let job = {
+            let progress = progress.clone();
+            let cancelled = cancelled.clone();
+            JobSystem::global().spawn(move || self.run_with(&progress, &cancelled))
+        };
+        SweepHandle {
+            progress,
+            cancelled,
+            job: Some(job),
+            results: None,
+            eta: EtaEstimator::new(),
+        }
+    }
+
+    fn run_with(self, progress: &Mutex<SweepProgress>, cancelled: &AtomicBool) -> SweepResults {
+        let points = self.points();
+        let mut results = SweepResults {
+            axis_names: self.axes.iter().map(|axis| axis.name.clone()).collect(),
+            metric_names: self.metrics.iter().map(|(name, _)| name.clone()).collect(),
+            runs: Vec::with_capacity(points.len()),
+        };
+        *progress.lock().unwrap() = SweepProgress {
+            completed: 0,
+            total: points.len(),
+            current: None,
+        };
+        let mut console = self.console.then(ConsoleProgress::new);
+
+        for point in points {
+            if cancelled.load(Ordering::Relaxed) {
+                tracing::info!(
+                    "Parameter sweep cancelled after {} of {} runs",
+                    results.runs.len(),
+                    progress.lock().unwrap().total
+                );
+                break;
+            }
+            progress.lock().unwrap().current = Some(point.clone());
+            if let Some(console) = &mut console {
+                console.report(&*progress.lock().unwrap());
+            }
+
+            let metrics = self.run_point(&point);
+            results.runs.push(SweepRun { point, metrics });
+
+            let mut progress = progress.lock().unwrap();
+            progress.completed += 1;
+            progress.current = None;
+            if let Some(console) = &mut console {
+                console.report(&*progress);
+            }
+        }
+        results
+    }
+
+    /// Simulate one grid point and measure it
+    fn run_point(&self, point: &SweepPoint) -> Vec<f64> {
+        let camera = OrbitCamera::new(8.0, 0.4, 0.2, Vector3::new(0.0, 0.0, 0.0), 1.0);
+        let mut scene = Scene::new(CameraManager::new(
+            camera,
+            CameraController::new(0.005, 0.1),
+        ));
+
+        let mut simulation = (self.factory)(point);
+        simulation.initialize(&mut scene);
+        if let Some((device, queue)) = &self.gpu {
+            simulation.initialize_gpu(device, queue);
+        }
+        simulation.set_running(true);
+
+        for _ in 0..self.steps {
+            simulation.update(self.dt, &mut scene);
+            if let Some((device, queue)) = &self.gpu {
+                simulation.update_gpu(device, queue, self.dt);
+                simulation.apply_gpu_results_to_scene(device, &mut scene);
+            }
+        }
+
+        let metrics = self
+            .metrics
+            .iter()
+            .map(|(_, metric)| metric(&simulation, &scene))
+            .collect();
+        simulation.cleanup(&mut scene);
+        metrics
+    }
+}
+
+/// Handle to a sweep running in the background
+pub struct SweepHandle {
+    progress: Arc<Mutex<SweepProgress>>,
+    cancelled: Arc<AtomicBool>,
+    job: Option<JobHandle<SweepResults>>,
+    results: Option<SweepResults>,
+    eta: EtaEstimator,
+}
+
+impl SweepHandle {
+    /// Current progress
+    pub fn progress(&self) -> SweepProgress {
+        self.progress.lock().unwrap().clone()
+    }
+
+    /// Stop after the run in progress; finished runs are kept
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the sweep has finished or been cancelled
+    pub fn is_finished(&mut self) -> bool {
+        self.poll();
+        self.results.is_some()
+    }
+
+    /// Results once the sweep has finished
+    pub fn results(&mut self) -> Option<&SweepResults> {
+        self.poll();
+        self.results.as_ref()
+    }
+
+    /// Block until the sweep finishes and return its results
+    pub fn wait(mut self) -> SweepResults {
+        match self.job.take() {
+            Some(job) => job.wait(),
+            None => self.results.take().unwrap_or_default(),
+        }
+    }
+
+    fn poll(&mut self) {
+        if let Some(job) = self.job.take() {
+            match job.try_wait() {
+                Ok(results) => self.results = Some(results),
+                Err(job) => self.job = Some(job),
+            }
+        }
+    }
+
+    /// Draw a progress window with a cancel button
+    ///
+    /// Call from the app's UI callback while the sweep runs.
+    pub fn render_ui(&mut self, ui: &imgui::Ui) {
+        let finished = self.is_finished();
+        let progress = self.progress();
+        let snapshot = ProgressSnapshot::read(&progress, &mut self.eta);
+
+        ui.window("Parameter Sweep")
+            .size([320.0, 0.0], imgui::Condition::FirstUseEver)
+            .always_auto_resize(true)
+            .build(|| {
+                imgui::ProgressBar::new(progress.fraction())
+                    .overlay_text(format!("{} / {} runs", progress.completed, progress.total))
+                    .build(ui);
+
+                if let Some(point) = &progress.current {
+                    ui.text(point.label());
+                }
+                if let (Some(eta), false) = (snapshot.eta, finished) {
+                    ui.text(format!("{} left", format_eta(eta)));
+                }
+
+                if finished {
+                    ui.text("Finished");
+                } else if self.cancelled.load(Ordering::Relaxed) {
+                    ui.text_disabled("Cancelling...");
+                } else if ui.button("Cancel") {
+                    self.cancel();
+                }
+            });
+    }
+}
+
+/// Create a GPU device without a window, for sweeps of GPU simulations
+///
+/// # Errors
+///
+/// Returns [`HaggisError::NoAdapter`] or [`HaggisError::Device`] if no
+/// usable GPU is available.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn headless_gpu() -> Result<(Device, Queue)> {
+    pollster::block_on(async {
+        let instance = wgpu::Instance::default();
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions::default())
+            .await?;
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor {
+                label: Some("Sweep Device"),
+                ..Default::default()
+            })
+            .await?;
+        Ok((device, queue))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::callback::CallbackSimulation;
+
+    fn sweep() -> ParameterSweep<CallbackSimulation> {
+        ParameterSweep::new(|point| {
+            let size = point.get("size").unwrap() as usize;
+            CallbackSimulation::new("Rate", move |_dt, scene| {
+                scene.events.emit(size);
+            })
+        })
+        .axis(SweepAxis::linspace("rate", 0.0, 1.0, 3))
+        .axis(SweepAxis::new("size", vec![1.0, 2.0]))
+        .steps(10)
+        .dt(0.1)
+    }
+
+    #[test]
+    fn test_points_cover_grid() {
+        let points = sweep().points();
+        assert_eq!(points.len(), 6);
+        assert_eq!(
+            points[0].values,
+            vec![("rate".into(), 0.0), ("size".into(), 1.0)]
+        );
+        assert_eq!(points[1].get("size"), Some(2.0));
+        assert_eq!(points[5].get("rate"), Some(1.0));
+        assert_eq!(points[3].get("rate"), Some(0.5));
+    }
+
+    #[test]
+    fn test_run_collects_metrics_as_csv() {
+        let results = sweep()
+            .metric("time", |sim, _scene| sim.time() as f64)
+            .metric("rate, x2", |_sim, _scene| 2.0)
+            .run();
+        assert_eq!(results.runs.len(), 6);
+        assert!((results.runs[0].metrics[0] - 1.0).abs() < 1e-5);
+
+        let csv = results.to_csv();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("rate,size,time,\"rate, x2\""));
+        assert!(lines.next().unwrap().starts_with("0,1,"));
+        assert_eq!(csv.lines().count(), 7);
+    }
+}
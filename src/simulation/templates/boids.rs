@@ -0,0 +1,599 @@
+//! Boids flocking
+//!
+//! Reynolds' boids: every agent steers by three local rules, applied to the
+//! neighbors it sees within its perception radius and field of view:
+//!
+//! - **Separation** - move away from neighbors that are too close
+//! - **Alignment** - match the neighbors' average heading
+//! - **Cohesion** - move towards the neighbors' center
+//!
+//! and turns back before leaving the domain box. Each step runs two compute
+//! passes, one computing the new velocities and one moving the agents, so all
+//! agents steer from the same state. Neighbors are found through a GPU
+//! [`SpatialHash`] rebuilt before every step, so each agent only visits the
+//! agents in the cells around it. The agent buffer is drawn directly by an
+//! [`AgentView`], without a readback.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::simulation::templates::Boids;
+//!
+//! let mut app = haggis::default();
+//! app.attach_simulation(Boids::new(20_000).with_bounds([-10.0, -10.0, 0.0], [10.0, 10.0, 10.0]));
+//! app.run();
+//! ```
+
+use cgmath::Vector3;
+use imgui::Ui;
+use wgpu::util::DeviceExt;
+use wgpu::{Device, Queue};
+
+use crate::gfx::rendering::AgentInstance;
+use crate::gfx::scene::Scene;
+use crate::simulation::agents::Swarm;
+use crate::simulation::gpu::SpatialHash;
+use crate::simulation::params::{ParamsUniform, SimParams};
+use crate::simulation::traits::Simulation;
+use crate::simulation::BaseSimulation;
+use crate::visualization::{AgentView, DomainBox};
+
+const WORKGROUP_SIZE: u32 = 64;
+
+/// Tunable parameters of [`Boids`]
+#[derive(Debug, Clone, SimParams)]
+pub struct BoidsParams {
+    #[param(range = 0.0..=5.0, step = 0.05, label = "Separation")]
+    pub separation_weight: f32,
+    #[param(range = 0.0..=5.0, step = 0.05, label = "Alignment")]
+    pub alignment_weight: f32,
+    #[param(range = 0.0..=5.0, step = 0.05, label = "Cohesion")]
+    pub cohesion_weight: f32,
+    #[param(range = 0.1..=5.0, step = 0.05, label = "Perception Radius")]
+    pub perception_radius: f32,
+    #[param(range = 0.05..=2.0, step = 0.01, label = "Separation Radius")]
+    pub separation_radius: f32,
+    /// Full viewing angle around the heading
+    #[param(range = 30.0..=360.0, step = 5.0, label = "Field of View (deg)")]
+    pub field_of_view: f32,
+    #[param(range = 0.1..=20.0, step = 0.1, label = "Max Speed")]
+    pub max_speed: f32,
+    #[param(range = 0.1..=50.0, step = 0.1, label = "Max Force")]
+    pub max_force: f32,
+    #[param(range = 0.01..=1.0, step = 0.01, label = "Agent Size")]
+    pub agent_size: f32,
+}
+
+impl Default for BoidsParams {
+    fn default() -> Self {
+        Self {
+            separation_weight: 1.5,
+            alignment_weight: 1.0,
+            cohesion_weight: 1.0,
+            perception_radius: 1.0,
+            separation_radius: 0.35,
+            field_of_view: 270.0,
+            max_speed: 4.0,
+            max_force: 8.0,
+            agent_size: 0.15,
+        }
+    }
+}
+
+/// Uniforms of the boids shader
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct BoidsUniforms {
+    count: u32,
+    _padding: [u32; 3],
+    /// Containment margin in `w`
+    bounds_min: [f32; 4],
+    /// Time step in `w`
+    bounds_max: [f32; 4],
+    /// Separation, alignment, cohesion and containment weights
+    weights: [f32; 4],
+    /// Perception radius, separation radius, cosine of half the field of
+    /// view, agent size
+    perception: [f32; 4],
+    /// Max speed, max force, min speed
+    limits: [f32; 4],
+}
+
+struct BoidsGpuResources {
+    steer_pipeline: wgpu::ComputePipeline,
+    integrate_pipeline: wgpu::ComputePipeline,
+    uniforms: ParamsUniform<BoidsUniforms>,
+    /// Hash over the agent positions, bound for the steer pass
+    hash: SpatialHash,
+    /// [`AgentInstance`]s, drawn directly by the agent view
+    agents: wgpu::Buffer,
+    /// Also keeps the buffer of steered velocities alive
+    bind_group: wgpu::BindGroup,
+}
+
+/// GPU boids flock drawn as instanced agents in a domain box
+pub struct Boids {
+    base: BaseSimulation,
+    count: u32,
+    bounds_min: Vector3<f32>,
+    bounds_max: Vector3<f32>,
+    pub params: BoidsParams,
+    running: bool,
+    steps: u64,
+    needs_reset: bool,
+    needs_step: bool,
+    time_step: f32,
+    gpu: Option<BoidsGpuResources>,
+}
+
+impl Boids {
+    /// Flock of `count` boids in the default 16 x 16 x 8 box
+    pub fn new(count: u32) -> Self {
+        assert!(count > 0, "boids flock must not be empty");
+        Self {
+            base: BaseSimulation::new("Boids"),
+            count,
+            bounds_min: Vector3::new(-8.0, -8.0, 0.0),
+            bounds_max: Vector3::new(8.0, 8.0, 8.0),
+            params: BoidsParams::default(),
+            running: true,
+            steps: 0,
+            needs_reset: true,
+            needs_step: false,
+            time_step: 1.0 / 60.0,
+            gpu: None,
+        }
+    }
+
+    /// Domain the boids are kept in
+    pub fn with_bounds(
+        mut self,
+        min: impl Into<Vector3<f32>>,
+        max: impl Into<Vector3<f32>>,
+    ) -> Self {
+        self.bounds_min = min.into();
+        self.bounds_max = max.into();
+        self
+    }
+
+    pub fn with_params(mut self, params: BoidsParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// Number of steps taken since the last reset
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// GPU buffer holding the current [`AgentInstance`]s
+    pub fn agent_buffer(&self) -> Option<&wgpu::Buffer> {
+        self.gpu.as_ref().map(|gpu| &gpu.agents)
+    }
+
+    /// Starting state: random positions in the box, random headings at half speed
+    fn initial_agents(&self) -> Vec<AgentInstance> {
+        let mut swarm = Swarm::new(self.params.perception_radius);
+        swarm.scatter(
+            self.count as usize,
+            self.bounds_min.into(),
+            self.bounds_max.into(),
+            self.params.max_speed * 0.5,
+        );
+        swarm.instances(self.params.agent_size, [0.8, 0.8, 0.8, 1.0])
+    }
+
+    fn uniforms(&self) -> BoidsUniforms {
+        let p = &self.params;
+        let size = self.bounds_max - self.bounds_min;
+        let margin = (size.x.min(size.y).min(size.z) * 0.1).min(p.perception_radius * 2.0);
+        BoidsUniforms {
+            count: self.count,
+            _padding: [0; 3],
+            bounds_min: [
+                self.bounds_min.x,
+                self.bounds_min.y,
+                self.bounds_min.z,
+                margin,
+            ],
+            bounds_max: [
+                self.bounds_max.x,
+                self.bounds_max.y,
+                self.bounds_max.z,
+                self.time_step,
+            ],
+            weights: [
+                p.separation_weight,
+                p.alignment_weight,
+                p.cohesion_weight,
+                2.0,
+            ],
+            perception: [
+                p.perception_radius,
+                p.separation_radius,
+                (p.field_of_view.to_radians() * 0.5).cos(),
+                p.agent_size,
+            ],
+            limits: [p.max_speed, p.max_force, p.max_speed * 0.3, 0.0],
+        }
+    }
+
+    fn create_gpu_resources(&self, device: &Device) -> BoidsGpuResources {
+        let source = format!("{}{}", SpatialHash::query_wgsl(0, 3), BOIDS_SHADER);
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Boids Shader"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+
+        let buffer_entry = |binding: u32, ty: wgpu::BufferBindingType| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let mut layout_entries = vec![
+            buffer_entry(0, wgpu::BufferBindingType::Uniform),
+            buffer_entry(1, wgpu::BufferBindingType::Storage { read_only: false }),
+            buffer_entry(2, wgpu::BufferBindingType::Storage { read_only: false }),
+        ];
+        layout_entries.extend(SpatialHash::bind_group_layout_entries(
+            3,
+            wgpu::ShaderStages::COMPUTE,
+        ));
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Boids Bind Group Layout"),
+            entries: &layout_entries,
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Boids Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |entry_point: &str| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+
+        let agents = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Boids Agents"),
+            contents: bytemuck::cast_slice(&self.initial_agents()),
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::VERTEX
+                | wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::COPY_SRC,
+        });
+        let velocities = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Boids Steered Velocities"),
+            size: self.count as u64 * std::mem::size_of::<[f32; 4]>() as u64,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let uniforms = ParamsUniform::new(device, "Boids Uniforms", &self.uniforms());
+        let hash = SpatialHash::new(
+            device,
+            &agents,
+            std::mem::size_of::<AgentInstance>() as u64,
+            self.count,
+            self.params.perception_radius,
+        );
+
+        let mut entries = vec![
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniforms.buffer().as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: agents.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: velocities.as_entire_binding(),
+            },
+        ];
+        entries.extend(hash.bind_group_entries(3));
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Boids Bind Group"),
+            layout: &bind_group_layout,
+            entries: &entries,
+        });
+
+        BoidsGpuResources {
+            steer_pipeline: pipeline("steer"),
+            integrate_pipeline: pipeline("integrate"),
+            uniforms,
+            hash,
+            agents,
+            bind_group,
+        }
+    }
+}
+
+impl Simulation for Boids {
+    fn initialize(&mut self, scene: &mut Scene) {
+        self.base.initialize(scene);
+    }
+
+    fn initialize_gpu(&mut self, device: &Device, queue: &Queue) {
+        let gpu = self.create_gpu_resources(device);
+        // The buffer starts out with a fresh flock
+        self.needs_reset = false;
+        self.steps = 0;
+
+        let mut view = AgentView::new();
+        view.set_gpu_buffer(gpu.agents.clone(), self.count);
+
+        self.gpu = Some(gpu);
+        self.base.remove_visualization("agents");
+        self.base.remove_visualization("bounds");
+        self.base.add_visualization("agents", view);
+        self.base
+            .add_visualization("bounds", DomainBox::new(self.bounds_min, self.bounds_max));
+        self.base.initialize_gpu(device, queue);
+    }
+
+    fn update(&mut self, delta_time: f32, scene: &mut Scene) {
+        self.base.update(delta_time, scene);
+    }
+
+    fn update_gpu(&mut self, device: &Device, queue: &Queue, delta_time: f32) {
+        self.time_step = delta_time;
+        let uniforms = self.uniforms();
+        let reset_agents = self.needs_reset.then(|| self.initial_agents());
+        if let Some(gpu) = self.gpu.as_mut() {
+            gpu.uniforms.update(queue, &uniforms);
+            gpu.hash.set_cell_size(self.params.perception_radius);
+
+            if let Some(agents) = reset_agents {
+                queue.write_buffer(&gpu.agents, 0, bytemuck::cast_slice(&agents));
+                self.needs_reset = false;
+            }
+
+            if self.running || self.needs_step {
+                let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Boids Encoder"),
+                });
+                gpu.hash.build(queue, &mut encoder);
+                {
+                    crate::logging::crash::record_pass("Boids Step");
+                    let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                        label: Some("Boids Step"),
+                        timestamp_writes: None,
+                    });
+                    let groups = self.count.div_ceil(WORKGROUP_SIZE);
+                    pass.set_bind_group(0, &gpu.bind_group, &[]);
+                    pass.set_pipeline(&gpu.steer_pipeline);
+                    pass.dispatch_workgroups(groups, 1, 1);
+                    pass.set_pipeline(&gpu.integrate_pipeline);
+                    pass.dispatch_workgroups(groups, 1, 1);
+                }
+                queue.submit(std::iter::once(encoder.finish()));
+                self.steps += 1;
+            }
+            self.needs_step = false;
+        }
+
+        self.base.update_gpu(device, queue, delta_time);
+    }
+
+    fn apply_gpu_results_to_scene(&mut self, device: &Device, scene: &mut Scene) {
+        self.base.apply_gpu_results_to_scene(device, scene);
+    }
+
+    fn render_ui(&mut self, ui: &Ui) {
+        ui.window("Boids")
+            .size([360.0, 400.0], imgui::Condition::FirstUseEver)
+            .build(|| {
+                ui.text(format!("Agents: {}", self.count));
+                ui.text(format!("Steps: {}", self.steps));
+                ui.separator();
+
+                if ui.button(if self.running { "Pause" } else { "Play" }) {
+                    self.running = !self.running;
+                }
+                ui.same_line();
+                if ui.button("Step") {
+                    self.needs_step = true;
+                }
+                ui.same_line();
+                if ui.button("Reset") {
+                    self.needs_reset = true;
+                    self.steps = 0;
+                }
+
+                ui.separator();
+                self.params.build_ui(ui);
+            });
+
+        self.base.render_ui(ui);
+    }
+
+    fn name(&self) -> &str {
+        "Boids"
+    }
+
+    fn is_running(&self) -> bool {
+        self.running
+    }
+
+    fn set_running(&mut self, running: bool) {
+        self.running = running;
+    }
+
+    fn reset(&mut self, scene: &mut Scene) {
+        self.needs_reset = true;
+        self.steps = 0;
+        self.base.reset(scene);
+    }
+
+    fn is_gpu_ready(&self) -> bool {
+        self.gpu.is_some()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        &self.base
+    }
+}
+
+const BOIDS_SHADER: &str = r#"
+struct Agent {
+    position: vec4<f32>, // size in w
+    velocity: vec4<f32>,
+    color: vec4<f32>,
+}
+
+struct BoidsUniforms {
+    count: u32,
+    _padding0: u32,
+    _padding1: u32,
+    _padding2: u32,
+    bounds_min: vec4<f32>, // margin in w
+    bounds_max: vec4<f32>, // time step in w
+    weights: vec4<f32>,    // separation, alignment, cohesion, containment
+    perception: vec4<f32>, // radius, separation radius, cos(fov / 2), size
+    limits: vec4<f32>,     // max speed, max force, min speed
+}
+
+@group(0) @binding(0) var<uniform> params: BoidsUniforms;
+@group(0) @binding(1) var<storage, read_write> agents: array<Agent>;
+@group(0) @binding(2) var<storage, read_write> steered: array<vec4<f32>>;
+
+fn limit(v: vec3<f32>, max_length: f32) -> vec3<f32> {
+    let length2 = dot(v, v);
+    if (length2 > max_length * max_length) {
+        return v * (max_length / sqrt(length2));
+    }
+    return v;
+}
+
+// Force turning `velocity` towards full speed along `direction`
+fn steer_towards(direction: vec3<f32>, velocity: vec3<f32>) -> vec3<f32> {
+    if (dot(direction, direction) < 1e-12) {
+        return vec3<f32>(0.0);
+    }
+    return limit(normalize(direction) * params.limits.x - velocity, params.limits.y);
+}
+
+@compute @workgroup_size(64)
+fn steer(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if (index >= params.count) {
+        return;
+    }
+    let p = agents[index].position.xyz;
+    let v = agents[index].velocity.xyz;
+    let speed = length(v);
+    let radius2 = params.perception.x * params.perception.x;
+    let separation2 = params.perception.y * params.perception.y;
+    let fov_cos = params.perception.z;
+
+    var neighbors = 0u;
+    var sum_position = vec3<f32>(0.0);
+    var sum_velocity = vec3<f32>(0.0);
+    var away = vec3<f32>(0.0);
+
+    let keys = spatial_hash_neighbor_keys(p);
+    for (var c = 0u; c < 27u; c++) {
+        let range = spatial_hash_range(keys[c]);
+        for (var k = range.x; k < range.y; k++) {
+            let j = spatial_hash_sorted[k];
+            if (j == index) {
+                continue;
+            }
+            let other = agents[j];
+            let offset = other.position.xyz - p;
+            let distance2 = dot(offset, offset);
+            if (distance2 > radius2 || distance2 < 1e-12) {
+                continue;
+            }
+            if (speed > 1e-6 && dot(v, offset) < fov_cos * speed * sqrt(distance2)) {
+                continue;
+            }
+            neighbors++;
+            sum_position += other.position.xyz;
+            sum_velocity += other.velocity.xyz;
+            if (distance2 < separation2) {
+                away -= offset / distance2;
+            }
+        }
+    }
+
+    var force = vec3<f32>(0.0);
+    if (neighbors > 0u) {
+        force += params.weights.x * steer_towards(away, v);
+        force += params.weights.y * steer_towards(sum_velocity, v);
+        force += params.weights.z * steer_towards(sum_position / f32(neighbors) - p, v);
+    }
+
+    // Turn back near the walls
+    let margin = params.bounds_min.w;
+    let inward = select(vec3<f32>(0.0), vec3<f32>(1.0), p < params.bounds_min.xyz + margin)
+        - select(vec3<f32>(0.0), vec3<f32>(1.0), p > params.bounds_max.xyz - margin);
+    force += params.weights.w * steer_towards(inward, v);
+    force = limit(force, params.limits.y);
+
+    var velocity = limit(v + force * params.bounds_max.w, params.limits.x);
+    let new_speed = length(velocity);
+    if (new_speed < params.limits.z) {
+        velocity = select(vec3<f32>(params.limits.z, 0.0, 0.0), velocity / new_speed * params.limits.z, new_speed > 1e-6);
+    }
+
+    steered[index] = vec4<f32>(velocity, 0.0);
+}
+
+@compute @workgroup_size(64)
+fn integrate(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if (i >= params.count) {
+        return;
+    }
+    var velocity = steered[i].xyz;
+    var position = agents[i].position.xyz + velocity * params.bounds_max.w;
+
+    // Bounce off the walls if steering wasn't enough
+    let lower = params.bounds_min.xyz;
+    let upper = params.bounds_max.xyz;
+    velocity = select(velocity, abs(velocity), position < lower);
+    velocity = select(velocity, -abs(velocity), position > upper);
+    position = clamp(position, lower, upper);
+
+    let heading = velocity / max(length(velocity), 1e-6);
+    agents[i].position = vec4<f32>(position, params.perception.w);
+    agents[i].velocity = vec4<f32>(velocity, 0.0);
+    agents[i].color = vec4<f32>(0.55 + 0.4 * heading, 1.0);
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_initial_agents_fill_the_bounds() {
+        let boids = Boids::new(500).with_bounds([0.0, 0.0, 0.0], [2.0, 3.0, 4.0]);
+        let agents = boids.initial_agents();
+        assert_eq!(agents.len(), 500);
+        for agent in &agents {
+            let p = agent.position;
+            assert!((0.0..=2.0).contains(&p[0]));
+            assert!((0.0..=3.0).contains(&p[1]));
+            assert!((0.0..=4.0).contains(&p[2]));
+            assert_eq!(p[3], boids.params.agent_size);
+        }
+        assert_eq!(std::mem::size_of::<BoidsUniforms>(), 96);
+    }
+}
@@ -0,0 +1,838 @@
+//! Cloth simulation on a dynamic scene mesh
+//!
+//! A grid of particles connected by structural, shear and bend constraints,
+//! solved with position-based dynamics on the GPU: each substep integrates
+//! gravity and wind with Verlet steps, relaxes the constraints with Jacobi
+//! iterations and pushes particles out of sphere colliders.
+//!
+//! The cloth is a regular scene object. Every frame the particle positions
+//! are read back and written into its mesh with [`Mesh::set_positions`], so it
+//! is lit, shadowed and picked like any other object.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::simulation::templates::Cloth;
+//!
+//! let mut app = haggis::default();
+//! app.add_sphere(32, 16)
+//!     .with_name("ball")
+//!     .with_position([0.0, 0.3, 0.6])
+//!     .with_scale(0.3);
+//! app.attach_simulation(
+//!     Cloth::new([2.0, 1.5], [40, 30])
+//!         .at([0.0, 0.0, 1.5])
+//!         .pin_top_corners()
+//!         .with_sphere_collider("ball", 1.0),
+//! );
+//! app.run();
+//! ```
+//!
+//! [`Mesh::set_positions`]: crate::gfx::scene::object::Mesh::set_positions
+
+use cgmath::InnerSpace;
+use imgui::Ui;
+use wgpu::{Device, Queue};
+
+use crate::gfx::geometry::GeometryData;
+use crate::gfx::scene::Scene;
+use crate::simulation::params::{ParamsUniform, SimParams};
+use crate::simulation::traits::Simulation;
+
+const WORKGROUP_SIZE: u32 = 64;
+
+/// Colliders passed to the shader; more are ignored
+const MAX_COLLIDERS: usize = 8;
+
+/// Orientation of the cloth before it starts moving
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClothPlane {
+    /// Hanging in the XZ plane, first row at the top, like a curtain
+    #[default]
+    Vertical,
+    /// Lying flat in the XY plane, like a sheet about to drop
+    Horizontal,
+}
+
+/// Sphere the cloth can't enter
+#[derive(Debug, Clone, PartialEq)]
+pub struct SphereCollider {
+    /// Scene object the sphere follows; `None` for a fixed sphere
+    pub object: Option<String>,
+    pub center: [f32; 3],
+    /// Radius, multiplied by the object's scale when following an object
+    pub radius: f32,
+    /// Scale of the followed object
+    scale: f32,
+}
+
+/// Tunable parameters of [`Cloth`]
+#[derive(Debug, Clone, SimParams)]
+pub struct ClothParams {
+    #[param(range = 0.0..=1.0, step = 0.01)]
+    pub stiffness: f32,
+    /// Share of the velocity kept each substep
+    #[param(range = 0.9..=1.0, step = 0.001)]
+    pub damping: f32,
+    #[param(range = -20.0..=0.0, step = 0.1)]
+    pub gravity: f32,
+    #[param(range = 0.0..=20.0, step = 0.1, label = "Wind Strength")]
+    pub wind_strength: f32,
+    /// How much the wind gusts over time, relative to its strength
+    #[param(range = 0.0..=1.0, step = 0.01)]
+    pub turbulence: f32,
+    #[param(range = 1..=32)]
+    pub substeps: u32,
+    /// Constraint iterations per substep, rounded up to an even number
+    #[param(range = 2..=64, label = "Solver Iterations")]
+    pub iterations: u32,
+    /// Direction the wind blows in (normalized on upload)
+    #[param(skip)]
+    pub wind_direction: [f32; 3],
+}
+
+impl Default for ClothParams {
+    fn default() -> Self {
+        Self {
+            stiffness: 0.9,
+            damping: 0.995,
+            gravity: -9.81,
+            wind_strength: 2.0,
+            turbulence: 0.5,
+            substeps: 8,
+            iterations: 8,
+            wind_direction: [0.0, 1.0, 0.0],
+        }
+    }
+}
+
+/// Uniforms of the cloth shader
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct ClothUniforms {
+    columns: u32,
+    rows: u32,
+    collider_count: u32,
+    time_step: f32,
+    /// Structural, shear and bend rest lengths, stiffness
+    rest: [f32; 4],
+    /// Gravity along Z in `z`, velocity damping in `w`
+    gravity: [f32; 4],
+    /// Wind direction times strength, time in `w`
+    wind: [f32; 4],
+    /// Turbulence in `x`
+    extra: [f32; 4],
+    /// Center and radius
+    colliders: [[f32; 4]; MAX_COLLIDERS],
+}
+
+struct ClothGpuResources {
+    integrate_pipeline: wgpu::ComputePipeline,
+    constrain_pipeline: wgpu::ComputePipeline,
+    collide_pipeline: wgpu::ComputePipeline,
+    uniforms: ParamsUniform<ClothUniforms>,
+    /// Current positions, inverse mass in `w`
+    positions: wgpu::Buffer,
+    previous: wgpu::Buffer,
+    staging: wgpu::Buffer,
+    a_to_b: wgpu::BindGroup,
+    b_to_a: wgpu::BindGroup,
+}
+
+/// GPU position-based cloth driving a scene mesh
+pub struct Cloth {
+    object_name: String,
+    material: Option<String>,
+    color: [f32; 4],
+    size: [f32; 2],
+    columns: u32,
+    rows: u32,
+    origin: [f32; 3],
+    plane: ClothPlane,
+    pinned: Vec<u32>,
+    colliders: Vec<SphereCollider>,
+    pub params: ClothParams,
+    running: bool,
+    time: f32,
+    /// Latest positions read back from the GPU, waiting to go into the mesh
+    readback: Option<Vec<[f32; 3]>>,
+    needs_upload: bool,
+    gpu: Option<ClothGpuResources>,
+}
+
+impl Cloth {
+    /// Create a cloth of `size` world units with `resolution` particles per side
+    pub fn new(size: [f32; 2], resolution: [u32; 2]) -> Self {
+        assert!(
+            resolution[0] >= 2 && resolution[1] >= 2,
+            "cloth needs at least 2x2 particles"
+        );
+        Self {
+            object_name: "Cloth".to_string(),
+            material: None,
+            color: [0.8, 0.25, 0.2, 1.0],
+            size,
+            columns: resolution[0],
+            rows: resolution[1],
+            origin: [0.0, 0.0, 1.5],
+            plane: ClothPlane::default(),
+            pinned: Vec::new(),
+            colliders: Vec::new(),
+            params: ClothParams::default(),
+            running: true,
+            time: 0.0,
+            readback: None,
+            needs_upload: true,
+            gpu: None,
+        }
+    }
+
+    /// Center of the top edge of a vertical cloth, or of a horizontal one
+    pub fn at(mut self, origin: [f32; 3]) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    pub fn with_plane(mut self, plane: ClothPlane) -> Self {
+        self.plane = plane;
+        self
+    }
+
+    /// Name of the scene object holding the cloth mesh
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.object_name = name.to_string();
+        self
+    }
+
+    /// Use an existing material instead of creating one from the color
+    pub fn with_material(mut self, material_id: &str) -> Self {
+        self.material = Some(material_id.to_string());
+        self
+    }
+
+    pub fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_params(mut self, params: ClothParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Hold the particle in `column`, `row` in place
+    pub fn pin(mut self, column: u32, row: u32) -> Self {
+        if column < self.columns && row < self.rows {
+            self.pinned.push(row * self.columns + column);
+        }
+        self
+    }
+
+    /// Pin both ends of the first row
+    pub fn pin_top_corners(self) -> Self {
+        let last = self.columns - 1;
+        self.pin(0, 0).pin(last, 0)
+    }
+
+    /// Pin the whole first row
+    pub fn pin_top_edge(mut self) -> Self {
+        self.pinned.extend(0..self.columns);
+        self
+    }
+
+    /// Keep the cloth out of a sphere following the scene object `object`
+    ///
+    /// `radius` is scaled with the object, so `1.0` matches the sphere primitive.
+    pub fn with_sphere_collider(mut self, object: &str, radius: f32) -> Self {
+        self.colliders.push(SphereCollider {
+            object: Some(object.to_string()),
+            center: [0.0; 3],
+            radius,
+            scale: 1.0,
+        });
+        self
+    }
+
+    /// Keep the cloth out of a fixed sphere
+    pub fn with_static_sphere(mut self, center: [f32; 3], radius: f32) -> Self {
+        self.colliders.push(SphereCollider {
+            object: None,
+            center,
+            radius,
+            scale: 1.0,
+        });
+        self
+    }
+
+    /// Spacing between neighboring particles along each side
+    fn spacing(&self) -> [f32; 2] {
+        [
+            self.size[0] / (self.columns - 1) as f32,
+            self.size[1] / (self.rows - 1) as f32,
+        ]
+    }
+
+    /// Rest positions, row by row from the first row
+    fn rest_positions(&self) -> Vec<[f32; 3]> {
+        let [dx, dy] = self.spacing();
+        let mut positions = Vec::with_capacity((self.columns * self.rows) as usize);
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let u = column as f32 * dx - 0.5 * self.size[0];
+                let v = row as f32 * dy;
+                let [x, y, z] = self.origin;
+                positions.push(match self.plane {
+                    ClothPlane::Vertical => [x + u, y, z - v],
+                    ClothPlane::Horizontal => [x + u, y - v + 0.5 * self.size[1], z],
+                });
+            }
+        }
+        positions
+    }
+
+    /// Mesh over the particle grid
+    fn geometry(&self) -> GeometryData {
+        let mut geometry = GeometryData::new();
+        let normal = match self.plane {
+            ClothPlane::Vertical => [0.0, -1.0, 0.0],
+            ClothPlane::Horizontal => [0.0, 0.0, 1.0],
+        };
+        for (i, position) in self.rest_positions().into_iter().enumerate() {
+            let i = i as u32;
+            geometry.vertices.push(position);
+            geometry.normals.push(normal);
+            geometry.tex_coords.push([
+                (i % self.columns) as f32 / (self.columns - 1) as f32,
+                (i / self.columns) as f32 / (self.rows - 1) as f32,
+            ]);
+        }
+        for row in 0..self.rows - 1 {
+            for column in 0..self.columns - 1 {
+                let i = row * self.columns + column;
+                let below = i + self.columns;
+                geometry.indices.extend_from_slice(&[i, i + 1, below, below, i + 1, below + 1]);
+            }
+        }
+        geometry
+    }
+
+    /// Particles with inverse mass in `w`, zero for pinned ones
+    fn initial_particles(&self) -> Vec<[f32; 4]> {
+        self.rest_positions()
+            .into_iter()
+            .enumerate()
+            .map(|(i, [x, y, z])| {
+                let inverse_mass = if self.pinned.contains(&(i as u32)) {
+                    0.0
+                } else {
+                    1.0
+                };
+                [x, y, z, inverse_mass]
+            })
+            .collect()
+    }
+
+    fn uniforms(&self) -> ClothUniforms {
+        let [dx, dy] = self.spacing();
+        let structural = 0.5 * (dx + dy);
+        let direction = cgmath::Vector3::from(self.params.wind_direction);
+        let wind = if direction.magnitude2() > 0.0 {
+            direction.normalize() * self.params.wind_strength
+        } else {
+            direction
+        };
+
+        let mut colliders = [[0.0; 4]; MAX_COLLIDERS];
+        for (slot, collider) in colliders.iter_mut().zip(&self.colliders) {
+            let [x, y, z] = collider.center;
+            *slot = [x, y, z, collider.radius * collider.scale];
+        }
+
+        ClothUniforms {
+            columns: self.columns,
+            rows: self.rows,
+            collider_count: self.colliders.len().min(MAX_COLLIDERS) as u32,
+            time_step: 0.0,
+            rest: [
+                structural,
+                (dx * dx + dy * dy).sqrt(),
+                2.0 * structural,
+                self.params.stiffness,
+            ],
+            gravity: [0.0, 0.0, self.params.gravity, self.params.damping],
+            wind: [wind.x, wind.y, wind.z, self.time],
+            extra: [self.params.turbulence, 0.0, 0.0, 0.0],
+            colliders,
+        }
+    }
+
+    fn create_gpu_resources(&self, device: &Device) -> ClothGpuResources {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Cloth Shader"),
+            source: wgpu::ShaderSource::Wgsl(CLOTH_SHADER.into()),
+        });
+
+        let storage_entry = |binding: u32, read_only: bool| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Cloth Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage_entry(1, true),
+                storage_entry(2, false),
+                storage_entry(3, false),
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Cloth Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |entry_point: &str| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+
+        let size = (self.columns * self.rows) as u64 * std::mem::size_of::<[f32; 4]>() as u64;
+        let particle_buffer = |label: &str| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size,
+                usage: wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_DST
+                    | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            })
+        };
+        let positions = particle_buffer("Cloth Positions A");
+        // Second half of the ping-pong pair, kept alive by the bind groups
+        let scratch = particle_buffer("Cloth Positions B");
+        let previous = particle_buffer("Cloth Previous Positions");
+        let staging = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Cloth Staging Buffer"),
+            size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let uniforms = ParamsUniform::new(device, "Cloth Uniforms", &self.uniforms());
+
+        let bind_group = |label: &str, src: &wgpu::Buffer, dst: &wgpu::Buffer| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout: &bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: uniforms.buffer().as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: src.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: dst.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: previous.as_entire_binding(),
+                    },
+                ],
+            })
+        };
+        let a_to_b = bind_group("Cloth A->B", &positions, &scratch);
+        let b_to_a = bind_group("Cloth B->A", &scratch, &positions);
+
+        ClothGpuResources {
+            integrate_pipeline: pipeline("integrate"),
+            constrain_pipeline: pipeline("constrain"),
+            collide_pipeline: pipeline("collide"),
+            uniforms,
+            positions,
+            previous,
+            staging,
+            a_to_b,
+            b_to_a,
+        }
+    }
+
+    /// Copy the positions to the staging buffer and wait for them
+    fn read_positions(gpu: &ClothGpuResources, device: &Device) -> Option<Vec<[f32; 3]>> {
+        let slice = gpu.staging.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        let _ = device.poll(wgpu::MaintainBase::Wait);
+
+        let positions = match receiver.recv() {
+            Ok(Ok(())) => {
+                let data = slice.get_mapped_range();
+                let particles: &[[f32; 4]] = bytemuck::cast_slice(&data);
+                Some(particles.iter().map(|p| [p[0], p[1], p[2]]).collect())
+            }
+            _ => None,
+        };
+        gpu.staging.unmap();
+        positions
+    }
+}
+
+impl Simulation for Cloth {
+    fn initialize(&mut self, scene: &mut Scene) {
+        scene.add_procedural_object(self.geometry(), &self.object_name);
+        let material = match &self.material {
+            Some(material) => material.clone(),
+            None => {
+                let material = format!("{}_material", self.object_name);
+                let [r, g, b, _] = self.color;
+                scene.add_material_rgb(&material, r, g, b, 0.0, 0.8);
+                material
+            }
+        };
+        if let Some(object) = scene.objects.last_mut() {
+            object.set_material(&material);
+        }
+    }
+
+    fn initialize_gpu(&mut self, device: &Device, _queue: &Queue) {
+        self.gpu = Some(self.create_gpu_resources(device));
+        self.needs_upload = true;
+    }
+
+    fn update(&mut self, _delta_time: f32, scene: &mut Scene) {
+        // Follow the collider objects
+        for collider in &mut self.colliders {
+            let Some(name) = &collider.object else {
+                continue;
+            };
+            if let Some(object) = scene.objects.iter().find(|object| &object.name == name) {
+                collider.center = object.transform.w.truncate().into();
+                collider.scale = object.transform.x.truncate().magnitude();
+            }
+        }
+    }
+
+    fn update_gpu(&mut self, device: &Device, queue: &Queue, delta_time: f32) {
+        if self.running {
+            self.time += delta_time;
+        }
+
+        let mut uniforms = self.uniforms();
+        let substeps = self.params.substeps.max(1);
+        uniforms.time_step = delta_time / substeps as f32;
+        let particles = self.needs_upload.then(|| self.initial_particles());
+
+        let Some(gpu) = self.gpu.as_mut() else {
+            return;
+        };
+        gpu.uniforms.update(queue, &uniforms);
+
+        if let Some(particles) = particles {
+            queue.write_buffer(&gpu.positions, 0, bytemuck::cast_slice(&particles));
+            queue.write_buffer(&gpu.previous, 0, bytemuck::cast_slice(&particles));
+            self.needs_upload = false;
+        }
+
+        if !self.running {
+            return;
+        }
+
+        let groups = (self.columns * self.rows).div_ceil(WORKGROUP_SIZE);
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Cloth Encoder"),
+        });
+        {
+            crate::logging::crash::record_pass("Cloth Step");
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Cloth Step"),
+                timestamp_writes: None,
+            });
+            for _ in 0..substeps {
+                pass.set_pipeline(&gpu.integrate_pipeline);
+                pass.set_bind_group(0, &gpu.a_to_b, &[]);
+                pass.dispatch_workgroups(groups, 1, 1);
+
+                // Pairs of Jacobi sweeps so the result stays in the scratch buffer
+                pass.set_pipeline(&gpu.constrain_pipeline);
+                for _ in 0..self.params.iterations.div_ceil(2) {
+                    pass.set_bind_group(0, &gpu.b_to_a, &[]);
+                    pass.dispatch_workgroups(groups, 1, 1);
+                    pass.set_bind_group(0, &gpu.a_to_b, &[]);
+                    pass.dispatch_workgroups(groups, 1, 1);
+                }
+
+                pass.set_pipeline(&gpu.collide_pipeline);
+                pass.set_bind_group(0, &gpu.b_to_a, &[]);
+                pass.dispatch_workgroups(groups, 1, 1);
+            }
+        }
+        encoder.copy_buffer_to_buffer(&gpu.positions, 0, &gpu.staging, 0, gpu.staging.size());
+        queue.submit(std::iter::once(encoder.finish()));
+
+        self.readback = Self::read_positions(gpu, device);
+    }
+
+    fn apply_gpu_results_to_scene(&mut self, _device: &Device, scene: &mut Scene) {
+        let Some(positions) = self.readback.take() else {
+            return;
+        };
+        if let Some(object) = scene
+            .objects
+            .iter_mut()
+            .find(|object| object.name == self.object_name)
+        {
+            if let Some(mesh) = object.meshes.first_mut() {
+                mesh.set_positions(&positions);
+            }
+        }
+    }
+
+    fn render_ui(&mut self, ui: &Ui) {
+        ui.window("Cloth")
+            .size([340.0, 380.0], imgui::Condition::FirstUseEver)
+            .build(|| {
+                ui.text(format!(
+                    "{}x{} particles, {} pinned",
+                    self.columns,
+                    self.rows,
+                    self.pinned.len()
+                ));
+                ui.text(format!("Colliders: {}", self.colliders.len()));
+                ui.separator();
+
+                if ui.button(if self.running { "Pause" } else { "Play" }) {
+                    self.running = !self.running;
+                }
+                ui.same_line();
+                if ui.button("Reset") {
+                    self.needs_upload = true;
+                    self.time = 0.0;
+                }
+
+                ui.separator();
+                self.params.build_ui(ui);
+                ui.input_float3("Wind Direction", &mut self.params.wind_direction)
+                    .build();
+            });
+    }
+
+    fn name(&self) -> &str {
+        "Cloth"
+    }
+
+    fn is_running(&self) -> bool {
+        self.running
+    }
+
+    fn set_running(&mut self, running: bool) {
+        self.running = running;
+    }
+
+    fn reset(&mut self, _scene: &mut Scene) {
+        self.needs_upload = true;
+        self.time = 0.0;
+    }
+
+    fn cleanup(&mut self, scene: &mut Scene) {
+        if let Some(index) = scene
+            .objects
+            .iter()
+            .position(|object| object.name == self.object_name)
+        {
+            scene.remove_object(index);
+        }
+    }
+
+    fn is_gpu_ready(&self) -> bool {
+        self.gpu.is_some()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+const CLOTH_SHADER: &str = r#"
+struct ClothUniforms {
+    columns: u32,
+    rows: u32,
+    collider_count: u32,
+    time_step: f32,
+    rest: vec4<f32>,    // structural, shear, bend, stiffness
+    gravity: vec4<f32>, // acceleration, damping in w
+    wind: vec4<f32>,    // wind vector, time in w
+    extra: vec4<f32>,   // turbulence in x
+    colliders: array<vec4<f32>, 8>,
+}
+
+@group(0) @binding(0) var<uniform> params: ClothUniforms;
+@group(0) @binding(1) var<storage, read> src: array<vec4<f32>>;
+@group(0) @binding(2) var<storage, read_write> dst: array<vec4<f32>>;
+@group(0) @binding(3) var<storage, read_write> previous: array<vec4<f32>>;
+
+fn particle_index(column: i32, row: i32) -> i32 {
+    if (column < 0 || row < 0 || column >= i32(params.columns) || row >= i32(params.rows)) {
+        return -1;
+    }
+    return row * i32(params.columns) + column;
+}
+
+fn surface_normal(column: i32, row: i32) -> vec3<f32> {
+    let left = particle_index(max(column - 1, 0), row);
+    let right = particle_index(min(column + 1, i32(params.columns) - 1), row);
+    let up = particle_index(column, max(row - 1, 0));
+    let down = particle_index(column, min(row + 1, i32(params.rows) - 1));
+    let n = cross(src[right].xyz - src[left].xyz, src[down].xyz - src[up].xyz);
+    let length_n = length(n);
+    if (length_n < 1e-6) {
+        return vec3<f32>(0.0);
+    }
+    return n / length_n;
+}
+
+@compute @workgroup_size(64)
+fn integrate(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if (i >= params.columns * params.rows) {
+        return;
+    }
+    let p = src[i];
+    if (p.w == 0.0) {
+        dst[i] = p;
+        return;
+    }
+
+    let column = i32(i % params.columns);
+    let row = i32(i / params.columns);
+    let time = params.wind.w;
+    let gust = 1.0 + params.extra.x * sin(time * 2.3 + f32(column) * 0.15) * sin(time * 1.1 + f32(row) * 0.1);
+    let n = surface_normal(column, row);
+    // Wind pushes along the normal, in proportion to how much it faces the wind
+    let wind_force = n * dot(n, params.wind.xyz * gust);
+
+    let velocity = (p.xyz - previous[i].xyz) * params.gravity.w;
+    let acceleration = params.gravity.xyz + wind_force * p.w;
+    let dt = params.time_step;
+    previous[i] = p;
+    dst[i] = vec4<f32>(p.xyz + velocity + acceleration * dt * dt, p.w);
+}
+
+@compute @workgroup_size(64)
+fn constrain(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if (i >= params.columns * params.rows) {
+        return;
+    }
+    let p = src[i];
+    if (p.w == 0.0) {
+        dst[i] = p;
+        return;
+    }
+
+    let column = i32(i % params.columns);
+    let row = i32(i / params.columns);
+    let offsets = array<vec3<i32>, 12>(
+        vec3<i32>(1, 0, 0), vec3<i32>(-1, 0, 0), vec3<i32>(0, 1, 0), vec3<i32>(0, -1, 0),
+        vec3<i32>(1, 1, 1), vec3<i32>(-1, -1, 1), vec3<i32>(1, -1, 1), vec3<i32>(-1, 1, 1),
+        vec3<i32>(2, 0, 2), vec3<i32>(-2, 0, 2), vec3<i32>(0, 2, 2), vec3<i32>(0, -2, 2),
+    );
+
+    var correction = vec3<f32>(0.0);
+    var count = 0.0;
+    for (var k = 0u; k < 12u; k++) {
+        let offset = offsets[k];
+        let j = particle_index(column + offset.x, row + offset.y);
+        if (j < 0) {
+            continue;
+        }
+        let q = src[j];
+        let total_inverse_mass = p.w + q.w;
+        let delta = q.xyz - p.xyz;
+        let distance = length(delta);
+        if (total_inverse_mass == 0.0 || distance < 1e-6) {
+            continue;
+        }
+        let rest = params.rest[offset.z];
+        correction += (p.w / total_inverse_mass) * (distance - rest) * delta / distance;
+        count += 1.0;
+    }
+
+    // Over-relaxed Jacobi average of all constraint corrections
+    if (count > 0.0) {
+        let stiffness = params.rest.w;
+        dst[i] = vec4<f32>(p.xyz + correction * (1.5 * stiffness / count), p.w);
+    } else {
+        dst[i] = p;
+    }
+}
+
+@compute @workgroup_size(64)
+fn collide(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if (i >= params.columns * params.rows) {
+        return;
+    }
+    var p = src[i];
+    if (p.w != 0.0) {
+        for (var c = 0u; c < params.collider_count; c++) {
+            let sphere = params.colliders[c];
+            let radius = sphere.w * 1.02;
+            let offset = p.xyz - sphere.xyz;
+            let distance = length(offset);
+            if (distance < radius && distance > 1e-6) {
+                p = vec4<f32>(sphere.xyz + offset / distance * radius, p.w);
+            }
+        }
+    }
+    dst[i] = p;
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grid_mesh_and_pinning() {
+        let cloth = Cloth::new([2.0, 1.0], [5, 3]).pin_top_corners();
+        let geometry = cloth.geometry();
+        assert_eq!(geometry.vertices.len(), 15);
+        assert_eq!(geometry.indices.len(), 4 * 2 * 6);
+
+        let particles = cloth.initial_particles();
+        let pinned: Vec<usize> = (0..particles.len())
+            .filter(|&i| particles[i][3] == 0.0)
+            .collect();
+        assert_eq!(pinned, vec![0, 4]);
+        // Vertical cloth hangs down from the origin row
+        assert_eq!(particles[10][2], cloth.origin[2] - 1.0);
+
+        let uniforms = cloth.uniforms();
+        assert_eq!(uniforms.rest[0], 0.5);
+        assert_eq!(std::mem::size_of::<ClothUniforms>(), 80 + 16 * MAX_COLLIDERS);
+    }
+}
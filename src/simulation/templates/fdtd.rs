@@ -0,0 +1,930 @@
+//! 2D electromagnetics (FDTD)
+//!
+//! Maxwell's equations on a 2D Yee grid, stepped with the finite-difference
+//! time-domain method on the GPU. Fields don't vary along Z, so they split
+//! into two independent polarizations:
+//!
+//! - **TM** - `Ez` with `Hx` and `Hy` in the plane
+//! - **TE** - `Hz` with `Ex` and `Ey` in the plane
+//!
+//! Both are solved by the same kernels, swapping the roles of the electric
+//! and magnetic fields. Units are normalized: cells are the unit of length,
+//! waves move half a cell per step, and fields are scaled so that `E` and `H`
+//! of a plane wave have the same amplitude.
+//!
+//! The grid is surrounded by a perfectly matched layer (PML) that absorbs
+//! outgoing waves, so the domain behaves like a window into open space.
+//! Waves are driven by point sources or plane wave lines, continuous or
+//! pulsed, and scattered by dielectric and conducting objects.
+//!
+//! The out-of-plane field is shown with a diverging colormap, red and blue
+//! for the two signs, and the field intensity with a heatmap.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::simulation::templates::{Fdtd2D, FdtdMaterial, FdtdRegion, FdtdSource};
+//!
+//! let mut app = haggis::default();
+//! app.attach_simulation(
+//!     Fdtd2D::new([256, 256])
+//!         .without_sources()
+//!         .with_source(FdtdSource::plane(40, 16.0))
+//!         .with_object(
+//!             FdtdRegion::Disk { center: [150.0, 128.0], radius: 30.0 },
+//!             FdtdMaterial::Dielectric(4.0),
+//!         ),
+//! );
+//! app.run();
+//! ```
+
+use std::sync::Arc;
+
+use bytemuck::Zeroable;
+use cgmath::Vector3;
+use imgui::Ui;
+use wgpu::{Device, Queue};
+
+use crate::gfx::scene::Scene;
+use crate::simulation::params::{ParamsUniform, SimParams};
+use crate::simulation::traits::Simulation;
+use crate::simulation::BaseSimulation;
+use crate::visualization::cut_plane_2d::{BufferElementType, BufferFormat};
+use crate::visualization::ui::cut_plane_controls::{Colormap, FilterMode};
+use crate::visualization::CutPlane2D;
+
+const WORKGROUP_SIZE: u32 = 8;
+
+/// Sources passed to the shader; more are ignored
+const MAX_SOURCES: usize = 8;
+
+/// Polarization of the fields
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FdtdMode {
+    /// Electric field along Z, magnetic field in the plane
+    #[default]
+    Tm,
+    /// Magnetic field along Z, electric field in the plane
+    Te,
+}
+
+impl FdtdMode {
+    pub const ALL: [FdtdMode; 2] = [FdtdMode::Tm, FdtdMode::Te];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FdtdMode::Tm => "TM (Ez, Hx, Hy)",
+            FdtdMode::Te => "TE (Hz, Ex, Ey)",
+        }
+    }
+}
+
+/// What the heatmap shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FdtdQuantity {
+    /// The field along Z (`Ez` or `Hz`), signed
+    #[default]
+    Field,
+    /// Squared magnitude of all three field components
+    Intensity,
+}
+
+impl FdtdQuantity {
+    pub const ALL: [FdtdQuantity; 2] = [FdtdQuantity::Field, FdtdQuantity::Intensity];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FdtdQuantity::Field => "Field",
+            FdtdQuantity::Intensity => "Intensity",
+        }
+    }
+}
+
+/// Time dependence of a source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FdtdWaveform {
+    /// A sine, ramped up over its first two periods
+    #[default]
+    Continuous,
+    /// A single Gaussian wave packet about one period long
+    Pulse,
+}
+
+/// Where a source injects its field
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FdtdSourceShape {
+    /// A single cell
+    Point([u32; 2]),
+    /// A line across the grid at this column, launching plane waves along X
+    Plane(u32),
+}
+
+/// Soft source adding to the out-of-plane field
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FdtdSource {
+    pub shape: FdtdSourceShape,
+    pub waveform: FdtdWaveform,
+    /// Wavelength in cells
+    pub wavelength: f32,
+    pub amplitude: f32,
+}
+
+impl FdtdSource {
+    /// Continuous point source at `cell` with `wavelength` in cells
+    pub fn point(cell: [u32; 2], wavelength: f32) -> Self {
+        Self {
+            shape: FdtdSourceShape::Point(cell),
+            waveform: FdtdWaveform::Continuous,
+            wavelength,
+            amplitude: 1.0,
+        }
+    }
+
+    /// Continuous plane wave launched from `column` with `wavelength` in cells
+    pub fn plane(column: u32, wavelength: f32) -> Self {
+        Self {
+            shape: FdtdSourceShape::Plane(column),
+            ..Self::point([column, 0], wavelength)
+        }
+    }
+
+    /// Emit a single wave packet instead of a continuous wave
+    pub fn pulsed(mut self) -> Self {
+        self.waveform = FdtdWaveform::Pulse;
+        self
+    }
+
+    pub fn with_amplitude(mut self, amplitude: f32) -> Self {
+        self.amplitude = amplitude;
+        self
+    }
+}
+
+/// Area of the grid covered by an object, in cells
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FdtdRegion {
+    Disk { center: [f32; 2], radius: f32 },
+    Rect { min: [f32; 2], max: [f32; 2] },
+}
+
+impl FdtdRegion {
+    fn contains(&self, x: f32, y: f32) -> bool {
+        match *self {
+            FdtdRegion::Disk { center, radius } => {
+                let (dx, dy) = (x - center[0], y - center[1]);
+                dx * dx + dy * dy <= radius * radius
+            }
+            FdtdRegion::Rect { min, max } => {
+                (min[0]..=max[0]).contains(&x) && (min[1]..=max[1]).contains(&y)
+            }
+        }
+    }
+}
+
+/// What an object is made of
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FdtdMaterial {
+    /// Lossless dielectric with this relative permittivity
+    Dielectric(f32),
+    /// Perfect electric conductor, reflecting everything
+    Conductor,
+}
+
+/// Tunable parameters of [`Fdtd2D`]
+#[derive(Debug, Clone, SimParams)]
+pub struct FdtdParams {
+    #[param(range = 1..=64, label = "Steps / Frame")]
+    pub steps_per_frame: u32,
+    /// Field value shown at the ends of the colormap
+    #[param(range = 0.01..=10.0, step = 0.01, label = "Display Range")]
+    pub display_range: f32,
+}
+
+impl Default for FdtdParams {
+    fn default() -> Self {
+        Self {
+            steps_per_frame: 4,
+            display_range: 0.5,
+        }
+    }
+}
+
+/// Source as laid out in the shader
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct GpuSource {
+    /// Cell, then 1 for plane sources, 1 for pulses
+    shape: [f32; 4],
+    /// Wavelength, amplitude
+    wave: [f32; 4],
+}
+
+/// Uniforms of the FDTD shader
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct FdtdUniforms {
+    /// Grid size, PML thickness, source count
+    dims: [u32; 4],
+    /// Shown quantity
+    view: [u32; 4],
+    sources: [GpuSource; MAX_SOURCES],
+}
+
+struct FdtdGpuResources {
+    normal_pipeline: wgpu::ComputePipeline,
+    plane_pipeline: wgpu::ComputePipeline,
+    tick_pipeline: wgpu::ComputePipeline,
+    display_pipeline: wgpu::ComputePipeline,
+    uniforms: ParamsUniform<FdtdUniforms>,
+    bind_group: wgpu::BindGroup,
+    /// Field along Z, the two in-plane fields, and the flux density along Z
+    fields: wgpu::Buffer,
+    /// PML integrals of the in-plane updates
+    integrals: wgpu::Buffer,
+    /// Material factors of the Z and in-plane updates
+    coefficients: wgpu::Buffer,
+    /// Steps taken, read by the sources
+    clock: wgpu::Buffer,
+    /// Shown quantity, one `f32` per cell
+    display: wgpu::Buffer,
+}
+
+/// GPU 2D FDTD electromagnetics with PML boundaries, shown on a cut plane
+pub struct Fdtd2D {
+    base: BaseSimulation,
+    dims: [u32; 2],
+    pml: u32,
+    mode: FdtdMode,
+    quantity: FdtdQuantity,
+    pub params: FdtdParams,
+    sources: Vec<FdtdSource>,
+    objects: Vec<(FdtdRegion, FdtdMaterial)>,
+    plane_position: Vector3<f32>,
+    plane_size: f32,
+    running: bool,
+    steps: u64,
+    needs_reset: bool,
+    needs_step: bool,
+    gpu: Option<FdtdGpuResources>,
+}
+
+impl Fdtd2D {
+    /// A grid of `dims` cells in TM mode with a continuous point source in
+    /// the middle and a 16 cell PML around the edges
+    pub fn new(dims: [u32; 2]) -> Self {
+        assert!(
+            dims[0] >= 8 && dims[1] >= 8,
+            "FDTD grid needs at least 8 cells per side"
+        );
+        Self {
+            base: BaseSimulation::new("FDTD 2D"),
+            dims,
+            pml: 16.min(dims[0].min(dims[1]) / 4),
+            mode: FdtdMode::default(),
+            quantity: FdtdQuantity::default(),
+            params: FdtdParams::default(),
+            sources: vec![FdtdSource::point([dims[0] / 2, dims[1] / 2], 20.0)],
+            objects: Vec::new(),
+            plane_position: Vector3::new(0.0, 2.0, 0.0),
+            plane_size: 2.0,
+            running: true,
+            steps: 0,
+            needs_reset: true,
+            needs_step: false,
+            gpu: None,
+        }
+    }
+
+    pub fn with_mode(mut self, mode: FdtdMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Cells of absorbing layer along each edge
+    pub fn with_pml(mut self, cells: u32) -> Self {
+        self.pml = cells.min(self.dims[0].min(self.dims[1]) / 2 - 1);
+        self
+    }
+
+    /// Add a source; only the first eight are used
+    pub fn with_source(mut self, source: FdtdSource) -> Self {
+        self.sources.push(source);
+        self
+    }
+
+    /// Drop all sources, including the default one
+    pub fn without_sources(mut self) -> Self {
+        self.sources.clear();
+        self
+    }
+
+    /// Fill `region` with `material`; later objects cover earlier ones
+    pub fn with_object(mut self, region: FdtdRegion, material: FdtdMaterial) -> Self {
+        self.objects.push((region, material));
+        self
+    }
+
+    pub fn with_quantity(mut self, quantity: FdtdQuantity) -> Self {
+        self.quantity = quantity;
+        self
+    }
+
+    pub fn with_params(mut self, params: FdtdParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Place the heatmap in the world
+    pub fn with_plane(mut self, position: Vector3<f32>, size: f32) -> Self {
+        self.plane_position = position;
+        self.plane_size = size;
+        self
+    }
+
+    pub fn mode(&self) -> FdtdMode {
+        self.mode
+    }
+
+    /// Switch polarization, starting over from empty fields
+    pub fn set_mode(&mut self, mode: FdtdMode) {
+        self.mode = mode;
+        self.needs_reset = true;
+    }
+
+    pub fn quantity(&self) -> FdtdQuantity {
+        self.quantity
+    }
+
+    pub fn set_quantity(&mut self, quantity: FdtdQuantity) {
+        self.quantity = quantity;
+        self.update_heatmap(true);
+    }
+
+    pub fn sources(&self) -> &[FdtdSource] {
+        &self.sources
+    }
+
+    /// Number of steps taken since the last reset
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// GPU buffer holding the shown quantity (`f32` per cell)
+    pub fn display_buffer(&self) -> Option<&wgpu::Buffer> {
+        self.gpu.as_ref().map(|gpu| &gpu.display)
+    }
+
+    /// Factors of the out-of-plane and in-plane updates for every cell: the
+    /// inverse permittivity for electric fields, zero inside conductors
+    fn coefficients(&self) -> Vec<[f32; 2]> {
+        let [width, height] = self.dims;
+        let mut coefficients = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let material = self
+                    .objects
+                    .iter()
+                    .rev()
+                    .find(|(region, _)| region.contains(x as f32, y as f32))
+                    .map(|(_, material)| *material);
+                let electric = match material {
+                    None => 1.0,
+                    Some(FdtdMaterial::Dielectric(permittivity)) => 1.0 / permittivity.max(1.0),
+                    Some(FdtdMaterial::Conductor) => 0.0,
+                };
+                coefficients.push(match self.mode {
+                    FdtdMode::Tm => [electric, 1.0],
+                    FdtdMode::Te => [1.0, electric],
+                });
+            }
+        }
+        coefficients
+    }
+
+    fn uniforms(&self) -> FdtdUniforms {
+        let mut sources = [GpuSource::zeroed(); MAX_SOURCES];
+        for (gpu, source) in sources.iter_mut().zip(&self.sources) {
+            let (cell, plane) = match source.shape {
+                FdtdSourceShape::Point(cell) => (cell, 0.0),
+                FdtdSourceShape::Plane(column) => ([column, 0], 1.0),
+            };
+            let pulse = (source.waveform == FdtdWaveform::Pulse) as u32 as f32;
+            *gpu = GpuSource {
+                shape: [cell[0] as f32, cell[1] as f32, plane, pulse],
+                wave: [source.wavelength.max(2.0), source.amplitude, 0.0, 0.0],
+            };
+        }
+        FdtdUniforms {
+            dims: [
+                self.dims[0],
+                self.dims[1],
+                self.pml,
+                self.sources.len().min(MAX_SOURCES) as u32,
+            ],
+            view: [(self.quantity == FdtdQuantity::Intensity) as u32, 0, 0, 0],
+            sources,
+        }
+    }
+
+    /// Match the heatmap range to the display range, and its colors to the
+    /// quantity when `recolor` is set
+    fn update_heatmap(&mut self, recolor: bool) {
+        let range = self.params.display_range.max(1e-6);
+        let (min, max, colormap) = match self.quantity {
+            FdtdQuantity::Field => (-range, range, Colormap::DivergingDark),
+            FdtdQuantity::Intensity => (0.0, range * range, Colormap::Heat),
+        };
+        if let Some(plane) = self
+            .base
+            .get_visualization_mut("heatmap")
+            .and_then(|component| component.as_any_mut().downcast_mut::<CutPlane2D>())
+        {
+            plane.set_value_range(min, max);
+            if recolor {
+                plane.set_colormap(colormap);
+            }
+        }
+    }
+
+    fn create_gpu_resources(&self, device: &Device) -> FdtdGpuResources {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("FDTD Shader"),
+            source: wgpu::ShaderSource::Wgsl(FDTD_SHADER.into()),
+        });
+
+        let entry = |binding, ty| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let storage = |read_only| wgpu::BufferBindingType::Storage { read_only };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("FDTD Bind Group Layout"),
+            entries: &[
+                entry(0, wgpu::BufferBindingType::Uniform),
+                entry(1, storage(false)),
+                entry(2, storage(false)),
+                entry(3, storage(true)),
+                entry(4, storage(false)),
+                entry(5, storage(false)),
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("FDTD Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |entry_point: &str| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+
+        let cells = (self.dims[0] * self.dims[1]) as u64;
+        let buffer = |label: &str, size: u64| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size,
+                usage: wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_DST
+                    | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            })
+        };
+        let fields = buffer("FDTD Fields", cells * 16);
+        let integrals = buffer("FDTD PML Integrals", cells * 8);
+        let coefficients = buffer("FDTD Coefficients", cells * 8);
+        let clock = buffer("FDTD Clock", 4);
+        let display = buffer("FDTD Display", cells * 4);
+        let uniforms = ParamsUniform::new(device, "FDTD Uniforms", &self.uniforms());
+
+        let buffers = [
+            uniforms.buffer(),
+            &fields,
+            &integrals,
+            &coefficients,
+            &clock,
+            &display,
+        ];
+        let entries: Vec<wgpu::BindGroupEntry> = buffers
+            .iter()
+            .enumerate()
+            .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                binding: binding as u32,
+                resource: buffer.as_entire_binding(),
+            })
+            .collect();
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("FDTD Bind Group"),
+            layout: &bind_group_layout,
+            entries: &entries,
+        });
+
+        FdtdGpuResources {
+            normal_pipeline: pipeline("update_normal"),
+            plane_pipeline: pipeline("update_plane"),
+            tick_pipeline: pipeline("tick"),
+            display_pipeline: pipeline("display"),
+            uniforms,
+            bind_group,
+            fields,
+            integrals,
+            coefficients,
+            clock,
+            display,
+        }
+    }
+}
+
+impl Simulation for Fdtd2D {
+    fn initialize(&mut self, scene: &mut Scene) {
+        self.base.initialize(scene);
+    }
+
+    fn initialize_gpu(&mut self, device: &Device, queue: &Queue) {
+        let gpu = self.create_gpu_resources(device);
+
+        let mut heatmap = CutPlane2D::new();
+        heatmap.set_position(self.plane_position);
+        heatmap.set_size(self.plane_size);
+        heatmap.set_filter_mode(FilterMode::Smooth);
+        heatmap.update_gpu_buffer(
+            Arc::new(gpu.display.clone()),
+            BufferFormat {
+                element_type: BufferElementType::F32,
+                width: self.dims[0],
+                height: self.dims[1],
+            },
+        );
+
+        self.gpu = Some(gpu);
+        self.needs_reset = true;
+        self.base.remove_visualization("heatmap");
+        self.base.add_visualization("heatmap", heatmap);
+        self.update_heatmap(true);
+        self.base.initialize_gpu(device, queue);
+    }
+
+    fn update(&mut self, delta_time: f32, scene: &mut Scene) {
+        self.base.update(delta_time, scene);
+    }
+
+    fn update_gpu(&mut self, device: &Device, queue: &Queue, delta_time: f32) {
+        self.update_heatmap(false);
+        let uniforms = self.uniforms();
+        let coefficients = self.needs_reset.then(|| self.coefficients());
+        let steps = if self.running {
+            self.params.steps_per_frame.max(1)
+        } else {
+            u32::from(self.needs_step)
+        };
+        let groups = self.dims.map(|n| n.div_ceil(WORKGROUP_SIZE));
+
+        if let Some(gpu) = self.gpu.as_mut() {
+            gpu.uniforms.update(queue, &uniforms);
+            if let Some(coefficients) = coefficients {
+                for buffer in [&gpu.fields, &gpu.integrals, &gpu.clock] {
+                    queue.write_buffer(buffer, 0, &vec![0u8; buffer.size() as usize]);
+                }
+                queue.write_buffer(&gpu.coefficients, 0, bytemuck::cast_slice(&coefficients));
+                self.needs_reset = false;
+                self.steps = 0;
+            }
+
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("FDTD Encoder"),
+            });
+            {
+                crate::logging::crash::record_pass("FDTD Step");
+                let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: Some("FDTD Step"),
+                    timestamp_writes: None,
+                });
+                pass.set_bind_group(0, &gpu.bind_group, &[]);
+                for _ in 0..steps {
+                    pass.set_pipeline(&gpu.normal_pipeline);
+                    pass.dispatch_workgroups(groups[0], groups[1], 1);
+                    pass.set_pipeline(&gpu.plane_pipeline);
+                    pass.dispatch_workgroups(groups[0], groups[1], 1);
+                    pass.set_pipeline(&gpu.tick_pipeline);
+                    pass.dispatch_workgroups(1, 1, 1);
+                }
+                // Refresh the display even when paused, the quantity may change
+                pass.set_pipeline(&gpu.display_pipeline);
+                pass.dispatch_workgroups(groups[0], groups[1], 1);
+            }
+            queue.submit(std::iter::once(encoder.finish()));
+            self.steps += steps as u64;
+            self.needs_step = false;
+        }
+
+        self.base.update_gpu(device, queue, delta_time);
+    }
+
+    fn apply_gpu_results_to_scene(&mut self, device: &Device, scene: &mut Scene) {
+        self.base.apply_gpu_results_to_scene(device, scene);
+    }
+
+    fn render_ui(&mut self, ui: &Ui) {
+        ui.window("FDTD Electromagnetics")
+            .size([360.0, 400.0], imgui::Condition::FirstUseEver)
+            .build(|| {
+                ui.text(format!(
+                    "Grid: {}x{}, PML: {} cells",
+                    self.dims[0], self.dims[1], self.pml
+                ));
+                ui.text(format!(
+                    "Steps: {} ({:.1} periods of the first source)",
+                    self.steps,
+                    self.sources
+                        .first()
+                        .map_or(0.0, |source| self.steps as f32 * 0.5 / source.wavelength)
+                ));
+                ui.text(format!(
+                    "Sources: {}, objects: {}",
+                    self.sources.len().min(MAX_SOURCES),
+                    self.objects.len()
+                ));
+                ui.separator();
+
+                if ui.button(if self.running { "Pause" } else { "Play" }) {
+                    self.running = !self.running;
+                }
+                ui.same_line();
+                if ui.button("Step") {
+                    self.needs_step = true;
+                }
+                ui.same_line();
+                if ui.button("Reset") {
+                    self.needs_reset = true;
+                }
+
+                ui.separator();
+                ui.text("Mode:");
+                for mode in FdtdMode::ALL {
+                    if ui.radio_button_bool(mode.as_str(), self.mode == mode) {
+                        self.set_mode(mode);
+                    }
+                }
+                ui.text("Show:");
+                for quantity in FdtdQuantity::ALL {
+                    ui.same_line();
+                    if ui.radio_button_bool(quantity.as_str(), self.quantity == quantity) {
+                        self.set_quantity(quantity);
+                    }
+                }
+
+                ui.separator();
+                self.params.build_ui(ui);
+            });
+
+        self.base.render_ui(ui);
+    }
+
+    fn name(&self) -> &str {
+        "FDTD 2D"
+    }
+
+    fn is_running(&self) -> bool {
+        self.running
+    }
+
+    fn set_running(&mut self, running: bool) {
+        self.running = running;
+    }
+
+    fn reset(&mut self, scene: &mut Scene) {
+        self.needs_reset = true;
+        self.base.reset(scene);
+    }
+
+    fn is_gpu_ready(&self) -> bool {
+        self.gpu.is_some()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        &self.base
+    }
+}
+
+const FDTD_SHADER: &str = r#"
+struct GpuSource {
+    shape: vec4<f32>, // cell, plane flag, pulse flag
+    wave: vec4<f32>,  // wavelength in cells, amplitude
+}
+
+struct FdtdUniforms {
+    dims: vec4<u32>, // width, height, PML cells, source count
+    view: vec4<u32>, // 1 to show the intensity
+    sources: array<GpuSource, 8>,
+}
+
+@group(0) @binding(0) var<uniform> uniforms: FdtdUniforms;
+// Field along Z, field along X, field along Y, flux density along Z
+@group(0) @binding(1) var<storage, read_write> fields: array<vec4<f32>>;
+@group(0) @binding(2) var<storage, read_write> integrals: array<vec2<f32>>;
+@group(0) @binding(3) var<storage, read> coefficients: array<vec2<f32>>;
+@group(0) @binding(4) var<storage, read_write> clock: array<u32>;
+@group(0) @binding(5) var<storage, read_write> display_values: array<f32>;
+
+const PI: f32 = 3.14159265;
+
+fn cell_index(x: u32, y: u32) -> u32 {
+    return y * uniforms.dims.x + x;
+}
+
+// PML conductivity at `position` along an axis of `size` cells, graded
+// cubically from zero at the inner edge of the layer
+fn pml_loss(position: f32, size: u32) -> f32 {
+    let thickness = f32(uniforms.dims.z);
+    if (thickness == 0.0) {
+        return 0.0;
+    }
+    let depth = max(max(thickness - position, position - (f32(size) - 1.0 - thickness)), 0.0);
+    let x = min(depth / thickness, 1.0);
+    return 0.333 * x * x * x;
+}
+
+// Source value at `step`: a ramped sine or a Gaussian wave packet
+fn waveform(source: GpuSource, step: f32) -> f32 {
+    // Waves move half a cell per step
+    let period = 2.0 * source.wave.x;
+    if (source.shape.w > 0.5) {
+        let delay = 3.0 * period;
+        let t = (step - delay) / period;
+        return source.wave.y * exp(-4.0 * t * t) * sin(2.0 * PI * t);
+    }
+    let ramp = min(step / (2.0 * period), 1.0);
+    return source.wave.y * ramp * sin(2.0 * PI * step / period);
+}
+
+fn source_term(x: u32, y: u32, step: f32) -> f32 {
+    var total = 0.0;
+    for (var i = 0u; i < uniforms.dims.w; i++) {
+        let source = uniforms.sources[i];
+        let cell = vec2<u32>(source.shape.xy);
+        let plane = source.shape.z > 0.5;
+        // Plane sources stop at the PML so they don't feed it
+        let inside = y >= uniforms.dims.z && y < uniforms.dims.y - uniforms.dims.z;
+        if ((plane && x == cell.x && inside) || (!plane && x == cell.x && y == cell.y)) {
+            total += waveform(source, step);
+        }
+    }
+    return total;
+}
+
+// Out-of-plane field from the curl of the in-plane fields
+@compute @workgroup_size(8, 8)
+fn update_normal(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= uniforms.dims.x || id.y >= uniforms.dims.y) {
+        return;
+    }
+    let index = cell_index(id.x, id.y);
+    var cell = fields[index];
+    let left = select(vec4<f32>(0.0), fields[cell_index(id.x - 1u, id.y)], id.x > 0u);
+    let below = select(vec4<f32>(0.0), fields[cell_index(id.x, id.y - 1u)], id.y > 0u);
+    let curl = cell.z - left.z - cell.y + below.y;
+
+    let gx = pml_loss(f32(id.x), uniforms.dims.x);
+    let gy = pml_loss(f32(id.y), uniforms.dims.y);
+    let decay = (1.0 - gx) / (1.0 + gx) * (1.0 - gy) / (1.0 + gy);
+    let gain = 1.0 / ((1.0 + gx) * (1.0 + gy));
+    cell.w = decay * cell.w + gain * 0.5 * curl + source_term(id.x, id.y, f32(clock[0]));
+    cell.x = coefficients[index].x * cell.w;
+    fields[index] = cell;
+}
+
+// In-plane fields from the gradient of the out-of-plane field
+@compute @workgroup_size(8, 8)
+fn update_plane(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= uniforms.dims.x || id.y >= uniforms.dims.y) {
+        return;
+    }
+    let index = cell_index(id.x, id.y);
+    var cell = fields[index];
+    var integral = integrals[index];
+    let factor = coefficients[index].y;
+    let right = select(0.0, fields[cell_index(id.x + 1u, id.y)].x, id.x + 1u < uniforms.dims.x);
+    let above = select(0.0, fields[cell_index(id.x, id.y + 1u)].x, id.y + 1u < uniforms.dims.y);
+
+    // Losses at the half cell the in-plane fields sit on
+    let fx = pml_loss(f32(id.x) + 0.5, uniforms.dims.x);
+    let fy = pml_loss(f32(id.y) + 0.5, uniforms.dims.y);
+    let gx = pml_loss(f32(id.x), uniforms.dims.x);
+    let gy = pml_loss(f32(id.y), uniforms.dims.y);
+
+    let curl_x = cell.x - above;
+    integral.x += gx * curl_x;
+    cell.y = (1.0 - fy) / (1.0 + fy) * cell.y + factor / (1.0 + fy) * 0.5 * (curl_x + integral.x);
+
+    let curl_y = right - cell.x;
+    integral.y += gy * curl_y;
+    cell.z = (1.0 - fx) / (1.0 + fx) * cell.z + factor / (1.0 + fx) * 0.5 * (curl_y + integral.y);
+
+    fields[index] = cell;
+    integrals[index] = integral;
+}
+
+@compute @workgroup_size(1)
+fn tick() {
+    clock[0] += 1u;
+}
+
+@compute @workgroup_size(8, 8)
+fn display(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= uniforms.dims.x || id.y >= uniforms.dims.y) {
+        return;
+    }
+    let index = cell_index(id.x, id.y);
+    let cell = fields[index];
+    if (uniforms.view.x == 1u) {
+        display_values[index] = dot(cell.xyz, cell.xyz);
+    } else {
+        display_values[index] = cell.x;
+    }
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_material_coefficients_follow_mode() {
+        assert_eq!(std::mem::size_of::<FdtdUniforms>(), 32 + 32 * MAX_SOURCES);
+
+        let fdtd = Fdtd2D::new([32, 32])
+            .with_object(
+                FdtdRegion::Disk {
+                    center: [8.0, 8.0],
+                    radius: 3.0,
+                },
+                FdtdMaterial::Dielectric(4.0),
+            )
+            .with_object(
+                FdtdRegion::Rect {
+                    min: [20.0, 0.0],
+                    max: [21.0, 31.0],
+                },
+                FdtdMaterial::Conductor,
+            );
+        let tm = fdtd.coefficients();
+        assert_eq!(tm[8 * 32 + 8], [0.25, 1.0]);
+        assert_eq!(tm[5 * 32 + 20], [0.0, 1.0]);
+        assert_eq!(tm[0], [1.0, 1.0]);
+
+        let te = fdtd.with_mode(FdtdMode::Te).coefficients();
+        assert_eq!(te[8 * 32 + 8], [1.0, 0.25]);
+        assert_eq!(te[5 * 32 + 21], [1.0, 0.0]);
+    }
+
+    #[test]
+    fn test_pml_absorbs_pulse() {
+        use crate::wgpu_utils::compute_primitives::{read_buffer, test_device};
+
+        // Needs a GPU adapter, skipped without one
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        for mode in FdtdMode::ALL {
+            let mut fdtd = Fdtd2D::new([64, 64])
+                .without_sources()
+                .with_source(FdtdSource::point([32, 32], 8.0).pulsed())
+                .with_mode(mode)
+                .with_quantity(FdtdQuantity::Intensity)
+                .with_params(FdtdParams {
+                    steps_per_frame: 40,
+                    ..FdtdParams::default()
+                });
+            fdtd.initialize_gpu(&device, &queue);
+
+            // Energy in the grid after each frame
+            let mut energy = Vec::new();
+            for _ in 0..20 {
+                fdtd.update_gpu(&device, &queue, 1.0 / 60.0);
+                let values: Vec<f32> =
+                    read_buffer(&device, &queue, fdtd.display_buffer().unwrap(), 64 * 64).unwrap();
+                assert!(values.iter().all(|value| value.is_finite()));
+                energy.push(values.iter().sum::<f32>());
+            }
+            let peak = energy.iter().copied().fold(0.0, f32::max);
+            assert!(peak > 1.0, "{mode:?} pulse too weak: {peak}");
+            // The packet has left through the PML with little reflected
+            assert!(
+                energy[19] < peak * 0.01,
+                "{mode:?} kept {} of {peak}",
+                energy[19]
+            );
+        }
+    }
+}
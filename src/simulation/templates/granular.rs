@@ -0,0 +1,817 @@
+//! Granular material
+//!
+//! Discrete element method (DEM) for sand and other grains. Every grain is a
+//! sphere, and touching grains push each other apart with a spring-dashpot
+//! contact:
+//!
+//! - **Normal force** - a spring proportional to the overlap, damped by the
+//!   approach speed, that only ever pushes
+//! - **Friction** - viscous damping of the sliding speed, capped by Coulomb
+//!   friction at `friction` times the normal force
+//!
+//! The ground, the side walls of the domain box and the funnel of the
+//! [`GranularPreset::Hopper`] preset push on grains with the same contact.
+//! Contacts are stiff, so each frame is split into substeps; raise
+//! [`GranularParams::substeps`] along with the stiffness. Every substep
+//! rebuilds a GPU [`SpatialHash`] over the grains and runs two compute
+//! passes, one summing the contact forces into new velocities and one
+//! moving the grains. The grain buffer is drawn directly as instanced
+//! spheres by an [`AgentView`], without a readback.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::simulation::templates::Granular;
+//!
+//! let mut app = haggis::default();
+//! app.attach_simulation(Granular::hopper(20_000));
+//! app.run();
+//! ```
+
+use cgmath::Vector3;
+use imgui::Ui;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use wgpu::util::DeviceExt;
+use wgpu::{Device, Queue};
+
+use crate::gfx::rendering::{AgentInstance, AgentShape};
+use crate::gfx::scene::Scene;
+use crate::simulation::gpu::SpatialHash;
+use crate::simulation::params::{ParamsUniform, SimParams};
+use crate::simulation::traits::Simulation;
+use crate::simulation::BaseSimulation;
+use crate::visualization::{AgentView, DomainBox};
+
+const WORKGROUP_SIZE: u32 = 64;
+
+/// Smallest grain radius, relative to the nominal one
+const MIN_RADIUS_SCALE: f32 = 0.85;
+
+/// Grain layout the simulation starts from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GranularPreset {
+    /// A column of grains collapsing into a pile on the ground
+    #[default]
+    Pile,
+    /// A funnel full of grains draining through its outlet onto the ground
+    Hopper,
+}
+
+impl GranularPreset {
+    pub const ALL: [GranularPreset; 2] = [GranularPreset::Pile, GranularPreset::Hopper];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GranularPreset::Pile => "Pile",
+            GranularPreset::Hopper => "Hopper",
+        }
+    }
+}
+
+/// Tunable parameters of [`Granular`]
+#[derive(Debug, Clone, SimParams)]
+pub struct GranularParams {
+    /// Contact spring constant per unit grain mass
+    #[param(range = 1000.0..=500000.0, step = 1000.0)]
+    pub stiffness: f32,
+    /// Contact damping relative to critical damping
+    #[param(range = 0.0..=1.0, step = 0.01, label = "Damping Ratio")]
+    pub damping: f32,
+    /// Coulomb friction coefficient between grains and against walls
+    #[param(range = 0.0..=1.5, step = 0.01)]
+    pub friction: f32,
+    #[param(range = -20.0..=0.0, step = 0.1)]
+    pub gravity: f32,
+    #[param(range = 1..=64)]
+    pub substeps: u32,
+}
+
+impl Default for GranularParams {
+    fn default() -> Self {
+        Self {
+            stiffness: 100_000.0,
+            damping: 0.3,
+            friction: 0.5,
+            gravity: -9.81,
+            substeps: 16,
+        }
+    }
+}
+
+/// Funnel of the hopper preset, centered over the domain
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Funnel {
+    /// Radius of the outlet
+    opening: f32,
+    /// Height of the outlet
+    outlet: f32,
+    /// Radius gained per unit of height
+    slope: f32,
+    /// Height the funnel wall ends at
+    top: f32,
+}
+
+impl Funnel {
+    fn radius_at(&self, z: f32) -> f32 {
+        self.opening + self.slope * (z - self.outlet)
+    }
+
+    /// Distance from the wall of a point `rho` off the axis at height `z`,
+    /// positive inside
+    fn distance(&self, rho: f32, z: f32) -> f32 {
+        (self.radius_at(z) - rho) / (1.0 + self.slope * self.slope).sqrt()
+    }
+}
+
+/// Uniforms of the granular shader
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct GranularUniforms {
+    count: u32,
+    /// 1 when the funnel walls are present
+    hopper: u32,
+    /// 1 while the funnel outlet is closed
+    gate_closed: u32,
+    _padding: u32,
+    /// Substep time step in `w`
+    bounds_min: [f32; 4],
+    /// Gravity in `w`
+    bounds_max: [f32; 4],
+    /// Stiffness, damping ratio, friction, nominal grain radius
+    contact: [f32; 4],
+    /// Funnel opening radius, outlet height, slope, top height
+    funnel: [f32; 4],
+}
+
+struct GranularGpuResources {
+    forces_pipeline: wgpu::ComputePipeline,
+    integrate_pipeline: wgpu::ComputePipeline,
+    uniforms: ParamsUniform<GranularUniforms>,
+    /// Hash over the grain positions, bound for the forces pass
+    hash: SpatialHash,
+    /// Grains as [`AgentInstance`]s with the radius as size, drawn directly
+    /// by the agent view
+    grains: wgpu::Buffer,
+    /// Also keeps the buffer of new velocities alive
+    bind_group: wgpu::BindGroup,
+}
+
+/// GPU discrete element simulation of spherical grains drawn as instanced spheres
+pub struct Granular {
+    base: BaseSimulation,
+    count: u32,
+    preset: GranularPreset,
+    grain_radius: f32,
+    bounds_min: Vector3<f32>,
+    bounds_max: Vector3<f32>,
+    color: [f32; 4],
+    funnel: Funnel,
+    gate_open: bool,
+    pub params: GranularParams,
+    running: bool,
+    steps: u64,
+    needs_reset: bool,
+    needs_step: bool,
+    time_step: f32,
+    gpu: Option<GranularGpuResources>,
+}
+
+impl Granular {
+    /// `count` grains laid out by `preset` in a 6 x 6 x 5 box
+    pub fn new(count: u32, preset: GranularPreset) -> Self {
+        assert!(count > 0, "granular material must have grains");
+        Self {
+            base: BaseSimulation::new("Granular"),
+            count,
+            preset,
+            grain_radius: 0.04,
+            bounds_min: Vector3::new(-3.0, -3.0, 0.0),
+            bounds_max: Vector3::new(3.0, 3.0, 5.0),
+            color: [0.86, 0.72, 0.48, 1.0],
+            funnel: Funnel {
+                opening: 0.2,
+                outlet: 1.5,
+                slope: 1.0,
+                top: 4.0,
+            },
+            gate_open: true,
+            params: GranularParams::default(),
+            running: true,
+            steps: 0,
+            needs_reset: true,
+            needs_step: false,
+            time_step: 1.0 / 60.0,
+            gpu: None,
+        }
+    }
+
+    /// Column of `count` grains collapsing into a pile
+    pub fn pile(count: u32) -> Self {
+        Self::new(count, GranularPreset::Pile)
+    }
+
+    /// Funnel filled with `count` grains
+    pub fn hopper(count: u32) -> Self {
+        Self::new(count, GranularPreset::Hopper)
+    }
+
+    /// Domain the grains are kept in; the top is open
+    ///
+    /// The funnel of the hopper preset stays centered over the domain.
+    pub fn with_bounds(
+        mut self,
+        min: impl Into<Vector3<f32>>,
+        max: impl Into<Vector3<f32>>,
+    ) -> Self {
+        self.bounds_min = min.into();
+        self.bounds_max = max.into();
+        self
+    }
+
+    /// Radius of the largest grains; the others are up to 15% smaller
+    pub fn with_grain_radius(mut self, radius: f32) -> Self {
+        assert!(radius > 0.0, "grain radius must be positive");
+        self.grain_radius = radius;
+        self
+    }
+
+    /// Base color, varied in brightness per grain
+    pub fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Start the hopper with its outlet closed, see [`set_gate_open`](Self::set_gate_open)
+    pub fn with_gate_closed(mut self) -> Self {
+        self.gate_open = false;
+        self
+    }
+
+    pub fn with_params(mut self, params: GranularParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    pub fn preset(&self) -> GranularPreset {
+        self.preset
+    }
+
+    /// Switch to another preset, starting over from its layout
+    pub fn set_preset(&mut self, preset: GranularPreset) {
+        self.preset = preset;
+        self.needs_reset = true;
+        self.steps = 0;
+    }
+
+    /// Open or close the outlet of the hopper funnel
+    pub fn set_gate_open(&mut self, open: bool) {
+        self.gate_open = open;
+    }
+
+    pub fn is_gate_open(&self) -> bool {
+        self.gate_open
+    }
+
+    /// Number of steps taken since the last reset
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// GPU buffer holding the current grains as [`AgentInstance`]s
+    pub fn grain_buffer(&self) -> Option<&wgpu::Buffer> {
+        self.gpu.as_ref().map(|gpu| &gpu.grains)
+    }
+
+    /// Whether a grain of radius `radius` may start at `position`
+    fn in_spawn_region(&self, position: Vector3<f32>, radius: f32) -> bool {
+        let center = (self.bounds_min + self.bounds_max) * 0.5;
+        let rho = ((position.x - center.x).powi(2) + (position.y - center.y).powi(2)).sqrt();
+        match self.preset {
+            GranularPreset::Pile => {
+                let size = self.bounds_max - self.bounds_min;
+                let column = size.x.min(size.y) / 6.0;
+                rho <= column && position.z >= self.bounds_min.z + size.z * 0.2
+            }
+            GranularPreset::Hopper => {
+                let funnel = &self.funnel;
+                // Above the funnel the column keeps its top radius
+                let z = position.z.min(funnel.top);
+                position.z >= funnel.outlet + radius && funnel.distance(rho, z) >= radius * 1.5
+            }
+        }
+    }
+
+    /// Starting state: grains on a jittered lattice filling the preset's
+    /// region from the bottom up, at rest
+    fn initial_grains(&self) -> Vec<AgentInstance> {
+        let mut rng = StdRng::seed_from_u64(0x6a7e);
+        let radius = self.grain_radius;
+        let spacing = radius * 2.1;
+        let jitter = (spacing - radius * 2.0) * 0.5;
+        let columns = ((self.bounds_max.x - self.bounds_min.x) / spacing).floor() as u32;
+        let rows = ((self.bounds_max.y - self.bounds_min.y) / spacing).floor() as u32;
+
+        let mut grains = Vec::with_capacity(self.count as usize);
+        let mut layer = 0;
+        while grains.len() < self.count as usize {
+            let z = self.bounds_min.z + radius + layer as f32 * spacing;
+            layer += 1;
+            for row in 0..rows {
+                for column in 0..columns {
+                    if grains.len() == self.count as usize {
+                        break;
+                    }
+                    let position = Vector3::new(
+                        self.bounds_min.x
+                            + (column as f32 + 0.5) * spacing
+                            + rng.random_range(-jitter..=jitter),
+                        self.bounds_min.y
+                            + (row as f32 + 0.5) * spacing
+                            + rng.random_range(-jitter..=jitter),
+                        z,
+                    );
+                    if !self.in_spawn_region(position, radius) {
+                        continue;
+                    }
+                    let shade = rng.random_range(0.8..=1.1);
+                    let [r, g, b, a] = self.color;
+                    grains.push(AgentInstance::new(
+                        position.into(),
+                        [0.0; 3],
+                        radius * rng.random_range(MIN_RADIUS_SCALE..=1.0),
+                        [
+                            (r * shade).min(1.0),
+                            (g * shade).min(1.0),
+                            (b * shade).min(1.0),
+                            a,
+                        ],
+                    ));
+                }
+            }
+        }
+        grains
+    }
+
+    fn uniforms(&self) -> GranularUniforms {
+        let p = &self.params;
+        let substeps = p.substeps.max(1);
+        let hopper = self.preset == GranularPreset::Hopper;
+        GranularUniforms {
+            count: self.count,
+            hopper: hopper as u32,
+            gate_closed: (hopper && !self.gate_open) as u32,
+            _padding: 0,
+            bounds_min: [
+                self.bounds_min.x,
+                self.bounds_min.y,
+                self.bounds_min.z,
+                self.time_step / substeps as f32,
+            ],
+            bounds_max: [
+                self.bounds_max.x,
+                self.bounds_max.y,
+                self.bounds_max.z,
+                p.gravity,
+            ],
+            contact: [p.stiffness, p.damping, p.friction, self.grain_radius],
+            funnel: [
+                self.funnel.opening,
+                self.funnel.outlet,
+                self.funnel.slope,
+                self.funnel.top,
+            ],
+        }
+    }
+
+    fn create_gpu_resources(&self, device: &Device) -> GranularGpuResources {
+        let source = format!("{}{}", SpatialHash::query_wgsl(0, 3), GRANULAR_SHADER);
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Granular Shader"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+
+        let buffer_entry = |binding: u32, ty: wgpu::BufferBindingType| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let mut layout_entries = vec![
+            buffer_entry(0, wgpu::BufferBindingType::Uniform),
+            buffer_entry(1, wgpu::BufferBindingType::Storage { read_only: false }),
+            buffer_entry(2, wgpu::BufferBindingType::Storage { read_only: false }),
+        ];
+        layout_entries.extend(SpatialHash::bind_group_layout_entries(
+            3,
+            wgpu::ShaderStages::COMPUTE,
+        ));
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Granular Bind Group Layout"),
+            entries: &layout_entries,
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Granular Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |entry_point: &str| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+
+        let grains = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Granular Grains"),
+            contents: bytemuck::cast_slice(&self.initial_grains()),
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::VERTEX
+                | wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::COPY_SRC,
+        });
+        let velocities = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Granular New Velocities"),
+            size: self.count as u64 * std::mem::size_of::<[f32; 4]>() as u64,
+            usage: wgpu::BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+        let uniforms = ParamsUniform::new(device, "Granular Uniforms", &self.uniforms());
+        // Touching grains are at most two radii apart
+        let hash = SpatialHash::new(
+            device,
+            &grains,
+            std::mem::size_of::<AgentInstance>() as u64,
+            self.count,
+            self.grain_radius * 2.0,
+        );
+
+        let mut entries = vec![
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniforms.buffer().as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: grains.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: velocities.as_entire_binding(),
+            },
+        ];
+        entries.extend(hash.bind_group_entries(3));
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Granular Bind Group"),
+            layout: &bind_group_layout,
+            entries: &entries,
+        });
+
+        GranularGpuResources {
+            forces_pipeline: pipeline("forces"),
+            integrate_pipeline: pipeline("integrate"),
+            uniforms,
+            hash,
+            grains,
+            bind_group,
+        }
+    }
+}
+
+impl Simulation for Granular {
+    fn initialize(&mut self, scene: &mut Scene) {
+        self.base.initialize(scene);
+    }
+
+    fn initialize_gpu(&mut self, device: &Device, queue: &Queue) {
+        let gpu = self.create_gpu_resources(device);
+        // The buffer starts out with the preset's layout
+        self.needs_reset = false;
+        self.steps = 0;
+
+        let mut view = AgentView::new().with_shape(AgentShape::Sphere);
+        view.set_gpu_buffer(gpu.grains.clone(), self.count);
+
+        self.gpu = Some(gpu);
+        self.base.remove_visualization("grains");
+        self.base.remove_visualization("bounds");
+        self.base.add_visualization("grains", view);
+        self.base
+            .add_visualization("bounds", DomainBox::new(self.bounds_min, self.bounds_max));
+        self.base.initialize_gpu(device, queue);
+    }
+
+    fn update(&mut self, delta_time: f32, scene: &mut Scene) {
+        self.base.update(delta_time, scene);
+    }
+
+    fn update_gpu(&mut self, device: &Device, queue: &Queue, delta_time: f32) {
+        // Long frames would need more substeps than configured to stay stable
+        self.time_step = delta_time.min(1.0 / 30.0);
+        let uniforms = self.uniforms();
+        let reset_grains = self.needs_reset.then(|| self.initial_grains());
+        if let Some(gpu) = self.gpu.as_mut() {
+            gpu.uniforms.update(queue, &uniforms);
+
+            if let Some(grains) = reset_grains {
+                queue.write_buffer(&gpu.grains, 0, bytemuck::cast_slice(&grains));
+                self.needs_reset = false;
+            }
+
+            if self.running || self.needs_step {
+                let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Granular Encoder"),
+                });
+                let groups = self.count.div_ceil(WORKGROUP_SIZE);
+                crate::logging::crash::record_pass("Granular Step");
+                for _ in 0..self.params.substeps.max(1) {
+                    gpu.hash.build(queue, &mut encoder);
+                    let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                        label: Some("Granular Step"),
+                        timestamp_writes: None,
+                    });
+                    pass.set_bind_group(0, &gpu.bind_group, &[]);
+                    pass.set_pipeline(&gpu.forces_pipeline);
+                    pass.dispatch_workgroups(groups, 1, 1);
+                    pass.set_pipeline(&gpu.integrate_pipeline);
+                    pass.dispatch_workgroups(groups, 1, 1);
+                }
+                queue.submit(std::iter::once(encoder.finish()));
+                self.steps += 1;
+            }
+            self.needs_step = false;
+        }
+
+        self.base.update_gpu(device, queue, delta_time);
+    }
+
+    fn apply_gpu_results_to_scene(&mut self, device: &Device, scene: &mut Scene) {
+        self.base.apply_gpu_results_to_scene(device, scene);
+    }
+
+    fn render_ui(&mut self, ui: &Ui) {
+        ui.window("Granular")
+            .size([360.0, 360.0], imgui::Condition::FirstUseEver)
+            .build(|| {
+                ui.text(format!("Grains: {}", self.count));
+                ui.text(format!("Steps: {}", self.steps));
+                ui.separator();
+
+                if ui.button(if self.running { "Pause" } else { "Play" }) {
+                    self.running = !self.running;
+                }
+                ui.same_line();
+                if ui.button("Step") {
+                    self.needs_step = true;
+                }
+                ui.same_line();
+                if ui.button("Reset") {
+                    self.needs_reset = true;
+                    self.steps = 0;
+                }
+
+                ui.separator();
+                ui.text("Preset:");
+                for preset in GranularPreset::ALL {
+                    if ui.radio_button_bool(preset.as_str(), self.preset == preset) {
+                        self.set_preset(preset);
+                    }
+                }
+                if self.preset == GranularPreset::Hopper {
+                    ui.checkbox("Gate Open", &mut self.gate_open);
+                }
+
+                ui.separator();
+                self.params.build_ui(ui);
+            });
+
+        self.base.render_ui(ui);
+    }
+
+    fn name(&self) -> &str {
+        "Granular"
+    }
+
+    fn is_running(&self) -> bool {
+        self.running
+    }
+
+    fn set_running(&mut self, running: bool) {
+        self.running = running;
+    }
+
+    fn reset(&mut self, scene: &mut Scene) {
+        self.needs_reset = true;
+        self.steps = 0;
+        self.base.reset(scene);
+    }
+
+    fn is_gpu_ready(&self) -> bool {
+        self.gpu.is_some()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        &self.base
+    }
+}
+
+const GRANULAR_SHADER: &str = r#"
+struct Grain {
+    position: vec4<f32>, // radius in w
+    velocity: vec4<f32>,
+    color: vec4<f32>,
+}
+
+struct GranularUniforms {
+    count: u32,
+    hopper: u32,
+    gate_closed: u32,
+    _padding: u32,
+    bounds_min: vec4<f32>, // time step in w
+    bounds_max: vec4<f32>, // gravity in w
+    contact: vec4<f32>,    // stiffness, damping ratio, friction, nominal radius
+    funnel: vec4<f32>,     // opening radius, outlet height, slope, top height
+}
+
+@group(0) @binding(0) var<uniform> params: GranularUniforms;
+@group(0) @binding(1) var<storage, read_write> grains: array<Grain>;
+@group(0) @binding(2) var<storage, read_write> new_velocities: array<vec4<f32>>;
+
+// Mass relative to a grain of the nominal radius
+fn grain_mass(radius: f32) -> f32 {
+    let scale = radius / params.contact.w;
+    return scale * scale * scale;
+}
+
+// Spring-dashpot force along `normal` with Coulomb-capped sliding friction
+fn contact_force(relative_velocity: vec3<f32>, mass: f32, normal: vec3<f32>, overlap: f32) -> vec3<f32> {
+    if (overlap <= 0.0) {
+        return vec3<f32>(0.0);
+    }
+    let stiffness = params.contact.x * mass;
+    let damping = 2.0 * params.contact.y * sqrt(stiffness * mass);
+    let normal_speed = dot(relative_velocity, normal);
+    let normal_force = max(stiffness * overlap - damping * normal_speed, 0.0);
+    var force = normal_force * normal;
+
+    let sliding = relative_velocity - normal_speed * normal;
+    let sliding_speed = length(sliding);
+    if (sliding_speed > 1e-6) {
+        let friction = min(params.contact.z * normal_force, damping * sliding_speed);
+        force -= friction * sliding / sliding_speed;
+    }
+    return force;
+}
+
+@compute @workgroup_size(64)
+fn forces(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if (index >= params.count) {
+        return;
+    }
+    let p = grains[index].position.xyz;
+    let radius = grains[index].position.w;
+    let v = grains[index].velocity.xyz;
+    let mass = grain_mass(radius);
+    var force = vec3<f32>(0.0, 0.0, params.bounds_max.w * mass);
+
+    let keys = spatial_hash_neighbor_keys(p);
+    for (var c = 0u; c < 27u; c++) {
+        let range = spatial_hash_range(keys[c]);
+        for (var k = range.x; k < range.y; k++) {
+            let j = spatial_hash_sorted[k];
+            if (j == index) {
+                continue;
+            }
+            let other = grains[j];
+            let offset = p - other.position.xyz;
+            let reach = radius + other.position.w;
+            let distance2 = dot(offset, offset);
+            if (distance2 >= reach * reach || distance2 < 1e-12) {
+                continue;
+            }
+            let distance = sqrt(distance2);
+            let other_mass = grain_mass(other.position.w);
+            let effective_mass = mass * other_mass / (mass + other_mass);
+            force += contact_force(v - other.velocity.xyz, effective_mass, offset / distance, reach - distance);
+        }
+    }
+
+    // Ground and side walls; the top is open
+    let lower = params.bounds_min.xyz;
+    let upper = params.bounds_max.xyz;
+    force += contact_force(v, mass, vec3<f32>(0.0, 0.0, 1.0), radius - (p.z - lower.z));
+    force += contact_force(v, mass, vec3<f32>(1.0, 0.0, 0.0), radius - (p.x - lower.x));
+    force += contact_force(v, mass, vec3<f32>(-1.0, 0.0, 0.0), radius - (upper.x - p.x));
+    force += contact_force(v, mass, vec3<f32>(0.0, 1.0, 0.0), radius - (p.y - lower.y));
+    force += contact_force(v, mass, vec3<f32>(0.0, -1.0, 0.0), radius - (upper.y - p.y));
+
+    if (params.hopper != 0u) {
+        let opening = params.funnel.x;
+        let outlet = params.funnel.y;
+        let slope = params.funnel.z;
+        let axis = 0.5 * (lower.xy + upper.xy);
+        let radial = p.xy - axis;
+        let rho = length(radial);
+        let outward = select(vec2<f32>(1.0, 0.0), radial / rho, rho > 1e-6);
+
+        // Thin conical wall, pushing grains away from whichever side they touch
+        if (p.z >= outlet && p.z <= params.funnel.w) {
+            let scale = sqrt(1.0 + slope * slope);
+            let inward = vec3<f32>(-outward, slope) / scale;
+            let distance = (opening + slope * (p.z - outlet) - rho) / scale;
+            let side = select(-1.0, 1.0, distance >= 0.0);
+            force += contact_force(v, mass, side * inward, radius - abs(distance));
+        }
+        // Closed gate: a disk across the outlet
+        if (params.gate_closed != 0u && rho < opening + radius) {
+            let distance = p.z - outlet;
+            let side = select(-1.0, 1.0, distance >= 0.0);
+            force += contact_force(v, mass, vec3<f32>(0.0, 0.0, side), radius - abs(distance));
+        }
+    }
+
+    new_velocities[index] = vec4<f32>(v + force / mass * params.bounds_min.w, 0.0);
+}
+
+@compute @workgroup_size(64)
+fn integrate(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if (i >= params.count) {
+        return;
+    }
+    let velocity = new_velocities[i].xyz;
+    let grain = grains[i].position;
+    grains[i].position = vec4<f32>(grain.xyz + velocity * params.bounds_min.w, grain.w);
+    grains[i].velocity = vec4<f32>(velocity, 0.0);
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_initial_grains_fill_the_presets_without_overlap() {
+        for preset in GranularPreset::ALL {
+            let granular = Granular::new(3000, preset);
+            let grains = granular.initial_grains();
+            assert_eq!(grains.len(), 3000);
+            for (i, grain) in grains.iter().enumerate() {
+                let p = Vector3::new(grain.position[0], grain.position[1], grain.position[2]);
+                assert!(granular.in_spawn_region(p, granular.grain_radius * 0.99));
+                assert!(grain.position[3] <= granular.grain_radius);
+                for other in &grains[i + 1..] {
+                    let q = Vector3::new(other.position[0], other.position[1], other.position[2]);
+                    let distance = cgmath::InnerSpace::magnitude(p - q);
+                    assert!(distance >= grain.position[3] + other.position[3]);
+                }
+            }
+        }
+        assert_eq!(std::mem::size_of::<GranularUniforms>(), 80);
+    }
+
+    #[test]
+    fn test_grains_settle_on_the_ground() {
+        use crate::wgpu_utils::compute_primitives::{read_buffer, test_device};
+
+        // Needs a GPU adapter, skipped without one
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let mut granular = Granular::pile(500);
+        granular.initialize_gpu(&device, &queue);
+        for _ in 0..120 {
+            granular.update_gpu(&device, &queue, 1.0 / 60.0);
+        }
+
+        let grains: Vec<AgentInstance> =
+            read_buffer(&device, &queue, granular.grain_buffer().unwrap(), 500).unwrap();
+        let start_height =
+            granular.bounds_min.z + (granular.bounds_max.z - granular.bounds_min.z) * 0.2;
+        for grain in &grains {
+            let [x, y, z, radius] = grain.position;
+            assert!(x.is_finite() && y.is_finite() && z.is_finite());
+            // Resting on the ground or other grains, well below where they started
+            assert!(z > -radius * 0.5 && z < start_height);
+            let speed = grain.velocity[..3]
+                .iter()
+                .map(|v| v * v)
+                .sum::<f32>()
+                .sqrt();
+            assert!(speed < 1.0, "grain still moving at {speed}");
+        }
+    }
+}
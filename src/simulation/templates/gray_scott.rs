@@ -0,0 +1,629 @@
+//! Gray-Scott reaction-diffusion
+//!
+//! Two chemicals `u` and `v` diffuse at different rates and react as
+//! `u + 2v -> 3v`, while `u` is fed in and `v` is removed:
+//!
+//! ```text
+//! du/dt = Du ∇²u - u v² + F (1 - u)
+//! dv/dt = Dv ∇²v + u v² - (F + k) v
+//! ```
+//!
+//! Depending on the feed rate `F` and kill rate `k` the concentration of `v`
+//! settles into spots, stripes, worms or coral-like growth. The grid wraps
+//! around at the edges and is stepped explicitly on the GPU; `v` is shown
+//! directly as a heatmap.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::simulation::templates::{GrayScott, GrayScottPreset};
+//!
+//! let mut app = haggis::default();
+//! app.attach_simulation(GrayScott::new(256, 256).with_preset(GrayScottPreset::Worms));
+//! app.run();
+//! ```
+
+use std::sync::Arc;
+
+use cgmath::Vector3;
+use imgui::Ui;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use wgpu::{Device, Queue};
+
+use crate::gfx::scene::Scene;
+use crate::simulation::params::{ParamsUniform, SimParams};
+use crate::simulation::traits::Simulation;
+use crate::simulation::BaseSimulation;
+use crate::visualization::cut_plane_2d::{BufferElementType, BufferFormat};
+use crate::visualization::CutPlane2D;
+
+const WORKGROUP_SIZE: u32 = 8;
+
+/// Feed and kill rates of well-known patterns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GrayScottPreset {
+    /// Dividing spots ("mitosis")
+    #[default]
+    Spots,
+    /// Labyrinth of stripes
+    Stripes,
+    /// Short worms that settle in place
+    Worms,
+    /// Branching, coral-like growth
+    Coral,
+}
+
+impl GrayScottPreset {
+    pub const ALL: [GrayScottPreset; 4] = [
+        GrayScottPreset::Spots,
+        GrayScottPreset::Stripes,
+        GrayScottPreset::Worms,
+        GrayScottPreset::Coral,
+    ];
+
+    /// `(feed, kill)`
+    pub fn feed_kill(&self) -> (f32, f32) {
+        match self {
+            GrayScottPreset::Spots => (0.0367, 0.0649),
+            GrayScottPreset::Stripes => (0.029, 0.057),
+            GrayScottPreset::Worms => (0.078, 0.061),
+            GrayScottPreset::Coral => (0.0545, 0.062),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GrayScottPreset::Spots => "Spots",
+            GrayScottPreset::Stripes => "Stripes",
+            GrayScottPreset::Worms => "Worms",
+            GrayScottPreset::Coral => "Coral",
+        }
+    }
+}
+
+/// Tunable parameters of [`GrayScott`]
+#[derive(Debug, Clone, SimParams)]
+pub struct GrayScottParams {
+    #[param(range = 0.0..=0.1, step = 0.0005, label = "Feed Rate")]
+    pub feed: f32,
+    #[param(range = 0.0..=0.1, step = 0.0005, label = "Kill Rate")]
+    pub kill: f32,
+    #[param(range = 0.0..=1.0, step = 0.01, label = "Diffusion U")]
+    pub diffusion_u: f32,
+    #[param(range = 0.0..=1.0, step = 0.01, label = "Diffusion V")]
+    pub diffusion_v: f32,
+    /// Explicit step; unstable above about 1.0 with the default diffusion
+    #[param(range = 0.1..=1.2, step = 0.05, label = "Time Step")]
+    pub time_step: f32,
+    /// Rounded up to an even number so the result lands in the shown buffer
+    #[param(range = 2..=64, label = "Steps per Frame")]
+    pub steps_per_frame: u32,
+}
+
+impl Default for GrayScottParams {
+    fn default() -> Self {
+        let (feed, kill) = GrayScottPreset::default().feed_kill();
+        Self {
+            feed,
+            kill,
+            diffusion_u: 1.0,
+            diffusion_v: 0.5,
+            time_step: 1.0,
+            steps_per_frame: 8,
+        }
+    }
+}
+
+/// Uniforms of the reaction-diffusion shader
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct GrayScottUniforms {
+    width: u32,
+    height: u32,
+    feed: f32,
+    kill: f32,
+    diffusion_u: f32,
+    diffusion_v: f32,
+    time_step: f32,
+    _padding: u32,
+}
+
+struct GrayScottGpuResources {
+    pipeline: wgpu::ComputePipeline,
+    uniforms: ParamsUniform<GrayScottUniforms>,
+    u_a: wgpu::Buffer,
+    /// Concentration of `v`, shown by the heatmap
+    v_a: wgpu::Buffer,
+    a_to_b: wgpu::BindGroup,
+    b_to_a: wgpu::BindGroup,
+}
+
+/// GPU Gray-Scott reaction-diffusion with a built-in heatmap of `v`
+pub struct GrayScott {
+    base: BaseSimulation,
+    width: u32,
+    height: u32,
+    pub params: GrayScottParams,
+    preset: Option<GrayScottPreset>,
+    /// Number of random squares of `v` seeded on reset
+    seeds: u32,
+    /// Seed of the square placement, random if `None`
+    rng_seed: Option<u64>,
+    plane_position: Vector3<f32>,
+    plane_size: f32,
+    running: bool,
+    steps: u64,
+    needs_seed: bool,
+    needs_step: bool,
+    gpu: Option<GrayScottGpuResources>,
+}
+
+impl GrayScott {
+    /// Create a `width` x `height` grid with the spots preset
+    pub fn new(width: u32, height: u32) -> Self {
+        assert!(width > 0 && height > 0, "reaction-diffusion grid must not be empty");
+        Self {
+            base: BaseSimulation::new("Gray-Scott"),
+            width,
+            height,
+            params: GrayScottParams::default(),
+            preset: Some(GrayScottPreset::default()),
+            seeds: 12,
+            rng_seed: None,
+            plane_position: Vector3::new(0.0, 2.0, 0.0),
+            plane_size: 2.0,
+            running: true,
+            steps: 0,
+            needs_seed: true,
+            needs_step: false,
+            gpu: None,
+        }
+    }
+
+    /// Use the feed and kill rates of `preset`
+    pub fn with_preset(mut self, preset: GrayScottPreset) -> Self {
+        self.set_preset(preset);
+        self
+    }
+
+    pub fn with_params(mut self, params: GrayScottParams) -> Self {
+        self.params = params;
+        self.preset = None;
+        self
+    }
+
+    /// Number of random squares of `v` the grid is seeded with
+    pub fn with_seeds(mut self, seeds: u32) -> Self {
+        self.seeds = seeds;
+        self
+    }
+
+    /// Place the seed squares from a fixed random seed, so runs are reproducible
+    pub fn with_rng_seed(mut self, seed: u64) -> Self {
+        self.rng_seed = Some(seed);
+        self
+    }
+
+    /// Place the heatmap in the world
+    pub fn with_plane(mut self, position: Vector3<f32>, size: f32) -> Self {
+        self.plane_position = position;
+        self.plane_size = size;
+        self
+    }
+
+    pub fn set_preset(&mut self, preset: GrayScottPreset) {
+        (self.params.feed, self.params.kill) = preset.feed_kill();
+        self.preset = Some(preset);
+    }
+
+    /// Preset matching the current feed and kill rates, if any
+    pub fn preset(&self) -> Option<GrayScottPreset> {
+        self.preset
+    }
+
+    /// Number of steps taken since the last reseed
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// GPU buffer holding the current `v` concentration (`f32` per cell)
+    pub fn concentration_buffer(&self) -> Option<&wgpu::Buffer> {
+        self.gpu.as_ref().map(|gpu| &gpu.v_a)
+    }
+
+    /// Starting state: `u = 1` everywhere, with squares of `v` at random spots
+    fn seed_fields(&self) -> (Vec<f32>, Vec<f32>) {
+        let cells = (self.width * self.height) as usize;
+        let mut u = vec![1.0; cells];
+        let mut v = vec![0.0; cells];
+
+        let mut rng = match self.rng_seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_rng(&mut rand::rng()),
+        };
+        let half = (self.width.min(self.height) / 20).max(1) as i32;
+        for _ in 0..self.seeds {
+            let cx = rng.random_range(0..self.width) as i32;
+            let cy = rng.random_range(0..self.height) as i32;
+            for dy in -half..=half {
+                for dx in -half..=half {
+                    let x = (cx + dx).rem_euclid(self.width as i32) as u32;
+                    let y = (cy + dy).rem_euclid(self.height as i32) as u32;
+                    let index = (y * self.width + x) as usize;
+                    u[index] = 0.5;
+                    v[index] = 0.25;
+                }
+            }
+        }
+        (u, v)
+    }
+
+    fn uniforms(&self) -> GrayScottUniforms {
+        GrayScottUniforms {
+            width: self.width,
+            height: self.height,
+            feed: self.params.feed,
+            kill: self.params.kill,
+            diffusion_u: self.params.diffusion_u,
+            diffusion_v: self.params.diffusion_v,
+            time_step: self.params.time_step,
+            _padding: 0,
+        }
+    }
+
+    fn create_gpu_resources(&self, device: &Device) -> GrayScottGpuResources {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Gray-Scott Shader"),
+            source: wgpu::ShaderSource::Wgsl(GRAY_SCOTT_SHADER.into()),
+        });
+
+        let storage_entry = |binding: u32, read_only: bool| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Gray-Scott Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage_entry(1, true),
+                storage_entry(2, true),
+                storage_entry(3, false),
+                storage_entry(4, false),
+            ],
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Gray-Scott Pipeline"),
+            layout: Some(
+                &device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: Some("Gray-Scott Pipeline Layout"),
+                    bind_group_layouts: &[&bind_group_layout],
+                    push_constant_ranges: &[],
+                }),
+            ),
+            module: &shader,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let size = (self.width * self.height) as u64 * std::mem::size_of::<f32>() as u64;
+        let field_buffer = |label: &str| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size,
+                usage: wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_DST
+                    | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            })
+        };
+        let u_a = field_buffer("Gray-Scott U A");
+        let v_a = field_buffer("Gray-Scott V A");
+        // Second half of the ping-pong pair, kept alive by the bind groups
+        let u_b = field_buffer("Gray-Scott U B");
+        let v_b = field_buffer("Gray-Scott V B");
+        let uniforms = ParamsUniform::new(device, "Gray-Scott Uniforms", &self.uniforms());
+
+        let bind_group = |label: &str, buffers: [&wgpu::Buffer; 4]| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout: &bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: uniforms.buffer().as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: buffers[0].as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: buffers[1].as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: buffers[2].as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: buffers[3].as_entire_binding(),
+                    },
+                ],
+            })
+        };
+        let a_to_b = bind_group("Gray-Scott A->B", [&u_a, &v_a, &u_b, &v_b]);
+        let b_to_a = bind_group("Gray-Scott B->A", [&u_b, &v_b, &u_a, &v_a]);
+
+        GrayScottGpuResources {
+            pipeline,
+            uniforms,
+            u_a,
+            v_a,
+            a_to_b,
+            b_to_a,
+        }
+    }
+}
+
+impl Simulation for GrayScott {
+    fn initialize(&mut self, scene: &mut Scene) {
+        self.base.initialize(scene);
+    }
+
+    fn initialize_gpu(&mut self, device: &Device, queue: &Queue) {
+        let gpu = self.create_gpu_resources(device);
+
+        let mut heatmap = CutPlane2D::new();
+        heatmap.set_position(self.plane_position);
+        heatmap.set_size(self.plane_size);
+        heatmap.update_gpu_buffer(
+            Arc::new(gpu.v_a.clone()),
+            BufferFormat {
+                element_type: BufferElementType::F32,
+                width: self.width,
+                height: self.height,
+            },
+        );
+        heatmap.set_value_range(0.0, 0.4);
+
+        self.gpu = Some(gpu);
+        self.base.remove_visualization("heatmap");
+        self.base.add_visualization("heatmap", heatmap);
+        self.base.initialize_gpu(device, queue);
+    }
+
+    fn update(&mut self, delta_time: f32, scene: &mut Scene) {
+        self.base.update(delta_time, scene);
+    }
+
+    fn update_gpu(&mut self, device: &Device, queue: &Queue, delta_time: f32) {
+        let uniforms = self.uniforms();
+        if let Some(gpu) = self.gpu.as_mut() {
+            gpu.uniforms.update(queue, &uniforms);
+        }
+
+        if let Some(gpu) = self.gpu.as_ref() {
+            if self.needs_seed {
+                let (u, v) = self.seed_fields();
+                queue.write_buffer(&gpu.u_a, 0, bytemuck::cast_slice(&u));
+                queue.write_buffer(&gpu.v_a, 0, bytemuck::cast_slice(&v));
+                self.needs_seed = false;
+            }
+
+            let pairs = if self.running {
+                self.params.steps_per_frame.div_ceil(2)
+            } else {
+                u32::from(self.needs_step)
+            };
+            if pairs > 0 {
+                let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Gray-Scott Encoder"),
+                });
+                {
+                    crate::logging::crash::record_pass("Gray-Scott Step");
+                    let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                        label: Some("Gray-Scott Step"),
+                        timestamp_writes: None,
+                    });
+                    pass.set_pipeline(&gpu.pipeline);
+                    let groups_x = self.width.div_ceil(WORKGROUP_SIZE);
+                    let groups_y = self.height.div_ceil(WORKGROUP_SIZE);
+                    for _ in 0..pairs {
+                        pass.set_bind_group(0, &gpu.a_to_b, &[]);
+                        pass.dispatch_workgroups(groups_x, groups_y, 1);
+                        pass.set_bind_group(0, &gpu.b_to_a, &[]);
+                        pass.dispatch_workgroups(groups_x, groups_y, 1);
+                    }
+                }
+                queue.submit(std::iter::once(encoder.finish()));
+                self.steps += 2 * pairs as u64;
+            }
+            self.needs_step = false;
+        }
+
+        self.base.update_gpu(device, queue, delta_time);
+    }
+
+    fn apply_gpu_results_to_scene(&mut self, device: &Device, scene: &mut Scene) {
+        self.base.apply_gpu_results_to_scene(device, scene);
+    }
+
+    fn render_ui(&mut self, ui: &Ui) {
+        ui.window("Gray-Scott Reaction-Diffusion")
+            .size([360.0, 380.0], imgui::Condition::FirstUseEver)
+            .build(|| {
+                ui.text(format!("Grid: {}x{}", self.width, self.height));
+                ui.text(format!("Steps: {}", self.steps));
+                ui.separator();
+
+                if ui.button(if self.running { "Pause" } else { "Play" }) {
+                    self.running = !self.running;
+                }
+                ui.same_line();
+                if ui.button("Step") {
+                    self.needs_step = true;
+                }
+                ui.same_line();
+                if ui.button("Reseed") {
+                    self.needs_seed = true;
+                    self.steps = 0;
+                }
+
+                ui.separator();
+                ui.text("Preset:");
+                for preset in GrayScottPreset::ALL {
+                    if ui.radio_button_bool(preset.as_str(), self.preset == Some(preset)) {
+                        self.set_preset(preset);
+                    }
+                }
+
+                let changed = self.params.build_ui(ui);
+                if changed.contains(&"feed") || changed.contains(&"kill") {
+                    self.preset = None;
+                }
+            });
+
+        self.base.render_ui(ui);
+    }
+
+    fn name(&self) -> &str {
+        "Gray-Scott"
+    }
+
+    fn is_running(&self) -> bool {
+        self.running
+    }
+
+    fn set_running(&mut self, running: bool) {
+        self.running = running;
+    }
+
+    fn reset(&mut self, scene: &mut Scene) {
+        self.needs_seed = true;
+        self.steps = 0;
+        self.base.reset(scene);
+    }
+
+    fn is_gpu_ready(&self) -> bool {
+        self.gpu.is_some()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        &self.base
+    }
+}
+
+const GRAY_SCOTT_SHADER: &str = r#"
+struct GrayScottUniforms {
+    width: u32,
+    height: u32,
+    feed: f32,
+    kill: f32,
+    diffusion_u: f32,
+    diffusion_v: f32,
+    time_step: f32,
+    _padding: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: GrayScottUniforms;
+@group(0) @binding(1) var<storage, read> u_in: array<f32>;
+@group(0) @binding(2) var<storage, read> v_in: array<f32>;
+@group(0) @binding(3) var<storage, read_write> u_out: array<f32>;
+@group(0) @binding(4) var<storage, read_write> v_out: array<f32>;
+
+fn wrap(x: i32, y: i32) -> u32 {
+    let w = i32(params.width);
+    let h = i32(params.height);
+    return u32(((y + h) % h) * w + (x + w) % w);
+}
+
+@compute @workgroup_size(8, 8)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= params.width || id.y >= params.height) {
+        return;
+    }
+    let x = i32(id.x);
+    let y = i32(id.y);
+    let index = id.y * params.width + id.x;
+
+    // 3x3 Laplacian: 0.2 for edge neighbors, 0.05 for corners
+    var lap = -vec2<f32>(u_in[index], v_in[index]);
+    for (var dy = -1; dy <= 1; dy++) {
+        for (var dx = -1; dx <= 1; dx++) {
+            if (dx == 0 && dy == 0) {
+                continue;
+            }
+            let weight = select(0.2, 0.05, dx != 0 && dy != 0);
+            let neighbor = wrap(x + dx, y + dy);
+            lap += weight * vec2<f32>(u_in[neighbor], v_in[neighbor]);
+        }
+    }
+
+    let u = u_in[index];
+    let v = v_in[index];
+    let reaction = u * v * v;
+    let du = params.diffusion_u * lap.x - reaction + params.feed * (1.0 - u);
+    let dv = params.diffusion_v * lap.y + reaction - (params.feed + params.kill) * v;
+    u_out[index] = clamp(u + params.time_step * du, 0.0, 1.0);
+    v_out[index] = clamp(v + params.time_step * dv, 0.0, 1.0);
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::SimulationRun;
+    use crate::wgpu_utils::compute_primitives::test_device;
+
+    #[test]
+    fn test_presets_and_seeding() {
+        let mut simulation = GrayScott::new(40, 20).with_preset(GrayScottPreset::Worms);
+        assert_eq!(simulation.params.feed, 0.078);
+        assert_eq!(simulation.preset(), Some(GrayScottPreset::Worms));
+
+        simulation.seeds = 1;
+        let (u, v) = simulation.seed_fields();
+        assert_eq!(u.len(), 40 * 20);
+        // One 3x3 square (half size 20 / 20 = 1)
+        assert_eq!(v.iter().filter(|&&value| value > 0.0).count(), 9);
+        assert_eq!(std::mem::size_of::<GrayScottUniforms>(), 32);
+
+        let seeded = || GrayScott::new(40, 20).with_rng_seed(7).seed_fields();
+        assert_eq!(seeded(), seeded());
+    }
+
+    #[test]
+    fn test_gpu_steps_are_deterministic() {
+        let record = || {
+            let (device, queue) = test_device()?;
+            let simulation = GrayScott::new(32, 32).with_rng_seed(3);
+            let mut run = SimulationRun::new(simulation).with_gpu(device, queue);
+            run.record(20, 5, |run| {
+                run.hash_buffer(run.simulation().concentration_buffer().unwrap())
+            })
+            .ok()
+        };
+        let Some(first) = record() else {
+            return;
+        };
+        assert_eq!(record(), Some(first.clone()));
+        assert_ne!(first.entries()[0].1, first.entries()[4].1);
+    }
+}
@@ -0,0 +1,1098 @@
+//! 2D heat diffusion with implicit time stepping
+//!
+//! Solves the heat equation `du/dt = α ∇²u` on a regular grid with backward
+//! Euler steps, which stay stable for any time step. Each step solves the
+//! linear system
+//!
+//! ```text
+//! (1 + 4r) u[i,j] - r (u[i-1,j] + u[i+1,j] + u[i,j-1] + u[i,j+1]) = u_old[i,j]
+//! r = α dt / dx²
+//! ```
+//!
+//! iteratively on the GPU, with either Jacobi (ping-pong buffers) or red-black
+//! Gauss-Seidel (in place, converges about twice as fast). The temperature
+//! buffer is shown directly as a heatmap, without a readback.
+//!
+//! [`with_refinement`](HeatDiffusion2D::with_refinement) adds finer patches,
+//! e.g. around a hot spot. They are solved with Jacobi iterations after each
+//! coarse step, averaged back into the heatmap and outlined on top of it.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::simulation::templates::{HeatBoundary, HeatDiffusion2D};
+//!
+//! let mut app = haggis::default();
+//! app.attach_simulation(
+//!     HeatDiffusion2D::new(256, 256)
+//!         .with_boundary(HeatBoundary::hot_wall(1.0, 0.0))
+//!         .with_hot_spot(0.5, 0.5, 0.1, 1.0),
+//! );
+//! app.run();
+//! ```
+
+use std::sync::Arc;
+
+use cgmath::Vector3;
+use imgui::Ui;
+use wgpu::{Device, Queue};
+
+use crate::gfx::scene::Scene;
+use crate::simulation::gpu::{AmrHierarchy2D, AmrLayout};
+use crate::simulation::params::{ParamsUniform, SimParams};
+use crate::simulation::traits::Simulation;
+use crate::simulation::BaseSimulation;
+use crate::visualization::cut_plane_2d::{BufferElementType, BufferFormat};
+use crate::visualization::{CutPlane2D, DomainBox};
+
+const WORKGROUP_SIZE: u32 = 8;
+
+/// Iterative method used for each implicit step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HeatSolver {
+    /// Jacobi iteration, reading one buffer and writing the other
+    Jacobi,
+    /// Red-black Gauss-Seidel, updating the field in place in two half sweeps
+    #[default]
+    RedBlackGaussSeidel,
+}
+
+impl HeatSolver {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HeatSolver::Jacobi => "Jacobi",
+            HeatSolver::RedBlackGaussSeidel => "Red-Black Gauss-Seidel",
+        }
+    }
+}
+
+/// Boundary condition on the edges of the grid
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HeatBoundary {
+    /// Dirichlet: each edge is held at a fixed temperature
+    Fixed {
+        left: f32,
+        right: f32,
+        bottom: f32,
+        top: f32,
+    },
+    /// Neumann with zero flux: no heat crosses the edges
+    Insulated,
+    /// Opposite edges are connected
+    Periodic,
+}
+
+impl Default for HeatBoundary {
+    fn default() -> Self {
+        HeatBoundary::fixed(0.0)
+    }
+}
+
+impl HeatBoundary {
+    /// All edges held at `temperature`
+    pub fn fixed(temperature: f32) -> Self {
+        HeatBoundary::Fixed {
+            left: temperature,
+            right: temperature,
+            bottom: temperature,
+            top: temperature,
+        }
+    }
+
+    /// Left edge at `hot`, the other edges at `cold`
+    pub fn hot_wall(hot: f32, cold: f32) -> Self {
+        HeatBoundary::Fixed {
+            left: hot,
+            right: cold,
+            bottom: cold,
+            top: cold,
+        }
+    }
+
+    /// Left edge at `hot`, right edge at `cold`, top and bottom at the mean
+    pub fn gradient(hot: f32, cold: f32) -> Self {
+        let mean = 0.5 * (hot + cold);
+        HeatBoundary::Fixed {
+            left: hot,
+            right: cold,
+            bottom: mean,
+            top: mean,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HeatBoundary::Fixed { .. } => "Fixed",
+            HeatBoundary::Insulated => "Insulated",
+            HeatBoundary::Periodic => "Periodic",
+        }
+    }
+
+    /// Mode index and edge temperatures as seen by the shader
+    fn to_gpu(self) -> (u32, [f32; 4]) {
+        match self {
+            HeatBoundary::Fixed {
+                left,
+                right,
+                bottom,
+                top,
+            } => (0, [left, right, bottom, top]),
+            HeatBoundary::Insulated => (1, [0.0; 4]),
+            HeatBoundary::Periodic => (2, [0.0; 4]),
+        }
+    }
+}
+
+/// Tunable parameters of [`HeatDiffusion2D`]
+#[derive(Debug, Clone, SimParams)]
+pub struct HeatParams {
+    /// Thermal diffusivity α in cells²/s (scaled by `1 / cell_size²`)
+    #[param(range = 0.0..=200.0, step = 1.0)]
+    pub diffusivity: f32,
+    #[param(range = 0.001..=1.0, step = 0.01, label = "Time Step")]
+    pub time_step: f32,
+    #[param(range = 1..=200, label = "Solver Iterations")]
+    pub iterations: u32,
+    #[param(range = 1..=16, label = "Steps per Frame")]
+    pub steps_per_frame: u32,
+}
+
+impl Default for HeatParams {
+    fn default() -> Self {
+        Self {
+            diffusivity: 50.0,
+            time_step: 0.1,
+            iterations: 40,
+            steps_per_frame: 1,
+        }
+    }
+}
+
+/// Uniforms of the heat shader
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct HeatUniforms {
+    width: u32,
+    height: u32,
+    /// α dt / dx²
+    r: f32,
+    boundary: u32,
+    /// Left, right, bottom, top temperatures for fixed boundaries
+    edges: [f32; 4],
+}
+
+struct HeatGpuResources {
+    jacobi_pipeline: wgpu::ComputePipeline,
+    red_pipeline: wgpu::ComputePipeline,
+    black_pipeline: wgpu::ComputePipeline,
+    uniforms: ParamsUniform<HeatUniforms>,
+    /// Temperature at the start of the step
+    rhs: wgpu::Buffer,
+    /// Current temperature, shown by the heatmap
+    field_a: wgpu::Buffer,
+    jacobi_a_to_b: wgpu::BindGroup,
+    jacobi_b_to_a: wgpu::BindGroup,
+    /// Updates `field_a` in place
+    gauss_seidel: wgpu::BindGroup,
+    amr: Option<HeatAmrResources>,
+}
+
+/// Fine patches of a refined grid
+struct HeatAmrResources {
+    hierarchy: AmrHierarchy2D,
+    pipeline: wgpu::ComputePipeline,
+    /// Fine temperature at the start of the step
+    rhs: wgpu::Buffer,
+    jacobi_a_to_b: wgpu::BindGroup,
+    jacobi_b_to_a: wgpu::BindGroup,
+}
+
+/// GPU solver for the 2D heat equation with a built-in heatmap
+///
+/// Hot spot positions are normalized to the grid: `(0, 0)` is the bottom left
+/// corner and `(1, 1)` the top right.
+pub struct HeatDiffusion2D {
+    base: BaseSimulation,
+    width: u32,
+    height: u32,
+    cell_size: f32,
+    pub params: HeatParams,
+    solver: HeatSolver,
+    boundary: HeatBoundary,
+    initial_temperature: f32,
+    hot_spots: Vec<([f32; 2], f32, f32)>,
+    custom_field: Option<Vec<f32>>,
+    refinement: Option<AmrLayout>,
+    plane_position: Vector3<f32>,
+    plane_size: f32,
+    running: bool,
+    steps: u64,
+    time: f64,
+    needs_upload: bool,
+    needs_step: bool,
+    gpu: Option<HeatGpuResources>,
+}
+
+impl HeatDiffusion2D {
+    /// Create a solver for a `width` x `height` grid, initially at zero with
+    /// all edges held at zero
+    pub fn new(width: u32, height: u32) -> Self {
+        assert!(width > 0 && height > 0, "heat grid must not be empty");
+        Self {
+            base: BaseSimulation::new("Heat Diffusion 2D"),
+            width,
+            height,
+            cell_size: 1.0,
+            params: HeatParams::default(),
+            solver: HeatSolver::default(),
+            boundary: HeatBoundary::default(),
+            initial_temperature: 0.0,
+            hot_spots: Vec::new(),
+            custom_field: None,
+            refinement: None,
+            plane_position: Vector3::new(0.0, 2.0, 0.0),
+            plane_size: 2.0,
+            running: true,
+            steps: 0,
+            time: 0.0,
+            needs_upload: true,
+            needs_step: false,
+            gpu: None,
+        }
+    }
+
+    pub fn with_boundary(mut self, boundary: HeatBoundary) -> Self {
+        self.boundary = boundary;
+        self
+    }
+
+    pub fn with_solver(mut self, solver: HeatSolver) -> Self {
+        self.solver = solver;
+        self
+    }
+
+    pub fn with_params(mut self, params: HeatParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    pub fn with_diffusivity(mut self, diffusivity: f32) -> Self {
+        self.params.diffusivity = diffusivity;
+        self
+    }
+
+    /// Grid spacing; diffusivity is divided by its square
+    pub fn with_cell_size(mut self, cell_size: f32) -> Self {
+        self.cell_size = cell_size;
+        self
+    }
+
+    /// Temperature of the whole grid before hot spots are added
+    pub fn with_initial_temperature(mut self, temperature: f32) -> Self {
+        self.initial_temperature = temperature;
+        self
+    }
+
+    /// Disc at normalized `(x, y)` with normalized `radius`, set to `temperature`
+    pub fn with_hot_spot(mut self, x: f32, y: f32, radius: f32, temperature: f32) -> Self {
+        self.hot_spots.push(([x, y], radius, temperature));
+        self
+    }
+
+    /// Start from an arbitrary field, row by row from the bottom
+    ///
+    /// # Panics
+    ///
+    /// If `field` doesn't have `width * height` values
+    pub fn with_initial_field(mut self, field: Vec<f32>) -> Self {
+        assert_eq!(
+            field.len(),
+            (self.width * self.height) as usize,
+            "initial field must have width * height values"
+        );
+        self.custom_field = Some(field);
+        self
+    }
+
+    /// Solve the patches of `layout` on a finer grid
+    ///
+    /// Patches always use Jacobi iterations, with ghost cells interpolated
+    /// from the coarse grid.
+    ///
+    /// # Panics
+    ///
+    /// If `layout` isn't laid out over a `width` x `height` grid
+    pub fn with_refinement(mut self, layout: AmrLayout) -> Self {
+        assert_eq!(
+            layout.coarse_size(),
+            [self.width, self.height],
+            "refinement layout must cover the heat grid"
+        );
+        self.refinement = Some(layout);
+        self
+    }
+
+    /// Place the heatmap in the world
+    pub fn with_plane(mut self, position: Vector3<f32>, size: f32) -> Self {
+        self.plane_position = position;
+        self.plane_size = size;
+        self
+    }
+
+    pub fn boundary(&self) -> HeatBoundary {
+        self.boundary
+    }
+
+    pub fn solver(&self) -> HeatSolver {
+        self.solver
+    }
+
+    /// Change the boundary condition; takes effect on the next step
+    pub fn set_boundary(&mut self, boundary: HeatBoundary) {
+        self.boundary = boundary;
+        self.update_heatmap_range();
+    }
+
+    pub fn set_solver(&mut self, solver: HeatSolver) {
+        self.solver = solver;
+    }
+
+    /// Number of implicit steps taken since the last reset
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Simulated time since the last reset
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// GPU buffer holding the current temperature (`f32` per cell), for use
+    /// by other passes once the GPU is initialized
+    pub fn temperature_buffer(&self) -> Option<&wgpu::Buffer> {
+        self.gpu.as_ref().map(|gpu| &gpu.field_a)
+    }
+
+    /// GPU buffer holding the temperature of the refined patches, laid out
+    /// as described by [`AmrHierarchy2D::fine`]
+    pub fn fine_temperature_buffer(&self) -> Option<&wgpu::Buffer> {
+        let amr = self.gpu.as_ref()?.amr.as_ref()?;
+        Some(amr.hierarchy.fine())
+    }
+
+    /// Temperature field at time zero
+    fn initial_field(&self) -> Vec<f32> {
+        if let Some(field) = &self.custom_field {
+            return field.clone();
+        }
+
+        let mut field = vec![self.initial_temperature; (self.width * self.height) as usize];
+        for &([cx, cy], radius, temperature) in &self.hot_spots {
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    let dx = (x as f32 + 0.5) / self.width as f32 - cx;
+                    let dy = (y as f32 + 0.5) / self.height as f32 - cy;
+                    if dx * dx + dy * dy <= radius * radius {
+                        field[(y * self.width + x) as usize] = temperature;
+                    }
+                }
+            }
+        }
+        field
+    }
+
+    /// Lowest and highest temperature the solution can reach
+    ///
+    /// The heat equation obeys a maximum principle, so the initial field and
+    /// the fixed edge temperatures bound it for all time.
+    fn temperature_range(&self) -> [f32; 2] {
+        let mut values = self.initial_field();
+        if let HeatBoundary::Fixed { .. } = self.boundary {
+            values.extend_from_slice(&self.boundary.to_gpu().1);
+        }
+
+        let min = values.iter().copied().fold(f32::INFINITY, f32::min);
+        let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        if max - min > f32::EPSILON {
+            [min, max]
+        } else {
+            [min, min + 1.0]
+        }
+    }
+
+    fn uniforms(&self) -> HeatUniforms {
+        let (boundary, edges) = self.boundary.to_gpu();
+        HeatUniforms {
+            width: self.width,
+            height: self.height,
+            r: self.params.diffusivity * self.params.time_step
+                / (self.cell_size * self.cell_size),
+            boundary,
+            edges,
+        }
+    }
+
+    fn update_heatmap_range(&mut self) {
+        let [min, max] = self.temperature_range();
+        if let Some(plane) = self
+            .base
+            .get_visualization_mut("heatmap")
+            .and_then(|component| component.as_any_mut().downcast_mut::<CutPlane2D>())
+        {
+            plane.set_value_range(min, max);
+        }
+    }
+
+    fn create_gpu_resources(&self, device: &Device) -> HeatGpuResources {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Heat Diffusion Shader"),
+            source: wgpu::ShaderSource::Wgsl(HEAT_DIFFUSION_SHADER.into()),
+        });
+
+        let storage_entry = |binding: u32, read_only: bool| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Heat Diffusion Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage_entry(1, true),
+                storage_entry(2, true),
+                storage_entry(3, false),
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Heat Diffusion Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |entry_point: &str| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+
+        let size = (self.width * self.height) as u64 * std::mem::size_of::<f32>() as u64;
+        let field_buffer = |label: &str| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size,
+                usage: wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_DST
+                    | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            })
+        };
+        let rhs = field_buffer("Heat RHS Buffer");
+        let field_a = field_buffer("Heat Field A");
+        // Scratch buffer for Jacobi, kept alive by the bind groups
+        let field_b = field_buffer("Heat Field B");
+        let uniforms = ParamsUniform::new(device, "Heat Diffusion Uniforms", &self.uniforms());
+
+        let bind_group = |label: &str, src: &wgpu::Buffer, dst: &wgpu::Buffer| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout: &bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: uniforms.buffer().as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: rhs.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: src.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: dst.as_entire_binding(),
+                    },
+                ],
+            })
+        };
+        let jacobi_a_to_b = bind_group("Heat Jacobi A->B", &field_a, &field_b);
+        let jacobi_b_to_a = bind_group("Heat Jacobi B->A", &field_b, &field_a);
+        // Gauss-Seidel doesn't read `src`; bind the scratch buffer so it never
+        // aliases the field being written
+        let gauss_seidel = bind_group("Heat Gauss-Seidel", &field_b, &field_a);
+
+        let amr = self.refinement.clone().map(|layout| {
+            let hierarchy = AmrHierarchy2D::new(device, layout, &field_a);
+            let fine_size = hierarchy.fine().size();
+            let fine_buffer = |label: &str| {
+                device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some(label),
+                    size: fine_size,
+                    usage: wgpu::BufferUsages::STORAGE
+                        | wgpu::BufferUsages::COPY_DST
+                        | wgpu::BufferUsages::COPY_SRC,
+                    mapped_at_creation: false,
+                })
+            };
+            let rhs = fine_buffer("Heat Fine RHS Buffer");
+            let fine_b = fine_buffer("Heat Fine Field B");
+
+            let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("Heat Refinement Shader"),
+                source: wgpu::ShaderSource::Wgsl(heat_refinement_shader().into()),
+            });
+            let mut entries = vec![wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }];
+            entries.extend(AmrHierarchy2D::bind_group_layout_entries(
+                1,
+                wgpu::ShaderStages::COMPUTE,
+            ));
+            entries.extend([storage_entry(5, true), storage_entry(6, false)]);
+            let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Heat Refinement Bind Group Layout"),
+                entries: &entries,
+            });
+            let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Heat Refinement Pipeline Layout"),
+                bind_group_layouts: &[&layout],
+                push_constant_ranges: &[],
+            });
+            let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("fine_jacobi"),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: Some("fine_jacobi"),
+                compilation_options: Default::default(),
+                cache: None,
+            });
+
+            let bind_group = |label: &str, src: &wgpu::Buffer, dst: &wgpu::Buffer| {
+                let mut entries = vec![wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniforms.buffer().as_entire_binding(),
+                }];
+                entries.extend(hierarchy.bind_group_entries(1, src));
+                entries.extend([
+                    wgpu::BindGroupEntry {
+                        binding: 5,
+                        resource: rhs.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 6,
+                        resource: dst.as_entire_binding(),
+                    },
+                ]);
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some(label),
+                    layout: &layout,
+                    entries: &entries,
+                })
+            };
+            let jacobi_a_to_b = bind_group("Heat Fine Jacobi A->B", hierarchy.fine(), &fine_b);
+            let jacobi_b_to_a = bind_group("Heat Fine Jacobi B->A", &fine_b, hierarchy.fine());
+
+            HeatAmrResources {
+                pipeline,
+                rhs,
+                jacobi_a_to_b,
+                jacobi_b_to_a,
+                hierarchy,
+            }
+        });
+
+        HeatGpuResources {
+            jacobi_pipeline: pipeline("jacobi"),
+            red_pipeline: pipeline("red"),
+            black_pipeline: pipeline("black"),
+            uniforms,
+            rhs,
+            field_a,
+            jacobi_a_to_b,
+            jacobi_b_to_a,
+            gauss_seidel,
+            amr,
+        }
+    }
+
+    /// Record `count` implicit steps
+    fn encode_steps(&self, gpu: &HeatGpuResources, encoder: &mut wgpu::CommandEncoder, count: u32) {
+        let size = gpu.field_a.size();
+        let groups_x = self.width.div_ceil(WORKGROUP_SIZE);
+        let groups_y = self.height.div_ceil(WORKGROUP_SIZE);
+        let iterations = self.params.iterations.max(1);
+
+        for _ in 0..count {
+            encoder.copy_buffer_to_buffer(&gpu.field_a, 0, &gpu.rhs, 0, size);
+
+            crate::logging::crash::record_pass("Heat Diffusion Step");
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Heat Diffusion Step"),
+                timestamp_writes: None,
+            });
+            match self.solver {
+                HeatSolver::Jacobi => {
+                    pass.set_pipeline(&gpu.jacobi_pipeline);
+                    // Pairs of sweeps so the result always ends up in field A
+                    for _ in 0..iterations.div_ceil(2) {
+                        pass.set_bind_group(0, &gpu.jacobi_a_to_b, &[]);
+                        pass.dispatch_workgroups(groups_x, groups_y, 1);
+                        pass.set_bind_group(0, &gpu.jacobi_b_to_a, &[]);
+                        pass.dispatch_workgroups(groups_x, groups_y, 1);
+                    }
+                }
+                HeatSolver::RedBlackGaussSeidel => {
+                    pass.set_bind_group(0, &gpu.gauss_seidel, &[]);
+                    for _ in 0..iterations {
+                        pass.set_pipeline(&gpu.red_pipeline);
+                        pass.dispatch_workgroups(groups_x, groups_y, 1);
+                        pass.set_pipeline(&gpu.black_pipeline);
+                        pass.dispatch_workgroups(groups_x, groups_y, 1);
+                    }
+                }
+            }
+            drop(pass);
+
+            if let Some(amr) = &gpu.amr {
+                Self::encode_fine_step(amr, encoder, iterations);
+            }
+        }
+    }
+
+    /// Record one implicit step of the refined patches, using the coarse
+    /// field just solved for ghost cells, and average them back onto it
+    fn encode_fine_step(
+        amr: &HeatAmrResources,
+        encoder: &mut wgpu::CommandEncoder,
+        iterations: u32,
+    ) {
+        let fine = amr.hierarchy.fine();
+        encoder.copy_buffer_to_buffer(fine, 0, &amr.rhs, 0, fine.size());
+
+        let groups = (amr.hierarchy.layout().fine_len() as u32).div_ceil(256);
+        {
+            crate::logging::crash::record_pass("Heat Refinement Step");
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Heat Refinement Step"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&amr.pipeline);
+            for _ in 0..iterations.div_ceil(2) {
+                pass.set_bind_group(0, &amr.jacobi_a_to_b, &[]);
+                pass.dispatch_workgroups(groups, 1, 1);
+                pass.set_bind_group(0, &amr.jacobi_b_to_a, &[]);
+                pass.dispatch_workgroups(groups, 1, 1);
+            }
+        }
+        amr.hierarchy.restrict(encoder);
+    }
+
+    /// Outlines of the refined patches on the heatmap
+    fn patch_outlines(&self) -> Vec<DomainBox> {
+        let Some(layout) = &self.refinement else {
+            return Vec::new();
+        };
+        let origin = self.plane_position;
+        let to_plane = |[u, v]: [f32; 2]| {
+            [
+                origin.x + self.plane_size * (2.0 * u - 1.0),
+                origin.y + self.plane_size * (2.0 * v - 1.0),
+                origin.z,
+            ]
+        };
+        (0..layout.patches().len())
+            .map(|patch| {
+                let [min, max] = layout.patch_rect(patch);
+                DomainBox::new(to_plane(min), to_plane(max)).with_color([1.0, 1.0, 1.0, 0.9])
+            })
+            .collect()
+    }
+}
+
+impl Simulation for HeatDiffusion2D {
+    fn initialize(&mut self, scene: &mut Scene) {
+        self.base.initialize(scene);
+    }
+
+    fn initialize_gpu(&mut self, device: &Device, queue: &Queue) {
+        let gpu = self.create_gpu_resources(device);
+
+        let mut heatmap = CutPlane2D::new();
+        heatmap.set_position(self.plane_position);
+        heatmap.set_size(self.plane_size);
+        heatmap.update_gpu_buffer(
+            Arc::new(gpu.field_a.clone()),
+            BufferFormat {
+                element_type: BufferElementType::F32,
+                width: self.width,
+                height: self.height,
+            },
+        );
+        let [min, max] = self.temperature_range();
+        heatmap.set_value_range(min, max);
+
+        self.gpu = Some(gpu);
+        self.base.remove_visualization("heatmap");
+        self.base.add_visualization("heatmap", heatmap);
+        for (patch, outline) in self.patch_outlines().into_iter().enumerate() {
+            let name = format!("refined_patch_{}", patch);
+            self.base.remove_visualization(&name);
+            self.base.add_visualization(&name, outline);
+        }
+        self.base.initialize_gpu(device, queue);
+    }
+
+    fn update(&mut self, delta_time: f32, scene: &mut Scene) {
+        self.base.update(delta_time, scene);
+    }
+
+    fn update_gpu(&mut self, device: &Device, queue: &Queue, delta_time: f32) {
+        let uniforms = self.uniforms();
+        if let Some(gpu) = self.gpu.as_mut() {
+            gpu.uniforms.update(queue, &uniforms);
+        }
+
+        if let Some(gpu) = self.gpu.as_ref() {
+            if self.needs_upload {
+                let field = self.initial_field();
+                queue.write_buffer(&gpu.field_a, 0, bytemuck::cast_slice(&field));
+                if let Some(amr) = &gpu.amr {
+                    amr.hierarchy
+                        .write_fine(queue, &amr.hierarchy.layout().prolong(&field));
+                }
+                self.needs_upload = false;
+            }
+
+            let count = if self.running {
+                self.params.steps_per_frame
+            } else {
+                u32::from(self.needs_step)
+            };
+            if count > 0 {
+                let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Heat Diffusion Encoder"),
+                });
+                self.encode_steps(gpu, &mut encoder, count);
+                queue.submit(std::iter::once(encoder.finish()));
+
+                self.steps += count as u64;
+                self.time += count as f64 * self.params.time_step as f64;
+            }
+            self.needs_step = false;
+        }
+
+        self.base.update_gpu(device, queue, delta_time);
+    }
+
+    fn apply_gpu_results_to_scene(&mut self, device: &Device, scene: &mut Scene) {
+        self.base.apply_gpu_results_to_scene(device, scene);
+    }
+
+    fn render_ui(&mut self, ui: &Ui) {
+        ui.window("Heat Diffusion 2D")
+            .size([360.0, 420.0], imgui::Condition::FirstUseEver)
+            .build(|| {
+                ui.text(format!("Grid: {}x{}", self.width, self.height));
+                if let Some(layout) = &self.refinement {
+                    ui.text(format!(
+                        "Refined patches: {} ({}x)",
+                        layout.patches().len(),
+                        layout.ratio()
+                    ));
+                }
+                ui.text(format!("Time: {:.2} s ({} steps)", self.time, self.steps));
+                ui.text(format!("r = a*dt/dx^2 = {:.3}", self.uniforms().r));
+                ui.separator();
+
+                if ui.button(if self.running { "Pause" } else { "Play" }) {
+                    self.running = !self.running;
+                }
+                ui.same_line();
+                if ui.button("Step") {
+                    self.needs_step = true;
+                }
+                ui.same_line();
+                if ui.button("Reset") {
+                    self.needs_upload = true;
+                    self.steps = 0;
+                    self.time = 0.0;
+                }
+
+                ui.separator();
+                ui.text("Solver:");
+                for solver in [HeatSolver::Jacobi, HeatSolver::RedBlackGaussSeidel] {
+                    if ui.radio_button_bool(solver.as_str(), self.solver == solver) {
+                        self.solver = solver;
+                    }
+                }
+                self.params.build_ui(ui);
+
+                ui.separator();
+                ui.text("Boundary:");
+                let mut boundary = self.boundary;
+                for preset in [
+                    HeatBoundary::fixed(0.0),
+                    HeatBoundary::Insulated,
+                    HeatBoundary::Periodic,
+                ] {
+                    if ui.radio_button_bool(preset.as_str(), boundary.as_str() == preset.as_str())
+                        && boundary.as_str() != preset.as_str()
+                    {
+                        boundary = preset;
+                    }
+                }
+                if let HeatBoundary::Fixed {
+                    left,
+                    right,
+                    bottom,
+                    top,
+                } = &mut boundary
+                {
+                    ui.input_float("Left", left).build();
+                    ui.input_float("Right", right).build();
+                    ui.input_float("Bottom", bottom).build();
+                    ui.input_float("Top", top).build();
+                }
+                if boundary != self.boundary {
+                    self.set_boundary(boundary);
+                }
+            });
+
+        self.base.render_ui(ui);
+    }
+
+    fn name(&self) -> &str {
+        "Heat Diffusion 2D"
+    }
+
+    fn is_running(&self) -> bool {
+        self.running
+    }
+
+    fn set_running(&mut self, running: bool) {
+        self.running = running;
+    }
+
+    fn reset(&mut self, scene: &mut Scene) {
+        self.needs_upload = true;
+        self.steps = 0;
+        self.time = 0.0;
+        self.base.reset(scene);
+    }
+
+    fn is_gpu_ready(&self) -> bool {
+        self.gpu.is_some()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        &self.base
+    }
+}
+
+const HEAT_DIFFUSION_SHADER: &str = r#"
+struct HeatUniforms {
+    width: u32,
+    height: u32,
+    r: f32,
+    boundary: u32,    // 0 = fixed, 1 = insulated, 2 = periodic
+    edges: vec4<f32>, // left, right, bottom, top
+}
+
+@group(0) @binding(0) var<uniform> params: HeatUniforms;
+@group(0) @binding(1) var<storage, read> rhs: array<f32>;
+@group(0) @binding(2) var<storage, read> src: array<f32>;
+@group(0) @binding(3) var<storage, read_write> dst: array<f32>;
+
+// Index of the cell at (x, y), or -1 - edge for a fixed-temperature ghost cell
+fn neighbor(x: i32, y: i32) -> i32 {
+    let w = i32(params.width);
+    let h = i32(params.height);
+    if (params.boundary == 2u) {
+        return ((y + h) % h) * w + (x + w) % w;
+    }
+    if (params.boundary == 1u) {
+        // Mirrored ghost cells: zero flux across the edge
+        return clamp(y, 0, h - 1) * w + clamp(x, 0, w - 1);
+    }
+    if (x < 0) { return -1; }
+    if (x >= w) { return -2; }
+    if (y < 0) { return -3; }
+    if (y >= h) { return -4; }
+    return y * w + x;
+}
+
+fn read_src(index: i32) -> f32 {
+    if (index < 0) {
+        return params.edges[-index - 1];
+    }
+    return src[index];
+}
+
+fn read_dst(index: i32) -> f32 {
+    if (index < 0) {
+        return params.edges[-index - 1];
+    }
+    return dst[index];
+}
+
+fn in_grid(id: vec3<u32>) -> bool {
+    return id.x < params.width && id.y < params.height;
+}
+
+@compute @workgroup_size(8, 8)
+fn jacobi(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (!in_grid(id)) {
+        return;
+    }
+    let x = i32(id.x);
+    let y = i32(id.y);
+    let sum = read_src(neighbor(x - 1, y)) + read_src(neighbor(x + 1, y))
+        + read_src(neighbor(x, y - 1)) + read_src(neighbor(x, y + 1));
+    let index = id.y * params.width + id.x;
+    dst[index] = (rhs[index] + params.r * sum) / (1.0 + 4.0 * params.r);
+}
+
+fn gauss_seidel(id: vec3<u32>, color: u32) {
+    if (!in_grid(id) || (id.x + id.y) % 2u != color) {
+        return;
+    }
+    let x = i32(id.x);
+    let y = i32(id.y);
+    let sum = read_dst(neighbor(x - 1, y)) + read_dst(neighbor(x + 1, y))
+        + read_dst(neighbor(x, y - 1)) + read_dst(neighbor(x, y + 1));
+    let index = id.y * params.width + id.x;
+    dst[index] = (rhs[index] + params.r * sum) / (1.0 + 4.0 * params.r);
+}
+
+@compute @workgroup_size(8, 8)
+fn red(@builtin(global_invocation_id) id: vec3<u32>) {
+    gauss_seidel(id, 0u);
+}
+
+@compute @workgroup_size(8, 8)
+fn black(@builtin(global_invocation_id) id: vec3<u32>) {
+    gauss_seidel(id, 1u);
+}
+"#;
+
+/// Fine Jacobi sweep over the refined patches, sharing the coarse uniforms
+fn heat_refinement_shader() -> String {
+    format!(
+        "{}{}{}",
+        r#"
+struct HeatUniforms {
+    width: u32,
+    height: u32,
+    r: f32,
+    boundary: u32,
+    edges: vec4<f32>,
+}
+
+@group(0) @binding(0) var<uniform> params: HeatUniforms;
+@group(0) @binding(5) var<storage, read> fine_rhs: array<f32>;
+@group(0) @binding(6) var<storage, read_write> fine_dst: array<f32>;
+"#,
+        AmrHierarchy2D::query_wgsl(0, 1),
+        r#"
+// Fine neighbor value, applying the boundary condition at the domain edge
+fn fine_neighbor(patch_index: u32, cell: vec2<i32>) -> f32 {
+    let size = vec2<i32>(amr_grid.coarse * amr_grid.ratio);
+    var c = cell;
+    if (params.boundary == 2u) {
+        c = (c + size) % size;
+    } else if (params.boundary == 1u) {
+        c = clamp(c, vec2<i32>(0), size - 1);
+    } else {
+        if (c.x < 0) { return params.edges.x; }
+        if (c.x >= size.x) { return params.edges.y; }
+        if (c.y < 0) { return params.edges.z; }
+        if (c.y >= size.y) { return params.edges.w; }
+    }
+    return amr_sample(patch_index, c);
+}
+
+@compute @workgroup_size(256)
+fn fine_jacobi(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x >= amr_grid.fine_cells) {
+        return;
+    }
+    let patch_index = amr_patch_at(id.x);
+    let cell = amr_global_cell(patch_index, id.x);
+    let sum = fine_neighbor(patch_index, cell + vec2<i32>(-1, 0)) + fine_neighbor(patch_index, cell + vec2<i32>(1, 0))
+        + fine_neighbor(patch_index, cell + vec2<i32>(0, -1)) + fine_neighbor(patch_index, cell + vec2<i32>(0, 1));
+    // Fine cells are `ratio` times smaller
+    let r = params.r * f32(amr_grid.ratio * amr_grid.ratio);
+    fine_dst[id.x] = (fine_rhs[id.x] + r * sum) / (1.0 + 4.0 * r);
+}
+"#
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::SimulationRun;
+    use crate::wgpu_utils::compute_primitives::test_device;
+
+    #[test]
+    fn test_initial_field_and_range() {
+        let heat = HeatDiffusion2D::new(10, 10)
+            .with_initial_temperature(0.25)
+            .with_hot_spot(0.5, 0.5, 0.15, 2.0);
+        let field = heat.initial_field();
+        assert_eq!(field[5 * 10 + 5], 2.0);
+        assert_eq!(field[0], 0.25);
+        // Fixed edges at zero extend the range below the initial field
+        assert_eq!(heat.temperature_range(), [0.0, 2.0]);
+
+        let insulated = heat.with_boundary(HeatBoundary::Insulated);
+        assert_eq!(insulated.temperature_range(), [0.25, 2.0]);
+        assert_eq!(insulated.uniforms().boundary, 1);
+        assert_eq!(std::mem::size_of::<HeatUniforms>(), 32);
+    }
+
+    #[test]
+    fn test_gpu_steps_are_deterministic() {
+        let record = || {
+            let (device, queue) = test_device()?;
+            let heat = HeatDiffusion2D::new(32, 32).with_hot_spot(0.3, 0.6, 0.1, 1.0);
+            let mut run = SimulationRun::new(heat).with_gpu(device, queue);
+            run.record(20, 5, |run| {
+                run.hash_buffer(run.simulation().temperature_buffer().unwrap())
+            })
+            .ok()
+        };
+        let Some(first) = record() else {
+            return;
+        };
+        assert_eq!(record(), Some(first.clone()));
+        // The field diffuses, so the state changes between samples
+        assert_ne!(first.entries()[0].1, first.entries()[4].1);
+    }
+}
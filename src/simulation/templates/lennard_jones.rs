@@ -0,0 +1,994 @@
+//! Lennard-Jones molecular dynamics
+//!
+//! Atoms interacting through the Lennard-Jones pair potential, the standard
+//! model of simple liquids and noble gases:
+//!
+//! ```text
+//! U(r) = 4 ε ((σ / r)^12 - (σ / r)^6)
+//! ```
+//!
+//! Everything runs in reduced units, with σ, ε and the atom mass set to one,
+//! so temperatures are in ε / k_B and times in σ √(m / ε). The potential is
+//! cut off at 2.5 σ and shifted to zero there.
+//!
+//! Atoms fill a cubic box with periodic boundaries at the chosen number
+//! density, starting from an FCC lattice. Each step is a velocity Verlet
+//! update: a half kick and drift, a rebuild of the GPU [`SpatialHash`] for
+//! the neighbor search, the new forces, and a second half kick. An optional
+//! Berendsen thermostat rescales velocities towards the target temperature;
+//! without it the run conserves energy.
+//!
+//! Every frame the kinetic and potential energies are summed on the GPU and
+//! the radial distribution function g(r) is histogrammed from the neighbor
+//! search, then read back for the temperature, energy and g(r) plots in the
+//! UI. The atoms are drawn straight from the GPU buffer as spheres colored by
+//! speed.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::simulation::templates::LennardJones;
+//!
+//! let mut app = haggis::default();
+//! app.attach_simulation(LennardJones::new(4000).with_density(0.8).with_temperature(1.2));
+//! app.run();
+//! ```
+
+use cgmath::Vector3;
+use imgui::Ui;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use wgpu::util::DeviceExt;
+use wgpu::{Device, Queue};
+
+use crate::gfx::rendering::{AgentInstance, AgentShape};
+use crate::gfx::scene::Scene;
+use crate::simulation::gpu::SpatialHash;
+use crate::simulation::params::{ParamsUniform, SimParams};
+use crate::simulation::traits::Simulation;
+use crate::simulation::BaseSimulation;
+use crate::visualization::{AgentView, DomainBox};
+use crate::wgpu_utils::compute_primitives::{read_buffer, Reduce, ReduceOp};
+
+const WORKGROUP_SIZE: u32 = 64;
+
+/// Bins of the radial distribution function, up to the cutoff
+const RDF_BINS: usize = 100;
+
+/// Frames kept in the temperature and energy plots
+const HISTORY: usize = 300;
+
+/// Weight of the newest frame in the averaged g(r)
+const RDF_SMOOTHING: f32 = 0.05;
+
+/// Tunable parameters of [`LennardJones`]
+#[derive(Debug, Clone, SimParams)]
+pub struct LennardJonesParams {
+    /// Target temperature of the thermostat, and of the initial velocities
+    #[param(range = 0.05..=5.0, step = 0.01)]
+    pub temperature: f32,
+    /// Rescale velocities towards the target temperature
+    pub thermostat: bool,
+    /// Relaxation time of the Berendsen thermostat
+    #[param(range = 0.01..=10.0, step = 0.01, label = "Thermostat Time")]
+    pub thermostat_time: f32,
+    #[param(range = 0.0005..=0.01, step = 0.0005, label = "Time Step")]
+    pub time_step: f32,
+    #[param(range = 1..=100, label = "Steps / Frame")]
+    pub steps_per_frame: u32,
+}
+
+impl Default for LennardJonesParams {
+    fn default() -> Self {
+        Self {
+            temperature: 1.0,
+            thermostat: true,
+            thermostat_time: 0.5,
+            time_step: 0.005,
+            steps_per_frame: 10,
+        }
+    }
+}
+
+/// Uniforms of the Lennard-Jones shader
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct LennardJonesUniforms {
+    count: u32,
+    /// Hash cells along each side of the box, even
+    cells: u32,
+    bins: u32,
+    _padding: u32,
+    /// Half box side in world units, σ in world units, time step, thermostat
+    /// velocity scale
+    domain: [f32; 4],
+    /// Cutoff, potential at the cutoff, g(r) bin width, speed shown hottest
+    potential: [f32; 4],
+}
+
+struct LennardJonesGpuResources {
+    drift_pipeline: wgpu::ComputePipeline,
+    forces_pipeline: wgpu::ComputePipeline,
+    kick_pipeline: wgpu::ComputePipeline,
+    histogram_pipeline: wgpu::ComputePipeline,
+    uniforms: ParamsUniform<LennardJonesUniforms>,
+    /// Hash over the atom positions, bound for the force and histogram passes
+    hash: SpatialHash,
+    /// Atoms as [`AgentInstance`]s with reduced velocities, drawn directly by
+    /// the agent view
+    atoms: wgpu::Buffer,
+    /// Kinetic energy of every atom (`f32` each)
+    kinetic: wgpu::Buffer,
+    /// Pairs counted per g(r) bin (`u32` each)
+    rdf_counts: wgpu::Buffer,
+    kinetic_sum: Reduce<f32>,
+    potential_sum: Reduce<f32>,
+    /// Kinetic and potential sums, then the g(r) counts, for one readback
+    stats: wgpu::Buffer,
+    /// Also keeps the force and potential buffers alive
+    bind_group: wgpu::BindGroup,
+}
+
+/// GPU molecular dynamics of Lennard-Jones atoms in a periodic box
+pub struct LennardJones {
+    base: BaseSimulation,
+    count: u32,
+    density: f32,
+    cutoff: f32,
+    sigma: f32,
+    pub params: LennardJonesParams,
+    running: bool,
+    steps: u64,
+    time: f64,
+    needs_reset: bool,
+    needs_step: bool,
+    /// Forces are stale after a reset and are computed before the next step
+    needs_forces: bool,
+    temperature: f32,
+    kinetic_energy: f32,
+    potential_energy: f32,
+    rdf: Vec<f32>,
+    temperature_history: Vec<f32>,
+    energy_history: Vec<f32>,
+    gpu: Option<LennardJonesGpuResources>,
+}
+
+impl LennardJones {
+    /// `count` atoms at density 0.8 and temperature 1, in the liquid phase,
+    /// drawn with σ a quarter of a world unit
+    pub fn new(count: u32) -> Self {
+        assert!(count > 1, "molecular dynamics needs at least two atoms");
+        Self {
+            base: BaseSimulation::new("Lennard-Jones"),
+            count,
+            density: 0.8,
+            cutoff: 2.5,
+            sigma: 0.25,
+            params: LennardJonesParams::default(),
+            running: true,
+            steps: 0,
+            time: 0.0,
+            needs_reset: true,
+            needs_step: false,
+            needs_forces: true,
+            temperature: 0.0,
+            kinetic_energy: 0.0,
+            potential_energy: 0.0,
+            rdf: vec![0.0; RDF_BINS],
+            temperature_history: Vec::new(),
+            energy_history: Vec::new(),
+            gpu: None,
+        }
+    }
+
+    /// Atoms per σ³, which sets the box size
+    pub fn with_density(mut self, density: f32) -> Self {
+        assert!(density > 0.0, "density must be positive");
+        self.density = density;
+        self
+    }
+
+    /// Distance in σ beyond which atoms don't interact, at most half the box
+    pub fn with_cutoff(mut self, cutoff: f32) -> Self {
+        assert!(cutoff >= 1.0, "cutoff must be at least one σ");
+        self.cutoff = cutoff;
+        self
+    }
+
+    /// World size of σ, the diameter the atoms are drawn with
+    pub fn with_sigma(mut self, sigma: f32) -> Self {
+        assert!(sigma > 0.0, "σ must be positive");
+        self.sigma = sigma;
+        self
+    }
+
+    /// Start at, and thermostat to, `temperature`
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.params.temperature = temperature;
+        self
+    }
+
+    pub fn with_params(mut self, params: LennardJonesParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// Side of the periodic box in σ
+    pub fn box_side(&self) -> f32 {
+        (self.count as f32 / self.density).cbrt()
+    }
+
+    /// Interaction range in σ, the cutoff limited to half the box
+    pub fn effective_cutoff(&self) -> f32 {
+        self.cutoff.min(self.box_side() * 0.5)
+    }
+
+    /// Number of steps taken since the last reset
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Reduced time since the last reset
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// Temperature from the kinetic energy, after the last frame
+    pub fn temperature(&self) -> f32 {
+        self.temperature
+    }
+
+    /// Kinetic energy per atom
+    pub fn kinetic_energy(&self) -> f32 {
+        self.kinetic_energy
+    }
+
+    /// Potential energy per atom
+    pub fn potential_energy(&self) -> f32 {
+        self.potential_energy
+    }
+
+    /// Total energy per atom, conserved without the thermostat
+    pub fn total_energy(&self) -> f32 {
+        self.kinetic_energy + self.potential_energy
+    }
+
+    /// Radial distribution function g(r), averaged over recent frames
+    pub fn rdf(&self) -> &[f32] {
+        &self.rdf
+    }
+
+    /// Width in σ of the [`rdf`](Self::rdf) bins, starting from zero
+    pub fn rdf_bin_width(&self) -> f32 {
+        self.effective_cutoff() / RDF_BINS as f32
+    }
+
+    /// GPU buffer holding the atoms as [`AgentInstance`]s
+    pub fn atom_buffer(&self) -> Option<&wgpu::Buffer> {
+        self.gpu.as_ref().map(|gpu| &gpu.atoms)
+    }
+
+    fn half_side(&self) -> f32 {
+        self.box_side() * self.sigma * 0.5
+    }
+
+    /// Hash cells along a side: as many as fit the cutoff, rounded down to an
+    /// even number so that cells line up with the centered box
+    fn hash_cells(&self) -> u32 {
+        ((self.box_side() / self.effective_cutoff()) as u32 / 2 * 2).max(2)
+    }
+
+    /// Starting state: an FCC lattice filled up to the atom count, with
+    /// random velocities at the target temperature and no net momentum
+    fn initial_atoms(&self) -> Vec<AgentInstance> {
+        let mut rng = StdRng::seed_from_u64(0x1e77);
+        let side = self.box_side();
+        let cells = (self.count as f32 / 4.0).cbrt().ceil() as u32;
+        let spacing = side / cells as f32;
+        let basis = [
+            [0.0, 0.0, 0.0],
+            [0.5, 0.5, 0.0],
+            [0.5, 0.0, 0.5],
+            [0.0, 0.5, 0.5],
+        ];
+
+        let mut positions = Vec::with_capacity(self.count as usize);
+        'fill: for z in 0..cells {
+            for y in 0..cells {
+                for x in 0..cells {
+                    for offset in basis {
+                        if positions.len() == self.count as usize {
+                            break 'fill;
+                        }
+                        let cell = [x, y, z];
+                        positions.push(std::array::from_fn::<f32, 3, _>(|axis| {
+                            (-0.5 * side + (cell[axis] as f32 + offset[axis] + 0.25) * spacing)
+                                * self.sigma
+                        }));
+                    }
+                }
+            }
+        }
+
+        let mut velocities: Vec<Vector3<f32>> = (0..self.count)
+            .map(|_| {
+                Vector3::new(
+                    rng.random_range(-1.0..1.0),
+                    rng.random_range(-1.0..1.0),
+                    rng.random_range(-1.0..1.0),
+                )
+            })
+            .collect();
+        let drift = velocities.iter().sum::<Vector3<f32>>() / self.count as f32;
+        let mut squared = 0.0;
+        for velocity in &mut velocities {
+            *velocity -= drift;
+            squared += cgmath::InnerSpace::magnitude2(*velocity);
+        }
+        let degrees_of_freedom = 3.0 * (self.count - 1) as f32;
+        let scale = (self.params.temperature * degrees_of_freedom / squared).sqrt();
+
+        positions
+            .into_iter()
+            .zip(velocities)
+            .map(|(position, velocity)| {
+                AgentInstance::new(
+                    position,
+                    (velocity * scale).into(),
+                    self.sigma * 0.5,
+                    [0.6, 0.6, 0.8, 1.0],
+                )
+            })
+            .collect()
+    }
+
+    /// Berendsen velocity scale per step for the last measured temperature
+    fn thermostat_scale(&self) -> f32 {
+        let p = &self.params;
+        if !p.thermostat || self.temperature <= 0.0 {
+            return 1.0;
+        }
+        let ratio = p.temperature / self.temperature - 1.0;
+        (1.0 + p.time_step / p.thermostat_time.max(p.time_step) * ratio)
+            .max(0.0)
+            .sqrt()
+            .clamp(0.9, 1.1)
+    }
+
+    fn uniforms(&self) -> LennardJonesUniforms {
+        let cutoff = self.effective_cutoff();
+        let inverse6 = cutoff.powi(-6);
+        LennardJonesUniforms {
+            count: self.count,
+            cells: self.hash_cells(),
+            bins: RDF_BINS as u32,
+            _padding: 0,
+            domain: [
+                self.half_side(),
+                self.sigma,
+                self.params.time_step,
+                self.thermostat_scale(),
+            ],
+            potential: [
+                cutoff,
+                4.0 * inverse6 * (inverse6 - 1.0),
+                self.rdf_bin_width(),
+                // Twice the typical speed at the target temperature
+                2.0 * (3.0 * self.params.temperature.max(0.01)).sqrt(),
+            ],
+        }
+    }
+
+    /// Take in the energy sums and g(r) counts read back after a frame
+    fn record_stats(&mut self, stats: &[u32]) {
+        let atoms = self.count as f32;
+        let kinetic = f32::from_bits(stats[0]);
+        let potential = f32::from_bits(stats[1]);
+        self.temperature = 2.0 * kinetic / (3.0 * (atoms - 1.0));
+        self.kinetic_energy = kinetic / atoms;
+        self.potential_energy = potential / atoms;
+
+        for history in [&mut self.temperature_history, &mut self.energy_history] {
+            if history.len() == HISTORY {
+                history.remove(0);
+            }
+        }
+        self.temperature_history.push(self.temperature);
+        self.energy_history.push(self.total_energy());
+
+        // Pairs per atom in each shell over those of an ideal gas
+        let density = atoms / self.box_side().powi(3);
+        let width = self.rdf_bin_width();
+        let fresh = self.needs_forces;
+        for (bin, (g, &count)) in self.rdf.iter_mut().zip(&stats[2..]).enumerate() {
+            let (inner, outer) = (bin as f32 * width, (bin + 1) as f32 * width);
+            let shell = 4.0 / 3.0 * std::f32::consts::PI * (outer.powi(3) - inner.powi(3));
+            let sample = count as f32 / (atoms * density * shell);
+            *g = if fresh {
+                sample
+            } else {
+                *g + RDF_SMOOTHING * (sample - *g)
+            };
+        }
+    }
+
+    fn create_gpu_resources(&self, device: &Device) -> LennardJonesGpuResources {
+        let source = format!("{}{}", SpatialHash::query_wgsl(0, 6), LENNARD_JONES_SHADER);
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Lennard-Jones Shader"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+
+        let buffer_entry = |binding: u32, ty: wgpu::BufferBindingType| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let storage = wgpu::BufferBindingType::Storage { read_only: false };
+        let mut layout_entries = vec![buffer_entry(0, wgpu::BufferBindingType::Uniform)];
+        layout_entries.extend((1..6).map(|binding| buffer_entry(binding, storage)));
+        layout_entries.extend(SpatialHash::bind_group_layout_entries(
+            6,
+            wgpu::ShaderStages::COMPUTE,
+        ));
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Lennard-Jones Bind Group Layout"),
+            entries: &layout_entries,
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Lennard-Jones Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |entry_point: &str| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+
+        let atoms = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Lennard-Jones Atoms"),
+            contents: bytemuck::cast_slice(&self.initial_atoms()),
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::VERTEX
+                | wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::COPY_SRC,
+        });
+        let buffer = |label: &str, size: u64| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size,
+                usage: wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_DST
+                    | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            })
+        };
+        let count = self.count as u64;
+        let forces = buffer("Lennard-Jones Forces", count * 16);
+        let kinetic = buffer("Lennard-Jones Kinetic Energy", count * 4);
+        let potential = buffer("Lennard-Jones Potential Energy", count * 4);
+        let rdf_counts = buffer("Lennard-Jones RDF Counts", RDF_BINS as u64 * 4);
+        let stats = buffer("Lennard-Jones Stats", (2 + RDF_BINS as u64) * 4);
+        let uniforms = ParamsUniform::new(device, "Lennard-Jones Uniforms", &self.uniforms());
+        let hash = SpatialHash::new(
+            device,
+            &atoms,
+            std::mem::size_of::<AgentInstance>() as u64,
+            self.count,
+            2.0 * self.half_side() / self.hash_cells() as f32,
+        );
+        let kinetic_sum = Reduce::new(device, ReduceOp::Sum, &kinetic, self.count);
+        let potential_sum = Reduce::new(device, ReduceOp::Sum, &potential, self.count);
+
+        let buffers = [
+            uniforms.buffer(),
+            &atoms,
+            &forces,
+            &kinetic,
+            &potential,
+            &rdf_counts,
+        ];
+        let mut entries: Vec<wgpu::BindGroupEntry> = buffers
+            .iter()
+            .enumerate()
+            .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                binding: binding as u32,
+                resource: buffer.as_entire_binding(),
+            })
+            .collect();
+        entries.extend(hash.bind_group_entries(6));
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Lennard-Jones Bind Group"),
+            layout: &bind_group_layout,
+            entries: &entries,
+        });
+
+        LennardJonesGpuResources {
+            drift_pipeline: pipeline("drift"),
+            forces_pipeline: pipeline("compute_forces"),
+            kick_pipeline: pipeline("kick"),
+            histogram_pipeline: pipeline("histogram"),
+            uniforms,
+            hash,
+            atoms,
+            kinetic,
+            rdf_counts,
+            kinetic_sum,
+            potential_sum,
+            stats,
+            bind_group,
+        }
+    }
+}
+
+impl Simulation for LennardJones {
+    fn initialize(&mut self, scene: &mut Scene) {
+        self.base.initialize(scene);
+    }
+
+    fn initialize_gpu(&mut self, device: &Device, queue: &Queue) {
+        let gpu = self.create_gpu_resources(device);
+        // The kinetic energies are written on the first frame, with the atoms
+        self.needs_reset = true;
+
+        let mut view = AgentView::new().with_shape(AgentShape::Sphere);
+        view.set_gpu_buffer(gpu.atoms.clone(), self.count);
+        let half = self.half_side();
+
+        self.gpu = Some(gpu);
+        self.base.remove_visualization("atoms");
+        self.base.remove_visualization("bounds");
+        self.base.add_visualization("atoms", view);
+        self.base.add_visualization(
+            "bounds",
+            DomainBox::new([-half, -half, -half], [half, half, half]),
+        );
+        self.base.initialize_gpu(device, queue);
+    }
+
+    fn update(&mut self, delta_time: f32, scene: &mut Scene) {
+        self.base.update(delta_time, scene);
+    }
+
+    fn update_gpu(&mut self, device: &Device, queue: &Queue, delta_time: f32) {
+        let uniforms = self.uniforms();
+        let reset_atoms = self.needs_reset.then(|| self.initial_atoms());
+        let steps = if self.running {
+            self.params.steps_per_frame.max(1)
+        } else {
+            u32::from(self.needs_step)
+        };
+
+        let mut stats = None;
+        if let Some(gpu) = self.gpu.as_mut() {
+            gpu.uniforms.update(queue, &uniforms);
+
+            if let Some(atoms) = reset_atoms {
+                let kinetic: Vec<f32> = atoms
+                    .iter()
+                    .map(|atom| 0.5 * atom.velocity[..3].iter().map(|v| v * v).sum::<f32>())
+                    .collect();
+                queue.write_buffer(&gpu.atoms, 0, bytemuck::cast_slice(&atoms));
+                queue.write_buffer(&gpu.kinetic, 0, bytemuck::cast_slice(&kinetic));
+                self.needs_reset = false;
+                self.needs_forces = true;
+                self.steps = 0;
+                self.time = 0.0;
+                self.temperature_history.clear();
+                self.energy_history.clear();
+            }
+
+            if steps > 0 || self.needs_forces {
+                let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Lennard-Jones Encoder"),
+                });
+                let groups = self.count.div_ceil(WORKGROUP_SIZE);
+                let dispatch =
+                    |encoder: &mut wgpu::CommandEncoder, pipelines: &[&wgpu::ComputePipeline]| {
+                        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                            label: Some("Lennard-Jones Step"),
+                            timestamp_writes: None,
+                        });
+                        pass.set_bind_group(0, &gpu.bind_group, &[]);
+                        for pipeline in pipelines {
+                            pass.set_pipeline(pipeline);
+                            pass.dispatch_workgroups(groups, 1, 1);
+                        }
+                    };
+
+                crate::logging::crash::record_pass("Lennard-Jones Step");
+                if self.needs_forces {
+                    gpu.hash.build(queue, &mut encoder);
+                    dispatch(&mut encoder, &[&gpu.forces_pipeline]);
+                }
+                for _ in 0..steps {
+                    dispatch(&mut encoder, &[&gpu.drift_pipeline]);
+                    gpu.hash.build(queue, &mut encoder);
+                    dispatch(&mut encoder, &[&gpu.forces_pipeline, &gpu.kick_pipeline]);
+                }
+
+                // Statistics of the final state, hashed by the last step
+                encoder.clear_buffer(&gpu.rdf_counts, 0, None);
+                dispatch(&mut encoder, &[&gpu.histogram_pipeline]);
+                gpu.kinetic_sum.encode(queue, &mut encoder, self.count);
+                gpu.potential_sum.encode(queue, &mut encoder, self.count);
+                encoder.copy_buffer_to_buffer(gpu.kinetic_sum.result(), 0, &gpu.stats, 0, 4);
+                encoder.copy_buffer_to_buffer(gpu.potential_sum.result(), 0, &gpu.stats, 4, 4);
+                encoder.copy_buffer_to_buffer(
+                    &gpu.rdf_counts,
+                    0,
+                    &gpu.stats,
+                    8,
+                    RDF_BINS as u64 * 4,
+                );
+                queue.submit(std::iter::once(encoder.finish()));
+
+                stats = read_buffer::<u32>(device, queue, &gpu.stats, 2 + RDF_BINS).ok();
+                self.steps += steps as u64;
+                self.time += steps as f64 * self.params.time_step as f64;
+            }
+            self.needs_step = false;
+        }
+        if let Some(stats) = stats {
+            self.record_stats(&stats);
+            self.needs_forces = false;
+        }
+
+        self.base.update_gpu(device, queue, delta_time);
+    }
+
+    fn apply_gpu_results_to_scene(&mut self, device: &Device, scene: &mut Scene) {
+        self.base.apply_gpu_results_to_scene(device, scene);
+    }
+
+    fn render_ui(&mut self, ui: &Ui) {
+        ui.window("Lennard-Jones")
+            .size([380.0, 560.0], imgui::Condition::FirstUseEver)
+            .build(|| {
+                ui.text(format!(
+                    "Atoms: {}, density: {:.3}, box: {:.2} σ",
+                    self.count,
+                    self.density,
+                    self.box_side()
+                ));
+                ui.text(format!("Steps: {} (t = {:.2})", self.steps, self.time));
+                ui.separator();
+
+                if ui.button(if self.running { "Pause" } else { "Play" }) {
+                    self.running = !self.running;
+                }
+                ui.same_line();
+                if ui.button("Step") {
+                    self.needs_step = true;
+                }
+                ui.same_line();
+                if ui.button("Reset") {
+                    self.needs_reset = true;
+                }
+
+                ui.separator();
+                ui.text(format!(
+                    "Temperature: {:.3} (target {:.3})",
+                    self.temperature, self.params.temperature
+                ));
+                let width = ui.content_region_avail()[0];
+                if !self.temperature_history.is_empty() {
+                    ui.plot_lines("##temperature", &self.temperature_history)
+                        .graph_size([width, 60.0])
+                        .scale_min(0.0)
+                        .build();
+                }
+                ui.text(format!(
+                    "Energy / atom: {:.4} (kinetic {:.4}, potential {:.4})",
+                    self.total_energy(),
+                    self.kinetic_energy,
+                    self.potential_energy
+                ));
+                if !self.energy_history.is_empty() {
+                    ui.plot_lines("##energy", &self.energy_history)
+                        .graph_size([width, 60.0])
+                        .build();
+                }
+                ui.text(format!(
+                    "g(r), r from 0 to {:.2} σ",
+                    self.effective_cutoff()
+                ));
+                ui.plot_lines("##rdf", &self.rdf)
+                    .graph_size([width, 100.0])
+                    .scale_min(0.0)
+                    .build();
+
+                ui.separator();
+                self.params.build_ui(ui);
+            });
+
+        self.base.render_ui(ui);
+    }
+
+    fn name(&self) -> &str {
+        "Lennard-Jones"
+    }
+
+    fn is_running(&self) -> bool {
+        self.running
+    }
+
+    fn set_running(&mut self, running: bool) {
+        self.running = running;
+    }
+
+    fn reset(&mut self, scene: &mut Scene) {
+        self.needs_reset = true;
+        self.base.reset(scene);
+    }
+
+    fn is_gpu_ready(&self) -> bool {
+        self.gpu.is_some()
+    }
+
+    fn params_mut(&mut self) -> Option<&mut dyn SimParams> {
+        Some(&mut self.params)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        &self.base
+    }
+}
+
+const LENNARD_JONES_SHADER: &str = r#"
+struct Atom {
+    position: vec4<f32>, // drawn radius in w
+    velocity: vec4<f32>, // reduced units
+    color: vec4<f32>,
+}
+
+struct LennardJonesUniforms {
+    count: u32,
+    cells: u32,
+    bins: u32,
+    _padding: u32,
+    domain: vec4<f32>,    // half box side, sigma, time step, thermostat scale
+    potential: vec4<f32>, // cutoff, potential at the cutoff, bin width, hottest speed
+}
+
+@group(0) @binding(0) var<uniform> params: LennardJonesUniforms;
+@group(0) @binding(1) var<storage, read_write> atoms: array<Atom>;
+@group(0) @binding(2) var<storage, read_write> forces: array<vec4<f32>>;
+@group(0) @binding(3) var<storage, read_write> kinetic_energy: array<f32>;
+@group(0) @binding(4) var<storage, read_write> potential_energy: array<f32>;
+@group(0) @binding(5) var<storage, read_write> rdf_counts: array<atomic<u32>>;
+
+// Keys of the 27 cells around `position`, wrapped around the periodic box;
+// repeated keys are SPATIAL_HASH_NONE
+fn periodic_neighbor_keys(position: vec3<f32>) -> array<u32, 27> {
+    let n = i32(params.cells);
+    let half = n / 2;
+    let center = spatial_hash_cell(position);
+    var keys: array<u32, 27>;
+    for (var c = 0u; c < 27u; c++) {
+        let offset = vec3<i32>(i32(c % 3u) - 1, i32(c / 3u % 3u) - 1, i32(c / 9u) - 1);
+        let cell = (center + offset + vec3<i32>(half + n)) % vec3<i32>(n) - vec3<i32>(half);
+        let key = spatial_hash_key(cell);
+        keys[c] = key;
+        for (var m = 0u; m < c; m++) {
+            if (keys[m] == key) {
+                keys[c] = SPATIAL_HASH_NONE;
+                break;
+            }
+        }
+    }
+    return keys;
+}
+
+// Offset between two atoms in sigma, to the nearest periodic image
+fn minimum_image(offset: vec3<f32>) -> vec3<f32> {
+    let side = 2.0 * params.domain.x;
+    return (offset - side * round(offset / side)) / params.domain.y;
+}
+
+// First half kick and drift, wrapping atoms back into the box
+@compute @workgroup_size(64)
+fn drift(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if (i >= params.count) {
+        return;
+    }
+    let dt = params.domain.z;
+    let velocity = atoms[i].velocity.xyz + 0.5 * dt * forces[i].xyz;
+    let half = params.domain.x;
+    let side = 2.0 * half;
+    var p = atoms[i].position.xyz + params.domain.y * dt * velocity;
+    p -= side * floor((p + half) / side);
+    // Rounding may land exactly on the upper face, which belongs to no cell
+    p = clamp(p, vec3<f32>(-half), vec3<f32>(half - side * 1e-6));
+    atoms[i].position = vec4<f32>(p, atoms[i].position.w);
+    atoms[i].velocity = vec4<f32>(velocity, 0.0);
+}
+
+@compute @workgroup_size(64)
+fn compute_forces(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if (i >= params.count) {
+        return;
+    }
+    let p = atoms[i].position.xyz;
+    let cutoff2 = params.potential.x * params.potential.x;
+    var force = vec3<f32>(0.0);
+    var energy = 0.0;
+
+    let keys = periodic_neighbor_keys(p);
+    for (var c = 0u; c < 27u; c++) {
+        let range = spatial_hash_range(keys[c]);
+        for (var k = range.x; k < range.y; k++) {
+            let j = spatial_hash_sorted[k];
+            if (j == i) {
+                continue;
+            }
+            let offset = minimum_image(p - atoms[j].position.xyz);
+            // Keeps overlapping atoms from blowing up a bad starting state
+            let r2 = max(dot(offset, offset), 0.5);
+            if (r2 >= cutoff2) {
+                continue;
+            }
+            let inverse2 = 1.0 / r2;
+            let inverse6 = inverse2 * inverse2 * inverse2;
+            force += 24.0 * inverse2 * inverse6 * (2.0 * inverse6 - 1.0) * offset;
+            // Each pair is visited from both atoms
+            energy += 0.5 * (4.0 * inverse6 * (inverse6 - 1.0) - params.potential.y);
+        }
+    }
+    forces[i] = vec4<f32>(force, 0.0);
+    potential_energy[i] = energy;
+}
+
+// Second half kick with the new forces, then the thermostat
+@compute @workgroup_size(64)
+fn kick(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if (i >= params.count) {
+        return;
+    }
+    let velocity = (atoms[i].velocity.xyz + 0.5 * params.domain.z * forces[i].xyz) * params.domain.w;
+    atoms[i].velocity = vec4<f32>(velocity, 0.0);
+    kinetic_energy[i] = 0.5 * dot(velocity, velocity);
+
+    let heat = clamp(length(velocity) / params.potential.w, 0.0, 1.0);
+    let cold = vec3<f32>(0.2, 0.4, 1.0);
+    let hot = vec3<f32>(1.0, 0.35, 0.2);
+    atoms[i].color = vec4<f32>(mix(cold, hot, heat), 1.0);
+}
+
+// Pair distances binned for g(r)
+@compute @workgroup_size(64)
+fn histogram(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if (i >= params.count) {
+        return;
+    }
+    let p = atoms[i].position.xyz;
+    let keys = periodic_neighbor_keys(p);
+    for (var c = 0u; c < 27u; c++) {
+        let range = spatial_hash_range(keys[c]);
+        for (var k = range.x; k < range.y; k++) {
+            let j = spatial_hash_sorted[k];
+            if (j == i) {
+                continue;
+            }
+            let bin = u32(length(minimum_image(p - atoms[j].position.xyz)) / params.potential.z);
+            if (bin < params.bins) {
+                atomicAdd(&rdf_counts[bin], 1u);
+            }
+        }
+    }
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_initial_lattice_at_temperature() {
+        assert_eq!(std::mem::size_of::<LennardJonesUniforms>(), 48);
+
+        let md = LennardJones::new(500).with_temperature(1.5);
+        let atoms = md.initial_atoms();
+        assert_eq!(atoms.len(), 500);
+        let half = md.half_side();
+        let mut momentum = Vector3::new(0.0, 0.0, 0.0);
+        let mut squared = 0.0;
+        for (i, atom) in atoms.iter().enumerate() {
+            assert!(atom.position[..3]
+                .iter()
+                .all(|&p| (-half..half).contains(&p)));
+            let v = Vector3::new(atom.velocity[0], atom.velocity[1], atom.velocity[2]);
+            momentum += v;
+            squared += cgmath::InnerSpace::magnitude2(v);
+            // No two atoms closer than the FCC nearest neighbor distance
+            for other in &atoms[i + 1..] {
+                let d: f32 = (0..3)
+                    .map(|axis| (atom.position[axis] - other.position[axis]).powi(2))
+                    .sum();
+                assert!(d.sqrt() / md.sigma > 1.0);
+            }
+        }
+        assert!(cgmath::InnerSpace::magnitude(momentum) < 1e-3);
+        assert!((squared / (3.0 * 499.0) - 1.5).abs() < 1e-3);
+        // Cells of an even count at least a cutoff wide
+        let cells = md.hash_cells();
+        assert_eq!(cells % 2, 0);
+        assert!(md.box_side() / cells as f32 >= md.effective_cutoff());
+    }
+
+    #[test]
+    fn test_energy_conservation_thermostat_and_rdf() {
+        use crate::wgpu_utils::compute_primitives::test_device;
+
+        // Needs a GPU adapter, skipped without one
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let mut md = LennardJones::new(864).with_params(LennardJonesParams {
+            thermostat: false,
+            ..LennardJonesParams::default()
+        });
+        md.initialize_gpu(&device, &queue);
+
+        // Without the thermostat the lattice melts at constant energy
+        md.update_gpu(&device, &queue, 1.0 / 60.0);
+        let start = md.total_energy();
+        let lattice_potential = md.potential_energy();
+        for _ in 0..30 {
+            md.update_gpu(&device, &queue, 1.0 / 60.0);
+            assert!(
+                (md.total_energy() - start).abs() < 0.02,
+                "energy drifted from {start} to {}",
+                md.total_energy()
+            );
+        }
+        assert!(md.potential_energy() > lattice_potential);
+        assert!(start < 0.0);
+
+        // The thermostat pulls the temperature to its target
+        md.params.thermostat = true;
+        md.params.temperature = 2.0;
+        for _ in 0..60 {
+            md.update_gpu(&device, &queue, 1.0 / 60.0);
+        }
+        assert!(
+            (md.temperature() - 2.0).abs() < 0.2,
+            "temperature {}",
+            md.temperature()
+        );
+
+        // A liquid's g(r): nothing inside σ, a first shell peak, then about one
+        let rdf = md.rdf();
+        let width = md.rdf_bin_width();
+        let (peak, height) =
+            rdf.iter().enumerate().fold(
+                (0, 0.0),
+                |best, (bin, &g)| if g > best.1 { (bin, g) } else { best },
+            );
+        let r_peak = (peak as f32 + 0.5) * width;
+        assert!((1.0..1.3).contains(&r_peak), "peak at {r_peak}");
+        assert!(height > 1.5);
+        assert!(rdf[(0.8 / width) as usize] < 0.05);
+        let tail = rdf[RDF_BINS - 10..].iter().sum::<f32>() / 10.0;
+        assert!((0.8..1.2).contains(&tail), "g(r) tail {tail}");
+    }
+}
@@ -0,0 +1,49 @@
+//! # Simulation Templates
+//!
+//! Complete simulations of common physical models, ready to attach to an app.
+//! Each template runs on the GPU, brings its own visualization and UI, and is
+//! meant both as a worked example and as a starting point for custom solvers.
+//!
+//! - [`HeatDiffusion2D`] - implicit 2D heat equation with Jacobi or red-black
+//!   Gauss-Seidel iterations and optional locally refined patches
+//! - [`GrayScott`] - reaction-diffusion with spot, stripe, worm and coral presets
+//! - [`Cloth`] - position-based cloth driving a scene mesh, with pins, wind and
+//!   sphere colliders
+//! - [`Boids`] - flocking agents drawn straight from the GPU buffer
+//! - [`SoftBody`] - tetrahedral soft body that can be dragged with the mouse,
+//!   on the CPU or GPU position-based dynamics solver
+//! - [`Granular`] - discrete element sand with pile and hopper presets, drawn
+//!   as instanced spheres
+//! - [`Smoke`] - stable fluids smoke with buoyancy and a pressure projection,
+//!   drawn as a ray marched volume
+//! - [`ShallowWater`] - shallow water waves and dam breaks on a reflective
+//!   height field mesh
+//! - [`Fdtd2D`] - 2D TE/TM electromagnetics with absorbing boundaries, point
+//!   and plane wave sources, and dielectric or conducting objects
+//! - [`LennardJones`] - molecular dynamics of Lennard-Jones atoms in a periodic
+//!   box, with a thermostat and live temperature, energy and g(r) plots
+
+pub mod boids;
+pub mod cloth;
+pub mod fdtd;
+pub mod granular;
+pub mod gray_scott;
+pub mod heat_diffusion;
+pub mod lennard_jones;
+pub mod shallow_water;
+pub mod smoke;
+pub mod soft_body;
+
+pub use boids::{Boids, BoidsParams};
+pub use cloth::{Cloth, ClothParams, ClothPlane, SphereCollider};
+pub use fdtd::{
+    Fdtd2D, FdtdMaterial, FdtdMode, FdtdParams, FdtdQuantity, FdtdRegion, FdtdSource,
+    FdtdSourceShape, FdtdWaveform,
+};
+pub use granular::{Granular, GranularParams, GranularPreset};
+pub use gray_scott::{GrayScott, GrayScottParams, GrayScottPreset};
+pub use heat_diffusion::{HeatBoundary, HeatDiffusion2D, HeatParams, HeatSolver};
+pub use lennard_jones::{LennardJones, LennardJonesParams};
+pub use shallow_water::{ShallowWater, ShallowWaterParams, ShallowWaterPreset};
+pub use smoke::{Smoke, SmokeParams};
+pub use soft_body::{SoftBody, SoftBodyParams, SoftBodySolver};
@@ -0,0 +1,847 @@
+//! Shallow water
+//!
+//! The 2D shallow water equations on a regular grid: every cell holds the
+//! water depth `h` and the momentum `(hu, hv)`, and waves travel at
+//! `sqrt(g h)`. The GPU solver is a finite volume scheme with Rusanov fluxes,
+//! which conserves water exactly, keeps depths from going negative and copes
+//! with hydraulic jumps such as a collapsing dam. The walls of the basin
+//! reflect waves.
+//!
+//! The water surface is a regular scene object built with
+//! [`HeightField`]. Depths are read back every frame and written into its
+//! mesh, and the default material is smooth and reflective, so the PBR
+//! pipeline lights it with highlights and a mirrored sky like any other
+//! object.
+//!
+//! Raindrops disturb the surface at random, and a drop can be added from the
+//! UI or with [`ShallowWater::add_drop`]. Drops add no water: each raises
+//! the surface in its center and lowers a ring around it by the same volume.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::simulation::templates::ShallowWater;
+//!
+//! let mut app = haggis::default();
+//! app.attach_simulation(ShallowWater::dam_break([160, 96]).with_size([5.0, 3.0]));
+//! app.run();
+//! ```
+
+use imgui::Ui;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use wgpu::{Device, Queue};
+
+use crate::gfx::geometry::heightfield::HeightField;
+use crate::gfx::scene::Scene;
+use crate::simulation::params::{ParamsUniform, SimParams};
+use crate::simulation::traits::Simulation;
+
+const WORKGROUP_SIZE: u32 = 8;
+
+/// Drops passed to the shader per frame; more wait for the next frame
+const MAX_DROPS: usize = 4;
+
+/// Substeps per frame at most; faster waves slow the simulation down instead
+const MAX_SUBSTEPS: u32 = 63;
+
+/// Share of the time a wave needs to cross a cell taken per substep
+const COURANT: f32 = 0.25;
+
+/// Initial state of the water
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShallowWaterPreset {
+    /// A mound of water in the middle of a still basin
+    #[default]
+    Drop,
+    /// A deep column of water along the -X wall released into the basin
+    DamBreak,
+}
+
+impl ShallowWaterPreset {
+    pub const ALL: [ShallowWaterPreset; 2] =
+        [ShallowWaterPreset::Drop, ShallowWaterPreset::DamBreak];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ShallowWaterPreset::Drop => "Drop",
+            ShallowWaterPreset::DamBreak => "Dam Break",
+        }
+    }
+}
+
+/// Tunable parameters of [`ShallowWater`]
+#[derive(Debug, Clone, SimParams)]
+pub struct ShallowWaterParams {
+    #[param(range = 0.1..=20.0, step = 0.1)]
+    pub gravity: f32,
+    /// Share of the momentum lost per second
+    #[param(range = 0.0..=2.0, step = 0.01)]
+    pub damping: f32,
+    /// Raindrops per second
+    #[param(range = 0.0..=20.0, step = 0.1)]
+    pub rain: f32,
+    /// Height of the crest of a drop
+    #[param(range = 0.0..=0.2, step = 0.005, label = "Drop Height")]
+    pub drop_height: f32,
+    /// Radius of a drop in cells
+    #[param(range = 1.0..=10.0, step = 0.1, label = "Drop Radius")]
+    pub drop_radius: f32,
+}
+
+impl Default for ShallowWaterParams {
+    fn default() -> Self {
+        Self {
+            gravity: 9.81,
+            damping: 0.05,
+            rain: 0.0,
+            drop_height: 0.04,
+            drop_radius: 3.0,
+        }
+    }
+}
+
+/// Uniforms of the shallow water shader
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct ShallowWaterUniforms {
+    /// Grid size, then drop count
+    dims: [u32; 4],
+    /// Time step, gravity, share of the momentum kept
+    params: [f32; 4],
+    /// Cell size along X and Y
+    spacing: [f32; 4],
+    /// Center in cells, radius in cells and crest height
+    drops: [[f32; 4]; MAX_DROPS],
+}
+
+struct ShallowWaterGpuResources {
+    drop_pipeline: wgpu::ComputePipeline,
+    step_pipeline: wgpu::ComputePipeline,
+    uniforms: ParamsUniform<ShallowWaterUniforms>,
+    /// Depth and momentum of every cell
+    state: wgpu::Buffer,
+    staging: wgpu::Buffer,
+    a_to_b: wgpu::BindGroup,
+    b_to_a: wgpu::BindGroup,
+}
+
+/// GPU shallow water basin drawn as a reflective height field
+pub struct ShallowWater {
+    field: HeightField,
+    /// Water depth at rest
+    depth: f32,
+    preset: ShallowWaterPreset,
+    pub params: ShallowWaterParams,
+    object_name: String,
+    material: Option<String>,
+    color: [f32; 4],
+    /// Drops waiting for the next step, in cells
+    drops: Vec<[f32; 4]>,
+    rng: StdRng,
+    /// Time until the next raindrop
+    rain_timer: f32,
+    /// Fastest wave at the last readback, setting the substep
+    wave_speed: f32,
+    running: bool,
+    steps: u64,
+    time: f32,
+    needs_upload: bool,
+    /// Latest state read back from the GPU, waiting to go into the mesh
+    readback: Option<Vec<[f32; 4]>>,
+    gpu: Option<ShallowWaterGpuResources>,
+}
+
+impl ShallowWater {
+    /// A basin of `dims` cells, four units along its longer side, with water
+    /// a quarter unit deep starting from `preset`
+    pub fn new(dims: [u32; 2], preset: ShallowWaterPreset) -> Self {
+        let longest = dims[0].max(dims[1]).max(2) as f32;
+        let size = dims.map(|n| 4.0 * n as f32 / longest);
+        let mut water = Self {
+            field: HeightField::new(dims, size),
+            depth: 0.25,
+            preset,
+            params: ShallowWaterParams::default(),
+            object_name: "water".to_string(),
+            material: None,
+            color: [0.05, 0.22, 0.3, 0.85],
+            drops: Vec::new(),
+            rng: StdRng::seed_from_u64(0x5a7e),
+            rain_timer: 0.0,
+            wave_speed: 0.0,
+            running: true,
+            steps: 0,
+            time: 0.0,
+            needs_upload: true,
+            readback: None,
+            gpu: None,
+        };
+        water.wave_speed = water.fastest_wave(&water.initial_state());
+        water
+    }
+
+    /// A mound of water spreading out in a still basin
+    pub fn drop(dims: [u32; 2]) -> Self {
+        Self::new(dims, ShallowWaterPreset::Drop)
+    }
+
+    /// A column of water collapsing into a shallow basin
+    pub fn dam_break(dims: [u32; 2]) -> Self {
+        Self::new(dims, ShallowWaterPreset::DamBreak)
+    }
+
+    /// Size of the basin along X and Y
+    pub fn with_size(mut self, size: [f32; 2]) -> Self {
+        self.field = HeightField::new(self.field.dims(), size).at(self.field.origin());
+        self
+    }
+
+    /// Center of the basin floor
+    pub fn at(mut self, origin: [f32; 3]) -> Self {
+        self.field = self.field.at(origin);
+        self
+    }
+
+    /// Water depth at rest
+    pub fn with_depth(mut self, depth: f32) -> Self {
+        self.depth = depth.max(1e-3);
+        self.wave_speed = self.fastest_wave(&self.initial_state());
+        self
+    }
+
+    /// Name of the scene object holding the water surface
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.object_name = name.to_string();
+        self
+    }
+
+    /// Use an existing material instead of the reflective water one
+    pub fn with_material(mut self, material_id: &str) -> Self {
+        self.material = Some(material_id.to_string());
+        self
+    }
+
+    /// Color of the default water material; alpha below one lets the floor
+    /// show through
+    pub fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_params(mut self, params: ShallowWaterParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    pub fn preset(&self) -> ShallowWaterPreset {
+        self.preset
+    }
+
+    /// Start over from `preset`
+    pub fn set_preset(&mut self, preset: ShallowWaterPreset) {
+        self.preset = preset;
+        self.needs_upload = true;
+    }
+
+    /// Disturb the surface at `position` on the XY plane with a drop of
+    /// `radius` and crest `height`, both in world units
+    pub fn add_drop(&mut self, position: [f32; 2], radius: f32, height: f32) {
+        let [dx, dy] = self.field.spacing();
+        let corner = self.field.position(0, 0, 0.0);
+        self.drops.push([
+            (position[0] - corner[0]) / dx,
+            (position[1] - corner[1]) / dy,
+            radius / dx.min(dy),
+            height,
+        ]);
+    }
+
+    pub fn height_field(&self) -> &HeightField {
+        &self.field
+    }
+
+    /// Number of substeps taken since the last reset
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// GPU buffer holding depth and momentum `(h, hu, hv, 0)` of every cell
+    pub fn state_buffer(&self) -> Option<&wgpu::Buffer> {
+        self.gpu.as_ref().map(|gpu| &gpu.state)
+    }
+
+    /// Depth and momentum of every cell for the current preset
+    fn initial_state(&self) -> Vec<[f32; 4]> {
+        let [columns, rows] = self.field.dims();
+        let center = [(columns - 1) as f32 * 0.5, (rows - 1) as f32 * 0.5];
+        let radius = columns.min(rows) as f32 / 8.0;
+        let mut state = Vec::with_capacity(self.field.len());
+        for row in 0..rows {
+            for column in 0..columns {
+                let depth = match self.preset {
+                    ShallowWaterPreset::Drop => {
+                        let x = column as f32 - center[0];
+                        let y = row as f32 - center[1];
+                        let falloff = (-(x * x + y * y) / (radius * radius)).exp();
+                        self.depth * (1.0 + falloff)
+                    }
+                    ShallowWaterPreset::DamBreak if column < columns / 4 => self.depth * 3.0,
+                    ShallowWaterPreset::DamBreak => self.depth,
+                };
+                state.push([depth, 0.0, 0.0, 0.0]);
+            }
+        }
+        state
+    }
+
+    /// Fastest wave in `state`, at least that of the still water
+    fn fastest_wave(&self, state: &[[f32; 4]]) -> f32 {
+        let gravity = self.params.gravity.max(0.0);
+        state
+            .iter()
+            .map(|&[h, hu, hv, _]| {
+                let flow = if h > 1e-4 {
+                    hu.abs().max(hv.abs()) / h
+                } else {
+                    0.0
+                };
+                flow + (gravity * h.max(0.0)).sqrt()
+            })
+            .fold((gravity * self.depth).sqrt(), f32::max)
+    }
+
+    /// Substep count, odd so the state ends up back in its own buffer, and
+    /// the time each substep covers
+    fn substeps(&self, delta_time: f32) -> (u32, f32) {
+        let [dx, dy] = self.field.spacing();
+        let stable = COURANT * dx.min(dy) / self.wave_speed.max(1e-3);
+        let count = (delta_time / stable).ceil().clamp(1.0, MAX_SUBSTEPS as f32) as u32;
+        let count = count | 1;
+        (count, delta_time.min(stable * count as f32) / count as f32)
+    }
+
+    fn uniforms(&self, time_step: f32, drops: &[[f32; 4]]) -> ShallowWaterUniforms {
+        let [columns, rows] = self.field.dims();
+        let [dx, dy] = self.field.spacing();
+        let mut uniforms = ShallowWaterUniforms {
+            dims: [columns, rows, drops.len() as u32, 0],
+            params: [
+                time_step,
+                self.params.gravity,
+                (1.0 - self.params.damping * time_step).clamp(0.0, 1.0),
+                0.0,
+            ],
+            spacing: [dx, dy, 0.0, 0.0],
+            drops: [[0.0; 4]; MAX_DROPS],
+        };
+        uniforms.drops[..drops.len()].copy_from_slice(drops);
+        uniforms
+    }
+
+    /// Queue raindrops due in the next `delta_time`
+    fn rain(&mut self, delta_time: f32) {
+        if self.params.rain <= 0.0 {
+            return;
+        }
+        self.rain_timer -= delta_time;
+        let [columns, rows] = self.field.dims();
+        while self.rain_timer <= 0.0 {
+            // Exponential gaps between drops look like real rain
+            let gap: f32 = self.rng.random_range(f32::EPSILON..1.0);
+            self.rain_timer += -gap.ln() / self.params.rain;
+            let height = self.params.drop_height * self.rng.random_range(0.5..=1.0);
+            self.drops.push([
+                self.rng.random_range(0.0..columns as f32),
+                self.rng.random_range(0.0..rows as f32),
+                self.params.drop_radius * self.rng.random_range(0.7..=1.3),
+                height,
+            ]);
+        }
+    }
+
+    fn create_gpu_resources(&self, device: &Device) -> ShallowWaterGpuResources {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shallow Water Shader"),
+            source: wgpu::ShaderSource::Wgsl(SHALLOW_WATER_SHADER.into()),
+        });
+
+        let storage_entry = |binding: u32, read_only: bool| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Shallow Water Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage_entry(1, true),
+                storage_entry(2, false),
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Shallow Water Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |entry_point: &str| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+
+        let size = self.field.len() as u64 * std::mem::size_of::<[f32; 4]>() as u64;
+        let state_buffer = |label: &str| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size,
+                usage: wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_DST
+                    | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            })
+        };
+        let state = state_buffer("Shallow Water State A");
+        // Second half of the ping-pong pair, kept alive by the bind groups
+        let scratch = state_buffer("Shallow Water State B");
+        let staging = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Shallow Water Staging Buffer"),
+            size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let uniforms =
+            ParamsUniform::new(device, "Shallow Water Uniforms", &self.uniforms(0.0, &[]));
+
+        let bind_group = |label: &str, src: &wgpu::Buffer, dst: &wgpu::Buffer| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(label),
+                layout: &bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: uniforms.buffer().as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: src.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: dst.as_entire_binding(),
+                    },
+                ],
+            })
+        };
+        let a_to_b = bind_group("Shallow Water A->B", &state, &scratch);
+        let b_to_a = bind_group("Shallow Water B->A", &scratch, &state);
+
+        ShallowWaterGpuResources {
+            drop_pipeline: pipeline("add_drops"),
+            step_pipeline: pipeline("advance"),
+            uniforms,
+            state,
+            staging,
+            a_to_b,
+            b_to_a,
+        }
+    }
+
+    /// Copy the state to the staging buffer and wait for it
+    fn read_state(gpu: &ShallowWaterGpuResources, device: &Device) -> Option<Vec<[f32; 4]>> {
+        let slice = gpu.staging.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        let _ = device.poll(wgpu::MaintainBase::Wait);
+
+        let state = match receiver.recv() {
+            Ok(Ok(())) => {
+                let data = slice.get_mapped_range();
+                Some(bytemuck::cast_slice(&data).to_vec())
+            }
+            _ => None,
+        };
+        gpu.staging.unmap();
+        state
+    }
+}
+
+impl Simulation for ShallowWater {
+    fn initialize(&mut self, scene: &mut Scene) {
+        let depths: Vec<f32> = self.initial_state().iter().map(|cell| cell[0]).collect();
+        scene.add_procedural_object(self.field.geometry(&depths), &self.object_name);
+        let material = match &self.material {
+            Some(material) => material.clone(),
+            None => {
+                let material = format!("{}_material", self.object_name);
+                scene
+                    .add_material(&material, self.color, 0.0, 0.05)
+                    .reflectance = 1.0;
+                material
+            }
+        };
+        if let Some(object) = scene.objects.last_mut() {
+            object.set_material(&material);
+        }
+    }
+
+    fn initialize_gpu(&mut self, device: &Device, _queue: &Queue) {
+        self.gpu = Some(self.create_gpu_resources(device));
+        self.needs_upload = true;
+    }
+
+    fn update(&mut self, _delta_time: f32, _scene: &mut Scene) {}
+
+    fn update_gpu(&mut self, device: &Device, queue: &Queue, delta_time: f32) {
+        let initial = self.needs_upload.then(|| self.initial_state());
+        if let Some(state) = &initial {
+            self.wave_speed = self.fastest_wave(state);
+            self.drops.clear();
+            self.steps = 0;
+            self.time = 0.0;
+        }
+        if self.running {
+            self.rain(delta_time);
+        }
+
+        // A hitch shouldn't turn into a burst of substeps
+        let (substeps, time_step) = self.substeps(delta_time.min(1.0 / 30.0));
+        let drop_count = self.drops.len().min(MAX_DROPS);
+        let drops: Vec<[f32; 4]> = if self.running {
+            self.drops.drain(..drop_count).collect()
+        } else {
+            Vec::new()
+        };
+        let uniforms = self.uniforms(time_step, &drops);
+        let [columns, rows] = self.field.dims();
+        let groups = [columns, rows].map(|n| n.div_ceil(WORKGROUP_SIZE));
+
+        let Some(gpu) = self.gpu.as_mut() else {
+            return;
+        };
+        gpu.uniforms.update(queue, &uniforms);
+
+        if let Some(state) = initial {
+            queue.write_buffer(&gpu.state, 0, bytemuck::cast_slice(&state));
+            self.needs_upload = false;
+            self.readback = Some(state);
+        }
+
+        if !self.running {
+            return;
+        }
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Shallow Water Encoder"),
+        });
+        {
+            crate::logging::crash::record_pass("Shallow Water Step");
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Shallow Water Step"),
+                timestamp_writes: None,
+            });
+            // Drops go from A into B, then an odd number of substeps brings
+            // the state back into A
+            pass.set_pipeline(&gpu.drop_pipeline);
+            pass.set_bind_group(0, &gpu.a_to_b, &[]);
+            pass.dispatch_workgroups(groups[0], groups[1], 1);
+            pass.set_pipeline(&gpu.step_pipeline);
+            for substep in 0..substeps {
+                let bind_group = if substep % 2 == 0 {
+                    &gpu.b_to_a
+                } else {
+                    &gpu.a_to_b
+                };
+                pass.set_bind_group(0, bind_group, &[]);
+                pass.dispatch_workgroups(groups[0], groups[1], 1);
+            }
+        }
+        encoder.copy_buffer_to_buffer(&gpu.state, 0, &gpu.staging, 0, gpu.staging.size());
+        queue.submit(std::iter::once(encoder.finish()));
+
+        self.steps += substeps as u64;
+        self.time += time_step * substeps as f32;
+        self.readback = Self::read_state(gpu, device);
+        if let Some(state) = &self.readback {
+            self.wave_speed = self.fastest_wave(state);
+        }
+    }
+
+    fn apply_gpu_results_to_scene(&mut self, _device: &Device, scene: &mut Scene) {
+        let Some(state) = self.readback.take() else {
+            return;
+        };
+        let depths: Vec<f32> = state.iter().map(|cell| cell[0]).collect();
+        if let Some(object) = scene
+            .objects
+            .iter_mut()
+            .find(|object| object.name == self.object_name)
+        {
+            if let Some(mesh) = object.meshes.first_mut() {
+                mesh.set_vertices(self.field.vertices(&depths));
+            }
+        }
+    }
+
+    fn render_ui(&mut self, ui: &Ui) {
+        ui.window("Shallow Water")
+            .size([340.0, 380.0], imgui::Condition::FirstUseEver)
+            .build(|| {
+                let [columns, rows] = self.field.dims();
+                ui.text(format!("Grid: {columns} x {rows}"));
+                ui.text(format!(
+                    "Time: {:.2} s ({} substeps)",
+                    self.time, self.steps
+                ));
+                ui.text(format!("Fastest wave: {:.2}", self.wave_speed));
+                ui.separator();
+
+                if ui.button(if self.running { "Pause" } else { "Play" }) {
+                    self.running = !self.running;
+                }
+                ui.same_line();
+                if ui.button("Reset") {
+                    self.needs_upload = true;
+                }
+                ui.same_line();
+                if ui.button("Drop") {
+                    let [columns, rows] = self.field.dims();
+                    let drop = [
+                        self.rng.random_range(0.0..columns as f32),
+                        self.rng.random_range(0.0..rows as f32),
+                        self.params.drop_radius * 2.0,
+                        self.params.drop_height * 2.0,
+                    ];
+                    self.drops.push(drop);
+                }
+
+                ui.separator();
+                ui.text("Preset:");
+                for preset in ShallowWaterPreset::ALL {
+                    if ui.radio_button_bool(preset.as_str(), self.preset == preset) {
+                        self.set_preset(preset);
+                    }
+                }
+
+                ui.separator();
+                self.params.build_ui(ui);
+            });
+    }
+
+    fn name(&self) -> &str {
+        "Shallow Water"
+    }
+
+    fn is_running(&self) -> bool {
+        self.running
+    }
+
+    fn set_running(&mut self, running: bool) {
+        self.running = running;
+    }
+
+    fn reset(&mut self, _scene: &mut Scene) {
+        self.needs_upload = true;
+    }
+
+    fn cleanup(&mut self, scene: &mut Scene) {
+        if let Some(index) = scene
+            .objects
+            .iter()
+            .position(|object| object.name == self.object_name)
+        {
+            scene.remove_object(index);
+        }
+    }
+
+    fn is_gpu_ready(&self) -> bool {
+        self.gpu.is_some()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+const SHALLOW_WATER_SHADER: &str = r#"
+struct ShallowWaterUniforms {
+    dims: vec4<u32>,     // columns, rows, drop count
+    params: vec4<f32>,   // time step, gravity, momentum kept
+    spacing: vec4<f32>,
+    drops: array<vec4<f32>, 4>,
+}
+
+@group(0) @binding(0) var<uniform> uniforms: ShallowWaterUniforms;
+@group(0) @binding(1) var<storage, read> state_in: array<vec4<f32>>;
+@group(0) @binding(2) var<storage, read_write> state_out: array<vec4<f32>>;
+
+// Below this depth a cell is dry and doesn't move
+const DRY: f32 = 1e-4;
+
+fn cell_index(cell: vec2<i32>) -> u32 {
+    return u32(cell.y) * uniforms.dims.x + u32(cell.x);
+}
+
+// State of a neighbor; beyond the walls the cell itself, mirrored so no
+// water flows through
+fn neighbor(cell: vec2<i32>, offset: vec2<i32>) -> vec3<f32> {
+    let other = cell + offset;
+    let size = vec2<i32>(uniforms.dims.xy);
+    if (all(other >= vec2<i32>(0)) && all(other < size)) {
+        return state_in[cell_index(other)].xyz;
+    }
+    let u = state_in[cell_index(cell)].xyz;
+    if (offset.x != 0) {
+        return vec3<f32>(u.x, -u.y, u.z);
+    }
+    return vec3<f32>(u.x, u.y, -u.z);
+}
+
+fn velocity(u: vec3<f32>) -> vec2<f32> {
+    if (u.x < DRY) {
+        return vec2<f32>(0.0);
+    }
+    return u.yz / u.x;
+}
+
+fn flux_x(u: vec3<f32>) -> vec3<f32> {
+    let v = velocity(u);
+    let g = uniforms.params.y;
+    return vec3<f32>(u.y, u.y * v.x + 0.5 * g * u.x * u.x, u.z * v.x);
+}
+
+fn flux_y(u: vec3<f32>) -> vec3<f32> {
+    let v = velocity(u);
+    let g = uniforms.params.y;
+    return vec3<f32>(u.z, u.y * v.y, u.z * v.y + 0.5 * g * u.x * u.x);
+}
+
+fn wave_speed(u: vec3<f32>, flow: f32) -> f32 {
+    return abs(flow) + sqrt(uniforms.params.y * max(u.x, 0.0));
+}
+
+// Rusanov flux between `l` and `r` across a face normal to X
+fn face_x(l: vec3<f32>, r: vec3<f32>) -> vec3<f32> {
+    let a = max(wave_speed(l, velocity(l).x), wave_speed(r, velocity(r).x));
+    return 0.5 * (flux_x(l) + flux_x(r)) - 0.5 * a * (r - l);
+}
+
+fn face_y(l: vec3<f32>, r: vec3<f32>) -> vec3<f32> {
+    let a = max(wave_speed(l, velocity(l).y), wave_speed(r, velocity(r).y));
+    return 0.5 * (flux_y(l) + flux_y(r)) - 0.5 * a * (r - l);
+}
+
+@compute @workgroup_size(8, 8)
+fn add_drops(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id.xy >= uniforms.dims.xy)) {
+        return;
+    }
+    let index = cell_index(vec2<i32>(id.xy));
+    var u = state_in[index];
+    for (var i = 0u; i < uniforms.dims.z; i++) {
+        let drop = uniforms.drops[i];
+        let d = vec2<f32>(id.xy) - drop.xy;
+        let q = dot(d, d) / (drop.z * drop.z);
+        // Raised center, lowered ring, no net volume
+        u.x += drop.w * (1.0 - q) * exp(-q);
+    }
+    u.x = max(u.x, 0.0);
+    state_out[index] = u;
+}
+
+@compute @workgroup_size(8, 8)
+fn advance(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id.xy >= uniforms.dims.xy)) {
+        return;
+    }
+    let cell = vec2<i32>(id.xy);
+    let u = state_in[cell_index(cell)].xyz;
+    let fx = face_x(u, neighbor(cell, vec2<i32>(1, 0))) - face_x(neighbor(cell, vec2<i32>(-1, 0)), u);
+    let fy = face_y(u, neighbor(cell, vec2<i32>(0, 1))) - face_y(neighbor(cell, vec2<i32>(0, -1)), u);
+    let dt = uniforms.params.x;
+    var next = u - dt / uniforms.spacing.x * fx - dt / uniforms.spacing.y * fy;
+    next.x = max(next.x, 0.0);
+    if (next.x < DRY) {
+        next = vec3<f32>(next.x, 0.0, 0.0);
+    }
+    next = vec3<f32>(next.x, next.yz * uniforms.params.z);
+    state_out[cell_index(cell)] = vec4<f32>(next, 0.0);
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_and_substeps() {
+        assert_eq!(std::mem::size_of::<ShallowWaterUniforms>(), 112);
+
+        let drop = ShallowWater::drop([64, 32]);
+        assert_eq!(drop.height_field().size(), [4.0, 2.0]);
+        let state = drop.initial_state();
+        let center = state[16 * 64 + 32][0];
+        assert!(center > 0.45 && state[0][0] == 0.25);
+
+        let dam = ShallowWater::dam_break([64, 32]);
+        let state = dam.initial_state();
+        assert_eq!(state[0][0], 0.75);
+        assert_eq!(state[63][0], 0.25);
+
+        // Odd substep counts that keep waves within a cell per substep
+        let (count, time_step) = dam.substeps(1.0 / 60.0);
+        assert_eq!(count % 2, 1);
+        let [dx, _] = dam.height_field().spacing();
+        assert!(time_step * dam.wave_speed <= COURANT * dx + 1e-6);
+        assert!((time_step * count as f32 - 1.0 / 60.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_dam_break_conserves_water() {
+        use crate::wgpu_utils::compute_primitives::test_device;
+
+        // Needs a GPU adapter, skipped without one
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let mut water = ShallowWater::dam_break([48, 24]);
+        water.params.damping = 0.0;
+        let volume = |state: &[[f32; 4]]| state.iter().map(|cell| cell[0]).sum::<f32>();
+        let initial = volume(&water.initial_state());
+        water.initialize_gpu(&device, &queue);
+        for _ in 0..30 {
+            water.update_gpu(&device, &queue, 1.0 / 30.0);
+        }
+        water.add_drop([0.5, 0.0], 0.2, 0.05);
+        water.update_gpu(&device, &queue, 1.0 / 30.0);
+
+        let state = water.readback.take().unwrap();
+        assert!(state.iter().flatten().all(|value| value.is_finite()));
+        assert!(state.iter().all(|cell| cell[0] >= 0.0));
+        assert!((volume(&state) - initial).abs() < initial * 1e-3);
+        // The column has collapsed and its front is most of the way across
+        assert!(state[0][0] < 0.6);
+        assert!(state[40][0] > 0.3);
+    }
+}
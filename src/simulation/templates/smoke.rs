@@ -0,0 +1,774 @@
+//! Smoke
+//!
+//! Stam's stable fluids: an incompressible, inviscid flow on a 3D grid that
+//! carries smoke density and is pushed up by it. Each step runs on the GPU:
+//!
+//! 1. **Buoyancy** - smoke accelerates the flow upwards in proportion to its
+//!    density
+//! 2. **Advection** - every cell traces its velocity back over the time step
+//!    and takes the velocity found there (semi-Lagrangian, so any time step is
+//!    stable)
+//! 3. **Projection** - the divergence of the advected velocity is removed by
+//!    solving a Poisson equation for the pressure with Jacobi iterations and
+//!    subtracting its gradient
+//! 4. **Density** - a [`DyeField`] carries the smoke along the new velocity
+//!    and adds it at the emitter
+//!
+//! The walls of the grid box are solid. Smoke can also be injected by hand:
+//! in paint mode, strokes add density on a plane through the grid with a
+//! [`FieldBrush`]. The density is drawn as a ray marched [`VolumeView`].
+//!
+//! Compared to the lattice Boltzmann examples there are no relaxation rates
+//! or lattice units to get right: velocities are in grid cells per second,
+//! and the flow stays stable at any frame rate, at the cost of numerical
+//! diffusion that makes it look smoother than it should.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::simulation::templates::Smoke;
+//!
+//! let mut app = haggis::default();
+//! app.attach_simulation(Smoke::new([48, 48, 64]));
+//! app.run();
+//! ```
+
+use imgui::Ui;
+use wgpu::{Device, Queue};
+
+use crate::events::{BrushStroke, EventReceiver};
+use crate::gfx::scene::Scene;
+use crate::simulation::paint::{Brush, BrushMode, BrushSurface, FieldBrush, FieldPainter};
+use crate::simulation::params::{ParamsUniform, SimParams};
+use crate::simulation::tracer::{DyeField, DyeSource, VelocityField};
+use crate::simulation::traits::Simulation;
+use crate::simulation::BaseSimulation;
+use crate::visualization::{GridTransform, VolumeView};
+
+const WORKGROUP_SIZE: u32 = 4;
+
+/// Tunable parameters of [`Smoke`]
+#[derive(Debug, Clone, SimParams)]
+pub struct SmokeParams {
+    /// Upward acceleration per unit of density, in cells per second squared
+    #[param(range = 0.0..=100.0, step = 0.5)]
+    pub buoyancy: f32,
+    /// Share of the velocity lost per second
+    #[param(range = 0.0..=2.0, step = 0.01, label = "Velocity Dissipation")]
+    pub velocity_dissipation: f32,
+    /// Share of the density lost per second
+    #[param(range = 0.0..=2.0, step = 0.01, label = "Density Decay")]
+    pub density_decay: f32,
+    /// Density added at the emitter center per step
+    #[param(range = 0.0..=1.0, step = 0.01, label = "Emission Rate")]
+    pub emission: f32,
+    /// Jacobi iterations of the pressure solve, rounded up to an even number
+    #[param(range = 2..=200, label = "Pressure Iterations")]
+    pub iterations: u32,
+}
+
+impl Default for SmokeParams {
+    fn default() -> Self {
+        Self {
+            buoyancy: 20.0,
+            velocity_dissipation: 0.1,
+            density_decay: 0.05,
+            emission: 0.2,
+            iterations: 40,
+        }
+    }
+}
+
+/// Uniforms of the smoke shader
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct SmokeUniforms {
+    dims: [u32; 4],
+    /// Time step, buoyancy, share of the velocity kept
+    params: [f32; 4],
+}
+
+struct SmokeGpuResources {
+    buoyancy_pipeline: wgpu::ComputePipeline,
+    advect_pipeline: wgpu::ComputePipeline,
+    divergence_pipeline: wgpu::ComputePipeline,
+    jacobi_pipeline: wgpu::ComputePipeline,
+    project_pipeline: wgpu::ComputePipeline,
+    uniforms: ParamsUniform<SmokeUniforms>,
+    density_layout: wgpu::BindGroupLayout,
+    /// Velocity and divergence buffers
+    fields: wgpu::BindGroup,
+    /// Jacobi iterations from pressure A into B and from B into A
+    pressure: [wgpu::BindGroup; 2],
+    /// Density bind groups for each of the dye field's buffers, made on first use
+    density: Vec<(wgpu::Buffer, wgpu::BindGroup)>,
+    /// Projected velocity, four floats per cell with the velocity first
+    velocity: wgpu::Buffer,
+    /// Velocity and pressure buffers cleared on reset
+    scratch: [wgpu::Buffer; 3],
+    dye: DyeField,
+    painter: FieldPainter<f32>,
+    /// Copy of the current density, drawn by the volume view
+    display: wgpu::Buffer,
+}
+
+impl SmokeGpuResources {
+    /// Bind group reading `density`, reused while the dye field swaps buffers
+    fn density_group(&mut self, device: &Device, density: &wgpu::Buffer) -> usize {
+        if let Some(index) = self
+            .density
+            .iter()
+            .position(|(buffer, _)| buffer == density)
+        {
+            return index;
+        }
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Smoke Density Bind Group"),
+            layout: &self.density_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: density.as_entire_binding(),
+            }],
+        });
+        self.density.push((density.clone(), bind_group));
+        self.density.len() - 1
+    }
+}
+
+/// GPU stable fluids smoke in a closed box, drawn as a volume
+pub struct Smoke {
+    base: BaseSimulation,
+    grid: GridTransform,
+    pub params: SmokeParams,
+    /// Emitter position in grid coordinates and radius in cells
+    emitter: Option<([f32; 3], f32)>,
+    color: [f32; 4],
+    brush: FieldBrush,
+    strokes: Option<EventReceiver<BrushStroke>>,
+    running: bool,
+    steps: u64,
+    needs_reset: bool,
+    needs_step: bool,
+    gpu: Option<SmokeGpuResources>,
+}
+
+impl Smoke {
+    /// Smoke on a grid of `dims` cells standing on the ground, two units
+    /// along its longest side, with an emitter near the bottom center
+    pub fn new(dims: [u32; 3]) -> Self {
+        assert!(
+            dims.iter().all(|&n| n >= 2),
+            "smoke grid needs two cells per axis"
+        );
+        let longest = dims.iter().max().copied().unwrap_or(2);
+        let size = dims.map(|n| 2.0 * (n - 1) as f32 / (longest - 1) as f32);
+        let grid = GridTransform::new(
+            dims,
+            [-size[0] * 0.5, -size[1] * 0.5, 0.0],
+            [size[0] * 0.5, size[1] * 0.5, size[2]],
+        );
+        let [width, height, depth] = dims.map(|n| n as f32);
+        let brush = FieldBrush::new(
+            grid,
+            BrushSurface::Plane {
+                axis: 1,
+                normalized: 0.5,
+            },
+        )
+        .with_brush(
+            Brush::new(3.0)
+                .with_value([0.5, 0.0, 0.0, 0.0])
+                .with_mode(BrushMode::Add)
+                .with_soft_edge(true),
+        );
+        Self {
+            base: BaseSimulation::new("Smoke"),
+            grid,
+            params: SmokeParams::default(),
+            emitter: Some((
+                [(width - 1.0) * 0.5, (height - 1.0) * 0.5, depth * 0.1],
+                (width.min(height) / 10.0).max(1.5),
+            )),
+            color: [0.85, 0.85, 0.9, 1.0],
+            brush,
+            strokes: None,
+            running: true,
+            steps: 0,
+            needs_reset: false,
+            needs_step: false,
+            gpu: None,
+        }
+    }
+
+    /// Place the grid in the world; the brush paints on the same grid
+    pub fn with_grid(mut self, grid: GridTransform) -> Self {
+        self.grid = grid;
+        self.brush.grid = grid;
+        self
+    }
+
+    /// Emit smoke at `position` in grid coordinates, fading out over `radius` cells
+    pub fn with_emitter(mut self, position: [f32; 3], radius: f32) -> Self {
+        self.emitter = Some((position, radius));
+        self
+    }
+
+    /// Only add smoke by painting
+    pub fn without_emitter(mut self) -> Self {
+        self.emitter = None;
+        self
+    }
+
+    /// Color of the smoke; alpha scales its opacity
+    pub fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Where paint strokes add smoke, by default the middle plane facing along Y
+    pub fn with_brush_surface(mut self, surface: BrushSurface) -> Self {
+        self.brush.surface = surface;
+        self
+    }
+
+    pub fn with_params(mut self, params: SmokeParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    pub fn grid(&self) -> &GridTransform {
+        &self.grid
+    }
+
+    /// Number of steps taken since the last reset
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// GPU buffer holding the current velocity, four floats per cell with the
+    /// velocity in cells per second first
+    pub fn velocity_buffer(&self) -> Option<&wgpu::Buffer> {
+        self.gpu.as_ref().map(|gpu| &gpu.velocity)
+    }
+
+    /// GPU buffer holding the current density, one `f32` per cell
+    pub fn density_buffer(&self) -> Option<&wgpu::Buffer> {
+        self.gpu.as_ref().map(|gpu| &gpu.display)
+    }
+
+    fn uniforms(&self, time_step: f32) -> SmokeUniforms {
+        let [width, height, depth] = self.grid.dims();
+        SmokeUniforms {
+            dims: [width, height, depth, 0],
+            params: [
+                time_step,
+                self.params.buoyancy,
+                (1.0 - self.params.velocity_dissipation * time_step).clamp(0.0, 1.0),
+                0.0,
+            ],
+        }
+    }
+
+    fn place_emitter(&self, dye: &mut DyeField) {
+        dye.clear_sources();
+        if let Some((position, radius)) = self.emitter {
+            dye.add_source(DyeSource::new(position, radius, self.params.emission));
+        }
+        dye.decay = self.params.density_decay;
+    }
+
+    fn create_gpu_resources(&self, device: &Device) -> SmokeGpuResources {
+        let [width, height, depth] = self.grid.dims();
+        let cells = width as u64 * height as u64 * depth as u64;
+        let field_buffer = |label, floats: u64, usage| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: cells * floats * 4,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST | usage,
+                mapped_at_creation: false,
+            })
+        };
+        let velocity = field_buffer("Smoke Velocity", 4, wgpu::BufferUsages::COPY_SRC);
+        let advected = field_buffer("Smoke Advected Velocity", 4, wgpu::BufferUsages::empty());
+        let divergence = field_buffer("Smoke Divergence", 1, wgpu::BufferUsages::empty());
+        let pressure_a = field_buffer("Smoke Pressure A", 1, wgpu::BufferUsages::COPY_SRC);
+        let pressure_b = field_buffer("Smoke Pressure B", 1, wgpu::BufferUsages::empty());
+        let display = field_buffer("Smoke Density", 1, wgpu::BufferUsages::COPY_SRC);
+
+        let entry = |binding, read_only: Option<bool>| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: match read_only {
+                    None => wgpu::BufferBindingType::Uniform,
+                    Some(read_only) => wgpu::BufferBindingType::Storage { read_only },
+                },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let fields_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Smoke Fields Layout"),
+            entries: &[
+                entry(0, None),
+                entry(1, Some(false)),
+                entry(2, Some(false)),
+                entry(3, Some(false)),
+            ],
+        });
+        let pressure_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Smoke Pressure Layout"),
+            entries: &[entry(0, Some(true)), entry(1, Some(false))],
+        });
+        let density_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Smoke Density Layout"),
+            entries: &[entry(0, Some(true))],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Smoke Shader"),
+            source: wgpu::ShaderSource::Wgsl(SMOKE_SHADER.into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Smoke Pipeline Layout"),
+            bind_group_layouts: &[&fields_layout, &pressure_layout, &density_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |entry_point: &str| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &shader,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+
+        let uniforms = ParamsUniform::new(device, "Smoke Uniforms", &self.uniforms(0.0));
+        let buffers = [uniforms.buffer(), &velocity, &advected, &divergence];
+        let entries: Vec<wgpu::BindGroupEntry> = buffers
+            .iter()
+            .enumerate()
+            .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                binding: binding as u32,
+                resource: buffer.as_entire_binding(),
+            })
+            .collect();
+        let fields = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Smoke Fields Bind Group"),
+            layout: &fields_layout,
+            entries: &entries,
+        });
+        let pressure =
+            [(&pressure_a, &pressure_b), (&pressure_b, &pressure_a)].map(|(input, output)| {
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Smoke Pressure Bind Group"),
+                    layout: &pressure_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: input.as_entire_binding(),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: output.as_entire_binding(),
+                        },
+                    ],
+                })
+            });
+
+        // Velocities are already in cells per second
+        let mut dye = DyeField::new(device, self.grid, VelocityField::new(&velocity, 4));
+        self.place_emitter(&mut dye);
+
+        SmokeGpuResources {
+            buoyancy_pipeline: pipeline("buoyancy"),
+            advect_pipeline: pipeline("advect"),
+            divergence_pipeline: pipeline("divergence"),
+            jacobi_pipeline: pipeline("jacobi"),
+            project_pipeline: pipeline("project"),
+            uniforms,
+            density_layout,
+            fields,
+            pressure,
+            density: Vec::new(),
+            velocity,
+            scratch: [advected, pressure_a, pressure_b],
+            dye,
+            painter: FieldPainter::new(device, self.grid.dims(), 1),
+            display,
+        }
+    }
+
+    /// Add the strokes made since the last frame to the density
+    fn paint(&mut self, device: &Device, queue: &Queue) {
+        let (Some(strokes), Some(gpu)) = (&self.strokes, &self.gpu) else {
+            return;
+        };
+        for stroke in strokes.drain() {
+            let stamps = self.brush.stamps(&stroke);
+            let brush = if stroke.erase {
+                self.brush.brush.eraser()
+            } else {
+                self.brush.brush
+            };
+            gpu.painter
+                .paint(device, queue, gpu.dye.dye_buffer(), &brush, &stamps);
+        }
+    }
+
+    fn clear(gpu: &mut SmokeGpuResources, queue: &Queue) {
+        for buffer in [&gpu.velocity, &gpu.display]
+            .into_iter()
+            .chain(&gpu.scratch)
+        {
+            queue.write_buffer(buffer, 0, &vec![0u8; buffer.size() as usize]);
+        }
+        gpu.dye.clear(queue);
+    }
+}
+
+impl Simulation for Smoke {
+    fn initialize(&mut self, scene: &mut Scene) {
+        self.strokes = Some(scene.events.subscribe());
+        self.base.initialize(scene);
+    }
+
+    fn initialize_gpu(&mut self, device: &Device, queue: &Queue) {
+        let gpu = self.create_gpu_resources(device);
+        // New buffers start out empty
+        self.needs_reset = false;
+        self.steps = 0;
+
+        let mut view = VolumeView::new(self.grid).with_color(self.color);
+        view.set_gpu_buffer(gpu.display.clone());
+
+        self.gpu = Some(gpu);
+        self.base.remove_visualization("smoke");
+        self.base.remove_visualization("bounds");
+        self.base.add_visualization("smoke", view);
+        self.base
+            .add_visualization("bounds", self.grid.domain_box());
+        self.base.initialize_gpu(device, queue);
+    }
+
+    fn update(&mut self, delta_time: f32, scene: &mut Scene) {
+        self.base.update(delta_time, scene);
+    }
+
+    fn update_gpu(&mut self, device: &Device, queue: &Queue, delta_time: f32) {
+        self.paint(device, queue);
+        // Advection is stable at any step, but a hitch shouldn't fling the smoke
+        let uniforms = self.uniforms(delta_time.min(1.0 / 20.0));
+        let time_step = uniforms.params[0];
+        let iterations = self.params.iterations.max(2).div_ceil(2);
+        let [width, height, depth] = self.grid.dims();
+        let groups = [width, height, depth].map(|n| n.div_ceil(WORKGROUP_SIZE));
+
+        let Some(mut gpu) = self.gpu.take() else {
+            return;
+        };
+        self.place_emitter(&mut gpu.dye);
+        gpu.uniforms.update(queue, &uniforms);
+        if std::mem::take(&mut self.needs_reset) {
+            Self::clear(&mut gpu, queue);
+        }
+
+        if self.running || self.needs_step {
+            let dye_buffer = gpu.dye.dye_buffer().clone();
+            let density = gpu.density_group(device, &dye_buffer);
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Smoke Encoder"),
+            });
+            {
+                crate::logging::crash::record_pass("Smoke Step");
+                let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: Some("Smoke Step"),
+                    timestamp_writes: None,
+                });
+                pass.set_bind_group(0, &gpu.fields, &[]);
+                pass.set_bind_group(1, &gpu.pressure[0], &[]);
+                pass.set_bind_group(2, &gpu.density[density].1, &[]);
+                let dispatch = |pass: &mut wgpu::ComputePass, pipeline: &wgpu::ComputePipeline| {
+                    pass.set_pipeline(pipeline);
+                    pass.dispatch_workgroups(groups[0], groups[1], groups[2]);
+                };
+                dispatch(&mut pass, &gpu.buoyancy_pipeline);
+                dispatch(&mut pass, &gpu.advect_pipeline);
+                dispatch(&mut pass, &gpu.divergence_pipeline);
+                // Pairs of sweeps, so the pressure ends up back in buffer A
+                for _ in 0..iterations {
+                    for pressure in &gpu.pressure {
+                        pass.set_bind_group(1, pressure, &[]);
+                        dispatch(&mut pass, &gpu.jacobi_pipeline);
+                    }
+                }
+                pass.set_bind_group(1, &gpu.pressure[0], &[]);
+                dispatch(&mut pass, &gpu.project_pipeline);
+            }
+            gpu.dye.encode(queue, &mut encoder, time_step);
+            encoder.copy_buffer_to_buffer(
+                gpu.dye.dye_buffer(),
+                0,
+                &gpu.display,
+                0,
+                gpu.display.size(),
+            );
+            queue.submit(std::iter::once(encoder.finish()));
+            self.steps += 1;
+        }
+        self.needs_step = false;
+        self.gpu = Some(gpu);
+
+        self.base.update_gpu(device, queue, delta_time);
+    }
+
+    fn apply_gpu_results_to_scene(&mut self, device: &Device, scene: &mut Scene) {
+        self.base.apply_gpu_results_to_scene(device, scene);
+    }
+
+    fn render_ui(&mut self, ui: &Ui) {
+        ui.window("Smoke")
+            .size([360.0, 420.0], imgui::Condition::FirstUseEver)
+            .build(|| {
+                let [width, height, depth] = self.grid.dims();
+                ui.text(format!("Grid: {width} x {height} x {depth}"));
+                ui.text(format!("Steps: {}", self.steps));
+                ui.separator();
+
+                if ui.button(if self.running { "Pause" } else { "Play" }) {
+                    self.running = !self.running;
+                }
+                ui.same_line();
+                if ui.button("Step") {
+                    self.needs_step = true;
+                }
+                ui.same_line();
+                if ui.button("Reset") {
+                    self.needs_reset = true;
+                    self.steps = 0;
+                }
+
+                ui.separator();
+                self.params.build_ui(ui);
+
+                // Brush for paint mode (toggle "Paint" in the simulation toolbar)
+                ui.separator();
+                ui.text("Paint (left drag adds smoke, right drag clears it):");
+                self.brush.brush.render_ui(ui, 1);
+            });
+
+        self.base.render_ui(ui);
+    }
+
+    fn name(&self) -> &str {
+        "Smoke"
+    }
+
+    fn is_running(&self) -> bool {
+        self.running
+    }
+
+    fn set_running(&mut self, running: bool) {
+        self.running = running;
+    }
+
+    fn reset(&mut self, scene: &mut Scene) {
+        self.needs_reset = true;
+        self.steps = 0;
+        self.base.reset(scene);
+    }
+
+    fn is_gpu_ready(&self) -> bool {
+        self.gpu.is_some()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        &self.base
+    }
+}
+
+const SMOKE_SHADER: &str = r#"
+struct SmokeUniforms {
+    dims: vec4<u32>,
+    params: vec4<f32>, // time step, buoyancy, velocity kept
+}
+
+@group(0) @binding(0) var<uniform> uniforms: SmokeUniforms;
+@group(0) @binding(1) var<storage, read_write> velocity: array<vec4<f32>>;
+@group(0) @binding(2) var<storage, read_write> advected: array<vec4<f32>>;
+@group(0) @binding(3) var<storage, read_write> divergence_field: array<f32>;
+@group(1) @binding(0) var<storage, read> pressure_in: array<f32>;
+@group(1) @binding(1) var<storage, read_write> pressure_out: array<f32>;
+@group(2) @binding(0) var<storage, read> density: array<f32>;
+
+fn cell_index(cell: vec3<u32>) -> u32 {
+    return (cell.z * uniforms.dims.y + cell.y) * uniforms.dims.x + cell.x;
+}
+
+fn inside(cell: vec3<i32>) -> bool {
+    return all(cell >= vec3<i32>(0)) && all(cell < vec3<i32>(uniforms.dims.xyz));
+}
+
+fn load_velocity(cell: vec3<i32>) -> vec3<f32> {
+    let last = vec3<i32>(uniforms.dims.xyz) - vec3<i32>(1);
+    return velocity[cell_index(vec3<u32>(clamp(cell, vec3<i32>(0), last)))].xyz;
+}
+
+// Trilinear interpolation, clamped to the grid
+fn sample_velocity(position: vec3<f32>) -> vec3<f32> {
+    let base = floor(position);
+    let f = position - base;
+    let b = vec3<i32>(base);
+    let x00 = mix(load_velocity(b), load_velocity(b + vec3<i32>(1, 0, 0)), f.x);
+    let x10 = mix(load_velocity(b + vec3<i32>(0, 1, 0)), load_velocity(b + vec3<i32>(1, 1, 0)), f.x);
+    let x01 = mix(load_velocity(b + vec3<i32>(0, 0, 1)), load_velocity(b + vec3<i32>(1, 0, 1)), f.x);
+    let x11 = mix(load_velocity(b + vec3<i32>(0, 1, 1)), load_velocity(b + vec3<i32>(1, 1, 1)), f.x);
+    return mix(mix(x00, x10, f.y), mix(x01, x11, f.y), f.z);
+}
+
+// No flow through the walls of the box, per axis
+fn wall_velocity(cell: vec3<u32>, v: vec3<f32>) -> vec3<f32> {
+    let last = uniforms.dims.xyz - vec3<u32>(1u);
+    let on_wall = (cell == vec3<u32>(0u)) | (cell == last);
+    return select(v, vec3<f32>(0.0), on_wall);
+}
+
+// Advected velocity of a neighbor, zero beyond the walls
+fn advected_at(cell: vec3<i32>) -> vec3<f32> {
+    if (!inside(cell)) {
+        return vec3<f32>(0.0);
+    }
+    return advected[cell_index(vec3<u32>(cell))].xyz;
+}
+
+// Pressure of a neighbor, mirrored at the walls so no flow crosses them
+fn pressure_at(cell: vec3<i32>, center: f32) -> f32 {
+    if (!inside(cell)) {
+        return center;
+    }
+    return pressure_in[cell_index(vec3<u32>(cell))];
+}
+
+@compute @workgroup_size(4, 4, 4)
+fn buoyancy(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id >= uniforms.dims.xyz)) {
+        return;
+    }
+    let index = cell_index(id);
+    let lift = uniforms.params.y * max(density[index], 0.0) * uniforms.params.x;
+    velocity[index].z += lift;
+}
+
+@compute @workgroup_size(4, 4, 4)
+fn advect(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id >= uniforms.dims.xyz)) {
+        return;
+    }
+    let index = cell_index(id);
+    let origin = vec3<f32>(id) - velocity[index].xyz * uniforms.params.x;
+    let v = sample_velocity(origin) * uniforms.params.z;
+    advected[index] = vec4<f32>(wall_velocity(id, v), 0.0);
+}
+
+@compute @workgroup_size(4, 4, 4)
+fn divergence(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id >= uniforms.dims.xyz)) {
+        return;
+    }
+    let c = vec3<i32>(id);
+    let dx = advected_at(c + vec3<i32>(1, 0, 0)).x - advected_at(c - vec3<i32>(1, 0, 0)).x;
+    let dy = advected_at(c + vec3<i32>(0, 1, 0)).y - advected_at(c - vec3<i32>(0, 1, 0)).y;
+    let dz = advected_at(c + vec3<i32>(0, 0, 1)).z - advected_at(c - vec3<i32>(0, 0, 1)).z;
+    divergence_field[cell_index(id)] = 0.5 * (dx + dy + dz);
+}
+
+@compute @workgroup_size(4, 4, 4)
+fn jacobi(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id >= uniforms.dims.xyz)) {
+        return;
+    }
+    let index = cell_index(id);
+    let c = vec3<i32>(id);
+    let p = pressure_in[index];
+    let neighbors = pressure_at(c + vec3<i32>(1, 0, 0), p) + pressure_at(c - vec3<i32>(1, 0, 0), p)
+        + pressure_at(c + vec3<i32>(0, 1, 0), p) + pressure_at(c - vec3<i32>(0, 1, 0), p)
+        + pressure_at(c + vec3<i32>(0, 0, 1), p) + pressure_at(c - vec3<i32>(0, 0, 1), p);
+    pressure_out[index] = (neighbors - divergence_field[index]) / 6.0;
+}
+
+@compute @workgroup_size(4, 4, 4)
+fn project(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id >= uniforms.dims.xyz)) {
+        return;
+    }
+    let index = cell_index(id);
+    let c = vec3<i32>(id);
+    let p = pressure_in[index];
+    let gradient = 0.5 * vec3<f32>(
+        pressure_at(c + vec3<i32>(1, 0, 0), p) - pressure_at(c - vec3<i32>(1, 0, 0), p),
+        pressure_at(c + vec3<i32>(0, 1, 0), p) - pressure_at(c - vec3<i32>(0, 1, 0), p),
+        pressure_at(c + vec3<i32>(0, 0, 1), p) - pressure_at(c - vec3<i32>(0, 0, 1), p),
+    );
+    velocity[index] = vec4<f32>(wall_velocity(id, advected[index].xyz - gradient), 0.0);
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::Vector3;
+
+    #[test]
+    fn test_grid_stands_on_the_ground() {
+        let smoke = Smoke::new([33, 33, 65]);
+        let grid = smoke.grid();
+        assert_eq!(grid.min(), Vector3::new(-0.5, -0.5, 0.0));
+        assert_eq!(grid.max(), Vector3::new(0.5, 0.5, 2.0));
+        let (emitter, _) = smoke.emitter.unwrap();
+        assert_eq!(emitter, [16.0, 16.0, 6.5]);
+        assert_eq!(std::mem::size_of::<SmokeUniforms>(), 32);
+    }
+
+    #[test]
+    fn test_smoke_rises_from_the_emitter() {
+        use crate::wgpu_utils::compute_primitives::{read_buffer, test_device};
+
+        // Needs a GPU adapter, skipped without one
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let dims = [16, 16, 24];
+        let mut smoke = Smoke::new(dims);
+        smoke.initialize_gpu(&device, &queue);
+        for _ in 0..60 {
+            smoke.update_gpu(&device, &queue, 1.0 / 30.0);
+        }
+
+        let cells = (dims[0] * dims[1] * dims[2]) as usize;
+        let density: Vec<f32> =
+            read_buffer(&device, &queue, smoke.density_buffer().unwrap(), cells).unwrap();
+        let velocity: Vec<[f32; 4]> =
+            read_buffer(&device, &queue, smoke.velocity_buffer().unwrap(), cells).unwrap();
+        assert!(velocity.iter().flatten().all(|v| v.is_finite()));
+
+        // Density weighted height well above the emitter, carried by upward flow
+        let layer = (dims[0] * dims[1]) as usize;
+        let total: f32 = density.iter().sum();
+        let height: f32 = density
+            .iter()
+            .enumerate()
+            .map(|(index, d)| d * (index / layer) as f32)
+            .sum::<f32>()
+            / total;
+        assert!(total > 1.0);
+        assert!(
+            height > smoke.emitter.unwrap().0[2] + 2.0,
+            "smoke at {height}"
+        );
+        let lift: f32 = velocity.iter().zip(&density).map(|(v, d)| v[2] * d).sum();
+        assert!(lift > 0.0);
+    }
+}
@@ -0,0 +1,743 @@
+//! Soft body simulation on a tetrahedral mesh
+//!
+//! A volume split into tetrahedra and solved with [position-based
+//! dynamics](crate::simulation::pbd): every tetrahedron edge is a distance
+//! constraint and every tetrahedron keeps its volume, so the body squashes
+//! and wobbles without collapsing. The boundary faces of the tetrahedra are
+//! extracted into a scene mesh that follows the particles every step, so the
+//! body is lit, shadowed and picked like any other object.
+//!
+//! The object is [`Draggable`]: pressing the left mouse button on it grabs the
+//! surface particle nearest to the click, which then follows the mouse at the
+//! depth it was grabbed at while the solver pulls the rest of the body along.
+//! Releasing the button lets go, with the particle keeping the speed of the
+//! last mouse move on the CPU solver.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::simulation::templates::SoftBody;
+//!
+//! let mut app = haggis::default();
+//! app.attach_simulation(
+//!     SoftBody::new([1.0, 1.0, 1.0], [4, 4, 4])
+//!         .at([0.0, 0.0, 1.5])
+//!         .with_color([0.2, 0.7, 0.4, 1.0]),
+//! );
+//! app.run();
+//! ```
+
+use std::collections::{BTreeSet, HashMap};
+
+use cgmath::{InnerSpace, Vector3};
+use imgui::Ui;
+use wgpu::{Device, Queue};
+
+use crate::ecs::Draggable;
+use crate::events::{DragEvent, DragPhase, EventReceiver};
+use crate::gfx::geometry::GeometryData;
+use crate::gfx::scene::object::Mesh;
+use crate::gfx::scene::Scene;
+use crate::simulation::params::SimParams;
+use crate::simulation::pbd::{CpuPbdSolver, GpuPbdSolver, PbdCollider, PbdConstraint, PbdSystem};
+use crate::simulation::traits::Simulation;
+
+/// Longest step taken at once; longer frames slow the body down instead of
+/// launching it
+const MAX_STEP: f32 = 1.0 / 30.0;
+
+/// Solver that steps a [`SoftBody`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SoftBodySolver {
+    /// Sequential projection on the CPU; converges fastest and supports SDF
+    /// colliders
+    #[default]
+    Cpu,
+    /// Graph-colored projection in compute shaders, for large bodies
+    Gpu,
+}
+
+/// Tunable parameters of [`SoftBody`]
+#[derive(Debug, Clone, SimParams)]
+pub struct SoftBodyParams {
+    /// Compliance of the tetrahedron edges, `0.0` is rigid
+    #[param(range = 0.0..=0.01, step = 0.0001, label = "Edge Compliance")]
+    pub edge_compliance: f32,
+    /// Compliance of the tetrahedron volumes, `0.0` is incompressible
+    #[param(range = 0.0..=0.01, step = 0.0001, label = "Volume Compliance")]
+    pub volume_compliance: f32,
+    /// Share of the velocity kept per second
+    #[param(range = 0.5..=1.0, step = 0.01)]
+    pub damping: f32,
+    #[param(range = -20.0..=0.0, step = 0.1)]
+    pub gravity: f32,
+    /// Fraction of the sliding motion removed on contact
+    #[param(range = 0.0..=1.0, step = 0.01)]
+    pub friction: f32,
+    #[param(range = 1..=32)]
+    pub substeps: u32,
+}
+
+impl Default for SoftBodyParams {
+    fn default() -> Self {
+        Self {
+            edge_compliance: 0.0005,
+            volume_compliance: 0.0,
+            damping: 0.98,
+            gravity: -9.81,
+            friction: 0.3,
+            substeps: 10,
+        }
+    }
+}
+
+/// Particle held by the mouse
+#[derive(Debug, Clone, Copy)]
+struct Grab {
+    particle: usize,
+    /// Inverse mass to restore on release
+    inverse_mass: f32,
+    /// Distance along the mouse ray the particle is held at
+    depth: f32,
+    target: Vector3<f32>,
+    /// Target before the last mouse move, for the release velocity
+    previous_target: Vector3<f32>,
+}
+
+/// Position-based soft body driving a draggable scene mesh
+pub struct SoftBody {
+    object_name: String,
+    material: Option<String>,
+    color: [f32; 4],
+    /// Particle positions relative to `origin`
+    rest: Vec<Vector3<f32>>,
+    tetrahedra: Vec<[usize; 4]>,
+    origin: [f32; 3],
+    mass: f32,
+    ground: Option<f32>,
+    colliders: Vec<PbdCollider>,
+    solver: SoftBodySolver,
+    pub params: SoftBodyParams,
+    running: bool,
+    system: PbdSystem,
+    /// Particles on the boundary, in mesh vertex order
+    surface: Vec<usize>,
+    /// Boundary triangles indexing into `surface`
+    triangles: Vec<u32>,
+    cpu: CpuPbdSolver,
+    gpu: Option<GpuPbdSolver>,
+    /// Whether the GPU solver has to be rebuilt from `system`
+    gpu_stale: bool,
+    /// Particle whose position or mass changed outside the GPU solver
+    gpu_dirty: Option<usize>,
+    drags: Option<EventReceiver<DragEvent>>,
+    grab: Option<Grab>,
+    /// Whether the mesh lags behind the particles
+    needs_mesh_update: bool,
+}
+
+impl SoftBody {
+    /// Create a box of `size` world units split into `resolution` cells per
+    /// side, each cut into six tetrahedra
+    pub fn new(size: [f32; 3], resolution: [u32; 3]) -> Self {
+        assert!(
+            resolution.iter().all(|&cells| cells >= 1),
+            "soft body needs at least one cell per side"
+        );
+        let [nx, ny, nz] = resolution.map(|cells| cells as usize);
+        let index = |x: usize, y: usize, z: usize| (z * (ny + 1) + y) * (nx + 1) + x;
+
+        let mut rest = Vec::with_capacity((nx + 1) * (ny + 1) * (nz + 1));
+        for z in 0..=nz {
+            for y in 0..=ny {
+                for x in 0..=nx {
+                    rest.push(Vector3::new(
+                        size[0] * (x as f32 / nx as f32 - 0.5),
+                        size[1] * (y as f32 / ny as f32 - 0.5),
+                        size[2] * (z as f32 / nz as f32 - 0.5),
+                    ));
+                }
+            }
+        }
+
+        // Six tetrahedra around each cell's main diagonal; neighboring cells
+        // split their shared faces the same way
+        const AXIS_ORDERS: [[usize; 3]; 6] = [
+            [1, 2, 4],
+            [1, 4, 2],
+            [2, 1, 4],
+            [2, 4, 1],
+            [4, 1, 2],
+            [4, 2, 1],
+        ];
+        let mut tetrahedra = Vec::with_capacity(6 * nx * ny * nz);
+        for z in 0..nz {
+            for y in 0..ny {
+                for x in 0..nx {
+                    let corner = |bits: usize| {
+                        index(x + (bits & 1), y + ((bits >> 1) & 1), z + ((bits >> 2) & 1))
+                    };
+                    for [a, b, _] in AXIS_ORDERS {
+                        tetrahedra.push([corner(0), corner(a), corner(a | b), corner(7)]);
+                    }
+                }
+            }
+        }
+        Self::from_tetrahedra(rest.into_iter().map(Into::into).collect(), tetrahedra)
+    }
+
+    /// Create a body from any tetrahedral mesh, e.g. one exported by TetGen
+    ///
+    /// `vertices` are relative to the point set with [`at`](Self::at).
+    pub fn from_tetrahedra(vertices: Vec<[f32; 3]>, tetrahedra: Vec<[usize; 4]>) -> Self {
+        assert!(
+            tetrahedra.iter().flatten().all(|&i| i < vertices.len()),
+            "tetrahedron references a missing vertex"
+        );
+        let rest: Vec<Vector3<f32>> = vertices.into_iter().map(Vector3::from).collect();
+        let (surface, triangles) = extract_surface(&rest, &tetrahedra);
+        Self {
+            object_name: "Soft Body".to_string(),
+            material: None,
+            color: [0.3, 0.7, 0.45, 1.0],
+            rest,
+            tetrahedra,
+            origin: [0.0, 0.0, 1.0],
+            mass: 1.0,
+            ground: Some(0.0),
+            colliders: Vec::new(),
+            solver: SoftBodySolver::default(),
+            params: SoftBodyParams::default(),
+            running: true,
+            system: PbdSystem::new(),
+            surface,
+            triangles,
+            cpu: CpuPbdSolver::new(),
+            gpu: None,
+            gpu_stale: false,
+            gpu_dirty: None,
+            drags: None,
+            grab: None,
+            needs_mesh_update: false,
+        }
+    }
+
+    /// Center of the body before it starts moving
+    pub fn at(mut self, origin: [f32; 3]) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Name of the scene object holding the surface mesh
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.object_name = name.to_string();
+        self
+    }
+
+    /// Use an existing material instead of creating one from the color
+    pub fn with_material(mut self, material_id: &str) -> Self {
+        self.material = Some(material_id.to_string());
+        self
+    }
+
+    pub fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Total mass in kilograms, spread evenly over the particles
+    pub fn with_mass(mut self, mass: f32) -> Self {
+        self.mass = mass;
+        self
+    }
+
+    /// Height of the ground plane, or `None` to let the body fall forever
+    pub fn with_ground(mut self, height: Option<f32>) -> Self {
+        self.ground = height;
+        self
+    }
+
+    /// Keep the body out of `collider` as well
+    pub fn with_collider(mut self, collider: PbdCollider) -> Self {
+        self.colliders.push(collider);
+        self
+    }
+
+    pub fn with_solver(mut self, solver: SoftBodySolver) -> Self {
+        self.solver = solver;
+        self
+    }
+
+    pub fn with_params(mut self, params: SoftBodyParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// The particles, constraints and colliders being solved
+    pub fn system(&self) -> &PbdSystem {
+        &self.system
+    }
+
+    pub fn tetrahedra(&self) -> &[[usize; 4]] {
+        &self.tetrahedra
+    }
+
+    /// Particle held by the mouse, if any
+    pub fn grabbed_particle(&self) -> Option<usize> {
+        self.grab.map(|grab| grab.particle)
+    }
+
+    /// Particles at their rest positions, with one distance constraint per
+    /// tetrahedron edge and one volume constraint per tetrahedron
+    fn build_system(&self) -> PbdSystem {
+        let mut system = PbdSystem::new();
+        let particle_mass = self.mass / self.rest.len().max(1) as f32;
+        let origin = Vector3::from(self.origin);
+        for &position in &self.rest {
+            system.add_particle(origin + position, particle_mass);
+        }
+
+        let edges: BTreeSet<(usize, usize)> = self
+            .tetrahedra
+            .iter()
+            .flat_map(|&[a, b, c, d]| [(a, b), (a, c), (a, d), (b, c), (b, d), (c, d)])
+            .map(|(a, b)| (a.min(b), a.max(b)))
+            .collect();
+        for (a, b) in edges {
+            system.add_distance(a, b, self.params.edge_compliance);
+        }
+        for &tetrahedron in &self.tetrahedra {
+            system.add_volume(tetrahedron, self.params.volume_compliance);
+        }
+
+        if let Some(height) = self.ground {
+            system.add_collider(PbdCollider::ground(height));
+        }
+        system.colliders.extend(self.colliders.iter().cloned());
+        system
+    }
+
+    /// Copy the parameters into the system, updating the constraint
+    /// compliances if they were changed
+    fn apply_params(&mut self) {
+        let settings = &mut self.system.settings;
+        settings.gravity = Vector3::new(0.0, 0.0, self.params.gravity);
+        settings.damping = self.params.damping;
+        settings.friction = self.params.friction;
+        settings.substeps = self.params.substeps.max(1);
+
+        for constraint in &mut self.system.constraints {
+            let (compliance, target) = match constraint {
+                PbdConstraint::Distance { compliance, .. } => {
+                    (compliance, self.params.edge_compliance)
+                }
+                PbdConstraint::Volume { compliance, .. } => {
+                    (compliance, self.params.volume_compliance)
+                }
+                PbdConstraint::Bending { .. } => continue,
+            };
+            if *compliance != target {
+                *compliance = target;
+                // Constraints live in GPU buffers built with the solver
+                self.gpu_stale = true;
+            }
+        }
+    }
+
+    /// Follow the mouse: grab on press, move the target while dragging and
+    /// let go on release
+    fn handle_drags(&mut self, scene: &Scene) {
+        let Some(drags) = &self.drags else {
+            return;
+        };
+        let object_index = scene
+            .objects
+            .iter()
+            .position(|object| object.name == self.object_name);
+
+        for drag in drags.drain() {
+            if Some(drag.object_index) != object_index && drag.phase == DragPhase::Begin {
+                continue;
+            }
+            match drag.phase {
+                DragPhase::Begin => {
+                    self.release();
+                    let Some(particle) = self.nearest_surface_particle(drag.point) else {
+                        continue;
+                    };
+                    let depth = (drag.point - drag.ray.origin).dot(drag.ray.direction);
+                    let target = drag.ray.origin + drag.ray.direction * depth;
+                    self.grab = Some(Grab {
+                        particle,
+                        inverse_mass: self.system.inverse_masses[particle],
+                        depth,
+                        target,
+                        previous_target: target,
+                    });
+                }
+                DragPhase::Move => {
+                    if let Some(grab) = &mut self.grab {
+                        grab.previous_target = grab.target;
+                        grab.target = drag.ray.origin + drag.ray.direction * grab.depth;
+                    }
+                }
+                DragPhase::End => self.release(),
+            }
+        }
+    }
+
+    fn nearest_surface_particle(&self, point: Vector3<f32>) -> Option<usize> {
+        self.surface.iter().copied().min_by(|&a, &b| {
+            let distance = |i: usize| (self.system.positions[i] - point).magnitude2();
+            distance(a).total_cmp(&distance(b))
+        })
+    }
+
+    /// Hold the grabbed particle at the mouse target for the next step
+    fn hold_grab(&mut self) {
+        let Some(grab) = self.grab else {
+            return;
+        };
+        self.system.positions[grab.particle] = grab.target;
+        self.system.velocities[grab.particle] = Vector3::new(0.0, 0.0, 0.0);
+        self.system.inverse_masses[grab.particle] = 0.0;
+        self.gpu_dirty = Some(grab.particle);
+    }
+
+    /// Let go of the grabbed particle, throwing it with the last mouse move
+    fn release(&mut self) {
+        let Some(grab) = self.grab.take() else {
+            return;
+        };
+        self.system.inverse_masses[grab.particle] = grab.inverse_mass;
+        self.system.velocities[grab.particle] = (grab.target - grab.previous_target) / MAX_STEP;
+        self.gpu_dirty = Some(grab.particle);
+    }
+
+    /// Mesh over the boundary faces at the current particle positions
+    fn geometry(&self) -> GeometryData {
+        let mut geometry = GeometryData::new();
+        geometry.vertices = self.surface_positions();
+        geometry.indices = self.triangles.clone();
+        let flat: Vec<f32> = geometry.vertices.iter().flatten().copied().collect();
+        geometry.normals = Mesh::calculate_face_normals(&flat, &geometry.indices)
+            .chunks(3)
+            .map(|n| [n[0], n[1], n[2]])
+            .collect();
+        geometry
+    }
+
+    fn surface_positions(&self) -> Vec<[f32; 3]> {
+        self.surface
+            .iter()
+            .map(|&i| self.system.positions[i].into())
+            .collect()
+    }
+
+    fn update_mesh(&mut self, scene: &mut Scene) {
+        if !std::mem::take(&mut self.needs_mesh_update) {
+            return;
+        }
+        let positions = self.surface_positions();
+        if let Some(object) = scene
+            .objects
+            .iter_mut()
+            .find(|object| object.name == self.object_name)
+        {
+            if let Some(mesh) = object.meshes.first_mut() {
+                mesh.set_positions(&positions);
+            }
+        }
+    }
+
+    /// Write the particle changed by grabbing into the GPU solver
+    fn upload_dirty_particle(&mut self, queue: &Queue) {
+        let (Some(gpu), Some(particle)) = (&self.gpu, self.gpu_dirty.take()) else {
+            return;
+        };
+        let p = self.system.positions[particle];
+        let data = [p.x, p.y, p.z, self.system.inverse_masses[particle]];
+        queue.write_buffer(
+            gpu.positions_buffer(),
+            (particle * std::mem::size_of::<[f32; 4]>()) as u64,
+            bytemuck::cast_slice(&data),
+        );
+    }
+
+    fn restart(&mut self) {
+        self.grab = None;
+        self.system = self.build_system();
+        self.apply_params();
+        self.cpu = CpuPbdSolver::new();
+        self.gpu_stale = self.gpu.is_some();
+        self.gpu_dirty = None;
+        self.needs_mesh_update = true;
+    }
+}
+
+impl Simulation for SoftBody {
+    fn initialize(&mut self, scene: &mut Scene) {
+        self.restart();
+        scene.add_procedural_object(self.geometry(), &self.object_name);
+        let material = match &self.material {
+            Some(material) => material.clone(),
+            None => {
+                let material = format!("{}_material", self.object_name);
+                let [r, g, b, _] = self.color;
+                scene.add_material_rgb(&material, r, g, b, 0.0, 0.6);
+                material
+            }
+        };
+        if let Some(object) = scene.objects.last_mut() {
+            object.set_material(&material);
+            if let Some(entity) = object.entity {
+                scene.world.insert(entity, Draggable);
+            }
+        }
+        self.drags = Some(scene.events.subscribe::<DragEvent>());
+    }
+
+    fn initialize_gpu(&mut self, device: &Device, _queue: &Queue) {
+        if self.solver == SoftBodySolver::Gpu {
+            self.gpu = Some(GpuPbdSolver::new(device, &self.system));
+            self.gpu_stale = false;
+            self.gpu_dirty = None;
+        }
+    }
+
+    fn update(&mut self, delta_time: f32, scene: &mut Scene) {
+        self.handle_drags(scene);
+        self.apply_params();
+        self.hold_grab();
+
+        // The GPU solver steps in `update_gpu` once it exists
+        if self.running && self.gpu.is_none() {
+            self.cpu.step(&mut self.system, delta_time.min(MAX_STEP));
+            for impact in self.cpu.impacts() {
+                scene.events.emit(*impact);
+            }
+            self.needs_mesh_update = true;
+        }
+        self.update_mesh(scene);
+    }
+
+    fn update_gpu(&mut self, device: &Device, queue: &Queue, delta_time: f32) {
+        if self.gpu.is_none() {
+            return;
+        }
+        if std::mem::take(&mut self.gpu_stale) {
+            self.gpu = Some(GpuPbdSolver::new(device, &self.system));
+            self.gpu_dirty = None;
+        }
+        self.upload_dirty_particle(queue);
+        if !self.running {
+            return;
+        }
+        if let Some(gpu) = &mut self.gpu {
+            gpu.step(device, queue, &self.system, delta_time.min(MAX_STEP));
+            gpu.read_back(device, queue, &mut self.system);
+            self.needs_mesh_update = true;
+        }
+    }
+
+    fn apply_gpu_results_to_scene(&mut self, _device: &Device, scene: &mut Scene) {
+        self.update_mesh(scene);
+    }
+
+    fn render_ui(&mut self, ui: &Ui) {
+        ui.window("Soft Body")
+            .size([340.0, 330.0], imgui::Condition::FirstUseEver)
+            .build(|| {
+                ui.text(format!(
+                    "{} particles, {} tetrahedra, {} surface triangles",
+                    self.system.particle_count(),
+                    self.tetrahedra.len(),
+                    self.triangles.len() / 3
+                ));
+                ui.text(format!(
+                    "Solver: {}",
+                    if self.gpu.is_some() { "GPU" } else { "CPU" }
+                ));
+                match self.grab {
+                    Some(grab) => ui.text(format!("Dragging particle {}", grab.particle)),
+                    None => ui.text_disabled("Drag the body with the left mouse button"),
+                }
+                ui.separator();
+
+                if ui.button(if self.running { "Pause" } else { "Play" }) {
+                    self.running = !self.running;
+                }
+                ui.same_line();
+                if ui.button("Reset") {
+                    self.restart();
+                }
+
+                ui.separator();
+                self.params.build_ui(ui);
+            });
+    }
+
+    fn name(&self) -> &str {
+        "Soft Body"
+    }
+
+    fn is_running(&self) -> bool {
+        self.running
+    }
+
+    fn set_running(&mut self, running: bool) {
+        self.running = running;
+    }
+
+    fn reset(&mut self, _scene: &mut Scene) {
+        self.restart();
+    }
+
+    fn cleanup(&mut self, scene: &mut Scene) {
+        self.drags = None;
+        if let Some(index) = scene
+            .objects
+            .iter()
+            .position(|object| object.name == self.object_name)
+        {
+            scene.remove_object(index);
+        }
+    }
+
+    fn is_gpu_ready(&self) -> bool {
+        self.gpu.is_some()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// Boundary of a tetrahedral mesh
+///
+/// Faces that belong to only one tetrahedron are on the boundary. Returns the
+/// particles on the boundary and triangles indexing into that list, wound
+/// counter-clockwise seen from outside.
+pub fn extract_surface(
+    positions: &[Vector3<f32>],
+    tetrahedra: &[[usize; 4]],
+) -> (Vec<usize>, Vec<u32>) {
+    // Faces by sorted corners: use count, corners and the opposite corner
+    let mut faces: HashMap<[usize; 3], (u32, [usize; 3], usize)> = HashMap::new();
+    let mut order = Vec::new();
+    for &[a, b, c, d] in tetrahedra {
+        for (face, opposite) in [
+            ([a, b, c], d),
+            ([a, b, d], c),
+            ([a, c, d], b),
+            ([b, c, d], a),
+        ] {
+            let mut key = face;
+            key.sort_unstable();
+            let entry = faces.entry(key).or_insert_with(|| {
+                order.push(key);
+                (0, face, opposite)
+            });
+            entry.0 += 1;
+        }
+    }
+
+    let mut surface = Vec::new();
+    let mut vertex_of = HashMap::new();
+    let mut triangles = Vec::new();
+    for key in order {
+        let (count, [a, b, c], opposite) = faces[&key];
+        if count != 1 {
+            continue;
+        }
+        let [pa, pb, pc, po] = [a, b, c, opposite].map(|i| positions[i]);
+        let corners = if (pb - pa).cross(pc - pa).dot(po - pa) > 0.0 {
+            [a, c, b]
+        } else {
+            [a, b, c]
+        };
+        for particle in corners {
+            let vertex = *vertex_of.entry(particle).or_insert_with(|| {
+                surface.push(particle);
+                surface.len() as u32 - 1
+            });
+            triangles.push(vertex);
+        }
+    }
+    (surface, triangles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gfx::camera::{
+        camera_controller::CameraController, camera_utils::CameraManager, orbit_camera::OrbitCamera,
+    };
+    use crate::gfx::picking::Ray;
+    use crate::simulation::pbd::tetrahedron_volume;
+
+    #[test]
+    fn test_lattice_tetrahedra_and_surface() {
+        let body = SoftBody::new([1.0, 2.0, 1.0], [2, 2, 2]);
+        assert_eq!(body.rest.len(), 27);
+        assert_eq!(body.tetrahedra.len(), 8 * 6);
+        let volume: f32 = body
+            .tetrahedra
+            .iter()
+            .map(|t| tetrahedron_volume(&t.map(|i| body.rest[i])).abs())
+            .sum();
+        assert!((volume - 2.0).abs() < 1e-5);
+
+        // Every lattice point but the center is on the surface, with two
+        // triangles per boundary square, all facing outwards
+        assert_eq!(body.surface.len(), 26);
+        assert_eq!(body.triangles.len() / 3, 6 * 4 * 2);
+        for triangle in body.triangles.chunks(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| body.rest[body.surface[triangle[k] as usize]]);
+            assert!((b - a).cross(c - a).dot(a + b + c) > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_dragging_pulls_the_body() {
+        let camera = OrbitCamera::new(8.0, 0.4, 0.2, Vector3::new(0.0, 0.0, 0.0), 1.0);
+        let mut scene = Scene::new(CameraManager::new(
+            camera,
+            CameraController::new(0.005, 0.1),
+        ));
+        let mut body = SoftBody::new([1.0, 1.0, 1.0], [2, 2, 2])
+            .at([0.0, 0.0, 2.0])
+            .with_params(SoftBodyParams {
+                gravity: 0.0,
+                ..SoftBodyParams::default()
+            });
+        body.initialize(&mut scene);
+        let object_index = scene.objects.len() - 1;
+        let entity = scene.objects[object_index].entity.unwrap();
+        assert!(scene.world.has::<Draggable>(entity));
+
+        // Grab a top corner from above and pull it sideways by half a unit
+        let corner = Vector3::new(0.5, 0.5, 2.5);
+        let ray = |x: f32| Ray::new(Vector3::new(x, 0.5, 5.0), Vector3::new(0.0, 0.0, -1.0));
+        let drag = |phase, ray| DragEvent {
+            object_index,
+            phase,
+            ray,
+            point: corner,
+        };
+        scene.events.emit(drag(DragPhase::Begin, ray(0.5)));
+        scene.events.emit(drag(DragPhase::Move, ray(1.0)));
+        for _ in 0..30 {
+            body.update(1.0 / 60.0, &mut scene);
+        }
+        let particle = body.grabbed_particle().expect("grabbed");
+        assert_eq!(body.system.positions[particle], Vector3::new(1.0, 0.5, 2.5));
+        let center = body.system.positions.iter().sum::<Vector3<f32>>() / 27.0;
+        assert!(center.x > 0.1, "body stayed at {center:?}");
+
+        scene.events.emit(drag(DragPhase::End, ray(1.0)));
+        body.update(1.0 / 60.0, &mut scene);
+        assert!(body.grabbed_particle().is_none());
+        assert!(body.system.inverse_masses[particle] > 0.0);
+    }
+}
@@ -0,0 +1,560 @@
+//! Passive dye advected by a flow
+//!
+//! Vorticity shows where a flow rotates, but dye shows where it goes. A
+//! [`DyeField`] carries a scalar dye concentration along any velocity buffer
+//! a flow solver already has on the GPU, with a semi-Lagrangian step: each
+//! cell traces its velocity back over the time step and takes the dye found
+//! there, interpolated trilinearly. This is unconditionally stable and never
+//! creates new extrema, so the dye stays between zero and the injected
+//! amounts. [`DyeSource`]s add dye every step, and the field brings a
+//! [`CutPlane2D`] showing one layer of it.
+//!
+//! The velocity buffer is described by a [`VelocityField`]: `stride` floats
+//! per cell with the velocity in the first two or three, like the `[vx, vy,
+//! vz, density]` layout of an LBM solver, in grid cells per time unit. Buffers
+//! registered in the [`BufferRegistry`] can be found by name with
+//! [`VelocityField::published`]. The dye buffer holds one `f32` per cell, so
+//! it can also be painted into with a
+//! [`FieldPainter`](crate::simulation::paint::FieldPainter).
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::simulation::tracer::{DyeField, DyeSource, VelocityField};
+//! use haggis::visualization::GridTransform;
+//!
+//! # fn example(device: &wgpu::Device, queue: &wgpu::Queue) {
+//! let grid = GridTransform::centered([128, 64, 64], 1.0);
+//! let velocity = VelocityField::published("LBM Velocity/Density", 4).expect("LBM running");
+//! let mut dye = DyeField::new(device, grid, velocity)
+//!     .with_source(DyeSource::new([4.0, 32.0, 32.0], 3.0, 0.2));
+//! let plane = dye.visualization();
+//!
+//! // After each solver step, in lattice units of one cell per step
+//! let mut encoder = device.create_command_encoder(&Default::default());
+//! dye.encode(queue, &mut encoder, 1.0);
+//! queue.submit(std::iter::once(encoder.finish()));
+//! # }
+//! ```
+
+use std::sync::Arc;
+
+use wgpu::{Buffer, CommandEncoder, Device, Queue};
+
+use crate::visualization::cut_plane_2d::{BufferElementType, BufferFormat};
+use crate::visualization::{CutPlane2D, GridTransform};
+use crate::wgpu_utils::BufferRegistry;
+
+/// Most dye sources a field injects from
+pub const MAX_SOURCES: usize = 16;
+
+const WORKGROUP_SIZE: u32 = 4;
+
+/// A velocity buffer the dye is carried along
+#[derive(Debug, Clone)]
+pub struct VelocityField {
+    buffer: Buffer,
+    /// Floats per cell; the velocity is in the first two or three
+    stride: u32,
+    /// Grid cells per time unit per unit of velocity
+    scale: f32,
+}
+
+impl VelocityField {
+    /// Velocity in the first two (`stride` 2) or three floats of each cell,
+    /// in grid cells per time unit
+    ///
+    /// The buffer needs `STORAGE` usage.
+    pub fn new(buffer: &Buffer, stride: u32) -> Self {
+        Self {
+            buffer: buffer.clone(),
+            stride: stride.max(2),
+            scale: 1.0,
+        }
+    }
+
+    /// Velocity buffer registered in the global [`BufferRegistry`] as `name`
+    pub fn published(name: &str, stride: u32) -> Option<Self> {
+        let registered = BufferRegistry::global().get(name)?;
+        Some(Self::new(&registered.buffer, stride))
+    }
+
+    /// Convert velocities in other units, e.g. `1 / dx` for world units
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn stride(&self) -> u32 {
+        self.stride
+    }
+}
+
+/// Spot that adds dye every step
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DyeSource {
+    /// Center in continuous grid coordinates
+    pub position: [f32; 3],
+    /// Radius in grid cells
+    pub radius: f32,
+    /// Dye added at the center per step, fading to zero at the radius
+    pub rate: f32,
+}
+
+impl DyeSource {
+    pub fn new(position: [f32; 3], radius: f32, rate: f32) -> Self {
+        Self {
+            position,
+            radius,
+            rate,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct DyeUniforms {
+    /// Grid size, then velocity stride
+    dims: [u32; 4],
+    /// Source count
+    counts: [u32; 4],
+    /// Cells moved per unit of velocity, fraction kept, most dye per cell
+    params: [f32; 4],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct GpuDyeSource {
+    /// Position, then radius
+    position: [f32; 4],
+    /// Rate
+    rate: [f32; 4],
+}
+
+/// Scalar dye carried by a velocity buffer on the GPU
+pub struct DyeField {
+    grid: GridTransform,
+    velocity: VelocityField,
+    sources: Vec<DyeSource>,
+    /// Fraction of the dye lost per time unit
+    pub decay: f32,
+    /// Most dye a cell holds
+    pub max_density: f32,
+    /// Layer shown by the visualization, 0 to 1 along Z
+    slice: f32,
+    pipeline: wgpu::ComputePipeline,
+    layout: wgpu::BindGroupLayout,
+    uniforms: Buffer,
+    source_buffer: Buffer,
+    dye_a: Buffer,
+    dye_b: Buffer,
+    /// Copy of the visualized layer
+    slice_buffer: Buffer,
+    /// Bind groups advecting A into B and B into A
+    bind_groups: [wgpu::BindGroup; 2],
+    /// Whether B holds the current dye
+    flipped: bool,
+    steps: u64,
+}
+
+impl DyeField {
+    /// Dye on `grid`, initially empty, carried by `velocity`
+    pub fn new(device: &Device, grid: GridTransform, velocity: VelocityField) -> Self {
+        let [width, height, depth] = grid.dims();
+        let cells = width as u64 * height as u64 * depth as u64;
+        let field_buffer = |label| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: cells * 4,
+                usage: wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_SRC
+                    | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        };
+        let dye_a = field_buffer("Dye A");
+        let dye_b = field_buffer("Dye B");
+        let slice_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Dye Slice"),
+            size: width as u64 * height as u64 * 4,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let uniforms = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Dye Uniforms"),
+            size: std::mem::size_of::<DyeUniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let source_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Dye Sources"),
+            size: (MAX_SOURCES * std::mem::size_of::<GpuDyeSource>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let entry = |binding, ty| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let read_only = wgpu::BufferBindingType::Storage { read_only: true };
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Dye Advection Layout"),
+            entries: &[
+                entry(0, wgpu::BufferBindingType::Uniform),
+                entry(1, read_only),
+                entry(2, read_only),
+                entry(3, wgpu::BufferBindingType::Storage { read_only: false }),
+                entry(4, read_only),
+            ],
+        });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Dye Advection Shader"),
+            source: wgpu::ShaderSource::Wgsl(DYE_SHADER.into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Dye Advection Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Dye Advection Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: Some("advect"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let bind_groups = Self::bind_groups(
+            device,
+            &layout,
+            &uniforms,
+            &velocity,
+            [&dye_a, &dye_b],
+            &source_buffer,
+        );
+        Self {
+            grid,
+            velocity,
+            sources: Vec::new(),
+            decay: 0.0,
+            max_density: 1.0,
+            slice: 0.5,
+            pipeline,
+            layout,
+            uniforms,
+            source_buffer,
+            dye_a,
+            dye_b,
+            slice_buffer,
+            bind_groups,
+            flipped: false,
+            steps: 0,
+        }
+    }
+
+    fn bind_groups(
+        device: &Device,
+        layout: &wgpu::BindGroupLayout,
+        uniforms: &Buffer,
+        velocity: &VelocityField,
+        [dye_a, dye_b]: [&Buffer; 2],
+        sources: &Buffer,
+    ) -> [wgpu::BindGroup; 2] {
+        [(dye_a, dye_b), (dye_b, dye_a)].map(|(input, output)| {
+            let buffers = [uniforms, &velocity.buffer, input, output, sources];
+            let entries: Vec<wgpu::BindGroupEntry> = buffers
+                .iter()
+                .enumerate()
+                .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                    binding: binding as u32,
+                    resource: buffer.as_entire_binding(),
+                })
+                .collect();
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Dye Advection Bind Group"),
+                layout,
+                entries: &entries,
+            })
+        })
+    }
+
+    pub fn with_source(mut self, source: DyeSource) -> Self {
+        self.add_source(source);
+        self
+    }
+
+    pub fn with_decay(mut self, decay: f32) -> Self {
+        self.decay = decay;
+        self
+    }
+
+    /// Add a source; sources beyond [`MAX_SOURCES`] are ignored
+    pub fn add_source(&mut self, source: DyeSource) {
+        if self.sources.len() < MAX_SOURCES {
+            self.sources.push(source);
+        }
+    }
+
+    pub fn clear_sources(&mut self) {
+        self.sources.clear();
+    }
+
+    pub fn sources(&self) -> &[DyeSource] {
+        &self.sources
+    }
+
+    /// Carry the dye along a different velocity buffer
+    pub fn set_velocity(&mut self, device: &Device, velocity: VelocityField) {
+        self.bind_groups = Self::bind_groups(
+            device,
+            &self.layout,
+            &self.uniforms,
+            &velocity,
+            [&self.dye_a, &self.dye_b],
+            &self.source_buffer,
+        );
+        self.velocity = velocity;
+    }
+
+    pub fn grid(&self) -> &GridTransform {
+        &self.grid
+    }
+
+    /// Steps advected so far
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Buffer holding the current dye, one `f32` per cell
+    pub fn dye_buffer(&self) -> &Buffer {
+        if self.flipped {
+            &self.dye_b
+        } else {
+            &self.dye_a
+        }
+    }
+
+    /// Replace the dye with one value per cell, x fastest
+    pub fn upload(&self, queue: &Queue, dye: &[f32]) {
+        queue.write_buffer(self.dye_buffer(), 0, bytemuck::cast_slice(dye));
+    }
+
+    /// Remove all dye
+    pub fn clear(&mut self, queue: &Queue) {
+        let zeros = vec![0u8; self.dye_a.size() as usize];
+        queue.write_buffer(&self.dye_a, 0, &zeros);
+        queue.write_buffer(&self.dye_b, 0, &zeros);
+        queue.write_buffer(
+            &self.slice_buffer,
+            0,
+            &zeros[..self.slice_buffer.size() as usize],
+        );
+    }
+
+    /// Layer shown by the visualization, 0 to 1 along Z
+    pub fn slice(&self) -> f32 {
+        self.slice
+    }
+
+    /// Show the layer nearest to `normalized` along Z from the next step on
+    ///
+    /// Move the plane from [`visualization`](Self::visualization) along with
+    /// it, with [`CutPlane2D::set_grid_slice`].
+    pub fn set_slice(&mut self, normalized: f32) {
+        self.slice = normalized.clamp(0.0, 1.0);
+    }
+
+    /// Heatmap of the visualized layer, placed on the grid
+    pub fn visualization(&self) -> CutPlane2D {
+        let [width, height, _] = self.grid.dims();
+        let mut plane = CutPlane2D::new();
+        plane.set_grid_slice(&self.grid, self.slice);
+        plane.update_gpu_buffer(
+            Arc::new(self.slice_buffer.clone()),
+            BufferFormat {
+                element_type: BufferElementType::F32,
+                width,
+                height,
+            },
+        );
+        plane.set_value_range(0.0, self.max_density);
+        plane
+    }
+
+    /// Record one advection and injection step of `dt` time units
+    ///
+    /// Uniforms and sources are written through the queue, so steps recorded
+    /// into the same submission share them.
+    pub fn encode(&mut self, queue: &Queue, encoder: &mut CommandEncoder, dt: f32) {
+        let [width, height, depth] = self.grid.dims();
+        let uniforms = DyeUniforms {
+            dims: [width, height, depth, self.velocity.stride],
+            counts: [self.sources.len() as u32, 0, 0, 0],
+            params: [
+                dt * self.velocity.scale,
+                (1.0 - self.decay * dt).clamp(0.0, 1.0),
+                self.max_density,
+                0.0,
+            ],
+        };
+        queue.write_buffer(&self.uniforms, 0, bytemuck::bytes_of(&uniforms));
+        let sources: Vec<GpuDyeSource> = self
+            .sources
+            .iter()
+            .map(|source| GpuDyeSource {
+                position: [
+                    source.position[0],
+                    source.position[1],
+                    source.position[2],
+                    source.radius.max(0.5),
+                ],
+                rate: [source.rate, 0.0, 0.0, 0.0],
+            })
+            .collect();
+        if !sources.is_empty() {
+            queue.write_buffer(&self.source_buffer, 0, bytemuck::cast_slice(&sources));
+        }
+
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Dye Advection"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &self.bind_groups[self.flipped as usize], &[]);
+            pass.dispatch_workgroups(
+                width.div_ceil(WORKGROUP_SIZE),
+                height.div_ceil(WORKGROUP_SIZE),
+                depth.div_ceil(WORKGROUP_SIZE),
+            );
+        }
+        self.flipped = !self.flipped;
+        self.steps += 1;
+
+        // Layers are contiguous, so the visualized one is a single copy
+        let layer_size = width as u64 * height as u64 * 4;
+        let layer = self.grid.layer(2, self.slice) as u64;
+        encoder.copy_buffer_to_buffer(
+            self.dye_buffer(),
+            layer * layer_size,
+            &self.slice_buffer,
+            0,
+            layer_size,
+        );
+    }
+
+    /// Controls for decay and the strength of the sources
+    pub fn render_ui(&mut self, ui: &imgui::Ui) {
+        ui.slider("Dye Decay", 0.0, 0.1, &mut self.decay);
+        for (index, source) in self.sources.iter_mut().enumerate() {
+            ui.slider(format!("Source {index} Rate"), 0.0, 1.0, &mut source.rate);
+        }
+    }
+}
+
+const DYE_SHADER: &str = r#"
+struct DyeUniforms {
+    dims: vec4<u32>,
+    counts: vec4<u32>,
+    params: vec4<f32>,
+}
+
+struct DyeSource {
+    position: vec4<f32>,
+    rate: vec4<f32>,
+}
+
+@group(0) @binding(0) var<uniform> uniforms: DyeUniforms;
+@group(0) @binding(1) var<storage, read> velocity: array<f32>;
+@group(0) @binding(2) var<storage, read> dye_in: array<f32>;
+@group(0) @binding(3) var<storage, read_write> dye_out: array<f32>;
+@group(0) @binding(4) var<storage, read> sources: array<DyeSource>;
+
+fn cell_index(cell: vec3<u32>) -> u32 {
+    return (cell.z * uniforms.dims.y + cell.y) * uniforms.dims.x + cell.x;
+}
+
+fn load(cell: vec3<i32>) -> f32 {
+    let last = vec3<i32>(uniforms.dims.xyz) - vec3<i32>(1);
+    return dye_in[cell_index(vec3<u32>(clamp(cell, vec3<i32>(0), last)))];
+}
+
+// Trilinear interpolation, clamped to the grid
+fn sample(position: vec3<f32>) -> f32 {
+    let base = floor(position);
+    let f = position - base;
+    let b = vec3<i32>(base);
+    let x00 = mix(load(b), load(b + vec3<i32>(1, 0, 0)), f.x);
+    let x10 = mix(load(b + vec3<i32>(0, 1, 0)), load(b + vec3<i32>(1, 1, 0)), f.x);
+    let x01 = mix(load(b + vec3<i32>(0, 0, 1)), load(b + vec3<i32>(1, 0, 1)), f.x);
+    let x11 = mix(load(b + vec3<i32>(0, 1, 1)), load(b + vec3<i32>(1, 1, 1)), f.x);
+    return mix(mix(x00, x10, f.y), mix(x01, x11, f.y), f.z);
+}
+
+@compute @workgroup_size(4, 4, 4)
+fn advect(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (any(id >= uniforms.dims.xyz)) {
+        return;
+    }
+    let index = cell_index(id);
+    let stride = uniforms.dims.w;
+    var v = vec3<f32>(velocity[index * stride], velocity[index * stride + 1u], 0.0);
+    if (stride >= 3u) {
+        v.z = velocity[index * stride + 2u];
+    }
+
+    // Take the dye from where this cell's flow came from
+    let position = vec3<f32>(id);
+    var dye = sample(position - v * uniforms.params.x) * uniforms.params.y;
+
+    for (var i = 0u; i < uniforms.counts.x; i++) {
+        let source = sources[i];
+        let d = distance(position, source.position.xyz);
+        dye += source.rate.x * (1.0 - smoothstep(0.0, source.position.w, d));
+    }
+    dye_out[index] = clamp(dye, 0.0, uniforms.params.z);
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wgpu_utils::compute_primitives::{read_buffer, test_buffer, test_device};
+
+    #[test]
+    fn test_dye_follows_velocity() {
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        // One cell per time unit along +X
+        let velocity = test_buffer(&device, &[[1.0f32, 0.0]; 64]);
+        let grid = GridTransform::new([8, 8, 1], [0.0, 0.0, 0.0], [7.0, 7.0, 0.0]);
+        let mut dye = DyeField::new(&device, grid, VelocityField::new(&velocity, 2))
+            .with_source(DyeSource::new([6.0, 6.0, 0.0], 0.5, 0.25));
+        let mut initial = vec![0.0f32; 64];
+        initial[3 * 8 + 2] = 1.0;
+        dye.upload(&queue, &initial);
+
+        let mut encoder = device.create_command_encoder(&Default::default());
+        dye.encode(&queue, &mut encoder, 1.0);
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let values: Vec<f32> = read_buffer(&device, &queue, dye.dye_buffer(), 64).unwrap();
+        assert_eq!(values[3 * 8 + 3], 1.0);
+        assert_eq!(values[3 * 8 + 2], 0.0);
+        assert_eq!(values[6 * 8 + 6], 0.25);
+        assert_eq!(values.iter().sum::<f32>(), 1.25);
+        let slice: Vec<f32> = read_buffer(&device, &queue, &dye.slice_buffer, 64).unwrap();
+        assert_eq!(slice, values);
+    }
+}
@@ -4,6 +4,10 @@
 //! to integrate with the Haggis simulation system.
 
 use crate::gfx::scene::Scene;
+use crate::simulation::checkpoint::Checkpoint;
+use crate::simulation::gpu::ComputeDevice;
+use crate::simulation::params::SimParams;
+use crate::simulation::progress::SimulationProgress;
 use imgui::Ui;
 use std::any::Any;
 use wgpu::{Device, Queue};
@@ -243,6 +247,27 @@ pub trait Simulation {
         // Default: no GPU results to apply
     }
 
+    /// Copy GPU results needed for rendering from the compute device.
+    ///
+    /// Only called when simulations run on a separate compute adapter (see
+    /// [`ComputeDevice`]); otherwise buffers can be bound directly. Override
+    /// this to refresh [`SharedBuffer`](crate::simulation::gpu::SharedBuffer)s
+    /// that visualizations draw from.
+    ///
+    /// # Arguments
+    ///
+    /// * `_compute` - Device and queue the simulation's buffers live on
+    /// * `_display_device` - Device used for rendering
+    /// * `_display_queue` - Queue used for rendering
+    fn sync_to_display(
+        &mut self,
+        _compute: &ComputeDevice,
+        _display_device: &Device,
+        _display_queue: &Queue,
+    ) {
+        // Default: renders straight from its own buffers
+    }
+
     /// Check if GPU resources are ready for use.
     ///
     /// # Returns
@@ -252,6 +277,80 @@ pub trait Simulation {
         false // Default: not GPU-ready
     }
 
+    /// Handle a console or script command not claimed by a registered command.
+    ///
+    /// Override this to add simulation-specific commands such as
+    /// `export field vorticity frame_%04d.vtk`.
+    ///
+    /// # Arguments
+    ///
+    /// * `_args` - Words of the command line, `_args[0]` is the command name
+    /// * `_scene` - Mutable reference to the scene
+    ///
+    /// # Returns
+    ///
+    /// `None` if the command is not recognized, otherwise its output or error
+    fn handle_command(&mut self, _args: &[&str], _scene: &mut Scene) -> Option<Result<String, String>> {
+        None
+    }
+
+    /// Parameters that tools outside the simulation may read and set by name
+    ///
+    /// Simulations with `#[derive(SimParams)]` parameters can return them
+    /// here to make them available to remote control. The default exposes none.
+    fn params_mut(&mut self) -> Option<&mut dyn SimParams> {
+        None
+    }
+
+    /// Progress of a run with a known end, such as a bake or a sweep
+    ///
+    /// Simulations implementing [`SimulationProgress`] can return `Some(self)`
+    /// to get a progress window with an estimated time left. The default
+    /// reports none, for simulations that run indefinitely.
+    fn progress(&self) -> Option<&dyn SimulationProgress> {
+        None
+    }
+
+    /// Check the simulation state for divergence.
+    ///
+    /// Called by the manager according to its
+    /// [`StabilityPolicy`](crate::simulation::stability::StabilityPolicy).
+    /// Override this to inspect fields the simulation already has on the CPU,
+    /// e.g. with [`check_field`](crate::simulation::stability::check_field).
+    ///
+    /// # Returns
+    ///
+    /// `None` while the state is stable, otherwise what went wrong
+    fn check_stability(&self) -> Option<String> {
+        None
+    }
+
+    /// Parameter changes that may keep the simulation stable.
+    ///
+    /// Shown with a failed stability check, e.g. from
+    /// [`suggest_tau`](crate::simulation::stability::suggest_tau).
+    fn stability_suggestions(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Save the state the manager rolls back to after a divergence.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the simulation doesn't support rollback (the default)
+    fn save_checkpoint(&self, _step: u64, _time: f64) -> Option<Checkpoint> {
+        None
+    }
+
+    /// Restore a state saved by [`save_checkpoint`](Self::save_checkpoint).
+    ///
+    /// # Returns
+    ///
+    /// `true` if the state was restored
+    fn restore_checkpoint(&mut self, _checkpoint: &Checkpoint, _scene: &mut Scene) -> bool {
+        false
+    }
+
     /// Support for downcasting to concrete types
     fn as_any(&self) -> &dyn Any;
 }
@@ -0,0 +1,345 @@
+//! # Telemetry Export
+//!
+//! Pushes named simulation scalars to external monitoring tools during long
+//! runs, e.g. total energy, live cell count or Reynolds number plotted in a
+//! Grafana dashboard. Scalars are the latest
+//! [`MetricSample`](crate::events::MetricSample) events emitted by
+//! simulations, plus the frame rate and simulation clock.
+//!
+//! Two transports are available, and one exporter can use both:
+//!
+//! - **OSC over UDP**: every interval, one `/<prefix>/<name>` message with a
+//!   float argument per metric. Suits TouchOSC, Max/MSP, Pure Data and the
+//!   Grafana Live OSC plugins.
+//! - **Prometheus**: an HTTP `/metrics` endpoint in the text exposition
+//!   format, with one gauge per metric, for Prometheus to scrape.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::events::MetricSample;
+//! use haggis::telemetry::TelemetryExporter;
+//!
+//! let mut app = haggis::default();
+//! let mut telemetry = TelemetryExporter::new("haggis");
+//! telemetry.send_osc("192.168.1.20:9000".parse().unwrap()).expect("OSC socket");
+//! telemetry.serve_prometheus("0.0.0.0:9184".parse().unwrap()).expect("metrics endpoint");
+//! app.enable_telemetry(telemetry);
+//!
+//! // In a simulation's update:
+//! # let scene: &mut haggis::gfx::scene::Scene = todo!();
+//! scene.events.emit(MetricSample::new("total_energy", 1.25));
+//! ```
+
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::error::{HaggisError, Result};
+use crate::platform::Instant;
+
+/// Metrics exporter over OSC and Prometheus
+pub struct TelemetryExporter {
+    prefix: String,
+    /// Time between OSC pushes
+    pub interval: Duration,
+    osc: Vec<(UdpSocket, SocketAddr)>,
+    prometheus: Option<PrometheusEndpoint>,
+    last_push: Option<Instant>,
+}
+
+impl TelemetryExporter {
+    /// Exporter naming metrics `<prefix>/<name>` (OSC) or `<prefix>_<name>`
+    /// (Prometheus), pushing once per second
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            interval: Duration::from_secs(1),
+            osc: Vec::new(),
+            prometheus: None,
+            last_push: None,
+        }
+    }
+
+    /// Set the time between OSC pushes
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Push OSC messages to `target` over UDP
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::Telemetry`] if no UDP socket can be opened.
+    pub fn send_osc(&mut self, target: SocketAddr) -> Result<()> {
+        let local: SocketAddr = if target.is_ipv4() {
+            "0.0.0.0:0".parse().unwrap()
+        } else {
+            "[::]:0".parse().unwrap()
+        };
+        let socket = UdpSocket::bind(local).map_err(|error| telemetry_error(target, error))?;
+        self.osc.push((socket, target));
+        Ok(())
+    }
+
+    /// Serve the metrics for Prometheus at `http://<address>/metrics`
+    ///
+    /// # Returns
+    ///
+    /// The address the endpoint is bound to
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::Telemetry`] if the address cannot be bound.
+    pub fn serve_prometheus(&mut self, address: SocketAddr) -> Result<SocketAddr> {
+        let endpoint = PrometheusEndpoint::start(address)?;
+        let bound = endpoint.address;
+        tracing::info!("Serving Prometheus metrics on http://{}/metrics", bound);
+        self.prometheus = Some(endpoint);
+        Ok(bound)
+    }
+
+    /// Export the current metric values
+    ///
+    /// Called every frame by the app; the Prometheus endpoint always serves
+    /// the latest values, OSC messages are sent once per interval.
+    pub fn update(&mut self, metrics: &BTreeMap<String, f64>) {
+        if let Some(prometheus) = &self.prometheus {
+            *prometheus.text.lock().unwrap() = prometheus_text(&self.prefix, metrics);
+        }
+
+        if self.osc.is_empty()
+            || self
+                .last_push
+                .is_some_and(|last| last.elapsed() < self.interval)
+        {
+            return;
+        }
+        self.last_push = Some(Instant::now());
+
+        for (name, value) in metrics {
+            let message = osc_message(
+                &format!("/{}/{}", self.prefix, osc_name(name)),
+                *value as f32,
+            );
+            for (socket, target) in &self.osc {
+                if let Err(error) = socket.send_to(&message, target) {
+                    tracing::debug!("Failed to send OSC metric to {}: {}", target, error);
+                }
+            }
+        }
+    }
+}
+
+fn telemetry_error(address: SocketAddr, error: std::io::Error) -> HaggisError {
+    HaggisError::Telemetry {
+        address: address.to_string(),
+        message: error.to_string(),
+    }
+}
+
+/// OSC message with one float argument
+fn osc_message(address: &str, value: f32) -> Vec<u8> {
+    let mut message = Vec::with_capacity(address.len() + 12);
+    push_osc_string(&mut message, address);
+    push_osc_string(&mut message, ",f");
+    message.extend_from_slice(&value.to_be_bytes());
+    message
+}
+
+/// Null-terminated string padded to a multiple of 4 bytes
+fn push_osc_string(message: &mut Vec<u8>, text: &str) {
+    message.extend_from_slice(text.as_bytes());
+    let padding = 4 - text.len() % 4;
+    message.extend(std::iter::repeat_n(0, padding));
+}
+
+/// OSC address part for a metric name, without spaces or pattern characters
+fn osc_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            ' ' | '#' | '*' | ',' | '?' | '[' | ']' | '{' | '}' => '_',
+            c => c,
+        })
+        .collect()
+}
+
+/// Prometheus metric name: letters, digits and underscores
+fn prometheus_name(prefix: &str, name: &str) -> String {
+    let name: String = format!("{}_{}", prefix, name)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", name)
+    } else {
+        name
+    }
+}
+
+/// Metrics in the Prometheus text exposition format
+fn prometheus_text(prefix: &str, metrics: &BTreeMap<String, f64>) -> String {
+    let mut text = String::new();
+    for (name, value) in metrics {
+        let metric = prometheus_name(prefix, name);
+        let value = match *value {
+            v if v.is_nan() => "NaN".to_string(),
+            v if v.is_infinite() => if v > 0.0 { "+Inf" } else { "-Inf" }.to_string(),
+            v => v.to_string(),
+        };
+        text.push_str(&format!("# TYPE {metric} gauge\n{metric} {value}\n"));
+    }
+    text
+}
+
+/// Background HTTP endpoint serving the latest Prometheus text
+struct PrometheusEndpoint {
+    address: SocketAddr,
+    text: Arc<Mutex<String>>,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl PrometheusEndpoint {
+    fn start(address: SocketAddr) -> Result<Self> {
+        let listener =
+            TcpListener::bind(address).map_err(|error| telemetry_error(address, error))?;
+        let bound = listener
+            .local_addr()
+            .map_err(|error| telemetry_error(address, error))?;
+        // Polled so the thread notices shutdown
+        listener
+            .set_nonblocking(true)
+            .map_err(|error| telemetry_error(address, error))?;
+
+        let text = Arc::new(Mutex::new(String::new()));
+        let shutdown = Arc::new(AtomicBool::new(false));
+        {
+            let text = text.clone();
+            let shutdown = shutdown.clone();
+            thread::Builder::new()
+                .name("haggis-prometheus".to_string())
+                .spawn(move || {
+                    while !shutdown.load(Ordering::Relaxed) {
+                        match listener.accept() {
+                            Ok((stream, _)) => {
+                                let _ = serve_scrape(stream, &text);
+                            }
+                            Err(error) if error.kind() == std::io::ErrorKind::WouldBlock => {
+                                thread::sleep(Duration::from_millis(50));
+                            }
+                            Err(error) => {
+                                tracing::warn!("Prometheus endpoint stopped: {}", error);
+                                return;
+                            }
+                        }
+                    }
+                })
+                .map_err(|error| telemetry_error(address, error))?;
+        }
+
+        Ok(Self {
+            address: bound,
+            text,
+            shutdown,
+        })
+    }
+}
+
+impl Drop for PrometheusEndpoint {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Answer one scrape request
+fn serve_scrape(stream: TcpStream, text: &Mutex<String>) -> std::io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Skip the headers
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+
+    let path = request_line.split_whitespace().nth(1).unwrap_or("");
+    let (status, body) = if path == "/metrics" || path.starts_with("/metrics?") {
+        ("200 OK", text.lock().unwrap().clone())
+    } else {
+        (
+            "404 Not Found",
+            "metrics are served at /metrics\n".to_string(),
+        )
+    };
+    write!(
+        writer,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_osc_encoding() {
+        let message = osc_message("/sim/live cells", 2.0);
+        assert_eq!(&message[..16], b"/sim/live cells\0");
+        assert_eq!(&message[16..20], b",f\0\0");
+        assert_eq!(&message[20..], &2.0f32.to_be_bytes());
+        assert_eq!(osc_message("/abc", 0.0).len(), 16);
+        assert_eq!(osc_name("live cells[0]"), "live_cells_0_");
+    }
+
+    #[test]
+    fn test_prometheus_endpoint() {
+        let mut exporter = TelemetryExporter::new("haggis");
+        let address = exporter
+            .serve_prometheus("127.0.0.1:0".parse().unwrap())
+            .unwrap();
+        exporter.update(&BTreeMap::from([
+            ("Reynolds number".to_string(), 120.5),
+            ("energy".to_string(), f64::NAN),
+        ]));
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: x\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.ends_with(
+            "# TYPE haggis_Reynolds_number gauge\nhaggis_Reynolds_number 120.5\n# TYPE haggis_energy gauge\nhaggis_energy NaN\n"
+        ));
+    }
+
+    #[test]
+    fn test_osc_push() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        receiver
+            .set_read_timeout(Some(Duration::from_secs(2)))
+            .unwrap();
+        let mut exporter = TelemetryExporter::new("sim");
+        exporter.send_osc(receiver.local_addr().unwrap()).unwrap();
+        exporter.update(&BTreeMap::from([("fps".to_string(), 60.0)]));
+
+        let mut buffer = [0; 64];
+        let length = receiver.recv(&mut buffer).unwrap();
+        assert_eq!(&buffer[..length], osc_message("/sim/fps", 60.0).as_slice());
+    }
+}
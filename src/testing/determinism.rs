@@ -0,0 +1,294 @@
+//! Deterministic simulation runs
+//!
+//! [`SimulationRun`] steps a simulation headlessly with a fixed time step and
+//! hashes its fields, on the CPU or read back from GPU buffers. The hashes
+//! taken every few steps form [`StepHashes`], which can be compared between
+//! two runs to check that a simulation is reproducible, or stored in a file
+//! to lock in its behavior across refactors.
+//!
+//! Hashes are exact, so stored ones only hold on the same GPU and driver;
+//! [`hash_f32`] can drop low mantissa bits to tolerate rounding noise.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::simulation::sweep::headless_gpu;
+//! use haggis::simulation::templates::HeatDiffusion2D;
+//! use haggis::testing::SimulationRun;
+//!
+//! # fn main() -> haggis::Result<()> {
+//! let (device, queue) = headless_gpu()?;
+//! let mut run = SimulationRun::new(HeatDiffusion2D::new(64, 64)).with_gpu(device, queue);
+//! let hashes = run.record(100, 25, |run| {
+//!     run.hash_buffer(run.simulation().temperature_buffer().unwrap())
+//! })?;
+//! hashes.check("tests/hashes/heat_64.txt")?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::path::Path;
+
+use cgmath::Vector3;
+use wgpu::{Buffer, Device, Queue};
+
+use super::golden::UPDATE_ENV;
+use crate::error::{HaggisError, Result};
+use crate::gfx::camera::{
+    camera_controller::CameraController, camera_utils::CameraManager, orbit_camera::OrbitCamera,
+};
+use crate::gfx::scene::Scene;
+use crate::simulation::traits::Simulation;
+use crate::wgpu_utils::compute_primitives::read_buffer;
+
+/// A simulation stepped with a fixed time step, without a window
+pub struct SimulationRun<S: Simulation> {
+    simulation: S,
+    scene: Scene,
+    gpu: Option<(Device, Queue)>,
+    dt: f32,
+    steps: u64,
+}
+
+impl<S: Simulation> SimulationRun<S> {
+    /// Initialize `simulation` in an empty scene and start it
+    pub fn new(mut simulation: S) -> Self {
+        let camera = OrbitCamera::new(8.0, 0.4, 0.2, Vector3::new(0.0, 0.0, 0.0), 1.0);
+        let mut scene = Scene::new(CameraManager::new(
+            camera,
+            CameraController::new(0.005, 0.1),
+        ));
+        simulation.initialize(&mut scene);
+        simulation.set_running(true);
+        Self {
+            simulation,
+            scene,
+            gpu: None,
+            dt: 1.0 / 60.0,
+            steps: 0,
+        }
+    }
+
+    /// Initialize and run the GPU side of the simulation on this device
+    pub fn with_gpu(mut self, device: Device, queue: Queue) -> Self {
+        self.simulation.initialize_gpu(&device, &queue);
+        self.gpu = Some((device, queue));
+        self
+    }
+
+    /// Time step passed to every update (1/60 s by default)
+    pub fn with_dt(mut self, dt: f32) -> Self {
+        self.dt = dt;
+        self
+    }
+
+    pub fn simulation(&self) -> &S {
+        &self.simulation
+    }
+
+    pub fn simulation_mut(&mut self) -> &mut S {
+        &mut self.simulation
+    }
+
+    pub fn scene(&self) -> &Scene {
+        &self.scene
+    }
+
+    /// Number of updates run so far
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Run `count` CPU and, with a device, GPU updates
+    pub fn step(&mut self, count: u64) {
+        for _ in 0..count {
+            self.simulation.update(self.dt, &mut self.scene);
+            if let Some((device, queue)) = &self.gpu {
+                self.simulation.update_gpu(device, queue, self.dt);
+                self.simulation
+                    .apply_gpu_results_to_scene(device, &mut self.scene);
+            }
+            self.steps += 1;
+        }
+    }
+
+    /// Hash the whole contents of a GPU buffer with `COPY_SRC` usage
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::Readback`] if the buffer can't be read or the
+    /// run has no GPU.
+    pub fn hash_buffer(&self, buffer: &Buffer) -> Result<u64> {
+        let Some((device, queue)) = &self.gpu else {
+            return Err(HaggisError::Readback(wgpu::BufferAsyncError));
+        };
+        let bytes: Vec<u8> = read_buffer(device, queue, buffer, buffer.size() as usize)?;
+        Ok(hash_bytes(&bytes))
+    }
+
+    /// Step `total` updates, hashing the state with `hash` every `interval`
+    /// updates (and before the first)
+    pub fn record(
+        &mut self,
+        total: u64,
+        interval: u64,
+        mut hash: impl FnMut(&Self) -> Result<u64>,
+    ) -> Result<StepHashes> {
+        let interval = interval.max(1);
+        let mut hashes = StepHashes::default();
+        hashes.push(self.steps, hash(self)?);
+        let end = self.steps + total;
+        while self.steps < end {
+            self.step(interval.min(end - self.steps));
+            hashes.push(self.steps, hash(self)?);
+        }
+        Ok(hashes)
+    }
+}
+
+/// Hashes of a simulation's state at given steps
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StepHashes {
+    entries: Vec<(u64, u64)>,
+}
+
+impl StepHashes {
+    pub fn push(&mut self, step: u64, hash: u64) {
+        self.entries.push((step, hash));
+    }
+
+    /// `(step, hash)` pairs in recording order
+    pub fn entries(&self) -> &[(u64, u64)] {
+        &self.entries
+    }
+
+    /// Parse the format written by `Display`: one `step hash` pair per line,
+    /// the hash in hex
+    pub fn parse(text: &str) -> std::result::Result<Self, String> {
+        let mut hashes = Self::default();
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let (step, hash) = line
+                .split_once(' ')
+                .ok_or_else(|| format!("expected `step hash`, got `{line}`"))?;
+            let step = step.parse().map_err(|_| format!("invalid step `{step}`"))?;
+            let hash = u64::from_str_radix(hash.trim(), 16)
+                .map_err(|_| format!("invalid hash `{hash}`"))?;
+            hashes.push(step, hash);
+        }
+        Ok(hashes)
+    }
+
+    /// First step whose hash differs from `expected`, if any
+    pub fn first_difference(&self, expected: &StepHashes) -> Option<u64> {
+        let common = self.entries.len().min(expected.entries.len());
+        if let Some(index) = (0..common).find(|&i| self.entries[i] != expected.entries[i]) {
+            return Some(self.entries[index].0);
+        }
+        // Otherwise the shorter list ends where the longer one goes on
+        self.entries
+            .get(common)
+            .or(expected.entries.get(common))
+            .map(|(step, _)| *step)
+    }
+
+    /// Compare against hashes stored at `path`
+    ///
+    /// Like [`GoldenImage`](super::GoldenImage), a missing file, or
+    /// `HAGGIS_UPDATE_GOLDEN` in the environment, stores these hashes instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::HashMismatch`] naming the first differing step,
+    /// and [`HaggisError::Io`] if the file can't be read or written.
+    pub fn check(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let io_error = |source| HaggisError::Io {
+            path: path.into(),
+            source,
+        };
+        if std::env::var_os(UPDATE_ENV).is_some() || !path.exists() {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).map_err(io_error)?;
+            }
+            return std::fs::write(path, self.to_string()).map_err(io_error);
+        }
+
+        let text = std::fs::read_to_string(path).map_err(io_error)?;
+        let expected = StepHashes::parse(&text).map_err(|message| HaggisError::HashMismatch {
+            path: path.into(),
+            message,
+        })?;
+        match self.first_difference(&expected) {
+            None => Ok(()),
+            Some(step) => Err(HaggisError::HashMismatch {
+                path: path.into(),
+                message: format!("state differs from step {step} on"),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for StepHashes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (step, hash) in &self.entries {
+            writeln!(f, "{step} {hash:016x}")?;
+        }
+        Ok(())
+    }
+}
+
+/// FNV-1a hash of `bytes`, stable across platforms and Rust versions
+pub fn hash_bytes(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Hash `f32` values, ignoring their lowest `ignored_bits` mantissa bits
+///
+/// `-0.0` hashes like `0.0` and all NaNs alike. Ignoring a few bits keeps the
+/// hash stable under rounding differences, e.g. fused multiply-adds.
+pub fn hash_f32(values: &[f32], ignored_bits: u32) -> u64 {
+    let mask = !((1u32 << ignored_bits.min(23)) - 1);
+    let bytes: Vec<u8> = values
+        .iter()
+        .flat_map(|&value| {
+            let bits = if value.is_nan() {
+                f32::NAN.to_bits()
+            } else if value == 0.0 {
+                0
+            } else {
+                value.to_bits() & mask
+            };
+            bits.to_le_bytes()
+        })
+        .collect();
+    hash_bytes(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::callback::CallbackSimulation;
+
+    #[test]
+    fn test_hashes_and_records() {
+        assert_eq!(hash_bytes(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash_bytes(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(hash_f32(&[0.0], 0), hash_f32(&[-0.0], 0));
+        assert_ne!(hash_f32(&[1.0], 0), hash_f32(&[1.0 + f32::EPSILON], 0));
+        assert_eq!(hash_f32(&[1.0], 4), hash_f32(&[1.0 + f32::EPSILON], 4));
+
+        let mut run = SimulationRun::new(CallbackSimulation::new("Counter", |_dt, _scene| {}));
+        let hashes = run.record(10, 4, |run| Ok(run.steps() * 10)).unwrap();
+        let steps: Vec<u64> = hashes.entries().iter().map(|(step, _)| *step).collect();
+        assert_eq!(steps, [0, 4, 8, 10]);
+        assert_eq!(StepHashes::parse(&hashes.to_string()).unwrap(), hashes);
+
+        let mut changed = hashes.clone();
+        changed.entries[2].1 = 0;
+        assert_eq!(changed.first_difference(&hashes), Some(8));
+        assert_eq!(hashes.first_difference(&hashes), None);
+    }
+}
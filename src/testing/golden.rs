@@ -0,0 +1,274 @@
+//! Reference image comparison
+//!
+//! [`ImageDiff`] compares two frames pixel by pixel with a perceptual color
+//! distance (the YIQ metric used by pixelmatch), so antialiasing noise and
+//! small driver differences stay below the threshold while visible changes
+//! don't. [`GoldenImage`] checks a frame against a reference PNG and writes
+//! the actual frame and a diff image next to it when they differ.
+
+use std::path::{Path, PathBuf};
+
+use crate::error::{HaggisError, Result};
+use crate::gfx::rendering::CapturedFrame;
+
+/// Set to any value to overwrite reference images instead of comparing
+pub const UPDATE_ENV: &str = "HAGGIS_UPDATE_GOLDEN";
+
+/// Largest possible squared YIQ distance between two colors
+const MAX_YIQ_DELTA: f32 = 35215.0;
+
+/// How much two frames may differ and still match
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiffOptions {
+    /// Per-pixel color distance from 0 to 1 above which a pixel differs
+    pub threshold: f32,
+    /// Fraction of pixels that may differ
+    pub max_differing: f32,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            threshold: 0.1,
+            max_differing: 0.001,
+        }
+    }
+}
+
+/// Result of comparing two frames of the same size
+#[derive(Debug, Clone)]
+pub struct ImageDiff {
+    /// Pixels whose distance exceeds the threshold
+    pub differing_pixels: usize,
+    pub total_pixels: usize,
+    /// Largest per-pixel distance, from 0 to 1
+    pub max_delta: f32,
+    /// Faded expected frame with differing pixels in red
+    pub image: CapturedFrame,
+}
+
+impl ImageDiff {
+    /// Compare `actual` against `expected`
+    ///
+    /// # Returns
+    ///
+    /// `None` if the frames differ in size
+    pub fn compare(
+        expected: &CapturedFrame,
+        actual: &CapturedFrame,
+        threshold: f32,
+    ) -> Option<Self> {
+        if (expected.width, expected.height) != (actual.width, actual.height) {
+            return None;
+        }
+        let limit = threshold * threshold;
+        let mut differing_pixels = 0;
+        let mut max_delta = 0.0f32;
+        let mut rgba = Vec::with_capacity(expected.rgba.len());
+        for (a, b) in expected
+            .rgba
+            .chunks_exact(4)
+            .zip(actual.rgba.chunks_exact(4))
+        {
+            let delta = yiq_delta(a, b);
+            max_delta = max_delta.max(delta);
+            if delta > limit {
+                differing_pixels += 1;
+                rgba.extend_from_slice(&[255, 0, 0, 255]);
+            } else {
+                // Faded grayscale keeps the context readable around red pixels
+                let gray = (luma(a) * 0.1 + 229.5) as u8;
+                rgba.extend_from_slice(&[gray, gray, gray, 255]);
+            }
+        }
+        Some(Self {
+            differing_pixels,
+            total_pixels: expected.rgba.len() / 4,
+            max_delta: max_delta.sqrt(),
+            image: CapturedFrame {
+                width: expected.width,
+                height: expected.height,
+                rgba,
+            },
+        })
+    }
+
+    /// Fraction of pixels that differ
+    pub fn differing_fraction(&self) -> f32 {
+        self.differing_pixels as f32 / self.total_pixels.max(1) as f32
+    }
+
+    /// Whether the difference is within `options`
+    pub fn passes(&self, options: &DiffOptions) -> bool {
+        self.differing_fraction() <= options.max_differing
+    }
+}
+
+/// A reference PNG that rendered frames must match
+///
+/// When the reference file doesn't exist, or [`UPDATE_ENV`] is set, the
+/// checked frame is written as the new reference and the check passes.
+/// Otherwise a mismatch writes `<name>.actual.png` and `<name>.diff.png`
+/// beside the reference.
+#[derive(Debug, Clone)]
+pub struct GoldenImage {
+    path: PathBuf,
+    options: DiffOptions,
+}
+
+impl GoldenImage {
+    /// Reference stored at `path`, compared with default options
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            options: DiffOptions::default(),
+        }
+    }
+
+    pub fn with_threshold(mut self, threshold: f32) -> Self {
+        self.options.threshold = threshold;
+        self
+    }
+
+    pub fn with_max_differing(mut self, fraction: f32) -> Self {
+        self.options.max_differing = fraction;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Compare a frame against the reference
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::ImageMismatch`] if the frame differs, and
+    /// [`HaggisError::Io`] or [`HaggisError::Image`] if the reference can't
+    /// be read or the output images can't be written.
+    pub fn check(&self, actual: &CapturedFrame) -> Result<()> {
+        if std::env::var_os(UPDATE_ENV).is_some() || !self.path.exists() {
+            if let Some(parent) = self.path.parent() {
+                std::fs::create_dir_all(parent).map_err(|source| HaggisError::Io {
+                    path: parent.into(),
+                    source,
+                })?;
+            }
+            return actual.save_png(&self.path);
+        }
+
+        let expected = CapturedFrame::load_png(&self.path)?;
+        let message = match ImageDiff::compare(&expected, actual, self.options.threshold) {
+            None => format!(
+                "size {}x{} differs from the reference's {}x{}",
+                actual.width, actual.height, expected.width, expected.height
+            ),
+            Some(diff) if diff.passes(&self.options) => {
+                // Outputs of an earlier failure are stale now
+                let _ = std::fs::remove_file(self.output_path("actual"));
+                let _ = std::fs::remove_file(self.output_path("diff"));
+                return Ok(());
+            }
+            Some(diff) => {
+                diff.image.save_png(self.output_path("diff"))?;
+                format!(
+                    "{} of {} pixels ({:.3}%) differ, max distance {:.3}; see {}",
+                    diff.differing_pixels,
+                    diff.total_pixels,
+                    diff.differing_fraction() * 100.0,
+                    diff.max_delta,
+                    self.output_path("diff").display()
+                )
+            }
+        };
+        actual.save_png(self.output_path("actual"))?;
+        Err(HaggisError::ImageMismatch {
+            path: self.path.clone(),
+            message,
+        })
+    }
+
+    /// Like [`check`](Self::check), panicking with the mismatch
+    ///
+    /// # Panics
+    ///
+    /// Panics if the frame doesn't match the reference.
+    #[track_caller]
+    pub fn assert_matches(&self, actual: &CapturedFrame) {
+        if let Err(error) = self.check(actual) {
+            panic!("{error} (set {UPDATE_ENV}=1 to accept the new image)");
+        }
+    }
+
+    /// `<name>.<kind>.png` beside the reference
+    fn output_path(&self, kind: &str) -> PathBuf {
+        let stem = self
+            .path
+            .file_stem()
+            .map_or_else(|| "golden".into(), |stem| stem.to_string_lossy());
+        self.path.with_file_name(format!("{stem}.{kind}.png"))
+    }
+}
+
+/// Squared YIQ distance of two RGBA pixels blended onto white, from 0 to 1
+fn yiq_delta(a: &[u8], b: &[u8]) -> f32 {
+    let [y1, i1, q1] = yiq(a);
+    let [y2, i2, q2] = yiq(b);
+    let (y, i, q) = (y1 - y2, i1 - i2, q1 - q2);
+    (0.5053 * y * y + 0.299 * i * i + 0.1957 * q * q) / MAX_YIQ_DELTA
+}
+
+fn yiq(pixel: &[u8]) -> [f32; 3] {
+    let alpha = pixel[3] as f32 / 255.0;
+    let [r, g, b] = [pixel[0], pixel[1], pixel[2]].map(|c| 255.0 + (c as f32 - 255.0) * alpha);
+    [
+        r * 0.298_895_3 + g * 0.586_622_5 + b * 0.114_482_23,
+        r * 0.595_977_99 - g * 0.274_176_1 - b * 0.321_801_9,
+        r * 0.211_470_17 - g * 0.522_617_1 + b * 0.311_146_94,
+    ]
+}
+
+fn luma(pixel: &[u8]) -> f32 {
+    yiq(pixel)[0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(pixels: &[[u8; 4]]) -> CapturedFrame {
+        CapturedFrame {
+            width: pixels.len() as u32,
+            height: 1,
+            rgba: pixels.concat(),
+        }
+    }
+
+    #[test]
+    fn test_perceptual_diff() {
+        let expected = frame(&[
+            [0, 0, 0, 255],
+            [200, 100, 50, 255],
+            [255; 4],
+            [10, 10, 10, 0],
+        ]);
+        // Slight noise, one black pixel turned white, and invisible alpha
+        let actual = frame(&[
+            [2, 1, 0, 255],
+            [200, 100, 50, 255],
+            [0, 0, 0, 255],
+            [90, 0, 0, 0],
+        ]);
+
+        let diff = ImageDiff::compare(&expected, &actual, 0.1).unwrap();
+        assert_eq!(diff.differing_pixels, 1);
+        assert!(diff.max_delta > 0.9, "{}", diff.max_delta);
+        assert_eq!(&diff.image.rgba[8..12], &[255, 0, 0, 255]);
+        assert!(!diff.passes(&DiffOptions::default()));
+        assert!(diff.passes(&DiffOptions {
+            max_differing: 0.25,
+            ..Default::default()
+        }));
+        assert!(ImageDiff::compare(&expected, &frame(&[[0; 4]]), 0.1).is_none());
+    }
+}
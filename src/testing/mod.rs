@@ -0,0 +1,357 @@
+//! # Regression Testing
+//!
+//! Render scenes without a window and compare the frames against reference
+//! PNGs, so changes to rendering in the crate or in an app show up in
+//! `cargo test`. [`HeadlessRenderer`] renders a [`Scene`] offscreen and
+//! [`GoldenImage`] compares the result with a perceptual threshold, writing
+//! the actual frame and a diff image beside the reference on a mismatch.
+//! [`determinism`] does the same for simulation state with step hashes.
+//!
+//! References are created on the first run; set `HAGGIS_UPDATE_GOLDEN=1` to
+//! overwrite them after an intended change. Different GPUs and drivers
+//! rasterize slightly differently, so keep references per machine class (or
+//! render them on a software adapter in CI) and raise the threshold rather
+//! than comparing exactly.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::gfx::camera::{
+//!     camera_controller::CameraController, camera_utils::CameraManager,
+//!     orbit_camera::OrbitCamera,
+//! };
+//! use haggis::gfx::geometry::primitives::generate_cube;
+//! use haggis::gfx::scene::Scene;
+//! use haggis::testing::{GoldenImage, HeadlessRenderer};
+//!
+//! #[test]
+//! fn cube_renders() {
+//!     let Ok(mut renderer) = HeadlessRenderer::new(128, 128) else {
+//!         return; // no GPU
+//!     };
+//!     let camera = OrbitCamera::new(5.0, 0.4, 0.6, [0.0; 3].into(), 1.0);
+//!     let mut scene = Scene::new(CameraManager::new(camera, CameraController::new(0.005, 0.1)));
+//!     scene.add_procedural_object(generate_cube(), "Cube");
+//!
+//!     let frame = renderer.render(&mut scene).unwrap();
+//!     GoldenImage::new("tests/golden/cube.png").assert_matches(&frame);
+//! }
+//! ```
+
+pub mod determinism;
+pub mod golden;
+
+pub use determinism::{hash_bytes, hash_f32, SimulationRun, StepHashes};
+pub use golden::{DiffOptions, GoldenImage, ImageDiff};
+
+use crate::error::Result;
+use crate::gfx::rendering::{CapturedFrame, RenderConfig, RenderEngine, VisualizationPlane};
+use crate::gfx::scene::Scene;
+
+/// Renders scenes offscreen at a fixed size
+pub struct HeadlessRenderer {
+    engine: RenderEngine,
+    size: (u32, u32),
+}
+
+impl HeadlessRenderer {
+    /// Create a renderer on the default adapter
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::NoAdapter`](crate::HaggisError::NoAdapter) or
+    /// [`HaggisError::Device`](crate::HaggisError::Device) without a usable GPU;
+    /// tests usually skip themselves then.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new(width: u32, height: u32) -> Result<Self> {
+        Self::with_config(width, height, &RenderConfig::default())
+    }
+
+    /// Create a renderer with explicit adapter and device settings
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_config(width: u32, height: u32, config: &RenderConfig) -> Result<Self> {
+        let engine = pollster::block_on(RenderEngine::headless(width, height, config))?;
+        Ok(Self {
+            engine,
+            size: (width, height),
+        })
+    }
+
+    /// The engine, e.g. to change lighting or render settings
+    pub fn engine_mut(&mut self) -> &mut RenderEngine {
+        &mut self.engine
+    }
+
+    /// Upload the scene's objects and materials and render a frame
+    pub fn render(&mut self, scene: &mut Scene) -> Result<CapturedFrame> {
+        self.render_with_planes(scene, &[])
+    }
+
+    /// Render a frame including visualization planes
+    pub fn render_with_planes(
+        &mut self,
+        scene: &mut Scene,
+        visualization_planes: &[VisualizationPlane],
+    ) -> Result<CapturedFrame> {
+        let (device, queue) = (self.engine.device(), self.engine.queue());
+        scene.init_gpu_resources(device, queue);
+        scene.update_all_transforms(queue);
+        scene.update_materials(device, queue);
+        Ok(self
+            .engine
+            .capture_frame(scene, visualization_planes, self.size)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gfx::camera::{
+        camera_controller::CameraController, camera_utils::CameraManager, orbit_camera::OrbitCamera,
+    };
+    use crate::gfx::geometry::primitives::generate_cube;
+    use crate::HaggisError;
+    use cgmath::Vector3;
+
+    #[test]
+    fn test_golden_roundtrip() {
+        let Ok(mut renderer) = HeadlessRenderer::new(64, 48) else {
+            return;
+        };
+        let camera = OrbitCamera::new(5.0, 0.4, 0.6, Vector3::new(0.0, 0.0, 0.0), 64.0 / 48.0);
+        let mut scene = Scene::new(CameraManager::new(
+            camera,
+            CameraController::new(0.005, 0.1),
+        ));
+        scene.add_procedural_object(generate_cube(), "Cube");
+
+        let frame = renderer.render(&mut scene).unwrap();
+        assert_eq!((frame.width, frame.height), (64, 48));
+
+        let dir = std::env::temp_dir().join(format!("haggis_golden_{}", std::process::id()));
+        let golden = GoldenImage::new(dir.join("cube.png"));
+        golden.check(&frame).unwrap();
+        assert!(golden.path().exists());
+        golden.check(&renderer.render(&mut scene).unwrap()).unwrap();
+
+        scene
+            .get_object_mut(0)
+            .unwrap()
+            .set_translation(Vector3::new(1.5, 0.0, 0.0));
+        let moved = renderer.render(&mut scene).unwrap();
+        assert!(matches!(
+            golden.check(&moved),
+            Err(HaggisError::ImageMismatch { .. })
+        ));
+        assert!(dir.join("cube.diff.png").exists());
+        assert!(dir.join("cube.actual.png").exists());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_depth_prepass_renders_the_same_image() {
+        let Ok(mut renderer) = HeadlessRenderer::new(64, 48) else {
+            return;
+        };
+        let camera = OrbitCamera::new(5.0, 0.4, 0.6, Vector3::new(0.0, 0.0, 0.0), 64.0 / 48.0);
+        let mut scene = Scene::new(CameraManager::new(
+            camera,
+            CameraController::new(0.005, 0.1),
+        ));
+        // Overlapping cubes, so the pre-pass has hidden surfaces to reject
+        for (index, x) in [-0.5, 0.0, 0.5].into_iter().enumerate() {
+            scene.add_procedural_object(generate_cube(), "Cube");
+            scene
+                .get_object_mut(index)
+                .unwrap()
+                .set_translation(Vector3::new(x, 0.0, -x));
+        }
+        let direct = renderer.render(&mut scene).unwrap();
+
+        let mut settings = *renderer.engine_mut().render_settings();
+        settings.depth_prepass = true;
+        settings.front_to_back = true;
+        renderer.engine_mut().set_render_settings(&settings);
+        let prepass = renderer.render(&mut scene).unwrap();
+
+        let diff = ImageDiff::compare(&direct, &prepass, 0.1).unwrap();
+        assert!(diff.passes(&DiffOptions::default()));
+    }
+
+    #[test]
+    fn test_reverse_z_renders_the_same_image() {
+        let config = RenderConfig {
+            depth_mode: crate::gfx::rendering::DepthMode::Reverse,
+            ..RenderConfig::default()
+        };
+        let (Ok(mut standard), Ok(mut reverse)) = (
+            HeadlessRenderer::new(64, 48),
+            HeadlessRenderer::with_config(64, 48, &config),
+        ) else {
+            return;
+        };
+        // Each renderer has its own device, so each gets its own scene
+        let make_scene = || {
+            let camera = OrbitCamera::new(5.0, 0.4, 0.6, Vector3::new(0.0, 0.0, 0.0), 64.0 / 48.0);
+            let mut scene = Scene::new(CameraManager::new(
+                camera,
+                CameraController::new(0.005, 0.1),
+            ));
+            scene.add_procedural_object(generate_cube(), "Cube");
+            scene.add_procedural_object(generate_cube(), "Cube");
+            scene
+                .get_object_mut(1)
+                .unwrap()
+                .set_translation(Vector3::new(0.6, 0.6, 0.3));
+            scene
+        };
+
+        let expected = standard.render(&mut make_scene()).unwrap();
+        let actual = reverse.render(&mut make_scene()).unwrap();
+        let diff = ImageDiff::compare(&expected, &actual, 0.1).unwrap();
+        assert!(diff.passes(&DiffOptions::default()));
+    }
+
+    #[test]
+    fn test_custom_shader_replaces_pbr_shading() {
+        use crate::gfx::rendering::CustomShader;
+
+        let Ok(mut renderer) = HeadlessRenderer::new(64, 48) else {
+            return;
+        };
+        let camera = OrbitCamera::new(5.0, 0.4, 0.6, Vector3::new(0.0, 0.0, 0.0), 64.0 / 48.0);
+        let mut scene = Scene::new(CameraManager::new(
+            camera,
+            CameraController::new(0.005, 0.1),
+        ));
+        scene.add_procedural_object(generate_cube(), "Cube");
+        scene.objects[0].set_material("Flat");
+        let flat = CustomShader::new(
+            "flat",
+            "@fragment\n\
+             fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {\n\
+                 return vec4<f32>(encode_output(material.base_color.rgb, global.color_flags), 1.0);\n\
+             }\n",
+        );
+        scene.add_material("Flat", [1.0, 0.0, 1.0, 1.0], 0.0, 0.5).shader = Some(flat);
+
+        let frame = renderer.render(&mut scene).unwrap();
+        let center = ((24 * 64 + 32) * 4) as usize;
+        assert_eq!(&frame.rgba[center..center + 3], &[255, 0, 255]);
+    }
+
+    #[test]
+    fn test_projector_colors_the_geometry_it_reaches() {
+        use crate::gfx::rendering::{Projector, ProjectorImage};
+
+        let Ok(mut renderer) = HeadlessRenderer::new(64, 48) else {
+            return;
+        };
+        let camera = OrbitCamera::new(5.0, 0.4, 0.6, Vector3::new(0.0, 0.0, 0.0), 64.0 / 48.0);
+        let mut scene = Scene::new(CameraManager::new(
+            camera,
+            CameraController::new(0.005, 0.1),
+        ));
+        scene.add_procedural_object(generate_cube(), "Cube");
+        let center = ((24 * 64 + 32) * 4) as usize;
+        let unlit = renderer.render(&mut scene).unwrap();
+        assert_ne!(&unlit.rgba[center..center + 3], &[255, 0, 0]);
+
+        // Shining along +x through the whole cube
+        let mut projector =
+            Projector::new(Vector3::new(-2.0, 0.0, 0.0), Vector3::unit_x(), [4.0, 4.0]);
+        projector.range = 4.0;
+        projector.set_image(ProjectorImage::Pixels {
+            rgba: vec![255, 0, 0, 255],
+            width: 1,
+            height: 1,
+        });
+        renderer.engine_mut().set_projector(Some(&projector));
+        let projected = renderer.render(&mut scene).unwrap();
+        assert_eq!(&projected.rgba[center..center + 3], &[255, 0, 0]);
+
+        // Out of range, the surface is shaded as before
+        projector.range = 0.1;
+        renderer.engine_mut().set_projector(Some(&projector));
+        let out_of_range = renderer.render(&mut scene).unwrap();
+        assert_eq!(out_of_range.rgba, unlit.rgba);
+    }
+
+    #[test]
+    fn test_triplanar_mapping_textures_meshes_without_uvs() {
+        use crate::gfx::resources::{material::TextureMapping, TextureResource};
+
+        let Ok(mut renderer) = HeadlessRenderer::new(64, 48) else {
+            return;
+        };
+        let camera = OrbitCamera::new(5.0, 0.4, 0.6, Vector3::new(0.0, 0.0, 0.0), 64.0 / 48.0);
+        let mut scene = Scene::new(CameraManager::new(
+            camera,
+            CameraController::new(0.005, 0.1),
+        ));
+        scene.add_procedural_object(generate_cube(), "Cube");
+        scene.objects[0].set_material("Red");
+        let engine = renderer.engine_mut();
+        let red = TextureResource::create_from_rgba_data(
+            engine.device(),
+            engine.queue(),
+            &[255, 0, 0, 255].repeat(4),
+            2,
+            2,
+            "Red",
+        );
+        let material = scene.add_material("Red", [1.0, 1.0, 1.0, 1.0], 0.0, 0.5);
+        material.set_texture(red);
+        material.texture_mapping = TextureMapping::None;
+
+        let center = ((24 * 64 + 32) * 4) as usize;
+        let untextured = renderer.render(&mut scene).unwrap();
+        let [r, g, b] = [0, 1, 2].map(|c| untextured.rgba[center + c]);
+        assert!(r.abs_diff(g) < 8 && g.abs_diff(b) < 8);
+
+        scene.material_manager.get_material_mut(&"Red".to_string()).unwrap().texture_mapping =
+            TextureMapping::default();
+        let textured = renderer.render(&mut scene).unwrap();
+        let [r, g, b] = [0, 1, 2].map(|c| textured.rgba[center + c]);
+        assert!(r > 2 * g && r > 2 * b, "{:?}", [r, g, b]);
+    }
+
+    #[test]
+    fn test_ghost_shows_through_cut_planes() {
+        use crate::visualization::{cut_plane_2d::CutPlane2D, traits::VisualizationComponent};
+
+        let Ok(mut renderer) = HeadlessRenderer::new(64, 48) else {
+            return;
+        };
+        // Looking down onto a cut plane above the cube
+        let camera = OrbitCamera::new(5.0, 1.2, 0.0, Vector3::new(0.0, 0.0, 0.0), 64.0 / 48.0);
+        let mut scene = Scene::new(CameraManager::new(
+            camera,
+            CameraController::new(0.005, 0.1),
+        ));
+        scene.add_procedural_object(generate_cube(), "Obstacle");
+        scene.add_material_rgb("White", 1.0, 1.0, 1.0, 0.0, 0.5);
+        scene.objects[0].set_material("White");
+
+        let mut cut_plane = CutPlane2D::new();
+        cut_plane.update_data(vec![0.0; 16], 4, 4);
+        cut_plane.set_position(Vector3::new(0.0, 0.0, 1.0));
+        cut_plane.set_size(2.0);
+        let engine = renderer.engine_mut();
+        cut_plane.initialize(Some(engine.device()), Some(engine.queue()));
+        let planes = [cut_plane.to_visualization_plane().unwrap()];
+
+        // The plane hides the solid cube completely
+        let solid = renderer.render_with_planes(&mut scene, &planes).unwrap();
+        scene.objects[0].visible = false;
+        let plane_only = renderer.render_with_planes(&mut scene, &planes).unwrap();
+        let center = ((24 * 64 + 32) * 4) as usize;
+        assert_eq!(solid.rgba[center..center + 3], plane_only.rgba[center..center + 3]);
+
+        // Ghosted, it shows faintly over the plane
+        scene.objects[0].visible = true;
+        scene.objects[0].ghost = true;
+        let ghost = renderer.render_with_planes(&mut scene, &planes).unwrap();
+        assert_ne!(ghost.rgba[center..center + 3], plane_only.rgba[center..center + 3]);
+    }
+}
@@ -0,0 +1,475 @@
+//! GPU buffer inspector panel
+//!
+//! Lists the buffers in the [`BufferRegistry`] with their size and usage,
+//! reads a slice of the selected buffer back without blocking the frame, and
+//! shows it as a table of `f32`, `u32` or `i32` values or as a small
+//! grayscale image (one pixel per value, normalized to the slice's range).
+//!
+//! Only buffers with `COPY_SRC` usage can be read back. The window collects
+//! read requests; [`BufferInspector::update`] issues and collects the copies
+//! once per frame, where the device is available.
+
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+use imgui::Ui;
+use wgpu::{Buffer, Device, Queue};
+
+use crate::wgpu_utils::buffer_registry::{BufferRegistry, RegisteredBuffer};
+
+/// Largest slice read back at once, in 4-byte values
+pub const MAX_READ_VALUES: u64 = 1 << 16;
+
+/// Largest side of the image view in screen pixels
+const IMAGE_SIZE: f32 = 256.0;
+
+/// Interpretation of the read values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InspectorView {
+    #[default]
+    F32,
+    U32,
+    I32,
+    /// `f32` values as a grayscale image
+    Image,
+}
+
+impl InspectorView {
+    const ALL: [InspectorView; 4] = [
+        InspectorView::F32,
+        InspectorView::U32,
+        InspectorView::I32,
+        InspectorView::Image,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InspectorView::F32 => "f32",
+            InspectorView::U32 => "u32",
+            InspectorView::I32 => "i32",
+            InspectorView::Image => "Image",
+        }
+    }
+
+    /// Text of one value
+    pub fn format(&self, word: u32) -> String {
+        match self {
+            InspectorView::F32 | InspectorView::Image => format!("{:.6}", f32::from_bits(word)),
+            InspectorView::U32 => word.to_string(),
+            InspectorView::I32 => (word as i32).to_string(),
+        }
+    }
+}
+
+/// Values read back from a buffer
+#[derive(Debug, Clone, PartialEq)]
+pub struct BufferSnapshot {
+    pub name: String,
+    /// Index of the first value, in 4-byte values from the buffer start
+    pub first: u64,
+    pub words: Vec<u32>,
+}
+
+struct PendingRead {
+    name: String,
+    first: u64,
+    staging: Buffer,
+    receiver: Receiver<Result<(), wgpu::BufferAsyncError>>,
+}
+
+/// Buffer inspector state: selection, slice, view and the last readback
+pub struct BufferInspector {
+    /// Whether the window is visible
+    pub open: bool,
+    selected: Option<String>,
+    first: i32,
+    count: i32,
+    view: InspectorView,
+    image_width: i32,
+    values_per_row: i32,
+    requested: Option<(String, u64, u64)>,
+    pending: Option<PendingRead>,
+    snapshot: Option<BufferSnapshot>,
+    status: Option<String>,
+}
+
+impl Default for BufferInspector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BufferInspector {
+    /// Create a closed inspector
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            selected: None,
+            first: 0,
+            count: 256,
+            view: InspectorView::F32,
+            image_width: 16,
+            values_per_row: 8,
+            requested: None,
+            pending: None,
+            snapshot: None,
+            status: None,
+        }
+    }
+
+    /// Show or hide the window
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    /// Read `count` values starting at value `first` of a registered buffer
+    /// on the next [`update`](Self::update)
+    pub fn request_read(&mut self, name: &str, first: u64, count: u64) {
+        self.selected = Some(name.to_string());
+        self.requested = Some((name.to_string(), first, count));
+    }
+
+    /// Last completed readback
+    pub fn snapshot(&self) -> Option<&BufferSnapshot> {
+        self.snapshot.as_ref()
+    }
+
+    /// Issue requested copies and collect finished ones; call once per frame
+    pub fn update(&mut self, device: &Device, queue: &Queue) {
+        if let Some(pending) = &self.pending {
+            let _ = device.poll(wgpu::MaintainBase::Poll);
+            match pending.receiver.try_recv() {
+                Err(TryRecvError::Empty) => return,
+                Ok(Ok(())) => {
+                    let pending = self.pending.take().unwrap();
+                    let words = {
+                        let view = pending.staging.slice(..).get_mapped_range();
+                        bytemuck::cast_slice(&view).to_vec()
+                    };
+                    pending.staging.unmap();
+                    self.status = None;
+                    self.snapshot = Some(BufferSnapshot {
+                        name: pending.name,
+                        first: pending.first,
+                        words,
+                    });
+                }
+                Ok(Err(_)) | Err(TryRecvError::Disconnected) => {
+                    let pending = self.pending.take().unwrap();
+                    self.status = Some(format!("Reading {} failed", pending.name));
+                }
+            }
+        }
+
+        let Some((name, first, count)) = self.requested.take() else {
+            return;
+        };
+        let Some(registered) = BufferRegistry::global().get(&name) else {
+            self.status = Some(format!("{name} is not registered"));
+            return;
+        };
+        if !registered.is_readable() {
+            self.status = Some(format!("{name} has no COPY_SRC usage"));
+            return;
+        }
+        let Some((first, len)) = clamp_slice(registered.size(), first, count) else {
+            self.status = Some(format!("{name} has no values at {first}"));
+            return;
+        };
+
+        let staging = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Buffer Inspector Staging"),
+            size: len * 4,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Buffer Inspector Encoder"),
+        });
+        encoder.copy_buffer_to_buffer(&registered.buffer, first * 4, &staging, 0, len * 4);
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let (sender, receiver) = mpsc::channel();
+        staging
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+        self.pending = Some(PendingRead {
+            name,
+            first,
+            staging,
+            receiver,
+        });
+    }
+
+    /// Draw the window if it is open
+    pub fn render_ui(&mut self, ui: &Ui) {
+        if !self.open {
+            return;
+        }
+
+        let buffers = BufferRegistry::global().buffers();
+        let mut open = self.open;
+        ui.window("Buffer Inspector")
+            .size([560.0, 520.0], imgui::Condition::FirstUseEver)
+            .opened(&mut open)
+            .build(|| {
+                let total: u64 = buffers.iter().map(RegisteredBuffer::size).sum();
+                ui.text(format!(
+                    "{} buffers, {}",
+                    buffers.len(),
+                    format_bytes(total)
+                ));
+                self.buffer_list(ui, &buffers);
+                ui.separator();
+                self.read_controls(ui);
+                ui.separator();
+                self.snapshot_view(ui);
+            });
+        self.open = open;
+    }
+
+    fn buffer_list(&mut self, ui: &Ui, buffers: &[RegisteredBuffer]) {
+        ui.child_window("buffers")
+            .size([0.0, 150.0])
+            .border(true)
+            .build(|| {
+                ui.columns(3, "buffer_columns", true);
+                for header in ["Name", "Size", "Usage"] {
+                    ui.text_disabled(header);
+                    ui.next_column();
+                }
+                ui.separator();
+
+                for buffer in buffers {
+                    let selected = self.selected.as_deref() == Some(buffer.name.as_str());
+                    if ui
+                        .selectable_config(&buffer.name)
+                        .selected(selected)
+                        .span_all_columns(true)
+                        .build()
+                    {
+                        self.selected = Some(buffer.name.clone());
+                    }
+                    ui.next_column();
+                    ui.text(format_bytes(buffer.size()));
+                    ui.next_column();
+                    ui.text(format_usage(buffer.usage()));
+                    ui.next_column();
+                }
+                ui.columns(1, "", false);
+            });
+    }
+
+    fn read_controls(&mut self, ui: &Ui) {
+        let Some(name) = self.selected.clone() else {
+            ui.text_disabled("Select a buffer to read it back");
+            return;
+        };
+        ui.text(&name);
+
+        ui.set_next_item_width(120.0);
+        ui.input_int("First value", &mut self.first).build();
+        ui.same_line();
+        ui.set_next_item_width(120.0);
+        ui.input_int("Count", &mut self.count).build();
+        self.first = self.first.max(0);
+        self.count = self.count.clamp(1, MAX_READ_VALUES as i32);
+
+        ui.set_next_item_width(120.0);
+        if let Some(_combo) = ui.begin_combo("View", self.view.as_str()) {
+            for view in InspectorView::ALL {
+                if ui
+                    .selectable_config(view.as_str())
+                    .selected(self.view == view)
+                    .build()
+                {
+                    self.view = view;
+                }
+            }
+        }
+        ui.same_line();
+        ui.set_next_item_width(120.0);
+        if self.view == InspectorView::Image {
+            ui.input_int("Width", &mut self.image_width).build();
+            self.image_width = self.image_width.clamp(1, 1024);
+        } else {
+            ui.input_int("Per row", &mut self.values_per_row).build();
+            self.values_per_row = self.values_per_row.clamp(1, 32);
+        }
+
+        let reading = self.pending.is_some();
+        if reading {
+            ui.text_disabled("Reading...");
+        } else if ui.button("Read") {
+            self.request_read(&name, self.first as u64, self.count as u64);
+        }
+        if let Some(status) = &self.status {
+            ui.same_line();
+            ui.text_colored([1.0, 0.4, 0.4, 1.0], status);
+        }
+    }
+
+    fn snapshot_view(&self, ui: &Ui) {
+        let Some(snapshot) = &self.snapshot else {
+            return;
+        };
+        let (min, max) = value_range(&snapshot.words);
+        ui.text(format!(
+            "{}: values {}..{}",
+            snapshot.name,
+            snapshot.first,
+            snapshot.first + snapshot.words.len() as u64
+        ));
+        if min.is_finite() {
+            ui.same_line();
+            ui.text_disabled(format!("(f32 range {min:.4} to {max:.4})"));
+        }
+
+        if self.view == InspectorView::Image {
+            self.image(ui, &snapshot.words, min, max);
+            return;
+        }
+
+        let per_row = self.values_per_row as usize;
+        let rows = snapshot.words.len().div_ceil(per_row);
+        ui.child_window("values")
+            .border(true)
+            .horizontal_scrollbar(true)
+            .build(|| {
+                ui.columns(per_row as i32 + 1, "value_columns", false);
+                let clipper = imgui::ListClipper::new(rows as i32).begin(ui);
+                for row in clipper.iter() {
+                    let start = row as usize * per_row;
+                    ui.text_disabled((snapshot.first + start as u64).to_string());
+                    ui.next_column();
+                    for &word in snapshot.words[start..].iter().take(per_row) {
+                        ui.text(self.view.format(word));
+                        ui.next_column();
+                    }
+                    // Pad a short last row so column order is kept
+                    for _ in snapshot.words[start..].len()..per_row {
+                        ui.next_column();
+                    }
+                }
+                ui.columns(1, "", false);
+            });
+    }
+
+    fn image(&self, ui: &Ui, words: &[u32], min: f32, max: f32) {
+        let width = self.image_width as usize;
+        let height = words.len().div_ceil(width);
+        let pixel = (IMAGE_SIZE / width.max(height) as f32).max(1.0);
+        let origin = ui.cursor_screen_pos();
+        let draw_list = ui.get_window_draw_list();
+
+        for (index, &word) in words.iter().enumerate() {
+            let value = f32::from_bits(word);
+            let color = if value.is_finite() {
+                let t = if max > min {
+                    (value - min) / (max - min)
+                } else {
+                    0.5
+                };
+                [t, t, t, 1.0]
+            } else {
+                // NaN and infinities stand out in magenta
+                [1.0, 0.0, 1.0, 1.0]
+            };
+            let x = origin[0] + (index % width) as f32 * pixel;
+            let y = origin[1] + (index / width) as f32 * pixel;
+            draw_list
+                .add_rect([x, y], [x + pixel, y + pixel], color)
+                .filled(true)
+                .build();
+        }
+        ui.dummy([width as f32 * pixel, height as f32 * pixel]);
+    }
+}
+
+/// Clamp a slice of 4-byte values to a buffer of `size` bytes, returning
+/// its first value and length
+pub fn clamp_slice(size: u64, first: u64, count: u64) -> Option<(u64, u64)> {
+    let available = (size / 4).checked_sub(first).filter(|&n| n > 0)?;
+    Some((first, count.min(available).min(MAX_READ_VALUES)))
+}
+
+/// Finite minimum and maximum of values read as `f32`
+fn value_range(words: &[u32]) -> (f32, f32) {
+    words
+        .iter()
+        .map(|&word| f32::from_bits(word))
+        .filter(|value| value.is_finite())
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), value| {
+            (min.min(value), max.max(value))
+        })
+}
+
+/// `STORAGE | COPY_SRC` style usage text
+pub fn format_usage(usage: wgpu::BufferUsages) -> String {
+    usage
+        .iter_names()
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+fn format_bytes(bytes: u64) -> String {
+    match bytes {
+        0..1024 => format!("{bytes} B"),
+        1024..1_048_576 => format!("{:.1} KiB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MiB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slice_and_formatting() {
+        assert_eq!(clamp_slice(64, 4, 100), Some((4, 12)));
+        assert_eq!(clamp_slice(64, 16, 1), None);
+        assert_eq!(
+            clamp_slice(1 << 30, 0, u64::MAX),
+            Some((0, MAX_READ_VALUES))
+        );
+
+        let usage = wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC;
+        assert_eq!(format_usage(usage), "COPY_SRC | STORAGE");
+        assert_eq!(InspectorView::I32.format(u32::MAX), "-1");
+        assert_eq!(InspectorView::F32.format(1.5f32.to_bits()), "1.500000");
+        assert_eq!(
+            value_range(&[1.0f32.to_bits(), f32::NAN.to_bits(), (-2.0f32).to_bits()]),
+            (-2.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn test_update_reads_registered_slice() {
+        use crate::wgpu_utils::compute_primitives::{test_buffer, test_device};
+
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let values: Vec<u32> = (0..64).collect();
+        let buffer = test_buffer(&device, &values);
+        BufferRegistry::global().register("inspector test", &buffer);
+
+        let mut inspector = BufferInspector::new();
+        inspector.request_read("inspector test", 60, 10);
+        inspector.update(&device, &queue);
+        for _ in 0..100 {
+            let _ = device.poll(wgpu::MaintainBase::Wait);
+            inspector.update(&device, &queue);
+            if inspector.snapshot().is_some() {
+                break;
+            }
+        }
+        BufferRegistry::global().unregister("inspector test");
+
+        let snapshot = inspector.snapshot().expect("readback finished");
+        assert_eq!(snapshot.first, 60);
+        assert_eq!(snapshot.words, vec![60, 61, 62, 63]);
+    }
+}
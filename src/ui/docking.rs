@@ -0,0 +1,174 @@
+// src/ui/docking.rs
+//! Docking layouts for the ImGui interface
+//!
+//! With the `docking` feature enabled, a transparent dockspace covers the
+//! main window so panels can be docked to its edges while the 3D view stays
+//! visible and interactive in the central area.
+//!
+//! A [`DockLayout`] describes where windows go the first time the app runs
+//! (or whenever no saved layout exists). Layouts the user arranges by hand are
+//! persisted to the ini file configured with `HaggisApp::set_layout_file`.
+//!
+//! Without the `docking` feature the layout is accepted but ignored, and
+//! windows keep their floating default positions.
+
+/// Edge of the dockspace a group of windows is docked to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DockSide {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+/// One split of the remaining dockspace area
+#[derive(Debug, Clone)]
+pub struct DockSplit {
+    /// Edge the split is taken from
+    pub side: DockSide,
+    /// Fraction of the remaining area given to this split (0.0 - 1.0)
+    pub ratio: f32,
+    /// Window titles docked into this split, as tabs
+    pub windows: Vec<String>,
+}
+
+/// Declarative default docking layout
+///
+/// Splits are applied in order, each one carving its area out of what is
+/// left of the dockspace. Windows listed in [`DockLayout::center`] are docked
+/// into the remaining central node; leave it empty to keep the 3D view clear.
+///
+/// # Examples
+///
+/// ```no_run
+/// use haggis::ui::DockLayout;
+///
+/// let layout = DockLayout::new()
+///     .left(0.22, &["Simulation Control"])
+///     .right(0.25, &["Transform Studio", "Gizmo Manager"])
+///     .bottom(0.2, &["Performance Metrics"]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DockLayout {
+    pub splits: Vec<DockSplit>,
+    pub center: Vec<String>,
+}
+
+impl DockLayout {
+    /// Create an empty layout
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a split on the given side containing the given windows
+    pub fn split(mut self, side: DockSide, ratio: f32, windows: &[&str]) -> Self {
+        self.splits.push(DockSplit {
+            side,
+            ratio: ratio.clamp(0.05, 0.95),
+            windows: windows.iter().map(|w| w.to_string()).collect(),
+        });
+        self
+    }
+
+    /// Dock windows to the left edge
+    pub fn left(self, ratio: f32, windows: &[&str]) -> Self {
+        self.split(DockSide::Left, ratio, windows)
+    }
+
+    /// Dock windows to the right edge
+    pub fn right(self, ratio: f32, windows: &[&str]) -> Self {
+        self.split(DockSide::Right, ratio, windows)
+    }
+
+    /// Dock windows to the top edge
+    pub fn top(self, ratio: f32, windows: &[&str]) -> Self {
+        self.split(DockSide::Top, ratio, windows)
+    }
+
+    /// Dock windows to the bottom edge
+    pub fn bottom(self, ratio: f32, windows: &[&str]) -> Self {
+        self.split(DockSide::Bottom, ratio, windows)
+    }
+
+    /// Dock windows into the central node (covers the 3D view)
+    pub fn center(mut self, windows: &[&str]) -> Self {
+        self.center = windows.iter().map(|w| w.to_string()).collect();
+        self
+    }
+
+    /// Build the layout into the dockspace with the given id.
+    ///
+    /// Must be called during a frame, before the windows are submitted.
+    #[cfg(feature = "docking")]
+    pub(crate) fn build(&self, dockspace_id: imgui::sys::ImGuiID) {
+        use imgui::sys;
+        use std::ffi::CString;
+
+        unsafe {
+            let viewport = sys::igGetMainViewport();
+
+            sys::igDockBuilderRemoveNode(dockspace_id);
+            sys::igDockBuilderAddNode(
+                dockspace_id,
+                sys::ImGuiDockNodeFlags_DockSpace
+                    | sys::ImGuiDockNodeFlags_PassthruCentralNode as i32,
+            );
+            sys::igDockBuilderSetNodeSize(dockspace_id, (*viewport).WorkSize);
+
+            let mut remaining = dockspace_id;
+            for split in &self.splits {
+                let dir = match split.side {
+                    DockSide::Left => sys::ImGuiDir_Left,
+                    DockSide::Right => sys::ImGuiDir_Right,
+                    DockSide::Top => sys::ImGuiDir_Up,
+                    DockSide::Bottom => sys::ImGuiDir_Down,
+                };
+
+                let mut node_at_dir: sys::ImGuiID = 0;
+                let mut node_other: sys::ImGuiID = 0;
+                sys::igDockBuilderSplitNode(
+                    remaining,
+                    dir,
+                    split.ratio,
+                    &mut node_at_dir,
+                    &mut node_other,
+                );
+                remaining = node_other;
+
+                for window in &split.windows {
+                    if let Ok(name) = CString::new(window.as_str()) {
+                        sys::igDockBuilderDockWindow(name.as_ptr(), node_at_dir);
+                    }
+                }
+            }
+
+            for window in &self.center {
+                if let Ok(name) = CString::new(window.as_str()) {
+                    sys::igDockBuilderDockWindow(name.as_ptr(), remaining);
+                }
+            }
+
+            sys::igDockBuilderFinish(dockspace_id);
+        }
+    }
+}
+
+/// Submit the passthrough dockspace over the main window for this frame.
+///
+/// Returns the dockspace id, and whether it already has a saved split
+/// layout (in which case the default layout should not be applied).
+#[cfg(feature = "docking")]
+pub(crate) fn submit_main_dockspace() -> (imgui::sys::ImGuiID, bool) {
+    use imgui::sys;
+
+    unsafe {
+        let dockspace_id = sys::igDockSpaceOverViewport(
+            sys::igGetMainViewport(),
+            sys::ImGuiDockNodeFlags_PassthruCentralNode as i32,
+            std::ptr::null(),
+        );
+        let node = sys::igDockBuilderGetNode(dockspace_id);
+        let has_layout = !node.is_null() && !(*node).ChildNodes[0].is_null();
+        (dockspace_id, has_layout)
+    }
+}
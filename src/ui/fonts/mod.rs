@@ -0,0 +1,145 @@
+//! # Font Fallback
+//!
+//! The default ImGui font only covers ASCII and Latin-1, so Greek symbols,
+//! Cyrillic, CJK or emoji in simulation UIs render as `?` boxes. Fallback
+//! fonts are merged into the UI font and supply the glyphs it lacks, limited
+//! to the [`GlyphRange`] they are added for; glyphs the UI font already has
+//! are never replaced.
+//!
+//! By default the bundled Roboto is merged for Latin Extended, Greek,
+//! Cyrillic and common math symbols (see [`FallbackFont::builtin`]). Other
+//! scripts need a font that covers them:
+//!
+//! ```no_run
+//! use haggis::ui::fonts::{FallbackFont, GlyphRange};
+//!
+//! let font = std::fs::read("fonts/NotoSansJP-Regular.ttf").expect("font file");
+//! let mut app = haggis::default();
+//! app.add_ui_fallback_font(FallbackFont::new(font.leak(), GlyphRange::Japanese));
+//! ```
+//!
+//! Every glyph in a range is rasterized into the font atlas, so prefer
+//! [`GlyphRange::ChineseSimplifiedCommon`] over [`GlyphRange::ChineseFull`]
+//! unless the full set is needed. Emoji are drawn in the text color; use a
+//! monochrome emoji font such as Noto Emoji.
+
+use imgui::{FontConfig, FontGlyphRanges, FontSource};
+
+/// Roboto, bundled as the default fallback font
+pub const ROBOTO: &[u8] = include_bytes!("roboto.ttf");
+
+/// Ranges of the built-in fallback font
+const EXTENDED_RANGES: &[u32] = &[
+    0x0100, 0x024F, // Latin Extended-A and B
+    0x0370, 0x03FF, // Greek
+    0x0400, 0x052F, // Cyrillic
+    0x1E00, 0x1EFF, // Latin Extended Additional
+    0x2000, 0x206F, // General Punctuation
+    0x2070, 0x209F, // Superscripts and Subscripts
+    0x2100, 0x214F, // Letterlike Symbols
+    0x2200, 0x22FF, // Mathematical Operators
+    0,
+];
+const GREEK_RANGES: &[u32] = &[0x0020, 0x00FF, 0x0370, 0x03FF, 0];
+const MATH_RANGES: &[u32] = &[
+    0x2070, 0x209F, // Superscripts and Subscripts
+    0x2190, 0x21FF, // Arrows
+    0x2200, 0x22FF, // Mathematical Operators
+    0x27C0, 0x27EF, // Miscellaneous Mathematical Symbols-A
+    0,
+];
+const EMOJI_RANGES: &[u32] = &[
+    0x2600, 0x27BF, // Miscellaneous Symbols and Dingbats
+    0x1F300, 0x1F5FF, // Miscellaneous Symbols and Pictographs
+    0x1F600, 0x1F64F, // Emoticons
+    0x1F680, 0x1F6FF, // Transport and Map Symbols
+    0x1F900, 0x1F9FF, // Supplemental Symbols and Pictographs
+    0,
+];
+
+/// Set of Unicode characters a font contributes to the atlas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlyphRange {
+    /// Basic Latin and Latin-1
+    Default,
+    /// Latin Extended, Greek, Cyrillic, punctuation and math operators
+    Extended,
+    Greek,
+    Cyrillic,
+    /// Arrows, super- and subscripts and mathematical operators
+    Math,
+    Japanese,
+    Korean,
+    /// The 2500 most common simplified Chinese ideograms
+    ChineseSimplifiedCommon,
+    /// All Chinese ideograms, a very large atlas
+    ChineseFull,
+    Thai,
+    Vietnamese,
+    /// Pictographs, emoticons and dingbats
+    Emoji,
+    /// Pairs of inclusive first and last code points, terminated by 0
+    Custom(&'static [u32]),
+}
+
+impl GlyphRange {
+    /// ImGui glyph ranges for this set
+    pub fn glyph_ranges(self) -> FontGlyphRanges {
+        match self {
+            GlyphRange::Default => FontGlyphRanges::default(),
+            GlyphRange::Extended => FontGlyphRanges::from_slice(EXTENDED_RANGES),
+            GlyphRange::Greek => FontGlyphRanges::from_slice(GREEK_RANGES),
+            GlyphRange::Cyrillic => FontGlyphRanges::cyrillic(),
+            GlyphRange::Math => FontGlyphRanges::from_slice(MATH_RANGES),
+            GlyphRange::Japanese => FontGlyphRanges::japanese(),
+            GlyphRange::Korean => FontGlyphRanges::korean(),
+            GlyphRange::ChineseSimplifiedCommon => FontGlyphRanges::chinese_simplified_common(),
+            GlyphRange::ChineseFull => FontGlyphRanges::chinese_full(),
+            GlyphRange::Thai => FontGlyphRanges::thai(),
+            GlyphRange::Vietnamese => FontGlyphRanges::vietnamese(),
+            GlyphRange::Emoji => FontGlyphRanges::from_slice(EMOJI_RANGES),
+            GlyphRange::Custom(ranges) => FontGlyphRanges::from_slice(ranges),
+        }
+    }
+}
+
+/// TTF font merged into the UI font for the glyphs of one range
+#[derive(Debug, Clone)]
+pub struct FallbackFont {
+    pub data: &'static [u8],
+    pub range: GlyphRange,
+    /// Size relative to the UI font, to match differing font metrics
+    pub size_scale: f32,
+}
+
+impl FallbackFont {
+    /// Fallback font for `range` at the size of the UI font
+    pub fn new(data: &'static [u8], range: GlyphRange) -> Self {
+        Self {
+            data,
+            range,
+            size_scale: 1.0,
+        }
+    }
+
+    /// Bundled Roboto for Latin Extended, Greek, Cyrillic and math symbols
+    pub fn builtin() -> Self {
+        Self::new(ROBOTO, GlyphRange::Extended)
+    }
+
+    /// Font source merging this font at the given UI font size
+    pub(crate) fn source(&self, size_pixels: f32) -> FontSource<'static> {
+        let size_pixels = (size_pixels * self.size_scale).round();
+        FontSource::TtfData {
+            data: self.data,
+            size_pixels,
+            config: Some(FontConfig {
+                oversample_h: 1,
+                pixel_snap_h: true,
+                size_pixels,
+                glyph_ranges: self.range.glyph_ranges(),
+                ..Default::default()
+            }),
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! # Localization
+//!
+//! A translation hook for UI text. UI code looks up display strings with
+//! [`tr`], which returns the translation from the installed translator or
+//! the key itself when there is none, so untranslated UIs keep working.
+//!
+//! [`StringTable`] is a ready-made translator read from TOML-style files of
+//! quoted strings; tables prefix the keys that follow them:
+//!
+//! ```toml
+//! title = "Симуляция жидкости"
+//!
+//! [controls]
+//! reset = "Сброс"
+//! viscosity = "Вязкость ν"
+//! ```
+//!
+//! Any other source (gettext catalogs, Fluent bundles) can be plugged in
+//! with [`set_translator`]. Text outside the default font's Latin-1 range
+//! also needs a font that covers it, see [`fonts`](super::fonts).
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::ui::localization::{tr, StringTable};
+//!
+//! let mut app = haggis::default();
+//! StringTable::load("lang/ru.toml").expect("string table").install();
+//! app.set_ui(|ui, _scene, _selected| {
+//!     ui.window(tr("title")).build(|| {
+//!         if ui.button(tr("controls.reset")) {
+//!             // ...
+//!         }
+//!     });
+//! });
+//! ```
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::RwLock;
+
+use crate::error::{HaggisError, Result};
+use crate::util::toml_lite::split_string;
+
+type Translator = Box<dyn Fn(&str) -> Option<String> + Send + Sync>;
+
+static TRANSLATOR: RwLock<Option<Translator>> = RwLock::new(None);
+
+/// Install the function translating UI strings, replacing any previous one
+///
+/// The translator returns `None` for keys it has no translation for.
+pub fn set_translator(translator: impl Fn(&str) -> Option<String> + Send + Sync + 'static) {
+    *TRANSLATOR
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(Box::new(translator));
+}
+
+/// Remove the installed translator, so [`tr`] returns keys unchanged
+pub fn clear_translator() {
+    *TRANSLATOR
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = None;
+}
+
+/// Translation of `key`, or `key` itself if it has none
+pub fn tr(key: &str) -> String {
+    TRANSLATOR
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .as_ref()
+        .and_then(|translator| translator(key))
+        .unwrap_or_else(|| key.to_string())
+}
+
+/// Map from keys to translated strings
+#[derive(Debug, Clone, Default)]
+pub struct StringTable {
+    strings: HashMap<String, String>,
+}
+
+impl StringTable {
+    /// Create an empty table
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or replace a translation
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.strings.insert(key.into(), value.into());
+    }
+
+    /// Translation of `key`, if the table has one
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.strings.get(key).map(String::as_str)
+    }
+
+    /// Number of translations
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Whether the table has no translations
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Add the translations of TOML-style `key = "value"` text
+    ///
+    /// `[table]` lines prefix the keys that follow with `table.`. Strings
+    /// support the TOML basic string escapes. Malformed lines are
+    /// errors, reported with their line number.
+    pub fn merge_toml(&mut self, text: &str) -> std::result::Result<(), String> {
+        let mut prefix = String::new();
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(table) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                prefix = format!("{}.", table.trim());
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(format!("line {line_number}: expected `key = \"value\"`"));
+            };
+            let value = parse_string(value.trim())
+                .ok_or_else(|| format!("line {line_number}: expected a quoted string"))?;
+            self.insert(format!("{prefix}{}", key.trim()), value);
+        }
+        Ok(())
+    }
+
+    /// Read a string table file
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::Io`] if the file cannot be read,
+    /// [`HaggisError::Localization`] if it is malformed, and
+    /// [`HaggisError::NoFileSystem`] on platforms without file access.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if !crate::platform::HAS_FILESYSTEM {
+            return Err(HaggisError::NoFileSystem { path: path.into() });
+        }
+        let text = std::fs::read_to_string(path).map_err(|source| HaggisError::Io {
+            path: path.into(),
+            source,
+        })?;
+        let mut table = Self::new();
+        table
+            .merge_toml(&text)
+            .map_err(|message| HaggisError::Localization {
+                path: path.into(),
+                message,
+            })?;
+        Ok(table)
+    }
+
+    /// Make this table the translator used by [`tr`]
+    pub fn install(self) {
+        set_translator(move |key| self.get(key).map(str::to_string));
+    }
+}
+
+/// Parse a quoted string, allowing a trailing comment
+fn parse_string(value: &str) -> Option<String> {
+    let (text, rest) = split_string(value)?;
+    let rest = rest.trim();
+    (rest.is_empty() || rest.starts_with('#')).then_some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_string_table_parsing() {
+        let mut table = StringTable::new();
+        table
+            .merge_toml(
+                "# Russian\ntitle = \"Симуляция\"\n\n[controls]\nreset = \"Сброс\" # button\nhint = \"a \\\"b\\\"\\nc\"\n",
+            )
+            .unwrap();
+        assert_eq!(table.len(), 3);
+        assert_eq!(table.get("title"), Some("Симуляция"));
+        assert_eq!(table.get("controls.reset"), Some("Сброс"));
+        assert_eq!(table.get("controls.hint"), Some("a \"b\"\nc"));
+
+        assert!(table.merge_toml("bare = value").is_err());
+        assert!(table.merge_toml("open = \"unterminated").is_err());
+        assert!(table.merge_toml("no value").is_err());
+    }
+
+    #[test]
+    fn test_tr_falls_back_to_key() {
+        let mut table = StringTable::new();
+        table.insert("test.greeting", "Привет");
+        table.install();
+        assert_eq!(tr("test.greeting"), "Привет");
+        assert_eq!(tr("test.missing"), "test.missing");
+        clear_translator();
+        assert_eq!(tr("test.greeting"), "test.greeting");
+    }
+}
@@ -4,22 +4,30 @@
 //! Handles ImGui integration with wgpu and winit, providing frame management,
 //! input handling, and rendering capabilities for the engine's user interface.
 
-use imgui::{Context, FontConfig, FontSource, MouseCursor, StyleColor};
+use imgui::{Context, FontConfig, FontSource, MouseCursor, Style, StyleColor};
 use imgui_wgpu::{Renderer, RendererConfig};
 use imgui_winit_support::{HiDpiMode, WinitPlatform};
-use std::time::Instant;
+use std::path::PathBuf;
+use crate::platform::Instant;
 use wgpu::{CommandEncoder, Device, Queue, TextureFormat, TextureView};
 use winit::{
     event::{Event, WindowEvent},
     window::Window,
 };
 
+use super::docking::DockLayout;
+use super::fonts::FallbackFont;
+use super::theme::UiTheme;
+
 /// Font configuration options
+///
+/// Sizes are logical pixels; fonts are rasterized at the window's scale
+/// factor times the UI scale.
 #[derive(Debug, Clone)]
 pub enum UiFont {
     /// Default ImGui font
     Default,
-    /// Custom TTF font from bytes
+    /// Custom TTF font from bytes, `size` in logical pixels
     Custom { data: &'static [u8], size: f32 },
     /// System monospace font (fallback to default if not available)
     Monospace,
@@ -58,24 +66,44 @@ impl Default for UiStyle {
     }
 }
 
+/// Logical size of the default font in pixels
+pub const DEFAULT_FONT_SIZE: f32 = 13.0;
+
+/// Smallest and largest user UI scale
+pub const UI_SCALE_RANGE: (f32, f32) = (0.5, 3.0);
+
 /// ImGui UI manager
 ///
 /// Manages ImGui context, platform integration, and rendering pipeline.
 /// Handles input capture, frame timing, and coordinate scaling for proper
 /// UI display across different DPI settings.
+///
+/// ImGui works in physical pixels. Fonts and style metrics are scaled by the
+/// window's scale factor times the user UI scale, and rebuilt when either
+/// changes (e.g. when the window moves to a monitor with a different DPI).
 pub struct UiManager {
     pub context: Context,
     platform: WinitPlatform,
     renderer: Renderer,
+    font: UiFont,
+    fallback_fonts: Vec<FallbackFont>,
+    /// Style before scaling, so metrics can be rescaled without drift
+    base_style: Style,
+    scale_factor: f32,
+    ui_scale: f32,
     last_frame: Instant,
     last_cursor: Option<MouseCursor>,
+    default_layout: Option<DockLayout>,
+    layout_pending: bool,
+    force_layout: bool,
 }
 
 impl UiManager {
     /// Creates a new UI manager
     ///
     /// Sets up ImGui with proper DPI handling and font configuration.
-    /// Uses locked DPI mode to prevent automatic scaling conflicts.
+    /// Uses locked DPI mode so ImGui coordinates are physical pixels; fonts
+    /// and style are scaled by the window's scale factor instead.
     ///
     /// # Arguments
     /// * `device` - WGPU device for creating renderer resources
@@ -84,6 +112,7 @@ impl UiManager {
     /// * `window` - Window for platform integration
     /// * `style` - UI color theme to apply
     /// * `font` - Font configuration to use
+    /// * `fallback_fonts` - Fonts merged into `font` for glyphs it lacks
     pub fn new(
         device: &Device,
         queue: &Queue,
@@ -91,9 +120,16 @@ impl UiManager {
         window: &Window,
         style: UiStyle,
         font: UiFont,
+        fallback_fonts: Vec<FallbackFont>,
     ) -> Self {
         let mut context = Context::create();
         context.set_ini_filename(None);
+
+        // Dockable panels over a passthrough dockspace covering the window
+        #[cfg(feature = "docking")]
+        {
+            context.io_mut().config_flags |= imgui::ConfigFlags::DOCKING_ENABLE;
+        }
         
         // Apply selected color theme
         Self::apply_style(&mut context, style);
@@ -102,12 +138,22 @@ impl UiManager {
         let mut platform = WinitPlatform::new(&mut context);
         platform.attach_window(context.io_mut(), window, HiDpiMode::Locked(1.0));
 
-        // Configure fonts
-        Self::apply_font(&mut context, font);
+        // Configure fonts and metrics for the window's monitor
+        let scale_factor = window.scale_factor() as f32;
+        let base_style = *context.style();
+        context.style_mut().scale_all_sizes(scale_factor);
+        Self::apply_font(&mut context, &font, &fallback_fonts, scale_factor);
 
+        // ImGui colors are sRGB: pass them through to plain targets and
+        // linearize them for sRGB targets, which encode on write
+        let shaders = if output_color_format.is_srgb() {
+            RendererConfig::new()
+        } else {
+            RendererConfig::new_srgb()
+        };
         let renderer_config = RendererConfig {
             texture_format: output_color_format,
-            ..Default::default()
+            ..shaders
         };
         let renderer = Renderer::new(&mut context, device, queue, renderer_config);
 
@@ -115,27 +161,168 @@ impl UiManager {
             context,
             platform,
             renderer,
+            font,
+            fallback_fonts,
+            base_style,
+            scale_factor,
+            ui_scale: 1.0,
             last_frame: Instant::now(),
             last_cursor: None,
+            default_layout: None,
+            layout_pending: false,
+            force_layout: false,
         }
     }
 
-    /// Applies the specified font configuration to the ImGui context
-    fn apply_font(context: &mut Context, font: UiFont) {
-        match font {
+    /// Sets the file used to persist window and docking layouts
+    ///
+    /// ImGui loads the file on the first frame and saves changes back to it
+    /// periodically. `None` disables persistence (the default).
+    ///
+    /// # Arguments
+    /// * `path` - Path of the ini file, or `None` to disable persistence
+    pub fn set_layout_file(&mut self, path: Option<PathBuf>) {
+        // No file system in the browser
+        #[cfg(target_arch = "wasm32")]
+        let path: Option<PathBuf> = path.and(None);
+
+        self.context.set_ini_filename(path);
+    }
+
+    /// Replaces the window and docking layout with saved ImGui ini settings
+    pub fn load_layout(&mut self, ini: &str) {
+        self.context.load_ini_settings(ini);
+    }
+
+    /// Current window and docking layout as ImGui ini settings
+    pub fn save_layout(&mut self) -> String {
+        let mut ini = String::new();
+        self.context.save_ini_settings(&mut ini);
+        ini
+    }
+
+    /// Sets the default docking layout
+    ///
+    /// The layout is applied on the next frame unless a saved layout was
+    /// loaded from the layout file. Has no effect without the `docking` feature.
+    ///
+    /// # Arguments
+    /// * `layout` - Layout to apply, or `None` to leave windows floating
+    pub fn set_default_layout(&mut self, layout: Option<DockLayout>) {
+        self.layout_pending = layout.is_some();
+        self.default_layout = layout;
+    }
+
+    /// Re-applies the default docking layout on the next frame,
+    /// discarding any layout the user arranged
+    pub fn reset_layout(&mut self) {
+        if self.default_layout.is_some() {
+            self.layout_pending = true;
+            self.force_layout = true;
+        }
+    }
+
+    /// Returns the window scale factor the UI is rasterized for
+    pub fn scale_factor(&self) -> f32 {
+        self.scale_factor
+    }
+
+    /// Returns the user UI scale
+    pub fn ui_scale(&self) -> f32 {
+        self.ui_scale
+    }
+
+    /// Returns the combined scale of fonts and style metrics
+    pub fn effective_scale(&self) -> f32 {
+        self.scale_factor * self.ui_scale
+    }
+
+    /// Updates the window scale factor, rebuilding fonts and style if it changed
+    ///
+    /// Call on `WindowEvent::ScaleFactorChanged`, outside of a UI frame.
+    ///
+    /// # Arguments
+    /// * `device` - WGPU device for uploading the font atlas
+    /// * `queue` - WGPU queue for uploading the font atlas
+    /// * `scale_factor` - New scale factor of the window
+    pub fn set_scale_factor(&mut self, device: &Device, queue: &Queue, scale_factor: f32) {
+        if scale_factor > 0.0 && scale_factor != self.scale_factor {
+            self.scale_factor = scale_factor;
+            self.rebuild_scale(device, queue);
+        }
+    }
+
+    /// Sets the user UI scale, rebuilding fonts and style if it changed
+    ///
+    /// The scale multiplies the window scale factor and is clamped to
+    /// [`UI_SCALE_RANGE`]. Must be called outside of a UI frame.
+    ///
+    /// # Arguments
+    /// * `device` - WGPU device for uploading the font atlas
+    /// * `queue` - WGPU queue for uploading the font atlas
+    /// * `ui_scale` - UI scale, 1.0 for the platform's native size
+    pub fn set_ui_scale(&mut self, device: &Device, queue: &Queue, ui_scale: f32) {
+        let ui_scale = ui_scale.clamp(UI_SCALE_RANGE.0, UI_SCALE_RANGE.1);
+        if ui_scale != self.ui_scale {
+            self.ui_scale = ui_scale;
+            self.rebuild_scale(device, queue);
+        }
+    }
+
+    /// Returns the current theme, with metrics in logical pixels
+    pub fn theme(&self) -> UiTheme {
+        UiTheme::from_style(&self.base_style)
+    }
+
+    /// Replaces the UI theme
+    ///
+    /// Takes effect on the next frame; metrics are scaled like the rest of
+    /// the UI.
+    ///
+    /// # Arguments
+    /// * `theme` - Theme to apply, with metrics in logical pixels
+    pub fn set_theme(&mut self, theme: &UiTheme) {
+        theme.apply(&mut self.base_style);
+        self.apply_scaled_style();
+    }
+
+    /// Writes the base style, scaled by the effective scale, to the context
+    fn apply_scaled_style(&mut self) {
+        let mut style = self.base_style;
+        style.scale_all_sizes(self.effective_scale());
+        *self.context.style_mut() = style;
+    }
+
+    /// Re-rasterizes fonts and rescales style metrics for the current scale
+    fn rebuild_scale(&mut self, device: &Device, queue: &Queue) {
+        let scale = self.effective_scale();
+        self.apply_scaled_style();
+
+        self.context.fonts().clear();
+        Self::apply_font(&mut self.context, &self.font, &self.fallback_fonts, scale);
+        self.renderer
+            .reload_font_texture(&mut self.context, device, queue);
+    }
+
+    /// Applies the specified font configuration to the ImGui context,
+    /// merging the fallback fonts into it
+    fn apply_font(context: &mut Context, font: &UiFont, fallback_fonts: &[FallbackFont], scale: f32) {
+        let (main, font_size) = match *font {
             UiFont::Default => {
-                let font_size = 24.0;
-                context.fonts().add_font(&[FontSource::DefaultFontData {
+                let font_size = (DEFAULT_FONT_SIZE * scale).round();
+                let source = FontSource::DefaultFontData {
                     config: Some(FontConfig {
                         oversample_h: 1,
                         pixel_snap_h: true,
                         size_pixels: font_size,
                         ..Default::default()
                     }),
-                }]);
+                };
+                (source, font_size)
             }
             UiFont::Custom { data, size } => {
-                context.fonts().add_font(&[FontSource::TtfData {
+                let size = (size * scale).round();
+                let source = FontSource::TtfData {
                     data,
                     size_pixels: size,
                     config: Some(FontConfig {
@@ -144,12 +331,13 @@ impl UiManager {
                         size_pixels: size,
                         ..Default::default()
                     }),
-                }]);
+                };
+                (source, size)
             }
             UiFont::Monospace => {
                 // Try to use a monospace font, fallback to default with monospace hint
-                let font_size = 24.0;
-                context.fonts().add_font(&[FontSource::DefaultFontData {
+                let font_size = (DEFAULT_FONT_SIZE * scale).round();
+                let source = FontSource::DefaultFontData {
                     config: Some(FontConfig {
                         oversample_h: 1,
                         pixel_snap_h: true,
@@ -158,9 +346,16 @@ impl UiManager {
                         // but this serves as a placeholder for future enhancement
                         ..Default::default()
                     }),
-                }]);
+                };
+                (source, font_size)
             }
-        }
+        };
+
+        // Sources after the first are merged into it
+        let sources: Vec<FontSource> = std::iter::once(main)
+            .chain(fallback_fonts.iter().map(|fallback| fallback.source(font_size)))
+            .collect();
+        context.fonts().add_font(&sources);
     }
 
     /// Applies the specified UI style to the ImGui context
@@ -232,6 +427,11 @@ impl UiManager {
         self.context.io().display_size
     }
 
+    /// Returns whether ImGui wants exclusive keyboard input (e.g. a focused text field)
+    pub fn wants_keyboard(&self) -> bool {
+        self.context.io().want_capture_keyboard
+    }
+
     /// Handles input events and returns whether UI captured them
     ///
     /// Processes mouse and keyboard events through ImGui's input system.
@@ -297,6 +497,21 @@ impl UiManager {
 
         // Build UI
         let ui = self.context.frame();
+
+        #[cfg(feature = "docking")]
+        {
+            let (dockspace_id, has_saved_layout) = super::docking::submit_main_dockspace();
+            if self.layout_pending {
+                if let Some(layout) = &self.default_layout {
+                    if self.force_layout || !has_saved_layout {
+                        layout.build(dockspace_id);
+                    }
+                }
+                self.layout_pending = false;
+                self.force_layout = false;
+            }
+        }
+
         run_ui(&ui);
 
         // Handle cursor changes
@@ -309,6 +524,14 @@ impl UiManager {
         io.want_capture_mouse || io.want_capture_keyboard
     }
 
+    /// Ends the frame built by `update_logic()` without drawing it
+    ///
+    /// Used when a frame is skipped (e.g. the surface is being reconfigured),
+    /// since ImGui requires every frame to be ended before the next one starts.
+    pub fn discard_frame(&mut self) {
+        self.context.render();
+    }
+
     /// Renders the UI overlay to the specified render target
     ///
     /// Renders the UI built in the last `update_logic()` call to the
@@ -356,6 +579,61 @@ impl UiManager {
             .expect("Failed to render ImGui");
     }
 
+    /// Registers an externally rendered texture for use with `ui.image(...)`
+    ///
+    /// Wraps the given wgpu texture so the ImGui renderer can sample it. If
+    /// `existing` is given, that texture slot is replaced (e.g. after a resize)
+    /// and its id is kept.
+    ///
+    /// # Arguments
+    /// * `device` - WGPU device for creating the sampler and bind group
+    /// * `texture` - Texture to display, must have `TEXTURE_BINDING` usage
+    /// * `view` - View of the texture
+    /// * `size` - Texture size in pixels
+    /// * `existing` - Texture id to replace, if already registered
+    ///
+    /// # Returns
+    /// ImGui texture id to pass to `imgui::Image`
+    pub fn register_texture(
+        &mut self,
+        device: &Device,
+        texture: std::sync::Arc<wgpu::Texture>,
+        view: std::sync::Arc<TextureView>,
+        size: (u32, u32),
+        existing: Option<imgui::TextureId>,
+    ) -> imgui::TextureId {
+        let config = imgui_wgpu::RawTextureConfig {
+            label: Some("Render Texture Bind Group"),
+            sampler_desc: wgpu::SamplerDescriptor {
+                label: Some("Render Texture Sampler"),
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            },
+        };
+        let imgui_texture = imgui_wgpu::Texture::from_raw_parts(
+            device,
+            &self.renderer,
+            texture,
+            view,
+            None,
+            Some(&config),
+            wgpu::Extent3d {
+                width: size.0,
+                height: size.1,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        match existing {
+            Some(id) => {
+                self.renderer.textures.replace(id, imgui_texture);
+                id
+            }
+            None => self.renderer.textures.insert(imgui_texture),
+        }
+    }
+
     /// Convenience method that combines update and render
     ///
     /// Equivalent to calling `update_logic()` followed by `render_display_only()`.
@@ -15,8 +15,15 @@
 //! ## Key Components
 //!
 //! - [`UiManager`] - Core UI manager that handles ImGui integration
+//! - [`DockLayout`] - Default docking layout for panels (`docking` feature)
 //! - [`panel`] - Pre-built UI panels for common operations
+//! - [`notifications`] - Toasts for messages, warnings and non-fatal errors
 //! - [`default_transform_panel`] - Default object transform editor
+//! - [`UiTheme`] - Complete UI style with TOML load/save
+//! - [`ThemeEditor`] - Panel for editing the theme with live preview
+//! - [`BufferInspector`] - Panel listing GPU buffers and reading slices back
+//! - [`fonts`] - Fallback fonts for Greek, Cyrillic, CJK and emoji text
+//! - [`localization`] - Translation hook for UI strings
 //!
 //! ## Usage
 //!
@@ -48,9 +55,22 @@
 //!
 //! [`HaggisApp`]: crate::app::HaggisApp
 
+pub mod buffer_inspector;
+pub mod docking;
+pub mod fonts;
+pub mod localization;
 pub mod manager;
+pub mod notifications;
 pub mod panel;
+pub mod theme;
+pub mod theme_editor;
 
 // Re-export main types
-pub use manager::{UiFont, UiManager, UiStyle};
+pub use buffer_inspector::BufferInspector;
+pub use docking::{DockLayout, DockSide};
+pub use fonts::{FallbackFont, GlyphRange};
+pub use localization::tr;
+pub use manager::{UiFont, UiManager, UiStyle, DEFAULT_FONT_SIZE, UI_SCALE_RANGE};
 pub use panel::default_transform_panel;
+pub use theme::UiTheme;
+pub use theme_editor::ThemeEditor;
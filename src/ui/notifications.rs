@@ -0,0 +1,334 @@
+//! In-app notifications
+//!
+//! Non-fatal problems (a model that failed to load, a lost GPU device that was
+//! recreated, a software GPU adapter, errors reported by simulations) and
+//! status messages are shown as toasts stacked in the bottom-right corner,
+//! instead of only going to the terminal. Info and warning toasts fade out
+//! after a few seconds; errors stay until clicked. Hovering a toast keeps it
+//! on screen. Every notification is also logged and kept in a short history.
+//!
+//! Apps post notifications with `app.notify(...)`, simulations through the
+//! event bus:
+//!
+//! ```no_run
+//! # use haggis::gfx::scene::Scene;
+//! use haggis::ui::notifications::Notification;
+//!
+//! # fn step(scene: &mut Scene) {
+//! scene.events.emit(Notification::warning("Timestep too large, clamping to 0.01"));
+//! # }
+//! ```
+
+use imgui::Ui;
+
+/// Maximum number of notifications kept
+const MAX_NOTIFICATIONS: usize = 50;
+/// Maximum number of toasts on screen
+const MAX_TOASTS: usize = 5;
+/// Seconds a toast takes to fade out
+const FADE_TIME: f32 = 0.5;
+
+/// Severity of a notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationLevel {
+    Info,
+    Warning,
+    Error,
+}
+
+impl NotificationLevel {
+    /// Seconds a toast of this level stays on screen, `None` until clicked
+    pub fn toast_duration(self) -> Option<f32> {
+        match self {
+            NotificationLevel::Info => Some(3.0),
+            NotificationLevel::Warning => Some(6.0),
+            NotificationLevel::Error => None,
+        }
+    }
+
+    /// Text color of the level
+    fn color(self) -> [f32; 4] {
+        match self {
+            NotificationLevel::Info => [0.9, 0.9, 0.9, 1.0],
+            NotificationLevel::Warning => [1.0, 0.8, 0.3, 1.0],
+            NotificationLevel::Error => [1.0, 0.4, 0.4, 1.0],
+        }
+    }
+}
+
+/// A message shown as a toast
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub level: NotificationLevel,
+    pub message: String,
+}
+
+impl Notification {
+    /// A message of the given level
+    pub fn new(level: NotificationLevel, message: impl Into<String>) -> Self {
+        Self {
+            level,
+            message: message.into(),
+        }
+    }
+
+    /// An informational message
+    pub fn info(message: impl Into<String>) -> Self {
+        Self {
+            level: NotificationLevel::Info,
+            message: message.into(),
+        }
+    }
+
+    /// A warning
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self {
+            level: NotificationLevel::Warning,
+            message: message.into(),
+        }
+    }
+
+    /// An error
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            level: NotificationLevel::Error,
+            message: message.into(),
+        }
+    }
+}
+
+/// A notification with its toast state
+struct Entry {
+    notification: Notification,
+    /// Number of times it was repeated in a row
+    count: u32,
+    /// Unique id, keeps the toast window stable while the stack shifts
+    id: u64,
+    /// Seconds left on screen, `None` until dismissed
+    remaining: Option<f32>,
+    /// Whether the toast is still shown
+    shown: bool,
+    /// Whether the mouse was over the toast last frame
+    hovered: bool,
+}
+
+/// Notification history and toast state
+#[derive(Default)]
+pub struct Notifications {
+    entries: Vec<Entry>,
+    next_id: u64,
+}
+
+impl Notifications {
+    /// Create an empty notification list
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a notification and show it as a toast; a repeat of the latest one
+    /// only bumps its count and shows it again
+    pub fn push(&mut self, notification: Notification) {
+        match notification.level {
+            NotificationLevel::Info => tracing::info!("{}", notification.message),
+            NotificationLevel::Warning => tracing::warn!("{}", notification.message),
+            NotificationLevel::Error => tracing::error!("{}", notification.message),
+        }
+
+        let remaining = notification.level.toast_duration();
+        if let Some(last) = self.entries.last_mut() {
+            if last.notification == notification {
+                last.count += 1;
+                last.remaining = remaining;
+                last.shown = true;
+                return;
+            }
+        }
+
+        self.entries.push(Entry {
+            notification,
+            count: 1,
+            id: self.next_id,
+            remaining,
+            shown: true,
+            hovered: false,
+        });
+        self.next_id += 1;
+        if self.entries.len() > MAX_NOTIFICATIONS {
+            self.entries.remove(0);
+        }
+    }
+
+    /// Add an info notification
+    pub fn info(&mut self, message: impl Into<String>) {
+        self.push(Notification::info(message));
+    }
+
+    /// Add an error notification
+    pub fn error(&mut self, message: impl Into<String>) {
+        self.push(Notification::error(message));
+    }
+
+    /// Add a warning notification
+    pub fn warning(&mut self, message: impl Into<String>) {
+        self.push(Notification::warning(message));
+    }
+
+    /// Current notifications, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &Notification> {
+        self.entries.iter().map(|entry| &entry.notification)
+    }
+
+    /// Notifications currently shown as toasts, oldest first
+    pub fn toasts(&self) -> impl Iterator<Item = &Notification> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.shown)
+            .map(|entry| &entry.notification)
+    }
+
+    /// Number of notifications
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether there are no notifications
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Forget all notifications
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Hide all toasts, keeping the history
+    pub fn dismiss_all(&mut self) {
+        for entry in &mut self.entries {
+            entry.shown = false;
+        }
+    }
+
+    /// Count down toast timers by `delta_time` seconds
+    ///
+    /// Toasts under the mouse don't expire.
+    pub fn update(&mut self, delta_time: f32) {
+        for entry in self
+            .entries
+            .iter_mut()
+            .filter(|entry| entry.shown && !entry.hovered)
+        {
+            if let Some(remaining) = entry.remaining.as_mut() {
+                *remaining -= delta_time;
+                if *remaining <= 0.0 {
+                    entry.shown = false;
+                }
+            }
+        }
+    }
+
+    /// Draw the toasts, newest at the bottom-right corner
+    pub fn render_ui(&mut self, ui: &Ui) {
+        self.update(ui.io().delta_time);
+
+        let display_size = ui.io().display_size;
+        let mut bottom = display_size[1] - 10.0;
+        let mut dismissed = None;
+
+        for entry in self
+            .entries
+            .iter_mut()
+            .rev()
+            .filter(|entry| entry.shown)
+            .take(MAX_TOASTS)
+        {
+            let fade = entry
+                .remaining
+                .map_or(1.0, |remaining| (remaining / FADE_TIME).clamp(0.0, 1.0));
+            let mut color = entry.notification.level.color();
+            color[3] *= fade;
+
+            let mut height = 0.0;
+            ui.window(format!("##toast_{}", entry.id))
+                .position([display_size[0] - 10.0, bottom], imgui::Condition::Always)
+                .position_pivot([1.0, 1.0])
+                .size([360.0, 0.0], imgui::Condition::Always)
+                .bg_alpha(0.85 * fade)
+                .no_decoration()
+                .no_nav()
+                .focus_on_appearing(false)
+                .build(|| {
+                    let _wrap = ui.push_text_wrap_pos_with_pos(0.0);
+                    if entry.count > 1 {
+                        ui.text_colored(
+                            color,
+                            format!("{} (x{})", entry.notification.message, entry.count),
+                        );
+                    } else {
+                        ui.text_colored(color, &entry.notification.message);
+                    }
+                    entry.hovered = ui.is_window_hovered();
+                    if entry.hovered && ui.is_mouse_clicked(imgui::MouseButton::Left) {
+                        dismissed = Some(entry.id);
+                    }
+                    height = ui.window_size()[1];
+                });
+            bottom -= height + 6.0;
+        }
+
+        if let Some(id) = dismissed {
+            if let Some(entry) = self.entries.iter_mut().find(|entry| entry.id == id) {
+                entry.shown = false;
+                entry.hovered = false;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repeats_are_collapsed_and_list_is_capped() {
+        let mut notifications = Notifications::new();
+        notifications.error("device lost");
+        notifications.error("device lost");
+        notifications.warning("device lost");
+        assert_eq!(notifications.len(), 2);
+        assert_eq!(notifications.entries[0].count, 2);
+
+        for i in 0..MAX_NOTIFICATIONS {
+            notifications.error(format!("error {}", i));
+        }
+        assert_eq!(notifications.len(), MAX_NOTIFICATIONS);
+        assert_eq!(notifications.iter().next().unwrap().message, "error 0");
+    }
+
+    #[test]
+    fn test_toasts_expire_by_level() {
+        let mut notifications = Notifications::new();
+        notifications.info("checkpoint saved");
+        notifications.warning("software adapter");
+        notifications.error("model not found");
+        assert_eq!(notifications.toasts().count(), 3);
+
+        notifications.update(4.0);
+        let shown: Vec<_> = notifications.toasts().map(|n| n.level).collect();
+        assert_eq!(
+            shown,
+            vec![NotificationLevel::Warning, NotificationLevel::Error]
+        );
+
+        notifications.update(4.0);
+        assert_eq!(notifications.toasts().count(), 1);
+        // A repeat shows the toast again
+        notifications.error("model not found");
+        notifications.warning("software adapter");
+        assert_eq!(notifications.toasts().count(), 2);
+        assert_eq!(notifications.len(), 4);
+
+        notifications.dismiss_all();
+        assert_eq!(notifications.toasts().count(), 0);
+        assert_eq!(notifications.len(), 4);
+    }
+}
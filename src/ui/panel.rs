@@ -4,12 +4,17 @@
 //! Provides pre-built UI panels for common engine functionality like object
 //! transforms, material editing, and scene management.
 
-use crate::gfx::scene::{object::UiTransformState, scene::Scene};
+use crate::gfx::scene::{
+    layers::Layers,
+    object::{Shading, UiTransformState},
+    scene::Scene,
+};
 
 /// Default transform panel for object manipulation
 ///
 /// Provides a comprehensive UI for selecting objects and editing their transforms,
-/// visibility, and viewing object statistics. This is the main panel users will
+/// visibility and layers, showing or hiding whole layers, and viewing object
+/// statistics. This is the main panel users will
 /// interact with for basic scene editing.
 ///
 /// # Arguments
@@ -37,6 +42,7 @@ pub fn default_transform_panel(
         .collapsible(true)
         .build(|| {
             render_object_list(ui, scene, selected_index);
+            render_layer_toggles(ui, scene);
             ui.separator();
             render_transform_controls(ui, scene, selected_index);
         });
@@ -76,12 +82,26 @@ fn render_object_list(ui: &imgui::Ui, scene: &mut Scene, selected_index: &mut Op
     }
 }
 
+/// Renders visibility toggles for the layers in use
+fn render_layer_toggles(ui: &imgui::Ui, scene: &mut Scene) {
+    if ui.collapsing_header("Layers", imgui::TreeNodeFlags::empty()) {
+        let used = scene.used_layers();
+        scene.layers.render_ui(ui, used);
+    }
+}
+
 /// Renders transform controls for the selected object
 fn render_transform_controls(
     ui: &imgui::Ui,
     scene: &mut Scene,
     selected_index: &mut Option<usize>,
 ) {
+    let layer_names: Vec<(u32, String)> = scene
+        .used_layers()
+        .indices()
+        .map(|index| (index, scene.layers.name(index).to_string()))
+        .collect();
+
     if let Some(selected_idx) = *selected_index {
         if let Some(object) = scene.get_object_mut(selected_idx) {
             ui.spacing();
@@ -93,6 +113,9 @@ fn render_transform_controls(
             render_rotation_controls(ui, &mut object.ui_transform);
             render_scale_controls(ui, &mut object.ui_transform);
             render_action_buttons(ui, &mut object.ui_transform, &mut object.visible);
+            render_shading_toggle(ui, object);
+            render_opacity_slider(ui, object);
+            render_layer_membership(ui, &layer_names, &mut object.layers);
             render_object_info(ui, object);
         }
     }
@@ -256,6 +279,40 @@ fn render_action_buttons(ui: &imgui::Ui, transform: &mut UiTransformState, visib
     ui.spacing();
 }
 
+/// Renders the toggle between smooth and flat shading
+fn render_shading_toggle(ui: &imgui::Ui, object: &mut crate::gfx::scene::object::Object) {
+    let mut flat = object.shading() == Shading::Flat;
+    if ui.checkbox("Flat Shading", &mut flat) {
+        object.set_shading(if flat { Shading::Flat } else { Shading::Smooth });
+    }
+    ui.spacing();
+}
+
+/// Renders the slider fading the object independently of its material and
+/// the ghost toggle
+fn render_opacity_slider(ui: &imgui::Ui, object: &mut crate::gfx::scene::object::Object) {
+    let mut opacity = object.opacity();
+    if ui.slider("Opacity", 0.0, 1.0, &mut opacity) {
+        object.set_opacity(opacity);
+    }
+    ui.checkbox("Ghost (X-ray)", &mut object.ghost);
+    ui.spacing();
+}
+
+/// Renders checkboxes for the layers the object belongs to
+fn render_layer_membership(ui: &imgui::Ui, layer_names: &[(u32, String)], layers: &mut Layers) {
+    if ui.collapsing_header("Object Layers", imgui::TreeNodeFlags::empty()) {
+        for (index, name) in layer_names {
+            let layer = Layers::layer(*index);
+            let mut member = layers.intersects(layer);
+            if ui.checkbox(format!("{name}##member{index}"), &mut member) {
+                *layers = if member { *layers | layer } else { *layers & !layer };
+            }
+        }
+    }
+    ui.spacing();
+}
+
 /// Renders object statistics information
 fn render_object_info(ui: &imgui::Ui, object: &crate::gfx::scene::object::Object) {
     ui.child_window("info_panel").border(true).build(|| {
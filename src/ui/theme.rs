@@ -0,0 +1,388 @@
+//! # UI Themes
+//!
+//! [`UiTheme`] holds the complete look of the UI: every ImGui style color
+//! plus the rounding, padding, spacing and border metrics. Themes are stored
+//! as TOML files with the metrics at the top level and the colors in a
+//! `[colors]` table keyed by ImGui color name:
+//!
+//! ```toml
+//! alpha = 1.0
+//! window_rounding = 6.0
+//! frame_padding = [6.0, 4.0]
+//!
+//! [colors]
+//! Text = [0.9, 0.9, 0.9, 1.0]
+//! WindowBg = [0.1, 0.1, 0.12, 0.95]
+//! ```
+//!
+//! A file only needs the values it changes; loading it over a theme keeps
+//! everything else. Metrics are in logical pixels and are scaled with the
+//! rest of the UI by the [`UiManager`](super::UiManager).
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! let mut app = haggis::default();
+//! app.set_ui_theme_file("themes/dusk.toml");
+//! app.show_theme_editor(true);
+//! ```
+
+use std::path::Path;
+
+use imgui::{Style, StyleColor};
+
+use crate::error::{HaggisError, Result};
+use crate::util::toml_lite::format_array;
+
+/// Complete UI style: colors, rounding, padding and spacing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UiTheme {
+    pub alpha: f32,
+    pub window_padding: [f32; 2],
+    pub window_rounding: f32,
+    pub window_border_size: f32,
+    pub child_rounding: f32,
+    pub child_border_size: f32,
+    pub popup_rounding: f32,
+    pub popup_border_size: f32,
+    pub frame_padding: [f32; 2],
+    pub frame_rounding: f32,
+    pub frame_border_size: f32,
+    pub item_spacing: [f32; 2],
+    pub item_inner_spacing: [f32; 2],
+    pub cell_padding: [f32; 2],
+    pub indent_spacing: f32,
+    pub scrollbar_size: f32,
+    pub scrollbar_rounding: f32,
+    pub grab_min_size: f32,
+    pub grab_rounding: f32,
+    pub tab_rounding: f32,
+    pub tab_border_size: f32,
+    /// Colors indexed by [`StyleColor`]
+    pub colors: [[f32; 4]; StyleColor::COUNT],
+}
+
+impl UiTheme {
+    /// Capture the theme of an ImGui style
+    pub fn from_style(style: &Style) -> Self {
+        Self {
+            alpha: style.alpha,
+            window_padding: style.window_padding,
+            window_rounding: style.window_rounding,
+            window_border_size: style.window_border_size,
+            child_rounding: style.child_rounding,
+            child_border_size: style.child_border_size,
+            popup_rounding: style.popup_rounding,
+            popup_border_size: style.popup_border_size,
+            frame_padding: style.frame_padding,
+            frame_rounding: style.frame_rounding,
+            frame_border_size: style.frame_border_size,
+            item_spacing: style.item_spacing,
+            item_inner_spacing: style.item_inner_spacing,
+            cell_padding: style.cell_padding,
+            indent_spacing: style.indent_spacing,
+            scrollbar_size: style.scrollbar_size,
+            scrollbar_rounding: style.scrollbar_rounding,
+            grab_min_size: style.grab_min_size,
+            grab_rounding: style.grab_rounding,
+            tab_rounding: style.tab_rounding,
+            tab_border_size: style.tab_border_size,
+            colors: style.colors,
+        }
+    }
+
+    /// Write the theme into an ImGui style, leaving other settings untouched
+    pub fn apply(&self, style: &mut Style) {
+        style.alpha = self.alpha;
+        style.window_padding = self.window_padding;
+        style.window_rounding = self.window_rounding;
+        style.window_border_size = self.window_border_size;
+        style.child_rounding = self.child_rounding;
+        style.child_border_size = self.child_border_size;
+        style.popup_rounding = self.popup_rounding;
+        style.popup_border_size = self.popup_border_size;
+        style.frame_padding = self.frame_padding;
+        style.frame_rounding = self.frame_rounding;
+        style.frame_border_size = self.frame_border_size;
+        style.item_spacing = self.item_spacing;
+        style.item_inner_spacing = self.item_inner_spacing;
+        style.cell_padding = self.cell_padding;
+        style.indent_spacing = self.indent_spacing;
+        style.scrollbar_size = self.scrollbar_size;
+        style.scrollbar_rounding = self.scrollbar_rounding;
+        style.grab_min_size = self.grab_min_size;
+        style.grab_rounding = self.grab_rounding;
+        style.tab_rounding = self.tab_rounding;
+        style.tab_border_size = self.tab_border_size;
+        style.colors = self.colors;
+    }
+
+    /// Color of one style slot
+    pub fn color(&self, color: StyleColor) -> [f32; 4] {
+        self.colors[color as usize]
+    }
+
+    /// Set the color of one style slot
+    pub fn set_color(&mut self, color: StyleColor, value: [f32; 4]) {
+        self.colors[color as usize] = value;
+    }
+
+    /// Single-value metrics by TOML key
+    pub fn scalars_mut(&mut self) -> [(&'static str, &mut f32); 16] {
+        [
+            ("alpha", &mut self.alpha),
+            ("window_rounding", &mut self.window_rounding),
+            ("window_border_size", &mut self.window_border_size),
+            ("child_rounding", &mut self.child_rounding),
+            ("child_border_size", &mut self.child_border_size),
+            ("popup_rounding", &mut self.popup_rounding),
+            ("popup_border_size", &mut self.popup_border_size),
+            ("frame_rounding", &mut self.frame_rounding),
+            ("frame_border_size", &mut self.frame_border_size),
+            ("indent_spacing", &mut self.indent_spacing),
+            ("scrollbar_size", &mut self.scrollbar_size),
+            ("scrollbar_rounding", &mut self.scrollbar_rounding),
+            ("grab_min_size", &mut self.grab_min_size),
+            ("grab_rounding", &mut self.grab_rounding),
+            ("tab_rounding", &mut self.tab_rounding),
+            ("tab_border_size", &mut self.tab_border_size),
+        ]
+    }
+
+    /// Two-component metrics by TOML key
+    pub fn vectors_mut(&mut self) -> [(&'static str, &mut [f32; 2]); 5] {
+        [
+            ("window_padding", &mut self.window_padding),
+            ("frame_padding", &mut self.frame_padding),
+            ("item_spacing", &mut self.item_spacing),
+            ("item_inner_spacing", &mut self.item_inner_spacing),
+            ("cell_padding", &mut self.cell_padding),
+        ]
+    }
+
+    /// Serialize the theme as TOML
+    pub fn to_toml(&self) -> String {
+        let mut theme = *self;
+        let mut out = String::from("# Haggis UI theme\n\n");
+        for (key, value) in theme.scalars_mut() {
+            out.push_str(&format!("{key} = {value:?}\n"));
+        }
+        for (key, value) in theme.vectors_mut() {
+            out.push_str(&format!("{key} = {}\n", format_array(value)));
+        }
+        out.push_str("\n[colors]\n");
+        for color in StyleColor::VARIANTS {
+            out.push_str(&format!(
+                "{} = {}\n",
+                color.name(),
+                format_array(&self.color(color))
+            ));
+        }
+        out
+    }
+
+    /// Override the values present in TOML theme text
+    ///
+    /// Keys missing from the text keep their current values. Unknown keys,
+    /// unknown tables and malformed values are errors, reported with their
+    /// line number; the theme is unchanged on error.
+    pub fn merge_toml(&mut self, text: &str) -> std::result::Result<(), String> {
+        let mut theme = *self;
+        let mut in_colors = false;
+
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(table) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                match table.trim() {
+                    "colors" => in_colors = true,
+                    other => return Err(format!("line {line_number}: unknown table [{other}]")),
+                }
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(format!("line {line_number}: expected `key = value`"));
+            };
+            let (key, value) = (key.trim(), value.trim());
+            let values = parse_numbers(value)
+                .ok_or_else(|| format!("line {line_number}: invalid value for {key}"))?;
+
+            let expect = |count: usize| {
+                if values.len() == count && (count == 1) != value.starts_with('[') {
+                    Ok(())
+                } else if count == 1 {
+                    Err(format!("line {line_number}: {key} expects a number"))
+                } else {
+                    Err(format!(
+                        "line {line_number}: {key} expects an array of {count} numbers"
+                    ))
+                }
+            };
+
+            if in_colors {
+                let color = StyleColor::VARIANTS
+                    .into_iter()
+                    .find(|color| color.name() == key)
+                    .ok_or_else(|| format!("line {line_number}: unknown color {key}"))?;
+                expect(4)?;
+                theme.set_color(color, [values[0], values[1], values[2], values[3]]);
+                continue;
+            }
+            if let Some((_, target)) = theme
+                .scalars_mut()
+                .into_iter()
+                .find(|(name, _)| *name == key)
+            {
+                expect(1)?;
+                *target = values[0];
+                continue;
+            }
+            let (_, target) = theme
+                .vectors_mut()
+                .into_iter()
+                .find(|(name, _)| *name == key)
+                .ok_or_else(|| format!("line {line_number}: unknown key {key}"))?;
+            expect(2)?;
+            *target = [values[0], values[1]];
+        }
+
+        *self = theme;
+        Ok(())
+    }
+
+    /// Override the values present in a TOML theme file
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::Io`] if the file cannot be read,
+    /// [`HaggisError::Theme`] if it is malformed, and
+    /// [`HaggisError::NoFileSystem`] on platforms without file access.
+    pub fn load(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if !crate::platform::HAS_FILESYSTEM {
+            return Err(HaggisError::NoFileSystem { path: path.into() });
+        }
+        let text = std::fs::read_to_string(path).map_err(|source| HaggisError::Io {
+            path: path.into(),
+            source,
+        })?;
+        self.merge_toml(&text)
+            .map_err(|message| HaggisError::Theme {
+                path: path.into(),
+                message,
+            })
+    }
+
+    /// Save the theme as a TOML file
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HaggisError::Io`] if the file cannot be written and
+    /// [`HaggisError::NoFileSystem`] on platforms without file access.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if !crate::platform::HAS_FILESYSTEM {
+            return Err(HaggisError::NoFileSystem { path: path.into() });
+        }
+        std::fs::write(path, self.to_toml()).map_err(|source| HaggisError::Io {
+            path: path.into(),
+            source,
+        })
+    }
+}
+
+/// Parse a TOML number or flat array of numbers
+fn parse_numbers(value: &str) -> Option<Vec<f32>> {
+    match value.strip_prefix('[') {
+        Some(array) => array
+            .strip_suffix(']')?
+            .split(',')
+            .map(str::trim)
+            // TOML allows a trailing comma
+            .filter(|item| !item.is_empty())
+            .map(|item| item.parse().ok())
+            .collect(),
+        None => value.parse().ok().map(|number| vec![number]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn theme() -> UiTheme {
+        UiTheme {
+            alpha: 1.0,
+            window_padding: [8.0, 8.0],
+            window_rounding: 0.0,
+            window_border_size: 1.0,
+            child_rounding: 0.0,
+            child_border_size: 1.0,
+            popup_rounding: 0.0,
+            popup_border_size: 1.0,
+            frame_padding: [4.0, 3.0],
+            frame_rounding: 0.0,
+            frame_border_size: 0.0,
+            item_spacing: [8.0, 4.0],
+            item_inner_spacing: [4.0, 4.0],
+            cell_padding: [4.0, 2.0],
+            indent_spacing: 21.0,
+            scrollbar_size: 14.0,
+            scrollbar_rounding: 9.0,
+            grab_min_size: 12.0,
+            grab_rounding: 0.0,
+            tab_rounding: 4.0,
+            tab_border_size: 0.0,
+            colors: [[0.5, 0.25, 0.125, 1.0]; StyleColor::COUNT],
+        }
+    }
+
+    #[test]
+    fn test_toml_round_trip() {
+        let mut original = theme();
+        original.frame_rounding = 3.5;
+        original.item_spacing = [6.0, 2.0];
+        original.set_color(StyleColor::WindowBg, [0.1, 0.2, 0.3, 0.95]);
+
+        let mut loaded = theme();
+        loaded.merge_toml(&original.to_toml()).unwrap();
+        assert_eq!(loaded, original);
+    }
+
+    #[test]
+    fn test_partial_file_keeps_other_values() {
+        let mut loaded = theme();
+        loaded
+            .merge_toml(
+                "# dusk\nwindow_rounding = 6 # rounded\nframe_padding = [6.0, 4.0,]\n\n[colors]\nText = [0.9, 0.9, 0.9, 1.0]\n",
+            )
+            .unwrap();
+        assert_eq!(loaded.window_rounding, 6.0);
+        assert_eq!(loaded.frame_padding, [6.0, 4.0]);
+        assert_eq!(loaded.color(StyleColor::Text), [0.9, 0.9, 0.9, 1.0]);
+        assert_eq!(loaded.grab_min_size, 12.0);
+        assert_eq!(loaded.color(StyleColor::Button), [0.5, 0.25, 0.125, 1.0]);
+    }
+
+    #[test]
+    fn test_malformed_toml_is_rejected() {
+        let original = theme();
+        let mut loaded = original;
+        for text in [
+            "window_rounding = 6\nbogus = 1",
+            "frame_padding = 4",
+            "alpha = [1.0]",
+            "[colors]\nText = [1.0, 1.0]",
+            "[fonts]",
+            "alpha",
+        ] {
+            assert!(loaded.merge_toml(text).is_err(), "accepted {text:?}");
+            assert_eq!(loaded, original);
+        }
+    }
+}
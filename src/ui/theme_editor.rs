@@ -0,0 +1,160 @@
+//! Theme editor panel
+//!
+//! Edits a copy of the UI theme with sliders and color pickers. Changes are
+//! handed back to the app every frame, so the UI previews them live, and the
+//! theme can be loaded from and saved to TOML files.
+
+use imgui::{StyleColor, TreeNodeFlags, Ui};
+
+use super::theme::UiTheme;
+
+/// Theme editor state: the edited theme and file controls
+pub struct ThemeEditor {
+    /// Whether the window is visible
+    pub open: bool,
+    theme: Option<UiTheme>,
+    /// Theme at the last sync, restored by "Revert"
+    original: Option<UiTheme>,
+    changed: bool,
+    path: String,
+    color_filter: String,
+    /// Outcome of the last load or save
+    status: Option<Result<String, String>>,
+}
+
+impl ThemeEditor {
+    /// Create a closed theme editor
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            theme: None,
+            original: None,
+            changed: false,
+            path: String::from("theme.toml"),
+            color_filter: String::new(),
+            status: None,
+        }
+    }
+
+    /// Show or hide the window
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    /// Start editing the theme currently in use
+    pub fn set_theme(&mut self, theme: UiTheme) {
+        self.theme = Some(theme);
+        self.original = Some(theme);
+        self.changed = false;
+    }
+
+    /// Theme edited since the last call, to apply to the UI
+    pub fn take_changed(&mut self) -> Option<UiTheme> {
+        std::mem::take(&mut self.changed)
+            .then_some(self.theme)
+            .flatten()
+    }
+
+    /// Draw the window if it is open
+    pub fn render_ui(&mut self, ui: &Ui) {
+        if !self.open {
+            return;
+        }
+        let Some(mut theme) = self.theme else {
+            return;
+        };
+
+        let mut open = self.open;
+        let mut changed = false;
+        ui.window("Theme Editor")
+            .size([420.0, 520.0], imgui::Condition::FirstUseEver)
+            .opened(&mut open)
+            .build(|| {
+                ui.set_next_item_width(200.0);
+                ui.input_text("File", &mut self.path).build();
+                ui.same_line();
+                if ui.button("Load") {
+                    self.status = Some(match theme.load(&self.path) {
+                        Ok(()) => {
+                            changed = true;
+                            Ok(format!("Loaded {}", self.path))
+                        }
+                        Err(error) => Err(error.to_string()),
+                    });
+                }
+                ui.same_line();
+                if ui.button("Save") {
+                    self.status = Some(
+                        theme
+                            .save(&self.path)
+                            .map(|()| format!("Saved {}", self.path))
+                            .map_err(|error| error.to_string()),
+                    );
+                }
+                ui.same_line();
+                if ui.button("Revert") {
+                    if let Some(original) = self.original {
+                        theme = original;
+                        changed = true;
+                    }
+                }
+                match &self.status {
+                    Some(Ok(message)) => ui.text_disabled(message),
+                    Some(Err(message)) => ui.text_colored([1.0, 0.4, 0.4, 1.0], message),
+                    None => {}
+                }
+                ui.separator();
+
+                if ui.collapsing_header("Metrics", TreeNodeFlags::DEFAULT_OPEN) {
+                    for (key, value) in theme.scalars_mut() {
+                        let max = match key {
+                            "alpha" => 1.0,
+                            _ if key.ends_with("border_size") => 2.0,
+                            _ => 30.0,
+                        };
+                        changed |= ui.slider(label(key), 0.0, max, value);
+                    }
+                    for (key, value) in theme.vectors_mut() {
+                        changed |= ui.slider_config(label(key), 0.0, 20.0).build_array(value);
+                    }
+                }
+
+                if ui.collapsing_header("Colors", TreeNodeFlags::empty()) {
+                    ui.input_text("Filter", &mut self.color_filter).build();
+                    let filter = self.color_filter.to_lowercase();
+                    for color in StyleColor::VARIANTS {
+                        if !color.name().to_lowercase().contains(&filter) {
+                            continue;
+                        }
+                        let mut value = theme.color(color);
+                        if ui.color_edit4(color.name(), &mut value) {
+                            theme.set_color(color, value);
+                            changed = true;
+                        }
+                    }
+                }
+            });
+        self.open = open;
+
+        if changed {
+            self.theme = Some(theme);
+            self.changed = true;
+        }
+    }
+}
+
+impl Default for ThemeEditor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Slider label for a TOML key, e.g. "Frame rounding" for `frame_rounding`
+fn label(key: &str) -> String {
+    let text = key.replace('_', " ");
+    let mut chars = text.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
@@ -0,0 +1,3 @@
+//! Helpers shared across modules
+
+pub(crate) mod toml_lite;
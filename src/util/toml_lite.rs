@@ -0,0 +1,110 @@
+//! Reading and writing the small TOML subset used by project, session,
+//! theme, string table and annotation files
+//!
+//! These files are parsed line by line by their own modules; this module
+//! holds the value formats they have in common: basic strings and flat
+//! arrays of numbers.
+
+use std::str::FromStr;
+
+/// Format floats as a TOML array
+pub(crate) fn format_array(values: &[f32]) -> String {
+    let items: Vec<String> = values.iter().map(|value| format!("{value:?}")).collect();
+    format!("[{}]", items.join(", "))
+}
+
+/// Parse a TOML array of exactly `N` numbers
+pub(crate) fn parse_array<T: FromStr, const N: usize>(value: &str) -> Option<[T; N]> {
+    let values: Vec<T> = value
+        .strip_prefix('[')?
+        .strip_suffix(']')?
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| item.parse().ok())
+        .collect::<Option<_>>()?;
+    values.try_into().ok()
+}
+
+/// Quote text as a TOML basic string
+pub(crate) fn quote(text: &str) -> String {
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04X}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Parse a TOML basic string, the inverse of [`quote`]
+pub(crate) fn parse_string(value: &str) -> Option<String> {
+    match split_string(value)? {
+        (text, "") => Some(text),
+        _ => None,
+    }
+}
+
+/// Parse the TOML basic string at the start of `value`, returning it and
+/// the text after its closing quote
+pub(crate) fn split_string(value: &str) -> Option<(String, &str)> {
+    let mut chars = value.strip_prefix('"')?.chars();
+    let mut out = String::new();
+    loop {
+        match chars.next()? {
+            '"' => return Some((out, chars.as_str())),
+            '\\' => match chars.next()? {
+                '"' => out.push('"'),
+                '\\' => out.push('\\'),
+                'n' => out.push('\n'),
+                't' => out.push('\t'),
+                'r' => out.push('\r'),
+                'u' => {
+                    let code: String = chars.by_ref().take(4).collect();
+                    out.push(char::from_u32(u32::from_str_radix(&code, 16).ok()?)?);
+                }
+                _ => return None,
+            },
+            c => out.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_string_round_trip() {
+        for text in ["", "plain", "a \"b\"\\\n\t\r", "\u{1}bell", "Симуляция"] {
+            assert_eq!(parse_string(&quote(text)).as_deref(), Some(text));
+        }
+        assert_eq!(parse_string("\"\\u00e9\""), Some("é".to_string()));
+        for value in ["unquoted", "\"open", "\"a\"b\"", "\"\\q\""] {
+            assert_eq!(parse_string(value), None, "accepted {value:?}");
+        }
+        assert_eq!(
+            split_string("\"a\\\"b\" # note"),
+            Some(("a\"b".to_string(), " # note"))
+        );
+    }
+
+    #[test]
+    fn test_array_round_trip() {
+        assert_eq!(
+            parse_array(&format_array(&[0.5, -1.0, 2.0])),
+            Some([0.5, -1.0, 2.0])
+        );
+        assert_eq!(parse_array("[1600, 900,]"), Some([1600u32, 900]));
+        assert_eq!(parse_array::<f32, 2>("[1.0]"), None);
+        assert_eq!(parse_array::<u32, 1>("[x]"), None);
+        assert_eq!(parse_array::<u32, 1>("1"), None);
+    }
+}
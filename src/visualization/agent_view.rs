@@ -0,0 +1,173 @@
+//! # Agent View
+//!
+//! Shows a set of agents as instanced darts pointing along their velocity,
+//! or as spheres for particles without a heading, see [`AgentView::with_shape`].
+//! GPU simulations hand over their agent buffer once with
+//! [`AgentView::set_gpu_buffer`] and it is drawn every frame without a
+//! readback. When the GPU also decides how many agents there are, the
+//! count comes from indirect draw arguments set with
+//! [`AgentView::set_indirect_args`]. CPU simulations pass [`AgentInstance`]s with
+//! [`AgentView::set_instances`], which are uploaded on the next update.
+
+use imgui::Ui;
+use wgpu::{Device, Queue};
+
+use super::traits::VisualizationComponent;
+use crate::gfx::rendering::{AgentBatch, AgentInstance, AgentShape};
+
+/// Visualization component drawing agents from a GPU or CPU source
+pub struct AgentView {
+    enabled: bool,
+    /// CPU instances waiting for upload
+    pending: Option<Vec<AgentInstance>>,
+    buffer: Option<wgpu::Buffer>,
+    /// Instances the owned buffer can hold; `None` for external buffers
+    capacity: Option<usize>,
+    count: u32,
+    indirect: Option<wgpu::Buffer>,
+    shape: AgentShape,
+}
+
+impl AgentView {
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            pending: None,
+            buffer: None,
+            capacity: None,
+            count: 0,
+            indirect: None,
+            shape: AgentShape::Dart,
+        }
+    }
+
+    /// Draw every agent as `shape` instead of a dart
+    pub fn with_shape(mut self, shape: AgentShape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    pub fn shape(&self) -> AgentShape {
+        self.shape
+    }
+
+    /// Draw the first `count` [`AgentInstance`]s of a buffer with `VERTEX` usage
+    pub fn set_gpu_buffer(&mut self, buffer: wgpu::Buffer, count: u32) {
+        self.pending = None;
+        self.buffer = Some(buffer);
+        self.capacity = None;
+        self.count = count;
+    }
+
+    /// Take the number of agents to draw from `DrawIndexedIndirectArgs` at
+    /// the start of `args`, written on the GPU
+    ///
+    /// The index count of the arguments must be
+    /// [`AgentRenderer::INDEX_COUNT`](crate::gfx::rendering::AgentRenderer::INDEX_COUNT)
+    /// for darts or
+    /// [`AgentRenderer::SPHERE_INDEX_COUNT`](crate::gfx::rendering::AgentRenderer::SPHERE_INDEX_COUNT)
+    /// for spheres, and the count given to [`set_gpu_buffer`](Self::set_gpu_buffer)
+    /// becomes the capacity. CPU instances clear the arguments.
+    pub fn set_indirect_args(&mut self, args: wgpu::Buffer) {
+        self.indirect = Some(args);
+    }
+
+    /// Replace the drawn agents with CPU data, uploaded on the next update
+    pub fn set_instances(&mut self, instances: &[AgentInstance]) {
+        self.pending = Some(instances.to_vec());
+        self.indirect = None;
+    }
+
+    /// Number of agents drawn, or the capacity with indirect arguments
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// Batch for the renderer, if there is anything to draw
+    pub fn batch(&self) -> Option<AgentBatch> {
+        let instances = self.buffer.clone()?;
+        (self.count > 0).then_some(AgentBatch {
+            instances,
+            count: self.count,
+            indirect: self.indirect.clone(),
+            shape: self.shape,
+        })
+    }
+
+    fn upload(&mut self, device: &Device, queue: &Queue, instances: Vec<AgentInstance>) {
+        self.count = instances.len() as u32;
+        if instances.is_empty() {
+            return;
+        }
+        if self
+            .capacity
+            .is_none_or(|capacity| instances.len() > capacity)
+        {
+            let capacity = instances.len().next_power_of_two();
+            self.buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Agent View Instances"),
+                size: (capacity * std::mem::size_of::<AgentInstance>()) as u64,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+            self.capacity = Some(capacity);
+        }
+        if let Some(ref buffer) = self.buffer {
+            queue.write_buffer(buffer, 0, bytemuck::cast_slice(&instances));
+        }
+    }
+}
+
+impl Default for AgentView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VisualizationComponent for AgentView {
+    fn initialize(&mut self, _device: Option<&Device>, _queue: Option<&Queue>) {}
+
+    fn update(&mut self, _delta_time: f32, device: Option<&Device>, queue: Option<&Queue>) {
+        let (Some(device), Some(queue)) = (device, queue) else {
+            return;
+        };
+        if let Some(instances) = self.pending.take() {
+            self.upload(device, queue, instances);
+        }
+    }
+
+    fn render_ui(&mut self, ui: &Ui) {
+        ui.checkbox("Enabled", &mut self.enabled);
+        ui.text(format!("Agents: {}", self.count));
+        ui.text(match (&self.buffer, self.capacity) {
+            (None, _) => "Source: none",
+            (Some(_), Some(_)) => "Source: CPU",
+            (Some(_), None) if self.indirect.is_some() => "Source: GPU buffer (indirect count)",
+            (Some(_), None) => "Source: GPU buffer",
+        });
+    }
+
+    fn name(&self) -> &str {
+        "Agent View"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn get_ui_size(&self) -> (f32, f32) {
+        (250.0, 120.0)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
@@ -3,10 +3,13 @@
 //! Generic 2D data visualizer that accepts 2D data arrays directly from the user.
 //! No hardcoded 3D slicing logic - purely for displaying 2D data.
 
+use super::grid_transform::GridTransform;
 use super::rendering::VisualizationMaterial;
 use super::traits::VisualizationComponent;
-use super::ui::cut_plane_controls::{FilterMode, VisualizationMode};
-use crate::gfx::{resources::texture_resource::TextureResource, scene::Scene};
+use super::ui::cut_plane_controls::{Colormap, FilterMode, VisualizationMode};
+use crate::gfx::{
+    rendering::ClipPlane, resources::texture_resource::TextureResource, scene::Scene,
+};
 use cgmath::Vector3;
 use imgui::Ui;
 use wgpu::{Device, Queue, Buffer};
@@ -22,6 +25,9 @@ pub enum DataSource {
         buffer: Arc<Buffer>,
         format: BufferFormat,
     },
+    /// GPU texture - written by compute shaders through a storage binding and
+    /// sampled as is, already colored
+    GpuTexture(TextureResource),
 }
 
 /// Buffer data format specification
@@ -66,11 +72,17 @@ pub struct CutPlane2D {
     // Display position in 3D space
     position: Vector3<f32>,
     size: f32,
+    // Cut away scene geometry above the plane
+    clip_geometry: bool,
 
     // Update flags
     needs_material_update: bool,
     needs_scene_object_update: bool,
     needs_filter_update: bool, // Track filter changes separately
+
+    // Heatmap range and colors for f32 GPU buffers
+    value_range: [f32; 2],
+    colormap: Colormap,
 }
 
 impl CutPlane2D {
@@ -88,9 +100,12 @@ impl CutPlane2D {
             material: None,
             position: Vector3::new(0.0, 0.0, 0.0),
             size: 2.0,
+            clip_geometry: false,
             needs_material_update: true,
             needs_scene_object_update: true,
             needs_filter_update: false,
+            value_range: [0.0, 1.0],
+            colormap: Colormap::default(),
         }
     }
 
@@ -112,6 +127,14 @@ impl CutPlane2D {
         self.needs_scene_object_update = true;
     }
 
+    /// Set a GPU texture for direct visualization, e.g. one created with
+    /// [TextureResource::create_storage_texture] and filled by a compute shader
+    pub fn update_gpu_texture(&mut self, texture: TextureResource) {
+        self.data_source = Some(DataSource::GpuTexture(texture));
+        self.needs_material_update = true;
+        self.needs_scene_object_update = true;
+    }
+
     /// Convenience method for Conway's Game of Life and similar u32 grid simulations
     pub fn update_u32_buffer(&mut self, buffer: Arc<Buffer>, width: u32, height: u32) {
         let format = BufferFormat {
@@ -130,6 +153,9 @@ impl CutPlane2D {
                 self.cpu_data_dimensions.unwrap_or((64, 64)) // Fallback to default if not set
             }
             Some(DataSource::GpuBuffer { format, .. }) => (format.width, format.height),
+            Some(DataSource::GpuTexture(texture)) => {
+                (texture.texture.width(), texture.texture.height())
+            }
             None => (0, 0),
         }
     }
@@ -144,18 +170,69 @@ impl CutPlane2D {
         self.size = size;
     }
 
+    /// Place the plane over the grid's XY extent at the Z layer nearest to
+    /// `z_normalized` (0 = bottom, 1 = top)
+    pub fn set_grid_slice(&mut self, grid: &GridTransform, z_normalized: f32) {
+        let center = grid.center();
+        let half_extent = grid.size() * 0.5;
+        self.position = Vector3::new(center.x, center.y, grid.layer_position(2, z_normalized));
+        self.size = half_extent.x.max(half_extent.y);
+    }
+
+    /// Cut away scene geometry above the plane, revealing the plane inside
+    /// solid meshes
+    pub fn set_clip_geometry(&mut self, clip_geometry: bool) {
+        self.clip_geometry = clip_geometry;
+    }
+
+    /// Whether scene geometry above the plane is cut away
+    pub fn clips_geometry(&self) -> bool {
+        self.clip_geometry
+    }
+
+    /// Clip plane at the plane's height, if geometry clipping is enabled
+    pub fn clip_plane(&self) -> Option<ClipPlane> {
+        self.clip_geometry
+            .then(|| ClipPlane::new(self.position, Vector3::unit_z()))
+    }
+
     /// Set texture filtering mode (Sharp vs Smooth)
     pub fn set_filter_mode(&mut self, filter_mode: FilterMode) {
         if self.filter_mode != filter_mode {
             self.filter_mode = filter_mode;
             self.needs_filter_update = true;
-            // For CPU materials, we need to recreate with new filtering
-            if matches!(self.data_source, Some(DataSource::CpuData(_))) {
+            // For CPU and texture materials, we need to recreate with new filtering
+            if matches!(
+                self.data_source,
+                Some(DataSource::CpuData(_) | DataSource::GpuTexture(_))
+            ) {
                 self.needs_material_update = true;
             }
         }
     }
 
+    /// Set the values mapped to the ends of the heatmap for f32 GPU buffers
+    pub fn set_value_range(&mut self, min: f32, max: f32) {
+        if self.value_range != [min, max] {
+            self.value_range = [min, max];
+            self.needs_filter_update = true;
+        }
+    }
+
+    /// Set the colors of f32 GPU buffer data; pair the diverging maps with a
+    /// value range symmetric around zero
+    pub fn set_colormap(&mut self, colormap: Colormap) {
+        if self.colormap != colormap {
+            self.colormap = colormap;
+            self.needs_filter_update = true;
+        }
+    }
+
+    /// Get current colormap
+    pub fn get_colormap(&self) -> Colormap {
+        self.colormap
+    }
+
     /// Get current filter mode
     pub fn get_filter_mode(&self) -> FilterMode {
         self.filter_mode
@@ -234,14 +311,37 @@ impl CutPlane2D {
             }
             DataSource::GpuBuffer { buffer, format } => {
                 // High-performance GPU buffer path - create material that references buffer directly
-                self.material = Some(VisualizationMaterial::from_gpu_buffer(
+                let mut material = VisualizationMaterial::from_gpu_buffer(
                     device,
                     queue,
                     buffer.clone(),
                     *format,
                     self.mode,
                     "GPU Buffer Material",
-                ));
+                );
+                material.value_range = self.value_range;
+                material.colormap = self.colormap;
+                material.update_filter_mode(queue, self.filter_mode);
+                self.material = Some(material);
+                self.last_filter_mode = self.filter_mode;
+            }
+            DataSource::GpuTexture(texture) => {
+                // Sample the texture as it is, with a sampler for the current filter mode
+                let wgpu_filter = match self.filter_mode {
+                    FilterMode::Sharp => wgpu::FilterMode::Nearest,
+                    FilterMode::Smooth => wgpu::FilterMode::Linear,
+                };
+                let mut texture = texture.clone();
+                texture.sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+                    label: Some("GPU Texture Sampler"),
+                    address_mode_u: wgpu::AddressMode::ClampToEdge,
+                    address_mode_v: wgpu::AddressMode::ClampToEdge,
+                    mag_filter: wgpu_filter,
+                    min_filter: wgpu_filter,
+                    ..Default::default()
+                });
+                self.material = Some(VisualizationMaterial::from_texture(device, queue, texture));
+                self.last_filter_mode = self.filter_mode;
             }
         }
 
@@ -385,8 +485,10 @@ impl VisualizationComponent for CutPlane2D {
         }
         
         // Update filter mode for GPU materials (only when changed)
-        if self.needs_filter_update && self.filter_mode != self.last_filter_mode {
-            if let (Some(material), Some(queue)) = (&self.material, queue) {
+        if self.needs_filter_update {
+            if let (Some(material), Some(queue)) = (&mut self.material, queue) {
+                material.value_range = self.value_range;
+                material.colormap = self.colormap;
                 material.update_filter_mode(queue, self.filter_mode);
                 self.last_filter_mode = self.filter_mode;
                 self.needs_filter_update = false;
@@ -430,6 +532,18 @@ impl VisualizationComponent for CutPlane2D {
             self.set_filter_mode(FilterMode::Smooth);
         }
 
+        // Colors of continuous data read straight from the GPU
+        if let Some(DataSource::GpuBuffer { format, .. }) = &self.data_source {
+            if matches!(format.element_type, BufferElementType::F32) {
+                ui.separator();
+                for colormap in Colormap::all() {
+                    if ui.radio_button_bool(colormap.as_str(), self.colormap == colormap) {
+                        self.set_colormap(colormap);
+                    }
+                }
+            }
+        }
+
         ui.separator();
 
         // View controls
@@ -447,6 +561,7 @@ impl VisualizationComponent for CutPlane2D {
         ui.slider_config("Position Z", -5.0, 5.0)
             .build(&mut self.position.z);
         ui.slider_config("Size", 0.1, 10.0).build(&mut self.size);
+        ui.checkbox("Clip geometry above", &mut self.clip_geometry);
 
         ui.separator();
 
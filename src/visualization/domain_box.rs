@@ -0,0 +1,281 @@
+//! Domain Box Visualization Component
+//!
+//! Wireframe box showing the extent of a simulation domain, with optional tick
+//! marks along its edges and labels on its faces. Replaces placing small cubes
+//! at the domain corners.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::visualization::{BoxFace, DomainBox};
+//!
+//! let mut app = haggis::default();
+//! app.add_visualization(
+//!     "domain",
+//!     DomainBox::new([-1.0, -1.0, -1.0], [1.0, 1.0, 1.0])
+//!         .with_ticks(0.25)
+//!         .with_face_label(BoxFace::NegX, "Inlet")
+//!         .with_face_label(BoxFace::PosX, "Outlet"),
+//! );
+//! ```
+
+use cgmath::{Matrix4, Vector3};
+use imgui::Ui;
+use wgpu::{Device, Queue};
+
+use super::traits::VisualizationComponent;
+use crate::gfx::overlay::project;
+use crate::gfx::rendering::line_renderer::LineVertex;
+
+/// Face of a [`DomainBox`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoxFace {
+    NegX,
+    PosX,
+    NegY,
+    PosY,
+    NegZ,
+    PosZ,
+}
+
+impl BoxFace {
+    pub const ALL: [BoxFace; 6] = [
+        BoxFace::NegX,
+        BoxFace::PosX,
+        BoxFace::NegY,
+        BoxFace::PosY,
+        BoxFace::NegZ,
+        BoxFace::PosZ,
+    ];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Axis the face is perpendicular to, and whether it's on the max side
+    fn axis(self) -> (usize, bool) {
+        (self.index() / 2, self.index() % 2 == 1)
+    }
+
+    fn default_label(self) -> &'static str {
+        ["-X", "+X", "-Y", "+Y", "-Z", "+Z"][self.index()]
+    }
+}
+
+/// Wireframe box marking a simulation domain
+pub struct DomainBox {
+    enabled: bool,
+    min: Vector3<f32>,
+    max: Vector3<f32>,
+    color: [f32; 4],
+    /// Distance between tick marks, `None` for no ticks
+    tick_spacing: Option<f32>,
+    face_labels: [Option<String>; 6],
+    show_face_labels: bool,
+}
+
+impl DomainBox {
+    /// Create a box spanning `min` to `max` in world space
+    pub fn new(min: impl Into<Vector3<f32>>, max: impl Into<Vector3<f32>>) -> Self {
+        Self {
+            enabled: true,
+            min: min.into(),
+            max: max.into(),
+            color: [0.8, 0.8, 0.8, 0.8],
+            tick_spacing: None,
+            face_labels: [None, None, None, None, None, None],
+            show_face_labels: false,
+        }
+    }
+
+    /// Set the line color
+    pub fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Draw tick marks every `spacing` world units along the edges through `min`
+    pub fn with_ticks(mut self, spacing: f32) -> Self {
+        self.tick_spacing = (spacing > 0.0).then_some(spacing);
+        self
+    }
+
+    /// Label every face with its axis ("-X", "+Z", ...) unless it has its own label
+    pub fn with_face_labels(mut self) -> Self {
+        self.show_face_labels = true;
+        self
+    }
+
+    /// Label one face, e.g. "Inlet"; also turns face labels on
+    pub fn with_face_label(mut self, face: BoxFace, label: &str) -> Self {
+        self.face_labels[face.index()] = Some(label.to_string());
+        self.show_face_labels = true;
+        self
+    }
+
+    /// Move or resize the box
+    pub fn set_bounds(&mut self, min: impl Into<Vector3<f32>>, max: impl Into<Vector3<f32>>) {
+        self.min = min.into();
+        self.max = max.into();
+    }
+
+    pub fn min(&self) -> Vector3<f32> {
+        self.min
+    }
+
+    pub fn max(&self) -> Vector3<f32> {
+        self.max
+    }
+
+    fn corner(&self, index: usize) -> [f32; 3] {
+        let pick = |axis: usize| {
+            if index & (1 << axis) != 0 {
+                self.max[axis]
+            } else {
+                self.min[axis]
+            }
+        };
+        [pick(0), pick(1), pick(2)]
+    }
+
+    /// Line list for the 12 edges and the tick marks
+    pub fn line_vertices(&self) -> Vec<LineVertex> {
+        let mut vertices = Vec::new();
+
+        for corner in 0..8 {
+            for axis in 0..3 {
+                // Each edge once, from the corner on its min side
+                if corner & (1 << axis) == 0 {
+                    vertices.push(LineVertex::new(self.corner(corner), self.color));
+                    vertices.push(LineVertex::new(self.corner(corner | (1 << axis)), self.color));
+                }
+            }
+        }
+
+        if let Some(spacing) = self.tick_spacing {
+            let size = self.max - self.min;
+            let tick_length = 0.03 * size.x.max(size.y).max(size.z);
+            for axis in 0..3 {
+                // Ticks point away from the box, along the next axis
+                let outward = if axis == 0 { 1 } else { 0 };
+                let mut t = (self.min[axis] / spacing).ceil() * spacing;
+                while t <= self.max[axis] + spacing * 1e-3 {
+                    let mut start: [f32; 3] = self.min.into();
+                    start[axis] = t;
+                    let mut end = start;
+                    end[outward] -= tick_length;
+                    vertices.push(LineVertex::new(start, self.color));
+                    vertices.push(LineVertex::new(end, self.color));
+                    t += spacing;
+                }
+            }
+        }
+
+        vertices
+    }
+
+    /// Draw face labels at the projected face centers
+    pub fn render_labels(&self, ui: &Ui, view_proj: Matrix4<f32>) {
+        if !self.enabled || !self.show_face_labels {
+            return;
+        }
+
+        let display_size = ui.io().display_size;
+        let draw_list = ui.get_background_draw_list();
+        let center = (self.min + self.max) * 0.5;
+
+        for face in BoxFace::ALL {
+            let (axis, positive) = face.axis();
+            let mut point = center;
+            point[axis] = if positive { self.max[axis] } else { self.min[axis] };
+
+            if let Some(screen) = project(view_proj, point, display_size) {
+                let label = self.face_labels[face.index()]
+                    .as_deref()
+                    .unwrap_or(face.default_label());
+                let half_width = ui.calc_text_size(label)[0] * 0.5;
+                draw_list.add_text([screen[0] - half_width, screen[1]], self.color, label);
+            }
+        }
+    }
+}
+
+impl VisualizationComponent for DomainBox {
+    fn initialize(&mut self, _device: Option<&Device>, _queue: Option<&Queue>) {}
+
+    fn update(&mut self, _delta_time: f32, _device: Option<&Device>, _queue: Option<&Queue>) {}
+
+    fn render_ui(&mut self, ui: &Ui) {
+        ui.checkbox("Enabled", &mut self.enabled);
+        ui.text(format!(
+            "Min: ({:.2}, {:.2}, {:.2})",
+            self.min.x, self.min.y, self.min.z
+        ));
+        ui.text(format!(
+            "Max: ({:.2}, {:.2}, {:.2})",
+            self.max.x, self.max.y, self.max.z
+        ));
+        let size = self.max - self.min;
+        ui.text(format!("Size: {:.2} x {:.2} x {:.2}", size.x, size.y, size.z));
+
+        ui.separator();
+        ui.checkbox("Face labels", &mut self.show_face_labels);
+        let mut show_ticks = self.tick_spacing.is_some();
+        if ui.checkbox("Ticks", &mut show_ticks) {
+            self.tick_spacing = show_ticks.then(|| size.x.max(size.y).max(size.z) / 8.0);
+        }
+        if let Some(ref mut spacing) = self.tick_spacing {
+            if ui.input_float("Tick spacing", spacing).build() && *spacing <= 0.0 {
+                *spacing = 0.01;
+            }
+        }
+        ui.color_edit4("Color", &mut self.color);
+    }
+
+    fn name(&self) -> &str {
+        "Domain Box"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn get_ui_size(&self) -> (f32, f32) {
+        (300.0, 200.0)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edges_and_ticks() {
+        let domain = DomainBox::new([-1.0, -1.0, -1.0], [1.0, 1.0, 1.0]);
+        let edges = domain.line_vertices();
+        assert_eq!(edges.len(), 12 * 2);
+        // Every edge runs along exactly one axis
+        for edge in edges.chunks(2) {
+            let changed = (0..3)
+                .filter(|&axis| edge[0].position[axis] != edge[1].position[axis])
+                .count();
+            assert_eq!(changed, 1);
+        }
+
+        // -1, -0.5, 0, 0.5, 1 on each of the three axes
+        let ticked = domain.with_ticks(0.5).line_vertices();
+        assert_eq!(ticked.len(), (12 + 3 * 5) * 2);
+    }
+}
@@ -0,0 +1,207 @@
+//! Simulation Grid to World Mapping
+//!
+//! [`GridTransform`] places a simulation grid in the world: grid dimensions
+//! plus the world-space box the grid fills. Grid samples are node-aligned,
+//! so sample 0 lies on the `min` face and sample `n - 1` on the `max` face
+//! along each axis.
+//!
+//! Cut planes, domain boxes and pick events take a `GridTransform` directly,
+//! instead of each example hand-computing `(z - 0.5) * scale * 2.0`.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::visualization::{CutPlane2D, GridTransform};
+//!
+//! let grid = GridTransform::centered([96, 96, 96], 1.0);
+//!
+//! let mut app = haggis::default();
+//! app.add_visualization("domain", grid.domain_box().with_ticks(0.25));
+//!
+//! let mut cut_plane = CutPlane2D::new();
+//! cut_plane.set_grid_slice(&grid, 0.5); // middle Z layer
+//! app.add_visualization("slice", cut_plane);
+//! ```
+
+use cgmath::{ElementWise, Vector3};
+
+use super::domain_box::DomainBox;
+use crate::events::PickEvent;
+
+/// Maps between simulation grid coordinates and world space
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GridTransform {
+    dims: [u32; 3],
+    min: Vector3<f32>,
+    max: Vector3<f32>,
+}
+
+impl GridTransform {
+    /// Grid of `dims` samples filling the world box `min`..`max`
+    pub fn new(dims: [u32; 3], min: impl Into<Vector3<f32>>, max: impl Into<Vector3<f32>>) -> Self {
+        Self {
+            dims: dims.map(|n| n.max(1)),
+            min: min.into(),
+            max: max.into(),
+        }
+    }
+
+    /// Grid filling the cube `[-half_extent, half_extent]³` around the origin
+    pub fn centered(dims: [u32; 3], half_extent: f32) -> Self {
+        Self::new(
+            dims,
+            [-half_extent, -half_extent, -half_extent],
+            [half_extent, half_extent, half_extent],
+        )
+    }
+
+    /// Number of samples along each axis
+    pub fn dims(&self) -> [u32; 3] {
+        self.dims
+    }
+
+    /// World-space minimum corner
+    pub fn min(&self) -> Vector3<f32> {
+        self.min
+    }
+
+    /// World-space maximum corner
+    pub fn max(&self) -> Vector3<f32> {
+        self.max
+    }
+
+    /// World-space center of the grid
+    pub fn center(&self) -> Vector3<f32> {
+        (self.min + self.max) * 0.5
+    }
+
+    /// World-space size of the grid box
+    pub fn size(&self) -> Vector3<f32> {
+        self.max - self.min
+    }
+
+    /// World distance between neighboring samples along each axis
+    pub fn spacing(&self) -> Vector3<f32> {
+        let steps = self.steps();
+        self.size().div_element_wise(steps)
+    }
+
+    /// Number of sample intervals per axis, at least 1 so single-sample axes
+    /// don't divide by zero
+    fn steps(&self) -> Vector3<f32> {
+        let [x, y, z] = self.dims.map(|n| (n.max(2) - 1) as f32);
+        Vector3::new(x, y, z)
+    }
+
+    /// Continuous grid coordinates to world position
+    pub fn grid_to_world(&self, grid: Vector3<f32>) -> Vector3<f32> {
+        self.normalized_to_world(grid.div_element_wise(self.steps()))
+    }
+
+    /// World position of a grid sample
+    pub fn cell_to_world(&self, cell: [u32; 3]) -> Vector3<f32> {
+        self.grid_to_world(Vector3::new(cell[0] as f32, cell[1] as f32, cell[2] as f32))
+    }
+
+    /// World position to continuous grid coordinates
+    pub fn world_to_grid(&self, world: Vector3<f32>) -> Vector3<f32> {
+        self.world_to_normalized(world)
+            .mul_element_wise(self.steps())
+    }
+
+    /// Nearest grid sample to a world position, `None` outside the grid
+    pub fn world_to_cell(&self, world: Vector3<f32>) -> Option<[u32; 3]> {
+        let grid = self.world_to_grid(world);
+        let mut cell = [0; 3];
+        for axis in 0..3 {
+            let index = grid[axis].round();
+            if !(0.0..self.dims[axis] as f32).contains(&index) {
+                return None;
+            }
+            cell[axis] = index as u32;
+        }
+        Some(cell)
+    }
+
+    /// Position in `[0, 1]³` across the grid box to world position
+    pub fn normalized_to_world(&self, normalized: Vector3<f32>) -> Vector3<f32> {
+        self.min + self.size().mul_element_wise(normalized)
+    }
+
+    /// World position to its position in `[0, 1]³` across the grid box
+    pub fn world_to_normalized(&self, world: Vector3<f32>) -> Vector3<f32> {
+        let size = self
+            .size()
+            .map(|extent| if extent == 0.0 { 1.0 } else { extent });
+        (world - self.min).div_element_wise(size)
+    }
+
+    /// Offset of a sample in an x-fastest, then y, then z array
+    pub fn linear_index(&self, cell: [u32; 3]) -> usize {
+        let [width, height, _] = self.dims;
+        cell[0] as usize + width as usize * (cell[1] as usize + height as usize * cell[2] as usize)
+    }
+
+    /// Layer of the grid nearest to `normalized` (0 to 1) along `axis`
+    pub fn layer(&self, axis: usize, normalized: f32) -> u32 {
+        let last = self.dims[axis] - 1;
+        ((normalized.clamp(0.0, 1.0) * last as f32).round() as u32).min(last)
+    }
+
+    /// World coordinate of the layer nearest to `normalized` along `axis`
+    pub fn layer_position(&self, axis: usize, normalized: f32) -> f32 {
+        let mut cell = [0; 3];
+        cell[axis] = self.layer(axis, normalized);
+        self.cell_to_world(cell)[axis]
+    }
+
+    /// Wireframe box around the grid
+    pub fn domain_box(&self) -> DomainBox {
+        DomainBox::new(self.min, self.max)
+    }
+
+    /// Grid sample under a click in the 3D view, for probing field values
+    ///
+    /// The picked point is the hit on the clicked object's bounds, so this is
+    /// most useful with an object covering the domain, such as a cut plane.
+    pub fn probe(&self, pick: &PickEvent) -> Option<[u32; 3]> {
+        self.world_to_cell(pick.point)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grid_and_world_round_trip() {
+        let grid = GridTransform::centered([5, 9, 3], 2.0);
+        assert_eq!(
+            grid.cell_to_world([0, 0, 0]),
+            Vector3::new(-2.0, -2.0, -2.0)
+        );
+        assert_eq!(grid.cell_to_world([4, 8, 2]), Vector3::new(2.0, 2.0, 2.0));
+        assert_eq!(grid.cell_to_world([2, 4, 1]), Vector3::new(0.0, 0.0, 0.0));
+        assert_eq!(
+            grid.world_to_cell(Vector3::new(0.9, -1.1, 0.1)),
+            Some([3, 2, 1])
+        );
+        assert_eq!(grid.world_to_cell(Vector3::new(2.5, 0.0, 0.0)), None);
+        assert_eq!(grid.linear_index([1, 2, 1]), 1 + 5 * (2 + 9));
+
+        // Matches the hand-written `(t - 0.5) * scale * 2.0` mapping of the examples
+        let t = 0.75;
+        assert_eq!(grid.layer(2, t), 2);
+        assert_eq!(grid.layer_position(2, t), (1.0 - 0.5) * 2.0 * 2.0);
+    }
+
+    #[test]
+    fn test_single_sample_axes_map_to_min() {
+        let grid = GridTransform::new([4, 4, 1], [0.0, 0.0, 1.0], [3.0, 3.0, 1.0]);
+        assert_eq!(grid.cell_to_world([3, 0, 0]), Vector3::new(3.0, 0.0, 1.0));
+        assert_eq!(
+            grid.world_to_cell(Vector3::new(1.2, 2.0, 1.0)),
+            Some([1, 2, 0])
+        );
+    }
+}
@@ -0,0 +1,657 @@
+//! Line Probe Visualization Component
+//!
+//! [`LineProbe`] samples a field along a segment through a simulation grid
+//! and plots the profile every frame, e.g. the velocity profile across a
+//! channel. The segment is drawn in the 3D view, and its endpoints can be
+//! dragged with handles while the probe's panel is open; dragging moves an
+//! endpoint parallel to the screen and keeps it inside the grid.
+//!
+//! Fields come either from the CPU through
+//! [`VisualizationComponent::set_data`] (one value per cell), or from a GPU
+//! storage buffer with [`LineProbe::with_buffer`], sampled by a compute pass
+//! and read back without blocking. Cells with several values, like the
+//! `[vx, vy, vz, density]` cells of an LBM solver, are probed per component or
+//! by the magnitude of their first three values (see [`ProbeValue`]).
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::visualization::{GridTransform, LineProbe, ProbeValue};
+//!
+//! # fn example(velocity: &wgpu::Buffer) {
+//! let grid = GridTransform::centered([128, 64, 1], 1.0);
+//! // Streamwise velocity across the channel, halfway down it
+//! let probe = LineProbe::new(grid, [0.0, -1.0, 0.0], [0.0, 1.0, 0.0])
+//!     .with_buffer(velocity, 4)
+//!     .with_value(ProbeValue::Component(0));
+//!
+//! let mut app = haggis::default();
+//! app.add_visualization("velocity_profile", probe);
+//! # }
+//! ```
+
+use std::sync::{Arc, OnceLock};
+
+use cgmath::{Matrix4, SquareMatrix, Vector3};
+use imgui::Ui;
+use wgpu::{Buffer, Device, Queue};
+
+use super::grid_transform::GridTransform;
+use super::traits::VisualizationComponent;
+use crate::gfx::overlay::project;
+use crate::gfx::rendering::line_renderer::LineVertex;
+use crate::gfx::scene::Scene;
+
+/// Most samples along a probe
+pub const MAX_SAMPLES: u32 = 512;
+
+/// Screen radius of the endpoint handles, in pixels
+const HANDLE_RADIUS: f32 = 7.0;
+
+/// Value of a cell plotted by a [`LineProbe`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProbeValue {
+    /// One value of each cell, e.g. `0` for the X velocity
+    Component(u32),
+    /// Length of the first (up to) three values of each cell
+    #[default]
+    Magnitude,
+}
+
+impl ProbeValue {
+    fn of(self, cell: &[f32]) -> f32 {
+        match self {
+            ProbeValue::Component(component) => {
+                cell.get(component as usize).copied().unwrap_or(0.0)
+            }
+            ProbeValue::Magnitude => cell.iter().take(3).map(|v| v * v).sum::<f32>().sqrt(),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct ProbeUniforms {
+    /// Segment ends in grid coordinates
+    start: [f32; 4],
+    end: [f32; 4],
+    /// Grid size, then values per cell
+    dims: [u32; 4],
+    /// Sample count, component, and 1 for the magnitude
+    mode: [u32; 4],
+}
+
+#[derive(Debug)]
+enum ReadState {
+    Idle,
+    /// Waiting for the readback buffer to map, then whether it mapped
+    Reading(u32, Arc<OnceLock<bool>>),
+}
+
+/// Compute pass sampling a GPU field along the probe
+struct ProbeGpu {
+    pipeline: wgpu::ComputePipeline,
+    bind_group: wgpu::BindGroup,
+    uniforms: Buffer,
+    samples: Buffer,
+    readback: Buffer,
+    state: ReadState,
+}
+
+impl ProbeGpu {
+    fn new(device: &Device, field: &Buffer) -> Self {
+        let uniforms = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Line Probe Uniforms"),
+            size: std::mem::size_of::<ProbeUniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let samples = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Line Probe Samples"),
+            size: MAX_SAMPLES as u64 * 4,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Line Probe Readback"),
+            size: MAX_SAMPLES as u64 * 4,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Line Probe Shader"),
+            source: wgpu::ShaderSource::Wgsl(PROBE_SHADER.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Line Probe Pipeline"),
+            layout: None,
+            module: &shader,
+            entry_point: Some("sample_line"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Line Probe Bind Group"),
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniforms.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: field.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: samples.as_entire_binding(),
+                },
+            ],
+        });
+        Self {
+            pipeline,
+            bind_group,
+            uniforms,
+            samples,
+            readback,
+            state: ReadState::Idle,
+        }
+    }
+
+    /// Sample along the probe unless the last samples are still read back
+    fn sample(&mut self, device: &Device, queue: &Queue, uniforms: &ProbeUniforms) {
+        if !matches!(self.state, ReadState::Idle) {
+            return;
+        }
+        let count = uniforms.mode[0];
+        queue.write_buffer(&self.uniforms, 0, bytemuck::bytes_of(uniforms));
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Line Probe Encoder"),
+        });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Line Probe"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &self.bind_group, &[]);
+            pass.dispatch_workgroups(count.div_ceil(64), 1, 1);
+        }
+        encoder.copy_buffer_to_buffer(&self.samples, 0, &self.readback, 0, count as u64 * 4);
+        queue.submit(Some(encoder.finish()));
+
+        let mapped = Arc::new(OnceLock::new());
+        {
+            let mapped = mapped.clone();
+            self.readback
+                .slice(..count as u64 * 4)
+                .map_async(wgpu::MapMode::Read, move |result| {
+                    let _ = mapped.set(result.is_ok());
+                });
+        }
+        self.state = ReadState::Reading(count, mapped);
+    }
+
+    /// Samples read back since the last call, without blocking
+    fn poll(&mut self, device: &Device) -> Option<Vec<f32>> {
+        let ReadState::Reading(count, mapped) = &self.state else {
+            return None;
+        };
+        let count = *count as u64;
+        if mapped.get().is_none() {
+            let _ = device.poll(wgpu::MaintainBase::Poll);
+        }
+        match mapped.get() {
+            None => return None,
+            Some(false) => {
+                self.state = ReadState::Idle;
+                return None;
+            }
+            Some(true) => {}
+        }
+        let samples = {
+            let range = self.readback.slice(..count * 4).get_mapped_range();
+            bytemuck::cast_slice(&range).to_vec()
+        };
+        self.readback.unmap();
+        self.state = ReadState::Idle;
+        Some(samples)
+    }
+}
+
+/// Profile of a field along a segment, plotted live
+pub struct LineProbe {
+    enabled: bool,
+    grid: GridTransform,
+    start: Vector3<f32>,
+    end: Vector3<f32>,
+    sample_count: u32,
+    value: ProbeValue,
+    /// Values per cell of the field
+    stride: u32,
+    color: [f32; 4],
+    /// Latest samples, from start to end
+    profile: Vec<f32>,
+    /// CPU field from `set_data`, one value per cell
+    cpu_field: Option<Vec<f32>>,
+    /// GPU field, sampled once a device is available
+    field: Option<Buffer>,
+    gpu: Option<ProbeGpu>,
+    /// Camera of the last scene update, for the handles
+    view_proj: Option<Matrix4<f32>>,
+}
+
+impl LineProbe {
+    /// Probe from `start` to `end` in world space, through `grid`
+    pub fn new(
+        grid: GridTransform,
+        start: impl Into<Vector3<f32>>,
+        end: impl Into<Vector3<f32>>,
+    ) -> Self {
+        Self {
+            enabled: true,
+            grid,
+            start: start.into(),
+            end: end.into(),
+            sample_count: 128,
+            value: ProbeValue::default(),
+            stride: 1,
+            color: [1.0, 0.85, 0.2, 1.0],
+            profile: Vec::new(),
+            cpu_field: None,
+            field: None,
+            gpu: None,
+            view_proj: None,
+        }
+    }
+
+    /// Sample a GPU storage buffer with `stride` values per cell, x fastest
+    pub fn with_buffer(mut self, buffer: &Buffer, stride: u32) -> Self {
+        self.field = Some(buffer.clone());
+        self.gpu = None;
+        self.stride = stride.max(1);
+        self
+    }
+
+    pub fn with_value(mut self, value: ProbeValue) -> Self {
+        self.value = value;
+        self
+    }
+
+    /// Number of samples along the segment, up to [`MAX_SAMPLES`]
+    pub fn with_samples(mut self, count: u32) -> Self {
+        self.sample_count = count.clamp(2, MAX_SAMPLES);
+        self
+    }
+
+    pub fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Move the segment, keeping both ends inside the grid
+    pub fn set_endpoints(&mut self, start: impl Into<Vector3<f32>>, end: impl Into<Vector3<f32>>) {
+        self.start = self.clamp_to_grid(start.into());
+        self.end = self.clamp_to_grid(end.into());
+    }
+
+    pub fn endpoints(&self) -> (Vector3<f32>, Vector3<f32>) {
+        (self.start, self.end)
+    }
+
+    /// Latest samples, from start to end
+    pub fn profile(&self) -> &[f32] {
+        &self.profile
+    }
+
+    fn clamp_to_grid(&self, point: Vector3<f32>) -> Vector3<f32> {
+        let (min, max) = (self.grid.min(), self.grid.max());
+        Vector3::new(
+            point.x.clamp(min.x.min(max.x), min.x.max(max.x)),
+            point.y.clamp(min.y.min(max.y), min.y.max(max.y)),
+            point.z.clamp(min.z.min(max.z), min.z.max(max.z)),
+        )
+    }
+
+    fn uniforms(&self) -> ProbeUniforms {
+        let start = self.grid.world_to_grid(self.start);
+        let end = self.grid.world_to_grid(self.end);
+        let [width, height, depth] = self.grid.dims();
+        let (component, magnitude) = match self.value {
+            ProbeValue::Component(component) => (component, 0),
+            ProbeValue::Magnitude => (0, 1),
+        };
+        ProbeUniforms {
+            start: [start.x, start.y, start.z, 0.0],
+            end: [end.x, end.y, end.z, 0.0],
+            dims: [width, height, depth, self.stride],
+            mode: [self.sample_count, component, magnitude, 0],
+        }
+    }
+
+    /// Sample a CPU field with `stride` values per cell, like the GPU pass
+    fn sample_cpu(&self, data: &[f32], stride: usize) -> Vec<f32> {
+        let [width, height, depth] = self.grid.dims().map(|n| n as i64);
+        let cell = |x: i64, y: i64, z: i64| {
+            let index = (x.clamp(0, width - 1)
+                + width * (y.clamp(0, height - 1) + height * z.clamp(0, depth - 1)))
+                as usize;
+            data.get(index * stride..(index + 1) * stride)
+                .map_or(0.0, |values| self.value.of(values))
+        };
+        let (start, end) = (
+            self.grid.world_to_grid(self.start),
+            self.grid.world_to_grid(self.end),
+        );
+        (0..self.sample_count)
+            .map(|i| {
+                let t = i as f32 / (self.sample_count - 1) as f32;
+                let p = start + (end - start) * t;
+                let base = p.map(f32::floor);
+                let f = p - base;
+                let [x, y, z] = [base.x as i64, base.y as i64, base.z as i64];
+                let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+                let along_x = |y, z| lerp(cell(x, y, z), cell(x + 1, y, z), f.x);
+                let along_y = |z| lerp(along_x(y, z), along_x(y + 1, z), f.y);
+                lerp(along_y(z), along_y(z + 1), f.z)
+            })
+            .collect()
+    }
+
+    /// Line list for the segment and a cross at each end
+    pub fn line_vertices(&self) -> Vec<LineVertex> {
+        let size = self.grid.size();
+        let tick = 0.015 * size.x.abs().max(size.y.abs()).max(size.z.abs());
+        let mut vertices = vec![
+            LineVertex::new(self.start.into(), self.color),
+            LineVertex::new(self.end.into(), self.color),
+        ];
+        for point in [self.start, self.end] {
+            for axis in 0..3 {
+                let mut offset = Vector3::new(0.0, 0.0, 0.0);
+                offset[axis] = tick;
+                vertices.push(LineVertex::new((point - offset).into(), self.color));
+                vertices.push(LineVertex::new((point + offset).into(), self.color));
+            }
+        }
+        vertices
+    }
+
+    /// Draw draggable handles over the endpoints and move them when dragged
+    fn render_handles(&mut self, ui: &Ui) {
+        let Some(view_proj) = self.view_proj else {
+            return;
+        };
+        let display_size = ui.io().display_size;
+        for (index, label) in ["A", "B"].into_iter().enumerate() {
+            let point = if index == 0 { self.start } else { self.end };
+            let Some(screen) = project(view_proj, point, display_size) else {
+                continue;
+            };
+            let size = 2.0 * HANDLE_RADIUS;
+            let mut dragged = None;
+            // A tiny window takes the mouse from the camera over the handle
+            ui.window(format!("##line_probe_{:p}_{index}", self))
+                .position(
+                    [screen[0] - HANDLE_RADIUS, screen[1] - HANDLE_RADIUS],
+                    imgui::Condition::Always,
+                )
+                .size([size, size], imgui::Condition::Always)
+                .no_decoration()
+                .movable(false)
+                .draw_background(false)
+                .build(|| {
+                    ui.set_cursor_pos([0.0, 0.0]);
+                    ui.invisible_button("handle", [size, size]);
+                    if ui.is_item_active() {
+                        dragged = Some(ui.io().mouse_delta);
+                    }
+                });
+
+            let draw_list = ui.get_background_draw_list();
+            draw_list
+                .add_circle(screen, HANDLE_RADIUS, self.color)
+                .filled(dragged.is_some())
+                .build();
+            draw_list.add_text(
+                [screen[0] + HANDLE_RADIUS + 2.0, screen[1] - HANDLE_RADIUS],
+                self.color,
+                label,
+            );
+
+            if let Some(moved) =
+                dragged.and_then(|delta| drag_point(view_proj, point, delta, display_size))
+            {
+                let moved = self.clamp_to_grid(moved);
+                if index == 0 {
+                    self.start = moved;
+                } else {
+                    self.end = moved;
+                }
+            }
+        }
+    }
+}
+
+/// `point` moved by a screen-space `delta`, parallel to the screen
+fn drag_point(
+    view_proj: Matrix4<f32>,
+    point: Vector3<f32>,
+    delta: [f32; 2],
+    display_size: [f32; 2],
+) -> Option<Vector3<f32>> {
+    let clip = view_proj * point.extend(1.0);
+    if clip.w <= 0.0 {
+        return None;
+    }
+    let mut ndc = clip.truncate() / clip.w;
+    ndc.x += 2.0 * delta[0] / display_size[0];
+    ndc.y -= 2.0 * delta[1] / display_size[1];
+    let world = view_proj.invert()? * ndc.extend(1.0);
+    Some(world.truncate() / world.w)
+}
+
+impl VisualizationComponent for LineProbe {
+    fn initialize(&mut self, _device: Option<&Device>, _queue: Option<&Queue>) {}
+
+    fn update(&mut self, _delta_time: f32, device: Option<&Device>, queue: Option<&Queue>) {
+        if let Some(data) = &self.cpu_field {
+            self.profile = self.sample_cpu(data, 1);
+            return;
+        }
+        let (Some(device), Some(queue), Some(field)) = (device, queue, &self.field) else {
+            return;
+        };
+        let uniforms = self.uniforms();
+        let gpu = self.gpu.get_or_insert_with(|| ProbeGpu::new(device, field));
+        if let Some(profile) = gpu.poll(device) {
+            self.profile = profile;
+        }
+        gpu.sample(device, queue, &uniforms);
+    }
+
+    fn render_ui(&mut self, ui: &Ui) {
+        ui.checkbox("Enabled", &mut self.enabled);
+        if !self.profile.is_empty() {
+            let min = self.profile.iter().copied().fold(f32::INFINITY, f32::min);
+            let max = self
+                .profile
+                .iter()
+                .copied()
+                .fold(f32::NEG_INFINITY, f32::max);
+            let mean = self.profile.iter().sum::<f32>() / self.profile.len() as f32;
+            ui.plot_lines("##profile", &self.profile)
+                .graph_size([ui.content_region_avail()[0], 120.0])
+                .scale_min(min)
+                .scale_max(if max > min { max } else { min + 1.0 })
+                .build();
+            ui.text(format!("Min {min:.4}  Max {max:.4}  Mean {mean:.4}"));
+        }
+
+        ui.separator();
+        let mut count = self.sample_count as i32;
+        if ui.slider("Samples", 2, MAX_SAMPLES as i32, &mut count) {
+            self.sample_count = count as u32;
+        }
+        if self.stride > 1 {
+            let mut selected = match self.value {
+                ProbeValue::Magnitude => 0,
+                ProbeValue::Component(component) => component as usize + 1,
+            };
+            let mut items = vec!["Magnitude".to_string()];
+            items.extend((0..self.stride).map(|component| format!("Component {component}")));
+            if ui.combo_simple_string("Value", &mut selected, &items) {
+                self.value = match selected {
+                    0 => ProbeValue::Magnitude,
+                    n => ProbeValue::Component(n as u32 - 1),
+                };
+            }
+        }
+
+        let mut start: [f32; 3] = self.start.into();
+        let mut end: [f32; 3] = self.end.into();
+        let edited_start = ui.input_float3("Start (A)", &mut start).build();
+        let edited_end = ui.input_float3("End (B)", &mut end).build();
+        if edited_start || edited_end {
+            self.set_endpoints(start, end);
+        }
+        let length = (self.end - self.start).map(f32::abs);
+        ui.text(format!(
+            "Length: {:.3}",
+            (length.x * length.x + length.y * length.y + length.z * length.z).sqrt()
+        ));
+        ui.text_disabled("Drag the A and B handles to move the probe");
+
+        self.render_handles(ui);
+    }
+
+    fn name(&self) -> &str {
+        "Line Probe"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn set_data(&mut self, data: &[f32], _dimensions: (u32, u32, u32)) {
+        self.cpu_field = Some(data.to_vec());
+        self.stride = 1;
+    }
+
+    fn get_ui_size(&self) -> (f32, f32) {
+        (400.0, 320.0)
+    }
+
+    fn update_scene_objects(&mut self, scene: &mut Scene) {
+        self.view_proj = Some(scene.camera_manager.get_view_proj_matrix());
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
+
+const PROBE_SHADER: &str = r#"
+struct ProbeUniforms {
+    start: vec4<f32>,
+    end: vec4<f32>,
+    dims: vec4<u32>,
+    mode: vec4<u32>,
+}
+
+@group(0) @binding(0) var<uniform> probe: ProbeUniforms;
+@group(0) @binding(1) var<storage, read> field: array<f32>;
+@group(0) @binding(2) var<storage, read_write> samples: array<f32>;
+
+fn cell_value(cell: vec3<i32>) -> f32 {
+    let c = vec3<u32>(clamp(cell, vec3<i32>(0), vec3<i32>(probe.dims.xyz) - vec3<i32>(1)));
+    let base = ((c.z * probe.dims.y + c.y) * probe.dims.x + c.x) * probe.dims.w;
+    if (probe.mode.z == 0u) {
+        return select(0.0, field[base + probe.mode.y], probe.mode.y < probe.dims.w);
+    }
+    var sum = 0.0;
+    for (var i = 0u; i < min(probe.dims.w, 3u); i++) {
+        sum += field[base + i] * field[base + i];
+    }
+    return sqrt(sum);
+}
+
+@compute @workgroup_size(64)
+fn sample_line(@builtin(global_invocation_id) id: vec3<u32>) {
+    let count = probe.mode.x;
+    if (id.x >= count) {
+        return;
+    }
+    let t = f32(id.x) / f32(count - 1u);
+    let p = mix(probe.start.xyz, probe.end.xyz, t);
+    let base = floor(p);
+    let f = p - base;
+    let b = vec3<i32>(base);
+    let x00 = mix(cell_value(b), cell_value(b + vec3<i32>(1, 0, 0)), f.x);
+    let x10 = mix(cell_value(b + vec3<i32>(0, 1, 0)), cell_value(b + vec3<i32>(1, 1, 0)), f.x);
+    let x01 = mix(cell_value(b + vec3<i32>(0, 0, 1)), cell_value(b + vec3<i32>(1, 0, 1)), f.x);
+    let x11 = mix(cell_value(b + vec3<i32>(0, 1, 1)), cell_value(b + vec3<i32>(1, 1, 1)), f.x);
+    samples[id.x] = mix(mix(x00, x10, f.y), mix(x01, x11, f.y), f.z);
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wgpu_utils::compute_primitives::{test_buffer, test_device};
+    use cgmath::InnerSpace;
+
+    /// 5 x 3 x 1 grid at world x = 0..4, with cells `[x, 2]`
+    fn ramp_probe() -> (LineProbe, Vec<f32>) {
+        let grid = GridTransform::new([5, 3, 1], [0.0, 0.0, 0.0], [4.0, 2.0, 0.0]);
+        let probe = LineProbe::new(grid, [0.0, 1.0, 0.0], [4.0, 1.0, 0.0]).with_samples(9);
+        let field = (0..15).flat_map(|i| [(i % 5) as f32, 2.0]).collect();
+        (probe, field)
+    }
+
+    #[test]
+    fn test_samples_and_drag() {
+        let (probe, field) = ramp_probe();
+        let probe = probe.with_value(ProbeValue::Component(0));
+        let profile = probe.sample_cpu(&field, 2);
+        let expected: Vec<f32> = (0..9).map(|i| i as f32 * 0.5).collect();
+        assert_eq!(profile, expected);
+
+        // Half the screen width is one NDC unit
+        let identity = Matrix4::identity();
+        let moved = drag_point(
+            identity,
+            Vector3::new(0.0, 0.0, 0.5),
+            [100.0, 50.0],
+            [200.0, 200.0],
+        );
+        assert!((moved.unwrap() - Vector3::new(1.0, -0.5, 0.5)).magnitude() < 1e-6);
+    }
+
+    #[test]
+    fn test_gpu_samples_match_cpu() {
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let (probe, field) = ramp_probe();
+        let buffer = test_buffer(&device, &field);
+        let mut probe = probe.with_buffer(&buffer, 2);
+        let expected = probe.sample_cpu(&field, 2);
+
+        probe.update(0.0, Some(&device), Some(&queue));
+        let _ = device.poll(wgpu::MaintainBase::Wait);
+        probe.update(0.0, Some(&device), Some(&queue));
+        for (gpu, cpu) in probe.profile().iter().zip(&expected) {
+            assert!((gpu - cpu).abs() < 1e-5, "{gpu} != {cpu}");
+        }
+        assert_eq!(probe.profile().len(), 9);
+    }
+}
@@ -4,7 +4,11 @@
 //! the main engine loop and UI system.
 
 use super::traits::VisualizationComponent;
-use crate::gfx::{rendering::VisualizationPlane, scene::Scene};
+use crate::gfx::{
+    rendering::{AgentBatch, ClipPlane, LineVertex, VisualizationPlane, VolumeBatch},
+    scene::Scene,
+};
+use cgmath::Matrix4;
 use imgui::Ui;
 use std::collections::HashMap;
 use wgpu::{Device, Queue};
@@ -231,4 +235,90 @@ impl VisualizationManager {
         }
         planes
     }
+
+    fn domain_boxes(&self) -> impl Iterator<Item = &super::domain_box::DomainBox> {
+        self.components
+            .values()
+            .filter(|component| self.enabled && component.is_enabled())
+            .filter_map(|component| {
+                component
+                    .as_any()
+                    .downcast_ref::<super::domain_box::DomainBox>()
+            })
+    }
+
+    /// Get world-space lines of enabled domain boxes and line probes for rendering
+    pub fn get_visualization_lines(&self) -> Vec<LineVertex> {
+        let probes = self
+            .components
+            .values()
+            .filter(|component| self.enabled && component.is_enabled())
+            .filter_map(|component| {
+                component
+                    .as_any()
+                    .downcast_ref::<super::line_probe::LineProbe>()
+            });
+        self.domain_boxes()
+            .flat_map(|domain| domain.line_vertices())
+            .chain(probes.flat_map(|probe| probe.line_vertices()))
+            .collect()
+    }
+
+    /// Get clip planes of enabled cut planes that clip scene geometry
+    pub fn get_clip_planes(&self) -> Vec<ClipPlane> {
+        if !self.enabled {
+            return Vec::new();
+        }
+        self.components
+            .values()
+            .filter(|component| component.is_enabled())
+            .filter_map(|component| {
+                component
+                    .as_any()
+                    .downcast_ref::<super::cut_plane_2d::CutPlane2D>()
+            })
+            .filter_map(|cut_plane| cut_plane.clip_plane())
+            .collect()
+    }
+
+    /// Get agent batches of enabled agent views for rendering
+    pub fn get_agent_batches(&self) -> Vec<AgentBatch> {
+        if !self.enabled {
+            return Vec::new();
+        }
+        self.components
+            .values()
+            .filter(|component| component.is_enabled())
+            .filter_map(|component| {
+                component
+                    .as_any()
+                    .downcast_ref::<super::agent_view::AgentView>()
+            })
+            .filter_map(|view| view.batch())
+            .collect()
+    }
+
+    /// Get volume batches of enabled volume views for rendering
+    pub fn get_volume_batches(&self) -> Vec<VolumeBatch> {
+        if !self.enabled {
+            return Vec::new();
+        }
+        self.components
+            .values()
+            .filter(|component| component.is_enabled())
+            .filter_map(|component| {
+                component
+                    .as_any()
+                    .downcast_ref::<super::volume_view::VolumeView>()
+            })
+            .filter_map(|view| view.batch())
+            .collect()
+    }
+
+    /// Draw world-space labels (e.g. domain box faces) over the scene
+    pub fn render_labels(&self, ui: &Ui, view_proj: Matrix4<f32>) {
+        for domain in self.domain_boxes() {
+            domain.render_labels(ui, view_proj);
+        }
+    }
 }
@@ -12,8 +12,13 @@
 //!
 //! ## Key Components
 //!
+//! - [`AgentView`] - Instanced agents pointing along their velocity, or spheres
 //! - [`CutPlane2D`] - 2D cross-section visualization of 3D data
+//! - [`DomainBox`] - Wireframe bounds of a simulation domain
+//! - [`GridTransform`] - Mapping between simulation grid and world coordinates
+//! - [`LineProbe`] - Live profile of a field along a draggable segment
 //! - [`VisualizationManager`] - Manages multiple visualization components
+//! - [`VolumeView`] - Ray marched volume of a scalar field on a grid
 //! - [`ui`] - UI panels for visualization controls
 //!
 //! ## Usage
@@ -26,14 +31,24 @@
 //! viz_manager.add_component("cut_plane", Box::new(cut_plane));
 //! ```
 
+pub mod agent_view;
 pub mod cut_plane_2d;
+pub mod domain_box;
+pub mod grid_transform;
+pub mod line_probe;
 pub mod manager;
 pub mod rendering;
 pub mod traits;
 pub mod ui;
+pub mod volume_view;
 
 // Re-export main types
+pub use agent_view::AgentView;
 pub use cut_plane_2d::CutPlane2D;
+pub use domain_box::{BoxFace, DomainBox};
+pub use grid_transform::GridTransform;
+pub use line_probe::{LineProbe, ProbeValue};
 pub use manager::VisualizationManager;
 pub use rendering::{VisualizationMaterial, VisualizationRenderer};
 pub use traits::VisualizationComponent;
+pub use volume_view::VolumeView;
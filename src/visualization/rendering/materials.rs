@@ -4,11 +4,45 @@
 //! Supports both traditional texture-based rendering and direct GPU buffer access.
 
 use crate::gfx::resources::texture_resource::TextureResource;
-use crate::visualization::cut_plane_2d::BufferFormat;
-use crate::visualization::ui::cut_plane_controls::VisualizationMode;
-use std::sync::Arc;
+use crate::visualization::cut_plane_2d::{BufferElementType, BufferFormat};
+use crate::visualization::ui::cut_plane_controls::{Colormap, VisualizationMode};
+use std::sync::{Arc, Mutex};
 use wgpu::*;
 
+/// Uniforms of the GPU buffer path, matching `FilterUniforms` in the shader
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct FilterUniforms {
+    filter_mode: u32, // 0 = sharp, 1 = smooth
+    grid_width: u32,
+    grid_height: u32,
+    element_type: u32, // 0 = integer data (vorticity colors), 1 = f32 data (heatmap)
+    value_min: f32,
+    value_max: f32,
+    colormap: u32, // Colormap::shader_index of f32 data
+    _padding: u32,
+}
+
+impl FilterUniforms {
+    fn new(
+        filter_mode: u32,
+        format: &BufferFormat,
+        value_range: [f32; 2],
+        colormap: Colormap,
+    ) -> Self {
+        Self {
+            filter_mode,
+            grid_width: format.width,
+            grid_height: format.height,
+            element_type: matches!(format.element_type, BufferElementType::F32) as u32,
+            value_min: value_range[0],
+            value_max: value_range[1],
+            colormap: colormap.shader_index(),
+            _padding: 0,
+        }
+    }
+}
+
 /// Material for visualization components
 #[derive(Clone)]
 pub struct VisualizationMaterial {
@@ -19,6 +53,11 @@ pub struct VisualizationMaterial {
     pub bind_group: Option<BindGroup>,
     pub transform_buffer: Option<Buffer>,
     pub filter_uniform_buffer: Option<Buffer>,   // For GPU filter mode
+    pub value_range: [f32; 2],                   // Heatmap range of f32 GPU data
+    pub colormap: Colormap,                      // Colors of f32 GPU data
+    /// Model matrix last written to `transform_buffer`, shared by clones
+    /// since they share the buffer
+    uploaded_transform: Arc<Mutex<Option<[[f32; 4]; 4]>>>,
 }
 
 impl VisualizationMaterial {
@@ -32,6 +71,9 @@ impl VisualizationMaterial {
             bind_group: None,
             transform_buffer: None,
             filter_uniform_buffer: None,
+            value_range: [0.0, 1.0],
+            colormap: Colormap::default(),
+            uploaded_transform: Arc::default(),
         }
     }
 
@@ -145,22 +187,17 @@ impl VisualizationMaterial {
         });
 
         // Create and initialize filter uniform buffer with default sharp filtering
-        let filter_uniform_data = [
-            0u32,                    // filter_mode: 0 = sharp (default)
-            format.width,            // grid_width
-            format.height,           // grid_height  
-            0u32,                    // padding
-        ];
-        
+        let filter_uniform_data = FilterUniforms::new(0, &format, [0.0, 1.0], Colormap::default());
+
         let filter_uniform_buffer = device.create_buffer(&BufferDescriptor {
             label: Some(&format!("{} Filter Uniform Buffer", label)),
-            size: (4 * std::mem::size_of::<u32>()) as BufferAddress,
+            size: std::mem::size_of::<FilterUniforms>() as BufferAddress,
             usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
 
         // Initialize the buffer with default values
-        queue.write_buffer(&filter_uniform_buffer, 0, bytemuck::cast_slice(&filter_uniform_data));
+        queue.write_buffer(&filter_uniform_buffer, 0, bytemuck::bytes_of(&filter_uniform_data));
 
         let bind_group = device.create_bind_group(&BindGroupDescriptor {
             label: Some(&format!("{} GPU Buffer Bind Group", label)),
@@ -197,6 +234,9 @@ impl VisualizationMaterial {
             bind_group: Some(bind_group),
             transform_buffer: Some(transform_buffer),
             filter_uniform_buffer: Some(filter_uniform_buffer),
+            value_range: [0.0, 1.0],
+            colormap: Colormap::default(),
+            uploaded_transform: Arc::default(),
         }
     }
 
@@ -231,7 +271,7 @@ impl VisualizationMaterial {
         // Convert f32 data to RGBA8 with proper row alignment
         let expected_size = (width * height) as usize;
         if data.len() != expected_size {
-            eprintln!("Warning: Data size mismatch. Expected {}, got {}", expected_size, data.len());
+            tracing::warn!("Data size mismatch. Expected {}, got {}", expected_size, data.len());
         }
         
         let rgba_data: Vec<u8> = data
@@ -252,6 +292,15 @@ impl VisualizationMaterial {
         let texture =
             TextureResource::create_from_rgba_data_with_filter(device, queue, &rgba_data, width, height, label, filter_mode);
 
+        Self::from_texture(device, queue, texture)
+    }
+
+    /// Create a material sampling an existing texture, such as one written by a
+    /// compute shader through a storage binding
+    ///
+    /// The texture needs a filterable float format like `Rgba8Unorm` and must be
+    /// larger than 1x1, which the shader reserves for the GPU buffer path.
+    pub fn from_texture(device: &Device, queue: &Queue, texture: TextureResource) -> Self {
         // Create a dummy storage buffer for the material bind group
         let dummy_buffer = device.create_buffer(&BufferDescriptor {
             label: Some("Dummy Storage Buffer"),
@@ -285,7 +334,7 @@ impl VisualizationMaterial {
         // Create dummy filter uniform buffer for consistency with GPU path
         let dummy_filter_buffer = device.create_buffer(&BufferDescriptor {
             label: Some("Dummy Filter Uniform Buffer"),
-            size: (4 * std::mem::size_of::<u32>()) as BufferAddress,
+            size: std::mem::size_of::<FilterUniforms>() as BufferAddress,
             usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
@@ -383,6 +432,9 @@ impl VisualizationMaterial {
             bind_group: Some(bind_group),
             transform_buffer: Some(transform_buffer),
             filter_uniform_buffer: Some(dummy_filter_buffer),
+            value_range: [0.0, 1.0],
+            colormap: Colormap::default(),
+            uploaded_transform: Arc::default(),
         }
     }
 
@@ -432,7 +484,7 @@ impl VisualizationMaterial {
         Self::from_2d_data(device, queue, &data, width, height, "Checkerboard Material")
     }
 
-    /// Update the filter mode for GPU materials
+    /// Update the filter mode (and `value_range` and `colormap`) for GPU materials
     pub fn update_filter_mode(&self, queue: &Queue, filter_mode: crate::visualization::ui::cut_plane_controls::FilterMode) {
         if let (Some(filter_buffer), Some(format)) = (&self.filter_uniform_buffer, &self.buffer_format) {
             let filter_mode_value = match filter_mode {
@@ -440,18 +492,13 @@ impl VisualizationMaterial {
                 crate::visualization::ui::cut_plane_controls::FilterMode::Smooth => 1u32,
             };
             
-            let filter_uniform_data = [
-                filter_mode_value,   // filter_mode
-                format.width,        // grid_width
-                format.height,       // grid_height
-                0u32,                // padding
-            ];
-            
-            queue.write_buffer(filter_buffer, 0, bytemuck::cast_slice(&filter_uniform_data));
+            let filter_uniform_data = FilterUniforms::new(filter_mode_value, format, self.value_range, self.colormap);
+            queue.write_buffer(filter_buffer, 0, bytemuck::bytes_of(&filter_uniform_data));
         }
     }
 
-    /// Update the transform matrix for this material
+    /// Update the transform matrix for this material, skipping the write when
+    /// it is unchanged
     pub fn update_transform(
         &self,
         queue: &Queue,
@@ -467,7 +514,11 @@ impl VisualizationMaterial {
 
             // Convert to the format expected by wgsl (column-major)
             let matrix_array: [[f32; 4]; 4] = model_matrix.into();
-            queue.write_buffer(transform_buffer, 0, bytemuck::cast_slice(&[matrix_array]));
+            let mut uploaded = self.uploaded_transform.lock().unwrap();
+            if *uploaded != Some(matrix_array) {
+                queue.write_buffer(transform_buffer, 0, bytemuck::cast_slice(&[matrix_array]));
+                *uploaded = Some(matrix_array);
+            }
         }
     }
 }
@@ -3,6 +3,8 @@
 //! Dedicated rendering system for visualization components, independent of scene objects.
 
 use super::materials::VisualizationMaterial;
+use crate::gfx::rendering::color::{color_flags, with_color_functions};
+use crate::gfx::rendering::depth::DepthMode;
 use cgmath::{Matrix4, Vector3};
 use wgpu::util::DeviceExt;
 use wgpu::*;
@@ -42,7 +44,11 @@ impl VisualizationVertex {
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct VisualizationCameraUniform {
+    pub view_position: [f32; 4],
     pub view_proj: [[f32; 4]; 4],
+    /// Output encoding of the target, see [`color`](crate::gfx::rendering::color)
+    pub color_flags: u32,
+    pub _padding: [u32; 3],
 }
 
 /// Visualization item to be rendered
@@ -93,6 +99,7 @@ pub struct VisualizationRenderer {
     render_pipeline: RenderPipeline,
     camera_buffer: Buffer,
     camera_bind_group: BindGroup,
+    color_flags: u32,
     vertex_buffer: Option<Buffer>,
     index_buffer: Option<Buffer>,
     vertex_count: u32,
@@ -100,11 +107,13 @@ pub struct VisualizationRenderer {
 }
 
 impl VisualizationRenderer {
-    pub fn new(device: &Device, surface_format: TextureFormat) -> Self {
+    pub fn new(device: &Device, surface_format: TextureFormat, depth_mode: DepthMode) -> Self {
         // Create shader
         let shader = device.create_shader_module(ShaderModuleDescriptor {
             label: Some("Visualization Shader"),
-            source: ShaderSource::Wgsl(super::shaders::VISUALIZATION_SHADER.into()),
+            source: ShaderSource::Wgsl(
+                with_color_functions(super::shaders::VISUALIZATION_SHADER).into(),
+            ),
         });
 
         // Create camera buffer
@@ -121,7 +130,7 @@ impl VisualizationRenderer {
                 label: Some("Visualization Camera Bind Group Layout"),
                 entries: &[BindGroupLayoutEntry {
                     binding: 0,
-                    visibility: ShaderStages::VERTEX,
+                    visibility: ShaderStages::VERTEX_FRAGMENT,
                     ty: BindingType::Buffer {
                         ty: BufferBindingType::Uniform,
                         has_dynamic_offset: false,
@@ -204,7 +213,7 @@ impl VisualizationRenderer {
             depth_stencil: Some(DepthStencilState {
                 format: TextureFormat::Depth32Float,
                 depth_write_enabled: true,
-                depth_compare: CompareFunction::Less,
+                depth_compare: depth_mode.compare(CompareFunction::Less),
                 stencil: StencilState::default(),
                 bias: DepthBiasState::default(),
             }),
@@ -221,6 +230,7 @@ impl VisualizationRenderer {
             render_pipeline,
             camera_buffer,
             camera_bind_group,
+            color_flags: color_flags(surface_format, false),
             vertex_buffer: None,
             index_buffer: None,
             vertex_count: 0,
@@ -231,7 +241,10 @@ impl VisualizationRenderer {
     /// Update camera uniform
     pub fn update_camera(&self, queue: &Queue, view_proj_matrix: Matrix4<f32>) {
         let camera_uniform = VisualizationCameraUniform {
+            view_position: [0.0, 0.0, 0.0, 1.0],
             view_proj: view_proj_matrix.into(),
+            color_flags: self.color_flags,
+            _padding: [0; 3],
         };
         queue.write_buffer(
             &self.camera_buffer,
@@ -4,6 +4,8 @@
 struct CameraUniform {
     view_position: vec4<f32>,
     view_proj: mat4x4<f32>,
+    // Output encoding, see gfx::rendering::color
+    color_flags: u32,
 };
 
 struct TransformUniform {
@@ -53,6 +55,10 @@ struct FilterUniforms {
     filter_mode: u32,  // 0 = nearest/sharp, 1 = linear/smooth
     grid_width: u32,
     grid_height: u32,
+    element_type: u32, // 0 = integer data, 1 = f32 data
+    value_min: f32,    // Heatmap range for f32 data
+    value_max: f32,
+    colormap: u32,     // 0 = heat, 1 = diverging, 2 = dark diverging
     _padding: u32,
 };
 
@@ -79,9 +85,52 @@ fn vorticity_to_color(vorticity: f32) -> vec4<f32> {
     }
 }
 
-// Fragment shader with dual mode support
+// Map a value in [value_min, value_max] to the selected colormap
+fn heat_to_color(value: f32) -> vec4<f32> {
+    let range = max(filter_uniforms.value_max - filter_uniforms.value_min, 1e-6);
+    let t = clamp((value - filter_uniforms.value_min) / range, 0.0, 1.0);
+    if (filter_uniforms.colormap != 0u) {
+        return diverging_color(t * 2.0 - 1.0, filter_uniforms.colormap == 2u);
+    }
+    let r = clamp(1.5 - abs(4.0 * t - 3.0), 0.0, 1.0);
+    let g = clamp(1.5 - abs(4.0 * t - 2.0), 0.0, 1.0);
+    let b = clamp(1.5 - abs(4.0 * t - 1.0), 0.0, 1.0);
+    return vec4<f32>(r, g, b, 1.0);
+}
+
+// Blue for s = -1, red for s = 1, white or black in the middle
+fn diverging_color(s: f32, dark: bool) -> vec4<f32> {
+    let middle = select(vec3<f32>(0.97), vec3<f32>(0.0), dark);
+    let low = select(vec3<f32>(0.23, 0.3, 0.75), vec3<f32>(0.25, 0.6, 1.0), dark);
+    let high = select(vec3<f32>(0.7, 0.02, 0.15), vec3<f32>(1.0, 0.45, 0.15), dark);
+    let end = select(high, low, s < 0.0);
+    return vec4<f32>(mix(middle, end, abs(s)), 1.0);
+}
+
+// Read one cell of the data buffer as a float
+fn cell_value(index: u32) -> f32 {
+    if (filter_uniforms.element_type == 1u) {
+        return bitcast<f32>(gpu_data_buffer[index]);
+    }
+    return f32(gpu_data_buffer[index]);
+}
+
+fn data_to_color(value: f32) -> vec4<f32> {
+    if (filter_uniforms.element_type == 1u) {
+        return heat_to_color(value);
+    }
+    return vorticity_to_color(value);
+}
+
+// Colormaps and CPU textures hold sRGB display colors
 @fragment
 fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let color = plane_color(input.tex_coords);
+    return vec4<f32>(encode_output(srgb_to_linear(color.rgb), camera.color_flags), color.a);
+}
+
+// Plane color with dual mode support
+fn plane_color(tex_coords: vec2<f32>) -> vec4<f32> {
     // Check if we have valid texture dimensions (1x1 indicates dummy texture = GPU mode)
     let tex_dimensions = textureDimensions(t_diffuse);
     
@@ -92,21 +141,19 @@ fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
         
         if (filter_uniforms.filter_mode == 0u) {
             // Sharp/Nearest filtering - sample exact pixel
-            let grid_x = u32(input.tex_coords.x * f32(grid_width));
-            let grid_y = u32(input.tex_coords.y * f32(grid_height));
+            let grid_x = u32(tex_coords.x * f32(grid_width));
+            let grid_y = u32(tex_coords.y * f32(grid_height));
             let index = grid_y * grid_width + grid_x;
             
             if (index < arrayLength(&gpu_data_buffer)) {
-                let cell_value = gpu_data_buffer[index];
-                let vorticity = f32(cell_value);
-                return vorticity_to_color(vorticity);
+                return data_to_color(cell_value(index));
             } else {
                 return vec4<f32>(0.0, 0.0, 0.0, 1.0);
             }
         } else {
             // Smooth/Linear filtering - bilinear interpolation between 4 neighboring pixels
-            let x_scaled = input.tex_coords.x * f32(grid_width) - 0.5;
-            let y_scaled = input.tex_coords.y * f32(grid_height) - 0.5;
+            let x_scaled = tex_coords.x * f32(grid_width) - 0.5;
+            let y_scaled = tex_coords.y * f32(grid_height) - 0.5;
             
             let x0 = u32(max(0.0, floor(x_scaled)));
             let y0 = u32(max(0.0, floor(y_scaled)));
@@ -122,20 +169,20 @@ fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
             let idx_10 = y1 * grid_width + x0;
             let idx_11 = y1 * grid_width + x1;
             
-            let val_00 = f32(gpu_data_buffer[idx_00]);
-            let val_01 = f32(gpu_data_buffer[idx_01]);
-            let val_10 = f32(gpu_data_buffer[idx_10]);
-            let val_11 = f32(gpu_data_buffer[idx_11]);
+            let val_00 = cell_value(idx_00);
+            let val_01 = cell_value(idx_01);
+            let val_10 = cell_value(idx_10);
+            let val_11 = cell_value(idx_11);
             
             // Bilinear interpolation
             let top = mix(val_00, val_01, fx);
             let bottom = mix(val_10, val_11, fx);
-            let vorticity = mix(top, bottom, fy);
+            let value = mix(top, bottom, fy);
             
-            return vorticity_to_color(vorticity);
+            return data_to_color(value);
         }
     } else {
         // Texture-based rendering (CPU data path)
-        return textureSample(t_diffuse, s_diffuse, input.tex_coords);
+        return textureSample(t_diffuse, s_diffuse, tex_coords);
     }
 }
\ No newline at end of file
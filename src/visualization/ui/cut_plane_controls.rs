@@ -93,6 +93,52 @@ impl FilterMode {
 
 }
 
+/// Colors of continuous (f32) data on GPU buffer planes
+///
+/// The diverging maps are meant for signed fields such as electric fields or
+/// vorticity: with a value range symmetric around zero, zero sits in the
+/// middle and the sign is shown by the hue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Colormap {
+    /// Blue, cyan, yellow, red from the low to the high end
+    #[default]
+    Heat,
+    /// Blue through white to red
+    Diverging,
+    /// Blue through black to red, for fields that are mostly zero
+    DivergingDark,
+}
+
+impl Colormap {
+    /// Get all available colormaps
+    pub fn all() -> [Colormap; 3] {
+        [Colormap::Heat, Colormap::Diverging, Colormap::DivergingDark]
+    }
+
+    /// Get the string representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Colormap::Heat => "Heat",
+            Colormap::Diverging => "Diverging",
+            Colormap::DivergingDark => "Diverging (Dark)",
+        }
+    }
+
+    /// Whether the map is meant for values of either sign
+    pub fn is_signed(&self) -> bool {
+        !matches!(self, Colormap::Heat)
+    }
+
+    /// Index of the map in the visualization shader
+    pub(crate) fn shader_index(&self) -> u32 {
+        match self {
+            Colormap::Heat => 0,
+            Colormap::Diverging => 1,
+            Colormap::DivergingDark => 2,
+        }
+    }
+}
+
 /// Renders the cut plane control UI
 ///
 /// # Arguments
@@ -0,0 +1,110 @@
+//! # Volume View
+//!
+//! Shows a scalar field on a simulation grid, such as smoke density or dye,
+//! as a ray marched volume filling the grid box. GPU simulations hand over
+//! their field buffer with [`VolumeView::set_gpu_buffer`] and it is drawn
+//! every frame without a readback; the buffer has to keep holding the
+//! current field, so solvers that swap buffers copy the result into one.
+
+use imgui::Ui;
+use wgpu::{Device, Queue};
+
+use super::traits::VisualizationComponent;
+use super::GridTransform;
+use crate::gfx::rendering::VolumeBatch;
+
+/// Visualization component drawing a scalar field as fog inside its grid box
+pub struct VolumeView {
+    enabled: bool,
+    grid: GridTransform,
+    buffer: Option<wgpu::Buffer>,
+    /// Color of the medium; alpha scales its overall opacity
+    pub color: [f32; 4],
+    /// Extinction per world unit at a field value of one
+    pub density: f32,
+}
+
+impl VolumeView {
+    /// Volume filling the box of `grid`, with one value per grid node
+    pub fn new(grid: GridTransform) -> Self {
+        Self {
+            enabled: true,
+            grid,
+            buffer: None,
+            color: [0.9, 0.9, 0.9, 1.0],
+            density: 4.0,
+        }
+    }
+
+    pub fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_density(mut self, density: f32) -> Self {
+        self.density = density;
+        self
+    }
+
+    /// Draw the `f32` field in a buffer with `STORAGE` usage, x fastest
+    pub fn set_gpu_buffer(&mut self, buffer: wgpu::Buffer) {
+        self.buffer = Some(buffer);
+    }
+
+    pub fn grid(&self) -> &GridTransform {
+        &self.grid
+    }
+
+    /// Batch for the renderer, if there is a field to draw
+    pub fn batch(&self) -> Option<VolumeBatch> {
+        Some(VolumeBatch {
+            field: self.buffer.clone()?,
+            dims: self.grid.dims(),
+            min: self.grid.min().into(),
+            max: self.grid.max().into(),
+            color: self.color,
+            density: self.density,
+        })
+    }
+}
+
+impl VisualizationComponent for VolumeView {
+    fn initialize(&mut self, _device: Option<&Device>, _queue: Option<&Queue>) {}
+
+    fn update(&mut self, _delta_time: f32, _device: Option<&Device>, _queue: Option<&Queue>) {}
+
+    fn render_ui(&mut self, ui: &Ui) {
+        ui.checkbox("Enabled", &mut self.enabled);
+        ui.slider("Density", 0.1, 50.0, &mut self.density);
+        ui.color_edit4("Color", &mut self.color);
+        let [x, y, z] = self.grid.dims();
+        ui.text(format!("Grid: {x} x {y} x {z}"));
+        if self.buffer.is_none() {
+            ui.text("Source: none");
+        }
+    }
+
+    fn name(&self) -> &str {
+        "Volume View"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn get_ui_size(&self) -> (f32, f32) {
+        (250.0, 140.0)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+}
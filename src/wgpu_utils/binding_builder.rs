@@ -116,6 +116,15 @@ impl<'a> BindGroupBuilder<'a> {
     pub fn buffer(self, buffer_binding: &'a wgpu::Buffer) -> Self {
         self.resource(buffer_binding.as_entire_binding())
     }
+    /// Binds `size` bytes from the start of the buffer, for dynamic offset bindings
+    /// where the offset passed to `set_bind_group` picks the slot
+    pub fn buffer_slot(self, buffer: &'a wgpu::Buffer, size: u64) -> Self {
+        self.resource(wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+            buffer,
+            offset: 0,
+            size: wgpu::BufferSize::new(size),
+        }))
+    }
     pub fn sampler(self, sampler: &'a wgpu::Sampler) -> Self {
         self.resource(wgpu::BindingResource::Sampler(sampler))
     }
@@ -134,3 +143,79 @@ impl<'a> BindGroupBuilder<'a> {
         device.create_bind_group(&descriptor)
     }
 }
+
+/// Tool to create pipeline layouts from bind group layouts and push constant ranges
+///
+/// Push constants need [wgpu::Features::PUSH_CONSTANTS] and a
+/// `max_push_constant_size` limit large enough for the ranges, which
+/// [supports_push_constants] checks. Without them, a
+/// [DynamicUniformBuffer](super::uniform_buffer::DynamicUniformBuffer) bound with
+/// [dynamic offsets](super::binding_types::uniform_dynamic) covers the same per-object
+/// and per-step parameters.
+pub struct PipelineLayoutBuilder<'a> {
+    bind_group_layouts: Vec<&'a wgpu::BindGroupLayout>,
+    push_constant_ranges: Vec<wgpu::PushConstantRange>,
+    next_push_constant_offset: u32,
+}
+
+impl<'a> PipelineLayoutBuilder<'a> {
+    /// Constructor
+    pub fn new() -> Self {
+        PipelineLayoutBuilder {
+            bind_group_layouts: Vec::new(),
+            push_constant_ranges: Vec::new(),
+            next_push_constant_offset: 0,
+        }
+    }
+
+    /// Adds the bind group layout of the next group index
+    pub fn bind_group_layout(mut self, layout: &'a wgpu::BindGroupLayout) -> Self {
+        self.bind_group_layouts.push(layout);
+        self
+    }
+
+    /// Adds a push constant range holding a `T`, placed after the previous range
+    ///
+    /// The offset of the range is what `set_push_constants` takes for these stages.
+    pub fn push_constants<T: bytemuck::Pod>(mut self, stages: wgpu::ShaderStages) -> Self {
+        let size = std::mem::size_of::<T>() as u32;
+        assert!(
+            size.is_multiple_of(wgpu::PUSH_CONSTANT_ALIGNMENT),
+            "push constant size must be a multiple of {} bytes",
+            wgpu::PUSH_CONSTANT_ALIGNMENT
+        );
+        let start = self.next_push_constant_offset;
+        self.next_push_constant_offset += size;
+        self.push_constant_ranges.push(wgpu::PushConstantRange {
+            stages,
+            range: start..start + size,
+        });
+        self
+    }
+
+    /// Total size of the push constant ranges, to check against the device limit
+    pub fn push_constant_size(&self) -> u32 {
+        self.next_push_constant_offset
+    }
+
+    /// Creates a pipeline layout with a description/label passed in for debugging and identification
+    pub fn create(self, device: &wgpu::Device, label: &str) -> wgpu::PipelineLayout {
+        device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(label),
+            bind_group_layouts: &self.bind_group_layouts,
+            push_constant_ranges: &self.push_constant_ranges,
+        })
+    }
+}
+
+impl Default for PipelineLayoutBuilder<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether the device was created with push constants of at least `size` bytes
+pub fn supports_push_constants(device: &wgpu::Device, size: u32) -> bool {
+    device.features().contains(wgpu::Features::PUSH_CONSTANTS)
+        && device.limits().max_push_constant_size >= size
+}
@@ -25,6 +25,26 @@ pub fn uniform() -> wgpu::BindingType {
     }
 }
 
+/// Storage buffer bound at an offset given with `set_bind_group`, one slot
+/// per object or step in a shared buffer
+pub fn buffer_dynamic(read_only: bool) -> wgpu::BindingType {
+    wgpu::BindingType::Buffer {
+        ty: wgpu::BufferBindingType::Storage { read_only },
+        has_dynamic_offset: true,
+        min_binding_size: None,
+    }
+}
+
+/// Uniform buffer bound at an offset given with `set_bind_group`, see
+/// [`DynamicUniformBuffer`](super::uniform_buffer::DynamicUniformBuffer)
+pub fn uniform_dynamic() -> wgpu::BindingType {
+    wgpu::BindingType::Buffer {
+        ty: wgpu::BufferBindingType::Uniform,
+        has_dynamic_offset: true,
+        min_binding_size: None,
+    }
+}
+
 pub fn sampler(filtering: wgpu::SamplerBindingType) -> wgpu::BindingType {
     wgpu::BindingType::Sampler(filtering)
 }
@@ -45,6 +65,15 @@ pub fn texture_2d_array() -> wgpu::BindingType {
     }
 }
 
+/// 2D texture of a float format that can't be filtered, such as `r32float`
+pub fn texture_2d_unfilterable() -> wgpu::BindingType {
+    wgpu::BindingType::Texture {
+        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+        view_dimension: wgpu::TextureViewDimension::D2,
+        multisampled: false,
+    }
+}
+
 pub fn itexture_2d() -> wgpu::BindingType {
     wgpu::BindingType::Texture {
         sample_type: wgpu::TextureSampleType::Sint,
@@ -69,6 +98,16 @@ pub fn texture_3d() -> wgpu::BindingType {
     }
 }
 
+/// 3D texture of a float format that can't be filtered, such as `r32float`,
+/// sampled with a non-filtering sampler or loaded with `textureLoad`
+pub fn texture_3d_unfilterable() -> wgpu::BindingType {
+    wgpu::BindingType::Texture {
+        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+        view_dimension: wgpu::TextureViewDimension::D3,
+        multisampled: false,
+    }
+}
+
 pub fn itexture_3d() -> wgpu::BindingType {
     wgpu::BindingType::Texture {
         sample_type: wgpu::TextureSampleType::Sint,
@@ -126,6 +165,17 @@ pub fn image_3d(
     }
 }
 
+/// Write-only 2D storage texture, for compute shaders filling a texture that
+/// is sampled afterwards
+pub fn image_2d_write(format: wgpu::TextureFormat) -> wgpu::BindingType {
+    image_2d(format, wgpu::StorageTextureAccess::WriteOnly)
+}
+
+/// Write-only 3D storage texture, see [`Texture3D`](crate::gfx::resources::Texture3D)
+pub fn image_3d_write(format: wgpu::TextureFormat) -> wgpu::BindingType {
+    image_3d(format, wgpu::StorageTextureAccess::WriteOnly)
+}
+
 // Additional compute-specific helpers
 pub fn compute_storage_read_write() -> wgpu::BindingType {
     storage_buffer_read_write()
@@ -0,0 +1,395 @@
+//! # Buffer Pool
+//!
+//! Recycles short-lived GPU buffers instead of creating and dropping one per
+//! use. Readbacks need a fresh `MAP_READ` staging buffer each time and
+//! dynamic vertex data a fresh upload buffer each frame; [`BufferPool`] keeps
+//! released buffers around, keyed by size class and usage, and hands them
+//! out again.
+//!
+//! Sizes are rounded up to a power of two (at least 256 bytes), so requests
+//! of similar size share buffers. A released buffer only becomes available
+//! again after `frames_in_flight` calls to [`BufferPool::end_frame`], so work
+//! still queued on the GPU never sees its contents overwritten, and buffers
+//! idle for longer than `max_idle_frames` are dropped.
+//!
+//! The pool also serves the named allocations of the low-level simulation
+//! API ([`allocate`](BufferPool::allocate) and
+//! [`deallocate`](BufferPool::deallocate)), which keep their exact size and
+//! are re-exported as `simulation::low_level::BufferPool`.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::wgpu_utils::BufferPool;
+//!
+//! # fn example(device: std::sync::Arc<wgpu::Device>, queue: &wgpu::Queue, field: &wgpu::Buffer) {
+//! let mut pool = BufferPool::new(device);
+//!
+//! // Each frame
+//! let values: Vec<f32> = pool.read_buffer(queue, field, 64 * 64).unwrap();
+//! pool.end_frame();
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use wgpu::{Buffer, BufferUsages, Device, Queue};
+
+/// Smallest size class handed out by the pool
+const MIN_SIZE_CLASS: u64 = 256;
+
+/// Buffer size the pool allocates for a request of `size` bytes
+pub fn size_class(size: u64) -> u64 {
+    size.max(MIN_SIZE_CLASS).next_power_of_two()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct PoolKey {
+    size: u64,
+    usage: BufferUsages,
+}
+
+struct IdleBuffer {
+    buffer: Arc<Buffer>,
+    /// Frame the buffer became available again
+    since_frame: u64,
+}
+
+/// Buffer on loan from a [`BufferPool`]
+///
+/// Dereferences to the underlying [`wgpu::Buffer`], which may be larger than
+/// requested. Hand it back with [`BufferPool::release`]; dropping it instead
+/// frees the buffer.
+pub struct PooledBuffer {
+    buffer: Arc<Buffer>,
+    key: PoolKey,
+}
+
+impl PooledBuffer {
+    /// The underlying buffer
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    /// Allocated size in bytes
+    pub fn size_class(&self) -> u64 {
+        self.key.size
+    }
+}
+
+impl std::ops::Deref for PooledBuffer {
+    type Target = Buffer;
+
+    fn deref(&self) -> &Buffer {
+        &self.buffer
+    }
+}
+
+/// Allocation counters of a [`BufferPool`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BufferPoolStats {
+    /// Buffers created by the pool
+    pub allocations: u64,
+    /// Requests served by a recycled buffer
+    pub reuses: u64,
+    /// Buffers dropped after staying idle too long
+    pub evictions: u64,
+    /// Bytes held by the pool, idle or waiting for their frame to pass
+    pub pooled_bytes: u64,
+}
+
+/// Recycles GPU buffers by size class and usage across frames
+pub struct BufferPool {
+    device: Arc<Device>,
+    idle: HashMap<PoolKey, Vec<IdleBuffer>>,
+    /// Released buffers with the frame they were released in
+    pending: Vec<(u64, PoolKey, Arc<Buffer>)>,
+    /// Buffers lent out by [`allocate`](Self::allocate)
+    named: HashMap<String, PooledBuffer>,
+    frame: u64,
+    frames_in_flight: u64,
+    max_idle_frames: u64,
+    stats: BufferPoolStats,
+}
+
+impl BufferPool {
+    /// Create a pool holding buffers back for two frames after release and
+    /// dropping buffers unused for 120 frames
+    pub fn new(device: Arc<Device>) -> Self {
+        Self::with_lifetimes(device, 2, 120)
+    }
+
+    /// Create a pool with custom lifetimes
+    ///
+    /// `frames_in_flight` is the number of [`end_frame`](Self::end_frame)
+    /// calls before a released buffer can be handed out again; 0 makes it
+    /// available immediately.
+    pub fn with_lifetimes(
+        device: Arc<Device>,
+        frames_in_flight: u64,
+        max_idle_frames: u64,
+    ) -> Self {
+        Self {
+            device,
+            idle: HashMap::new(),
+            pending: Vec::new(),
+            named: HashMap::new(),
+            frame: 0,
+            frames_in_flight,
+            max_idle_frames,
+            stats: BufferPoolStats::default(),
+        }
+    }
+
+    /// Get a buffer of at least `size` bytes with exactly `usage`
+    pub fn acquire(&mut self, size: u64, usage: BufferUsages) -> PooledBuffer {
+        let key = PoolKey {
+            size: size_class(size),
+            usage,
+        };
+        self.take(key, "Pooled Buffer")
+    }
+
+    /// Get a buffer of exactly `size` bytes, lent out under `name` until
+    /// [`deallocate`](Self::deallocate)
+    ///
+    /// Allocating a name that is already lent out hands the earlier buffer
+    /// back first.
+    pub fn allocate(&mut self, name: &str, size: u64, usage: BufferUsages) -> Arc<Buffer> {
+        self.deallocate(name);
+        let buffer = self.take(PoolKey { size, usage }, name);
+        let shared = buffer.buffer.clone();
+        self.named.insert(name.to_string(), buffer);
+        shared
+    }
+
+    /// Hand back the buffer lent out under `name`, as with
+    /// [`release`](Self::release)
+    pub fn deallocate(&mut self, name: &str) {
+        if let Some(buffer) = self.named.remove(name) {
+            self.release(buffer);
+        }
+    }
+
+    fn take(&mut self, key: PoolKey, label: &str) -> PooledBuffer {
+        if let Some(idle) = self.idle.get_mut(&key).and_then(|buffers| buffers.pop()) {
+            self.stats.reuses += 1;
+            self.stats.pooled_bytes -= key.size;
+            return PooledBuffer {
+                buffer: idle.buffer,
+                key,
+            };
+        }
+
+        self.stats.allocations += 1;
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: key.size,
+            usage: key.usage,
+            mapped_at_creation: false,
+        });
+        PooledBuffer {
+            buffer: Arc::new(buffer),
+            key,
+        }
+    }
+
+    /// Hand a buffer back; it is reused once `frames_in_flight` frames passed
+    pub fn release(&mut self, buffer: PooledBuffer) {
+        self.stats.pooled_bytes += buffer.key.size;
+        if self.frames_in_flight == 0 {
+            self.idle.entry(buffer.key).or_default().push(IdleBuffer {
+                buffer: buffer.buffer,
+                since_frame: self.frame,
+            });
+        } else {
+            self.pending.push((self.frame, buffer.key, buffer.buffer));
+        }
+    }
+
+    /// Advance the frame counter, recycling and evicting buffers
+    pub fn end_frame(&mut self) {
+        self.frame += 1;
+
+        let frame = self.frame;
+        let frames_in_flight = self.frames_in_flight;
+        let (ready, pending): (Vec<_>, Vec<_>) = self
+            .pending
+            .drain(..)
+            .partition(|(released, _, _)| released + frames_in_flight <= frame);
+        self.pending = pending;
+        for (_, key, buffer) in ready {
+            self.idle.entry(key).or_default().push(IdleBuffer {
+                buffer,
+                since_frame: frame,
+            });
+        }
+
+        let max_idle_frames = self.max_idle_frames;
+        let mut evicted = Vec::new();
+        for (key, buffers) in self.idle.iter_mut() {
+            buffers.retain(|idle| {
+                let keep = idle.since_frame + max_idle_frames >= frame;
+                if !keep {
+                    evicted.push(key.size);
+                }
+                keep
+            });
+        }
+        self.idle.retain(|_, buffers| !buffers.is_empty());
+        for size in evicted {
+            self.stats.evictions += 1;
+            self.stats.pooled_bytes -= size;
+        }
+    }
+
+    /// Drop every idle buffer and forget named allocations; buffers waiting
+    /// for their frame are kept
+    pub fn clear(&mut self) {
+        for (key, buffers) in self.idle.drain() {
+            self.stats.pooled_bytes -= key.size * buffers.len() as u64;
+        }
+        self.named.clear();
+    }
+
+    /// Allocation counters since the pool was created
+    pub fn stats(&self) -> BufferPoolStats {
+        self.stats
+    }
+
+    /// Number of [`end_frame`](Self::end_frame) calls so far
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// Get a buffer filled with `data`
+    ///
+    /// `COPY_DST` is added to `usage`. Bytes past the data keep whatever an
+    /// earlier user left there, so draw and dispatch only the uploaded range.
+    pub fn upload<T: bytemuck::Pod>(
+        &mut self,
+        queue: &Queue,
+        data: &[T],
+        usage: BufferUsages,
+    ) -> PooledBuffer {
+        let bytes: &[u8] = bytemuck::cast_slice(data);
+        let buffer = self.acquire(bytes.len() as u64, usage | BufferUsages::COPY_DST);
+        queue.write_buffer(&buffer, 0, bytes);
+        buffer
+    }
+
+    /// Copy the first `len` elements of `buffer` to the CPU through a pooled
+    /// staging buffer, waiting for the GPU
+    ///
+    /// The buffer needs `COPY_SRC` usage.
+    pub fn read_buffer<T: bytemuck::Pod>(
+        &mut self,
+        queue: &Queue,
+        buffer: &Buffer,
+        len: usize,
+    ) -> Result<Vec<T>, wgpu::BufferAsyncError> {
+        let size = (len * std::mem::size_of::<T>()) as u64;
+        if size == 0 {
+            return Ok(Vec::new());
+        }
+        let staging = self.acquire(size, BufferUsages::MAP_READ | BufferUsages::COPY_DST);
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Pooled Readback Encoder"),
+            });
+        encoder.copy_buffer_to_buffer(buffer, 0, &staging, 0, size);
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = staging.slice(..size);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        let _ = self.device.poll(wgpu::MaintainBase::Wait);
+        let mapped = receiver.recv().unwrap_or(Err(wgpu::BufferAsyncError));
+
+        let values = mapped.map(|()| {
+            let values = bytemuck::cast_slice(&slice.get_mapped_range()).to_vec();
+            staging.unmap();
+            values
+        });
+        self.release(staging);
+        values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wgpu_utils::compute_primitives::{test_buffer, test_device};
+
+    #[test]
+    fn test_size_classes_round_up_to_powers_of_two() {
+        assert_eq!(size_class(0), 256);
+        assert_eq!(size_class(256), 256);
+        assert_eq!(size_class(257), 512);
+        assert_eq!(size_class(4 * 1000), 4096);
+    }
+
+    #[test]
+    fn test_released_buffers_are_reused_after_frames_in_flight() {
+        // Needs a GPU adapter, skipped without one
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let device = Arc::new(device);
+        let mut pool = BufferPool::with_lifetimes(device.clone(), 1, 2);
+        let usage = BufferUsages::STORAGE | BufferUsages::COPY_SRC;
+
+        let first = pool.upload(&queue, &[1.0f32, 2.0, 3.0], usage);
+        pool.release(first);
+        // Still in flight this frame
+        let second = pool.acquire(12, usage | BufferUsages::COPY_DST);
+        assert_eq!(pool.stats().allocations, 2);
+        pool.release(second);
+
+        pool.end_frame();
+        let reused = pool.acquire(100, usage | BufferUsages::COPY_DST);
+        assert_eq!(pool.stats().reuses, 1);
+        assert_eq!(reused.size_class(), 256);
+        pool.release(reused);
+
+        // Idle buffers are evicted after max_idle_frames
+        for _ in 0..4 {
+            pool.end_frame();
+        }
+        assert_eq!(pool.stats().evictions, 2);
+        assert_eq!(pool.stats().pooled_bytes, 0);
+
+        let source = test_buffer(&device, &[5u32, 6, 7]);
+        let values: Vec<u32> = pool.read_buffer(&queue, &source, 3).unwrap();
+        assert_eq!(values, vec![5, 6, 7]);
+    }
+
+    #[test]
+    fn test_named_allocations_keep_their_size() {
+        // Needs a GPU adapter, skipped without one
+        let Some((device, _queue)) = test_device() else {
+            return;
+        };
+        let mut pool = BufferPool::with_lifetimes(Arc::new(device), 0, 2);
+        let usage = BufferUsages::STORAGE;
+
+        let field = pool.allocate("field", 300, usage);
+        assert_eq!(field.size(), 300);
+        // Reallocating a name hands the earlier buffer back
+        let again = pool.allocate("field", 300, usage);
+        assert!(Arc::ptr_eq(&field, &again));
+        assert_eq!(pool.stats().allocations, 1);
+
+        pool.deallocate("field");
+        assert_eq!(pool.stats().pooled_bytes, 300);
+        // Usage is part of the key
+        let other = pool.allocate("other", 300, usage | BufferUsages::COPY_SRC);
+        assert!(!Arc::ptr_eq(&field, &other));
+        pool.clear();
+        assert_eq!(pool.stats().pooled_bytes, 0);
+    }
+}
@@ -0,0 +1,139 @@
+//! Named GPU buffers for debugging
+//!
+//! Simulations register the buffers worth looking at under readable names,
+//! and the [`BufferInspector`](crate::ui::buffer_inspector::BufferInspector)
+//! panel lists them and reads slices back. The registry holds a handle to
+//! each buffer, which keeps it alive until it is unregistered or replaced
+//! under the same name.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::wgpu_utils::BufferRegistry;
+//!
+//! # fn example(distributions: &wgpu::Buffer) {
+//! // In `initialize_gpu`; the buffer needs `COPY_SRC` usage to be read back
+//! BufferRegistry::global().register("LBM Distributions A", distributions);
+//! # }
+//! ```
+
+use std::sync::{Mutex, OnceLock};
+
+use wgpu::Buffer;
+
+/// A buffer in the registry
+#[derive(Debug, Clone)]
+pub struct RegisteredBuffer {
+    pub name: String,
+    pub buffer: Buffer,
+}
+
+impl RegisteredBuffer {
+    pub fn size(&self) -> u64 {
+        self.buffer.size()
+    }
+
+    pub fn usage(&self) -> wgpu::BufferUsages {
+        self.buffer.usage()
+    }
+
+    /// Whether slices can be copied out of the buffer
+    pub fn is_readable(&self) -> bool {
+        self.usage().contains(wgpu::BufferUsages::COPY_SRC)
+    }
+}
+
+/// Buffers registered for inspection, in registration order
+#[derive(Debug, Default)]
+pub struct BufferRegistry {
+    buffers: Mutex<Vec<RegisteredBuffer>>,
+}
+
+impl BufferRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry shared by the app and its simulations
+    pub fn global() -> &'static BufferRegistry {
+        static GLOBAL: OnceLock<BufferRegistry> = OnceLock::new();
+        GLOBAL.get_or_init(BufferRegistry::new)
+    }
+
+    /// Add a buffer, replacing any buffer registered under the same name
+    pub fn register(&self, name: &str, buffer: &Buffer) {
+        let mut buffers = self.buffers.lock().unwrap();
+        let entry = RegisteredBuffer {
+            name: name.to_string(),
+            buffer: buffer.clone(),
+        };
+        match buffers
+            .iter_mut()
+            .find(|registered| registered.name == name)
+        {
+            Some(registered) => *registered = entry,
+            None => buffers.push(entry),
+        }
+    }
+
+    pub fn unregister(&self, name: &str) {
+        self.buffers
+            .lock()
+            .unwrap()
+            .retain(|registered| registered.name != name);
+    }
+
+    pub fn clear(&self) {
+        self.buffers.lock().unwrap().clear();
+    }
+
+    pub fn get(&self, name: &str) -> Option<RegisteredBuffer> {
+        self.buffers
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|registered| registered.name == name)
+            .cloned()
+    }
+
+    /// Copy of the current entries
+    pub fn buffers(&self) -> Vec<RegisteredBuffer> {
+        self.buffers.lock().unwrap().clone()
+    }
+
+    /// Combined size of all registered buffers in bytes
+    pub fn total_size(&self) -> u64 {
+        self.buffers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(RegisteredBuffer::size)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wgpu_utils::compute_primitives::{test_buffer, test_device};
+
+    #[test]
+    fn test_register_replaces_by_name() {
+        let Some((device, _queue)) = test_device() else {
+            return;
+        };
+        let registry = BufferRegistry::new();
+        registry.register("field", &test_buffer(&device, &[0u32; 4]));
+        registry.register("other", &test_buffer(&device, &[0u32; 2]));
+        registry.register("field", &test_buffer(&device, &[0u32; 8]));
+
+        let names: Vec<_> = registry.buffers().into_iter().map(|b| b.name).collect();
+        assert_eq!(names, ["field", "other"]);
+        assert_eq!(registry.get("field").unwrap().size(), 32);
+        assert!(registry.get("field").unwrap().is_readable());
+        assert_eq!(registry.total_size(), 40);
+
+        registry.unregister("field");
+        assert!(registry.get("field").is_none());
+    }
+}
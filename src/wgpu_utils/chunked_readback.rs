@@ -0,0 +1,321 @@
+//! # Chunked Readback
+//!
+//! Reads a large storage buffer back to the CPU a piece at a time. Copying a
+//! whole 256³ field in one go needs a staging buffer as large as the field,
+//! which can exceed `max_buffer_size`, and waiting for it stalls the frame.
+//! [`ChunkedReadback`] copies one chunk per [`poll`](ChunkedReadback::poll)
+//! through a single chunk-sized staging buffer, never blocking, and fills the
+//! CPU array progressively.
+//!
+//! While a pass is running the CPU array mixes chunks of the new pass with
+//! chunks of the previous one. Visualizations that must not show such a mix
+//! should wait for [`ReadbackStatus::Complete`] (or check
+//! [`is_complete`](ChunkedReadback::is_complete)) before using the data.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::wgpu_utils::chunked_readback::{ChunkedReadback, ReadbackStatus};
+//!
+//! # fn example(device: &wgpu::Device, queue: &wgpu::Queue, field: &wgpu::Buffer) {
+//! let mut readback = ChunkedReadback::<f32>::new(device, 256 * 256 * 256, 4 << 20);
+//! readback.start();
+//!
+//! // Each frame
+//! if readback.poll(device, queue, field) == ReadbackStatus::Complete {
+//!     let values = readback.data();
+//!     // ... update the visualization, then request the next snapshot
+//!     readback.start();
+//! }
+//! # }
+//! ```
+
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+use wgpu::{Buffer, Device, Queue};
+
+/// Progress reported by [`ChunkedReadback::poll`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadbackStatus {
+    /// No pass is running; [`ChunkedReadback::start`] begins one
+    Idle,
+    /// Chunks are still being copied
+    InProgress,
+    /// The last chunk of the pass landed during this poll
+    Complete,
+    /// Mapping a chunk failed; the pass was abandoned
+    Failed,
+}
+
+struct InFlight {
+    chunk: usize,
+    receiver: Receiver<Result<(), wgpu::BufferAsyncError>>,
+}
+
+/// Progressive, non-blocking readback of a large buffer into a CPU array
+pub struct ChunkedReadback<T> {
+    len: usize,
+    chunk_len: usize,
+    staging: Buffer,
+    data: Vec<T>,
+    /// Next chunk to copy, `None` when no pass is running
+    next_chunk: Option<usize>,
+    in_flight: Option<InFlight>,
+    complete: bool,
+    completed_passes: u64,
+}
+
+impl<T: bytemuck::Pod> ChunkedReadback<T> {
+    /// Create a readback of `len` elements, copying up to `chunk_bytes` per poll
+    ///
+    /// The chunk size is limited to the device's `max_buffer_size` and
+    /// rounded down to whole elements and the copy alignment.
+    pub fn new(device: &Device, len: usize, chunk_bytes: u64) -> Self {
+        let element_size = std::mem::size_of::<T>() as u64;
+        assert!(
+            (len as u64 * element_size).is_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT),
+            "buffer size must be a multiple of {} bytes",
+            wgpu::COPY_BUFFER_ALIGNMENT
+        );
+
+        // Chunks start at multiples of both the element size and the copy alignment
+        let step = lcm(element_size, wgpu::COPY_BUFFER_ALIGNMENT);
+        let chunk_bytes = chunk_bytes.min(device.limits().max_buffer_size);
+        let chunk_bytes = (chunk_bytes / step * step).max(step);
+        let chunk_len = ((chunk_bytes / element_size) as usize).min(len.max(1));
+
+        let staging = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Chunked Readback Staging Buffer"),
+            size: (chunk_len as u64 * element_size).max(wgpu::COPY_BUFFER_ALIGNMENT),
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            len,
+            chunk_len,
+            staging,
+            data: vec![T::zeroed(); len],
+            next_chunk: None,
+            in_flight: None,
+            complete: false,
+            completed_passes: 0,
+        }
+    }
+
+    /// Begin a new pass over the buffer; does nothing while one is running
+    ///
+    /// The CPU array keeps the previous pass's values until they are
+    /// overwritten chunk by chunk.
+    pub fn start(&mut self) {
+        if self.next_chunk.is_none() {
+            self.next_chunk = Some(0);
+            self.complete = false;
+        }
+    }
+
+    /// Advance the running pass without blocking
+    ///
+    /// Collects the chunk copied by an earlier poll if the GPU finished it,
+    /// then issues the copy of the next one. `source` needs `COPY_SRC` usage
+    /// and must be the same buffer for the whole pass.
+    pub fn poll(&mut self, device: &Device, queue: &Queue, source: &Buffer) -> ReadbackStatus {
+        let Some(next_chunk) = self.next_chunk else {
+            return ReadbackStatus::Idle;
+        };
+
+        if let Some(in_flight) = &self.in_flight {
+            let _ = device.poll(wgpu::MaintainBase::Poll);
+            match in_flight.receiver.try_recv() {
+                Err(TryRecvError::Empty) => return ReadbackStatus::InProgress,
+                Ok(Ok(())) => {
+                    let chunk = in_flight.chunk;
+                    self.in_flight = None;
+                    self.collect(chunk);
+                    if chunk + 1 == self.chunk_count() {
+                        self.next_chunk = None;
+                        self.complete = true;
+                        self.completed_passes += 1;
+                        return ReadbackStatus::Complete;
+                    }
+                }
+                Ok(Err(_)) | Err(TryRecvError::Disconnected) => {
+                    self.in_flight = None;
+                    self.next_chunk = None;
+                    return ReadbackStatus::Failed;
+                }
+            }
+        }
+
+        if self.len == 0 {
+            self.next_chunk = None;
+            self.complete = true;
+            self.completed_passes += 1;
+            return ReadbackStatus::Complete;
+        }
+
+        let chunk = next_chunk;
+        let (offset, size) = self.chunk_range(chunk);
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Chunked Readback Encoder"),
+        });
+        encoder.copy_buffer_to_buffer(source, offset, &self.staging, 0, size);
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let (sender, receiver) = mpsc::channel();
+        self.staging
+            .slice(..size)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+        self.in_flight = Some(InFlight { chunk, receiver });
+        self.next_chunk = Some(chunk + 1);
+        ReadbackStatus::InProgress
+    }
+
+    /// Run a whole pass now, waiting for the GPU after every chunk
+    pub fn read_blocking(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        source: &Buffer,
+    ) -> Result<&[T], wgpu::BufferAsyncError> {
+        self.start();
+        loop {
+            match self.poll(device, queue, source) {
+                ReadbackStatus::Complete | ReadbackStatus::Idle => return Ok(&self.data),
+                ReadbackStatus::Failed => return Err(wgpu::BufferAsyncError),
+                ReadbackStatus::InProgress => {
+                    let _ = device.poll(wgpu::MaintainBase::Wait);
+                }
+            }
+        }
+    }
+
+    /// The CPU array, fully up to date when [`is_complete`](Self::is_complete)
+    pub fn data(&self) -> &[T] {
+        &self.data
+    }
+
+    /// Whether the last pass finished and no new one has started
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// Whether a pass is running
+    pub fn is_in_progress(&self) -> bool {
+        self.next_chunk.is_some()
+    }
+
+    /// Fraction of the running pass already on the CPU, 1.0 when idle
+    pub fn progress(&self) -> f32 {
+        match self.next_chunk {
+            None => 1.0,
+            Some(next_chunk) => {
+                let landed = next_chunk - usize::from(self.in_flight.is_some());
+                landed as f32 / self.chunk_count().max(1) as f32
+            }
+        }
+    }
+
+    /// Number of passes finished so far
+    pub fn completed_passes(&self) -> u64 {
+        self.completed_passes
+    }
+
+    /// Number of elements read back
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the readback covers no elements
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Elements copied per poll
+    pub fn chunk_len(&self) -> usize {
+        self.chunk_len
+    }
+
+    /// Number of polls a pass takes, at least
+    pub fn chunk_count(&self) -> usize {
+        self.len.div_ceil(self.chunk_len)
+    }
+
+    /// Byte offset and size of a chunk in the source buffer
+    fn chunk_range(&self, chunk: usize) -> (u64, u64) {
+        let element_size = std::mem::size_of::<T>() as u64;
+        let start = chunk * self.chunk_len;
+        let end = (start + self.chunk_len).min(self.len);
+        (start as u64 * element_size, (end - start) as u64 * element_size)
+    }
+
+    fn collect(&mut self, chunk: usize) {
+        let (_, size) = self.chunk_range(chunk);
+        let start = chunk * self.chunk_len;
+        {
+            let mapped = self.staging.slice(..size).get_mapped_range();
+            let values: &[T] = bytemuck::cast_slice(&mapped);
+            self.data[start..start + values.len()].copy_from_slice(values);
+        }
+        self.staging.unmap();
+    }
+}
+
+fn lcm(a: u64, b: u64) -> u64 {
+    fn gcd(a: u64, b: u64) -> u64 {
+        if b == 0 {
+            a
+        } else {
+            gcd(b, a % b)
+        }
+    }
+    a / gcd(a, b) * b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wgpu_utils::compute_primitives::{test_buffer, test_device};
+
+    #[test]
+    fn test_lcm_aligns_chunks() {
+        assert_eq!(lcm(4, 4), 4);
+        assert_eq!(lcm(12, 4), 12);
+        assert_eq!(lcm(2, 4), 4);
+    }
+
+    #[test]
+    fn test_chunks_reassemble_the_buffer() {
+        // Needs a GPU adapter, skipped without one
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let values: Vec<u32> = (0..1000).collect();
+        let source = test_buffer(&device, &values);
+        let mut readback = ChunkedReadback::<u32>::new(&device, values.len(), 256);
+        assert_eq!(readback.chunk_count(), 16);
+
+        readback.start();
+        let mut polls = 0;
+        loop {
+            polls += 1;
+            match readback.poll(&device, &queue, &source) {
+                ReadbackStatus::Complete => break,
+                ReadbackStatus::InProgress => {
+                    let _ = device.poll(wgpu::MaintainBase::Wait);
+                }
+                status => panic!("unexpected status {status:?}"),
+            }
+        }
+        assert!(polls > readback.chunk_count());
+        assert!(readback.is_complete());
+        assert_eq!(readback.data(), &values[..]);
+
+        // A blocking pass returns the same data
+        let data = readback.read_blocking(&device, &queue, &source).unwrap();
+        assert_eq!(data, &values[..]);
+        assert_eq!(readback.completed_passes(), 2);
+    }
+}
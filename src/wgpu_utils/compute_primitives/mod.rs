@@ -0,0 +1,236 @@
+//! # Compute Primitives
+//!
+//! Parallel building blocks over storage buffers, for simulations that need
+//! histograms, stream compaction, neighbor grids or isosurface extraction:
+//!
+//! - [`Reduce`] - sum, minimum or maximum of a buffer
+//! - [`Scan`] - exclusive prefix sum of a buffer, plus its total
+//! - [`RadixSort`] - stable sort of `u32` keys, optionally carrying `u32` values
+//!
+//! Each primitive is created for an input buffer and a capacity, and records
+//! its compute passes into a command encoder, so it can run in the same
+//! submission as the passes producing and consuming its data. Buffers passed
+//! in need `STORAGE` usage; the number of elements to process can change from
+//! call to call, up to the capacity.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::wgpu_utils::compute_primitives::{read_buffer, Reduce, ReduceOp};
+//!
+//! # fn example(device: &wgpu::Device, queue: &wgpu::Queue, values: &wgpu::Buffer) {
+//! let mut max = Reduce::<f32>::new(device, ReduceOp::Max, values, 100_000);
+//! let mut encoder = device.create_command_encoder(&Default::default());
+//! max.encode(queue, &mut encoder, 100_000);
+//! queue.submit(std::iter::once(encoder.finish()));
+//!
+//! let largest: Vec<f32> = read_buffer(device, queue, max.result(), 1).unwrap();
+//! # }
+//! ```
+
+pub mod reduce;
+pub mod scan;
+pub mod sort;
+
+pub use reduce::{Reduce, ReduceOp};
+pub use scan::Scan;
+pub use sort::RadixSort;
+
+use wgpu::{Buffer, Device, Queue};
+
+/// Threads per workgroup of all primitives
+const WORKGROUP_SIZE: u32 = 256;
+
+/// Largest number of workgroups in one dispatch dimension
+const MAX_WORKGROUPS: u32 = 65_535;
+
+/// Element types the primitives work on
+pub trait GpuScalar: bytemuck::Pod {
+    /// WGSL name of the type
+    const WGSL_TYPE: &'static str;
+    /// WGSL literal of zero
+    const ZERO: &'static str;
+    /// WGSL expression of the lowest value
+    const LOWEST: &'static str;
+    /// WGSL expression of the highest value
+    const HIGHEST: &'static str;
+}
+
+impl GpuScalar for f32 {
+    const WGSL_TYPE: &'static str = "f32";
+    const ZERO: &'static str = "0.0";
+    const LOWEST: &'static str = "-3.40282347e+38";
+    const HIGHEST: &'static str = "3.40282347e+38";
+}
+
+impl GpuScalar for u32 {
+    const WGSL_TYPE: &'static str = "u32";
+    const ZERO: &'static str = "0u";
+    const LOWEST: &'static str = "0u";
+    const HIGHEST: &'static str = "4294967295u";
+}
+
+impl GpuScalar for i32 {
+    const WGSL_TYPE: &'static str = "i32";
+    const ZERO: &'static str = "0i";
+    const LOWEST: &'static str = "i32(-2147483647 - 1)";
+    const HIGHEST: &'static str = "2147483647i";
+}
+
+/// Copy the first `len` elements of `buffer` to the CPU, waiting for the GPU
+///
+/// The buffer needs `COPY_SRC` usage.
+pub fn read_buffer<T: bytemuck::Pod>(
+    device: &Device,
+    queue: &Queue,
+    buffer: &Buffer,
+    len: usize,
+) -> Result<Vec<T>, wgpu::BufferAsyncError> {
+    let size = (len * std::mem::size_of::<T>()) as u64;
+    if size == 0 {
+        return Ok(Vec::new());
+    }
+    let staging = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Readback Staging Buffer"),
+        size,
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Readback Encoder"),
+    });
+    encoder.copy_buffer_to_buffer(buffer, 0, &staging, 0, size);
+    queue.submit(std::iter::once(encoder.finish()));
+
+    let slice = staging.slice(..);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    let _ = device.poll(wgpu::MaintainBase::Wait);
+    receiver.recv().unwrap_or(Err(wgpu::BufferAsyncError))?;
+
+    let values = bytemuck::cast_slice(&slice.get_mapped_range()).to_vec();
+    staging.unmap();
+    Ok(values)
+}
+
+fn buffer_entry(binding: u32, ty: wgpu::BufferBindingType) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+fn bind_group(
+    device: &Device,
+    label: &str,
+    layout: &wgpu::BindGroupLayout,
+    buffers: &[&Buffer],
+) -> wgpu::BindGroup {
+    let entries: Vec<wgpu::BindGroupEntry> = buffers
+        .iter()
+        .enumerate()
+        .map(|(binding, buffer)| wgpu::BindGroupEntry {
+            binding: binding as u32,
+            resource: buffer.as_entire_binding(),
+        })
+        .collect();
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some(label),
+        layout,
+        entries: &entries,
+    })
+}
+
+fn storage_buffer(device: &Device, label: &str, size: u64) -> Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(label),
+        // Bindings can't be empty
+        size: size.max(4),
+        usage: wgpu::BufferUsages::STORAGE
+            | wgpu::BufferUsages::COPY_SRC
+            | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+/// Create one pipeline per entry point, sharing a layout with one bind group
+fn pipelines<const N: usize>(
+    device: &Device,
+    label: &str,
+    source: &str,
+    layout: &wgpu::BindGroupLayout,
+    entry_points: [&str; N],
+) -> [wgpu::ComputePipeline; N] {
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some(label),
+        source: wgpu::ShaderSource::Wgsl(source.into()),
+    });
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some(label),
+        bind_group_layouts: &[layout],
+        push_constant_ranges: &[],
+    });
+    entry_points.map(|entry_point| {
+        device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some(entry_point),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: Some(entry_point),
+            compilation_options: Default::default(),
+            cache: None,
+        })
+    })
+}
+
+/// Uniforms shared by the primitives' shaders
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct PassUniforms {
+    count: u32,
+    /// Bit shift of the radix sort digit
+    shift: u32,
+    /// Number of radix sort blocks
+    block_count: u32,
+    /// Whether the radix sort carries values
+    has_values: u32,
+}
+
+fn uniform_buffer(device: &Device, label: &str) -> Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(label),
+        size: std::mem::size_of::<PassUniforms>() as u64,
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+#[cfg(test)]
+pub(crate) fn test_device() -> Option<(Device, Queue)> {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+    let adapter = pollster::block_on(instance.request_adapter(&Default::default())).ok()?;
+    pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+        required_limits: adapter.limits(),
+        ..Default::default()
+    }))
+    .ok()
+}
+
+#[cfg(test)]
+pub(crate) fn test_buffer<T: bytemuck::Pod>(device: &Device, data: &[T]) -> Buffer {
+    use wgpu::util::DeviceExt;
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Test Buffer"),
+        contents: bytemuck::cast_slice(data),
+        usage: wgpu::BufferUsages::STORAGE
+            | wgpu::BufferUsages::COPY_SRC
+            | wgpu::BufferUsages::COPY_DST,
+    })
+}
@@ -0,0 +1,251 @@
+//! Parallel reduction
+//!
+//! Each pass combines blocks of 512 elements in workgroup memory and writes
+//! one value per block, until a single value is left in
+//! [`Reduce::result`].
+
+use std::marker::PhantomData;
+
+use wgpu::{Buffer, CommandEncoder, Device, Queue};
+
+use super::{GpuScalar, PassUniforms, MAX_WORKGROUPS, WORKGROUP_SIZE};
+
+/// Elements combined by one workgroup
+const BLOCK: u32 = WORKGROUP_SIZE * 2;
+
+/// How [`Reduce`] combines elements
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReduceOp {
+    Sum,
+    Min,
+    Max,
+}
+
+impl ReduceOp {
+    fn combine(self) -> &'static str {
+        match self {
+            ReduceOp::Sum => "a + b",
+            ReduceOp::Min => "min(a, b)",
+            ReduceOp::Max => "max(a, b)",
+        }
+    }
+
+    fn identity<T: GpuScalar>(self) -> &'static str {
+        match self {
+            ReduceOp::Sum => T::ZERO,
+            ReduceOp::Min => T::HIGHEST,
+            ReduceOp::Max => T::LOWEST,
+        }
+    }
+}
+
+struct ReducePass {
+    uniforms: Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+/// Sum, minimum or maximum of a storage buffer of `T`, computed on the GPU
+///
+/// Reducing zero elements gives the identity of the operation: zero for
+/// sums, the highest value for minimums and the lowest for maximums.
+pub struct Reduce<T: GpuScalar> {
+    op: ReduceOp,
+    capacity: u32,
+    pipeline: wgpu::ComputePipeline,
+    passes: Vec<ReducePass>,
+    result: Buffer,
+    /// Keeps the intermediate buffers alive
+    _partials: Vec<Buffer>,
+    _scalar: PhantomData<T>,
+}
+
+impl<T: GpuScalar> Reduce<T> {
+    /// Create a reduction of up to `capacity` elements of `input`
+    pub fn new(device: &Device, op: ReduceOp, input: &Buffer, capacity: u32) -> Self {
+        assert!(
+            capacity.div_ceil(BLOCK) <= MAX_WORKGROUPS,
+            "reduction capacity exceeds {} elements",
+            MAX_WORKGROUPS * BLOCK
+        );
+        let source = REDUCE_SHADER
+            .replace("SCALAR", T::WGSL_TYPE)
+            .replace("IDENTITY", op.identity::<T>())
+            .replace("COMBINE", op.combine());
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Reduce Layout"),
+            entries: &[
+                super::buffer_entry(0, wgpu::BufferBindingType::Uniform),
+                super::buffer_entry(1, wgpu::BufferBindingType::Storage { read_only: true }),
+                super::buffer_entry(2, wgpu::BufferBindingType::Storage { read_only: false }),
+            ],
+        });
+        let [pipeline] = super::pipelines(device, "Reduce Shader", &source, &layout, ["reduce"]);
+
+        // One pass per level, each one BLOCK times smaller, the last writing
+        // the result
+        let element_size = std::mem::size_of::<T>() as u64;
+        let mut partials = Vec::new();
+        let mut groups = capacity.div_ceil(BLOCK).max(1);
+        while groups > 1 {
+            partials.push(super::storage_buffer(
+                device,
+                "Reduce Partials",
+                groups as u64 * element_size,
+            ));
+            groups = groups.div_ceil(BLOCK);
+        }
+        let result = super::storage_buffer(device, "Reduce Result", element_size);
+
+        let outputs = partials.iter().chain(std::iter::once(&result));
+        let inputs = std::iter::once(input).chain(partials.iter());
+        let passes = inputs
+            .zip(outputs)
+            .map(|(input, output)| {
+                let uniforms = super::uniform_buffer(device, "Reduce Uniforms");
+                let bind_group = super::bind_group(
+                    device,
+                    "Reduce Bind Group",
+                    &layout,
+                    &[&uniforms, input, output],
+                );
+                ReducePass {
+                    uniforms,
+                    bind_group,
+                }
+            })
+            .collect();
+
+        Self {
+            op,
+            capacity,
+            pipeline,
+            passes,
+            result,
+            _partials: partials,
+            _scalar: PhantomData,
+        }
+    }
+
+    pub fn op(&self) -> ReduceOp {
+        self.op
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    /// Buffer holding the single reduced value once the passes ran
+    pub fn result(&self) -> &Buffer {
+        &self.result
+    }
+
+    /// Record the passes reducing the first `count` elements of the input
+    pub fn encode(&self, queue: &Queue, encoder: &mut CommandEncoder, count: u32) {
+        let mut count = count.min(self.capacity);
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Reduce"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(&self.pipeline);
+        for level in &self.passes {
+            let groups = count.div_ceil(BLOCK).max(1);
+            let uniforms = PassUniforms {
+                count,
+                ..bytemuck::Zeroable::zeroed()
+            };
+            queue.write_buffer(&level.uniforms, 0, bytemuck::bytes_of(&uniforms));
+            pass.set_bind_group(0, &level.bind_group, &[]);
+            pass.dispatch_workgroups(groups, 1, 1);
+            count = groups;
+        }
+    }
+
+    /// Reduce the first `count` elements and read the result back, waiting
+    /// for the GPU
+    pub fn run(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        count: u32,
+    ) -> Result<T, wgpu::BufferAsyncError> {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Reduce Encoder"),
+        });
+        self.encode(queue, &mut encoder, count);
+        queue.submit(std::iter::once(encoder.finish()));
+        Ok(super::read_buffer(device, queue, &self.result, 1)?[0])
+    }
+}
+
+const REDUCE_SHADER: &str = r#"
+struct PassUniforms {
+    count: u32,
+    shift: u32,
+    block_count: u32,
+    has_values: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: PassUniforms;
+@group(0) @binding(1) var<storage, read> input: array<SCALAR>;
+@group(0) @binding(2) var<storage, read_write> output: array<SCALAR>;
+
+var<workgroup> partial: array<SCALAR, 256>;
+
+fn combine(a: SCALAR, b: SCALAR) -> SCALAR {
+    return COMBINE;
+}
+
+fn load(i: u32) -> SCALAR {
+    if (i < params.count) {
+        return input[i];
+    }
+    return IDENTITY;
+}
+
+@compute @workgroup_size(256)
+fn reduce(
+    @builtin(local_invocation_index) local: u32,
+    @builtin(workgroup_id) group: vec3<u32>,
+) {
+    let base = group.x * 512u + local;
+    partial[local] = combine(load(base), load(base + 256u));
+    workgroupBarrier();
+
+    for (var stride = 128u; stride > 0u; stride >>= 1u) {
+        if (local < stride) {
+            partial[local] = combine(partial[local], partial[local + stride]);
+        }
+        workgroupBarrier();
+    }
+    if (local == 0u) {
+        output[group.x] = partial[0];
+    }
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wgpu_utils::compute_primitives::{test_buffer, test_device};
+
+    #[test]
+    fn test_reduce_matches_cpu() {
+        // Needs a GPU adapter, skipped without one
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let values: Vec<i32> = (0..300_000).map(|i| (i * 37) % 2001 - 1000).collect();
+        let buffer = test_buffer(&device, &values);
+
+        let sum = Reduce::<i32>::new(&device, ReduceOp::Sum, &buffer, values.len() as u32);
+        let min = Reduce::<i32>::new(&device, ReduceOp::Min, &buffer, values.len() as u32);
+        let max = Reduce::<i32>::new(&device, ReduceOp::Max, &buffer, values.len() as u32);
+        for count in [0, 1, 1000, 300_000] {
+            let slice = &values[..count];
+            let run = |reduce: &Reduce<i32>| reduce.run(&device, &queue, count as u32).unwrap();
+            assert_eq!(run(&sum), slice.iter().sum::<i32>());
+            assert_eq!(run(&min), slice.iter().copied().min().unwrap_or(i32::MAX));
+            assert_eq!(run(&max), slice.iter().copied().max().unwrap_or(i32::MIN));
+        }
+    }
+}
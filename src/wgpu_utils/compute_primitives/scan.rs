@@ -0,0 +1,293 @@
+//! Parallel exclusive prefix sum
+//!
+//! Every workgroup scans a block of 512 elements and writes the block's sum.
+//! The block sums are scanned the same way, level by level until they fit
+//! in one block, and the scanned sums are then added back to the level
+//! below.
+
+use std::marker::PhantomData;
+
+use wgpu::{Buffer, CommandEncoder, Device, Queue};
+
+use super::{GpuScalar, PassUniforms, MAX_WORKGROUPS, WORKGROUP_SIZE};
+
+/// Elements scanned by one workgroup
+const BLOCK: u32 = WORKGROUP_SIZE * 2;
+
+struct ScanLevel {
+    uniforms: Buffer,
+    /// Scans the level's input into its output and block sums
+    scan_bind_group: wgpu::BindGroup,
+    /// Adds the scanned block sums of the next level to the output
+    add_bind_group: Option<wgpu::BindGroup>,
+}
+
+/// Exclusive prefix sum of a storage buffer of `T` into another buffer
+///
+/// `output[i]` becomes the sum of `input[..i]`, and [`total`](Self::total)
+/// the sum of all elements. Input and output must be different buffers.
+pub struct Scan<T: GpuScalar> {
+    capacity: u32,
+    scan_pipeline: wgpu::ComputePipeline,
+    add_pipeline: wgpu::ComputePipeline,
+    levels: Vec<ScanLevel>,
+    total: Buffer,
+    /// Keeps the block sum buffers alive
+    _sums: Vec<Buffer>,
+    _scalar: PhantomData<T>,
+}
+
+impl<T: GpuScalar> Scan<T> {
+    /// Create a scan of up to `capacity` elements of `input` into `output`
+    pub fn new(device: &Device, input: &Buffer, output: &Buffer, capacity: u32) -> Self {
+        assert!(
+            capacity.div_ceil(BLOCK) <= MAX_WORKGROUPS,
+            "scan capacity exceeds {} elements",
+            MAX_WORKGROUPS * BLOCK
+        );
+        let source = SCAN_SHADER
+            .replace("SCALAR", T::WGSL_TYPE)
+            .replace("ZERO", T::ZERO);
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Scan Layout"),
+            entries: &[
+                super::buffer_entry(0, wgpu::BufferBindingType::Uniform),
+                super::buffer_entry(1, wgpu::BufferBindingType::Storage { read_only: true }),
+                super::buffer_entry(2, wgpu::BufferBindingType::Storage { read_only: false }),
+                super::buffer_entry(3, wgpu::BufferBindingType::Storage { read_only: false }),
+            ],
+        });
+        let [scan_pipeline, add_pipeline] = super::pipelines(
+            device,
+            "Scan Shader",
+            &source,
+            &layout,
+            ["scan_blocks", "add_block_offsets"],
+        );
+
+        // Each level above the first scans the block sums of the one below:
+        // `sums[2 * l]` are the raw sums of level `l`, `sums[2 * l + 1]` the
+        // scanned ones, which are also the output of level `l + 1`
+        let element_size = std::mem::size_of::<T>() as u64;
+        let mut sums = Vec::new();
+        let mut size = capacity.max(1);
+        while size > BLOCK {
+            size = size.div_ceil(BLOCK);
+            for label in ["Scan Block Sums", "Scan Block Offsets"] {
+                sums.push(super::storage_buffer(
+                    device,
+                    label,
+                    size as u64 * element_size,
+                ));
+            }
+        }
+        let total = super::storage_buffer(device, "Scan Total", element_size);
+
+        let level_count = sums.len() / 2 + 1;
+        let levels = (0..level_count)
+            .map(|level| {
+                let (input, output) = if level == 0 {
+                    (input, output)
+                } else {
+                    (&sums[2 * level - 2], &sums[2 * level - 1])
+                };
+                let block_sums = sums.get(2 * level).unwrap_or(&total);
+                let uniforms = super::uniform_buffer(device, "Scan Uniforms");
+                let scan_bind_group = super::bind_group(
+                    device,
+                    "Scan Bind Group",
+                    &layout,
+                    &[&uniforms, input, output, block_sums],
+                );
+                let add_bind_group = sums.get(2 * level + 1).map(|offsets| {
+                    super::bind_group(
+                        device,
+                        "Scan Offsets Bind Group",
+                        &layout,
+                        &[&uniforms, input, output, offsets],
+                    )
+                });
+                ScanLevel {
+                    uniforms,
+                    scan_bind_group,
+                    add_bind_group,
+                }
+            })
+            .collect();
+
+        Self {
+            capacity,
+            scan_pipeline,
+            add_pipeline,
+            levels,
+            total,
+            _sums: sums,
+            _scalar: PhantomData,
+        }
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    /// Buffer holding the sum of all scanned elements once the passes ran
+    pub fn total(&self) -> &Buffer {
+        &self.total
+    }
+
+    /// Record the passes scanning the first `count` elements of the input
+    pub fn encode(&self, queue: &Queue, encoder: &mut CommandEncoder, count: u32) {
+        let mut counts = Vec::with_capacity(self.levels.len());
+        let mut count = count.min(self.capacity);
+        for level in &self.levels {
+            let uniforms = PassUniforms {
+                count,
+                ..bytemuck::Zeroable::zeroed()
+            };
+            queue.write_buffer(&level.uniforms, 0, bytemuck::bytes_of(&uniforms));
+            counts.push(count);
+            count = count.div_ceil(BLOCK);
+        }
+
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Scan"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(&self.scan_pipeline);
+        for (level, &count) in self.levels.iter().zip(&counts) {
+            pass.set_bind_group(0, &level.scan_bind_group, &[]);
+            pass.dispatch_workgroups(count.div_ceil(BLOCK).max(1), 1, 1);
+        }
+        pass.set_pipeline(&self.add_pipeline);
+        for (level, &count) in self.levels.iter().zip(&counts).rev() {
+            if let Some(bind_group) = &level.add_bind_group {
+                if count > 0 {
+                    pass.set_bind_group(0, bind_group, &[]);
+                    pass.dispatch_workgroups(count.div_ceil(BLOCK), 1, 1);
+                }
+            }
+        }
+    }
+
+    /// Scan the first `count` elements in a submission of its own
+    pub fn run(&self, device: &Device, queue: &Queue, count: u32) {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Scan Encoder"),
+        });
+        self.encode(queue, &mut encoder, count);
+        queue.submit(std::iter::once(encoder.finish()));
+    }
+}
+
+const SCAN_SHADER: &str = r#"
+struct PassUniforms {
+    count: u32,
+    shift: u32,
+    block_count: u32,
+    has_values: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: PassUniforms;
+@group(0) @binding(1) var<storage, read> input: array<SCALAR>;
+@group(0) @binding(2) var<storage, read_write> output: array<SCALAR>;
+// Block sums when scanning, scanned block sums when adding offsets
+@group(0) @binding(3) var<storage, read_write> block_sums: array<SCALAR>;
+
+var<workgroup> temp: array<SCALAR, 256>;
+
+fn load(i: u32) -> SCALAR {
+    if (i < params.count) {
+        return input[i];
+    }
+    return ZERO;
+}
+
+// Every invocation scans two neighboring elements
+@compute @workgroup_size(256)
+fn scan_blocks(
+    @builtin(local_invocation_index) local: u32,
+    @builtin(workgroup_id) group: vec3<u32>,
+) {
+    let base = group.x * 512u + local * 2u;
+    let a = load(base);
+    let b = load(base + 1u);
+    temp[local] = a + b;
+    workgroupBarrier();
+
+    // Inclusive Hillis-Steele scan of the pair sums
+    for (var offset = 1u; offset < 256u; offset <<= 1u) {
+        var add = ZERO;
+        if (local >= offset) {
+            add = temp[local - offset];
+        }
+        workgroupBarrier();
+        temp[local] += add;
+        workgroupBarrier();
+    }
+
+    var before = ZERO;
+    if (local > 0u) {
+        before = temp[local - 1u];
+    }
+    if (base < params.count) {
+        output[base] = before;
+    }
+    if (base + 1u < params.count) {
+        output[base + 1u] = before + a;
+    }
+    if (local == 255u) {
+        block_sums[group.x] = temp[255];
+    }
+}
+
+@compute @workgroup_size(256)
+fn add_block_offsets(
+    @builtin(local_invocation_index) local: u32,
+    @builtin(workgroup_id) group: vec3<u32>,
+) {
+    let offset = block_sums[group.x];
+    let base = group.x * 512u + local * 2u;
+    if (base < params.count) {
+        output[base] += offset;
+    }
+    if (base + 1u < params.count) {
+        output[base + 1u] += offset;
+    }
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wgpu_utils::compute_primitives::{read_buffer, test_buffer, test_device};
+
+    #[test]
+    fn test_scan_matches_cpu() {
+        // Needs a GPU adapter, skipped without one
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        // Three scan levels
+        let len = 300_000;
+        let values: Vec<u32> = (0..len).map(|i| i % 7).collect();
+        let input = test_buffer(&device, &values);
+        let output = test_buffer(&device, &vec![0u32; len as usize]);
+        let scan = Scan::<u32>::new(&device, &input, &output, len);
+
+        for count in [1, 777, len] {
+            scan.run(&device, &queue, count);
+            let scanned: Vec<u32> = read_buffer(&device, &queue, &output, count as usize).unwrap();
+            let expected: Vec<u32> = values[..count as usize]
+                .iter()
+                .scan(0, |sum, &value| {
+                    let before = *sum;
+                    *sum += value;
+                    Some(before)
+                })
+                .collect();
+            assert_eq!(scanned, expected);
+            let total: Vec<u32> = read_buffer(&device, &queue, scan.total(), 1).unwrap();
+            assert_eq!(total[0], values[..count as usize].iter().sum::<u32>());
+        }
+    }
+}
@@ -0,0 +1,330 @@
+//! Parallel least-significant-digit radix sort
+//!
+//! Keys are sorted four bits at a time. Each pass counts the digits of every
+//! block of 256 keys, scans the counts into global offsets with a
+//! [`Scan`], and scatters the keys: every block sorts itself by the digit in
+//! workgroup memory with four stable one-bit splits, then writes its keys to
+//! the offsets of their digit. The passes alternate between the key buffer
+//! and an internal buffer, and always take an even number of passes, so the
+//! sorted keys end up back in the key buffer.
+
+use wgpu::{Buffer, CommandEncoder, Device, Queue};
+
+use super::{PassUniforms, Scan, MAX_WORKGROUPS, WORKGROUP_SIZE};
+
+/// Keys ranked by one workgroup
+const BLOCK: u32 = WORKGROUP_SIZE;
+
+/// Buckets of one digit
+const RADIX: u32 = 16;
+const DIGIT_BITS: u32 = 4;
+
+struct SortPass {
+    uniforms: Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+/// Stable GPU sort of `u32` keys, optionally reordering `u32` values along
+///
+/// The values can be indices into other buffers, to sort particles by cell
+/// or depth without moving the particles themselves.
+pub struct RadixSort {
+    capacity: u32,
+    key_bits: u32,
+    has_values: bool,
+    histogram_pipeline: wgpu::ComputePipeline,
+    scatter_pipeline: wgpu::ComputePipeline,
+    /// One per digit, alternating between the key buffer and the scratch one
+    passes: Vec<SortPass>,
+    scan: Scan<u32>,
+    /// Keeps the scratch, histogram and placeholder buffers alive
+    _buffers: Vec<Buffer>,
+}
+
+impl RadixSort {
+    /// Create a sort of up to `capacity` `keys`, moving `values` along when given
+    pub fn new(device: &Device, keys: &Buffer, values: Option<&Buffer>, capacity: u32) -> Self {
+        assert!(
+            capacity.div_ceil(BLOCK) <= MAX_WORKGROUPS,
+            "radix sort capacity exceeds {} keys",
+            MAX_WORKGROUPS * BLOCK
+        );
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Radix Sort Layout"),
+            entries: &[
+                super::buffer_entry(0, wgpu::BufferBindingType::Uniform),
+                super::buffer_entry(1, wgpu::BufferBindingType::Storage { read_only: true }),
+                super::buffer_entry(2, wgpu::BufferBindingType::Storage { read_only: false }),
+                super::buffer_entry(3, wgpu::BufferBindingType::Storage { read_only: true }),
+                super::buffer_entry(4, wgpu::BufferBindingType::Storage { read_only: false }),
+                super::buffer_entry(5, wgpu::BufferBindingType::Storage { read_only: false }),
+                super::buffer_entry(6, wgpu::BufferBindingType::Storage { read_only: true }),
+            ],
+        });
+        let [histogram_pipeline, scatter_pipeline] = super::pipelines(
+            device,
+            "Radix Sort Shader",
+            SORT_SHADER,
+            &layout,
+            ["histogram", "scatter"],
+        );
+
+        let has_values = values.is_some();
+        let key_bytes = capacity as u64 * 4;
+        let scratch_keys = super::storage_buffer(device, "Radix Sort Scratch Keys", key_bytes);
+        // Without values, small placeholders fill the value bindings
+        let (values, scratch_values) = match values {
+            Some(values) => (
+                values.clone(),
+                super::storage_buffer(device, "Radix Sort Scratch Values", key_bytes),
+            ),
+            None => (
+                super::storage_buffer(device, "Radix Sort Values Placeholder", 4),
+                super::storage_buffer(device, "Radix Sort Scratch Values Placeholder", 4),
+            ),
+        };
+        let histogram_len = capacity.div_ceil(BLOCK).max(1) * RADIX;
+        let histogram =
+            super::storage_buffer(device, "Radix Sort Histogram", histogram_len as u64 * 4);
+        let offsets = super::storage_buffer(device, "Radix Sort Offsets", histogram_len as u64 * 4);
+        let scan = Scan::new(device, &histogram, &offsets, histogram_len);
+
+        let passes = (0..32 / DIGIT_BITS)
+            .map(|pass| {
+                let uniforms = super::uniform_buffer(device, "Radix Sort Uniforms");
+                let (keys_in, keys_out, values_in, values_out) = if pass % 2 == 0 {
+                    (keys, &scratch_keys, &values, &scratch_values)
+                } else {
+                    (&scratch_keys, keys, &scratch_values, &values)
+                };
+                let bind_group = super::bind_group(
+                    device,
+                    "Radix Sort Bind Group",
+                    &layout,
+                    &[
+                        &uniforms, keys_in, keys_out, values_in, values_out, &histogram, &offsets,
+                    ],
+                );
+                SortPass {
+                    uniforms,
+                    bind_group,
+                }
+            })
+            .collect();
+
+        Self {
+            capacity,
+            key_bits: 32,
+            has_values,
+            histogram_pipeline,
+            scatter_pipeline,
+            passes,
+            scan,
+            _buffers: vec![scratch_keys, values, scratch_values, histogram, offsets],
+        }
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    /// Only sort by the lowest `bits` bits of the keys, skipping passes when
+    /// the keys are known to be small; rounded up to a multiple of 8
+    pub fn set_key_bits(&mut self, bits: u32) {
+        self.key_bits = (bits.clamp(1, 32).div_ceil(8) * 8).min(32);
+    }
+
+    pub fn key_bits(&self) -> u32 {
+        self.key_bits
+    }
+
+    /// Record the passes sorting the first `count` keys
+    pub fn encode(&self, queue: &Queue, encoder: &mut CommandEncoder, count: u32) {
+        let count = count.min(self.capacity);
+        if count < 2 {
+            return;
+        }
+        let block_count = count.div_ceil(BLOCK);
+        let pass_count = (self.key_bits / DIGIT_BITS) as usize;
+        for (index, pass) in self.passes.iter().take(pass_count).enumerate() {
+            let uniforms = PassUniforms {
+                count,
+                shift: index as u32 * DIGIT_BITS,
+                block_count,
+                has_values: self.has_values as u32,
+            };
+            queue.write_buffer(&pass.uniforms, 0, bytemuck::bytes_of(&uniforms));
+
+            {
+                let mut compute = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: Some("Radix Sort Histogram"),
+                    timestamp_writes: None,
+                });
+                compute.set_pipeline(&self.histogram_pipeline);
+                compute.set_bind_group(0, &pass.bind_group, &[]);
+                compute.dispatch_workgroups(block_count, 1, 1);
+            }
+            self.scan.encode(queue, encoder, block_count * RADIX);
+            {
+                let mut compute = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: Some("Radix Sort Scatter"),
+                    timestamp_writes: None,
+                });
+                compute.set_pipeline(&self.scatter_pipeline);
+                compute.set_bind_group(0, &pass.bind_group, &[]);
+                compute.dispatch_workgroups(block_count, 1, 1);
+            }
+        }
+    }
+
+    /// Sort the first `count` keys in a submission of its own
+    pub fn run(&self, device: &Device, queue: &Queue, count: u32) {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Radix Sort Encoder"),
+        });
+        self.encode(queue, &mut encoder, count);
+        queue.submit(std::iter::once(encoder.finish()));
+    }
+}
+
+const SORT_SHADER: &str = r#"
+struct PassUniforms {
+    count: u32,
+    shift: u32,
+    block_count: u32,
+    has_values: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: PassUniforms;
+@group(0) @binding(1) var<storage, read> keys_in: array<u32>;
+@group(0) @binding(2) var<storage, read_write> keys_out: array<u32>;
+@group(0) @binding(3) var<storage, read> values_in: array<u32>;
+@group(0) @binding(4) var<storage, read_write> values_out: array<u32>;
+// Digit counts, digit-major: digit_counts[digit * block_count + block]
+@group(0) @binding(5) var<storage, read_write> digit_counts: array<u32>;
+// Exclusive scan of the digit counts
+@group(0) @binding(6) var<storage, read> offsets: array<u32>;
+
+var<workgroup> block_counts: array<atomic<u32>, 16>;
+var<workgroup> sorted_keys: array<u32, 256>;
+var<workgroup> sorted_values: array<u32, 256>;
+var<workgroup> zeros: array<u32, 256>;
+var<workgroup> digit_start: array<u32, 16>;
+
+fn digit_of(key: u32) -> u32 {
+    return (key >> params.shift) & 15u;
+}
+
+@compute @workgroup_size(256)
+fn histogram(
+    @builtin(local_invocation_index) local: u32,
+    @builtin(workgroup_id) group: vec3<u32>,
+) {
+    if (local < 16u) {
+        atomicStore(&block_counts[local], 0u);
+    }
+    workgroupBarrier();
+    let i = group.x * 256u + local;
+    if (i < params.count) {
+        atomicAdd(&block_counts[digit_of(keys_in[i])], 1u);
+    }
+    workgroupBarrier();
+    if (local < 16u) {
+        digit_counts[local * params.block_count + group.x] = atomicLoad(&block_counts[local]);
+    }
+}
+
+@compute @workgroup_size(256)
+fn scatter(
+    @builtin(local_invocation_index) local: u32,
+    @builtin(workgroup_id) group: vec3<u32>,
+) {
+    let i = group.x * 256u + local;
+    let valid_count = min(256u, params.count - group.x * 256u);
+
+    // Missing keys have the highest digit and the highest indices, so they
+    // end up behind the valid ones
+    var key = 0xffffffffu;
+    var value = 0u;
+    if (local < valid_count) {
+        key = keys_in[i];
+        if (params.has_values != 0u) {
+            value = values_in[i];
+        }
+    }
+
+    // Sort the block by digit with stable splits on each bit, lowest first
+    for (var bit = 0u; bit < 4u; bit++) {
+        let one = (digit_of(key) >> bit) & 1u;
+        zeros[local] = 1u - one;
+        workgroupBarrier();
+        for (var offset = 1u; offset < 256u; offset <<= 1u) {
+            var add = 0u;
+            if (local >= offset) {
+                add = zeros[local - offset];
+            }
+            workgroupBarrier();
+            zeros[local] += add;
+            workgroupBarrier();
+        }
+        var destination = zeros[local] - 1u;
+        if (one == 1u) {
+            destination = zeros[255] + local - zeros[local];
+        }
+        sorted_keys[destination] = key;
+        sorted_values[destination] = value;
+        workgroupBarrier();
+        key = sorted_keys[local];
+        value = sorted_values[local];
+        workgroupBarrier();
+    }
+
+    let digit = digit_of(key);
+    if (local == 0u || digit_of(sorted_keys[local - 1u]) != digit) {
+        digit_start[digit] = local;
+    }
+    workgroupBarrier();
+
+    if (local < valid_count) {
+        let destination = offsets[digit * params.block_count + group.x] + local - digit_start[digit];
+        keys_out[destination] = key;
+        if (params.has_values != 0u) {
+            values_out[destination] = value;
+        }
+    }
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wgpu_utils::compute_primitives::{read_buffer, test_buffer, test_device};
+
+    #[test]
+    fn test_radix_sort_is_stable_and_matches_cpu() {
+        // Needs a GPU adapter, skipped without one
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let len = 70_001u32;
+        let keys: Vec<u32> = (0..len)
+            .map(|i| i.wrapping_mul(2_654_435_761) >> 20)
+            .collect();
+        let values: Vec<u32> = (0..len).collect();
+        let key_buffer = test_buffer(&device, &keys);
+        let value_buffer = test_buffer(&device, &values);
+        let mut sort = RadixSort::new(&device, &key_buffer, Some(&value_buffer), len);
+        sort.set_key_bits(12);
+        assert_eq!(sort.key_bits(), 16);
+        sort.run(&device, &queue, len);
+
+        let mut expected: Vec<(u32, u32)> = keys.iter().copied().zip(values).collect();
+        expected.sort_by_key(|&(key, _)| key);
+        let sorted_keys: Vec<u32> =
+            read_buffer(&device, &queue, &key_buffer, len as usize).unwrap();
+        let sorted_values: Vec<u32> =
+            read_buffer(&device, &queue, &value_buffer, len as usize).unwrap();
+        let sorted: Vec<(u32, u32)> = sorted_keys.into_iter().zip(sorted_values).collect();
+        assert_eq!(sorted, expected);
+    }
+}
@@ -0,0 +1,218 @@
+//! Half-precision field storage
+//!
+//! Large fields, e.g. the 19 distributions of a D3Q19 lattice Boltzmann grid,
+//! can be stored as 16-bit floats to halve their VRAM and bandwidth. Values
+//! are packed two per `u32` word, the layout of WGSL's `pack2x16float` and
+//! `unpack2x16float`, so no `SHADER_F16` device feature is needed: shaders
+//! declare the field as `array<u32>` and work on pairs of values.
+//!
+//! ## Usage
+//!
+//! ```wgsl
+//! @group(0) @binding(0) var<storage, read_write> density: array<u32>;
+//!
+//! // Each invocation owns two neighboring cells
+//! let pair = unpack2x16float(density[id.x]);
+//! density[id.x] = pack2x16float(pair * 0.99);
+//! ```
+//!
+//! The Rust side packs and unpacks the same layout with [`pack_f16`] and
+//! [`unpack_f16`], and [`HALF_WGSL`] adds per-element helpers for shaders
+//! that can't process values in pairs.
+
+use wgpu::{Buffer, Device, Queue};
+
+/// WGSL helpers reading and replacing single values in packed words
+///
+/// Replacing one half of a word is a read-modify-write: two invocations
+/// writing the two values of a word at once race, so writers should own
+/// whole words.
+pub const HALF_WGSL: &str = r#"
+// Value `index` of the packed field, given the word `index / 2u`
+fn half_load(word: u32, index: u32) -> f32 {
+    let pair = unpack2x16float(word);
+    return select(pair.x, pair.y, (index & 1u) == 1u);
+}
+
+// `word` with value `index` replaced by `value`
+fn half_replace(word: u32, index: u32, value: f32) -> u32 {
+    var pair = unpack2x16float(word);
+    if ((index & 1u) == 1u) {
+        pair.y = value;
+    } else {
+        pair.x = value;
+    }
+    return pack2x16float(pair);
+}
+"#;
+
+/// Storage precision of a field buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FieldPrecision {
+    /// 32-bit floats, one per element
+    #[default]
+    F32,
+    /// 16-bit floats packed two per `u32`
+    F16,
+}
+
+impl FieldPrecision {
+    /// Bytes needed to store `len` values
+    pub fn buffer_size(self, len: usize) -> u64 {
+        match self {
+            FieldPrecision::F32 => len as u64 * 4,
+            FieldPrecision::F16 => len.div_ceil(2) as u64 * 4,
+        }
+    }
+}
+
+/// Convert to the nearest half-precision value (ties to even)
+///
+/// Values beyond ±65504 become infinities, values below 2⁻²⁴ become zero.
+pub fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xFF) as i32;
+    let mantissa = bits & 0x7F_FFFF;
+
+    if exponent == 0xFF {
+        // Infinity, or NaN with a quiet bit set
+        return sign | 0x7C00 | if mantissa != 0 { 0x200 } else { 0 };
+    }
+
+    let half_exponent = exponent - 127 + 15;
+    if half_exponent >= 0x1F {
+        return sign | 0x7C00;
+    }
+
+    // Drop `shift` mantissa bits, rounding to nearest even
+    let round = |mantissa: u32, shift: u32| {
+        let kept = mantissa >> shift;
+        let rest = mantissa & ((1 << shift) - 1);
+        let halfway = 1 << (shift - 1);
+        if rest > halfway || (rest == halfway && kept & 1 == 1) {
+            kept + 1
+        } else {
+            kept
+        }
+    };
+
+    if half_exponent <= 0 {
+        // Subnormal or zero
+        if half_exponent < -10 {
+            return sign;
+        }
+        let shift = (14 - half_exponent) as u32;
+        return sign | round(mantissa | 0x80_0000, shift) as u16;
+    }
+
+    // A rounding carry correctly bumps the exponent
+    sign | round(((half_exponent as u32) << 23) | mantissa, 13) as u16
+}
+
+/// Convert a half-precision value to `f32` (exact)
+pub fn f16_to_f32(half: u16) -> f32 {
+    let sign = ((half & 0x8000) as u32) << 16;
+    let exponent = ((half >> 10) & 0x1F) as u32;
+    let mantissa = (half & 0x3FF) as u32;
+
+    let bits = match exponent {
+        0 if mantissa == 0 => sign,
+        0 => {
+            let value = mantissa as f32 * (-24f32).exp2();
+            return if sign != 0 { -value } else { value };
+        }
+        0x1F => sign | 0x7F80_0000 | (mantissa << 13),
+        _ => sign | ((exponent + 112) << 23) | (mantissa << 13),
+    };
+    f32::from_bits(bits)
+}
+
+/// Pack values two per word, the first of each pair in the low 16 bits
+///
+/// An odd final value is paired with zero.
+pub fn pack_f16(values: &[f32]) -> Vec<u32> {
+    values
+        .chunks(2)
+        .map(|pair| {
+            let low = f32_to_f16(pair[0]) as u32;
+            let high = pair.get(1).map_or(0, |&value| f32_to_f16(value) as u32);
+            low | (high << 16)
+        })
+        .collect()
+}
+
+/// Unpack the first `len` values of packed words
+pub fn unpack_f16(words: &[u32], len: usize) -> Vec<f32> {
+    words
+        .iter()
+        .flat_map(|&word| [f16_to_f32(word as u16), f16_to_f32((word >> 16) as u16)])
+        .take(len)
+        .collect()
+}
+
+/// Upload `values` to a field buffer stored with `precision`
+pub fn write_field(queue: &Queue, buffer: &Buffer, precision: FieldPrecision, values: &[f32]) {
+    match precision {
+        FieldPrecision::F32 => queue.write_buffer(buffer, 0, bytemuck::cast_slice(values)),
+        FieldPrecision::F16 => {
+            queue.write_buffer(buffer, 0, bytemuck::cast_slice(&pack_f16(values)))
+        }
+    }
+}
+
+/// Read the first `len` values of a field buffer stored with `precision`,
+/// waiting for the GPU
+///
+/// The buffer needs `COPY_SRC` usage.
+pub fn read_field(
+    device: &Device,
+    queue: &Queue,
+    buffer: &Buffer,
+    precision: FieldPrecision,
+    len: usize,
+) -> Result<Vec<f32>, wgpu::BufferAsyncError> {
+    use super::compute_primitives::read_buffer;
+
+    match precision {
+        FieldPrecision::F32 => read_buffer(device, queue, buffer, len),
+        FieldPrecision::F16 => {
+            let words: Vec<u32> = read_buffer(device, queue, buffer, len.div_ceil(2))?;
+            Ok(unpack_f16(&words, len))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_f16_conversion() {
+        assert_eq!(f32_to_f16(1.0), 0x3C00);
+        assert_eq!(f32_to_f16(-2.0), 0xC000);
+        assert_eq!(f32_to_f16(65504.0), 0x7BFF);
+        assert_eq!(f32_to_f16(1.0e6), 0x7C00);
+        assert_eq!(f32_to_f16(f32::NEG_INFINITY), 0xFC00);
+        assert!(f16_to_f32(f32_to_f16(f32::NAN)).is_nan());
+        // Smallest subnormal and below
+        assert_eq!(f32_to_f16(5.960_464_5e-8), 0x0001);
+        assert_eq!(f32_to_f16(1.0e-8), 0x0000);
+        // 1 + 2^-11 is halfway between 1 and the next half; ties to even
+        assert_eq!(f32_to_f16(1.0 + (-11f32).exp2()), 0x3C00);
+        assert_eq!(f32_to_f16(1.0 + 3.0 * (-11f32).exp2()), 0x3C02);
+
+        for half in [0x0001u16, 0x03FF, 0x0400, 0x3555, 0x7BFF, 0x8001, 0xC000] {
+            assert_eq!(f32_to_f16(f16_to_f32(half)), half);
+        }
+    }
+
+    #[test]
+    fn test_pack_layout() {
+        let words = pack_f16(&[1.0, -2.0, 0.5]);
+        assert_eq!(words, vec![0xC000_3C00, 0x0000_3800]);
+        assert_eq!(unpack_f16(&words, 3), vec![1.0, -2.0, 0.5]);
+        assert_eq!(FieldPrecision::F16.buffer_size(3), 8);
+        assert_eq!(FieldPrecision::F32.buffer_size(3), 12);
+    }
+}
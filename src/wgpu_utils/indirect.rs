@@ -0,0 +1,356 @@
+//! # Indirect Dispatch and Draw
+//!
+//! Lets the GPU size its own work. A simulation whose element count changes
+//! on the GPU (particles emitted or killed in a compute shader) keeps the
+//! count in a storage buffer; [`IndirectArgsPass`] turns it into dispatch
+//! and draw arguments, which `dispatch_workgroups_indirect` and
+//! `draw_indexed_indirect` read without the count ever reaching the CPU.
+//!
+//! Shaders can also fill argument buffers themselves, with the structs from
+//! [`INDIRECT_ARGS_WGSL`]. Argument buffers are created with `INDIRECT`,
+//! `STORAGE`, `COPY_DST` and `COPY_SRC` usage, so they can be bound for
+//! writing in one pass, consumed in the next and read back for debugging.
+//!
+//! Indirect execution needs `DownlevelFlags::INDIRECT_EXECUTION`, which
+//! WebGL2 doesn't provide.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::wgpu_utils::indirect::IndirectArgsPass;
+//!
+//! # fn example(device: &wgpu::Device, encoder: &mut wgpu::CommandEncoder,
+//! #     alive_count: &wgpu::Buffer, update: &wgpu::ComputePipeline, bind_group: &wgpu::BindGroup) {
+//! // `alive_count` holds an atomic counter maintained by the emit/kill shaders
+//! let args = IndirectArgsPass::new(device, alive_count, 64, 0);
+//! args.encode(encoder);
+//!
+//! let mut pass = encoder.begin_compute_pass(&Default::default());
+//! pass.set_pipeline(update);
+//! pass.set_bind_group(0, bind_group, &[]);
+//! pass.dispatch_workgroups_indirect(args.dispatch_args(), 0);
+//! # }
+//! ```
+
+use wgpu::util::DeviceExt;
+use wgpu::{Buffer, CommandEncoder, Device};
+
+pub use wgpu::util::{DispatchIndirectArgs, DrawIndexedIndirectArgs, DrawIndirectArgs};
+
+/// WGSL declarations of the indirect argument layouts, for shaders writing
+/// their own arguments
+pub const INDIRECT_ARGS_WGSL: &str = r#"
+struct DispatchIndirectArgs {
+    x: u32,
+    y: u32,
+    z: u32,
+}
+
+struct DrawIndirectArgs {
+    vertex_count: u32,
+    instance_count: u32,
+    first_vertex: u32,
+    first_instance: u32,
+}
+
+struct DrawIndexedIndirectArgs {
+    index_count: u32,
+    instance_count: u32,
+    first_index: u32,
+    base_vertex: i32,
+    first_instance: u32,
+}
+"#;
+
+const ARGS_USAGE: wgpu::BufferUsages = wgpu::BufferUsages::INDIRECT
+    .union(wgpu::BufferUsages::STORAGE)
+    .union(wgpu::BufferUsages::COPY_DST)
+    .union(wgpu::BufferUsages::COPY_SRC);
+
+/// Buffer holding dispatch arguments, initialized to `args`
+pub fn dispatch_args_buffer(device: &Device, label: &str, args: DispatchIndirectArgs) -> Buffer {
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some(label),
+        contents: args.as_bytes(),
+        usage: ARGS_USAGE,
+    })
+}
+
+/// Buffer holding non-indexed draw arguments, initialized to `args`
+pub fn draw_args_buffer(device: &Device, label: &str, args: DrawIndirectArgs) -> Buffer {
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some(label),
+        contents: args.as_bytes(),
+        usage: ARGS_USAGE,
+    })
+}
+
+/// Buffer holding indexed draw arguments, initialized to `args`
+pub fn draw_indexed_args_buffer(
+    device: &Device,
+    label: &str,
+    args: DrawIndexedIndirectArgs,
+) -> Buffer {
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some(label),
+        contents: args.as_bytes(),
+        usage: ARGS_USAGE,
+    })
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct ArgsSettings {
+    workgroup_size: u32,
+    index_count: u32,
+    _padding: [u32; 2],
+}
+
+/// Compute pass deriving indirect arguments from an element count on the GPU
+///
+/// Reads the `u32` at the start of the counter buffer and writes
+/// - dispatch arguments for one invocation per element, in workgroups of
+///   `workgroup_size`
+/// - indexed draw arguments drawing `index_count` indices once per element
+pub struct IndirectArgsPass {
+    pipeline: wgpu::ComputePipeline,
+    bind_group: wgpu::BindGroup,
+    dispatch_args: Buffer,
+    draw_args: Buffer,
+}
+
+impl IndirectArgsPass {
+    /// Create the pass for `counter`, a storage buffer starting with the count
+    ///
+    /// `index_count` is the number of indices of the mesh drawn per element;
+    /// pass 0 when only dispatching.
+    pub fn new(device: &Device, counter: &Buffer, workgroup_size: u32, index_count: u32) -> Self {
+        assert!(workgroup_size > 0, "workgroup size must not be zero");
+        let settings = ArgsSettings {
+            workgroup_size,
+            index_count,
+            _padding: [0; 2],
+        };
+        let settings = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Indirect Args Settings"),
+            contents: bytemuck::bytes_of(&settings),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let dispatch_args = dispatch_args_buffer(
+            device,
+            "Indirect Dispatch Args",
+            DispatchIndirectArgs { x: 0, y: 1, z: 1 },
+        );
+        let draw_args = draw_indexed_args_buffer(
+            device,
+            "Indirect Draw Args",
+            DrawIndexedIndirectArgs {
+                index_count,
+                instance_count: 0,
+                first_index: 0,
+                base_vertex: 0,
+                first_instance: 0,
+            },
+        );
+
+        let entry = |binding: u32, ty: wgpu::BufferBindingType| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Indirect Args Layout"),
+            entries: &[
+                entry(0, wgpu::BufferBindingType::Uniform),
+                entry(1, wgpu::BufferBindingType::Storage { read_only: true }),
+                entry(2, wgpu::BufferBindingType::Storage { read_only: false }),
+                entry(3, wgpu::BufferBindingType::Storage { read_only: false }),
+            ],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Indirect Args Bind Group"),
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: settings.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: counter,
+                        offset: 0,
+                        size: wgpu::BufferSize::new(4),
+                    }),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: dispatch_args.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: draw_args.as_entire_binding(),
+                },
+            ],
+        });
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Indirect Args Shader"),
+            source: wgpu::ShaderSource::Wgsl(format!("{INDIRECT_ARGS_WGSL}{ARGS_SHADER}").into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Indirect Args Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Indirect Args Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: Some("write_args"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        Self {
+            pipeline,
+            bind_group,
+            dispatch_args,
+            draw_args,
+        }
+    }
+
+    /// [`DispatchIndirectArgs`] for `dispatch_workgroups_indirect`
+    pub fn dispatch_args(&self) -> &Buffer {
+        &self.dispatch_args
+    }
+
+    /// [`DrawIndexedIndirectArgs`] for `draw_indexed_indirect`
+    pub fn draw_args(&self) -> &Buffer {
+        &self.draw_args
+    }
+
+    /// Record the pass; later passes in the encoder see the new arguments
+    pub fn encode(&self, encoder: &mut CommandEncoder) {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("Indirect Args"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.dispatch_workgroups(1, 1, 1);
+    }
+}
+
+const ARGS_SHADER: &str = r#"
+struct Settings {
+    workgroup_size: u32,
+    index_count: u32,
+    _padding0: u32,
+    _padding1: u32,
+}
+
+@group(0) @binding(0) var<uniform> settings: Settings;
+@group(0) @binding(1) var<storage, read> counter: array<u32>;
+@group(0) @binding(2) var<storage, read_write> dispatch_args: DispatchIndirectArgs;
+@group(0) @binding(3) var<storage, read_write> draw_args: DrawIndexedIndirectArgs;
+
+@compute @workgroup_size(1)
+fn write_args() {
+    let count = counter[0];
+    // A dispatch dimension holds at most 65535 workgroups
+    dispatch_args.x = min((count + settings.workgroup_size - 1u) / settings.workgroup_size, 65535u);
+    dispatch_args.y = 1u;
+    dispatch_args.z = 1u;
+
+    draw_args.index_count = settings.index_count;
+    draw_args.instance_count = count;
+    draw_args.first_index = 0u;
+    draw_args.base_vertex = 0;
+    draw_args.first_instance = 0u;
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wgpu_utils::compute_primitives::{read_buffer, test_buffer, test_device};
+
+    #[test]
+    fn test_dispatch_is_sized_by_gpu_count() {
+        // Needs a GPU adapter, skipped without one
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let counter = test_buffer(&device, &[1000u32]);
+        let hits = test_buffer(&device, &[0u32]);
+        let args = IndirectArgsPass::new(&device, &counter, 64, 12);
+
+        // Count the invocations an indirect dispatch launches
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: false },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: hits.as_entire_binding(),
+            }],
+        });
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(
+                "@group(0) @binding(0) var<storage, read_write> hits: atomic<u32>;
+                @compute @workgroup_size(64) fn main() { atomicAdd(&hits, 1u); }"
+                    .into(),
+            ),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: None,
+            layout: Some(
+                &device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: None,
+                    bind_group_layouts: &[&layout],
+                    push_constant_ranges: &[],
+                }),
+            ),
+            module: &shader,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let mut encoder = device.create_command_encoder(&Default::default());
+        args.encode(&mut encoder);
+        {
+            let mut pass = encoder.begin_compute_pass(&Default::default());
+            pass.set_pipeline(&pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups_indirect(args.dispatch_args(), 0);
+        }
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let dispatch: Vec<u32> = read_buffer(&device, &queue, args.dispatch_args(), 3).unwrap();
+        assert_eq!(dispatch, vec![16, 1, 1]);
+        let draw: Vec<u32> = read_buffer(&device, &queue, args.draw_args(), 5).unwrap();
+        assert_eq!(draw, vec![12, 1000, 0, 0, 0]);
+        let hits: Vec<u32> = read_buffer(&device, &queue, &hits, 1).unwrap();
+        assert_eq!(hits[0], 16 * 64);
+    }
+}
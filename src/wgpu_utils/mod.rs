@@ -8,7 +8,17 @@
 //!
 //! - **Binding Group Builders** - Fluent API for creating bind groups and layouts
 //! - **Uniform Buffer Management** - Simplified uniform buffer creation and updates
+//! - **Per-Object Parameters** - Dynamic uniform offsets and push constant ranges
 //! - **Binding Type Helpers** - Convenient functions for common binding types
+//! - **Compute Primitives** - GPU reduction, prefix scan and radix sort
+//! - **Indirect Execution** - GPU-written dispatch and draw arguments
+//! - **Buffer Pooling** - Recycled staging and per-frame buffers
+//! - **Buffer Registry** - Named buffers listed by the buffer inspector panel
+//! - **Chunked Readback** - Progressive, non-blocking readback of large fields
+//! - **Half-Precision Fields** - f16 packing helpers for Rust and WGSL
+//! - **Shader Preprocessing** - WGSL includes, constants and naga validation
+//! - **Binding Checks** - Bind groups checked against shader reflection
+//! - **Shared Structs** - WGSL declarations and layout checks derived from Rust structs
 //! - **Resource Management** - Efficient GPU resource creation and management
 //!
 //! ## Architecture
@@ -18,6 +28,15 @@
 //! - [`binding_builder`] - Builder pattern for bind groups and layouts
 //! - [`binding_types`] - Helper functions for common binding types
 //! - [`uniform_buffer`] - Uniform buffer management utilities
+//! - [`compute_primitives`] - Parallel reduce, scan and sort over storage buffers
+//! - [`indirect`] - Indirect dispatch and draw argument buffers
+//! - [`buffer_pool`] - Size-classed pool for transient buffers
+//! - [`buffer_registry`] - Buffers registered by name for debugging
+//! - [`chunked_readback`] - Large buffer readback spread across frames
+//! - [`half`] - Half-precision field storage packed two values per word
+//! - [`shader`] - `#include` and constant expansion for WGSL sources
+//! - [`shader_interface`] - Shader bindings reflected with naga, and mismatch checks
+//! - [`wgsl_struct`] - `#[derive(WgslStruct)]` for structs shared with shaders
 //!
 //! ## Usage
 //!
@@ -46,10 +65,29 @@
 //! - **Flexibility** - Support both simple and complex use cases
 
 pub mod binding_builder;
+pub mod buffer_pool;
+pub mod buffer_registry;
+pub mod chunked_readback;
 pub mod binding_types;
+pub mod compute_primitives;
+pub mod half;
+pub mod indirect;
+pub mod shader;
+pub mod shader_interface;
 pub mod uniform_buffer;
+pub mod wgsl_struct;
 
 // Re-export main types for convenience
-pub use binding_builder::{BindGroupBuilder, BindGroupLayoutBuilder, BindGroupLayoutWithDesc};
+pub use binding_builder::{
+    supports_push_constants, BindGroupBuilder, BindGroupLayoutBuilder, BindGroupLayoutWithDesc,
+    PipelineLayoutBuilder,
+};
 pub use binding_types::*;
-pub use uniform_buffer::UniformBuffer;
+pub use buffer_pool::{BufferPool, BufferPoolStats, PooledBuffer};
+pub use buffer_registry::{BufferRegistry, RegisteredBuffer};
+pub use chunked_readback::{ChunkedReadback, ReadbackStatus};
+pub use half::FieldPrecision;
+pub use shader::{validate_wgsl, ShaderPreprocessor};
+pub use shader_interface::{BindingKind, BoundResource, ShaderInterface};
+pub use uniform_buffer::{DynamicUniformBuffer, UniformBuffer};
+pub use wgsl_struct::{WgslLayout, WgslStruct, WgslType};
@@ -0,0 +1,238 @@
+//! WGSL preprocessing
+//!
+//! WGSL has no include mechanism, so shaders sharing code end up pasting the
+//! same snippets and constants into every source string. [`ShaderPreprocessor`]
+//! adds two directives on top of plain WGSL:
+//!
+//! - `#include "name"` on its own line is replaced by the snippet registered
+//!   under `name`. Snippets may include others; each is inserted once per
+//!   shader, so two snippets can include a common dependency.
+//! - `{{NAME}}` anywhere in the source or a snippet is replaced by the
+//!   constant registered under `NAME`.
+//!
+//! Processed shaders are validated with naga before the device sees them, so
+//! a typo in a snippet is reported with its line and a readable message as a
+//! [`HaggisError::Shader`] instead of a device validation panic.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::simulation::boundary::D3Q19_WGSL;
+//! use haggis::wgpu_utils::ShaderPreprocessor;
+//! # fn run(device: &wgpu::Device) -> haggis::error::Result<()> {
+//! let shaders = ShaderPreprocessor::new()
+//!     .with_include("d3q19", D3Q19_WGSL)
+//!     .with_constant("GRID_WIDTH", 96);
+//!
+//! let module = shaders.create_module(
+//!     device,
+//!     "Stream",
+//!     r#"
+//! #include "d3q19"
+//! const GRID_WIDTH: u32 = {{GRID_WIDTH}}u;
+//! // ...
+//! "#,
+//! )?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+
+use wgpu::{Device, ShaderModule};
+
+use crate::error::{HaggisError, Result};
+
+/// Expands `#include` directives and `{{NAME}}` constants in WGSL sources
+#[derive(Debug, Clone, Default)]
+pub struct ShaderPreprocessor {
+    includes: HashMap<String, String>,
+    constants: HashMap<String, String>,
+}
+
+impl ShaderPreprocessor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a snippet for `#include "name"`
+    pub fn with_include(mut self, name: &str, source: impl Into<String>) -> Self {
+        self.add_include(name, source);
+        self
+    }
+
+    pub fn add_include(&mut self, name: &str, source: impl Into<String>) {
+        self.includes.insert(name.to_string(), source.into());
+    }
+
+    /// Register a constant substituted for `{{name}}`
+    ///
+    /// The value is inserted as formatted by `Display`; note that floats
+    /// without a fraction format as integers (`1.0` as `1`).
+    pub fn with_constant(mut self, name: &str, value: impl Display) -> Self {
+        self.add_constant(name, value);
+        self
+    }
+
+    pub fn add_constant(&mut self, name: &str, value: impl Display) {
+        self.constants.insert(name.to_string(), value.to_string());
+    }
+
+    /// Expand includes and constants, without validating the result
+    pub fn process(&self, label: &str, source: &str) -> Result<String> {
+        let mut output = String::with_capacity(source.len());
+        let mut included = HashSet::new();
+        self.expand(label, source, &mut Vec::new(), &mut included, &mut output)?;
+        Ok(output)
+    }
+
+    /// Expand a source and check it with naga
+    pub fn process_and_validate(&self, label: &str, source: &str) -> Result<String> {
+        let processed = self.process(label, source)?;
+        validate_wgsl(label, &processed)?;
+        Ok(processed)
+    }
+
+    /// Expand and validate a source, then create its shader module
+    pub fn create_module(
+        &self,
+        device: &Device,
+        label: &str,
+        source: &str,
+    ) -> Result<ShaderModule> {
+        let processed = self.process_and_validate(label, source)?;
+        Ok(device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(label),
+            source: wgpu::ShaderSource::Wgsl(processed.into()),
+        }))
+    }
+
+    fn expand<'a>(
+        &'a self,
+        label: &str,
+        source: &'a str,
+        stack: &mut Vec<&'a str>,
+        included: &mut HashSet<&'a str>,
+        output: &mut String,
+    ) -> Result<()> {
+        for line in source.lines() {
+            let Some(directive) = line.trim().strip_prefix("#include") else {
+                output.push_str(&self.substitute(label, line)?);
+                output.push('\n');
+                continue;
+            };
+
+            let name = directive
+                .trim()
+                .strip_prefix('"')
+                .and_then(|rest| rest.strip_suffix('"'))
+                .ok_or_else(|| error(label, format!("malformed include `{}`", line.trim())))?;
+            let (name, snippet) = self
+                .includes
+                .get_key_value(name)
+                .ok_or_else(|| error(label, format!("unknown include \"{name}\"")))?;
+
+            if stack.contains(&name.as_str()) {
+                return Err(error(
+                    label,
+                    format!("include cycle {} -> {name}", stack.join(" -> ")),
+                ));
+            }
+            if !included.insert(name) {
+                continue;
+            }
+            stack.push(name);
+            self.expand(label, snippet, stack, included, output)?;
+            stack.pop();
+        }
+        Ok(())
+    }
+
+    fn substitute(&self, label: &str, line: &str) -> Result<String> {
+        let mut result = String::with_capacity(line.len());
+        let mut rest = line;
+        while let Some(start) = rest.find("{{") {
+            let end = rest[start..].find("}}").ok_or_else(|| {
+                error(label, format!("unterminated constant in `{}`", line.trim()))
+            })?;
+            let name = rest[start + 2..start + end].trim();
+            let value = self
+                .constants
+                .get(name)
+                .ok_or_else(|| error(label, format!("unknown constant {{{{{name}}}}}")))?;
+            result.push_str(&rest[..start]);
+            result.push_str(value);
+            rest = &rest[start + end + 2..];
+        }
+        result.push_str(rest);
+        Ok(result)
+    }
+}
+
+/// Parse and validate WGSL with naga, reporting errors with source context
+pub fn validate_wgsl(label: &str, source: &str) -> Result<()> {
+    parse_wgsl(label, source).map(|_| ())
+}
+
+/// Parsed and validated module, for reflection
+pub(crate) fn parse_wgsl(
+    label: &str,
+    source: &str,
+) -> Result<(naga::Module, naga::valid::ModuleInfo)> {
+    let module = naga::front::wgsl::parse_str(source)
+        .map_err(|parse_error| error(label, parse_error.emit_to_string(source)))?;
+    let info = naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::all(),
+    )
+    .validate(&module)
+    .map_err(|validation_error| error(label, validation_error.emit_to_string(source)))?;
+    Ok((module, info))
+}
+
+pub(crate) fn error(label: &str, message: String) -> HaggisError {
+    HaggisError::Shader {
+        label: label.to_string(),
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_includes_and_constants() {
+        let shaders = ShaderPreprocessor::new()
+            .with_include("common", "const SIZE: u32 = {{SIZE}}u;")
+            .with_include("a", "#include \"common\"\nfn a() -> u32 { return SIZE; }")
+            .with_include(
+                "b",
+                "#include \"common\"\nfn b() -> u32 { return SIZE * 2u; }",
+            )
+            .with_constant("SIZE", 16);
+
+        let source = "#include \"a\"\n  #include \"b\"\nfn c() -> u32 { return a() + b(); }";
+        let processed = shaders.process_and_validate("test", source).unwrap();
+        assert_eq!(processed.matches("const SIZE: u32 = 16u;").count(), 1);
+        assert!(processed.contains("fn c()"));
+
+        let cyclic = ShaderPreprocessor::new()
+            .with_include("x", "#include \"y\"")
+            .with_include("y", "#include \"x\"");
+        assert!(cyclic.process("test", "#include \"x\"").is_err());
+        assert!(shaders.process("test", "#include \"missing\"").is_err());
+        assert!(shaders.process("test", "let x = {{MISSING}};").is_err());
+    }
+
+    #[test]
+    fn test_validation_reports_errors() {
+        let error =
+            validate_wgsl("broken", "fn f() -> f32 { return undefined_name; }").unwrap_err();
+        let message = error.to_string();
+        assert!(message.contains("broken"), "{message}");
+        assert!(message.contains("undefined_name"), "{message}");
+        assert!(validate_wgsl("fine", "fn f() -> f32 { return 1.0; }").is_ok());
+    }
+}
@@ -0,0 +1,408 @@
+//! Shader binding reflection
+//!
+//! A bind group that doesn't match its pipeline's shader is only reported by
+//! wgpu when the pipeline runs, as a validation panic from deep inside the
+//! render loop. [`ShaderInterface`] reflects the bindings a WGSL shader
+//! declares (through naga) and checks bind group layouts and bind groups
+//! against them up front, with errors naming the binding:
+//!
+//! ```text
+//! Shader Collision: group 0, binding 3 (`boundary_params`) expects uniform buffer, got storage buffer
+//! ```
+//!
+//! [`ComputeContext`](crate::simulation::low_level::ComputeContext) runs these
+//! checks automatically in debug builds before each dispatch.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::wgpu_utils::ShaderInterface;
+//! # fn run(source: &str, entries: &[wgpu::BindGroupLayoutEntry]) -> haggis::error::Result<()> {
+//! let interface = ShaderInterface::from_wgsl("Collision", source)?.entry_point("main");
+//! interface.check_layout(0, entries)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+
+use wgpu::{BindGroupEntry, BindGroupLayoutEntry, BindingResource, BindingType, BufferUsages};
+
+use super::shader::{error, parse_wgsl};
+use crate::error::Result;
+
+/// Kind of resource a binding holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindingKind {
+    UniformBuffer,
+    StorageBuffer {
+        read_only: bool,
+    },
+    Texture,
+    StorageTexture,
+    Sampler,
+    /// Binding arrays, acceleration structures and other resources that
+    /// aren't checked
+    Other,
+}
+
+impl BindingKind {
+    /// Kind of a bind group layout entry
+    pub fn of_layout(ty: &BindingType) -> Self {
+        match ty {
+            BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                ..
+            } => BindingKind::UniformBuffer,
+            BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                ..
+            } => BindingKind::StorageBuffer {
+                read_only: *read_only,
+            },
+            BindingType::Texture { .. } => BindingKind::Texture,
+            BindingType::StorageTexture { .. } => BindingKind::StorageTexture,
+            BindingType::Sampler(_) => BindingKind::Sampler,
+            _ => BindingKind::Other,
+        }
+    }
+
+    fn of_global(module: &naga::Module, global: &naga::GlobalVariable) -> Self {
+        match global.space {
+            naga::AddressSpace::Uniform => BindingKind::UniformBuffer,
+            naga::AddressSpace::Storage { access } => BindingKind::StorageBuffer {
+                read_only: !access.contains(naga::StorageAccess::STORE),
+            },
+            naga::AddressSpace::Handle => match module.types[global.ty].inner {
+                naga::TypeInner::Image {
+                    class: naga::ImageClass::Storage { .. },
+                    ..
+                } => BindingKind::StorageTexture,
+                naga::TypeInner::Image { .. } => BindingKind::Texture,
+                naga::TypeInner::Sampler { .. } => BindingKind::Sampler,
+                _ => BindingKind::Other,
+            },
+            _ => BindingKind::Other,
+        }
+    }
+}
+
+impl fmt::Display for BindingKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BindingKind::UniformBuffer => "uniform buffer",
+            BindingKind::StorageBuffer { read_only: true } => "read-only storage buffer",
+            BindingKind::StorageBuffer { read_only: false } => "storage buffer",
+            BindingKind::Texture => "texture",
+            BindingKind::StorageTexture => "storage texture",
+            BindingKind::Sampler => "sampler",
+            BindingKind::Other => "resource",
+        })
+    }
+}
+
+/// Resource bound in a bind group, as far as it can be told from the
+/// resource itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoundResource {
+    Buffer(BufferUsages),
+    TextureView,
+    Sampler,
+    Other,
+}
+
+impl BoundResource {
+    pub fn of(resource: &BindingResource) -> Self {
+        match resource {
+            BindingResource::Buffer(binding) => BoundResource::Buffer(binding.buffer.usage()),
+            BindingResource::TextureView(_) => BoundResource::TextureView,
+            BindingResource::Sampler(_) => BoundResource::Sampler,
+            _ => BoundResource::Other,
+        }
+    }
+
+    fn satisfies(&self, kind: BindingKind) -> bool {
+        match (self, kind) {
+            (BoundResource::Buffer(usage), BindingKind::UniformBuffer) => {
+                usage.contains(BufferUsages::UNIFORM)
+            }
+            (BoundResource::Buffer(usage), BindingKind::StorageBuffer { .. }) => {
+                usage.contains(BufferUsages::STORAGE)
+            }
+            (BoundResource::TextureView, BindingKind::Texture | BindingKind::StorageTexture) => {
+                true
+            }
+            (BoundResource::Sampler, BindingKind::Sampler) => true,
+            (BoundResource::Other, _) | (_, BindingKind::Other) => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for BoundResource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoundResource::Buffer(usage) if usage.contains(BufferUsages::UNIFORM) => {
+                f.write_str("uniform buffer")
+            }
+            BoundResource::Buffer(usage) if usage.contains(BufferUsages::STORAGE) => {
+                f.write_str("storage buffer")
+            }
+            BoundResource::Buffer(usage) => write!(f, "buffer with usage {usage:?}"),
+            BoundResource::TextureView => f.write_str("texture view"),
+            BoundResource::Sampler => f.write_str("sampler"),
+            BoundResource::Other => f.write_str("resource"),
+        }
+    }
+}
+
+/// One resource binding declared by a shader
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShaderBinding {
+    pub group: u32,
+    pub binding: u32,
+    pub name: String,
+    pub kind: BindingKind,
+    /// Entry points using the binding
+    pub entry_points: Vec<String>,
+}
+
+/// Resource bindings declared by a WGSL shader
+#[derive(Debug, Clone)]
+pub struct ShaderInterface {
+    label: String,
+    bindings: Vec<ShaderBinding>,
+}
+
+impl ShaderInterface {
+    /// Reflect the bindings of a WGSL source, which must be valid
+    pub fn from_wgsl(label: &str, source: &str) -> Result<Self> {
+        let (module, info) = parse_wgsl(label, source)?;
+
+        let mut bindings = Vec::new();
+        for (handle, global) in module.global_variables.iter() {
+            let Some(binding) = &global.binding else {
+                continue;
+            };
+            let entry_points = module
+                .entry_points
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| !info.get_entry_point(*index)[handle].is_empty())
+                .map(|(_, entry_point)| entry_point.name.clone())
+                .collect();
+            bindings.push(ShaderBinding {
+                group: binding.group,
+                binding: binding.binding,
+                name: global.name.clone().unwrap_or_default(),
+                kind: BindingKind::of_global(&module, global),
+                entry_points,
+            });
+        }
+        bindings.sort_by_key(|binding| (binding.group, binding.binding));
+
+        Ok(Self {
+            label: label.to_string(),
+            bindings,
+        })
+    }
+
+    /// Only the bindings an entry point uses, which is what an automatic
+    /// pipeline layout contains
+    pub fn entry_point(&self, name: &str) -> Self {
+        Self {
+            label: self.label.clone(),
+            bindings: self
+                .bindings
+                .iter()
+                .filter(|binding| binding.entry_points.iter().any(|entry| entry == name))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// Bindings sorted by group and binding index
+    pub fn bindings(&self) -> &[ShaderBinding] {
+        &self.bindings
+    }
+
+    /// Bindings of one bind group
+    pub fn group(&self, group: u32) -> impl Iterator<Item = &ShaderBinding> {
+        self.bindings
+            .iter()
+            .filter(move |binding| binding.group == group)
+    }
+
+    /// Check that a bind group layout provides every binding of `group`
+    /// with the kind the shader declares
+    pub fn check_layout(&self, group: u32, entries: &[BindGroupLayoutEntry]) -> Result<()> {
+        self.check(group, |binding| {
+            let entry = entries
+                .iter()
+                .find(|entry| entry.binding == binding.binding)?;
+            let kind = BindingKind::of_layout(&entry.ty);
+            let matches = kind == binding.kind || kind == BindingKind::Other;
+            Some((!matches).then(|| kind.to_string()))
+        })
+    }
+
+    /// Check that a bind group provides every binding of `group` with a
+    /// resource the shader can use
+    pub fn check_bind_group(&self, group: u32, entries: &[BindGroupEntry]) -> Result<()> {
+        let resources: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.binding, BoundResource::of(&entry.resource)))
+            .collect();
+        self.check_resources(group, &resources)
+    }
+
+    /// [`check_bind_group`](Self::check_bind_group) for resources recorded
+    /// when the bind group was created
+    pub fn check_resources(&self, group: u32, resources: &[(u32, BoundResource)]) -> Result<()> {
+        self.check(group, |binding| {
+            let (_, resource) = resources
+                .iter()
+                .find(|(index, _)| *index == binding.binding)?;
+            Some((!resource.satisfies(binding.kind)).then(|| resource.to_string()))
+        })
+    }
+
+    /// `provided` returns `None` for a missing binding, `Some(Some(found))`
+    /// for a mismatch
+    fn check(
+        &self,
+        group: u32,
+        provided: impl Fn(&ShaderBinding) -> Option<Option<String>>,
+    ) -> Result<()> {
+        let problems: Vec<String> = self
+            .group(group)
+            .filter_map(|binding| {
+                let expected = format!(
+                    "group {group}, binding {} (`{}`) expects {}",
+                    binding.binding, binding.name, binding.kind
+                );
+                match provided(binding) {
+                    None => Some(format!(
+                        "{expected}, but the bind group has no binding {}",
+                        binding.binding
+                    )),
+                    Some(Some(found)) => Some(format!("{expected}, got {found}")),
+                    Some(None) => None,
+                }
+            })
+            .collect();
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(error(&self.label, problems.join("\n")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wgpu_utils::compute_primitives::{test_buffer, test_device};
+
+    const SHADER: &str = r#"
+@group(0) @binding(0) var<storage, read> input: array<f32>;
+@group(0) @binding(1) var<storage, read_write> output: array<f32>;
+@group(0) @binding(2) var<uniform> scale: f32;
+@group(0) @binding(3) var<uniform> unused: f32;
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    output[id.x] = input[id.x] * scale;
+}
+"#;
+
+    fn buffer_entry(binding: u32, ty: wgpu::BufferBindingType) -> BindGroupLayoutEntry {
+        BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }
+    }
+
+    #[test]
+    fn test_layout_mismatch_is_reported() {
+        let interface = ShaderInterface::from_wgsl("Scale", SHADER).unwrap();
+        assert_eq!(interface.bindings().len(), 4);
+        let interface = interface.entry_point("main");
+        assert_eq!(interface.bindings().len(), 3);
+
+        let storage = |read_only| wgpu::BufferBindingType::Storage { read_only };
+        let mut entries = vec![
+            buffer_entry(0, storage(true)),
+            buffer_entry(1, storage(false)),
+            buffer_entry(2, wgpu::BufferBindingType::Uniform),
+        ];
+        interface.check_layout(0, &entries).unwrap();
+
+        entries[2] = buffer_entry(2, storage(true));
+        entries.remove(1);
+        let message = interface.check_layout(0, &entries).unwrap_err().to_string();
+        assert!(
+            message.contains(
+                "binding 1 (`output`) expects storage buffer, but the bind group has no binding 1"
+            ),
+            "{message}"
+        );
+        assert!(
+            message.contains(
+                "binding 2 (`scale`) expects uniform buffer, got read-only storage buffer"
+            ),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn test_bind_group_resources() {
+        let Some((device, _queue)) = test_device() else {
+            return;
+        };
+        let storage = test_buffer(&device, &[0.0f32; 64]);
+        let uniform = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: 16,
+            usage: BufferUsages::UNIFORM,
+            mapped_at_creation: false,
+        });
+        let interface = ShaderInterface::from_wgsl("Scale", SHADER)
+            .unwrap()
+            .entry_point("main");
+
+        fn entry(binding: u32, buffer: &wgpu::Buffer) -> BindGroupEntry<'_> {
+            BindGroupEntry {
+                binding,
+                resource: buffer.as_entire_binding(),
+            }
+        }
+        let good = [entry(0, &storage), entry(1, &storage), entry(2, &uniform)];
+        interface.check_bind_group(0, &good).unwrap();
+
+        let swapped = [entry(0, &storage), entry(1, &uniform), entry(2, &storage)];
+        let message = interface
+            .check_bind_group(0, &swapped)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            message.contains("binding 1 (`output`) expects storage buffer, got uniform buffer"),
+            "{message}"
+        );
+        assert!(
+            message.contains("binding 2 (`scale`) expects uniform buffer, got storage buffer"),
+            "{message}"
+        );
+    }
+}
@@ -117,12 +117,22 @@ impl<Content: bytemuck::Pod> UniformBuffer<Content> {
 
     /// Update buffer content (optimized to skip unnecessary writes)
     pub fn update_content(&mut self, queue: &wgpu::Queue, content: Content) {
-        let new_content = bytemuck::bytes_of(&content);
+        self.set_if_changed(queue, &content);
+    }
+
+    /// Write `content` only if it differs from the last written content
+    ///
+    /// Returns whether a write was queued, so callers can tell static frames
+    /// apart from ones that touched the GPU.
+    pub fn set_if_changed(&mut self, queue: &wgpu::Queue, content: &Content) -> bool {
+        let new_content = bytemuck::bytes_of(content);
         if self.previous_content == new_content {
-            return;
+            return false;
         }
         queue.write_buffer(&self.buffer, 0, new_content);
-        self.previous_content = new_content.to_vec();
+        self.previous_content.clear();
+        self.previous_content.extend_from_slice(new_content);
+        true
     }
 
     /// Force update buffer content (skips optimization check)
@@ -148,6 +158,96 @@ impl<Content: bytemuck::Pod> UniformBuffer<Content> {
     }
 }
 
+/// Uniform buffer holding one `Content` per object or step, each in a slot
+/// aligned for dynamic offsets
+///
+/// Bind it once with [binding_resource](Self::binding_resource) to a
+/// [uniform_dynamic](super::binding_types::uniform_dynamic) layout entry, then pick
+/// the slot with [offset](Self::offset) in `set_bind_group` instead of rebuilding
+/// bind groups or keeping a buffer per object.
+pub struct DynamicUniformBuffer<Content> {
+    buffer: wgpu::Buffer,
+    content_type: PhantomData<Content>,
+    stride: u64,
+    capacity: usize,
+}
+
+impl<Content: bytemuck::Pod> DynamicUniformBuffer<Content> {
+    /// Create a dynamic uniform buffer with `capacity` slots
+    pub fn new(device: &wgpu::Device, capacity: usize) -> Self {
+        let alignment = device.limits().min_uniform_buffer_offset_alignment as u64;
+        let stride = (std::mem::size_of::<Content>() as u64).next_multiple_of(alignment);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!(
+                "DynamicUniformBuffer: {}",
+                UniformBuffer::<Content>::name()
+            )),
+            size: stride * capacity.max(1) as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        DynamicUniformBuffer {
+            buffer,
+            content_type: PhantomData,
+            stride,
+            capacity,
+        }
+    }
+
+    /// Write the content of one slot
+    pub fn write(&self, queue: &wgpu::Queue, index: usize, content: &Content) {
+        assert!(index < self.capacity, "Slot exceeds buffer capacity");
+        queue.write_buffer(
+            &self.buffer,
+            self.offset(index) as u64,
+            bytemuck::bytes_of(content),
+        );
+    }
+
+    /// Write the content of the first `contents.len()` slots
+    pub fn write_all(&self, queue: &wgpu::Queue, contents: &[Content]) {
+        assert!(
+            contents.len() <= self.capacity,
+            "Data exceeds buffer capacity"
+        );
+        let mut bytes = vec![0u8; self.stride as usize * contents.len()];
+        for (slot, content) in bytes.chunks_exact_mut(self.stride as usize).zip(contents) {
+            slot[..std::mem::size_of::<Content>()].copy_from_slice(bytemuck::bytes_of(content));
+        }
+        queue.write_buffer(&self.buffer, 0, &bytes);
+    }
+
+    /// Dynamic offset of a slot, to pass to `set_bind_group`
+    pub fn offset(&self, index: usize) -> u32 {
+        (index as u64 * self.stride) as u32
+    }
+
+    /// Bytes between two slots
+    pub fn stride(&self) -> u64 {
+        self.stride
+    }
+
+    /// Get binding resource covering one slot
+    pub fn binding_resource(&self) -> wgpu::BindingResource<'_> {
+        wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+            buffer: &self.buffer,
+            offset: 0,
+            size: wgpu::BufferSize::new(std::mem::size_of::<Content>() as u64),
+        })
+    }
+
+    /// Get the underlying buffer
+    pub fn buffer(&self) -> &wgpu::Buffer {
+        &self.buffer
+    }
+
+    /// Get number of slots
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
 /// Array buffer for handling multiple instances of the same type
 pub struct ArrayBuffer<Content> {
     buffer: wgpu::Buffer,
@@ -267,3 +367,95 @@ impl<Content: bytemuck::Pod + Clone> ArrayBuffer<Content> {
         self.capacity
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wgpu_utils::binding_builder::{
+        BindGroupBuilder, BindGroupLayoutBuilder, PipelineLayoutBuilder,
+    };
+    use crate::wgpu_utils::binding_types;
+    use crate::wgpu_utils::compute_primitives::{read_buffer, test_buffer, test_device};
+
+    #[repr(C)]
+    #[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+    struct Slot {
+        index: u32,
+        value: u32,
+    }
+
+    #[test]
+    fn test_set_if_changed_skips_identical_content() {
+        // Needs a GPU adapter, skipped without one
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let mut ubo = UniformBuffer::new_with_data(&device, &[1.0f32, 2.0, 3.0, 4.0]);
+        assert!(!ubo.set_if_changed(&queue, &[1.0, 2.0, 3.0, 4.0]));
+        assert!(ubo.set_if_changed(&queue, &[1.0, 2.0, 3.0, 5.0]));
+        assert!(!ubo.set_if_changed(&queue, &[1.0, 2.0, 3.0, 5.0]));
+    }
+
+    #[test]
+    fn test_dynamic_offsets_select_slots() {
+        // Needs a GPU adapter, skipped without one
+        let Some((device, queue)) = test_device() else {
+            return;
+        };
+        let slots = DynamicUniformBuffer::<Slot>::new(&device, 3);
+        assert!(slots.stride() >= device.limits().min_uniform_buffer_offset_alignment as u64);
+        let slot = |index, value| Slot { index, value };
+        slots.write_all(&queue, &[slot(0, 10), slot(1, 20)]);
+        slots.write(&queue, 2, &slot(2, 30));
+        let output = test_buffer(&device, &[0u32; 3]);
+
+        let layout = BindGroupLayoutBuilder::new()
+            .next_binding_compute(binding_types::uniform_dynamic())
+            .next_binding_compute(binding_types::storage_buffer_read_write())
+            .create(&device, "Dynamic Slot Layout");
+        let bind_group = BindGroupBuilder::new(&layout)
+            .resource(slots.binding_resource())
+            .buffer(&output)
+            .create(&device, "Dynamic Slot Bind Group");
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(
+                r#"
+struct Slot { index: u32, value: u32 }
+@group(0) @binding(0) var<uniform> slot: Slot;
+@group(0) @binding(1) var<storage, read_write> output: array<u32>;
+@compute @workgroup_size(1)
+fn main() {
+    output[slot.index] = slot.value;
+}
+"#
+                .into(),
+            ),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: None,
+            layout: Some(
+                &PipelineLayoutBuilder::new()
+                    .bind_group_layout(&layout.layout)
+                    .create(&device, "Dynamic Slot Pipeline Layout"),
+            ),
+            module: &shader,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        let mut encoder = device.create_command_encoder(&Default::default());
+        {
+            let mut pass = encoder.begin_compute_pass(&Default::default());
+            pass.set_pipeline(&pipeline);
+            for index in 0..slots.capacity() {
+                pass.set_bind_group(0, &bind_group, &[slots.offset(index)]);
+                pass.dispatch_workgroups(1, 1, 1);
+            }
+        }
+        queue.submit(std::iter::once(encoder.finish()));
+        let values: Vec<u32> = read_buffer(&device, &queue, &output, 3).unwrap();
+        assert_eq!(values, [10, 20, 30]);
+    }
+}
@@ -0,0 +1,208 @@
+//! Rust structs shared with WGSL
+//!
+//! Uniform and storage structs are declared twice, once in Rust and once in
+//! the shader, and the two drift apart silently: WGSL aligns a `vec3<f32>` to
+//! 16 bytes where Rust packs `[f32; 3]` after the previous field, and a
+//! missing padding field shifts every later value. `#[derive(WgslStruct)]`
+//! generates the WGSL declaration from the Rust struct and checks at compile
+//! time that every field offset and the struct size match WGSL's rules for
+//! storage buffers (std430-like), and with `#[wgsl(uniform)]` also for
+//! uniform buffers (std140-like: 16-byte aligned arrays and structs).
+//!
+//! Field types map to WGSL as follows:
+//!
+//! | Rust | WGSL |
+//! |------|------|
+//! | `f32`, `u32`, `i32` | same |
+//! | `[f32; 2]` .. `[f32; 4]` (and `u32`, `i32`) | `vec2<f32>` .. `vec4<f32>` |
+//! | other `[T; N]` | `array<T, N>` |
+//! | a `WgslStruct` | the struct, declared separately |
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use haggis::wgpu_utils::WgslStruct;
+//!
+//! #[repr(C)]
+//! #[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, WgslStruct)]
+//! #[wgsl(uniform)]
+//! struct FluidUniforms {
+//!     gravity: [f32; 3],
+//!     viscosity: f32,
+//!     size: [u32; 2],
+//!     _padding: [u32; 2],
+//! }
+//!
+//! let shader = format!(
+//!     "{}\n@group(0) @binding(0) var<uniform> fluid: FluidUniforms;",
+//!     FluidUniforms::wgsl_struct()
+//! );
+//! ```
+//!
+//! Moving `viscosity` before `gravity` fails to compile: WGSL would place
+//! `gravity` at offset 16, Rust at offset 4. Padding with `[u32; 3]` declares
+//! a 16-byte aligned `vec3<u32>`; pad with `u32` and `[u32; 2]` instead.
+
+pub use haggis_derive::WgslStruct;
+
+/// Alignment and size of a type in a WGSL address space
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WgslLayout {
+    pub align: usize,
+    pub size: usize,
+}
+
+impl WgslLayout {
+    pub const fn new(align: usize, size: usize) -> Self {
+        Self { align, size }
+    }
+
+    /// Distance between consecutive elements of an array of this type
+    pub const fn stride(&self) -> usize {
+        align_to(self.size, self.align)
+    }
+}
+
+/// Round `offset` up to a multiple of `align`
+pub const fn align_to(offset: usize, align: usize) -> usize {
+    offset.div_ceil(align) * align
+}
+
+/// A Rust type with a WGSL equivalent of the same layout
+///
+/// Implemented for scalars, arrays and `#[derive(WgslStruct)]` structs.
+pub trait WgslType {
+    /// Layout in storage buffers
+    const STORAGE: WgslLayout;
+    /// Layout in uniform buffers, `None` if the type can't be used there
+    const UNIFORM: Option<WgslLayout>;
+    /// WGSL name of scalar types, which form vectors in arrays of 2 to 4
+    const SCALAR: Option<&'static str> = None;
+
+    /// WGSL type name, e.g. `vec3<f32>` or `array<u32, 8>`
+    fn wgsl_type() -> String;
+}
+
+macro_rules! scalar {
+    ($($ty:ty => $name:literal),*) => {$(
+        impl WgslType for $ty {
+            const STORAGE: WgslLayout = WgslLayout::new(4, 4);
+            const UNIFORM: Option<WgslLayout> = Some(WgslLayout::new(4, 4));
+            const SCALAR: Option<&'static str> = Some($name);
+
+            fn wgsl_type() -> String {
+                $name.to_string()
+            }
+        }
+    )*};
+}
+
+scalar!(f32 => "f32", u32 => "u32", i32 => "i32");
+
+impl<T: WgslType, const N: usize> WgslType for [T; N] {
+    const STORAGE: WgslLayout = match Self::VECTOR {
+        Some(vector) => vector,
+        None => WgslLayout::new(T::STORAGE.align, T::STORAGE.stride() * N),
+    };
+    const UNIFORM: Option<WgslLayout> = match (Self::VECTOR, T::UNIFORM) {
+        (Some(vector), _) => Some(vector),
+        // Uniform array elements are 16-byte aligned
+        (None, Some(element)) if element.stride() % 16 == 0 => Some(WgslLayout::new(
+            align_to(element.align, 16),
+            element.stride() * N,
+        )),
+        _ => None,
+    };
+
+    fn wgsl_type() -> String {
+        match (T::SCALAR, Self::VECTOR) {
+            (Some(scalar), Some(_)) => format!("vec{N}<{scalar}>"),
+            _ => format!("array<{}, {N}>", T::wgsl_type()),
+        }
+    }
+}
+
+trait Vector {
+    const VECTOR: Option<WgslLayout>;
+}
+
+impl<T: WgslType, const N: usize> Vector for [T; N] {
+    const VECTOR: Option<WgslLayout> = match (T::SCALAR, N) {
+        (Some(_), 2) => Some(WgslLayout::new(8, 8)),
+        (Some(_), 3) => Some(WgslLayout::new(16, 12)),
+        (Some(_), 4) => Some(WgslLayout::new(16, 16)),
+        _ => None,
+    };
+}
+
+/// A `#[repr(C)]` struct with a generated WGSL declaration
+///
+/// Derive it with `#[derive(WgslStruct)]`; the derive also implements
+/// [`WgslType`] so structs can be nested, and `#[wgsl(name = "...")]` renames
+/// the WGSL struct.
+pub trait WgslStruct: WgslType {
+    /// Struct name in WGSL
+    const WGSL_NAME: &'static str;
+
+    /// Field names and WGSL types, in declaration order
+    fn wgsl_fields() -> Vec<(&'static str, String)>;
+
+    /// WGSL struct declaration
+    fn wgsl_struct() -> String {
+        let mut declaration = format!("struct {} {{\n", Self::WGSL_NAME);
+        for (name, ty) in Self::wgsl_fields() {
+            declaration.push_str(&format!("    {name}: {ty},\n"));
+        }
+        declaration.push_str("}\n");
+        declaration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C)]
+    #[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, WgslStruct)]
+    #[wgsl(uniform)]
+    struct Inner {
+        direction: [f32; 3],
+        strength: f32,
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable, WgslStruct)]
+    #[wgsl(name = "OuterData")]
+    struct Outer {
+        size: [u32; 2],
+        count: u32,
+        _padding: u32,
+        weights: [f32; 5],
+        _padding2: u32,
+        _padding3: [u32; 2],
+        inner: [Inner; 2],
+    }
+
+    #[test]
+    fn test_layouts() {
+        assert_eq!(<[f32; 3]>::STORAGE, WgslLayout::new(16, 12));
+        assert_eq!(<[f32; 3]>::wgsl_type(), "vec3<f32>");
+        assert_eq!(<[u32; 8]>::wgsl_type(), "array<u32, 8>");
+        assert_eq!(<[u32; 8]>::UNIFORM, None);
+        assert_eq!(<[[f32; 4]; 6]>::UNIFORM, Some(WgslLayout::new(16, 96)));
+        assert_eq!(Inner::UNIFORM, Some(WgslLayout::new(16, 16)));
+        assert_eq!(Outer::STORAGE, WgslLayout::new(16, 80));
+    }
+
+    #[test]
+    fn test_declaration_validates() {
+        let source = format!(
+            "{}{}@group(0) @binding(0) var<uniform> inner: Inner;
+@group(0) @binding(1) var<storage, read> outer: array<OuterData>;",
+            Inner::wgsl_struct(),
+            Outer::wgsl_struct()
+        );
+        assert!(source.contains("    inner: array<Inner, 2>,\n"));
+        crate::wgpu_utils::validate_wgsl("wgsl_struct", &source).unwrap();
+    }
+}
@@ -0,0 +1,427 @@
+//! # Stereo / VR Rendering
+//!
+//! This module (behind the `xr` feature) renders the scene a second time per
+//! frame, once for each eye of a head-mounted display, from head-tracked
+//! cameras. Controllers act as pointers: pressing select casts a ray from the
+//! controller and picks the object it hits, emitting the same
+//! [`PickEvent`] a mouse click does.
+//!
+//! The headset itself is driven by an [`XrRuntime`] implementation, which
+//! adapts an OpenXR binding (or any other tracking API) to three calls: the
+//! per-eye resolution, waiting for the next frame's eye and controller poses,
+//! and presenting the rendered eye images. The runtime owns the XR instance
+//! and swapchains and creates the wgpu device they share, so the camera
+//! math, rendering and picking here are written once whichever binding a
+//! project uses.
+//!
+//! ## Scope
+//!
+//! Haggis does not bundle an OpenXR binding. The `xr` feature compiles the
+//! stereo cameras, eye rendering and controller picking only; driving a
+//! headset needs an [`XrRuntime`] written against a binding such as the
+//! `openxr` crate, in the application or a companion crate.
+//! `MyOpenXrRuntime` below stands for such an implementation.
+//!
+//! Runtimes report poses in a Y-up tracking space measured in meters.
+//! [`TrackingSpace`] places that space in the world: where the tracked floor
+//! origin sits and how many world units one tracked meter spans. A scale
+//! below 1 makes structures that are only a couple of world units wide, such
+//! as an LBM vorticity field, room-sized to walk around in.
+//!
+//! The desktop window keeps showing the orbit camera view.
+//!
+//! ## Usage
+//!
+//! ```ignore
+//! use cgmath::Vector3;
+//! use haggis::xr::TrackingSpace;
+//!
+//! let mut app = haggis::default();
+//! app.enable_xr(MyOpenXrRuntime::new()?);
+//! if let Some(xr) = app.app_state.xr_session.as_mut() {
+//!     // Floor below the domain, starting 2 units in front of it;
+//!     // one tracked meter is half a world unit
+//!     xr.tracking = TrackingSpace {
+//!         origin: Vector3::new(0.0, -2.0, -1.0),
+//!         scale: 0.5,
+//!     };
+//! }
+//! app.run();
+//! ```
+//!
+//! [`PickEvent`]: crate::events::PickEvent
+
+use cgmath::{
+    EuclideanSpace, InnerSpace, Matrix4, Point3, Quaternion, SquareMatrix, Transform, Vector3,
+};
+
+use crate::events::PickEvent;
+use crate::gfx::{
+    camera::{camera_utils::CameraUniform, orbit_camera::OPENGL_TO_WGPU_MATRIX},
+    coordinates::UpAxis,
+    picking::{ObjectPicker, Ray},
+    rendering::{
+        render_engine::ViewCamera, viewport::ViewportTarget, DepthMode, RenderEngine,
+        VisualizationPlane,
+    },
+    scene::Scene,
+};
+
+/// Position and orientation in tracking space
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pose {
+    /// Position in meters
+    pub position: Vector3<f32>,
+    /// Rotation from the pose's local frame (looking down -Z, Y up)
+    pub orientation: Quaternion<f32>,
+}
+
+impl Pose {
+    /// Pose at the tracking origin, looking down -Z
+    pub fn identity() -> Self {
+        Self {
+            position: Vector3::new(0.0, 0.0, 0.0),
+            orientation: Quaternion::new(1.0, 0.0, 0.0, 0.0),
+        }
+    }
+
+    /// Transform from the pose's local frame into tracking space
+    pub fn matrix(&self) -> Matrix4<f32> {
+        Matrix4::from_translation(self.position) * Matrix4::from(self.orientation)
+    }
+}
+
+/// Asymmetric field of view of one eye, as angles in radians from the view
+/// direction; `angle_left` and `angle_down` are negative
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fov {
+    pub angle_left: f32,
+    pub angle_right: f32,
+    pub angle_up: f32,
+    pub angle_down: f32,
+}
+
+impl Fov {
+    /// Symmetric field of view with the given total vertical angle and aspect ratio
+    pub fn symmetric(fovy: f32, aspect: f32) -> Self {
+        let tan_half = (fovy * 0.5).tan();
+        let half_width = (tan_half * aspect).atan();
+        Self {
+            angle_left: -half_width,
+            angle_right: half_width,
+            angle_up: fovy * 0.5,
+            angle_down: -fovy * 0.5,
+        }
+    }
+
+    /// Projection matrix with wgpu's 0 to 1 depth range
+    pub fn projection(&self, znear: f32, zfar: f32) -> Matrix4<f32> {
+        OPENGL_TO_WGPU_MATRIX
+            * cgmath::frustum(
+                znear * self.angle_left.tan(),
+                znear * self.angle_right.tan(),
+                znear * self.angle_down.tan(),
+                znear * self.angle_up.tan(),
+                znear,
+                zfar,
+            )
+    }
+}
+
+/// Pose and field of view of one eye
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EyeView {
+    pub pose: Pose,
+    pub fov: Fov,
+}
+
+/// Tracked controller
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ControllerState {
+    /// Aim pose; the pointing ray runs along its -Z axis
+    pub pose: Pose,
+    /// Whether the select button (usually the trigger) is held
+    pub select: bool,
+}
+
+/// Tracking data for one frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct XrFrame {
+    /// Left and right eye
+    pub views: [EyeView; 2],
+    /// Controllers currently tracked
+    pub controllers: Vec<ControllerState>,
+}
+
+/// A headset runtime, such as an OpenXR session
+pub trait XrRuntime {
+    /// Runtime name, shown in logs (e.g. "OpenXR (SteamVR)")
+    fn name(&self) -> &str;
+
+    /// Recommended render size of each eye in pixels
+    fn resolution(&self) -> (u32, u32);
+
+    /// Wait for the next frame and return the predicted eye and controller poses
+    ///
+    /// Returns `None` when the headset is not displaying, e.g. while the
+    /// session is idle or the headset is off; nothing is rendered then.
+    fn begin_frame(&mut self) -> Option<XrFrame>;
+
+    /// Present the rendered left and right eye images and end the frame
+    ///
+    /// The images have the window's surface format and support
+    /// `COPY_SRC` and `TEXTURE_BINDING`, for copying or blitting into the
+    /// runtime's swapchain.
+    fn end_frame(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, eyes: [&wgpu::Texture; 2]);
+}
+
+/// Placement of the Y-up, meter-based tracking space in the world
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrackingSpace {
+    /// World position of the tracked floor origin
+    pub origin: Vector3<f32>,
+    /// World units per tracked meter
+    pub scale: f32,
+}
+
+impl Default for TrackingSpace {
+    fn default() -> Self {
+        Self {
+            origin: Vector3::new(0.0, 0.0, 0.0),
+            scale: 1.0,
+        }
+    }
+}
+
+impl TrackingSpace {
+    /// Transform from tracking space into a world with the given up axis
+    pub fn to_world(self, up_axis: UpAxis) -> Matrix4<f32> {
+        let axis = |v: Vector3<f32>| up_axis.convert_from(UpAxis::Y, v).extend(0.0);
+        let rotation = Matrix4::from_cols(
+            axis(Vector3::unit_x()),
+            axis(Vector3::unit_y()),
+            axis(Vector3::unit_z()),
+            cgmath::Vector4::unit_w(),
+        );
+        Matrix4::from_translation(self.origin) * Matrix4::from_scale(self.scale) * rotation
+    }
+}
+
+/// A running headset session: runtime, tracking space and per-eye targets
+pub struct XrSession {
+    runtime: Box<dyn XrRuntime>,
+    /// Where the tracking space sits in the world
+    pub tracking: TrackingSpace,
+    /// Near clip distance in tracked meters
+    pub znear: f32,
+    /// Far clip distance in tracked meters
+    pub zfar: f32,
+    /// Depth convention of the eye projections, the render engine's
+    depth_mode: DepthMode,
+    targets: [Option<ViewportTarget>; 2],
+    /// Select state of each controller last frame, to pick on press only
+    select_held: Vec<bool>,
+}
+
+impl XrSession {
+    /// Start a session on `runtime`
+    pub fn new(runtime: impl XrRuntime + 'static) -> Self {
+        Self {
+            runtime: Box::new(runtime),
+            tracking: TrackingSpace::default(),
+            znear: 0.05,
+            zfar: 100.0,
+            depth_mode: DepthMode::default(),
+            targets: [None, None],
+            select_held: Vec::new(),
+        }
+    }
+
+    /// Name of the runtime driving the headset
+    pub fn runtime_name(&self) -> &str {
+        self.runtime.name()
+    }
+
+    /// Camera uniforms for rendering one eye
+    pub fn eye_camera(&self, view: &EyeView, up_axis: UpAxis) -> CameraUniform {
+        let world_from_eye = self.tracking.to_world(up_axis) * view.pose.matrix();
+        let view_matrix = world_from_eye.invert().unwrap_or(Matrix4::identity());
+        let eye = world_from_eye.transform_point(Point3::origin());
+        CameraUniform {
+            view_position: [eye.x, eye.y, eye.z, 1.0],
+            view_proj: (self.eye_projection(view) * view_matrix).into(),
+        }
+    }
+
+    /// Projection of one eye in the render engine's depth mode
+    fn eye_projection(&self, view: &EyeView) -> Matrix4<f32> {
+        let projection = view.fov.projection(self.znear, self.zfar);
+        self.depth_mode.projection(projection, self.znear, self.zfar)
+    }
+
+    /// World-space pointing ray of a controller
+    pub fn controller_ray(&self, controller: &ControllerState, up_axis: UpAxis) -> Ray {
+        let world_from_aim = self.tracking.to_world(up_axis) * controller.pose.matrix();
+        let origin = world_from_aim.transform_point(Point3::origin());
+        let direction = world_from_aim.transform_vector(-Vector3::unit_z());
+        Ray::new(origin.to_vec(), direction.normalize())
+    }
+
+    /// Render both eyes, present them and pick with controllers whose select
+    /// button was just pressed
+    pub(crate) fn render_frame(
+        &mut self,
+        render_engine: &mut RenderEngine,
+        scene: &Scene,
+        visualization_planes: &[VisualizationPlane],
+        picker: &mut ObjectPicker,
+    ) -> Vec<PickEvent> {
+        let Some(frame) = self.runtime.begin_frame() else {
+            return Vec::new();
+        };
+        let up_axis = scene.coordinate_system().up_axis;
+        let size = self.runtime.resolution();
+        self.depth_mode = render_engine.depth_mode();
+        let clear_color = scene.render_settings.wgpu_clear_color();
+
+        for (eye, view) in frame.views.iter().enumerate() {
+            let camera = self.eye_camera(view, up_axis);
+            render_engine.render_camera_to_target(
+                &mut self.targets[eye],
+                size,
+                scene,
+                visualization_planes,
+                ViewCamera {
+                    uniform: camera,
+                    layers: scene.camera_manager.camera.layers,
+                },
+                clear_color,
+            );
+        }
+        if let [Some(left), Some(right)] = &self.targets {
+            self.runtime.end_frame(
+                render_engine.device(),
+                render_engine.queue(),
+                [&left.color, &right.color],
+            );
+        }
+
+        let pressed: Vec<&ControllerState> = frame
+            .controllers
+            .iter()
+            .enumerate()
+            .filter(|(i, controller)| {
+                controller.select && !self.select_held.get(*i).copied().unwrap_or(false)
+            })
+            .map(|(_, controller)| controller)
+            .collect();
+        self.select_held = frame
+            .controllers
+            .iter()
+            .map(|controller| controller.select)
+            .collect();
+
+        let mut picks = Vec::new();
+        for controller in pressed {
+            let ray = self.controller_ray(controller, up_axis);
+            if let Some(pick) = picker.pick_ray(&ray, scene) {
+                picks.push(PickEvent {
+                    object_index: pick.object_index,
+                    distance: pick.distance,
+                    point: pick.intersection_point,
+                });
+            }
+        }
+        picks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gfx::camera::{
+        camera_controller::CameraController, camera_utils::CameraManager, orbit_camera::OrbitCamera,
+    };
+    use cgmath::{Deg, Rotation3};
+
+    fn assert_near(a: Vector3<f32>, b: Vector3<f32>) {
+        assert!((a - b).magnitude() < 1e-5, "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn test_symmetric_fov_matches_perspective() {
+        let fov = Fov::symmetric(1.0, 1.5);
+        let expected =
+            OPENGL_TO_WGPU_MATRIX * cgmath::perspective(cgmath::Rad(1.0), 1.5, 0.1, 50.0);
+        let actual = fov.projection(0.1, 50.0);
+        for column in 0..4 {
+            for row in 0..4 {
+                assert!((actual[column][row] - expected[column][row]).abs() < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn test_tracking_space_maps_into_z_up_world() {
+        let tracking = TrackingSpace {
+            origin: Vector3::new(1.0, 2.0, 0.0),
+            scale: 2.0,
+        };
+        let to_world = tracking.to_world(UpAxis::Z);
+        // One tracked meter up is two world units along +Z
+        assert_near(
+            to_world
+                .transform_point(Point3::new(0.0, 1.0, 0.0))
+                .to_vec(),
+            Vector3::new(1.0, 2.0, 2.0),
+        );
+        // Tracking -Z (forward) is world +Y
+        assert_near(
+            to_world.transform_vector(-Vector3::unit_z()),
+            Vector3::new(0.0, 2.0, 0.0),
+        );
+    }
+
+    #[test]
+    fn test_controller_ray_picks_object_ahead() {
+        struct NoRuntime;
+        impl XrRuntime for NoRuntime {
+            fn name(&self) -> &str {
+                "none"
+            }
+            fn resolution(&self) -> (u32, u32) {
+                (1, 1)
+            }
+            fn begin_frame(&mut self) -> Option<XrFrame> {
+                None
+            }
+            fn end_frame(&mut self, _: &wgpu::Device, _: &wgpu::Queue, _: [&wgpu::Texture; 2]) {}
+        }
+
+        let session = XrSession::new(NoRuntime);
+        // Controller at head height, turned 90 degrees left to point along tracking -X
+        let controller = ControllerState {
+            pose: Pose {
+                position: Vector3::new(0.0, 1.5, 0.0),
+                orientation: Quaternion::from_angle_y(Deg(90.0)),
+            },
+            select: true,
+        };
+        let ray = session.controller_ray(&controller, UpAxis::Z);
+        assert_near(ray.origin, Vector3::new(0.0, 0.0, 1.5));
+        assert_near(ray.direction, Vector3::new(-1.0, 0.0, 0.0));
+
+        let camera = OrbitCamera::new(8.0, 0.4, 0.2, Vector3::new(0.0, 0.0, 0.0), 1.0);
+        let mut scene = Scene::new(CameraManager::new(
+            camera,
+            CameraController::new(0.005, 0.1),
+        ));
+        let mut cube = crate::gfx::scene::Object::new(Vec::new());
+        cube.set_translation(Vector3::new(-3.0, 0.0, 1.5));
+        scene.objects.push(cube);
+        // Objects without meshes are picked as unit cubes
+        let pick = ObjectPicker::new().pick_ray(&ray, &scene).unwrap();
+        assert_eq!(pick.object_index, 0);
+        assert!((pick.distance - 2.5).abs() < 1e-5);
+    }
+}